@@ -125,12 +125,21 @@ impl IpMatch {
         self.0.is_empty()
     }
 
+    /// Returns true if any network contains the address.
+    ///
+    /// IPv4-mapped IPv6 addresses are matched against IPv4 networks by their
+    /// IPv4 form, and IPv4 addresses are matched against IPv6 networks by
+    /// their IPv4-mapped form, so that networks match regardless of the
+    /// family with which an address was observed.
     #[inline]
     pub fn matches(&self, addr: IpAddr) -> bool {
         self.0.iter().any(|net| match (net, addr) {
             (IpNet::V4(net), IpAddr::V4(ip)) => net.contains(&ip),
+            (IpNet::V4(net), IpAddr::V6(ip)) => {
+                ip.to_ipv4_mapped().is_some_and(|ip| net.contains(&ip))
+            }
             (IpNet::V6(net), IpAddr::V6(ip)) => net.contains(&ip),
-            _ => false,
+            (IpNet::V6(net), IpAddr::V4(ip)) => net.contains(&ip.to_ipv6_mapped()),
         })
     }
 }
@@ -150,3 +159,46 @@ impl<T: fmt::Display> fmt::Debug for Display<'_, T> {
         fmt::Display::fmt(self.0, f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nets(nets: &[&str]) -> IpMatch {
+        IpMatch::new(nets.iter().map(|n| n.parse().unwrap()))
+    }
+
+    #[test]
+    fn ip_match_v4_mapped() {
+        let m = nets(&["10.0.0.0/8"]);
+        assert!(m.matches("10.1.2.3".parse().unwrap()));
+        assert!(m.matches("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!m.matches("::ffff:192.0.2.3".parse().unwrap()));
+        // IPv4-compatible addresses are not IPv4-mapped.
+        assert!(!m.matches("::10.1.2.3".parse().unwrap()));
+    }
+
+    #[test]
+    fn ip_match_v6() {
+        let m = nets(&["fd00::/8"]);
+        assert!(m.matches("fd00::1".parse().unwrap()));
+        assert!(!m.matches("2001:db8::1".parse().unwrap()));
+        assert!(!m.matches("10.1.2.3".parse().unwrap()));
+
+        // IPv4 addresses match networks that contain their mapped form.
+        let m = nets(&["::ffff:10.0.0.0/104"]);
+        assert!(m.matches("10.1.2.3".parse().unwrap()));
+        assert!(m.matches("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!m.matches("192.0.2.3".parse().unwrap()));
+    }
+
+    #[test]
+    fn ip_match_mixed_families() {
+        let m = nets(&["10.0.0.0/8", "fd00::/8"]);
+        assert!(m.matches("10.1.2.3".parse().unwrap()));
+        assert!(m.matches("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(m.matches("fd00::1".parse().unwrap()));
+        assert!(!m.matches("192.0.2.3".parse().unwrap()));
+        assert!(!m.matches("2001:db8::1".parse().unwrap()));
+    }
+}
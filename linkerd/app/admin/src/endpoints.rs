@@ -0,0 +1,193 @@
+use std::{collections::HashSet, str::FromStr};
+use thiserror::Error;
+
+/// An endpoint served by the admin server.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    Metrics,
+    Ready,
+    Live,
+    Env,
+    InboundPorts,
+    RolloutGuards,
+    Breakers,
+    BackendFaults,
+    DiscoveryCache,
+    PortMappings,
+    Panics,
+    LogLevel,
+    Logs,
+    Shutdown,
+    Profile,
+}
+
+/// The set of endpoints served on an admin listener.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Endpoints(HashSet<Endpoint>);
+
+#[derive(Debug, Error)]
+#[error("unknown admin endpoint: {0}")]
+pub struct InvalidEndpoint(String);
+
+// === impl Endpoint ===
+
+impl Endpoint {
+    pub const ALL: [Self; 15] = [
+        Self::Metrics,
+        Self::Ready,
+        Self::Live,
+        Self::Env,
+        Self::InboundPorts,
+        Self::RolloutGuards,
+        Self::Breakers,
+        Self::BackendFaults,
+        Self::DiscoveryCache,
+        Self::PortMappings,
+        Self::Panics,
+        Self::LogLevel,
+        Self::Logs,
+        Self::Shutdown,
+        Self::Profile,
+    ];
+
+    /// Returns the endpoint that serves the given request path, if any.
+    pub(crate) fn from_path(path: &str) -> Option<Self> {
+        match path {
+            "/metrics" => Some(Self::Metrics),
+            "/ready" | "/ready/inbound" | "/ready/outbound" => Some(Self::Ready),
+            "/live" => Some(Self::Live),
+            "/env.json" => Some(Self::Env),
+            "/inbound-ports.json" => Some(Self::InboundPorts),
+            "/rollout-guards.json" => Some(Self::RolloutGuards),
+            "/breakers.json" => Some(Self::Breakers),
+            "/backend-faults.json" => Some(Self::BackendFaults),
+            "/discovery-cache.json" => Some(Self::DiscoveryCache),
+            "/port-mappings.json" => Some(Self::PortMappings),
+            "/panics.json" => Some(Self::Panics),
+            "/proxy-log-level" => Some(Self::LogLevel),
+            "/logs.json" => Some(Self::Logs),
+            "/shutdown" => Some(Self::Shutdown),
+            "/debug/pprof/profile.pb.gz" => Some(Self::Profile),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Metrics => "metrics",
+            Self::Ready => "ready",
+            Self::Live => "live",
+            Self::Env => "env",
+            Self::InboundPorts => "inbound-ports",
+            Self::RolloutGuards => "rollout-guards",
+            Self::Breakers => "breakers",
+            Self::BackendFaults => "backend-faults",
+            Self::DiscoveryCache => "discovery-cache",
+            Self::PortMappings => "port-mappings",
+            Self::Panics => "panics",
+            Self::LogLevel => "proxy-log-level",
+            Self::Logs => "logs",
+            Self::Shutdown => "shutdown",
+            Self::Profile => "pprof",
+        }
+    }
+
+    /// Returns true if the endpoint mutates the proxy's state or exposes
+    /// sensitive diagnostics, so that it may only be served to clients on the
+    /// loopback interface.
+    pub fn is_local_only(&self) -> bool {
+        matches!(
+            self,
+            Self::Env
+                | Self::BackendFaults
+                | Self::Panics
+                | Self::LogLevel
+                | Self::Logs
+                | Self::Shutdown
+                | Self::Profile
+        )
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = InvalidEndpoint;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|e| e.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| InvalidEndpoint(s.to_string()))
+    }
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// === impl Endpoints ===
+
+impl Endpoints {
+    /// Returns all endpoints.
+    pub fn all() -> Self {
+        Endpoint::ALL.into_iter().collect()
+    }
+
+    /// Returns all endpoints that may be served to remote clients.
+    pub fn read_only() -> Self {
+        Self::all().without_local_only()
+    }
+
+    pub fn contains(&self, endpoint: Endpoint) -> bool {
+        self.0.contains(&endpoint)
+    }
+
+    /// Returns true if any of the endpoints may only be served to local
+    /// clients.
+    pub fn has_local_only(&self) -> bool {
+        self.0.iter().any(Endpoint::is_local_only)
+    }
+
+    /// Returns the endpoints without those that may only be served to local
+    /// clients.
+    pub fn without_local_only(self) -> Self {
+        self.0.into_iter().filter(|e| !e.is_local_only()).collect()
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl FromIterator<Endpoint> for Endpoints {
+    fn from_iter<I: IntoIterator<Item = Endpoint>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names() {
+        for endpoint in Endpoint::ALL {
+            assert_eq!(endpoint.name().parse::<Endpoint>().unwrap(), endpoint);
+        }
+        assert!("tasks".parse::<Endpoint>().is_err());
+    }
+
+    #[test]
+    fn read_only_excludes_local_endpoints() {
+        let read_only = Endpoints::read_only();
+        assert!(!read_only.has_local_only());
+        assert!(read_only.contains(Endpoint::Metrics));
+        assert!(!read_only.contains(Endpoint::Shutdown));
+        // The environment includes credentials and identity key paths.
+        assert!(!read_only.contains(Endpoint::Env));
+        assert!(Endpoints::all().has_local_only());
+    }
+}
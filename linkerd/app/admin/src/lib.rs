@@ -1,10 +1,12 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
+mod endpoints;
 #[cfg(feature = "pprof")]
 mod pprof;
 mod server;
 mod stack;
 
-pub use self::server::{Admin, Latch, Readiness};
-pub use self::stack::{Config, Task};
+pub use self::endpoints::{Endpoint, Endpoints, InvalidEndpoint};
+pub use self::server::{Admin, Checks, Direction, Health, Latch, Readiness};
+pub use self::stack::{Config, ScrapeConfig, ScrapeMetrics, Task};
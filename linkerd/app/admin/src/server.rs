@@ -2,41 +2,87 @@
 //!
 //! * `GET /metrics` -- reports prometheus-formatted metrics.
 //! * `GET /ready` -- returns 200 when the proxy is ready to participate in meshed
-//!   traffic.
+//!   traffic, i.e. when both its inbound and outbound directions are ready.
+//! * `GET /ready/inbound` -- returns 200 when the proxy is ready to serve
+//!   inbound traffic.
+//! * `GET /ready/outbound` -- returns 200 when the proxy is ready to serve
+//!   outbound traffic.
 //! * `GET /live` -- returns 200 when the proxy is live.
 //! * `GET /proxy-log-level` -- returns the current proxy tracing filter.
 //! * `PUT /proxy-log-level` -- sets a new tracing filter.
 //! * `GET /tasks` -- returns a dump of spawned Tokio tasks (when enabled by the
 //!   tracing configuration).
+//! * `GET /inbound-ports.json` -- returns the policy, protocol, and forwarding
+//!   state of each inbound port on which connections have been accepted.
+//! * `GET /rollout-guards.json` -- returns the state of each outbound route's
+//!   rollout guard.
+//! * `GET /breakers.json` -- returns the latency outlier state of each outbound
+//!   balancer's endpoints and the state of the balancer's endpoint discovery.
+//! * `GET /backend-faults.json` -- returns the faults that are injected into
+//!   requests to outbound backends.
+//! * `POST /backend-faults.json` -- injects faults into requests to an outbound
+//!   backend until they expire.
+//! * `DELETE /backend-faults.json` -- stops injecting faults into requests to an
+//!   outbound backend.
+//! * `GET /discovery-cache.json` -- returns the outbound discovery cache entries
+//!   that are retained beyond the idle timeout and why.
+//! * `GET /port-mappings.json` -- returns the configured outbound port mappings
+//!   and the number of times each has been applied.
+//! * `GET /panics.json` -- returns the most recent panics in the proxy's tasks,
+//!   newest first, with their backtraces.
 //! * `POST /shutdown` -- shuts down the proxy.
+//!
+//! Each listener may serve a subset of these endpoints. Requests for endpoints
+//! that are not served return 404.
 
+use crate::{Endpoint, Endpoints};
 use futures::future::{self, TryFutureExt};
 use http::StatusCode;
 use linkerd_app_core::{
     metrics::{self as metrics, legacy::FmtMetrics},
+    panics,
     proxy::http::{Body, BoxBody, ClientHandle, Request, Response},
-    trace, Error, Result,
+    svc::idle_cache::Periodic,
+    trace,
+    transport::OrigDstAddr,
+    Error, Result,
+};
+use linkerd_app_inbound::{self as inbound, ports::PortRegistry};
+use linkerd_app_outbound::{
+    http::{policy::RolloutGuards, BackendFaults, Breakers},
+    PortMapTarget, PortMappings,
 };
 use std::{
     future::Future,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
-use tokio::sync::mpsc;
+use tokio::{sync::mpsc, time};
 
+mod backend_faults;
 mod json;
 mod log;
 mod readiness;
 
-pub use self::readiness::{Latch, Readiness};
+pub use self::readiness::{Checks, Direction, Health, Latch, Readiness};
 
 #[derive(Clone)]
 pub struct Admin<M> {
     metrics: metrics::legacy::Serve<M>,
     tracing: trace::Handle,
     ready: Readiness,
+    health: Health,
+    checks: Checks,
     shutdown_tx: mpsc::UnboundedSender<()>,
     enable_shutdown: bool,
+    endpoints: Endpoints,
+    inbound_ports: PortRegistry,
+    rollout_guards: RolloutGuards,
+    breakers: Breakers,
+    backend_faults: BackendFaults,
+    discovery_retention: Option<Arc<Periodic<OrigDstAddr>>>,
+    port_mappings: PortMappings,
     #[cfg(feature = "pprof")]
     pprof: Option<crate::pprof::Pprof>,
 }
@@ -54,34 +100,113 @@ impl<M> Admin<M> {
         Self {
             metrics: metrics::legacy::Serve::new(metrics),
             ready,
+            health: Health::default(),
+            checks: Checks::default(),
             shutdown_tx,
             enable_shutdown,
             tracing,
+            endpoints: Endpoints::all(),
+            inbound_ports: PortRegistry::default(),
+            rollout_guards: RolloutGuards::default(),
+            breakers: Breakers::default(),
+            backend_faults: BackendFaults::default(),
+            discovery_retention: None,
+            port_mappings: PortMappings::default(),
 
             #[cfg(feature = "pprof")]
             pprof: None,
         }
     }
 
+    /// Reports the process as not ready while the data path is unhealthy.
+    pub fn with_health(mut self, health: Health) -> Self {
+        self.health = health;
+        self
+    }
+
+    /// Reports each direction as not ready while any of its checks fail.
+    pub fn with_checks(mut self, checks: Checks) -> Self {
+        self.checks = checks;
+        self
+    }
+
+    /// Limits the endpoints that are served.
+    pub fn with_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    pub fn with_inbound_ports(mut self, ports: PortRegistry) -> Self {
+        self.inbound_ports = ports;
+        self
+    }
+
+    pub fn with_rollout_guards(mut self, guards: RolloutGuards) -> Self {
+        self.rollout_guards = guards;
+        self
+    }
+
+    pub fn with_breakers(mut self, breakers: Breakers) -> Self {
+        self.breakers = breakers;
+        self
+    }
+
+    pub fn with_backend_faults(mut self, faults: BackendFaults) -> Self {
+        self.backend_faults = faults;
+        self
+    }
+
+    pub fn with_discovery_retention(
+        mut self,
+        retention: Option<Arc<Periodic<OrigDstAddr>>>,
+    ) -> Self {
+        self.discovery_retention = retention;
+        self
+    }
+
+    pub fn with_port_mappings(mut self, mappings: PortMappings) -> Self {
+        self.port_mappings = mappings;
+        self
+    }
+
     #[cfg(feature = "pprof")]
     pub fn with_profiling(mut self, enabled: bool) -> Self {
         self.pprof = enabled.then_some(crate::pprof::Pprof);
         self
     }
 
-    fn ready_rsp(&self) -> Response<BoxBody> {
-        if self.ready.is_ready() {
-            Response::builder()
-                .status(StatusCode::OK)
-                .header(http::header::CONTENT_TYPE, "text/plain")
-                .body(BoxBody::from_static("ready\n"))
-                .expect("builder with known status code must not fail")
-        } else {
-            Response::builder()
+    /// Reports the readiness of the given directions. The process's readiness
+    /// and health apply to all directions.
+    fn ready_rsp(&self, directions: &[Direction]) -> Response<BoxBody> {
+        if !self.ready.is_ready() {
+            return Response::builder()
                 .status(StatusCode::SERVICE_UNAVAILABLE)
                 .body(BoxBody::from_static("not ready\n"))
-                .expect("builder with known status code must not fail")
+                .expect("builder with known status code must not fail");
+        }
+        if !self.health.is_healthy() {
+            return Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body(BoxBody::from_static("data path unhealthy\n"))
+                .expect("builder with known status code must not fail");
         }
+        for direction in directions {
+            let failing = self.checks.failing(*direction);
+            if !failing.is_empty() {
+                return Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(BoxBody::new(format!(
+                        "{direction} not ready: {}\n",
+                        failing.join(", ")
+                    )))
+                    .expect("builder with known status code must not fail");
+            }
+        }
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(http::header::CONTENT_TYPE, "text/plain")
+            .body(BoxBody::from_static("ready\n"))
+            .expect("builder with known status code must not fail")
     }
 
     fn live_rsp() -> Response<BoxBody> {
@@ -138,6 +263,247 @@ impl<M> Admin<M> {
         json::json_rsp(&env)
     }
 
+    fn inbound_ports_rsp<B>(&self, req: Request<B>) -> Response<BoxBody> {
+        if req.method() != http::Method::GET {
+            return Self::method_not_allowed();
+        }
+
+        if let Err(not_acceptable) = json::accepts_json(&req) {
+            return not_acceptable;
+        }
+
+        let ports = self
+            .inbound_ports
+            .ports()
+            .into_iter()
+            .map(|p| {
+                serde_json::json!({
+                    "port": p.port,
+                    "policy_protocol": p.policy_protocol,
+                    "server": {
+                        "group": p.server.group(),
+                        "kind": p.server.kind(),
+                        "name": p.server.name(),
+                        "default": matches!(*p.server, inbound::policy::Meta::Default { .. }),
+                    },
+                    "connections": p.connections,
+                    "denied_connections": p.denied_connections,
+                    "protocols": {
+                        "http1": p.protocols.http1,
+                        "http2": p.protocols.http2,
+                        "opaque": p.protocols.opaque,
+                        "tls": p.protocols.tls,
+                    },
+                    "detection": {
+                        "http1": p.detection.http1,
+                        "http2": p.detection.http2,
+                        "not_http": p.detection.not_http,
+                        "read_timeout": p.detection.read_timeout,
+                    },
+                    "forward": p.forward.map(|f| serde_json::json!({
+                        "target": f.target.to_string(),
+                        "connections": f.connections,
+                        "failures": f.failures,
+                        "last_error": f.last_error,
+                    })),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json::json_rsp(&serde_json::json!({ "ports": ports }))
+    }
+
+    fn rollout_guards_rsp<B>(&self, req: Request<B>) -> Response<BoxBody> {
+        if req.method() != http::Method::GET {
+            return Self::method_not_allowed();
+        }
+
+        if let Err(not_acceptable) = json::accepts_json(&req) {
+            return not_acceptable;
+        }
+
+        let meta = |m: &linkerd_app_outbound::policy::Meta| {
+            serde_json::json!({
+                "group": m.group(),
+                "kind": m.kind(),
+                "namespace": m.namespace(),
+                "name": m.name(),
+            })
+        };
+        let window = |w: linkerd_app_outbound::http::policy::WindowCounts| {
+            serde_json::json!({
+                "requests": w.requests,
+                "failures": w.failures,
+            })
+        };
+        let guards = self
+            .rollout_guards
+            .guards()
+            .into_iter()
+            .map(|g| {
+                serde_json::json!({
+                    "parent": meta(&g.parent),
+                    "route": meta(&g.route),
+                    "canary": meta(&g.canary),
+                    "state": if g.tripped { "tripped" } else { "active" },
+                    "cooldown_remaining_seconds": g.cooldown_remaining.map(|d| d.as_secs_f64()),
+                    "trips": g.trips,
+                    "primary_window": window(g.primary_window),
+                    "canary_window": window(g.canary_window),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json::json_rsp(&serde_json::json!({ "rollout_guards": guards }))
+    }
+
+    fn breakers_rsp<B>(&self, req: Request<B>) -> Response<BoxBody> {
+        if req.method() != http::Method::GET {
+            return Self::method_not_allowed();
+        }
+
+        if let Err(not_acceptable) = json::accepts_json(&req) {
+            return not_acceptable;
+        }
+
+        let meta = |m: &linkerd_app_outbound::policy::Meta| {
+            serde_json::json!({
+                "group": m.group(),
+                "kind": m.kind(),
+                "namespace": m.namespace(),
+                "name": m.name(),
+            })
+        };
+        let breakers = self
+            .breakers
+            .breakers()
+            .into_iter()
+            .map(|b| {
+                let endpoints = b
+                    .endpoints
+                    .into_iter()
+                    .map(|ep| {
+                        let remaining = ep.ejection_remaining.map(|d| d.as_secs_f64());
+                        serde_json::json!({
+                            "addr": ep.addr.to_string(),
+                            "state": if remaining.is_some() { "ejected" } else { "active" },
+                            "latency_seconds": ep.latency.map(|d| d.as_secs_f64()),
+                            "ejection_remaining_seconds": remaining,
+                            "ejections": ep.ejections,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::json!({
+                    "parent": meta(&b.parent),
+                    "backend": meta(&b.backend),
+                    "discovery": b.discovery.map(|d| d.as_str()),
+                    "endpoints": endpoints,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json::json_rsp(&serde_json::json!({ "breakers": breakers }))
+    }
+
+    fn discovery_cache_rsp<B>(&self, req: Request<B>) -> Response<BoxBody> {
+        if req.method() != http::Method::GET {
+            return Self::method_not_allowed();
+        }
+
+        if let Err(not_acceptable) = json::accepts_json(&req) {
+            return not_acceptable;
+        }
+
+        let now = time::Instant::now();
+        let retained = self
+            .discovery_retention
+            .iter()
+            .flat_map(|p| p.retained())
+            .map(|r| {
+                let intervals = r
+                    .intervals
+                    .iter()
+                    .map(|i| i.as_secs_f64())
+                    .collect::<Vec<_>>();
+                serde_json::json!({
+                    "addr": r.key.0.to_string(),
+                    "reason": "periodic",
+                    "period_seconds": r.period.as_secs_f64(),
+                    "intervals_seconds": intervals,
+                    "retained_seconds": now.saturating_duration_since(r.since).as_secs_f64(),
+                    "expires_in_seconds": r.until.saturating_duration_since(now).as_secs_f64(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json::json_rsp(&serde_json::json!({
+            "periodic_retention": self.discovery_retention.is_some(),
+            "retained": retained,
+        }))
+    }
+
+    fn port_mappings_rsp<B>(&self, req: Request<B>) -> Response<BoxBody> {
+        if req.method() != http::Method::GET {
+            return Self::method_not_allowed();
+        }
+
+        if let Err(not_acceptable) = json::accepts_json(&req) {
+            return not_acceptable;
+        }
+
+        let mappings = self
+            .port_mappings
+            .mappings()
+            .into_iter()
+            .map(|m| {
+                let target = match m.mapping.target {
+                    PortMapTarget::Port(port) => serde_json::json!({ "port": port }),
+                    PortMapTarget::Authority(ref name) => {
+                        serde_json::json!({ "authority": name.to_string() })
+                    }
+                };
+                serde_json::json!({
+                    "net": m.mapping.net.map(|n| n.to_string()),
+                    "port": m.mapping.port,
+                    "target": target,
+                    "applied": m.applied,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json::json_rsp(&serde_json::json!({ "port_mappings": mappings }))
+    }
+
+    fn panics_rsp<B>(req: Request<B>) -> Response<BoxBody> {
+        if req.method() != http::Method::GET {
+            return Self::method_not_allowed();
+        }
+
+        if let Err(not_acceptable) = json::accepts_json(&req) {
+            return not_acceptable;
+        }
+
+        let panics = panics::recent()
+            .into_iter()
+            .rev()
+            .map(|p| {
+                let time = p
+                    .time
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default();
+                serde_json::json!({
+                    "timestamp_seconds": time.as_secs_f64(),
+                    "thread": p.thread,
+                    "location": p.location,
+                    "message": p.message,
+                    "backtrace": p.backtrace,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json::json_rsp(&serde_json::json!({ "panics": panics }))
+    }
+
     fn shutdown(&self) -> Response<BoxBody> {
         if !self.enable_shutdown {
             return Response::builder()
@@ -226,9 +592,17 @@ where
     }
 
     fn call(&mut self, req: Request<B>) -> Self::Future {
+        if let Some(endpoint) = Endpoint::from_path(req.uri().path()) {
+            if !self.endpoints.contains(endpoint) {
+                return Box::pin(future::ok(Self::not_found()));
+            }
+        }
+
         match req.uri().path() {
             "/live" => Box::pin(future::ok(Self::live_rsp())),
-            "/ready" => Box::pin(future::ok(self.ready_rsp())),
+            "/ready" => Box::pin(future::ok(self.ready_rsp(&Direction::ALL))),
+            "/ready/inbound" => Box::pin(future::ok(self.ready_rsp(&[Direction::Inbound]))),
+            "/ready/outbound" => Box::pin(future::ok(self.ready_rsp(&[Direction::Outbound]))),
             "/metrics" => {
                 let rsp = self.metrics.serve(req).unwrap_or_else(|error| {
                     ::tracing::error!(%error, "Failed to format metrics");
@@ -267,7 +641,42 @@ where
                 )
             }
 
-            "/env.json" => Box::pin(future::ok(Self::env_rsp(req))),
+            "/env.json" => {
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+                Box::pin(future::ok(Self::env_rsp(req)))
+            }
+
+            "/inbound-ports.json" => Box::pin(future::ok(self.inbound_ports_rsp(req))),
+
+            "/rollout-guards.json" => Box::pin(future::ok(self.rollout_guards_rsp(req))),
+
+            "/breakers.json" => Box::pin(future::ok(self.breakers_rsp(req))),
+
+            "/backend-faults.json" => {
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+
+                Box::pin(
+                    backend_faults::serve(self.backend_faults.clone(), req).or_else(|error| {
+                        tracing::error!(error, "Failed to configure backend faults");
+                        future::ok(Self::internal_error_rsp(error))
+                    }),
+                )
+            }
+
+            "/discovery-cache.json" => Box::pin(future::ok(self.discovery_cache_rsp(req))),
+
+            "/port-mappings.json" => Box::pin(future::ok(self.port_mappings_rsp(req))),
+
+            "/panics.json" => {
+                if !Self::client_is_localhost(&req) {
+                    return Box::pin(future::ok(Self::forbidden_not_localhost()));
+                }
+                Box::pin(future::ok(Self::panics_rsp(req)))
+            }
 
             "/shutdown" => {
                 if req.method() == http::Method::POST {
@@ -344,4 +753,127 @@ mod tests {
         drop(l1);
         assert_eq!(call!().status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn not_ready_when_unhealthy() {
+        let (r, l) = Readiness::new();
+        drop(l);
+        let health = Health::default();
+
+        let (_, t) = trace::Settings::default().build();
+        let (s, _) = mpsc::unbounded_channel();
+        let admin = Admin::new((), r, s, true, t).with_health(health.clone());
+        macro_rules! call {
+            () => {{
+                let r = Request::builder()
+                    .method(Method::GET)
+                    .uri("http://0.0.0.0/ready")
+                    .body(BoxBody::empty())
+                    .unwrap();
+                let f = admin.clone().oneshot(r);
+                timeout(TIMEOUT, f).await.expect("timeout").expect("call")
+            }};
+        }
+
+        assert_eq!(call!().status(), StatusCode::OK);
+
+        health.set_healthy(false);
+        assert_eq!(call!().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        health.set_healthy(true);
+        assert_eq!(call!().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn ready_by_direction() {
+        let (r, l) = Readiness::new();
+        let health = Health::default();
+        let checks = Checks::default();
+        let inbound = checks.register(Direction::Inbound, "listener");
+        let outbound = checks.register(Direction::Outbound, "policy");
+
+        let (_, t) = trace::Settings::default().build();
+        let (s, _) = mpsc::unbounded_channel();
+        let admin = Admin::new((), r, s, true, t)
+            .with_health(health.clone())
+            .with_checks(checks);
+        let status = |path: &'static str| {
+            let req = Request::builder()
+                .method(Method::GET)
+                .uri(format!("http://0.0.0.0{path}"))
+                .body(BoxBody::empty())
+                .unwrap();
+            let rsp = timeout(TIMEOUT, admin.clone().oneshot(req));
+            async move { rsp.await.expect("timeout").expect("call").status() }
+        };
+        macro_rules! assert_ready {
+            ($ready:expr, $inbound:expr, $outbound:expr) => {{
+                let ok = |ok: bool| {
+                    if ok {
+                        StatusCode::OK
+                    } else {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    }
+                };
+                assert_eq!(status("/ready").await, ok($ready), "/ready");
+                assert_eq!(
+                    status("/ready/inbound").await,
+                    ok($inbound),
+                    "/ready/inbound"
+                );
+                assert_eq!(
+                    status("/ready/outbound").await,
+                    ok($outbound),
+                    "/ready/outbound"
+                );
+            }};
+        }
+
+        // Neither direction is ready until the process is ready.
+        assert_ready!(false, false, false);
+        drop(l);
+        assert_ready!(true, true, true);
+
+        outbound.set_healthy(false);
+        assert_ready!(false, true, false);
+        outbound.set_healthy(true);
+        assert_ready!(true, true, true);
+
+        inbound.set_healthy(false);
+        assert_ready!(false, false, true);
+        inbound.set_healthy(true);
+        assert_ready!(true, true, true);
+
+        // The data path's health applies to both directions.
+        health.set_healthy(false);
+        assert_ready!(false, false, false);
+        health.set_healthy(true);
+        assert_ready!(true, true, true);
+    }
+
+    #[tokio::test]
+    async fn omitted_endpoints_not_found() {
+        let (r, _l) = Readiness::new();
+        let (_, t) = trace::Settings::default().build();
+        let (s, _) = mpsc::unbounded_channel();
+        let admin = Admin::new((), r, s, true, t).with_endpoints(Endpoints::read_only());
+
+        let call = |path: &str| {
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri(format!("http://0.0.0.0{path}"))
+                .body(BoxBody::empty())
+                .unwrap();
+            timeout(TIMEOUT, admin.clone().oneshot(req))
+        };
+
+        let rsp = call("/shutdown").await.expect("timeout").expect("call");
+        assert_eq!(rsp.status(), StatusCode::NOT_FOUND);
+
+        let rsp = call("/env.json").await.expect("timeout").expect("call");
+        assert_eq!(rsp.status(), StatusCode::NOT_FOUND);
+
+        let rsp = call("/live").await.expect("timeout").expect("call");
+        assert_eq!(rsp.status(), StatusCode::OK);
+    }
 }
@@ -0,0 +1,252 @@
+//! Configures the faults that are injected into requests to outbound backends.
+//!
+//! * `GET` returns the active faults.
+//! * `POST` injects a fault, e.g.
+//!   `{"target": {"concrete": "web.ns.svc.cluster.local:80"}, "latency_seconds": 0.5, "ttl_seconds": 300}`
+//!   or `{"target": {"endpoint": "10.1.2.3"}, "fail": true, "ttl_seconds": 60}`,
+//!   replacing any fault for the same target.
+//! * `DELETE` removes the fault for a target, e.g.
+//!   `{"target": {"endpoint": "10.1.2.3"}}`.
+
+use super::json;
+use bytes::Buf;
+use http::{header, StatusCode};
+use linkerd_app_core::{
+    proxy::http::{Body, BoxBody},
+    Error,
+};
+use linkerd_app_outbound::http::{
+    BackendFault, BackendFaultState, BackendFaultTarget, BackendFaults,
+};
+use std::{io, time::Duration};
+
+pub async fn serve<B>(
+    faults: BackendFaults,
+    req: http::Request<B>,
+) -> Result<http::Response<BoxBody>, Error>
+where
+    B: Body,
+    B::Error: Into<Error>,
+{
+    if let Err(not_acceptable) = json::accepts_json(&req) {
+        return Ok(not_acceptable);
+    }
+
+    Ok(match *req.method() {
+        http::Method::GET => faults_rsp(&faults),
+
+        http::Method::POST => {
+            let body = match read_json(req).await? {
+                Ok(body) => body,
+                Err(rsp) => return Ok(rsp),
+            };
+            let parsed = parse_target(&body).and_then(|target| {
+                let (fault, ttl) = parse_fault(&body)?;
+                Ok((target, fault, ttl))
+            });
+            let (target, fault, ttl) = match parsed {
+                Ok(parsed) => parsed,
+                Err(error) => return Ok(json::json_error_rsp(error, StatusCode::BAD_REQUEST)),
+            };
+            if let Err(error) = faults.insert(target, fault, ttl) {
+                return Ok(json::json_error_rsp(error, StatusCode::BAD_REQUEST));
+            }
+            faults_rsp(&faults)
+        }
+
+        http::Method::DELETE => {
+            let body = match read_json(req).await? {
+                Ok(body) => body,
+                Err(rsp) => return Ok(rsp),
+            };
+            let target = match parse_target(&body) {
+                Ok(target) => target,
+                Err(error) => return Ok(json::json_error_rsp(error, StatusCode::BAD_REQUEST)),
+            };
+            if !faults.remove(&target) {
+                return Ok(json::json_error_rsp(
+                    format!("no fault is active for {target}"),
+                    StatusCode::NOT_FOUND,
+                ));
+            }
+            faults_rsp(&faults)
+        }
+
+        _ => http::Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(header::ALLOW, "GET")
+            .header(header::ALLOW, "POST")
+            .header(header::ALLOW, "DELETE")
+            .body(BoxBody::empty())
+            .expect("builder with known status code must not fail"),
+    })
+}
+
+fn faults_rsp(faults: &BackendFaults) -> http::Response<BoxBody> {
+    let faults = faults
+        .faults()
+        .into_iter()
+        .map(
+            |BackendFaultState {
+                 target,
+                 fault,
+                 remaining,
+                 injected,
+             }| {
+                let target = match target {
+                    BackendFaultTarget::Concrete(name) => {
+                        serde_json::json!({ "concrete": name.to_string() })
+                    }
+                    BackendFaultTarget::Endpoint(ip) => {
+                        serde_json::json!({ "endpoint": ip.to_string() })
+                    }
+                };
+                serde_json::json!({
+                    "target": target,
+                    "latency_seconds": fault.latency.map(|d| d.as_secs_f64()),
+                    "fail": fault.fail,
+                    "remaining_seconds": remaining.as_secs_f64(),
+                    "injected": injected,
+                })
+            },
+        )
+        .collect::<Vec<_>>();
+    json::json_rsp(&serde_json::json!({ "backend_faults": faults }))
+}
+
+async fn read_json<B>(
+    req: http::Request<B>,
+) -> Result<Result<serde_json::Value, http::Response<BoxBody>>, Error>
+where
+    B: Body,
+    B::Error: Into<Error>,
+{
+    use http_body_util::BodyExt;
+    let body = req
+        .into_body()
+        .collect()
+        .await
+        .map_err(io::Error::other)?
+        .aggregate();
+    Ok(serde_json::from_reader(body.reader())
+        .map_err(|error| json::json_error_rsp(error, StatusCode::BAD_REQUEST)))
+}
+
+fn parse_target(body: &serde_json::Value) -> Result<BackendFaultTarget, String> {
+    let target = body
+        .get("target")
+        .and_then(serde_json::Value::as_object)
+        .ok_or("a target object is required")?;
+    match (target.get("concrete"), target.get("endpoint")) {
+        (Some(name), None) => name
+            .as_str()
+            .and_then(|n| n.parse().ok())
+            .map(BackendFaultTarget::Concrete)
+            .ok_or_else(|| format!("invalid concrete address: {name}")),
+        (None, Some(ip)) => ip
+            .as_str()
+            .and_then(|ip| ip.parse().ok())
+            .map(BackendFaultTarget::Endpoint)
+            .ok_or_else(|| format!("invalid endpoint IP address: {ip}")),
+        _ => Err("a target must name either a concrete address or an endpoint".to_string()),
+    }
+}
+
+fn parse_fault(body: &serde_json::Value) -> Result<(BackendFault, Duration), String> {
+    let seconds = |key: &str| -> Result<Option<Duration>, String> {
+        match body.get(key) {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(v) => v
+                .as_f64()
+                .and_then(|s| Duration::try_from_secs_f64(s).ok())
+                .map(Some)
+                .ok_or_else(|| format!("invalid {key}: {v}")),
+        }
+    };
+    let fail = match body.get("fail") {
+        None => false,
+        Some(v) => v.as_bool().ok_or_else(|| format!("invalid fail: {v}"))?,
+    };
+    let latency = seconds("latency_seconds")?;
+    let ttl = seconds("ttl_seconds")?.ok_or("ttl_seconds is required")?;
+    Ok((BackendFault { latency, fail }, ttl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn call(
+        faults: &BackendFaults,
+        method: http::Method,
+        body: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        use http_body_util::BodyExt;
+        let req = http::Request::builder()
+            .method(method)
+            .uri("http://0.0.0.0/backend-faults.json")
+            .body(BoxBody::new(body.to_string()))
+            .unwrap();
+        let rsp = serve(faults.clone(), req).await.expect("must serve");
+        let status = rsp.status();
+        let body = rsp.into_body().collect().await.unwrap().aggregate();
+        (status, serde_json::from_reader(body.reader()).unwrap())
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn injects_and_removes_faults() {
+        let faults = BackendFaults::default();
+        let target = serde_json::json!({ "endpoint": "10.1.2.3" });
+
+        let (status, body) = call(
+            &faults,
+            http::Method::POST,
+            serde_json::json!({ "target": target, "fail": true, "ttl_seconds": 60 }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({ "backend_faults": [{
+                "target": target,
+                "latency_seconds": null,
+                "fail": true,
+                "remaining_seconds": 60.0,
+                "injected": 0,
+            }] })
+        );
+
+        let (status, body) = call(
+            &faults,
+            http::Method::DELETE,
+            serde_json::json!({ "target": target }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "backend_faults": [] }));
+
+        let (status, _) = call(
+            &faults,
+            http::Method::DELETE,
+            serde_json::json!({ "target": target }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn rejects_invalid_faults() {
+        let faults = BackendFaults::default();
+        for body in [
+            serde_json::json!({ "target": { "endpoint": "10.1.2.3" }, "fail": true }),
+            serde_json::json!({ "target": { "endpoint": "web" }, "fail": true, "ttl_seconds": 1 }),
+            serde_json::json!({ "target": {}, "fail": true, "ttl_seconds": 1 }),
+            serde_json::json!({ "target": { "concrete": "web:80" }, "ttl_seconds": 1 }),
+            serde_json::json!({ "target": { "concrete": "web:80" }, "fail": true, "ttl_seconds": 86400 }),
+        ] {
+            let (status, _) = call(&faults, http::Method::POST, body.clone()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        }
+        assert!(faults.faults().is_empty());
+    }
+}
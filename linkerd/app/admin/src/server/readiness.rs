@@ -1,4 +1,8 @@
-use std::sync::{Arc, Weak};
+use parking_lot::RwLock;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Weak,
+};
 
 /// Tracks the processes's readiness to serve traffic.
 ///
@@ -10,6 +14,36 @@ pub struct Readiness(Weak<()>);
 #[derive(Clone, Debug)]
 pub struct Latch(#[allow(dead_code)] Arc<()>);
 
+/// Tracks whether the process's data path is serving connections.
+///
+/// Unlike readiness, health may change at any time. The process is only ready
+/// while it is healthy.
+#[derive(Clone, Debug)]
+pub struct Health(Arc<AtomicBool>);
+
+/// A direction in which the proxy serves traffic.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// Tracks the named checks that determine whether each direction is ready.
+///
+/// Checks may be registered by any component that observes the state of a
+/// direction (e.g. its listeners or its policies). A direction is ready while
+/// all of its checks are healthy, so that one direction may report an
+/// impairment while the other continues to serve traffic.
+#[derive(Clone, Debug, Default)]
+pub struct Checks(Arc<RwLock<Vec<Check>>>);
+
+#[derive(Debug)]
+struct Check {
+    direction: Direction,
+    name: &'static str,
+    health: Health,
+}
+
 impl Readiness {
     pub fn new() -> (Readiness, Latch) {
         let r = Arc::new(());
@@ -34,3 +68,92 @@ impl Latch {
         drop(self);
     }
 }
+
+impl Health {
+    pub fn is_healthy(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    pub fn set_healthy(&self, healthy: bool) {
+        self.0.store(healthy, Ordering::Release);
+    }
+}
+
+/// Healthy until marked otherwise.
+impl Default for Health {
+    fn default() -> Self {
+        Self(Arc::new(AtomicBool::new(true)))
+    }
+}
+
+// === impl Direction ===
+
+impl Direction {
+    pub const ALL: [Self; 2] = [Self::Inbound, Self::Outbound];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Inbound => "inbound",
+            Self::Outbound => "outbound",
+        }
+    }
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// === impl Checks ===
+
+impl Checks {
+    /// Registers a check for `direction`, returning a handle through which its
+    /// state is updated. Checks are healthy until marked otherwise.
+    pub fn register(&self, direction: Direction, name: &'static str) -> Health {
+        let health = Health::default();
+        self.0.write().push(Check {
+            direction,
+            name,
+            health: health.clone(),
+        });
+        health
+    }
+
+    /// Returns the names of the unhealthy checks for `direction`.
+    pub fn failing(&self, direction: Direction) -> Vec<&'static str> {
+        self.0
+            .read()
+            .iter()
+            .filter(|c| c.direction == direction && !c.health.is_healthy())
+            .map(|c| c.name)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_are_scoped_to_directions() {
+        let checks = Checks::default();
+        let inbound = checks.register(Direction::Inbound, "listener");
+        let outbound = checks.register(Direction::Outbound, "listener");
+        let policy = checks.register(Direction::Outbound, "policy");
+        assert!(checks.failing(Direction::Inbound).is_empty());
+        assert!(checks.failing(Direction::Outbound).is_empty());
+
+        policy.set_healthy(false);
+        assert!(checks.failing(Direction::Inbound).is_empty());
+        assert_eq!(checks.failing(Direction::Outbound), vec!["policy"]);
+
+        outbound.set_healthy(false);
+        inbound.set_healthy(false);
+        assert_eq!(checks.failing(Direction::Inbound), vec!["listener"]);
+        assert_eq!(
+            checks.failing(Direction::Outbound),
+            vec!["listener", "policy"]
+        );
+    }
+}
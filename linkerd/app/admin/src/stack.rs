@@ -1,11 +1,13 @@
+use crate::Endpoints;
+use futures::FutureExt;
 use linkerd_app_core::{
     classify,
     config::ServerConfig,
     drain, errors, identity,
-    metrics::{self, legacy::FmtMetrics},
+    metrics::{self, legacy::FmtMetrics, prom},
     proxy::http,
     serve,
-    svc::{self, ExtractParam, InsertParam, Param},
+    svc::{self, idle_cache::Periodic, ExtractParam, InsertParam, Param},
     tls, trace,
     transport::{
         self, addrs::AddrPair, listen::Bind, ClientAddr, Local, OrigDstAddr, Remote, ServerAddr,
@@ -13,7 +15,8 @@ use linkerd_app_core::{
     Error, Result,
 };
 use linkerd_app_inbound as inbound;
-use std::{pin::Pin, time::Duration};
+use linkerd_app_outbound as outbound;
+use std::{collections::HashSet, net::SocketAddr, pin::Pin, sync::Arc, time::Duration};
 use thiserror::Error;
 use tokio::sync::mpsc;
 use tracing::debug;
@@ -21,15 +24,43 @@ use tracing::debug;
 #[derive(Clone, Debug)]
 pub struct Config {
     pub server: ServerConfig,
+
+    /// The endpoints served on the admin listener.
+    pub endpoints: Endpoints,
+
+    /// Configures an additional listener that serves read-only endpoints to
+    /// authorized scrapers.
+    pub scrape: Option<ScrapeConfig>,
+
     pub metrics_retain_idle: Duration,
     #[cfg(feature = "pprof")]
     pub enable_profiling: bool,
     pub enable_shutdown: bool,
 }
 
+/// Configures an admin listener that requires mesh mTLS.
+///
+/// Connections are only accepted from clients with one of the permitted
+/// identities. Endpoints that may only be served to local clients are never
+/// served on this listener.
+#[derive(Clone, Debug)]
+pub struct ScrapeConfig {
+    pub server: ServerConfig,
+    pub endpoints: Endpoints,
+    pub permitted_client_ids: HashSet<tls::ClientId>,
+}
+
+/// Counts the connections accepted on the scrape listener.
+#[derive(Clone, Debug, Default)]
+pub struct ScrapeMetrics {
+    connections: prom::Family<ScrapeLabels, prom::Counter>,
+}
+
 pub struct Task {
     pub listen_addr: Local<ServerAddr>,
+    pub scrape_addr: Option<Local<ServerAddr>>,
     pub latch: crate::Latch,
+    pub checks: crate::Checks,
     pub serve: Pin<Box<dyn std::future::Future<Output = ()> + Send + 'static>>,
 }
 
@@ -41,6 +72,37 @@ struct NonHttpClient(Remote<ClientAddr>);
 #[error("Unexpected TLS connection to {} from {}", self.0, self.1)]
 struct UnexpectedSni(tls::ServerName, Remote<ClientAddr>);
 
+#[derive(Debug, Error)]
+#[error("unauthorized scrape connection from {0}")]
+struct UnauthorizedScraper(Remote<ClientAddr>);
+
+#[derive(Debug, Error)]
+#[error("local-only admin endpoints may not be served on {0}; the admin listener must bind to a loopback address when the scrape listener is enabled")]
+struct LocalEndpointsNotLoopback(SocketAddr);
+
+/// Authorizes connections on the scrape listener by their mesh identity.
+#[derive(Clone, Debug)]
+struct Scrapers {
+    permitted: Arc<HashSet<tls::ClientId>>,
+    metrics: ScrapeMetrics,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct ScrapeLabels {
+    result: ScrapeResult,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum ScrapeResult {
+    /// The client presented a permitted identity.
+    authorized,
+    /// The client did not present a mesh identity.
+    unauthenticated,
+    /// The client's identity is not permitted.
+    unauthorized,
+}
+
 #[derive(Clone, Debug)]
 struct Tcp {
     policy: inbound::policy::AllowPolicy,
@@ -82,133 +144,268 @@ impl Config {
         identity: identity::Server,
         report: R,
         metrics: inbound::InboundMetrics,
+        scrape_metrics: ScrapeMetrics,
+        rollout_guards: outbound::http::policy::RolloutGuards,
+        breakers: outbound::http::Breakers,
+        backend_faults: outbound::http::BackendFaults,
+        discovery_retention: Option<Arc<Periodic<OrigDstAddr>>>,
+        port_mappings: outbound::PortMappings,
         trace: trace::Handle,
         drain: drain::Watch,
         shutdown: mpsc::UnboundedSender<()>,
     ) -> Result<Task>
     where
         R: FmtMetrics + Clone + Send + Sync + Unpin + 'static,
-        B: Bind<ServerConfig, BoundAddrs = Local<ServerAddr>>,
+        B: Bind<ServerConfig, BoundAddrs = Local<ServerAddr>> + Clone,
         B::Addrs: svc::Param<Remote<ClientAddr>>,
         B::Addrs: svc::Param<Local<ServerAddr>>,
         B::Addrs: svc::Param<AddrPair>,
     {
-        let (listen_addr, listen) = bind.bind(&self.server)?;
+        // When read-only endpoints are exposed to scrapers, endpoints that
+        // mutate the proxy are only served on the loopback interface.
+        if self.scrape.is_some() && self.endpoints.has_local_only() {
+            let addr = self.server.addr.0;
+            if !addr.ip().is_loopback() {
+                return Err(LocalEndpointsNotLoopback(addr).into());
+            }
+        }
 
-        // Get the policy for the admin server.
-        let policy = policy.get_policy(OrigDstAddr(listen_addr.into()));
+        let (listen_addr, listen) = bind.clone().bind(&self.server)?;
 
         let (ready, latch) = crate::server::Readiness::new();
+        let checks = crate::Checks::default();
 
         #[cfg_attr(not(feature = "pprof"), allow(unused_mut))]
-        let admin = crate::server::Admin::new(report, ready, shutdown, self.enable_shutdown, trace);
+        let admin = crate::server::Admin::new(report, ready, shutdown, self.enable_shutdown, trace)
+            .with_inbound_ports(metrics.ports.clone())
+            .with_rollout_guards(rollout_guards)
+            .with_breakers(breakers)
+            .with_backend_faults(backend_faults)
+            .with_discovery_retention(discovery_retention)
+            .with_port_mappings(port_mappings)
+            .with_checks(checks.clone());
 
         #[cfg(feature = "pprof")]
         let admin = admin.with_profiling(self.enable_profiling);
 
-        let http = svc::stack(move |_| admin.clone())
-            .push(
-                metrics
-                    .proxy
-                    .http_endpoint
-                    .to_layer::<classify::Response, _, Permitted>(),
-            )
-            .push(classify::NewClassify::layer_default())
-            .push_map_target(|(permit, http)| Permitted { permit, http })
-            .push(inbound::policy::NewHttpPolicy::layer(
-                metrics.http_authz.clone(),
-            ))
-            .push(Rescue::layer())
-            .push_on_service(http::BoxResponse::layer())
-            .arc_new_clone_http();
-
-        let inbound::DetectMetrics(detect_metrics) = metrics.detect.clone();
-        let tcp = http
-            .unlift_new()
-            .push(http::NewServeHttp::layer({
-                let drain = drain.clone();
-                move |t: &Http| {
-                    http::ServerParams {
-                        version: t.version,
-                        http2: Default::default(),
-                        drain: drain.clone(),
-                    }
-                }
-            }))
-            .push_filter(
-                |(http, tcp): (
-                    http::Detection,
-                    Tcp,
-                )| {
-                    match http {
-                        http::Detection::Http(version) => Ok(Http { version, tcp }),
-                        // If detection timed out, we can make an educated guess at the proper
-                        // behavior:
-                        // - If the connection was meshed, it was most likely transported over
-                        //   HTTP/2.
-                        // - If the connection was unmeshed, it was mostly likely HTTP/1.
-                        // - If we received some unexpected SNI, the client is mostly likely
-                        //   confused/stale.
-                        http::Detection::ReadTimeout(_timeout) => {
-                            let version = match tcp.tls {
-                                tls::ConditionalServerTls::None(_) => http::Variant::Http1,
-                                tls::ConditionalServerTls::Some(tls::ServerTls::Established {
-                                    ..
-                                }) => http::Variant::H2,
-                                tls::ConditionalServerTls::Some(tls::ServerTls::Passthru {
-                                    sni,
-                                }) => {
-                                    debug_assert!(false, "If we know the stream is non-mesh TLS, we should be able to prove its not HTTP.");
-                                    return Err(Error::from(UnexpectedSni(sni, tcp.client)));
-                                }
-                            };
-                            debug!(?version, "HTTP detection timed out; assuming HTTP");
-                            Ok(Http { version, tcp })
-                        }
-                        // If the connection failed HTTP detection, check if we detected TLS for
-                        // another target. This might indicate that the client is confused/stale.
-                        http::Detection::NotHttp => match tcp.tls {
-                            tls::ConditionalServerTls::Some(tls::ServerTls::Passthru { sni }) => {
-                                Err(UnexpectedSni(sni, tcp.client).into())
-                            }
-                            _ => Err(NonHttpClient(tcp.client).into()),
-                        },
-                    }
-                },
-            )
-            .arc_new_tcp()
-            .lift_new_with_target()
-            .push(http::NewDetect::layer(move |tcp: &Tcp| {
-                http::DetectParams {
-                    read_timeout: DETECT_TIMEOUT,
-                    metrics: detect_metrics.metrics(tcp.policy.server_label())
-                }
-            }))
-            .push(transport::metrics::NewServer::layer(metrics.proxy.transport))
-            .push_map_target(move |(tls, addrs): (tls::ConditionalServerTls, B::Addrs)| {
-                Tcp {
-                    tls,
-                    client: addrs.param(),
-                    addr: addrs.param(),
-                    policy: policy.clone(),
-                }
-            })
-            .arc_new_tcp()
-            .push(tls::NewDetectTls::<identity::Server, _, _>::layer(TlsParams {
-                identity,
-            }))
-            .arc_new_tcp()
-            .into_inner();
-
-        let serve = Box::pin(serve::serve(listen, tcp, drain.signaled()));
+        let serve = build_server::<B, R>(
+            listen,
+            admin.clone().with_endpoints(self.endpoints),
+            // Get the policy for the admin server.
+            policy.get_policy(OrigDstAddr(listen_addr.into())),
+            identity.clone(),
+            &metrics,
+            None,
+            drain.clone(),
+        );
+
+        let (scrape_addr, serve) = match self.scrape {
+            None => (None, serve),
+            Some(ScrapeConfig {
+                server,
+                endpoints,
+                permitted_client_ids,
+            }) => {
+                let (scrape_addr, listen) = bind.bind(&server)?;
+                let scrapers = Scrapers {
+                    permitted: Arc::new(permitted_client_ids),
+                    metrics: scrape_metrics,
+                };
+                let scrape = build_server::<B, R>(
+                    listen,
+                    admin.with_endpoints(endpoints.without_local_only()),
+                    policy.get_policy(OrigDstAddr(scrape_addr.into())),
+                    identity,
+                    &metrics,
+                    Some(scrapers),
+                    drain,
+                );
+                let serve: Pin<Box<dyn std::future::Future<Output = ()> + Send + 'static>> =
+                    Box::pin(futures::future::join(serve, scrape).map(|((), ())| ()));
+                (Some(scrape_addr), serve)
+            }
+        };
+
         Ok(Task {
             listen_addr,
+            scrape_addr,
             latch,
+            checks,
             serve,
         })
     }
 }
 
+/// Builds a server for an admin listener.
+///
+/// When `scrapers` is set, connections are only accepted from clients with a
+/// permitted mesh identity.
+fn build_server<B, R>(
+    listen: B::Incoming,
+    admin: crate::server::Admin<R>,
+    policy: inbound::policy::AllowPolicy,
+    identity: identity::Server,
+    metrics: &inbound::InboundMetrics,
+    scrapers: Option<Scrapers>,
+    drain: drain::Watch,
+) -> Pin<Box<dyn std::future::Future<Output = ()> + Send + 'static>>
+where
+    R: FmtMetrics + Clone + Send + Sync + Unpin + 'static,
+    B: Bind<ServerConfig, BoundAddrs = Local<ServerAddr>>,
+    B::Addrs: svc::Param<Remote<ClientAddr>>,
+    B::Addrs: svc::Param<Local<ServerAddr>>,
+    B::Addrs: svc::Param<AddrPair>,
+{
+    let http = svc::stack(move |_| admin.clone())
+        .push(
+            metrics
+                .proxy
+                .http_endpoint
+                .to_layer::<classify::Response, _, Permitted>(),
+        )
+        .push(classify::NewClassify::layer_default())
+        .push_map_target(|(permit, http)| Permitted { permit, http })
+        .push(inbound::policy::NewHttpPolicy::layer(
+            metrics.http_authz.clone(),
+        ))
+        .push(Rescue::layer())
+        .push_on_service(http::BoxResponse::layer())
+        .arc_new_clone_http();
+
+    let inbound::DetectMetrics(detect_metrics) = metrics.detect.clone();
+    let tcp = http
+        .unlift_new()
+        .push(http::NewServeHttp::layer({
+            let drain = drain.clone();
+            move |t: &Http| {
+                http::ServerParams {
+                    version: t.version,
+                    http2: Default::default(),
+                    drain: drain.clone(),
+                }
+            }
+        }))
+        .push_filter(
+            move |(http, tcp): (
+                http::Detection,
+                Tcp,
+            )| {
+                if let Some(scrapers) = scrapers.as_ref() {
+                    scrapers.authorize(&tcp)?;
+                }
+                match http {
+                    http::Detection::Http(version) => Ok(Http { version, tcp }),
+                    // If detection timed out, we can make an educated guess at the proper
+                    // behavior:
+                    // - If the connection was meshed, it was most likely transported over
+                    //   HTTP/2.
+                    // - If the connection was unmeshed, it was mostly likely HTTP/1.
+                    // - If we received some unexpected SNI, the client is mostly likely
+                    //   confused/stale.
+                    http::Detection::ReadTimeout(_timeout) => {
+                        let version = match tcp.tls {
+                            tls::ConditionalServerTls::None(_) => http::Variant::Http1,
+                            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                                ..
+                            }) => http::Variant::H2,
+                            tls::ConditionalServerTls::Some(tls::ServerTls::Passthru {
+                                sni,
+                            }) => {
+                                debug_assert!(false, "If we know the stream is non-mesh TLS, we should be able to prove its not HTTP.");
+                                return Err(Error::from(UnexpectedSni(sni, tcp.client)));
+                            }
+                        };
+                        debug!(?version, "HTTP detection timed out; assuming HTTP");
+                        Ok(Http { version, tcp })
+                    }
+                    // If the connection failed HTTP detection, check if we detected TLS for
+                    // another target. This might indicate that the client is confused/stale.
+                    http::Detection::NotHttp => match tcp.tls {
+                        tls::ConditionalServerTls::Some(tls::ServerTls::Passthru { sni }) => {
+                            Err(UnexpectedSni(sni, tcp.client).into())
+                        }
+                        _ => Err(NonHttpClient(tcp.client).into()),
+                    },
+                }
+            },
+        )
+        .arc_new_tcp()
+        .lift_new_with_target()
+        .push(http::NewDetect::layer(move |tcp: &Tcp| {
+            http::DetectParams {
+                read_timeout: DETECT_TIMEOUT,
+                metrics: detect_metrics.metrics(tcp.policy.server_label())
+            }
+        }))
+        .push(transport::metrics::NewServer::layer(metrics.proxy.transport.clone()))
+        .push_map_target(move |(tls, addrs): (tls::ConditionalServerTls, B::Addrs)| {
+            Tcp {
+                tls,
+                client: addrs.param(),
+                addr: addrs.param(),
+                policy: policy.clone(),
+            }
+        })
+        .arc_new_tcp()
+        .push(tls::NewDetectTls::<identity::Server, _, _>::layer(TlsParams {
+            identity,
+        }))
+        .arc_new_tcp()
+        .into_inner();
+
+    Box::pin(serve::serve(listen, tcp, drain.signaled()))
+}
+
+// === impl ScrapeMetrics ===
+
+impl ScrapeMetrics {
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let connections = prom::Family::default();
+        registry.register(
+            "connections",
+            "The number of connections accepted on the admin scrape listener, by authorization result",
+            connections.clone(),
+        );
+        Self { connections }
+    }
+
+    fn inc(&self, result: ScrapeResult) {
+        self.connections
+            .get_or_create(&ScrapeLabels { result })
+            .inc();
+    }
+}
+
+// === impl Scrapers ===
+
+impl Scrapers {
+    fn authorize(&self, tcp: &Tcp) -> Result<()> {
+        let result = match tcp.tls {
+            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_id: Some(ref id),
+                ..
+            }) if self.permitted.contains(id) => ScrapeResult::authorized,
+            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_id: Some(_),
+                ..
+            }) => ScrapeResult::unauthorized,
+            _ => ScrapeResult::unauthenticated,
+        };
+        self.metrics.inc(result.clone());
+
+        if result != ScrapeResult::authorized {
+            tracing::info!(
+                client.addr = %tcp.client,
+                client.tls = ?tcp.tls,
+                ?result,
+                "Scrape connection denied",
+            );
+            return Err(UnauthorizedScraper(tcp.client).into());
+        }
+        Ok(())
+    }
+}
+
 // === impl Tcp ===
 
 impl Param<transport::labels::Key> for Tcp {
@@ -342,11 +539,17 @@ impl<T: Param<tls::ConditionalServerTls>> ExtractParam<errors::respond::EmitHead
 impl errors::HttpRescue<Error> for Rescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if let Some(cause) = errors::cause_ref::<inbound::policy::HttpRouteNotFound>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::not_found(cause));
+            return Ok(errors::SyntheticHttpResponse::not_found(
+                errors::ErrorCode::NotFound,
+                cause,
+            ));
         }
 
         if let Some(cause) = errors::cause_ref::<inbound::policy::HttpRouteUnauthorized>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::permission_denied(cause));
+            return Ok(errors::SyntheticHttpResponse::permission_denied(
+                errors::ErrorCode::Unauthorized,
+                cause,
+            ));
         }
 
         tracing::warn!(error, "Unexpected error");
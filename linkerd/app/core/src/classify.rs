@@ -281,6 +281,28 @@ mod tests {
         assert_eq!(class, Class::Http(Err(StatusCode::INTERNAL_SERVER_ERROR)));
     }
 
+    #[test]
+    fn http_response_status_overridden() {
+        let statuses =
+            linkerd_proxy_client_policy::http::StatusRanges(std::sync::Arc::new([404..=404]));
+        let req = Request::builder().body(()).unwrap();
+        let classify = super::Request::ClientPolicy(super::ClientPolicy::Http(statuses));
+
+        let rsp = Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(())
+            .unwrap();
+        let class = classify.classify(&req).start(&rsp).eos(None);
+        assert_eq!(class, Class::Http(Err(StatusCode::NOT_FOUND)));
+
+        let rsp = Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(())
+            .unwrap();
+        let class = classify.classify(&req).start(&rsp).eos(None);
+        assert_eq!(class, Class::Http(Ok(StatusCode::INTERNAL_SERVER_ERROR)));
+    }
+
     #[test]
     fn grpc_response_header_ok() {
         let rsp = Response::builder()
@@ -5,6 +5,7 @@ use crate::{
     transport::{DualListenAddr, Keepalive, ListenAddr, UserTimeout},
 };
 use std::time::Duration;
+use tokio::sync::watch;
 
 #[derive(Clone, Debug)]
 pub struct ServerConfig {
@@ -32,6 +33,27 @@ pub struct ProxyConfig {
     pub detect_protocol_timeout: Duration,
 }
 
+/// Settings that may be changed while the proxy is running.
+///
+/// The rest of the proxy's configuration is fixed when the proxy starts. These
+/// settings, however, are read by the proxy's stacks as each connection or
+/// request is handled. Unset values defer to the static configuration.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tunables {
+    /// Overrides the inbound protocol detection timeout for connections
+    /// whose server policy does not configure one.
+    pub inbound_detect_timeout: Option<Duration>,
+
+    /// Overrides the outbound protocol detection timeout.
+    pub outbound_detect_timeout: Option<Duration>,
+
+    /// The fraction of inbound HTTP requests that are recorded in the access
+    /// log. All requests are recorded if unset.
+    pub access_log_sample_ratio: Option<f64>,
+}
+
+pub type TunablesRx = watch::Receiver<Tunables>;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct QueueConfig {
     /// The number of requests (or connections, depending on the context) that
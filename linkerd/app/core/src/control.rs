@@ -63,6 +63,12 @@ impl svc::Param<http::balance::EwmaConfig> for ControlAddr {
     }
 }
 
+impl<Req> svc::Param<Option<http::balance::SourceAffinity<Req>>> for ControlAddr {
+    fn param(&self) -> Option<http::balance::SourceAffinity<Req>> {
+        None
+    }
+}
+
 impl fmt::Display for ControlAddr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(&self.addr, f)
@@ -1,14 +1,15 @@
 //! A specialized `NewIdleCache` to manage discovery stae, usually from a
 //! control plane client.
 
-use futures::TryFutureExt;
+use futures::{Future, TryFutureExt};
 use linkerd_error::Error;
-use linkerd_idle_cache::{Cached, NewIdleCached};
+use linkerd_idle_cache::{Cached, NewIdleCached, Periodic};
+use linkerd_metrics::prom;
 use linkerd_stack::{
     layer, queue, CloneParam, FutureService, MapErrBoxed, NewQueueWithoutTimeout, NewService,
     Oneshot, Param, QueueWithoutTimeout, Service, ServiceExt, ThunkClone,
 };
-use std::{fmt, hash::Hash, task, time};
+use std::{fmt, hash::Hash, sync::Arc, task, time};
 
 /// A [`NewService`] that extracts a `K`-typed key from each target to build a
 /// [`Cached`]<[`DiscoverThunk`]>.
@@ -94,6 +95,47 @@ where
     pub fn layer(disco: D, idle: time::Duration) -> impl layer::Layer<N, Service = Self> + Clone {
         layer::mk(move |inner| Self::new(inner, disco.clone(), idle))
     }
+
+    /// Retains idle discovery results for keys that are accessed at regular
+    /// intervals, as determined by `periodic`.
+    pub fn with_periodic(self, periodic: Arc<Periodic<K>>) -> Self {
+        Self {
+            cache: self.cache.with_periodic(periodic),
+            inner: self.inner,
+        }
+    }
+
+    /// Records the number of discovery lookups that are being set up in
+    /// `builds`.
+    pub fn with_builds_gauge(self, builds: prom::Gauge) -> Self {
+        Self {
+            cache: self.cache.with_builds_gauge(builds),
+            inner: self.inner,
+        }
+    }
+
+    /// Returns a `NewCachedDiscover` that shares this discovery cache but
+    /// builds services with `inner`.
+    pub fn with_inner<M>(&self, inner: M) -> NewCachedDiscover<K, D, M> {
+        NewCachedDiscover {
+            cache: self.cache.clone(),
+            inner,
+        }
+    }
+
+    /// Discovers `key` through the cache, returning the discovery result
+    /// wrapped in a handle that prevents the cache's idle timeout from starting
+    /// until it is dropped.
+    pub fn discover(&self, key: K) -> impl Future<Output = Result<Cached<D::Response>, Error>>
+    where
+        D::Response: 'static,
+    {
+        let cached = self.cache.new_service(key);
+        cached
+            .clone()
+            .oneshot(())
+            .map_ok(move |rsp| cached.clone_with(rsp))
+    }
 }
 
 impl<T, K, D, M, N> NewService<T> for NewCachedDiscover<K, D, M>
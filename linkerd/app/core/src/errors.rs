@@ -2,7 +2,7 @@ pub mod body;
 pub mod respond;
 
 pub use self::respond::{HttpRescue, NewRespond, NewRespondService, SyntheticHttpResponse};
-pub use linkerd_error::{cause_ref, is_caused_by};
+pub use linkerd_error::{cause_ref, is_caused_by, ErrorCode};
 pub use linkerd_proxy_http::h2::H2Error;
 pub use linkerd_stack::{FailFastError, LoadShedError};
 pub use tonic::Code as Grpc;
@@ -162,7 +162,10 @@ mod tests {
     impl<E> HttpRescue<E> for MockRescue {
         /// Attempts to synthesize a response from the given error.
         fn rescue(&self, _: E) -> Result<SyntheticHttpResponse, E> {
-            let synthetic = SyntheticHttpResponse::internal_error("MockRescue::rescue");
+            let synthetic = SyntheticHttpResponse::internal_error(
+                linkerd_error::ErrorCode::Unexpected,
+                "MockRescue::rescue",
+            );
             Ok(synthetic)
         }
     }
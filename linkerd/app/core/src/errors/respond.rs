@@ -3,12 +3,12 @@ use super::{
     header::{GRPC_CONTENT_TYPE, GRPC_MESSAGE, GRPC_STATUS, L5D_PROXY_CONNECTION, L5D_PROXY_ERROR},
 };
 use crate::svc;
-use http::header::{HeaderValue, LOCATION};
-use linkerd_error::{Error, Result};
+use http::header::{HeaderValue, LOCATION, RETRY_AFTER};
+use linkerd_error::{Error, ErrorCode, Result};
 use linkerd_error_respond as respond;
-use linkerd_proxy_http::{orig_proto, ClientHandle};
+use linkerd_proxy_http::{orig_proto, ClientHandle, RequestId};
 use linkerd_stack::ExtractParam;
-use std::borrow::Cow;
+use std::{borrow::Cow, time::Duration};
 use tracing::{debug, info_span, warn};
 
 pub fn layer<R, P: Clone, N>(
@@ -26,13 +26,21 @@ pub trait HttpRescue<E> {
     fn rescue(&self, error: E) -> Result<SyntheticHttpResponse, E>;
 }
 
+/// A response synthesized by the proxy to describe an error.
+///
+/// Each response carries an [`ErrorCode`] that classifies the error. The code
+/// prefixes the message in the `l5d-proxy-error` header and is set as an
+/// extension on the response so that it may be observed by metrics and access
+/// logs.
 #[derive(Clone, Debug)]
 pub struct SyntheticHttpResponse {
+    pub code: ErrorCode,
     pub grpc_status: tonic::Code,
     http_status: http::StatusCode,
     close_connection: bool,
     pub message: Cow<'static, str>,
     location: Option<HeaderValue>,
+    retry_after: Option<HeaderValue>,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -54,6 +62,7 @@ pub struct Respond<R> {
     is_grpc: bool,
     is_orig_proto_upgrade: bool,
     client: Option<ClientHandle>,
+    request_id: Option<RequestId>,
     emit_headers: bool,
 }
 
@@ -72,111 +81,150 @@ where
 
 impl SyntheticHttpResponse {
     pub fn unexpected_error() -> Self {
-        Self::internal_error("unexpected error")
+        Self::internal_error(ErrorCode::Unexpected, "unexpected error")
     }
 
-    pub fn internal_error(msg: impl Into<Cow<'static, str>>) -> Self {
+    pub fn internal_error(code: ErrorCode, msg: impl Into<Cow<'static, str>>) -> Self {
         Self {
+            code,
             close_connection: true,
             http_status: http::StatusCode::INTERNAL_SERVER_ERROR,
             grpc_status: tonic::Code::Internal,
             message: msg.into(),
             location: None,
+            retry_after: None,
         }
     }
 
-    pub fn bad_gateway(msg: impl ToString) -> Self {
+    pub fn bad_gateway(code: ErrorCode, msg: impl ToString) -> Self {
         Self {
+            code,
             close_connection: true,
             http_status: http::StatusCode::BAD_GATEWAY,
             grpc_status: tonic::Code::Unavailable,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            retry_after: None,
         }
     }
 
-    pub fn gateway_timeout(msg: impl ToString) -> Self {
+    pub fn gateway_timeout(code: ErrorCode, msg: impl ToString) -> Self {
         Self {
+            code,
             close_connection: true,
             http_status: http::StatusCode::GATEWAY_TIMEOUT,
             grpc_status: tonic::Code::DeadlineExceeded,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            retry_after: None,
         }
     }
 
-    pub fn gateway_timeout_nonfatal(msg: impl ToString) -> Self {
+    pub fn gateway_timeout_nonfatal(code: ErrorCode, msg: impl ToString) -> Self {
         Self {
+            code,
             close_connection: false,
             http_status: http::StatusCode::GATEWAY_TIMEOUT,
             grpc_status: tonic::Code::DeadlineExceeded,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            retry_after: None,
         }
     }
 
-    pub fn unavailable(msg: impl ToString) -> Self {
+    pub fn unavailable(code: ErrorCode, msg: impl ToString) -> Self {
         Self {
+            code,
             close_connection: true,
             http_status: http::StatusCode::SERVICE_UNAVAILABLE,
             grpc_status: tonic::Code::Unavailable,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            retry_after: None,
         }
     }
 
-    pub fn unauthenticated(msg: impl ToString) -> Self {
+    /// A response indicating that the request was not processed because the
+    /// application is overloaded. Unlike [`Self::unavailable`], the connection
+    /// is retained, as the client may retry after the given delay.
+    pub fn overloaded(code: ErrorCode, msg: impl ToString, retry_after: Duration) -> Self {
+        // The header is expressed in whole seconds, so the delay is rounded
+        // up.
+        let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
         Self {
+            code,
+            close_connection: false,
+            http_status: http::StatusCode::SERVICE_UNAVAILABLE,
+            grpc_status: tonic::Code::Unavailable,
+            message: Cow::Owned(msg.to_string()),
+            location: None,
+            retry_after: Some(HeaderValue::from(secs)),
+        }
+    }
+
+    pub fn unauthenticated(code: ErrorCode, msg: impl ToString) -> Self {
+        Self {
+            code,
             http_status: http::StatusCode::FORBIDDEN,
             grpc_status: tonic::Code::Unauthenticated,
             close_connection: false,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            retry_after: None,
         }
     }
 
-    pub fn permission_denied(msg: impl ToString) -> Self {
+    pub fn permission_denied(code: ErrorCode, msg: impl ToString) -> Self {
         Self {
+            code,
             http_status: http::StatusCode::FORBIDDEN,
             grpc_status: tonic::Code::PermissionDenied,
             close_connection: false,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            retry_after: None,
         }
     }
 
-    pub fn rate_limited(msg: impl ToString) -> Self {
+    pub fn rate_limited(code: ErrorCode, msg: impl ToString) -> Self {
         Self {
+            code,
             http_status: http::StatusCode::TOO_MANY_REQUESTS,
             grpc_status: tonic::Code::ResourceExhausted,
             close_connection: false,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            retry_after: None,
         }
     }
 
-    pub fn loop_detected(msg: impl ToString) -> Self {
+    pub fn loop_detected(code: ErrorCode, msg: impl ToString) -> Self {
         Self {
+            code,
             http_status: http::StatusCode::LOOP_DETECTED,
             grpc_status: tonic::Code::Aborted,
             close_connection: true,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            retry_after: None,
         }
     }
 
-    pub fn not_found(msg: impl ToString) -> Self {
+    pub fn not_found(code: ErrorCode, msg: impl ToString) -> Self {
         Self {
+            code,
             http_status: http::StatusCode::NOT_FOUND,
             grpc_status: tonic::Code::NotFound,
             close_connection: false,
             message: Cow::Owned(msg.to_string()),
             location: None,
+            retry_after: None,
         }
     }
 
     pub fn redirect(http_status: http::StatusCode, location: &http::Uri) -> Self {
         Self {
+            code: ErrorCode::Redirect,
             http_status,
             grpc_status: tonic::Code::NotFound,
             close_connection: false,
@@ -185,24 +233,37 @@ impl SyntheticHttpResponse {
                 HeaderValue::try_from(location.to_string())
                     .expect("location must be a valid header value"),
             ),
+            retry_after: None,
         }
     }
 
-    pub fn response(http_status: http::StatusCode, message: impl Into<Cow<'static, str>>) -> Self {
+    pub fn response(
+        code: ErrorCode,
+        http_status: http::StatusCode,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Self {
         Self {
+            code,
             http_status,
             location: None,
+            retry_after: None,
             grpc_status: tonic::Code::FailedPrecondition,
             close_connection: false,
             message: message.into(),
         }
     }
 
-    pub fn grpc(grpc_status: tonic::Code, message: impl Into<Cow<'static, str>>) -> Self {
+    pub fn grpc(
+        code: ErrorCode,
+        grpc_status: tonic::Code,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Self {
         Self {
+            code,
             grpc_status,
             http_status: http::StatusCode::OK,
             location: None,
+            retry_after: None,
             close_connection: false,
             message: message.into(),
         }
@@ -219,8 +280,22 @@ impl SyntheticHttpResponse {
         }
     }
 
+    /// Formats the `l5d-proxy-error` header as the error's code followed by
+    /// its message, e.g. `failfast: service in fail-fast`.
+    #[inline]
+    fn proxy_error(&self) -> HeaderValue {
+        HeaderValue::from_str(&format!("{}: {}", self.code, self.message)).unwrap_or_else(|error| {
+            warn!(%error, "Failed to encode error header");
+            HeaderValue::from_static("unexpected: unexpected error")
+        })
+    }
+
     #[inline]
-    fn grpc_response<B: Default>(&self, emit_headers: bool) -> http::Response<B> {
+    fn grpc_response<B: Default>(
+        &self,
+        emit_headers: bool,
+        request_id: Option<&RequestId>,
+    ) -> http::Response<B> {
         debug!(code = %self.grpc_status, "Handling error on gRPC connection");
         let mut rsp = http::Response::builder()
             .version(http::Version::HTTP_2)
@@ -231,7 +306,7 @@ impl SyntheticHttpResponse {
         if emit_headers {
             rsp = rsp
                 .header(GRPC_MESSAGE, self.message())
-                .header(L5D_PROXY_ERROR, self.message());
+                .header(L5D_PROXY_ERROR, self.proxy_error());
         }
 
         if self.close_connection && emit_headers {
@@ -239,7 +314,12 @@ impl SyntheticHttpResponse {
             rsp = rsp.header(L5D_PROXY_CONNECTION, "close");
         }
 
-        rsp.body(B::default())
+        if let Some(id) = request_id {
+            rsp = rsp.header(id.header(), id.value());
+        }
+
+        rsp.extension(self.code)
+            .body(B::default())
             .expect("error response must be valid")
     }
 
@@ -249,6 +329,7 @@ impl SyntheticHttpResponse {
         version: http::Version,
         emit_headers: bool,
         is_orig_proto_upgrade: bool,
+        request_id: Option<&RequestId>,
     ) -> http::Response<B> {
         debug!(
             status = %self.http_status,
@@ -262,7 +343,7 @@ impl SyntheticHttpResponse {
             .header(http::header::CONTENT_LENGTH, "0");
 
         if emit_headers {
-            rsp = rsp.header(L5D_PROXY_ERROR, self.message());
+            rsp = rsp.header(L5D_PROXY_ERROR, self.proxy_error());
         }
 
         if self.close_connection {
@@ -283,7 +364,16 @@ impl SyntheticHttpResponse {
             rsp = rsp.header(LOCATION, loc);
         }
 
-        rsp.body(B::default())
+        if let Some(retry_after) = &self.retry_after {
+            rsp = rsp.header(RETRY_AFTER, retry_after);
+        }
+
+        if let Some(id) = request_id {
+            rsp = rsp.header(id.header(), id.value());
+        }
+
+        rsp.extension(self.code)
+            .body(B::default())
             .expect("error response must be valid")
     }
 }
@@ -316,6 +406,9 @@ where
     fn new_respond(&self, req: &http::Request<B>) -> Self::Respond {
         let client = req.extensions().get::<ClientHandle>().cloned();
         debug_assert!(client.is_some(), "Missing client handle");
+        // Synthesized responses carry the request's ID so that errors may be
+        // correlated with the proxy's logs.
+        let request_id = req.extensions().get::<RequestId>().cloned();
 
         let rescue = self.rescue.clone();
         let emit_headers = self.emit_headers;
@@ -341,6 +434,7 @@ where
                     is_grpc,
                     is_orig_proto_upgrade: false,
                     version: http::Version::HTTP_2,
+                    request_id,
                     emit_headers,
                 }
             }
@@ -352,6 +446,7 @@ where
                     version,
                     is_grpc: false,
                     is_orig_proto_upgrade: is_h2_upgrade,
+                    request_id,
                     emit_headers,
                 }
             }
@@ -415,11 +510,50 @@ where
         }
 
         let rsp = if self.is_grpc {
-            rsp.grpc_response(self.emit_headers)
+            rsp.grpc_response(self.emit_headers, self.request_id.as_ref())
         } else {
-            rsp.http_response(self.version, self.emit_headers, self.is_orig_proto_upgrade)
+            rsp.http_response(
+                self.version,
+                self.emit_headers,
+                self.is_orig_proto_upgrade,
+                self.request_id.as_ref(),
+            )
         };
 
         Ok(rsp)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emits_error_code() {
+        let rsp =
+            SyntheticHttpResponse::gateway_timeout(ErrorCode::Failfast, "service in fail-fast");
+
+        let http = rsp.http_response::<()>(http::Version::HTTP_11, true, false, None);
+        assert_eq!(
+            http.headers().get(L5D_PROXY_ERROR).unwrap(),
+            "failfast: service in fail-fast"
+        );
+        assert_eq!(http.extensions().get(), Some(&ErrorCode::Failfast));
+
+        let grpc = rsp.grpc_response::<()>(true, None);
+        assert_eq!(
+            grpc.headers().get(L5D_PROXY_ERROR).unwrap(),
+            "failfast: service in fail-fast"
+        );
+        assert_eq!(
+            grpc.headers().get(GRPC_MESSAGE).unwrap(),
+            "service in fail-fast"
+        );
+        assert_eq!(grpc.extensions().get(), Some(&ErrorCode::Failfast));
+
+        // The code is recorded even when headers are not emitted.
+        let http = rsp.http_response::<()>(http::Version::HTTP_11, false, false, None);
+        assert!(http.headers().get(L5D_PROXY_ERROR).is_none());
+        assert_eq!(http.extensions().get(), Some(&ErrorCode::Failfast));
+    }
+}
@@ -2,11 +2,15 @@ use linkerd_error::Error;
 use linkerd_stack::layer;
 use linkerd_trace_context::{
     self as trace_context,
-    export::{ExportSpan, SpanKind, SpanLabels},
+    export::{SpanKind, SpanLabels},
     Span, TraceContext,
 };
 use std::{str::FromStr, sync::Arc};
-use tokio::sync::mpsc;
+
+mod queue;
+
+pub use self::queue::{span_queue, SpanQueue, SpanQueueMetrics, SpanSink};
+pub use linkerd_trace_context::{export::ExportSpan, SpanEvent, SpanRecorder};
 
 #[derive(Debug, Copy, Clone, Default)]
 pub enum CollectorProtocol {
@@ -29,8 +33,6 @@ impl FromStr for CollectorProtocol {
     }
 }
 
-pub type SpanSink = mpsc::Sender<ExportSpan>;
-
 pub fn server<S>(
     sink: Option<SpanSink>,
     labels: impl Into<SpanLabels>,
@@ -66,11 +68,11 @@ impl trace_context::SpanSink for SpanConverter {
     }
 
     fn try_send(&mut self, span: Span) -> Result<(), Error> {
-        self.sink.try_send(ExportSpan {
+        self.sink.send(ExportSpan {
             span,
             kind: self.kind,
             labels: Arc::clone(&self.labels),
-        })?;
+        });
         Ok(())
     }
 }
@@ -0,0 +1,373 @@
+//! A bounded queue of spans awaiting export.
+//!
+//! Recording a span must never delay the request that produced it. Spans that
+//! are recorded faster than the configured rate are counted but not exported,
+//! and when the exporter falls behind, the oldest queued spans are dropped to
+//! make room for new ones.
+
+use super::ExportSpan;
+use crate::metrics::prom::{self, encoding::*};
+use futures::{task::AtomicWaker, Stream};
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    fmt,
+    num::NonZeroU32,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::time;
+
+/// Records spans into a [`SpanQueue`] without waiting for the exporter.
+#[derive(Clone)]
+pub struct SpanSink(Arc<Sender>);
+
+/// A stream of the spans that are to be exported.
+///
+/// The stream ends once all of its [`SpanSink`]s have been dropped and all
+/// queued spans have been consumed.
+pub struct SpanQueue(Arc<Shared>);
+
+#[derive(Clone, Debug, Default)]
+pub struct SpanQueueMetrics {
+    rate_limited: prom::Counter,
+    queue_full: prom::Counter,
+    depth: prom::Gauge,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct DropLabels {
+    reason: DropReason,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum DropReason {
+    /// The span was recorded after the rate limit was exhausted.
+    RateLimited,
+    /// The span was evicted from a full queue by a newer span.
+    QueueFull,
+}
+
+/// Closes the queue when the last sink is dropped.
+struct Sender(Arc<Shared>);
+
+struct Shared {
+    capacity: usize,
+    state: Mutex<State>,
+    rx_task: AtomicWaker,
+    metrics: SpanQueueMetrics,
+}
+
+struct State {
+    spans: VecDeque<ExportSpan>,
+    limit: Option<RateLimit>,
+    closed: bool,
+}
+
+/// A token bucket that admits up to a second's worth of spans at once.
+struct RateLimit {
+    per_second: f64,
+    tokens: f64,
+    refilled: time::Instant,
+}
+
+/// Creates a queue that holds at most `capacity` spans and admits at most
+/// `max_per_second` spans each second, if set.
+pub fn span_queue(
+    capacity: usize,
+    max_per_second: Option<NonZeroU32>,
+    metrics: SpanQueueMetrics,
+) -> (SpanSink, SpanQueue) {
+    let shared = Arc::new(Shared {
+        capacity: capacity.max(1),
+        state: Mutex::new(State {
+            spans: VecDeque::new(),
+            limit: max_per_second.map(RateLimit::new),
+            closed: false,
+        }),
+        rx_task: AtomicWaker::new(),
+        metrics,
+    });
+    (
+        SpanSink(Arc::new(Sender(shared.clone()))),
+        SpanQueue(shared),
+    )
+}
+
+// === impl SpanSink ===
+
+impl SpanSink {
+    /// Queues a span for export, evicting the oldest queued span if the queue
+    /// is full. Spans that exceed the rate limit are discarded.
+    pub fn send(&self, span: ExportSpan) {
+        let Sender(shared) = &*self.0;
+        let mut state = shared.state.lock();
+        if let Some(limit) = state.limit.as_mut() {
+            if !limit.acquire(time::Instant::now()) {
+                drop(state);
+                tracing::trace!("Span rate limited");
+                shared.metrics.rate_limited.inc();
+                return;
+            }
+        }
+
+        if state.spans.len() >= shared.capacity {
+            state.spans.pop_front();
+            shared.metrics.queue_full.inc();
+        }
+        state.spans.push_back(span);
+        shared.metrics.depth.set(state.spans.len() as i64);
+        drop(state);
+
+        shared.rx_task.wake();
+    }
+}
+
+impl fmt::Debug for SpanSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Sender(shared) = &*self.0;
+        f.debug_struct("SpanSink")
+            .field("capacity", &shared.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        self.0.state.lock().closed = true;
+        self.0.rx_task.wake();
+    }
+}
+
+// === impl SpanQueue ===
+
+impl Stream for SpanQueue {
+    type Item = ExportSpan;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ExportSpan>> {
+        let shared = &*self.0;
+        // Register before checking the queue so that spans sent concurrently
+        // wake this task.
+        shared.rx_task.register(cx.waker());
+
+        let mut state = shared.state.lock();
+        if let Some(span) = state.spans.pop_front() {
+            shared.metrics.depth.set(state.spans.len() as i64);
+            return Poll::Ready(Some(span));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
+
+impl fmt::Debug for SpanQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpanQueue")
+            .field("capacity", &self.0.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+// === impl SpanQueueMetrics ===
+
+impl SpanQueueMetrics {
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let dropped = prom::Family::<DropLabels, prom::Counter>::default();
+        registry.register(
+            "spans_dropped",
+            "The number of recorded spans that were not exported, by reason",
+            dropped.clone(),
+        );
+        let depth = prom::Gauge::default();
+        registry.register(
+            "span_queue_depth",
+            "The number of spans waiting to be exported",
+            depth.clone(),
+        );
+
+        let dropped = |reason| dropped.get_or_create(&DropLabels { reason }).clone();
+        Self {
+            rate_limited: dropped(DropReason::RateLimited),
+            queue_full: dropped(DropReason::QueueFull),
+            depth,
+        }
+    }
+}
+
+// === impl DropReason ===
+
+impl DropReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::RateLimited => "rate_limited",
+            Self::QueueFull => "queue_full",
+        }
+    }
+}
+
+impl EncodeLabelValue for DropReason {
+    fn encode(&self, enc: &mut LabelValueEncoder<'_>) -> fmt::Result {
+        use fmt::Write;
+        enc.write_str(self.as_str())
+    }
+}
+
+// === impl RateLimit ===
+
+impl RateLimit {
+    fn new(per_second: NonZeroU32) -> Self {
+        let per_second = f64::from(per_second.get());
+        Self {
+            per_second,
+            tokens: per_second,
+            refilled: time::Instant::now(),
+        }
+    }
+
+    fn acquire(&mut self, now: time::Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.per_second).min(self.per_second);
+        self.refilled = now;
+
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{http_tracing, svc::ServiceExt};
+    use futures::StreamExt;
+    use linkerd_stack::layer::Layer;
+    use linkerd_trace_context::{
+        export::{SpanKind, SpanLabels},
+        Span,
+    };
+    use std::time::{Duration, SystemTime};
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn rate_limits_spans() {
+        let metrics = SpanQueueMetrics::default();
+        let (sink, queue) = span_queue(100, NonZeroU32::new(5), metrics.clone());
+
+        for i in 0..8 {
+            sink.send(span(i));
+        }
+        assert_eq!(metrics.rate_limited.get(), 3);
+        assert_eq!(metrics.depth.get(), 5);
+
+        // Spans are admitted as the limit replenishes.
+        time::sleep(Duration::from_millis(400)).await;
+        for i in 8..12 {
+            sink.send(span(i));
+        }
+        assert_eq!(metrics.rate_limited.get(), 5);
+        assert_eq!(metrics.depth.get(), 7);
+
+        drop(sink);
+        let names = names(queue).await;
+        assert_eq!(names, ["0", "1", "2", "3", "4", "8", "9"]);
+        assert_eq!(metrics.depth.get(), 0);
+        assert_eq!(metrics.queue_full.get(), 0);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn drops_oldest_spans() {
+        let metrics = SpanQueueMetrics::default();
+        let (sink, queue) = span_queue(3, None, metrics.clone());
+
+        for i in 0..5 {
+            sink.send(span(i));
+        }
+        assert_eq!(metrics.queue_full.get(), 2);
+        assert_eq!(metrics.depth.get(), 3);
+
+        drop(sink);
+        assert_eq!(names(queue).await, ["2", "3", "4"]);
+    }
+
+    /// Tests that requests are not delayed by a collector that cannot keep up
+    /// with the spans they produce.
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn slow_collector_does_not_delay_requests() {
+        const CAPACITY: usize = 10;
+        const REQUESTS: usize = 1_000;
+
+        let metrics = SpanQueueMetrics::default();
+        let (sink, mut queue) = span_queue(CAPACITY, None, metrics.clone());
+
+        // The collector takes a second to export each span.
+        let collector = tokio::spawn(async move {
+            let mut exported = 0;
+            while queue.next().await.is_some() {
+                exported += 1;
+                time::sleep(Duration::from_secs(1)).await;
+            }
+            exported
+        });
+
+        let svc = http_tracing::server(Some(sink), SpanLabels::default()).layer(tower::service_fn(
+            |_: http::Request<()>| async { Ok::<_, linkerd_error::Error>(http::Response::new(())) },
+        ));
+        for _ in 0..REQUESTS {
+            let req = http::Request::get("/")
+                .header(
+                    "traceparent",
+                    "00-94d7f6ec6b95f3e916179cb6cfd01390-55ccfce77f972614-01",
+                )
+                .body(())
+                .unwrap();
+            let start = time::Instant::now();
+            svc.clone()
+                .oneshot(req)
+                .await
+                .expect("request must succeed");
+            assert_eq!(
+                time::Instant::now().saturating_duration_since(start),
+                Duration::ZERO,
+                "request must not wait for the collector"
+            );
+            assert!(metrics.depth.get() <= CAPACITY as i64);
+
+            // Let the collector take spans from the queue.
+            tokio::task::yield_now().await;
+        }
+
+        // The collector never finished exporting its first span, so nearly
+        // all spans were dropped, but the most recent spans remain queued.
+        assert!(metrics.queue_full.get() >= (REQUESTS - CAPACITY - 1) as u64);
+        assert_eq!(metrics.depth.get(), CAPACITY as i64);
+        drop(svc);
+        let exported = collector.await.expect("collector must not panic");
+        assert_eq!(exported as u64 + metrics.queue_full.get(), REQUESTS as u64);
+    }
+
+    fn span(i: usize) -> ExportSpan {
+        let now = SystemTime::now();
+        ExportSpan {
+            span: Span {
+                trace_id: Default::default(),
+                span_id: Default::default(),
+                parent_id: Default::default(),
+                span_name: i.to_string(),
+                start: now,
+                end: now,
+                labels: Default::default(),
+                events: Default::default(),
+            },
+            kind: SpanKind::Server,
+            labels: Default::default(),
+        }
+    }
+
+    async fn names(queue: SpanQueue) -> Vec<String> {
+        queue.map(|s| s.span.span_name).collect().await
+    }
+}
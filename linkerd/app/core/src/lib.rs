@@ -22,6 +22,7 @@ pub mod dns;
 pub mod errors;
 pub mod http_tracing;
 pub mod metrics;
+pub mod panics;
 pub mod proxy;
 pub mod serve;
 pub mod svc;
@@ -53,6 +54,7 @@ pub mod identity {
     pub use linkerd_meshtls::*;
     pub mod client {
         pub use linkerd_proxy_identity_client as linkerd;
+        #[cfg(feature = "spire")]
         pub use linkerd_proxy_spire_client as spire;
     }
 }
@@ -68,6 +70,7 @@ pub struct ProxyRuntime {
     pub tap: proxy::tap::Registry,
     pub span_sink: Option<http_tracing::SpanSink>,
     pub drain: drain::Watch,
+    pub tunables: config::TunablesRx,
 }
 
 pub fn http_request_authority_addr<B>(req: &http::Request<B>) -> Result<Addr, addr::Error> {
@@ -103,6 +103,9 @@ pub struct OutboundEndpointLabels {
     pub labels: Option<String>,
     pub zone_locality: OutboundZoneLocality,
     pub target_addr: SocketAddr,
+    /// The HTTP version of the stack that dispatched to the endpoint, if it
+    /// is an HTTP endpoint.
+    pub http_version: Option<proxy::http::Variant>,
 }
 
 #[derive(Debug, Copy, Clone, Default, Hash, Eq, PartialEq, EncodeLabelValue)]
@@ -435,6 +438,7 @@ impl legacy::FmtLabels for OutboundEndpointLabels {
             // TODO(kate): this label is not currently emitted.
             zone_locality: _,
             target_addr,
+            http_version,
         } = self;
 
         if let Some(a) = authority.as_ref() {
@@ -450,6 +454,10 @@ impl legacy::FmtLabels for OutboundEndpointLabels {
             write!(f, ",{labels}")?;
         }
 
+        if let Some(version) = http_version {
+            write!(f, ",http_version=\"{}\"", version.as_str())?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,140 @@
+//! Accounts for panics in the proxy's tasks.
+//!
+//! Tokio catches panics in spawned tasks, so a panicking connection task does
+//! not bring down the proxy; but, without accounting, such panics are only
+//! visible in the proxy's logs. Once [`install`]ed, a panic hook counts each
+//! panic by the source file in which it occurred and retains the most recent
+//! panics, with their backtraces, so that they may be inspected via the admin
+//! server.
+
+use linkerd_metrics::prom;
+use parking_lot::Mutex;
+use prometheus_client::encoding::EncodeLabelSet;
+use std::{
+    collections::VecDeque,
+    panic::PanicHookInfo,
+    sync::{Arc, Once, OnceLock},
+    time::SystemTime,
+};
+
+/// The number of panics that are retained.
+const CAPACITY: usize = 16;
+
+static PANICS: OnceLock<Panics> = OnceLock::new();
+
+#[derive(Clone, Debug)]
+struct Panics {
+    total: prom::Family<PanicLabels, prom::Counter>,
+    recent: Arc<Mutex<VecDeque<Panic>>>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct PanicLabels {
+    /// The source file in which the panic occurred.
+    pub location: String,
+}
+
+/// A panic recorded by the panic hook.
+#[derive(Clone, Debug)]
+pub struct Panic {
+    pub time: SystemTime,
+    pub thread: Option<String>,
+    pub location: Option<String>,
+    pub message: String,
+    pub backtrace: String,
+}
+
+/// Installs a panic hook that records panics before invoking the previously
+/// installed hook.
+///
+/// The hook is only installed once per process.
+pub fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let prior = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            panics().record(info);
+            prior(info);
+        }));
+    });
+}
+
+/// Returns the metric that counts panics by location.
+pub fn metric() -> prom::Family<PanicLabels, prom::Counter> {
+    panics().total.clone()
+}
+
+/// Returns the most recent panics, oldest first.
+pub fn recent() -> Vec<Panic> {
+    panics().recent.lock().iter().cloned().collect()
+}
+
+fn panics() -> &'static Panics {
+    PANICS.get_or_init(|| Panics {
+        total: prom::Family::default(),
+        recent: Default::default(),
+    })
+}
+
+// === impl Panics ===
+
+impl Panics {
+    fn record(&self, info: &PanicHookInfo<'_>) {
+        // Locations are labeled by file, not line, to bound the metric's
+        // cardinality.
+        let location = info.location().map_or("unknown", |l| l.file());
+        self.total
+            .get_or_create(&PanicLabels {
+                location: location.to_string(),
+            })
+            .inc();
+
+        let message = info
+            .payload_as_str()
+            .unwrap_or("<non-string panic payload>")
+            .to_string();
+        let panic = Panic {
+            time: SystemTime::now(),
+            thread: std::thread::current().name().map(Into::into),
+            location: info.location().map(ToString::to_string),
+            message,
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        };
+
+        let mut recent = self.recent.lock();
+        if recent.len() == CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(panic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_panics() {
+        install();
+
+        let res = std::thread::Builder::new()
+            .name("panicky".to_string())
+            .spawn(|| panic!("records_panics"))
+            .unwrap()
+            .join();
+        assert!(res.is_err());
+
+        let location = PanicLabels {
+            location: file!().to_string(),
+        };
+        assert!(metric().get_or_create(&location).get() >= 1);
+
+        let panic = recent()
+            .into_iter()
+            .rev()
+            .find(|p| p.message == "records_panics")
+            .expect("panic must be recorded");
+        assert_eq!(panic.thread.as_deref(), Some("panicky"));
+        assert!(panic.location.as_ref().unwrap().starts_with(file!()));
+    }
+}
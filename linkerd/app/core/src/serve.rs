@@ -7,7 +7,7 @@ use futures::prelude::*;
 use linkerd_error::Error;
 use linkerd_proxy_transport::AddrPair;
 use tower::util::ServiceExt;
-use tracing::{debug, debug_span, info, instrument::Instrument, warn};
+use tracing::{debug, debug_span, error, info, instrument::Instrument, warn};
 
 /// Spawns a task that binds an `L`-typed listener with an `A`-typed connection-accepting service.
 ///
@@ -50,13 +50,18 @@ pub async fn serve<M, S, I, A>(
                         async move {
                             match accept.ready_oneshot().err_into::<Error>().await {
                                 Ok(mut accept) => {
-                                    match accept
-                                        .call(io::ScopedIo::server(io))
-                                        .err_into::<Error>()
-                                        .await
-                                    {
-                                        Ok(()) => debug!("Connection closed"),
-                                        Err(reason) if is_caused_by::<std::io::Error>(&*reason) => {
+                                    // If the connection's stack panics, the
+                                    // connection is dropped and closed, and the
+                                    // panic is recorded by the panic hook.
+                                    let conn = std::panic::AssertUnwindSafe(async {
+                                        accept
+                                            .call(io::ScopedIo::server(io))
+                                            .err_into::<Error>()
+                                            .await
+                                    });
+                                    match conn.catch_unwind().await {
+                                        Ok(Ok(())) => debug!("Connection closed"),
+                                        Ok(Err(reason)) if is_caused_by::<std::io::Error>(&*reason) => {
                                             debug!(
                                                 reason,
                                                 client.addr = %client_addr,
@@ -64,7 +69,7 @@ pub async fn serve<M, S, I, A>(
                                                 "Connection closed"
                                             );
                                         }
-                                        Err(error) => {
+                                        Ok(Err(error)) => {
                                             info!(
                                                 error,
                                                 client.addr = %client_addr,
@@ -72,6 +77,13 @@ pub async fn serve<M, S, I, A>(
                                                 "Connection closed"
                                             );
                                         }
+                                        Err(_) => {
+                                            error!(
+                                                client.addr = %client_addr,
+                                                server.addr = %server_addr,
+                                                "Connection task panicked"
+                                            );
+                                        }
                                     }
                                     // Hold the service until the connection is complete. This
                                     // helps tie any inner cache lifetimes to the services they
@@ -99,3 +111,49 @@ pub async fn serve<M, S, I, A>(
         _ = shutdown => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::panics;
+    use io::AsyncReadExt;
+    use linkerd_proxy_transport::{ClientAddr, ServerAddr};
+    use tokio::time;
+
+    #[tokio::test]
+    async fn closes_panicked_connections() {
+        panics::install();
+        let location = panics::PanicLabels {
+            location: file!().to_string(),
+        };
+        let panicked = panics::metric().get_or_create(&location).get();
+
+        let addrs = AddrPair(
+            ClientAddr(([127, 0, 0, 1], 30000).into()),
+            ServerAddr(([127, 0, 0, 1], 4143).into()),
+        );
+        let (mut client, server) = io::duplex(64);
+        let listen = stream::iter(vec![Ok((addrs, server))]).chain(stream::pending());
+
+        // A stack that panics on every connection.
+        let new_accept = |_: AddrPair| {
+            svc::mk(
+                |_: io::ScopedIo<io::DuplexStream>| -> future::Ready<Result<()>> {
+                    panic!("injected panic")
+                },
+            )
+        };
+        tokio::spawn(serve(listen, new_accept, future::pending::<()>()));
+
+        let mut buf = vec![];
+        let read = time::timeout(time::Duration::from_secs(10), client.read_to_end(&mut buf))
+            .await
+            .expect("connection must be closed");
+        assert_eq!(read.expect("read must succeed"), 0);
+
+        assert!(panics::metric().get_or_create(&location).get() > panicked);
+        assert!(panics::recent()
+            .iter()
+            .any(|p| p.message == "injected panic"));
+    }
+}
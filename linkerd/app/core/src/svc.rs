@@ -1,6 +1,6 @@
 // Possibly unused, but useful during development.
 
-use crate::{disco_cache::NewCachedDiscover, Error};
+use crate::{disco_cache::NewCachedDiscover, metrics::prom, Error};
 use linkerd_error::Recover;
 use linkerd_exp_backoff::{ExponentialBackoff, ExponentialBackoffStream};
 use std::{
@@ -245,6 +245,23 @@ impl<S> Stack<S> {
         self.push(idle_cache::NewIdleCached::layer(idle))
     }
 
+    /// Caches services like [`Stack::push_new_idle_cached`], recording the
+    /// number of services that are being built in `builds`.
+    pub fn push_new_idle_cached_with_builds<T>(
+        self,
+        idle: Duration,
+        builds: prom::Gauge,
+    ) -> Stack<idle_cache::NewIdleCached<T, S>>
+    where
+        T: Clone + Eq + std::fmt::Debug + std::hash::Hash + Send + Sync + 'static,
+        S: NewService<T> + 'static,
+        S::Service: Send + Sync + 'static,
+    {
+        self.push(layer::mk(move |inner| {
+            idle_cache::NewIdleCached::new(inner, idle).with_builds_gauge(builds.clone())
+        }))
+    }
+
     /// Push a service that either calls the inner service if it is ready, or
     /// calls a `secondary` service if the inner service fails to become ready
     /// for the `skip_after` duration.
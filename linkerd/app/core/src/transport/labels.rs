@@ -195,6 +195,12 @@ mod tests {
         }
     }
 
+    impl std::fmt::Display for Key {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            self.fmt_labels(f)
+        }
+    }
+
     #[test]
     fn server_labels() {
         use linkerd_proxy_server_policy::Meta;
@@ -222,4 +228,40 @@ mod tests {
             srv_group=\"policy.linkerd.io\",srv_kind=\"server\",srv_name=\"testserver\",srv_port=\"40000\""
         );
     }
+
+    #[test]
+    fn outbound_client_labels() {
+        use crate::{metrics::OutboundZoneLocality, proxy::http};
+
+        let labels = |http_version| {
+            Key::OutboundClient(OutboundEndpointLabels {
+                server_id: tls::ConditionalClientTlsLabels::None(
+                    tls::NoClientTls::NotProvidedByServiceDiscovery,
+                ),
+                authority: Some("web.ns.svc.cluster.local:8080".parse().unwrap()),
+                labels: None,
+                zone_locality: OutboundZoneLocality::Unknown,
+                target_addr: ([192, 0, 2, 4], 8080).into(),
+                http_version,
+            })
+            .to_string()
+        };
+
+        const ENDPOINT: &str = "direction=\"outbound\",peer=\"dst\",\
+            authority=\"web.ns.svc.cluster.local:8080\",\
+            target_addr=\"192.0.2.4:8080\",target_ip=\"192.0.2.4\",target_port=\"8080\",\
+            tls=\"no_identity\",no_tls_reason=\"not_provided_by_service_discovery\"";
+
+        // Endpoints that are not dispatched by an HTTP stack keep their
+        // existing labels.
+        assert_eq!(labels(None), ENDPOINT);
+        assert_eq!(
+            labels(Some(http::Variant::Http1)),
+            format!("{ENDPOINT},http_version=\"http/1\"")
+        );
+        assert_eq!(
+            labels(Some(http::Variant::H2)),
+            format!("{ENDPOINT},http_version=\"http/2\"")
+        );
+    }
 }
@@ -0,0 +1,118 @@
+//! Restricts which remote clusters' clients may reach each exported service.
+//!
+//! The gateway otherwise admits any client with a valid identity. When an
+//! authorization applies to an exported service, only clients whose identities
+//! match one of its suffixes may reach the service. Services to which no
+//! authorization applies are not restricted.
+
+use linkerd_app_core::{dns, identity as id, metrics::prom, svc, tls, NameMatch};
+use linkerd_app_inbound::{GatewayAddr, GatewayUnauthorized};
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests;
+
+/// Permits clients to reach exported services.
+#[derive(Clone, Debug)]
+pub struct GatewayAuthorization {
+    /// Matches the names of the exported services to which the authorization
+    /// applies.
+    pub services: dns::Suffix,
+
+    /// Matches the identities of clients that may reach the services.
+    pub clients: NameMatch,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GatewayMetrics {
+    denied: prom::Family<DeniedLabels, prom::Counter>,
+}
+
+/// Authorizes gateway connections before their targets are discovered.
+#[derive(Clone, Debug)]
+pub(crate) struct Authorize {
+    authorizations: Arc<[GatewayAuthorization]>,
+    metrics: GatewayMetrics,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct DeniedLabels {
+    client_trust_domain: String,
+    service: String,
+}
+
+// === impl Authorize ===
+
+impl Authorize {
+    pub(crate) fn new(authorizations: &[GatewayAuthorization], metrics: GatewayMetrics) -> Self {
+        Self {
+            authorizations: authorizations.into(),
+            metrics,
+        }
+    }
+
+    /// Returns the target if its client may reach its exported service.
+    pub(crate) fn check<T>(&self, target: T) -> Result<T, GatewayUnauthorized>
+    where
+        T: svc::Param<GatewayAddr> + svc::Param<tls::ClientId>,
+    {
+        let GatewayAddr(addr) = svc::Param::<GatewayAddr>::param(&target);
+        let mut authzs = self
+            .authorizations
+            .iter()
+            .filter(|authz| authz.services.contains(addr.name()))
+            .peekable();
+        if authzs.peek().is_none() {
+            return Ok(target);
+        }
+
+        let tls::ClientId(client) = svc::Param::<tls::ClientId>::param(&target);
+        let authorized = match client {
+            id::Id::Dns(ref name) => authzs.any(|authz| authz.clients.matches(name)),
+            // Only DNS-like identities are matched by suffix.
+            id::Id::Uri(_) => false,
+        };
+        if authorized {
+            return Ok(target);
+        }
+
+        tracing::info!(%client, service = %addr, "Gateway client not authorized");
+        self.metrics
+            .denied
+            .get_or_create(&DeniedLabels {
+                client_trust_domain: trust_domain(&client),
+                service: addr.name().to_string(),
+            })
+            .inc();
+        Err(GatewayUnauthorized)
+    }
+}
+
+/// Returns the trust domain of a client's identity, which identifies the
+/// cluster from which it connects.
+fn trust_domain(client: &id::Id) -> String {
+    match client {
+        id::Id::Dns(name) => {
+            let name = name.without_trailing_dot();
+            match name.split_once(".identity.linkerd.") {
+                Some((_, td)) => td.to_string(),
+                None => name.to_string(),
+            }
+        }
+        id::Id::Uri(uri) => uri.host_str().unwrap_or_default().to_string(),
+    }
+}
+
+// === impl GatewayMetrics ===
+
+impl GatewayMetrics {
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let denied = prom::Family::default();
+        registry.register(
+            "unauthorized_connections",
+            "The number of gateway connections that were not authorized to reach an exported service",
+            denied.clone(),
+        );
+        Self { denied }
+    }
+}
@@ -0,0 +1,101 @@
+use super::*;
+
+const EAST: &str = "default.ns.serviceaccount.identity.linkerd.east.example.com";
+const WEST: &str = "default.ns.serviceaccount.identity.linkerd.west.example.com";
+
+#[derive(Clone, Debug)]
+struct Target {
+    service: &'static str,
+    client: &'static str,
+}
+
+impl svc::Param<GatewayAddr> for Target {
+    fn param(&self) -> GatewayAddr {
+        GatewayAddr(self.service.parse().unwrap())
+    }
+}
+
+impl svc::Param<tls::ClientId> for Target {
+    fn param(&self) -> tls::ClientId {
+        tls::ClientId(self.client.parse().unwrap())
+    }
+}
+
+fn authorization(services: &str, clients: &[&str]) -> GatewayAuthorization {
+    GatewayAuthorization {
+        services: services.parse().unwrap(),
+        clients: clients.iter().map(|c| c.parse().unwrap()).collect(),
+    }
+}
+
+fn denied(metrics: &GatewayMetrics, trust_domain: &str, service: &str) -> u64 {
+    metrics
+        .denied
+        .get_or_create(&DeniedLabels {
+            client_trust_domain: trust_domain.to_string(),
+            service: service.to_string(),
+        })
+        .get()
+}
+
+#[test]
+fn authorizes_by_client_cluster() {
+    let metrics = GatewayMetrics::default();
+    let authorize = Authorize::new(
+        &[
+            authorization("web.ns.svc.cluster.local", &["east.example.com"]),
+            authorization(
+                "api.ns.svc.cluster.local",
+                &["east.example.com", "west.example.com"],
+            ),
+        ],
+        metrics.clone(),
+    );
+
+    let check = |service, client| authorize.check(Target { service, client }).is_ok();
+    assert!(check("web.ns.svc.cluster.local:8080", EAST));
+    assert!(!check("web.ns.svc.cluster.local:8080", WEST));
+    assert!(check("api.ns.svc.cluster.local:8080", EAST));
+    assert!(check("api.ns.svc.cluster.local:8080", WEST));
+
+    assert_eq!(
+        denied(&metrics, "west.example.com", "web.ns.svc.cluster.local"),
+        1
+    );
+    assert_eq!(
+        denied(&metrics, "east.example.com", "web.ns.svc.cluster.local"),
+        0
+    );
+}
+
+#[test]
+fn unrestricted_services() {
+    let metrics = GatewayMetrics::default();
+    let authorize = Authorize::new(
+        &[authorization(
+            "web.ns.svc.cluster.local",
+            &["east.example.com"],
+        )],
+        metrics,
+    );
+    for client in [EAST, WEST] {
+        authorize
+            .check(Target {
+                service: "other.ns.svc.cluster.local:8080",
+                client,
+            })
+            .expect("services without authorizations must not be restricted");
+    }
+}
+
+#[test]
+fn trust_domains() {
+    let td = |id: &str| trust_domain(&id.parse().unwrap());
+    assert_eq!(td(EAST), "east.example.com");
+    assert_eq!(td(WEST), "west.example.com");
+    assert_eq!(td("foo.example.com"), "foo.example.com");
+    assert_eq!(
+        td("spiffe://cluster.example.com/ns/default"),
+        "cluster.example.com"
+    );
+}
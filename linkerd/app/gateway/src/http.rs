@@ -81,50 +81,55 @@ impl Gateway {
         R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
         R::Resolution: Unpin,
     {
-        let http = self
-            .outbound
-            .clone()
-            .with_stack(inner)
-            .push_http_cached(resolve)
-            .into_stack()
-            // Discard `T` and its associated client-specific metadata.
-            .push_map_target(Target::discard_parent)
-            .push(svc::ArcNewService::layer())
-            // Add headers to prevent loops.
-            .push(NewHttpGateway::layer(
-                self.inbound.identity().local_id().clone(),
-            ))
-            .push_on_service(svc::LoadShed::layer())
-            .lift_new()
-            .push(svc::ArcNewService::layer())
-            // After protocol-downgrade, we need to build an inner stack for
-            // each request-level HTTP version.
-            .push(svc::NewOneshotRoute::layer_via(|t: &Target<T>| {
-                ByRequestVersion(t.clone())
-            }))
-            // Only permit gateway traffic to endpoints for which we have
-            // discovery information.
-            .push_filter(|(_, parent): (_, T)| -> Result<_, GatewayDomainInvalid> {
-                let routes = {
-                    let mut profile =
-                        svc::Param::<Option<watch::Receiver<profiles::Profile>>>::param(&parent)
+        let route_updates = self.outbound.route_updates();
+        let http =
+            self.outbound
+                .clone()
+                .with_stack(inner)
+                .push_http_cached(resolve)
+                .into_stack()
+                // Discard `T` and its associated client-specific metadata.
+                .push_map_target(Target::discard_parent)
+                .push(svc::ArcNewService::layer())
+                // Add headers to prevent loops.
+                .push(NewHttpGateway::layer(
+                    self.inbound.identity().local_id().clone(),
+                ))
+                .push_on_service(svc::LoadShed::layer())
+                .lift_new()
+                .push(svc::ArcNewService::layer())
+                // After protocol-downgrade, we need to build an inner stack for
+                // each request-level HTTP version.
+                .push(svc::NewOneshotRoute::layer_via(|t: &Target<T>| {
+                    ByRequestVersion(t.clone())
+                }))
+                // Only permit gateway traffic to endpoints for which we have
+                // discovery information.
+                .push_filter(
+                    move |(_, parent): (_, T)| -> Result<_, GatewayDomainInvalid> {
+                        let routes = {
+                            let mut profile = svc::Param::<
+                                Option<watch::Receiver<profiles::Profile>>,
+                            >::param(&parent)
                             .ok_or(GatewayDomainInvalid)?;
-                    let init =
-                        mk_routes(&profile.borrow_and_update()).ok_or(GatewayDomainInvalid)?;
-                    outbound::http::spawn_routes(profile, init, mk_routes)
-                };
-
-                Ok(Target {
-                    routes,
-                    addr: parent.param(),
-                    version: parent.param(),
-                    parent,
-                })
-            })
-            .push(svc::ArcNewService::layer())
-            // Authorize requests to the gateway.
-            .push(self.inbound.authorize_http())
-            .arc_new_clone_http();
+                            let init = mk_routes(&profile.borrow_and_update())
+                                .ok_or(GatewayDomainInvalid)?;
+                            outbound::http::spawn_routes(&route_updates, profile, init, mk_routes)
+                        };
+
+                        Ok(Target {
+                            routes,
+                            addr: parent.param(),
+                            version: parent.param(),
+                            parent,
+                        })
+                    },
+                )
+                .push(svc::ArcNewService::layer())
+                // Authorize requests to the gateway.
+                .push(self.inbound.authorize_http())
+                .push_on_service(http::BoxResponse::layer())
+                .arc_new_clone_http();
 
         self.inbound
             .clone()
@@ -130,6 +130,7 @@ async fn upgraded_request_remains_relative_form() {
                     }]))]),
                 },
                 local_rate_limit: Arc::new(Default::default()),
+                termination_grace_period: Default::default(),
             };
             let (policy, tx) = inbound::policy::AllowPolicy::for_test(self.param(), policy);
             tokio::spawn(async move {
@@ -151,7 +152,9 @@ async fn upgraded_request_remains_relative_form() {
             outbound,
             config: crate::Config {
                 allow_discovery: std::iter::once("example.com".parse().unwrap()).collect(),
+                ..Default::default()
             },
+            metrics: Default::default(),
         };
 
         let resolve = linkerd_app_test::resolver::Dst::default().endpoint_exists(
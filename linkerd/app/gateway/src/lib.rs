@@ -3,7 +3,9 @@
 #![forbid(unsafe_code)]
 
 use linkerd_app_core::{
-    io, profiles,
+    io,
+    metrics::prom,
+    profiles,
     proxy::{
         api_resolve::{ConcreteAddr, Metadata},
         core::Resolve,
@@ -17,14 +19,21 @@ use linkerd_app_inbound::{self as inbound, GatewayAddr, Inbound};
 use linkerd_app_outbound::{self as outbound, Outbound};
 use std::fmt::Debug;
 
+mod authz;
 mod discover;
 mod http;
 mod opaq;
 mod server;
 
+pub use self::authz::{GatewayAuthorization, GatewayMetrics};
+
 #[derive(Clone, Debug, Default)]
 pub struct Config {
     pub allow_discovery: NameMatch,
+
+    /// Restricts the clients that may reach exported services. Services to
+    /// which no authorization applies may be reached by any client.
+    pub authorizations: Vec<GatewayAuthorization>,
 }
 
 /// Gateway stack builder utility.
@@ -33,14 +42,21 @@ pub struct Gateway {
     config: Config,
     inbound: Inbound<()>,
     outbound: Outbound<()>,
+    metrics: GatewayMetrics,
 }
 
 impl Gateway {
-    pub fn new(config: Config, inbound: Inbound<()>, outbound: Outbound<()>) -> Self {
+    pub fn new(
+        config: Config,
+        inbound: Inbound<()>,
+        outbound: Outbound<()>,
+        prom: &mut prom::Registry,
+    ) -> Self {
         Self {
             config,
             inbound,
             outbound,
+            metrics: GatewayMetrics::register(prom),
         }
     }
 
@@ -63,7 +79,7 @@ impl Gateway {
         T: svc::Param<Option<SessionProtocol>>,
         T: Clone + Send + Sync + Unpin + 'static,
         // Server-side socket
-        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr,
+        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + io::Splice,
         I: Debug + Send + Sync + Unpin + 'static,
         // Endpoint resolution.
         R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
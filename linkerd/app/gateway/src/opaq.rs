@@ -25,20 +25,21 @@ impl Gateway {
         T: svc::Param<inbound::policy::AllowPolicy>,
         T: Clone + Send + Sync + Unpin + 'static,
         // Server-side socket.
-        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + Send + Unpin + 'static,
+        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + io::Splice + Send + Unpin + 'static,
         // Opaq outbound stack.
         N: svc::NewService<Target, Service = NSvc> + Clone + Send + Sync + Unpin + 'static,
         NSvc: svc::Service<I, Response = (), Error = Error>,
         NSvc: Send + Unpin + 'static,
         NSvc::Future: Send + 'static,
     {
+        let route_updates = self.outbound.route_updates();
         svc::stack(inner)
             // Only permit gateway traffic to endpoints for which we have
             // discovery information.
             .push_filter(
-                |(_, opaq): (_, Opaq<T>)| -> Result<_, GatewayDomainInvalid> {
+                move |(_, opaq): (_, Opaq<T>)| -> Result<_, GatewayDomainInvalid> {
                     // Fail connections were not resolved.
-                    Target::try_from(opaq)
+                    Target::from_opaq(opaq, &route_updates)
                 },
             )
             // Authorize connections to the gateway.
@@ -47,13 +48,14 @@ impl Gateway {
     }
 }
 
-impl<T> TryFrom<Opaq<T>> for Target
-where
-    T: svc::Param<GatewayAddr>,
-{
-    type Error = GatewayDomainInvalid;
-
-    fn try_from(opaq: Opaq<T>) -> Result<Self, Self::Error> {
+impl Target {
+    fn from_opaq<T>(
+        opaq: Opaq<T>,
+        route_updates: &outbound::RouteUpdates,
+    ) -> Result<Self, GatewayDomainInvalid>
+    where
+        T: svc::Param<GatewayAddr>,
+    {
         use svc::Param;
 
         let addr: GatewayAddr = (**opaq).param();
@@ -62,6 +64,7 @@ where
             return Err(GatewayDomainInvalid);
         };
         let routes = outbound::opaq::routes_from_discovery(
+            route_updates,
             addr.0.clone().into(),
             Some(profile),
             (*opaq).param(),
@@ -1,4 +1,4 @@
-use crate::Gateway;
+use crate::{authz::Authorize, Gateway};
 use linkerd_app_core::{
     io, profiles, proxy::http, svc, tls, transport::addrs::*, transport_header::SessionProtocol,
     Addr, Error,
@@ -74,11 +74,15 @@ impl Gateway {
             .into_inner();
 
         let discover = self.resolver(profiles, policies);
+        let authorize = Authorize::new(&self.config.authorizations, self.metrics.clone());
 
         self.outbound
             .with_stack(protocol)
             .push_discover(discover)
             .into_stack()
+            // Authorize clients to reach the exported service before it is
+            // discovered.
+            .push_filter(move |t: T| authorize.check(t))
             .arc_new_tcp()
     }
 }
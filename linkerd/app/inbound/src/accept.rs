@@ -10,6 +10,10 @@ use linkerd_app_core::{
 use std::fmt::Debug;
 use tracing::info_span;
 
+mod reap;
+
+pub(crate) use self::reap::ReapMetrics;
+
 #[derive(Clone, Debug)]
 pub(crate) struct Accept {
     client_addr: Remote<ClientAddr>,
@@ -45,7 +49,14 @@ impl<N> Inbound<N> {
         DSvc::Future: Send,
     {
         self.map_stack(|cfg, rt, accept| {
+            let ports = rt.metrics.ports.clone();
             accept
+                // Close connections on which clients send no data, rather than
+                // waiting for protocol detection to time out.
+                .push(reap::NewReapSilent::layer(
+                    cfg.silent_connection_timeout,
+                    rt.metrics.reap.clone(),
+                ))
                 .push_switch(
                     // Switch to the `direct` stack when a connection's original destination is the
                     // proxy's inbound port. Otherwise, check that connections are allowed on the
@@ -58,6 +69,7 @@ impl<N> Inbound<N> {
 
                         let policy = policies.get_policy(addr);
                         tracing::debug!(policy = ?&*policy.borrow(), "Accepted");
+                        ports.accept(&policy);
                         Ok(svc::Either::Left(Accept {
                             client_addr: t.param(),
                             orig_dst_addr: addr,
@@ -139,6 +151,7 @@ mod tests {
                     name: "testsrv".into(),
                 }),
                 local_rate_limit: Default::default(),
+                termination_grace_period: Default::default(),
             },
             None,
         );
@@ -0,0 +1,284 @@
+//! Closes accepted connections on which the client never sends any data.
+//!
+//! Port scanners and broken clients may open connections without ever writing
+//! to them. Each of these connections would otherwise hold a task, the stack
+//! built for it, and its detection buffers until protocol detection times out.
+//! Instead, the connection's stack is only built once the client has sent its
+//! first byte, and connections that remain silent past a deadline are closed.
+//!
+//! Deadlines are tracked by the runtime's timer wheel, so a pending connection
+//! costs little more than a timer entry and a one-byte peek.
+
+use crate::policy::{AllowPolicy, Protocol};
+use futures::TryFutureExt;
+use linkerd_app_core::{
+    io,
+    metrics::{prom, ServerLabel},
+    svc, Error,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::time;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ReapMetrics {
+    reaped: prom::Family<ServerLabel, prom::Counter>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewReapSilent<N> {
+    timeout: Option<time::Duration>,
+    metrics: ReapMetrics,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ReapSilent<T, N> {
+    target: T,
+    timeout: Option<time::Duration>,
+    metrics: ReapMetrics,
+    inner: N,
+}
+
+// === impl ReapMetrics ===
+
+impl ReapMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let reaped = prom::Family::default();
+        registry.register(
+            "reaped_connections",
+            "The number of connections closed because the client sent no data before the silent connection timeout",
+            reaped.clone(),
+        );
+        Self { reaped }
+    }
+}
+
+// === impl NewReapSilent ===
+
+impl<N> NewReapSilent<N> {
+    /// Returns a layer that closes connections on which no data is received
+    /// within `timeout`. Connections are never reaped if `timeout` is `None`.
+    pub(crate) fn layer(
+        timeout: Option<time::Duration>,
+        metrics: ReapMetrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            timeout,
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewReapSilent<N>
+where
+    T: svc::Param<AllowPolicy>,
+    N: Clone,
+{
+    type Service = ReapSilent<T, N>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        // Clients of server-speaks-first protocols wait for the server to
+        // write first, so these protocols are configured on opaque ports,
+        // which are never reaped.
+        let policy: AllowPolicy = target.param();
+        let timeout = self
+            .timeout
+            .filter(|_| !matches!(policy.borrow().protocol, Protocol::Opaque(_)));
+        ReapSilent {
+            target,
+            timeout,
+            metrics: self.metrics.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// === impl ReapSilent ===
+
+impl<I, T, N, S> svc::Service<I> for ReapSilent<T, N>
+where
+    I: io::Peek + Send + Sync + 'static,
+    T: svc::Param<AllowPolicy> + Clone + Send + 'static,
+    N: svc::NewService<T, Service = S> + Clone + Send + 'static,
+    S: svc::Service<I, Response = ()> + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send,
+{
+    type Response = ();
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, io: I) -> Self::Future {
+        use svc::ServiceExt;
+
+        let target = self.target.clone();
+        let inner = self.inner.clone();
+        let Some(timeout) = self.timeout else {
+            return Box::pin(inner.new_service(target).oneshot(io).err_into::<Error>());
+        };
+
+        let metrics = self.metrics.clone();
+        Box::pin(async move {
+            // Peeking a single byte waits for the client to send data without
+            // allocating a buffer or consuming the data. Peek errors are left
+            // for the inner stack to encounter when it reads the connection.
+            let mut byte = [0u8; 1];
+            if time::timeout(timeout, io.peek(&mut byte)).await.is_err() {
+                tracing::debug!(?timeout, "Closing connection on which no data was received");
+                let policy: AllowPolicy = target.param();
+                metrics.reaped.get_or_create(&policy.server_label()).inc();
+                return Ok(());
+            }
+
+            inner
+                .new_service(target)
+                .oneshot(io)
+                .err_into::<Error>()
+                .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Authentication, Authorization, Meta, ServerPolicy};
+    use futures::future;
+    use linkerd_app_core::{
+        io::AsyncWriteExt,
+        svc::{Layer, NewService, ServiceExt},
+        transport::addrs::OrigDstAddr,
+    };
+    use std::{
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+    use tokio::net::{TcpListener, TcpStream};
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(1);
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn reaps_silent_connections() {
+        let metrics = ReapMetrics::default();
+        let (_client, server) = connect().await;
+        let (new_inner, built) = new_inner();
+        let svc = NewReapSilent::layer(Some(TIMEOUT), metrics.clone())
+            .layer(new_inner)
+            .new_service(policy(detect()));
+
+        svc.oneshot(server).await.expect("reaping must not fail");
+        assert_eq!(
+            built.load(Ordering::SeqCst),
+            0,
+            "inner stack must not be built"
+        );
+        assert_eq!(reaped(&metrics), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn forwards_connections_with_data() {
+        let metrics = ReapMetrics::default();
+        let (mut client, server) = connect().await;
+        let (new_inner, built) = new_inner();
+        let svc = NewReapSilent::layer(Some(TIMEOUT), metrics.clone())
+            .layer(new_inner)
+            .new_service(policy(detect()));
+
+        client.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+        svc.oneshot(server)
+            .await
+            .expect("connection must be served");
+        assert_eq!(built.load(Ordering::SeqCst), 1);
+        assert_eq!(reaped(&metrics), 0);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn exempts_opaque_ports() {
+        let metrics = ReapMetrics::default();
+        let (_client, server) = connect().await;
+        let (new_inner, built) = new_inner();
+        let svc = NewReapSilent::layer(Some(TIMEOUT), metrics.clone())
+            .layer(new_inner)
+            .new_service(policy(Protocol::Opaque(authorizations())));
+
+        svc.oneshot(server)
+            .await
+            .expect("connection must be served");
+        assert_eq!(built.load(Ordering::SeqCst), 1);
+        assert_eq!(reaped(&metrics), 0);
+    }
+
+    async fn connect() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await
+            .unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        (client, server)
+    }
+
+    /// Returns a stack that serves connections immediately, counting the
+    /// number of services it builds.
+    fn new_inner() -> (svc::ArcNewTcp<AllowPolicy, TcpStream>, Arc<AtomicUsize>) {
+        let built = Arc::new(AtomicUsize::new(0));
+        let new_inner = svc::ArcNewService::new({
+            let built = built.clone();
+            move |_: AllowPolicy| {
+                built.fetch_add(1, Ordering::SeqCst);
+                svc::BoxService::new(svc::mk(|_: TcpStream| future::ok::<(), Error>(())))
+            }
+        });
+        (new_inner, built)
+    }
+
+    fn reaped(metrics: &ReapMetrics) -> u64 {
+        let (policy, _) = policy_pair(detect());
+        metrics.reaped.get_or_create(&policy.server_label()).get()
+    }
+
+    fn policy(protocol: Protocol) -> AllowPolicy {
+        policy_pair(protocol).0
+    }
+
+    fn policy_pair(protocol: Protocol) -> (AllowPolicy, tokio::sync::watch::Sender<ServerPolicy>) {
+        AllowPolicy::for_test(
+            OrigDstAddr(([192, 0, 2, 2], 1000).into()),
+            ServerPolicy {
+                protocol,
+                meta: Meta::new_default("test"),
+                local_rate_limit: Default::default(),
+                termination_grace_period: Default::default(),
+            },
+        )
+    }
+
+    fn detect() -> Protocol {
+        Protocol::Detect {
+            timeout: TIMEOUT * 10,
+            http: Arc::new([]),
+            tcp_authorizations: authorizations(),
+        }
+    }
+
+    fn authorizations() -> Arc<[Authorization]> {
+        Arc::new([Authorization {
+            authentication: Authentication::Unauthenticated,
+            networks: vec![Default::default()],
+            meta: Meta::new_default("test"),
+        }])
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     policy::{self, AllowPolicy, Protocol, ServerPermit},
+    ports::Effective,
     Inbound,
 };
 use linkerd_app_core::{
@@ -101,16 +102,32 @@ impl Inbound<svc::ArcNewTcp<Http, io::BoxedIo>> {
         FSvc::Future: Send,
     {
         self.map_stack(|cfg, rt, http| {
+            let ports = rt.metrics.ports.clone();
             let forward = svc::stack(forward)
                 .push_on_service(svc::MapTargetLayer::new(io::BoxedIo::new))
                 .push(transport::metrics::NewServer::layer(
                     rt.metrics.proxy.transport.clone(),
                 ))
-                .push_map_target(Forward::from)
+                .push_map_target({
+                    let ports = ports.clone();
+                    move |(permit, tls): (ServerPermit, Tls)| {
+                        ports.handled(tls.orig_dst_addr.port(), Effective::Opaque);
+                        Forward::from((permit, tls))
+                    }
+                })
                 .push(policy::NewTcpPolicy::layer(rt.metrics.tcp_authz.clone()))
                 .arc_new_tcp();
 
+            let http = http.push_map_target({
+                let ports = ports.clone();
+                move |http: Http| {
+                    ports.handled(http.tls.orig_dst_addr.port(), Effective::Http(http.http));
+                    http
+                }
+            });
+
             let detect_timeout = cfg.proxy.detect_protocol_timeout;
+            let tunables = rt.tunables.clone();
             let detect = http
                 .clone()
                 .push_on_service(svc::MapTargetLayer::new(io::BoxedIo::new))
@@ -118,7 +135,8 @@ impl Inbound<svc::ArcNewTcp<Http, io::BoxedIo>> {
                     rt.metrics.proxy.transport.clone(),
                 ))
                 .push_switch(
-                    |(detected, Detect { tls, .. })| -> Result<_, Infallible> {
+                    move |(detected, Detect { tls, .. })| -> Result<_, Infallible> {
+                        ports.detected(tls.orig_dst_addr.port(), &detected);
                         match detected {
                             http::Detection::Http(http) => {
                                 Ok(svc::Either::Left(Http { http, tls }))
@@ -193,10 +211,11 @@ impl Inbound<svc::ArcNewTcp<Http, io::BoxedIo>> {
                             // TODO(ver) outbound clients should hint this with ALPN so we don't
                             // have to detect this situation.
                             Protocol::Http1 { .. } if tls.status.is_some() => {
-                                return Ok(svc::Either::Right(Detect {
-                                    timeout: detect_timeout,
-                                    tls,
-                                }));
+                                let timeout = tunables
+                                    .borrow()
+                                    .inbound_detect_timeout
+                                    .unwrap_or(detect_timeout);
+                                return Ok(svc::Either::Right(Detect { timeout, tls }));
                             }
                             // Unmeshed services don't use protocol upgrading, so we can use the
                             // hint without further detection.
@@ -229,12 +248,22 @@ impl<I> Inbound<svc::ArcNewTcp<Tls, TlsIo<I>>> {
         FSvc::Future: Send,
     {
         self.map_stack(|cfg, rt, detect| {
+            let ports = rt.metrics.ports.clone();
             let forward = svc::stack(forward)
                 .push_on_service(svc::MapTargetLayer::new(io::BoxedIo::new))
                 .push(transport::metrics::NewServer::layer(
                     rt.metrics.proxy.transport.clone(),
                 ))
-                .push_map_target(Forward::from)
+                .push_map_target(move |(permit, tls): (ServerPermit, Tls)| {
+                    // Connections are forwarded without detection either because
+                    // the port is opaque or because it terminates application TLS.
+                    let protocol = match tls.policy.protocol() {
+                        Protocol::Tls { .. } => Effective::Tls,
+                        _ => Effective::Opaque,
+                    };
+                    ports.handled(tls.orig_dst_addr.port(), protocol);
+                    Forward::from((permit, tls))
+                })
                 .push(policy::NewTcpPolicy::layer(rt.metrics.tcp_authz.clone()))
                 .arc_new_tcp();
 
@@ -42,6 +42,7 @@ fn allow(protocol: Protocol) -> AllowPolicy {
                 name: "testsrv".into(),
             }),
             local_rate_limit: Arc::new(Default::default()),
+            termination_grace_period: Default::default(),
         },
     );
     allow
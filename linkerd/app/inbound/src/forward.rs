@@ -0,0 +1,301 @@
+//! Forwards inbound connections to configured application addresses.
+//!
+//! By default, the inbound proxy connects to each connection's original
+//! destination address. An inbound port may instead be mapped to another
+//! address--e.g., when the application listens on a different interface or
+//! port than the one on which its clients connect.
+
+use crate::ports::PortRegistry;
+use linkerd_app_core::{
+    svc,
+    transport::{Remote, ServerAddr},
+    Error,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use thiserror::Error;
+
+/// Maps inbound ports to the application addresses to which their
+/// connections are forwarded.
+///
+/// Connections on unmapped ports are forwarded to their original destination
+/// address.
+#[derive(Clone, Debug, Default)]
+pub struct ForwardTargets(Arc<HashMap<u16, SocketAddr>>);
+
+/// Indicates that an inbound port would be forwarded to one of the proxy's
+/// own listeners.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("inbound port {port} must not be forwarded to the proxy's own listener {target}")]
+pub struct ForwardLoop {
+    pub port: u16,
+    pub target: SocketAddr,
+}
+
+/// The address to which an inbound connection is forwarded.
+#[derive(Clone, Debug)]
+pub(crate) struct Target {
+    addr: Remote<ServerAddr>,
+
+    /// The inbound port, if its connections are forwarded to a configured
+    /// address.
+    mapped_port: Option<u16>,
+}
+
+/// Records the results of connections to configured forward targets.
+#[derive(Clone, Debug)]
+pub(crate) struct RecordConnect<S> {
+    ports: PortRegistry,
+    inner: S,
+}
+
+#[pin_project::pin_project]
+pub(crate) struct RecordConnectFuture<F> {
+    #[pin]
+    inner: F,
+    target: Option<(u16, SocketAddr)>,
+    ports: PortRegistry,
+}
+
+// === impl ForwardTargets ===
+
+impl ForwardTargets {
+    /// Validates that no port is forwarded to one of the proxy's listeners.
+    ///
+    /// A target refers to the proxy when its port is one of `proxy_ports` and
+    /// its IP is a loopback or unspecified address or one of `local_ips`.
+    pub fn new(
+        targets: HashMap<u16, SocketAddr>,
+        local_ips: &HashSet<IpAddr>,
+        proxy_ports: &[u16],
+    ) -> Result<Self, ForwardLoop> {
+        for (&port, &target) in &targets {
+            let ip = target.ip();
+            let is_local = ip.is_loopback() || ip.is_unspecified() || local_ips.contains(&ip);
+            if is_local && proxy_ports.contains(&target.port()) {
+                return Err(ForwardLoop { port, target });
+            }
+        }
+        Ok(Self(Arc::new(targets)))
+    }
+
+    /// Returns the address to which connections on `port` are forwarded, if
+    /// one is configured.
+    pub fn get(&self, port: u16) -> Option<SocketAddr> {
+        self.0.get(&port).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn target(&self, orig: Remote<ServerAddr>) -> Target {
+        let port = orig.port();
+        match self.get(port) {
+            Some(addr) => Target {
+                addr: Remote(ServerAddr(addr)),
+                mapped_port: Some(port),
+            },
+            None => Target {
+                addr: orig,
+                mapped_port: None,
+            },
+        }
+    }
+}
+
+// === impl Target ===
+
+impl svc::Param<Remote<ServerAddr>> for Target {
+    fn param(&self) -> Remote<ServerAddr> {
+        self.addr
+    }
+}
+
+// === impl RecordConnect ===
+
+impl<S> RecordConnect<S> {
+    pub(crate) fn layer(ports: PortRegistry) -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            ports: ports.clone(),
+            inner,
+        })
+    }
+}
+
+impl<S> svc::Service<Target> for RecordConnect<S>
+where
+    S: svc::Service<Target, Error = Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = RecordConnectFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: Target) -> Self::Future {
+        let mapped = target.mapped_port.map(|port| (port, target.addr.0 .0));
+        RecordConnectFuture {
+            inner: self.inner.call(target),
+            target: mapped,
+            ports: self.ports.clone(),
+        }
+    }
+}
+
+// === impl RecordConnectFuture ===
+
+impl<F, T> Future for RecordConnectFuture<F>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = futures::ready!(this.inner.poll(cx));
+        if let Some((port, target)) = this.target.take() {
+            // The error is returned unchanged so that the connection is
+            // handled as any other failed connection.
+            this.ports.forwarded(port, target, res.as_ref().err());
+        }
+        Poll::Ready(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{AllowPolicy, Meta, Protocol, ServerPolicy};
+    use linkerd_app_core::{
+        svc::ServiceExt,
+        transport::{self, Keepalive, OrigDstAddr, UserTimeout},
+    };
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn forwards_to_targets() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let app = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let app_addr = app.local_addr().unwrap();
+        let refused = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap()
+        };
+
+        let targets = ForwardTargets::new(
+            [(8080, app_addr), (9090, refused)].into_iter().collect(),
+            &HashSet::new(),
+            &[4143],
+        )
+        .unwrap();
+        let ports = PortRegistry::default();
+        for port in [8080, 9090] {
+            ports.accept(&allow(port));
+        }
+        let connect = svc::stack(transport::ConnectTcp::new(
+            Keepalive(None),
+            UserTimeout(None),
+        ))
+        .push(svc::MapErr::layer_boxed())
+        .push(RecordConnect::layer(ports.clone()))
+        .into_inner();
+
+        // Connections on a mapped port are established to its target rather
+        // than to the original destination.
+        let orig = |port: u16| Remote(ServerAddr(([192, 0, 2, 2], port).into()));
+        let (_io, local) = connect
+            .clone()
+            .oneshot(targets.target(orig(8080)))
+            .await
+            .expect("connection must be forwarded to the target");
+        let (_, client) = app.accept().await.unwrap();
+        assert_eq!(SocketAddr::from(local), client);
+
+        // Connections refused by the application fail as before.
+        connect
+            .oneshot(targets.target(orig(9090)))
+            .await
+            .expect_err("connection must be refused");
+
+        let forward = |port: u16| {
+            ports
+                .ports()
+                .into_iter()
+                .find(|p| p.port == port)
+                .and_then(|p| p.forward)
+                .expect("forward state must be recorded")
+        };
+        let ok = forward(8080);
+        assert_eq!((ok.target, ok.connections, ok.failures), (app_addr, 1, 0));
+        let failed = forward(9090);
+        assert_eq!(
+            (failed.target, failed.connections, failed.failures),
+            (refused, 0, 1)
+        );
+        assert!(failed.last_error.is_some());
+    }
+
+    fn allow(port: u16) -> AllowPolicy {
+        let (allow, _tx) = AllowPolicy::for_test(
+            OrigDstAddr(([192, 0, 2, 2], port).into()),
+            ServerPolicy {
+                protocol: Protocol::Opaque(Arc::new([])),
+                meta: Meta::new_default("test"),
+                local_rate_limit: Default::default(),
+                termination_grace_period: Default::default(),
+            },
+        );
+        allow
+    }
+
+    #[test]
+    fn rejects_loops() {
+        let local = [IpAddr::from([192, 0, 2, 2])].into_iter().collect();
+        let proxy_ports = [4143, 4140, 4191];
+        let targets = |port: u16, target: &str| {
+            ForwardTargets::new(
+                [(port, target.parse().unwrap())].into_iter().collect(),
+                &local,
+                &proxy_ports,
+            )
+        };
+
+        for target in [
+            "127.0.0.1:4143",
+            "0.0.0.0:4191",
+            "[::1]:4140",
+            "192.0.2.2:4143",
+        ] {
+            assert_eq!(
+                targets(8080, target).unwrap_err(),
+                ForwardLoop {
+                    port: 8080,
+                    target: target.parse().unwrap(),
+                },
+            );
+        }
+
+        for target in ["127.0.0.1:8080", "192.0.2.2:9090", "192.0.2.3:4143"] {
+            let forward = targets(8080, target).expect("target must be valid");
+            assert_eq!(forward.get(8080), Some(target.parse().unwrap()));
+            assert_eq!(forward.get(9090), None);
+        }
+
+        assert!(
+            ForwardTargets::new(HashMap::new(), &HashSet::new(), &proxy_ports)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
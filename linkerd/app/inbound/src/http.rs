@@ -1,9 +1,17 @@
+mod h2_settings;
+pub(crate) mod pressure;
 mod router;
 mod server;
 mod set_identity_header;
 #[cfg(test)]
 mod tests;
 
+pub use self::{
+    h2_settings::{Http2PortSettings, Http2Settings},
+    pressure::{AppOverloadedError, AppPressureConfig},
+    set_identity_header::ClientIdHeaderConfig,
+};
+
 fn trace_labels() -> std::collections::HashMap<String, String> {
     let mut l = std::collections::HashMap::new();
     l.insert("direction".to_string(), "inbound".to_string());
@@ -230,6 +238,7 @@ pub mod fuzz {
                     local_rate_limit: Arc::new(
                         linkerd_proxy_server_policy::LocalRateLimit::default(),
                     ),
+                    termination_grace_period: Default::default(),
                 },
             );
             policy
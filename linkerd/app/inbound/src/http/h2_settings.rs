@@ -0,0 +1,91 @@
+use linkerd_app_core::proxy::http::h2;
+use std::{collections::HashMap, sync::Arc};
+
+/// The initial window size defined by the HTTP/2 specification, used when a
+/// port only overrides one of the stream and connection windows and flow
+/// control is not otherwise fixed.
+const SPEC_WINDOW_SIZE: u32 = 65_535;
+
+/// Overrides the proxy's HTTP/2 settings for specific inbound ports.
+///
+/// Ports without overrides use the proxy's server and client settings.
+#[derive(Clone, Debug, Default)]
+pub struct Http2PortSettings(Arc<HashMap<u16, Http2Settings>>);
+
+/// HTTP/2 settings for an inbound port, applied both to the proxy's server
+/// and to its clients to the application.
+///
+/// Unset values fall back to the proxy's settings.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Http2Settings {
+    pub initial_stream_window_size: Option<u32>,
+    pub initial_connection_window_size: Option<u32>,
+    pub max_frame_size: Option<u32>,
+
+    /// Limits the streams that clients may open on the proxy's server. The
+    /// application advertises its own limit to the proxy's clients.
+    pub max_concurrent_streams: Option<u32>,
+}
+
+// === impl Http2PortSettings ===
+
+impl Http2PortSettings {
+    pub fn new(ports: HashMap<u16, Http2Settings>) -> Self {
+        Self(Arc::new(ports))
+    }
+
+    pub fn get(&self, port: u16) -> Option<&Http2Settings> {
+        self.0.get(&port)
+    }
+
+    pub(crate) fn server(&self, port: u16, params: &h2::ServerParams) -> h2::ServerParams {
+        let Some(settings) = self.get(port) else {
+            return params.clone();
+        };
+        h2::ServerParams {
+            flow_control: settings.flow_control(params.flow_control),
+            max_frame_size: settings.max_frame_size.or(params.max_frame_size),
+            max_concurrent_streams: settings
+                .max_concurrent_streams
+                .or(params.max_concurrent_streams),
+            ..params.clone()
+        }
+    }
+
+    pub(crate) fn client(&self, port: u16, params: &h2::ClientParams) -> h2::ClientParams {
+        let Some(settings) = self.get(port) else {
+            return params.clone();
+        };
+        h2::ClientParams {
+            flow_control: settings.flow_control(params.flow_control),
+            max_frame_size: settings.max_frame_size.or(params.max_frame_size),
+            ..params.clone()
+        }
+    }
+}
+
+// === impl Http2Settings ===
+
+impl Http2Settings {
+    fn flow_control(&self, flow_control: Option<h2::FlowControl>) -> Option<h2::FlowControl> {
+        if self.initial_stream_window_size.is_none()
+            && self.initial_connection_window_size.is_none()
+        {
+            return flow_control;
+        }
+
+        let (stream, connection) = match flow_control {
+            Some(h2::FlowControl::Fixed {
+                initial_stream_window_size,
+                initial_connection_window_size,
+            }) => (initial_stream_window_size, initial_connection_window_size),
+            _ => (SPEC_WINDOW_SIZE, SPEC_WINDOW_SIZE),
+        };
+        Some(h2::FlowControl::Fixed {
+            initial_stream_window_size: self.initial_stream_window_size.unwrap_or(stream),
+            initial_connection_window_size: self
+                .initial_connection_window_size
+                .unwrap_or(connection),
+        })
+    }
+}
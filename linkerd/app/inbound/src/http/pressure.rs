@@ -0,0 +1,521 @@
+//! Sheds inbound requests when the application is overloaded.
+//!
+//! The application's load is estimated from the number of requests in flight
+//! to it and from the 99th percentile latency of its recent responses. While
+//! either exceeds its configured threshold, a fraction of new requests are
+//! failed with a 503 response that instructs the client to retry later.
+//! Requests on routes that policy marks as sheddable are shed before requests
+//! on other routes. Requests are admitted normally once the application's load
+//! subsides.
+//!
+//! Requests are considered in flight until their response headers are
+//! received, so streaming response bodies do not count against the limit.
+
+use crate::policy::HttpRoutePermit;
+use futures::future;
+use linkerd_app_core::{
+    metrics::prom::{self, encoding::*},
+    proxy::http,
+    svc, Error, Result,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+use tokio::time;
+
+/// The maximum number of response latencies retained to estimate the
+/// application's p99 latency.
+const MAX_LATENCY_SAMPLES: usize = 1_000;
+
+/// The minimum number of response latencies required to estimate the
+/// application's p99 latency, so that a few slow responses do not cause
+/// requests to be shed.
+const MIN_LATENCY_SAMPLES: usize = 10;
+
+/// Requests on routes that are not sheddable are shed only once this many
+/// requests are owed, i.e. when there are too few requests on sheddable routes
+/// to shed the configured fraction of requests.
+const MAX_SHED_DEBT: f64 = 2.0;
+
+#[derive(Clone, Debug)]
+pub struct AppPressureConfig {
+    /// The number of requests that may be in flight to the application before
+    /// it is considered overloaded.
+    pub max_in_flight: Option<usize>,
+
+    /// The p99 latency of the application's responses beyond which it is
+    /// considered overloaded.
+    pub max_p99_latency: Option<time::Duration>,
+
+    /// The window of time over which response latencies are observed.
+    pub latency_window: time::Duration,
+
+    /// The fraction of new requests that are shed while the application is
+    /// overloaded, between 0 and 1.
+    pub shed_ratio: f64,
+
+    /// The delay after which clients are instructed to retry shed requests.
+    pub retry_after: time::Duration,
+}
+
+/// Tracks the application's load, shared by all inbound HTTP requests.
+#[derive(Clone, Debug)]
+pub(crate) struct AppPressure(Arc<Shared>);
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AppPressureMetrics {
+    shed: prom::Family<ShedLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewAppPressure<N> {
+    pressure: Option<AppPressure>,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AppPressureService<S> {
+    pressure: Option<AppPressure>,
+    sheddable: bool,
+    inner: S,
+}
+
+#[pin_project::pin_project]
+#[derive(Debug)]
+pub(crate) struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    in_flight: Option<InFlight>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("application is overloaded: {reason}")]
+pub struct AppOverloadedError {
+    reason: Reason,
+    retry_after: time::Duration,
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: AppPressureConfig,
+    in_flight: AtomicUsize,
+    state: Mutex<State>,
+    metrics: AppPressureMetrics,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    latencies: VecDeque<(time::Instant, time::Duration)>,
+
+    /// The number of requests that should have been shed but have not yet
+    /// been, so that requests on sheddable routes are shed first.
+    debt: f64,
+}
+
+/// Tracks a request that has been admitted to the application.
+#[derive(Debug)]
+struct InFlight {
+    shared: Arc<Shared>,
+    start: time::Instant,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct ShedLabels {
+    reason: Reason,
+    route: RouteClass,
+}
+
+/// Describes why the application is considered overloaded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum Reason {
+    in_flight,
+    latency,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum RouteClass {
+    sheddable,
+    default,
+}
+
+// === impl AppPressure ===
+
+impl AppPressure {
+    pub(crate) fn new(config: AppPressureConfig, metrics: AppPressureMetrics) -> Self {
+        Self(Arc::new(Shared {
+            config,
+            in_flight: AtomicUsize::new(0),
+            state: Mutex::new(State::default()),
+            metrics,
+        }))
+    }
+
+    /// Admits a request to the application unless it is overloaded and the
+    /// request is chosen to be shed.
+    fn admit(&self, sheddable: bool) -> Result<InFlight, AppOverloadedError> {
+        let Shared {
+            config,
+            in_flight,
+            state,
+            metrics,
+        } = &*self.0;
+        let now = time::Instant::now();
+
+        let mut state = state.lock();
+        let overloaded = if config
+            .max_in_flight
+            .is_some_and(|max| in_flight.load(Ordering::Acquire) >= max)
+        {
+            Some(Reason::in_flight)
+        } else {
+            config.max_p99_latency.and_then(|max| {
+                if let Some(min) = now.checked_sub(config.latency_window) {
+                    state.prune(min);
+                }
+                (state.p99()? > max).then_some(Reason::latency)
+            })
+        };
+
+        match overloaded {
+            None => state.debt = 0.0,
+            Some(reason) => {
+                state.debt += config.shed_ratio;
+                let threshold = if sheddable { 1.0 } else { MAX_SHED_DEBT };
+                if state.debt >= threshold {
+                    state.debt -= 1.0;
+                    drop(state);
+                    metrics.shed(reason, sheddable);
+                    return Err(AppOverloadedError {
+                        reason,
+                        retry_after: config.retry_after,
+                    });
+                }
+            }
+        }
+        drop(state);
+
+        in_flight.fetch_add(1, Ordering::AcqRel);
+        Ok(InFlight {
+            shared: self.0.clone(),
+            start: now,
+        })
+    }
+}
+
+// === impl State ===
+
+impl State {
+    fn record(&mut self, now: time::Instant, latency: time::Duration) {
+        if self.latencies.len() == MAX_LATENCY_SAMPLES {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back((now, latency));
+    }
+
+    /// Discards latencies observed before `min`.
+    fn prune(&mut self, min: time::Instant) {
+        while self.latencies.front().is_some_and(|(t, _)| *t < min) {
+            self.latencies.pop_front();
+        }
+    }
+
+    fn p99(&self) -> Option<time::Duration> {
+        if self.latencies.len() < MIN_LATENCY_SAMPLES {
+            return None;
+        }
+        let mut latencies = self.latencies.iter().map(|(_, l)| *l).collect::<Vec<_>>();
+        let idx = (latencies.len() * 99).div_ceil(100) - 1;
+        let (_, p99, _) = latencies.select_nth_unstable(idx);
+        Some(*p99)
+    }
+}
+
+// === impl InFlight ===
+
+impl InFlight {
+    /// Records the latency of the application's response.
+    fn complete(self) {
+        let now = time::Instant::now();
+        let latency = now.saturating_duration_since(self.start);
+        self.shared.state.lock().record(now, latency);
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.shared.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+// === impl AppPressureMetrics ===
+
+impl AppPressureMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let shed = prom::Family::default();
+        registry.register(
+            "shed_requests",
+            "The number of requests shed because the application was overloaded",
+            shed.clone(),
+        );
+        Self { shed }
+    }
+
+    fn shed(&self, reason: Reason, sheddable: bool) {
+        let route = if sheddable {
+            RouteClass::sheddable
+        } else {
+            RouteClass::default
+        };
+        self.shed.get_or_create(&ShedLabels { reason, route }).inc();
+    }
+}
+
+// === impl NewAppPressure ===
+
+impl<N> NewAppPressure<N> {
+    /// Returns a layer that sheds requests when the application is overloaded.
+    /// Requests are never shed if `pressure` is `None`.
+    pub(crate) fn layer(
+        pressure: Option<AppPressure>,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            pressure: pressure.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<(HttpRoutePermit, T)> for NewAppPressure<N>
+where
+    N: svc::NewService<(HttpRoutePermit, T)>,
+{
+    type Service = AppPressureService<N::Service>;
+
+    fn new_service(&self, (permit, target): (HttpRoutePermit, T)) -> Self::Service {
+        AppPressureService {
+            pressure: self.pressure.clone(),
+            sheddable: permit.sheddable,
+            inner: self.inner.new_service((permit, target)),
+        }
+    }
+}
+
+// === impl AppPressureService ===
+
+impl<B, S> svc::Service<http::Request<B>> for AppPressureService<S>
+where
+    S: svc::Service<http::Request<B>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future =
+        future::Either<ResponseFuture<S::Future>, future::Ready<Result<S::Response, Error>>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let in_flight = match self.pressure.as_ref().map(|p| p.admit(self.sheddable)) {
+            None => None,
+            Some(Ok(in_flight)) => Some(in_flight),
+            Some(Err(error)) => return future::Either::Right(future::err(error.into())),
+        };
+        future::Either::Left(ResponseFuture {
+            inner: self.inner.call(req),
+            in_flight,
+        })
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, R, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<R, E>>,
+    E: Into<Error>,
+{
+    type Output = Result<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = futures::ready!(this.inner.poll(cx));
+        if let Some(in_flight) = this.in_flight.take() {
+            in_flight.complete();
+        }
+        Poll::Ready(res.map_err(Into::into))
+    }
+}
+
+// === impl AppOverloadedError ===
+
+impl AppOverloadedError {
+    pub fn retry_after(&self) -> time::Duration {
+        self.retry_after
+    }
+}
+
+// === impl Reason ===
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::in_flight => f.write_str("too many requests in flight"),
+            Self::latency => f.write_str("p99 latency exceeds its threshold"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svc::ServiceExt;
+
+    const THRESHOLD: time::Duration = time::Duration::from_millis(100);
+    const WINDOW: time::Duration = time::Duration::from_secs(10);
+    const RETRY_AFTER: time::Duration = time::Duration::from_secs(1);
+
+    fn mk_pressure(config: AppPressureConfig) -> (AppPressure, AppPressureMetrics) {
+        let metrics = AppPressureMetrics::register(&mut prom::Registry::default());
+        (AppPressure::new(config, metrics.clone()), metrics)
+    }
+
+    fn shed_count(metrics: &AppPressureMetrics, reason: Reason, route: RouteClass) -> u64 {
+        metrics
+            .shed
+            .get_or_create(&ShedLabels { reason, route })
+            .get()
+    }
+
+    /// Sends `n` requests sequentially, returning the number that were shed.
+    async fn send_requests<S>(svc: &mut AppPressureService<S>, n: usize) -> usize
+    where
+        S: svc::Service<http::Request<()>, Response = http::Response<()>, Error = Error>,
+    {
+        let mut shed = 0;
+        for _ in 0..n {
+            let req = http::Request::new(());
+            match svc.ready().await.unwrap().call(req).await {
+                Ok(_) => {}
+                Err(error) => {
+                    let error = error
+                        .downcast::<AppOverloadedError>()
+                        .expect("requests must only fail when shed");
+                    assert_eq!(error.retry_after(), RETRY_AFTER);
+                    shed += 1;
+                }
+            }
+        }
+        shed
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn sheds_while_latency_is_high() {
+        let (pressure, metrics) = mk_pressure(AppPressureConfig {
+            max_in_flight: None,
+            max_p99_latency: Some(THRESHOLD),
+            latency_window: WINDOW,
+            shed_ratio: 0.5,
+            retry_after: RETRY_AFTER,
+        });
+
+        // The application's latency is injected by the inner service.
+        let latency = Arc::new(Mutex::new(time::Duration::from_millis(10)));
+        let mut svc = AppPressureService {
+            pressure: Some(pressure),
+            sheddable: true,
+            inner: svc::mk({
+                let latency = latency.clone();
+                move |_: http::Request<()>| {
+                    let latency = *latency.lock();
+                    async move {
+                        time::sleep(latency).await;
+                        Ok::<_, Error>(http::Response::new(()))
+                    }
+                }
+            }),
+        };
+
+        assert_eq!(send_requests(&mut svc, 20).await, 0);
+
+        // Once the application's p99 latency exceeds the threshold, half of
+        // all requests are shed.
+        *latency.lock() = time::Duration::from_millis(500);
+        assert_eq!(send_requests(&mut svc, 1).await, 0);
+        assert_eq!(send_requests(&mut svc, 20).await, 10);
+        assert_eq!(
+            shed_count(&metrics, Reason::latency, RouteClass::sheddable),
+            10
+        );
+
+        // Once the slow responses age out of the window, requests are no
+        // longer shed.
+        *latency.lock() = time::Duration::from_millis(10);
+        time::sleep(WINDOW).await;
+        assert_eq!(send_requests(&mut svc, 20).await, 0);
+        assert_eq!(
+            shed_count(&metrics, Reason::latency, RouteClass::sheddable),
+            10
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn prefers_sheddable_routes() {
+        let (pressure, metrics) = mk_pressure(AppPressureConfig {
+            max_in_flight: Some(1),
+            max_p99_latency: None,
+            latency_window: WINDOW,
+            shed_ratio: 0.5,
+            retry_after: RETRY_AFTER,
+        });
+
+        let held = pressure.admit(false).expect("request must be admitted");
+
+        // While the application is overloaded, only requests on sheddable
+        // routes are shed so long as there are enough of them.
+        let mut admitted = vec![];
+        for _ in 0..10 {
+            admitted.push(pressure.admit(false).expect("request must be admitted"));
+            assert!(pressure.admit(true).is_err());
+        }
+        assert_eq!(
+            shed_count(&metrics, Reason::in_flight, RouteClass::sheddable),
+            10
+        );
+        assert_eq!(
+            shed_count(&metrics, Reason::in_flight, RouteClass::default),
+            0
+        );
+
+        // Without requests on sheddable routes, other requests are shed once
+        // enough requests are owed.
+        let shed = (0..10)
+            .map(|_| pressure.admit(false).is_err())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shed,
+            [false, false, false, true, false, true, false, true, false, true]
+        );
+        assert_eq!(
+            shed_count(&metrics, Reason::in_flight, RouteClass::default),
+            4
+        );
+
+        // Once requests complete, requests are admitted again.
+        drop((held, admitted));
+        assert!(pressure.admit(true).is_ok());
+        assert_eq!(pressure.0.in_flight.load(Ordering::Acquire), 0);
+    }
+}
@@ -86,6 +86,7 @@ impl<C> Inbound<C> {
             let unsafe_authority_labels = config.unsafe_authority_labels;
             let h1_params = config.proxy.connect.http1;
             let h2_params = config.proxy.connect.http2.clone();
+            let h2_ports = config.http2_ports.clone();
 
             // Creates HTTP clients for each inbound port & HTTP settings.
             let http = connect
@@ -107,7 +108,9 @@ impl<C> Inbound<C> {
                         permit: t.permit,
                         params: match t.http {
                             http::Variant::Http1 => http::client::Params::Http1(h1_params),
-                            http::Variant::H2 => http::client::Params::H2(h2_params.clone())
+                            http::Variant::H2 => {
+                                http::client::Params::H2(h2_ports.client(t.addr.port(), &h2_params))
+                            }
                         },
                     }
                 })
@@ -242,8 +245,15 @@ impl<C> Inbound<C> {
                     LogicalPerRequest::from((permit.clone(), t.clone()))
                 }))
                 .check_new_service::<(policy::HttpRoutePermit, T), http::Request<http::BoxBody>>()
+                // Consult the external authorization service for routes that
+                // require it.
+                .push(policy::NewExtAuthz::layer(rt.ext_authz.clone()))
+                // Shed requests when the application is overloaded, once the
+                // route is known.
+                .push(super::pressure::NewAppPressure::layer(rt.app_pressure.clone()))
                 .push(svc::ArcNewService::layer())
                 .push(policy::NewHttpPolicy::layer(rt.metrics.http_authz.clone()))
+                .push_on_service(http::BoxResponse::layer())
                 // Used by tap.
                 .push_http_insert_target::<tls::ConditionalServerTls>()
                 .push_http_insert_target::<Remote<ClientAddr>>()
@@ -496,10 +506,16 @@ impl ExtractParam<errors::respond::EmitHeaders, Logical> for ClientRescue {
 impl errors::HttpRescue<Error> for ClientRescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if errors::is_caused_by::<std::io::Error>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(
+                errors::ErrorCode::ConnectFailed,
+                error,
+            ));
         }
         if errors::is_caused_by::<errors::ConnectTimeout>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(
+                errors::ErrorCode::ConnectTimeout,
+                error,
+            ));
         }
 
         Err(error)
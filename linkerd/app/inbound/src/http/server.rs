@@ -1,4 +1,4 @@
-use super::set_identity_header::NewSetIdentityHeader;
+use super::{set_identity_header::NewSetIdentityHeader, Http2PortSettings};
 use crate::{policy, Inbound};
 pub use linkerd_app_core::proxy::http::{normalize_uri, Variant};
 use linkerd_app_core::{
@@ -11,7 +11,7 @@ use linkerd_app_core::{
     transport::{ClientAddr, OrigDstAddr, Remote},
     Error, Result,
 };
-use linkerd_http_access_log::NewAccessLog;
+use linkerd_http_access_log::{NewAccessLog, SampleRatio};
 
 #[derive(Copy, Clone, Debug)]
 struct ServerRescue;
@@ -54,13 +54,27 @@ impl<H> Inbound<H> {
                 max_in_flight_requests,
                 ..
             } = config.proxy;
+            let compression = config.http_compression.clone();
+            let request_id = config.http_request_id.clone();
+            let deadline = config.http_deadline.clone();
+            let client_id_header = config.http_client_id_header.clone();
+            let workload_identity = config.http_workload_identity.clone();
 
             http.check_new_service::<T, http::Request<_>>()
+                // Translate gRPC-Web requests from browser clients into
+                // native (HTTP/2) gRPC. This must be below the URI
+                // normalization layer so that HTTP/1 requests are given an
+                // authority before they are upgraded.
+                .push_on_service(http::GrpcWeb::layer())
                 // Convert origin form HTTP/1 URIs to absolute form for Hyper's
                 // `Client`. This must be below the `orig_proto::Downgrade` layer, since
                 // the request may have been downgraded from a HTTP/2 orig-proto request.
                 .push(http::NewNormalizeUri::layer())
-                .push(NewSetIdentityHeader::layer(()))
+                .push(NewSetIdentityHeader::layer(
+                    (),
+                    client_id_header,
+                    workload_identity,
+                ))
                 // Downgrades the protocol if upgraded by an outbound proxy.
                 .push_on_service(http::orig_proto::Downgrade::layer())
                 // Limit the number of in-flight inbound requests.
@@ -74,6 +88,8 @@ impl<H> Inbound<H> {
                 // Shed load by failing requests when the concurrency
                 // limit is reached.
                 .push_on_service(svc::LoadShed::layer())
+                // Fail requests that have already exceeded their deadline.
+                .push_on_service(http::PropagateDeadline::layer(deadline))
                 .push(svc::NewMapErr::layer_from_target::<ServerError, _>())
                 .push_on_service(svc::MapErr::layer_boxed())
                 .push(rt.metrics.http_errors.to_layer())
@@ -84,8 +100,23 @@ impl<H> Inbound<H> {
                 ))
                 // Record when an HTTP/1 URI was in absolute form
                 .push_on_service(http::normalize_uri::MarkAbsoluteForm::layer())
+                // Compress eligible application responses, if configured.
+                .push_on_service(http::Compress::layer(
+                    compression,
+                    rt.metrics.http_compression.clone(),
+                ))
                 .push_on_service(http::BoxResponse::layer())
-                .push(NewAccessLog::layer())
+                // The sample ratio may be changed while the proxy is running.
+                .push(NewAccessLog::layer_sampled({
+                    let tunables = rt.tunables.clone();
+                    move |_: &()| {
+                        SampleRatio(tunables.borrow().access_log_sample_ratio.unwrap_or(1.0))
+                    }
+                }))
+                // Ensure that each request has an ID, if configured. This
+                // must be above the access log and error responder so that
+                // the ID is available to them.
+                .push_on_service(http::SetRequestId::layer(request_id))
                 .arc_new_clone_http()
         })
     }
@@ -95,7 +126,7 @@ impl<H> Inbound<H> {
     pub fn push_http_tcp_server<T, I, HSvc>(self) -> Inbound<svc::ArcNewTcp<T, I>>
     where
         // Connection target.
-        T: Param<Variant>,
+        T: Param<Variant> + Param<OrigDstAddr> + Param<tls::ConditionalServerTls>,
         T: Clone + Send + Unpin + 'static,
         // Server-side socket.
         I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + Send + Unpin + 'static,
@@ -113,15 +144,31 @@ impl<H> Inbound<H> {
     {
         self.map_stack(|config, rt, http| {
             let h2 = config.proxy.server.http2.clone();
+            let h2_ports = config.http2_ports.clone();
+            let mesh_h2 = config
+                .http2_mesh_adaptive_flow_control
+                .then(|| http::h2::ServerParams {
+                    flow_control: Some(http::h2::FlowControl::Adaptive),
+                    ..h2.clone()
+                });
             let drain = rt.drain.clone();
 
             http.check_new_service::<T, http::Request<http::BoxBody>>()
                 .unlift_new()
                 .check_new_new_service::<T, http::ClientHandle, http::Request<_>>()
-                .push(http::NewServeHttp::layer(move |t: &T| http::ServerParams {
-                    version: t.param(),
-                    http2: h2.clone(),
-                    drain: drain.clone(),
+                .push(http::NewServeHttp::layer(move |t: &T| {
+                    let OrigDstAddr(addr) = t.param();
+                    http::ServerParams {
+                        version: t.param(),
+                        http2: server_http2(
+                            &h2,
+                            mesh_h2.as_ref(),
+                            &h2_ports,
+                            addr.port(),
+                            &t.param(),
+                        ),
+                        drain: drain.clone(),
+                    }
                 }))
                 .check_new_service::<T, I>()
                 .arc_new_tcp()
@@ -129,6 +176,29 @@ impl<H> Inbound<H> {
     }
 }
 
+/// Returns the HTTP/2 server settings for a connection.
+///
+/// Connections from meshed clients use `mesh_h2`, if it is set, so that they
+/// may use adaptive flow control. Port settings take precedence.
+fn server_http2(
+    h2: &http::h2::ServerParams,
+    mesh_h2: Option<&http::h2::ServerParams>,
+    h2_ports: &Http2PortSettings,
+    port: u16,
+    tls: &tls::ConditionalServerTls,
+) -> http::h2::ServerParams {
+    let h2 = match (mesh_h2, tls) {
+        (
+            Some(mesh_h2),
+            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_id: Some(_), ..
+            }),
+        ) => mesh_h2,
+        _ => h2,
+    };
+    h2_ports.server(port, h2)
+}
+
 impl<T> From<(&T, Error)> for ServerError
 where
     T: Param<OrigDstAddr>,
@@ -181,16 +251,34 @@ impl<T: Param<tls::ConditionalServerTls>> ExtractParam<errors::respond::EmitHead
 impl errors::HttpRescue<Error> for ServerRescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if errors::is_caused_by::<policy::HttpRouteNotFound>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::not_found(error));
+            return Ok(errors::SyntheticHttpResponse::not_found(
+                errors::ErrorCode::NoRoute,
+                error,
+            ));
         }
 
         if errors::is_caused_by::<policy::HttpRouteUnauthorized>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::permission_denied(error));
+            return Ok(errors::SyntheticHttpResponse::permission_denied(
+                errors::ErrorCode::Unauthorized,
+                error,
+            ));
+        }
+
+        if errors::is_caused_by::<policy::ExtAuthzDenied>(&*error)
+            || errors::is_caused_by::<policy::ExtAuthzUnavailable>(&*error)
+        {
+            return Ok(errors::SyntheticHttpResponse::permission_denied(
+                errors::ErrorCode::Unauthorized,
+                error,
+            ));
         }
 
         if errors::is_caused_by::<policy::HttpRouteInvalidRedirect>(&*error) {
             tracing::warn!(%error);
-            return Ok(errors::SyntheticHttpResponse::unexpected_error());
+            return Ok(errors::SyntheticHttpResponse::internal_error(
+                errors::ErrorCode::InvalidRedirect,
+                "unexpected error",
+            ));
         }
         if let Some(policy::HttpRouteRedirect { status, location }) =
             errors::cause_ref::<policy::HttpRouteRedirect>(&*error)
@@ -199,28 +287,69 @@ impl errors::HttpRescue<Error> for ServerRescue {
         }
         if errors::is_caused_by::<policy::HttpInvalidPolicy>(&*error) {
             return Ok(errors::SyntheticHttpResponse::internal_error(
+                errors::ErrorCode::InvalidPolicy,
                 error.to_string(),
             ));
         }
 
         if errors::is_caused_by::<linkerd_proxy_server_policy::RateLimitError>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::rate_limited(error));
+            return Ok(errors::SyntheticHttpResponse::rate_limited(
+                errors::ErrorCode::RateLimited,
+                error,
+            ));
         }
 
         if errors::is_caused_by::<crate::GatewayDomainInvalid>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::not_found(error));
+            return Ok(errors::SyntheticHttpResponse::not_found(
+                errors::ErrorCode::InvalidGatewayDomain,
+                error,
+            ));
         }
         if errors::is_caused_by::<crate::GatewayIdentityRequired>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::unauthenticated(error));
+            return Ok(errors::SyntheticHttpResponse::unauthenticated(
+                errors::ErrorCode::Unauthenticated,
+                error,
+            ));
         }
         if errors::is_caused_by::<crate::GatewayLoop>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::loop_detected(error));
+            return Ok(errors::SyntheticHttpResponse::loop_detected(
+                errors::ErrorCode::LoopDetected,
+                error,
+            ));
+        }
+        if errors::is_caused_by::<crate::GatewayUnauthorized>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::permission_denied(
+                errors::ErrorCode::Unauthorized,
+                error,
+            ));
         }
         if errors::is_caused_by::<errors::FailFastError>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(
+                errors::ErrorCode::Failfast,
+                error,
+            ));
         }
         if errors::is_caused_by::<errors::LoadShedError>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::unavailable(error));
+            return Ok(errors::SyntheticHttpResponse::unavailable(
+                errors::ErrorCode::LoadShed,
+                error,
+            ));
+        }
+        if let Some(retry_after) =
+            errors::cause_ref::<crate::AppOverloadedError>(&*error).map(|e| e.retry_after())
+        {
+            return Ok(errors::SyntheticHttpResponse::overloaded(
+                errors::ErrorCode::Overloaded,
+                error,
+                retry_after,
+            ));
+        }
+
+        if errors::is_caused_by::<http::stream_timeouts::DeadlineExceededError>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout_nonfatal(
+                errors::ErrorCode::DeadlineExceeded,
+                error,
+            ));
         }
 
         if errors::is_caused_by::<errors::H2Error>(&*error) {
@@ -231,3 +360,93 @@ impl errors::HttpRescue<Error> for ServerRescue {
         Ok(errors::SyntheticHttpResponse::unexpected_error())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Http2Settings;
+
+    fn meshed() -> tls::ConditionalServerTls {
+        tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+            client_id: Some(tls::ClientId(
+                "foosa.barns.serviceaccount.identity.linkerd.cluster.local"
+                    .parse()
+                    .unwrap(),
+            )),
+            negotiated_protocol: None,
+        })
+    }
+
+    fn mesh_h2(h2: &http::h2::ServerParams) -> http::h2::ServerParams {
+        http::h2::ServerParams {
+            flow_control: Some(http::h2::FlowControl::Adaptive),
+            ..h2.clone()
+        }
+    }
+
+    #[test]
+    fn adaptive_flow_control_for_meshed_clients() {
+        let h2 = http::h2::ServerParams {
+            flow_control: Some(http::h2::FlowControl::Fixed {
+                initial_stream_window_size: 1_000,
+                initial_connection_window_size: 10_000,
+            }),
+            ..Default::default()
+        };
+        let mesh_h2 = mesh_h2(&h2);
+        let ports = Http2PortSettings::default();
+
+        assert_eq!(
+            server_http2(&h2, Some(&mesh_h2), &ports, 8080, &meshed()).flow_control,
+            Some(http::h2::FlowControl::Adaptive),
+        );
+
+        // Unmeshed clients, and meshed clients without an identity, use the
+        // server's settings.
+        let unmeshed = tls::ConditionalServerTls::None(tls::NoServerTls::NoClientHello);
+        assert_eq!(
+            server_http2(&h2, Some(&mesh_h2), &ports, 8080, &unmeshed),
+            h2
+        );
+        let anonymous = tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+            client_id: None,
+            negotiated_protocol: None,
+        });
+        assert_eq!(
+            server_http2(&h2, Some(&mesh_h2), &ports, 8080, &anonymous),
+            h2
+        );
+
+        // Adaptive flow control may be disabled.
+        assert_eq!(server_http2(&h2, None, &ports, 8080, &meshed()), h2);
+    }
+
+    #[test]
+    fn port_settings_override_adaptive_flow_control() {
+        let h2 = http::h2::ServerParams::default();
+        let mesh_h2 = mesh_h2(&h2);
+        let ports = Http2PortSettings::new(
+            [(
+                8080,
+                Http2Settings {
+                    initial_stream_window_size: Some(1_000),
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        );
+
+        assert_eq!(
+            server_http2(&h2, Some(&mesh_h2), &ports, 8080, &meshed()).flow_control,
+            Some(http::h2::FlowControl::Fixed {
+                initial_stream_window_size: 1_000,
+                initial_connection_window_size: 65_535,
+            }),
+        );
+        assert_eq!(
+            server_http2(&h2, Some(&mesh_h2), &ports, 8081, &meshed()).flow_control,
+            Some(http::h2::FlowControl::Adaptive),
+        );
+    }
+}
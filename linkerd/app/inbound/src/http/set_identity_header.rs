@@ -1,12 +1,36 @@
-use linkerd_app_core::{proxy::http, svc, tls};
-use std::task::{Context, Poll};
+use linkerd_app_core::{
+    identity::Id,
+    proxy::http::{self, workload_identity},
+    svc, tls,
+};
+use std::{
+    task::{Context, Poll},
+    time::SystemTime,
+};
 use tracing::{debug, trace};
 
 const HEADER_NAME: &str = "l5d-client-id";
 
+/// Configures how the `l5d-client-id` header is set on inbound requests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientIdHeaderConfig {
+    /// Whether the verified client identity is set on requests.
+    pub inject: bool,
+
+    /// A value that is set on requests from clients without a verified
+    /// identity. When unset, no value is set on these requests.
+    pub unauthenticated: Option<http::HeaderValue>,
+
+    /// Whether client-supplied values are removed when the header is not
+    /// injected. Client-supplied values are always removed when it is.
+    pub strict: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct NewSetIdentityHeader<P, N> {
     params: P,
+    config: ClientIdHeaderConfig,
+    verifier: Option<workload_identity::Verifier>,
     inner: N,
 }
 
@@ -14,15 +38,51 @@ pub struct NewSetIdentityHeader<P, N> {
 pub struct SetIdentityHeader<M> {
     inner: M,
     value: Option<http::HeaderValue>,
+    inject: bool,
+    strip: bool,
+    assertions: Assertions,
+}
+
+/// Determines how workload identity assertions are handled.
+#[derive(Clone, Debug)]
+enum Assertions {
+    /// Assertions are forwarded to the application.
+    Forward,
+
+    /// Assertions are removed without being verified, because the client is
+    /// not authenticated.
+    Strip,
+
+    /// Assertions are removed and, if they are valid, the asserted identity
+    /// replaces the client's identity.
+    Verify(workload_identity::Verifier),
+}
+
+// === impl ClientIdHeaderConfig ===
+
+impl Default for ClientIdHeaderConfig {
+    fn default() -> Self {
+        Self {
+            inject: true,
+            unauthenticated: None,
+            strict: true,
+        }
+    }
 }
 
 // === impl NewSetIdentityHeader ===
 
 impl<P: Clone, N> NewSetIdentityHeader<P, N> {
-    pub fn layer(params: P) -> impl svc::Layer<N, Service = Self> + Clone {
+    pub fn layer(
+        params: P,
+        config: ClientIdHeaderConfig,
+        verifier: Option<workload_identity::Verifier>,
+    ) -> impl svc::Layer<N, Service = Self> + Clone {
         svc::layer::mk(move |inner| Self {
             inner,
             params: params.clone(),
+            config: config.clone(),
+            verifier: verifier.clone(),
         })
     }
 }
@@ -36,26 +96,40 @@ where
 
     #[inline]
     fn new_service(&self, t: T) -> Self::Service {
-        let value = self
-            .params
-            .extract_param(&t)
-            .value()
-            .and_then(|tls| match tls {
-                tls::ServerTls::Established { client_id, .. } => {
-                    client_id.as_ref().and_then(|id| {
-                        match http::HeaderValue::from_str(&id.to_str()) {
-                            Ok(v) => Some(v),
-                            Err(error) => {
-                                tracing::warn!(%error, "identity not a valid header value");
-                                None
-                            }
-                        }
-                    })
-                }
-                _ => None,
-            });
+        let client_id = match self.params.extract_param(&t) {
+            tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+                client_id: Some(tls::ClientId(id)),
+                ..
+            }) => Some(id),
+            _ => None,
+        };
+
+        // Assertions are only accepted from authenticated clients.
+        let assertions = match (self.verifier.clone(), client_id.is_some()) {
+            (None, _) => Assertions::Forward,
+            (Some(verifier), true) => Assertions::Verify(verifier),
+            (Some(_), false) => Assertions::Strip,
+        };
+
+        if !self.config.inject {
+            return SetIdentityHeader {
+                value: None,
+                inject: false,
+                strip: self.config.strict,
+                assertions,
+                inner: self.inner.new_service(t),
+            };
+        }
+
+        let value = client_id
+            .as_ref()
+            .and_then(header_value)
+            .or_else(|| self.config.unauthenticated.clone());
         SetIdentityHeader {
             value,
+            inject: true,
+            strip: true,
+            assertions,
             inner: self.inner.new_service(t),
         }
     }
@@ -77,16 +151,72 @@ where
     }
 
     fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
-        let prior = if let Some(id) = self.value.clone() {
+        let asserted = self.verify_assertion(&mut req);
+        let value = match asserted {
+            Some(ref id) if self.inject => header_value(id),
+            _ => self.value.clone(),
+        };
+
+        let prior = if let Some(id) = value {
             trace!(header = %HEADER_NAME, ?id, "Setting identity header");
             req.headers_mut().insert(HEADER_NAME, id)
-        } else {
+        } else if self.strip {
             req.headers_mut().remove(HEADER_NAME)
+        } else {
+            None
         };
         if let Some(value) = prior {
             debug!(header = %HEADER_NAME, ?value, "Stripped identity header");
         }
 
+        if let Some(id) = asserted {
+            req.extensions_mut()
+                .insert(workload_identity::VerifiedIdentity(id));
+        }
+
         self.inner.call(req)
     }
 }
+
+impl<S> SetIdentityHeader<S> {
+    /// Removes a workload identity assertion from the request, returning the
+    /// asserted identity if the assertion is verified.
+    fn verify_assertion<B>(&self, req: &mut http::Request<B>) -> Option<Id> {
+        let verifier = match self.assertions {
+            Assertions::Forward => return None,
+            Assertions::Strip => {
+                if req
+                    .headers_mut()
+                    .remove(&workload_identity::HEADER)
+                    .is_some()
+                {
+                    debug!("Stripped workload identity assertion from unauthenticated client");
+                }
+                return None;
+            }
+            Assertions::Verify(ref verifier) => verifier,
+        };
+
+        let value = req.headers_mut().remove(&workload_identity::HEADER)?;
+        match verifier.verify(&value, SystemTime::now()) {
+            Ok(id) => {
+                debug!(%id, "Verified workload identity assertion");
+                Some(id)
+            }
+            Err(error) => {
+                debug!(%error, "Ignoring workload identity assertion");
+                None
+            }
+        }
+    }
+}
+
+fn header_value(id: &Id) -> Option<http::HeaderValue> {
+    match http::HeaderValue::from_str(&id.to_str()) {
+        Ok(v) => Some(v),
+        Err(error) => {
+            tracing::warn!(%error, "identity not a valid header value");
+            None
+        }
+    }
+}
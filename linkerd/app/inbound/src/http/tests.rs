@@ -24,6 +24,9 @@ use tokio::time;
 use tower::ServiceExt;
 use tracing::Instrument;
 
+static REQUEST_ID: http::HeaderName = http::HeaderName::from_static("x-request-id");
+static CLIENT_ID: http::HeaderName = http::HeaderName::from_static("l5d-client-id");
+
 fn build_server<I>(
     cfg: Config,
     rt: ProxyRuntime,
@@ -278,7 +281,314 @@ async fn http1_bad_gateway_meshed_response_error_header() {
     // because we don't build a real HTTP endpoint stack, which adds error
     // context to this error, and the client rescue layer is below where the
     // logical error context is added.
-    check_error_header(rsp.headers(), "client error (Connect)");
+    check_error_header(rsp.headers(), "connect_failed: client error (Connect)");
+
+    // Wait for all of the background tasks to complete, panicking if any returned an error.
+    drop(client);
+    bg.join_all()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<()>, Error>>()
+        .expect("background task failed");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn http1_request_id() {
+    let mut server = hyper::server::conn::http1::Builder::new();
+    server.timer(hyper_util::rt::TokioTimer::new());
+    let mut client = hyper::client::conn::http1::Builder::new();
+
+    let _trace = trace_init();
+
+    let connect = support::connect()
+        .endpoint_fn_boxed(Target::addr(), echo_header_server(server, &REQUEST_ID));
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+    let cfg = Config {
+        http_request_id: Some(http::request_id::Config {
+            header: REQUEST_ID.clone(),
+            overwrite: false,
+        }),
+        ..default_config()
+    };
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_HTTP1);
+    let (mut client, bg) = http_util::connect_and_accept_http1(&mut client, server).await;
+
+    // Requests without an ID are assigned one.
+    let req = Request::builder()
+        .method(http::Method::GET)
+        .uri("http://foo.svc.cluster.local:5550")
+        .body(BoxBody::default())
+        .unwrap();
+    let rsp = client
+        .send_request(req)
+        .await
+        .expect("HTTP client request failed");
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    let id = http_util::body_to_string(rsp.into_body()).await.unwrap();
+    assert_eq!(id.len(), 26, "expected a ULID; got {id:?}");
+
+    // Requests with an ID are forwarded unmodified.
+    let req = Request::builder()
+        .method(http::Method::GET)
+        .uri("http://foo.svc.cluster.local:5550")
+        .header(&REQUEST_ID, "client-id")
+        .body(BoxBody::default())
+        .unwrap();
+    let rsp = client
+        .send_request(req)
+        .await
+        .expect("HTTP client request failed");
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    let id = http_util::body_to_string(rsp.into_body()).await.unwrap();
+    assert_eq!(id, "client-id");
+
+    // Wait for all of the background tasks to complete, panicking if any returned an error.
+    drop(client);
+    bg.join_all()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<()>, Error>>()
+        .expect("background task failed");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn http1_client_id_header() {
+    let _trace = trace_init();
+    const MESHED_ID: &str = "foosa.barns.serviceaccount.identity.linkerd.cluster.local";
+
+    // By default, the verified identity replaces client-supplied values, which
+    // are otherwise stripped.
+    let cfg = default_config();
+    assert_eq!(
+        client_id_header(cfg.clone(), Target::meshed_http1(), Some("spoofed")).await,
+        MESHED_ID
+    );
+    assert_eq!(
+        client_id_header(cfg.clone(), Target::UNMESHED_HTTP1, Some("spoofed")).await,
+        ""
+    );
+    assert_eq!(
+        client_id_header(cfg, Target::UNMESHED_HTTP1, None).await,
+        ""
+    );
+
+    // Unauthenticated clients may be marked with a sentinel value.
+    let cfg = Config {
+        http_client_id_header: crate::ClientIdHeaderConfig {
+            unauthenticated: Some(http::HeaderValue::from_static("unauthenticated")),
+            ..Default::default()
+        },
+        ..default_config()
+    };
+    assert_eq!(
+        client_id_header(cfg.clone(), Target::UNMESHED_HTTP1, Some("spoofed")).await,
+        "unauthenticated"
+    );
+    assert_eq!(
+        client_id_header(cfg, Target::meshed_http1(), Some("spoofed")).await,
+        MESHED_ID
+    );
+
+    // When injection is disabled, client-supplied values are still stripped
+    // in strict mode.
+    let cfg = Config {
+        http_client_id_header: crate::ClientIdHeaderConfig {
+            inject: false,
+            strict: true,
+            unauthenticated: Some(http::HeaderValue::from_static("unauthenticated")),
+        },
+        ..default_config()
+    };
+    assert_eq!(
+        client_id_header(cfg.clone(), Target::meshed_http1(), Some("spoofed")).await,
+        ""
+    );
+    assert_eq!(
+        client_id_header(cfg, Target::UNMESHED_HTTP1, Some("spoofed")).await,
+        ""
+    );
+
+    // Otherwise, client-supplied values are forwarded unmodified.
+    let cfg = Config {
+        http_client_id_header: crate::ClientIdHeaderConfig {
+            inject: false,
+            strict: false,
+            unauthenticated: None,
+        },
+        ..default_config()
+    };
+    assert_eq!(
+        client_id_header(cfg, Target::meshed_http1(), Some("spoofed")).await,
+        "spoofed"
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn http1_workload_identity() {
+    use linkerd_app_core::proxy::http::workload_identity::{Key, Signer, Verifier, HEADER};
+    use std::time::{Duration, SystemTime};
+
+    let _trace = trace_init();
+    const MESHED_ID: &str = "foosa.barns.serviceaccount.identity.linkerd.cluster.local";
+    const ASSERTED_ID: &str = "client.ns.serviceaccount.identity.linkerd.cluster.local";
+
+    let key = |b: u8| Key::from_hex(&format!("{b:02x}").repeat(32)).unwrap();
+    let assert = |key: Key, now: SystemTime| {
+        let signer = Signer::new(ASSERTED_ID.parse().unwrap(), key).unwrap();
+        let mut headers = http::HeaderMap::new();
+        headers.insert(&CLIENT_ID, http::HeaderValue::from_static("spoofed"));
+        headers.insert(&HEADER, signer.sign(now));
+        headers
+    };
+    let cfg = Config {
+        http_workload_identity: Some(Verifier::new([key(1), key(2)], Duration::from_secs(60))),
+        ..default_config()
+    };
+
+    // A valid assertion from a meshed client replaces its identity.
+    assert_eq!(
+        client_id_header_with(
+            cfg.clone(),
+            Target::meshed_http1(),
+            assert(key(1), SystemTime::now())
+        )
+        .await,
+        ASSERTED_ID
+    );
+    // Assertions signed with a previous key are accepted during rotation.
+    assert_eq!(
+        client_id_header_with(
+            cfg.clone(),
+            Target::meshed_http1(),
+            assert(key(2), SystemTime::now())
+        )
+        .await,
+        ASSERTED_ID
+    );
+
+    // Forged and expired assertions are ignored.
+    assert_eq!(
+        client_id_header_with(
+            cfg.clone(),
+            Target::meshed_http1(),
+            assert(key(3), SystemTime::now())
+        )
+        .await,
+        MESHED_ID
+    );
+    let expired = SystemTime::now() - Duration::from_secs(120);
+    assert_eq!(
+        client_id_header_with(cfg.clone(), Target::meshed_http1(), assert(key(1), expired)).await,
+        MESHED_ID
+    );
+
+    // Assertions are never honored from unauthenticated clients.
+    assert_eq!(
+        client_id_header_with(
+            cfg,
+            Target::UNMESHED_HTTP1,
+            assert(key(1), SystemTime::now())
+        )
+        .await,
+        ""
+    );
+}
+
+/// Sends an HTTP/1 request with the given `l5d-client-id` header value and
+/// returns the value observed by the application.
+async fn client_id_header(cfg: Config, target: Target, client_id: Option<&str>) -> String {
+    let mut headers = http::HeaderMap::new();
+    if let Some(id) = client_id {
+        headers.insert(&CLIENT_ID, http::HeaderValue::from_str(id).unwrap());
+    }
+    client_id_header_with(cfg, target, headers).await
+}
+
+/// Sends an HTTP/1 request with the given headers and returns the
+/// `l5d-client-id` value observed by the application.
+async fn client_id_header_with(cfg: Config, target: Target, headers: http::HeaderMap) -> String {
+    let mut server = hyper::server::conn::http1::Builder::new();
+    server.timer(hyper_util::rt::TokioTimer::new());
+    let mut client = hyper::client::conn::http1::Builder::new();
+
+    let connect = support::connect()
+        .endpoint_fn_boxed(Target::addr(), echo_header_server(server, &CLIENT_ID));
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(target);
+    let (mut client, bg) = http_util::connect_and_accept_http1(&mut client, server).await;
+
+    let mut req = Request::builder()
+        .method(http::Method::GET)
+        .uri("http://foo.svc.cluster.local:5550")
+        .body(BoxBody::default())
+        .unwrap();
+    *req.headers_mut() = headers;
+    let rsp = client
+        .send_request(req)
+        .await
+        .expect("HTTP client request failed");
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    let id = http_util::body_to_string(rsp.into_body()).await.unwrap();
+
+    // Wait for all of the background tasks to complete, panicking if any returned an error.
+    drop(client);
+    bg.join_all()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<()>, Error>>()
+        .expect("background task failed");
+    id
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn http1_bad_gateway_request_id() {
+    let _trace = trace_init();
+
+    // Build a mock connect that always errors.
+    let connect = support::connect().endpoint_fn_boxed(Target::addr(), connect_error());
+
+    let mut client = hyper::client::conn::http1::Builder::new();
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+    let cfg = Config {
+        http_request_id: Some(http::request_id::Config {
+            header: REQUEST_ID.clone(),
+            overwrite: false,
+        }),
+        ..default_config()
+    };
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_HTTP1);
+    let (mut client, bg) = http_util::connect_and_accept_http1(&mut client, server).await;
+
+    // Send a request and assert that the synthesized error response carries
+    // the request's ID.
+    let req = Request::builder()
+        .method(http::Method::GET)
+        .uri("http://foo.svc.cluster.local:5550")
+        .header(&REQUEST_ID, "client-id")
+        .body(BoxBody::default())
+        .unwrap();
+    let rsp = client
+        .send_request(req)
+        .await
+        .expect("HTTP client request failed");
+    tracing::info!(?rsp);
+    assert_eq!(rsp.status(), http::StatusCode::BAD_GATEWAY);
+    assert_eq!(
+        rsp.headers().get(&REQUEST_ID).expect("missing request ID"),
+        "client-id"
+    );
 
     // Wait for all of the background tasks to complete, panicking if any returned an error.
     drop(client);
@@ -374,7 +684,7 @@ async fn http1_connect_timeout_meshed_response_error_header() {
     // because we don't build a real HTTP endpoint stack, which adds error
     // context to this error, and the client rescue layer is below where the
     // logical error context is added.
-    check_error_header(rsp.headers(), "client error (Connect)");
+    check_error_header(rsp.headers(), "connect_failed: client error (Connect)");
 
     // Wait for all of the background tasks to complete, panicking if any returned an error.
     drop(client);
@@ -466,7 +776,7 @@ async fn h2_response_meshed_error_header() {
     tracing::info!(?rsp);
     assert_eq!(rsp.status(), http::StatusCode::GATEWAY_TIMEOUT);
 
-    check_error_header(rsp.headers(), "service in fail-fast");
+    check_error_header(rsp.headers(), "failfast: service in fail-fast");
 
     // Drop the client and discard the result of awaiting the proxy background
     // task. The result is discarded because it hits an error that is related
@@ -517,6 +827,85 @@ async fn h2_response_unmeshed_error_header() {
     let _ = bg.join_all().await;
 }
 
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn h2_port_settings_flow_control() {
+    const SIZE: usize = 16 * 1024 * 1024;
+    let _trace = trace_init();
+
+    // Use the proxy's default client windows. The application is a
+    // millisecond away, so responses that are limited by flow control take a
+    // round trip for each window.
+    let mut defaults = default_config();
+    defaults.proxy.connect.http2.flow_control = Some(http::h2::FlowControl::Fixed {
+        initial_stream_window_size: 65_535,
+        initial_connection_window_size: 1024 * 1024,
+    });
+    let default = time_h2_response(defaults.clone(), SIZE).await;
+
+    let cfg = Config {
+        http2_ports: crate::Http2PortSettings::new(
+            [(
+                Target::addr().port(),
+                crate::Http2Settings {
+                    initial_stream_window_size: Some(SIZE as u32),
+                    initial_connection_window_size: Some(SIZE as u32),
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        ),
+        ..defaults
+    };
+    let enlarged = time_h2_response(cfg, SIZE).await;
+
+    tracing::info!(?default, ?enlarged);
+    assert!(
+        enlarged * 10 < default,
+        "enlarged windows must not stall the response: {enlarged:?} vs {default:?}"
+    );
+}
+
+/// Returns the time taken to receive a `size`-byte response over HTTP/2 from
+/// an application that is one millisecond away from the proxy.
+async fn time_h2_response(cfg: Config, size: usize) -> time::Duration {
+    let connect = support::connect().endpoint_fn_boxed(Target::addr(), large_h2_server(size));
+    let mut client = hyper::client::conn::http2::Builder::new(TokioExecutor::new());
+    client
+        .timer(hyper_util::rt::TokioTimer::new())
+        .initial_stream_window_size(size as u32)
+        .initial_connection_window_size(size as u32);
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_H2);
+    let (mut client, bg) = http_util::connect_and_accept_http2(&mut client, server).await;
+
+    let start = time::Instant::now();
+    let req = Request::builder()
+        .method(http::Method::GET)
+        .uri("http://foo.svc.cluster.local:5550")
+        .body(BoxBody::empty())
+        .unwrap();
+    let rsp = client
+        .send_request(req)
+        .await
+        .expect("HTTP client request failed");
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    let body = http_body_util::BodyExt::collect(rsp.into_body())
+        .await
+        .expect("body must be received")
+        .to_bytes();
+    assert_eq!(body.len(), size);
+    let elapsed = time::Instant::now().saturating_duration_since(start);
+
+    drop(client);
+    let _ = bg.join_all().await;
+    elapsed
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn grpc_meshed_response_error_header() {
     let _trace = trace_init();
@@ -551,7 +940,7 @@ async fn grpc_meshed_response_error_header() {
     tracing::info!(?rsp);
     assert_eq!(rsp.status(), http::StatusCode::OK);
 
-    check_error_header(rsp.headers(), "service in fail-fast");
+    check_error_header(rsp.headers(), "failfast: service in fail-fast");
 
     // Drop the client and discard the result of awaiting the proxy background
     // task. The result is discarded because it hits an error that is related
@@ -695,6 +1084,54 @@ async fn grpc_response_class() {
     drop(bg);
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn grpc_web_http1() {
+    let _trace = trace_init();
+
+    // Build a mock connector that serves native (HTTP/2) gRPC.
+    let connect = {
+        let mut server = hyper::server::conn::http2::Builder::new(TokioExecutor::new());
+        server.timer(hyper_util::rt::TokioTimer::new());
+        support::connect()
+            .endpoint_fn_boxed(Target::addr(), grpc_status_server(server, tonic::Code::Ok))
+    };
+
+    let mut client = hyper::client::conn::http1::Builder::new();
+    let profiles = profile::resolver();
+    let profile_tx =
+        profiles.profile_tx(NameAddr::from_str_and_port("foo.svc.cluster.local", 5550).unwrap());
+    profile_tx.send(profile::Profile::default()).unwrap();
+    let cfg = default_config();
+    let (rt, _shutdown) = runtime();
+    let server = build_server(cfg, rt, profiles, connect).new_service(Target::UNMESHED_HTTP1);
+    let (mut client, bg) = http_util::connect_and_accept_http1(&mut client, server).await;
+
+    // An HTTP/1.1 gRPC-Web request is upgraded to HTTP/2 and its response
+    // trailers are encoded in the body.
+    let req = Request::builder()
+        .method(http::Method::POST)
+        .uri("http://foo.svc.cluster.local:5550/svc/method")
+        .header(http::header::CONTENT_TYPE, "application/grpc-web+proto")
+        .body(BoxBody::default())
+        .unwrap();
+    let rsp = client
+        .send_request(req)
+        .await
+        .expect("HTTP client request failed");
+    tracing::info!(?rsp);
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert_eq!(
+        rsp.headers()[http::header::CONTENT_TYPE],
+        "application/grpc-web"
+    );
+    use http_body_util::BodyExt;
+    let body = rsp.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(&body[..], b"\x80\0\0\0\x10grpc-status: 0\r\n");
+
+    drop(client);
+    drop(bg);
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn unsafe_authority_labels_true() {
     let _trace = trace_init();
@@ -816,6 +1253,63 @@ fn hello_server(
     }
 }
 
+/// Serves HTTP/2 responses with a `size`-byte body over a connection with a
+/// millisecond of latency in each direction.
+fn large_h2_server(size: usize) -> impl Fn(Remote<ServerAddr>) -> io::Result<io::BoxedIo> {
+    move |_| {
+        let (client_io, server_io) = latency::duplex(time::Duration::from_millis(1));
+        let body = bytes::Bytes::from(vec![0; size]);
+        let svc = hyper::service::service_fn(move |_: Request<hyper::body::Incoming>| {
+            let body = body.clone();
+            async move {
+                Ok::<_, io::Error>(Response::new(BoxBody::new(http_body_util::Full::new(body))))
+            }
+        });
+        let mut server = hyper::server::conn::http2::Builder::new(TokioExecutor::new());
+        server.timer(hyper_util::rt::TokioTimer::new());
+        tokio::spawn(
+            server
+                .serve_connection(hyper_util::rt::TokioIo::new(server_io), svc)
+                .in_current_span(),
+        );
+        Ok(io::BoxedIo::new(client_io))
+    }
+}
+
+/// Responds with the value of the request's ID header.
+#[tracing::instrument]
+/// Responds to each request with the value of its `header`, or an empty body
+/// if the request does not have one.
+fn echo_header_server(
+    server: hyper::server::conn::http1::Builder,
+    header: &'static http::HeaderName,
+) -> impl Fn(Remote<ServerAddr>) -> io::Result<io::BoxedIo> {
+    move |endpoint| {
+        let span = tracing::info_span!("echo_header_server", ?endpoint, %header);
+        let _e = span.enter();
+        tracing::info!("mock connecting");
+        let (client_io, server_io) = support::io::duplex(4096);
+        let svc =
+            hyper::service::service_fn(move |request: Request<hyper::body::Incoming>| async move {
+                tracing::info!(?request);
+                let id = request
+                    .headers()
+                    .get(header)
+                    .map(|v| v.to_str().unwrap().to_string())
+                    .unwrap_or_default();
+                Ok::<_, io::Error>(Response::new(BoxBody::new(http_body_util::Full::new(
+                    bytes::Bytes::from(id),
+                ))))
+            });
+        tokio::spawn(
+            server
+                .serve_connection(hyper_util::rt::TokioIo::new(server_io), svc)
+                .in_current_span(),
+        );
+        Ok(io::BoxedIo::new(client_io))
+    }
+}
+
 #[tracing::instrument]
 fn grpc_status_server(
     server: hyper::server::conn::http2::Builder<TokioExecutor>,
@@ -995,6 +1489,7 @@ impl svc::Param<policy::AllowPolicy> for Target {
                     name: "testsrv".into(),
                 }),
                 local_rate_limit: Default::default(),
+                termination_grace_period: Default::default(),
             },
         );
         policy
@@ -1025,3 +1520,99 @@ impl svc::Param<Option<identity::Id>> for Target {
         None
     }
 }
+
+mod latency {
+    use bytes::{Buf, Bytes};
+    use linkerd_app_core::io;
+    use std::{
+        future::Future,
+        net::SocketAddr,
+        pin::Pin,
+        task::{ready, Context, Poll},
+    };
+    use tokio::{sync::mpsc, time};
+
+    /// An in-memory connection on which written bytes only become readable
+    /// by the peer after a fixed latency.
+    pub(super) struct LatencyIo {
+        latency: time::Duration,
+        tx: Option<mpsc::UnboundedSender<(time::Instant, Bytes)>>,
+        rx: mpsc::UnboundedReceiver<(time::Instant, Bytes)>,
+        pending: Option<(time::Instant, Bytes)>,
+        sleep: Pin<Box<time::Sleep>>,
+    }
+
+    pub(super) fn duplex(latency: time::Duration) -> (LatencyIo, LatencyIo) {
+        let (a_tx, a_rx) = mpsc::unbounded_channel();
+        let (b_tx, b_rx) = mpsc::unbounded_channel();
+        let io = |tx, rx| LatencyIo {
+            latency,
+            tx: Some(tx),
+            rx,
+            pending: None,
+            sleep: Box::pin(time::sleep(time::Duration::ZERO)),
+        };
+        (io(a_tx, b_rx), io(b_tx, a_rx))
+    }
+
+    impl io::AsyncRead for LatencyIo {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut io::ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let this = &mut *self;
+            if this.pending.is_none() {
+                match ready!(this.rx.poll_recv(cx)) {
+                    Some(chunk) => this.pending = Some(chunk),
+                    None => return Poll::Ready(Ok(())),
+                }
+            }
+            let (at, bytes) = this.pending.as_mut().expect("chunk must be pending");
+            if time::Instant::now() < *at {
+                this.sleep.as_mut().reset(*at);
+                ready!(this.sleep.as_mut().poll(cx));
+            }
+            let n = buf.remaining().min(bytes.len());
+            buf.put_slice(&bytes[..n]);
+            bytes.advance(n);
+            if bytes.is_empty() {
+                this.pending = None;
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl io::AsyncWrite for LatencyIo {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let at = time::Instant::now() + self.latency;
+            let sent = self
+                .tx
+                .as_ref()
+                .is_some_and(|tx| tx.send((at, Bytes::copy_from_slice(buf))).is_ok());
+            if !sent {
+                return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+            }
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            self.tx = None;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl io::PeerAddr for LatencyIo {
+        fn peer_addr(&self) -> io::Result<SocketAddr> {
+            Ok(([127, 0, 0, 1], 80).into())
+        }
+    }
+}
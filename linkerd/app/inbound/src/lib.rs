@@ -10,9 +10,11 @@
 mod accept;
 mod detect;
 pub mod direct;
+mod forward;
 mod http;
 mod metrics;
 pub mod policy;
+pub mod ports;
 mod server;
 
 #[cfg(any(test, feature = "test-util", fuzzing))]
@@ -21,15 +23,28 @@ pub mod test_util;
 #[cfg(fuzzing)]
 pub use self::http::fuzz as http_fuzz;
 pub use self::{
-    detect::MetricsFamilies as DetectMetrics, metrics::InboundMetrics, policy::DefaultPolicy,
+    detect::MetricsFamilies as DetectMetrics,
+    forward::{ForwardLoop, ForwardTargets},
+    http::{
+        AppOverloadedError, AppPressureConfig, ClientIdHeaderConfig, Http2PortSettings,
+        Http2Settings,
+    },
+    metrics::InboundMetrics,
+    policy::{
+        DefaultPolicy, ExtAuthzConfig, ExtAuthzDenied, ExtAuthzUnavailable,
+        FailureMode as ExtAuthzFailureMode,
+    },
 };
 use linkerd_app_core::{
-    config::{ConnectConfig, ProxyConfig, QueueConfig},
+    config::{ConnectConfig, ProxyConfig, QueueConfig, TunablesRx},
     drain,
     http_tracing::SpanSink,
     identity, io,
     metrics::prom,
-    proxy::{tap, tcp},
+    proxy::{
+        http::{compress, request_id, stream_timeouts, workload_identity},
+        tap, tcp,
+    },
     svc,
     transport::{self, Remote, ServerAddr},
     Error, NameAddr, NameMatch, ProxyRuntime,
@@ -58,6 +73,42 @@ pub struct Config {
 
     /// Enables unsafe authority labels.
     pub unsafe_authority_labels: bool,
+
+    /// Configures compression of HTTP responses from the application.
+    pub http_compression: compress::Config,
+
+    /// Configures how request IDs are set on HTTP requests, if at all.
+    pub http_request_id: Option<request_id::Config>,
+
+    /// Configures how request deadlines are propagated, if at all.
+    pub http_deadline: Option<stream_timeouts::DeadlineConfig>,
+
+    /// Configures how the client's identity is set on HTTP requests.
+    pub http_client_id_header: ClientIdHeaderConfig,
+
+    /// Verifies workload identity assertions on HTTP requests, if configured.
+    /// Verified identities replace the client's identity for authorization.
+    pub http_workload_identity: Option<workload_identity::Verifier>,
+
+    /// Configures the application addresses to which connections on each
+    /// inbound port are forwarded.
+    pub forward_targets: ForwardTargets,
+
+    /// Overrides HTTP/2 settings for specific inbound ports.
+    pub http2_ports: Http2PortSettings,
+
+    /// Enables adaptive HTTP/2 flow control on connections from meshed
+    /// clients, so that windows are sized from each connection's estimated
+    /// bandwidth-delay product rather than the static server settings.
+    pub http2_mesh_adaptive_flow_control: bool,
+
+    /// Sheds requests when the application is overloaded, if configured.
+    pub app_pressure: Option<AppPressureConfig>,
+
+    /// Closes accepted connections on which the client sends no data within
+    /// this timeout, if set. Connections to opaque ports are exempt, since
+    /// their servers may speak first.
+    pub silent_connection_timeout: Option<Duration>,
 }
 
 #[derive(Clone)]
@@ -74,6 +125,13 @@ struct Runtime {
     tap: tap::Registry,
     span_sink: Option<SpanSink>,
     drain: drain::Watch,
+    tunables: TunablesRx,
+
+    /// Tracks the application's load across all inbound stacks, if configured.
+    app_pressure: Option<http::pressure::AppPressure>,
+
+    /// Consults an external authorization service, if configured.
+    ext_authz: Option<policy::ExtAuthz>,
 }
 
 /// Indicates the name to be used to route gateway connections.
@@ -95,6 +153,10 @@ pub struct GatewayDomainInvalid;
 #[error("gateway loop detected")]
 pub struct GatewayLoop;
 
+#[derive(Debug, Error)]
+#[error("client not authorized to reach the exported service")]
+pub struct GatewayUnauthorized;
+
 #[derive(Debug, thiserror::Error)]
 #[error("server {addr}: {source}")]
 pub struct ForwardError {
@@ -155,12 +217,20 @@ impl<S> Inbound<S> {
 
 impl Inbound<()> {
     pub fn new(config: Config, runtime: ProxyRuntime, prom: &mut prom::Registry) -> Self {
+        let metrics = InboundMetrics::new(runtime.metrics, prom);
+        let app_pressure = config
+            .app_pressure
+            .clone()
+            .map(|c| http::pressure::AppPressure::new(c, metrics.app_pressure.clone()));
         let runtime = Runtime {
-            metrics: InboundMetrics::new(runtime.metrics, prom),
+            metrics,
             identity: runtime.identity,
             tap: runtime.tap,
             span_sink: runtime.span_sink,
             drain: runtime.drain,
+            tunables: runtime.tunables,
+            app_pressure,
+            ext_authz: None,
         };
         Self {
             config,
@@ -184,6 +254,23 @@ impl Inbound<()> {
         self.runtime.metrics.clone()
     }
 
+    /// Configures the external authorization service that is consulted for
+    /// requests on routes that require it.
+    pub fn with_ext_authz<S>(mut self, config: ExtAuthzConfig, client: S) -> Self
+    where
+        S: tonic::client::GrpcService<tonic::body::BoxBody, Error = Error>,
+        S: Clone + Send + Sync + 'static,
+        S::ResponseBody: linkerd_app_core::proxy::http::Body<Data = tonic::codegen::Bytes, Error = Error>
+            + Send
+            + 'static,
+        S::Future: Send + 'static,
+    {
+        let client = policy::ExtAuthzClient::new(client);
+        let metrics = self.runtime.metrics.ext_authz.clone();
+        self.runtime.ext_authz = Some(policy::ExtAuthz::new(config, client, metrics));
+        self
+    }
+
     pub fn with_stack<S>(self, stack: S) -> Inbound<S> {
         self.map_stack(move |_, _, _| svc::stack(stack))
     }
@@ -205,7 +292,7 @@ impl Inbound<()> {
     where
         T: svc::Param<Remote<ServerAddr>> + 'static,
     {
-        self.map_stack(|config, _, _| {
+        self.map_stack(|config, rt, _| {
             // Establishes connections to remote peers (for both TCP
             // forwarding and HTTP proxying).
             let ConnectConfig {
@@ -219,17 +306,22 @@ impl Inbound<()> {
             #[error("inbound connection must not target port {0}")]
             struct Loop(u16);
 
+            let forward_targets = config.forward_targets.clone();
+
             svc::stack(transport::ConnectTcp::new(*keepalive, *user_timeout))
                 // Limits the time we wait for a connection to be established.
                 .push_connect_timeout(*timeout)
+                .push(forward::RecordConnect::layer(rt.metrics.ports.clone()))
                 // Prevent connections that would target the inbound proxy port from looping.
+                // Otherwise, connections are forwarded to the port's configured target, if
+                // any.
                 .push_filter(move |t: T| {
                     let addr = t.param();
                     let port = addr.port();
                     if port == proxy_port {
                         return Err(Loop(port));
                     }
-                    Ok(addr)
+                    Ok(forward_targets.target(addr))
                 })
         })
     }
@@ -12,6 +12,7 @@ pub(crate) mod authz;
 pub(crate) mod error;
 
 pub use linkerd_app_core::metrics::*;
+use linkerd_app_core::proxy::http::compress;
 
 /// Holds outbound proxy metrics.
 #[derive(Clone, Debug)]
@@ -28,6 +29,13 @@ pub struct InboundMetrics {
 
     pub detect: crate::detect::MetricsFamilies,
     pub direct: crate::direct::MetricsFamilies,
+    pub http_compression: compress::Metrics,
+    pub(crate) app_pressure: crate::http::pressure::AppPressureMetrics,
+    pub(crate) ext_authz: crate::policy::ExtAuthzMetrics,
+    pub(crate) reap: crate::accept::ReapMetrics,
+
+    /// Tracks the state of each inbound port for diagnostics.
+    pub ports: crate::ports::PortRegistry,
 }
 
 impl InboundMetrics {
@@ -37,15 +45,30 @@ impl InboundMetrics {
         let direct = crate::direct::MetricsFamilies::register(
             reg.sub_registry_with_prefix("tcp_transport_header"),
         );
+        let http_compression =
+            compress::Metrics::register(reg.sub_registry_with_prefix("http_compression"));
+        let app_pressure = crate::http::pressure::AppPressureMetrics::register(
+            reg.sub_registry_with_prefix("app_pressure"),
+        );
+        let ext_authz =
+            crate::policy::ExtAuthzMetrics::register(reg.sub_registry_with_prefix("ext_authz"));
+        let reap = crate::accept::ReapMetrics::register(reg.sub_registry_with_prefix("tcp_accept"));
+
+        let ports = crate::ports::PortRegistry::default();
 
         Self {
             http_authz: authz::HttpAuthzMetrics::default(),
             http_errors: error::HttpErrorMetrics::default(),
-            tcp_authz: authz::TcpAuthzMetrics::default(),
+            tcp_authz: authz::TcpAuthzMetrics::new(ports.clone()),
             tcp_errors: error::TcpErrorMetrics::default(),
             proxy,
             detect,
             direct,
+            http_compression,
+            app_pressure,
+            ext_authz,
+            reap,
+            ports,
         }
     }
 }
@@ -1,4 +1,7 @@
-use crate::policy::{AllowPolicy, HttpRoutePermit, Meta, ServerPermit};
+use crate::{
+    policy::{AllowPolicy, HttpRoutePermit, Meta, ServerPermit},
+    ports::PortRegistry,
+};
 use linkerd_app_core::{
     metrics::{
         legacy::{Counter, FmtLabels, FmtMetrics},
@@ -21,6 +24,15 @@ metrics! {
     inbound_http_route_not_found_total: Counter {
         "The total number of inbound HTTP requests that could not be associated with a route"
     },
+    inbound_http_route_redirect_total: Counter {
+        "The total number of inbound HTTP requests that were redirected by a route filter"
+    },
+    inbound_http_authz_terminate_total: Counter {
+        "The total number of inbound HTTP requests that were terminated due to an authorization change"
+    },
+    inbound_http_route_slo_requests_total: Counter {
+        "The total number of inbound HTTP requests on routes with a latency objective, by whether they completed within it"
+    },
 
     inbound_http_local_ratelimit_total: Counter {
         "The total number of inbound HTTP requests that were rate-limited"
@@ -48,6 +60,9 @@ struct HttpInner {
     allow: Mutex<HashMap<RouteAuthzKey, Counter>>,
     deny: Mutex<HashMap<RouteKey, Counter>>,
     route_not_found: Mutex<HashMap<ServerKey, Counter>>,
+    redirect: Mutex<HashMap<RouteKey, Counter>>,
+    terminate: Mutex<HashMap<RouteAuthzKey, Counter>>,
+    route_slo: Mutex<HashMap<RouteSloKey, Counter>>,
     http_local_rate_limit: Mutex<HashMap<HttpLocalRateLimitKey, Counter>>,
 }
 
@@ -56,6 +71,7 @@ struct TcpInner {
     allow: Mutex<HashMap<ServerAuthzKey, Counter>>,
     deny: Mutex<HashMap<ServerKey, Counter>>,
     terminate: Mutex<HashMap<ServerKey, Counter>>,
+    ports: PortRegistry,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -65,6 +81,12 @@ pub struct HTTPLocalRateLimitLabels {
     pub scope: &'static str,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct RouteSloLabels {
+    route: RouteLabels,
+    within: bool,
+}
+
 #[derive(Debug, Hash, PartialEq, Eq)]
 struct Key<L> {
     target: TargetAddr,
@@ -76,6 +98,7 @@ type ServerKey = Key<ServerLabel>;
 type ServerAuthzKey = Key<ServerAuthzLabels>;
 type RouteKey = Key<RouteLabels>;
 type RouteAuthzKey = Key<RouteAuthzLabels>;
+type RouteSloKey = Key<RouteSloLabels>;
 type HttpLocalRateLimitKey = Key<HTTPLocalRateLimitLabels>;
 
 // === impl HttpAuthzMetrics ===
@@ -104,6 +127,44 @@ impl HttpAuthzMetrics {
             .incr();
     }
 
+    pub fn redirect(&self, permit: &HttpRoutePermit, tls: tls::ConditionalServerTlsLabels) {
+        self.0
+            .redirect
+            .lock()
+            .entry(RouteKey::new(permit.labels.route.clone(), permit.dst, tls))
+            .or_default()
+            .incr();
+    }
+
+    pub fn terminate(&self, permit: &HttpRoutePermit, tls: tls::ConditionalServerTlsLabels) {
+        self.0
+            .terminate
+            .lock()
+            .entry(RouteAuthzKey::from_permit(permit, tls))
+            .or_default()
+            .incr();
+    }
+
+    /// Records whether a request on a route with a latency objective completed
+    /// within it.
+    pub fn route_slo(
+        &self,
+        permit: &HttpRoutePermit,
+        tls: tls::ConditionalServerTlsLabels,
+        within: bool,
+    ) {
+        let labels = RouteSloLabels {
+            route: permit.labels.route.clone(),
+            within,
+        };
+        self.0
+            .route_slo
+            .lock()
+            .entry(RouteSloKey::new(labels, permit.dst, tls))
+            .or_default()
+            .incr();
+    }
+
     pub fn deny(
         &self,
         labels: RouteLabels,
@@ -173,6 +234,45 @@ impl FmtMetrics for HttpAuthzMetrics {
         }
         drop(route_not_found);
 
+        let redirect = self.0.redirect.lock();
+        if !redirect.is_empty() {
+            inbound_http_route_redirect_total.fmt_help(f)?;
+            inbound_http_route_redirect_total.fmt_scopes(
+                f,
+                redirect
+                    .iter()
+                    .map(|(k, c)| ((k.target, (&k.labels, TlsAccept(&k.tls))), c)),
+                |c| c,
+            )?;
+        }
+        drop(redirect);
+
+        let terminate = self.0.terminate.lock();
+        if !terminate.is_empty() {
+            inbound_http_authz_terminate_total.fmt_help(f)?;
+            inbound_http_authz_terminate_total.fmt_scopes(
+                f,
+                terminate
+                    .iter()
+                    .map(|(k, c)| ((k.target, (&k.labels, TlsAccept(&k.tls))), c)),
+                |c| c,
+            )?;
+        }
+        drop(terminate);
+
+        let route_slo = self.0.route_slo.lock();
+        if !route_slo.is_empty() {
+            inbound_http_route_slo_requests_total.fmt_help(f)?;
+            inbound_http_route_slo_requests_total.fmt_scopes(
+                f,
+                route_slo
+                    .iter()
+                    .map(|(k, c)| ((k.target, (&k.labels, TlsAccept(&k.tls))), c)),
+                |c| c,
+            )?;
+        }
+        drop(route_slo);
+
         let local_ratelimit = self.0.http_local_rate_limit.lock();
         if !local_ratelimit.is_empty() {
             inbound_http_local_ratelimit_total.fmt_help(f)?;
@@ -193,6 +293,13 @@ impl FmtMetrics for HttpAuthzMetrics {
 // === impl TcpAuthzMetrics ===
 
 impl TcpAuthzMetrics {
+    pub(crate) fn new(ports: PortRegistry) -> Self {
+        Self(Arc::new(TcpInner {
+            ports,
+            ..Default::default()
+        }))
+    }
+
     pub fn allow(&self, permit: &ServerPermit, tls: tls::ConditionalServerTlsLabels) {
         self.0
             .allow
@@ -203,6 +310,7 @@ impl TcpAuthzMetrics {
     }
 
     pub fn deny(&self, policy: &AllowPolicy, tls: tls::ConditionalServerTlsLabels) {
+        self.0.ports.deny(policy.dst_addr().port());
         self.0
             .deny
             .lock()
@@ -274,6 +382,17 @@ impl FmtLabels for HTTPLocalRateLimitLabels {
     }
 }
 
+// === impl RouteSloLabels ===
+
+impl FmtLabels for RouteSloLabels {
+    fn fmt_labels(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { route, within } = self;
+
+        route.fmt_labels(f)?;
+        write!(f, ",within=\"{within}\"")
+    }
+}
+
 // === impl Key ===
 
 impl<L> Key<L> {
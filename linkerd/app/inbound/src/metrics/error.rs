@@ -3,8 +3,9 @@ mod tcp;
 
 pub(crate) use self::{http::HttpErrorMetrics, tcp::TcpErrorMetrics};
 use crate::{
-    policy::{HttpRouteNotFound, HttpRouteUnauthorized, ServerUnauthorized},
-    GatewayDomainInvalid, GatewayIdentityRequired, GatewayLoop,
+    policy::{ExtAuthzDenied, HttpRouteNotFound, HttpRouteUnauthorized, ServerUnauthorized},
+    AppOverloadedError, GatewayDomainInvalid, GatewayIdentityRequired, GatewayLoop,
+    GatewayUnauthorized,
 };
 use linkerd_app_core::{
     errors::{FailFastError, LoadShedError},
@@ -21,6 +22,7 @@ enum ErrorKind {
     GatewayDomainInvalid,
     GatewayIdentityRequired,
     GatewayLoop,
+    GatewayUnauthorized,
     Io,
     TlsDetectTimeout,
     Unexpected,
@@ -34,6 +36,7 @@ impl ErrorKind {
         if err.is::<ServerUnauthorized>()
             || err.is::<HttpRouteUnauthorized>()
             || err.is::<HttpRouteNotFound>()
+            || err.is::<ExtAuthzDenied>()
         {
             return None;
         }
@@ -50,7 +53,9 @@ impl ErrorKind {
             Some(ErrorKind::GatewayIdentityRequired)
         } else if err.is::<GatewayLoop>() {
             Some(ErrorKind::GatewayLoop)
-        } else if err.is::<LoadShedError>() {
+        } else if err.is::<GatewayUnauthorized>() {
+            Some(ErrorKind::GatewayUnauthorized)
+        } else if err.is::<LoadShedError>() || err.is::<AppOverloadedError>() {
             Some(ErrorKind::LoadShed)
         } else if let Some(e) = err.source() {
             Self::mk(e)
@@ -71,6 +76,7 @@ impl FmtLabels for ErrorKind {
                 ErrorKind::TlsDetectTimeout => "tls detection timeout",
                 ErrorKind::GatewayIdentityRequired => "gateway identity required",
                 ErrorKind::GatewayLoop => "gateway loop",
+                ErrorKind::GatewayUnauthorized => "gateway unauthorized",
                 ErrorKind::GatewayDomainInvalid => "gateway domain invalid",
                 ErrorKind::Io => "i/o",
                 ErrorKind::Unexpected => "unexpected",
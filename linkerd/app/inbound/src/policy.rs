@@ -1,21 +1,26 @@
 mod api;
 mod config;
 pub mod defaults;
+mod ext_authz;
 mod http;
 mod store;
 mod tcp;
 
 use crate::metrics::authz::HTTPLocalRateLimitLabels;
 
-pub(crate) use self::store::Store;
 pub use self::{
     config::Config,
+    ext_authz::{ExtAuthzConfig, ExtAuthzDenied, ExtAuthzUnavailable, FailureMode},
     http::{
         HttpInvalidPolicy, HttpRouteInvalidRedirect, HttpRouteNotFound, HttpRouteRedirect,
         HttpRouteUnauthorized, NewHttpPolicy,
     },
     tcp::NewTcpPolicy,
 };
+pub(crate) use self::{
+    ext_authz::{Client as ExtAuthzClient, ExtAuthz, ExtAuthzMetrics, NewExtAuthz},
+    store::Store,
+};
 
 pub use linkerd_app_core::metrics::ServerLabel;
 use linkerd_app_core::{
@@ -29,8 +34,8 @@ pub use linkerd_proxy_server_policy::{
     authz::Suffix,
     grpc::Route as GrpcRoute,
     http::{filter::Redirection, Route as HttpRoute},
-    route, Authentication, Authorization, Meta, Protocol, RateLimitError, RoutePolicy,
-    ServerPolicy,
+    route, Authentication, Authorization, Meta, Protocol, RateLimitError, ResourceKey,
+    RouteOverride, RouteOverrides, RoutePolicy, ServerOverride, ServerOverrides, ServerPolicy,
 };
 use std::sync::Arc;
 use thiserror::Error;
@@ -72,6 +77,14 @@ pub struct ServerPermit {
 pub struct HttpRoutePermit {
     pub dst: OrigDstAddr,
     pub labels: RouteAuthzLabels,
+
+    /// Indicates that the request may be shed in preference to others when the
+    /// application is overloaded.
+    pub sheddable: bool,
+
+    /// Indicates that the request must also be authorized by an external
+    /// authorization service.
+    pub ext_authz: bool,
 }
 
 pub enum Routes {
@@ -94,6 +107,7 @@ impl From<DefaultPolicy> for ServerPolicy {
             DefaultPolicy::Deny => ServerPolicy {
                 protocol: Protocol::Opaque(Arc::new([])),
                 local_rate_limit: Default::default(),
+                termination_grace_period: Default::default(),
                 meta: Meta::new_default("deny"),
             },
         }
@@ -150,13 +164,46 @@ impl AllowPolicy {
         }
     }
 
-    async fn changed(&mut self) {
+    /// Completes when the server's policy is updated, e.g. once it has been
+    /// discovered from the control plane.
+    pub async fn changed(&mut self) {
         if self.server.changed().await.is_err() {
             // If the sender was dropped, then there can be no further changes.
             futures::future::pending::<()>().await;
         }
     }
 
+    /// Completes when a policy update no longer authorizes a connection (or
+    /// request), as determined by `is_authorized`, and the server's
+    /// termination grace period has elapsed.
+    ///
+    /// If the policy is updated to authorize the connection again before the
+    /// grace period elapses, it is permitted to continue.
+    async fn revoked(&mut self, mut is_authorized: impl FnMut(&Self) -> bool) {
+        loop {
+            self.changed().await;
+            if is_authorized(self) {
+                continue;
+            }
+
+            let grace = self.server.borrow().termination_grace_period;
+            if grace.is_zero() {
+                return;
+            }
+            tracing::debug!(?grace, "Authorization revoked; waiting for grace period");
+            let deadline = tokio::time::sleep(grace);
+            tokio::pin!(deadline);
+            loop {
+                tokio::select! {
+                    () = &mut deadline => return,
+                    () = self.changed() => if is_authorized(self) {
+                        break;
+                    },
+                }
+            }
+        }
+    }
+
     fn routes(&self) -> Option<Routes> {
         let borrow = self.server.borrow();
         match &borrow.protocol {
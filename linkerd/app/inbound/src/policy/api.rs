@@ -8,7 +8,7 @@ use linkerd_app_core::{
     svc::Service,
     Error, Recover, Result,
 };
-use linkerd_proxy_server_policy::ServerPolicy;
+use linkerd_proxy_server_policy::{RouteOverrides, ServerOverrides, ServerPolicy};
 use linkerd_tonic_stream::{LimitReceiveFuture, ReceiveLimits};
 use linkerd_tonic_watch::StreamWatch;
 use std::sync::Arc;
@@ -19,6 +19,8 @@ pub(super) struct Api<S> {
     workload: Arc<str>,
     limits: ReceiveLimits,
     default_detect_timeout: time::Duration,
+    routes: RouteOverrides,
+    servers: ServerOverrides,
     client: Client<S>,
 }
 
@@ -40,12 +42,16 @@ where
         workload: Arc<str>,
         limits: ReceiveLimits,
         default_detect_timeout: time::Duration,
+        routes: RouteOverrides,
+        servers: ServerOverrides,
         client: S,
     ) -> Self {
         Self {
             workload,
             limits,
             default_detect_timeout,
+            routes,
+            servers,
             client: Client::new(client),
         }
     }
@@ -82,6 +88,8 @@ where
 
         let detect_timeout = self.default_detect_timeout;
         let limits = self.limits;
+        let routes = self.routes.clone();
+        let servers = self.servers.clone();
         let mut client = self.client.clone();
         Box::pin(async move {
             let rsp = LimitReceiveFuture::new(limits, client.watch_port(tonic::Request::new(req)))
@@ -91,12 +99,13 @@ where
                     // If the server returned an invalid server policy, we
                     // default to using an invalid policy that causes all
                     // requests to report an internal error.
-                    let policy = ServerPolicy::try_from(up).unwrap_or_else(|error| {
-                        tracing::warn!(%error, "Server misconfigured");
-                        INVALID_POLICY
-                            .get_or_init(|| ServerPolicy::invalid(detect_timeout))
-                            .clone()
-                    });
+                    let policy =
+                        ServerPolicy::try_from(&routes, &servers, up).unwrap_or_else(|error| {
+                            tracing::warn!(%error, "Server misconfigured");
+                            INVALID_POLICY
+                                .get_or_init(|| ServerPolicy::invalid(detect_timeout))
+                                .clone()
+                        });
                     tracing::debug!(?policy);
                     policy
                 })
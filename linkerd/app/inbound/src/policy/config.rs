@@ -1,4 +1,7 @@
-use super::{api::Api, DefaultPolicy, GetPolicy, Protocol, ServerPolicy, Store};
+use super::{
+    api::Api, DefaultPolicy, GetPolicy, Protocol, RouteOverrides, ServerOverrides, ServerPolicy,
+    Store,
+};
 use linkerd_app_core::{exp_backoff::ExponentialBackoff, proxy::http, Error};
 use linkerd_tonic_stream::ReceiveLimits;
 use rangemap::RangeInclusiveSet;
@@ -20,6 +23,12 @@ pub enum Config {
         cache_max_idle_age: Duration,
         ports: HashSet<u16>,
         opaque_ports: RangeInclusiveSet<u16>,
+
+        /// Configures discovered routes by resource.
+        routes: RouteOverrides,
+
+        /// Configures discovered servers by resource.
+        servers: ServerOverrides,
     },
     Fixed {
         default: DefaultPolicy,
@@ -59,6 +68,8 @@ impl Config {
                 ports,
                 cache_max_idle_age,
                 opaque_ports,
+                routes,
+                servers,
             } => {
                 let watch = {
                     let detect_timeout = match default {
@@ -68,7 +79,8 @@ impl Config {
                         }) => timeout,
                         _ => Duration::from_secs(10),
                     };
-                    Api::new(workload, limits, detect_timeout, client).into_watch(backoff)
+                    Api::new(workload, limits, detect_timeout, routes, servers, client)
+                        .into_watch(backoff)
                 };
                 Store::spawn_discover(default, cache_max_idle_age, watch, ports, opaque_ports)
             }
@@ -0,0 +1,474 @@
+//! Consults an external authorization service for requests on routes that
+//! require it.
+//!
+//! Once policy has authorized a request, requests on routes that policy marks
+//! as requiring external authorization are described to the configured
+//! service, which allows or denies them. Each check is bounded by a timeout.
+//! When a check fails or times out, the request is allowed or denied according
+//! to the configured [`FailureMode`]. Requests on marked routes are denied if
+//! no service is configured.
+//!
+//! Decisions are cached for each client identity and route, so, when caching
+//! is enabled, the service's decisions should not depend on other request
+//! metadata.
+
+use super::HttpRoutePermit;
+use futures::{future, FutureExt, TryFutureExt};
+use linkerd_app_core::{
+    metrics::{
+        prom::{self, encoding::*},
+        RouteLabels,
+    },
+    proxy::http::{self, workload_identity::VerifiedIdentity},
+    svc::{self, ServiceExt},
+    tls,
+    transport::{ClientAddr, Remote},
+    Conditional, Error, Result,
+};
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc, task};
+use tokio::time;
+use tracing::{debug, warn};
+
+mod api;
+#[cfg(test)]
+mod tests;
+
+pub(crate) use self::api::Client;
+
+/// Bounds the number of cached decisions. Expired decisions are discarded
+/// when the cache is full; if the cache remains full, new decisions are not
+/// cached.
+const MAX_CACHED_DECISIONS: usize = 10_000;
+
+#[derive(Clone, Debug)]
+pub struct ExtAuthzConfig {
+    /// The time within which the service must respond to a check.
+    pub timeout: time::Duration,
+
+    /// Determines whether requests are allowed when the service cannot be
+    /// consulted.
+    pub failure_mode: FailureMode,
+
+    /// The duration for which decisions are cached. Decisions are not cached
+    /// if this is zero.
+    pub cache_ttl: time::Duration,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FailureMode {
+    /// Requests are allowed when the service cannot be consulted.
+    Open,
+
+    /// Requests are denied when the service cannot be consulted.
+    Closed,
+}
+
+/// A client for the external authorization service, shared by all inbound
+/// HTTP requests.
+#[derive(Clone, Debug)]
+pub(crate) struct ExtAuthz(Arc<Shared>);
+
+#[derive(Clone, Debug)]
+pub(crate) struct ExtAuthzMetrics {
+    checks: prom::Family<CheckLabels, prom::Counter>,
+    cache_hits: prom::Family<CheckLabels, prom::Counter>,
+    duration: prom::Histogram,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewExtAuthz<N> {
+    ext_authz: Option<ExtAuthz>,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ExtAuthzService<T, N> {
+    ext_authz: Option<ExtAuthz>,
+    target: (HttpRoutePermit, T),
+    client_addr: Remote<ClientAddr>,
+    tls: tls::ConditionalServerTls,
+    inner: N,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("request denied by external authorization service: {0}")]
+pub struct ExtAuthzDenied(Arc<str>);
+
+#[derive(Debug, thiserror::Error)]
+#[error("external authorization service unavailable")]
+pub struct ExtAuthzUnavailable(#[source] Error);
+
+#[derive(Debug, thiserror::Error)]
+#[error("no external authorization service is configured")]
+struct NotConfigured(());
+
+#[derive(Debug, thiserror::Error)]
+#[error("external authorization check timed out after {0:?}")]
+struct CheckTimeout(time::Duration);
+
+struct Shared {
+    config: ExtAuthzConfig,
+    client: Check,
+    cache: Mutex<HashMap<Key, Cached>>,
+    metrics: ExtAuthzMetrics,
+}
+
+type Check = svc::BoxCloneSyncService<api::CheckRequest, api::CheckResponse>;
+
+type Key = (Option<tls::ClientId>, RouteLabels);
+
+#[derive(Clone, Debug)]
+struct Cached {
+    decision: Decision,
+    expiry: time::Instant,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Decision {
+    Allow,
+    Deny(Arc<str>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct CheckLabels {
+    result: CheckResult,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum CheckResult {
+    allowed,
+    denied,
+    error,
+    timeout,
+}
+
+// === impl ExtAuthz ===
+
+impl ExtAuthz {
+    pub(crate) fn new<S>(config: ExtAuthzConfig, client: S, metrics: ExtAuthzMetrics) -> Self
+    where
+        S: svc::Service<api::CheckRequest, Response = api::CheckResponse>,
+        S: Clone + Send + Sync + 'static,
+        S::Error: Into<Error>,
+        S::Future: Send + 'static,
+    {
+        Self(Arc::new(Shared {
+            config,
+            client: svc::BoxCloneSyncService::new(client),
+            cache: Mutex::new(HashMap::new()),
+            metrics,
+        }))
+    }
+
+    /// Checks whether the request described by `req` is authorized, using a
+    /// cached decision if one exists for `key`.
+    async fn check(self, key: Key, req: api::CheckRequest) -> Result<()> {
+        let Shared {
+            config,
+            client,
+            metrics,
+            ..
+        } = &*self.0;
+
+        if let Some(decision) = self.cached(&key) {
+            metrics.cache_hit(&decision);
+            return decision.into_result();
+        }
+
+        let start = time::Instant::now();
+        let res = time::timeout(config.timeout, client.clone().oneshot(req)).await;
+        let elapsed = time::Instant::now().saturating_duration_since(start);
+        metrics.duration.observe(elapsed.as_secs_f64());
+
+        let decision = match res {
+            Ok(Ok(rsp)) if rsp.allowed => Decision::Allow,
+            Ok(Ok(rsp)) => Decision::Deny(rsp.message.into()),
+            Ok(Err(error)) => {
+                metrics.check(CheckResult::error);
+                return self.on_failure(error);
+            }
+            Err(_) => {
+                metrics.check(CheckResult::timeout);
+                return self.on_failure(CheckTimeout(config.timeout).into());
+            }
+        };
+        metrics.check(decision.result());
+        self.cache(key, decision.clone());
+        decision.into_result()
+    }
+
+    fn on_failure(&self, error: Error) -> Result<()> {
+        match self.0.config.failure_mode {
+            FailureMode::Open => {
+                warn!(%error, "External authorization failed; allowing request");
+                Ok(())
+            }
+            FailureMode::Closed => {
+                warn!(%error, "External authorization failed; denying request");
+                Err(ExtAuthzUnavailable(error).into())
+            }
+        }
+    }
+
+    fn cached(&self, key: &Key) -> Option<Decision> {
+        let mut cache = self.0.cache.lock();
+        let cached = cache.get(key)?;
+        if cached.expiry > time::Instant::now() {
+            return Some(cached.decision.clone());
+        }
+        cache.remove(key);
+        None
+    }
+
+    fn cache(&self, key: Key, decision: Decision) {
+        let ttl = self.0.config.cache_ttl;
+        if ttl.is_zero() {
+            return;
+        }
+
+        let now = time::Instant::now();
+        let mut cache = self.0.cache.lock();
+        if cache.len() >= MAX_CACHED_DECISIONS && !cache.contains_key(&key) {
+            cache.retain(|_, c| c.expiry > now);
+            if cache.len() >= MAX_CACHED_DECISIONS {
+                debug!("Decision cache is full");
+                return;
+            }
+        }
+        cache.insert(
+            key,
+            Cached {
+                decision,
+                expiry: now + ttl,
+            },
+        );
+    }
+}
+
+impl std::fmt::Debug for Shared {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Shared")
+            .field("config", &self.config)
+            .field("metrics", &self.metrics)
+            .finish_non_exhaustive()
+    }
+}
+
+// === impl Decision ===
+
+impl Decision {
+    fn result(&self) -> CheckResult {
+        match self {
+            Self::Allow => CheckResult::allowed,
+            Self::Deny(_) => CheckResult::denied,
+        }
+    }
+
+    fn into_result(self) -> Result<()> {
+        match self {
+            Self::Allow => Ok(()),
+            Self::Deny(message) => Err(ExtAuthzDenied(message).into()),
+        }
+    }
+}
+
+// === impl ExtAuthzMetrics ===
+
+impl ExtAuthzMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let checks = prom::Family::default();
+        registry.register(
+            "checks",
+            "The number of requests checked by the external authorization service, by result",
+            checks.clone(),
+        );
+
+        let cache_hits = prom::Family::default();
+        registry.register(
+            "cache_hits",
+            "The number of requests authorized by a cached decision, by result",
+            cache_hits.clone(),
+        );
+
+        let duration = prom::Histogram::new([0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0]);
+        registry.register_with_unit(
+            "check_duration",
+            "The time taken by the external authorization service to check requests",
+            prom::Unit::Seconds,
+            duration.clone(),
+        );
+
+        Self {
+            checks,
+            cache_hits,
+            duration,
+        }
+    }
+
+    fn check(&self, result: CheckResult) {
+        self.checks.get_or_create(&CheckLabels { result }).inc();
+    }
+
+    fn cache_hit(&self, decision: &Decision) {
+        let result = decision.result();
+        self.cache_hits.get_or_create(&CheckLabels { result }).inc();
+    }
+}
+
+// === impl NewExtAuthz ===
+
+impl<N> NewExtAuthz<N> {
+    /// Returns a layer that consults the external authorization service, if
+    /// one is configured, for requests on routes that require it.
+    pub(crate) fn layer(
+        ext_authz: Option<ExtAuthz>,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            ext_authz: ext_authz.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<(HttpRoutePermit, T)> for NewExtAuthz<N>
+where
+    T: svc::Param<Remote<ClientAddr>>,
+    T: svc::Param<tls::ConditionalServerTls>,
+    N: Clone,
+{
+    type Service = ExtAuthzService<T, N>;
+
+    fn new_service(&self, (permit, target): (HttpRoutePermit, T)) -> Self::Service {
+        ExtAuthzService {
+            ext_authz: self.ext_authz.clone(),
+            client_addr: target.param(),
+            tls: target.param(),
+            target: (permit, target),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// === impl ExtAuthzService ===
+
+impl<B, T, N, S> svc::Service<http::Request<B>> for ExtAuthzService<T, N>
+where
+    B: Send + 'static,
+    T: Clone + Send + 'static,
+    N: svc::NewService<(HttpRoutePermit, T), Service = S> + Clone + Send + 'static,
+    S: svc::Service<http::Request<B>> + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<svc::stack::Oneshot<S, http::Request<B>>, Error>,
+        future::BoxFuture<'static, Result<S::Response>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut task::Context<'_>) -> task::Poll<Result<()>> {
+        task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let (permit, _) = &self.target;
+        if !permit.ext_authz {
+            return future::Either::Left(
+                self.inner
+                    .new_service(self.target.clone())
+                    .oneshot(req)
+                    .err_into::<Error>(),
+            );
+        }
+
+        let Some(ext_authz) = self.ext_authz.clone() else {
+            let error = ExtAuthzUnavailable(NotConfigured(()).into());
+            return future::Either::Right(future::err::<S::Response, Error>(error.into()).boxed());
+        };
+
+        let client_id = client_id(&self.tls, req.extensions());
+        let check = self.check_request(client_id.as_ref(), &req);
+        let key = (client_id, permit.labels.route.clone());
+        let inner = self.inner.clone();
+        let target = self.target.clone();
+        future::Either::Right(
+            async move {
+                ext_authz.check(key, check).await?;
+                inner
+                    .new_service(target)
+                    .oneshot(req)
+                    .await
+                    .map_err(Into::into)
+            }
+            .boxed(),
+        )
+    }
+}
+
+impl<T, N> ExtAuthzService<T, N> {
+    fn check_request<B>(
+        &self,
+        client_id: Option<&tls::ClientId>,
+        req: &http::Request<B>,
+    ) -> api::CheckRequest {
+        let (permit, _) = &self.target;
+        let resource = |meta: &super::Meta| api::ResourceRef {
+            group: meta.group().to_string(),
+            kind: meta.kind().to_string(),
+            name: meta.name().to_string(),
+        };
+
+        let mut headers = HashMap::<String, String>::new();
+        for (name, value) in req.headers() {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            headers
+                .entry(name.as_str().to_string())
+                .and_modify(|v| {
+                    v.push(',');
+                    v.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        }
+
+        api::CheckRequest {
+            method: req.method().to_string(),
+            scheme: req.uri().scheme_str().unwrap_or_default().to_string(),
+            authority: req
+                .uri()
+                .authority()
+                .map(|a| a.to_string())
+                .unwrap_or_default(),
+            path: req
+                .uri()
+                .path_and_query()
+                .map(|p| p.to_string())
+                .unwrap_or_default(),
+            headers,
+            client_identity: client_id.map(|id| id.to_string()).unwrap_or_default(),
+            client_addr: self.client_addr.to_string(),
+            server: Some(resource(&permit.labels.route.server.0)),
+            route: Some(resource(&permit.labels.route.route)),
+        }
+    }
+}
+
+/// Returns the request's client identity: a workload identity verified from
+/// the request, if any, or the connection's client identity.
+fn client_id(tls: &tls::ConditionalServerTls, ext: &http::Extensions) -> Option<tls::ClientId> {
+    if let Some(VerifiedIdentity(id)) = ext.get::<VerifiedIdentity>() {
+        return Some(tls::ClientId(id.clone()));
+    }
+    match tls {
+        Conditional::Some(tls::ServerTls::Established {
+            client_id: Some(id),
+            ..
+        }) => Some(id.clone()),
+        _ => None,
+    }
+}
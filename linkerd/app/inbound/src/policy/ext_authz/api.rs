@@ -0,0 +1,137 @@
+//! A gRPC client for external authorization services.
+//!
+//! Services implement a single unary method,
+//! `/io.linkerd.proxy.ext_authz.v1.Authorizer/Check`, with the following
+//! messages:
+//!
+//! ```protobuf
+//! message CheckRequest {
+//!   string method = 1;
+//!   string scheme = 2;
+//!   string authority = 3;
+//!   string path = 4;
+//!   map<string, string> headers = 5;
+//!   // The client's verified identity, if any.
+//!   string client_identity = 6;
+//!   string client_addr = 7;
+//!   ResourceRef server = 8;
+//!   ResourceRef route = 9;
+//! }
+//!
+//! message ResourceRef {
+//!   string group = 1;
+//!   string kind = 2;
+//!   string name = 3;
+//! }
+//!
+//! message CheckResponse {
+//!   bool allowed = 1;
+//!   // Describes why a request was denied.
+//!   string message = 2;
+//! }
+//! ```
+
+use linkerd_app_core::{proxy::http, svc, Error};
+use std::{collections::HashMap, task};
+
+const CHECK_PATH: &str = "/io.linkerd.proxy.ext_authz.v1.Authorizer/Check";
+
+#[derive(Clone, Debug)]
+pub(crate) struct Client<S> {
+    grpc: tonic::client::Grpc<S>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct CheckRequest {
+    #[prost(string, tag = "1")]
+    pub method: String,
+
+    #[prost(string, tag = "2")]
+    pub scheme: String,
+
+    #[prost(string, tag = "3")]
+    pub authority: String,
+
+    #[prost(string, tag = "4")]
+    pub path: String,
+
+    /// Values of headers that occur more than once are joined with commas.
+    /// Headers with values that are not valid UTF-8 are omitted.
+    #[prost(map = "string, string", tag = "5")]
+    pub headers: HashMap<String, String>,
+
+    #[prost(string, tag = "6")]
+    pub client_identity: String,
+
+    #[prost(string, tag = "7")]
+    pub client_addr: String,
+
+    #[prost(message, optional, tag = "8")]
+    pub server: Option<ResourceRef>,
+
+    #[prost(message, optional, tag = "9")]
+    pub route: Option<ResourceRef>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ResourceRef {
+    #[prost(string, tag = "1")]
+    pub group: String,
+
+    #[prost(string, tag = "2")]
+    pub kind: String,
+
+    #[prost(string, tag = "3")]
+    pub name: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct CheckResponse {
+    #[prost(bool, tag = "1")]
+    pub allowed: bool,
+
+    #[prost(string, tag = "2")]
+    pub message: String,
+}
+
+// === impl Client ===
+
+impl<S> Client<S> {
+    pub(crate) fn new(inner: S) -> Self {
+        Self {
+            grpc: tonic::client::Grpc::new(inner),
+        }
+    }
+}
+
+impl<S> svc::Service<CheckRequest> for Client<S>
+where
+    S: tonic::client::GrpcService<tonic::body::BoxBody, Error = Error>,
+    S: Clone + Send + 'static,
+    S::ResponseBody: http::Body<Data = tonic::codegen::Bytes, Error = Error> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = CheckResponse;
+    type Error = Error;
+    type Future = futures::future::BoxFuture<'static, Result<CheckResponse, Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut task::Context<'_>) -> task::Poll<Result<(), Error>> {
+        task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: CheckRequest) -> Self::Future {
+        let mut grpc = self.grpc.clone();
+        Box::pin(async move {
+            grpc.ready().await?;
+            let rsp = grpc
+                .unary(
+                    tonic::Request::new(req),
+                    http::uri::PathAndQuery::from_static(CHECK_PATH),
+                    tonic::codec::ProstCodec::default(),
+                )
+                .await?;
+            Ok(rsp.into_inner())
+        })
+    }
+}
@@ -0,0 +1,211 @@
+use super::*;
+use crate::policy::{Meta, ServerLabel};
+use linkerd_app_core::{
+    metrics::RouteAuthzLabels,
+    svc::{NewService, Service},
+    transport::OrigDstAddr,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const TIMEOUT: time::Duration = time::Duration::from_millis(100);
+const TTL: time::Duration = time::Duration::from_secs(10);
+
+#[derive(Clone, Debug)]
+struct Target;
+
+impl svc::Param<Remote<ClientAddr>> for Target {
+    fn param(&self) -> Remote<ClientAddr> {
+        Remote(ClientAddr(([192, 168, 3, 3], 30120).into()))
+    }
+}
+
+impl svc::Param<tls::ConditionalServerTls> for Target {
+    fn param(&self) -> tls::ConditionalServerTls {
+        tls::ConditionalServerTls::Some(tls::ServerTls::Established {
+            client_id: Some("foo.bar.bah".parse().unwrap()),
+            negotiated_protocol: None,
+        })
+    }
+}
+
+fn meta(kind: &str, name: &str) -> Arc<Meta> {
+    Arc::new(Meta::Resource {
+        group: "policy.linkerd.io".into(),
+        kind: kind.into(),
+        name: name.into(),
+    })
+}
+
+fn permit(ext_authz: bool) -> HttpRoutePermit {
+    HttpRoutePermit {
+        dst: OrigDstAddr(([192, 168, 3, 4], 8080).into()),
+        labels: RouteAuthzLabels {
+            route: RouteLabels {
+                server: ServerLabel(meta("Server", "testsrv"), 8080),
+                route: meta("HTTPRoute", "testrt"),
+            },
+            authz: meta("AuthorizationPolicy", "testauthz"),
+        },
+        sheddable: false,
+        ext_authz,
+    }
+}
+
+fn config(failure_mode: FailureMode) -> ExtAuthzConfig {
+    ExtAuthzConfig {
+        timeout: TIMEOUT,
+        failure_mode,
+        cache_ttl: TTL,
+    }
+}
+
+/// Builds a service that consults `check` for requests on a route that
+/// requires external authorization.
+fn mk_svc<C, F>(
+    config: ExtAuthzConfig,
+    check: C,
+) -> impl svc::Service<http::Request<()>, Response = http::Response<()>, Error = Error>
+where
+    C: FnMut(api::CheckRequest) -> F + Clone + Send + Sync + 'static,
+    F: std::future::Future<Output = Result<api::CheckResponse>> + Send + 'static,
+{
+    let metrics = ExtAuthzMetrics::register(&mut prom::Registry::default());
+    let ext_authz = ExtAuthz::new(config, svc::mk(check), metrics);
+    new_svc(Some(ext_authz), true)
+}
+
+fn new_svc(
+    ext_authz: Option<ExtAuthz>,
+    route_ext_authz: bool,
+) -> impl svc::Service<http::Request<()>, Response = http::Response<()>, Error = Error> {
+    let new = NewExtAuthz {
+        ext_authz,
+        inner: |_: (HttpRoutePermit, Target)| {
+            svc::mk(|_: http::Request<()>| future::ok::<_, Error>(http::Response::new(())))
+        },
+    };
+    new.new_service((permit(route_ext_authz), Target))
+}
+
+fn allow(allowed: bool) -> api::CheckResponse {
+    api::CheckResponse {
+        allowed,
+        message: if allowed { "" } else { "nope" }.to_string(),
+    }
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn skips_routes_without_ext_authz() {
+    let mut svc = new_svc(None, false);
+    svc.call(http::Request::new(()))
+        .await
+        .expect("requests on routes without ext-authz must be allowed");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn denies_when_not_configured() {
+    let mut svc = new_svc(None, true);
+    let error = svc
+        .call(http::Request::new(()))
+        .await
+        .expect_err("requests must be denied without an ext-authz service");
+    assert!(error.is::<ExtAuthzUnavailable>());
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn checks_request_metadata() {
+    let mut svc = mk_svc(
+        config(FailureMode::Closed),
+        |req: api::CheckRequest| async move {
+            assert_eq!(req.method, "POST");
+            assert_eq!(req.path, "/foo?bar");
+            assert_eq!(req.headers.get("x-foo").map(String::as_str), Some("a,b"));
+            assert_eq!(req.client_identity, "foo.bar.bah");
+            assert_eq!(req.client_addr, "192.168.3.3:30120");
+            assert_eq!(req.route.expect("route must be set").name, "testrt");
+            assert_eq!(req.server.expect("server must be set").name, "testsrv");
+            Ok(allow(true))
+        },
+    );
+
+    let req = http::Request::post("http://example.com/foo?bar")
+        .header("x-foo", "a")
+        .header("x-foo", "b")
+        .body(())
+        .unwrap();
+    svc.call(req).await.expect("request must be allowed");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn caches_decisions() {
+    let checks = Arc::new(AtomicUsize::new(0));
+    let mut svc = mk_svc(config(FailureMode::Closed), {
+        let checks = checks.clone();
+        move |_: api::CheckRequest| {
+            checks.fetch_add(1, Ordering::SeqCst);
+            future::ok(allow(false))
+        }
+    });
+
+    for _ in 0..3 {
+        let error = svc
+            .call(http::Request::new(()))
+            .await
+            .expect_err("request must be denied");
+        assert!(error.is::<ExtAuthzDenied>());
+    }
+    assert_eq!(checks.load(Ordering::SeqCst), 1, "decisions must be cached");
+
+    time::sleep(TTL).await;
+    svc.call(http::Request::new(()))
+        .await
+        .expect_err("request must be denied");
+    assert_eq!(
+        checks.load(Ordering::SeqCst),
+        2,
+        "decisions must expire after the TTL"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn fails_closed() {
+    let mut svc = mk_svc(config(FailureMode::Closed), |_: api::CheckRequest| {
+        future::err::<api::CheckResponse, Error>("unavailable".into())
+    });
+    let error = svc
+        .call(http::Request::new(()))
+        .await
+        .expect_err("request must be denied");
+    assert!(error.is::<ExtAuthzUnavailable>());
+
+    let mut svc = mk_svc(config(FailureMode::Closed), |_: api::CheckRequest| {
+        future::pending::<Result<api::CheckResponse>>()
+    });
+    let error = svc
+        .call(http::Request::new(()))
+        .await
+        .expect_err("request must be denied");
+    assert!(error.is::<ExtAuthzUnavailable>());
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn fails_open() {
+    let checks = Arc::new(AtomicUsize::new(0));
+    let mut svc = mk_svc(config(FailureMode::Open), {
+        let checks = checks.clone();
+        move |_: api::CheckRequest| {
+            checks.fetch_add(1, Ordering::SeqCst);
+            future::pending::<Result<api::CheckResponse>>()
+        }
+    });
+    for _ in 0..2 {
+        svc.call(http::Request::new(()))
+            .await
+            .expect("request must be allowed");
+    }
+    assert_eq!(
+        checks.load(Ordering::SeqCst),
+        2,
+        "failures must not be cached"
+    );
+}
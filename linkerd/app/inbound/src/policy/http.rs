@@ -6,21 +6,28 @@ use crate::{
 use futures::{future, TryFutureExt};
 use linkerd_app_core::{
     metrics::{RouteAuthzLabels, RouteLabels},
+    proxy::http::workload_identity::VerifiedIdentity,
     svc::{self, ServiceExt},
     tls,
     transport::{ClientAddr, OrigDstAddr, Remote},
     Conditional, Error, Result,
 };
 use linkerd_proxy_server_policy::{grpc, http, route::RouteMatch};
-use std::{sync::Arc, task};
+use std::{borrow::Cow, sync::Arc, task};
 
+mod revoke;
+mod slo;
 #[cfg(test)]
 mod tests;
 
+pub use self::revoke::{ResponseBody, ResponseFuture};
+
 /// A middleware that enforces policy on each HTTP request.
 ///
 /// This enforcement is done lazily on each request so that policy updates are
-/// honored as the connection progresses.
+/// honored as the connection progresses. In-flight requests are re-authorized
+/// when the policy changes and are terminated, after the server's grace
+/// period, if they are no longer permitted.
 ///
 /// The inner service is created for each request, so it's expected that this is
 /// combined with caching.
@@ -135,17 +142,18 @@ macro_rules! try_fut {
     };
 }
 
-impl<B, T, N, S> svc::Service<::http::Request<B>> for HttpPolicyService<T, N>
+impl<B, T, N, S, RB> svc::Service<::http::Request<B>> for HttpPolicyService<T, N>
 where
     T: Clone,
     N: svc::NewService<(HttpRoutePermit, T), Service = S>,
-    S: svc::Service<::http::Request<B>>,
+    S: svc::Service<::http::Request<B>, Response = ::http::Response<RB>>,
     S::Error: Into<Error>,
+    RB: http_body::Body,
 {
-    type Response = S::Response;
+    type Response = ::http::Response<ResponseBody<RB>>;
     type Error = Error;
     type Future = future::Either<
-        future::ErrInto<svc::stack::Oneshot<S, ::http::Request<B>>, Error>,
+        ResponseFuture<future::ErrInto<svc::stack::Oneshot<S, ::http::Request<B>>, Error>>,
         future::Ready<Result<Self::Response>>,
     >;
 
@@ -155,30 +163,67 @@ where
     }
 
     fn call(&mut self, mut req: ::http::Request<B>) -> Self::Future {
+        // Retain the request's head so that it may be re-authorized if the
+        // policy changes while the request is in flight.
+        let head = {
+            let mut head = ::http::Request::new(());
+            *head.method_mut() = req.method().clone();
+            *head.uri_mut() = req.uri().clone();
+            *head.version_mut() = req.version();
+            *head.headers_mut() = req.headers().clone();
+            head
+        };
+
+        // Requests are authorized by an identity asserted on the request, if
+        // one was verified, rather than by the connection's identity.
+        let connection = self.connection.for_request(req.extensions());
+
         // Find an appropriate route for the request and ensure that it's
         // authorized.
-        let permit = match self.policy.routes() {
+        let (permit, objective) = match self.policy.routes() {
             None => err!(self.mk_route_not_found()),
             Some(Routes::Http(routes)) => {
-                let (permit, mtch, route) = try_fut!(self.authorize(&routes, &req));
-                try_fut!(apply_http_filters(mtch, route, &mut req));
-                permit
+                let (permit, mtch, route) = try_fut!(self.authorize(&connection, &routes, &req));
+                // Filters are applied only once the request has been
+                // authorized. Redirects are returned without dispatching the
+                // request to the application.
+                if let Err(error) = apply_http_filters(mtch, route, &mut req) {
+                    if error.is::<HttpRouteRedirect>() {
+                        self.metrics
+                            .redirect(&permit, connection.tls.as_ref().map(|t| t.labels()));
+                    }
+                    err!(error);
+                }
+                (permit, route.latency_objective)
             }
             Some(Routes::Grpc(routes)) => {
-                let (permit, _, route) = try_fut!(self.authorize(&routes, &req));
+                let (permit, _, route) = try_fut!(self.authorize(&connection, &routes, &req));
                 try_fut!(apply_grpc_filters(route, &mut req));
-                permit
+                (permit, route.latency_objective)
             }
         };
 
-        try_fut!(self.check_rate_limit());
-
-        future::Either::Left(
+        try_fut!(self.check_rate_limit(&connection));
+
+        // The objective is read from the route on each request so that policy
+        // updates apply to subsequent requests.
+        let slo = objective.map(|objective| {
+            slo::RecordSlo::new(
+                objective,
+                permit.clone(),
+                connection.tls.as_ref().map(|t| t.labels()),
+                self.metrics.clone(),
+            )
+        });
+        let revoked = self.revoked(connection.into_owned(), head, permit.clone());
+        future::Either::Left(ResponseFuture::new(
             self.inner
                 .new_service((permit, self.target.clone()))
                 .oneshot(req)
                 .err_into::<Error>(),
-        )
+            revoked,
+            slo,
+        ))
     }
 }
 
@@ -188,6 +233,7 @@ impl<T, N> HttpPolicyService<T, N> {
     /// authorization.
     fn authorize<'m, M: super::route::Match + 'm, P, B>(
         &self,
+        connection: &ConnectionMeta,
         routes: &'m [super::route::Route<M, RoutePolicy<P>>],
         req: &::http::Request<B>,
     ) -> Result<(HttpRoutePermit, RouteMatch<M::Summary>, &'m RoutePolicy<P>)> {
@@ -202,7 +248,7 @@ impl<T, N> HttpPolicyService<T, N> {
         let authz = match route
             .authorizations
             .iter()
-            .find(|a| super::is_authorized(a, self.connection.client, &self.connection.tls))
+            .find(|a| super::is_authorized(a, connection.client, &connection.tls))
         {
             Some(authz) => {
                 if authz.meta.is_audit() {
@@ -213,8 +259,8 @@ impl<T, N> HttpPolicyService<T, N> {
                         route.group = %labels.route.group(),
                         route.kind = %labels.route.kind(),
                         route.name = %labels.route.name(),
-                        client.tls = ?self.connection.tls,
-                        client.ip = %self.connection.client.ip(),
+                        client.tls = ?connection.tls,
+                        client.ip = %connection.client.ip(),
                         authz.group = %authz.meta.group(),
                         authz.kind = %authz.meta.kind(),
                         authz.name = %authz.meta.name(),
@@ -231,8 +277,8 @@ impl<T, N> HttpPolicyService<T, N> {
                     route.group = %labels.route.group(),
                     route.kind = %labels.route.kind(),
                     route.name = %labels.route.name(),
-                    client.tls = ?self.connection.tls,
-                    client.ip = %self.connection.client.ip(),
+                    client.tls = ?connection.tls,
+                    client.ip = %connection.client.ip(),
                     "Request denied",
                 );
                 if tracing::event_enabled!(tracing::Level::DEBUG) {
@@ -250,8 +296,8 @@ impl<T, N> HttpPolicyService<T, N> {
                 }
                 self.metrics.deny(
                     labels,
-                    self.connection.dst,
-                    self.connection.tls.as_ref().map(|t| t.labels()),
+                    connection.dst,
+                    connection.tls.as_ref().map(|t| t.labels()),
                 );
                 return Err(HttpRouteUnauthorized(()).into());
             }
@@ -272,22 +318,59 @@ impl<T, N> HttpPolicyService<T, N> {
                 authz.group = %labels.authz.group(),
                 authz.kind = %labels.authz.kind(),
                 authz.name = %labels.authz.name(),
-                client.tls = ?self.connection.tls,
-                client.ip = %self.connection.client.ip(),
+                client.tls = ?connection.tls,
+                client.ip = %connection.client.ip(),
                 "Request authorized",
             );
             HttpRoutePermit {
-                dst: self.connection.dst,
+                dst: connection.dst,
                 labels,
+                sheddable: route.sheddable,
+                ext_authz: route.ext_authz,
             }
         };
 
         self.metrics
-            .allow(&permit, self.connection.tls.as_ref().map(|t| t.labels()));
+            .allow(&permit, connection.tls.as_ref().map(|t| t.labels()));
 
         Ok((permit, r#match, route))
     }
 
+    /// Returns a future that completes when a policy update revokes the
+    /// request's authorization and the server's grace period has elapsed.
+    fn revoked(
+        &self,
+        connection: ConnectionMeta,
+        head: ::http::Request<()>,
+        permit: HttpRoutePermit,
+    ) -> revoke::Revoked {
+        let mut policy = self.policy.clone();
+        let metrics = self.metrics.clone();
+        Box::pin(async move {
+            policy
+                .revoked(|p| match p.routes() {
+                    None => false,
+                    Some(Routes::Http(routes)) => is_permitted(&routes, &head, &connection),
+                    Some(Routes::Grpc(routes)) => is_permitted(&routes, &head, &connection),
+                })
+                .await;
+
+            let labels = &permit.labels.route;
+            tracing::info!(
+                server.group = %labels.server.0.group(),
+                server.kind = %labels.server.0.kind(),
+                server.name = %labels.server.0.name(),
+                route.group = %labels.route.group(),
+                route.kind = %labels.route.kind(),
+                route.name = %labels.route.name(),
+                client.tls = ?connection.tls,
+                client.ip = %connection.client.ip(),
+                "Request terminated due to policy change",
+            );
+            metrics.terminate(&permit, connection.tls.as_ref().map(|t| t.labels()));
+        })
+    }
+
     fn mk_route_not_found(&self) -> Error {
         let labels = self.policy.server_label();
         self.metrics.route_not_found(
@@ -298,8 +381,8 @@ impl<T, N> HttpPolicyService<T, N> {
         HttpRouteNotFound(()).into()
     }
 
-    fn check_rate_limit(&self) -> Result<()> {
-        let id = match self.connection.tls {
+    fn check_rate_limit(&self, connection: &ConnectionMeta) -> Result<()> {
+        let id = match connection.tls {
             Conditional::Some(tls::ServerTls::Established {
                 client_id: Some(tls::ClientId(ref id)),
                 ..
@@ -313,20 +396,60 @@ impl<T, N> HttpPolicyService<T, N> {
             .map_err(|err| {
                 self.metrics.ratelimit(
                     self.policy.ratelimit_label(&err),
-                    self.connection.dst,
-                    self.connection.tls.as_ref().map(|t| t.labels()),
+                    connection.dst,
+                    connection.tls.as_ref().map(|t| t.labels()),
                 );
                 err.into()
             })
     }
 }
 
+// === impl ConnectionMeta ===
+
+impl ConnectionMeta {
+    /// Returns the connection's metadata with its client identity replaced by
+    /// a workload identity that was verified from the request, if any.
+    fn for_request(&self, extensions: &::http::Extensions) -> Cow<'_, Self> {
+        let Some(VerifiedIdentity(id)) = extensions.get::<VerifiedIdentity>() else {
+            return Cow::Borrowed(self);
+        };
+        let negotiated_protocol = match self.tls {
+            Conditional::Some(tls::ServerTls::Established {
+                ref negotiated_protocol,
+                ..
+            }) => negotiated_protocol.clone(),
+            _ => None,
+        };
+        Cow::Owned(Self {
+            tls: Conditional::Some(tls::ServerTls::Established {
+                client_id: Some(tls::ClientId(id.clone())),
+                negotiated_protocol,
+            }),
+            ..self.clone()
+        })
+    }
+}
+
+/// Returns true if the request matches a route that authorizes the
+/// connection.
+fn is_permitted<M: super::route::Match, P>(
+    routes: &[super::route::Route<M, RoutePolicy<P>>],
+    req: &::http::Request<()>,
+    connection: &ConnectionMeta,
+) -> bool {
+    super::route::find(routes, req).is_some_and(|(_, route)| {
+        route
+            .authorizations
+            .iter()
+            .any(|a| super::is_authorized(a, connection.client, &connection.tls))
+    })
+}
+
 fn apply_http_filters<B>(
     r#match: http::RouteMatch,
     route: &http::Policy,
     req: &mut ::http::Request<B>,
 ) -> Result<()> {
-    // TODO Do any metrics apply here?
     for filter in &route.filters {
         match filter {
             http::Filter::InjectFailure(fail) => {
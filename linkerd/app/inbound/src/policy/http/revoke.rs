@@ -0,0 +1,160 @@
+use super::{slo::RecordSlo, HttpRouteUnauthorized};
+use linkerd_app_core::{Error, Result};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Completes when a policy update revokes a request's authorization.
+pub(super) type Revoked = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Fails a request if its authorization is revoked before a response is
+/// received.
+///
+/// Once a response is received, the revocation is passed to the response
+/// body so that streaming responses are also terminated. The response body
+/// also records whether the request completes within its route's latency
+/// objective, if one is configured.
+#[pin_project]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    revoked: Option<Revoked>,
+    slo: Option<RecordSlo>,
+}
+
+/// Fails a response body stream if its request's authorization is revoked.
+#[pin_project]
+#[derive(Default)]
+pub struct ResponseBody<B> {
+    #[pin]
+    inner: B,
+    revoked: Option<Revoked>,
+    slo: Option<RecordSlo>,
+}
+
+// === impl ResponseFuture ===
+
+impl<F> ResponseFuture<F> {
+    pub(super) fn new(inner: F, revoked: Revoked, slo: Option<RecordSlo>) -> Self {
+        Self {
+            inner,
+            revoked: Some(revoked),
+            slo,
+        }
+    }
+}
+
+impl<F, B> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>>>,
+    B: http_body::Body,
+{
+    type Output = Result<http::Response<ResponseBody<B>>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if let Some(revoked) = this.revoked.as_mut() {
+            if revoked.as_mut().poll(cx).is_ready() {
+                *this.revoked = None;
+                *this.slo = None;
+                return Poll::Ready(Err(HttpRouteUnauthorized(()).into()));
+            }
+        }
+
+        let rsp = match futures::ready!(this.inner.poll(cx)) {
+            Ok(rsp) => rsp,
+            Err(error) => {
+                *this.slo = None;
+                return Poll::Ready(Err(error));
+            }
+        };
+        let revoked = this.revoked.take();
+        let mut slo = this.slo.take();
+        if rsp.body().is_end_stream() {
+            // The body may never be polled, so the request is complete.
+            if let Some(slo) = slo.take() {
+                slo.complete();
+            }
+        }
+        Poll::Ready(Ok(rsp.map(|inner| ResponseBody {
+            inner,
+            revoked,
+            slo,
+        })))
+    }
+}
+
+impl<F: std::fmt::Debug> std::fmt::Debug for ResponseFuture<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseFuture")
+            .field("inner", &self.inner)
+            .field("revoked", &self.revoked.is_some())
+            .field("slo", &self.slo)
+            .finish()
+    }
+}
+
+// === impl ResponseBody ===
+
+impl<B> http_body::Body for ResponseBody<B>
+where
+    B: http_body::Body,
+    B::Error: Into<Error>,
+{
+    type Data = B::Data;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<http_body::Frame<Self::Data>>>> {
+        let this = self.project();
+        if let Some(revoked) = this.revoked.as_mut() {
+            if revoked.as_mut().poll(cx).is_ready() {
+                *this.revoked = None;
+                *this.slo = None;
+                return Poll::Ready(Some(Err(HttpRouteUnauthorized(()).into())));
+            }
+        }
+
+        let frame = futures::ready!(this.inner.poll_frame(cx));
+        match frame {
+            None => {
+                // The stream is complete, so there's no need to continue
+                // watching the policy.
+                *this.revoked = None;
+                if let Some(slo) = this.slo.take() {
+                    slo.complete();
+                }
+            }
+            Some(Err(_)) => {
+                *this.slo = None;
+            }
+            Some(Ok(_)) => {}
+        }
+        Poll::Ready(frame.map(|f| f.map_err(Into::into)))
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl<B: std::fmt::Debug> std::fmt::Debug for ResponseBody<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseBody")
+            .field("inner", &self.inner)
+            .field("revoked", &self.revoked.is_some())
+            .field("slo", &self.slo)
+            .finish()
+    }
+}
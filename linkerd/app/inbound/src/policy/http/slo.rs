@@ -0,0 +1,70 @@
+use crate::{metrics::authz::HttpAuthzMetrics, policy::HttpRoutePermit};
+use linkerd_app_core::tls;
+use tokio::time;
+
+/// Records whether a request completes within its route's latency objective.
+///
+/// A request is within its objective if its response stream completes
+/// successfully before the objective elapses. Requests that fail, or that are
+/// dropped before their response completes, are not within the objective.
+#[derive(Debug)]
+pub(super) struct RecordSlo(Option<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    objective: time::Duration,
+    start: time::Instant,
+    permit: HttpRoutePermit,
+    tls: tls::ConditionalServerTlsLabels,
+    metrics: HttpAuthzMetrics,
+}
+
+// === impl RecordSlo ===
+
+impl RecordSlo {
+    pub(super) fn new(
+        objective: time::Duration,
+        permit: HttpRoutePermit,
+        tls: tls::ConditionalServerTlsLabels,
+        metrics: HttpAuthzMetrics,
+    ) -> Self {
+        Self(Some(Inner {
+            objective,
+            start: time::Instant::now(),
+            permit,
+            tls,
+            metrics,
+        }))
+    }
+
+    /// Records that the request's response completed successfully.
+    pub(super) fn complete(mut self) {
+        if let Some(inner) = self.0.take() {
+            let elapsed = time::Instant::now().saturating_duration_since(inner.start);
+            let within = elapsed <= inner.objective;
+            inner.record(within);
+        }
+    }
+}
+
+impl Drop for RecordSlo {
+    fn drop(&mut self) {
+        if let Some(inner) = self.0.take() {
+            inner.record(false);
+        }
+    }
+}
+
+// === impl Inner ===
+
+impl Inner {
+    fn record(self, within: bool) {
+        let Self {
+            permit,
+            tls,
+            metrics,
+            ..
+        } = self;
+        metrics.route_slo(&permit, tls, within);
+    }
+}
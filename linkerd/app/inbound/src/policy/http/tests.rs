@@ -32,6 +32,7 @@ macro_rules! new_svc {
                     name: "testsrv".into(),
                 }),
                 local_rate_limit: Arc::new($rl),
+                termination_grace_period: Default::default(),
             },
         );
         let svc = HttpPolicyService {
@@ -102,6 +103,9 @@ async fn http_route() {
                     }]),
                     filters: vec![],
                     meta: rmeta.clone(),
+                    latency_objective: None,
+                    sheddable: false,
+                    ext_authz: false,
                 },
             },
             Rule {
@@ -113,6 +117,9 @@ async fn http_route() {
                     authorizations: Arc::new([]),
                     filters: vec![],
                     meta: rmeta.clone(),
+                    latency_objective: None,
+                    sheddable: false,
+                    ext_authz: false,
                 },
             }
         ],
@@ -182,6 +189,9 @@ async fn http_route() {
                         }]),
                         filters: vec![],
                         meta: rmeta.clone(),
+                        latency_objective: None,
+                        sheddable: false,
+                        ext_authz: false,
                     },
                 },
                 Rule {
@@ -201,11 +211,15 @@ async fn http_route() {
                         }]),
                         filters: vec![],
                         meta: rmeta.clone(),
+                        latency_objective: None,
+                        sheddable: false,
+                        ext_authz: false,
                     },
                 },
             ],
         }])),
         local_rate_limit: Arc::new(Default::default()),
+        termination_grace_period: Default::default(),
     })
     .expect("must send");
 
@@ -275,6 +289,9 @@ async fn http_filter_header() {
                     ..filter::ModifyHeader::default()
                 })],
                 meta: rmeta.clone(),
+                latency_objective: None,
+                sheddable: false,
+                ext_authz: false,
             },
         }],
     }]));
@@ -339,6 +356,9 @@ async fn http_filter_inject_failure() {
                     },
                 })],
                 meta: rmeta.clone(),
+                latency_objective: None,
+                sheddable: false,
+                ext_authz: false,
             },
         }],
     }]));
@@ -360,6 +380,358 @@ async fn http_filter_inject_failure() {
     );
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn http_filter_redirect() {
+    use linkerd_app_core::metrics::legacy::FmtMetrics;
+    use linkerd_proxy_server_policy::http::{
+        filter, r#match::MatchRequest, Filter, Policy, Route, Rule,
+    };
+
+    let rmeta = Arc::new(Meta::Resource {
+        group: "gateway.networking.k8s.io".into(),
+        kind: "httproute".into(),
+        name: "testrt".into(),
+    });
+    let proto = Protocol::Http1(Arc::new([Route {
+        hosts: vec![],
+        rules: vec![Rule {
+            matches: vec![MatchRequest::default()],
+            policy: Policy {
+                authorizations: Arc::new([Authorization {
+                    authentication: Authentication::Unauthenticated,
+                    networks: vec![std::net::IpAddr::from([192, 168, 3, 3]).into()],
+                    meta: Arc::new(Meta::Resource {
+                        group: "policy.linkerd.io".into(),
+                        kind: "AuthorizatoinPolicy".into(),
+                        name: "test".into(),
+                    }),
+                }]),
+                filters: vec![Filter::Redirect(filter::RedirectRequest {
+                    path: Some(filter::ModifyPath::ReplaceFullPath("/v2".into())),
+                    status: Some(::http::StatusCode::MOVED_PERMANENTLY),
+                    ..Default::default()
+                })],
+                meta: rmeta.clone(),
+                latency_objective: None,
+                sheddable: false,
+                ext_authz: false,
+            },
+        }],
+    }]));
+    let inner = |_: HttpRoutePermit,
+                 _: ::http::Request<BoxBody>|
+     -> Result<::http::Response<BoxBody>> { unreachable!() };
+    let (mut svc, _tx) = new_svc!(proto, conn!(), inner);
+
+    let err = svc
+        .call(
+            ::http::Request::builder()
+                .uri("http://example.com/v1")
+                .body(BoxBody::default())
+                .unwrap(),
+        )
+        .await
+        .expect_err("redirects");
+    let redirect = err
+        .downcast_ref::<HttpRouteRedirect>()
+        .expect("redirect error");
+    assert_eq!(redirect.status, ::http::StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(redirect.location, "http://example.com/v2");
+
+    let metrics = svc.metrics.as_display().to_string();
+    assert!(
+        metrics
+            .lines()
+            .any(|l| l.starts_with("inbound_http_route_redirect_total{")
+                && l.contains("route_name=\"testrt\"")
+                && l.ends_with(" 1")),
+        "{metrics}"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_route_revoked() {
+    use http_body_util::BodyExt;
+    use linkerd_app_core::metrics::legacy::FmtMetrics;
+    use linkerd_proxy_server_policy::http::{r#match::MatchRequest, Policy, Route, Rule};
+    use tokio::time;
+
+    let rmeta = Arc::new(Meta::Resource {
+        group: "gateway.networking.k8s.io".into(),
+        kind: "httproute".into(),
+        name: "testrt".into(),
+    });
+    let mk_proto = |networks: Vec<std::net::IpAddr>| {
+        Protocol::Http1(Arc::new([Route {
+            hosts: vec![],
+            rules: vec![Rule {
+                matches: vec![MatchRequest::default()],
+                policy: Policy {
+                    authorizations: Arc::new([Authorization {
+                        authentication: Authentication::Unauthenticated,
+                        networks: networks.into_iter().map(Into::into).collect(),
+                        meta: Arc::new(Meta::Resource {
+                            group: "policy.linkerd.io".into(),
+                            kind: "AuthorizationPolicy".into(),
+                            name: "test".into(),
+                        }),
+                    }]),
+                    filters: vec![],
+                    meta: rmeta.clone(),
+                    latency_objective: None,
+                    sheddable: false,
+                    ext_authz: false,
+                },
+            }],
+        }]))
+    };
+
+    // Serve a response body that streams indefinitely.
+    let inner = |_: HttpRoutePermit, _: ::http::Request<BoxBody>| {
+        let body = http_body_util::StreamBody::new(futures::stream::pending::<
+            Result<http_body::Frame<bytes::Bytes>>,
+        >());
+        Ok::<_, Infallible>(::http::Response::new(BoxBody::new(body)))
+    };
+    let (mut svc, tx) = new_svc!(mk_proto(vec![[192, 168, 3, 3].into()]), conn!(), inner);
+    tx.send_modify(|p| p.termination_grace_period = time::Duration::from_secs(10));
+
+    let mut body = svc
+        .call(::http::Request::builder().body(BoxBody::default()).unwrap())
+        .await
+        .expect("serves")
+        .into_body();
+
+    // Updating the policy without revoking the request's authorization does
+    // not interrupt the stream.
+    tx.send_modify(|p| {
+        p.protocol = mk_proto(vec![[192, 168, 3, 0].into(), [192, 168, 3, 3].into()])
+    });
+    assert!(
+        time::timeout(time::Duration::from_secs(60), body.frame())
+            .await
+            .is_err(),
+        "stream must not be terminated"
+    );
+
+    // Once the authorization is revoked, the stream continues until the grace
+    // period elapses.
+    tx.send_modify(|p| p.protocol = mk_proto(vec![[192, 168, 3, 0].into()]));
+    let t0 = time::Instant::now();
+    let Some(Err(err)) = body.frame().await else {
+        panic!("stream must fail");
+    };
+    assert!(err.is::<HttpRouteUnauthorized>());
+    assert_eq!(
+        time::Instant::now().saturating_duration_since(t0),
+        time::Duration::from_secs(10)
+    );
+
+    let metrics = svc.metrics.as_display().to_string();
+    assert!(
+        metrics
+            .lines()
+            .any(|l| l.starts_with("inbound_http_authz_terminate_total{")
+                && l.contains("route_name=\"testrt\"")
+                && l.ends_with(" 1")),
+        "{metrics}"
+    );
+
+    // Requests that are in flight when their authorization is revoked fail
+    // once the grace period elapses, without waiting for a response.
+    let mk_pending_svc = |termination_grace_period| {
+        let (policy, tx) = AllowPolicy::for_test(
+            conn!().dst,
+            ServerPolicy {
+                protocol: mk_proto(vec![[192, 168, 3, 3].into()]),
+                meta: Meta::new_default("test"),
+                local_rate_limit: Default::default(),
+                termination_grace_period,
+            },
+        );
+        let svc = HttpPolicyService {
+            target: (),
+            policy,
+            connection: conn!(),
+            metrics: HttpAuthzMetrics::default(),
+            inner: |_: (HttpRoutePermit, ())| {
+                svc::mk(|_: ::http::Request<BoxBody>| {
+                    futures::future::pending::<Result<::http::Response<BoxBody>>>()
+                })
+            },
+        };
+        (svc, tx)
+    };
+    let (mut svc, tx) = mk_pending_svc(time::Duration::from_secs(10));
+    let rsp = svc.call(::http::Request::builder().body(BoxBody::default()).unwrap());
+    tx.send_modify(|p| p.protocol = mk_proto(vec![]));
+    assert!(rsp
+        .await
+        .expect_err("request must fail")
+        .is::<HttpRouteUnauthorized>());
+
+    // Without a grace period, requests are terminated as soon as their
+    // authorization is revoked.
+    let (mut svc, tx) = mk_pending_svc(time::Duration::ZERO);
+    let rsp = svc.call(::http::Request::builder().body(BoxBody::default()).unwrap());
+    tx.send_modify(|p| p.protocol = mk_proto(vec![]));
+    let t0 = time::Instant::now();
+    assert!(rsp
+        .await
+        .expect_err("request must fail")
+        .is::<HttpRouteUnauthorized>());
+    assert_eq!(time::Instant::now(), t0);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_route_revoked_without_grace_period() {
+    use http_body_util::BodyExt;
+    use linkerd_proxy_server_policy::http::{r#match::MatchRequest, Policy, Route, Rule};
+    use tokio::time;
+
+    let mk_proto = |networks: Vec<std::net::IpAddr>| {
+        Protocol::Http1(Arc::new([Route {
+            hosts: vec![],
+            rules: vec![Rule {
+                matches: vec![MatchRequest::default()],
+                policy: Policy {
+                    authorizations: Arc::new([Authorization {
+                        authentication: Authentication::Unauthenticated,
+                        networks: networks.into_iter().map(Into::into).collect(),
+                        meta: Arc::new(Meta::Resource {
+                            group: "policy.linkerd.io".into(),
+                            kind: "AuthorizationPolicy".into(),
+                            name: "test".into(),
+                        }),
+                    }]),
+                    filters: vec![],
+                    meta: Arc::new(Meta::new_default("testrt")),
+                    latency_objective: None,
+                    sheddable: false,
+                    ext_authz: false,
+                },
+            }],
+        }]))
+    };
+
+    let inner = |_: HttpRoutePermit, _: ::http::Request<BoxBody>| {
+        let body = http_body_util::StreamBody::new(futures::stream::pending::<
+            Result<http_body::Frame<bytes::Bytes>>,
+        >());
+        Ok::<_, Infallible>(::http::Response::new(BoxBody::new(body)))
+    };
+    let (mut svc, tx) = new_svc!(mk_proto(vec![[192, 168, 3, 3].into()]), conn!(), inner);
+    assert!(svc.policy.borrow().termination_grace_period.is_zero());
+
+    let mut body = svc
+        .call(::http::Request::builder().body(BoxBody::default()).unwrap())
+        .await
+        .expect("serves")
+        .into_body();
+
+    // Tightening the policy while the response streams terminates the stream
+    // immediately.
+    tx.send_modify(|p| p.protocol = mk_proto(vec![[192, 168, 3, 0].into()]));
+    let t0 = time::Instant::now();
+    let Some(Err(err)) = body.frame().await else {
+        panic!("stream must fail");
+    };
+    assert!(err.is::<HttpRouteUnauthorized>());
+    assert_eq!(time::Instant::now(), t0);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_route_slo() {
+    use http_body_util::BodyExt;
+    use linkerd_app_core::metrics::legacy::FmtMetrics;
+    use linkerd_proxy_server_policy::http::{r#match::MatchRequest, Policy, Route, Rule};
+    use tokio::time;
+
+    let mk_proto = |objective: time::Duration| {
+        Protocol::Http1(Arc::new([Route {
+            hosts: vec![],
+            rules: vec![Rule {
+                matches: vec![MatchRequest::default()],
+                policy: Policy {
+                    authorizations: Arc::new([Authorization {
+                        authentication: Authentication::Unauthenticated,
+                        networks: vec![std::net::IpAddr::from([192, 168, 3, 3]).into()],
+                        meta: Arc::new(Meta::Resource {
+                            group: "policy.linkerd.io".into(),
+                            kind: "AuthorizationPolicy".into(),
+                            name: "test".into(),
+                        }),
+                    }]),
+                    filters: vec![],
+                    meta: Arc::new(Meta::Resource {
+                        group: "gateway.networking.k8s.io".into(),
+                        kind: "httproute".into(),
+                        name: "testrt".into(),
+                    }),
+                    latency_objective: Some(objective),
+                    sheddable: false,
+                    ext_authz: false,
+                },
+            }],
+        }]))
+    };
+    let count = |svc: &HttpPolicyService<_, _>, within: bool| {
+        let metrics = svc.metrics.as_display().to_string();
+        metrics
+            .lines()
+            .find(|l| {
+                l.starts_with("inbound_http_route_slo_requests_total{")
+                    && l.contains(&format!("within=\"{within}\""))
+            })
+            .and_then(|l| l.rsplit(' ').next()?.parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+
+    // Serve a response body that completes after a second.
+    let inner = |_: HttpRoutePermit, req: ::http::Request<BoxBody>| {
+        if req.uri().path() == "/fail" {
+            return Err(Error::from("boom"));
+        }
+        let body = http_body_util::StreamBody::new(futures::stream::once(async {
+            time::sleep(time::Duration::from_secs(1)).await;
+            Ok::<_, Error>(http_body::Frame::data(bytes::Bytes::from_static(b"ok")))
+        }));
+        Ok(::http::Response::new(BoxBody::new(body)))
+    };
+    let (mut svc, tx) = new_svc!(mk_proto(time::Duration::from_secs(2)), conn!(), inner);
+
+    let rsp = svc
+        .call(::http::Request::builder().body(BoxBody::default()).unwrap())
+        .await
+        .expect("serves");
+    rsp.into_body().collect().await.expect("body must complete");
+    assert_eq!(count(&svc, true), 1);
+    assert_eq!(count(&svc, false), 0);
+
+    // Requests that fail are not within the objective.
+    svc.call(
+        ::http::Request::builder()
+            .uri("/fail")
+            .body(BoxBody::default())
+            .unwrap(),
+    )
+    .await
+    .expect_err("request must fail");
+    assert_eq!(count(&svc, true), 1);
+    assert_eq!(count(&svc, false), 1);
+
+    // Updating the objective applies to subsequent requests without resetting
+    // the route's counts.
+    tx.send_modify(|p| p.protocol = mk_proto(time::Duration::from_millis(500)));
+    let rsp = svc
+        .call(::http::Request::builder().body(BoxBody::default()).unwrap())
+        .await
+        .expect("serves");
+    rsp.into_body().collect().await.expect("body must complete");
+    assert_eq!(count(&svc, true), 1);
+    assert_eq!(count(&svc, false), 2);
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn rate_limit_allow() {
     use linkerd_app_core::{Ipv4Net, Ipv6Net};
@@ -436,6 +808,40 @@ async fn rate_limit_deny() {
     };
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn http_route_verified_identity() {
+    use linkerd_app_core::{proxy::http::workload_identity::VerifiedIdentity, Ipv4Net};
+
+    let authorizations = Arc::new([Authorization {
+        meta: Meta::new_default("default"),
+        networks: vec![Ipv4Net::default().into()],
+        authentication: Authentication::TlsAuthenticated {
+            identities: ["client.ns.serviceaccount.identity.linkerd.cluster.local".to_string()]
+                .into_iter()
+                .collect(),
+            suffixes: vec![],
+        },
+    }]);
+    let (mut svc, _tx) = new_svc!(Protocol::Http1(Arc::new([http::default(authorizations)])));
+
+    // The gateway's own identity is not authorized.
+    assert!(svc
+        .call(::http::Request::builder().body(BoxBody::default()).unwrap())
+        .await
+        .expect_err("fails")
+        .is::<HttpRouteUnauthorized>());
+
+    // A verified assertion is authorized in place of the connection's identity.
+    let mut req = ::http::Request::builder().body(BoxBody::default()).unwrap();
+    req.extensions_mut().insert(VerifiedIdentity(
+        "client.ns.serviceaccount.identity.linkerd.cluster.local"
+            .parse()
+            .unwrap(),
+    ));
+    let rsp = svc.call(req).await.expect("serves");
+    assert!(rsp.extensions().get::<HttpRoutePermit>().is_some());
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn grpc_route() {
     use linkerd_proxy_server_policy::grpc::{
@@ -471,6 +877,9 @@ async fn grpc_route() {
                     }]),
                     filters: vec![],
                     meta: rmeta.clone(),
+                    latency_objective: None,
+                    sheddable: false,
+                    ext_authz: false,
                 },
             },
             Rule {
@@ -485,6 +894,9 @@ async fn grpc_route() {
                     authorizations: Arc::new([]),
                     filters: vec![],
                     meta: rmeta.clone(),
+                    latency_objective: None,
+                    sheddable: false,
+                    ext_authz: false,
                 },
             }
         ],
@@ -572,6 +984,9 @@ async fn grpc_filter_header() {
                     ..http::filter::ModifyHeader::default()
                 })],
                 meta: rmeta.clone(),
+                latency_objective: None,
+                sheddable: false,
+                ext_authz: false,
             },
         }],
     }]));
@@ -648,6 +1063,9 @@ async fn grpc_filter_inject_failure() {
                     },
                 })],
                 meta: rmeta.clone(),
+                latency_objective: None,
+                sheddable: false,
+                ext_authz: false,
             },
         }],
     }]));
@@ -153,26 +153,23 @@ where
 
         let call = inner.call(io);
         future::Either::Left(Box::pin(async move {
-            tokio::pin!(call);
-            loop {
-                tokio::select! {
-                    res = &mut call => return res.map_err(Into::into),
-                    _ = policy.changed() => {
-                        if let Err(denied) = check_authorized(&policy.server.borrow(), policy.dst, client, &tls) {
-                            let meta = policy.meta();
-                            tracing::info!(
-                                server.group = %meta.group(),
-                                server.kind = %meta.kind(),
-                                server.name = %meta.name(),
-                                ?tls,
-                                %client,
-                                "Connection terminated due to policy change",
-                            );
-                            metrics.terminate(&policy, tls.as_ref().map(|t| t.labels()));
-                            return Err(denied.into());
-                        }
-                    }
-                };
+            let revoked =
+                policy.revoked(|p| check_authorized(&p.borrow(), p.dst, client, &tls).is_ok());
+            tokio::select! {
+                res = call => res.map_err(Into::into),
+                () = revoked => {
+                    let meta = policy.meta();
+                    tracing::info!(
+                        server.group = %meta.group(),
+                        server.kind = %meta.kind(),
+                        server.name = %meta.name(),
+                        ?tls,
+                        %client,
+                        "Connection terminated due to policy change",
+                    );
+                    metrics.terminate(&policy, tls.as_ref().map(|t| t.labels()));
+                    Err(ServerUnauthorized { server: meta }.into())
+                }
             }
         }))
     }
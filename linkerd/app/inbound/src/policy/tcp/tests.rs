@@ -27,6 +27,7 @@ async fn unauthenticated_allowed() {
             name: "test".into(),
         }),
         local_rate_limit: Arc::new(Default::default()),
+        termination_grace_period: Default::default(),
     };
 
     let tls = tls::ConditionalServerTls::None(tls::NoServerTls::NoClientHello);
@@ -80,6 +81,7 @@ async fn authenticated_identity() {
             name: "test".into(),
         }),
         local_rate_limit: Arc::new(Default::default()),
+        termination_grace_period: Default::default(),
     };
 
     let tls = tls::ConditionalServerTls::Some(tls::ServerTls::Established {
@@ -147,6 +149,7 @@ async fn authenticated_suffix() {
             name: "test".into(),
         }),
         local_rate_limit: Arc::new(Default::default()),
+        termination_grace_period: Default::default(),
     };
 
     let tls = tls::ConditionalServerTls::Some(tls::ServerTls::Established {
@@ -210,6 +213,7 @@ async fn tls_unauthenticated() {
             name: "test".into(),
         }),
         local_rate_limit: Arc::new(Default::default()),
+        termination_grace_period: Default::default(),
     };
 
     let tls = tls::ConditionalServerTls::Some(tls::ServerTls::Established {
@@ -249,6 +253,82 @@ async fn tls_unauthenticated() {
         .expect_err("policy must require a TLS termination identity");
 }
 
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn terminated_after_grace_period() {
+    use linkerd_app_core::metrics::legacy::FmtMetrics;
+    use svc::Service;
+    use tokio::time;
+
+    let mk_proto = |networks: Vec<linkerd_app_core::IpNet>| {
+        Protocol::Opaque(
+            vec![Authorization {
+                authentication: Authentication::Unauthenticated,
+                networks: networks.into_iter().map(Into::into).collect(),
+                meta: Arc::new(Meta::Resource {
+                    group: "policy.linkerd.io".into(),
+                    kind: "serverauthorization".into(),
+                    name: "unauth".into(),
+                }),
+            }]
+            .into(),
+        )
+    };
+    let (policy, tx) = AllowPolicy::for_test(
+        orig_dst_addr(),
+        ServerPolicy {
+            protocol: mk_proto(vec!["192.0.2.0/24".parse().unwrap()]),
+            meta: Arc::new(Meta::Resource {
+                group: "policy.linkerd.io".into(),
+                kind: "server".into(),
+                name: "test".into(),
+            }),
+            local_rate_limit: Arc::new(Default::default()),
+            termination_grace_period: time::Duration::from_secs(10),
+        },
+    );
+    let metrics = TcpAuthzMetrics::default();
+    let mut svc = TcpPolicy::Authorized(Authorized {
+        inner: svc::mk(|()| future::pending::<Result<()>>()),
+        policy,
+        client: client_addr(),
+        tls: tls::ConditionalServerTls::None(tls::NoServerTls::NoClientHello),
+        metrics: metrics.clone(),
+    });
+    let conn = tokio::spawn(svc.call(()));
+
+    // The connection is permitted to continue for the server's grace period
+    // once its authorization is revoked.
+    tx.send_modify(|p| p.protocol = mk_proto(vec!["198.51.100.0/24".parse().unwrap()]));
+    time::sleep(time::Duration::from_secs(5)).await;
+    assert!(!conn.is_finished());
+
+    // If the connection is authorized again during the grace period, it is not
+    // terminated.
+    tx.send_modify(|p| p.protocol = mk_proto(vec!["192.0.2.0/24".parse().unwrap()]));
+    time::sleep(time::Duration::from_secs(60)).await;
+    assert!(!conn.is_finished());
+
+    tx.send_modify(|p| p.protocol = mk_proto(vec![]));
+    let t0 = time::Instant::now();
+    let err = conn
+        .await
+        .unwrap()
+        .expect_err("connection must be terminated");
+    assert!(err.is::<ServerUnauthorized>());
+    assert_eq!(
+        time::Instant::now().saturating_duration_since(t0),
+        time::Duration::from_secs(10)
+    );
+
+    let metrics = metrics.as_display().to_string();
+    assert!(
+        metrics
+            .lines()
+            .any(|l| l.starts_with("inbound_tcp_authz_terminate_total{") && l.ends_with(" 1")),
+        "{metrics}"
+    );
+}
+
 fn client_id() -> tls::ClientId {
     "testsa.testns.serviceaccount.identity.linkerd.cluster.local"
         .parse()
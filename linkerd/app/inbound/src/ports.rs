@@ -0,0 +1,270 @@
+//! Tracks per-port inbound server state for diagnostics.
+//!
+//! The accept and detection stacks record how each connection to an inbound
+//! port was handled so that protocol mismatches--e.g., a port that is marked as
+//! opaque but that is expected to serve HTTP--can be diagnosed without reading
+//! the policy controller's state.
+
+use crate::policy::{AllowPolicy, Meta, Protocol};
+use linkerd_app_core::{proxy::http, Error};
+use parking_lot::Mutex;
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
+
+/// A registry of the inbound ports on which the proxy has accepted
+/// connections.
+#[derive(Clone, Debug, Default)]
+pub struct PortRegistry(Arc<Mutex<BTreeMap<u16, PortState>>>);
+
+/// A snapshot of an inbound port's state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortState {
+    pub port: u16,
+
+    /// The protocol declared by the port's policy when the most recent
+    /// connection was accepted.
+    pub policy_protocol: &'static str,
+
+    /// The server that most recently provided the port's policy.
+    pub server: Arc<Meta>,
+
+    /// The number of connections accepted on the port.
+    pub connections: u64,
+
+    /// The number of connections that were denied by the port's policy.
+    pub denied_connections: u64,
+
+    /// The protocols with which connections were ultimately handled.
+    pub protocols: ProtocolCounts,
+
+    /// The results of HTTP protocol detection.
+    pub detection: DetectCounts,
+
+    /// The results of connecting to the port's configured forward target, if
+    /// connections have been forwarded to one.
+    pub forward: Option<ForwardState>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolCounts {
+    pub http1: u64,
+    pub http2: u64,
+    pub opaque: u64,
+    pub tls: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DetectCounts {
+    pub http1: u64,
+    pub http2: u64,
+    pub not_http: u64,
+    pub read_timeout: u64,
+}
+
+/// The health of an inbound port's configured forward target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForwardState {
+    /// The application address to which connections are forwarded.
+    pub target: SocketAddr,
+
+    /// The number of connections established to the target.
+    pub connections: u64,
+
+    /// The number of connections to the target that failed, e.g. because the
+    /// application refused them.
+    pub failures: u64,
+
+    /// The error with which the most recent connection failed.
+    pub last_error: Option<String>,
+}
+
+/// The protocol with which a connection is handled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Effective {
+    Http(http::Variant),
+    Opaque,
+    Tls,
+}
+
+// === impl PortRegistry ===
+
+impl PortRegistry {
+    /// Returns a snapshot of all ports, ordered by port number.
+    pub fn ports(&self) -> Vec<PortState> {
+        self.0.lock().values().cloned().collect()
+    }
+
+    pub(crate) fn accept(&self, policy: &AllowPolicy) {
+        let port = policy.dst_addr().port();
+        let policy_protocol = protocol_name(&policy.protocol());
+        let server = policy.meta();
+        let mut ports = self.0.lock();
+        let state = ports.entry(port).or_insert_with(|| PortState {
+            port,
+            policy_protocol,
+            server: server.clone(),
+            connections: 0,
+            denied_connections: 0,
+            protocols: ProtocolCounts::default(),
+            detection: DetectCounts::default(),
+            forward: None,
+        });
+        state.policy_protocol = policy_protocol;
+        state.server = server;
+        state.connections += 1;
+    }
+
+    pub(crate) fn deny(&self, port: u16) {
+        self.update(port, |s| s.denied_connections += 1);
+    }
+
+    pub(crate) fn detected(&self, port: u16, detection: &http::Detection) {
+        self.update(port, |s| match detection {
+            http::Detection::Http(http::Variant::Http1) => s.detection.http1 += 1,
+            http::Detection::Http(http::Variant::H2) => s.detection.http2 += 1,
+            http::Detection::NotHttp => s.detection.not_http += 1,
+            http::Detection::ReadTimeout(_) => s.detection.read_timeout += 1,
+        });
+    }
+
+    pub(crate) fn handled(&self, port: u16, protocol: Effective) {
+        self.update(port, |s| match protocol {
+            Effective::Http(http::Variant::Http1) => s.protocols.http1 += 1,
+            Effective::Http(http::Variant::H2) => s.protocols.http2 += 1,
+            Effective::Opaque => s.protocols.opaque += 1,
+            Effective::Tls => s.protocols.tls += 1,
+        });
+    }
+
+    pub(crate) fn forwarded(&self, port: u16, target: SocketAddr, error: Option<&Error>) {
+        self.update(port, |s| {
+            let forward = s.forward.get_or_insert(ForwardState {
+                target,
+                connections: 0,
+                failures: 0,
+                last_error: None,
+            });
+            forward.target = target;
+            match error {
+                None => forward.connections += 1,
+                Some(error) => {
+                    forward.failures += 1;
+                    forward.last_error = Some(error.to_string());
+                }
+            }
+        });
+    }
+
+    /// Updates a port's state, if the port has been accepted.
+    fn update(&self, port: u16, f: impl FnOnce(&mut PortState)) {
+        if let Some(state) = self.0.lock().get_mut(&port) {
+            f(state);
+        }
+    }
+}
+
+fn protocol_name(protocol: &Protocol) -> &'static str {
+    match protocol {
+        Protocol::Detect { .. } => "detect",
+        Protocol::Http1(_) => "http1",
+        Protocol::Http2(_) => "http2",
+        Protocol::Grpc(_) => "grpc",
+        Protocol::Tls(_) => "tls",
+        Protocol::Opaque(_) => "opaque",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::ServerPolicy;
+    use linkerd_app_core::transport::OrigDstAddr;
+
+    fn allow(port: u16, protocol: Protocol, meta: Arc<Meta>) -> AllowPolicy {
+        let (allow, _tx) = AllowPolicy::for_test(
+            OrigDstAddr(([192, 0, 2, 2], port).into()),
+            ServerPolicy {
+                protocol,
+                meta,
+                local_rate_limit: Default::default(),
+                termination_grace_period: Default::default(),
+            },
+        );
+        allow
+    }
+
+    #[test]
+    fn records_port_state() {
+        let ports = PortRegistry::default();
+
+        // Updates are ignored for ports that have not been accepted.
+        ports.deny(8080);
+        assert!(ports.ports().is_empty());
+
+        let server = Arc::new(Meta::Resource {
+            group: "policy.linkerd.io".into(),
+            kind: "server".into(),
+            name: "web".into(),
+        });
+        let web = allow(8080, Protocol::Opaque(Arc::new([])), server.clone());
+        ports.accept(&web);
+        ports.handled(8080, Effective::Opaque);
+        ports.accept(&web);
+        ports.deny(8080);
+        let target = SocketAddr::from(([192, 0, 2, 3], 8081));
+        ports.forwarded(8080, target, None);
+        ports.forwarded(8080, target, Some(&"connection refused".into()));
+
+        let detect = allow(
+            4191,
+            Protocol::Detect {
+                http: Arc::new([]),
+                timeout: std::time::Duration::from_secs(10),
+                tcp_authorizations: Arc::new([]),
+            },
+            Meta::new_default("all-unauthenticated"),
+        );
+        ports.accept(&detect);
+        ports.detected(4191, &http::Detection::Http(http::Variant::H2));
+        ports.handled(4191, Effective::Http(http::Variant::H2));
+
+        assert_eq!(
+            ports.ports(),
+            vec![
+                PortState {
+                    port: 4191,
+                    policy_protocol: "detect",
+                    server: Meta::new_default("all-unauthenticated"),
+                    connections: 1,
+                    denied_connections: 0,
+                    protocols: ProtocolCounts {
+                        http2: 1,
+                        ..Default::default()
+                    },
+                    detection: DetectCounts {
+                        http2: 1,
+                        ..Default::default()
+                    },
+                    forward: None,
+                },
+                PortState {
+                    port: 8080,
+                    policy_protocol: "opaque",
+                    server,
+                    connections: 2,
+                    denied_connections: 1,
+                    protocols: ProtocolCounts {
+                        opaque: 1,
+                        ..Default::default()
+                    },
+                    detection: DetectCounts::default(),
+                    forward: Some(ForwardState {
+                        target,
+                        connections: 1,
+                        failures: 1,
+                        last_error: Some("connection refused".to_string()),
+                    }),
+                },
+            ]
+        );
+    }
+}
@@ -47,6 +47,7 @@ pub fn default_config() -> Config {
                 name: "testsrv".into(),
             }),
             local_rate_limit: Arc::new(Default::default()),
+            termination_grace_period: Default::default(),
         }
         .into(),
         ports: Default::default(),
@@ -90,6 +91,16 @@ pub fn default_config() -> Config {
         discovery_idle_timeout: Duration::from_secs(20),
         profile_skip_timeout: Duration::from_secs(1),
         unsafe_authority_labels: false,
+        http_compression: Default::default(),
+        http_request_id: None,
+        http_deadline: None,
+        http_client_id_header: Default::default(),
+        http_workload_identity: None,
+        forward_targets: Default::default(),
+        http2_ports: Default::default(),
+        http2_mesh_adaptive_flow_control: false,
+        app_pressure: None,
+        silent_connection_timeout: None,
     }
 }
 
@@ -103,6 +114,7 @@ pub fn runtime() -> (ProxyRuntime, drain::Signal) {
         tap,
         span_sink: None,
         drain,
+        tunables: tokio::sync::watch::channel(Default::default()).1,
     };
     (runtime, drain_tx)
 }
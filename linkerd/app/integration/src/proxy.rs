@@ -39,6 +39,8 @@ pub struct Listening {
     pub tap: Option<SocketAddr>,
     pub inbound: SocketAddr,
     pub outbound: SocketAddr,
+    pub outbound_explicit: Option<SocketAddr>,
+    pub outbound_socks5: Option<SocketAddr>,
     pub admin: SocketAddr,
 
     pub outbound_server: Option<server::Listening>,
@@ -506,6 +508,8 @@ async fn run(proxy: Proxy, mut env: TestEnv, random_ports: bool) -> Listening {
                             main.inbound_addr(),
                             main.outbound_addr(),
                             main.outbound_addr_additional(),
+                            main.outbound_explicit_addr(),
+                            main.outbound_socks5_addr(),
                             main.admin_addr(),
                         );
                         let mut running = Some((running_tx, addrs));
@@ -567,6 +571,8 @@ async fn run(proxy: Proxy, mut env: TestEnv, random_ports: bool) -> Listening {
         inbound_addr,
         outbound_addr,
         outbound_addr_additional,
+        outbound_explicit_addr,
+        outbound_socks5_addr,
         admin_addr,
     ) = running_rx.await.unwrap();
 
@@ -578,6 +584,8 @@ async fn run(proxy: Proxy, mut env: TestEnv, random_ports: bool) -> Listening {
         outbound.addr = ?outbound_addr,
         outbound.addr.additional = ?outbound_addr_additional,
         outbound.orig_dst = ?outbound,
+        outbound.explicit.addr = ?outbound_explicit_addr,
+        outbound.socks5.addr = ?outbound_socks5_addr,
         metrics.addr = ?admin_addr,
     );
 
@@ -585,6 +593,8 @@ async fn run(proxy: Proxy, mut env: TestEnv, random_ports: bool) -> Listening {
         tap: tap_addr.map(Into::into),
         inbound: inbound_addr.into(),
         outbound: outbound_addr.into(),
+        outbound_explicit: outbound_explicit_addr.map(Into::into),
+        outbound_socks5: outbound_socks5_addr.map(Into::into),
         admin: admin_addr.into(),
 
         outbound_server: proxy.outbound_server,
@@ -1,11 +1,15 @@
 mod client_policy;
+mod deadlines;
 mod direct;
 mod discovery;
+mod explicit;
 mod identity;
 mod orig_proto;
 mod profile_dst_overrides;
 mod profiles;
 mod shutdown;
+mod socks5;
 mod tap;
 mod telemetry;
 mod transparency;
+mod workload_identity;
@@ -0,0 +1,123 @@
+use crate::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const AUTHORITY: &str = "deadlines.test.svc.cluster.local";
+
+fn env() -> TestEnv {
+    let mut env = TestEnv::default();
+    env.put(app::env::ENV_HTTP_DEADLINE_PROPAGATION, "true".into());
+    env
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// Runs an outbound proxy that forwards requests to a second proxy's inbound
+/// server, which forwards requests to `srv`.
+async fn chain(srv: server::Listening) -> (proxy::Listening, proxy::Listening) {
+    let ctrl2 = controller::new();
+    let _profile2 = ctrl2.profile_tx_default(srv.addr, AUTHORITY);
+    let proxy2 = proxy::new()
+        .controller(ctrl2.run().await)
+        .inbound(srv)
+        .run_with_test_env(env())
+        .await;
+
+    let ctrl1 = controller::new();
+    let _profile1 = ctrl1.profile_tx_default(proxy2.inbound, AUTHORITY);
+    let dst = ctrl1.destination_tx(format!("{AUTHORITY}:{}", proxy2.inbound.port()));
+    dst.send_addr(proxy2.inbound);
+    let proxy1 = proxy::new()
+        .controller(ctrl1.run().await)
+        .outbound_ip(proxy2.inbound)
+        .run_with_test_env(env())
+        .await;
+
+    (proxy1, proxy2)
+}
+
+#[tokio::test]
+async fn propagates_deadlines() {
+    let _trace = trace_init();
+
+    let sent = unix_millis() + 10_000;
+    let srv = server::http1()
+        .route_fn("/", move |req| {
+            let timeout = req.headers()["grpc-timeout"].to_str().unwrap();
+            let millis = timeout
+                .strip_suffix('m')
+                .expect("timeout must be in millis")
+                .parse::<u64>()
+                .unwrap();
+            assert!(millis <= 5_000, "timeout must not increase: {timeout}");
+            assert!(millis > 0, "timeout must not be exhausted: {timeout}");
+
+            let deadline = req.headers()["x-deadline"]
+                .to_str()
+                .unwrap()
+                .parse::<u64>()
+                .unwrap();
+            assert!(
+                deadline <= unix_millis() + 5_000,
+                "deadline must be bounded by the timeout"
+            );
+            assert!(deadline < sent, "deadline must not increase");
+            Response::default()
+        })
+        .run()
+        .await;
+    let (proxy1, proxy2) = chain(srv).await;
+
+    let client = client::http1(proxy1.outbound, AUTHORITY);
+    let rsp = client
+        .request(
+            client
+                .request_builder("/")
+                .header("x-deadline", sent.to_string())
+                .header("grpc-timeout", "5S"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+
+    // Ensure panics are propagated.
+    proxy1.join_servers().await;
+    proxy2.join_servers().await;
+}
+
+#[tokio::test]
+async fn rejects_expired_deadlines() {
+    let _trace = trace_init();
+
+    let srv = server::http1()
+        .route_fn("/", |_| panic!("requests must not reach the server"))
+        .run()
+        .await;
+    let (proxy1, proxy2) = chain(srv).await;
+
+    let client = client::http1(proxy1.outbound, AUTHORITY);
+    let rsp = client
+        .request(
+            client
+                .request_builder("/")
+                .header("x-deadline", (unix_millis() - 1_000).to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::GATEWAY_TIMEOUT);
+
+    let client = client::http1(proxy2.inbound, AUTHORITY);
+    let rsp = client
+        .request(client.request_builder("/").header("grpc-timeout", "0m"))
+        .await
+        .unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::GATEWAY_TIMEOUT);
+
+    // Ensure panics are propagated.
+    proxy1.join_servers().await;
+    proxy2.join_servers().await;
+}
@@ -0,0 +1,93 @@
+use crate::*;
+
+const AUTHORITY: &str = "explicit.test.svc.cluster.local";
+
+fn env() -> TestEnv {
+    let mut env = TestEnv::default();
+    env.put(
+        app::env::ENV_OUTBOUND_EXPLICIT_LISTEN_ADDR,
+        "127.0.0.1:0".into(),
+    );
+    env
+}
+
+/// Runs a proxy with an explicit listener that discovers `srv` by name.
+///
+/// The returned senders must be held for the duration of the test.
+async fn run_proxy(
+    srv: server::Listening,
+) -> (
+    proxy::Listening,
+    String,
+    (controller::DstSender, controller::ProfileSender),
+) {
+    let ctrl = controller::new();
+    let dst = format!("{AUTHORITY}:{}", srv.addr.port());
+    let dst_tx = ctrl.destination_tx(&dst);
+    dst_tx.send_addr(srv.addr);
+    let profile_tx = ctrl.profile_tx_default(&dst, AUTHORITY);
+    let dst_addr: Addr = dst.parse().unwrap();
+    let policy = controller::policy()
+        // stop the admin server from entering an infinite retry loop
+        .with_inbound_default(policy::all_unauthenticated())
+        .outbound_default(dst_addr, &dst);
+
+    let proxy = proxy::new()
+        .controller(ctrl.run().await)
+        .policy(policy.run().await)
+        .outbound(srv)
+        .run_with_test_env(env())
+        .await;
+    (proxy, dst, (dst_tx, profile_tx))
+}
+
+#[tokio::test]
+async fn http1_absolute_form_discovers_by_name() {
+    let _trace = trace_init();
+
+    let srv = server::http1().route("/", "hello explicit").run().await;
+    let (proxy, dst, _senders) = run_proxy(srv).await;
+    let explicit = proxy.outbound_explicit.expect("explicit listener");
+
+    let client = client::http1_absolute_uris(explicit, &dst);
+    assert_eq!(client.get("/").await, "hello explicit");
+}
+
+#[tokio::test]
+async fn http1_origin_form_rejected() {
+    let _trace = trace_init();
+
+    let srv = server::http1().route("/", "hello explicit").run().await;
+    let (proxy, dst, _senders) = run_proxy(srv).await;
+    let explicit = proxy.outbound_explicit.expect("explicit listener");
+
+    let client = client::http1(explicit, &dst);
+    let rsp = client.request(client.request_builder("/")).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn connect_discovers_by_name() {
+    let _trace = trace_init();
+
+    let srv = server::http1().route("/", "hello tunnel").run().await;
+    let (proxy, dst, _senders) = run_proxy(srv).await;
+    let explicit = proxy.outbound_explicit.expect("explicit listener");
+
+    let client = tcp::client(explicit);
+    let conn = client.connect().await;
+    conn.write(format!("CONNECT {dst} HTTP/1.1\r\nhost: {dst}\r\n\r\n"))
+        .await;
+    assert_eq!(
+        conn.read().await,
+        b"HTTP/1.1 200 Connection established\r\n\r\n"
+    );
+
+    conn.write(format!(
+        "GET / HTTP/1.1\r\nhost: {dst}\r\nconnection: close\r\n\r\n"
+    ))
+    .await;
+    let rsp = String::from_utf8(conn.read().await).unwrap();
+    assert!(rsp.starts_with("HTTP/1.1 200 OK\r\n"), "{rsp}");
+    assert!(rsp.ends_with("hello tunnel"), "{rsp}");
+}
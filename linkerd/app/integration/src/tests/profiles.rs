@@ -8,12 +8,13 @@ struct TestBuilder {
     routes: Vec<controller::RouteBuilder>,
     budget: Option<controller::pb::RetryBudget>,
     default_routes: bool,
+    env: TestEnv,
 }
 
 struct Test {
     metrics: client::Client,
     client: client::Client,
-    _proxy: proxy::Listening,
+    proxy: proxy::Listening,
     port: u16,
     // stuff that tests currently never use, but we can't drop while the test is running
     _guards: (
@@ -38,6 +39,7 @@ impl TestBuilder {
             ],
             budget: Some(controller::retry_budget(Duration::from_secs(1), 0.1, 1)),
             default_routes: true,
+            env: TestEnv::default(),
         }
     }
 
@@ -54,6 +56,16 @@ impl TestBuilder {
         Self { routes, ..self }
     }
 
+    /// Configures the proxy's per-try timeout for retryable profile routes.
+    fn with_retry_timeout(self, timeout: &str) -> Self {
+        let mut env = self.env;
+        env.put(
+            app::env::ENV_DESTINATION_PROFILE_RETRY_TIMEOUT,
+            timeout.to_string(),
+        );
+        Self { env, ..self }
+    }
+
     /// Don't add the default routes to the test server.
     ///
     /// Currently, no test actually *requires* this, but we may as well not add
@@ -100,7 +112,11 @@ impl TestBuilder {
         profile_tx.send(controller::profile(self.routes, self.budget, vec![], host));
 
         let ctrl = ctrl.run().await;
-        let proxy = proxy::new().controller(ctrl).outbound(srv).run().await;
+        let proxy = proxy::new()
+            .controller(ctrl)
+            .outbound(srv)
+            .run_with_test_env(self.env)
+            .await;
 
         let client = proxy.outbound_http_client(host);
 
@@ -111,7 +127,7 @@ impl TestBuilder {
         Test {
             metrics,
             client,
-            _proxy: proxy,
+            proxy,
             port,
             _guards: (dst_tx, profile_tx, trace),
         }
@@ -121,6 +137,7 @@ impl TestBuilder {
         let counter = AtomicUsize::new(0);
         let counter2 = AtomicUsize::new(0);
         let counter3 = AtomicUsize::new(0);
+        let counter4 = AtomicUsize::new(0);
 
         srv.route_fn("/1.0/sleep", move |_req| {
             ::std::thread::sleep(Duration::from_secs(1));
@@ -166,6 +183,22 @@ impl TestBuilder {
                     .unwrap()
             }
         })
+        .route_async("/slow-first", move |_req| {
+            let first = counter4.fetch_add(1, Ordering::Relaxed) == 0;
+            async move {
+                // Only the first attempt is slow, so that a retry issued
+                // after a per-try timeout succeeds.
+                if first {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                Ok::<_, Error>(
+                    Response::builder()
+                        .status(200)
+                        .body(BoxBody::from_static("fast"))
+                        .unwrap(),
+                )
+            }
+        })
         .route_fn("/0.5/100KB", move |_req| {
             if counter3.fetch_add(1, Ordering::Relaxed) % 2 == 0 {
                 Response::builder()
@@ -217,6 +250,32 @@ mod cross_version {
         assert_eq!(test.client.get("/0.5").await, "retried");
     }
 
+    pub(super) async fn retry_after_per_try_timeout(version: server::Server) {
+        let test = TestBuilder::new(version)
+            .with_retry_timeout("100ms")
+            .with_profile_route(
+                controller::route()
+                    .request_path("/slow-first")
+                    .retryable(true),
+            )
+            .run()
+            .await;
+
+        // The first attempt times out, and the retry succeeds well before the
+        // first attempt would have completed.
+        assert_eq!(test.client.get("/slow-first").await, "fast");
+
+        metrics::metric("route_retry_attempts_count")
+            .label("direction", "outbound")
+            .label(
+                "dst",
+                format_args!("profiles.test.svc.cluster.local:{}", test.port),
+            )
+            .value(1u64)
+            .assert_in(&test.metrics)
+            .await;
+    }
+
     pub(super) async fn retry_uses_budget(version: server::Server) {
         let test = TestBuilder::new(version)
             .with_profile_route(
@@ -270,6 +329,46 @@ mod cross_version {
         assert_eq!(res.status(), 200);
     }
 
+    /// Tests that a client waiting for a `100 Continue` receives exactly one,
+    /// and that the failed attempt's response does not leak after a retry.
+    pub(super) async fn retry_with_expect_continue(version: server::Server) {
+        let test = TestBuilder::new(version)
+            .with_profile_route(
+                controller::route()
+                    .request_any()
+                    .response_failure(500..600)
+                    .retryable(true),
+            )
+            .run()
+            .await;
+
+        let client = crate::tcp::client(test.proxy.outbound);
+        let tcp_client = client.connect().await;
+        tcp_client
+            .write(
+                "\
+                 POST /0.5 HTTP/1.1\r\n\
+                 Host: profiles.test.svc.cluster.local\r\n\
+                 Expect: 100-continue\r\n\
+                 Content-Length: 14\r\n\
+                 \r\n\
+                 ",
+            )
+            .await;
+        let rsp = tcp_client.read_timeout(Duration::from_secs(1)).await;
+        assert_eq!(s(&rsp), "HTTP/1.1 100 Continue\r\n\r\n");
+
+        tcp_client.write("req has a body").await;
+        let mut rsp = Vec::new();
+        while !s(&rsp).ends_with("retried") {
+            rsp.extend(tcp_client.read_timeout(Duration::from_secs(10)).await);
+        }
+        let rsp = s(&rsp);
+        assert!(rsp.starts_with("HTTP/1.1 200 OK\r\n"), "{rsp:?}");
+        assert!(!rsp.contains("100 Continue"), "{rsp:?}");
+        assert!(!rsp.contains("nope"), "{rsp:?}");
+    }
+
     pub(super) async fn retry_with_small_put_body(version: server::Server) {
         let test = TestBuilder::new(version)
             .with_profile_route(
@@ -553,8 +652,10 @@ mod http1 {
     version_tests! {
         server::http1() =>
         retry_if_profile_allows,
+        retry_after_per_try_timeout,
         retry_uses_budget,
         retry_with_small_post_body,
+        retry_with_expect_continue,
         retry_with_small_put_body,
         retry_without_content_length,
         does_not_retry_if_request_does_not_match,
@@ -575,8 +676,10 @@ mod http2 {
     version_tests! {
         server::http2() =>
         retry_if_profile_allows,
+        retry_after_per_try_timeout,
         retry_uses_budget,
         retry_with_small_post_body,
+        retry_with_expect_continue,
         retry_with_small_put_body,
         retry_without_content_length,
         does_not_retry_if_request_does_not_match,
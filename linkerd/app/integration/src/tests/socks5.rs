@@ -0,0 +1,105 @@
+use crate::*;
+
+const AUTHORITY: &str = "socks5.test.svc.cluster.local";
+
+fn env() -> TestEnv {
+    let mut env = TestEnv::default();
+    env.put(
+        app::env::ENV_OUTBOUND_SOCKS5_LISTEN_ADDR,
+        "127.0.0.1:0".into(),
+    );
+    env
+}
+
+/// Performs a SOCKS5 handshake without authentication, requesting a tunnel to
+/// the address described by `atyp` and `addr`.
+async fn handshake(conn: &tcp::TcpConn, atyp: u8, addr: &[u8], port: u16) {
+    conn.write(vec![0x05, 0x01, 0x00]).await;
+    assert_eq!(conn.read().await, [0x05, 0x00]);
+
+    let mut req = vec![0x05, 0x01, 0x00, atyp];
+    req.extend_from_slice(addr);
+    req.extend_from_slice(&port.to_be_bytes());
+    conn.write(req).await;
+    let rsp = conn.read().await;
+    assert_eq!(rsp[..2], [0x05, 0x00], "{rsp:?}");
+}
+
+async fn get(conn: &tcp::TcpConn, host: &str) -> String {
+    conn.write(format!(
+        "GET / HTTP/1.1\r\nhost: {host}\r\nconnection: close\r\n\r\n"
+    ))
+    .await;
+    String::from_utf8(conn.read().await).unwrap()
+}
+
+#[tokio::test]
+async fn connect_discovers_by_name() {
+    let _trace = trace_init();
+
+    let srv = server::http1().route("/", "hello socks").run().await;
+    let ctrl = controller::new();
+    let dst = format!("{AUTHORITY}:{}", srv.addr.port());
+    let dst_tx = ctrl.destination_tx(&dst);
+    dst_tx.send_addr(srv.addr);
+    let _profile = ctrl.profile_tx_default(&dst, AUTHORITY);
+    let dst_addr: Addr = dst.parse().unwrap();
+    let policy = controller::policy()
+        // stop the admin server from entering an infinite retry loop
+        .with_inbound_default(policy::all_unauthenticated())
+        .outbound_default(dst_addr, &dst);
+
+    let port = srv.addr.port();
+    let proxy = proxy::new()
+        .controller(ctrl.run().await)
+        .policy(policy.run().await)
+        .outbound(srv)
+        .run_with_test_env(env())
+        .await;
+    let socks5 = proxy.outbound_socks5.expect("SOCKS5 listener");
+
+    let conn = tcp::client(socks5).connect().await;
+    let mut name = vec![AUTHORITY.len() as u8];
+    name.extend_from_slice(AUTHORITY.as_bytes());
+    handshake(&conn, 0x03, &name, port).await;
+
+    let rsp = get(&conn, &dst).await;
+    assert!(rsp.starts_with("HTTP/1.1 200 OK\r\n"), "{rsp}");
+    assert!(rsp.ends_with("hello socks"), "{rsp}");
+}
+
+#[tokio::test]
+async fn connect_uses_sidecar_stack_by_addr() {
+    let _trace = trace_init();
+
+    let srv = server::http1().route("/", "hello socks").run().await;
+    let ctrl = controller::new();
+    let dst = format!("{AUTHORITY}:{}", srv.addr.port());
+    let _profile = ctrl.profile_tx_default(srv.addr, AUTHORITY);
+    let dst_tx = ctrl.destination_tx(&dst);
+    dst_tx.send_addr(srv.addr);
+    let policy = controller::policy()
+        // stop the admin server from entering an infinite retry loop
+        .with_inbound_default(policy::all_unauthenticated())
+        .outbound_default(srv.addr, &dst);
+
+    let addr = srv.addr;
+    let proxy = proxy::new()
+        .controller(ctrl.run().await)
+        .policy(policy.run().await)
+        .outbound(srv)
+        .run_with_test_env(env())
+        .await;
+    let socks5 = proxy.outbound_socks5.expect("SOCKS5 listener");
+
+    let ip = match addr.ip() {
+        std::net::IpAddr::V4(ip) => ip.octets(),
+        std::net::IpAddr::V6(_) => panic!("test server must listen on IPv4"),
+    };
+    let conn = tcp::client(socks5).connect().await;
+    handshake(&conn, 0x01, &ip, addr.port()).await;
+
+    let rsp = get(&conn, &dst).await;
+    assert!(rsp.starts_with("HTTP/1.1 200 OK\r\n"), "{rsp}");
+    assert!(rsp.ends_with("hello socks"), "{rsp}");
+}
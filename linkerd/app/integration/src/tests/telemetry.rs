@@ -4,6 +4,7 @@
 #![allow(unused_imports)]
 
 mod env;
+mod inbound_ports;
 mod log_stream;
 mod tcp_errors;
 
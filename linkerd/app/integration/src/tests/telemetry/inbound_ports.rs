@@ -0,0 +1,52 @@
+use super::*;
+
+#[tokio::test]
+async fn reports_inbound_port_state() {
+    let _trace = trace_init();
+    let Fixture {
+        client,
+        metrics,
+        proxy,
+        _profile,
+        ..
+    } = Fixture::inbound().await;
+    let port = proxy
+        .inbound_server
+        .as_ref()
+        .expect("inbound server")
+        .addr
+        .port();
+
+    // No ports are reported before the proxy accepts a connection.
+    let json = get_inbound_ports_json(&metrics).await;
+    assert_eq!(json, serde_json::json!({ "ports": [] }));
+
+    assert_eq!(client.get("/").await, "hello");
+
+    let json = get_inbound_ports_json(&metrics).await;
+    let ports = json["ports"].as_array().expect("ports must be a list");
+    assert_eq!(ports.len(), 1, "{json:#}");
+    let state = &ports[0];
+    assert_eq!(state["port"], port, "{json:#}");
+    assert_eq!(state["connections"], 1, "{json:#}");
+    assert_eq!(state["denied_connections"], 0, "{json:#}");
+    assert!(state["policy_protocol"].is_string(), "{json:#}");
+    assert!(state["server"]["default"].is_boolean(), "{json:#}");
+
+    // The connection was handled as HTTP.
+    let protocols = &state["protocols"];
+    assert_eq!(protocols["opaque"], 0, "{json:#}");
+    assert_eq!(protocols["tls"], 0, "{json:#}");
+    assert_eq!(
+        protocols["http1"].as_u64().unwrap() + protocols["http2"].as_u64().unwrap(),
+        1,
+        "{json:#}"
+    );
+}
+
+#[tracing::instrument(level = "info", skip(client))]
+async fn get_inbound_ports_json(client: &client::Client) -> serde_json::Value {
+    let json = client.get("/inbound-ports.json").await;
+    tracing::info!(json);
+    serde_json::from_str(json.as_str()).expect("response should be valid JSON")
+}
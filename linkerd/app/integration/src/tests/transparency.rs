@@ -1021,6 +1021,153 @@ macro_rules! http1_tests {
             proxy.join_servers().await;
         }
 
+        #[tokio::test]
+        async fn http1_expect_continue() {
+            let _trace = trace_init();
+
+            // The server requires clients to wait for its `100 Continue`
+            // before it reads the request body.
+            let srv = crate::tcp::server()
+                .accept_fut(move |mut sock| {
+                    async move {
+                        let mut buf = Vec::new();
+                        let mut chunk = vec![0; 1024];
+                        while !s(&buf).contains("\r\n\r\n") {
+                            let n = sock.read(&mut chunk).await?;
+                            assert_ne!(n, 0, "connection closed before headers");
+                            buf.extend_from_slice(&chunk[..n]);
+                        }
+                        assert_contains!(s(&buf).to_lowercase(), "\r\nexpect: 100-continue\r\n");
+
+                        sock.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+                        while !s(&buf).ends_with("\r\n\r\nhello") {
+                            let n = sock.read(&mut chunk).await?;
+                            assert_ne!(n, 0, "connection closed before body");
+                            buf.extend_from_slice(&chunk[..n]);
+                        }
+
+                        sock.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nworld")
+                            .await
+                    }
+                    .map(|res| res.expect("TCP server must not fail"))
+                })
+                .run()
+                .await;
+            let mk = $proxy;
+            let proxy = mk(srv).await;
+
+            // The client waits for a `100 Continue` before sending the body.
+            let client = crate::tcp::client(proxy.inbound);
+            let tcp_client = client.connect().await;
+            tcp_client
+                .write(
+                    "\
+                     POST / HTTP/1.1\r\n\
+                     Host: transparency.test.svc.cluster.local\r\n\
+                     Expect: 100-continue\r\n\
+                     Content-Length: 5\r\n\
+                     \r\n\
+                     ",
+                )
+                .await;
+
+            // Clients typically wait about a second before sending the body
+            // anyway.
+            let rsp = tcp_client.read_timeout(Duration::from_secs(1)).await;
+            assert_eq!(s(&rsp), "HTTP/1.1 100 Continue\r\n\r\n");
+
+            tcp_client.write("hello").await;
+            let mut rsp = Vec::new();
+            while !s(&rsp).ends_with("world") {
+                rsp.extend(tcp_client.read_timeout(Duration::from_secs(10)).await);
+            }
+            let rsp = s(&rsp);
+            assert!(rsp.starts_with("HTTP/1.1 200 OK\r\n"), "{rsp:?}");
+            assert!(!rsp.contains("100 Continue"), "{rsp:?}");
+
+            tcp_client.shutdown().await;
+
+            // ensure panics from the server are propagated
+            proxy.join_servers().await;
+        }
+
+        #[tokio::test]
+        async fn http1_server_sent_events() {
+            use http_body_util::{BodyExt, StreamBody};
+
+            let _trace = trace_init();
+
+            const EVENTS: usize = 3;
+            // The server only emits each event once the client has received
+            // the previous one, so events that are buffered are never
+            // received.
+            let (ack_tx, ack_rx) = mpsc::unbounded_channel::<()>();
+            let ack_rx = Arc::new(tokio::sync::Mutex::new(ack_rx));
+            let srv = server::http1()
+                .route_async("/events", move |_| {
+                    let ack_rx = ack_rx.clone();
+                    async move {
+                        let events = futures::stream::unfold(0, move |n| {
+                            let ack_rx = ack_rx.clone();
+                            async move {
+                                if n == EVENTS {
+                                    return None;
+                                }
+                                if n > 0 {
+                                    ack_rx.lock().await.recv().await?;
+                                }
+                                let event = Bytes::from(format!("data: {n}\n\n"));
+                                Some((Ok::<_, Error>(http_body::Frame::data(event)), n + 1))
+                            }
+                        });
+                        Ok::<_, std::io::Error>(
+                            Response::builder()
+                                .header("content-type", "text/event-stream")
+                                .body(linkerd_app_core::svc::http::BoxBody::new(StreamBody::new(events)))
+                                .unwrap(),
+                        )
+                    }
+                })
+                .run()
+                .await;
+            let mk = $proxy;
+            let proxy = mk(srv).await;
+            let client = client::http1(proxy.inbound, "transparency.test.svc.cluster.local");
+
+            let rsp = client.request(client.request_builder("/events")).await.unwrap();
+            assert_eq!(rsp.status(), StatusCode::OK);
+            assert_eq!(rsp.headers()["transfer-encoding"], "chunked");
+
+            // Each event must be delivered, in order, as it is written rather
+            // than being buffered with subsequent events.
+            let mut body = rsp.into_body();
+            let read = async move {
+                let mut buf = Vec::new();
+                let mut received = 0;
+                while let Some(frame) = body.frame().await {
+                    let Ok(data) = frame.unwrap().into_data() else {
+                        continue;
+                    };
+                    buf.extend_from_slice(&data);
+                    while let Some(end) = buf.windows(2).position(|w| w == b"\n\n") {
+                        let event = buf.drain(..end + 2).collect::<Vec<_>>();
+                        tracing::info!(event = ?String::from_utf8_lossy(&event));
+                        assert_eq!(event, format!("data: {received}\n\n").as_bytes());
+                        received += 1;
+                        let _ = ack_tx.send(());
+                    }
+                }
+                received
+            };
+            let received = tokio::time::timeout(Duration::from_secs(10), read)
+                .await
+                .expect("events must be delivered as they are written");
+            assert_eq!(received, EVENTS);
+
+            // ensure panics from the server are propagated
+            proxy.join_servers().await;
+        }
+
         #[tokio::test]
         async fn http1_requests_without_body_doesnt_add_transfer_encoding() {
             let _trace = trace_init();
@@ -1285,6 +1432,72 @@ mod one_proxy {
         let _profile = dstctl.profile_tx_default(srv.addr, "transparency.test.svc.cluster.local");
         proxy::new().inbound(srv).controller(dstctl.run().await).run().await
     }}
+
+    /// Tests that a server's final response to a request that expects a `100
+    /// Continue` is relayed before the client sends its body, rather than a
+    /// `100 Continue` being sent on the server's behalf.
+    #[tokio::test]
+    async fn http1_expect_continue_rejected() {
+        let _trace = trace_init();
+
+        let srv = crate::tcp::server()
+            .accept_fut(move |mut sock| {
+                async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = vec![0; 1024];
+                    while !s(&buf).contains("\r\n\r\n") {
+                        let n = sock.read(&mut chunk).await?;
+                        assert_ne!(n, 0, "connection closed before headers");
+                        buf.extend_from_slice(&chunk[..n]);
+                    }
+                    assert_contains!(s(&buf).to_lowercase(), "\r\nexpect: 100-continue\r\n");
+
+                    sock.write_all(b"HTTP/1.1 417 Expectation Failed\r\ncontent-length: 0\r\n\r\n")
+                        .await
+                }
+                .map(|res| res.expect("TCP server must not fail"))
+            })
+            .run()
+            .await;
+        let dstctl = controller::new();
+        let _profile = dstctl.profile_tx_default(srv.addr, "transparency.test.svc.cluster.local");
+        let proxy = proxy::new()
+            .inbound(srv)
+            .controller(dstctl.run().await)
+            .run()
+            .await;
+
+        let client = crate::tcp::client(proxy.inbound);
+        let tcp_client = client.connect().await;
+        tcp_client
+            .write(
+                "\
+                 POST / HTTP/1.1\r\n\
+                 Host: transparency.test.svc.cluster.local\r\n\
+                 Expect: 100-continue\r\n\
+                 Content-Length: 5\r\n\
+                 \r\n\
+                 ",
+            )
+            .await;
+
+        // The body is never sent.
+        let mut rsp = Vec::new();
+        while !s(&rsp).contains("\r\n\r\n") {
+            rsp.extend(tcp_client.read_timeout(Duration::from_secs(10)).await);
+        }
+        let rsp = s(&rsp);
+        assert!(
+            rsp.starts_with("HTTP/1.1 417 Expectation Failed\r\n"),
+            "{rsp:?}"
+        );
+        assert!(!rsp.contains("100 Continue"), "{rsp:?}");
+
+        tcp_client.shutdown().await;
+
+        // ensure panics from the server are propagated
+        proxy.join_servers().await;
+    }
 }
 
 mod proxy_to_proxy {
@@ -0,0 +1,175 @@
+use crate::*;
+use linkerd_app_core::svc::http::BoxBody;
+
+const SIGNER: &str = "bar.ns1.serviceaccount.identity.linkerd.cluster.local";
+const GATEWAY: &str = "foo.ns1.serviceaccount.identity.linkerd.cluster.local";
+/// The identity of proxies for which tests do not configure one.
+const RELAY: &str = "default.default.serviceaccount.identity.linkerd.cluster.local";
+
+const KEY1: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+const KEY2: &str = "2222222222222222222222222222222222222222222222222222222222222222";
+
+#[tokio::test]
+async fn gateway_accepts_rotated_keys() {
+    let _trace = trace_init();
+
+    let signed_with_key1 = assert_identity(KEY1).await;
+    let signed_with_key2 = assert_identity(KEY2).await;
+
+    // Before keys are rotated, only the first key is accepted.
+    assert_eq!(
+        gateway_client_id(KEY1, None, &signed_with_key1).await,
+        SIGNER
+    );
+    assert_eq!(
+        gateway_client_id(KEY1, None, &signed_with_key2).await,
+        RELAY
+    );
+
+    // While keys are rotated, both keys are accepted.
+    assert_eq!(
+        gateway_client_id(KEY2, Some(KEY1), &signed_with_key1).await,
+        SIGNER
+    );
+    assert_eq!(
+        gateway_client_id(KEY2, Some(KEY1), &signed_with_key2).await,
+        SIGNER
+    );
+
+    // Once keys are rotated, the first key is no longer accepted.
+    assert_eq!(
+        gateway_client_id(KEY2, None, &signed_with_key1).await,
+        RELAY
+    );
+    assert_eq!(
+        gateway_client_id(KEY2, None, &signed_with_key2).await,
+        SIGNER
+    );
+}
+
+/// Returns the assertion that an outbound proxy, signing with `key`, sets on
+/// requests to a route that is configured to assert its identity.
+async fn assert_identity(key: &str) -> String {
+    const AUTHORITY: &str = "assert.test.svc.cluster.local";
+
+    let srv = server::http1()
+        .route_fn("/", |req| {
+            let assertion = req
+                .headers()
+                .get("l5d-workload-identity")
+                .map(|v| Bytes::copy_from_slice(v.as_bytes()))
+                .unwrap_or_default();
+            Response::new(BoxBody::new(http_body_util::Full::new(assertion)))
+        })
+        .run()
+        .await;
+    let ctrl = controller::new();
+    let dst = format!("{AUTHORITY}:{}", srv.addr.port());
+    let dst_tx = ctrl.destination_tx(&dst);
+    dst_tx.send_addr(srv.addr);
+    let _profile_tx = ctrl.profile_tx_default(srv.addr, AUTHORITY);
+    let policy = controller::policy()
+        // stop the admin server from entering an infinite retry loop
+        .with_inbound_default(policy::all_unauthenticated())
+        .outbound_default(srv.addr, &dst);
+
+    let id = identity::Identity::new("bar-ns1", SIGNER.to_string());
+    let id_svc = id.service().run().await;
+    let mut env = id.env;
+    env.put(app::env::ENV_WORKLOAD_IDENTITY_KEY, key.to_string());
+    env.put(
+        app::env::ENV_OUTBOUND_ROUTE_OVERRIDES,
+        "default:default=assert-workload-identity".to_string(),
+    );
+    let proxy = proxy::new()
+        .controller(ctrl.run().await)
+        .identity(id_svc)
+        .policy(policy.run().await)
+        .outbound(srv)
+        .run_with_test_env(env)
+        .await;
+
+    let client = client::http1(proxy.outbound, AUTHORITY);
+    let assertion = client.get("/").await;
+    assert!(
+        assertion.starts_with(&format!("{SIGNER};")),
+        "unexpected assertion: {assertion:?}"
+    );
+    drop(client);
+    proxy.join_servers().await;
+    assertion
+}
+
+/// Sends `assertion` to a gateway whose inbound proxy verifies assertions
+/// with `key` and `previous_key`, returning the client identity that the
+/// gateway sets on the request.
+///
+/// The assertion is relayed through another proxy's outbound, so that the
+/// asserted identity differs from the identity of the gateway's client.
+async fn gateway_client_id(key: &str, previous_key: Option<&str>, assertion: &str) -> String {
+    const AUTHORITY: &str = "gateway.test.svc.cluster.local";
+
+    let srv = server::http1()
+        .route_fn("/", |req| {
+            let client_id = req
+                .headers()
+                .get("l5d-client-id")
+                .map(|v| Bytes::copy_from_slice(v.as_bytes()))
+                .unwrap_or_default();
+            Response::new(BoxBody::new(http_body_util::Full::new(client_id)))
+        })
+        .run()
+        .await;
+    let id = identity::Identity::new("foo-ns1", GATEWAY.to_string());
+    let id_svc = id.service().run().await;
+    let mut env = id.env;
+    env.put(app::env::ENV_WORKLOAD_IDENTITY_KEY, key.to_string());
+    if let Some(previous_key) = previous_key {
+        env.put(
+            app::env::ENV_WORKLOAD_IDENTITY_PREVIOUS_KEY,
+            previous_key.to_string(),
+        );
+    }
+    env.put(
+        app::env::ENV_INBOUND_WORKLOAD_IDENTITY_VERIFY,
+        "true".to_string(),
+    );
+    let gateway = proxy::new()
+        .identity(id_svc)
+        .inbound(srv)
+        .run_with_test_env(env)
+        .await;
+
+    let ctrl = controller::new();
+    let dst = format!("{AUTHORITY}:{}", gateway.inbound.port());
+    let dst_tx = ctrl.destination_tx(&dst);
+    dst_tx.send(controller::destination_add(gateway.inbound).identity(GATEWAY));
+    let _profile_tx = ctrl.profile_tx_default(gateway.inbound, AUTHORITY);
+    let policy = controller::policy()
+        // stop the admin server from entering an infinite retry loop
+        .with_inbound_default(policy::all_unauthenticated())
+        .outbound_default(gateway.inbound, &dst);
+    let relay = proxy::new()
+        .controller(ctrl.run().await)
+        .policy(policy.run().await)
+        .outbound_ip(gateway.inbound)
+        .run()
+        .await;
+
+    let client = client::http1(relay.outbound, AUTHORITY);
+    let rsp = client
+        .request(
+            client
+                .request_builder("/")
+                .header("l5d-workload-identity", assertion),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    let client_id = http_util::body_to_string(rsp.into_body()).await.unwrap();
+
+    drop(client);
+    relay.join_servers().await;
+    gateway.join_servers().await;
+    client_id
+}
@@ -0,0 +1,62 @@
+//! Measures the cost of a policy update that does not change a parent's
+//! routes, as observed by each routes watch that shares the parent.
+//!
+//! Each update is decoded into new allocations. Unless the policy client
+//! shares unchanged routes with the previous update, every watch must compare
+//! every route to determine that its routes are unchanged.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use linkerd_app_outbound::test_util::fixture::{FixtureProtocol, Parent, Route, WeightedBackend};
+
+const ROUTES: usize = 500;
+const WATCHES: usize = 10;
+
+fn policy_updates(c: &mut Criterion) {
+    let parent = Parent {
+        addr: "10.0.0.1:8080".parse().unwrap(),
+        name: "web.bench.svc.cluster.local:8080".parse().unwrap(),
+        protocol: FixtureProtocol::Http1,
+        routes: (0..ROUTES)
+            .map(|i| Route {
+                path_prefix: Some(format!("/r{i}")),
+                backends: vec![WeightedBackend {
+                    name: "web.bench.svc.cluster.local:8080".parse().unwrap(),
+                    weight: 1,
+                }],
+            })
+            .collect(),
+    };
+    let current = parent.client_policy();
+
+    let mut group = c.benchmark_group("policy_updates");
+
+    group.bench_function(format!("decoded {ROUTES} routes"), |b| {
+        b.iter_batched(
+            || parent.client_policy(),
+            |update| {
+                for _ in 0..WATCHES {
+                    black_box(update == current);
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function(format!("reused {ROUTES} routes"), |b| {
+        b.iter_batched(
+            || parent.client_policy(),
+            |mut update| {
+                update.reuse_unchanged(&current);
+                for _ in 0..WATCHES {
+                    black_box(update == current);
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, policy_updates);
+criterion_main!(benches);
@@ -0,0 +1,92 @@
+//! Measures the request throughput of a parent with many HTTP routes, so that
+//! the cost of matching and labeling each request is visible.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use http_body_util::{BodyExt, Empty};
+use hyper_util::rt::TokioIo;
+use linkerd_app_core::{io, svc, transport::OrigDstAddr};
+use linkerd_app_outbound::{
+    test_util::fixture::{Fixture, FixtureProtocol, Parent, Route, WeightedBackend},
+    Outbound,
+};
+use std::net::SocketAddr;
+use tower::ServiceExt;
+
+type Body = Empty<bytes::Bytes>;
+
+const ROUTES: usize = 200;
+const REQUESTS: u64 = 100;
+
+fn routes(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("runtime");
+
+    let parent = "10.0.0.1:8080".parse::<SocketAddr>().unwrap();
+    let backend = "web.bench.svc.cluster.local:8080".parse().unwrap();
+    let fixture = Fixture {
+        parents: vec![Parent {
+            addr: parent,
+            name: "web.bench.svc.cluster.local:8080".parse().unwrap(),
+            protocol: FixtureProtocol::Http1,
+            routes: (0..ROUTES)
+                .map(|i| Route {
+                    path_prefix: Some(format!("/r{i}")),
+                    backends: vec![WeightedBackend {
+                        name: "web.bench.svc.cluster.local:8080".parse().unwrap(),
+                        weight: 1,
+                    }],
+                })
+                .collect(),
+        }],
+        endpoints: [(backend, vec!["10.1.0.1:8080".parse().unwrap()])]
+            .into_iter()
+            .collect(),
+    };
+    let (outbound, _drain) = Outbound::for_test();
+    let stack = rt.block_on(async { fixture.mk_sidecar::<io::DuplexStream>(&outbound) });
+
+    let mut group = c.benchmark_group("routes");
+    group.throughput(Throughput::Elements(REQUESTS));
+
+    // Requests match the last route, which is compared against every other.
+    let path = format!("/r{}/index.html", ROUTES - 1);
+    group.bench_function(format!("http1 {ROUTES} routes"), |b| {
+        b.to_async(&rt).iter(|| async {
+            let io = connect(&stack, parent);
+            let (mut client, conn) = hyper::client::conn::http1::handshake(io)
+                .await
+                .expect("handshake");
+            tokio::spawn(conn);
+            for _ in 0..REQUESTS {
+                client.ready().await.expect("ready");
+                let rsp = client.send_request(request(&path)).await.expect("response");
+                assert_eq!(rsp.status(), http::StatusCode::OK);
+                rsp.into_body().collect().await.expect("body");
+            }
+        })
+    });
+
+    group.finish();
+}
+
+/// Serves a new client connection to the parent with the sidecar stack.
+fn connect(
+    stack: &svc::ArcNewTcp<OrigDstAddr, io::DuplexStream>,
+    parent: SocketAddr,
+) -> TokioIo<io::DuplexStream> {
+    let (client, server) = io::duplex(64 * 1024);
+    let svc = svc::NewService::new_service(stack, OrigDstAddr(parent));
+    tokio::spawn(svc.oneshot(server));
+    TokioIo::new(client)
+}
+
+fn request(path: &str) -> http::Request<Body> {
+    http::Request::get(format!("http://web.bench.svc.cluster.local:8080{path}"))
+        .body(Body::new())
+        .unwrap()
+}
+
+criterion_group!(benches, routes);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+//! Compares reading the logical address and default authority of a routes
+//! watch from a [`RoutesAddrs`] cache with computing them from the watch on
+//! each read.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use linkerd_app_core::profiles;
+use linkerd_app_outbound::http::{profile, LogicalAddr, Routes, RoutesAddrs};
+use tokio::sync::watch;
+
+fn routes_addrs(c: &mut Criterion) {
+    let name = "web.bench.svc.cluster.local:8080".parse().unwrap();
+    let (_tx, routes) = watch::channel(Routes::Profile(profile::Routes {
+        addr: profiles::LogicalAddr(name),
+        routes: Default::default(),
+        targets: Default::default(),
+    }));
+
+    let mut group = c.benchmark_group("routes_addrs");
+
+    let addrs = RoutesAddrs::new(routes.clone());
+    group.bench_function("cached", |b| {
+        b.iter(|| black_box((addrs.logical_addr(), addrs.default_authority())))
+    });
+
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            let logical = match *routes.borrow() {
+                Routes::Profile(ref p) => LogicalAddr(p.addr.0.clone().into()),
+                _ => unreachable!(),
+            };
+            let authority = match *routes.borrow() {
+                Routes::Profile(ref p) => Some((*p.addr).as_http_authority()),
+                _ => unreachable!(),
+            };
+            black_box((logical, authority))
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, routes_addrs);
+criterion_main!(benches);
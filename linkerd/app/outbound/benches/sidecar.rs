@@ -0,0 +1,94 @@
+//! Measures the throughput of the sidecar stack, with discovery and endpoints
+//! served in-memory from `fixtures/sidecar.json`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use http_body_util::{BodyExt, Empty};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use linkerd_app_core::{io, svc, transport::OrigDstAddr};
+use linkerd_app_outbound::{test_util::fixture::Fixture, Outbound};
+use std::net::SocketAddr;
+use tower::ServiceExt;
+
+type Body = Empty<bytes::Bytes>;
+
+const PARENT: &str = "10.0.0.1:8080";
+const REQUESTS: u64 = 100;
+
+fn sidecar(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("runtime");
+
+    let fixture = Fixture::load(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/benches/fixtures/sidecar.json"
+    ))
+    .expect("fixture must load");
+    let (outbound, _drain) = Outbound::for_test();
+    let stack = rt.block_on(async { fixture.mk_sidecar::<io::DuplexStream>(&outbound) });
+    let parent = PARENT.parse::<SocketAddr>().unwrap();
+
+    let mut group = c.benchmark_group("sidecar");
+    group.throughput(Throughput::Elements(REQUESTS));
+
+    for path in ["/", "/api"] {
+        group.bench_function(format!("http1 {path}"), |b| {
+            b.to_async(&rt).iter(|| async {
+                let io = connect(&stack, parent);
+                let (mut client, conn) = hyper::client::conn::http1::handshake(io)
+                    .await
+                    .expect("handshake");
+                tokio::spawn(conn);
+                for _ in 0..REQUESTS {
+                    client.ready().await.expect("ready");
+                    let rsp = client.send_request(request(path)).await.expect("response");
+                    drain(rsp).await;
+                }
+            })
+        });
+
+        group.bench_function(format!("h2 {path}"), |b| {
+            b.to_async(&rt).iter(|| async {
+                let io = connect(&stack, parent);
+                let (mut client, conn) =
+                    hyper::client::conn::http2::handshake(TokioExecutor::new(), io)
+                        .await
+                        .expect("handshake");
+                tokio::spawn(conn);
+                for _ in 0..REQUESTS {
+                    client.ready().await.expect("ready");
+                    let rsp = client.send_request(request(path)).await.expect("response");
+                    drain(rsp).await;
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Serves a new client connection to the parent with the sidecar stack.
+fn connect(
+    stack: &svc::ArcNewTcp<OrigDstAddr, io::DuplexStream>,
+    parent: SocketAddr,
+) -> TokioIo<io::DuplexStream> {
+    let (client, server) = io::duplex(64 * 1024);
+    let svc = svc::NewService::new_service(stack, OrigDstAddr(parent));
+    tokio::spawn(svc.oneshot(server));
+    TokioIo::new(client)
+}
+
+fn request(path: &str) -> http::Request<Body> {
+    http::Request::get(format!("http://web.bench.svc.cluster.local:8080{path}"))
+        .body(Body::new())
+        .unwrap()
+}
+
+async fn drain(rsp: http::Response<hyper::body::Incoming>) {
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    rsp.into_body().collect().await.expect("body");
+}
+
+criterion_group!(benches, sidecar);
+criterion_main!(benches);
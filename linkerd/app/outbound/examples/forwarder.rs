@@ -0,0 +1,108 @@
+//! Assembles an outbound forwarder from in-memory discovery and an in-memory
+//! echo server, and sends a message through it.
+//!
+//! This also ensures that the stack may be assembled with the crate's public
+//! API. Run it with:
+//!
+//! ```sh
+//! cargo run -p linkerd-app-outbound --features test-util --example forwarder
+//! ```
+
+use futures::{future, stream};
+use linkerd_app_core::{
+    io::{self, AsyncReadExt, AsyncWriteExt},
+    profiles,
+    proxy::{
+        api_resolve::{ConcreteAddr, Metadata},
+        core::Update,
+    },
+    svc::{self, NewService, ServiceExt},
+    transport::addrs::*,
+    Addr, Error,
+};
+use linkerd_app_outbound::{policy, synthesize_forward_policy, tcp, Outbound};
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::sync::watch;
+
+const ECHO: &str = "192.0.2.10:7000";
+const MESSAGE: &[u8] = b"hello\r\n";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let (outbound, _drain) = Outbound::for_test();
+    let stack = outbound
+        .sidecar_builder()
+        .with_profiles(svc::mk(|_: profiles::LookupAddr| {
+            future::ok::<_, Error>(None)
+        }))
+        .with_policies(svc::mk(forward_policy))
+        .with_resolve(svc::mk(|_: ConcreteAddr| {
+            // Forwarded connections are not balanced, so nothing is resolved.
+            future::ok::<_, Error>(stream::pending::<Result<Update<Metadata>, Error>>())
+        }))
+        .with_connect(EchoConnect)
+        .build::<OrigDstAddr, io::DuplexStream>();
+
+    let (mut client, server) = io::duplex(1024);
+    let svc = stack.new_service(OrigDstAddr(ECHO.parse().unwrap()));
+    tokio::spawn(svc.oneshot(server));
+
+    client
+        .write_all(MESSAGE)
+        .await
+        .expect("message must be sent");
+    let mut echo = vec![0; MESSAGE.len()];
+    client
+        .read_exact(&mut echo)
+        .await
+        .expect("message must be echoed");
+    assert_eq!(echo, MESSAGE);
+    println!("{}", String::from_utf8_lossy(&echo).trim_end());
+}
+
+/// Forwards connections to each address to the address itself.
+fn forward_policy(addr: Addr) -> future::Ready<Result<policy::Receiver, Error>> {
+    let Addr::Socket(addr) = addr else {
+        return future::err(format!("{addr} is not known").into());
+    };
+    let policy = synthesize_forward_policy(
+        &policy::Meta::new_default("forward"),
+        Duration::from_secs(1),
+        policy::Queue {
+            capacity: 100,
+            failfast_timeout: Duration::from_secs(3),
+        },
+        addr,
+        Arc::new(Metadata::default()),
+    );
+    let (_, rx) = watch::channel(policy);
+    future::ok(rx)
+}
+
+/// Connects to an in-memory server that echoes what it reads.
+#[derive(Clone, Debug)]
+struct EchoConnect;
+
+impl svc::Service<tcp::Connect> for EchoConnect {
+    type Response = (io::DuplexStream, Local<ClientAddr>);
+    type Error = io::Error;
+    type Future = future::Ready<io::Result<Self::Response>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: tcp::Connect) -> Self::Future {
+        let (client, server) = io::duplex(1024);
+        tokio::spawn(async move {
+            let (mut rx, mut tx) = tokio::io::split(server);
+            tokio::io::copy(&mut rx, &mut tx).await
+        });
+        let local = Local(ClientAddr(([127, 0, 0, 1], 0).into()));
+        future::ok((client, local))
+    }
+}
@@ -2,7 +2,9 @@ use crate::{
     policy::{self, ClientPolicy},
     Outbound,
 };
-use linkerd_app_core::{errors, profiles, svc, transport::OrigDstAddr, Error};
+use linkerd_app_core::{
+    disco_cache::NewCachedDiscover, errors, profiles, svc, transport::OrigDstAddr, Addr, Error,
+};
 use once_cell::sync::Lazy;
 use std::{
     fmt::Debug,
@@ -15,15 +17,22 @@ use std::{
 use tokio::sync::watch;
 use tracing::Instrument;
 
+mod forward;
+mod parent;
 #[cfg(test)]
 mod tests;
 
+pub use self::forward::ForwardReason;
+pub(crate) use self::forward::{ForwardMetrics, NewRecordForward};
+pub(crate) use self::parent::ParentMetrics;
+
 /// Target with a discovery result.
 #[derive(Clone, Debug)]
 pub struct Discovery<T> {
     parent: T,
     profile: Option<profiles::Receiver>,
     policy: policy::Receiver,
+    forward: Option<ForwardReason>,
 }
 
 impl<N> Outbound<N> {
@@ -51,9 +60,40 @@ impl<N> Outbound<N> {
         NSvc: svc::Service<Req, Error = Error> + Send + 'static,
         NSvc::Future: Send,
     {
-        self.map_stack(|config, _, stk| {
+        let builds = &self.runtime.metrics.prom.stack_builds;
+        let cache = NewCachedDiscover::new((), discover, self.config.discovery_idle_timeout)
+            .with_builds_gauge(builds.in_progress("discover"));
+        self.push_discover_cache(cache)
+    }
+
+    /// Discovers routing configuration through the given discovery cache, so
+    /// that it may be shared with other stacks.
+    pub(crate) fn push_discover_cache<T, K, Req, NSvc, D>(
+        self,
+        cache: NewCachedDiscover<K, D, ()>,
+    ) -> Outbound<svc::ArcNewService<T, svc::BoxService<Req, NSvc::Response, Error>>>
+    where
+        // Discoverable target.
+        T: svc::Param<K>,
+        T: Clone + Send + Sync + 'static,
+        K: Clone + Debug + Eq + Hash + Send + Sync + 'static,
+        // Request type.
+        Req: Send + 'static,
+        // Discovery client.
+        D: svc::Service<K, Error = Error> + Clone + Send + Sync + 'static,
+        D::Future: Send + Unpin + 'static,
+        D::Error: Send + Sync + 'static,
+        D::Response: Clone + Send + Sync + 'static,
+        Discovery<T>: From<(D::Response, T)>,
+        // Inner stack.
+        N: svc::NewService<Discovery<T>, Service = NSvc>,
+        N: Clone + Send + Sync + 'static,
+        NSvc: svc::Service<Req, Error = Error> + Send + 'static,
+        NSvc::Future: Send,
+    {
+        self.map_stack(|_, _, stk| {
             stk.lift_new_with_target()
-                .push_new_cached_discover(discover, config.discovery_idle_timeout)
+                .push(svc::layer::mk(move |inner| cache.with_inner(inner)))
                 .check_new_service::<T, _>()
                 .arc_new_box()
         })
@@ -66,7 +106,11 @@ impl<N> Outbound<N> {
     ) -> impl svc::Service<
         OrigDstAddr,
         Error = Error,
-        Response = (Option<profiles::Receiver>, policy::Receiver),
+        Response = (
+            Option<profiles::Receiver>,
+            policy::Receiver,
+            Option<ForwardReason>,
+        ),
         Future = impl Send,
     > + Clone
            + Send
@@ -80,46 +124,159 @@ impl<N> Outbound<N> {
                 failfast_timeout: queue.failfast_timeout,
             }
         };
+        let discover = self.config.listener.discover;
+        let snapshot = self.runtime.discovery_snapshot.clone();
+        let port_mappings = self.runtime.port_mappings.clone();
+        let allow_discovery = self.config.allow_discovery.clone();
+        let unmap_ipv4 = self.config.discovery_unmap_ipv4;
+        let forward = self.runtime.metrics.prom.forward.clone();
+        let parents = self.runtime.metrics.prom.parents.clone();
         svc::mk(move |OrigDstAddr(orig_dst)| {
-            tracing::debug!(addr = %orig_dst, "Discover");
-
-            let profile = profiles
-                .clone()
-                .get_profile(profiles::LookupAddr(orig_dst.into()))
-                .instrument(tracing::debug_span!("profiles").or_current());
-            let policy = policies
-                .get_policy(orig_dst.into())
-                .instrument(tracing::debug_span!("policy").or_current());
+            let snapshot = snapshot.clone();
+            let forward = forward.clone();
+            let parents = parents.clone();
+            let parent_policies = policies.clone();
+            // Destinations are discovered by their normalized address, but
+            // connections are forwarded to the original destination as it was
+            // observed.
+            let dst = discovery_addr(orig_dst, unmap_ipv4);
+            let lookups = discover.then(|| {
+                // Mapped destinations are discovered by their logical address,
+                // but fall back to forwarding to the original destination.
+                let mapped = port_mappings.get(dst);
+                let is_mapped = mapped.is_some();
+                let addr = match mapped {
+                    Some(addr) => {
+                        tracing::debug!(%orig_dst, %addr, "Discover mapped destination");
+                        addr
+                    }
+                    None => {
+                        tracing::debug!(addr = %dst, %orig_dst, "Discover");
+                        Addr::Socket(dst)
+                    }
+                };
+                let allowed = allow_discovery.matches(&addr);
+                // Provisional parents were persisted without a profile, so
+                // they are served without waiting for one.
+                let provisional = snapshot
+                    .as_ref()
+                    .is_some_and(|s| s.is_provisional(orig_dst));
+                let profile = (!provisional).then(|| {
+                    profiles
+                        .clone()
+                        .get_profile(profiles::LookupAddr(addr.clone()))
+                        .instrument(tracing::debug_span!("profiles").or_current())
+                });
+                let profile = async move {
+                    match profile {
+                        Some(profile) => profile.await,
+                        None => Ok(None),
+                    }
+                };
+                let policy = policies
+                    .get_policy(addr)
+                    .instrument(tracing::debug_span!("policy").or_current());
+                (profile, policy, is_mapped, allowed)
+            });
 
             Box::pin(async move {
+                let Some((profile, policy, mapped, allowed)) = lookups else {
+                    tracing::debug!(addr = %orig_dst, "Discovery disabled");
+                    let reason = if dst.ip().is_loopback() {
+                        ForwardReason::Loopback
+                    } else {
+                        ForwardReason::DiscoveryDisabled
+                    };
+                    let policy = spawn_synthesized_origdst_policy(orig_dst, queue, detect_timeout);
+                    return Ok((None, policy, forward.decide(reason)));
+                };
                 let (profile, policy) = tokio::join!(profile, policy);
                 tracing::debug!("Discovered");
 
+                let mut errored = false;
                 let profile = profile.unwrap_or_else(|error| {
                     tracing::warn!(%error, "Error resolving ServiceProfile");
+                    errored = true;
                     None
                 });
 
+                // Pods that are dialed directly, e.g. through a headless
+                // service, are attributed to the service that exposes the
+                // dialed port, if the endpoint's metadata names one.
+                let parent = profile
+                    .as_ref()
+                    .filter(|_| !mapped)
+                    .and_then(|p| parents.select(p, dst.port()));
+                if let Some((parent, addr, metadata)) = parent {
+                    tracing::debug!(%parent, "Discover endpoint's parent");
+                    let port = parent.port();
+                    match parent_policies
+                        .get_policy(Addr::Name(parent))
+                        .instrument(tracing::debug_span!("parent").or_current())
+                        .await
+                    {
+                        Ok(policy)
+                            if !matches!(*policy.borrow().parent, policy::Meta::Default { .. }) =>
+                        {
+                            let policy =
+                                parent::spawn_endpoint_policy(policy, port, addr, metadata);
+                            return Ok((profile, policy, None));
+                        }
+                        Ok(_) => tracing::debug!("Parent not found"),
+                        Err(error) => tracing::debug!(%error, "Failed to discover parent"),
+                    }
+                }
+
                 // If there was a policy resolution, return it with the profile so
                 // the stack can determine how to switch on them.
                 match policy {
-                    Ok(policy) => return Ok((profile, policy)),
+                    // The control plane serves a default policy for addresses
+                    // that are not known to it, which would forward to the
+                    // mapped address rather than the original destination.
+                    Ok(policy)
+                        if mapped
+                            && matches!(*policy.borrow().parent, policy::Meta::Default { .. }) =>
+                    {
+                        tracing::debug!("Mapped destination not found")
+                    }
+                    Ok(policy) => {
+                        if let (Some(snapshot), Some(profile)) =
+                            (snapshot.as_ref(), profile.as_ref())
+                        {
+                            if crate::http::profile::should_override_policy(&profile.clone().into())
+                                .is_some()
+                            {
+                                snapshot.set_profiled(orig_dst);
+                            }
+                        }
+                        return Ok((profile, policy, None));
+                    }
                     // XXX(ver) The policy controller may (for the time being) reject
                     // our lookups, since it doesn't yet serve endpoint metadata for
                     // forwarding.
-                    Err(error) if errors::has_grpc_status(&error, tonic::Code::NotFound) => tracing::debug!("Policy not found"),
+                    Err(error) if errors::has_grpc_status(&error, tonic::Code::NotFound) => {
+                        tracing::debug!("Policy not found")
+                    }
                     // Earlier versions of the Linkerd control plane (e.g.
                     // 2.12.x) will return `Unimplemented` for requests to the
                     // OutboundPolicy API. Log a warning and synthesize a policy
                     // for backwards compatibility.
-                    Err(error) if errors::has_grpc_status(&error, tonic::Code::Unimplemented) =>
-                        tracing::warn!("Policy controller returned `Unimplemented`, the control plane may be out of date."),
+                    Err(error) if errors::has_grpc_status(&error, tonic::Code::Unimplemented) => {
+                        tracing::warn!("Policy controller returned `Unimplemented`, the control plane may be out of date.");
+                        errored = true;
+                    }
                     Err(error) => return Err(error),
                 }
 
                 // If there was a profile resolution, try to use it to synthesize a
                 // enpdoint policy.
                 if let Some(profile) = profile {
+                    // Profiles with logical addresses provide routes, so only
+                    // endpoint profiles are forwarded.
+                    let reason = profile
+                        .logical_addr()
+                        .is_none()
+                        .then(|| ForwardReason::classify(dst, allowed, errored, true));
                     let policy = spawn_synthesized_profile_policy(
                         profile.clone().into(),
                         move |profile: &profiles::Profile| {
@@ -143,17 +300,36 @@ impl<N> Outbound<N> {
                             )
                         },
                     );
-                    return Ok((Some(profile), policy));
+                    return Ok((
+                        Some(profile),
+                        policy,
+                        reason.and_then(|r| forward.decide(r)),
+                    ));
                 }
 
                 // Otherwise, route the request to the original destination address.
+                let reason = ForwardReason::classify(dst, allowed, errored, false);
                 let policy = spawn_synthesized_origdst_policy(orig_dst, queue, detect_timeout);
-                Ok((None, policy))
+                Ok((None, policy, forward.decide(reason)))
             })
         })
     }
 }
 
+/// Returns the address with which an original destination is discovered.
+///
+/// When `unmap_ipv4` is set, IPv4-mapped IPv6 addresses are discovered by their
+/// IPv4 form, since the control plane indexes endpoints by it.
+fn discovery_addr(orig_dst: SocketAddr, unmap_ipv4: bool) -> SocketAddr {
+    match orig_dst {
+        SocketAddr::V6(addr) if unmap_ipv4 => match addr.ip().to_ipv4_mapped() {
+            Some(ip) => SocketAddr::new(ip.into(), addr.port()),
+            None => orig_dst,
+        },
+        _ => orig_dst,
+    }
+}
+
 pub fn spawn_synthesized_profile_policy(
     mut profile: watch::Receiver<profiles::Profile>,
     synthesize: impl Fn(&profiles::Profile) -> policy::ClientPolicy + Send + 'static,
@@ -293,19 +469,28 @@ fn policy_for_backend(
     }
 }
 
-fn spawn_synthesized_origdst_policy(
+/// Synthesizes a policy that forwards connections to the original destination
+/// address.
+pub(crate) fn synthesize_origdst_policy(
     orig_dst: SocketAddr,
     queue: policy::Queue,
     detect_timeout: Duration,
-) -> watch::Receiver<policy::ClientPolicy> {
+) -> ClientPolicy {
     static META: Lazy<Arc<policy::Meta>> = Lazy::new(|| {
         Arc::new(policy::Meta::Default {
             name: "fallback".into(),
         })
     });
 
-    let policy =
-        synthesize_forward_policy(&META, detect_timeout, queue, orig_dst, Default::default());
+    synthesize_forward_policy(&META, detect_timeout, queue, orig_dst, Default::default())
+}
+
+fn spawn_synthesized_origdst_policy(
+    orig_dst: SocketAddr,
+    queue: policy::Queue,
+    detect_timeout: Duration,
+) -> watch::Receiver<policy::ClientPolicy> {
+    let policy = synthesize_origdst_policy(orig_dst, queue, detect_timeout);
     tracing::debug!(?policy, "Synthesizing policy");
     let (tx, rx) = watch::channel(policy);
     tokio::spawn(async move {
@@ -325,6 +510,36 @@ impl<T> From<((Option<profiles::Receiver>, policy::Receiver), T)> for Discovery<
             parent,
             profile,
             policy,
+            forward: None,
+        }
+    }
+}
+
+impl<T>
+    From<(
+        (
+            Option<profiles::Receiver>,
+            policy::Receiver,
+            Option<ForwardReason>,
+        ),
+        T,
+    )> for Discovery<T>
+{
+    fn from(
+        ((profile, policy, forward), parent): (
+            (
+                Option<profiles::Receiver>,
+                policy::Receiver,
+                Option<ForwardReason>,
+            ),
+            T,
+        ),
+    ) -> Self {
+        Self {
+            parent,
+            profile,
+            policy,
+            forward,
         }
     }
 }
@@ -362,6 +577,12 @@ impl<T> svc::Param<policy::Receiver> for Discovery<T> {
     }
 }
 
+impl<T> svc::Param<Option<ForwardReason>> for Discovery<T> {
+    fn param(&self) -> Option<ForwardReason> {
+        self.forward
+    }
+}
+
 impl<T> Deref for Discovery<T> {
     type Target = T;
 
@@ -0,0 +1,180 @@
+//! Records why targets are forwarded without discovered routes.
+//!
+//! When no policy is discovered for a target (and its profile does not
+//! provide routes), connections are forwarded directly to an endpoint. The
+//! reason for this decision is recorded on each connection's span and counted
+//! so that operators can distinguish misconfiguration from expected behavior.
+
+use linkerd_app_core::{
+    metrics::prom::{self, encoding::*},
+    svc,
+};
+use std::{fmt, net::SocketAddr};
+
+/// Describes why a target takes the forward path.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ForwardReason {
+    /// The destination is not in the networks for which discovery is allowed.
+    NotInNetworks,
+    /// Neither a profile nor a policy was found for the destination.
+    ProfileNotFound,
+    /// A profile without a logical address was found, but no policy was.
+    PolicyNotFound,
+    /// Discovery failed or is unsupported by the control plane.
+    DiscoveryError,
+    /// The destination is on the loopback interface.
+    Loopback,
+    /// Discovery is disabled on the listener that accepted the connection.
+    DiscoveryDisabled,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ForwardMetrics {
+    decisions: prom::Family<ForwardLabels, prom::Counter>,
+    connections: prom::Family<ForwardLabels, prom::Counter>,
+}
+
+/// Records the forward reason of each connection's target.
+#[derive(Clone, Debug)]
+pub(crate) struct NewRecordForward<N> {
+    metrics: ForwardMetrics,
+    inner: N,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct ForwardLabels {
+    reason: ForwardReason,
+}
+
+// === impl ForwardReason ===
+
+impl ForwardReason {
+    /// Determines why a target for which discovery was attempted is
+    /// forwarded.
+    ///
+    /// `allowed` indicates whether the destination may be discovered,
+    /// `errored` whether a lookup failed, and `profiled` whether a profile
+    /// (without a logical address) was found.
+    pub(crate) fn classify(
+        orig_dst: SocketAddr,
+        allowed: bool,
+        errored: bool,
+        profiled: bool,
+    ) -> Self {
+        if orig_dst.ip().is_loopback() {
+            Self::Loopback
+        } else if !allowed {
+            Self::NotInNetworks
+        } else if errored {
+            Self::DiscoveryError
+        } else if profiled {
+            Self::PolicyNotFound
+        } else {
+            Self::ProfileNotFound
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotInNetworks => "not-in-networks",
+            Self::ProfileNotFound => "profile-not-found",
+            Self::PolicyNotFound => "policy-not-found",
+            Self::DiscoveryError => "discovery-error",
+            Self::Loopback => "loopback",
+            Self::DiscoveryDisabled => "discovery-disabled",
+        }
+    }
+}
+
+impl fmt::Display for ForwardReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl EncodeLabelValue for ForwardReason {
+    fn encode(&self, enc: &mut LabelValueEncoder<'_>) -> fmt::Result {
+        use fmt::Write;
+        enc.write_str(self.as_str())
+    }
+}
+
+// === impl ForwardMetrics ===
+
+impl ForwardMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let decisions = prom::Family::default();
+        registry.register(
+            "forward_decisions",
+            "The number of discovery results that forward connections without discovered routes, by reason",
+            decisions.clone(),
+        );
+        let connections = prom::Family::default();
+        registry.register(
+            "tcp_forward_connections",
+            "The number of connections forwarded without discovered routes, by reason",
+            connections.clone(),
+        );
+        Self {
+            decisions,
+            connections,
+        }
+    }
+
+    /// Records that a discovery result forwards connections for `reason`.
+    pub(crate) fn decide(&self, reason: ForwardReason) -> Option<ForwardReason> {
+        tracing::debug!(%reason, "Forwarding without discovered routes");
+        self.decisions
+            .get_or_create(&ForwardLabels { reason })
+            .inc();
+        Some(reason)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn decisions(&self, reason: ForwardReason) -> u64 {
+        self.decisions
+            .get_or_create(&ForwardLabels { reason })
+            .get()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn connections(&self, reason: ForwardReason) -> u64 {
+        self.connections
+            .get_or_create(&ForwardLabels { reason })
+            .get()
+    }
+}
+
+// === impl NewRecordForward ===
+
+impl<N> NewRecordForward<N> {
+    pub(crate) fn layer(
+        metrics: ForwardMetrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRecordForward<N>
+where
+    T: svc::Param<Option<ForwardReason>>,
+    N: svc::NewService<T>,
+{
+    type Service = N::Service;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        // Targets are built for each connection, so the connection's span is
+        // current.
+        if let Some(reason) = target.param() {
+            tracing::Span::current().record("forward", tracing::field::display(reason));
+            self.metrics
+                .connections
+                .get_or_create(&ForwardLabels { reason })
+                .inc();
+        }
+        self.inner.new_service(target)
+    }
+}
@@ -0,0 +1,117 @@
+//! Attributes connections to pods to the services that expose them.
+//!
+//! Clients of headless services dial pods by their own addresses, often on a
+//! container port that differs from the service's port, so discovery by the
+//! original destination address does not find the service's policy. When the
+//! endpoint's metadata names the services that expose the dialed port, the
+//! service's policy is discovered instead. Its backends that target the
+//! service's port are replaced so that connections are still forwarded to the
+//! dialed pod; backends that declare other ports are retained, so that their
+//! traffic is not sent to the dialed port.
+
+use crate::policy;
+use linkerd_app_core::{metrics::prom, profiles, NameAddr};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::sync::watch;
+use tracing::Instrument;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ParentMetrics {
+    ambiguous: prom::Counter,
+}
+
+// === impl ParentMetrics ===
+
+impl ParentMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let ambiguous = prom::Counter::default();
+        registry.register(
+            "endpoint_parents_ambiguous",
+            "The number of endpoint destinations whose port is exposed by more than one service",
+            ambiguous.clone(),
+        );
+        Self { ambiguous }
+    }
+
+    /// Returns the service that exposes the endpoint's `port`, if the
+    /// profile's endpoint metadata names one.
+    ///
+    /// When multiple services expose the port, the first by name is used.
+    pub(crate) fn select(
+        &self,
+        profile: &profiles::Receiver,
+        port: u16,
+    ) -> Option<(NameAddr, SocketAddr, policy::EndpointMetadata)> {
+        let (addr, metadata) = profile.endpoint()?;
+        let mut parents = metadata
+            .parent_ports()
+            .iter()
+            .filter(|p| p.target_port == port)
+            .map(|p| &p.parent)
+            .collect::<Vec<_>>();
+        parents.sort_by_key(|p| p.to_string());
+        parents.dedup();
+        let parent = (*parents.first()?).clone();
+        if parents.len() > 1 {
+            tracing::debug!(?parents, %parent, "Endpoint port is exposed by multiple services");
+            self.ambiguous.inc();
+        }
+        Some((parent, addr, metadata))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn ambiguous(&self) -> u64 {
+        self.ambiguous.get()
+    }
+}
+
+/// Forwards connections to `addr` with the routes of the parent's policy.
+///
+/// Only backends that target the parent's `port` are forwarded to `addr`.
+pub(crate) fn spawn_endpoint_policy(
+    mut parent: policy::Receiver,
+    port: u16,
+    addr: SocketAddr,
+    metadata: policy::EndpointMetadata,
+) -> policy::Receiver {
+    let metadata = Arc::new(metadata);
+    let forward = move |policy: &policy::ClientPolicy| {
+        let mut policy = policy.clone();
+        policy.map_backends(|backend| match backend.port() {
+            Some(p) if p != port => backend.clone(),
+            _ => policy::Backend {
+                dispatcher: policy::BackendDispatcher::Forward(addr, metadata.clone()),
+                ..backend.clone()
+            },
+        });
+        policy
+    };
+
+    let (tx, rx) = watch::channel(forward(&parent.borrow_and_update()));
+    tokio::spawn(
+        async move {
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = tx.closed() => {
+                        tracing::debug!("Policy watch closed; terminating");
+                        return;
+                    }
+                    res = parent.changed() => {
+                        if res.is_err() {
+                            tracing::debug!("Parent policy watch closed; terminating");
+                            return;
+                        }
+                    }
+                };
+                let policy = forward(&parent.borrow_and_update());
+                if tx.send(policy).is_err() {
+                    tracing::debug!("Policy watch closed, terminating");
+                    return;
+                }
+            }
+        }
+        .in_current_span(),
+    );
+    rx
+}
@@ -3,10 +3,14 @@ use crate::test_util::*;
 use linkerd_app_core::{
     io,
     svc::{NewService, Service, ServiceExt},
+    IpMatch, IpNet, NameAddr,
 };
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 use tokio::time;
 
@@ -166,6 +170,446 @@ async fn caches_profiles_until_idle() {
     task2.abort();
 }
 
+/// Tests that concurrent first connections to a destination share a single
+/// discovery lookup.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn coalesces_concurrent_discovery() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    const CONNS: usize = 100;
+
+    let addr = SocketAddr::new([192, 0, 2, 22].into(), 5551);
+
+    // Mock an inner stack with a service that never returns.
+    let stack = |_: _| svc::mk(move |_: io::DuplexStream| future::pending::<Result<(), Error>>());
+
+    let profile_lookups = Arc::new(AtomicUsize::new(0));
+    let discover = {
+        let discover = support::resolver::OutboundDiscover::default().with_default(addr);
+        let lookups = profile_lookups.clone();
+        svc::mk(move |OrigDstAddr(addr)| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            discover.clone().oneshot(addr)
+        })
+    };
+
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(stack)
+        .push_discover(discover)
+        .into_inner();
+
+    // Start all connections at once, each of which builds a service for the
+    // same destination.
+    let barrier = Arc::new(tokio::sync::Barrier::new(CONNS));
+    let ready = Arc::new(AtomicUsize::new(0));
+    let tasks = (0..CONNS)
+        .map(|_| {
+            let stack = stack.clone();
+            let barrier = barrier.clone();
+            let ready = ready.clone();
+            tokio::spawn(async move {
+                barrier.wait().await;
+                let mut svc = stack.new_service(OrigDstAddr(addr));
+                let (server_io, _client_io) = io::duplex(1);
+                let svc = svc.ready().await?;
+                ready.fetch_add(1, Ordering::SeqCst);
+                svc.call(server_io).await
+            })
+        })
+        .collect::<Vec<_>>();
+
+    time::timeout(time::Duration::from_secs(10), async {
+        while ready.load(Ordering::SeqCst) < CONNS {
+            time::sleep(time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("all connections must become ready");
+    assert_eq!(
+        profile_lookups.load(Ordering::SeqCst),
+        1,
+        "exactly one profile lookup"
+    );
+
+    for task in tasks {
+        task.abort();
+    }
+}
+
+/// Tests that targets forwarded to their original destinations record why no
+/// routes were discovered.
+#[tokio::test(flavor = "current_thread")]
+async fn forward_reasons() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let addr = SocketAddr::new([192, 0, 2, 22].into(), 5552);
+    let loopback = SocketAddr::new([127, 0, 0, 1].into(), 5552);
+
+    let no_profile = svc::mk(|_: profiles::LookupAddr| future::ok::<_, Error>(None));
+    let profile_error = svc::mk(|_: profiles::LookupAddr| {
+        future::err::<Option<profiles::Receiver>, Error>(
+            io::Error::from(io::ErrorKind::ConnectionRefused).into(),
+        )
+    });
+    let endpoint_profile = {
+        let (_, rx) = watch::channel(profiles::Profile::default());
+        svc::mk(move |_: profiles::LookupAddr| {
+            future::ok::<_, Error>(Some(profiles::Receiver::from(rx.clone())))
+        })
+    };
+    let not_found = policy_error(tonic::Code::NotFound);
+    let unimplemented = policy_error(tonic::Code::Unimplemented);
+
+    let not_in_networks = {
+        let mut cfg = default_config();
+        cfg.allow_discovery = IpMatch::new(Some(IpNet::from_str("10.0.0.0/8").unwrap())).into();
+        cfg
+    };
+    let disabled = {
+        let mut cfg = default_config();
+        cfg.listener.discover = false;
+        cfg
+    };
+
+    use ForwardReason::*;
+    for (cfg, addr, reason) in [
+        (default_config(), addr, ProfileNotFound),
+        (not_in_networks, addr, NotInNetworks),
+        (default_config(), loopback, Loopback),
+        (disabled.clone(), addr, DiscoveryDisabled),
+        (disabled, loopback, Loopback),
+    ] {
+        assert_eq!(
+            resolve_forward(cfg, no_profile.clone(), not_found.clone(), addr).await,
+            reason,
+        );
+    }
+    assert_eq!(
+        resolve_forward(default_config(), endpoint_profile, not_found.clone(), addr).await,
+        PolicyNotFound,
+    );
+    assert_eq!(
+        resolve_forward(default_config(), profile_error, not_found, addr).await,
+        DiscoveryError,
+    );
+    assert_eq!(
+        resolve_forward(default_config(), no_profile, unimplemented, addr).await,
+        DiscoveryError,
+    );
+}
+
+/// Tests that targets with discovered policies are not forwarded.
+#[tokio::test(flavor = "current_thread")]
+async fn discovered_policies_are_not_forwarded() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let addr = SocketAddr::new([192, 0, 2, 22].into(), 5553);
+    let (_, policy) = watch::channel(synthesize_origdst_policy(
+        addr,
+        policy::Queue {
+            capacity: 10,
+            failfast_timeout: time::Duration::from_secs(1),
+        },
+        time::Duration::from_secs(1),
+    ));
+    let policies = svc::mk(move |_: Addr| future::ok::<_, Error>(policy.clone()));
+    let profiles = svc::mk(|_: profiles::LookupAddr| future::ok::<_, Error>(None));
+
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(default_config(), rt, &mut Default::default());
+    let (_, _, forward) = outbound
+        .resolver(profiles, policies)
+        .oneshot(OrigDstAddr(addr))
+        .await
+        .expect("discovery must succeed");
+    assert_eq!(forward, None);
+}
+
+/// Tests that IPv4-mapped original destinations are discovered by their IPv4
+/// form, unless unmapping is disabled, and forwarded to the address as it was
+/// observed.
+#[tokio::test(flavor = "current_thread")]
+async fn discovers_v4_mapped_destinations() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let mapped = SocketAddr::from_str("[::ffff:192.0.2.22]:5554").unwrap();
+    let unmapped = SocketAddr::from_str("192.0.2.22:5554").unwrap();
+
+    let (forward, discovered) = resolve_addrs(default_config(), None, mapped).await;
+    assert_eq!(discovered, Addr::Socket(unmapped));
+    assert_eq!(forward, mapped);
+
+    let mut cfg = default_config();
+    cfg.discovery_unmap_ipv4 = false;
+    let (forward, discovered) = resolve_addrs(cfg, None, mapped).await;
+    assert_eq!(discovered, Addr::Socket(mapped));
+    assert_eq!(forward, mapped);
+}
+
+/// Tests that IPv6 destinations are discovered and forwarded unchanged.
+#[tokio::test(flavor = "current_thread")]
+async fn discovers_v6_destinations() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let addr = SocketAddr::from_str("[2001:db8::22]:5555").unwrap();
+    let mut cfg = default_config();
+    cfg.allow_discovery = IpMatch::new(Some(IpNet::from_str("2001:db8::/32").unwrap())).into();
+    let (forward, discovered) = resolve_addrs(cfg, None, addr).await;
+    assert_eq!(discovered, Addr::Socket(addr));
+    assert_eq!(forward, addr);
+
+    // IPv6 destinations are not discovered through IPv4 networks.
+    assert_eq!(
+        resolve_forward(
+            default_config(),
+            svc::mk(|_: profiles::LookupAddr| future::ok::<_, Error>(None)),
+            policy_error(tonic::Code::NotFound),
+            addr,
+        )
+        .await,
+        ForwardReason::NotInNetworks,
+    );
+}
+
+/// Tests that connections are forwarded to the endpoint address that profiles
+/// specify, in its own family, regardless of the original destination's
+/// family.
+#[tokio::test(flavor = "current_thread")]
+async fn forwards_to_endpoint_family() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let v4 = SocketAddr::from_str("192.0.2.23:5556").unwrap();
+    let mapped = SocketAddr::from_str("[::ffff:192.0.2.23]:5556").unwrap();
+    let v6 = SocketAddr::from_str("[2001:db8::23]:5556").unwrap();
+
+    let mut cfg = default_config();
+    cfg.allow_discovery = IpMatch::new([
+        IpNet::from_str("0.0.0.0/0").unwrap(),
+        IpNet::from_str("::/0").unwrap(),
+    ])
+    .into();
+    for (orig_dst, endpoint) in [(v6, v4), (v4, v6), (mapped, v4), (mapped, v6), (v4, mapped)] {
+        let (forward, _) = resolve_addrs(cfg.clone(), Some(endpoint), orig_dst).await;
+        assert_eq!(forward, endpoint, "orig_dst={orig_dst}");
+    }
+}
+
+/// Tests that pods dialed on a port that a headless service remaps are
+/// discovered with the service's policy and still forwarded to the pod, unless
+/// a backend declares another port.
+#[tokio::test(flavor = "current_thread")]
+async fn discovers_endpoint_parents() {
+    use linkerd_app_core::proxy::api_resolve::ParentPort;
+
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let pod = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let metadata = policy::EndpointMetadata::default().with_parent_ports([
+        ParentPort {
+            target_port: 8080,
+            parent: NameAddr::from_str("web.ns.svc.cluster.local:80").unwrap(),
+        },
+        ParentPort {
+            target_port: 8080,
+            parent: NameAddr::from_str("api.ns.svc.cluster.local:8000").unwrap(),
+        },
+        ParentPort {
+            target_port: 9090,
+            parent: NameAddr::from_str("web.ns.svc.cluster.local:90").unwrap(),
+        },
+    ]);
+    let (_profile_tx, profile) = watch::channel(profiles::Profile {
+        endpoint: Some((pod, metadata)),
+        ..Default::default()
+    });
+    let profiles = svc::mk(move |_: profiles::LookupAddr| {
+        future::ok::<_, Error>(Some(profiles::Receiver::from(profile.clone())))
+    });
+    let queue = policy::Queue {
+        capacity: 10,
+        failfast_timeout: time::Duration::from_secs(1),
+    };
+    let load = policy::Load::PeakEwma(policy::PeakEwma {
+        decay: time::Duration::from_secs(10),
+        default_rtt: time::Duration::from_millis(30),
+    });
+    let other = "other.ns.svc.cluster.local:9090";
+    let policies = svc::mk(move |addr: Addr| {
+        let policy = match addr {
+            Addr::Name(ref name) => {
+                let parent = Arc::new(policy::Meta::Resource {
+                    group: "core".to_string(),
+                    kind: "Service".to_string(),
+                    name: name.name().to_string(),
+                    namespace: "ns".to_string(),
+                    section: None,
+                    port: std::num::NonZeroU16::new(name.port()),
+                });
+                let mut policy = synthesize_balance_policy(
+                    &parent,
+                    time::Duration::from_secs(1),
+                    queue,
+                    load,
+                    name,
+                );
+                // A backend that declares another port.
+                let backends = policy.backends.iter().cloned().chain(Some(policy::Backend {
+                    meta: policy::Meta::new_default("other"),
+                    queue,
+                    dispatcher: policy::BackendDispatcher::BalanceP2c(
+                        load,
+                        policy::EndpointDiscovery::DestinationGet {
+                            path: other.to_string(),
+                        },
+                    ),
+                }));
+                policy.backends = backends.collect();
+                policy
+            }
+            Addr::Socket(addr) => synthesize_forward_policy(
+                &policy::Meta::new_default("default"),
+                time::Duration::from_secs(1),
+                queue,
+                addr,
+                Default::default(),
+            ),
+        };
+        let (_, rx) = watch::channel(policy);
+        future::ok::<_, Error>(rx)
+    });
+
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(default_config(), rt, &mut Default::default());
+    let resolve = outbound.resolver(profiles, policies);
+
+    // Parents that expose the port are selected by name.
+    let (_, policy, forward) = resolve
+        .clone()
+        .oneshot(OrigDstAddr(pod))
+        .await
+        .expect("discovery must succeed");
+    assert_eq!(forward, None);
+    let policy = policy.borrow().clone();
+    assert_eq!(policy.parent.name(), "api.ns.svc.cluster.local");
+    assert_eq!(policy.parent.port().map(u16::from), Some(8000));
+    assert_eq!(policy.backends.len(), 2);
+    assert!(
+        matches!(
+            policy.backends[0].dispatcher,
+            policy::BackendDispatcher::Forward(addr, _) if addr == pod
+        ),
+        "connections to the parent's port must be forwarded to the pod"
+    );
+    assert!(
+        matches!(
+            policy.backends[1].dispatcher,
+            policy::BackendDispatcher::BalanceP2c(_, policy::EndpointDiscovery::DestinationGet { ref path })
+                if path == other
+        ),
+        "backends that declare another port must not be forwarded to the pod"
+    );
+    assert_eq!(outbound.runtime.metrics.prom.parents.ambiguous(), 1);
+
+    // Ports that are not exposed by a service are discovered by address.
+    let (_, policy, _) = resolve
+        .oneshot(OrigDstAddr(SocketAddr::new(pod.ip(), 7070)))
+        .await
+        .expect("discovery must succeed");
+    assert_eq!(policy.borrow().parent.name(), "default");
+    assert_eq!(outbound.runtime.metrics.prom.parents.ambiguous(), 1);
+}
+
+/// Tests that each connection's forward reason is counted.
+#[test]
+fn counts_forwarded_connections() {
+    #[derive(Clone, Debug)]
+    struct Target(Option<ForwardReason>);
+
+    impl svc::Param<Option<ForwardReason>> for Target {
+        fn param(&self) -> Option<ForwardReason> {
+            self.0
+        }
+    }
+
+    let metrics = ForwardMetrics::default();
+    let new_svc =
+        svc::layer::Layer::layer(&NewRecordForward::layer(metrics.clone()), |_: Target| ());
+    new_svc.new_service(Target(Some(ForwardReason::NotInNetworks)));
+    new_svc.new_service(Target(Some(ForwardReason::NotInNetworks)));
+    new_svc.new_service(Target(None));
+
+    assert_eq!(metrics.connections(ForwardReason::NotInNetworks), 2);
+    assert_eq!(metrics.connections(ForwardReason::Loopback), 0);
+}
+
+/// Resolves `addr` with the sidecar resolver, returning the reason it is
+/// forwarded.
+async fn resolve_forward(
+    cfg: crate::Config,
+    profiles: impl profiles::GetProfile<Error = Error>,
+    policies: impl policy::GetPolicy,
+    addr: SocketAddr,
+) -> ForwardReason {
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(cfg, rt, &mut Default::default());
+    let (profile, _, forward) = outbound
+        .resolver(profiles, policies)
+        .oneshot(OrigDstAddr(addr))
+        .await
+        .expect("discovery must succeed");
+    let reason = forward.expect("target must be forwarded");
+    assert!(profile.map_or(true, |p| p.logical_addr().is_none()));
+    assert_eq!(
+        outbound.runtime.metrics.prom.forward.decisions(reason),
+        1,
+        "the decision must be counted"
+    );
+    reason
+}
+
+/// Resolves `addr` with the sidecar resolver, returning the address to which
+/// it is forwarded and the address with which it was discovered. The resolved
+/// profile specifies `endpoint`, if any.
+async fn resolve_addrs(
+    cfg: crate::Config,
+    endpoint: Option<SocketAddr>,
+    addr: SocketAddr,
+) -> (SocketAddr, Addr) {
+    let (_, rx) = watch::channel(profiles::Profile {
+        endpoint: endpoint.map(|ep| (ep, Default::default())),
+        ..Default::default()
+    });
+    let profiles = svc::mk(move |_: profiles::LookupAddr| {
+        future::ok::<_, Error>(Some(profiles::Receiver::from(rx.clone())))
+    });
+    let (tx, mut discovered) = tokio::sync::mpsc::unbounded_channel();
+    let policies = svc::mk(move |addr: Addr| {
+        tx.send(addr).unwrap();
+        future::err::<policy::Receiver, Error>(tonic::Status::not_found("").into())
+    });
+
+    let (rt, _shutdown) = runtime();
+    let outbound = Outbound::new(cfg, rt, &mut Default::default());
+    let (_, policy, _) = outbound
+        .resolver(profiles, policies)
+        .oneshot(OrigDstAddr(addr))
+        .await
+        .expect("discovery must succeed");
+    let forward = match policy.borrow().backends[0].dispatcher {
+        policy::BackendDispatcher::Forward(addr, _) => addr,
+        ref dispatcher => panic!("unexpected dispatcher: {dispatcher:?}"),
+    };
+    let discovered = discovered.recv().await.expect("policy must be discovered");
+    (forward, discovered)
+}
+
+fn policy_error(code: tonic::Code) -> impl policy::GetPolicy {
+    svc::mk(move |_: Addr| {
+        future::err::<policy::Receiver, Error>(tonic::Status::new(code, "").into())
+    })
+}
+
 fn spawn_conn<S>(mut svc: S) -> tokio::task::JoinHandle<Result<(), Error>>
 where
     S: Service<io::DuplexStream, Response = (), Error = Error> + Send + 'static,
@@ -0,0 +1,216 @@
+//! Builds outbound stacks for binaries that embed the proxy as a library.
+//!
+//! [`SidecarBuilder`] assembles the same stack as [`Outbound::mk`] does for a
+//! sidecar proxy, except that discovery clients and the endpoint connector
+//! are supplied by the embedder rather than configured from the control
+//! plane. The bounds that each component must satisfy are named by the traits
+//! in this module, so that embedders may state them in their own signatures.
+//!
+//! See `examples/forwarder.rs` for a forwarder assembled from in-memory
+//! implementations.
+
+use crate::{policy, tcp, Outbound};
+use linkerd_app_core::{
+    io, profiles,
+    proxy::{
+        api_resolve::{ConcreteAddr, Metadata},
+        core::Resolve,
+    },
+    svc,
+    transport::{addrs::*, ConnectTcp},
+    Error,
+};
+use std::fmt::Debug;
+
+/// A target that describes an accepted outbound connection.
+pub trait SidecarTarget: svc::Param<OrigDstAddr> + Clone + Send + Sync + 'static {}
+
+/// A server-side socket on which an outbound connection is accepted.
+pub trait ServerIo:
+    io::AsyncRead
+    + io::AsyncWrite
+    + io::Peek
+    + io::PeerAddr
+    + io::Splice
+    + Debug
+    + Unpin
+    + Send
+    + Sync
+    + 'static
+{
+}
+
+/// Resolves the endpoints of a concrete service.
+pub trait EndpointResolve:
+    Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error, Resolution: Unpin>
+{
+}
+
+/// Establishes connections to endpoints.
+pub trait EndpointConnect:
+    svc::MakeConnection<
+        tcp::Connect,
+        Metadata = Local<ClientAddr>,
+        Error = io::Error,
+        Connection: io::Splice + Send + Unpin,
+        Future: Send + Unpin,
+    > + Clone
+    + Send
+    + Sync
+    + Unpin
+    + 'static
+{
+}
+
+/// Builds a sidecar stack from discovery clients and a connector.
+///
+/// Until a connector is set with [`SidecarBuilder::with_connect`], endpoint
+/// connections are established over TCP, as they are by the proxy.
+#[derive(Clone, Debug)]
+pub struct SidecarBuilder<P = (), Q = (), R = (), C = tcp::PreventLoopback<ConnectTcp>> {
+    outbound: Outbound<()>,
+    profiles: P,
+    policies: Q,
+    resolve: R,
+    connect: C,
+}
+
+// === impl SidecarTarget ===
+
+impl<T> SidecarTarget for T where T: svc::Param<OrigDstAddr> + Clone + Send + Sync + 'static {}
+
+// === impl ServerIo ===
+
+impl<I> ServerIo for I where
+    I: io::AsyncRead
+        + io::AsyncWrite
+        + io::Peek
+        + io::PeerAddr
+        + io::Splice
+        + Debug
+        + Unpin
+        + Send
+        + Sync
+        + 'static
+{
+}
+
+// === impl EndpointResolve ===
+
+impl<R> EndpointResolve for R where
+    R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error, Resolution: Unpin>
+{
+}
+
+// === impl EndpointConnect ===
+
+impl<C> EndpointConnect for C where
+    C: svc::MakeConnection<
+            tcp::Connect,
+            Metadata = Local<ClientAddr>,
+            Error = io::Error,
+            Connection: io::Splice + Send + Unpin,
+            Future: Send + Unpin,
+        > + Clone
+        + Send
+        + Sync
+        + Unpin
+        + 'static
+{
+}
+
+// === impl Outbound ===
+
+impl Outbound<()> {
+    /// Returns a builder for a sidecar stack with the given discovery clients
+    /// and connector.
+    pub fn sidecar_builder(&self) -> SidecarBuilder {
+        SidecarBuilder {
+            outbound: self.clone(),
+            profiles: (),
+            policies: (),
+            resolve: (),
+            connect: self.tcp_connector(),
+        }
+    }
+}
+
+// === impl SidecarBuilder ===
+
+impl<P, Q, R, C> SidecarBuilder<P, Q, R, C> {
+    /// Discovers service profiles with `profiles`.
+    ///
+    /// Profiles are only discovered for destinations that are allowed by the
+    /// outbound configuration.
+    pub fn with_profiles<P2>(self, profiles: P2) -> SidecarBuilder<P2, Q, R, C>
+    where
+        P2: profiles::GetProfile<Error = Error>,
+    {
+        SidecarBuilder {
+            outbound: self.outbound,
+            profiles,
+            policies: self.policies,
+            resolve: self.resolve,
+            connect: self.connect,
+        }
+    }
+
+    /// Discovers client policies with `policies`.
+    pub fn with_policies<Q2: policy::GetPolicy>(self, policies: Q2) -> SidecarBuilder<P, Q2, R, C> {
+        SidecarBuilder {
+            outbound: self.outbound,
+            profiles: self.profiles,
+            policies,
+            resolve: self.resolve,
+            connect: self.connect,
+        }
+    }
+
+    /// Resolves the endpoints of balanced backends with `resolve`.
+    pub fn with_resolve<R2: EndpointResolve>(self, resolve: R2) -> SidecarBuilder<P, Q, R2, C> {
+        SidecarBuilder {
+            outbound: self.outbound,
+            profiles: self.profiles,
+            policies: self.policies,
+            resolve,
+            connect: self.connect,
+        }
+    }
+
+    /// Establishes endpoint connections with `connect`.
+    pub fn with_connect<C2: EndpointConnect>(self, connect: C2) -> SidecarBuilder<P, Q, R, C2> {
+        SidecarBuilder {
+            outbound: self.outbound,
+            profiles: self.profiles,
+            policies: self.policies,
+            resolve: self.resolve,
+            connect,
+        }
+    }
+}
+
+impl<P, Q, R, C> SidecarBuilder<P, Q, R, C>
+where
+    P: profiles::GetProfile<Error = Error>,
+    Q: policy::GetPolicy,
+    R: EndpointResolve,
+    C: EndpointConnect,
+{
+    /// Builds a stack that serves `I`-typed connections described by
+    /// `T`-typed targets.
+    pub fn build<T: SidecarTarget, I: ServerIo>(self) -> svc::ArcNewTcp<T, I> {
+        let Self {
+            outbound,
+            profiles,
+            policies,
+            resolve,
+            connect,
+        } = self;
+        let profiles =
+            profiles::WithAllowlist::new(profiles, outbound.config.allow_discovery.clone());
+        outbound
+            .with_stack(connect)
+            .push_sidecar(profiles, policies, resolve)
+            .into_inner()
+    }
+}
@@ -0,0 +1,686 @@
+use crate::{
+    http,
+    ingress::{Http, Logical, RequestTarget},
+    opaq, policy, Discovery, Outbound, ParentRef, RouteUpdates,
+};
+use bytes::BytesMut;
+use futures::prelude::*;
+use linkerd_app_core::{
+    errors,
+    io::{self, AsyncReadExt, AsyncWriteExt},
+    profiles,
+    proxy::{
+        api_resolve::{ConcreteAddr, Metadata},
+        core::Resolve,
+    },
+    svc::{self, ServiceExt},
+    transport::addrs::*,
+    Addr, Error, Result,
+};
+use std::{
+    fmt::Debug,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use thiserror::Error;
+use tokio::{sync::watch, time};
+
+/// A connection accepted on the explicit proxy listener.
+///
+/// Connections are addressed to the proxy directly, so the listener's address
+/// stands in for the original destination address.
+#[derive(Clone, Debug)]
+struct Accept {
+    local: Local<ServerAddr>,
+}
+
+/// The authority of a `CONNECT` request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Connect {
+    pub(crate) addr: Addr,
+}
+
+/// A discovered `CONNECT` target, tunneled through the opaque stack.
+#[derive(Clone, Debug)]
+pub(crate) struct Tunnel {
+    addr: Addr,
+    routes: watch::Receiver<opaq::Routes>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct SelectProxyTarget;
+
+#[derive(Clone, Debug)]
+struct NewExplicitServer<H, C> {
+    http: H,
+    connect: C,
+    read_timeout: time::Duration,
+}
+
+#[derive(Clone, Debug)]
+struct ExplicitServer<S, C> {
+    http: S,
+    connect: C,
+    read_timeout: time::Duration,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct ExplicitRescue {
+    emit_headers: bool,
+}
+
+#[derive(Debug, Error)]
+#[error("explicit proxy requests must be in absolute-form")]
+struct ProxyFormRequired(());
+
+#[derive(Debug, Error)]
+#[error("explicit proxy connections must use HTTP")]
+struct HttpRequired(());
+
+#[derive(Debug, Error)]
+#[error("invalid CONNECT request")]
+struct InvalidConnect(());
+
+#[derive(Debug, Error)]
+#[error("CONNECT tunnels require an opaque policy for {0}")]
+pub(crate) struct OpaquePolicyRequired(Addr);
+
+const CONNECT_PREFIX: &[u8] = b"CONNECT ";
+
+/// The maximum size of a `CONNECT` request's head.
+const MAX_CONNECT_HEAD_LEN: usize = 8 * 1024;
+
+const CONNECT_ESTABLISHED: &[u8] = b"HTTP/1.1 200 Connection established\r\n\r\n";
+const CONNECT_BAD_REQUEST: &[u8] =
+    b"HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+const CONNECT_BAD_GATEWAY: &[u8] =
+    b"HTTP/1.1 502 Bad Gateway\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+
+// === impl Outbound ===
+
+impl Outbound<()> {
+    /// Builds an explicit proxy stack.
+    ///
+    /// Clients address the explicit proxy directly, as an HTTP proxy, instead
+    /// of having their connections redirected to it. `CONNECT` requests are
+    /// tunneled to the requested authority and absolute-form HTTP requests are
+    /// routed by their authority. As in ingress-mode, targets are discovered by
+    /// name rather than by original destination address. Requests that are not
+    /// in proxy-form are rejected.
+    pub fn mk_explicit<T, I, R>(
+        &self,
+        profiles: impl profiles::GetProfile<Error = Error>,
+        policies: impl policy::GetPolicy,
+        resolve: R,
+    ) -> svc::ArcNewTcp<T, I>
+    where
+        // Target describing a connection accepted on the explicit listener.
+        T: svc::Param<Local<ServerAddr>>,
+        T: Clone + Send + Sync + 'static,
+        // Server-side socket.
+        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + io::Splice,
+        I: Debug + Unpin + Send + Sync + 'static,
+        // Endpoint resolver.
+        R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
+        R::Resolution: Unpin,
+    {
+        let profiles = profiles::WithAllowlist::new(profiles, self.config.allow_discovery.clone());
+        let discover = self.ingress_resolver(profiles, policies);
+
+        let connect = self
+            .to_tcp_connect()
+            .push_opaq_cached(resolve.clone())
+            .map_stack(|_, rt, stk| {
+                let route_updates = rt.route_updates.clone();
+                stk.push_filter(move |discovery| Tunnel::from_discovery(discovery, &route_updates))
+            })
+            .push_discover(discover.clone())
+            .into_inner();
+
+        let http = self
+            .to_tcp_connect()
+            .push_tcp_endpoint()
+            .push_http_tcp_client()
+            .push_http_cached(resolve)
+            .push_http_server()
+            .map_stack(|_, rt, stk| {
+                let route_updates = rt.route_updates.clone();
+                stk.check_new_service::<Http<Logical>, _>()
+                    .push_filter(move |parent| Http::from_discovery(parent, &route_updates))
+            })
+            .push_discover(discover);
+
+        http.push_explicit(connect)
+            .push_tcp_instrument(
+                |a: &Accept| tracing::info_span!("explicit", listen.addr = %a.local),
+            )
+            .map_stack(|_, _, stk| {
+                stk.push_map_target(|t: T| Accept { local: t.param() })
+                    .arc_new_tcp()
+            })
+            .into_inner()
+    }
+}
+
+impl<N> Outbound<N> {
+    /// Serves explicitly proxied connections.
+    ///
+    /// Connections that begin with a `CONNECT` request are tunneled via the
+    /// `connect` stack once the requested authority has been discovered. All
+    /// other connections must be HTTP. Their requests are routed through the
+    /// inner stack by the authority of their absolute-form URIs.
+    fn push_explicit<T, I, C, CSvc, NSvc>(self, connect: C) -> Outbound<svc::ArcNewTcp<T, I>>
+    where
+        // Target type describing an accepted connection.
+        T: Clone + Send + Sync + Unpin + 'static,
+        // A server-side socket.
+        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + io::Splice,
+        I: Debug + Send + Unpin + 'static,
+        // CONNECT tunnel stack.
+        C: svc::NewService<Connect, Service = CSvc> + Clone + Send + Sync + 'static,
+        CSvc: svc::Service<io::PrefixedIo<I>, Response = (), Error = Error> + Send + 'static,
+        CSvc::Future: Send,
+        // HTTP stack.
+        N: svc::NewService<Http<RequestTarget>, Service = NSvc>,
+        N: Clone + Send + Sync + Unpin + 'static,
+        NSvc: svc::Service<
+            http::Request<http::BoxBody>,
+            Response = http::Response<http::BoxBody>,
+            Error = Error,
+        >,
+        NSvc: Send + Unpin + 'static,
+        NSvc::Future: Send,
+    {
+        self.map_stack(|config, rt, inner| {
+            let read_timeout = config.proxy.detect_protocol_timeout;
+            let detect_params = http::DetectParams {
+                read_timeout,
+                metrics: rt
+                    .metrics
+                    .prom
+                    .http_detect
+                    .metrics(ParentRef(policy::Meta::new_default("explicit"))),
+            };
+
+            // This stack creates one-off services for each request--so it is
+            // important that the inner stack caches any state that should be
+            // shared across requests.
+            let http = inner
+                .check_new_service::<Http<RequestTarget>, http::Request<http::BoxBody>>()
+                .push_on_service(http::BoxRequest::layer())
+                .lift_new()
+                .push(svc::NewOneshotRoute::layer_via(|_: &Http<T>| {
+                    SelectProxyTarget
+                }))
+                .push(ExplicitRescue::layer(config.emit_headers))
+                .push_on_service(http::BoxResponse::layer())
+                .check_new_service::<Http<T>, http::Request<_>>();
+
+            http.unlift_new()
+                .push(http::NewServeHttp::layer({
+                    let h2 = config.proxy.server.http2.clone();
+                    let drain = rt.drain.clone();
+                    move |http: &Http<T>| http::ServerParams {
+                        version: http.version,
+                        http2: h2.clone(),
+                        drain: drain.clone(),
+                    }
+                }))
+                .push_filter(
+                    |(detected, parent): (http::Detection, T)| -> Result<_, HttpRequired> {
+                        match detected {
+                            http::Detection::Http(version) => Ok(Http { version, parent }),
+                            _ => Err(HttpRequired(())),
+                        }
+                    },
+                )
+                .lift_new_with_target()
+                .push(http::NewDetect::layer(svc::CloneParam::from(detect_params)))
+                .push(NewExplicitServer::layer(connect, read_timeout))
+                .arc_new_tcp()
+        })
+    }
+}
+
+// === impl Accept ===
+
+impl svc::Param<OrigDstAddr> for Accept {
+    fn param(&self) -> OrigDstAddr {
+        let Local(ServerAddr(addr)) = self.local;
+        OrigDstAddr(addr)
+    }
+}
+
+// === impl SelectProxyTarget ===
+
+impl<B> svc::router::SelectRoute<http::Request<B>> for SelectProxyTarget {
+    type Key = Http<RequestTarget>;
+    type Error = ProxyFormRequired;
+
+    fn select(&self, req: &http::Request<B>) -> Result<Self::Key, Self::Error> {
+        let version = match req.version() {
+            ::http::Version::HTTP_2 => http::Variant::H2,
+            ::http::Version::HTTP_10 | ::http::Version::HTTP_11 => http::Variant::Http1,
+            _ => unreachable!("Only HTTP/1 and HTTP/2 are supported"),
+        };
+
+        // HTTP/1 requests must be in absolute-form. HTTP/2 requests always
+        // include an authority unless they target the proxy itself.
+        let uri = req.uri();
+        if version == http::Variant::Http1 && uri.scheme().is_none() {
+            return Err(ProxyFormRequired(()));
+        }
+        let authority = uri.authority().ok_or(ProxyFormRequired(()))?;
+        let default_port = if uri.scheme() == Some(&::http::uri::Scheme::HTTPS) {
+            443
+        } else {
+            80
+        };
+        let parent = match Addr::from_authority_and_default_port(authority, default_port) {
+            Ok(Addr::Name(addr)) => RequestTarget::Named(addr),
+            Ok(Addr::Socket(addr)) => RequestTarget::Orig(OrigDstAddr(addr)),
+            Err(_) => return Err(ProxyFormRequired(())),
+        };
+
+        Ok(Http { version, parent })
+    }
+}
+
+// === impl NewExplicitServer ===
+
+impl<H, C> NewExplicitServer<H, C> {
+    fn layer(
+        connect: C,
+        read_timeout: time::Duration,
+    ) -> impl svc::layer::Layer<H, Service = Self> + Clone
+    where
+        C: Clone,
+    {
+        svc::layer::mk(move |http| Self {
+            http,
+            connect: connect.clone(),
+            read_timeout,
+        })
+    }
+}
+
+impl<T, H, C> svc::NewService<T> for NewExplicitServer<H, C>
+where
+    H: svc::NewService<T>,
+    C: Clone,
+{
+    type Service = ExplicitServer<H::Service, C>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        ExplicitServer {
+            http: self.http.new_service(target),
+            connect: self.connect.clone(),
+            read_timeout: self.read_timeout,
+        }
+    }
+}
+
+// === impl ExplicitServer ===
+
+impl<I, S, C, CSvc> svc::Service<I> for ExplicitServer<S, C>
+where
+    I: io::AsyncRead + io::AsyncWrite + Send + Unpin + 'static,
+    S: svc::Service<io::PrefixedIo<I>, Response = (), Error = Error> + Clone + Send + 'static,
+    S::Future: Send,
+    C: svc::NewService<Connect, Service = CSvc> + Clone + Send + 'static,
+    CSvc: svc::Service<io::PrefixedIo<I>, Response = (), Error = Error> + Send + 'static,
+    CSvc::Future: Send,
+{
+    type Response = ();
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut io: I) -> Self::Future {
+        let http = self.http.clone();
+        let connect = self.connect.clone();
+        let read_timeout = self.read_timeout;
+        Box::pin(async move {
+            let mut buf = BytesMut::with_capacity(1024);
+            if !time::timeout(read_timeout, is_connect(&mut io, &mut buf)).await?? {
+                return http.oneshot(io::PrefixedIo::new(buf.freeze(), io)).await;
+            }
+
+            let head_len =
+                time::timeout(read_timeout, read_connect_head(&mut io, &mut buf)).await??;
+            let addr = match parse_connect(&buf.split_to(head_len)) {
+                Ok(addr) => addr,
+                Err(error) => {
+                    io.write_all(CONNECT_BAD_REQUEST).await?;
+                    return Err(error.into());
+                }
+            };
+            tracing::debug!(%addr, "CONNECT");
+
+            // Discover the target before acknowledging the tunnel so that
+            // discovery failures can be reported to the client.
+            let mut tunnel = connect.new_service(Connect { addr });
+            if let Err(error) = tunnel.ready().await {
+                io.write_all(CONNECT_BAD_GATEWAY).await?;
+                return Err(error);
+            }
+            io.write_all(CONNECT_ESTABLISHED).await?;
+            tunnel.call(io::PrefixedIo::new(buf.freeze(), io)).await
+        })
+    }
+}
+
+/// Reads from the socket until it can be determined whether the connection
+/// begins with a `CONNECT` request.
+async fn is_connect<I: io::AsyncRead + Unpin>(io: &mut I, buf: &mut BytesMut) -> io::Result<bool> {
+    while buf.len() < CONNECT_PREFIX.len() {
+        if !CONNECT_PREFIX.starts_with(buf) || io.read_buf(buf).await? == 0 {
+            return Ok(false);
+        }
+    }
+    Ok(buf.starts_with(CONNECT_PREFIX))
+}
+
+/// Reads a `CONNECT` request's head, returning its length.
+async fn read_connect_head<I: io::AsyncRead + Unpin>(
+    io: &mut I,
+    buf: &mut BytesMut,
+) -> Result<usize> {
+    loop {
+        if let Some(idx) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            return Ok(idx + 4);
+        }
+        if buf.len() >= MAX_CONNECT_HEAD_LEN {
+            return Err(InvalidConnect(()).into());
+        }
+        if io.read_buf(buf).await? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "socket closed before CONNECT request was read",
+            )
+            .into());
+        }
+    }
+}
+
+/// Parses the authority-form target of a `CONNECT` request.
+fn parse_connect(head: &[u8]) -> Result<Addr, InvalidConnect> {
+    let line = head.split(|b| *b == b'\n').next().unwrap_or_default();
+    let line = std::str::from_utf8(line).map_err(|_| InvalidConnect(()))?;
+    let mut parts = line.trim_end().split(' ');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some("CONNECT"), Some(target), Some(version), None) if version.starts_with("HTTP/1.") => {
+            let authority = target
+                .parse::<::http::uri::Authority>()
+                .map_err(|_| InvalidConnect(()))?;
+            Addr::from_authority_with_port(&authority).map_err(|_| InvalidConnect(()))
+        }
+        _ => Err(InvalidConnect(())),
+    }
+}
+
+// === impl Connect ===
+
+impl svc::Param<crate::ingress::DiscoverAddr> for Connect {
+    fn param(&self) -> crate::ingress::DiscoverAddr {
+        crate::ingress::DiscoverAddr(self.addr.clone())
+    }
+}
+
+// === impl Tunnel ===
+
+impl Tunnel {
+    pub(crate) fn from_discovery(
+        discovery: Discovery<Connect>,
+        route_updates: &RouteUpdates,
+    ) -> Result<Self, OpaquePolicyRequired> {
+        use svc::Param;
+
+        let addr = discovery.addr.clone();
+        let policy: policy::Receiver = discovery.param();
+        let is_opaque = matches!(
+            policy.borrow().protocol,
+            policy::Protocol::Opaque(_) | policy::Protocol::Detect { .. }
+        );
+        if !is_opaque {
+            return Err(OpaquePolicyRequired(addr));
+        }
+
+        let routes =
+            opaq::routes_from_discovery(route_updates, addr.clone(), discovery.param(), policy);
+        Ok(Self { addr, routes })
+    }
+}
+
+impl PartialEq for Tunnel {
+    fn eq(&self, other: &Self) -> bool {
+        self.addr == other.addr
+    }
+}
+
+impl Eq for Tunnel {}
+
+impl std::hash::Hash for Tunnel {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.addr.hash(state);
+    }
+}
+
+impl svc::Param<watch::Receiver<opaq::Routes>> for Tunnel {
+    fn param(&self) -> watch::Receiver<opaq::Routes> {
+        self.routes.clone()
+    }
+}
+
+// === impl ExplicitRescue ===
+
+impl ExplicitRescue {
+    fn layer<N>(
+        emit_headers: bool,
+    ) -> impl svc::layer::Layer<N, Service = errors::NewRespondService<Self, Self, N>> + Clone {
+        errors::respond::layer(Self { emit_headers })
+    }
+}
+
+impl<T> svc::ExtractParam<Self, T> for ExplicitRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> Self {
+        *self
+    }
+}
+
+impl<T> svc::ExtractParam<errors::respond::EmitHeaders, T> for ExplicitRescue {
+    #[inline]
+    fn extract_param(&self, _: &T) -> errors::respond::EmitHeaders {
+        errors::respond::EmitHeaders(self.emit_headers)
+    }
+}
+
+impl errors::HttpRescue<Error> for ExplicitRescue {
+    fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
+        if errors::is_caused_by::<ProxyFormRequired>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::response(
+                errors::ErrorCode::ProxyFormRequired,
+                http::StatusCode::BAD_REQUEST,
+                error.to_string(),
+            ));
+        }
+
+        // The request's target could not be discovered.
+        Ok(errors::SyntheticHttpResponse::bad_gateway(
+            errors::ErrorCode::DiscoveryFailed,
+            error,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::{drain, NameAddr};
+    use std::str::FromStr;
+    use svc::NewService;
+    use tokio::io::DuplexStream;
+
+    fn explicit<N, NSvc, C, CSvc>(
+        http: N,
+        connect: C,
+    ) -> (svc::ArcNewTcp<(), DuplexStream>, drain::Signal)
+    where
+        N: svc::NewService<Http<RequestTarget>, Service = NSvc>,
+        N: Clone + Send + Sync + Unpin + 'static,
+        NSvc: svc::Service<
+            http::Request<http::BoxBody>,
+            Response = http::Response<http::BoxBody>,
+            Error = Error,
+        >,
+        NSvc: Send + Unpin + 'static,
+        NSvc::Future: Send,
+        C: svc::NewService<Connect, Service = CSvc> + Clone + Send + Sync + 'static,
+        CSvc: svc::Service<io::PrefixedIo<DuplexStream>, Response = (), Error = Error>,
+        CSvc: Send + 'static,
+        CSvc::Future: Send,
+    {
+        let config = crate::test_util::default_config();
+        let (runtime, drain) = crate::test_util::runtime();
+        let stack = Outbound::new(config, runtime, &mut Default::default())
+            .with_stack(http)
+            .push_explicit(connect)
+            .into_inner();
+        (stack, drain)
+    }
+
+    fn no_connect() -> impl svc::NewService<
+        Connect,
+        Service = svc::BoxService<io::PrefixedIo<DuplexStream>, (), Error>,
+    > + Clone {
+        |_: Connect| -> svc::BoxService<io::PrefixedIo<DuplexStream>, (), Error> {
+            panic!("unexpected CONNECT")
+        }
+    }
+
+    fn no_http(
+    ) -> impl svc::NewService<Http<RequestTarget>, Service = svc::BoxHttp<http::BoxBody>> + Clone + Unpin
+    {
+        |_: Http<RequestTarget>| -> svc::BoxHttp<http::BoxBody> { panic!("unexpected request") }
+    }
+
+    async fn read_response(client: &mut DuplexStream) -> String {
+        let mut buf = BytesMut::with_capacity(1024);
+        while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+            if client.read_buf(&mut buf).await.expect("read must succeed") == 0 {
+                break;
+            }
+        }
+        String::from_utf8(buf.to_vec()).expect("response must be utf-8")
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn connect_tunnels_to_authority() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let connect = |Connect { addr }: Connect| {
+            assert_eq!(
+                addr,
+                Addr::from_str("foo.ns.svc.cluster.local:8080").unwrap()
+            );
+            svc::BoxService::new(svc::mk(|mut io: io::PrefixedIo<DuplexStream>| {
+                Box::pin(async move {
+                    let mut buf = [0u8; 5];
+                    io.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"hello");
+                    io.write_all(b"world").await?;
+                    Ok::<_, Error>(())
+                })
+            }))
+        };
+        let (stack, _drain) = explicit(no_http(), connect);
+        let svc = stack.new_service(());
+
+        let (mut client, server) = io::duplex(1024);
+        let task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(b"CONNECT foo.ns.svc.cluster.local:8080 HTTP/1.1\r\nhost: foo.ns.svc.cluster.local:8080\r\n\r\nhello")
+            .await
+            .unwrap();
+
+        task.await.unwrap().expect("tunnel must complete");
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, [CONNECT_ESTABLISHED, b"world"].concat());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn connect_requires_port() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let (stack, _drain) = explicit(no_http(), no_connect());
+        let svc = stack.new_service(());
+
+        let (mut client, server) = io::duplex(1024);
+        let task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(b"CONNECT foo.ns.svc.cluster.local HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+
+        let rsp = read_response(&mut client).await;
+        assert!(rsp.starts_with("HTTP/1.1 400 "), "{rsp}");
+        task.await.unwrap().expect_err("connection must fail");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn routes_absolute_form_by_authority() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let http = |Http { parent, version }: Http<RequestTarget>| {
+            assert_eq!(version, http::Variant::Http1);
+            assert_eq!(
+                parent,
+                RequestTarget::Named(NameAddr::from_str("foo.ns.svc.cluster.local:8080").unwrap())
+            );
+            svc::BoxHttp::new(svc::mk(|_: http::Request<http::BoxBody>| {
+                future::ok::<_, Error>(
+                    http::Response::builder()
+                        .status(http::StatusCode::NO_CONTENT)
+                        .body(http::BoxBody::default())
+                        .unwrap(),
+                )
+            }))
+        };
+        let (stack, _drain) = explicit(http, no_connect());
+        let svc = stack.new_service(());
+
+        let (mut client, server) = io::duplex(1024);
+        let _task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(b"GET http://foo.ns.svc.cluster.local:8080/ HTTP/1.1\r\nhost: foo.ns.svc.cluster.local:8080\r\n\r\n")
+            .await
+            .unwrap();
+
+        let rsp = read_response(&mut client).await;
+        assert!(rsp.starts_with("HTTP/1.1 204 "), "{rsp}");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn rejects_origin_form() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let (stack, _drain) = explicit(no_http(), no_connect());
+        let svc = stack.new_service(());
+
+        let (mut client, server) = io::duplex(1024);
+        let _task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(b"GET / HTTP/1.1\r\nhost: foo.ns.svc.cluster.local:8080\r\n\r\n")
+            .await
+            .unwrap();
+
+        let rsp = read_response(&mut client).await;
+        assert!(rsp.starts_with("HTTP/1.1 400 "), "{rsp}");
+    }
+}
@@ -1,6 +1,7 @@
 use self::require_id_header::NewRequireIdentity;
-use crate::Outbound;
+use crate::{route_updates, Outbound, RouteUpdates};
 use linkerd_app_core::{
+    idle_cache,
     metrics::prom,
     proxy::{
         api_resolve::{ConcreteAddr, Metadata},
@@ -10,20 +11,35 @@ use linkerd_app_core::{
     transport::addrs::*,
     Error,
 };
+use linkerd_http_retry::BufferBudget;
 use std::{fmt::Debug, hash::Hash};
 use tokio::sync::watch;
 
+mod backend_faults;
+mod body_buffer;
 mod breaker;
 pub mod concrete;
 mod endpoint;
 mod handle_proxy_error_headers;
+mod keepalive_exempt;
 pub mod logical;
 mod require_id_header;
 mod retry;
 mod server;
+mod timings;
+mod upgrade_probe;
 
-pub use self::logical::{policy, profile, LogicalAddr, Routes};
+pub use self::backend_faults::{
+    BackendFault, BackendFaultInjected, BackendFaultState, BackendFaultTarget, BackendFaults,
+    InvalidBackendFault,
+};
+pub use self::body_buffer::BodyBufferLimits;
+pub use self::breaker::{BreakerState, Breakers, EndpointBreakerState, LatencyOutlierConfig};
+pub use self::keepalive_exempt::KeepaliveExempt;
+pub use self::logical::{policy, profile, LogicalAddr, Routes, RoutesAddrs};
 pub(crate) use self::require_id_header::IdentityRequired;
+pub(crate) use self::timings::{Connections, RecordConnect};
+pub(crate) use self::upgrade_probe::UpgradeProbes;
 pub use linkerd_app_core::proxy::http::{self as http, *};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -32,44 +48,28 @@ pub struct Http<T>(T);
 #[derive(Clone, Debug, Default)]
 pub struct HttpMetrics {
     balancer: concrete::BalancerMetrics,
+    request_body: body_buffer::BodyBufferMetrics,
     http_route: policy::HttpRouteMetrics,
     grpc_route: policy::GrpcRouteMetrics,
+    rollout_guards: policy::RolloutGuards,
+    breakers: breaker::Breakers,
+    backend_faults: backend_faults::BackendFaults,
+    upgrade_probes: upgrade_probe::UpgradeProbeMetrics,
+    route_debug: policy::RouteDebugMetrics,
 }
 
+/// Spawns a task that publishes the routes computed by `mk` from each update
+/// on `route_rx`.
 pub fn spawn_routes<T>(
-    mut route_rx: watch::Receiver<T>,
+    updates: &RouteUpdates,
+    route_rx: watch::Receiver<T>,
     init: Routes,
-    mut mk: impl FnMut(&T) -> Option<Routes> + Send + Sync + 'static,
+    mk: impl FnMut(&T) -> Option<Routes> + Send + Sync + 'static,
 ) -> watch::Receiver<Routes>
 where
     T: Send + Sync + 'static,
 {
-    let (tx, rx) = watch::channel(init);
-
-    tokio::spawn(async move {
-        loop {
-            let res = tokio::select! {
-                biased;
-                _ = tx.closed() => return,
-                res = route_rx.changed() => res,
-            };
-
-            if res.is_err() {
-                // Drop the `tx` sender when the profile sender is
-                // dropped.
-                return;
-            }
-
-            if let Some(routes) = (mk)(&*route_rx.borrow_and_update()) {
-                if tx.send(routes).is_err() {
-                    // Drop the `tx` sender when all of its receivers are dropped.
-                    return;
-                }
-            }
-        }
-    });
-
-    rx
+    updates.spawn(route_updates::Protocol::http, route_rx, init, mk)
 }
 
 pub fn spawn_routes_default(addr: Remote<ServerAddr>) -> watch::Receiver<Routes> {
@@ -86,6 +86,8 @@ impl<T> Outbound<svc::ArcNewHttp<concrete::Endpoint<logical::Concrete<Http<T>>>>
     /// Builds a stack that routes HTTP requests to endpoint stacks.
     ///
     /// Buffered concrete services are cached in and evicted when idle.
+    /// Requests that are configured to be exempt do not keep services alive,
+    /// and services may be rebuilt after a maximum age.
     pub fn push_http_cached<R>(self, resolve: R) -> Outbound<svc::ArcNewCloneHttp<T>>
     where
         // Logical HTTP target.
@@ -99,10 +101,27 @@ impl<T> Outbound<svc::ArcNewHttp<concrete::Endpoint<logical::Concrete<Http<T>>>>
         self.push_http_endpoint()
             .push_http_concrete(resolve)
             .push_http_logical()
-            .map_stack(move |config, _, stk| {
-                stk.push_new_idle_cached(config.discovery_idle_timeout)
-                    .push_map_target(Http)
-                    .arc_new_clone_http()
+            .map_stack(move |config, rt, stk| {
+                let idle = config.discovery_idle_timeout;
+                let max_age = config.http_logical_max_age;
+                let exempt = config.http_keepalive_exempt.clone();
+                let track_activity = !exempt.is_empty();
+                let builds = rt.metrics.prom.stack_builds.in_progress("http");
+                stk.push(svc::layer::mk(move |inner| {
+                    let mut cache: idle_cache::NewIdleCached<Http<T>, _> =
+                        idle_cache::NewIdleCached::new(inner, idle)
+                            .with_builds_gauge(builds.clone());
+                    if let Some(max_age) = max_age {
+                        cache = cache.with_max_age(max_age);
+                    }
+                    if track_activity {
+                        cache = cache.with_activity_tracking();
+                    }
+                    cache
+                }))
+                .push(keepalive_exempt::NewMarkActive::layer(exempt))
+                .push_map_target(Http)
+                .arc_new_clone_http()
             })
     }
 }
@@ -135,14 +154,90 @@ impl HttpMetrics {
         let http_route = policy::HttpRouteMetrics::register(http.sub_registry_with_prefix("route"));
         let balancer =
             concrete::BalancerMetrics::register(http.sub_registry_with_prefix("balancer"));
+        let breakers =
+            breaker::Breakers::register(http.sub_registry_with_prefix("balancer_latency_outlier"))
+                .with_balancers(balancer.clone());
+        let backend_faults = backend_faults::BackendFaults::register(
+            http.sub_registry_with_prefix("backend_faults"),
+        );
+        let upgrade_probes = upgrade_probe::UpgradeProbeMetrics::register(
+            http.sub_registry_with_prefix("upgrade_probe"),
+        );
+        let request_body =
+            body_buffer::BodyBufferMetrics::register(http.sub_registry_with_prefix("request_body"));
+        let route_debug =
+            policy::RouteDebugMetrics::register(http.sub_registry_with_prefix("route_debug"));
 
         let grpc = registry.sub_registry_with_prefix("grpc");
         let grpc_route = policy::GrpcRouteMetrics::register(grpc.sub_registry_with_prefix("route"));
 
+        // Rollout guards are shared by HTTP and gRPC routes.
+        let rollout_guards = policy::RolloutGuards::register(
+            registry.sub_registry_with_prefix("route_rollout_guard"),
+        );
+
         Self {
             balancer,
-            http_route,
-            grpc_route,
+            request_body,
+            http_route: http_route.with_rollout_guards(rollout_guards.clone()),
+            grpc_route: grpc_route.with_rollout_guards(rollout_guards.clone()),
+            rollout_guards,
+            breakers,
+            backend_faults,
+            upgrade_probes,
+            route_debug,
+        }
+    }
+
+    /// Shares a budget for buffering retryable request bodies across all HTTP
+    /// and gRPC routes.
+    pub(crate) fn with_retry_buffer_budget(self, budget: BufferBudget) -> Self {
+        Self {
+            http_route: self.http_route.with_retry_buffer_budget(budget.clone()),
+            grpc_route: self.grpc_route.with_retry_buffer_budget(budget),
+            ..self
+        }
+    }
+
+    /// Shares a response cache across all HTTP routes.
+    pub(crate) fn with_response_cache(self, cache: policy::ResponseCache) -> Self {
+        Self {
+            http_route: self.http_route.with_response_cache(cache),
+            ..self
         }
     }
+
+    /// Shares a generator for injecting faults across all HTTP and gRPC
+    /// routes.
+    pub(crate) fn with_fault_rng(self, rng: policy::FaultRng) -> Self {
+        Self {
+            http_route: self.http_route.with_fault_rng(rng.clone()),
+            grpc_route: self.grpc_route.with_fault_rng(rng),
+            ..self
+        }
+    }
+
+    pub(crate) fn rollout_guards(&self) -> &policy::RolloutGuards {
+        &self.rollout_guards
+    }
+
+    pub(crate) fn breakers(&self) -> &breaker::Breakers {
+        &self.breakers
+    }
+
+    pub(crate) fn backend_faults(&self) -> &backend_faults::BackendFaults {
+        &self.backend_faults
+    }
+
+    pub(crate) fn request_body(&self) -> &body_buffer::BodyBufferMetrics {
+        &self.request_body
+    }
+
+    pub(crate) fn route_debug(&self) -> &policy::RouteDebugMetrics {
+        &self.route_debug
+    }
+
+    pub(crate) fn upgrade_probes(&self) -> &upgrade_probe::UpgradeProbeMetrics {
+        &self.upgrade_probes
+    }
 }
@@ -0,0 +1,354 @@
+//! Injects faults into the requests sent to specific backends, for game days.
+//!
+//! Unlike route faults, which are configured by policy, backend faults are
+//! configured through the admin server and apply to every route that uses a
+//! backend. A fault targets either a concrete service, by the name with which
+//! its endpoints are discovered, or an endpoint's IP address. Faults expire
+//! after a bounded duration so that a forgotten rehearsal does not degrade
+//! traffic indefinitely.
+//!
+//! Requests to a faulted endpoint may fail as if the endpoint's connection
+//! failed, and their responses may be delayed. Faults apply to HTTP endpoints
+//! only; opaque connections are not affected.
+
+use futures::{
+    future::{self, Either},
+    FutureExt, TryFutureExt,
+};
+use linkerd_app_core::{
+    metrics::prom,
+    proxy::{api_resolve::ConcreteAddr, http},
+    svc,
+    transport::{Remote, ServerAddr},
+    Error, NameAddr, Result,
+};
+use parking_lot::RwLock;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    net::IpAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+use tokio::time;
+
+#[cfg(test)]
+mod tests;
+
+/// The requests to which a backend fault applies.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BackendFaultTarget {
+    /// Requests to any endpoint of the concrete service with the given name.
+    Concrete(NameAddr),
+
+    /// Requests to the endpoint with the given IP address, on any port.
+    Endpoint(IpAddr),
+}
+
+/// Describes the faults that are injected into a backend's requests.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BackendFault {
+    /// Delays each response by the given duration.
+    pub latency: Option<time::Duration>,
+
+    /// Fails each request as if the endpoint's connection failed.
+    pub fail: bool,
+}
+
+/// The backend faults that are currently active.
+#[derive(Clone, Debug, Default)]
+pub struct BackendFaults(Arc<Inner>);
+
+/// A snapshot of an active backend fault.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackendFaultState {
+    pub target: BackendFaultTarget,
+    pub fault: BackendFault,
+
+    /// The time remaining until the fault expires.
+    pub remaining: time::Duration,
+
+    /// The number of requests into which the fault has been injected.
+    pub injected: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidBackendFault {
+    #[error("a backend fault must configure latency or failures")]
+    Empty,
+
+    #[error("a backend fault must expire within {max:?}, not {ttl:?}")]
+    Ttl {
+        ttl: time::Duration,
+        max: time::Duration,
+    },
+}
+
+/// The error with which requests fail when a backend fault fails them.
+#[derive(Debug, thiserror::Error)]
+#[error("injected backend fault: connection to {addr} failed")]
+pub struct BackendFaultInjected {
+    addr: Remote<ServerAddr>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct BackendFaultMetrics {
+    active: prom::Gauge,
+    injected: prom::Counter,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    faults: RwLock<HashMap<BackendFaultTarget, Active>>,
+    next_id: AtomicU64,
+    metrics: BackendFaultMetrics,
+}
+
+#[derive(Clone, Debug)]
+struct Active {
+    id: u64,
+    fault: BackendFault,
+    expires: time::Instant,
+    injected: Arc<AtomicU64>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewInjectBackendFaults<N> {
+    faults: BackendFaults,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct InjectBackendFaults<S> {
+    faults: BackendFaults,
+    concrete: Option<NameAddr>,
+    addr: Remote<ServerAddr>,
+    inner: S,
+}
+
+type Faulted<F> = Pin<Box<dyn Future<Output = Result<F>> + Send + 'static>>;
+
+// === impl BackendFaultTarget ===
+
+impl fmt::Display for BackendFaultTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Concrete(name) => fmt::Display::fmt(name, f),
+            Self::Endpoint(ip) => fmt::Display::fmt(ip, f),
+        }
+    }
+}
+
+// === impl BackendFaults ===
+
+impl BackendFaults {
+    /// The longest duration for which a fault may be active.
+    pub const MAX_TTL: time::Duration = time::Duration::from_secs(60 * 60);
+
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let active = prom::Gauge::default();
+        registry.register(
+            "active",
+            "The number of backend faults that have not expired",
+            active.clone(),
+        );
+        let injected = prom::Counter::default();
+        registry.register(
+            "injected",
+            "The number of requests into which a backend fault was injected",
+            injected.clone(),
+        );
+        Self(Arc::new(Inner {
+            metrics: BackendFaultMetrics { active, injected },
+            ..Default::default()
+        }))
+    }
+
+    /// Injects `fault` into requests to `target` until `ttl` elapses,
+    /// replacing any fault that is active for the target.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn insert(
+        &self,
+        target: BackendFaultTarget,
+        fault: BackendFault,
+        ttl: time::Duration,
+    ) -> Result<(), InvalidBackendFault> {
+        if fault.latency.is_none() && !fault.fail {
+            return Err(InvalidBackendFault::Empty);
+        }
+        if ttl.is_zero() || ttl > Self::MAX_TTL {
+            return Err(InvalidBackendFault::Ttl {
+                ttl,
+                max: Self::MAX_TTL,
+            });
+        }
+
+        let id = self.0.next_id.fetch_add(1, Ordering::Relaxed);
+        let expires = time::Instant::now() + ttl;
+        tracing::warn!(%target, ?fault, ?ttl, "Injecting backend faults");
+        {
+            let mut faults = self.0.faults.write();
+            faults.insert(
+                target.clone(),
+                Active {
+                    id,
+                    fault,
+                    expires,
+                    injected: Default::default(),
+                },
+            );
+            self.0.metrics.active.set(faults.len() as i64);
+        }
+
+        // Remove the fault when it expires, unless it has been replaced.
+        let faults = self.clone();
+        tokio::spawn(async move {
+            time::sleep_until(expires).await;
+            if faults.remove_if(&target, |a| a.id == id) {
+                tracing::warn!(%target, "Backend faults expired");
+            }
+        });
+        Ok(())
+    }
+
+    /// Stops injecting faults into requests to `target`.
+    ///
+    /// Returns false if no fault was active for the target.
+    pub fn remove(&self, target: &BackendFaultTarget) -> bool {
+        let removed = self.remove_if(target, |_| true);
+        if removed {
+            tracing::warn!(%target, "Backend faults removed");
+        }
+        removed
+    }
+
+    /// Returns a snapshot of the active faults, ordered by target.
+    pub fn faults(&self) -> Vec<BackendFaultState> {
+        let now = time::Instant::now();
+        let mut faults = self
+            .0
+            .faults
+            .read()
+            .iter()
+            .filter(|(_, a)| a.expires > now)
+            .map(|(target, a)| BackendFaultState {
+                target: target.clone(),
+                fault: a.fault.clone(),
+                remaining: a.expires.saturating_duration_since(now),
+                injected: a.injected.load(Ordering::Relaxed),
+            })
+            .collect::<Vec<_>>();
+        faults.sort_by_key(|f| f.target.to_string());
+        faults
+    }
+
+    /// Returns the fault for requests to `addr`, if one is active.
+    ///
+    /// A fault that targets the endpoint takes precedence over one that
+    /// targets its concrete service.
+    fn get(&self, concrete: Option<&NameAddr>, addr: IpAddr) -> Option<Active> {
+        let faults = self.0.faults.read();
+        if faults.is_empty() {
+            return None;
+        }
+        let now = time::Instant::now();
+        let endpoint = faults.get(&BackendFaultTarget::Endpoint(addr));
+        let concrete = concrete.and_then(|c| faults.get(&BackendFaultTarget::Concrete(c.clone())));
+        endpoint
+            .into_iter()
+            .chain(concrete)
+            .find(|a| a.expires > now)
+            .cloned()
+    }
+
+    fn remove_if(&self, target: &BackendFaultTarget, f: impl FnOnce(&Active) -> bool) -> bool {
+        let mut faults = self.0.faults.write();
+        if !faults.get(target).is_some_and(f) {
+            return false;
+        }
+        faults.remove(target);
+        self.0.metrics.active.set(faults.len() as i64);
+        true
+    }
+}
+
+// === impl NewInjectBackendFaults ===
+
+impl<N> NewInjectBackendFaults<N> {
+    pub(crate) fn layer(
+        faults: BackendFaults,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            faults: faults.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewInjectBackendFaults<N>
+where
+    T: svc::Param<Remote<ServerAddr>>,
+    T: svc::Param<Option<ConcreteAddr>>,
+    N: svc::NewService<T>,
+{
+    type Service = InjectBackendFaults<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let concrete = svc::Param::<Option<ConcreteAddr>>::param(&target).map(|ConcreteAddr(c)| c);
+        InjectBackendFaults {
+            faults: self.faults.clone(),
+            concrete,
+            addr: target.param(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl InjectBackendFaults ===
+
+impl<B, RspB, S> svc::Service<http::Request<B>> for InjectBackendFaults<S>
+where
+    RspB: Send + 'static,
+    S: svc::Service<http::Request<B>, Response = http::Response<RspB>>,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = Either<future::ErrInto<S::Future, Error>, Faulted<S::Response>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let Remote(ServerAddr(addr)) = self.addr;
+        let Some(active) = self.faults.get(self.concrete.as_ref(), addr.ip()) else {
+            return Either::Left(self.inner.call(req).err_into());
+        };
+        active.injected.fetch_add(1, Ordering::Relaxed);
+        self.faults.0.metrics.injected.inc();
+
+        let BackendFault { latency, fail } = active.fault;
+        let rsp = if fail {
+            tracing::debug!(%addr, "Failing request");
+            future::err::<_, Error>(BackendFaultInjected { addr: self.addr }.into()).left_future()
+        } else {
+            self.inner.call(req).err_into().right_future()
+        };
+        Either::Right(Box::pin(rsp.then(move |res| async move {
+            if let Some(latency) = latency {
+                tracing::debug!(%addr, ?latency, "Delaying response");
+                time::sleep(latency).await;
+            }
+            res
+        })))
+    }
+}
@@ -0,0 +1,172 @@
+use super::*;
+use linkerd_app_core::svc::{Layer, NewService, ServiceExt};
+
+const TTL: time::Duration = time::Duration::from_secs(60);
+
+#[derive(Clone, Debug)]
+struct Target {
+    addr: Remote<ServerAddr>,
+    concrete: Option<ConcreteAddr>,
+}
+
+fn mk(faults: &BackendFaults, ip: [u8; 4], concrete: Option<&str>) -> svc::BoxHttp {
+    let target = Target {
+        addr: Remote(ServerAddr((ip, 8080).into())),
+        concrete: concrete.map(|c| ConcreteAddr(c.parse().unwrap())),
+    };
+    let svc = NewInjectBackendFaults::layer(faults.clone())
+        .layer(|_: Target| {
+            svc::mk(|_: http::Request<http::BoxBody>| {
+                future::ok::<_, Error>(http::Response::new(http::BoxBody::empty()))
+            })
+        })
+        .new_service(target);
+    svc::BoxHttp::new(svc)
+}
+
+async fn send(svc: svc::BoxHttp) -> Result<time::Duration> {
+    let start = time::Instant::now();
+    svc.oneshot(http::Request::new(http::BoxBody::empty()))
+        .await?;
+    Ok(time::Instant::now().saturating_duration_since(start))
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn fails_faulted_endpoints() {
+    let faults = BackendFaults::default();
+    faults
+        .insert(
+            BackendFaultTarget::Endpoint([10, 0, 0, 1].into()),
+            BackendFault {
+                latency: None,
+                fail: true,
+            },
+            TTL,
+        )
+        .unwrap();
+
+    let error = send(mk(&faults, [10, 0, 0, 1], None))
+        .await
+        .expect_err("request to a faulted endpoint must fail");
+    assert!(error.is::<BackendFaultInjected>(), "{error}");
+    send(mk(&faults, [10, 0, 0, 2], None))
+        .await
+        .expect("requests to other endpoints must succeed");
+
+    let states = faults.faults();
+    assert_eq!(states.len(), 1);
+    assert_eq!(states[0].injected, 1);
+    assert_eq!(states[0].remaining, TTL);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn delays_faulted_concrete_services() {
+    let latency = time::Duration::from_millis(250);
+    let faults = BackendFaults::default();
+    faults
+        .insert(
+            BackendFaultTarget::Concrete("foo.ns.svc.cluster.local:8080".parse().unwrap()),
+            BackendFault {
+                latency: Some(latency),
+                fail: false,
+            },
+            TTL,
+        )
+        .unwrap();
+
+    for ip in [[10, 0, 0, 1], [10, 0, 0, 2]] {
+        let elapsed = send(mk(&faults, ip, Some("foo.ns.svc.cluster.local:8080")))
+            .await
+            .expect("request must succeed");
+        assert_eq!(elapsed, latency);
+    }
+    let elapsed = send(mk(
+        &faults,
+        [10, 0, 0, 1],
+        Some("bar.ns.svc.cluster.local:8080"),
+    ))
+    .await
+    .expect("request must succeed");
+    assert_eq!(elapsed, time::Duration::ZERO);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn faults_expire() {
+    let faults = BackendFaults::default();
+    let target = BackendFaultTarget::Endpoint([10, 0, 0, 1].into());
+    let fault = BackendFault {
+        latency: None,
+        fail: true,
+    };
+    faults.insert(target.clone(), fault.clone(), TTL).unwrap();
+
+    // Replacing a fault resets its expiry.
+    time::sleep(TTL / 2).await;
+    faults.insert(target.clone(), fault, TTL).unwrap();
+    time::sleep(TTL / 2).await;
+    assert_eq!(faults.faults().len(), 1);
+    assert!(send(mk(&faults, [10, 0, 0, 1], None)).await.is_err());
+
+    time::sleep(TTL / 2 + time::Duration::from_secs(1)).await;
+    assert!(faults.faults().is_empty());
+    send(mk(&faults, [10, 0, 0, 1], None))
+        .await
+        .expect("expired faults must not be injected");
+    assert!(!faults.remove(&target));
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn removes_faults() {
+    let faults = BackendFaults::default();
+    let target = BackendFaultTarget::Endpoint([10, 0, 0, 1].into());
+    faults
+        .insert(
+            target.clone(),
+            BackendFault {
+                latency: None,
+                fail: true,
+            },
+            TTL,
+        )
+        .unwrap();
+    assert!(faults.remove(&target));
+    assert!(faults.faults().is_empty());
+    send(mk(&faults, [10, 0, 0, 1], None))
+        .await
+        .expect("removed faults must not be injected");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn rejects_invalid_faults() {
+    let faults = BackendFaults::default();
+    let target = BackendFaultTarget::Endpoint([10, 0, 0, 1].into());
+    assert!(matches!(
+        faults.insert(target.clone(), BackendFault::default(), TTL),
+        Err(InvalidBackendFault::Empty)
+    ));
+    let fault = BackendFault {
+        latency: None,
+        fail: true,
+    };
+    for ttl in [time::Duration::ZERO, BackendFaults::MAX_TTL * 2] {
+        assert!(matches!(
+            faults.insert(target.clone(), fault.clone(), ttl),
+            Err(InvalidBackendFault::Ttl { .. })
+        ));
+    }
+    assert!(faults.faults().is_empty());
+}
+
+// === impl Target ===
+
+impl svc::Param<Remote<ServerAddr>> for Target {
+    fn param(&self) -> Remote<ServerAddr> {
+        self.addr
+    }
+}
+
+impl svc::Param<Option<ConcreteAddr>> for Target {
+    fn param(&self) -> Option<ConcreteAddr> {
+        self.concrete.clone()
+    }
+}
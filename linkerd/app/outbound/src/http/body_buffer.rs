@@ -0,0 +1,271 @@
+//! Bounds the request body data buffered by endpoint clients.
+//!
+//! Reads from downstream connections are already bounded: HTTP/1 servers read
+//! bodies into a fixed-size buffer and HTTP/2 servers are bounded by their
+//! flow control windows. Once body data has been read, however, an endpoint
+//! client may hold it until the upstream connection accepts it: HTTP/1
+//! clients queue writes and HTTP/2 clients buffer data until the peer opens
+//! its flow control window. When a slow upstream is paired with a fast
+//! downstream, this data accumulates in the proxy.
+//!
+//! Body data is tracked from when it is read until the client releases it.
+//! When a limit is configured for the client's protocol, a request's body is
+//! not polled while the data it has buffered exceeds the limit, so that the
+//! upstream's backpressure is applied to the downstream.
+
+use bytes::{Buf, Bytes};
+use http_body::{Body, Frame, SizeHint};
+use linkerd_app_core::{metrics::prom, proxy::http, svc};
+use parking_lot::Mutex;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Limits the request body data buffered by endpoint clients, per request.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BodyBufferLimits {
+    /// The number of bytes that may be buffered by HTTP/1 clients, if limited.
+    pub http1: Option<usize>,
+
+    /// The number of bytes that may be buffered by HTTP/2 clients, including
+    /// those used for HTTP/1 requests upgraded to HTTP/2, if limited.
+    pub http2: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BodyBufferMetrics {
+    buffered: prom::Gauge,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewBufferRequestBody<N> {
+    limits: BodyBufferLimits,
+    metrics: BodyBufferMetrics,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct BufferRequestBody<S> {
+    limit: Option<usize>,
+    metrics: BodyBufferMetrics,
+    inner: S,
+}
+
+/// A request body that tracks the data it has yielded until it is released.
+#[pin_project::pin_project]
+#[derive(Debug)]
+pub(crate) struct BufferedBody<B> {
+    #[pin]
+    inner: B,
+    limit: Option<usize>,
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    metrics: BodyBufferMetrics,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    buffered: usize,
+    waker: Option<Waker>,
+}
+
+/// Owns a chunk of body data, releasing it when the last reference to it is
+/// dropped.
+struct Release {
+    data: Bytes,
+    shared: Arc<Shared>,
+}
+
+// === impl BodyBufferLimits ===
+
+impl BodyBufferLimits {
+    fn limit(&self, params: &http::client::Params) -> Option<usize> {
+        match params {
+            http::client::Params::Http1(_) => self.http1,
+            http::client::Params::H2(_) | http::client::Params::OrigProtoUpgrade(..) => self.http2,
+        }
+    }
+}
+
+// === impl BodyBufferMetrics ===
+
+impl BodyBufferMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let buffered = prom::Gauge::default();
+        registry.register_with_unit(
+            "buffered",
+            "The number of request body bytes buffered by endpoint clients",
+            prom::Unit::Bytes,
+            buffered.clone(),
+        );
+        Self { buffered }
+    }
+}
+
+// === impl NewBufferRequestBody ===
+
+impl<N> NewBufferRequestBody<N> {
+    pub(crate) fn layer(
+        limits: BodyBufferLimits,
+        metrics: BodyBufferMetrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            limits,
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewBufferRequestBody<N>
+where
+    T: svc::Param<http::client::Params>,
+    N: svc::NewService<T>,
+{
+    type Service = BufferRequestBody<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let limit = self.limits.limit(&target.param());
+        BufferRequestBody {
+            limit,
+            metrics: self.metrics.clone(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl BufferRequestBody ===
+
+impl<B, S> svc::Service<http::Request<B>> for BufferRequestBody<S>
+where
+    S: svc::Service<http::Request<BufferedBody<B>>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let req = req.map(|inner| BufferedBody::new(inner, self.limit, self.metrics.clone()));
+        self.inner.call(req)
+    }
+}
+
+// === impl BufferedBody ===
+
+impl<B> BufferedBody<B> {
+    pub(crate) fn new(inner: B, limit: Option<usize>, metrics: BodyBufferMetrics) -> Self {
+        Self {
+            inner,
+            limit,
+            shared: Arc::new(Shared {
+                state: Mutex::new(State::default()),
+                metrics,
+            }),
+        }
+    }
+}
+
+impl<B> Body for BufferedBody<B>
+where
+    B: Body,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+
+        if let Some(limit) = *this.limit {
+            let mut state = this.shared.state.lock();
+            if state.buffered >= limit {
+                tracing::trace!(
+                    buffered = state.buffered,
+                    limit,
+                    "Waiting for data to be released"
+                );
+                state.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+
+        let frame = match futures::ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+            None => return Poll::Ready(None),
+        };
+        let shared = this.shared;
+        Poll::Ready(Some(Ok(frame.map_data(|mut data| {
+            let data = data.copy_to_bytes(data.remaining());
+            if data.is_empty() {
+                return data;
+            }
+            shared.acquire(data.len());
+            Bytes::from_owner(Release {
+                data,
+                shared: shared.clone(),
+            })
+        }))))
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl Shared ===
+
+impl Shared {
+    fn acquire(&self, sz: usize) {
+        self.state.lock().buffered += sz;
+        self.metrics.buffered.inc_by(sz as i64);
+    }
+
+    fn release(&self, sz: usize) {
+        let waker = {
+            let mut state = self.state.lock();
+            state.buffered -= sz;
+            state.waker.take()
+        };
+        self.metrics.buffered.dec_by(sz as i64);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+// === impl Release ===
+
+impl AsRef<[u8]> for Release {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Drop for Release {
+    fn drop(&mut self) {
+        self.shared.release(self.data.len());
+    }
+}
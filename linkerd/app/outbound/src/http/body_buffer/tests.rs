@@ -0,0 +1,175 @@
+use super::*;
+use futures::{future, stream};
+use http_body_util::StreamBody;
+use linkerd_app_core::{
+    svc::{NewService, Service, ServiceExt},
+    Error,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::time::Duration;
+
+const CHUNK: usize = 1024;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn stalled_upstream_stops_reading_downstream() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let metrics = BodyBufferMetrics::default();
+    let (body, reads) = downstream();
+    let body = BufferedBody::new(body, Some(4 * CHUNK), metrics.clone());
+
+    // The upstream server never reads from its connection, so the client can
+    // only write as much as fits in the connection's buffer.
+    let (client_io, _server_io) = tokio::io::duplex(CHUNK);
+    let (mut client, conn) =
+        hyper::client::conn::http1::handshake(hyper_util::rt::TokioIo::new(client_io))
+            .await
+            .expect("handshake must succeed");
+    tokio::spawn(conn);
+    let req = http::Request::post("http://example.com/")
+        .body(body)
+        .unwrap();
+    tokio::spawn(client.send_request(req));
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let read = reads.load(Ordering::SeqCst);
+    assert!(read > 0, "the body must be read");
+    assert!(
+        metrics.buffered.get() <= 4 * CHUNK as i64,
+        "buffered {} bytes",
+        metrics.buffered.get()
+    );
+
+    // Buffering stays flat while the upstream is stalled.
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    assert_eq!(reads.load(Ordering::SeqCst), read);
+    assert!(metrics.buffered.get() <= 4 * CHUNK as i64);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn resumes_reading_when_data_is_released() {
+    let metrics = BodyBufferMetrics::default();
+    let (body, reads) = downstream();
+    let mut body =
+        tokio_test::task::spawn(BufferedBody::new(body, Some(2 * CHUNK), metrics.clone()));
+
+    let first = next_data(&mut body);
+    let second = next_data(&mut body);
+    assert!(body.enter(|cx, b| b.poll_frame(cx)).is_pending());
+    assert!(body.enter(|cx, b| b.poll_frame(cx)).is_pending());
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+    assert_eq!(metrics.buffered.get(), 2 * CHUNK as i64);
+
+    // Data is buffered until all references to it are released.
+    let retained = first.clone();
+    drop(first);
+    assert!(!body.is_woken());
+    assert_eq!(metrics.buffered.get(), 2 * CHUNK as i64);
+    drop(retained);
+    assert!(body.is_woken());
+    assert_eq!(metrics.buffered.get(), CHUNK as i64);
+
+    let third = next_data(&mut body);
+    assert_eq!(reads.load(Ordering::SeqCst), 3);
+    assert_eq!(metrics.buffered.get(), 2 * CHUNK as i64);
+
+    // Data outlives the body that yielded it.
+    drop(body);
+    assert_eq!(metrics.buffered.get(), 2 * CHUNK as i64);
+    drop((second, third));
+    assert_eq!(metrics.buffered.get(), 0);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn unlimited_bodies_are_tracked() {
+    let metrics = BodyBufferMetrics::default();
+    let (body, reads) = downstream();
+    let mut body = tokio_test::task::spawn(BufferedBody::new(body, None, metrics.clone()));
+
+    let data = (0..10).map(|_| next_data(&mut body)).collect::<Vec<_>>();
+    assert_eq!(reads.load(Ordering::SeqCst), 10);
+    assert_eq!(metrics.buffered.get(), 10 * CHUNK as i64);
+
+    drop(data);
+    assert_eq!(metrics.buffered.get(), 0);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn limits_by_client_protocol() {
+    let limits = BodyBufferLimits {
+        http1: Some(CHUNK),
+        http2: Some(2 * CHUNK),
+    };
+    let new_svc = svc::layer::Layer::layer(
+        &NewBufferRequestBody::layer(limits, Default::default()),
+        |_: Target| {
+            svc::mk(|req: http::Request<BufferedBody<http::BoxBody>>| {
+                future::ok::<_, Error>(req.into_body().limit)
+            })
+        },
+    );
+
+    let pool = http::h1::PoolSettings {
+        max_idle: 1,
+        idle_timeout: Duration::from_secs(1),
+    };
+    for (params, limit) in [
+        (http::client::Params::Http1(pool), Some(CHUNK)),
+        (
+            http::client::Params::H2(Default::default()),
+            Some(2 * CHUNK),
+        ),
+        (
+            http::client::Params::OrigProtoUpgrade(Default::default(), pool),
+            Some(2 * CHUNK),
+        ),
+    ] {
+        let rsp = new_svc
+            .new_service(Target(params))
+            .ready_oneshot()
+            .await
+            .unwrap()
+            .call(http::Request::new(http::BoxBody::empty()))
+            .await
+            .unwrap();
+        assert_eq!(rsp, limit);
+    }
+}
+
+// === Utils ===
+
+#[derive(Clone, Debug)]
+struct Target(http::client::Params);
+
+impl svc::Param<http::client::Params> for Target {
+    fn param(&self) -> http::client::Params {
+        self.0.clone()
+    }
+}
+
+/// Returns a body that never ends, counting the chunks that are read from it.
+fn downstream() -> (
+    StreamBody<impl stream::Stream<Item = Result<Frame<Bytes>, Error>>>,
+    Arc<AtomicUsize>,
+) {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let body = StreamBody::new(stream::poll_fn({
+        let reads = reads.clone();
+        move |_| {
+            reads.fetch_add(1, Ordering::SeqCst);
+            Poll::Ready(Some(Ok(Frame::data(Bytes::from(vec![0; CHUNK])))))
+        }
+    }));
+    (body, reads)
+}
+
+fn next_data<B>(body: &mut tokio_test::task::Spawn<BufferedBody<B>>) -> Bytes
+where
+    B: Body,
+    B::Error: std::fmt::Debug,
+{
+    match body.enter(|cx, b| b.poll_frame(cx)) {
+        Poll::Ready(Some(Ok(frame))) => frame.into_data().expect("frame must be data"),
+        poll => panic!("unexpected poll: {:?}", poll.map(|f| f.map(|r| r.is_ok()))),
+    }
+}
@@ -3,8 +3,13 @@ use linkerd_proxy_client_policy::FailureAccrual;
 use tracing::{trace_span, Instrument};
 
 mod consecutive_failures;
+mod latency_outliers;
 
 use self::consecutive_failures::ConsecutiveFailures;
+pub(crate) use self::latency_outliers::NewLatencyOutliers;
+pub use self::latency_outliers::{
+    BreakerState, Breakers, EndpointBreakerState, LatencyOutlierConfig,
+};
 
 /// Params configuring a circuit breaker stack.
 #[derive(Copy, Clone, Debug)]
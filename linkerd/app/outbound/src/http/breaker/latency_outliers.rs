@@ -0,0 +1,708 @@
+//! Ejects endpoints whose latency is an outlier relative to their balancer's
+//! other endpoints.
+//!
+//! Failure accrual only reacts to errors, so an endpoint that becomes slow
+//! without failing continues to receive requests. Each balancer's endpoints
+//! form a cohort: the response latency of each endpoint is tracked as an EWMA
+//! and, when an endpoint's latency exceeds the cohort's median by the
+//! configured ratio for a sustained period, the endpoint's gate is shut so that
+//! the balancer does not select it until its ejection backoff elapses. Only a
+//! bounded fraction of a cohort's endpoints may be ejected at once.
+//!
+//! Cohort state is held in a [`Breakers`] registry so that it may be inspected
+//! via the admin server, along with the state of each balancer's endpoint
+//! discovery.
+
+use crate::{
+    metrics::{BalancerMetricsParams, ConcreteLabels},
+    BackendRef, ParentRef,
+};
+use linkerd_app_core::{
+    metrics::prom,
+    proxy::balance::DiscoveryState,
+    svc::{self, gate},
+};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Weak},
+    task::{Context, Poll},
+};
+use tokio::time;
+use tracing::{info_span, Instrument};
+
+/// Configures latency-based outlier detection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatencyOutlierConfig {
+    /// An endpoint is an outlier when its latency EWMA exceeds the median
+    /// latency of its cohort by this multiple.
+    pub max_latency_ratio: f64,
+
+    /// The duration for which an endpoint must remain an outlier before it is
+    /// ejected.
+    pub min_outlier_duration: time::Duration,
+
+    /// The duration of an endpoint's first ejection. The ejection duration
+    /// doubles each time the endpoint is ejected again without first
+    /// recovering, up to `max_ejection`.
+    pub base_ejection: time::Duration,
+
+    /// The maximum duration of an ejection.
+    pub max_ejection: time::Duration,
+
+    /// The maximum fraction of a cohort's endpoints that may be ejected at
+    /// once.
+    pub max_ejected_ratio: f64,
+}
+
+/// Builds a [`NewEndpointLatency`] for each balancer, so that each balancer's
+/// endpoints form a cohort.
+#[derive(Clone, Debug)]
+pub(crate) struct NewLatencyOutliers<N> {
+    config: Option<LatencyOutlierConfig>,
+    breakers: Breakers,
+    inner: N,
+}
+
+/// Wraps each of a balancer's endpoints with a [`RecordLatency`] middleware
+/// and a [`svc::Gate`] that is shut while the endpoint is ejected.
+#[derive(Clone, Debug)]
+pub(crate) struct NewEndpointLatency<N> {
+    cohort: Option<Arc<Cohort>>,
+    inner: N,
+}
+
+/// Records the time until each response's headers are received in the
+/// endpoint's latency EWMA.
+#[derive(Debug)]
+pub(crate) struct RecordLatency<S> {
+    latency: Arc<Latency>,
+    inner: S,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct RecordLatencyFuture<F> {
+    #[pin]
+    inner: F,
+    start: time::Instant,
+    latency: Arc<Latency>,
+}
+
+/// A registry of the latency outlier detectors for all balancers.
+#[derive(Clone, Debug, Default)]
+pub struct Breakers {
+    cohorts: Arc<Mutex<Vec<Weak<Cohort>>>>,
+    metrics: Metrics,
+    balancers: BalancerMetricsParams<ConcreteLabels>,
+}
+
+/// A snapshot of a balancer's endpoint breakers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BreakerState {
+    pub parent: ParentRef,
+    pub backend: BackendRef,
+
+    /// The state of the balancer's endpoint discovery, so that a balancer
+    /// without endpoints can be distinguished from one that cannot resolve
+    /// them.
+    pub discovery: Option<DiscoveryState>,
+    pub endpoints: Vec<EndpointBreakerState>,
+}
+
+/// A snapshot of an endpoint's breaker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndpointBreakerState {
+    pub addr: SocketAddr,
+
+    /// The endpoint's latency EWMA, if any responses have been observed since
+    /// it was last restored.
+    pub latency: Option<time::Duration>,
+
+    /// The time remaining until an ejected endpoint is restored.
+    pub ejection_remaining: Option<time::Duration>,
+
+    /// The number of times the endpoint has been ejected.
+    pub ejections: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Metrics {
+    ejected: prom::Family<ConcreteLabels, prom::Gauge>,
+    ejections: prom::Family<ConcreteLabels, prom::Counter>,
+}
+
+#[derive(Debug)]
+struct Cohort {
+    labels: ConcreteLabels,
+    config: LatencyOutlierConfig,
+    endpoints: Mutex<HashMap<SocketAddr, Endpoint>>,
+    ejected: prom::Gauge,
+    ejections: prom::Counter,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    /// Shared with the endpoint's [`RecordLatency`] middleware. The endpoint
+    /// is removed from the cohort once the middleware is dropped.
+    latency: Arc<Latency>,
+    gate: gate::Tx,
+    outlier_since: Option<time::Instant>,
+    ejected_until: Option<time::Instant>,
+    consecutive_ejections: u32,
+    ejections: u64,
+}
+
+/// An EWMA of an endpoint's response latency, in seconds.
+#[derive(Debug, Default)]
+struct Latency(Mutex<Option<f64>>);
+
+/// The weight given to each new latency observation.
+const EWMA_WEIGHT: f64 = 0.3;
+
+/// The interval at which each cohort's endpoints are evaluated.
+const EVALUATION_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+// === impl NewLatencyOutliers ===
+
+impl<N> NewLatencyOutliers<N> {
+    pub(crate) fn layer(
+        config: Option<LatencyOutlierConfig>,
+        breakers: Breakers,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            config: config.clone(),
+            breakers: breakers.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewLatencyOutliers<N>
+where
+    T: svc::Param<ParentRef> + svc::Param<BackendRef>,
+    N: svc::NewService<T>,
+{
+    type Service = NewEndpointLatency<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let cohort = self.config.clone().map(|config| {
+            self.breakers
+                .cohort(ConcreteLabels(target.param(), target.param()), config)
+        });
+        let inner = self.inner.new_service(target);
+        NewEndpointLatency { cohort, inner }
+    }
+}
+
+// === impl NewEndpointLatency ===
+
+impl<M, N> svc::NewService<(SocketAddr, M)> for NewEndpointLatency<N>
+where
+    N: svc::NewService<(SocketAddr, M)>,
+{
+    type Service = svc::Either<svc::Gate<RecordLatency<N::Service>>, N::Service>;
+
+    fn new_service(&self, target: (SocketAddr, M)) -> Self::Service {
+        let addr = target.0;
+        let inner = self.inner.new_service(target);
+        match self.cohort.as_ref() {
+            Some(cohort) => {
+                let (gate, latency) = cohort.add(addr);
+                svc::Either::Left(svc::Gate::new(gate, RecordLatency { latency, inner }))
+            }
+            None => svc::Either::Right(inner),
+        }
+    }
+}
+
+// === impl RecordLatency ===
+
+impl<Req, S> svc::Service<Req> for RecordLatency<S>
+where
+    S: svc::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = RecordLatencyFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        RecordLatencyFuture {
+            inner: self.inner.call(req),
+            start: time::Instant::now(),
+            latency: self.latency.clone(),
+        }
+    }
+}
+
+impl<F, T, E> Future for RecordLatencyFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    type Output = Result<T, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = futures::ready!(this.inner.poll(cx));
+        // Failures are handled by failure accrual.
+        if res.is_ok() {
+            let elapsed = time::Instant::now().saturating_duration_since(*this.start);
+            this.latency.record(elapsed);
+        }
+        Poll::Ready(res)
+    }
+}
+
+// === impl Breakers ===
+
+impl Breakers {
+    pub(crate) fn register(reg: &mut prom::Registry) -> Self {
+        let metrics = Metrics::default();
+        reg.register(
+            "ejected",
+            "The number of a balancer's endpoints that are ejected due to latency",
+            metrics.ejected.clone(),
+        );
+        reg.register(
+            "ejections",
+            "The number of times a balancer's endpoints have been ejected due to latency",
+            metrics.ejections.clone(),
+        );
+        Self {
+            cohorts: Default::default(),
+            metrics,
+            balancers: Default::default(),
+        }
+    }
+
+    /// Reads each balancer's discovery state from the given balancer metrics.
+    pub(crate) fn with_balancers(self, balancers: BalancerMetricsParams<ConcreteLabels>) -> Self {
+        Self { balancers, ..self }
+    }
+
+    /// Returns a snapshot of the breakers for all balancers.
+    pub fn breakers(&self) -> Vec<BreakerState> {
+        let now = time::Instant::now();
+        let mut breakers = self
+            .cohorts
+            .lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|c| {
+                let discovery = self.balancers.discovery_state(&c.labels);
+                BreakerState {
+                    discovery,
+                    ..c.snapshot(now)
+                }
+            })
+            .collect::<Vec<_>>();
+        breakers.sort_by(|a, b| {
+            let key = |b: &BreakerState| {
+                (
+                    b.parent.namespace().to_string(),
+                    b.parent.name().to_string(),
+                    b.backend.namespace().to_string(),
+                    b.backend.name().to_string(),
+                )
+            };
+            key(a).cmp(&key(b))
+        });
+        breakers
+    }
+
+    /// Creates a new cohort and spawns a task that evaluates it until the
+    /// balancer is dropped.
+    fn cohort(&self, labels: ConcreteLabels, config: LatencyOutlierConfig) -> Arc<Cohort> {
+        let cohort = Arc::new(Cohort {
+            ejected: self.metrics.ejected.get_or_create(&labels).clone(),
+            ejections: self.metrics.ejections.get_or_create(&labels).clone(),
+            labels,
+            config,
+            endpoints: Default::default(),
+        });
+
+        let mut cohorts = self.cohorts.lock();
+        cohorts.retain(|c| c.strong_count() > 0);
+        cohorts.push(Arc::downgrade(&cohort));
+
+        let ConcreteLabels(_, ref backend) = cohort.labels;
+        let span = info_span!(
+            "latency_outliers",
+            ns = %backend.namespace(),
+            name = %backend.name(),
+        );
+        tokio::spawn(Cohort::run(Arc::downgrade(&cohort)).instrument(span.or_current()));
+
+        cohort
+    }
+}
+
+// === impl Cohort ===
+
+impl Cohort {
+    async fn run(cohort: Weak<Self>) {
+        let mut interval = time::interval(EVALUATION_INTERVAL);
+        interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let Some(cohort) = cohort.upgrade() else {
+                tracing::debug!("Balancer dropped");
+                return;
+            };
+            cohort.evaluate(time::Instant::now());
+        }
+    }
+
+    fn add(&self, addr: SocketAddr) -> (gate::Rx, Arc<Latency>) {
+        let (tx, rx) = gate::channel();
+        let latency = Arc::new(Latency::default());
+        self.endpoints.lock().insert(
+            addr,
+            Endpoint {
+                latency: latency.clone(),
+                gate: tx,
+                outlier_since: None,
+                ejected_until: None,
+                consecutive_ejections: 0,
+                ejections: 0,
+            },
+        );
+        (rx, latency)
+    }
+
+    /// Restores endpoints whose ejections have elapsed and ejects endpoints
+    /// that have been outliers for at least the configured duration.
+    fn evaluate(&self, now: time::Instant) {
+        let mut endpoints = self.endpoints.lock();
+        endpoints.retain(|_, ep| Arc::strong_count(&ep.latency) > 1);
+
+        for (addr, ep) in endpoints.iter_mut() {
+            if ep.ejected_until.is_some_and(|until| until <= now) {
+                tracing::info!(%addr, "Restoring endpoint");
+                ep.ejected_until = None;
+                ep.latency.reset();
+                let _ = ep.gate.open();
+            }
+        }
+
+        let mut ejected = endpoints
+            .values()
+            .filter(|ep| ep.ejected_until.is_some())
+            .count();
+        let max_ejected = (endpoints.len() as f64 * self.config.max_ejected_ratio).floor() as usize;
+
+        // The lower median of the latencies of endpoints that are not ejected.
+        let mut latencies = endpoints
+            .values()
+            .filter(|ep| ep.ejected_until.is_none())
+            .filter_map(|ep| ep.latency.get())
+            .collect::<Vec<_>>();
+        latencies.sort_by(f64::total_cmp);
+        let median = match latencies.len() {
+            0 | 1 => None,
+            n => Some(latencies[(n - 1) / 2]),
+        };
+
+        for (addr, ep) in endpoints.iter_mut() {
+            if ep.ejected_until.is_some() {
+                continue;
+            }
+            let (Some(median), Some(latency)) = (median, ep.latency.get()) else {
+                ep.outlier_since = None;
+                continue;
+            };
+
+            if latency <= median * self.config.max_latency_ratio {
+                ep.outlier_since = None;
+                ep.consecutive_ejections = 0;
+                continue;
+            }
+
+            let since = *ep.outlier_since.get_or_insert(now);
+            if now.saturating_duration_since(since) < self.config.min_outlier_duration {
+                tracing::debug!(%addr, latency, median, "Endpoint is an outlier");
+                continue;
+            }
+            if ejected >= max_ejected {
+                tracing::debug!(%addr, ejected, "Too many endpoints ejected");
+                continue;
+            }
+
+            let ejection = self
+                .config
+                .base_ejection
+                .checked_mul(2_u32.saturating_pow(ep.consecutive_ejections))
+                .unwrap_or(self.config.max_ejection)
+                .min(self.config.max_ejection);
+            tracing::info!(%addr, latency, median, ?ejection, "Ejecting endpoint");
+            ep.ejected_until = Some(now + ejection);
+            ep.outlier_since = None;
+            ep.consecutive_ejections = ep.consecutive_ejections.saturating_add(1);
+            ep.ejections += 1;
+            let _ = ep.gate.shut();
+            self.ejections.inc();
+            ejected += 1;
+        }
+
+        self.ejected.set(ejected as i64);
+    }
+
+    fn snapshot(&self, now: time::Instant) -> BreakerState {
+        let ConcreteLabels(parent, backend) = self.labels.clone();
+        let mut endpoints = self
+            .endpoints
+            .lock()
+            .iter()
+            .map(|(addr, ep)| EndpointBreakerState {
+                addr: *addr,
+                latency: ep.latency.get().map(time::Duration::from_secs_f64),
+                ejection_remaining: ep
+                    .ejected_until
+                    .map(|until| until.saturating_duration_since(now)),
+                ejections: ep.ejections,
+            })
+            .collect::<Vec<_>>();
+        endpoints.sort_by_key(|ep| ep.addr);
+        BreakerState {
+            parent,
+            backend,
+            discovery: None,
+            endpoints,
+        }
+    }
+}
+
+// === impl Latency ===
+
+impl Latency {
+    fn record(&self, latency: time::Duration) {
+        let latency = latency.as_secs_f64();
+        let mut ewma = self.0.lock();
+        *ewma = Some(match *ewma {
+            Some(prior) => prior + EWMA_WEIGHT * (latency - prior),
+            None => latency,
+        });
+    }
+
+    fn get(&self) -> Option<f64> {
+        *self.0.lock()
+    }
+
+    fn reset(&self) {
+        *self.0.lock() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+    use linkerd_app_core::svc::{Service, ServiceExt};
+    use linkerd_proxy_client_policy::Meta;
+    use std::convert::Infallible;
+
+    type Sim = svc::Either<
+        svc::Gate<RecordLatency<svc::BoxService<(), usize, Infallible>>>,
+        svc::BoxService<(), usize, Infallible>,
+    >;
+
+    fn config() -> LatencyOutlierConfig {
+        LatencyOutlierConfig {
+            max_latency_ratio: 3.0,
+            min_outlier_duration: time::Duration::from_secs(2),
+            base_ejection: time::Duration::from_secs(10),
+            max_ejection: time::Duration::from_secs(60),
+            max_ejected_ratio: 0.5,
+        }
+    }
+
+    fn labels() -> ConcreteLabels {
+        ConcreteLabels(
+            ParentRef(Meta::new_default("parent")),
+            BackendRef(Meta::new_default("backend")),
+        )
+    }
+
+    /// Builds simulated endpoints that respond with their index after a
+    /// configurable latency.
+    fn endpoints(cohort: Arc<Cohort>, latencies: &[Arc<Mutex<time::Duration>>]) -> Vec<Sim> {
+        let new = NewEndpointLatency {
+            cohort: Some(cohort),
+            inner: |(addr, latency): (SocketAddr, Arc<Mutex<time::Duration>>)| {
+                let idx = addr.port() as usize;
+                svc::BoxService::new(svc::service_fn(move |()| {
+                    let latency = *latency.lock();
+                    async move {
+                        time::sleep(latency).await;
+                        Ok::<_, Infallible>(idx)
+                    }
+                }))
+            },
+        };
+        latencies
+            .iter()
+            .enumerate()
+            .map(|(idx, latency)| {
+                let addr = SocketAddr::new([192, 0, 2, 1].into(), idx as u16);
+                svc::NewService::new_service(&new, (addr, latency.clone()))
+            })
+            .collect()
+    }
+
+    /// Sends requests, one at a time, to a simulated round-robin balancer
+    /// that skips endpoints that are not ready. Returns the number of requests
+    /// served by each endpoint.
+    async fn send(endpoints: &mut [Sim], requests: usize) -> Vec<usize> {
+        let mut served = vec![0; endpoints.len()];
+        let mut next = 0;
+        for _ in 0..requests {
+            let mut sent = false;
+            for _ in 0..endpoints.len() {
+                let ep = &mut endpoints[next % served.len()];
+                next += 1;
+                if let Some(ready) = ep.ready().now_or_never() {
+                    let idx = ready.unwrap().call(()).await.unwrap();
+                    served[idx] += 1;
+                    sent = true;
+                    break;
+                }
+            }
+            assert!(sent, "an endpoint must be ready");
+        }
+        served
+    }
+
+    fn latencies(ms: &[u64]) -> Vec<Arc<Mutex<time::Duration>>> {
+        ms.iter()
+            .map(|ms| Arc::new(Mutex::new(time::Duration::from_millis(*ms))))
+            .collect()
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn slow_endpoint_ejected_and_restored() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let breakers = Breakers::default();
+        let cohort = breakers.cohort(labels(), config());
+        let latencies = latencies(&[10, 10, 100]);
+        let mut eps = endpoints(cohort.clone(), &latencies);
+
+        // The slow endpoint is an outlier for long enough to be ejected.
+        let served = send(&mut eps, 100).await;
+        assert!(served[2] > 0, "the slow endpoint must initially be used");
+        let [state] = &breakers.breakers()[..] else {
+            panic!("expected a single cohort");
+        };
+        assert_eq!(state.endpoints.len(), 3);
+        assert!(state.endpoints[2].ejection_remaining.is_some());
+        assert_eq!(state.endpoints[2].ejections, 1);
+        assert!(state.endpoints[..2]
+            .iter()
+            .all(|ep| ep.ejection_remaining.is_none()));
+        assert_eq!(cohort.ejections.get(), 1);
+        assert_eq!(cohort.ejected.get(), 1);
+
+        // Traffic shifts away from the ejected endpoint.
+        let served = send(&mut eps, 30).await;
+        assert_eq!(served, vec![15, 15, 0]);
+
+        // Once the endpoint recovers and its ejection elapses, traffic returns.
+        *latencies[2].lock() = time::Duration::from_millis(10);
+        time::sleep(config().base_ejection).await;
+        let served = send(&mut eps, 30).await;
+        assert_eq!(served, vec![10, 10, 10]);
+        time::sleep(config().min_outlier_duration * 2).await;
+        let [state] = &breakers.breakers()[..] else {
+            panic!("expected a single cohort");
+        };
+        assert!(state
+            .endpoints
+            .iter()
+            .all(|ep| ep.ejection_remaining.is_none()));
+        assert_eq!(cohort.ejected.get(), 0);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ejections_are_limited() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let breakers = Breakers::default();
+        let cohort = breakers.cohort(
+            labels(),
+            LatencyOutlierConfig {
+                max_ejected_ratio: 0.25,
+                ..config()
+            },
+        );
+        let latencies = latencies(&[10, 10, 100, 100]);
+        let mut eps = endpoints(cohort.clone(), &latencies);
+
+        send(&mut eps, 100).await;
+        assert_eq!(
+            cohort.ejections.get(),
+            1,
+            "only one endpoint may be ejected"
+        );
+        let served = send(&mut eps, 30).await;
+        assert_eq!(
+            served.iter().filter(|n| **n == 0).count(),
+            1,
+            "exactly one endpoint must be ejected: {served:?}"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ejections_back_off() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let breakers = Breakers::default();
+        let cohort = breakers.cohort(labels(), config());
+        let latencies = latencies(&[10, 10, 100]);
+        let mut eps = endpoints(cohort.clone(), &latencies);
+
+        send(&mut eps, 100).await;
+        assert_eq!(cohort.ejections.get(), 1);
+
+        // The endpoint remains slow after it is restored, so it is ejected
+        // again for twice as long.
+        time::sleep(config().base_ejection).await;
+        send(&mut eps, 100).await;
+        assert_eq!(cohort.ejections.get(), 2);
+        let [state] = &breakers.breakers()[..] else {
+            panic!("expected a single cohort");
+        };
+        let remaining = state.endpoints[2].ejection_remaining.unwrap();
+        assert!(
+            remaining > config().base_ejection,
+            "ejection must back off: {remaining:?}"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn dropped_endpoints_removed() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let breakers = Breakers::default();
+        let cohort = breakers.cohort(labels(), config());
+        let mut eps = endpoints(cohort.clone(), &latencies(&[10, 10]));
+        send(&mut eps, 2).await;
+        assert_eq!(breakers.breakers()[0].endpoints.len(), 2);
+
+        eps.pop();
+        time::sleep(EVALUATION_INTERVAL).await;
+        assert_eq!(breakers.breakers()[0].endpoints.len(), 1);
+
+        drop((eps, cohort));
+        time::sleep(EVALUATION_INTERVAL).await;
+        assert!(breakers.breakers().is_empty());
+    }
+}
@@ -1,7 +1,13 @@
 //! A stack that (optionally) resolves a service to a set of endpoint replicas
 //! and distributes HTTP requests among them.
 
-use super::{balance::EwmaConfig, client, handle_proxy_error_headers};
+use super::{
+    backend_faults,
+    balance::EwmaConfig,
+    client, handle_proxy_error_headers,
+    timings::{self, Connections},
+    upgrade_probe::{EndpointProtocol, ProbeTarget, UpgradeProbes},
+};
 use crate::{
     http, stack_labels,
     zone::{tcp_zone_labels, TcpZoneLabels},
@@ -25,8 +31,13 @@ use std::{fmt::Debug, net::SocketAddr, sync::Arc};
 use tracing::info_span;
 
 mod balance;
+mod hairpin;
+mod span_events;
 
-pub use self::balance::BalancerMetrics;
+pub use self::{
+    balance::{BalancerMetrics, SourceAffinityConfig},
+    hairpin::{Hairpin, HairpinConfig},
+};
 
 /// Parameter configuring dispatcher behavior.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -49,6 +60,9 @@ pub struct DispatcherFailed(Arc<str>);
 pub struct Endpoint<T> {
     addr: Remote<ServerAddr>,
     is_local: bool,
+    /// Set when the endpoint is the local workload, so that requests are
+    /// forwarded to the application over the loopback interface.
+    hairpin: bool,
     metadata: Arc<Metadata>,
     parent: T,
     queue: QueueConfig,
@@ -56,6 +70,10 @@ pub struct Endpoint<T> {
 
     http1: http::h1::PoolSettings,
     http2: http::h2::ClientParams,
+    upgrade_probes: Option<UpgradeProbes>,
+    /// Set when requests report their timings, so that requests may report
+    /// the time spent establishing the endpoint's connections.
+    connections: Option<Connections>,
 }
 
 // === impl Outbound ===
@@ -91,9 +109,21 @@ impl<N> Outbound<N> {
     {
         self.map_stack(|config, rt, inner| {
             let inbound_ips = config.inbound_ips.clone();
+            let hairpin = config.http_hairpin.clone();
+
+            // Annotate sampled spans with the endpoint that serves each
+            // request.
+            let inner = inner
+                .push(span_events::NewRecordEndpoint::layer())
+                .push_on_service(timings::Stamp::layer(timings::Stage::Balance))
+                .push(hairpin::NewMarkHairpin::layer())
+                .push(backend_faults::NewInjectBackendFaults::layer(
+                    rt.metrics.prom.http.backend_faults().clone(),
+                ));
 
             // TODO(ver) Configure this from discovery.
             let queue = config.http_request_queue;
+            let ewma_overrides = config.balancer_ewma;
 
             let forward = inner
                 .clone()
@@ -111,6 +141,10 @@ impl<N> Outbound<N> {
             });
 
             let ConnectConfig { http1, http2, .. } = config.proxy.connect.clone();
+            let mesh_adaptive = config.http2_mesh_adaptive_flow_control;
+            let upgrade_probes = rt.upgrade_probes.clone();
+            let source_affinity = config.http_source_affinity.clone();
+            let record_connections = config.http_request_timings_threshold.is_some();
 
             inner
                 .push(balance::Balance::layer(config, rt, resolve))
@@ -120,19 +154,27 @@ impl<N> Outbound<N> {
                     move |parent: T| -> Result<_, Infallible> {
                         Ok(match parent.param() {
                             Dispatch::Balance(addr, ewma) => {
+                                let affinity = source_affinity
+                                    .as_ref()
+                                    .and_then(|config| config.capacity(&addr));
                                 svc::Either::Left(svc::Either::Left(balance::Balance {
                                     addr,
-                                    ewma,
+                                    ewma: ewma_overrides.apply(ewma),
                                     parent,
                                     queue,
+                                    affinity,
                                 }))
                             }
                             Dispatch::Forward(addr, metadata) => {
                                 svc::Either::Left(svc::Either::Right({
                                     let is_local = inbound_ips.contains(&addr.ip());
-                                    let http2 = http2.override_from(metadata.http2_client_params());
+                                    let hairpin = hairpin.as_ref().is_some_and(|h| {
+                                        h.is_local_workload(&inbound_ips, addr.into(), &metadata)
+                                    });
+                                    let http2 = endpoint_http2(&http2, mesh_adaptive, &metadata);
                                     Endpoint {
                                         is_local,
+                                        hairpin,
                                         addr,
                                         metadata,
                                         parent,
@@ -140,6 +182,8 @@ impl<N> Outbound<N> {
                                         close_server_connection_on_remote_proxy_error: true,
                                         http1,
                                         http2,
+                                        upgrade_probes: upgrade_probes.clone(),
+                                        connections: record_connections.then(Connections::default),
                                     }
                                 }))
                             }
@@ -148,16 +192,85 @@ impl<N> Outbound<N> {
                     },
                     svc::stack(fail).check_new_clone().into_inner(),
                 )
+                .push_on_service(span_events::MarkEnqueued::layer())
+                .push_on_service(timings::Stamp::layer(timings::Stage::Queue))
                 .arc_new_clone_http()
         })
     }
 }
 
+/// Returns the HTTP/2 client settings for an endpoint.
+///
+/// Meshed endpoints, i.e. those with a TLS identity, use adaptive flow control
+/// if `mesh_adaptive` is set. Settings from discovery take precedence.
+fn endpoint_http2(
+    http2: &http::h2::ClientParams,
+    mesh_adaptive: bool,
+    metadata: &Metadata,
+) -> http::h2::ClientParams {
+    let mut params = http2.clone();
+    if mesh_adaptive && metadata.identity().is_some() {
+        params.flow_control = Some(http::h2::FlowControl::Adaptive);
+    }
+    params.override_from(metadata.http2_client_params())
+}
+
+// === impl Dispatch ===
+
+impl Dispatch {
+    /// Returns the port of the target to which requests are dispatched.
+    pub(crate) fn port(&self) -> Option<u16> {
+        match self {
+            Self::Balance(addr, _) => Some(addr.port()),
+            Self::Forward(Remote(ServerAddr(addr)), _) => Some(addr.port()),
+            Self::Fail { .. } => None,
+        }
+    }
+}
+
 // === impl Endpoint ===
 
+impl<T> Endpoint<T> {
+    /// Determines the protocol with which the endpoint's proxy communicates,
+    /// from its protocol hint or, if it's meshed without a hint, from the
+    /// result of an upgrade probe.
+    fn protocol(&self) -> EndpointProtocol {
+        match self.metadata.protocol_hint() {
+            ProtocolHint::Http2 => EndpointProtocol::Http2,
+            ProtocolHint::Opaque => EndpointProtocol::Http1,
+            ProtocolHint::Unknown => match self.upgrade_probes.as_ref() {
+                Some(probes) if self.metadata.identity().is_some() => {
+                    probes.protocol(self.addr.into(), &self.metadata)
+                }
+                _ => EndpointProtocol::Http1,
+            },
+        }
+    }
+}
+
 impl<T> svc::Param<Remote<ServerAddr>> for Endpoint<T> {
     fn param(&self) -> Remote<ServerAddr> {
-        self.addr
+        if self.hairpin {
+            return Remote(ServerAddr(hairpin::loopback(self.addr.into())));
+        }
+        // Connections are established with the endpoint's override address,
+        // if it has one. Its identity and labels are not affected.
+        match self.metadata.address_override() {
+            Some(addr) if !self.is_local => Remote(ServerAddr(addr)),
+            _ => self.addr,
+        }
+    }
+}
+
+impl<T> svc::Param<Option<ConcreteAddr>> for Endpoint<T>
+where
+    T: svc::Param<Dispatch>,
+{
+    fn param(&self) -> Option<ConcreteAddr> {
+        match self.parent.param() {
+            Dispatch::Balance(addr, _) => Some(ConcreteAddr(addr)),
+            _ => None,
+        }
     }
 }
 
@@ -207,6 +320,7 @@ impl<T> svc::Param<handle_proxy_error_headers::CloseServerConnection> for Endpoi
 impl<T> svc::Param<transport::labels::Key> for Endpoint<T>
 where
     T: svc::Param<Option<http::uri::Authority>>,
+    T: svc::Param<http::Variant>,
 {
     fn param(&self) -> transport::labels::Key {
         transport::labels::Key::OutboundClient(self.param())
@@ -216,6 +330,7 @@ where
 impl<T> svc::Param<OutboundEndpointLabels> for Endpoint<T>
 where
     T: svc::Param<Option<http::uri::Authority>>,
+    T: svc::Param<http::Variant>,
 {
     fn param(&self) -> OutboundEndpointLabels {
         OutboundEndpointLabels {
@@ -224,6 +339,7 @@ where
             zone_locality: self.param(),
             server_id: self.param(),
             target_addr: self.addr.into(),
+            http_version: Some(self.param()),
         }
     }
 }
@@ -243,6 +359,7 @@ impl<T> svc::Param<TcpZoneLabels> for Endpoint<T> {
 impl<T> svc::Param<EndpointLabels> for Endpoint<T>
 where
     T: svc::Param<Option<http::uri::Authority>>,
+    T: svc::Param<http::Variant>,
 {
     fn param(&self) -> EndpointLabels {
         EndpointLabels::Outbound(self.param())
@@ -308,14 +425,14 @@ where
                 if self.is_local {
                     return client::Params::Http1(self.http1);
                 }
-                match self.metadata.protocol_hint() {
-                    // If the protocol hint is unknown or indicates that the
-                    // endpoint's proxy will treat connections as opaque, do not
-                    // perform a protocol upgrade to HTTP/2.
-                    ProtocolHint::Unknown | ProtocolHint::Opaque => {
+                match self.protocol() {
+                    // If the endpoint's protocol is unknown or it will treat
+                    // connections as opaque, do not perform a protocol upgrade
+                    // to HTTP/2.
+                    EndpointProtocol::Unknown | EndpointProtocol::Http1 => {
                         client::Params::Http1(self.http1)
                     }
-                    ProtocolHint::Http2 => {
+                    EndpointProtocol::Http2 => {
                         client::Params::OrigProtoUpgrade(self.http2.clone(), self.http1)
                     }
                 }
@@ -324,6 +441,42 @@ where
     }
 }
 
+impl<T> svc::Param<Option<ProbeTarget>> for Endpoint<T>
+where
+    T: svc::Param<http::Variant>,
+{
+    fn param(&self) -> Option<ProbeTarget> {
+        // Only meshed endpoints without a protocol hint are probed, and only
+        // when their HTTP/1 requests could be upgraded. Endpoints that use the
+        // transport header are not probed, since their connections already
+        // negotiate ALPN. Probes are keyed by the endpoint's address, so
+        // endpoints that are dialed through an override address are not
+        // probed.
+        let probes = self.upgrade_probes.clone()?;
+        if self.is_local
+            || self.metadata.protocol_hint() != ProtocolHint::Unknown
+            || self.metadata.tagged_transport_port().is_some()
+            || self.metadata.authority_override().is_some()
+            || self.metadata.address_override().is_some()
+            || svc::Param::<http::Variant>::param(self) != http::Variant::Http1
+        {
+            return None;
+        }
+        Some(ProbeTarget {
+            addr: self.addr,
+            tls: self.metadata.identity()?.clone(),
+            metadata: self.metadata.clone(),
+            probes,
+        })
+    }
+}
+
+impl<T> svc::Param<Option<Connections>> for Endpoint<T> {
+    fn param(&self) -> Option<Connections> {
+        self.connections.clone()
+    }
+}
+
 impl<T> svc::Param<ProtocolHint> for Endpoint<T> {
     fn param(&self) -> ProtocolHint {
         self.metadata.protocol_hint()
@@ -331,7 +484,10 @@ impl<T> svc::Param<ProtocolHint> for Endpoint<T> {
 }
 
 // TODO(ver) move this into the endpoint stack?
-impl<T> tap::Inspect for Endpoint<T> {
+impl<T> tap::Inspect for Endpoint<T>
+where
+    T: svc::Param<Option<http::uri::Authority>>,
+{
     fn src_addr<B>(&self, req: &http::Request<B>) -> Option<SocketAddr> {
         req.extensions().get::<http::ClientHandle>().map(|c| c.addr)
     }
@@ -345,7 +501,21 @@ impl<T> tap::Inspect for Endpoint<T> {
     }
 
     fn dst_labels<B>(&self, _: &http::Request<B>) -> Option<tap::Labels> {
-        Some(self.metadata.labels())
+        let labels = self.metadata.labels();
+        if !labels.is_empty() {
+            return Some(labels);
+        }
+
+        // Endpoints that the control plane does not label, e.g. off-cluster
+        // authorities, are labeled with their logical name, if it is known.
+        let authority: Option<http::uri::Authority> = self.parent.param();
+        match authority {
+            Some(authority) => {
+                let labels = std::iter::once(("authority".to_string(), authority.to_string()));
+                Some(Arc::new(labels.collect()))
+            }
+            None => Some(labels),
+        }
     }
 
     fn dst_tls<B>(&self, _: &http::Request<B>) -> tls::ConditionalClientTls {
@@ -354,12 +524,101 @@ impl<T> tap::Inspect for Endpoint<T> {
 
     fn route_labels<B>(&self, req: &http::Request<B>) -> Option<tap::Labels> {
         // FIXME(ver) create a dedicated extension type for route labels.
-        req.extensions()
+        let labels = req
+            .extensions()
             .get::<profiles::http::Route>()
-            .map(|r| r.labels().clone())
+            .map(|r| r.labels().clone());
+
+        // Requests that override their route configuration with a debug header
+        // are labeled with its directives.
+        let Some(debug) = req.extensions().get::<http::policy::RouteDebug>() else {
+            return labels;
+        };
+        let mut labels = labels.map(|l| (*l).clone()).unwrap_or_default();
+        labels.insert("route_debug".to_string(), debug.to_string());
+        Some(Arc::new(labels))
     }
 
     fn is_outbound<B>(&self, _: &http::Request<B>) -> bool {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd2_proxy_api::destination;
+    use linkerd_app_core::proxy::api_resolve::pb;
+
+    fn metadata(identity: Option<&str>, http2: Option<destination::Http2ClientParams>) -> Metadata {
+        use destination::tls_identity::{DnsLikeIdentity, Strategy};
+
+        let pb = destination::WeightedAddr {
+            addr: Some(SocketAddr::new([192, 0, 2, 30].into(), 8080).into()),
+            weight: 1,
+            tls_identity: identity.map(|name| destination::TlsIdentity {
+                strategy: Some(Strategy::DnsLikeIdentity(DnsLikeIdentity {
+                    name: name.to_string(),
+                })),
+                ..Default::default()
+            }),
+            http2,
+            ..Default::default()
+        };
+        let (_, metadata) = pb::to_addr_meta(pb, &Default::default()).expect("valid endpoint");
+        metadata
+    }
+
+    #[test]
+    fn adaptive_flow_control_for_meshed_endpoints() {
+        const ID: &str = "foo.ns.serviceaccount.identity.linkerd.cluster.local";
+
+        let http2 = http::h2::ClientParams {
+            flow_control: Some(http::h2::FlowControl::Fixed {
+                initial_stream_window_size: 1_000,
+                initial_connection_window_size: 10_000,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            endpoint_http2(&http2, true, &metadata(Some(ID), None)).flow_control,
+            Some(http::h2::FlowControl::Adaptive),
+        );
+
+        // Unmeshed endpoints use the connect settings.
+        assert_eq!(endpoint_http2(&http2, true, &metadata(None, None)), http2);
+
+        // Adaptive flow control may be disabled.
+        assert_eq!(
+            endpoint_http2(&http2, false, &metadata(Some(ID), None)),
+            http2
+        );
+    }
+
+    #[test]
+    fn discovery_overrides_adaptive_flow_control() {
+        use destination::http2_client_params::FlowControl;
+
+        const ID: &str = "foo.ns.serviceaccount.identity.linkerd.cluster.local";
+
+        let metadata = metadata(
+            Some(ID),
+            Some(destination::Http2ClientParams {
+                flow_control: Some(FlowControl {
+                    initial_connection_window_size: 100,
+                    initial_stream_window_size: 10,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        );
+        assert_eq!(
+            endpoint_http2(&Default::default(), true, &metadata).flow_control,
+            Some(http::h2::FlowControl::Fixed {
+                initial_stream_window_size: 10,
+                initial_connection_window_size: 100,
+            }),
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use super::Endpoint;
 use crate::{
-    http::{self, balance, breaker},
+    http::{self, balance, breaker, Connections},
     metrics::{BalancerMetricsParams, ConcreteLabels},
-    stack_labels, BackendRef, ParentRef,
+    stack_labels,
+    topology::HintedResolve,
+    BackendRef, ParentRef,
 };
 use linkerd_app_core::{
     classify,
@@ -13,7 +15,7 @@ use linkerd_app_core::{
     },
     svc,
     transport::addrs::*,
-    Error, NameAddr,
+    Error, NameAddr, NameMatch,
 };
 use linkerd_proxy_client_policy::FailureAccrual;
 use std::{fmt::Debug, net::SocketAddr};
@@ -26,6 +28,22 @@ pub struct Balance<T> {
     pub ewma: balance::EwmaConfig,
     pub queue: QueueConfig,
     pub parent: T,
+    /// The number of clients whose endpoints are tracked, if the balancer
+    /// uses source affinity.
+    pub affinity: Option<usize>,
+}
+
+/// Configures HTTP balancers to dispatch each client's requests to the
+/// endpoint most recently selected for it, so long as that endpoint remains
+/// in the balancer and is ready.
+#[derive(Clone, Debug)]
+pub struct SourceAffinityConfig {
+    /// Matches the names of the backends whose balancers use source affinity.
+    pub backends: NameMatch,
+
+    /// The maximum number of clients whose endpoints are tracked by each
+    /// balancer. The least recently used clients are forgotten first.
+    pub capacity: usize,
 }
 
 /// Wraps errors encountered in this module.
@@ -59,6 +77,17 @@ impl<T> svc::Param<svc::queue::Timeout> for Balance<T> {
     }
 }
 
+impl<T> svc::Param<Option<http::balance::SourceAffinity<http::Request<http::BoxBody>>>>
+    for Balance<T>
+{
+    fn param(&self) -> Option<http::balance::SourceAffinity<http::Request<http::BoxBody>>> {
+        self.affinity.map(|capacity| http::balance::SourceAffinity {
+            capacity,
+            source: http::balance::client_ip,
+        })
+    }
+}
+
 impl<T: svc::Param<ParentRef>> svc::Param<ParentRef> for Balance<T> {
     fn param(&self) -> ParentRef {
         self.parent.param()
@@ -101,28 +130,50 @@ where
 
         // TODO(ver) Configure from discovery.
         let ConnectConfig { http1, http2, .. } = config.proxy.connect.clone();
+        let mesh_adaptive = config.http2_mesh_adaptive_flow_control;
 
         let inbound_ips = config.inbound_ips.clone();
+        let hairpin = config.http_hairpin.clone();
         let stack_metrics = rt.metrics.proxy.stack.clone();
-        let balance_metrics = rt.metrics.prom.http.balancer.clone();
+        let balance_metrics = rt
+            .metrics
+            .prom
+            .http
+            .balancer
+            .clone()
+            .with_endpoint_load(config.balancer_endpoint_load_metrics);
+        let latency_outliers = config.http_latency_outliers.clone();
+        let breakers = rt.metrics.prom.http.breakers.clone();
+        let upgrade_probes = rt.upgrade_probes.clone();
+        let record_connections = config.http_request_timings_threshold.is_some();
 
-        let resolve = svc::stack(resolve.into_service())
-            .push_map_target(|t: Self| ConcreteAddr(t.addr))
-            .into_inner();
+        let resolve = HintedResolve::new(
+            config.topology_hints.clone(),
+            rt.metrics.prom.topology.clone(),
+            svc::stack(resolve.into_service())
+                .push_map_target(|t: Self| ConcreteAddr(t.addr))
+                .into_inner(),
+        );
 
         svc::layer::mk(move |inner: N| {
             let endpoint = svc::stack(inner)
                 .push_map_target({
                     let http2 = http2.clone();
                     let inbound_ips = inbound_ips.clone();
+                    let hairpin = hairpin.clone();
+                    let upgrade_probes = upgrade_probes.clone();
                     move |((addr, metadata), target): ((SocketAddr, Metadata), Self)| {
                         tracing::trace!(%addr, ?metadata, ?target, "Resolved endpoint");
                         let is_local = inbound_ips.contains(&addr.ip());
-                        let http2 = http2.override_from(metadata.http2_client_params());
+                        let hairpin = hairpin
+                            .as_ref()
+                            .is_some_and(|h| h.is_local_workload(&inbound_ips, addr, &metadata));
+                        let http2 = super::endpoint_http2(&http2, mesh_adaptive, &metadata);
                         Endpoint {
                             addr: Remote(ServerAddr(addr)),
                             metadata: metadata.into(),
                             is_local,
+                            hairpin,
                             parent: target.parent,
                             queue: http_queue,
                             // We don't close server-side connections when we
@@ -132,6 +183,8 @@ where
                             // TODO(ver) Configure from metadata.
                             http1,
                             http2,
+                            upgrade_probes: upgrade_probes.clone(),
+                            connections: record_connections.then(Connections::default),
                         }
                     }
                 })
@@ -146,6 +199,11 @@ where
                         }
                     }),
                 )
+                // Eject endpoints whose latency is an outlier, if configured.
+                .push(breaker::NewLatencyOutliers::layer(
+                    latency_outliers.clone(),
+                    breakers.clone(),
+                ))
                 .push_on_service(svc::OnServiceLayer::new(
                     stack_metrics.layer(stack_labels("http", "endpoint")),
                 ))
@@ -179,6 +237,18 @@ where
     }
 }
 
+// === impl SourceAffinityConfig ===
+
+impl SourceAffinityConfig {
+    /// Returns the capacity of the source affinity map for the backend, if its
+    /// balancer uses source affinity.
+    pub(super) fn capacity(&self, backend: &NameAddr) -> Option<usize> {
+        self.backends
+            .matches(backend.name())
+            .then_some(self.capacity)
+    }
+}
+
 // === impl BalanceError ===
 
 impl<T> From<(&Balance<T>, Error)> for BalanceError
@@ -0,0 +1,188 @@
+//! Short-circuits requests that an application sends to itself.
+//!
+//! When an application calls a service that it backs, e.g. via the service's
+//! cluster IP, its own pod may be selected as the endpoint. Rather than sending
+//! the request out of the pod to be received by the inbound proxy, these
+//! "hairpin" requests are forwarded directly to the application over the
+//! loopback interface, without mTLS or any of the protocol handling that
+//! applies to remote endpoints.
+
+use super::Endpoint;
+use futures::prelude::*;
+use linkerd_app_core::{
+    identity,
+    proxy::{api_resolve::Metadata, http},
+    svc,
+};
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    task::{Context, Poll},
+};
+
+/// Configures whether requests to endpoints on the local workload are
+/// forwarded over the loopback interface.
+#[derive(Clone, Debug, Default)]
+pub struct HairpinConfig {
+    /// The local workload's identity, if it is known.
+    ///
+    /// Because an identity may be shared by other workloads (e.g. by
+    /// host-networked pods on the same node), endpoints on the proxy's inbound
+    /// IPs are only forwarded over the loopback interface if they have no
+    /// identity or if their identity matches the local identity.
+    pub local_id: Option<identity::Id>,
+}
+
+/// A response extension indicating that a response was served by the local
+/// workload over the loopback interface.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Hairpin(());
+
+#[derive(Clone, Debug)]
+pub(super) struct NewMarkHairpin<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct MarkHairpin<S> {
+    hairpin: bool,
+    inner: S,
+}
+
+// === impl HairpinConfig ===
+
+impl HairpinConfig {
+    /// Returns true if the endpoint is the local workload.
+    pub(super) fn is_local_workload(
+        &self,
+        inbound_ips: &HashSet<IpAddr>,
+        addr: SocketAddr,
+        metadata: &Metadata,
+    ) -> bool {
+        let id = metadata.identity().map(|tls| &tls.server_id.0);
+        self.is_local(inbound_ips, addr, id)
+    }
+
+    fn is_local(
+        &self,
+        inbound_ips: &HashSet<IpAddr>,
+        addr: SocketAddr,
+        id: Option<&identity::Id>,
+    ) -> bool {
+        if !inbound_ips.contains(&addr.ip()) {
+            return false;
+        }
+        match (id, self.local_id.as_ref()) {
+            (None, _) => true,
+            (Some(id), Some(local_id)) => id == local_id,
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Returns the loopback address on which the local workload serves `addr`.
+pub(super) fn loopback(addr: SocketAddr) -> SocketAddr {
+    let ip = match addr.ip() {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+    };
+    SocketAddr::new(ip, addr.port())
+}
+
+// === impl NewMarkHairpin ===
+
+impl<N> NewMarkHairpin<N> {
+    pub(super) fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<Endpoint<T>> for NewMarkHairpin<N>
+where
+    N: svc::NewService<Endpoint<T>>,
+{
+    type Service = MarkHairpin<N::Service>;
+
+    fn new_service(&self, target: Endpoint<T>) -> Self::Service {
+        MarkHairpin {
+            hairpin: target.hairpin,
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl MarkHairpin ===
+
+impl<Req, B, S> svc::Service<Req> for MarkHairpin<S>
+where
+    S: svc::Service<Req, Response = http::Response<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = future::Either<
+        S::Future,
+        future::MapOk<S::Future, fn(http::Response<B>) -> http::Response<B>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let call = self.inner.call(req);
+        if !self.hairpin {
+            return future::Either::Left(call);
+        }
+        future::Either::Right(call.map_ok(mark as fn(_) -> _))
+    }
+}
+
+fn mark<B>(mut rsp: http::Response<B>) -> http::Response<B> {
+    rsp.extensions_mut().insert(Hairpin(()));
+    rsp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(id: &str) -> identity::Id {
+        id.parse().unwrap()
+    }
+
+    #[test]
+    fn matches_local_workload() {
+        const LOCAL_ID: &str = "foo.ns.serviceaccount.identity.linkerd.cluster.local";
+        const OTHER_ID: &str = "bar.ns.serviceaccount.identity.linkerd.cluster.local";
+
+        let ips = [IpAddr::from([192, 0, 2, 3])].into_iter().collect();
+        let local = SocketAddr::new([192, 0, 2, 3].into(), 8080);
+        let remote = SocketAddr::new([192, 0, 2, 4].into(), 8080);
+        let config = HairpinConfig {
+            local_id: Some(id(LOCAL_ID)),
+        };
+
+        assert!(config.is_local(&ips, local, None));
+        assert!(config.is_local(&ips, local, Some(&id(LOCAL_ID))));
+        assert!(!config.is_local(&ips, local, Some(&id(OTHER_ID))));
+        assert!(!config.is_local(&ips, remote, None));
+        assert!(!config.is_local(&ips, remote, Some(&id(LOCAL_ID))));
+
+        let unknown = HairpinConfig::default();
+        assert!(unknown.is_local(&ips, local, None));
+        assert!(!unknown.is_local(&ips, local, Some(&id(LOCAL_ID))));
+    }
+
+    #[test]
+    fn loopback_preserves_port_and_family() {
+        assert_eq!(
+            loopback(SocketAddr::new([192, 0, 2, 3].into(), 8080)),
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 8080),
+        );
+        assert_eq!(
+            loopback("[2001:db8::3]:8080".parse().unwrap()),
+            SocketAddr::new(Ipv6Addr::LOCALHOST.into(), 8080),
+        );
+    }
+}
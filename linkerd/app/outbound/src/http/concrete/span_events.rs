@@ -0,0 +1,127 @@
+//! Annotates sampled request spans with how requests are dispatched to
+//! endpoints: the time spent waiting in a concrete service's queue, the
+//! endpoint that was selected, and whether the request probed an endpoint
+//! whose circuit breaker is half-open.
+
+use linkerd_app_core::{
+    http_tracing::SpanRecorder,
+    proxy::http::{self, classify::gate::Probe},
+    svc,
+    transport::addrs::*,
+};
+use std::{
+    net::SocketAddr,
+    task::{Context, Poll},
+};
+use tokio::time;
+
+/// Records when a request is dispatched to a concrete service.
+#[derive(Copy, Clone, Debug)]
+struct Enqueued(time::Instant);
+
+#[derive(Clone, Debug)]
+pub(super) struct MarkEnqueued<S> {
+    inner: S,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct NewRecordEndpoint<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct RecordEndpoint<S> {
+    addr: SocketAddr,
+    inner: S,
+}
+
+// === impl MarkEnqueued ===
+
+impl<S> MarkEnqueued<S> {
+    pub(super) fn layer() -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<B, S> svc::Service<http::Request<B>> for MarkEnqueued<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if SpanRecorder::get(&req).is_some() {
+            req.extensions_mut().insert(Enqueued(time::Instant::now()));
+        }
+        self.inner.call(req)
+    }
+}
+
+// === impl NewRecordEndpoint ===
+
+impl<N> NewRecordEndpoint<N> {
+    pub(super) fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRecordEndpoint<N>
+where
+    T: svc::Param<Remote<ServerAddr>>,
+    N: svc::NewService<T>,
+{
+    type Service = RecordEndpoint<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Remote(ServerAddr(addr)) = target.param();
+        RecordEndpoint {
+            addr,
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl RecordEndpoint ===
+
+impl<B, S> svc::Service<http::Request<B>> for RecordEndpoint<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if let Some(recorder) = SpanRecorder::get(&req) {
+            // When a request is retried, the last attempt's endpoint and queue
+            // wait are recorded.
+            recorder.set_attribute("proxy.endpoint.address", self.addr);
+            if let Some(Enqueued(at)) = req.extensions().get::<Enqueued>() {
+                let wait = time::Instant::now()
+                    .saturating_duration_since(*at)
+                    .as_secs_f64()
+                    * 1000.0;
+                recorder.set_attribute("proxy.queue.wait_ms", format!("{wait:.3}"));
+            }
+            if req.extensions().get::<Probe>().is_some() {
+                recorder.add_event(
+                    "breaker.probe",
+                    vec![("proxy.endpoint.address", self.addr.to_string())],
+                );
+            }
+        }
+        self.inner.call(req)
+    }
+}
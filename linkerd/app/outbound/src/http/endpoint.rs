@@ -1,10 +1,13 @@
 //! A stack that sends requests to an HTTP endpoint.
 
 use super::{
+    body_buffer::NewBufferRequestBody,
     handle_proxy_error_headers::{self, NewHandleProxyErrorHeaders},
+    timings::{Connections, NewTimeEndpoint},
+    upgrade_probe::{NewUpgradeProbe, ProbeTarget},
     NewRequireIdentity,
 };
-use crate::{tcp::tagged_transport, zone::TcpZoneLabels, Outbound};
+use crate::{lifetime::NewRecycle, tcp::tagged_transport, zone::TcpZoneLabels, Outbound};
 use linkerd_app_core::{
     classify, config, errors, http_tracing, metrics,
     proxy::{api_resolve::ProtocolHint, http, tap},
@@ -78,14 +81,26 @@ impl<T> Outbound<svc::ArcNewHttp<T, http::BoxBody>> {
         T: svc::Param<handle_proxy_error_headers::CloseServerConnection>,
         T: svc::Param<metrics::EndpointLabels>,
         T: svc::Param<tls::ConditionalClientTls>,
+        T: svc::Param<Option<ProbeTarget>>,
+        T: svc::Param<Option<Connections>>,
         T: tap::Inspect,
         T: Clone + Send + Sync + 'static,
         // Http endpoint body.
         B: http::Body<Error = Error> + std::fmt::Debug + Default + Send + 'static,
         B::Data: Send + 'static,
     {
+        let connect = self.tcp_connector();
         self.map_stack(|config, rt, inner| {
-            let config::ConnectConfig { backoff, .. } = config.proxy.connect;
+            let config::ConnectConfig {
+                backoff, timeout, ..
+            } = config.proxy.connect;
+
+            // Establishes TLS connections with endpoints to probe them for
+            // HTTP/2 upgrade support.
+            let probe = svc::stack(connect)
+                .push(tls::Client::layer(rt.identity.clone()))
+                .push_connect_timeout(timeout)
+                .into_inner();
 
             // Initiates an HTTP client on the underlying transport. Prior-knowledge HTTP/2
             // is typically used (i.e. when communicating with other proxies); though
@@ -94,16 +109,35 @@ impl<T> Outbound<svc::ArcNewHttp<T, http::BoxBody>> {
                 // Drive the connection to completion regardless of whether the reconnect is being
                 // actively polled.
                 .push_on_service(svc::layer::mk(svc::SpawnReady::new))
+                // Replaces the client once its connection exceeds its maximum
+                // lifetime.
+                .push(NewRecycle::layer(
+                    config.connection_lifetimes.clone(),
+                    rt.metrics.prom.lifetime.http(),
+                ))
                 .push_new_reconnect(backoff)
+                // Rebuilds the client when an upgrade probe determines the
+                // endpoint's protocol.
+                .push(NewUpgradeProbe::layer(probe))
                 .push(svc::NewMapErr::layer_from_target::<EndpointError, _>())
                 .push_on_service(svc::MapErr::layer_boxed())
                 .arc_new_http()
+                // Records when response headers are received, and how long the
+                // endpoint's connections took to establish, for requests that
+                // report their timings.
+                .push(NewTimeEndpoint::layer())
                 // Tear down server connections when a peer proxy generates a
                 // response with the `l5d-proxy-connection: close` header. This
                 // is only done when the `Closable` parameter is set to true.
                 // This module always strips error headers from responses.
                 .push(NewHandleProxyErrorHeaders::layer())
                 .push_on_service(http::BoxRequest::layer())
+                // Stop reading request bodies from the downstream while the
+                // client buffers too much of the request's body data.
+                .push(NewBufferRequestBody::layer(
+                    config.http_request_body_buffer,
+                    rt.metrics.prom.http.request_body().clone(),
+                ))
                 .push_on_service(http::EnforceTimeouts::layer())
                 // Handle connection-level errors eagerly so that we can report 5XX failures in tap
                 // and metrics. HTTP error metrics are not incremented here so that errors are not
@@ -162,13 +196,22 @@ impl<T> ExtractParam<errors::respond::EmitHeaders, T> for ClientRescue {
 impl errors::HttpRescue<Error> for ClientRescue {
     fn rescue(&self, error: Error) -> Result<errors::SyntheticHttpResponse> {
         if errors::is_caused_by::<http::orig_proto::DowngradedH2Error>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(
+                errors::ErrorCode::StreamReset,
+                error,
+            ));
         }
         if errors::is_caused_by::<std::io::Error>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(
+                errors::ErrorCode::ConnectFailed,
+                error,
+            ));
         }
         if errors::is_caused_by::<errors::ConnectTimeout>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(
+                errors::ErrorCode::ConnectTimeout,
+                error,
+            ));
         }
 
         Err(error)
@@ -204,6 +247,13 @@ impl<T: svc::Param<Remote<ServerAddr>>> svc::Param<Remote<ServerAddr>> for Conne
     }
 }
 
+impl<T: svc::Param<Option<Connections>>> svc::Param<Option<Connections>> for Connect<T> {
+    #[inline]
+    fn param(&self) -> Option<Connections> {
+        self.inner.param()
+    }
+}
+
 impl<T: svc::Param<tls::ConditionalClientTls>> svc::Param<tls::ConditionalClientTls>
     for Connect<T>
 {
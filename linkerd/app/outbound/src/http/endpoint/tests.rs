@@ -6,10 +6,13 @@ use linkerd_app_core::{
     io,
     proxy::api_resolve::ProtocolHint,
     svc::{http::TokioExecutor, NewService, ServiceExt},
+    transport::{ClientAddr, Local},
     Infallible,
 };
+use linkerd_app_test::connect::ConnectFuture;
 use linkerd_http_box::BoxBody;
 use std::net::SocketAddr;
+use tokio::time;
 
 static WAS_ORIG_PROTO: &str = "request-orig-proto";
 
@@ -37,6 +40,7 @@ async fn http11_forward() {
         addr: Remote(ServerAddr(addr)),
         version: http::Variant::Http1,
         hint: ProtocolHint::Unknown,
+        connections: None,
     });
 
     let req = http::Request::builder()
@@ -73,6 +77,7 @@ async fn http2_forward() {
         addr: Remote(ServerAddr(addr)),
         version: http::Variant::H2,
         hint: ProtocolHint::Unknown,
+        connections: None,
     });
 
     let req = http::Request::builder()
@@ -111,6 +116,7 @@ async fn orig_proto_upgrade() {
         addr: Remote(ServerAddr(addr)),
         version: http::Variant::Http1,
         hint: ProtocolHint::Http2,
+        connections: None,
     });
 
     let req = http::Request::builder()
@@ -167,6 +173,7 @@ async fn orig_proto_skipped_on_http_upgrade() {
         addr: Remote(ServerAddr(addr)),
         version: http::Variant::Http1,
         hint: ProtocolHint::Http2,
+        connections: None,
     });
 
     let req = http::Request::builder()
@@ -209,6 +216,7 @@ async fn orig_proto_http2_noop() {
         addr: Remote(ServerAddr(addr)),
         version: http::Variant::H2,
         hint: ProtocolHint::Http2,
+        connections: None,
     });
 
     let req = http::Request::builder()
@@ -222,6 +230,71 @@ async fn orig_proto_http2_noop() {
     assert!(rsp.headers().get(WAS_ORIG_PROTO).is_none());
 }
 
+/// Tests that a request that waits for its endpoint's connection reports how
+/// long the connection took to establish.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn timings_record_slow_connect() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    const CONNECT_DELAY: time::Duration = time::Duration::from_millis(500);
+    let addr = SocketAddr::new([192, 0, 2, 41].into(), 2041);
+
+    let slow_connect: Box<dyn FnMut(tcp::Connect) -> ConnectFuture + Send> =
+        Box::new(|_: tcp::Connect| {
+            Box::pin(async move {
+                time::sleep(CONNECT_DELAY).await;
+                let io = serve(::http::Version::HTTP_2)?;
+                Ok((io, Local(ClientAddr(([0, 0, 0, 0], 0).into()))))
+            })
+        });
+    let connect = support::connect().endpoint(addr, slow_connect);
+
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(connect)
+        .push_tcp_endpoint()
+        .push_http_tcp_client()
+        .push_http_endpoint::<http::BoxBody>()
+        .into_stack()
+        .push(classify::NewClassify::layer_default())
+        .into_inner();
+
+    let svc = stack.new_service(Endpoint {
+        addr: Remote(ServerAddr(addr)),
+        version: http::Variant::H2,
+        hint: ProtocolHint::Unknown,
+        connections: Some(Default::default()),
+    });
+
+    let timings = http::timings::Timings::new();
+    let req = http::Request::builder()
+        .version(::http::Version::HTTP_2)
+        .uri("http://foo.example.com")
+        .extension(http::ClientHandle::new(([192, 0, 2, 101], 40200).into()).0)
+        .extension(timings.clone())
+        .body(http::BoxBody::default())
+        .unwrap();
+    let rsp = svc.oneshot(req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::NO_CONTENT);
+
+    let http::timings::Breadcrumb {
+        route,
+        queue,
+        balance,
+        connect,
+        tls,
+        first_byte,
+    } = timings.breadcrumb();
+    // Routing and balancing are not exercised by the endpoint stack.
+    assert_eq!((route, queue, balance), (None, None, None));
+    assert_eq!(connect, Some(CONNECT_DELAY));
+    assert_eq!(tls, None, "connections to the endpoint are not TLS'd");
+    assert!(
+        first_byte.is_some_and(|t| t >= CONNECT_DELAY),
+        "{first_byte:?}"
+    );
+}
+
 /// Helper server that reads the l5d-orig-proto header on requests and uses it to set the header
 /// value in `WAS_ORIG_PROTO`.
 fn serve(version: ::http::Version) -> io::Result<io::BoxedIo> {
@@ -267,6 +340,7 @@ struct Endpoint {
     addr: Remote<ServerAddr>,
     hint: ProtocolHint,
     version: http::Variant,
+    connections: Option<http::timings::Connections>,
 }
 
 // === impl Endpoint ===
@@ -321,6 +395,7 @@ impl svc::Param<metrics::OutboundEndpointLabels> for Endpoint {
             zone_locality: OutboundZoneLocality::Unknown,
             server_id: self.param(),
             target_addr: self.addr.into(),
+            http_version: Some(self.version),
         }
     }
 }
@@ -360,6 +435,24 @@ impl svc::Param<http::client::Params> for Endpoint {
     }
 }
 
+impl svc::Param<Option<http::upgrade_probe::ProbeTarget>> for Endpoint {
+    fn param(&self) -> Option<http::upgrade_probe::ProbeTarget> {
+        None
+    }
+}
+
+impl svc::Param<Option<http::timings::Connections>> for Endpoint {
+    fn param(&self) -> Option<http::timings::Connections> {
+        self.connections.clone()
+    }
+}
+
+impl svc::Param<TcpZoneLabels> for Endpoint {
+    fn param(&self) -> TcpZoneLabels {
+        crate::zone::tcp_zone_labels(OutboundZoneLocality::Unknown)
+    }
+}
+
 impl svc::Param<ProtocolHint> for Endpoint {
     fn param(&self) -> ProtocolHint {
         self.hint
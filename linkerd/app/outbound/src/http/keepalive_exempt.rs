@@ -0,0 +1,107 @@
+//! Tracks the activity that keeps cached logical HTTP services alive.
+//!
+//! By default, a cached logical service is retained as long as any connection
+//! holds it. Clients that only send health checks (e.g. a load balancer's
+//! probes) would otherwise keep services alive indefinitely. When requests are
+//! configured to be exempt, services are instead retained only while they
+//! serve requests that are not exempt, and are evicted when idle even if
+//! connections hold them. Connections that hold an evicted service continue to
+//! use it; new connections build a new service.
+
+use linkerd_app_core::{idle_cache::Cached, proxy::http, svc};
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// Describes requests that do not keep a cached logical HTTP service alive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeepaliveExempt {
+    /// Requests whose path is the given path or is nested under it, e.g.
+    /// `/healthz` matches `/healthz` and `/healthz/ready`, but not
+    /// `/healthzz`.
+    PathPrefix(String),
+
+    /// Requests with the given method and exactly the given path.
+    Request(http::Method, String),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewMarkActive<N> {
+    exempt: Arc<[KeepaliveExempt]>,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct MarkActive<S> {
+    exempt: Arc<[KeepaliveExempt]>,
+    inner: Cached<S>,
+}
+
+// === impl KeepaliveExempt ===
+
+impl KeepaliveExempt {
+    fn matches<B>(&self, req: &http::Request<B>) -> bool {
+        let path = req.uri().path();
+        match self {
+            Self::PathPrefix(prefix) => match path.strip_prefix(prefix.as_str()) {
+                Some(rest) => rest.is_empty() || rest.starts_with('/') || prefix.ends_with('/'),
+                None => false,
+            },
+            Self::Request(method, exact) => req.method() == method && path == exact,
+        }
+    }
+}
+
+// === impl NewMarkActive ===
+
+impl<N> NewMarkActive<N> {
+    pub(crate) fn layer(
+        exempt: impl IntoIterator<Item = KeepaliveExempt>,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let exempt = exempt.into_iter().collect::<Arc<[_]>>();
+        svc::layer::mk(move |inner| Self {
+            exempt: exempt.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N, S> svc::NewService<T> for NewMarkActive<N>
+where
+    N: svc::NewService<T, Service = Cached<S>>,
+{
+    type Service = MarkActive<S>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        MarkActive {
+            exempt: self.exempt.clone(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl MarkActive ===
+
+impl<B, S> svc::Service<http::Request<B>> for MarkActive<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if self.exempt.iter().any(|e| e.matches(&req)) {
+            tracing::trace!(path = %req.uri().path(), "Request does not keep service alive");
+        } else {
+            self.inner.mark_active();
+        }
+        self.inner.call(req)
+    }
+}
@@ -1,14 +1,15 @@
 //! A stack that routes HTTP requests to concrete backends.
 
-use super::concrete;
-use crate::{BackendRef, EndpointRef, Outbound, OutboundMetrics, ParentRef};
+use super::{concrete, timings};
+use crate::{BackendRef, EndpointRef, Outbound, OutboundMetrics, ParentRef, RoutesFingerprint};
 use linkerd_app_core::{
     proxy::{api_resolve::Metadata, http},
     svc,
     transport::addrs::*,
     Addr, Error, Infallible, NameAddr, CANONICAL_DST_HEADER,
 };
-use std::{fmt::Debug, hash::Hash, sync::Arc};
+use parking_lot::{Mutex, MutexGuard};
+use std::{fmt::Debug, hash::Hash, sync::Arc, time::Duration};
 use tokio::sync::watch;
 
 pub mod policy;
@@ -22,7 +23,7 @@ mod tests;
 pub struct LogicalAddr(pub Addr);
 
 /// Configures the flavor of HTTP routing.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Routes {
     /// Policy routes.
     Policy(policy::Params),
@@ -35,6 +36,23 @@ pub enum Routes {
     Endpoint(Remote<ServerAddr>, Arc<Metadata>),
 }
 
+/// Caches the logical address and default authority derived from a routes
+/// watch.
+///
+/// These values are recomputed only when the watch has observed a new version,
+/// so that reading them in the steady state only checks the watch's version.
+/// Clones share a cache. A read that races with a routes update may return the
+/// values derived from the previous routes.
+#[derive(Clone, Debug)]
+pub struct RoutesAddrs(Arc<Mutex<CachedAddrs>>);
+
+#[derive(Debug)]
+struct CachedAddrs {
+    routes: watch::Receiver<Routes>,
+    logical: LogicalAddr,
+    authority: Option<http::uri::Authority>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Concrete<T> {
     target: concrete::Dispatch,
@@ -84,6 +102,7 @@ impl<N> Outbound<N> {
     pub fn push_http_logical<T, NSvc>(self) -> Outbound<svc::ArcNewCloneHttp<T>>
     where
         // Logical target.
+        T: svc::Param<http::Variant>,
         T: svc::Param<watch::Receiver<Routes>>,
         T: Eq + Hash + Clone + Debug + Send + Sync + 'static,
         // Concrete stack.
@@ -96,15 +115,33 @@ impl<N> Outbound<N> {
         NSvc: Clone + Send + Sync + 'static,
         NSvc::Future: Send,
     {
-        self.map_stack(|_config, rt, concrete| {
+        self.map_stack(|config, rt, concrete| {
             // For each `T` target, watch its `Profile`, rebuilding a
             // router stack.
             concrete
                 // Share the concrete stack with each router stack.
                 .lift_new()
-                .push_on_service(RouterParams::layer(rt.metrics.clone()))
+                .push_on_service(RouterParams::layer(
+                    rt.metrics.clone(),
+                    config.http_workload_identity.clone(),
+                    config.http_profile_retry_timeout,
+                ))
                 // Rebuild the inner router stack every time the watch changes.
                 .push(svc::NewSpawnWatch::<Routes, _>::layer_into::<RouterParams<T>>())
+                // Permit requests to be re-matched by the current router when
+                // all of their route's backends become unavailable.
+                .push(policy::NewSetRematch::layer())
+                // Interpret and strip `l5d-route-debug` headers before requests
+                // are routed.
+                .push(policy::NewRouteDebug::layer(
+                    config.http_route_debug_header,
+                    rt.metrics.prom.http.route_debug().clone(),
+                ))
+                // Record the time requests spend in each layer, if configured,
+                // so that slow requests may be diagnosed.
+                .push(timings::NewTimings::layer(
+                    config.http_request_timings_threshold,
+                ))
                 .arc_new_clone_http()
         })
     }
@@ -114,10 +151,13 @@ impl<N> Outbound<N> {
 
 impl<T> RouterParams<T>
 where
+    T: svc::Param<http::Variant>,
     T: Clone + Debug + Eq + Hash + Send + Sync + 'static,
 {
     fn layer<N, S>(
         metrics: OutboundMetrics,
+        workload_identity: Option<http::workload_identity::Signer>,
+        profile_retry_timeout: Option<Duration>,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<RouterParams<T>>> + Clone
     where
         N: svc::NewService<Concrete<T>, Service = S>,
@@ -134,9 +174,12 @@ where
             let policy = svc::stack(concrete.clone()).push(policy::Policy::layer(
                 metrics.prom.http.http_route.clone(),
                 metrics.prom.http.grpc_route.clone(),
+                workload_identity.clone(),
+            ));
+            let profile = svc::stack(concrete.clone()).push(profile::Params::layer(
+                metrics.proxy.clone(),
+                profile_retry_timeout,
             ));
-            let profile =
-                svc::stack(concrete.clone()).push(profile::Params::layer(metrics.proxy.clone()));
             svc::stack(concrete)
                 .push_switch(
                     |prms: Self| {
@@ -177,6 +220,7 @@ where
 
 impl<T> From<(Routes, T)> for RouterParams<T>
 where
+    T: svc::Param<http::Variant>,
     T: Eq + Hash + Clone + Debug,
 {
     fn from((routes, parent): (Routes, T)) -> Self {
@@ -204,6 +248,64 @@ where
     }
 }
 
+// === impl Routes ===
+
+impl svc::Param<RoutesFingerprint> for Routes {
+    fn param(&self) -> RoutesFingerprint {
+        RoutesFingerprint::of(self)
+    }
+}
+
+// === impl RoutesAddrs ===
+
+impl RoutesAddrs {
+    pub fn new(mut routes: watch::Receiver<Routes>) -> Self {
+        let (logical, authority) = Self::mk(&routes.borrow_and_update());
+        Self(Arc::new(Mutex::new(CachedAddrs {
+            routes,
+            logical,
+            authority,
+        })))
+    }
+
+    /// Returns the address used for logical routing.
+    pub fn logical_addr(&self) -> LogicalAddr {
+        self.current().logical.clone()
+    }
+
+    /// Returns the authority used for requests that don't specify one, if the
+    /// routes have a logical name.
+    pub fn default_authority(&self) -> Option<http::uri::Authority> {
+        self.current().authority.clone()
+    }
+
+    fn current(&self) -> MutexGuard<'_, CachedAddrs> {
+        let mut cache = self.0.lock();
+        // If the routes' sender has been dropped, the routes cannot change.
+        if cache.routes.has_changed().unwrap_or(false) {
+            let (logical, authority) = Self::mk(&cache.routes.borrow_and_update());
+            tracing::trace!(?logical, ?authority, "Routes updated");
+            cache.logical = logical;
+            cache.authority = authority;
+        }
+        cache
+    }
+
+    fn mk(routes: &Routes) -> (LogicalAddr, Option<http::uri::Authority>) {
+        match routes {
+            Routes::Policy(policy) => (
+                LogicalAddr(policy.addr().clone()),
+                Some(policy.addr().to_http_authority()),
+            ),
+            Routes::Profile(profile) => (
+                LogicalAddr(profile.addr.0.clone().into()),
+                Some((*profile.addr).as_http_authority()),
+            ),
+            Routes::Endpoint(Remote(ServerAddr(addr)), ..) => (LogicalAddr((*addr).into()), None),
+        }
+    }
+}
+
 // === impl LogicalError ===
 
 impl<T> From<(&RouterParams<T>, Error)> for LogicalError
@@ -1,20 +1,28 @@
 use super::{Concrete, LogicalAddr};
 use linkerd_app_core::{proxy::http, svc, Addr, Error, Infallible};
-use std::{fmt::Debug, hash::Hash};
+use linkerd_proxy_client_policy::EndpointMetadata;
+use std::{fmt::Debug, hash::Hash, net::SocketAddr, sync::Arc};
 
 mod route;
-mod router;
+pub(crate) mod router;
 #[cfg(test)]
 mod tests;
 
+pub(crate) use self::route::{
+    debug::{NewRouteDebug, RouteDebugMetrics},
+    rematch::NewSetRematch,
+};
 pub use self::{
-    route::{errors, GrpcRouteMetrics, HttpRouteMetrics},
+    route::{
+        errors, FaultRng, GrpcRouteMetrics, HttpRouteMetrics, ResponseCache, RolloutGuardState,
+        RolloutGuards, RouteDebug, WindowCounts,
+    },
     router::{GrpcParams, HttpParams},
 };
 pub use linkerd_proxy_client_policy::{ClientPolicy, FailureAccrual};
 
 /// HTTP or gRPC policy route parameters.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Params {
     Http(router::HttpParams),
     Grpc(router::GrpcParams),
@@ -36,6 +44,23 @@ impl Params {
             | Params::Grpc(router::Params { ref addr, .. }) => addr,
         }
     }
+
+    /// Trims these routes to forward requests to the endpoint at `addr`, so
+    /// that the routes' timeouts and retries apply to traffic that targets
+    /// one of the parent's endpoints directly.
+    ///
+    /// Returns `None` if no route configures timeouts or retries, since such
+    /// traffic is then simply forwarded to the endpoint.
+    pub fn forward_to_endpoint(
+        &self,
+        addr: SocketAddr,
+        metadata: Arc<EndpointMetadata>,
+    ) -> Option<Self> {
+        match self {
+            Params::Http(http) => http.forward_to_endpoint(addr, metadata).map(Params::Http),
+            Params::Grpc(grpc) => grpc.forward_to_endpoint(addr, metadata).map(Params::Grpc),
+        }
+    }
 }
 
 // === impl Policy ===
@@ -43,6 +68,7 @@ impl Params {
 impl<T> Policy<T>
 where
     // Parent target type.
+    T: svc::Param<http::Variant>,
     T: Debug + Eq + Hash,
     T: Clone + Send + Sync + 'static,
 {
@@ -52,6 +78,7 @@ where
     pub(super) fn layer<N, S>(
         http_metrics: route::HttpRouteMetrics,
         grpc_metrics: route::GrpcRouteMetrics,
+        workload_identity: Option<http::workload_identity::Signer>,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         // Inner stack.
@@ -66,8 +93,14 @@ where
         S::Future: Send,
     {
         svc::layer::mk(move |inner: N| {
-            let http = svc::stack(inner.clone()).push(router::Http::layer(http_metrics.clone()));
-            let grpc = svc::stack(inner).push(router::Grpc::layer(grpc_metrics.clone()));
+            let http = svc::stack(inner.clone()).push(router::Http::layer(
+                http_metrics.clone(),
+                workload_identity.clone(),
+            ));
+            let grpc = svc::stack(inner).push(router::Grpc::layer(
+                grpc_metrics.clone(),
+                workload_identity.clone(),
+            ));
 
             http.push_switch(
                 |pp: Policy<T>| {
@@ -86,6 +119,7 @@ where
 
 impl<T> From<(Params, T)> for Policy<T>
 where
+    T: svc::Param<http::Variant>,
     T: Eq + Hash + Clone + Debug,
 {
     fn from((pr, parent): (Params, T)) -> Self {
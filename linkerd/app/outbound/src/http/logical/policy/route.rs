@@ -1,21 +1,41 @@
 use super::super::Concrete;
-use crate::{ParentRef, RouteRef};
+use crate::{http::timings, ParentRef, RouteRef};
 use linkerd_app_core::{classify, proxy::http, svc, Addr, Error, Result};
 use linkerd_distribute as distribute;
 use linkerd_http_route as http_route;
 use linkerd_proxy_client_policy as policy;
-use std::{fmt::Debug, hash::Hash, sync::Arc};
+use std::{
+    fmt::Debug,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
 
+pub(crate) mod authority;
 pub(crate) mod backend;
+pub(crate) mod cache;
+pub(crate) mod coalesce;
+pub(crate) mod debug;
+pub(crate) mod decompress;
+pub(crate) mod direct_response;
 pub(crate) mod extensions;
+pub(crate) mod fault;
 pub(crate) mod filters;
+pub(crate) mod guard;
 pub(crate) mod metrics;
+pub(crate) mod rematch;
 pub(crate) mod retry;
+pub(crate) mod workload_identity;
 
 pub(crate) use self::backend::{Backend, MatchedBackend};
 pub use self::filters::errors;
 
-pub use self::metrics::{GrpcRouteMetrics, HttpRouteMetrics};
+pub use self::{
+    cache::ResponseCache,
+    debug::RouteDebug,
+    fault::FaultRng,
+    guard::{RolloutGuardState, RolloutGuards, WindowCounts},
+    metrics::{GrpcRouteMetrics, HttpRouteMetrics},
+};
 
 /// A target type that includes a summary of exactly how a request was matched.
 /// This match state is required to apply route filters.
@@ -27,18 +47,23 @@ pub(crate) struct Matched<M, P> {
     pub(super) params: P,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// A configured route rule.
+///
+/// Routes are built when a policy is updated and shared by every request they
+/// match, so that matching a request doesn't clone the route's configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct Route<T, F, P> {
     pub(super) parent: T,
     pub(super) addr: Addr,
     pub(super) parent_ref: ParentRef,
     pub(super) route_ref: RouteRef,
+    pub(super) labels: Arc<metrics::labels::RouteRule>,
     pub(super) filters: Arc<[F]>,
     pub(super) distribution: BackendDistribution<T, F>,
     pub(super) params: P,
 }
 
-pub(crate) type MatchedRoute<T, M, F, P> = Matched<M, Route<T, F, P>>;
+pub(crate) type MatchedRoute<T, M, F, P> = Matched<M, Arc<Route<T, F, P>>>;
 pub(crate) type Http<T> = MatchedRoute<
     T,
     http_route::http::r#match::RequestMatch,
@@ -53,7 +78,6 @@ pub(crate) type Grpc<T> = MatchedRoute<
 >;
 
 pub(crate) type BackendDistribution<T, F> = distribute::Distribution<Backend<T, F>>;
-pub(crate) type NewDistribute<T, F, N> = distribute::NewDistribute<Backend<T, F>, (), N>;
 
 pub type Metrics<R, B> = metrics::RouteMetrics<
     <R as metrics::MkStreamLabel>::StreamLabel,
@@ -69,6 +93,17 @@ struct RouteError {
     source: Error,
 }
 
+// === impl Route ===
+
+// Routes are hashed for each request to find their cached services. Hashing
+// only the route's labels is consistent with equality and avoids hashing the
+// route's full configuration.
+impl<T, F, P> Hash for Route<T, F, P> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.labels.hash(state)
+    }
+}
+
 // === impl MatchedRoute ===
 
 impl<T, M, F, P> MatchedRoute<T, M, F, P>
@@ -86,7 +121,15 @@ where
     // Assert that filters can be applied.
     Self: filters::Apply,
     Self: svc::Param<classify::Request>,
+    Self: svc::Param<authority::Params>,
     Self: svc::Param<extensions::Params>,
+    Self: svc::Param<fault::Params>,
+    Self: svc::Param<decompress::Params>,
+    Self: svc::Param<direct_response::Params>,
+    Self: svc::Param<workload_identity::Params>,
+    Self: svc::Param<guard::Params>,
+    Self: svc::Param<Option<linkerd_http_cache::Params>>,
+    Self: svc::Param<Option<linkerd_http_coalesce::Params>>,
     Self: metrics::MkStreamLabel,
     Self: svc::ExtractParam<metrics::labels::Route, http::Request<http::BoxBody>>,
     MatchedBackend<T, M, F>: filters::Apply,
@@ -97,6 +140,7 @@ where
     /// backends are expected to be cached/shared by the inner stack.
     pub(crate) fn layer<N, S>(
         metrics: Metrics<Self, MatchedBackend<T, M, F>>,
+        workload_identity: Option<http::workload_identity::Signer>,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         // Inner stack.
@@ -113,24 +157,76 @@ where
         svc::layer::mk(move |inner| {
             svc::stack(inner)
                 // Distribute requests across route backends, applying policies
-                // and filters for each of the route-backends.
+                // and filters for each of the route-backends. If the route
+                // guards a rollout, the canary backend may be removed from the
+                // distribution when it regresses.
                 .push(MatchedBackend::layer(metrics.backend.clone()))
                 .lift_new_with_target()
-                .push(NewDistribute::layer())
+                .push(guard::NewGuardedDistribute::layer(
+                    metrics.rollout_guards.clone(),
+                ))
                 .check_new::<Self>()
                 .check_new_service::<Self, http::Request<http::BoxBody>>()
+                // Decompress request bodies, if configured. This is applied
+                // after other filters so that, e.g., injected failures do not
+                // require the body to be read.
+                .push(decompress::NewDecompress::layer())
+                // Assert the workload's identity, if configured. This is
+                // applied for each retry so that assertions are fresh.
+                .push(workload_identity::NewAssertWorkloadIdentity::layer(
+                    workload_identity.clone(),
+                ))
+                .push(filters::NewApplyFilters::<Self, _, _>::layer())
                 // The router does not take the backend's availability into
                 // consideration, so we must eagerly fail requests to prevent
-                // leaking tasks onto the runtime.
-                .push_on_service(svc::LoadShed::layer())
-                .push(filters::NewApplyFilters::<Self, _, _>::layer())
-                .push(retry::NewHttpRetry::<Self, _>::layer(metrics.retry.clone()))
+                // leaking tasks onto the runtime. If the routes have changed
+                // since the request was matched, the attempt is instead
+                // re-matched, before this route's filters are applied.
+                .push_on_service(rematch::LoadShedOrRematch::layer())
+                // Abort requests, if configured, for each attempt when the
+                // route permits aborted requests to be retried.
+                .push(fault::NewInjectFaults::layer(
+                    metrics.fault_rng.clone(),
+                    fault::Stage::Attempt,
+                ))
+                // Retry requests, if configured. Retries are dispatched through
+                // this route's filters and backend distribution, so a request
+                // is only re-matched if the routes change while it's retried
+                // and all of this route's backends become unavailable.
+                .push(retry::NewHttpRetry::<Self, _>::layer(
+                    metrics.retry.clone(),
+                    metrics.retry_buffers.clone(),
+                ))
+                // Serve requests from the response cache, if the route enables
+                // it. Cached responses are not retried.
+                .push(cache::NewCache::<Self, _>::layer(
+                    metrics.response_cache.clone(),
+                    metrics.cache.clone(),
+                ))
+                // Coalesce identical in-flight requests, if the route enables
+                // it, so that only requests that miss the cache are coalesced.
+                .push(coalesce::NewCoalesce::<Self, _>::layer(
+                    metrics.coalescer.clone(),
+                    metrics.coalesce.clone(),
+                ))
+                // Delay and abort requests, if configured, before they may be
+                // retried.
+                .push(fault::NewInjectFaults::layer(
+                    metrics.fault_rng.clone(),
+                    fault::Stage::Request,
+                ))
+                // Respond directly, if configured, before any faults are
+                // injected or backends are selected.
+                .push(direct_response::NewDirectResponse::layer())
                 .check_new::<Self>()
                 .check_new_service::<Self, http::Request<http::BoxBody>>()
                 // Set request extensions based on the route configuration
                 // AND/OR headers
                 .push(extensions::NewSetExtensions::layer())
                 .push(metrics::layer(&metrics.requests, &metrics.body_data))
+                // Rewrite the request's authority, if configured, so that the
+                // request is labeled and forwarded with the canonical name.
+                .push(authority::NewRewriteAuthority::layer())
                 .check_new::<Self>()
                 .check_new_service::<Self, http::Request<http::BoxBody>>()
                 // Configure a classifier to use in the endpoint stack.
@@ -143,6 +239,7 @@ where
                         source,
                     }
                 }))
+                .push_on_service(timings::Stamp::layer(timings::Stage::Route))
                 .arc_new_clone_http()
                 .into_inner()
         })
@@ -171,11 +268,12 @@ impl<T> filters::Apply for Http<T> {
 
 impl<B, T> svc::ExtractParam<metrics::labels::Route, http::Request<B>> for Http<T> {
     fn extract_param(&self, req: &http::Request<B>) -> metrics::labels::Route {
-        metrics::labels::Route::new(
-            self.params.parent_ref.clone(),
-            self.params.route_ref.clone(),
-            self.params.params.export_hostname_labels.then(|| req.uri()),
+        let params = &self.params.params;
+        metrics::labels::Route::for_rule(
+            &self.params.labels,
+            params.export_hostname_labels.then(|| req.uri()),
         )
+        .with_method(params.export_method_labels.then(|| req.method()))
     }
 }
 
@@ -185,12 +283,12 @@ impl<T> metrics::MkStreamLabel for Http<T> {
     type StreamLabel = metrics::LabelHttpRouteRsp;
 
     fn mk_stream_labeler<B>(&self, req: &::http::Request<B>) -> Option<Self::StreamLabel> {
-        let parent = self.params.parent_ref.clone();
-        let route = self.params.route_ref.clone();
-        let uri = self.params.params.export_hostname_labels.then(|| req.uri());
-        Some(metrics::LabelHttpRsp::from(metrics::labels::Route::new(
-            parent, route, uri,
-        )))
+        let params = &self.params.params;
+        let uri = params.export_hostname_labels.then(|| req.uri());
+        let method = params.export_method_labels.then(|| req.method());
+        Some(metrics::LabelHttpRsp::from(
+            metrics::labels::Route::for_rule(&self.params.labels, uri).with_method(method),
+        ))
     }
 }
 
@@ -212,11 +310,91 @@ impl<T> svc::Param<extensions::Params> for Http<T> {
     }
 }
 
+impl<T> svc::Param<fault::Params> for Http<T> {
+    fn param(&self) -> fault::Params {
+        fault::Params(self.params.params.fault.clone().map(Into::into))
+    }
+}
+
+impl<T> svc::Param<authority::Params> for Http<T> {
+    fn param(&self) -> authority::Params {
+        authority::Params(self.params.filters.iter().find_map(|f| match f {
+            policy::http::Filter::RewriteAuthority(f) => Some(f.clone()),
+            _ => None,
+        }))
+    }
+}
+
+impl<T> svc::Param<decompress::Params> for Http<T> {
+    fn param(&self) -> decompress::Params {
+        decompress::Params(self.params.filters.iter().find_map(|f| match f {
+            policy::http::Filter::DecompressRequest(f) => Some(f.clone()),
+            _ => None,
+        }))
+    }
+}
+
+impl<T> svc::Param<direct_response::Params> for Http<T> {
+    fn param(&self) -> direct_response::Params {
+        direct_response::Params(self.params.filters.iter().find_map(|f| match f {
+            policy::http::Filter::DirectResponse(f) => {
+                Some(direct_response::Response::Http(f.clone()))
+            }
+            _ => None,
+        }))
+    }
+}
+
+impl<T> svc::Param<workload_identity::Params> for Http<T> {
+    fn param(&self) -> workload_identity::Params {
+        workload_identity::Params(
+            self.params
+                .filters
+                .iter()
+                .any(|f| matches!(f, policy::http::Filter::AssertWorkloadIdentity(_))),
+        )
+    }
+}
+
+impl<T> svc::Param<guard::Params> for Http<T> {
+    fn param(&self) -> guard::Params {
+        guard::Params(self.params.params.rollout_guard.clone())
+    }
+}
+
+impl<T> svc::Param<Option<linkerd_http_cache::Params>> for Http<T> {
+    fn param(&self) -> Option<linkerd_http_cache::Params> {
+        let cache = self.params.params.cache.as_ref()?;
+        // Each route's responses are stored separately.
+        let mut scope = DefaultHasher::new();
+        self.params.labels.hash(&mut scope);
+        Some(linkerd_http_cache::Params {
+            scope: scope.finish(),
+            allow_authorization: cache.allow_authorization,
+            max_age: cache.max_age,
+        })
+    }
+}
+
+impl<T> svc::Param<Option<linkerd_http_coalesce::Params>> for Http<T> {
+    fn param(&self) -> Option<linkerd_http_coalesce::Params> {
+        let coalesce = self.params.params.coalesce.as_ref()?;
+        // Each route's requests are coalesced separately.
+        let mut scope = DefaultHasher::new();
+        self.params.labels.hash(&mut scope);
+        Some(linkerd_http_coalesce::Params {
+            scope: scope.finish(),
+            headers: coalesce.headers.clone(),
+            max_waiters: coalesce.max_waiters,
+            timeout: coalesce.timeout,
+        })
+    }
+}
+
 impl<T> svc::Param<classify::Request> for Http<T> {
     fn param(&self) -> classify::Request {
-        classify::Request::ClientPolicy(classify::ClientPolicy::Http(
-            policy::http::StatusRanges::default(),
-        ))
+        let statuses = self.params.params.failure_statuses.clone();
+        classify::Request::ClientPolicy(classify::ClientPolicy::Http(statuses.unwrap_or_default()))
     }
 }
 
@@ -236,9 +414,8 @@ impl<T> filters::Apply for Grpc<T> {
 
 impl<B, T> svc::ExtractParam<metrics::labels::Route, http::Request<B>> for Grpc<T> {
     fn extract_param(&self, req: &http::Request<B>) -> metrics::labels::Route {
-        metrics::labels::Route::new(
-            self.params.parent_ref.clone(),
-            self.params.route_ref.clone(),
+        metrics::labels::Route::for_rule(
+            &self.params.labels,
             self.params.params.export_hostname_labels.then(|| req.uri()),
         )
     }
@@ -250,12 +427,10 @@ impl<T> metrics::MkStreamLabel for Grpc<T> {
     type StreamLabel = metrics::LabelGrpcRouteRsp;
 
     fn mk_stream_labeler<B>(&self, req: &::http::Request<B>) -> Option<Self::StreamLabel> {
-        let parent = self.params.parent_ref.clone();
-        let route = self.params.route_ref.clone();
         let uri = self.params.params.export_hostname_labels.then(|| req.uri());
-        Some(metrics::LabelGrpcRsp::from(metrics::labels::Route::new(
-            parent, route, uri,
-        )))
+        Some(metrics::LabelGrpcRsp::from(
+            metrics::labels::Route::for_rule(&self.params.labels, uri),
+        ))
     }
 }
 
@@ -277,10 +452,62 @@ impl<T> svc::Param<extensions::Params> for Grpc<T> {
     }
 }
 
+impl<T> svc::Param<fault::Params> for Grpc<T> {
+    fn param(&self) -> fault::Params {
+        fault::Params(self.params.params.fault.clone().map(Into::into))
+    }
+}
+
+impl<T> svc::Param<authority::Params> for Grpc<T> {
+    fn param(&self) -> authority::Params {
+        authority::Params::default()
+    }
+}
+
+impl<T> svc::Param<decompress::Params> for Grpc<T> {
+    fn param(&self) -> decompress::Params {
+        decompress::Params::default()
+    }
+}
+
+impl<T> svc::Param<direct_response::Params> for Grpc<T> {
+    fn param(&self) -> direct_response::Params {
+        direct_response::Params(self.params.filters.iter().find_map(|f| match f {
+            policy::grpc::Filter::DirectResponse(f) => {
+                Some(direct_response::Response::Grpc(f.clone()))
+            }
+            _ => None,
+        }))
+    }
+}
+
+impl<T> svc::Param<workload_identity::Params> for Grpc<T> {
+    fn param(&self) -> workload_identity::Params {
+        workload_identity::Params::default()
+    }
+}
+
+impl<T> svc::Param<guard::Params> for Grpc<T> {
+    fn param(&self) -> guard::Params {
+        guard::Params(self.params.params.rollout_guard.clone())
+    }
+}
+
+impl<T> svc::Param<Option<linkerd_http_cache::Params>> for Grpc<T> {
+    fn param(&self) -> Option<linkerd_http_cache::Params> {
+        None
+    }
+}
+
+impl<T> svc::Param<Option<linkerd_http_coalesce::Params>> for Grpc<T> {
+    fn param(&self) -> Option<linkerd_http_coalesce::Params> {
+        None
+    }
+}
+
 impl<T> svc::Param<classify::Request> for Grpc<T> {
     fn param(&self) -> classify::Request {
-        classify::Request::ClientPolicy(
-            classify::ClientPolicy::Grpc(policy::grpc::Codes::default()),
-        )
+        let codes = self.params.params.failure_codes.clone();
+        classify::Request::ClientPolicy(classify::ClientPolicy::Grpc(codes.unwrap_or_default()))
     }
 }
@@ -0,0 +1,100 @@
+use futures::{future, FutureExt, TryFutureExt};
+use linkerd_app_core::{proxy::http, svc, Error, Result};
+use linkerd_http_route::http::filter::{OriginalAuthority, RewriteAuthority};
+use std::task::{Context, Poll};
+
+/// Configures authority rewriting for a route.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Params(pub Option<RewriteAuthority>);
+
+/// Rewrites requests' authorities to a canonical name, as configured by a
+/// route's [`RewriteAuthority`] filter.
+///
+/// The rewrite is applied before requests are labeled by route metrics so that
+/// requests are recorded with the canonical name. The original authority is
+/// set as an [`OriginalAuthority`] request extension.
+#[derive(Clone, Debug)]
+pub(crate) struct NewRewriteAuthority<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RewriteAuthorityService<S> {
+    filter: Option<RewriteAuthority>,
+    inner: S,
+}
+
+// === impl NewRewriteAuthority ===
+
+impl<N> NewRewriteAuthority<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRewriteAuthority<N>
+where
+    T: svc::Param<Params>,
+    N: svc::NewService<T>,
+{
+    type Service = RewriteAuthorityService<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Params(filter) = target.param();
+        let inner = self.inner.new_service(target);
+        RewriteAuthorityService { filter, inner }
+    }
+}
+
+// === impl RewriteAuthorityService ===
+
+impl<B, RspB, S> svc::Service<http::Request<B>> for RewriteAuthorityService<S>
+where
+    RspB: Send + 'static,
+    S: svc::Service<http::Request<B>, Response = http::Response<RspB>>,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<S::Future, Error>,
+        future::BoxFuture<'static, Result<http::Response<RspB>>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let Some(filter) = self.filter.as_ref() else {
+            return future::Either::Left(self.inner.call(req).err_into());
+        };
+        let Some(original) = filter.apply(&mut req) else {
+            return future::Either::Left(self.inner.call(req).err_into());
+        };
+        tracing::debug!(
+            original = %original.0,
+            authority = %filter.authority,
+            "Rewrote request authority",
+        );
+        req.extensions_mut().insert(original.clone());
+        if !filter.rewrite_location {
+            return future::Either::Left(self.inner.call(req).err_into());
+        }
+
+        let filter = filter.clone();
+        future::Either::Right(
+            self.inner
+                .call(req)
+                .err_into::<Error>()
+                .map_ok(move |mut rsp| {
+                    let status = rsp.status();
+                    filter.restore_location(status, rsp.headers_mut(), &original);
+                    rsp
+                })
+                .boxed(),
+        )
+    }
+}
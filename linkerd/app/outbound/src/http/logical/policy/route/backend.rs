@@ -1,5 +1,5 @@
 use super::{super::Concrete, filters};
-use crate::{BackendRef, ParentRef, RouteRef};
+use crate::{http::concrete::Dispatch, BackendRef, ParentRef, RouteRef};
 use linkerd_app_core::{proxy::http, svc, Error, Result};
 use linkerd_http_prom::record_response::MkStreamLabel;
 use linkerd_http_route as http_route;
@@ -133,6 +133,21 @@ impl<T, M, F> svc::Param<BackendRef> for MatchedBackend<T, M, F> {
     }
 }
 
+impl<T, M, F> svc::Param<Dispatch> for MatchedBackend<T, M, F> {
+    fn param(&self) -> Dispatch {
+        self.params.concrete.target.clone()
+    }
+}
+
+impl<T, M, F> svc::Param<http::Variant> for MatchedBackend<T, M, F>
+where
+    T: svc::Param<http::Variant>,
+{
+    fn param(&self) -> http::Variant {
+        svc::Param::param(&self.params.concrete.parent)
+    }
+}
+
 // === impl Http ===
 
 impl<T> filters::Apply for Http<T> {
@@ -147,7 +162,10 @@ impl<T> filters::Apply for Http<T> {
     }
 }
 
-impl<T> metrics::MkStreamLabel for Http<T> {
+impl<T> metrics::MkStreamLabel for Http<T>
+where
+    T: svc::Param<http::Variant>,
+{
     type StatusLabels = metrics::labels::HttpRouteBackendRsp;
     type DurationLabels = metrics::labels::RouteBackend;
     type StreamLabel = metrics::LabelHttpRouteBackendRsp;
@@ -156,8 +174,10 @@ impl<T> metrics::MkStreamLabel for Http<T> {
         let parent = self.params.concrete.parent_ref.clone();
         let route = self.params.route_ref.clone();
         let backend = self.params.concrete.backend_ref.clone();
+        let port = self.params.concrete.target.port();
+        let version = svc::Param::<http::Variant>::param(&self.params.concrete.parent);
         Some(metrics::LabelHttpRsp::from(
-            metrics::labels::RouteBackend::from((parent, route, backend)),
+            metrics::labels::RouteBackend::from((parent, route, backend, port, Some(version))),
         ))
     }
 }
@@ -175,7 +195,10 @@ impl<T> filters::Apply for Grpc<T> {
     }
 }
 
-impl<T> metrics::MkStreamLabel for Grpc<T> {
+impl<T> metrics::MkStreamLabel for Grpc<T>
+where
+    T: svc::Param<http::Variant>,
+{
     type StatusLabels = metrics::labels::GrpcRouteBackendRsp;
     type DurationLabels = metrics::labels::RouteBackend;
     type StreamLabel = metrics::LabelGrpcRouteBackendRsp;
@@ -184,8 +207,10 @@ impl<T> metrics::MkStreamLabel for Grpc<T> {
         let parent = self.params.concrete.parent_ref.clone();
         let route = self.params.route_ref.clone();
         let backend = self.params.concrete.backend_ref.clone();
+        let port = self.params.concrete.target.port();
+        let version = svc::Param::<http::Variant>::param(&self.params.concrete.parent);
         Some(metrics::LabelGrpcRsp::from(
-            metrics::labels::RouteBackend::from((parent, route, backend)),
+            metrics::labels::RouteBackend::from((parent, route, backend, port, Some(version))),
         ))
     }
 }
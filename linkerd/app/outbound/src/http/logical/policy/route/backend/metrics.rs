@@ -1,5 +1,5 @@
-use crate::{BackendRef, ParentRef, RouteRef};
-use linkerd_app_core::{metrics::prom, svc};
+use crate::{http::concrete::Dispatch, BackendRef, ParentRef, RouteRef};
+use linkerd_app_core::{metrics::prom, proxy::http, svc};
 use linkerd_http_prom::{
     body_data::response::{BodyDataMetrics, NewRecordBodyData, ResponseBodyFamilies},
     record_response::{self, NewResponseDuration, StreamLabel},
@@ -96,8 +96,11 @@ impl<L: StreamLabel> RouteBackendMetrics<L> {
         p: ParentRef,
         r: RouteRef,
         b: BackendRef,
+        port: Option<u16>,
+        version: Option<http::Variant>,
     ) -> linkerd_http_prom::RequestCount {
-        self.requests.metrics(&labels::RouteBackend(p, r, b))
+        self.requests
+            .metrics(&labels::RouteBackend(p, r, b, port, version))
     }
 
     #[cfg(test)]
@@ -139,10 +142,18 @@ impl<L: StreamLabel> Clone for RouteBackendMetrics<L> {
 impl<T> svc::ExtractParam<RequestCount, T> for ExtractRequestCount
 where
     T: svc::Param<ParentRef> + svc::Param<RouteRef> + svc::Param<BackendRef>,
+    T: svc::Param<Dispatch> + svc::Param<http::Variant>,
 {
     fn extract_param(&self, t: &T) -> RequestCount {
-        self.0
-            .metrics(&labels::RouteBackend(t.param(), t.param(), t.param()))
+        let port = svc::Param::<Dispatch>::param(t).port();
+        let version = svc::Param::<http::Variant>::param(t);
+        self.0.metrics(&labels::RouteBackend(
+            t.param(),
+            t.param(),
+            t.param(),
+            port,
+            Some(version),
+        ))
     }
 }
 
@@ -151,10 +162,13 @@ where
 impl<T> svc::ExtractParam<BodyDataMetrics, T> for ExtractRecordBodyDataParams
 where
     T: svc::Param<ParentRef> + svc::Param<RouteRef> + svc::Param<BackendRef>,
+    T: svc::Param<Dispatch> + svc::Param<http::Variant>,
 {
     fn extract_param(&self, t: &T) -> BodyDataMetrics {
         let Self(families) = self;
-        let labels = labels::RouteBackend(t.param(), t.param(), t.param());
+        let port = svc::Param::<Dispatch>::param(t).port();
+        let version = svc::Param::<http::Variant>::param(t);
+        let labels = labels::RouteBackend(t.param(), t.param(), t.param(), port, Some(version));
 
         families.metrics(&labels)
     }
@@ -24,11 +24,21 @@ async fn http_request_statuses() {
     let (mut svc, mut handle) =
         mock_http_route_backend_metrics(&metrics, &parent_ref, &route_ref, &backend_ref);
 
-    let route_backend =
-        labels::RouteBackend(parent_ref.clone(), route_ref.clone(), backend_ref.clone());
-
-    let requests =
-        metrics.backend_request_count(parent_ref.clone(), route_ref.clone(), backend_ref.clone());
+    let route_backend = labels::RouteBackend(
+        parent_ref.clone(),
+        route_ref.clone(),
+        backend_ref.clone(),
+        Some(8080),
+        Some(svc::http::Variant::Http1),
+    );
+
+    let requests = metrics.backend_request_count(
+        parent_ref.clone(),
+        route_ref.clone(),
+        backend_ref.clone(),
+        Some(8080),
+        Some(svc::http::Variant::Http1),
+    );
     assert_eq!(requests.get(), 0);
 
     // Send one request and ensure it's counted.
@@ -37,6 +47,9 @@ async fn http_request_statuses() {
         labels::HttpRsp {
             status: Some(http::StatusCode::OK),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(&ok, &mut handle, &mut svc, Default::default(), |tx| {
@@ -57,6 +70,9 @@ async fn http_request_statuses() {
         labels::HttpRsp {
             status: Some(http::StatusCode::NO_CONTENT),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -82,6 +98,9 @@ async fn http_request_statuses() {
         labels::HttpRsp {
             status: None,
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(&unknown, &mut handle, &mut svc, Default::default(), |tx| {
@@ -97,6 +116,9 @@ async fn http_request_statuses() {
         labels::HttpRsp {
             status: Some(http::StatusCode::OK),
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(&mixed, &mut handle, &mut svc, Default::default(), |tx| {
@@ -151,7 +173,13 @@ async fn body_data_layer_records_frames() -> Result<(), Error> {
 
     // Acquire the counters for this backend.
     tracing::info!("acquiring response body metrics");
-    let labels = labels::RouteBackend(parent_ref.clone(), route_ref.clone(), backend_ref.clone());
+    let labels = labels::RouteBackend(
+        parent_ref.clone(),
+        route_ref.clone(),
+        backend_ref.clone(),
+        Some(8080),
+        Some(svc::http::Variant::Http1),
+    );
     let BodyDataMetrics {
         // TODO(kate): currently, histograms do not expose their observation count or sum. so,
         // we're left unable to exercise these metrics until prometheus/client_rust#242 lands.
@@ -266,15 +294,29 @@ async fn grpc_request_statuses_ok() {
     let (mut svc, mut handle) =
         mock_grpc_route_backend_metrics(&metrics, &parent_ref, &route_ref, &backend_ref);
 
-    let requests =
-        metrics.backend_request_count(parent_ref.clone(), route_ref.clone(), backend_ref.clone());
+    let requests = metrics.backend_request_count(
+        parent_ref.clone(),
+        route_ref.clone(),
+        backend_ref.clone(),
+        Some(8080),
+        Some(svc::http::Variant::H2),
+    );
     assert_eq!(requests.get(), 0);
 
     let ok = metrics.get_statuses(&labels::Rsp(
-        labels::RouteBackend(parent_ref.clone(), route_ref.clone(), backend_ref.clone()),
+        labels::RouteBackend(
+            parent_ref.clone(),
+            route_ref.clone(),
+            backend_ref.clone(),
+            Some(8080),
+            Some(svc::http::Variant::H2),
+        ),
         labels::GrpcRsp {
             status: Some(tonic::Code::Ok),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -310,10 +352,19 @@ async fn grpc_request_statuses_not_found() {
         mock_grpc_route_backend_metrics(&metrics, &parent_ref, &route_ref, &backend_ref);
 
     let not_found = metrics.get_statuses(&labels::Rsp(
-        labels::RouteBackend(parent_ref.clone(), route_ref.clone(), backend_ref.clone()),
+        labels::RouteBackend(
+            parent_ref.clone(),
+            route_ref.clone(),
+            backend_ref.clone(),
+            Some(8080),
+            Some(svc::http::Variant::H2),
+        ),
         labels::GrpcRsp {
             status: Some(tonic::Code::NotFound),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -348,10 +399,19 @@ async fn grpc_request_statuses_error_response() {
         mock_grpc_route_backend_metrics(&metrics, &parent_ref, &route_ref, &backend_ref);
 
     let unknown = metrics.get_statuses(&labels::Rsp(
-        labels::RouteBackend(parent_ref.clone(), route_ref.clone(), backend_ref.clone()),
+        labels::RouteBackend(
+            parent_ref.clone(),
+            route_ref.clone(),
+            backend_ref.clone(),
+            Some(8080),
+            Some(svc::http::Variant::H2),
+        ),
         labels::GrpcRsp {
             status: None,
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -380,10 +440,19 @@ async fn grpc_request_statuses_error_body() {
         mock_grpc_route_backend_metrics(&metrics, &parent_ref, &route_ref, &backend_ref);
 
     let unknown = metrics.get_statuses(&labels::Rsp(
-        labels::RouteBackend(parent_ref.clone(), route_ref.clone(), backend_ref.clone()),
+        labels::RouteBackend(
+            parent_ref.clone(),
+            route_ref.clone(),
+            backend_ref.clone(),
+            Some(8080),
+            Some(svc::http::Variant::H2),
+        ),
         labels::GrpcRsp {
             status: None,
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -434,7 +503,7 @@ fn mock_http_route_backend_metrics(
 
     let (tx, handle) = tower_test::mock::pair::<http::Request<BoxBody>, http::Response<BoxBody>>();
     let svc = super::layer(metrics)
-        .layer(move |_t: Http<()>| tx.clone())
+        .layer(move |_t: Http<svc::http::Variant>| tx.clone())
         .new_service(Http {
             r#match,
             params: Backend {
@@ -450,7 +519,7 @@ fn mock_http_route_backend_metrics(
                     ),
                     authority: None,
                     failure_accrual: Default::default(),
-                    parent: (),
+                    parent: svc::http::Variant::Http1,
                     parent_ref: parent_ref.clone(),
                     backend_ref: backend_ref.clone(),
                 },
@@ -490,7 +559,7 @@ fn mock_grpc_route_backend_metrics(
 
     let (tx, handle) = tower_test::mock::pair::<http::Request<BoxBody>, http::Response<BoxBody>>();
     let svc = super::layer(metrics)
-        .layer(move |_t: Grpc<()>| tx.clone())
+        .layer(move |_t: Grpc<svc::http::Variant>| tx.clone())
         .new_service(Grpc {
             r#match,
             params: Backend {
@@ -506,7 +575,7 @@ fn mock_grpc_route_backend_metrics(
                     ),
                     authority: None,
                     failure_accrual: Default::default(),
-                    parent: (),
+                    parent: svc::http::Variant::H2,
                     parent_ref: parent_ref.clone(),
                     backend_ref: backend_ref.clone(),
                 },
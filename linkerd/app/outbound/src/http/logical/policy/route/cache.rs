@@ -0,0 +1,10 @@
+use super::metrics::labels::Route as RouteLabels;
+use linkerd_http_cache as cache;
+
+pub use linkerd_http_cache::ResponseCache;
+
+/// Serves requests from the shared response cache, if the route enables
+/// caching.
+pub type NewCache<X, N> = cache::NewCache<RouteLabels, (), X, N>;
+
+pub type RouteCacheMetrics = cache::MetricFamilies<RouteLabels>;
@@ -0,0 +1,9 @@
+use super::metrics::labels::Route as RouteLabels;
+use linkerd_http_coalesce as coalesce;
+
+pub use linkerd_http_coalesce::Coalescer;
+
+/// Coalesces identical in-flight requests, if the route enables coalescing.
+pub type NewCoalesce<X, N> = coalesce::NewCoalesce<RouteLabels, (), X, N>;
+
+pub type RouteCoalesceMetrics = coalesce::MetricFamilies<RouteLabels>;
@@ -0,0 +1,249 @@
+//! Supports overriding a single request's route configuration with an
+//! `l5d-route-debug` header, to help diagnose a specific route.
+//!
+//! The header holds a comma-separated list of directives:
+//!
+//! - `no-retry` disables the route's retries;
+//! - `no-timeout` disables the route's timeouts;
+//! - `rule=<index>` selects the rule at the given index of the matched route.
+//!
+//! The header is only honored when enabled by configuration, but it is always
+//! stripped before requests are forwarded. Invalid directives are ignored.
+
+use linkerd_app_core::{
+    metrics::prom,
+    proxy::http::{self, HeaderName, HeaderValue},
+    svc,
+};
+use std::{
+    fmt,
+    task::{Context, Poll},
+};
+
+pub const HEADER: HeaderName = HeaderName::from_static("l5d-route-debug");
+
+/// The directives set by a request's `l5d-route-debug` header.
+///
+/// This is set as a request extension when the header is honored and holds at
+/// least one valid directive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteDebug {
+    pub no_retry: bool,
+    pub no_timeout: bool,
+    pub rule: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RouteDebugMetrics {
+    requests: prom::Counter,
+    invalid_directives: prom::Counter,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewRouteDebug<N> {
+    enabled: bool,
+    metrics: RouteDebugMetrics,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SetRouteDebug<S> {
+    enabled: bool,
+    metrics: RouteDebugMetrics,
+    inner: S,
+}
+
+// === impl RouteDebug ===
+
+impl RouteDebug {
+    /// Parses the directives in the header values, returning `None` when none
+    /// are valid. Invalid directives are counted and ignored.
+    fn parse<'v>(
+        values: impl IntoIterator<Item = &'v HeaderValue>,
+        metrics: &RouteDebugMetrics,
+    ) -> Option<Self> {
+        let mut debug = Self::default();
+        let mut valid = false;
+        for value in values {
+            let Ok(value) = value.to_str() else {
+                tracing::debug!(?value, "Ignoring invalid route debug header");
+                metrics.invalid_directives.inc();
+                continue;
+            };
+            for directive in value.split(',').map(str::trim) {
+                if directive.eq_ignore_ascii_case("no-retry") {
+                    debug.no_retry = true;
+                } else if directive.eq_ignore_ascii_case("no-timeout") {
+                    debug.no_timeout = true;
+                } else if let Some(rule) = directive
+                    .strip_prefix("rule=")
+                    .and_then(|idx| idx.parse::<usize>().ok())
+                {
+                    debug.rule = Some(rule);
+                } else {
+                    tracing::debug!(?directive, "Ignoring invalid route debug directive");
+                    metrics.invalid_directives.inc();
+                    continue;
+                }
+                valid = true;
+            }
+        }
+        valid.then_some(debug)
+    }
+}
+
+impl fmt::Display for RouteDebug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            no_retry,
+            no_timeout,
+            rule,
+        } = self;
+        let directives = no_retry
+            .then(|| "no-retry".to_string())
+            .into_iter()
+            .chain(no_timeout.then(|| "no-timeout".to_string()))
+            .chain(rule.map(|idx| format!("rule={idx}")))
+            .collect::<Vec<_>>();
+        f.write_str(&directives.join(","))
+    }
+}
+
+// === impl RouteDebugMetrics ===
+
+impl RouteDebugMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let requests = prom::Counter::default();
+        registry.register(
+            "requests",
+            "The number of requests whose route configuration was overridden by a debug header",
+            requests.clone(),
+        );
+
+        let invalid_directives = prom::Counter::default();
+        registry.register(
+            "invalid_directives",
+            "The number of invalid route debug header directives that were ignored",
+            invalid_directives.clone(),
+        );
+
+        Self {
+            requests,
+            invalid_directives,
+        }
+    }
+}
+
+// === impl NewRouteDebug ===
+
+impl<N> NewRouteDebug<N> {
+    pub(crate) fn layer(
+        enabled: bool,
+        metrics: RouteDebugMetrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            enabled,
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRouteDebug<N>
+where
+    N: svc::NewService<T>,
+{
+    type Service = SetRouteDebug<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        SetRouteDebug {
+            enabled: self.enabled,
+            metrics: self.metrics.clone(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl SetRouteDebug ===
+
+impl<B, S> svc::Service<http::Request<B>> for SetRouteDebug<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if self.enabled {
+            let debug = RouteDebug::parse(req.headers().get_all(&HEADER), &self.metrics);
+            if let Some(debug) = debug {
+                tracing::debug!(%debug, "Overriding route configuration");
+                self.metrics.requests.inc();
+                req.extensions_mut().insert(debug);
+            }
+        }
+
+        // Never forward the header, whether or not it is honored.
+        req.headers_mut().remove(&HEADER);
+
+        self.inner.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(values: &[&'static str]) -> (Option<RouteDebug>, u64) {
+        let metrics = RouteDebugMetrics::default();
+        let values = values
+            .iter()
+            .map(|v| HeaderValue::from_static(v))
+            .collect::<Vec<_>>();
+        let debug = RouteDebug::parse(&values, &metrics);
+        (debug, metrics.invalid_directives.get())
+    }
+
+    #[test]
+    fn parses_directives() {
+        assert_eq!(parse(&[]), (None, 0));
+        assert_eq!(
+            parse(&["no-retry, no-timeout", "rule=2"]),
+            (
+                Some(RouteDebug {
+                    no_retry: true,
+                    no_timeout: true,
+                    rule: Some(2),
+                }),
+                0
+            )
+        );
+        assert_eq!(
+            parse(&["NO-RETRY,rule=-1,bogus"]),
+            (
+                Some(RouteDebug {
+                    no_retry: true,
+                    ..Default::default()
+                }),
+                2
+            )
+        );
+        assert_eq!(parse(&["rule=x"]), (None, 1));
+    }
+
+    #[test]
+    fn displays_directives() {
+        let debug = RouteDebug {
+            no_retry: true,
+            no_timeout: false,
+            rule: Some(1),
+        };
+        assert_eq!(debug.to_string(), "no-retry,rule=1");
+    }
+}
@@ -0,0 +1,186 @@
+use super::errors;
+use futures::{future, FutureExt, TryFutureExt};
+use linkerd_app_core::{proxy::http, svc, Error, Result};
+use linkerd_http_route::http::filter::DecompressRequest;
+use std::{
+    io::{self, Write},
+    task::{Context, Poll},
+};
+
+/// Configures request body decompression for a route.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Params(pub Option<DecompressRequest>);
+
+/// Decompresses gzip-encoded request bodies, as configured by a route's
+/// [`DecompressRequest`] filter.
+///
+/// Request bodies are decompressed fully before the request is dispatched so
+/// that requests exceeding the configured limit are failed without sending a
+/// partial body upstream.
+#[derive(Clone, Debug)]
+pub(crate) struct NewDecompress<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Decompress<S> {
+    filter: Option<DecompressRequest>,
+    inner: S,
+}
+
+/// A writer that fails once the decompressed body exceeds its limit, so that
+/// highly-compressed bodies are never fully inflated into memory.
+struct LimitedWriter {
+    buf: Vec<u8>,
+    max_bytes: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("decompressed body exceeds limit")]
+struct LimitExceeded;
+
+// === impl NewDecompress ===
+
+impl<N> NewDecompress<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewDecompress<N>
+where
+    T: svc::Param<Params>,
+    N: svc::NewService<T>,
+{
+    type Service = Decompress<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Params(filter) = target.param();
+        let inner = self.inner.new_service(target);
+        Decompress { filter, inner }
+    }
+}
+
+// === impl Decompress ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for Decompress<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S: Clone + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<S::Future, Error>,
+        future::BoxFuture<'static, Result<http::Response<http::BoxBody>>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let max_bytes = match &self.filter {
+            Some(filter) if filter.applies(req.headers()) => filter.max_bytes,
+            _ => return future::Either::Left(self.inner.call(req).err_into()),
+        };
+
+        // The inner service has been driven to readiness, so it must be used
+        // to dispatch the request once the body has been decompressed.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        future::Either::Right(
+            async move {
+                let (mut parts, body) = req.into_parts();
+                let (body, trailers) = decompress(body, max_bytes).await?;
+                tracing::debug!(bytes = body.len(), "Decompressed request body");
+
+                parts.headers.remove(http::header::CONTENT_ENCODING);
+                parts.headers.remove(http::header::TRANSFER_ENCODING);
+                parts
+                    .headers
+                    .insert(http::header::CONTENT_LENGTH, body.len().into());
+
+                let mut frames = vec![Ok::<_, Error>(http_body::Frame::data(body))];
+                frames.extend(trailers.map(|t| Ok(http_body::Frame::trailers(t))));
+                let body = http::BoxBody::new(http_body_util::StreamBody::new(
+                    futures::stream::iter(frames),
+                ));
+
+                inner
+                    .call(http::Request::from_parts(parts, body))
+                    .await
+                    .map_err(Into::into)
+            }
+            .boxed(),
+        )
+    }
+}
+
+/// Reads and decompresses a gzip-encoded body, failing if the decompressed
+/// body exceeds `max_bytes`.
+async fn decompress(
+    mut body: http::BoxBody,
+    max_bytes: usize,
+) -> Result<(bytes::Bytes, Option<http::HeaderMap>)> {
+    use bytes::Buf;
+    use http_body_util::BodyExt;
+
+    let mut decoder = flate2::write::GzDecoder::new(LimitedWriter {
+        buf: Vec::new(),
+        max_bytes,
+    });
+    let mut trailers = None;
+    while let Some(frame) = body.frame().await {
+        let frame = match frame?.into_data() {
+            Ok(mut data) => {
+                while data.has_remaining() {
+                    let chunk = data.chunk();
+                    decoder.write_all(chunk).map_err(decode_error(max_bytes))?;
+                    let n = chunk.len();
+                    data.advance(n);
+                }
+                continue;
+            }
+            Err(frame) => frame,
+        };
+        if let Ok(t) = frame.into_trailers() {
+            trailers = Some(t);
+        }
+    }
+
+    let LimitedWriter { buf, .. } = decoder.finish().map_err(decode_error(max_bytes))?;
+    Ok((buf.into(), trailers))
+}
+
+fn decode_error(max_bytes: usize) -> impl Fn(io::Error) -> Error {
+    move |error| {
+        if error
+            .get_ref()
+            .is_some_and(|e| e.downcast_ref::<LimitExceeded>().is_some())
+        {
+            return errors::HttpRouteRequestTooLarge(max_bytes).into();
+        }
+        errors::HttpRouteInvalidRequestEncoding(error).into()
+    }
+}
+
+// === impl LimitedWriter ===
+
+impl Write for LimitedWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > self.max_bytes {
+            return Err(io::Error::other(LimitExceeded));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
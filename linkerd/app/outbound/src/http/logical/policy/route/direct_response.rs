@@ -0,0 +1,131 @@
+//! Responds to requests that match a route without forwarding them, e.g. to
+//! serve a maintenance page.
+//!
+//! A route's `DirectResponse` filter configures a static response. HTTP routes
+//! respond with the configured status, headers, and body; gRPC routes respond
+//! with a trailers-only response that carries the configured `grpc-status` and
+//! `grpc-message`. Direct responses are served before faults are injected and
+//! before any backend is selected, so backends need not be available.
+
+use futures::{future, TryFutureExt};
+use linkerd_app_core::{proxy::http, svc, Error, Result};
+use linkerd_proxy_client_policy as policy;
+use std::task::{Context, Poll};
+
+/// A route's direct response, if it has a `DirectResponse` filter.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Params(pub Option<Response>);
+
+#[derive(Clone, Debug)]
+pub(crate) enum Response {
+    Http(policy::http::filter::DirectResponse),
+    Grpc(policy::grpc::filter::DirectResponse),
+}
+
+/// Marks responses that were served by a route's `DirectResponse` filter, so
+/// that they may be distinguished in metrics.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Direct;
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewDirectResponse<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DirectResponse<S> {
+    response: Option<Response>,
+    inner: S,
+}
+
+// === impl NewDirectResponse ===
+
+impl<N> NewDirectResponse<N> {
+    pub fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewDirectResponse<N>
+where
+    T: svc::Param<Params>,
+    N: svc::NewService<T>,
+{
+    type Service = DirectResponse<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Params(response) = target.param();
+        let inner = self.inner.new_service(target);
+        DirectResponse { response, inner }
+    }
+}
+
+// === impl DirectResponse ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for DirectResponse<S>
+where
+    S: svc::Service<http::Request<http::BoxBody>, Response = http::Response<http::BoxBody>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::Ready<Result<http::Response<http::BoxBody>>>,
+        future::ErrInto<S::Future, Error>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        // Requests are never dispatched to the inner service, so it need not
+        // be ready.
+        if self.response.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        let Some(response) = self.response.as_ref() else {
+            return future::Either::Right(self.inner.call(req).err_into());
+        };
+        let mut rsp = match response {
+            Response::Http(direct) => http_response(direct, req.version()),
+            Response::Grpc(direct) => grpc_response(direct),
+        };
+        tracing::debug!(status = %rsp.status(), "Serving direct response");
+        rsp.extensions_mut().insert(Direct);
+        future::Either::Left(future::ok(rsp))
+    }
+}
+
+fn http_response(
+    direct: &policy::http::filter::DirectResponse,
+    version: ::http::Version,
+) -> http::Response<http::BoxBody> {
+    let body = direct.body().clone();
+    let mut rsp = http::Response::new(http::BoxBody::new(http_body_util::Full::new(body)));
+    *rsp.status_mut() = direct.status;
+    *rsp.version_mut() = version;
+    for (name, value) in &direct.headers {
+        rsp.headers_mut().append(name.clone(), value.clone());
+    }
+    rsp
+}
+
+fn grpc_response(direct: &policy::grpc::filter::DirectResponse) -> http::Response<http::BoxBody> {
+    let mut rsp = http::Response::new(http::BoxBody::empty());
+    *rsp.version_mut() = ::http::Version::HTTP_2;
+    let headers = rsp.headers_mut();
+    headers.insert(
+        ::http::header::CONTENT_TYPE,
+        ::http::HeaderValue::from_static("application/grpc"),
+    );
+    headers.insert("grpc-status", ::http::HeaderValue::from(direct.code));
+    match ::http::HeaderValue::from_str(&direct.message) {
+        Ok(message) => {
+            headers.insert("grpc-message", message);
+        }
+        Err(error) => tracing::debug!(%error, "Direct response message is not a valid header"),
+    }
+    rsp
+}
@@ -1,4 +1,4 @@
-use super::retry::RetryPolicy;
+use super::{debug::RouteDebug, retry::RetryPolicy};
 use linkerd_app_core::{config::ExponentialBackoff, proxy::http, svc};
 use linkerd_proxy_client_policy as policy;
 use std::task::{Context, Poll};
@@ -65,11 +65,21 @@ where
     }
 
     fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
-        let retry = self.configure_retry(req.headers_mut());
+        let mut retry = self.configure_retry(req.headers_mut());
+        let mut timeouts = self.configure_timeouts(req.headers_mut());
+
+        // A debug header may disable the route's retries and timeouts.
+        if let Some(debug) = req.extensions().get::<RouteDebug>() {
+            if debug.no_retry {
+                retry = None;
+            }
+            if debug.no_timeout {
+                timeouts = http::StreamTimeouts::default();
+            }
+        }
 
         // Ensure that we get response headers within the retry timeout. Note
         // that this may be cleared super::retry::RetryPolicy::set_extensions.
-        let mut timeouts = self.configure_timeouts(req.headers_mut());
         timeouts.response_headers = retry.as_ref().and_then(|r| r.timeout);
 
         tracing::debug!(?retry, ?timeouts, "Initializing route extensions");
@@ -146,6 +156,8 @@ impl<S> SetExtensions<S> {
         let mut timeouts = http::StreamTimeouts {
             response_headers: None,
             response_end: self.params.timeouts.response,
+            response_first_byte: self.params.timeouts.response_headers,
+            response_chunk_idle: self.params.timeouts.response_chunk_idle,
             idle: self.params.timeouts.idle,
             limit: self.params.timeouts.request.map(Into::into),
         };
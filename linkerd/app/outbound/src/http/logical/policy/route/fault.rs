@@ -0,0 +1,364 @@
+//! Injects faults into requests that match a route, for chaos testing.
+//!
+//! A route's [`policy::http::Fault`] may delay a percentage of requests before
+//! they are forwarded and abort a percentage of requests with a configured
+//! HTTP status or `grpc-status`. Faults are applied before requests may be
+//! retried, so aborted requests are not retried unless the route permits it.
+//!
+//! Whether each request is faulted is decided by a [`FaultRng`] shared by all
+//! routes, which may be seeded so that tests are reproducible.
+
+use super::{errors, metrics::labels};
+use futures::{
+    future::{self, Either},
+    FutureExt, TryFutureExt,
+};
+use linkerd_app_core::{proxy::http, svc, Error, Result};
+use linkerd_proxy_client_policy as policy;
+use parking_lot::Mutex;
+use rand::{distr::Distribution, rngs::SmallRng, Rng, SeedableRng};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::time;
+
+/// A route's fault injection configuration.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Params(pub(crate) Option<Faults>);
+
+#[derive(Clone, Debug)]
+pub(crate) struct Faults {
+    delay: Option<policy::http::FaultDelay>,
+    abort: Option<policy::http::FaultAbort<Abort>>,
+    retry_aborts: bool,
+}
+
+/// The error with which an aborted request fails.
+#[derive(Clone, Debug)]
+enum Abort {
+    Http(::http::StatusCode),
+    Grpc(u16),
+}
+
+/// Where faults are applied in a route stack.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Stage {
+    /// Applied once for each request, before it may be retried.
+    Request,
+
+    /// Applied for each attempt of a request, so that aborted requests may be
+    /// retried.
+    Attempt,
+}
+
+/// Decides which requests are faulted.
+///
+/// A single generator is shared by all routes so that, when it is seeded, the
+/// faults injected by the proxy are reproducible.
+#[derive(Clone, Debug)]
+pub struct FaultRng(Arc<Mutex<SmallRng>>);
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewInjectFaults<N> {
+    rng: FaultRng,
+    stage: Stage,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct InjectFaults<S> {
+    rng: FaultRng,
+    delay: Option<policy::http::FaultDelay>,
+    abort: Option<policy::http::FaultAbort<Abort>>,
+    inner: S,
+}
+
+type Delayed<F> = Pin<Box<dyn Future<Output = Result<F>> + Send + 'static>>;
+
+// === impl Faults ===
+
+impl From<policy::http::Fault> for Faults {
+    fn from(fault: policy::http::Fault) -> Self {
+        Self::new(fault, Abort::Http)
+    }
+}
+
+impl From<policy::http::Fault<tonic::Code>> for Faults {
+    fn from(fault: policy::http::Fault<tonic::Code>) -> Self {
+        Self::new(fault, |code| Abort::Grpc(code as u16))
+    }
+}
+
+impl Faults {
+    fn new<A>(fault: policy::http::Fault<A>, mk: impl FnOnce(A) -> Abort) -> Self {
+        let policy::http::Fault {
+            delay,
+            abort,
+            retry_aborts,
+        } = fault;
+        Self {
+            delay,
+            abort: abort.map(
+                |policy::http::FaultAbort {
+                     distribution,
+                     response,
+                 }| {
+                    policy::http::FaultAbort {
+                        distribution,
+                        response: mk(response),
+                    }
+                },
+            ),
+            retry_aborts,
+        }
+    }
+}
+
+// === impl Abort ===
+
+impl Abort {
+    fn error(&self) -> Error {
+        match *self {
+            Self::Http(status) => errors::HttpRouteFaultAbort { status }.into(),
+            Self::Grpc(code) => errors::GrpcRouteFaultAbort { code }.into(),
+        }
+    }
+}
+
+// === impl FaultRng ===
+
+impl FaultRng {
+    /// Returns a generator that is seeded with `seed`, if one is provided, or
+    /// from the thread-local generator otherwise.
+    pub fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => SmallRng::seed_from_u64(seed),
+            None => SmallRng::from_rng(&mut rand::rng()),
+        };
+        Self(Arc::new(Mutex::new(rng)))
+    }
+
+    fn sample(&self, dist: &policy::http::filter::Distribution) -> bool {
+        dist.sample(&mut *self.0.lock())
+    }
+
+    fn delay(&self, delay: &policy::http::FaultDelay) -> Option<time::Duration> {
+        if !self.sample(&delay.distribution) {
+            return None;
+        }
+        Some(match delay.duration {
+            policy::http::FaultDuration::Fixed(d) => d,
+            policy::http::FaultDuration::Random(ref range) => {
+                self.0.lock().random_range(range.clone())
+            }
+        })
+    }
+}
+
+impl Default for FaultRng {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+// === impl NewInjectFaults ===
+
+impl<N> NewInjectFaults<N> {
+    pub(crate) fn layer(
+        rng: FaultRng,
+        stage: Stage,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            rng: rng.clone(),
+            stage,
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewInjectFaults<N>
+where
+    T: svc::Param<Params>,
+    N: svc::NewService<T>,
+{
+    type Service = InjectFaults<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Params(faults) = target.param();
+        let (delay, abort) = match faults {
+            Some(Faults {
+                delay,
+                abort,
+                retry_aborts,
+            }) => match self.stage {
+                // Requests are delayed once, before they may be retried.
+                Stage::Request => (delay, abort.filter(|_| !retry_aborts)),
+                Stage::Attempt => (None, abort.filter(|_| retry_aborts)),
+            },
+            None => (None, None),
+        };
+        if delay.is_some() || abort.is_some() {
+            tracing::debug!(stage = ?self.stage, ?delay, ?abort, "Injecting faults");
+        }
+        InjectFaults {
+            rng: self.rng.clone(),
+            delay,
+            abort,
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl InjectFaults ===
+
+impl<B, RspB, S> svc::Service<http::Request<B>> for InjectFaults<S>
+where
+    B: Send + 'static,
+    RspB: Send + 'static,
+    S: svc::Service<http::Request<B>, Response = http::Response<RspB>, Error = Error>,
+    S: Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future =
+        Either<Either<future::Ready<Result<S::Response>>, S::Future>, Delayed<S::Response>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if let Some(abort) = self.abort.as_ref() {
+            if self.rng.sample(&abort.distribution) {
+                tracing::debug!(?abort.response, "Aborting request");
+                return Either::Left(Either::Left(future::err(abort.response.error())));
+            }
+        }
+
+        let Some(delay) = self.delay.as_ref().and_then(|d| self.rng.delay(d)) else {
+            return Either::Left(Either::Right(self.inner.call(req)));
+        };
+
+        // The inner service has been driven to readiness, so take it and leave
+        // a clone in its place.
+        tracing::debug!(?delay, "Delaying request");
+        let inner = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, inner);
+        Either::Right(Box::pin(time::sleep(delay).then(move |()| {
+            inner.call(req).map_ok(|mut rsp| {
+                rsp.extensions_mut().insert(labels::Fault::Delay);
+                rsp
+            })
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::svc::{Layer, NewService, ServiceExt};
+    use policy::http::filter::Distribution as Ratio;
+
+    fn fault(abort: u32, retry_aborts: bool) -> Params {
+        Params(Some(
+            policy::http::Fault {
+                delay: Some(policy::http::FaultDelay {
+                    distribution: Ratio::from_ratio(1, 1).unwrap(),
+                    duration: policy::http::FaultDuration::Fixed(time::Duration::from_secs(1)),
+                }),
+                abort: Some(policy::http::FaultAbort {
+                    distribution: Ratio::from_ratio(abort, 100).unwrap(),
+                    response: ::http::StatusCode::SERVICE_UNAVAILABLE,
+                }),
+                retry_aborts,
+            }
+            .into(),
+        ))
+    }
+
+    fn mk(stage: Stage, params: Params, rng: FaultRng) -> InjectFaults<svc::BoxCloneHttp> {
+        let inner = svc::BoxCloneHttp::new(svc::mk(|_: http::Request<http::BoxBody>| {
+            future::ok::<_, Error>(http::Response::new(http::BoxBody::empty()))
+        }));
+        NewInjectFaults::layer(rng, stage)
+            .layer(move |_: Params| inner.clone())
+            .new_service(params)
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn delays_requests() {
+        let svc = mk(Stage::Request, fault(0, false), FaultRng::new(Some(1)));
+
+        let start = time::Instant::now();
+        let rsp = svc
+            .oneshot(http::Request::new(http::BoxBody::empty()))
+            .await
+            .expect("request must succeed");
+        assert_eq!(
+            time::Instant::now().saturating_duration_since(start),
+            time::Duration::from_secs(1)
+        );
+        assert_eq!(
+            rsp.extensions().get::<labels::Fault>(),
+            Some(&labels::Fault::Delay)
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn aborts_requests() {
+        let svc = mk(Stage::Request, fault(100, false), FaultRng::new(Some(1)));
+        let error = svc
+            .oneshot(http::Request::new(http::BoxBody::empty()))
+            .await
+            .expect_err("request must be aborted");
+        assert_eq!(
+            error.downcast_ref::<errors::HttpRouteFaultAbort>(),
+            Some(&errors::HttpRouteFaultAbort {
+                status: ::http::StatusCode::SERVICE_UNAVAILABLE,
+            })
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn retryable_aborts_apply_to_attempts() {
+        // Only the delay is applied before retries.
+        let svc = mk(Stage::Request, fault(100, true), FaultRng::new(Some(1)));
+        svc.oneshot(http::Request::new(http::BoxBody::empty()))
+            .await
+            .expect("request must succeed");
+
+        let svc = mk(Stage::Attempt, fault(100, true), FaultRng::new(Some(1)));
+        let start = time::Instant::now();
+        svc.oneshot(http::Request::new(http::BoxBody::empty()))
+            .await
+            .expect_err("attempt must be aborted");
+        assert_eq!(
+            time::Instant::now().saturating_duration_since(start),
+            time::Duration::ZERO
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn seeded_faults_are_reproducible() {
+        async fn aborted(rng: FaultRng) -> Vec<bool> {
+            let mut aborted = vec![];
+            for _ in 0..100 {
+                let svc = mk(Stage::Request, fault(50, false), rng.clone());
+                let res = svc
+                    .oneshot(http::Request::new(http::BoxBody::empty()))
+                    .await;
+                aborted.push(res.is_err());
+            }
+            aborted
+        }
+
+        let first = aborted(FaultRng::new(Some(42))).await;
+        assert_eq!(first, aborted(FaultRng::new(Some(42))).await);
+        assert!(first.iter().any(|a| *a) && first.iter().any(|a| !*a));
+    }
+}
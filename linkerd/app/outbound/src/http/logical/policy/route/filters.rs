@@ -69,9 +69,29 @@ pub mod errors {
         pub message: Arc<str>,
     }
 
+    #[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
+    #[error("HTTP request aborted by fault injection with {status}")]
+    pub struct HttpRouteFaultAbort {
+        pub status: ::http::StatusCode,
+    }
+
+    #[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
+    #[error("gRPC request aborted by fault injection with {code}")]
+    pub struct GrpcRouteFaultAbort {
+        pub code: u16,
+    }
+
     #[derive(Debug, thiserror::Error)]
     #[error("invalid client policy: {0}")]
     pub struct HttpInvalidPolicy(pub &'static str);
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("decompressed request body exceeds {0} bytes")]
+    pub struct HttpRouteRequestTooLarge(pub usize);
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("invalid gzip request body: {0}")]
+    pub struct HttpRouteInvalidRequestEncoding(#[source] pub std::io::Error);
 }
 
 pub(crate) trait Apply {
@@ -115,6 +135,10 @@ pub fn apply_http_request<B>(
                 return Err(errors::HttpInvalidPolicy(msg).into());
             }
             http::Filter::ResponseHeaders(_) => {} // ResponseHeaders filter does not apply to requests.
+            http::Filter::DecompressRequest(_) => {} // DecompressRequest filter is applied to request bodies by the route stack.
+            http::Filter::AssertWorkloadIdentity(_) => {} // AssertWorkloadIdentity filter is applied by the route stack.
+            http::Filter::DirectResponse(_) => {} // DirectResponse filter is applied by the route stack.
+            http::Filter::RewriteAuthority(_) => {} // RewriteAuthority filter is applied by the route stack.
         }
     }
 
@@ -132,6 +156,10 @@ pub fn apply_http_response<B>(
             http::Filter::Redirect(_) => {}      // Redirect filter does not apply to responses.
             http::Filter::RequestHeaders(_) => {} // RequestHeaders filter does not apply to responses.
             http::Filter::InternalError(_) => {} // InternalError filter does not apply to responses.
+            http::Filter::DecompressRequest(_) => {} // DecompressRequest filter does not apply to responses.
+            http::Filter::AssertWorkloadIdentity(_) => {} // AssertWorkloadIdentity filter does not apply to responses.
+            http::Filter::DirectResponse(_) => {} // DirectResponse filter does not apply to responses.
+            http::Filter::RewriteAuthority(_) => {} // RewriteAuthority filter is applied by the route stack.
             http::Filter::ResponseHeaders(rh) => rh.apply(rsp.headers_mut()),
         }
     }
@@ -156,6 +184,8 @@ pub fn apply_grpc_request<B>(
                 rh.apply(req.headers_mut());
             }
 
+            grpc::Filter::DirectResponse(_) => {} // DirectResponse filter is applied by the route stack.
+
             grpc::Filter::InternalError(msg) => {
                 return Err(errors::HttpInvalidPolicy(msg).into());
             }
@@ -173,6 +203,7 @@ pub fn apply_grpc_response<B>(
         match filter {
             grpc::Filter::InjectFailure(_) => {} // InjectFailure filter does not apply to responses.
             grpc::Filter::RequestHeaders(_) => {} // RequestHeaders filter does not apply to responses.
+            grpc::Filter::DirectResponse(_) => {} // DirectResponse filter does not apply to responses.
             grpc::Filter::InternalError(_) => {} // InternalError filter does not apply to responses.
         }
     }
@@ -0,0 +1,671 @@
+//! Guards weighted rollouts between a primary and a canary backend.
+//!
+//! When a route configures a [`policy::http::RolloutGuard`] and distributes
+//! requests over exactly two weighted backends, the responses from each backend
+//! are classified and recorded over a sliding window. If the canary's failure
+//! rate regresses beyond the configured threshold relative to the primary's, the
+//! guard trips and all of the route's requests are sent to the primary until the
+//! cooldown elapses.
+//!
+//! Guard state is held in a [`RolloutGuards`] registry so that it is preserved
+//! as routes are rebuilt on policy updates.
+
+use super::{metrics::labels, Backend};
+use crate::{BackendRef, ParentRef, RouteRef};
+use linkerd_app_core::{
+    classify,
+    metrics::prom,
+    proxy::http::{self, classify::BroadcastClassification},
+    svc, Error,
+};
+use linkerd_distribute::{Distribute, Distribution};
+use linkerd_proxy_client_policy as policy;
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    hash::Hash,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::{sync::mpsc, time};
+use tracing::{info_span, Instrument};
+
+/// A route's rollout guard configuration.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Params(pub(crate) Option<policy::http::RolloutGuard>);
+
+/// Builds [`Distribute`] services, wrapped with a [`Guarded`] service when the
+/// route configures a rollout guard.
+#[derive(Clone, Debug)]
+pub(crate) struct NewGuardedDistribute<N> {
+    inner: N,
+    guards: RolloutGuards,
+}
+
+/// Distributes requests over a route's configured distribution unless the
+/// rollout guard has tripped, in which case requests are sent to the primary
+/// backend.
+#[derive(Debug)]
+pub(crate) struct Guarded<K, S> {
+    guard: Arc<Guard>,
+    configured: Distribute<K, Classified<S>>,
+    pinned: Distribute<K, Classified<S>>,
+    is_pinned: bool,
+}
+
+type Classified<S> = http::BoxResponse<BroadcastClassification<classify::Response, S>>;
+
+/// A registry of the rollout guards for all routes.
+#[derive(Clone, Debug, Default)]
+pub struct RolloutGuards {
+    guards: Arc<Mutex<HashMap<labels::RouteBackend, Arc<Guard>>>>,
+    metrics: Metrics,
+}
+
+/// A snapshot of a rollout guard's state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RolloutGuardState {
+    pub parent: ParentRef,
+    pub route: RouteRef,
+    pub canary: BackendRef,
+
+    /// Indicates whether the canary is currently removed from the route's
+    /// distribution.
+    pub tripped: bool,
+
+    /// The time remaining until a tripped guard resets.
+    pub cooldown_remaining: Option<time::Duration>,
+
+    /// The number of times the guard has tripped.
+    pub trips: u64,
+
+    /// The responses observed from the primary backend in the current window.
+    pub primary_window: WindowCounts,
+
+    /// The responses observed from the canary backend in the current window.
+    pub canary_window: WindowCounts,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WindowCounts {
+    pub requests: u64,
+    pub failures: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Metrics {
+    tripped: prom::Family<labels::RouteBackend, prom::Gauge>,
+    trips: prom::Family<labels::RouteBackend, prom::Counter>,
+}
+
+#[derive(Debug)]
+struct Guard {
+    labels: labels::RouteBackend,
+    state: Mutex<State>,
+    tripped: prom::Gauge,
+    trips: prom::Counter,
+}
+
+#[derive(Debug)]
+struct State {
+    config: policy::http::RolloutGuard,
+    primary: Window,
+    canary: Window,
+    tripped_until: Option<time::Instant>,
+}
+
+/// Counts responses over a sliding window of time-bucketed counters.
+#[derive(Debug, Default)]
+struct Window {
+    buckets: VecDeque<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    start: time::Instant,
+    counts: WindowCounts,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Side {
+    Primary,
+    Canary,
+}
+
+/// The number of buckets into which each window is divided.
+const BUCKETS: u32 = 10;
+
+const CHANNEL_CAPACITY: usize = 1_000;
+
+// === impl NewGuardedDistribute ===
+
+impl<N> NewGuardedDistribute<N> {
+    pub(crate) fn layer(
+        guards: RolloutGuards,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            inner,
+            guards: guards.clone(),
+        })
+    }
+}
+
+impl<T, M, F, P, N, KNew, S> svc::NewService<super::MatchedRoute<T, M, F, P>>
+    for NewGuardedDistribute<N>
+where
+    T: Clone + Debug + Eq + Hash,
+    F: Debug + Eq + Hash,
+    super::MatchedRoute<T, M, F, P>: svc::Param<Params>,
+    N: svc::NewService<super::MatchedRoute<T, M, F, P>, Service = KNew>,
+    KNew: svc::NewService<Backend<T, F>, Service = S>,
+{
+    type Service = svc::Either<Distribute<Backend<T, F>, S>, Guarded<Backend<T, F>, S>>;
+
+    fn new_service(&self, route: super::MatchedRoute<T, M, F, P>) -> Self::Service {
+        let Params(config) = svc::Param::param(&route);
+        let dist = route.params.distribution.clone();
+        let parent_ref = route.params.parent_ref.clone();
+        let route_ref = route.params.route_ref.clone();
+        let newk = self.inner.new_service(route);
+
+        let Some((config, (primary, canary))) = config.zip(primary_and_canary(&dist)) else {
+            tracing::debug!(backends = ?dist, "New distribution");
+            return svc::Either::Left(Distribute::new(dist, |k: &Backend<T, F>| {
+                newk.new_service(k.clone())
+            }));
+        };
+
+        // Guards are shared by stacks of all HTTP versions, so they are not
+        // keyed by version.
+        let labels = labels::RouteBackend(
+            parent_ref,
+            route_ref,
+            canary.concrete.backend_ref.clone(),
+            canary.concrete.target.port(),
+            None,
+        );
+        tracing::debug!(backends = ?dist, ?config, "New guarded distribution");
+        let guard = self.guards.guard(labels, config);
+
+        let (primary_tx, primary_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let (canary_tx, canary_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(
+            guard
+                .clone()
+                .record(primary_rx, canary_rx)
+                .instrument(info_span!("rollout_guard").or_current()),
+        );
+
+        let mk_backend = |k: &Backend<T, F>| {
+            let tx = if *k == canary {
+                canary_tx.clone()
+            } else {
+                primary_tx.clone()
+            };
+            http::BoxResponse::new(BroadcastClassification::new(
+                tx,
+                newk.new_service(k.clone()),
+            ))
+        };
+        svc::Either::Right(Guarded {
+            guard,
+            configured: Distribute::new(dist, mk_backend),
+            pinned: Distribute::new(Distribution::from(primary), mk_backend),
+            is_pinned: false,
+        })
+    }
+}
+
+/// Returns the primary and canary backends if the distribution is weighted
+/// over exactly two distinct backends.
+fn primary_and_canary<K: Clone + Eq>(dist: &Distribution<K>) -> Option<(K, K)> {
+    let Distribution::RandomAvailable(keys) = dist else {
+        return None;
+    };
+    let mut keys = keys.keys();
+    match (keys.next(), keys.next(), keys.next()) {
+        (Some(primary), Some(canary), None) if primary.key != canary.key => {
+            Some((primary.key.clone(), canary.key.clone()))
+        }
+        _ => None,
+    }
+}
+
+// === impl Guarded ===
+
+impl<K, S> svc::Service<http::Request<http::BoxBody>> for Guarded<K, S>
+where
+    K: Hash + Eq,
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = <Classified<S> as svc::Service<http::Request<http::BoxBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.is_pinned = self.guard.is_tripped(time::Instant::now());
+        if self.is_pinned {
+            self.pinned.poll_ready(cx)
+        } else {
+            self.configured.poll_ready(cx)
+        }
+    }
+
+    fn call(&mut self, req: http::Request<http::BoxBody>) -> Self::Future {
+        if self.is_pinned {
+            self.pinned.call(req)
+        } else {
+            self.configured.call(req)
+        }
+    }
+}
+
+impl<K: Clone, S: Clone> Clone for Guarded<K, S> {
+    fn clone(&self) -> Self {
+        Self {
+            guard: self.guard.clone(),
+            configured: self.configured.clone(),
+            pinned: self.pinned.clone(),
+            is_pinned: false,
+        }
+    }
+}
+
+// === impl RolloutGuards ===
+
+impl RolloutGuards {
+    pub(crate) fn register(reg: &mut prom::Registry) -> Self {
+        let metrics = Metrics::default();
+        reg.register(
+            "tripped",
+            "Indicates whether a route's canary backend has been removed from its distribution",
+            metrics.tripped.clone(),
+        );
+        reg.register(
+            "trips",
+            "The number of times a route's canary backend has been removed from its distribution",
+            metrics.trips.clone(),
+        );
+        Self {
+            guards: Default::default(),
+            metrics,
+        }
+    }
+
+    /// Returns a snapshot of all rollout guards.
+    pub fn guards(&self) -> Vec<RolloutGuardState> {
+        let now = time::Instant::now();
+        let mut guards = self
+            .guards
+            .lock()
+            .values()
+            .map(|g| g.snapshot(now))
+            .collect::<Vec<_>>();
+        guards.sort_by(|a, b| {
+            let key = |g: &RolloutGuardState| {
+                (
+                    g.parent.namespace().to_string(),
+                    g.parent.name().to_string(),
+                    g.route.name().to_string(),
+                    g.canary.name().to_string(),
+                )
+            };
+            key(a).cmp(&key(b))
+        });
+        guards
+    }
+
+    /// Returns the guard for a route's canary backend, updating its
+    /// configuration.
+    ///
+    /// Guards that are no longer used by any route are dropped unless they are
+    /// tripped, so that a tripped guard survives route updates.
+    fn guard(
+        &self,
+        labels: labels::RouteBackend,
+        config: policy::http::RolloutGuard,
+    ) -> Arc<Guard> {
+        let now = time::Instant::now();
+        let mut guards = self.guards.lock();
+        let guard = guards
+            .entry(labels.clone())
+            .or_insert_with(|| {
+                Arc::new(Guard {
+                    tripped: self.metrics.tripped.get_or_create(&labels).clone(),
+                    trips: self.metrics.trips.get_or_create(&labels).clone(),
+                    labels: labels.clone(),
+                    state: Mutex::new(State {
+                        config: config.clone(),
+                        primary: Window::default(),
+                        canary: Window::default(),
+                        tripped_until: None,
+                    }),
+                })
+            })
+            .clone();
+        guard.configure(config);
+
+        guards.retain(|l, g| *l == labels || Arc::strong_count(g) > 1 || g.is_tripped(now));
+        guard
+    }
+}
+
+// === impl Guard ===
+
+impl Guard {
+    fn configure(&self, config: policy::http::RolloutGuard) {
+        let mut state = self.state.lock();
+        if state.config != config {
+            tracing::debug!(?config, "Updating rollout guard");
+            if state.config.window != config.window {
+                state.primary = Window::default();
+                state.canary = Window::default();
+            }
+            state.config = config;
+        }
+    }
+
+    fn is_tripped(&self, now: time::Instant) -> bool {
+        self.state.lock().tripped_until.is_some_and(|t| t > now)
+    }
+
+    /// Records response classifications from each backend until all of the
+    /// guard's services have been dropped.
+    async fn record(
+        self: Arc<Self>,
+        mut primary: mpsc::Receiver<classify::Class>,
+        mut canary: mpsc::Receiver<classify::Class>,
+    ) {
+        loop {
+            let tripped_until = self.state.lock().tripped_until;
+            let (side, class) = tokio::select! {
+                Some(class) = primary.recv() => (Side::Primary, class),
+                Some(class) = canary.recv() => (Side::Canary, class),
+                () = time::sleep_until(tripped_until.unwrap_or_else(time::Instant::now)),
+                    if tripped_until.is_some() => {
+                    self.reset(time::Instant::now());
+                    continue;
+                }
+                else => return,
+            };
+            self.observe(time::Instant::now(), side, class.is_failure());
+        }
+    }
+
+    fn observe(&self, now: time::Instant, side: Side, is_failure: bool) {
+        let mut state = self.state.lock();
+        if state.tripped_until.is_some() {
+            // While the guard is tripped, the canary receives no requests and
+            // there is nothing to compare.
+            return;
+        }
+
+        let State {
+            config,
+            primary,
+            canary,
+            ..
+        } = &mut *state;
+        match side {
+            Side::Primary => primary.add(now, config.window, is_failure),
+            Side::Canary => canary.add(now, config.window, is_failure),
+        }
+
+        let canary = canary.counts(now, config.window);
+        if canary.requests == 0 || canary.requests < u64::from(config.min_requests) {
+            return;
+        }
+        let primary = primary.counts(now, config.window);
+        let increase = canary.failure_rate() - primary.failure_rate();
+        if increase <= f64::from(config.max_failure_rate_increase) {
+            return;
+        }
+
+        tracing::info!(
+            canary.failure_rate = canary.failure_rate(),
+            primary.failure_rate = primary.failure_rate(),
+            cooldown = ?config.cooldown,
+            "Canary backend regressed; sending all requests to the primary backend",
+        );
+        state.tripped_until = Some(now + state.config.cooldown);
+        state.primary = Window::default();
+        state.canary = Window::default();
+        self.tripped.set(1);
+        self.trips.inc();
+    }
+
+    fn reset(&self, now: time::Instant) {
+        let mut state = self.state.lock();
+        if state.tripped_until.is_some_and(|t| t <= now) {
+            tracing::info!("Rollout guard cooldown elapsed; restoring the configured distribution");
+            state.tripped_until = None;
+            self.tripped.set(0);
+        }
+    }
+
+    fn snapshot(&self, now: time::Instant) -> RolloutGuardState {
+        let mut state = self.state.lock();
+        let window = state.config.window;
+        let labels::RouteBackend(parent, route, canary, ..) = self.labels.clone();
+        RolloutGuardState {
+            parent,
+            route,
+            canary,
+            tripped: state.tripped_until.is_some_and(|t| t > now),
+            cooldown_remaining: state
+                .tripped_until
+                .filter(|t| *t > now)
+                .map(|t| t.saturating_duration_since(now)),
+            trips: self.trips.get(),
+            primary_window: state.primary.counts(now, window),
+            canary_window: state.canary.counts(now, window),
+        }
+    }
+}
+
+// === impl Window ===
+
+impl Window {
+    fn add(&mut self, now: time::Instant, window: time::Duration, is_failure: bool) {
+        self.evict(now, window);
+
+        let width = window / BUCKETS;
+        let bucket = match self.buckets.back_mut() {
+            Some(b) if now.saturating_duration_since(b.start) < width => b,
+            _ => {
+                self.buckets.push_back(Bucket {
+                    start: now,
+                    counts: WindowCounts::default(),
+                });
+                self.buckets.back_mut().expect("bucket must exist")
+            }
+        };
+        bucket.counts.requests += 1;
+        if is_failure {
+            bucket.counts.failures += 1;
+        }
+    }
+
+    fn counts(&mut self, now: time::Instant, window: time::Duration) -> WindowCounts {
+        self.evict(now, window);
+        self.buckets
+            .iter()
+            .fold(WindowCounts::default(), |acc, b| WindowCounts {
+                requests: acc.requests + b.counts.requests,
+                failures: acc.failures + b.counts.failures,
+            })
+    }
+
+    fn evict(&mut self, now: time::Instant, window: time::Duration) {
+        while let Some(b) = self.buckets.front() {
+            if now.saturating_duration_since(b.start) < window {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+    }
+}
+
+// === impl WindowCounts ===
+
+impl WindowCounts {
+    /// Returns the percentage of requests that failed.
+    pub fn failure_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        (self.failures as f64 / self.requests as f64) * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: policy::http::RolloutGuard = policy::http::RolloutGuard {
+        window: time::Duration::from_secs(10),
+        min_requests: 10,
+        max_failure_rate_increase: 20,
+        cooldown: time::Duration::from_secs(30),
+    };
+
+    fn labels(canary: &'static str) -> labels::RouteBackend {
+        labels::RouteBackend(
+            ParentRef(policy::Meta::new_default("parent")),
+            RouteRef(policy::Meta::new_default("route")),
+            BackendRef(policy::Meta::new_default(canary)),
+            Some(8080),
+            None,
+        )
+    }
+
+    fn failure() -> classify::Class {
+        classify::Class::Http(Err(::http::StatusCode::BAD_GATEWAY))
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn trips_on_regression() {
+        let guards = RolloutGuards::default();
+        let guard = guards.guard(labels("canary"), CONFIG);
+        let now = time::Instant::now();
+
+        // The primary fails 10% of requests.
+        for i in 0..100 {
+            guard.observe(now, Side::Primary, i % 10 == 0);
+        }
+
+        // The canary fails 30% of requests, which is within the threshold.
+        for i in 0..10 {
+            guard.observe(now, Side::Canary, i % 10 < 3);
+        }
+        assert!(!guard.is_tripped(now));
+
+        // Further failures exceed the threshold.
+        for _ in 0..3 {
+            guard.observe(now, Side::Canary, true);
+        }
+        assert!(guard.is_tripped(now));
+        assert!(!guard.is_tripped(now + CONFIG.cooldown));
+
+        let [state] = <[_; 1]>::try_from(guards.guards()).unwrap();
+        assert!(state.tripped);
+        assert_eq!(state.trips, 1);
+        assert_eq!(state.cooldown_remaining, Some(CONFIG.cooldown));
+        assert_eq!(state.canary_window, WindowCounts::default());
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn requires_min_requests() {
+        let guards = RolloutGuards::default();
+        let guard = guards.guard(labels("canary"), CONFIG);
+        let now = time::Instant::now();
+
+        for _ in 1..CONFIG.min_requests {
+            guard.observe(now, Side::Canary, true);
+        }
+        assert!(!guard.is_tripped(now));
+
+        guard.observe(now, Side::Canary, true);
+        assert!(guard.is_tripped(now));
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn window_slides() {
+        let guards = RolloutGuards::default();
+        let guard = guards.guard(labels("canary"), CONFIG);
+        let start = time::Instant::now();
+
+        for _ in 1..CONFIG.min_requests {
+            guard.observe(start, Side::Canary, true);
+        }
+
+        // The earlier failures have expired by the time the next one is
+        // observed.
+        let later = start + CONFIG.window;
+        guard.observe(later, Side::Canary, true);
+        assert!(!guard.is_tripped(later));
+
+        let [state] = <[_; 1]>::try_from(guards.guards()).unwrap();
+        assert_eq!(
+            guard.state.lock().canary.counts(later, CONFIG.window),
+            WindowCounts {
+                requests: 1,
+                failures: 1,
+            }
+        );
+        assert!(!state.tripped);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn resets_after_cooldown() {
+        let guards = RolloutGuards::default();
+        let guard = guards.guard(labels("canary"), CONFIG);
+        let (primary_tx, primary_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let (canary_tx, canary_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let task = tokio::spawn(guard.clone().record(primary_rx, canary_rx));
+
+        for _ in 0..CONFIG.min_requests {
+            canary_tx.send(failure()).await.unwrap();
+        }
+        tokio::task::yield_now().await;
+        assert!(guard.is_tripped(time::Instant::now()));
+        assert_eq!(guard.tripped.get(), 1);
+
+        time::sleep(CONFIG.cooldown).await;
+        tokio::task::yield_now().await;
+        assert!(!guard.is_tripped(time::Instant::now()));
+        assert_eq!(guard.tripped.get(), 0);
+        assert_eq!(guard.trips.get(), 1);
+
+        drop((primary_tx, canary_tx));
+        task.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn retains_tripped_guards() {
+        let guards = RolloutGuards::default();
+        let now = time::Instant::now();
+
+        // Unused guards are dropped.
+        drop(guards.guard(labels("a"), CONFIG));
+        let b = guards.guard(labels("b"), CONFIG);
+        assert_eq!(guards.guards().len(), 1);
+
+        // Tripped guards are retained, even when unused.
+        for _ in 0..CONFIG.min_requests {
+            b.observe(now, Side::Canary, true);
+        }
+        drop(b);
+        drop(guards.guard(labels("a"), CONFIG));
+        assert_eq!(guards.guards().len(), 2);
+
+        // A route update restores the tripped guard's state.
+        let b = guards.guard(labels("b"), CONFIG);
+        assert!(b.is_tripped(now));
+    }
+}
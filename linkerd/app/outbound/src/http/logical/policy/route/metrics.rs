@@ -1,4 +1,5 @@
-use super::{backend::metrics as backend, retry};
+use super::{backend::metrics as backend, cache, coalesce, direct_response, fault, guard, retry};
+use crate::http::concrete::Hairpin;
 use linkerd_app_core::{
     metrics::prom::{self, EncodeLabelSetMut},
     proxy::http,
@@ -8,6 +9,7 @@ use linkerd_http_prom::{
     body_data::request::{NewRecordBodyData, RequestBodyFamilies},
     record_response::{self, StreamLabel},
 };
+use linkerd_http_retry::BufferBudget;
 
 pub use linkerd_http_prom::record_response::MkStreamLabel;
 
@@ -28,6 +30,13 @@ pub struct RouteMetrics<R: StreamLabel, B: StreamLabel> {
     pub(super) requests: RequestMetrics<R>,
     pub(super) backend: backend::RouteBackendMetrics<B>,
     pub(super) body_data: RequestBodyFamilies<labels::Route>,
+    pub(super) rollout_guards: guard::RolloutGuards,
+    pub(super) retry_buffers: BufferBudget,
+    pub(super) cache: cache::RouteCacheMetrics,
+    pub(super) response_cache: cache::ResponseCache,
+    pub(super) coalesce: coalesce::RouteCoalesceMetrics,
+    pub(super) coalescer: coalesce::Coalescer,
+    pub(super) fault_rng: fault::FaultRng,
 }
 
 pub type HttpRouteMetrics = RouteMetrics<LabelHttpRouteRsp, LabelHttpRouteBackendRsp>;
@@ -39,6 +48,9 @@ pub struct LabelHttpRsp<L> {
     parent: L,
     status: Option<http::StatusCode>,
     error: Option<labels::Error>,
+    fault: Option<labels::Fault>,
+    hairpin: bool,
+    direct_response: bool,
 }
 
 /// Tracks gRPC streams to produce response labels.
@@ -47,6 +59,9 @@ pub struct LabelGrpcRsp<L> {
     parent: L,
     status: Option<tonic::Code>,
     error: Option<labels::Error>,
+    fault: Option<labels::Fault>,
+    hairpin: bool,
+    direct_response: bool,
 }
 
 pub type LabelHttpRouteRsp = LabelHttpRsp<labels::Route>;
@@ -111,6 +126,13 @@ impl<R: StreamLabel, B: StreamLabel> Default for RouteMetrics<R, B> {
             backend: Default::default(),
             retry: Default::default(),
             body_data: Default::default(),
+            rollout_guards: Default::default(),
+            retry_buffers: Default::default(),
+            cache: Default::default(),
+            response_cache: Default::default(),
+            coalesce: Default::default(),
+            coalescer: Default::default(),
+            fault_rng: Default::default(),
         }
     }
 }
@@ -122,6 +144,13 @@ impl<R: StreamLabel, B: StreamLabel> Clone for RouteMetrics<R, B> {
             backend: self.backend.clone(),
             retry: self.retry.clone(),
             body_data: self.body_data.clone(),
+            rollout_guards: self.rollout_guards.clone(),
+            retry_buffers: self.retry_buffers.clone(),
+            cache: self.cache.clone(),
+            response_cache: self.response_cache.clone(),
+            coalesce: self.coalesce.clone(),
+            coalescer: self.coalescer.clone(),
+            fault_rng: self.fault_rng.clone(),
         }
     }
 }
@@ -137,23 +166,61 @@ impl<R: StreamLabel, B: StreamLabel> RouteMetrics<R, B> {
 
         let retry = retry::RouteRetryMetrics::register(reg.sub_registry_with_prefix("retry"));
         let body_data = RequestBodyFamilies::register(reg);
+        let cache = cache::RouteCacheMetrics::register(reg.sub_registry_with_prefix("cache"));
+        let coalesce =
+            coalesce::RouteCoalesceMetrics::register(reg.sub_registry_with_prefix("coalesce"));
 
         Self {
             requests,
             backend,
             retry,
             body_data,
+            rollout_guards: Default::default(),
+            retry_buffers: Default::default(),
+            cache,
+            response_cache: Default::default(),
+            coalesce,
+            coalescer: Default::default(),
+            fault_rng: Default::default(),
         }
     }
 
+    /// Shares rollout guard state with other route metrics.
+    pub fn with_rollout_guards(mut self, guards: guard::RolloutGuards) -> Self {
+        self.rollout_guards = guards;
+        self
+    }
+
+    /// Shares a budget for buffering retryable request bodies with other route
+    /// metrics.
+    pub fn with_retry_buffer_budget(mut self, budget: BufferBudget) -> Self {
+        self.retry_buffers = budget;
+        self
+    }
+
+    /// Shares a response cache with other route metrics.
+    pub fn with_response_cache(mut self, cache: cache::ResponseCache) -> Self {
+        self.response_cache = cache;
+        self
+    }
+
+    /// Shares a generator that decides which requests are faulted with other
+    /// route metrics.
+    pub fn with_fault_rng(mut self, rng: fault::FaultRng) -> Self {
+        self.fault_rng = rng;
+        self
+    }
+
     #[cfg(test)]
     pub(crate) fn backend_request_count(
         &self,
         p: crate::ParentRef,
         r: crate::RouteRef,
         b: crate::BackendRef,
+        port: Option<u16>,
+        version: Option<http::Variant>,
     ) -> linkerd_http_prom::RequestCount {
-        self.backend.backend_request_count(p, r, b)
+        self.backend.backend_request_count(p, r, b, port, version)
     }
 }
 
@@ -180,6 +247,9 @@ impl<P> From<P> for LabelHttpRsp<P> {
             parent,
             status: None,
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         }
     }
 }
@@ -193,10 +263,14 @@ where
 
     fn init_response<B>(&mut self, rsp: &http::Response<B>) {
         self.status = Some(rsp.status());
+        self.fault = rsp.extensions().get::<labels::Fault>().copied();
+        self.hairpin = rsp.extensions().get::<Hairpin>().is_some();
+        self.direct_response = rsp.extensions().get::<direct_response::Direct>().is_some();
     }
 
     fn end_response(&mut self, res: Result<Option<&http::HeaderMap>, &linkerd_app_core::Error>) {
         if let Err(e) = res {
+            self.fault = labels::Fault::from_error(e).or(self.fault);
             match labels::Error::new_or_status(e) {
                 Ok(l) => self.error = Some(l),
                 Err(code) => match http::StatusCode::from_u16(code) {
@@ -214,6 +288,9 @@ where
             labels::HttpRsp {
                 status: self.status,
                 error: self.error,
+                fault: self.fault,
+                hairpin: self.hairpin,
+                direct_response: self.direct_response,
             },
         )
     }
@@ -231,6 +308,9 @@ impl<P> From<P> for LabelGrpcRsp<P> {
             parent,
             status: None,
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         }
     }
 }
@@ -247,20 +327,28 @@ where
             .headers()
             .get("grpc-status")
             .map(|v| tonic::Code::from_bytes(v.as_bytes()));
+        self.fault = rsp.extensions().get::<labels::Fault>().copied();
+        self.hairpin = rsp.extensions().get::<Hairpin>().is_some();
+        self.direct_response = rsp.extensions().get::<direct_response::Direct>().is_some();
     }
 
     fn end_response(&mut self, res: Result<Option<&http::HeaderMap>, &linkerd_app_core::Error>) {
         match res {
             Ok(Some(trailers)) => {
-                self.status = trailers
-                    .get("grpc-status")
-                    .map(|v| tonic::Code::from_bytes(v.as_bytes()));
+                // A trailers-only response carries its status in the response
+                // headers, so trailers without a status must not clear it.
+                if let Some(v) = trailers.get("grpc-status") {
+                    self.status = Some(tonic::Code::from_bytes(v.as_bytes()));
+                }
             }
             Ok(None) => {}
-            Err(e) => match labels::Error::new_or_status(e) {
-                Ok(l) => self.error = Some(l),
-                Err(code) => self.status = Some(tonic::Code::from_i32(i32::from(code))),
-            },
+            Err(e) => {
+                self.fault = labels::Fault::from_error(e).or(self.fault);
+                match labels::Error::new_or_status(e) {
+                    Ok(l) => self.error = Some(l),
+                    Err(code) => self.status = Some(tonic::Code::from_i32(i32::from(code))),
+                }
+            }
         }
     }
 
@@ -270,6 +358,9 @@ where
             labels::GrpcRsp {
                 status: self.status,
                 error: self.error,
+                fault: self.fault,
+                hairpin: self.hairpin,
+                direct_response: self.direct_response,
             },
         )
     }
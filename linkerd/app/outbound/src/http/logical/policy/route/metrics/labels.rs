@@ -1,20 +1,64 @@
 //! Prometheus label types.
 use linkerd_app_core::{
-    dns, errors, metrics::prom::EncodeLabelSetMut, proxy::http, Error as BoxError,
+    dns, errors,
+    metrics::prom::EncodeLabelSetMut,
+    proxy::{balance, http},
+    Error as BoxError,
 };
 use prometheus_client::encoding::*;
+use std::sync::Arc;
 
 use crate::{BackendRef, ParentRef, RouteRef};
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Route {
+    rule: Arc<RouteRule>,
+    hostname: Option<dns::Name>,
+    method: Option<Method>,
+}
+
+/// Labels identifying a route rule.
+///
+/// These are built once, when a route is configured, and shared by the labels
+/// of every request that matches the rule.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct RouteRule {
     parent: ParentRef,
     route: RouteRef,
-    hostname: Option<dns::Name>,
+    rule: usize,
+    /// The HTTP version with which the parent stack was built, if known.
+    version: Option<http::Variant>,
+}
+
+/// A request's method.
+///
+/// Methods other than the standard methods are labeled as `other`, so that
+/// clients cannot create arbitrarily many series.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Connect,
+    Options,
+    Trace,
+    Patch,
+    Other,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct RouteBackend(pub ParentRef, pub RouteRef, pub BackendRef);
+pub struct RouteBackend(
+    pub ParentRef,
+    pub RouteRef,
+    pub BackendRef,
+    /// The port of the backend's target, which may differ from the port of
+    /// the original destination.
+    pub Option<u16>,
+    /// The HTTP version with which the parent stack was built, if known.
+    pub Option<http::Variant>,
+);
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Rsp<P, L>(pub P, pub L);
@@ -31,21 +75,50 @@ pub type GrpcRouteBackendRsp = RouteBackendRsp<GrpcRsp>;
 pub struct HttpRsp {
     pub status: Option<http::StatusCode>,
     pub error: Option<Error>,
+    pub fault: Option<Fault>,
+    /// Whether the response was served by the local workload without leaving
+    /// the pod.
+    pub hairpin: bool,
+    /// Whether the response was served by the route's `DirectResponse` filter
+    /// without being forwarded.
+    pub direct_response: bool,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct GrpcRsp {
     pub status: Option<tonic::Code>,
     pub error: Option<Error>,
+    pub fault: Option<Fault>,
+    /// Whether the response was served by the local workload without leaving
+    /// the pod.
+    pub hairpin: bool,
+    /// Whether the response was served by the route's `DirectResponse` filter
+    /// without being forwarded.
+    pub direct_response: bool,
+}
+
+/// Identifies responses to requests into which faults were injected, so that
+/// they may be excluded from SLOs.
+///
+/// Delayed responses carry this as a response extension.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Fault {
+    Delay,
+    Abort,
 }
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 pub enum Error {
     FailFast,
+    FailFastUnresolved,
+    FailFastDiscoveryUnavailable,
+    FailFastNoEndpoints,
     LoadShed,
     RequestTimeout,
     ResponseHeadersTimeout,
+    ResponseFirstByteTimeout,
     ResponseStreamTimeout,
+    ResponseChunkIdleTimeout,
     IdleTimeout,
     Cancel,
     Refused,
@@ -59,15 +132,34 @@ pub enum Error {
 // === impl Route ===
 
 impl Route {
-    pub fn new(parent: ParentRef, route: RouteRef, uri: Option<&http::uri::Uri>) -> Self {
+    pub fn new(
+        parent: ParentRef,
+        route: RouteRef,
+        rule: usize,
+        uri: Option<&http::uri::Uri>,
+    ) -> Self {
+        Self::for_rule(&RouteRule::shared(parent, route, rule, None), uri)
+    }
+
+    /// Labels a request matching a rule, without allocating unless the
+    /// request's hostname is labeled.
+    pub fn for_rule(rule: &Arc<RouteRule>, uri: Option<&http::uri::Uri>) -> Self {
         let hostname = uri
             .and_then(http::uri::Uri::host)
             .and_then(|h| dns::Name::try_from_ascii(h.as_bytes()).ok());
 
         Self {
-            parent,
-            route,
+            rule: rule.clone(),
             hostname,
+            method: None,
+        }
+    }
+
+    /// Labels the request's method, if set.
+    pub fn with_method(self, method: Option<&http::Method>) -> Self {
+        Self {
+            method: method.map(Method::from),
+            ..self
         }
     }
 
@@ -75,12 +167,13 @@ impl Route {
     pub(super) fn new_with_name(
         parent: ParentRef,
         route: RouteRef,
+        rule: usize,
         hostname: Option<dns::Name>,
     ) -> Self {
         Self {
-            parent,
-            route,
+            rule: RouteRule::shared(parent, route, rule, None),
             hostname,
+            method: None,
         }
     }
 }
@@ -88,14 +181,27 @@ impl Route {
 impl EncodeLabelSetMut for Route {
     fn encode_label_set(&self, enc: &mut LabelSetEncoder<'_>) -> std::fmt::Result {
         let Self {
-            parent,
-            route,
+            rule,
             hostname,
+            method,
         } = self;
+        let RouteRule {
+            parent,
+            route,
+            rule,
+            version,
+        } = &**rule;
 
         parent.encode_label_set(enc)?;
         route.encode_label_set(enc)?;
+        ("route_rule", *rule as u64).encode(enc.encode_label())?;
         ("hostname", hostname.as_deref()).encode(enc.encode_label())?;
+        if let Some(method) = method {
+            ("method", method.as_str()).encode(enc.encode_label())?;
+        }
+        if let Some(version) = version {
+            ("http_version", version.as_str()).encode(enc.encode_label())?;
+        }
 
         Ok(())
     }
@@ -107,20 +213,94 @@ impl EncodeLabelSet for Route {
     }
 }
 
+// === impl RouteRule ===
+
+impl RouteRule {
+    pub fn shared(
+        parent: ParentRef,
+        route: RouteRef,
+        rule: usize,
+        version: Option<http::Variant>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            parent,
+            route,
+            rule,
+            version,
+        })
+    }
+}
+
+// === impl Method ===
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Delete => "DELETE",
+            Self::Connect => "CONNECT",
+            Self::Options => "OPTIONS",
+            Self::Trace => "TRACE",
+            Self::Patch => "PATCH",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl From<&http::Method> for Method {
+    fn from(method: &http::Method) -> Self {
+        match *method {
+            http::Method::GET => Self::Get,
+            http::Method::HEAD => Self::Head,
+            http::Method::POST => Self::Post,
+            http::Method::PUT => Self::Put,
+            http::Method::DELETE => Self::Delete,
+            http::Method::CONNECT => Self::Connect,
+            http::Method::OPTIONS => Self::Options,
+            http::Method::TRACE => Self::Trace,
+            http::Method::PATCH => Self::Patch,
+            _ => Self::Other,
+        }
+    }
+}
+
 // === impl RouteBackend ===
 
-impl From<(ParentRef, RouteRef, BackendRef)> for RouteBackend {
-    fn from((parent, route, backend): (ParentRef, RouteRef, BackendRef)) -> Self {
-        Self(parent, route, backend)
+impl
+    From<(
+        ParentRef,
+        RouteRef,
+        BackendRef,
+        Option<u16>,
+        Option<http::Variant>,
+    )> for RouteBackend
+{
+    fn from(
+        (parent, route, backend, port, version): (
+            ParentRef,
+            RouteRef,
+            BackendRef,
+            Option<u16>,
+            Option<http::Variant>,
+        ),
+    ) -> Self {
+        Self(parent, route, backend, port, version)
     }
 }
 
 impl EncodeLabelSetMut for RouteBackend {
     fn encode_label_set(&self, enc: &mut LabelSetEncoder<'_>) -> std::fmt::Result {
-        let Self(parent, route, backend) = self;
+        let Self(parent, route, backend, port, version) = self;
         parent.encode_label_set(enc)?;
         route.encode_label_set(enc)?;
         backend.encode_label_set(enc)?;
+        ("target_port", *port).encode(enc.encode_label())?;
+        if let Some(version) = version {
+            ("http_version", version.as_str()).encode(enc.encode_label())?;
+        }
         Ok(())
     }
 }
@@ -152,10 +332,23 @@ impl<P: EncodeLabelSetMut, L: EncodeLabelSetMut> EncodeLabelSet for Rsp<P, L> {
 
 impl EncodeLabelSetMut for HttpRsp {
     fn encode_label_set(&self, enc: &mut LabelSetEncoder<'_>) -> std::fmt::Result {
-        let Self { status, error } = self;
+        let Self {
+            status,
+            error,
+            fault,
+            hairpin,
+            direct_response,
+        } = self;
 
         ("http_status", status.map(|c| c.as_u16())).encode(enc.encode_label())?;
         ("error", *error).encode(enc.encode_label())?;
+        ("fault", *fault).encode(enc.encode_label())?;
+        ("hairpin", if *hairpin { "true" } else { "false" }).encode(enc.encode_label())?;
+        (
+            "direct_response",
+            if *direct_response { "true" } else { "false" },
+        )
+            .encode(enc.encode_label())?;
 
         Ok(())
     }
@@ -171,7 +364,13 @@ impl EncodeLabelSet for HttpRsp {
 
 impl EncodeLabelSetMut for GrpcRsp {
     fn encode_label_set(&self, enc: &mut LabelSetEncoder<'_>) -> std::fmt::Result {
-        let Self { status, error } = self;
+        let Self {
+            status,
+            error,
+            fault,
+            hairpin,
+            direct_response,
+        } = self;
 
         (
             "grpc_status",
@@ -198,6 +397,13 @@ impl EncodeLabelSetMut for GrpcRsp {
             .encode(enc.encode_label())?;
 
         ("error", *error).encode(enc.encode_label())?;
+        ("fault", *fault).encode(enc.encode_label())?;
+        ("hairpin", if *hairpin { "true" } else { "false" }).encode(enc.encode_label())?;
+        (
+            "direct_response",
+            if *direct_response { "true" } else { "false" },
+        )
+            .encode(enc.encode_label())?;
 
         Ok(())
     }
@@ -209,6 +415,33 @@ impl EncodeLabelSet for GrpcRsp {
     }
 }
 
+// === impl Fault ===
+
+impl Fault {
+    /// Returns the fault that caused a request to fail, if any.
+    pub fn from_error(error: &BoxError) -> Option<Self> {
+        use super::super::super::errors as policy;
+
+        if errors::is_caused_by::<policy::HttpRouteFaultAbort>(&**error)
+            || errors::is_caused_by::<policy::GrpcRouteFaultAbort>(&**error)
+        {
+            return Some(Self::Abort);
+        }
+
+        None
+    }
+}
+
+impl EncodeLabelValue for Fault {
+    fn encode(&self, enc: &mut LabelValueEncoder<'_>) -> std::fmt::Result {
+        use std::fmt::Write;
+        match self {
+            Self::Delay => enc.write_str("delay"),
+            Self::Abort => enc.write_str("abort"),
+        }
+    }
+}
+
 // === impl Error ===
 
 impl Error {
@@ -216,7 +449,16 @@ impl Error {
         use super::super::super::errors as policy;
         use crate::http::h2::{H2Error, Reason};
 
-        // No available backend can be found for a request.
+        // No available backend can be found for a request. Balancers qualify
+        // this with the state of their endpoint discovery.
+        if let Some(e) = errors::cause_ref::<balance::NoReadyEndpoints>(&**error) {
+            return Ok(match e.discovery() {
+                balance::DiscoveryState::Pending => Self::FailFastUnresolved,
+                balance::DiscoveryState::Unavailable => Self::FailFastDiscoveryUnavailable,
+                balance::DiscoveryState::Empty => Self::FailFastNoEndpoints,
+                balance::DiscoveryState::Resolved => Self::FailFast,
+            });
+        }
         if errors::is_caused_by::<errors::FailFastError>(&**error) {
             return Ok(Self::FailFast);
         }
@@ -229,23 +471,41 @@ impl Error {
         }
 
         // Policy-driven request failures.
+        if let Some(policy::HttpRouteFaultAbort { status }) = errors::cause_ref(&**error) {
+            return Err(status.as_u16());
+        }
+        if let Some(policy::GrpcRouteFaultAbort { code }) = errors::cause_ref(&**error) {
+            return Err(*code);
+        }
         if let Some(policy::HttpRouteInjectedFailure { status, .. }) = errors::cause_ref(&**error) {
             return Err(status.as_u16());
         }
         if let Some(policy::GrpcRouteInjectedFailure { code, .. }) = errors::cause_ref(&**error) {
             return Err(*code);
         }
+        if errors::is_caused_by::<policy::HttpRouteRequestTooLarge>(&**error) {
+            return Err(http::StatusCode::PAYLOAD_TOO_LARGE.as_u16());
+        }
+        if errors::is_caused_by::<policy::HttpRouteInvalidRequestEncoding>(&**error) {
+            return Err(http::StatusCode::BAD_REQUEST.as_u16());
+        }
 
         use http::stream_timeouts::{
-            ResponseHeadersTimeoutError, ResponseStreamTimeoutError, StreamDeadlineError,
-            StreamIdleError,
+            ResponseChunkIdleError, ResponseFirstByteTimeoutError, ResponseHeadersTimeoutError,
+            ResponseStreamTimeoutError, StreamDeadlineError, StreamIdleError,
         };
         if errors::is_caused_by::<ResponseHeadersTimeoutError>(&**error) {
             return Ok(Self::ResponseHeadersTimeout);
         }
+        if errors::is_caused_by::<ResponseFirstByteTimeoutError>(&**error) {
+            return Ok(Self::ResponseFirstByteTimeout);
+        }
         if errors::is_caused_by::<ResponseStreamTimeoutError>(&**error) {
             return Ok(Self::ResponseStreamTimeout);
         }
+        if errors::is_caused_by::<ResponseChunkIdleError>(&**error) {
+            return Ok(Self::ResponseChunkIdleTimeout);
+        }
         if errors::is_caused_by::<StreamDeadlineError>(&**error) {
             return Ok(Self::RequestTimeout);
         }
@@ -281,10 +541,15 @@ impl EncodeLabelValue for Error {
         use std::fmt::Write;
         match self {
             Self::FailFast => enc.write_str("FAIL_FAST"),
+            Self::FailFastUnresolved => enc.write_str("FAIL_FAST_UNRESOLVED"),
+            Self::FailFastDiscoveryUnavailable => enc.write_str("FAIL_FAST_DISCOVERY_UNAVAILABLE"),
+            Self::FailFastNoEndpoints => enc.write_str("FAIL_FAST_NO_ENDPOINTS"),
             Self::LoadShed => enc.write_str("LOAD_SHED"),
             Self::RequestTimeout => enc.write_str("REQUEST_TIMEOUT"),
             Self::ResponseHeadersTimeout => enc.write_str("RESPONSE_HEADERS_TIMEOUT"),
+            Self::ResponseFirstByteTimeout => enc.write_str("RESPONSE_FIRST_BYTE_TIMEOUT"),
             Self::ResponseStreamTimeout => enc.write_str("RESPONSE_STREAM_TIMEOUT"),
+            Self::ResponseChunkIdleTimeout => enc.write_str("RESPONSE_CHUNK_IDLE_TIMEOUT"),
             Self::IdleTimeout => enc.write_str("IDLE_TIMEOUT"),
             Self::Cancel => enc.write_str("CANCEL"),
             Self::Refused => enc.write_str("REFUSED"),
@@ -44,10 +44,13 @@ async fn http_request_statuses() {
 
     // Send one request and ensure it's counted.
     let ok = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::HttpRsp {
             status: Some(http::StatusCode::OK),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(&ok, &mut handle, &mut svc, Default::default(), |tx| {
@@ -63,10 +66,13 @@ async fn http_request_statuses() {
     // Send another request and ensure it's counted with a different response
     // status.
     let no_content = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::HttpRsp {
             status: Some(http::StatusCode::NO_CONTENT),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -87,10 +93,13 @@ async fn http_request_statuses() {
 
     // Emit a response with an error and ensure it's counted.
     let unknown = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::HttpRsp {
             status: None,
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(&unknown, &mut handle, &mut svc, Default::default(), |tx| {
@@ -101,10 +110,13 @@ async fn http_request_statuses() {
     // Emit a successful response with a body that fails and ensure that both
     // the status and error are recorded.
     let mixed = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref, route_ref, None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::HttpRsp {
             status: Some(http::StatusCode::OK),
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(&mixed, &mut handle, &mut svc, Default::default(), |tx| {
@@ -117,10 +129,58 @@ async fn http_request_statuses() {
     })
     .await;
 
+    // Emit a response served by the local workload and ensure it's counted as
+    // a hairpin.
+    let hairpin = requests.get_statuses(&labels::Rsp(
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
+        labels::HttpRsp {
+            status: Some(http::StatusCode::OK),
+            error: None,
+            fault: None,
+            hairpin: true,
+            direct_response: false,
+        },
+    ));
+    send_assert_incremented(&hairpin, &mut handle, &mut svc, Default::default(), |tx| {
+        tx.send_response(
+            http::Response::builder()
+                .status(200)
+                .extension(crate::http::concrete::Hairpin::default())
+                .body(BoxBody::default())
+                .unwrap(),
+        )
+    })
+    .await;
+
+    // Emit a response served by the route's direct response filter and ensure
+    // it's counted as such.
+    let direct = requests.get_statuses(&labels::Rsp(
+        labels::Route::new(parent_ref, route_ref, 0, None),
+        labels::HttpRsp {
+            status: Some(http::StatusCode::SERVICE_UNAVAILABLE),
+            error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: true,
+        },
+    ));
+    send_assert_incremented(&direct, &mut handle, &mut svc, Default::default(), |tx| {
+        tx.send_response(
+            http::Response::builder()
+                .status(503)
+                .extension(super::super::direct_response::Direct)
+                .body(BoxBody::default())
+                .unwrap(),
+        )
+    })
+    .await;
+
     assert_eq!(unknown.get(), 1);
-    assert_eq!(ok.get(), 1);
+    assert_eq!(ok.get(), 2);
     assert_eq!(no_content.get(), 1);
     assert_eq!(mixed.get(), 1);
+    assert_eq!(hairpin.get(), 1);
+    assert_eq!(direct.get(), 1);
 }
 
 #[tokio::test(flavor = "current_thread", start_paused = true)]
@@ -155,11 +215,15 @@ async fn http_request_hostnames() {
             labels::Route::new_with_name(
                 parent_ref.clone(),
                 route_ref.clone(),
+                0,
                 host.map(str::parse::<dns::Name>).map(Result::unwrap),
             ),
             labels::HttpRsp {
                 status,
                 error: None,
+                fault: None,
+                hairpin: false,
+                direct_response: false,
             },
         ))
     };
@@ -290,11 +354,15 @@ async fn http_request_hostnames_disabled() {
             labels::Route::new_with_name(
                 parent_ref.clone(),
                 route_ref.clone(),
+                0,
                 host.map(str::parse::<dns::Name>).map(Result::unwrap),
             ),
             labels::HttpRsp {
                 status,
                 error: None,
+                fault: None,
+                hairpin: false,
+                direct_response: false,
             },
         ))
     };
@@ -371,6 +439,78 @@ async fn http_request_hostnames_disabled() {
     assert_eq!(unlabeled_ok.get(), 3);
 }
 
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_request_methods() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let super::HttpRouteMetrics {
+        requests,
+        body_data,
+        ..
+    } = super::HttpRouteMetrics::default();
+    let parent_ref = crate::ParentRef(policy::Meta::new_default("parent"));
+    let route_ref = crate::RouteRef(policy::Meta::new_default("route"));
+    let (mut svc, mut handle) = mock_http_route_metrics_with_params(
+        &requests,
+        &body_data,
+        &parent_ref,
+        &route_ref,
+        policy::http::RouteParams {
+            export_method_labels: true,
+            ..Default::default()
+        },
+    );
+
+    let get_counter = |method: &http::Method| {
+        requests.get_statuses(&labels::Rsp(
+            labels::Route::new_with_name(parent_ref.clone(), route_ref.clone(), 0, None)
+                .with_method(Some(method)),
+            labels::HttpRsp {
+                status: Some(http::StatusCode::OK),
+                error: None,
+                fault: None,
+                hairpin: false,
+                direct_response: false,
+            },
+        ))
+    };
+
+    let get = get_counter(&http::Method::GET);
+    let post = get_counter(&http::Method::POST);
+    // Extension methods share a label to bound the metrics' cardinality.
+    let other = get_counter(&http::Method::from_bytes(b"OTHER").unwrap());
+
+    for (counter, method) in [
+        (&get, http::Method::GET),
+        (&post, http::Method::POST),
+        (&other, http::Method::from_bytes(b"PURGE").unwrap()),
+        (&other, http::Method::from_bytes(b"purge").unwrap()),
+    ] {
+        send_assert_incremented(
+            counter,
+            &mut handle,
+            &mut svc,
+            http::Request::builder()
+                .method(method)
+                .uri("http://example.com/")
+                .body(BoxBody::default())
+                .unwrap(),
+            |tx| {
+                tx.send_response(
+                    http::Response::builder()
+                        .status(200)
+                        .body(BoxBody::default())
+                        .unwrap(),
+                )
+            },
+        )
+        .await;
+    }
+    assert_eq!(get.get(), 1);
+    assert_eq!(post.get(), 1);
+    assert_eq!(other.get(), 2);
+}
+
 #[tokio::test(flavor = "current_thread", start_paused = true)]
 async fn http_route_request_body_frames() {
     use linkerd_http_prom::body_data::request::BodyDataMetrics;
@@ -397,6 +537,7 @@ async fn http_route_request_body_frames() {
     let labels = labels::Route::new(
         parent_ref,
         route_ref,
+        0,
         Some(&Uri::from_static("http://frame.count.test/")),
     );
     let BodyDataMetrics {
@@ -558,17 +699,23 @@ async fn http_response_body_drop_on_eos() {
 
     // Two counters for 200 responses that do/don't have an error.
     let ok = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::HttpRsp {
             status: Some(http::StatusCode::OK),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     let err = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::HttpRsp {
             status: Some(http::StatusCode::OK),
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     debug_assert_eq!(ok.get(), 0);
@@ -641,17 +788,23 @@ async fn http_response_body_drop_early() {
 
     // Two counters for 200 responses that do/don't have an error.
     let ok = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::HttpRsp {
             status: Some(http::StatusCode::OK),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     let err = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::HttpRsp {
             status: Some(http::StatusCode::OK),
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     debug_assert_eq!(ok.get(), 0);
@@ -707,11 +860,15 @@ async fn grpc_request_statuses_ok() {
         labels::Route::new(
             parent_ref.clone(),
             route_ref.clone(),
+            0,
             Some(&Uri::from_static(MOCK_GRPC_REQ_URI)),
         ),
         labels::GrpcRsp {
             status: Some(tonic::Code::Ok),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -761,11 +918,15 @@ async fn grpc_request_statuses_not_found() {
         labels::Route::new(
             parent_ref.clone(),
             route_ref.clone(),
+            0,
             Some(&Uri::from_static(MOCK_GRPC_REQ_URI)),
         ),
         labels::GrpcRsp {
             status: Some(tonic::Code::NotFound),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -812,11 +973,15 @@ async fn grpc_request_statuses_error_response() {
         labels::Route::new(
             parent_ref.clone(),
             route_ref.clone(),
+            0,
             Some(&Uri::from_static(MOCK_GRPC_REQ_URI)),
         ),
         labels::GrpcRsp {
             status: None,
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -857,11 +1022,15 @@ async fn grpc_request_statuses_error_body() {
         labels::Route::new(
             parent_ref.clone(),
             route_ref.clone(),
+            0,
             Some(&Uri::from_static(MOCK_GRPC_REQ_URI)),
         ),
         labels::GrpcRsp {
             status: None,
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     send_assert_incremented(
@@ -884,6 +1053,178 @@ async fn grpc_request_statuses_error_body() {
     .await;
 }
 
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn grpc_request_statuses_trailers_only_ok() {
+    const EXPORT_HOSTNAME_LABELS: bool = true;
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let super::GrpcRouteMetrics {
+        requests,
+        body_data,
+        ..
+    } = super::GrpcRouteMetrics::default();
+    let parent_ref = crate::ParentRef(policy::Meta::new_default("parent"));
+    let route_ref = crate::RouteRef(policy::Meta::new_default("route"));
+    let (mut svc, mut handle) = mock_grpc_route_metrics(
+        &requests,
+        &body_data,
+        &parent_ref,
+        &route_ref,
+        EXPORT_HOSTNAME_LABELS,
+    );
+
+    // A trailers-only response carries its status in the response headers.
+    let ok = requests.get_statuses(&labels::Rsp(
+        labels::Route::new(
+            parent_ref.clone(),
+            route_ref.clone(),
+            0,
+            Some(&Uri::from_static(MOCK_GRPC_REQ_URI)),
+        ),
+        labels::GrpcRsp {
+            status: Some(tonic::Code::Ok),
+            error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
+        },
+    ));
+    send_assert_incremented(
+        &ok,
+        &mut handle,
+        &mut svc,
+        http::Request::builder()
+            .method("POST")
+            .uri("http://host/svc/method")
+            .body(Default::default())
+            .unwrap(),
+        |tx| {
+            tx.send_response(
+                http::Response::builder()
+                    .header(&GRPC_STATUS, &GRPC_STATUS_OK)
+                    .body(BoxBody::empty())
+                    .unwrap(),
+            )
+        },
+    )
+    .await;
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn grpc_request_statuses_trailers_only_unavailable() {
+    const EXPORT_HOSTNAME_LABELS: bool = true;
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let super::GrpcRouteMetrics {
+        requests,
+        body_data,
+        ..
+    } = super::GrpcRouteMetrics::default();
+    let parent_ref = crate::ParentRef(policy::Meta::new_default("parent"));
+    let route_ref = crate::RouteRef(policy::Meta::new_default("route"));
+    let (mut svc, mut handle) = mock_grpc_route_metrics(
+        &requests,
+        &body_data,
+        &parent_ref,
+        &route_ref,
+        EXPORT_HOSTNAME_LABELS,
+    );
+
+    // Empty trailers must not clear the status read from the headers.
+    let unavailable = requests.get_statuses(&labels::Rsp(
+        labels::Route::new(
+            parent_ref.clone(),
+            route_ref.clone(),
+            0,
+            Some(&Uri::from_static(MOCK_GRPC_REQ_URI)),
+        ),
+        labels::GrpcRsp {
+            status: Some(tonic::Code::Unavailable),
+            error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
+        },
+    ));
+    send_assert_incremented(
+        &unavailable,
+        &mut handle,
+        &mut svc,
+        http::Request::builder()
+            .method("POST")
+            .uri("http://host/svc/method")
+            .body(Default::default())
+            .unwrap(),
+        |tx| {
+            tx.send_response(
+                http::Response::builder()
+                    .header(&GRPC_STATUS, "14")
+                    .body(BoxBody::new(MockBody::trailers(Default::default())))
+                    .unwrap(),
+            )
+        },
+    )
+    .await;
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn grpc_request_statuses_header_error_with_body() {
+    const EXPORT_HOSTNAME_LABELS: bool = true;
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let super::GrpcRouteMetrics {
+        requests,
+        body_data,
+        ..
+    } = super::GrpcRouteMetrics::default();
+    let parent_ref = crate::ParentRef(policy::Meta::new_default("parent"));
+    let route_ref = crate::RouteRef(policy::Meta::new_default("route"));
+    let (mut svc, mut handle) = mock_grpc_route_metrics(
+        &requests,
+        &body_data,
+        &parent_ref,
+        &route_ref,
+        EXPORT_HOSTNAME_LABELS,
+    );
+
+    // An HTTP/1 upstream may only send its status in the response headers,
+    // even when the response has a body.
+    let internal = requests.get_statuses(&labels::Rsp(
+        labels::Route::new(
+            parent_ref.clone(),
+            route_ref.clone(),
+            0,
+            Some(&Uri::from_static(MOCK_GRPC_REQ_URI)),
+        ),
+        labels::GrpcRsp {
+            status: Some(tonic::Code::Internal),
+            error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
+        },
+    ));
+    send_assert_incremented(
+        &internal,
+        &mut handle,
+        &mut svc,
+        http::Request::builder()
+            .method("POST")
+            .uri("http://host/svc/method")
+            .body(Default::default())
+            .unwrap(),
+        |tx| {
+            tx.send_response(
+                http::Response::builder()
+                    .header(&GRPC_STATUS, "13")
+                    .body(BoxBody::from_static("\0\0\0\0\0"))
+                    .unwrap(),
+            )
+        },
+    )
+    .await;
+}
+
 #[tokio::test(flavor = "current_thread", start_paused = true)]
 async fn grpc_response_body_drop_on_eos() {
     use linkerd_app_core::svc::{Service, ServiceExt};
@@ -926,17 +1267,23 @@ async fn grpc_response_body_drop_on_eos() {
 
     // Two counters for 200 responses that do/don't have an error.
     let ok = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::GrpcRsp {
             status: Some(tonic::Code::Ok),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     let err = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::GrpcRsp {
             status: Some(tonic::Code::Ok),
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     debug_assert_eq!(ok.get(), 0);
@@ -1031,17 +1378,23 @@ async fn grpc_response_body_drop_early() {
 
     // Two counters for 200 responses that do/don't have an error.
     let ok = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::GrpcRsp {
             status: Some(tonic::Code::Ok),
             error: None,
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     let err = requests.get_statuses(&labels::Rsp(
-        labels::Route::new(parent_ref.clone(), route_ref.clone(), None),
+        labels::Route::new(parent_ref.clone(), route_ref.clone(), 0, None),
         labels::GrpcRsp {
             status: None,
             error: Some(labels::Error::Unknown),
+            fault: None,
+            hairpin: false,
+            direct_response: false,
         },
     ));
     debug_assert_eq!(ok.get(), 0);
@@ -1098,6 +1451,25 @@ pub fn mock_http_route_metrics(
     parent_ref: &crate::ParentRef,
     route_ref: &crate::RouteRef,
     export_hostname_labels: bool,
+) -> (svc::BoxHttp, Handle) {
+    mock_http_route_metrics_with_params(
+        metrics,
+        body_data,
+        parent_ref,
+        route_ref,
+        policy::http::RouteParams {
+            export_hostname_labels,
+            ..Default::default()
+        },
+    )
+}
+
+pub fn mock_http_route_metrics_with_params(
+    metrics: &RequestMetrics<LabelHttpRouteRsp>,
+    body_data: &RequestBodyFamilies<labels::Route>,
+    parent_ref: &crate::ParentRef,
+    route_ref: &crate::RouteRef,
+    params: policy::http::RouteParams,
 ) -> (svc::BoxHttp, Handle) {
     let req = http::Request::builder().body(()).unwrap();
     let (r#match, _) = policy::route::find(
@@ -1109,10 +1481,7 @@ pub fn mock_http_route_metrics(
                     meta: route_ref.0.clone(),
                     filters: [].into(),
                     distribution: policy::RouteDistribution::Empty,
-                    params: policy::http::RouteParams {
-                        export_hostname_labels,
-                        ..Default::default()
-                    },
+                    params: params.clone(),
                 },
             }],
         }],
@@ -1125,18 +1494,16 @@ pub fn mock_http_route_metrics(
         .layer(move |_t: Http<()>| tx.clone())
         .new_service(Http {
             r#match,
-            params: Route {
+            params: std::sync::Arc::new(Route {
                 parent: (),
                 addr: std::net::SocketAddr::new([0, 0, 0, 0].into(), 8080).into(),
                 parent_ref: parent_ref.clone(),
                 route_ref: route_ref.clone(),
+                labels: labels::RouteRule::shared(parent_ref.clone(), route_ref.clone(), 0, None),
                 filters: [].into(),
                 distribution: Default::default(),
-                params: policy::http::RouteParams {
-                    export_hostname_labels,
-                    ..Default::default()
-                },
-            },
+                params,
+            }),
         });
 
     (svc::BoxHttp::new(svc), handle)
@@ -1179,18 +1546,19 @@ pub fn mock_grpc_route_metrics(
         .layer(move |_t: Grpc<()>| tx.clone())
         .new_service(Grpc {
             r#match,
-            params: Route {
+            params: std::sync::Arc::new(Route {
                 parent: (),
                 addr: std::net::SocketAddr::new([0, 0, 0, 0].into(), 8080).into(),
                 parent_ref: parent_ref.clone(),
                 route_ref: route_ref.clone(),
+                labels: labels::RouteRule::shared(parent_ref.clone(), route_ref.clone(), 0, None),
                 filters: [].into(),
                 distribution: Default::default(),
                 params: policy::grpc::RouteParams {
                     export_hostname_labels,
                     ..Default::default()
                 },
-            },
+            }),
         });
 
     (svc::BoxHttp::new(svc), handle)
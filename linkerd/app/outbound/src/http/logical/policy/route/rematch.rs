@@ -0,0 +1,173 @@
+//! Re-matches requests when all of their route's backends are unavailable.
+//!
+//! Requests are matched to a route once, so that retries are dispatched
+//! through the same rule's backends. When every backend of that rule becomes
+//! unavailable and the routes have been updated since the request was
+//! matched, the attempt is instead dispatched through the current router so
+//! that it may be re-matched. Otherwise, the attempt fails with a
+//! [`svc::LoadShedError`].
+
+use crate::http::logical::Routes;
+use futures::future::{self, Either};
+use linkerd_app_core::{proxy::http, svc, Error, Result};
+use std::task::{Context, Poll};
+use tokio::sync::watch;
+
+/// Sets a [`Rematch`] handle on each request, referencing the router that
+/// matched it.
+#[derive(Clone, Debug)]
+pub(crate) struct NewSetRematch<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SetRematch<S> {
+    routes: watch::Receiver<Routes>,
+    inner: S,
+}
+
+/// A request extension that permits a request to be re-matched by the router
+/// that originally matched it.
+#[derive(Clone)]
+pub(crate) struct Rematch {
+    /// Observes whether the routes have changed since the request was matched.
+    routes: watch::Receiver<Routes>,
+    router: svc::BoxCloneHttp,
+}
+
+/// Fails requests when the inner service is not ready, unless they may be
+/// re-matched.
+#[derive(Clone, Debug)]
+pub(crate) struct LoadShedOrRematch<S> {
+    inner: S,
+    open: bool,
+}
+
+type Shed<F> = Either<
+    Either<F, svc::Oneshot<svc::BoxCloneHttp, http::Request<http::BoxBody>>>,
+    future::Ready<Result<http::Response<http::BoxBody>>>,
+>;
+
+// === impl NewSetRematch ===
+
+impl<N> NewSetRematch<N> {
+    pub(crate) fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N, S> svc::NewService<T> for NewSetRematch<N>
+where
+    T: svc::Param<watch::Receiver<Routes>>,
+    N: svc::NewService<T, Service = S>,
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+    S: Clone + Send + Sync + 'static,
+    S::Future: Send,
+{
+    type Service = SetRematch<S>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        SetRematch {
+            routes: target.param(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl SetRematch ===
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for SetRematch<S>
+where
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+    S: Clone + Send + Sync + 'static,
+    S::Future: Send,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<http::BoxBody>) -> Self::Future {
+        // Mark the current routes as seen so that only later updates permit
+        // the request to be re-matched.
+        let mut routes = self.routes.clone();
+        routes.borrow_and_update();
+        req.extensions_mut().insert(Rematch {
+            routes,
+            router: svc::BoxCloneHttp::new(self.inner.clone()),
+        });
+        self.inner.call(req)
+    }
+}
+
+// === impl LoadShedOrRematch ===
+
+impl<S> LoadShedOrRematch<S> {
+    pub(crate) fn layer() -> impl svc::layer::Layer<S, Service = Self> + Copy {
+        svc::layer::mk(|inner| Self { inner, open: true })
+    }
+}
+
+impl<S> svc::Service<http::Request<http::BoxBody>> for LoadShedOrRematch<S>
+where
+    S: svc::Service<
+        http::Request<http::BoxBody>,
+        Response = http::Response<http::BoxBody>,
+        Error = Error,
+    >,
+{
+    type Response = http::Response<http::BoxBody>;
+    type Error = Error;
+    type Future = Shed<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        match self.inner.poll_ready(cx) {
+            Poll::Ready(ready) => {
+                if !self.open {
+                    tracing::debug!("Route backends have become available");
+                    self.open = true;
+                }
+                Poll::Ready(ready)
+            }
+            // As with `svc::LoadShed`, the inner distribution is responsible
+            // for driving its backends to ready.
+            Poll::Pending => {
+                if self.open {
+                    tracing::debug!("Route backends have become unavailable");
+                    self.open = false;
+                }
+                Poll::Ready(Ok(()))
+            }
+        }
+    }
+
+    fn call(&mut self, mut req: http::Request<http::BoxBody>) -> Self::Future {
+        if self.open {
+            return Either::Left(Either::Left(self.inner.call(req)));
+        }
+
+        // The handle is removed so that a request is re-matched at most once.
+        match req.extensions_mut().remove::<Rematch>() {
+            Some(Rematch { routes, router }) if routes.has_changed().unwrap_or(false) => {
+                tracing::debug!("Route backends unavailable; re-matching request");
+                Either::Left(Either::Right(svc::Oneshot::new(router, req)))
+            }
+            _ => {
+                tracing::debug!("Route backends unavailable; shedding load");
+                Either::Right(future::err(svc::LoadShedError::default().into()))
+            }
+        }
+    }
+}
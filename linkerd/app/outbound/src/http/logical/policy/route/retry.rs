@@ -1,8 +1,9 @@
-use super::{extensions, metrics::labels::Route as RouteLabels};
+use super::{errors, extensions, metrics::labels::Route as RouteLabels};
 use futures::future::{Either, Ready};
 use linkerd_app_core::{
     cause_ref, classify,
     exp_backoff::ExponentialBackoff,
+    http_tracing::SpanRecorder,
     is_caused_by,
     proxy::http::{self, stream_timeouts::ResponseTimeoutError},
     svc::{self, http::h2},
@@ -46,7 +47,7 @@ impl retry::Policy for RetryPolicy {
         let rsp = match res {
             Ok(rsp) => rsp,
             Err(error) => {
-                let retryable = Self::retryable_error(error);
+                let retryable = self.retryable_fault(error) || Self::retryable_error(error);
                 tracing::debug!(retryable, %error);
                 return retryable;
             }
@@ -93,12 +94,28 @@ impl retry::Policy for RetryPolicy {
             dst.insert(timeouts);
         }
 
+        // A client's deadline bounds each attempt.
+        if let Some(deadline) = src.get::<http::RequestDeadline>().cloned() {
+            dst.insert(deadline);
+        }
+
+        // Requests that override their route configuration with a debug
+        // header are labeled by tap.
+        if let Some(debug) = src.get::<super::RouteDebug>().cloned() {
+            dst.insert(debug);
+        }
+
         // The HTTP server sets a ClientHandle with the client's address and a means
         // to close the server-side connection.
         if let Some(client_handle) = src.get::<http::ClientHandle>().cloned() {
             dst.insert(client_handle);
         }
 
+        // Sampled requests are annotated by the endpoint stack.
+        if let Some(recorder) = src.get::<SpanRecorder>().cloned() {
+            dst.insert(recorder);
+        }
+
         // The legacy response classifier is set for the endpoint stack to use.
         // This informs endpoint-level behavior (failure accrual, etc.).
         // TODO(ver): This should ultimately be eliminated in favor of
@@ -107,6 +124,12 @@ impl retry::Policy for RetryPolicy {
         if let Some(classify) = src.get::<classify::Response>().cloned() {
             dst.insert(classify);
         }
+
+        // Retries may be re-matched if the route's backends become
+        // unavailable.
+        if let Some(rematch) = src.get::<super::rematch::Rematch>().cloned() {
+            dst.insert(rematch);
+        }
     }
 }
 
@@ -124,6 +147,25 @@ impl RetryPolicy {
         Some(status.to_str().ok()?.parse::<i32>().ok()?.into())
     }
 
+    /// Aborts are only injected within retries when the route permits aborted
+    /// requests to be retried, in which case they are retryable as if the
+    /// configured status had been returned.
+    fn retryable_fault(&self, error: &Error) -> bool {
+        if let Some(errors::HttpRouteFaultAbort { status }) = cause_ref(&**error) {
+            return self
+                .retryable_http_statuses
+                .as_ref()
+                .is_some_and(|statuses| statuses.contains(*status));
+        }
+        if let Some(errors::GrpcRouteFaultAbort { code }) = cause_ref(&**error) {
+            return self
+                .retryable_grpc_statuses
+                .as_ref()
+                .is_some_and(|codes| codes.contains(i32::from(*code).into()));
+        }
+        false
+    }
+
     fn retryable_error(error: &Error) -> bool {
         // While LoadShed errors are not retryable, FailFast errors are, since
         // retrying may put us in another backend that is available.
@@ -0,0 +1,92 @@
+use linkerd_app_core::{
+    proxy::http::{self, workload_identity},
+    svc,
+};
+use std::{
+    task::{Context, Poll},
+    time::SystemTime,
+};
+
+/// Configures whether a route asserts the local workload's identity, i.e.
+/// whether it has an `AssertWorkloadIdentity` filter.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Params(pub bool);
+
+/// Sets a signed assertion of the local workload's identity on requests to
+/// routes with an `AssertWorkloadIdentity` filter.
+#[derive(Clone, Debug)]
+pub(crate) struct NewAssertWorkloadIdentity<N> {
+    signer: Option<workload_identity::Signer>,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AssertWorkloadIdentity<S> {
+    assert: bool,
+    signer: Option<workload_identity::Signer>,
+    inner: S,
+}
+
+// === impl NewAssertWorkloadIdentity ===
+
+impl<N> NewAssertWorkloadIdentity<N> {
+    pub fn layer(
+        signer: Option<workload_identity::Signer>,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            signer: signer.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewAssertWorkloadIdentity<N>
+where
+    T: svc::Param<Params>,
+    N: svc::NewService<T>,
+{
+    type Service = AssertWorkloadIdentity<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Params(assert) = target.param();
+        if assert && self.signer.is_none() {
+            tracing::debug!("Route asserts workload identity, but no signing key is configured");
+        }
+        AssertWorkloadIdentity {
+            assert,
+            signer: self.signer.clone().filter(|_| assert),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl AssertWorkloadIdentity ===
+
+impl<B, S> svc::Service<http::Request<B>> for AssertWorkloadIdentity<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if self.assert {
+            // Never forward assertions set by the application.
+            let headers = req.headers_mut();
+            headers.remove(&workload_identity::HEADER);
+            if let Some(signer) = self.signer.as_ref() {
+                headers.insert(
+                    workload_identity::HEADER.clone(),
+                    signer.sign(SystemTime::now()),
+                );
+            }
+        }
+        self.inner.call(req)
+    }
+}
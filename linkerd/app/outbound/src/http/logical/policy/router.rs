@@ -9,9 +9,14 @@ use linkerd_app_core::{
 use linkerd_distribute as distribute;
 use linkerd_http_route as http_route;
 use linkerd_proxy_client_policy as policy;
+use once_cell::sync::Lazy;
 use std::{fmt::Debug, hash::Hash, sync::Arc};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// Identifies the backends of routes that forward to an endpoint, so that
+/// endpoint-forwarded traffic is distinguished in backend metrics.
+static ENDPOINT_META: Lazy<Arc<policy::Meta>> = Lazy::new(|| policy::Meta::new_default("endpoint"));
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Params<M, F, E> {
     pub addr: Addr,
     pub meta: ParentRef,
@@ -29,7 +34,7 @@ pub type GrpcParams =
 pub(crate) struct Router<T: Clone + Debug + Eq + Hash, M, F, E> {
     pub(super) parent: T,
     pub(super) addr: Addr,
-    pub(super) routes: Arc<[http_route::Route<M, route::Route<T, F, E>>]>,
+    pub(super) routes: Arc<[http_route::Route<M, Arc<route::Route<T, F, E>>>]>,
     pub(super) backends: distribute::Backends<Concrete<T>>,
 }
 
@@ -40,6 +45,145 @@ pub(super) type Grpc<T> =
 
 type NewBackendCache<T, N, S> = distribute::NewBackendCache<Concrete<T>, (), N, S>;
 
+/// Route parameters that may be retained when a parent's routes are applied
+/// to traffic that targets one of its endpoints.
+pub(crate) trait EndpointRouteParams: Sized {
+    /// Returns true if the route configures timeouts or retries.
+    fn configures_timeouts_or_retries(&self) -> bool;
+
+    /// Returns the parameters that apply when requests are forwarded to a
+    /// single endpoint.
+    fn for_endpoint(&self) -> Self;
+}
+
+// === impl Params ===
+
+impl<M, F, E> Params<M, F, E>
+where
+    M: Clone,
+    F: Clone,
+    E: EndpointRouteParams,
+{
+    /// Trims these routes so that each forwards requests to the endpoint at
+    /// `addr`, retaining the routes' matches, filters, timeouts, and retries.
+    ///
+    /// Backends that declare a port other than the parent's are retained, so
+    /// that their traffic is not forwarded to the endpoint's port.
+    ///
+    /// Returns `None` if no route configures timeouts or retries.
+    pub(crate) fn forward_to_endpoint(
+        &self,
+        addr: std::net::SocketAddr,
+        metadata: Arc<policy::EndpointMetadata>,
+    ) -> Option<Self> {
+        let configured = self.routes.iter().any(|route| {
+            route
+                .rules
+                .iter()
+                .any(|rule| rule.policy.params.configures_timeouts_or_retries())
+        });
+        if !configured {
+            return None;
+        }
+
+        let port = self.addr.port();
+        let retained = |backend: &policy::Backend| backend.port().is_some_and(|p| p != port);
+        let endpoint = policy::RouteBackend {
+            filters: Arc::new([]),
+            backend: policy::Backend {
+                meta: ENDPOINT_META.clone(),
+                queue: self.backends.first()?.queue,
+                dispatcher: policy::BackendDispatcher::Forward(addr, metadata),
+            },
+        };
+        let to_endpoint = |rb: &policy::RouteBackend<F>| {
+            if retained(&rb.backend) {
+                rb.clone()
+            } else {
+                endpoint.clone()
+            }
+        };
+        let distribution = |distribution: &policy::RouteDistribution<F>| match distribution {
+            policy::RouteDistribution::FirstAvailable(backends)
+                if backends.iter().any(|rb| retained(&rb.backend)) =>
+            {
+                policy::RouteDistribution::FirstAvailable(
+                    backends.iter().map(to_endpoint).collect(),
+                )
+            }
+            policy::RouteDistribution::RandomAvailable(backends)
+                if backends.iter().any(|(rb, _)| retained(&rb.backend)) =>
+            {
+                policy::RouteDistribution::RandomAvailable(
+                    backends
+                        .iter()
+                        .map(|(rb, weight)| (to_endpoint(rb), *weight))
+                        .collect(),
+                )
+            }
+            _ => policy::RouteDistribution::FirstAvailable(Arc::new([endpoint.clone()])),
+        };
+        let routes = self
+            .routes
+            .iter()
+            .map(|route| http_route::Route {
+                hosts: route.hosts.clone(),
+                rules: route
+                    .rules
+                    .iter()
+                    .map(|rule| http_route::Rule {
+                        matches: rule.matches.clone(),
+                        policy: policy::RoutePolicy {
+                            meta: rule.policy.meta.clone(),
+                            filters: rule.policy.filters.clone(),
+                            distribution: distribution(&rule.policy.distribution),
+                            params: rule.policy.params.for_endpoint(),
+                        },
+                    })
+                    .collect(),
+            })
+            .collect();
+        let backends = std::iter::once(endpoint.backend.clone())
+            .chain(self.backends.iter().filter(|b| retained(b)).cloned())
+            .collect();
+
+        Some(Self {
+            addr: self.addr.clone(),
+            meta: self.meta.clone(),
+            routes,
+            backends,
+            failure_accrual: self.failure_accrual,
+        })
+    }
+}
+
+impl EndpointRouteParams for policy::http::RouteParams {
+    fn configures_timeouts_or_retries(&self) -> bool {
+        self.retry.is_some() || self.timeouts != Default::default()
+    }
+
+    fn for_endpoint(&self) -> Self {
+        // A single backend cannot be guarded.
+        Self {
+            rollout_guard: None,
+            ..self.clone()
+        }
+    }
+}
+
+impl EndpointRouteParams for policy::grpc::RouteParams {
+    fn configures_timeouts_or_retries(&self) -> bool {
+        self.retry.is_some() || self.timeouts != Default::default()
+    }
+
+    fn for_endpoint(&self) -> Self {
+        Self {
+            rollout_guard: None,
+            ..self.clone()
+        }
+    }
+}
+
 // === impl Router ===
 
 impl<T, M, F, P> Router<T, M, F, P>
@@ -65,6 +209,12 @@ where
     route::MatchedRoute<T, M::Summary, F, P>: route::filters::Apply
         + svc::Param<classify::Request>
         + svc::Param<route::extensions::Params>
+        + svc::Param<route::fault::Params>
+        + svc::Param<route::decompress::Params>
+        + svc::Param<route::workload_identity::Params>
+        + svc::Param<route::guard::Params>
+        + svc::Param<Option<linkerd_http_cache::Params>>
+        + svc::Param<Option<linkerd_http_coalesce::Params>>
         + route::metrics::MkStreamLabel
         + svc::ExtractParam<route::metrics::labels::Route, http::Request<http::BoxBody>>,
     route::MatchedBackend<T, M::Summary, F>: route::filters::Apply + route::metrics::MkStreamLabel,
@@ -76,6 +226,7 @@ where
             route::MatchedRoute<T, M::Summary, F, P>,
             route::MatchedBackend<T, M::Summary, F>,
         >,
+        workload_identity: Option<http::workload_identity::Signer>,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         // Inner stack.
@@ -97,7 +248,10 @@ where
                 .push(NewBackendCache::layer())
                 // Lazily cache a service for each `RouteParams` returned from the
                 // `SelectRoute` impl.
-                .push_on_service(route::MatchedRoute::layer(metrics.clone()))
+                .push_on_service(route::MatchedRoute::layer(
+                    metrics.clone(),
+                    workload_identity.clone(),
+                ))
                 .push(svc::NewOneshotRoute::<Self, (), _>::layer_cached())
                 .arc_new_clone_http()
                 .into_inner()
@@ -107,6 +261,7 @@ where
 
 impl<T, M, F, P> From<(Params<M, F, P>, T)> for Router<T, M, F, P>
 where
+    T: svc::Param<http::Variant>,
     T: Eq + Hash + Clone + Debug,
     M: Clone,
     F: Clone,
@@ -124,13 +279,16 @@ where
         let mk_concrete = {
             let parent = parent.clone();
             let parent_ref = parent_ref.clone();
+            let logical_authority = addr.name_addr().map(NameAddr::as_http_authority);
             move |backend_ref: BackendRef, target: concrete::Dispatch| {
                 // XXX With policies we don't have a top-level authority name at
                 // the moment. So, instead, we use the concrete addr used for
-                // discovery for now.
+                // discovery for now. Forwarded traffic has no concrete name,
+                // so it is labeled with the logical name, if there is one.
                 let authority = match target {
                     concrete::Dispatch::Balance(ref addr, ..) => Some(addr.as_http_authority()),
-                    _ => None,
+                    concrete::Dispatch::Forward(..) => logical_authority.clone(),
+                    concrete::Dispatch::Fail { .. } => None,
                 };
                 Concrete {
                     target,
@@ -198,7 +356,8 @@ where
             let addr = addr.clone();
             let parent = parent.clone();
             let parent_ref = parent_ref.clone();
-            move |policy::RoutePolicy::<F, P> {
+            move |rule: usize,
+                  policy::RoutePolicy::<F, P> {
                       meta,
                       filters,
                       distribution,
@@ -206,15 +365,22 @@ where
                   }| {
                 let route_ref = RouteRef(meta);
                 let distribution = mk_distribution(&route_ref, &distribution);
-                route::Route {
+                let labels = route::metrics::labels::RouteRule::shared(
+                    parent_ref.clone(),
+                    route_ref.clone(),
+                    rule,
+                    Some(svc::Param::<http::Variant>::param(&parent)),
+                );
+                Arc::new(route::Route {
                     addr: addr.clone(),
                     parent: parent.clone(),
                     parent_ref: parent_ref.clone(),
                     route_ref,
+                    labels,
                     filters,
                     distribution,
                     params,
-                }
+                })
             }
         };
 
@@ -226,10 +392,13 @@ where
                     .rules
                     .iter()
                     .cloned()
-                    .map(|http_route::Rule { matches, policy }| http_route::Rule {
-                        matches,
-                        policy: mk_policy(policy),
-                    })
+                    .enumerate()
+                    .map(
+                        |(idx, http_route::Rule { matches, policy })| http_route::Rule {
+                            matches,
+                            policy: mk_policy(idx, policy),
+                        },
+                    )
                     .collect(),
             })
             .collect();
@@ -255,6 +424,7 @@ where
     fn select(&self, req: &http::Request<B>) -> Result<Self::Key, Self::Error> {
         tracing::trace!(uri = ?req.uri(), headers = ?req.headers(), "Selecting HTTP route");
         let (r#match, params) = policy::http::find(&self.routes, req).ok_or(NoRoute)?;
+        let params = debug_rule(&self.routes, params, req);
         tracing::debug!(meta = ?params.route_ref, "Selected route");
         tracing::trace!(?r#match);
         Ok(route::Matched {
@@ -274,6 +444,7 @@ where
     fn select(&self, req: &http::Request<B>) -> Result<Self::Key, Self::Error> {
         tracing::trace!(uri = ?req.uri(), headers = ?req.headers(), "Selecting gRPC route");
         let (r#match, params) = policy::grpc::find(&self.routes, req).ok_or(NoRoute)?;
+        let params = debug_rule(&self.routes, params, req);
         tracing::debug!(meta = ?params.route_ref, "Selected route");
         tracing::trace!(?r#match);
         Ok(route::Matched {
@@ -283,6 +454,38 @@ where
     }
 }
 
+/// Returns the rule selected by a request's `l5d-route-debug` header, if any,
+/// from the route that matched the request. Otherwise, the matched rule is
+/// returned.
+fn debug_rule<'r, M, P, B>(
+    routes: &'r [http_route::Route<M, Arc<P>>],
+    matched: &'r Arc<P>,
+    req: &http::Request<B>,
+) -> &'r Arc<P> {
+    let Some(idx) = req
+        .extensions()
+        .get::<route::RouteDebug>()
+        .and_then(|debug| debug.rule)
+    else {
+        return matched;
+    };
+
+    let rules = routes
+        .iter()
+        .map(|rt| &rt.rules)
+        .find(|rules| rules.iter().any(|r| Arc::ptr_eq(&r.policy, matched)));
+    match rules.and_then(|rules| rules.get(idx)) {
+        Some(rule) => {
+            tracing::debug!(rule = idx, "Selected rule from route debug header");
+            &rule.policy
+        }
+        None => {
+            tracing::debug!(rule = idx, "Ignoring route debug header for unknown rule");
+            matched
+        }
+    }
+}
+
 impl<T, M, F, P> svc::Param<LogicalAddr> for Router<T, M, F, P>
 where
     T: Eq + Hash + Clone + Debug,
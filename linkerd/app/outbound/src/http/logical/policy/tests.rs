@@ -57,7 +57,7 @@ async fn header_based_route() {
     // Stack that produces mock services.
     let (inner_default, mut default) = tower_test::mock::pair();
     let (inner_special, mut special) = tower_test::mock::pair();
-    let inner = move |concrete: Concrete<()>| {
+    let inner = move |concrete: Concrete<http::Variant>| {
         if let concrete::Dispatch::Balance(ref addr, ..) = concrete.target {
             if addr
                 .name()
@@ -124,19 +124,23 @@ async fn header_based_route() {
     });
 
     let metrics = HttpRouteMetrics::default();
-    let router = Policy::layer(metrics.clone(), Default::default())
+    let router = Policy::layer(metrics.clone(), Default::default(), None)
         .layer(inner)
-        .new_service(Policy::from((routes, ())));
+        .new_service(Policy::from((routes, http::Variant::Http1)));
 
     let default_reqs = metrics.backend_request_count(
         parent_ref.clone(),
         default_route_ref.clone(),
         default_backend_ref.clone(),
+        Some(8080),
+        Some(http::Variant::Http1),
     );
     let special_reqs = metrics.backend_request_count(
         parent_ref.clone(),
         special_route_ref.clone(),
         special_backend_ref.clone(),
+        Some(8080),
+        Some(http::Variant::Http1),
     );
     assert_eq!(default_reqs.get(), 0);
     assert_eq!(special_reqs.get(), 0);
@@ -192,7 +196,7 @@ async fn http_filter_request_headers() {
 
     // Stack that produces mock services.
     let (inner, mut handle) = tower_test::mock::pair();
-    let inner = move |_: Concrete<()>| inner.clone();
+    let inner = move |_: Concrete<http::Variant>| inner.clone();
 
     // Routes that configure a special header-based route and a default route.
     static PIZZA: http::HeaderName = http::HeaderName::from_static("pizza");
@@ -235,9 +239,9 @@ async fn http_filter_request_headers() {
         }
     });
 
-    let router = Policy::layer(Default::default(), Default::default())
+    let router = Policy::layer(Default::default(), Default::default(), None)
         .layer(inner)
-        .new_service(Policy::from((routes, ())));
+        .new_service(Policy::from((routes, http::Variant::Http1)));
 
     handle.allow(1);
     let req = http::Request::builder()
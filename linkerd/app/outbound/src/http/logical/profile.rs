@@ -1,5 +1,5 @@
 use super::{
-    super::{concrete, retry},
+    super::{concrete, retry, timings},
     CanonicalDstHeader, Concrete, NoRoute,
 };
 use crate::{service_meta, BackendRef, ParentRef, UNKNOWN_META};
@@ -17,7 +17,7 @@ pub use linkerd_app_core::profiles::{
     LogicalAddr, Profile, Target,
 };
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Routes {
     pub addr: LogicalAddr,
     pub routes: Arc<[(RequestMatch, Route)]>,
@@ -69,6 +69,7 @@ where
     /// we can reuse inner services.
     pub(super) fn layer<N, S>(
         metrics: metrics::Proxy,
+        retry_timeout: Option<time::Duration>,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         N: svc::NewService<Concrete<T>, Service = S> + Clone + Send + Sync + 'static,
@@ -88,7 +89,7 @@ where
                 .push(NewBackendCache::layer())
                 // Lazily cache a service for each `RouteParams`
                 // returned from the `SelectRoute` impl.
-                .push_on_service(RouteParams::layer(metrics.clone()))
+                .push_on_service(RouteParams::layer(metrics.clone(), retry_timeout))
                 .push(svc::NewOneshotRoute::<Params<T>, _, _>::layer_cached())
                 .arc_new_clone_http()
                 .into_inner()
@@ -226,6 +227,7 @@ where
 impl<T> RouteParams<T> {
     fn layer<N, S>(
         metrics: metrics::Proxy,
+        retry_timeout: Option<time::Duration>,
     ) -> impl svc::Layer<N, Service = svc::ArcNewCloneHttp<Self>> + Clone
     where
         T: Clone + Debug + Eq + Hash + Send + Sync + 'static,
@@ -261,6 +263,13 @@ impl<T> RouteParams<T> {
                 // retried, it may have one of two `Body` types. This
                 // layer unifies any `Body` type into `BoxBody`.
                 .push_on_service(http::BoxRequest::erased())
+                // Bounds each attempt of a retryable route by the proxy's
+                // per-try timeout, since profiles do not configure one. The
+                // route timeout, applied outside of retries, continues to
+                // bound the total time across all attempts.
+                .push(http::NewTimeout::layer_via(move |rp: &Self| {
+                    http::ResponseTimeout(rp.profile.retries().and(retry_timeout))
+                }))
                 // Sets an optional retry policy.
                 .push(retry::layer(metrics.http_profile_route_retry.clone()))
                 // Sets an optional request timeout.
@@ -286,6 +295,7 @@ impl<T> RouteParams<T> {
                 // Sets the per-route response classifier as a request
                 // extension.
                 .push(classify::NewClassify::layer())
+                .push_on_service(timings::Stamp::layer(timings::Stage::Route))
                 .push_on_service(http::BoxResponse::layer())
                 .arc_new_clone_http()
                 .into_inner()
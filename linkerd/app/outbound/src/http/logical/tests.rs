@@ -12,11 +12,30 @@ use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 use tokio::sync::watch;
 use tracing::Instrument;
 
+mod address_override;
+mod addrs;
 mod basic;
+mod cache;
+mod classification;
+mod coalesce;
+mod decompress;
+mod direct_response;
+mod discovery;
+mod endpoint;
 mod failure_accrual;
+mod hairpin;
 mod headers;
+mod http_version;
+mod keepalive;
+mod methods;
+mod query_params;
 mod retries;
+mod rewrite_authority;
+mod rollout_guard;
+mod route_debug;
+mod span_events;
 mod timeouts;
+mod workload_identity;
 
 type Request = http::Request<http::BoxBody>;
 type Response = http::Response<http::BoxBody>;
@@ -270,6 +289,20 @@ fn mock_grpc(params: client_policy::grpc::RouteParams) -> (svc::BoxCloneHttp, Ha
 }
 
 fn mock(params: policy::Params) -> (svc::BoxCloneHttp, Handle) {
+    mock_with_config(default_config(), params)
+}
+
+fn mock_with_config(config: crate::Config, params: policy::Params) -> (svc::BoxCloneHttp, Handle) {
+    let (svc, handle, _) = mock_with_routes(config, params);
+    (svc, handle)
+}
+
+/// Like [`mock_with_config`], but also returns the routes watch so that tests
+/// may update the target's policy.
+fn mock_with_routes(
+    config: crate::Config,
+    params: policy::Params,
+) -> (svc::BoxCloneHttp, Handle, watch::Sender<Routes>) {
     let (inner, handle) = tower_test::mock::pair();
 
     let addr = SocketAddr::new([192, 0, 2, 41].into(), 1234);
@@ -280,14 +313,15 @@ fn mock(params: policy::Params) -> (svc::BoxCloneHttp, Handle) {
         Default::default(),
     );
     let (rt, shutdown) = runtime();
-    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+    let stack = Outbound::new(config, rt, &mut Default::default())
         .with_stack(svc::ArcNewService::new(connect))
         .push_http_cached(resolve)
         .into_inner();
 
     let (tx, routes) = watch::channel(Routes::Policy(params));
+    let closed = tx.clone();
     tokio::spawn(async move {
-        tx.closed().await;
+        closed.closed().await;
         drop(shutdown);
     });
 
@@ -297,7 +331,7 @@ fn mock(params: policy::Params) -> (svc::BoxCloneHttp, Handle) {
         routes,
     });
 
-    (svc, handle)
+    (svc, handle, tx)
 }
 
 fn mk_route<M: Default, F, P>(
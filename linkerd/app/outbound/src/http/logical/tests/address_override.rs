@@ -0,0 +1,154 @@
+use super::*;
+use linkerd2_proxy_api::destination;
+use linkerd_app_core::{
+    metrics::{EndpointLabels, OutboundEndpointLabels},
+    proxy::api_resolve::pb,
+    tls, trace,
+};
+
+const ID: &str = "vm.ns.serviceaccount.identity.linkerd.cluster.local";
+
+/// The address dialed for each endpoint, with its TLS and metric labels.
+type Targets = Arc<Mutex<Vec<(SocketAddr, tls::ConditionalClientTls, EndpointLabels)>>>;
+
+/// Records the endpoint targets that are built, so that tests may inspect how
+/// each endpoint is dialed and labeled.
+#[derive(Clone)]
+struct RecordTargets {
+    connect: HttpConnect,
+    targets: Targets,
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn dials_override_address() {
+    let _trace = trace::test::trace_init();
+
+    // The endpoint is registered with an address at which it is not
+    // reachable, so requests are only served if the override is dialed.
+    let ep = SocketAddr::new([192, 0, 2, 50].into(), 8080);
+    let override_addr = SocketAddr::new([127, 0, 0, 1].into(), 18080);
+    let (svc, mut handle, targets) = mock_override(ep, Some(override_addr));
+    handle.allow(1);
+    let rsp = send_req(svc, http_get());
+    serve(&mut handle, mk_rsp(StatusCode::OK, "vm")).await;
+    assert_rsp(rsp, StatusCode::OK, "vm").await;
+
+    let targets = targets.lock();
+    let [(connect, tls, labels)] = &targets[..] else {
+        panic!("expected one endpoint target: {targets:?}");
+    };
+    assert_eq!(*connect, override_addr);
+
+    // The endpoint's declared identity is verified, regardless of the address
+    // that is dialed.
+    let tls::ConditionalClientTls::Some(tls) = tls else {
+        panic!("endpoint must be meshed: {tls:?}");
+    };
+    assert_eq!(tls.server_id.to_string(), ID);
+
+    // The endpoint is labeled by its own address.
+    let EndpointLabels::Outbound(OutboundEndpointLabels { target_addr, .. }) = labels else {
+        panic!("endpoint must be labeled as outbound: {labels:?}");
+    };
+    assert_eq!(*target_addr, ep);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn dials_endpoint_address_without_override() {
+    let _trace = trace::test::trace_init();
+
+    let ep = SocketAddr::new([192, 0, 2, 50].into(), 8080);
+    let (svc, mut handle, targets) = mock_override(ep, None);
+    handle.allow(1);
+    let rsp = send_req(svc, http_get());
+    serve(&mut handle, mk_rsp(StatusCode::OK, "pod")).await;
+    assert_rsp(rsp, StatusCode::OK, "pod").await;
+
+    let targets = targets.lock();
+    assert_eq!(targets.iter().map(|(a, _, _)| *a).collect::<Vec<_>>(), [ep]);
+}
+
+// === Utils ===
+
+/// Builds a stack whose destination resolves to a meshed endpoint at `ep`.
+/// Only connections to the override address, if one is set, or to `ep` are
+/// expected.
+fn mock_override(
+    ep: SocketAddr,
+    override_addr: Option<SocketAddr>,
+) -> (svc::BoxCloneHttp, Handle, Targets) {
+    use destination::tls_identity::{DnsLikeIdentity, Strategy};
+
+    let (inner, handle) = tower_test::mock::pair();
+
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let params = policy::Params::Http(policy::HttpParams {
+        addr: dest.clone().into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend.clone()]),
+        routes: Arc::new([default_route(backend)]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    });
+
+    let pb = destination::WeightedAddr {
+        addr: Some(ep.into()),
+        weight: 1,
+        tls_identity: Some(destination::TlsIdentity {
+            strategy: Some(Strategy::DnsLikeIdentity(DnsLikeIdentity {
+                name: ID.to_string(),
+            })),
+            ..Default::default()
+        }),
+        metric_labels: override_addr
+            .map(|a| ("address_override".to_string(), a.to_string()))
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    };
+    let (_, metadata) = pb::to_addr_meta(pb, &Default::default()).expect("valid endpoint");
+
+    let targets = Arc::new(Mutex::new(Vec::new()));
+    let connect = RecordTargets {
+        connect: HttpConnect::default().service(override_addr.unwrap_or(ep), inner),
+        targets: targets.clone(),
+    };
+    let resolve = support::resolver().endpoint_exists(dest, ep, metadata);
+    let (rt, shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let (tx, routes) = watch::channel(Routes::Policy(params));
+    tokio::spawn(async move {
+        tx.closed().await;
+        drop(shutdown);
+    });
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    (svc, handle, targets)
+}
+
+// === impl RecordTargets ===
+
+impl<T> svc::NewService<T> for RecordTargets
+where
+    T: svc::Param<Remote<ServerAddr>>,
+    T: svc::Param<tls::ConditionalClientTls>,
+    T: svc::Param<EndpointLabels>,
+{
+    type Service = svc::BoxHttp;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Remote(ServerAddr(addr)) = target.param();
+        self.targets
+            .lock()
+            .push((addr, target.param(), target.param()));
+        self.connect.new_service(target)
+    }
+}
@@ -0,0 +1,89 @@
+use super::*;
+use crate::http::{LogicalAddr, RoutesAddrs};
+use linkerd_app_core::Addr;
+
+fn policy_routes(dest: &NameAddr) -> Routes {
+    let backend = default_backend(dest);
+    Routes::Policy(policy::Params::Http(policy::HttpParams {
+        addr: dest.clone().into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend.clone()]),
+        routes: Arc::new([default_route(backend)]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    }))
+}
+
+#[test]
+fn authority_changes_mid_connection() {
+    let old: NameAddr = "old.test.svc.cluster.local:8080".parse().unwrap();
+    let new: NameAddr = "new.test.svc.cluster.local:8080".parse().unwrap();
+    let (tx, routes) = watch::channel(policy_routes(&old));
+
+    let addrs = RoutesAddrs::new(routes);
+    // A connection holds a clone of the target's cache.
+    let conn = addrs.clone();
+    assert_eq!(conn.logical_addr(), LogicalAddr(old.clone().into()));
+    assert_eq!(conn.default_authority(), Some(old.as_http_authority()));
+    // Values are stable until the routes change.
+    assert_eq!(conn.default_authority(), Some(old.as_http_authority()));
+
+    tx.send(policy_routes(&new)).unwrap();
+    assert_eq!(conn.default_authority(), Some(new.as_http_authority()));
+    assert_eq!(conn.logical_addr(), LogicalAddr(new.clone().into()));
+    assert_eq!(
+        addrs.default_authority(),
+        Some(new.as_http_authority()),
+        "clones must share the updated values"
+    );
+
+    // Endpoint routes have no default authority.
+    let addr = SocketAddr::new([192, 0, 2, 41].into(), 8080);
+    tx.send(Routes::Endpoint(
+        Remote(ServerAddr(addr)),
+        Default::default(),
+    ))
+    .unwrap();
+    assert_eq!(conn.default_authority(), None);
+    assert_eq!(conn.logical_addr(), LogicalAddr(Addr::Socket(addr)));
+
+    // The last values are retained once the routes can no longer change.
+    drop(tx);
+    assert_eq!(conn.default_authority(), None);
+    assert_eq!(conn.logical_addr(), LogicalAddr(Addr::Socket(addr)));
+}
+
+#[test]
+fn concurrent_reads_during_updates() {
+    let dests = (0..4)
+        .map(|i| format!("svc{i}.test.svc.cluster.local:8080").parse::<NameAddr>())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let (tx, routes) = watch::channel(policy_routes(&dests[0]));
+    let addrs = RoutesAddrs::new(routes);
+
+    let readers = (0..4)
+        .map(|_| {
+            let addrs = addrs.clone();
+            let dests = dests.clone();
+            std::thread::spawn(move || {
+                for _ in 0..1000 {
+                    // Each read observes one of the published routes.
+                    let LogicalAddr(addr) = addrs.logical_addr();
+                    let authority = addrs.default_authority().expect("authority");
+                    assert!(dests.iter().any(|d| Addr::from(d.clone()) == addr));
+                    assert!(dests.iter().any(|d| d.as_http_authority() == authority));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for dest in dests.iter().cycle().take(100) {
+        tx.send(policy_routes(dest)).unwrap();
+    }
+    for reader in readers {
+        reader.join().expect("reader must not panic");
+    }
+
+    // Once updates stop, reads observe the latest routes.
+    let last = dests.iter().cycle().nth(99).unwrap();
+    assert_eq!(addrs.default_authority(), Some(last.as_http_authority()));
+}
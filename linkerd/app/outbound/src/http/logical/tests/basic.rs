@@ -50,3 +50,83 @@ async fn routes() {
         http::StatusCode::OK
     );
 }
+
+/// Tests that a route update applies to requests made after the update, even
+/// while a request matched by the previous routes is still in flight.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn routes_update_in_flight() {
+    let _trace = trace::test::trace_init();
+
+    const PORT: u16 = 666;
+    let dest: NameAddr = format!("logical.test.svc.cluster.local:{PORT}")
+        .parse::<NameAddr>()
+        .expect("dest addr is valid");
+    let old_dest: NameAddr = format!("old.test.svc.cluster.local:{PORT}")
+        .parse::<NameAddr>()
+        .expect("dest addr is valid");
+    let new_dest: NameAddr = format!("new.test.svc.cluster.local:{PORT}")
+        .parse::<NameAddr>()
+        .expect("dest addr is valid");
+    let old_addr = SocketAddr::new([192, 0, 2, 41].into(), PORT);
+    let new_addr = SocketAddr::new([192, 0, 2, 42].into(), PORT);
+    let (old_svc, mut old_handle) = tower_test::mock::pair();
+    let (new_svc, mut new_handle) = tower_test::mock::pair();
+    let connect = HttpConnect::default()
+        .service(old_addr, old_svc)
+        .service(new_addr, new_svc);
+    let resolve = support::resolver()
+        .endpoint_exists(old_dest.clone(), old_addr, Default::default())
+        .endpoint_exists(new_dest.clone(), new_addr, Default::default());
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let mk_params = |backend: client_policy::Backend| {
+        Routes::Policy(policy::Params::Http(policy::HttpParams {
+            addr: dest.clone().into(),
+            meta: ParentRef(client_policy::Meta::new_default("parent")),
+            backends: Arc::new([backend.clone()]),
+            routes: Arc::new([default_route(backend)]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        }))
+    };
+    let (route_tx, routes) = watch::channel(mk_params(default_backend(&old_dest)));
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    // Send a request to the old backend, but don't respond to it yet.
+    old_handle.allow(1);
+    let in_flight = send_req(svc.clone(), http_get());
+    let (_, old_rsp) = old_handle
+        .next_request()
+        .await
+        .expect("old backend must receive request");
+
+    tracing::info!("Updating routes");
+    route_tx
+        .send(mk_params(default_backend(&new_dest)))
+        .expect("routes must be watched");
+    tokio::task::yield_now().await;
+
+    // Subsequent requests use the new routes, while the in-flight request
+    // remains bound to the old backend.
+    for _ in 0..2 {
+        new_handle.allow(1);
+        let rsp = send_req(svc.clone(), http_get());
+        serve(&mut new_handle, mk_rsp(StatusCode::OK, "new")).await;
+        assert_rsp(rsp, StatusCode::OK, "new").await;
+    }
+
+    old_rsp.send_response(
+        http::Response::builder()
+            .status(StatusCode::OK)
+            .body(http::BoxBody::new("old".to_string()))
+            .unwrap(),
+    );
+    assert_rsp(in_flight, StatusCode::OK, "old").await;
+}
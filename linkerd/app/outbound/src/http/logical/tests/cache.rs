@@ -0,0 +1,57 @@
+use super::*;
+use linkerd_app_core::{proxy::http::StatusCode, trace};
+use linkerd_proxy_client_policy::http::{Cache, RouteParams as HttpParams};
+use tokio::time;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn serves_cached_responses() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_http(HttpParams {
+        cache: Some(Cache::default()),
+        ..Default::default()
+    });
+
+    handle.allow(1);
+    let rsp = send_req(svc.clone(), http_get());
+    serve(&mut handle, cacheable_rsp("v1")).await;
+    assert_rsp(rsp, StatusCode::OK, "v1").await;
+
+    // The cached response is served without sending the request.
+    handle.allow(1);
+    let rsp = send_req(svc.clone(), http_get());
+    assert_rsp(rsp, StatusCode::OK, "v1").await;
+    assert!(
+        time::timeout(time::Duration::from_secs(1), handle.next_request())
+            .await
+            .is_err(),
+        "cached request must not be sent"
+    );
+
+    // Other methods are not cached.
+    handle.allow(1);
+    let post = http::Request::post("/").body(BoxBody::empty()).unwrap();
+    let rsp = send_req(svc.clone(), post);
+    serve(&mut handle, cacheable_rsp("posted")).await;
+    assert_rsp(rsp, StatusCode::OK, "posted").await;
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn routes_without_caching() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_http(Default::default());
+    for body in ["v1", "v2"] {
+        handle.allow(1);
+        let rsp = send_req(svc.clone(), http_get());
+        serve(&mut handle, cacheable_rsp(body)).await;
+        assert_rsp(rsp, StatusCode::OK, body).await;
+    }
+}
+
+async fn cacheable_rsp(body: &'static str) -> Result<Response> {
+    Ok(http::Response::builder()
+        .header(http::header::CACHE_CONTROL, "max-age=60")
+        .body(BoxBody::from_static(body))
+        .unwrap())
+}
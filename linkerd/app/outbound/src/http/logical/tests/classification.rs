@@ -0,0 +1,174 @@
+use super::*;
+use linkerd_app_core::{
+    drain,
+    metrics::{self, legacy::FmtMetrics},
+    proxy::{http::StatusCode, tap},
+    svc, trace, ProxyRuntime,
+};
+use linkerd_proxy_client_policy as client_policy;
+use std::{net::SocketAddr, sync::Arc};
+use tokio::sync::watch;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_failure_statuses() {
+    let _trace = trace::test::trace_init();
+
+    let (metrics, report) = metrics::Metrics::new(Duration::from_secs(10));
+    let (svc, mut handle, route_tx) = mock_with_metrics(metrics.proxy, http_params(None));
+
+    // By default, a 404 is classified as a success.
+    handle.allow(1);
+    let rsp = send_req(svc.clone(), http_get());
+    serve(&mut handle, mk_rsp(StatusCode::NOT_FOUND, "")).await;
+    assert_rsp(rsp, StatusCode::NOT_FOUND, "").await;
+    assert_eq!(responses(&report, "404", "success"), 1);
+    assert_eq!(responses(&report, "404", "failure"), 0);
+
+    // Once the route is updated to treat 404s as failures, the classification
+    // of subsequent responses changes.
+    let failures = client_policy::http::StatusRanges(Arc::new([404..=404]));
+    route_tx
+        .send(Routes::Policy(http_params(Some(failures))))
+        .expect("routes must be watched");
+    tokio::task::yield_now().await;
+
+    handle.allow(1);
+    let rsp = send_req(svc.clone(), http_get());
+    serve(&mut handle, mk_rsp(StatusCode::NOT_FOUND, "")).await;
+    assert_rsp(rsp, StatusCode::NOT_FOUND, "").await;
+    assert_eq!(responses(&report, "404", "success"), 1);
+    assert_eq!(responses(&report, "404", "failure"), 1);
+
+    // A 500 is no longer a failure, since it's not in the configured ranges.
+    handle.allow(1);
+    let rsp = send_req(svc.clone(), http_get());
+    serve(&mut handle, mk_rsp(StatusCode::INTERNAL_SERVER_ERROR, "")).await;
+    assert_rsp(rsp, StatusCode::INTERNAL_SERVER_ERROR, "").await;
+    assert_eq!(responses(&report, "500", "success"), 1);
+    assert_eq!(responses(&report, "500", "failure"), 0);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn grpc_failure_codes() {
+    let _trace = trace::test::trace_init();
+
+    let (metrics, report) = metrics::Metrics::new(Duration::from_secs(10));
+    let codes = client_policy::grpc::Codes(Arc::new(
+        [tonic::Code::NotFound as u16].into_iter().collect(),
+    ));
+    let (svc, mut handle, _route_tx) = mock_with_metrics(metrics.proxy, grpc_params(Some(codes)));
+
+    handle.allow(1);
+    let rsp = send_req(
+        svc.clone(),
+        http::Request::post("/svc/method")
+            .header("content-type", "application/grpc")
+            .body(Default::default())
+            .unwrap(),
+    );
+    serve(&mut handle, mk_grpc_rsp(tonic::Code::NotFound)).await;
+    assert_rsp(rsp, StatusCode::OK, "").await;
+    let metrics = report.as_display().to_string();
+    assert!(
+        metrics.lines().any(|l| l.starts_with("response_total{")
+            && l.contains("classification=\"failure\",grpc_status=\"5\"")),
+        "NotFound must be classified as a failure:\n{metrics}"
+    );
+}
+
+// === Utils ===
+
+fn http_params(failure_statuses: Option<client_policy::http::StatusRanges>) -> policy::Params {
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let route = mk_route(
+        backend.clone(),
+        client_policy::http::RouteParams {
+            failure_statuses,
+            ..Default::default()
+        },
+    );
+    policy::Params::Http(policy::HttpParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    })
+}
+
+fn grpc_params(failure_codes: Option<client_policy::grpc::Codes>) -> policy::Params {
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let route = mk_route(
+        backend.clone(),
+        client_policy::grpc::RouteParams {
+            failure_codes,
+            ..Default::default()
+        },
+    );
+    policy::Params::Grpc(policy::GrpcParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    })
+}
+
+fn mock_with_metrics(
+    metrics: metrics::Proxy,
+    params: policy::Params,
+) -> (svc::BoxCloneHttp, Handle, watch::Sender<Routes>) {
+    let (inner, handle) = tower_test::mock::pair();
+
+    let addr = SocketAddr::new([192, 0, 2, 41].into(), 1234);
+    let connect = HttpConnect::default().service(addr, inner);
+    let resolve = support::resolver().endpoint_exists(
+        params.addr().name_addr().unwrap().clone(),
+        addr,
+        Default::default(),
+    );
+    let (drain_tx, drain) = drain::channel();
+    let (tap, _) = tap::new();
+    let rt = ProxyRuntime {
+        identity: linkerd_meshtls_rustls::creds::default_for_test().1.into(),
+        metrics,
+        tap,
+        span_sink: None,
+        drain,
+        tunables: tokio::sync::watch::channel(Default::default()).1,
+    };
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let (tx, routes) = watch::channel(Routes::Policy(params));
+    let closed = tx.clone();
+    tokio::spawn(async move {
+        closed.closed().await;
+        drop(drain_tx);
+    });
+
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    (svc, handle, tx)
+}
+
+/// Returns the value of the endpoint-level `response_total` counter for the
+/// given status and classification.
+fn responses(report: &impl FmtMetrics, status: &str, class: &str) -> u64 {
+    let metrics = report.as_display().to_string();
+    metrics
+        .lines()
+        .filter(|l| l.starts_with("response_total{"))
+        .filter(|l| l.contains(&format!("status_code=\"{status}\"")))
+        .filter(|l| l.contains(&format!("classification=\"{class}\"")))
+        .filter_map(|l| l.rsplit(' ').next()?.parse::<u64>().ok())
+        .sum()
+}
@@ -0,0 +1,63 @@
+use super::*;
+use http_body_util::BodyExt;
+use linkerd_app_core::{proxy::http::StatusCode, trace};
+use linkerd_proxy_client_policy::http::{Coalesce, RouteParams as HttpParams};
+use tokio::time;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn coalesces_identical_requests() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_http(HttpParams {
+        coalesce: Some(Coalesce {
+            headers: vec![],
+            max_waiters: 10,
+            timeout: time::Duration::from_secs(5),
+        }),
+        ..Default::default()
+    });
+
+    handle.allow(3);
+    let rsps = [
+        send_req(svc.clone(), http_get()),
+        send_req(svc.clone(), http_get()),
+        send_req(svc.clone(), http_get()),
+    ];
+    // Let each request reach the route before the first is served.
+    time::sleep(time::Duration::from_millis(1)).await;
+    serve(&mut handle, mk_rsp(StatusCode::OK, "shared")).await;
+    for rsp in rsps {
+        assert_rsp(rsp, StatusCode::OK, "shared").await;
+    }
+    assert!(
+        time::timeout(time::Duration::from_secs(1), handle.next_request())
+            .await
+            .is_err(),
+        "coalesced requests must not be sent"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn routes_without_coalescing() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_http(Default::default());
+
+    handle.allow(2);
+    let rsps = [
+        send_req(svc.clone(), http_get()),
+        send_req(svc.clone(), http_get()),
+    ];
+    time::sleep(time::Duration::from_millis(1)).await;
+    for body in ["v1", "v2"] {
+        serve(&mut handle, mk_rsp(StatusCode::OK, body)).await;
+    }
+    let mut bodies = vec![];
+    for rsp in rsps {
+        let rsp = rsp.await.expect("response must succeed");
+        let body = rsp.into_body().collect().await.unwrap().to_bytes();
+        bodies.push(String::from_utf8(body.to_vec()).unwrap());
+    }
+    bodies.sort();
+    assert_eq!(bodies, ["v1", "v2"]);
+}
@@ -0,0 +1,123 @@
+use super::*;
+use flate2::{write::GzEncoder, Compression};
+use http_body_util::BodyExt;
+use linkerd_app_core::{errors, trace};
+use linkerd_http_route::http::filter::DecompressRequest;
+use linkerd_proxy_client_policy::http::{Filter, RouteParams as HttpParams};
+use std::io::Write;
+use tokio::time;
+use tracing::{info, Instrument};
+
+const MAX_BYTES: usize = 64;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn decompresses_gzip_body() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_decompress();
+    handle.allow(1);
+    let rsp = send_req(svc, gzip_req(b"hello, world"));
+
+    let (req, tx) = handle.next_request().await.expect("request");
+    assert!(req.headers().get(http::header::CONTENT_ENCODING).is_none());
+    assert_eq!(req.headers()[http::header::CONTENT_LENGTH], "12");
+    let body = req.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, &b"hello, world"[..]);
+    tx.send_response(
+        http::Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(BoxBody::empty())
+            .unwrap(),
+    );
+
+    let rsp = rsp.await.expect("response");
+    assert_eq!(rsp.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn rejects_oversized_body() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_decompress();
+    handle.allow(1);
+    tokio::spawn(
+        async move {
+            if handle.next_request().await.is_some() {
+                panic!("oversized request must not be dispatched");
+            }
+        }
+        .in_current_span(),
+    );
+
+    info!("Sending a request that decompresses beyond the limit");
+    let error = time::timeout(
+        time::Duration::from_secs(10),
+        send_req(svc, gzip_req(&[b'a'; MAX_BYTES + 1])),
+    )
+    .await
+    .expect("response")
+    .expect_err("request must fail");
+    assert!(
+        errors::is_caused_by::<policy::errors::HttpRouteRequestTooLarge>(error.as_ref()),
+        "expected request too large error; got {error}"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn rejects_invalid_encoding() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_decompress();
+    handle.allow(1);
+    tokio::spawn(
+        async move {
+            if handle.next_request().await.is_some() {
+                panic!("malformed request must not be dispatched");
+            }
+        }
+        .in_current_span(),
+    );
+
+    let req = http::Request::post("/")
+        .header(http::header::CONTENT_ENCODING, "gzip")
+        .body(BoxBody::from_static("not gzip"))
+        .unwrap();
+    let error = time::timeout(time::Duration::from_secs(10), send_req(svc, req))
+        .await
+        .expect("response")
+        .expect_err("request must fail");
+    assert!(
+        errors::is_caused_by::<policy::errors::HttpRouteInvalidRequestEncoding>(error.as_ref()),
+        "expected invalid encoding error; got {error}"
+    );
+}
+
+// === Utils ===
+
+fn mock_decompress() -> (svc::BoxCloneHttp, Handle) {
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let mut route = mk_route(backend.clone(), HttpParams::default());
+    route.rules[0].policy.filters = Arc::new([Filter::DecompressRequest(DecompressRequest {
+        max_bytes: MAX_BYTES,
+    })]);
+    mock(policy::Params::Http(policy::HttpParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    }))
+}
+
+fn gzip_req(body: &[u8]) -> Request {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body).unwrap();
+    let body = encoder.finish().unwrap();
+    http::Request::post("/")
+        .header(http::header::CONTENT_ENCODING, "gzip")
+        .body(BoxBody::new(http_body_util::Full::new(bytes::Bytes::from(
+            body,
+        ))))
+        .unwrap()
+}
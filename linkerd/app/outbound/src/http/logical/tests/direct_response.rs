@@ -0,0 +1,93 @@
+use super::*;
+use http_body_util::BodyExt;
+use linkerd_app_core::trace;
+use linkerd_proxy_client_policy::{grpc, http::filter::DirectResponse};
+use tokio::time;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn responds_directly() {
+    let _trace = trace::test::trace_init();
+
+    let direct = DirectResponse::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        vec![(
+            ::http::header::RETRY_AFTER,
+            ::http::HeaderValue::from_static("120"),
+        )],
+        r#"{"status":"maintenance"}"#,
+    )
+    .unwrap();
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let mut route = mk_route(backend.clone(), Default::default());
+    route.rules[0].policy.filters = Arc::new([client_policy::http::Filter::DirectResponse(direct)]);
+    let (svc, mut handle) = mock(policy::Params::Http(policy::HttpParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    }));
+    handle.allow(1);
+
+    let rsp = send_req(svc, http_get()).await.expect("response");
+    assert_eq!(rsp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(rsp.headers()[::http::header::RETRY_AFTER], "120");
+    let body = rsp.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, r#"{"status":"maintenance"}"#);
+    assert_no_request(&mut handle).await;
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn responds_directly_to_grpc() {
+    let _trace = trace::test::trace_init();
+
+    let direct = grpc::filter::DirectResponse {
+        code: tonic::Code::Unavailable as u16,
+        message: "down for maintenance".into(),
+    };
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let mut route = mk_route(backend.clone(), grpc::RouteParams::default());
+    route.rules[0].policy.filters = Arc::new([grpc::Filter::DirectResponse(direct)]);
+    let (svc, mut handle) = mock(policy::Params::Grpc(policy::GrpcParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    }));
+    handle.allow(1);
+
+    let req = http::Request::post("/svc/Method")
+        .version(::http::Version::HTTP_2)
+        .header("content-type", "application/grpc")
+        .body(BoxBody::empty())
+        .unwrap();
+    let rsp = send_req(svc, req).await.expect("response");
+    assert_eq!(rsp.status(), StatusCode::OK);
+    assert_eq!(rsp.headers()["grpc-status"], "14");
+    assert_eq!(rsp.headers()["grpc-message"], "down for maintenance");
+    assert!(rsp.body().is_end_stream(), "response must be trailers-only");
+    assert_no_request(&mut handle).await;
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn routes_without_direct_response() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_http(Default::default());
+    handle.allow(1);
+    let rsp = send_req(svc, http_get());
+    serve(&mut handle, mk_rsp(StatusCode::OK, "good")).await;
+    assert_rsp(rsp, StatusCode::OK, "good").await;
+}
+
+async fn assert_no_request(handle: &mut Handle) {
+    assert!(
+        time::timeout(time::Duration::from_secs(1), handle.next_request())
+            .await
+            .is_err(),
+        "directly answered requests must not be forwarded"
+    );
+}
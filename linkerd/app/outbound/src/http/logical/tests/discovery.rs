@@ -0,0 +1,106 @@
+use super::*;
+use linkerd_app_core::{errors, metrics::prom, proxy::api_resolve::Metadata, svc, trace, NameAddr};
+use linkerd_proxy_client_policy as client_policy;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+const PORT: u16 = 666;
+
+/// Tests that requests that fail because a backend has no endpoints describe
+/// whether the backend's endpoints have not been resolved, could not be
+/// resolved, or were resolved to an empty set.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn failfast_describes_discovery() {
+    let _trace = trace::test::trace_init();
+
+    // No update is received.
+    let (error, metrics) = failfast("pending", |_| {}).await;
+    assert!(
+        error.contains("endpoints have not been resolved"),
+        "unexpected error: {error}"
+    );
+    assert_error_label(&metrics, "pending", "FAIL_FAST_UNRESOLVED");
+
+    // The controller fails to resolve the service.
+    let (error, metrics) = failfast("unavailable", |tx| {
+        tx.unavailable().unwrap();
+    })
+    .await;
+    assert!(
+        error.contains("endpoint discovery is unavailable"),
+        "unexpected error: {error}"
+    );
+    assert_error_label(&metrics, "unavailable", "FAIL_FAST_DISCOVERY_UNAVAILABLE");
+
+    // The controller resolves the service without any endpoints.
+    let (error, metrics) = failfast("empty", |tx| {
+        tx.reset(vec![]).unwrap();
+    })
+    .await;
+    assert!(
+        error.contains("resolved to zero endpoints"),
+        "unexpected error: {error}"
+    );
+    assert_error_label(&metrics, "empty", "FAIL_FAST_NO_ENDPOINTS");
+}
+
+// === Utils ===
+
+/// Sends a request to a backend whose resolution is driven by `resolve`,
+/// returning the request's error and the encoded metrics once it fails fast.
+async fn failfast(
+    name: &'static str,
+    resolve: impl FnOnce(&mut support::resolver::DstSender<Metadata>),
+) -> (String, String) {
+    let dest: NameAddr = format!("{name}.test.svc.cluster.local:{PORT}")
+        .parse::<NameAddr>()
+        .expect("dest addr is valid");
+    let resolver = support::resolver();
+    let mut tx = resolver.endpoint_tx(dest.clone());
+    resolve(&mut tx);
+
+    let mut registry = prom::Registry::default();
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut registry)
+        .with_stack(svc::ArcNewService::new(HttpConnect::default()))
+        .push_http_cached(resolver)
+        .into_inner();
+
+    let backend = default_backend(&dest);
+    let (_route_tx, routes) =
+        watch::channel(Routes::Policy(policy::Params::Http(policy::HttpParams {
+            addr: dest.into(),
+            meta: ParentRef(client_policy::Meta::new_default(name)),
+            backends: Arc::new([backend.clone()]),
+            routes: Arc::new([default_route(backend)]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        })));
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    let error = send_req(svc, http_get())
+        .await
+        .expect_err("request must fail");
+    assert!(
+        errors::is_caused_by::<errors::FailFastError>(error.as_ref()),
+        "request must fail fast: {error}"
+    );
+    drop(tx);
+
+    let mut metrics = String::new();
+    prom::encoding::text::encode(&mut metrics, &registry).expect("metrics must encode");
+    (error.to_string(), metrics)
+}
+
+#[track_caller]
+fn assert_error_label(metrics: &str, parent: &str, error: &str) {
+    assert!(
+        metrics.lines().any(|l| l.contains("route_request_statuses")
+            && l.contains(&format!("parent_name=\"{parent}\""))
+            && l.contains(&format!("error=\"{error}\""))),
+        "missing {error} label for {parent}:\n{metrics}"
+    );
+}
@@ -0,0 +1,237 @@
+use super::*;
+use linkerd_app_core::trace;
+use linkerd_proxy_client_policy::{
+    grpc::RouteParams as GrpcParams,
+    http::{Retry, RouteParams as HttpParams, Timeouts},
+};
+use tokio::time;
+use tracing::info;
+
+const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+
+const OTHER_PORT_BACKEND: &str = "other.example.com:9090";
+
+fn parent_params(params: HttpParams) -> policy::Params {
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    policy::Params::Http(policy::HttpParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend.clone()]),
+        routes: Arc::new([mk_route(backend, params)]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    })
+}
+
+#[test]
+fn only_trims_routes_with_timeouts_or_retries() {
+    let ep = SocketAddr::new([10, 1, 2, 3].into(), 8080);
+    assert_eq!(
+        parent_params(Default::default()).forward_to_endpoint(ep, Default::default()),
+        None,
+        "routes without timeouts or retries must not be trimmed"
+    );
+
+    let trimmed = parent_params(HttpParams {
+        timeouts: Timeouts {
+            request: Some(TIMEOUT),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .forward_to_endpoint(ep, Default::default())
+    .expect("routes with timeouts must be trimmed");
+    let policy::Params::Http(trimmed) = trimmed else {
+        panic!("HTTP routes must remain HTTP routes");
+    };
+    assert_eq!(trimmed.backends.len(), 1);
+    assert!(matches!(
+        trimmed.backends[0].dispatcher,
+        client_policy::BackendDispatcher::Forward(addr, _) if addr == ep
+    ));
+    assert_eq!(
+        trimmed.routes[0].rules[0].policy.params.timeouts.request,
+        Some(TIMEOUT)
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn parent_retries_apply_to_endpoint() {
+    let _trace = trace::test::trace_init();
+
+    let ep = SocketAddr::new([10, 1, 2, 3].into(), 8080);
+    let params = parent_params(HttpParams {
+        retry: Some(Retry {
+            max_retries: 1,
+            status_ranges: Default::default(),
+            max_request_bytes: 1000,
+            timeout: None,
+            backoff: None,
+        }),
+        ..Default::default()
+    })
+    .forward_to_endpoint(ep, Default::default())
+    .expect("routes with retries must be trimmed");
+
+    // The parent is not resolvable, so requests are only served if they are
+    // forwarded to the endpoint.
+    let (inner, mut handle) = tower_test::mock::pair();
+    let connect = HttpConnect::default().service(ep, inner);
+    let (rt, shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(support::resolver())
+        .into_inner();
+    let (tx, routes) = watch::channel(Routes::Policy(params));
+    tokio::spawn(async move {
+        tx.closed().await;
+        drop(shutdown);
+    });
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    tokio::spawn(
+        async move {
+            handle.allow(2);
+            serve(&mut handle, mk_rsp(StatusCode::INTERNAL_SERVER_ERROR, "")).await;
+            serve(&mut handle, mk_rsp(StatusCode::NO_CONTENT, "")).await;
+            handle
+        }
+        .in_current_span(),
+    );
+
+    info!("Sending a request to the endpoint that will initially fail and then succeed");
+    let rsp = time::timeout(TIMEOUT, send_req(svc, http_get()))
+        .await
+        .expect("response");
+    assert_eq!(rsp.expect("response").status(), StatusCode::NO_CONTENT);
+}
+
+#[test]
+fn retains_backends_on_other_ports() {
+    let ep = SocketAddr::new([10, 1, 2, 3].into(), 1234);
+    for grpc in [false, true] {
+        let trimmed = other_port_params(grpc)
+            .forward_to_endpoint(ep, Default::default())
+            .expect("routes with timeouts must be trimmed");
+        let backends = match trimmed {
+            policy::Params::Http(ref p) => p.backends.clone(),
+            policy::Params::Grpc(ref p) => p.backends.clone(),
+        };
+        assert_eq!(backends.len(), 2);
+        assert!(matches!(
+            backends[0].dispatcher,
+            client_policy::BackendDispatcher::Forward(addr, _) if addr == ep
+        ));
+        assert_eq!(
+            backends[1].port(),
+            Some(9090),
+            "backends on other ports must be retained"
+        );
+    }
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_backend_port_overrides_endpoint_port() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_other_port(other_port_params(false));
+
+    handle.allow(1);
+    let rsp = send_req(svc, http_get());
+    serve(&mut handle, mk_rsp(StatusCode::OK, "good")).await;
+    assert_rsp(rsp, StatusCode::OK, "good").await;
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn grpc_backend_port_overrides_endpoint_port() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_other_port(other_port_params(true));
+
+    handle.allow(1);
+    let rsp = send_req(
+        svc,
+        http::Request::post("/svc/method")
+            .header("content-type", "application/grpc")
+            .body(Default::default())
+            .unwrap(),
+    );
+    serve(&mut handle, mk_grpc_rsp(tonic::Code::Ok)).await;
+    assert_rsp(rsp, StatusCode::OK, "").await;
+}
+
+/// Returns routes with timeouts for `example.com:1234` whose only route
+/// backend declares another port.
+fn other_port_params(grpc: bool) -> policy::Params {
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(OTHER_PORT_BACKEND);
+    let backends: Arc<[_]> = Arc::new([default_backend(&dest), backend.clone()]);
+    let meta = ParentRef(client_policy::Meta::new_default("parent"));
+    let timeouts = Timeouts {
+        request: Some(TIMEOUT),
+        ..Default::default()
+    };
+    if grpc {
+        let params = GrpcParams {
+            timeouts,
+            ..Default::default()
+        };
+        return policy::Params::Grpc(policy::GrpcParams {
+            addr: dest.into(),
+            meta,
+            backends,
+            routes: Arc::new([mk_route(backend, params)]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        });
+    }
+    let params = HttpParams {
+        timeouts,
+        ..Default::default()
+    };
+    policy::Params::Http(policy::HttpParams {
+        addr: dest.into(),
+        meta,
+        backends,
+        routes: Arc::new([mk_route(backend, params)]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    })
+}
+
+/// Forwards `params` to an endpoint on the parent's port, where only the
+/// backend on another port is served.
+fn mock_other_port(params: policy::Params) -> (svc::BoxCloneHttp, Handle) {
+    let ep = SocketAddr::new([10, 1, 2, 3].into(), 1234);
+    let params = params
+        .forward_to_endpoint(ep, Default::default())
+        .expect("routes with timeouts must be trimmed");
+
+    let (inner, handle) = tower_test::mock::pair();
+    let addr = SocketAddr::new([192, 0, 2, 41].into(), 9090);
+    let connect = HttpConnect::default().service(addr, inner);
+    let resolve = support::resolver().endpoint_exists(
+        OTHER_PORT_BACKEND.parse::<NameAddr>().unwrap(),
+        addr,
+        Default::default(),
+    );
+    let (rt, shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+    let (tx, routes) = watch::channel(Routes::Policy(params));
+    tokio::spawn(async move {
+        tx.closed().await;
+        drop(shutdown);
+    });
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    (svc, handle)
+}
@@ -0,0 +1,92 @@
+use super::*;
+use crate::http::concrete::{Hairpin, HairpinConfig};
+use linkerd_app_core::trace;
+use std::net::{IpAddr, Ipv4Addr};
+
+const POD_IP: [u8; 4] = [192, 0, 2, 41];
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn forwards_to_local_workload_over_loopback() {
+    let _trace = trace::test::trace_init();
+
+    // The resolution includes the local pod's IP, so the request is sent to
+    // the application over the loopback interface.
+    let loopback = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 1234);
+    let (svc, mut handle) = mock_hairpin(Some(HairpinConfig::default()), loopback);
+    handle.allow(1);
+    let rsp = send_req(svc, http_get());
+    serve(&mut handle, mk_rsp(StatusCode::OK, "")).await;
+    let rsp = rsp.await.expect("response must succeed");
+    assert_eq!(rsp.status(), StatusCode::OK);
+    assert!(
+        rsp.extensions().get::<Hairpin>().is_some(),
+        "response must be marked as a hairpin"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn disabled() {
+    let _trace = trace::test::trace_init();
+
+    // When hairpinning is disabled, the pod IP is dialed as with any other
+    // endpoint.
+    let pod = SocketAddr::new(POD_IP.into(), 1234);
+    let (svc, mut handle) = mock_hairpin(None, pod);
+    handle.allow(1);
+    let rsp = send_req(svc, http_get());
+    serve(&mut handle, mk_rsp(StatusCode::OK, "")).await;
+    let rsp = rsp.await.expect("response must succeed");
+    assert_eq!(rsp.status(), StatusCode::OK);
+    assert!(rsp.extensions().get::<Hairpin>().is_none());
+}
+
+// === Utils ===
+
+/// Builds a stack whose destination resolves to the local pod's IP. Only
+/// connections to `connect` are expected.
+fn mock_hairpin(
+    hairpin: Option<HairpinConfig>,
+    connect: SocketAddr,
+) -> (svc::BoxCloneHttp, Handle) {
+    let (inner, handle) = tower_test::mock::pair();
+
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let params = policy::Params::Http(policy::HttpParams {
+        addr: dest.clone().into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend.clone()]),
+        routes: Arc::new([default_route(backend)]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    });
+
+    let mut config = default_config();
+    config.inbound_ips = Arc::new([IpAddr::from(POD_IP)].into_iter().collect());
+    config.http_hairpin = hairpin;
+
+    let connect = HttpConnect::default().service(connect, inner);
+    let resolve = support::resolver().endpoint_exists(
+        dest,
+        SocketAddr::new(POD_IP.into(), 1234),
+        Default::default(),
+    );
+    let (rt, shutdown) = runtime();
+    let stack = Outbound::new(config, rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let (tx, routes) = watch::channel(Routes::Policy(params));
+    tokio::spawn(async move {
+        tx.closed().await;
+        drop(shutdown);
+    });
+
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    (svc, handle)
+}
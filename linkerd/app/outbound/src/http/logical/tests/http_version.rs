@@ -0,0 +1,72 @@
+use super::*;
+use linkerd_app_core::{metrics::prom, trace};
+
+const PORT: u16 = 8080;
+
+/// Tests that HTTP/1 and HTTP/2 requests to the same destination are recorded
+/// in distinct route and backend series.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn labels_http_version() {
+    let _trace = trace::test::trace_init();
+
+    let dest: NameAddr = format!("web.test.svc.cluster.local:{PORT}")
+        .parse::<NameAddr>()
+        .expect("dest addr is valid");
+    let ep = SocketAddr::new([192, 0, 2, 41].into(), PORT);
+    let (inner, mut handle) = tower_test::mock::pair();
+    let connect = HttpConnect::default().service(ep, inner);
+    let resolve = support::resolver().endpoint_exists(dest.clone(), ep, Default::default());
+
+    let mut registry = prom::Registry::default();
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut registry)
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let backend = default_backend(&dest);
+    let (_route_tx, routes) =
+        watch::channel(Routes::Policy(policy::Params::Http(policy::HttpParams {
+            addr: dest.into(),
+            meta: ParentRef(client_policy::Meta::new_default("parent")),
+            backends: Arc::new([backend.clone()]),
+            routes: Arc::new([default_route(backend)]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        })));
+
+    // Each version is served by its own logical stack.
+    for (num, version) in [(1, http::Variant::Http1), (2, http::Variant::H2)] {
+        let svc = stack.new_service(Target {
+            num,
+            version,
+            routes: routes.clone(),
+        });
+        handle.allow(1);
+        let rsp = send_req(svc, http_get());
+        serve(&mut handle, mk_rsp(StatusCode::OK, "good")).await;
+        assert_rsp(rsp, StatusCode::OK, "good").await;
+    }
+
+    let mut metrics = String::new();
+    prom::encoding::text::encode(&mut metrics, &registry).expect("metrics must encode");
+    for family in ["http_route_request_statuses", "http_route_backend_requests"] {
+        for version in ["http/1", "http/2"] {
+            assert_eq!(
+                count_series(&metrics, family, version),
+                1,
+                "expected one {version} series in {family}:\n{metrics}"
+            );
+        }
+    }
+}
+
+// === Utils ===
+
+/// Counts the series of a metric family with the given `http_version` label.
+fn count_series(metrics: &str, family: &str, version: &str) -> usize {
+    metrics
+        .lines()
+        .filter(|l| !l.starts_with('#') && l.contains(family))
+        .filter(|l| l.contains(&format!("http_version=\"{version}\"")))
+        .count()
+}
@@ -0,0 +1,167 @@
+use super::*;
+use crate::http::KeepaliveExempt;
+use linkerd_app_core::{proxy::http::StatusCode, trace};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::time;
+
+const IDLE: Duration = Duration::from_secs(10);
+
+/// Counts the endpoint services that are built, so that tests may observe when
+/// a logical service is rebuilt.
+#[derive(Clone)]
+struct CountBuilds {
+    connect: HttpConnect,
+    builds: Arc<AtomicUsize>,
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn evicts_services_used_only_by_probes() {
+    let _trace = trace::test::trace_init();
+
+    let mut config = default_config();
+    config.discovery_idle_timeout = IDLE;
+    config.http_keepalive_exempt = vec![
+        KeepaliveExempt::PathPrefix("/healthz".to_string()),
+        KeepaliveExempt::Request(http::Method::GET, "/ready".to_string()),
+    ];
+    let (stack, target, builds, mut handle) = mk_stack(config);
+
+    // A client holds the service while only sending probes.
+    let svc = stack.new_service(target.clone());
+    for path in ["/healthz", "/healthz/live", "/ready"]
+        .iter()
+        .cycle()
+        .take(6)
+    {
+        handle.allow(1);
+        let req = http::Request::get(*path).body(BoxBody::empty()).unwrap();
+        let rsp = send_req(svc.clone(), req);
+        serve(&mut handle, mk_rsp(StatusCode::OK, "ok")).await;
+        assert_rsp(rsp, StatusCode::OK, "ok").await;
+        time::sleep(IDLE / 2).await;
+    }
+    assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+    // The service was evicted, so a new client builds a new service.
+    let svc = stack.new_service(target);
+    handle.allow(1);
+    let rsp = send_req(svc, http_get());
+    serve(&mut handle, mk_rsp(StatusCode::OK, "ok")).await;
+    assert_rsp(rsp, StatusCode::OK, "ok").await;
+    assert_eq!(builds.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn retains_services_used_by_other_requests() {
+    let _trace = trace::test::trace_init();
+
+    let mut config = default_config();
+    config.discovery_idle_timeout = IDLE;
+    config.http_keepalive_exempt = vec![KeepaliveExempt::PathPrefix("/healthz".to_string())];
+    let (stack, target, builds, mut handle) = mk_stack(config);
+
+    // `/healthzz` is not nested under `/healthz`, so it is not exempt.
+    let svc = stack.new_service(target.clone());
+    for path in ["/", "/healthzz"].iter().cycle().take(6) {
+        handle.allow(1);
+        let req = http::Request::get(*path).body(BoxBody::empty()).unwrap();
+        let rsp = send_req(svc.clone(), req);
+        serve(&mut handle, mk_rsp(StatusCode::OK, "ok")).await;
+        assert_rsp(rsp, StatusCode::OK, "ok").await;
+        time::sleep(IDLE / 2).await;
+    }
+
+    let svc = stack.new_service(target);
+    handle.allow(1);
+    let rsp = send_req(svc, http_get());
+    serve(&mut handle, mk_rsp(StatusCode::OK, "ok")).await;
+    assert_rsp(rsp, StatusCode::OK, "ok").await;
+    assert_eq!(builds.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn rebuilds_services_after_max_age() {
+    let _trace = trace::test::trace_init();
+
+    let mut config = default_config();
+    config.discovery_idle_timeout = IDLE;
+    config.http_logical_max_age = Some(IDLE * 2);
+    let (stack, target, builds, mut handle) = mk_stack(config);
+
+    // The service is held and active, but it reaches its maximum age.
+    let svc = stack.new_service(target.clone());
+    for _ in 0..6 {
+        handle.allow(1);
+        let rsp = send_req(svc.clone(), http_get());
+        serve(&mut handle, mk_rsp(StatusCode::OK, "ok")).await;
+        assert_rsp(rsp, StatusCode::OK, "ok").await;
+        time::sleep(IDLE / 2).await;
+    }
+    assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+    // A new client builds a new service. The client that held the prior
+    // service continues to use it.
+    let rebuilt = stack.new_service(target);
+    for svc in [rebuilt, svc] {
+        handle.allow(1);
+        let rsp = send_req(svc, http_get());
+        serve(&mut handle, mk_rsp(StatusCode::OK, "ok")).await;
+        assert_rsp(rsp, StatusCode::OK, "ok").await;
+    }
+    assert_eq!(builds.load(Ordering::SeqCst), 2);
+}
+
+fn mk_stack(
+    config: crate::Config,
+) -> (
+    svc::ArcNewCloneHttp<Target>,
+    Target,
+    Arc<AtomicUsize>,
+    Handle,
+) {
+    let (inner, handle) = tower_test::mock::pair();
+    let addr = SocketAddr::new([192, 0, 2, 41].into(), 1234);
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let builds = Arc::new(AtomicUsize::new(0));
+    let connect = CountBuilds {
+        connect: HttpConnect::default().service(addr, inner),
+        builds: builds.clone(),
+    };
+    let resolve = support::resolver().endpoint_exists(dest.clone(), addr, Default::default());
+    let (rt, shutdown) = runtime();
+    let stack = Outbound::new(config, rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let backend = default_backend(&dest);
+    let (tx, routes) = watch::channel(Routes::Policy(policy::Params::Http(policy::HttpParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend.clone()]),
+        routes: Arc::new([default_route(backend)]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    })));
+    tokio::spawn(async move {
+        tx.closed().await;
+        drop(shutdown);
+    });
+    let target = Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    };
+
+    (stack, target, builds, handle)
+}
+
+// === impl CountBuilds ===
+
+impl<T: svc::Param<Remote<ServerAddr>>> svc::NewService<T> for CountBuilds {
+    type Service = svc::BoxHttp;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        self.builds.fetch_add(1, Ordering::SeqCst);
+        self.connect.new_service(target)
+    }
+}
@@ -0,0 +1,176 @@
+use super::*;
+use linkerd_app_core::{
+    proxy::http::{self, StatusCode},
+    svc, trace, NameAddr,
+};
+use linkerd_proxy_client_policy as client_policy;
+use std::{net::SocketAddr, sync::Arc};
+use tokio::sync::watch;
+use tracing::info;
+
+const PORT: u16 = 666;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn routes_by_method() {
+    let _trace = trace::test::trace_init();
+
+    let writes_addr = SocketAddr::new([192, 0, 2, 41].into(), PORT);
+    let reads_addr = SocketAddr::new([192, 0, 2, 42].into(), PORT);
+    let writes_dest: NameAddr = format!("writes.test.svc.cluster.local:{PORT}")
+        .parse()
+        .unwrap();
+    let reads_dest: NameAddr = format!("reads.test.svc.cluster.local:{PORT}")
+        .parse()
+        .unwrap();
+    let (writes_svc, mut writes) = tower_test::mock::pair();
+    let (reads_svc, mut reads) = tower_test::mock::pair();
+    let connect = HttpConnect::default()
+        .service(writes_addr, writes_svc)
+        .service(reads_addr, reads_svc);
+    let resolve = support::resolver()
+        .endpoint_exists(writes_dest.clone(), writes_addr, Default::default())
+        .endpoint_exists(reads_dest.clone(), reads_addr, Default::default());
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let writes_backend = backend("writes", &writes_dest);
+    let reads_backend = backend("reads", &reads_dest);
+    let (_route_tx, routes) =
+        watch::channel(Routes::Policy(policy::Params::Http(policy::HttpParams {
+            addr: reads_dest.into(),
+            meta: ParentRef(client_policy::Meta::new_default("parent")),
+            backends: Arc::new([writes_backend.clone(), reads_backend.clone()]),
+            routes: Arc::new([method_route(
+                http::Method::POST,
+                writes_backend,
+                reads_backend,
+            )]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        })));
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    info!("Sending POST");
+    let rsp = send_req(svc.clone(), mk_req(http::Method::POST));
+    serve(&mut writes, mk_rsp(StatusCode::OK, "writes")).await;
+    assert_rsp(rsp, StatusCode::OK, "writes").await;
+
+    for method in [
+        http::Method::GET,
+        http::Method::PUT,
+        http::Method::from_bytes(b"PURGE").unwrap(),
+    ] {
+        info!("Sending {method}");
+        let rsp = send_req(svc.clone(), mk_req(method));
+        serve(&mut reads, mk_rsp(StatusCode::OK, "reads")).await;
+        assert_rsp(rsp, StatusCode::OK, "reads").await;
+    }
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn routes_by_extension_method() {
+    let _trace = trace::test::trace_init();
+
+    let purge_addr = SocketAddr::new([192, 0, 2, 41].into(), PORT);
+    let fallback_addr = SocketAddr::new([192, 0, 2, 42].into(), PORT);
+    let purge_dest: NameAddr = format!("purge.test.svc.cluster.local:{PORT}")
+        .parse()
+        .unwrap();
+    let fallback_dest: NameAddr = format!("fallback.test.svc.cluster.local:{PORT}")
+        .parse()
+        .unwrap();
+    let (purge_svc, mut purge) = tower_test::mock::pair();
+    let (fallback_svc, mut fallback) = tower_test::mock::pair();
+    let connect = HttpConnect::default()
+        .service(purge_addr, purge_svc)
+        .service(fallback_addr, fallback_svc);
+    let resolve = support::resolver()
+        .endpoint_exists(purge_dest.clone(), purge_addr, Default::default())
+        .endpoint_exists(fallback_dest.clone(), fallback_addr, Default::default());
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let purge_backend = backend("purge", &purge_dest);
+    let fallback_backend = backend("fallback", &fallback_dest);
+    let (_route_tx, routes) =
+        watch::channel(Routes::Policy(policy::Params::Http(policy::HttpParams {
+            addr: fallback_dest.into(),
+            meta: ParentRef(client_policy::Meta::new_default("parent")),
+            backends: Arc::new([purge_backend.clone(), fallback_backend.clone()]),
+            routes: Arc::new([method_route(
+                http::Method::from_bytes(b"PURGE").unwrap(),
+                purge_backend,
+                fallback_backend,
+            )]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        })));
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    info!("Sending PURGE");
+    let rsp = send_req(
+        svc.clone(),
+        mk_req(http::Method::from_bytes(b"PURGE").unwrap()),
+    );
+    serve(&mut purge, mk_rsp(StatusCode::OK, "purge")).await;
+    assert_rsp(rsp, StatusCode::OK, "purge").await;
+
+    // Extension methods are case-sensitive.
+    info!("Sending purge");
+    let rsp = send_req(
+        svc.clone(),
+        mk_req(http::Method::from_bytes(b"purge").unwrap()),
+    );
+    serve(&mut fallback, mk_rsp(StatusCode::OK, "fallback")).await;
+    assert_rsp(rsp, StatusCode::OK, "fallback").await;
+}
+
+fn backend(name: &'static str, dest: &NameAddr) -> client_policy::Backend {
+    client_policy::Backend {
+        meta: client_policy::Meta::new_default(name),
+        ..default_backend(dest)
+    }
+}
+
+fn mk_req(method: http::Method) -> http::Request<http::BoxBody> {
+    http::Request::builder()
+        .method(method)
+        .uri("/")
+        .body(Default::default())
+        .unwrap()
+}
+
+/// Builds a route that sends requests with the given method to one backend
+/// and all other requests to another.
+fn method_route(
+    method: http::Method,
+    matched: client_policy::Backend,
+    unmatched: client_policy::Backend,
+) -> client_policy::http::Route {
+    use client_policy::{http::r#match::MatchRequest, RouteBackend, RouteDistribution};
+
+    let mut route = mk_route(unmatched, Default::default());
+    let mut rule = route.rules[0].clone();
+    rule.matches = vec![MatchRequest {
+        method: Some(method),
+        ..Default::default()
+    }];
+    rule.policy.distribution = RouteDistribution::FirstAvailable(Arc::new([RouteBackend {
+        filters: [].into(),
+        backend: matched,
+    }]));
+    route.rules.insert(0, rule);
+    route
+}
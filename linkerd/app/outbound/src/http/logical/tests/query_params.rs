@@ -0,0 +1,63 @@
+use super::*;
+use linkerd_app_core::trace;
+use linkerd_proxy_client_policy::http::{
+    filter::DirectResponse,
+    r#match::{MatchQueryParam, MatchRequest},
+    Filter,
+};
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn routes_by_query_param() {
+    let _trace = trace::test::trace_init();
+
+    // The rules differ only by their query parameter matches. Canary requests
+    // are answered directly, and stable requests are forwarded.
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let mut route = mk_route(backend.clone(), Default::default());
+    let mut canary = route.rules[0].clone();
+    canary.matches = vec![match_variant("canary")];
+    canary.policy.filters = Arc::new([Filter::DirectResponse(
+        DirectResponse::new(StatusCode::OK, vec![], "canary").unwrap(),
+    )]);
+    route.rules[0].matches = vec![match_variant("stable")];
+    route.rules.push(canary);
+    let (svc, mut handle) = mock(policy::Params::Http(policy::HttpParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    }));
+    handle.allow(2);
+
+    let rsp = send_req(svc.clone(), get("/?variant=canary"));
+    assert_rsp(rsp, StatusCode::OK, "canary").await;
+
+    let rsp = send_req(svc.clone(), get("/?variant=stable"));
+    serve(&mut handle, mk_rsp(StatusCode::OK, "stable")).await;
+    assert_rsp(rsp, StatusCode::OK, "stable").await;
+
+    // Values are decoded before they are compared.
+    let rsp = send_req(svc.clone(), get("/?variant=st%61ble"));
+    serve(&mut handle, mk_rsp(StatusCode::OK, "stable")).await;
+    assert_rsp(rsp, StatusCode::OK, "stable").await;
+
+    // Only the first occurrence of a parameter is matched.
+    let rsp = send_req(svc, get("/?variant=canary&variant=stable"));
+    assert_rsp(rsp, StatusCode::OK, "canary").await;
+}
+
+fn match_variant(value: &str) -> MatchRequest {
+    MatchRequest {
+        query_params: vec![MatchQueryParam::Exact(
+            "variant".to_string(),
+            value.to_string(),
+        )],
+        ..Default::default()
+    }
+}
+
+fn get(uri: &str) -> http::Request<BoxBody> {
+    http::Request::get(uri).body(Default::default()).unwrap()
+}
@@ -3,7 +3,7 @@ use http_body_util::BodyExt;
 use linkerd_app_core::{
     errors,
     proxy::http::{self, StatusCode},
-    svc::http::stream_timeouts::StreamDeadlineError,
+    svc::{http::stream_timeouts::StreamDeadlineError, Layer},
     trace,
 };
 use linkerd_proxy_client_policy::{
@@ -110,6 +110,59 @@ async fn http_5xx_limited() {
     assert_eq!(rsp.expect("response").status(), StatusCode::GATEWAY_TIMEOUT);
 }
 
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_5xx_request_id() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let (svc, mut handle) = mock_http(HttpParams {
+        retry: Some(client_policy::http::Retry {
+            max_retries: 1,
+            status_ranges: Default::default(),
+            max_request_bytes: 1000,
+            timeout: None,
+            backoff: None,
+        }),
+        ..Default::default()
+    });
+    // Request IDs are set by the server stack, above the logical stack.
+    let svc = http::SetRequestId::layer(Some(http::request_id::Config {
+        header: http::HeaderName::from_static("x-request-id"),
+        overwrite: false,
+    }))
+    .layer(svc);
+
+    let attempts = tokio::spawn(
+        async move {
+            handle.allow(2);
+            let mut ids = Vec::new();
+            for status in [StatusCode::INTERNAL_SERVER_ERROR, StatusCode::NO_CONTENT] {
+                let (req, tx) = handle.next_request().await.expect("request");
+                ids.push(req.headers().get("x-request-id").cloned());
+                tx.send_response(
+                    http::Response::builder()
+                        .status(status)
+                        .body(Default::default())
+                        .unwrap(),
+                );
+            }
+            ids
+        }
+        .in_current_span(),
+    );
+
+    info!("Sending a request that will initially fail and then succeed");
+    let rsp = time::timeout(TIMEOUT, send_req(svc, http_get()))
+        .await
+        .expect("response");
+    assert_eq!(rsp.expect("response").status(), StatusCode::NO_CONTENT);
+
+    info!("Verifying that each attempt has the same request ID");
+    let ids = attempts.await.expect("attempts");
+    assert!(ids[0].is_some(), "request must have an ID");
+    assert_eq!(ids[0], ids[1], "retries must have the same request ID");
+}
+
 #[tokio::test(flavor = "current_thread", start_paused = true)]
 async fn http_timeout() {
     let _trace = trace::test::trace_init();
@@ -302,6 +355,125 @@ async fn http_timeout_with_request_timeout() {
     assert!(errors::is_caused_by::<StreamDeadlineError>(&*error));
 }
 
+/// Tests that a request's retries are dispatched through the route it matched
+/// initially, even if the routes are updated before it is retried.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_retries_pin_route() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let (svc, mut handle, route_tx) = mock_with_routes(default_config(), retry_with_header("v1"));
+    handle.allow(3);
+
+    info!("Sending a request that will be retried");
+    let rsp = send_req(svc.clone(), http_get());
+    let (req, tx) = handle.next_request().await.expect("first attempt");
+    assert_eq!(req.headers()["x-route"], "v1");
+
+    info!("Updating the route's filters before the request is retried");
+    route_tx
+        .send(retry_with_header("v2"))
+        .expect("routes must be watched");
+    time::sleep(time::Duration::from_millis(1)).await;
+    tx.send_response(
+        http::Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(BoxBody::empty())
+            .unwrap(),
+    );
+
+    info!("Verifying that the retry applies the original route's filters");
+    let (req, tx) = handle.next_request().await.expect("second attempt");
+    assert_eq!(req.headers()["x-route"], "v1");
+    tx.send_response(
+        http::Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(BoxBody::empty())
+            .unwrap(),
+    );
+    let rsp = time::timeout(TIMEOUT, rsp)
+        .await
+        .expect("response timed out")
+        .expect("response ok");
+    assert_eq!(rsp.status(), StatusCode::NO_CONTENT);
+
+    info!("Verifying that new requests match the updated route");
+    let rsp = send_req(svc.clone(), http_get());
+    let (req, tx) = handle.next_request().await.expect("new request");
+    assert_eq!(req.headers()["x-route"], "v2");
+    tx.send_response(
+        http::Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(BoxBody::empty())
+            .unwrap(),
+    );
+    let rsp = time::timeout(TIMEOUT, rsp)
+        .await
+        .expect("response timed out")
+        .expect("response ok");
+    assert_eq!(rsp.status(), StatusCode::NO_CONTENT);
+}
+
+/// Tests that a retry is re-matched when all of the backends of the route it
+/// matched initially become unavailable after the routes are updated.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn http_retries_rematch_unavailable_route() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let dest_a = "a.example.com:1234".parse::<NameAddr>().unwrap();
+    let dest_b = "b.example.com:1234".parse::<NameAddr>().unwrap();
+    let addr_a = SocketAddr::new([192, 0, 2, 41].into(), 1234);
+    let addr_b = SocketAddr::new([192, 0, 2, 42].into(), 1234);
+    let (svc_a, mut handle_a) = tower_test::mock::pair();
+    let (svc_b, mut handle_b) = tower_test::mock::pair();
+    let connect = HttpConnect::default()
+        .service(addr_a, svc_a)
+        .service(addr_b, svc_b);
+    let resolve = support::resolver()
+        .endpoint_exists(dest_a.clone(), addr_a, Default::default())
+        .endpoint_exists(dest_b.clone(), addr_b, Default::default());
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let (route_tx, routes) = watch::channel(retry_to(&dest_a));
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    info!("Sending a request to a backend that never becomes ready");
+    handle_a.allow(0);
+    handle_b.allow(1);
+    let rsp = send_req(svc.clone(), http_get());
+
+    info!("Updating the routes before the backend enters failfast");
+    route_tx
+        .send(retry_to(&dest_b))
+        .expect("routes must be watched");
+
+    info!("Verifying that the retry is re-matched to the updated route");
+    let (_req, tx) = time::timeout(time::Duration::from_secs(60), handle_b.next_request())
+        .await
+        .expect("retry must be re-matched")
+        .expect("retry");
+    tx.send_response(
+        http::Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(BoxBody::empty())
+            .unwrap(),
+    );
+    let rsp = time::timeout(TIMEOUT, rsp)
+        .await
+        .expect("response timed out")
+        .expect("response ok");
+    assert_eq!(rsp.status(), StatusCode::NO_CONTENT);
+}
+
 #[tokio::test(flavor = "current_thread", start_paused = true)]
 async fn grpc_internal() {
     let _trace = trace::test::with_default_filter("linkerd=debug");
@@ -450,3 +622,67 @@ async fn grpc_timeout() {
         "0"
     );
 }
+
+// === Utils ===
+
+/// Returns a policy with a single route that retries 5XX responses and tags
+/// each request with an `x-route` header.
+fn retry_with_header(value: &'static str) -> Routes {
+    use client_policy::http::{filter::ModifyHeader, Filter};
+
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let mut route = mk_route(
+        backend.clone(),
+        HttpParams {
+            retry: Some(client_policy::http::Retry {
+                max_retries: 1,
+                status_ranges: Default::default(),
+                max_request_bytes: 1000,
+                timeout: None,
+                backoff: None,
+            }),
+            ..Default::default()
+        },
+    );
+    route.rules[0].policy.filters = Arc::new([Filter::RequestHeaders(ModifyHeader {
+        set: vec![(
+            ::http::HeaderName::from_static("x-route"),
+            ::http::HeaderValue::from_static(value),
+        )],
+        ..Default::default()
+    })]);
+    Routes::Policy(policy::Params::Http(policy::HttpParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    }))
+}
+
+/// Returns a policy with a single route that retries 5XX responses and
+/// failures to the given backend.
+fn retry_to(dest: &NameAddr) -> Routes {
+    let backend = default_backend(dest);
+    let route = mk_route(
+        backend.clone(),
+        HttpParams {
+            retry: Some(client_policy::http::Retry {
+                max_retries: 1,
+                status_ranges: Default::default(),
+                max_request_bytes: 1000,
+                timeout: None,
+                backoff: None,
+            }),
+            ..Default::default()
+        },
+    );
+    Routes::Policy(policy::Params::Http(policy::HttpParams {
+        addr: dest.clone().into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    }))
+}
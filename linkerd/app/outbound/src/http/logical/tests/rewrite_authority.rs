@@ -0,0 +1,76 @@
+use super::*;
+use linkerd_app_core::trace;
+use linkerd_http_route::http::filter::RewriteAuthority;
+
+const CANONICAL: &str = "web.ns.svc.cluster.local:8080";
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn rewrites_authority() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_rewrite(true);
+    handle.allow(1);
+    let rsp = send_req(svc, vanity_get());
+
+    let (req, tx) = handle.next_request().await.expect("request");
+    assert_eq!(req.headers()[::http::header::HOST], CANONICAL);
+    tx.send_response(redirect(&format!("http://{CANONICAL}/login")));
+
+    let rsp = rsp.await.expect("response");
+    assert_eq!(rsp.status(), StatusCode::FOUND);
+    assert_eq!(
+        rsp.headers()[::http::header::LOCATION],
+        "http://api.internal.example.com/login"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn preserves_location_unless_configured() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_rewrite(false);
+    handle.allow(1);
+    let rsp = send_req(svc, vanity_get());
+
+    let (req, tx) = handle.next_request().await.expect("request");
+    assert_eq!(req.headers()[::http::header::HOST], CANONICAL);
+    let location = format!("http://{CANONICAL}/login");
+    tx.send_response(redirect(&location));
+
+    let rsp = rsp.await.expect("response");
+    assert_eq!(rsp.headers()[::http::header::LOCATION], location.as_str());
+}
+
+fn mock_rewrite(rewrite_location: bool) -> (svc::BoxCloneHttp, Handle) {
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let mut route = mk_route(backend.clone(), Default::default());
+    route.rules[0].policy.filters = Arc::new([client_policy::http::Filter::RewriteAuthority(
+        RewriteAuthority {
+            authority: CANONICAL.parse().unwrap(),
+            rewrite_location,
+        },
+    )]);
+    mock(policy::Params::Http(policy::HttpParams {
+        addr: dest.into(),
+        meta: ParentRef(client_policy::Meta::new_default("parent")),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        failure_accrual: client_policy::FailureAccrual::None,
+    }))
+}
+
+fn vanity_get() -> Request {
+    http::Request::get("http://api.internal.example.com/")
+        .header(::http::header::HOST, "api.internal.example.com")
+        .body(Default::default())
+        .unwrap()
+}
+
+fn redirect(location: &str) -> Response {
+    http::Response::builder()
+        .status(StatusCode::FOUND)
+        .header(::http::header::LOCATION, location)
+        .body(BoxBody::empty())
+        .unwrap()
+}
@@ -0,0 +1,142 @@
+use super::*;
+use linkerd_app_core::{
+    proxy::http::{self, StatusCode},
+    svc, trace, NameAddr,
+};
+use linkerd_proxy_client_policy as client_policy;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{sync::watch, task, time};
+use tracing::info;
+
+const PORT: u16 = 666;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn canary_removed_on_regression() {
+    let _trace = trace::test::trace_init();
+
+    let primary_addr = SocketAddr::new([192, 0, 2, 41].into(), PORT);
+    let canary_addr = SocketAddr::new([192, 0, 2, 42].into(), PORT);
+    let primary_dest: NameAddr = format!("primary.test.svc.cluster.local:{PORT}")
+        .parse()
+        .unwrap();
+    let canary_dest: NameAddr = format!("canary.test.svc.cluster.local:{PORT}")
+        .parse()
+        .unwrap();
+    let (primary_svc, mut primary) = tower_test::mock::pair();
+    let (canary_svc, mut canary) = tower_test::mock::pair();
+    let connect = HttpConnect::default()
+        .service(primary_addr, primary_svc)
+        .service(canary_addr, canary_svc);
+    let resolve = support::resolver()
+        .endpoint_exists(primary_dest.clone(), primary_addr, Default::default())
+        .endpoint_exists(canary_dest.clone(), canary_addr, Default::default());
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(resolve)
+        .into_inner();
+
+    let guard = client_policy::http::RolloutGuard {
+        window: Duration::from_secs(10),
+        min_requests: 3,
+        max_failure_rate_increase: 10,
+        cooldown: Duration::from_secs(30),
+    };
+    let primary_backend = backend("primary", &primary_dest);
+    let canary_backend = backend("canary", &canary_dest);
+    let (_route_tx, routes) =
+        watch::channel(Routes::Policy(policy::Params::Http(policy::HttpParams {
+            addr: primary_dest.into(),
+            meta: ParentRef(client_policy::Meta::new_default("parent")),
+            backends: Arc::new([primary_backend.clone(), canary_backend.clone()]),
+            routes: Arc::new([guarded_route(
+                primary_backend,
+                canary_backend,
+                guard.clone(),
+            )]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        })));
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+
+    // The primary has no weight, so all requests are sent to the canary until
+    // the guard trips.
+    for i in 1..=guard.min_requests {
+        info!("Sending canary request {i}");
+        let rsp = send_req(svc.clone(), http_get());
+        serve(
+            &mut canary,
+            mk_rsp(StatusCode::INTERNAL_SERVER_ERROR, "canary"),
+        )
+        .await;
+        assert_rsp(rsp, StatusCode::INTERNAL_SERVER_ERROR, "canary").await;
+    }
+    task::yield_now().await;
+
+    info!("Sending request while the guard is tripped");
+    let rsp = send_req(svc.clone(), http_get());
+    time::timeout(
+        Duration::from_secs(10),
+        serve(&mut primary, mk_rsp(StatusCode::OK, "primary")),
+    )
+    .await
+    .expect("request must be sent to the primary");
+    assert_rsp(rsp, StatusCode::OK, "primary").await;
+
+    info!("Waiting for the cooldown to elapse");
+    time::sleep(guard.cooldown).await;
+    task::yield_now().await;
+
+    info!("Sending request after the cooldown");
+    let rsp = send_req(svc.clone(), http_get());
+    time::timeout(
+        Duration::from_secs(10),
+        serve(&mut canary, mk_rsp(StatusCode::OK, "canary")),
+    )
+    .await
+    .expect("request must be sent to the canary");
+    assert_rsp(rsp, StatusCode::OK, "canary").await;
+}
+
+fn backend(name: &'static str, dest: &NameAddr) -> client_policy::Backend {
+    client_policy::Backend {
+        meta: client_policy::Meta::new_default(name),
+        ..default_backend(dest)
+    }
+}
+
+fn guarded_route(
+    primary: client_policy::Backend,
+    canary: client_policy::Backend,
+    guard: client_policy::http::RolloutGuard,
+) -> client_policy::http::Route {
+    use client_policy::{RouteBackend, RouteDistribution};
+
+    let mut route = mk_route(
+        primary.clone(),
+        client_policy::http::RouteParams {
+            rollout_guard: Some(guard),
+            ..Default::default()
+        },
+    );
+    route.rules[0].policy.distribution = RouteDistribution::RandomAvailable(Arc::new([
+        (
+            RouteBackend {
+                filters: [].into(),
+                backend: primary,
+            },
+            0,
+        ),
+        (
+            RouteBackend {
+                filters: [].into(),
+                backend: canary,
+            },
+            1,
+        ),
+    ]));
+    route
+}
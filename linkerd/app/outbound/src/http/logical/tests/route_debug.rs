@@ -0,0 +1,98 @@
+use super::*;
+use linkerd_app_core::{proxy::http::StatusCode, trace};
+use linkerd_proxy_client_policy::http::{Retry, RouteParams as HttpParams};
+
+const HEADER: &str = "l5d-route-debug";
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn disables_retries() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_retry(true);
+    handle.allow(2);
+    let rsp = send_req(svc, debug_req("no-retry"));
+
+    let (req, tx) = handle.next_request().await.expect("request");
+    assert!(
+        req.headers().get(HEADER).is_none(),
+        "header must be stripped"
+    );
+    tx.send_response(
+        http::Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Default::default())
+            .unwrap(),
+    );
+
+    let rsp = rsp.await.expect("response");
+    assert_eq!(rsp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn ignored_unless_enabled() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_retry(false);
+    handle.allow(2);
+    let rsp = send_req(svc, debug_req("no-retry"));
+
+    let (req, tx) = handle.next_request().await.expect("request");
+    assert!(
+        req.headers().get(HEADER).is_none(),
+        "header must be stripped"
+    );
+    tx.send_response(
+        http::Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Default::default())
+            .unwrap(),
+    );
+
+    // The request is retried.
+    let (_, tx) = handle.next_request().await.expect("retry");
+    tx.send_response(http::Response::default());
+
+    let rsp = rsp.await.expect("response");
+    assert_eq!(rsp.status(), StatusCode::OK);
+}
+
+// === Utils ===
+
+fn debug_req(directives: &'static str) -> Request {
+    http::Request::get("/")
+        .header(HEADER, directives)
+        .body(BoxBody::empty())
+        .unwrap()
+}
+
+fn mock_retry(enabled: bool) -> (svc::BoxCloneHttp, Handle) {
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let route = mk_route(
+        backend.clone(),
+        HttpParams {
+            retry: Some(Retry {
+                max_retries: 1,
+                status_ranges: Default::default(),
+                max_request_bytes: 1000,
+                timeout: None,
+                backoff: None,
+            }),
+            ..Default::default()
+        },
+    );
+    let config = crate::Config {
+        http_route_debug_header: enabled,
+        ..default_config()
+    };
+    mock_with_config(
+        config,
+        policy::Params::Http(policy::HttpParams {
+            addr: dest.into(),
+            meta: ParentRef(client_policy::Meta::new_default("parent")),
+            backends: Arc::new([backend]),
+            routes: Arc::new([route]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        }),
+    )
+}
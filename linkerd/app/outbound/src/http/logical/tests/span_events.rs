@@ -0,0 +1,215 @@
+use super::*;
+use linkerd_app_core::{
+    exp_backoff::ExponentialBackoff,
+    http_tracing::{self, ExportSpan, SpanEvent},
+    svc::Layer,
+    trace,
+};
+use linkerd_proxy_client_policy::{
+    self as client_policy,
+    http::{RouteParams as HttpParams, Timeouts},
+};
+use tokio::{sync::mpsc, task, time};
+
+const SAMPLED: &str = "00-94d7f6ec6b95f3e916179cb6cfd01390-55ccfce77f972614-01";
+const UNSAMPLED: &str = "00-94d7f6ec6b95f3e916179cb6cfd01390-55ccfce77f972614-00";
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn records_retries_and_endpoints() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let (svc, mut handle) = mock_http(HttpParams {
+        timeouts: Timeouts {
+            request: Some(TIMEOUT),
+            ..Default::default()
+        },
+        retry: Some(client_policy::http::Retry {
+            max_retries: 1,
+            status_ranges: Default::default(),
+            max_request_bytes: 1000,
+            timeout: None,
+            backoff: None,
+        }),
+        ..Default::default()
+    });
+    let (svc, mut spans) = traced(svc);
+
+    handle.allow(2);
+    let rsp = send_req(svc.clone(), traced_get(SAMPLED));
+    serve(&mut handle, mk_rsp(StatusCode::INTERNAL_SERVER_ERROR, "")).await;
+    serve(&mut handle, mk_rsp(StatusCode::NO_CONTENT, "")).await;
+    assert_rsp(rsp, StatusCode::NO_CONTENT, "").await;
+
+    let ExportSpan { span, .. } = spans.try_recv().expect("span must be exported");
+    assert_eq!(
+        span.labels.get("proxy.endpoint.address").map(|v| &**v),
+        Some("192.0.2.41:1234"),
+    );
+    assert!(
+        span.labels.contains_key("proxy.queue.wait_ms"),
+        "{:?}",
+        span.labels
+    );
+    let [SpanEvent {
+        name, attributes, ..
+    }] = &span.events[..]
+    else {
+        panic!("expected a single retry event: {:?}", span.events);
+    };
+    assert_eq!(*name, "retry");
+    assert_eq!(
+        attributes,
+        &[
+            ("retry.attempt", "1".to_string()),
+            ("retry.backoff_ms", "0".to_string()),
+            ("retry.reason", "status 500".to_string()),
+        ]
+    );
+
+    // Unsampled requests are not annotated.
+    handle.allow(2);
+    let rsp = send_req(svc.clone(), traced_get(UNSAMPLED));
+    serve(&mut handle, mk_rsp(StatusCode::INTERNAL_SERVER_ERROR, "")).await;
+    serve(&mut handle, mk_rsp(StatusCode::NO_CONTENT, "")).await;
+    assert_rsp(rsp, StatusCode::NO_CONTENT, "").await;
+    assert!(
+        spans.try_recv().is_err(),
+        "unsampled spans are not exported"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn records_breaker_probes() {
+    let _trace = trace::test::trace_init();
+
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let cfg = default_config();
+    let min_backoff = cfg.http_request_queue.failfast_timeout + Duration::from_secs(1);
+    // No jitter, so that the probation period is deterministic.
+    let backoff = ExponentialBackoff::try_new(min_backoff, min_backoff * 6, 0.0).unwrap();
+    let (svc, mut handle) = mock_with_config(
+        cfg,
+        policy::Params::Http(policy::HttpParams {
+            addr: dest.into(),
+            meta: ParentRef(client_policy::Meta::new_default("parent")),
+            backends: Arc::new([backend.clone()]),
+            routes: Arc::new([default_route(backend)]),
+            failure_accrual: client_policy::FailureAccrual::ConsecutiveFailures {
+                max_failures: 1,
+                backoff,
+            },
+        }),
+    );
+    let (svc, mut spans) = traced(svc);
+
+    // A single failure shuts the breaker.
+    handle.allow(1);
+    let rsp = send_req(svc.clone(), traced_get(SAMPLED));
+    serve(
+        &mut handle,
+        mk_rsp(StatusCode::INTERNAL_SERVER_ERROR, "bad"),
+    )
+    .await;
+    assert_rsp(rsp, StatusCode::INTERNAL_SERVER_ERROR, "bad").await;
+    let ExportSpan { span, .. } = spans.try_recv().expect("span must be exported");
+    assert!(span.events.is_empty(), "{:?}", span.events);
+
+    // After the probation period, the next request probes the endpoint.
+    let mut backoffs = backoff.stream();
+    backoffs.next().await;
+    task::yield_now().await;
+
+    handle.allow(1);
+    let rsp = send_req(svc.clone(), traced_get(SAMPLED));
+    serve(&mut handle, mk_rsp(StatusCode::OK, "good")).await;
+    assert_rsp(rsp, StatusCode::OK, "good").await;
+    let ExportSpan { span, .. } = spans.try_recv().expect("span must be exported");
+    let [SpanEvent {
+        name, attributes, ..
+    }] = &span.events[..]
+    else {
+        panic!("expected a single probe event: {:?}", span.events);
+    };
+    assert_eq!(*name, "breaker.probe");
+    assert_eq!(
+        attributes,
+        &[("proxy.endpoint.address", "192.0.2.41:1234".to_string())]
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn records_slow_request_timings() {
+    let _trace = trace::test::trace_init();
+
+    const THRESHOLD: time::Duration = time::Duration::from_secs(1);
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let mut cfg = default_config();
+    cfg.http_request_timings_threshold = Some(THRESHOLD);
+    let (svc, mut handle) = mock_with_config(
+        cfg,
+        policy::Params::Http(policy::HttpParams {
+            addr: dest.into(),
+            meta: ParentRef(client_policy::Meta::new_default("parent")),
+            backends: Arc::new([backend.clone()]),
+            routes: Arc::new([default_route(backend)]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        }),
+    );
+    let (svc, mut spans) = traced(svc);
+
+    // Requests that are served within the threshold are not annotated.
+    handle.allow(1);
+    let rsp = send_req(svc.clone(), traced_get(SAMPLED));
+    serve(&mut handle, mk_rsp(StatusCode::NO_CONTENT, "")).await;
+    assert_rsp(rsp, StatusCode::NO_CONTENT, "").await;
+    let ExportSpan { span, .. } = spans.try_recv().expect("span must be exported");
+    assert!(
+        !span.labels.keys().any(|k| k.starts_with("proxy.timings.")),
+        "{:?}",
+        span.labels
+    );
+
+    handle.allow(1);
+    let rsp = send_req(svc.clone(), traced_get(SAMPLED));
+    time::sleep(THRESHOLD * 2).await;
+    serve(&mut handle, mk_rsp(StatusCode::NO_CONTENT, "")).await;
+    assert_rsp(rsp, StatusCode::NO_CONTENT, "").await;
+    let ExportSpan { span, .. } = spans.try_recv().expect("span must be exported");
+    for key in [
+        "proxy.timings.route_ms",
+        "proxy.timings.queue_ms",
+        "proxy.timings.balance_ms",
+        "proxy.timings.first_byte_ms",
+    ] {
+        assert!(span.labels.contains_key(key), "{key}: {:?}", span.labels);
+    }
+    assert_eq!(
+        span.labels.get("proxy.timings.first_byte_ms").map(|v| &**v),
+        Some("2000.000"),
+    );
+    // The mocked endpoint does not establish connections.
+    assert!(
+        !span.labels.contains_key("proxy.timings.connect_ms"),
+        "{:?}",
+        span.labels
+    );
+}
+
+// === Utils ===
+
+/// Wraps a service so that requests with a trace context produce spans.
+fn traced(svc: svc::BoxCloneHttp) -> (svc::BoxCloneHttp, mpsc::Receiver<ExportSpan>) {
+    let (tx, rx) = mpsc::channel(10);
+    let svc = http_tracing::server(Some(tx), Arc::new(HashMap::new())).layer(svc);
+    (svc::BoxCloneHttp::new(svc), rx)
+}
+
+fn traced_get(traceparent: &'static str) -> http::Request<BoxBody> {
+    http::Request::get("/")
+        .header("traceparent", traceparent)
+        .body(Default::default())
+        .unwrap()
+}
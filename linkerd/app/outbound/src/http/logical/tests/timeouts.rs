@@ -7,6 +7,7 @@ use linkerd_app_core::{
         stream_timeouts::{BodyTimeoutError, ResponseTimeoutError},
         BoxBody,
     },
+    svc::Layer,
     trace,
 };
 use linkerd_proxy_client_policy::{self as client_policy, http::Timeouts};
@@ -300,3 +301,321 @@ async fn idle_timeout_response_body() {
         "expected idle timeout, got {error:?}"
     );
 }
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn idle_timeout_streaming_response_body() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let (svc, mut handle) = mock_http(client_policy::http::RouteParams {
+        timeouts: Timeouts {
+            idle: Some(TIMEOUT),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    info!("Sending a request that is served with a slowly-streamed body");
+    handle.allow(1);
+    let call = send_req(svc.clone(), http_get());
+    serve(&mut handle, async move {
+        info!("Serving a response that emits a chunk every second");
+        let chunks = futures::stream::unfold(0, |n| async move {
+            if n == 5 {
+                return None;
+            }
+            time::sleep(TIMEOUT / 2).await;
+            let chunk = bytes::Bytes::from(format!("data: {n}\n\n"));
+            Some((Ok::<_, Error>(http_body::Frame::data(chunk)), n + 1))
+        });
+        Ok(http::Response::builder()
+            .status(200)
+            .header("content-type", "text/event-stream")
+            .body(BoxBody::new(http_body_util::StreamBody::new(chunks)))
+            .unwrap())
+    })
+    .await;
+
+    info!("Verifying that each chunk resets the idle timeout");
+    let body = call
+        .await
+        .expect("response")
+        .into_body()
+        .collect()
+        .await
+        .expect("body must not time out")
+        .to_bytes();
+    assert_eq!(body.iter().filter(|&&b| b == b'\n').count(), 10);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn response_headers_timeout() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let (svc, mut handle) = mock_http(client_policy::http::RouteParams {
+        timeouts: Timeouts {
+            response_headers: Some(TIMEOUT),
+            response_chunk_idle: Some(TIMEOUT * 10),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    info!("Sending a request whose response headers are delayed");
+    handle.allow(1);
+    let call = send_req(svc.clone(), http_get());
+    serve(&mut handle, async move {
+        time::sleep(TIMEOUT * 2).await;
+        Ok(http::Response::builder()
+            .status(204)
+            .body(http::BoxBody::default())
+            .unwrap())
+    })
+    .await;
+
+    info!("Verifying that the response fails with the expected error");
+    let error = time::timeout(TIMEOUT * 4, call)
+        .await
+        .expect("should timeout internally")
+        .expect_err("should timeout internally");
+    assert!(
+        matches!(
+            errors::cause_ref(error.as_ref()),
+            Some(ResponseTimeoutError::FirstByte(_)),
+        ),
+        "expected response first byte timeout, got {error:?}"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn response_chunk_idle_timeout() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let (svc, mut handle) = mock_http(client_policy::http::RouteParams {
+        timeouts: Timeouts {
+            response_headers: Some(TIMEOUT),
+            response_chunk_idle: Some(TIMEOUT * 2),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    info!("Sending a request that is served with a stream that stalls");
+    handle.allow(1);
+    let call = send_req(svc.clone(), http_get());
+    serve(&mut handle, async move {
+        info!("Serving a response that emits three chunks and then stalls");
+        let chunks = futures::stream::unfold(0, |n| async move {
+            if n == 3 {
+                future::pending::<()>().await;
+            }
+            time::sleep(TIMEOUT).await;
+            let chunk = bytes::Bytes::from(format!("data: {n}\n\n"));
+            Some((Ok::<_, Error>(http_body::Frame::data(chunk)), n + 1))
+        });
+        Ok(http::Response::builder()
+            .status(200)
+            .header("content-type", "text/event-stream")
+            .body(BoxBody::new(http_body_util::StreamBody::new(chunks)))
+            .unwrap())
+    })
+    .await;
+
+    info!("Verifying that the stream outlives the response headers timeout");
+    let mut rsp = call.await.expect("response").into_body();
+    for _ in 0..3 {
+        rsp.frame()
+            .await
+            .expect("stream must not end")
+            .expect("chunk must not time out");
+    }
+
+    info!("Verifying that the stalled stream times out with the expected error");
+    let error = time::timeout(TIMEOUT * 4, rsp.frame())
+        .await
+        .expect("should timeout internally")
+        .expect("should timeout internally")
+        .err()
+        .expect("should timeout internally");
+    assert!(
+        matches!(
+            errors::cause_ref(error.as_ref()),
+            Some(BodyTimeoutError::ChunkIdle(_)),
+        ),
+        "expected response chunk idle timeout, got {error:?}"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn request_deadline_bounds_request_timeout() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let (svc, mut handle) = mock_http(client_policy::http::RouteParams {
+        timeouts: Timeouts {
+            request: Some(TIMEOUT * 5),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    // Deadlines are read by the server stack, above the logical stack.
+    let svc = http::PropagateDeadline::layer(Some(deadline_config())).layer(svc);
+
+    info!("Sending a request with a deadline shorter than the route's timeout");
+    handle.allow(1);
+    let call = send_req(
+        svc,
+        http::Request::get("/")
+            .header("grpc-timeout", "2S")
+            .body(Default::default())
+            .unwrap(),
+    );
+    let (req, _tx) = handle.next_request().await.expect("request");
+    assert_eq!(
+        req.headers().get("grpc-timeout").unwrap(),
+        "2000m",
+        "the deadline must be propagated"
+    );
+
+    info!("Verifying that the request times out at its deadline");
+    let error = time::timeout(TIMEOUT * 2, call)
+        .await
+        .expect("request must fail with a timeout")
+        .expect_err("request must fail with a timeout");
+    assert!(
+        matches!(
+            errors::cause_ref(error.as_ref()),
+            Some(ResponseTimeoutError::Lifetime(_)),
+        ),
+        "expected response timeout, got {error}"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn request_timeout_bounds_request_deadline() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let (svc, mut handle) = mock_http(client_policy::http::RouteParams {
+        timeouts: Timeouts {
+            request: Some(TIMEOUT),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let svc = http::PropagateDeadline::layer(Some(deadline_config())).layer(svc);
+
+    info!("Sending a request with a deadline longer than the route's timeout");
+    handle.allow(1);
+    let _call = send_req(
+        svc,
+        http::Request::get("/")
+            .header("grpc-timeout", "10S")
+            .body(Default::default())
+            .unwrap(),
+    );
+    let (req, _tx) = handle.next_request().await.expect("request");
+    assert_eq!(
+        req.headers().get("grpc-timeout").unwrap(),
+        "2000m",
+        "the route's timeout must be propagated"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn request_deadline_exceeded() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_http(Default::default());
+    let svc = http::PropagateDeadline::layer(Some(http::stream_timeouts::DeadlineConfig {
+        min_budget: time::Duration::from_millis(100),
+        ..deadline_config()
+    }))
+    .layer(svc);
+
+    info!("Sending a request with less than the minimum remaining budget");
+    handle.allow(1);
+    let error = send_req(
+        svc,
+        http::Request::get("/")
+            .header("grpc-timeout", "50m")
+            .body(Default::default())
+            .unwrap(),
+    )
+    .await
+    .expect_err("request must fail");
+    assert!(
+        errors::is_caused_by::<http::stream_timeouts::DeadlineExceededError>(error.as_ref()),
+        "expected deadline exceeded, got {error}"
+    );
+    assert!(
+        handle.next_request().await.is_none(),
+        "the request must not be dispatched"
+    );
+}
+
+/// Tests that deadlines bound requests that are forwarded to an endpoint
+/// without policy routes, and not only those with route timeouts.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn request_deadline_bounds_forwarded_request() {
+    let _trace = trace::test::trace_init();
+
+    const TIMEOUT: time::Duration = time::Duration::from_secs(2);
+    let addr = SocketAddr::new([192, 0, 2, 41].into(), 1234);
+    let (inner, mut handle) = tower_test::mock::pair();
+    let connect = HttpConnect::default().service(addr, inner);
+    let (rt, _shutdown) = runtime();
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(svc::ArcNewService::new(connect))
+        .push_http_cached(support::resolver())
+        .into_inner();
+    let (_tx, routes) = watch::channel(Routes::Endpoint(
+        Remote(ServerAddr(addr)),
+        Default::default(),
+    ));
+    let svc = stack.new_service(Target {
+        num: 1,
+        version: http::Variant::H2,
+        routes,
+    });
+    let svc = http::PropagateDeadline::layer(Some(deadline_config())).layer(svc);
+
+    info!("Sending a request with a deadline");
+    handle.allow(1);
+    let call = send_req(
+        svc,
+        http::Request::get("/")
+            .header("grpc-timeout", "2S")
+            .body(Default::default())
+            .unwrap(),
+    );
+    let (req, _tx) = handle.next_request().await.expect("request");
+    assert_eq!(
+        req.headers().get("grpc-timeout").unwrap(),
+        "2000m",
+        "the deadline must be propagated"
+    );
+
+    info!("Verifying that the request times out at its deadline");
+    let error = time::timeout(TIMEOUT * 2, call)
+        .await
+        .expect("request must fail with a timeout")
+        .expect_err("request must fail with a timeout");
+    assert!(
+        matches!(
+            errors::cause_ref(error.as_ref()),
+            Some(ResponseTimeoutError::Lifetime(_)),
+        ),
+        "expected response timeout, got {error}"
+    );
+}
+
+fn deadline_config() -> http::stream_timeouts::DeadlineConfig {
+    http::stream_timeouts::DeadlineConfig {
+        deadline_header: http::HeaderName::from_static("x-deadline"),
+        timeout_header: http::HeaderName::from_static("grpc-timeout"),
+        min_budget: time::Duration::ZERO,
+    }
+}
@@ -0,0 +1,116 @@
+use super::*;
+use linkerd_app_core::{proxy::http::workload_identity, trace};
+use linkerd_http_route::http::filter::AssertWorkloadIdentity;
+use linkerd_proxy_client_policy::http::{Filter, RouteParams as HttpParams};
+use std::time::{Duration, SystemTime};
+
+const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn asserts_workload_identity() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_assert(true, Some(signer()));
+    handle.allow(1);
+    let rsp = send_req(svc, forged_req());
+
+    let (req, tx) = handle.next_request().await.expect("request");
+    let assertion = req
+        .headers()
+        .get(&workload_identity::HEADER)
+        .expect("request must carry an assertion");
+    let id = verifier()
+        .verify(assertion, SystemTime::now())
+        .expect("assertion must be verifiable");
+    assert_eq!(id, *signer().id());
+    tx.send_response(http::Response::default());
+
+    rsp.await.expect("response");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn strips_application_assertions() {
+    let _trace = trace::test::trace_init();
+
+    // Without a signing key, asserting routes must not forward the
+    // application's header.
+    let (svc, mut handle) = mock_assert(true, None);
+    handle.allow(1);
+    let rsp = send_req(svc, forged_req());
+
+    let (req, tx) = handle.next_request().await.expect("request");
+    assert!(req.headers().get(&workload_identity::HEADER).is_none());
+    tx.send_response(http::Response::default());
+    rsp.await.expect("response");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn only_asserts_on_filtered_routes() {
+    let _trace = trace::test::trace_init();
+
+    let (svc, mut handle) = mock_assert(false, Some(signer()));
+    handle.allow(1);
+    let rsp = send_req(svc, forged_req());
+
+    let (req, tx) = handle.next_request().await.expect("request");
+    assert_eq!(
+        req.headers()[&workload_identity::HEADER],
+        "forged;0;00",
+        "routes without the filter must not modify the header"
+    );
+    tx.send_response(http::Response::default());
+    rsp.await.expect("response");
+}
+
+// === Utils ===
+
+fn signer() -> workload_identity::Signer {
+    workload_identity::Signer::new(
+        "client.ns.serviceaccount.identity.linkerd.cluster.local"
+            .parse()
+            .unwrap(),
+        workload_identity::Key::from_hex(KEY).unwrap(),
+    )
+    .unwrap()
+}
+
+fn verifier() -> workload_identity::Verifier {
+    workload_identity::Verifier::new(
+        Some(workload_identity::Key::from_hex(KEY).unwrap()),
+        Duration::from_secs(60),
+    )
+}
+
+fn forged_req() -> Request {
+    http::Request::get("/")
+        .header(&workload_identity::HEADER, "forged;0;00")
+        .body(BoxBody::empty())
+        .unwrap()
+}
+
+fn mock_assert(
+    assert: bool,
+    signer: Option<workload_identity::Signer>,
+) -> (svc::BoxCloneHttp, Handle) {
+    let dest = "example.com:1234".parse::<NameAddr>().unwrap();
+    let backend = default_backend(&dest);
+    let mut route = mk_route(backend.clone(), HttpParams::default());
+    if assert {
+        route.rules[0].policy.filters =
+            Arc::new([Filter::AssertWorkloadIdentity(AssertWorkloadIdentity)]);
+    }
+    let config = crate::Config {
+        http_workload_identity: signer,
+        ..default_config()
+    };
+    mock_with_config(
+        config,
+        policy::Params::Http(policy::HttpParams {
+            addr: dest.into(),
+            meta: ParentRef(client_policy::Meta::new_default("parent")),
+            backends: Arc::new([backend]),
+            routes: Arc::new([route]),
+            failure_accrual: client_policy::FailureAccrual::None,
+        }),
+    )
+}
@@ -2,9 +2,11 @@ use futures::{future, FutureExt};
 use linkerd_app_core::{
     classify,
     http_metrics::retries::Handle,
+    http_tracing::SpanRecorder,
+    is_caused_by,
     metrics::{self, ProfileRouteLabels},
     profiles::{self, http::Route},
-    proxy::http::{Body, ClientHandle, EraseResponse},
+    proxy::http::{Body, ClientHandle, EraseResponse, RequestDeadline, ResponseTimeoutError},
     svc::{layer, Either, Param},
     Error, Result,
 };
@@ -35,6 +37,8 @@ pub struct RetryPolicy {
     metrics: Handle,
     budget: Arc<retry::TpsBudget>,
     response_classes: profiles::http::ResponseClasses,
+    /// The number of attempts that have been dispatched for this request.
+    attempts: u64,
 }
 
 /// Allow buffering requests up to 64 kb
@@ -61,6 +65,7 @@ where
             metrics: self.metrics.get_handle(labels),
             budget: route.retries()?.budget().clone(),
             response_classes: route.response_classes().clone(),
+            attempts: 1,
         })
     }
 }
@@ -85,6 +90,14 @@ where
         use retry::Budget as _;
 
         let retryable = match result {
+            // Attempts that exceed the per-try timeout have already been
+            // cancelled, so they may be retried so long as the request body
+            // can be replayed.
+            Err(error) if is_caused_by::<ResponseTimeoutError>(&**error) => {
+                let retryable = req.body().is_capped() == Some(false);
+                tracing::trace!(retryable, "Attempt timed out");
+                retryable
+            }
             Err(_) => false,
             Ok(rsp) => {
                 // is the request a failure?
@@ -112,15 +125,18 @@ where
 
         if !retryable {
             self.budget.deposit();
+            self.metrics.observe_attempts(self.attempts);
             return None;
         }
 
         let withdrew = self.budget.withdraw();
         self.metrics.incr_retryable(withdrew);
         if !withdrew {
+            self.metrics.observe_attempts(self.attempts);
             return None;
         }
 
+        self.attempts += 1;
         Some(future::ready(()))
     }
 
@@ -146,6 +162,15 @@ where
             clone.extensions_mut().insert(classify);
         }
 
+        if let Some(recorder) = req.extensions().get::<SpanRecorder>().cloned() {
+            clone.extensions_mut().insert(recorder);
+        }
+
+        // A client's deadline bounds each attempt.
+        if let Some(deadline) = req.extensions().get::<RequestDeadline>().cloned() {
+            clone.extensions_mut().insert(deadline);
+        }
+
         Some(clone)
     }
 }
@@ -42,6 +42,9 @@ impl<T> Outbound<svc::ArcNewCloneHttp<T>> {
                 // reached or the inner service is otherwise not ready for
                 // requests.
                 .push_on_service(svc::LoadShed::layer())
+                // Fail requests that have already exceeded their deadline and
+                // record the deadline so that it bounds route timeouts.
+                .push_on_service(http::PropagateDeadline::layer(config.http_deadline.clone()))
                 .push_on_service(rt.metrics.http_errors.to_layer())
                 // Synthesizes responses for proxy errors.
                 .check_new_service::<T, http::Request<_>>()
@@ -55,6 +58,10 @@ impl<T> Outbound<svc::ArcNewCloneHttp<T>> {
                 .push(http::NewNormalizeUri::layer())
                 // Record when a HTTP/1 URI originated in absolute form
                 .push_on_service(http::normalize_uri::MarkAbsoluteForm::layer())
+                // Ensure that each request has an ID, if configured. This is
+                // set before routing so that every retry attempt carries the
+                // same ID.
+                .push_on_service(http::SetRequestId::layer(config.http_request_id.clone()))
                 .arc_new_clone_http()
         })
     }
@@ -126,37 +133,73 @@ impl errors::HttpRescue<Error> for ServerRescue {
             // XXX(ver) This should probably be SERVICE_UNAVAILABLE, because
             // this is basically no different from a LoadShedError, but that
             // would be a change in behavior.
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(
+                errors::ErrorCode::Failfast,
+                error,
+            ));
         }
         if errors::is_caused_by::<errors::LoadShedError>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::unavailable(error));
+            return Ok(errors::SyntheticHttpResponse::unavailable(
+                errors::ErrorCode::LoadShed,
+                error,
+            ));
         }
 
         // Handle policy-driven timeouts.
         if errors::is_caused_by::<http::stream_timeouts::ResponseTimeoutError>(&*error) {
             return Ok(errors::SyntheticHttpResponse::gateway_timeout_nonfatal(
+                errors::ErrorCode::ResponseTimeout,
+                error,
+            ));
+        }
+
+        // A request arrived after its deadline.
+        if errors::is_caused_by::<http::stream_timeouts::DeadlineExceededError>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout_nonfatal(
+                errors::ErrorCode::DeadlineExceeded,
                 error,
             ));
         }
 
         // A profile configured request timeout was encountered.
         if errors::is_caused_by::<http::ResponseTimeoutError>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::gateway_timeout(error));
+            return Ok(errors::SyntheticHttpResponse::gateway_timeout(
+                errors::ErrorCode::ResponseTimeout,
+                error,
+            ));
         }
 
         // A request with a `l5d-require-id` header are dispatched to endpoints
         // with a different identity.
         if errors::is_caused_by::<IdentityRequired>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(
+                errors::ErrorCode::IdentityRequired,
+                error,
+            ));
         }
 
         if errors::is_caused_by::<super::concrete::DispatcherFailed>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::bad_gateway(error));
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(
+                errors::ErrorCode::DispatcherFailed,
+                error,
+            ));
+        }
+
+        // A backend fault, configured through the admin server, failed the
+        // request.
+        if errors::is_caused_by::<super::BackendFaultInjected>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::bad_gateway(
+                errors::ErrorCode::BackendFault,
+                error,
+            ));
         }
 
         // No routes configured for a request.
         if errors::is_caused_by::<super::logical::NoRoute>(&*error) {
-            return Ok(errors::SyntheticHttpResponse::not_found(error));
+            return Ok(errors::SyntheticHttpResponse::not_found(
+                errors::ErrorCode::NoRoute,
+                error,
+            ));
         }
 
         // Policy-driven request redirection.
@@ -169,6 +212,7 @@ impl errors::HttpRescue<Error> for ServerRescue {
             errors::cause_ref(&*error)
         {
             return Ok(errors::SyntheticHttpResponse::response(
+                errors::ErrorCode::InjectedFailure,
                 *status,
                 message.to_string(),
             ));
@@ -176,10 +220,39 @@ impl errors::HttpRescue<Error> for ServerRescue {
         if let Some(policy::GrpcRouteInjectedFailure { code, message }) = errors::cause_ref(&*error)
         {
             return Ok(errors::SyntheticHttpResponse::grpc(
+                errors::ErrorCode::InjectedFailure,
                 (*code as i32).into(),
                 message.to_string(),
             ));
         }
+        if let Some(policy::HttpRouteFaultAbort { status }) = errors::cause_ref(&*error) {
+            return Ok(errors::SyntheticHttpResponse::response(
+                errors::ErrorCode::FaultAbort,
+                *status,
+                error.to_string(),
+            ));
+        }
+        if let Some(policy::GrpcRouteFaultAbort { code }) = errors::cause_ref(&*error) {
+            return Ok(errors::SyntheticHttpResponse::grpc(
+                errors::ErrorCode::FaultAbort,
+                (*code as i32).into(),
+                error.to_string(),
+            ));
+        }
+        if errors::is_caused_by::<policy::HttpRouteRequestTooLarge>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::response(
+                errors::ErrorCode::RequestBodyTooLarge,
+                http::StatusCode::PAYLOAD_TOO_LARGE,
+                error.to_string(),
+            ));
+        }
+        if errors::is_caused_by::<policy::HttpRouteInvalidRequestEncoding>(&*error) {
+            return Ok(errors::SyntheticHttpResponse::response(
+                errors::ErrorCode::InvalidRequestEncoding,
+                http::StatusCode::BAD_REQUEST,
+                error.to_string(),
+            ));
+        }
 
         // HTTP/2 errors.
         if errors::is_caused_by::<errors::H2Error>(&*error) {
@@ -0,0 +1,475 @@
+//! Records where slow requests spend their time.
+//!
+//! When a latency threshold is configured, each request carries a [`Timings`]
+//! extension that key layers of the stack stamp as the request passes them:
+//! when its route is matched, when it is dispatched to a concrete service's
+//! queue, when the balancer selects an endpoint, and when the endpoint's
+//! response headers are received. If the request waited for the endpoint's
+//! connection, the time spent connecting and negotiating TLS is recorded as
+//! well.
+//!
+//! Requests whose responses take longer than the threshold are written to the
+//! access log and annotated on their span, if it is sampled. When no threshold
+//! is configured, requests carry no extension and each layer only checks for
+//! its absence.
+
+use futures::{future, TryFuture, TryFutureExt};
+use linkerd_app_core::{
+    http_tracing::SpanRecorder, proxy::http, svc, tls, trace::access_log::TRACE_TARGET, Error,
+};
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time;
+use tracing::Level;
+
+/// The layers of the stack that stamp a request's timings.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Stage {
+    /// The request matched a route.
+    Route,
+    /// The request was dispatched to a concrete service's queue.
+    Queue,
+    /// The request was dispatched to an endpoint.
+    Balance,
+}
+
+/// Stamps recorded as a request passes through the stack.
+///
+/// Clones share stamps. When a request is retried, the last attempt's stamps
+/// are recorded.
+#[derive(Clone, Debug)]
+pub(crate) struct Timings(Arc<Mutex<Stamps>>);
+
+/// The time elapsed, since the request was received, until it reached each
+/// stage; and the time spent establishing the connection on which it was
+/// sent, if the request waited for it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Breadcrumb {
+    pub(crate) route: Option<Duration>,
+    pub(crate) queue: Option<Duration>,
+    pub(crate) balance: Option<Duration>,
+    pub(crate) connect: Option<Duration>,
+    pub(crate) tls: Option<Duration>,
+    pub(crate) first_byte: Option<Duration>,
+}
+
+/// Records the most recent connection established for an endpoint, so that
+/// requests that waited for it may report how long it took.
+#[derive(Clone, Debug, Default)]
+pub struct Connections(Arc<Mutex<Option<Connection>>>);
+
+#[derive(Debug)]
+struct Stamps {
+    start: time::Instant,
+    route: Option<time::Instant>,
+    queue: Option<time::Instant>,
+    balance: Option<time::Instant>,
+    first_byte: Option<time::Instant>,
+    connections: Option<Connections>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Connection {
+    established: time::Instant,
+    connect: Duration,
+    tls: Option<Duration>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewTimings<N> {
+    threshold: Option<Duration>,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TimeRequests<S> {
+    threshold: Option<Duration>,
+    inner: S,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct TimingsFuture<F> {
+    slow: Option<SlowRequest>,
+    #[pin]
+    inner: F,
+}
+
+#[derive(Debug)]
+struct SlowRequest {
+    threshold: Duration,
+    timings: Timings,
+    recorder: Option<SpanRecorder>,
+    method: http::Method,
+    uri: http::uri::Uri,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Stamp<S> {
+    stage: Stage,
+    inner: S,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewTimeEndpoint<N> {
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TimeEndpoint<S> {
+    connections: Option<Connections>,
+    inner: S,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RecordConnect<S> {
+    inner: S,
+}
+
+type BoxFuture<T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'static>>;
+
+// === impl Timings ===
+
+impl Timings {
+    pub(crate) fn new() -> Self {
+        Self(Arc::new(Mutex::new(Stamps {
+            start: time::Instant::now(),
+            route: None,
+            queue: None,
+            balance: None,
+            first_byte: None,
+            connections: None,
+        })))
+    }
+
+    pub(crate) fn get<B>(req: &http::Request<B>) -> Option<&Self> {
+        req.extensions().get()
+    }
+
+    fn stamp(&self, stage: Stage) {
+        let now = time::Instant::now();
+        let mut stamps = self.0.lock();
+        match stage {
+            Stage::Route => stamps.route = Some(now),
+            Stage::Queue => stamps.queue = Some(now),
+            Stage::Balance => stamps.balance = Some(now),
+        }
+    }
+
+    fn stamp_first_byte(&self, connections: Option<Connections>) {
+        let mut stamps = self.0.lock();
+        stamps.first_byte = Some(time::Instant::now());
+        stamps.connections = connections;
+    }
+
+    pub(crate) fn breadcrumb(&self) -> Breadcrumb {
+        let stamps = self.0.lock();
+        let since_start =
+            |at: Option<time::Instant>| at.map(|at| at.saturating_duration_since(stamps.start));
+
+        // Connections established before the request was received were
+        // already available, so the request did not wait for them.
+        let connection = stamps
+            .connections
+            .as_ref()
+            .and_then(|c| *c.0.lock())
+            .filter(|c| c.established >= stamps.start);
+
+        Breadcrumb {
+            route: since_start(stamps.route),
+            queue: since_start(stamps.queue),
+            balance: since_start(stamps.balance),
+            connect: connection.map(|c| c.connect),
+            tls: connection.and_then(|c| c.tls),
+            first_byte: since_start(stamps.first_byte),
+        }
+    }
+}
+
+// === impl Breadcrumb ===
+
+impl Breadcrumb {
+    fn attributes(&self) -> [(&'static str, Option<Duration>); 6] {
+        [
+            ("proxy.timings.route_ms", self.route),
+            ("proxy.timings.queue_ms", self.queue),
+            ("proxy.timings.balance_ms", self.balance),
+            ("proxy.timings.connect_ms", self.connect),
+            ("proxy.timings.tls_ms", self.tls),
+            ("proxy.timings.first_byte_ms", self.first_byte),
+        ]
+    }
+}
+
+fn fmt_ms(d: Option<Duration>) -> String {
+    match d {
+        Some(d) => format!("{:.3}", d.as_secs_f64() * 1000.0),
+        None => "-".to_string(),
+    }
+}
+
+// === impl Connections ===
+
+impl Connections {
+    fn established(&self, connect: Duration, tls: Option<Duration>) {
+        *self.0.lock() = Some(Connection {
+            established: time::Instant::now(),
+            connect,
+            tls,
+        });
+    }
+}
+
+impl PartialEq for Connections {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Connections {}
+
+// === impl NewTimings ===
+
+impl<N> NewTimings<N> {
+    pub(crate) fn layer(
+        threshold: Option<Duration>,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self { threshold, inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewTimings<N>
+where
+    N: svc::NewService<T>,
+{
+    type Service = TimeRequests<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        TimeRequests {
+            threshold: self.threshold,
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl TimeRequests ===
+
+impl<B, RspB, S> svc::Service<http::Request<B>> for TimeRequests<S>
+where
+    S: svc::Service<http::Request<B>, Response = http::Response<RspB>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TimingsFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let Some(threshold) = self.threshold else {
+            return TimingsFuture {
+                slow: None,
+                inner: self.inner.call(req),
+            };
+        };
+
+        let timings = Timings::new();
+        req.extensions_mut().insert(timings.clone());
+        let slow = SlowRequest {
+            threshold,
+            timings,
+            recorder: SpanRecorder::get(&req).cloned(),
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+        };
+        TimingsFuture {
+            slow: Some(slow),
+            inner: self.inner.call(req),
+        }
+    }
+}
+
+impl<F, B> Future for TimingsFuture<F>
+where
+    F: TryFuture<Ok = http::Response<B>>,
+{
+    type Output = Result<http::Response<B>, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = futures::ready!(this.inner.try_poll(cx));
+        if let Some(slow) = this.slow.take() {
+            let status = res.as_ref().ok().map(|rsp| rsp.status());
+            slow.record(status);
+        }
+        Poll::Ready(res)
+    }
+}
+
+// === impl SlowRequest ===
+
+impl SlowRequest {
+    fn record(self, status: Option<http::StatusCode>) {
+        let start = self.timings.0.lock().start;
+        let elapsed = time::Instant::now().saturating_duration_since(start);
+        if elapsed < self.threshold {
+            return;
+        }
+
+        let breadcrumb = self.timings.breadcrumb();
+        tracing::debug!(?elapsed, ?breadcrumb, "Slow request");
+        if let Some(recorder) = self.recorder.as_ref() {
+            for (key, value) in breadcrumb.attributes() {
+                if let Some(value) = value {
+                    recorder.set_attribute(key, fmt_ms(Some(value)));
+                }
+            }
+        }
+
+        // The span is recorded by the access log, if it is enabled, when it
+        // is dropped.
+        let _span = tracing::span!(
+            target: TRACE_TARGET,
+            Level::INFO,
+            "slow_request",
+            method = self.method.as_str(),
+            uri = %self.uri,
+            status = status.map(|s| s.as_u16()).unwrap_or_default(),
+            total_ms = %fmt_ms(Some(elapsed)),
+            route_ms = %fmt_ms(breadcrumb.route),
+            queue_ms = %fmt_ms(breadcrumb.queue),
+            balance_ms = %fmt_ms(breadcrumb.balance),
+            connect_ms = %fmt_ms(breadcrumb.connect),
+            tls_ms = %fmt_ms(breadcrumb.tls),
+            first_byte_ms = %fmt_ms(breadcrumb.first_byte),
+        );
+    }
+}
+
+// === impl Stamp ===
+
+impl<S> Stamp<S> {
+    pub(crate) fn layer(stage: Stage) -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self { stage, inner })
+    }
+}
+
+impl<B, S> svc::Service<http::Request<B>> for Stamp<S>
+where
+    S: svc::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if let Some(timings) = Timings::get(&req) {
+            timings.stamp(self.stage);
+        }
+        self.inner.call(req)
+    }
+}
+
+// === impl NewTimeEndpoint ===
+
+impl<N> NewTimeEndpoint<N> {
+    pub(crate) fn layer() -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewTimeEndpoint<N>
+where
+    T: svc::Param<Option<Connections>>,
+    N: svc::NewService<T>,
+{
+    type Service = TimeEndpoint<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        TimeEndpoint {
+            connections: target.param(),
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl TimeEndpoint ===
+
+impl<B, S> svc::Service<http::Request<B>> for TimeEndpoint<S>
+where
+    S: svc::Service<http::Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = future::Either<S::Future, BoxFuture<S::Response, S::Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let Some(timings) = Timings::get(&req).cloned() else {
+            return future::Either::Left(self.inner.call(req));
+        };
+        let connections = self.connections.clone();
+        future::Either::Right(Box::pin(self.inner.call(req).map_ok(move |rsp| {
+            timings.stamp_first_byte(connections);
+            rsp
+        })))
+    }
+}
+
+// === impl RecordConnect ===
+
+impl<S> RecordConnect<S> {
+    pub(crate) fn layer() -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        svc::layer::mk(|inner| Self { inner })
+    }
+}
+
+impl<T, I, M, S> svc::Service<T> for RecordConnect<S>
+where
+    T: svc::Param<Option<Connections>>,
+    S: svc::Service<T, Response = (I, tls::ConnectMeta<M>), Error = Error>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<S::Future, BoxFuture<S::Response, Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        let Some(connections) = target.param() else {
+            return future::Either::Left(self.inner.call(target));
+        };
+        let start = time::Instant::now();
+        future::Either::Right(Box::pin(self.inner.call(target).map_ok(
+            move |(io, meta)| {
+                let total = time::Instant::now().saturating_duration_since(start);
+                let tls = meta.handshake;
+                connections.established(total.saturating_sub(tls.unwrap_or_default()), tls);
+                (io, meta)
+            },
+        )))
+    }
+}
@@ -0,0 +1,362 @@
+//! Probes meshed endpoints for HTTP/2 upgrade support.
+//!
+//! Discovery does not always indicate whether a meshed endpoint's proxy can
+//! accept HTTP/1 requests that have been upgraded to HTTP/2, in which case
+//! HTTP/1 connections are used. When probing is enabled, a TLS connection is
+//! established with such an endpoint while offering the transport header ALPN
+//! protocol: a peer that negotiates it is a proxy that supports the upgrade.
+//! Probe results are cached per endpoint address for a TTL and are discarded
+//! when the endpoint's metadata, including its identity, changes.
+//!
+//! Endpoint services are rebuilt when a probe determines a different protocol
+//! so that subsequent requests use it.
+
+use crate::{tcp, ConnectMeta};
+use linkerd_app_core::{
+    metrics::prom,
+    proxy::api_resolve::Metadata,
+    svc, tls,
+    transport::{Remote, ServerAddr},
+    transport_header::PROTOCOL,
+    Conditional, Error,
+};
+use parking_lot::RwLock;
+use std::{
+    collections::HashMap,
+    fmt,
+    net::SocketAddr,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::time::{Duration, Instant};
+use tracing::{debug, Instrument};
+
+#[cfg(test)]
+mod tests;
+
+/// The protocol with which an endpoint's proxy is known to communicate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum EndpointProtocol {
+    /// The endpoint accepts HTTP/1 requests upgraded to HTTP/2.
+    Http2,
+
+    /// The endpoint only accepts HTTP/1 requests.
+    Http1,
+
+    /// The endpoint's protocol support is not known.
+    Unknown,
+}
+
+/// A cache of upgrade probe results, shared by all endpoint stacks.
+#[derive(Clone)]
+pub(crate) struct UpgradeProbes(Arc<Inner>);
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UpgradeProbeMetrics {
+    attempts: prom::Counter,
+    results: prom::Family<ResultLabels, prom::Counter>,
+}
+
+/// Describes an endpoint that should be probed.
+#[derive(Clone, Debug)]
+pub struct ProbeTarget {
+    pub(crate) addr: Remote<ServerAddr>,
+    pub(crate) tls: tls::ClientTls,
+    pub(crate) metadata: Arc<Metadata>,
+    pub(crate) probes: UpgradeProbes,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewUpgradeProbe<C, N> {
+    connect: C,
+    inner: N,
+}
+
+/// Probes an endpoint and rebuilds its inner service when the probed protocol
+/// changes.
+pub(crate) struct UpgradeProbe<T, C, S, N> {
+    target: T,
+    probe: Option<ProbeTarget>,
+    protocol: EndpointProtocol,
+    connect: C,
+    inner: N,
+    service: S,
+}
+
+struct Inner {
+    ttl: Duration,
+    entries: RwLock<HashMap<SocketAddr, Entry>>,
+    metrics: UpgradeProbeMetrics,
+}
+
+struct Entry {
+    metadata: Arc<Metadata>,
+    state: State,
+}
+
+enum State {
+    Probing,
+    Probed {
+        protocol: EndpointProtocol,
+        expires_at: Instant,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct ResultLabels {
+    result: ProbeResult,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum ProbeResult {
+    /// The endpoint negotiated support for HTTP/2 upgrades.
+    http2,
+    /// The endpoint did not negotiate support for HTTP/2 upgrades.
+    http1,
+    /// A connection could not be established with the endpoint.
+    error,
+}
+
+// === impl UpgradeProbeMetrics ===
+
+impl UpgradeProbeMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let attempts = prom::Counter::default();
+        registry.register(
+            "attempts",
+            "The number of endpoints probed for HTTP/2 upgrade support",
+            attempts.clone(),
+        );
+        let results = prom::Family::default();
+        registry.register(
+            "results",
+            "The number of completed HTTP/2 upgrade probes by result",
+            results.clone(),
+        );
+        Self { attempts, results }
+    }
+
+    fn result(&self, result: ProbeResult) {
+        self.results.get_or_create(&ResultLabels { result }).inc();
+    }
+}
+
+// === impl UpgradeProbes ===
+
+impl UpgradeProbes {
+    pub(crate) fn new(ttl: Duration, metrics: UpgradeProbeMetrics) -> Self {
+        Self(Arc::new(Inner {
+            ttl,
+            entries: Default::default(),
+            metrics,
+        }))
+    }
+
+    /// Returns the protocol most recently probed for the endpoint, unless the
+    /// result has expired or was obtained with different metadata.
+    pub(crate) fn protocol(&self, addr: SocketAddr, metadata: &Metadata) -> EndpointProtocol {
+        let entries = self.0.entries.read();
+        match entries.get(&addr) {
+            Some(Entry {
+                metadata: probed,
+                state:
+                    State::Probed {
+                        protocol,
+                        expires_at,
+                    },
+            }) if **probed == *metadata && *expires_at > Instant::now() => *protocol,
+            _ => EndpointProtocol::Unknown,
+        }
+    }
+
+    /// Probes the endpoint in the background, unless a probe is already in
+    /// flight or a current result is cached.
+    pub(crate) fn spawn<C>(&self, target: &ProbeTarget, connect: C)
+    where
+        C: svc::MakeConnection<tcp::Connect, Metadata = ConnectMeta> + Send + 'static,
+        C::Connection: Send,
+        C::Error: Into<Error>,
+        C::Future: Send,
+    {
+        let Remote(ServerAddr(addr)) = target.addr;
+        if !self.start(addr, &target.metadata) {
+            return;
+        }
+        self.0.metrics.attempts.inc();
+
+        let mut tls = target.tls.clone();
+        tls.alpn = Some(tls::client::AlpnProtocols(vec![PROTOCOL.into()]));
+        let connect = svc::ServiceExt::oneshot(
+            connect.into_service(),
+            tcp::Connect::new(target.addr, Conditional::Some(tls)),
+        );
+        let probes = self.clone();
+        let metadata = target.metadata.clone();
+        tokio::spawn(
+            async move {
+                let (protocol, result) = match connect.await {
+                    Ok((_, meta)) if Self::upgrade_negotiated(&meta) => {
+                        (EndpointProtocol::Http2, ProbeResult::http2)
+                    }
+                    Ok(_) => (EndpointProtocol::Http1, ProbeResult::http1),
+                    // Failed probes are not retried until the TTL elapses so
+                    // that unreachable endpoints are not probed repeatedly.
+                    Err(error) => {
+                        let error: Error = error.into();
+                        debug!(%error, "Failed to probe endpoint");
+                        (EndpointProtocol::Http1, ProbeResult::error)
+                    }
+                };
+                debug!(?protocol, "Probed endpoint");
+                probes.0.metrics.result(result);
+                probes.finish(addr, &metadata, protocol);
+            }
+            .instrument(tracing::debug_span!("upgrade_probe", %addr)),
+        );
+    }
+
+    fn upgrade_negotiated(meta: &ConnectMeta) -> bool {
+        if let Conditional::Some(Some(np)) = meta.tls.as_ref() {
+            let tls::NegotiatedProtocolRef(protocol) = np.as_ref();
+            return protocol == PROTOCOL;
+        }
+        false
+    }
+
+    /// Records that a probe has started, returning false if the endpoint need
+    /// not be probed.
+    fn start(&self, addr: SocketAddr, metadata: &Arc<Metadata>) -> bool {
+        let now = Instant::now();
+        let mut entries = self.0.entries.write();
+        if let Some(entry) = entries.get(&addr) {
+            let current = match entry.state {
+                State::Probing => true,
+                State::Probed { expires_at, .. } => expires_at > now,
+            };
+            if current && *entry.metadata == **metadata {
+                return false;
+            }
+        }
+
+        // Expired results are dropped so that the cache does not retain
+        // endpoints that are no longer in use.
+        entries.retain(|_, entry| match entry.state {
+            State::Probing => true,
+            State::Probed { expires_at, .. } => expires_at > now,
+        });
+        entries.insert(
+            addr,
+            Entry {
+                metadata: metadata.clone(),
+                state: State::Probing,
+            },
+        );
+        true
+    }
+
+    fn finish(&self, addr: SocketAddr, metadata: &Arc<Metadata>, protocol: EndpointProtocol) {
+        let mut entries = self.0.entries.write();
+        // If the endpoint's metadata changed while the probe was in flight,
+        // the result is stale.
+        if let Some(entry) = entries.get_mut(&addr) {
+            if *entry.metadata == **metadata {
+                entry.state = State::Probed {
+                    protocol,
+                    expires_at: Instant::now() + self.0.ttl,
+                };
+            }
+        }
+    }
+}
+
+impl PartialEq for UpgradeProbes {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for UpgradeProbes {}
+
+impl fmt::Debug for UpgradeProbes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpgradeProbes")
+            .field("ttl", &self.0.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+// === impl NewUpgradeProbe ===
+
+impl<C: Clone, N> NewUpgradeProbe<C, N> {
+    pub(crate) fn layer(connect: C) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            connect: connect.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, C, N> svc::NewService<T> for NewUpgradeProbe<C, N>
+where
+    T: svc::Param<Option<ProbeTarget>> + Clone,
+    C: Clone,
+    N: svc::NewService<T> + Clone,
+{
+    type Service = UpgradeProbe<T, C, N::Service, N>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let probe: Option<ProbeTarget> = target.param();
+        let protocol = probe
+            .as_ref()
+            .map(|p| p.probes.protocol(p.addr.into(), &p.metadata))
+            .unwrap_or(EndpointProtocol::Unknown);
+        let service = self.inner.new_service(target.clone());
+        UpgradeProbe {
+            target,
+            probe,
+            protocol,
+            connect: self.connect.clone(),
+            inner: self.inner.clone(),
+            service,
+        }
+    }
+}
+
+// === impl UpgradeProbe ===
+
+impl<T, C, S, N, Req> svc::Service<Req> for UpgradeProbe<T, C, S, N>
+where
+    T: Clone,
+    C: svc::MakeConnection<tcp::Connect, Metadata = ConnectMeta> + Clone + Send + 'static,
+    C::Connection: Send,
+    C::Error: Into<Error>,
+    C::Future: Send,
+    N: svc::NewService<T, Service = S>,
+    S: svc::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if let Some(probe) = self.probe.as_ref() {
+            let protocol = probe.probes.protocol(probe.addr.into(), &probe.metadata);
+            // While an expired result is probed again, the service built from
+            // it continues to be used.
+            if protocol == EndpointProtocol::Unknown {
+                probe.probes.spawn(probe, self.connect.clone());
+            } else if protocol != self.protocol {
+                debug!(?protocol, "Rebuilding endpoint client");
+                self.service = self.inner.new_service(self.target.clone());
+                self.protocol = protocol;
+            }
+        }
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.service.call(req)
+    }
+}
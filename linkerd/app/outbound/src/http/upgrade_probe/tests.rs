@@ -0,0 +1,253 @@
+use super::*;
+use futures::future;
+use linkerd2_proxy_api::destination;
+use linkerd_app_core::{
+    io,
+    proxy::api_resolve::pb,
+    svc::{Layer, NewService, Service, ServiceExt},
+    transport::{ClientAddr, Local},
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const TTL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug)]
+struct Target(Option<ProbeTarget>);
+
+impl svc::Param<Option<ProbeTarget>> for Target {
+    fn param(&self) -> Option<ProbeTarget> {
+        self.0.clone()
+    }
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn rebuilds_service_when_upgrade_is_negotiated() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (probes, metrics) = probes();
+    let probe = probe_target(
+        &probes,
+        "foo.ns.serviceaccount.identity.linkerd.cluster.local",
+    );
+    let (connect, connects) = connect(Some(PROTOCOL));
+
+    // Each inner service responds with the protocol that was probed when it
+    // was built.
+    let builds = Arc::new(AtomicUsize::new(0));
+    let new_svc = NewUpgradeProbe::layer(connect).layer({
+        let builds = builds.clone();
+        move |Target(probe): Target| {
+            builds.fetch_add(1, Ordering::SeqCst);
+            let probe = probe.unwrap();
+            let protocol = probe.probes.protocol(probe.addr.into(), &probe.metadata);
+            svc::mk(move |()| future::ok::<_, Error>(protocol))
+        }
+    });
+    let mut svc = new_svc.new_service(Target(Some(probe.clone())));
+    assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+    // The first request is sent before the probe completes.
+    let protocol = svc.ready().await.unwrap().call(()).await.unwrap();
+    assert_eq!(protocol, EndpointProtocol::Unknown);
+    let protocol = svc.ready().await.unwrap().call(()).await.unwrap();
+    assert_eq!(protocol, EndpointProtocol::Unknown);
+
+    tokio::task::yield_now().await;
+    assert_eq!(connects.load(Ordering::SeqCst), 1, "probes once");
+    assert_eq!(
+        probes.protocol(probe.addr.into(), &probe.metadata),
+        EndpointProtocol::Http2
+    );
+
+    let protocol = svc.ready().await.unwrap().call(()).await.unwrap();
+    assert_eq!(protocol, EndpointProtocol::Http2);
+    assert_eq!(builds.load(Ordering::SeqCst), 2);
+
+    // New services use the cached result without probing.
+    let mut svc = new_svc.new_service(Target(Some(probe)));
+    let protocol = svc.ready().await.unwrap().call(()).await.unwrap();
+    assert_eq!(protocol, EndpointProtocol::Http2);
+    assert_eq!(builds.load(Ordering::SeqCst), 3);
+    assert_eq!(connects.load(Ordering::SeqCst), 1);
+
+    assert_eq!(metrics.attempts.get(), 1);
+    assert_eq!(result(&metrics, ProbeResult::http2), 1);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn results_expire() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (probes, metrics) = probes();
+    let probe = probe_target(
+        &probes,
+        "foo.ns.serviceaccount.identity.linkerd.cluster.local",
+    );
+    let (connect, connects) = connect(None);
+
+    probes.spawn(&probe, connect.clone());
+    tokio::task::yield_now().await;
+    assert_eq!(
+        probes.protocol(probe.addr.into(), &probe.metadata),
+        EndpointProtocol::Http1
+    );
+
+    probes.spawn(&probe, connect.clone());
+    tokio::task::yield_now().await;
+    assert_eq!(connects.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(TTL).await;
+    assert_eq!(
+        probes.protocol(probe.addr.into(), &probe.metadata),
+        EndpointProtocol::Unknown
+    );
+    probes.spawn(&probe, connect);
+    tokio::task::yield_now().await;
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+
+    assert_eq!(metrics.attempts.get(), 2);
+    assert_eq!(result(&metrics, ProbeResult::http1), 2);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn results_are_invalidated_by_metadata() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (probes, _) = probes();
+    let probe = probe_target(
+        &probes,
+        "foo.ns.serviceaccount.identity.linkerd.cluster.local",
+    );
+    let (connect, connects) = connect(Some(PROTOCOL));
+
+    probes.spawn(&probe, connect.clone());
+    tokio::task::yield_now().await;
+    assert_eq!(
+        probes.protocol(probe.addr.into(), &probe.metadata),
+        EndpointProtocol::Http2
+    );
+
+    // The endpoint's identity changes.
+    let changed = probe_target(
+        &probes,
+        "bar.ns.serviceaccount.identity.linkerd.cluster.local",
+    );
+    assert_eq!(
+        probes.protocol(changed.addr.into(), &changed.metadata),
+        EndpointProtocol::Unknown
+    );
+    probes.spawn(&changed, connect);
+    tokio::task::yield_now().await;
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        probes.protocol(probe.addr.into(), &probe.metadata),
+        EndpointProtocol::Unknown,
+        "the prior result must be discarded"
+    );
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn failed_probes_use_http1() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let (probes, metrics) = probes();
+    let probe = probe_target(
+        &probes,
+        "foo.ns.serviceaccount.identity.linkerd.cluster.local",
+    );
+    let connect = svc::mk(|_: tcp::Connect| {
+        future::err::<(tokio_test::io::Mock, ConnectMeta), _>(io::Error::from(
+            io::ErrorKind::ConnectionRefused,
+        ))
+    });
+
+    probes.spawn(&probe, connect);
+    tokio::task::yield_now().await;
+    assert_eq!(
+        probes.protocol(probe.addr.into(), &probe.metadata),
+        EndpointProtocol::Http1
+    );
+    assert_eq!(result(&metrics, ProbeResult::error), 1);
+}
+
+// === Utils ===
+
+fn probes() -> (UpgradeProbes, UpgradeProbeMetrics) {
+    let metrics = UpgradeProbeMetrics::register(&mut Default::default());
+    (UpgradeProbes::new(TTL, metrics.clone()), metrics)
+}
+
+fn result(metrics: &UpgradeProbeMetrics, result: ProbeResult) -> u64 {
+    metrics
+        .results
+        .get_or_create(&ResultLabels { result })
+        .get()
+}
+
+fn probe_target(probes: &UpgradeProbes, identity: &str) -> ProbeTarget {
+    use destination::tls_identity::{DnsLikeIdentity, Strategy};
+
+    let addr = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let pb = destination::WeightedAddr {
+        addr: Some(addr.into()),
+        weight: 1,
+        tls_identity: Some(destination::TlsIdentity {
+            strategy: Some(Strategy::DnsLikeIdentity(DnsLikeIdentity {
+                name: identity.to_string(),
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (addr, metadata) = pb::to_addr_meta(pb, &Default::default()).expect("valid endpoint");
+    ProbeTarget {
+        addr: Remote(ServerAddr(addr)),
+        tls: metadata
+            .identity()
+            .expect("endpoint must be meshed")
+            .clone(),
+        metadata: Arc::new(metadata),
+        probes: probes.clone(),
+    }
+}
+
+/// Returns a connector that negotiates the given ALPN protocol, if the probe
+/// offers it, and counts the connections it establishes.
+fn connect(
+    negotiated: Option<&'static [u8]>,
+) -> (
+    impl svc::MakeConnection<
+            tcp::Connect,
+            Connection = tokio_test::io::Mock,
+            Metadata = ConnectMeta,
+            Error = io::Error,
+            Future = impl Send,
+        > + Clone
+        + Send
+        + 'static,
+    Arc<AtomicUsize>,
+) {
+    let connects = Arc::new(AtomicUsize::new(0));
+    let connect = svc::mk({
+        let connects = connects.clone();
+        move |ep: tcp::Connect| {
+            connects.fetch_add(1, Ordering::SeqCst);
+            let alpn = match ep.tls() {
+                Conditional::Some(tls) => tls.alpn.clone(),
+                Conditional::None(_) => panic!("probes must use TLS"),
+            };
+            assert_eq!(
+                alpn,
+                Some(tls::client::AlpnProtocols(vec![PROTOCOL.into()])),
+                "probes must offer the transport header protocol"
+            );
+            let meta = tls::ConnectMeta {
+                socket: Local(ClientAddr(([0, 0, 0, 0], 0).into())),
+                tls: Conditional::Some(negotiated.map(|p| tls::NegotiatedProtocolRef(p).into())),
+                handshake: None,
+            };
+            future::ok::<_, io::Error>((tokio_test::io::Builder::new().build(), meta))
+        }
+    });
+    (connect, connects)
+}
@@ -1,4 +1,4 @@
-use crate::{http, opaq, policy, Discovery, Outbound, ParentRef};
+use crate::{http, opaq, policy, Discovery, Outbound, ParentRef, RouteUpdates};
 use linkerd_app_core::{
     errors, io, profiles,
     proxy::{
@@ -17,9 +17,9 @@ use tokio::sync::watch;
 use tracing::Instrument;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct Http<T> {
-    parent: T,
-    version: http::Variant,
+pub(crate) struct Http<T> {
+    pub(crate) parent: T,
+    pub(crate) version: http::Variant,
 }
 
 #[derive(Clone, Debug)]
@@ -32,16 +32,16 @@ struct Opaq {
 struct SelectTarget<T>(Http<T>);
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-enum RequestTarget {
+pub(crate) enum RequestTarget {
     Named(NameAddr),
     Orig(OrigDstAddr),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct DiscoverAddr(Addr);
+pub(crate) struct DiscoverAddr(pub(crate) Addr);
 
 #[derive(Clone, Debug)]
-struct Logical {
+pub(crate) struct Logical {
     addr: Addr,
     routes: watch::Receiver<http::Routes>,
 }
@@ -79,7 +79,7 @@ impl Outbound<()> {
         T: svc::Param<OrigDstAddr>,
         T: Clone + Send + Sync + 'static,
         // Server-side socket.
-        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr,
+        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr + io::Splice,
         I: Debug + Unpin + Send + Sync + 'static,
         // Endpoint resolver.
         R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
@@ -94,7 +94,12 @@ impl Outbound<()> {
             let discover = discover.clone();
             self.to_tcp_connect()
                 .push_opaq_cached(resolve.clone())
-                .map_stack(|_, _, stk| stk.push_map_target(Opaq::from))
+                .map_stack(|_, rt, stk| {
+                    let route_updates = rt.route_updates.clone();
+                    stk.push_map_target(move |discovery| {
+                        Opaq::from_discovery(discovery, &route_updates)
+                    })
+                })
                 .push_discover(svc::mk(move |OrigDstAddr(addr)| {
                     discover.clone().oneshot(DiscoverAddr(addr.into()))
                 }))
@@ -107,9 +112,10 @@ impl Outbound<()> {
             .push_http_tcp_client()
             .push_http_cached(resolve)
             .push_http_server()
-            .map_stack(|_, _, stk| {
+            .map_stack(|_, rt, stk| {
+                let route_updates = rt.route_updates.clone();
                 stk.check_new_service::<Http<Logical>, _>()
-                    .push_filter(Http::try_from)
+                    .push_filter(move |parent| Http::from_discovery(parent, &route_updates))
             })
             .push_discover(discover);
 
@@ -118,7 +124,7 @@ impl Outbound<()> {
             .into_inner()
     }
 
-    fn ingress_resolver(
+    pub(crate) fn ingress_resolver(
         &self,
         profiles: impl profiles::GetProfile<Error = Error>,
         policies: impl policy::GetPolicy,
@@ -256,7 +262,7 @@ impl<N> Outbound<N> {
         T: svc::Param<OrigDstAddr>,
         T: Clone + Send + Sync + Unpin + 'static,
         // A server-side socket.
-        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr,
+        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + io::Splice,
         I: std::fmt::Debug + Send + Unpin + 'static,
         // Fallback opaque stack.
         F: svc::NewService<T, Service = FSvc> + Clone + Send + Sync + 'static,
@@ -427,10 +433,11 @@ impl svc::Param<watch::Receiver<http::Routes>> for Http<Logical> {
     }
 }
 
-impl TryFrom<Discovery<Http<RequestTarget>>> for Http<Logical> {
-    type Error = Error;
-
-    fn try_from(parent: Discovery<Http<RequestTarget>>) -> std::result::Result<Self, Self::Error> {
+impl Http<Logical> {
+    pub(crate) fn from_discovery(
+        parent: Discovery<Http<RequestTarget>>,
+        route_updates: &RouteUpdates,
+    ) -> std::result::Result<Self, Error> {
         let version = parent.version;
         let profile =
             svc::Param::<Option<profiles::Receiver>>::param(&parent).map(watch::Receiver::from);
@@ -447,7 +454,7 @@ impl TryFrom<Discovery<Http<RequestTarget>>> for Http<Logical> {
                             let route =
                                 mk_profile_routes(laddr.clone(), &profile.borrow_and_update())
                                     .ok_or_else(|| DiscoveryRequired(addr.clone()))?;
-                            http::spawn_routes(profile, route, {
+                            http::spawn_routes(route_updates, profile, route, {
                                 let laddr = laddr.clone();
                                 move |profile| mk_profile_routes(laddr.clone(), profile)
                             })
@@ -471,7 +478,7 @@ impl TryFrom<Discovery<Http<RequestTarget>>> for Http<Logical> {
                     version: svc::Param::param(&*parent),
                     parent: Logical {
                         addr: addr.clone().into(),
-                        routes: http::spawn_routes(policy, route, move |policy| {
+                        routes: http::spawn_routes(route_updates, policy, route, move |policy| {
                             policy_routes(addr.clone().into(), version, policy)
                         }),
                     },
@@ -486,10 +493,11 @@ impl TryFrom<Discovery<Http<RequestTarget>>> for Http<Logical> {
                         let route = mk_profile_routes(laddr.clone(), &profile.borrow_and_update());
                         if let Some(route) = route {
                             tracing::debug!(%addr, "Using ServiceProfile");
-                            let routes = http::spawn_routes(profile.clone(), route, {
-                                let laddr = laddr.clone();
-                                move |profile| mk_profile_routes(laddr.clone(), profile)
-                            });
+                            let routes =
+                                http::spawn_routes(route_updates, profile.clone(), route, {
+                                    let laddr = laddr.clone();
+                                    move |profile| mk_profile_routes(laddr.clone(), profile)
+                                });
                             return Ok(Http {
                                 version,
                                 parent: Logical {
@@ -509,7 +517,7 @@ impl TryFrom<Discovery<Http<RequestTarget>>> for Http<Logical> {
                     version: svc::Param::param(&*parent),
                     parent: Logical {
                         addr: addr.into(),
-                        routes: http::spawn_routes(policy, route, move |policy| {
+                        routes: http::spawn_routes(route_updates, policy, route, move |policy| {
                             policy_routes(addr.into(), version, policy)
                         }),
                     },
@@ -615,15 +623,16 @@ impl std::hash::Hash for Logical {
     }
 }
 
-impl<T> From<Discovery<T>> for Opaq
-where
-    T: svc::Param<OrigDstAddr>,
-{
-    fn from(discovery: Discovery<T>) -> Self {
+impl Opaq {
+    fn from_discovery<T>(discovery: Discovery<T>, route_updates: &RouteUpdates) -> Self
+    where
+        T: svc::Param<OrigDstAddr>,
+    {
         use svc::Param;
 
         let orig_dst: OrigDstAddr = discovery.param();
         let routes = opaq::routes_from_discovery(
+            route_updates,
             Addr::Socket(orig_dst.into()),
             discovery.param(),
             discovery.param(),
@@ -7,7 +7,7 @@
 #![forbid(unsafe_code)]
 
 use linkerd_app_core::{
-    config::{ProxyConfig, QueueConfig},
+    config::{ProxyConfig, QueueConfig, ServerConfig, TunablesRx},
     drain,
     exp_backoff::ExponentialBackoff,
     http_tracing::SpanSink,
@@ -18,13 +18,19 @@ use linkerd_app_core::{
         self,
         api_resolve::{ConcreteAddr, Metadata},
         core::Resolve,
+        http::{request_id, stream_timeouts},
         tap,
     },
-    svc::{self, ServiceExt},
+    svc::{
+        self,
+        idle_cache::{Periodic, PeriodicConfig},
+        ServiceExt,
+    },
     tls::ConnectMeta as TlsConnectMeta,
     transport::addrs::*,
     AddrMatch, Error, NameAddr, ProxyRuntime,
 };
+use linkerd_http_retry::BufferBudget;
 use linkerd_tonic_stream::ReceiveLimits;
 use std::{
     collections::{HashMap, HashSet},
@@ -36,21 +42,45 @@ use std::{
 };
 
 mod discover;
+mod embed;
+mod explicit;
 pub mod http;
 mod ingress;
+mod lifetime;
+mod listener;
 mod metrics;
 pub mod opaq;
 pub mod policy;
+mod port_map;
+mod prewarm;
 mod protocol;
+mod route_updates;
 mod sidecar;
+mod snapshot;
+mod socks5;
 pub mod tcp;
 #[cfg(any(test, feature = "test-util"))]
 pub mod test_util;
 pub mod tls;
+mod topology;
 mod zone;
 
-pub use self::discover::{spawn_synthesized_profile_policy, synthesize_forward_policy, Discovery};
 use self::metrics::OutboundMetrics;
+pub use self::{
+    discover::{
+        spawn_synthesized_profile_policy, synthesize_forward_policy, Discovery, ForwardReason,
+    },
+    embed::{EndpointConnect, EndpointResolve, ServerIo, SidecarBuilder, SidecarTarget},
+    lifetime::{ConnectionExpired, ConnectionLifetimes},
+    listener::{ListenerConfig, ListenerOverrides},
+    port_map::{PortMapTarget, PortMapping, PortMappingState, PortMappings},
+    prewarm::PrewarmConfig,
+    protocol::HttpVersionMismatch,
+    route_updates::{RouteUpdates, RoutesFingerprint},
+    snapshot::{DiscoverySnapshot, DiscoverySnapshotConfig},
+    socks5::{Socks5Config, Socks5Credentials},
+    topology::TopologyHintsConfig,
+};
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -63,18 +93,92 @@ pub struct Config {
     /// dropped, all cached service discovery information is dropped.
     pub discovery_idle_timeout: Duration,
 
+    /// Configures how idle discovery information is retained for addresses
+    /// that are accessed at regular intervals longer than the idle timeout, if
+    /// at all.
+    pub discovery_retention: Option<PeriodicConfig>,
+
+    /// Configures how discovery results are persisted so that they may be
+    /// served provisionally when the proxy restarts, if at all.
+    pub discovery_snapshot: Option<DiscoverySnapshotConfig>,
+
+    /// Whether IPv4-mapped IPv6 original destination addresses are discovered
+    /// by their IPv4 form. Connections are still forwarded to the original
+    /// destination address as it was observed.
+    pub discovery_unmap_ipv4: bool,
+
     /// Configures how connections are buffered *for each outbound address*.
     ///
     /// A buffer capacity of 100 means that 100 connections may be buffered for
     /// each IP:port to which an application attempts to connect.
     pub tcp_connection_queue: QueueConfig,
 
+    /// Whether opaque connections are spliced between sockets, without being
+    /// copied through the proxy, when neither connection is TLS-encrypted by
+    /// the proxy.
+    pub tcp_splice: bool,
+
+    /// Configures how opaque connections are closed when a peer closes its
+    /// half of the connection, by destination port. Half-closes are propagated
+    /// on ports that are not configured.
+    pub tcp_half_close: Arc<HashMap<u16, proxy::tcp::HalfClose>>,
+
+    /// The number of times an opaque connection to a balanced backend is
+    /// reestablished when it fails, or is reset before any bytes are
+    /// exchanged, within the connection queue's failfast timeout. Connections
+    /// are not spliced while they may be retried. Disabled when zero.
+    pub tcp_connect_retries: usize,
+
+    /// Configures the maximum lifetime of connections to endpoints, by
+    /// destination network, after which they are replaced.
+    pub connection_lifetimes: ConnectionLifetimes,
+
+    /// Whether connections to TLS destinations on which the client sends a
+    /// plaintext HTTP request are answered with an HTTP 400 that describes the
+    /// mismatch. Such connections are closed either way.
+    pub tls_plaintext_http_response: bool,
+
+    /// Whether HTTP connections on which the client speaks a different HTTP
+    /// version than policy configures are served with the client's version,
+    /// treating the configured version as a hint. Otherwise, they are answered
+    /// with an HTTP 505 that describes the mismatch and closed.
+    pub http_version_hint: bool,
+
     /// Configures how HTTP requests are buffered *for each outbound address*.
     ///
     /// A buffer capacity of 100 means that 100 requests may be buffered for
     /// each IP:port to which an application has opened an outbound TCP connection.
     pub http_request_queue: QueueConfig,
 
+    /// Configures how balancers filter endpoints by their topology hints, if
+    /// at all.
+    pub topology_hints: Option<TopologyHintsConfig>,
+
+    /// Overrides the parameters with which balancers estimate their
+    /// endpoints' loads, which are otherwise configured by discovery. Each
+    /// balancer retains the parameters with which it was built.
+    pub balancer_ewma: proxy::balance::EwmaOverrides,
+
+    /// Whether balancers record each endpoint's load estimate in a gauge
+    /// labeled by the endpoint's address. Intended for debugging, since the
+    /// gauges' cardinality grows with the number of endpoints.
+    pub balancer_endpoint_load_metrics: bool,
+
+    /// The window within which policy and profile updates are coalesced
+    /// before routes are recomputed. Updates are applied immediately when
+    /// zero.
+    pub route_update_debounce: Duration,
+
+    /// Whether ServiceProfiles are discovered for sidecar connections.
+    /// Otherwise, no profile lookups are issued and routes are configured
+    /// exclusively by client policies.
+    pub discover_profiles: bool,
+
+    /// The maximum number of distinct logical names, discovered from
+    /// ServiceProfiles, with which metrics are labeled for HTTP destinations
+    /// that client policy does not name, e.g. off-cluster authorities.
+    pub logical_name_labels_limit: usize,
+
     // In "ingress mode", we assume we are always routing HTTP requests and do
     // not perform per-target-address discovery. Non-HTTP connections are
     // forwarded without discovery/routing/mTLS.
@@ -83,6 +187,100 @@ pub struct Config {
 
     // Whether the proxy may include informational headers on HTTP responses.
     pub emit_headers: bool,
+
+    /// Configures how request IDs are set on HTTP requests, if at all.
+    pub http_request_id: Option<request_id::Config>,
+
+    /// Configures how request deadlines are propagated, if at all.
+    pub http_deadline: Option<stream_timeouts::DeadlineConfig>,
+
+    /// Signs the workload identity assertions set by routes with an
+    /// `AssertWorkloadIdentity` filter, if configured.
+    pub http_workload_identity: Option<http::workload_identity::Signer>,
+
+    /// Configures latency-based outlier detection for load balancers, if at
+    /// all.
+    pub http_latency_outliers: Option<http::LatencyOutlierConfig>,
+
+    /// Configures which HTTP balancers dispatch each client's requests to the
+    /// endpoint most recently selected for it, if any.
+    pub http_source_affinity: Option<http::concrete::SourceAffinityConfig>,
+
+    /// Configures whether HTTP requests to endpoints on the local workload are
+    /// forwarded directly to the application over the loopback interface. When
+    /// unset, these requests are sent to the endpoint's address.
+    pub http_hairpin: Option<http::concrete::HairpinConfig>,
+
+    /// Enables adaptive HTTP/2 flow control on connections to meshed
+    /// endpoints, so that windows are sized from each connection's estimated
+    /// bandwidth-delay product rather than the static connect settings.
+    pub http2_mesh_adaptive_flow_control: bool,
+
+    /// The total number of bytes that may be buffered, across all requests,
+    /// so that request bodies can be replayed on retries.
+    pub http_retry_buffer_bytes: usize,
+
+    /// The total number of bytes that may be used to cache responses for
+    /// routes that enable caching.
+    pub http_response_cache_bytes: usize,
+
+    /// Seeds the generator that decides which requests are faulted by routes
+    /// that inject faults, if set.
+    pub http_fault_injection_seed: Option<u64>,
+
+    /// Bounds each attempt of retryable ServiceProfile routes, if set.
+    pub http_profile_retry_timeout: Option<Duration>,
+
+    /// Whether requests may override their route configuration with an
+    /// `l5d-route-debug` header. The header is stripped from requests either
+    /// way.
+    pub http_route_debug_header: bool,
+
+    /// When set, meshed endpoints without a protocol hint are probed for
+    /// HTTP/2 upgrade support, and each result is cached for this duration.
+    pub http_upgrade_probe_ttl: Option<Duration>,
+
+    /// When set, HTTP requests whose responses take longer than this are
+    /// written to the access log, and annotated on their spans, with the time
+    /// spent in each layer of the stack.
+    pub http_request_timings_threshold: Option<Duration>,
+
+    /// Requests that do not keep cached logical HTTP services alive, e.g.
+    /// health checks. When set, services are evicted once they have not
+    /// served other requests for the discovery idle timeout, even if
+    /// connections hold them.
+    pub http_keepalive_exempt: Vec<http::KeepaliveExempt>,
+
+    /// When set, cached logical HTTP services are rebuilt once they are this
+    /// old, regardless of activity.
+    pub http_logical_max_age: Option<Duration>,
+
+    /// Limits the request body data that endpoint clients may buffer for each
+    /// request before the request body is no longer read from the downstream.
+    pub http_request_body_buffer: http::BodyBufferLimits,
+
+    /// Configures a listener on which the proxy accepts explicitly-addressed
+    /// traffic (absolute-form HTTP requests and `CONNECT` tunnels), if at all.
+    pub explicit_proxy: Option<ServerConfig>,
+
+    /// Configures a listener on which the proxy accepts SOCKS5 connections, if
+    /// at all.
+    pub socks5_proxy: Option<Socks5Config>,
+
+    /// Configures destinations that are discovered when the proxy starts.
+    pub prewarm: PrewarmConfig,
+
+    /// Configures the destinations with which sidecar connections to mapped
+    /// ports are discovered, in the order in which they apply.
+    pub port_mappings: Vec<PortMapping>,
+
+    /// Configures how connections accepted on the outbound listener are
+    /// handled.
+    pub listener: ListenerOverrides,
+
+    /// Configures additional listeners on which the proxy accepts outbound
+    /// connections, each with its own overrides.
+    pub additional_listeners: Vec<ListenerConfig>,
 }
 
 #[derive(Clone, Debug)]
@@ -99,6 +297,13 @@ struct Runtime {
     tap: tap::Registry,
     span_sink: Option<SpanSink>,
     drain: drain::Watch,
+    tunables: TunablesRx,
+    discovery_retention: Option<Arc<Periodic<OrigDstAddr>>>,
+    discovery_snapshot: Option<DiscoverySnapshot>,
+    upgrade_probes: Option<http::UpgradeProbes>,
+    route_updates: RouteUpdates,
+    port_mappings: PortMappings,
+    logical_names: metrics::LogicalNameLabels,
 }
 
 pub type ConnectMeta = TlsConnectMeta<Local<ClientAddr>>;
@@ -123,12 +328,46 @@ pub struct EndpointRef(Arc<policy::Meta>);
 
 impl Outbound<()> {
     pub fn new(config: Config, runtime: ProxyRuntime, prom: &mut prom::Registry) -> Self {
+        let mut metrics = OutboundMetrics::new(runtime.metrics, prom);
+        // All routes share a single budget for buffering retryable requests.
+        metrics.prom.http = metrics
+            .prom
+            .http
+            .with_retry_buffer_budget(BufferBudget::new(config.http_retry_buffer_bytes))
+            // All routes share a single response cache.
+            .with_response_cache(http::policy::ResponseCache::new(
+                config.http_response_cache_bytes,
+            ))
+            // All routes share a single generator for injecting faults.
+            .with_fault_rng(http::policy::FaultRng::new(
+                config.http_fault_injection_seed,
+            ));
+        let upgrade_probes = config
+            .http_upgrade_probe_ttl
+            .map(|ttl| http::UpgradeProbes::new(ttl, metrics.prom.http.upgrade_probes().clone()));
+        let route_updates = RouteUpdates::new(
+            config.route_update_debounce,
+            metrics.prom.route_updates.clone(),
+        );
+        let port_mappings = PortMappings::new(&config.port_mappings, &metrics.prom.port_mappings);
         let runtime = Runtime {
-            metrics: OutboundMetrics::new(runtime.metrics, prom),
+            metrics,
             identity: runtime.identity.new_client(),
             tap: runtime.tap,
             span_sink: runtime.span_sink,
             drain: runtime.drain,
+            tunables: runtime.tunables,
+            discovery_retention: config
+                .discovery_retention
+                .map(|config| Arc::new(Periodic::new(config))),
+            discovery_snapshot: config
+                .discovery_snapshot
+                .clone()
+                .map(DiscoverySnapshot::load),
+            upgrade_probes,
+            route_updates,
+            port_mappings,
+            logical_names: metrics::LogicalNameLabels::new(config.logical_name_labels_limit),
         };
         Self {
             config,
@@ -143,7 +382,7 @@ impl Outbound<()> {
         client: C,
         backoff: ExponentialBackoff,
         limits: ReceiveLimits,
-        export_hostname_labels: bool,
+        overrides: policy::ClientPolicyOverrides,
     ) -> impl policy::GetPolicy
     where
         C: tonic::client::GrpcService<tonic::body::BoxBody, Error = Error>,
@@ -152,18 +391,33 @@ impl Outbound<()> {
         C::ResponseBody: Send + 'static,
         C::Future: Send,
     {
-        policy::Api::new(
+        let watch = policy::Api::new(
             workload,
             limits,
             Duration::from_secs(10),
-            export_hostname_labels,
+            overrides.clone(),
             client,
         )
+        .with_snapshot(self.runtime.discovery_snapshot.clone())
         .into_watch(backoff)
         .map_result(|res| match res {
             Err(e) => Err(e.into()),
             Ok(rsp) => Ok(rsp.into_inner()),
-        })
+        });
+
+        // Parents in the discovery snapshot are served provisionally while
+        // their policies are discovered.
+        let queue = self.config.tcp_connection_queue;
+        snapshot::ProvisionalPolicies::new(
+            watch,
+            self.runtime.discovery_snapshot.clone(),
+            overrides,
+            policy::Queue {
+                capacity: queue.capacity,
+                failfast_timeout: queue.failfast_timeout,
+            },
+            self.config.proxy.detect_protocol_timeout,
+        )
     }
 
     #[cfg(any(test, feature = "test-util"))]
@@ -187,6 +441,29 @@ impl<S> Outbound<S> {
         self.runtime.metrics.clone()
     }
 
+    /// Returns the tracker of sidecar discovery results that are retained
+    /// because their addresses are accessed periodically, if enabled.
+    pub fn discovery_retention(&self) -> Option<Arc<Periodic<OrigDstAddr>>> {
+        self.runtime.discovery_retention.clone()
+    }
+
+    /// Returns the port mappings that are applied when sidecar destinations
+    /// are discovered.
+    pub fn port_mappings(&self) -> PortMappings {
+        self.runtime.port_mappings.clone()
+    }
+
+    /// Returns the snapshot of discovery results that is persisted across
+    /// restarts, if enabled.
+    pub fn discovery_snapshot(&self) -> Option<DiscoverySnapshot> {
+        self.runtime.discovery_snapshot.clone()
+    }
+
+    /// Returns the spawner of route watches, which coalesces route updates.
+    pub fn route_updates(&self) -> RouteUpdates {
+        self.runtime.route_updates.clone()
+    }
+
     pub fn stack_metrics(&self) -> metrics::Stack {
         self.runtime.metrics.proxy.stack.clone()
     }
@@ -242,7 +519,7 @@ impl Outbound<()> {
         T: svc::Param<OrigDstAddr>,
         T: Clone + Send + Sync + 'static,
         // Server-side socket.
-        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr,
+        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr + io::Splice,
         I: Debug + Unpin + Send + Sync + 'static,
         // Endpoint resolution.
         R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
@@ -251,6 +528,9 @@ impl Outbound<()> {
         let profiles = profiles::WithAllowlist::new(profiles, self.config.allow_discovery.clone());
         if self.config.ingress_mode {
             tracing::info!("Outbound routing in ingress-mode");
+            if !self.config.prewarm.addrs.is_empty() {
+                tracing::warn!("Outbound destinations are not prewarmed in ingress-mode");
+            }
             self.mk_ingress(profiles, policies, resolve)
         } else {
             self.mk_sidecar(profiles, policies, resolve)
@@ -0,0 +1,576 @@
+//! Recycles outbound connections that exceed a maximum lifetime.
+//!
+//! Some load balancers silently stop routing long-lived connections, e.g.
+//! after their targets are rebalanced. Connections to destinations in
+//! configured networks are therefore replaced after a maximum lifetime:
+//!
+//! - HTTP clients stop dispatching new requests on an expired connection and
+//!   establish a new one, while requests in flight on the old connection are
+//!   allowed to complete.
+//! - Opaque and TLS-forwarded connections cannot be replaced transparently
+//!   while bytes are in flight, so an expired connection is closed once it has
+//!   been quiet--i.e. no bytes have been read or written--for a short period.
+//!   Clients establish replacements as they reconnect.
+//!
+//! Each connection's lifetime is shortened by a random jitter so that
+//! connections established together are not recycled together.
+
+use linkerd_app_core::{
+    io,
+    metrics::prom,
+    svc::{self, Param},
+    transport::{Remote, ServerAddr},
+    IpNet,
+};
+use pin_project::pin_project;
+use rand::Rng;
+use std::{
+    future::Future,
+    net::IpAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+use tokio::time::{self, Duration};
+use tracing::debug;
+
+/// Configures the maximum lifetimes of connections by destination network.
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionLifetimes {
+    /// Sorted so that the most specific network is matched first.
+    networks: Arc<[(IpNet, Duration)]>,
+    jitter: f64,
+    quiet: Duration,
+}
+
+/// The maximum lifetime of connections to a destination.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct Lifetime {
+    max: Duration,
+    jitter: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LifetimeMetrics {
+    recycled: prom::Family<RecycleLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct RecycleLabels {
+    protocol: Protocol,
+    reason: Reason,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum Protocol {
+    http,
+    tcp,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum Reason {
+    /// The connection exceeded its maximum lifetime.
+    max_lifetime,
+}
+
+/// Replaces an HTTP client once its connection exceeds its lifetime.
+#[derive(Clone, Debug)]
+pub(crate) struct NewRecycle<N> {
+    lifetimes: ConnectionLifetimes,
+    recycled: prom::Counter,
+    inner: N,
+}
+
+#[derive(Debug)]
+pub(crate) struct Recycle<T, N, S> {
+    lifetime: Option<Lifetime>,
+    /// Set once the current client becomes ready.
+    expiry: Option<time::Instant>,
+    recycled: prom::Counter,
+    target: T,
+    new_client: N,
+    client: S,
+}
+
+/// Wraps opaque connections so that they are closed once they exceed their
+/// lifetime.
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectLifetime<C> {
+    lifetimes: ConnectionLifetimes,
+    recycled: prom::Counter,
+    inner: C,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct ConnectFuture<F> {
+    #[pin]
+    inner: F,
+    expiry: Option<(Lifetime, Duration, prom::Counter)>,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct LifetimeIo<I> {
+    #[pin]
+    io: I,
+    expiry: Option<Expiry>,
+}
+
+#[derive(Debug)]
+struct Expiry {
+    timer: Pin<Box<time::Sleep>>,
+    quiet: Duration,
+    last_active: time::Instant,
+    expired: bool,
+    recycled: prom::Counter,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("connection exceeded its maximum lifetime")]
+pub struct ConnectionExpired(());
+
+// === impl ConnectionLifetimes ===
+
+impl ConnectionLifetimes {
+    /// Configures the maximum lifetime of connections to each network.
+    ///
+    /// Each lifetime is shortened by a random fraction of up to `jitter`, and
+    /// opaque connections are closed once no bytes have been transferred for
+    /// the `quiet` period.
+    pub fn new(
+        networks: impl IntoIterator<Item = (IpNet, Duration)>,
+        jitter: f64,
+        quiet: Duration,
+    ) -> Self {
+        let mut networks = networks.into_iter().collect::<Vec<_>>();
+        networks.sort_by_key(|(net, _)| std::cmp::Reverse(net.prefix_len()));
+        Self {
+            networks: networks.into(),
+            jitter: jitter.clamp(0.0, 1.0),
+            quiet,
+        }
+    }
+
+    /// Returns the lifetime of connections to `ip`, if one is configured.
+    pub(crate) fn get(&self, ip: IpAddr) -> Option<Lifetime> {
+        self.networks
+            .iter()
+            .find(|(net, _)| net.contains(&ip))
+            .map(|(_, max)| Lifetime {
+                max: *max,
+                jitter: self.jitter,
+            })
+    }
+}
+
+// === impl Lifetime ===
+
+impl Lifetime {
+    /// Returns the lifetime of a single connection.
+    fn sample(&self) -> Duration {
+        if self.jitter == 0.0 {
+            return self.max;
+        }
+        let jitter = rand::rng().random_range(0.0..=self.jitter);
+        self.max.mul_f64(1.0 - jitter)
+    }
+}
+
+// === impl LifetimeMetrics ===
+
+impl LifetimeMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let recycled = prom::Family::default();
+        registry.register(
+            "connection_recycles",
+            "The number of connections that were recycled",
+            recycled.clone(),
+        );
+        Self { recycled }
+    }
+
+    pub(crate) fn http(&self) -> prom::Counter {
+        self.counter(Protocol::http, Reason::max_lifetime)
+    }
+
+    pub(crate) fn tcp(&self) -> prom::Counter {
+        self.counter(Protocol::tcp, Reason::max_lifetime)
+    }
+
+    fn counter(&self, protocol: Protocol, reason: Reason) -> prom::Counter {
+        self.recycled
+            .get_or_create(&RecycleLabels { protocol, reason })
+            .clone()
+    }
+}
+
+// === impl NewRecycle ===
+
+impl<N> NewRecycle<N> {
+    pub(crate) fn layer(
+        lifetimes: ConnectionLifetimes,
+        recycled: prom::Counter,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            lifetimes: lifetimes.clone(),
+            recycled: recycled.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewRecycle<N>
+where
+    T: Param<Remote<ServerAddr>> + Clone,
+    N: svc::NewService<T> + Clone,
+{
+    type Service = Recycle<T, N, N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Remote(ServerAddr(addr)) = target.param();
+        Recycle {
+            lifetime: self.lifetimes.get(addr.ip()),
+            expiry: None,
+            recycled: self.recycled.clone(),
+            client: self.inner.new_service(target.clone()),
+            new_client: self.inner.clone(),
+            target,
+        }
+    }
+}
+
+// === impl Recycle ===
+
+impl<T, N, S, Req> svc::Service<Req> for Recycle<T, N, S>
+where
+    T: Clone,
+    N: svc::NewService<T, Service = S>,
+    S: svc::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        if self
+            .expiry
+            .is_some_and(|expiry| expiry <= time::Instant::now())
+        {
+            // Dropping the client stops new requests from being dispatched on
+            // its connection, which is closed once its requests complete.
+            debug!("Connection exceeded its maximum lifetime; reconnecting");
+            self.client = self.new_client.new_service(self.target.clone());
+            self.expiry = None;
+            self.recycled.inc();
+        }
+
+        ready!(self.client.poll_ready(cx))?;
+        if self.expiry.is_none() {
+            self.expiry = self
+                .lifetime
+                .map(|lifetime| time::Instant::now() + lifetime.sample());
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    #[inline]
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.client.call(req)
+    }
+}
+
+// === impl ConnectLifetime ===
+
+impl<C> ConnectLifetime<C> {
+    pub(crate) fn layer(
+        lifetimes: ConnectionLifetimes,
+        recycled: prom::Counter,
+    ) -> impl svc::layer::Layer<C, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            lifetimes: lifetimes.clone(),
+            recycled: recycled.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, C> svc::Service<T> for ConnectLifetime<C>
+where
+    T: Param<Remote<ServerAddr>>,
+    C: svc::MakeConnection<T>,
+{
+    type Response = (LifetimeIo<C::Connection>, C::Metadata);
+    type Error = C::Error;
+    type Future = ConnectFuture<C::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        let Remote(ServerAddr(addr)) = target.param();
+        let expiry = self
+            .lifetimes
+            .get(addr.ip())
+            .map(|lifetime| (lifetime, self.lifetimes.quiet, self.recycled.clone()));
+        ConnectFuture {
+            inner: self.inner.connect(target),
+            expiry,
+        }
+    }
+}
+
+impl<F, I, M, E> Future for ConnectFuture<F>
+where
+    F: Future<Output = Result<(I, M), E>>,
+{
+    type Output = Result<(LifetimeIo<I>, M), E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let (io, meta) = ready!(this.inner.poll(cx))?;
+        // The lifetime starts once the connection is established.
+        let expiry = this
+            .expiry
+            .take()
+            .map(|(lifetime, quiet, recycled)| Expiry::new(lifetime.sample(), quiet, recycled));
+        Poll::Ready(Ok((LifetimeIo { io, expiry }, meta)))
+    }
+}
+
+// === impl LifetimeIo ===
+
+impl<I: io::AsyncRead> io::AsyncRead for LifetimeIo<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> io::Poll<()> {
+        let this = self.project();
+        let Some(expiry) = this.expiry else {
+            return this.io.poll_read(cx, buf);
+        };
+        // Reads are polled for the duration of the connection, so the
+        // connection's expiry is enforced as it is read.
+        if expiry.poll_expired(cx) {
+            return Poll::Ready(Err(io::Error::other(ConnectionExpired(()))));
+        }
+        let filled = buf.filled().len();
+        ready!(this.io.poll_read(cx, buf))?;
+        if buf.filled().len() > filled {
+            expiry.record_activity();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<I: io::AsyncWrite> io::AsyncWrite for LifetimeIo<I> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> io::Poll<usize> {
+        let this = self.project();
+        let Some(expiry) = this.expiry else {
+            return this.io.poll_write(cx, buf);
+        };
+        if expiry.expired {
+            return Poll::Ready(Err(io::Error::other(ConnectionExpired(()))));
+        }
+        let sz = ready!(this.io.poll_write(cx, buf))?;
+        if sz > 0 {
+            expiry.record_activity();
+        }
+        Poll::Ready(Ok(sz))
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        self.project().io.poll_shutdown(cx)
+    }
+}
+
+impl<I: io::Splice> io::Splice for LifetimeIo<I> {
+    fn splice_socket(&self) -> Option<&tokio::net::TcpStream> {
+        // Spliced bytes bypass this I/O, so the lifetime of a spliced
+        // connection could not be enforced.
+        if self.expiry.is_some() {
+            return None;
+        }
+        self.io.splice_socket()
+    }
+
+    fn take_prefix(&mut self) -> bytes::Bytes {
+        self.io.take_prefix()
+    }
+
+    fn record_spliced(&mut self, read: usize, written: usize) {
+        self.io.record_spliced(read, written)
+    }
+}
+
+// === impl Expiry ===
+
+impl Expiry {
+    fn new(lifetime: Duration, quiet: Duration, recycled: prom::Counter) -> Self {
+        let now = time::Instant::now();
+        Self {
+            timer: Box::pin(time::sleep_until(now + lifetime)),
+            quiet,
+            last_active: now,
+            expired: false,
+            recycled,
+        }
+    }
+
+    /// Returns true once the connection has exceeded its lifetime and has
+    /// since been quiet.
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> bool {
+        if self.expired {
+            return true;
+        }
+        loop {
+            if self.timer.as_mut().poll(cx).is_pending() {
+                return false;
+            }
+            let quiet_at = self.last_active + self.quiet;
+            if quiet_at <= time::Instant::now() {
+                debug!("Connection exceeded its maximum lifetime; closing");
+                self.expired = true;
+                self.recycled.inc();
+                return true;
+            }
+            // Wait until the connection may have become quiet.
+            self.timer.as_mut().reset(quiet_at);
+        }
+    }
+
+    fn record_activity(&mut self) {
+        self.last_active = time::Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use io::{AsyncReadExt, AsyncWriteExt};
+    use linkerd_app_core::Error;
+    use std::{
+        net::SocketAddr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use svc::{NewService, Service, ServiceExt};
+
+    #[derive(Clone, Debug)]
+    struct Target(SocketAddr);
+
+    impl Param<Remote<ServerAddr>> for Target {
+        fn param(&self) -> Remote<ServerAddr> {
+            Remote(ServerAddr(self.0))
+        }
+    }
+
+    fn lifetimes(max: Duration) -> ConnectionLifetimes {
+        ConnectionLifetimes::new(
+            [
+                ("10.0.0.0/8".parse().unwrap(), Duration::from_secs(600)),
+                ("10.1.0.0/16".parse().unwrap(), max),
+            ],
+            0.0,
+            Duration::from_secs(1),
+        )
+    }
+
+    #[test]
+    fn matches_most_specific_network() {
+        let lifetimes = lifetimes(Duration::from_secs(60));
+        assert_eq!(
+            lifetimes.get([10, 1, 2, 3].into()).map(|l| l.max),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            lifetimes.get([10, 2, 3, 4].into()).map(|l| l.max),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(lifetimes.get([192, 168, 0, 1].into()), None);
+    }
+
+    #[test]
+    fn jitter_shortens_lifetimes() {
+        let lifetime = Lifetime {
+            max: Duration::from_secs(100),
+            jitter: 0.1,
+        };
+        for _ in 0..100 {
+            let sample = lifetime.sample();
+            assert!(sample <= Duration::from_secs(100));
+            assert!(sample >= Duration::from_secs(90));
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn recycles_http_clients() {
+        let built = Arc::new(AtomicUsize::new(0));
+        let metrics = LifetimeMetrics::default();
+        let new_recycle = NewRecycle {
+            lifetimes: lifetimes(Duration::from_secs(60)),
+            recycled: metrics.http(),
+            inner: {
+                let built = built.clone();
+                move |_: Target| {
+                    let id = built.fetch_add(1, Ordering::SeqCst);
+                    svc::mk(move |()| futures::future::ok::<_, Error>(id))
+                }
+            },
+        };
+
+        let mut svc = new_recycle.new_service(Target(([10, 1, 0, 1], 8080).into()));
+        assert_eq!(svc.ready().await.unwrap().call(()).await.unwrap(), 0);
+        time::sleep(Duration::from_secs(30)).await;
+        assert_eq!(svc.ready().await.unwrap().call(()).await.unwrap(), 0);
+        time::sleep(Duration::from_secs(31)).await;
+        assert_eq!(svc.ready().await.unwrap().call(()).await.unwrap(), 1);
+        assert_eq!(metrics.http().get(), 1);
+
+        // Connections to other networks are not recycled.
+        let mut svc = new_recycle.new_service(Target(([192, 168, 0, 1], 8080).into()));
+        assert_eq!(svc.ready().await.unwrap().call(()).await.unwrap(), 2);
+        time::sleep(Duration::from_secs(3600)).await;
+        assert_eq!(svc.ready().await.unwrap().call(()).await.unwrap(), 2);
+        assert_eq!(metrics.http().get(), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn closes_opaque_connections_when_quiet() {
+        let recycled = prom::Counter::default();
+        let (client, mut server) = io::duplex(64);
+        let mut io = LifetimeIo {
+            io: client,
+            expiry: Some(Expiry::new(
+                Duration::from_secs(60),
+                Duration::from_secs(1),
+                recycled.clone(),
+            )),
+        };
+
+        // Keep the connection active beyond its lifetime.
+        let mut buf = [0u8; 4];
+        for _ in 0..70 {
+            server.write_all(b"ping").await.unwrap();
+            io.read_exact(&mut buf).await.unwrap();
+            time::sleep(Duration::from_millis(900)).await;
+        }
+        assert_eq!(recycled.get(), 0, "active connections must not be closed");
+
+        // Once the connection is quiet, it is closed.
+        let err = io.read(&mut buf).await.unwrap_err();
+        assert!(err.get_ref().is_some_and(|e| e.is::<ConnectionExpired>()));
+        assert!(io.write_all(b"pong").await.is_err());
+        assert_eq!(recycled.get(), 1);
+    }
+}
@@ -0,0 +1,169 @@
+//! Additional outbound listeners.
+//!
+//! The proxy may accept outbound connections on listeners other than its
+//! primary outbound listener, e.g. so that an application can send traffic
+//! that should bypass protocol detection or discovery to a dedicated port.
+//! Each listener overrides how its connections are handled; listeners with
+//! the same overrides share a stack, and therefore its discovery caches.
+
+use crate::{policy, Outbound};
+use linkerd_app_core::{
+    config::ServerConfig,
+    io,
+    metrics::prom,
+    profiles,
+    proxy::{
+        api_resolve::{ConcreteAddr, Metadata},
+        core::Resolve,
+    },
+    svc,
+    transport::addrs::*,
+    Error,
+};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+// The tests drive stacks with in-memory discovery fixtures.
+#[cfg(all(test, feature = "test-util"))]
+mod tests;
+
+/// Configures an additional listener for outbound connections.
+#[derive(Clone, Debug)]
+pub struct ListenerConfig {
+    /// Identifies the listener in metrics and logs.
+    pub name: Arc<str>,
+    pub server: ServerConfig,
+    pub overrides: ListenerOverrides,
+}
+
+/// Configures how the connections accepted by an outbound listener are
+/// handled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ListenerOverrides {
+    /// Whether the protocol of a connection is detected when it is not known
+    /// from discovery. Otherwise, such connections are handled as opaque.
+    pub detect_protocol: bool,
+
+    /// Whether destinations are discovered. Otherwise, connections are
+    /// forwarded to their original destination address.
+    pub discover: bool,
+}
+
+/// Counts the connections accepted on each additional listener.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ListenerMetrics {
+    connections: prom::Family<ListenerLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct ListenerLabels {
+    listener: String,
+}
+
+// === impl Outbound ===
+
+impl Outbound<()> {
+    /// Builds a stack for each of the configured additional listeners, in
+    /// order.
+    ///
+    /// Listeners whose overrides match the outbound configuration's share the
+    /// `primary` stack. Otherwise, a stack is built for each distinct set of
+    /// overrides.
+    pub fn mk_listeners<T, I, R>(
+        &self,
+        primary: &svc::ArcNewTcp<T, I>,
+        profiles: impl profiles::GetProfile<Error = Error>,
+        policies: impl policy::GetPolicy,
+        resolve: R,
+    ) -> Vec<svc::ArcNewTcp<T, I>>
+    where
+        // Target describing an outbound connection.
+        T: svc::Param<OrigDstAddr>,
+        T: Clone + Send + Sync + 'static,
+        // Server-side socket.
+        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr + io::Splice,
+        I: Debug + Unpin + Send + Sync + 'static,
+        // Endpoint resolver.
+        R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
+        R::Resolution: Unpin,
+    {
+        if self.config.ingress_mode && !self.config.additional_listeners.is_empty() {
+            tracing::warn!("Outbound listener overrides are ignored in ingress-mode");
+        }
+        self.build_listeners(primary, |outbound| {
+            outbound.mk(profiles.clone(), policies.clone(), resolve.clone())
+        })
+    }
+
+    pub(crate) fn build_listeners<T, I>(
+        &self,
+        primary: &svc::ArcNewTcp<T, I>,
+        mut mk: impl FnMut(Self) -> svc::ArcNewTcp<T, I>,
+    ) -> Vec<svc::ArcNewTcp<T, I>>
+    where
+        T: Clone + Send + Sync + 'static,
+        I: Send + 'static,
+    {
+        let mut stacks = HashMap::new();
+        stacks.insert(self.config.listener, primary.clone());
+
+        let metrics = &self.runtime.metrics.prom.listener;
+        self.config
+            .additional_listeners
+            .iter()
+            .map(|listener| {
+                let stack = stacks
+                    .entry(listener.overrides)
+                    .or_insert_with(|| {
+                        tracing::debug!(listener = %listener.name, overrides = ?listener.overrides, "Building outbound stack");
+                        let mut outbound = self.clone();
+                        outbound.config.listener = listener.overrides;
+                        // Destinations are only prewarmed by the primary stack.
+                        outbound.config.prewarm = Default::default();
+                        mk(outbound)
+                    })
+                    .clone();
+                let connections = metrics.connections(&listener.name);
+                svc::stack(stack)
+                    .push_map_target(move |t: T| {
+                        connections.inc();
+                        t
+                    })
+                    .arc_new_tcp()
+                    .into_inner()
+            })
+            .collect()
+    }
+}
+
+// === impl ListenerOverrides ===
+
+impl Default for ListenerOverrides {
+    fn default() -> Self {
+        Self {
+            detect_protocol: true,
+            discover: true,
+        }
+    }
+}
+
+// === impl ListenerMetrics ===
+
+impl ListenerMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let connections = prom::Family::default();
+        registry.register(
+            "listener_connections",
+            "The number of connections accepted on each additional outbound listener",
+            connections.clone(),
+        );
+        Self { connections }
+    }
+
+    fn connections(&self, name: &str) -> prom::Counter {
+        self.connections
+            .get_or_create(&ListenerLabels {
+                listener: name.to_string(),
+            })
+            .clone()
+    }
+}
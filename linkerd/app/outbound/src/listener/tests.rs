@@ -0,0 +1,131 @@
+use super::*;
+use crate::test_util::{fixture::Fixture, *};
+use http_body_util::Empty;
+use hyper_util::rt::TokioIo;
+use linkerd_app_core::{
+    drain,
+    svc::{NewService, ServiceExt},
+};
+use std::net::SocketAddr;
+
+const PARENT: &str = "10.0.0.1:8080";
+
+/// Only requests for `/api` are routed, so requests for other paths fail
+/// unless the connection is handled as opaque.
+const FIXTURE: &str = r#"{
+    "parents": [{
+        "addr": "10.0.0.1:8080",
+        "name": "web.ns.svc.cluster.local:8080",
+        "routes": [{
+            "path_prefix": "/api",
+            "backends": [{ "name": "web.ns.svc.cluster.local:8080" }]
+        }]
+    }],
+    "endpoints": {
+        "web.ns.svc.cluster.local:8080": ["10.1.0.1:8080"]
+    }
+}"#;
+
+#[tokio::test(flavor = "current_thread")]
+async fn listeners_override_detection() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let fixture = Fixture::from_json(FIXTURE).unwrap();
+    let (outbound, _drain) = with_listeners([
+        listener("opaque", false, true),
+        listener("default", true, true),
+        listener("forward", true, false),
+    ]);
+    let primary = fixture.mk_sidecar(&outbound);
+    let listeners = fixture.mk_listeners(&outbound, &primary);
+    let [opaque, default, forward] = &listeners[..] else {
+        panic!("a stack must be built for each listener");
+    };
+
+    // The primary listener detects HTTP and routes requests.
+    assert_eq!(get(&primary, "/api").await, http::StatusCode::OK);
+    assert_ne!(get(&primary, "/other").await, http::StatusCode::OK);
+    assert_eq!(get(default, "/api").await, http::StatusCode::OK);
+    assert_ne!(get(default, "/other").await, http::StatusCode::OK);
+
+    // Without detection, connections are forwarded to the route's backend
+    // without routing requests.
+    assert_eq!(get(opaque, "/api").await, http::StatusCode::OK);
+    assert_eq!(get(opaque, "/other").await, http::StatusCode::OK);
+
+    // Without discovery, connections are forwarded to the parent address,
+    // which has no endpoint.
+    assert_ne!(get(forward, "/api").await, http::StatusCode::OK);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn listeners_share_stacks() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let fixture = Fixture::from_json(FIXTURE).unwrap();
+    let (outbound, _drain) = with_listeners([
+        listener("opaque-a", false, true),
+        listener("default", true, true),
+        listener("opaque-b", false, true),
+    ]);
+    let primary = fixture.mk_sidecar::<io::DuplexStream>(&outbound);
+
+    let mut built = Vec::new();
+    let listeners = outbound.build_listeners(&primary, |outbound| {
+        built.push(outbound.config().listener);
+        fixture.mk_sidecar(&outbound)
+    });
+    assert_eq!(listeners.len(), 3);
+    assert_eq!(
+        built,
+        [ListenerOverrides {
+            detect_protocol: false,
+            discover: true,
+        }],
+        "stacks must only be built for novel overrides"
+    );
+}
+
+fn listener(name: &str, detect_protocol: bool, discover: bool) -> ListenerConfig {
+    ListenerConfig {
+        name: name.into(),
+        server: default_config().proxy.server,
+        overrides: ListenerOverrides {
+            detect_protocol,
+            discover,
+        },
+    }
+}
+
+fn with_listeners(
+    listeners: impl IntoIterator<Item = ListenerConfig>,
+) -> (Outbound<()>, drain::Signal) {
+    let (mut outbound, drain) = Outbound::for_test();
+    outbound.config_mut().additional_listeners = listeners.into_iter().collect();
+    (outbound, drain)
+}
+
+/// Sends an HTTP/1 request for `path` on a connection served by `stack`.
+async fn get(
+    stack: &svc::ArcNewTcp<OrigDstAddr, io::DuplexStream>,
+    path: &str,
+) -> http::StatusCode {
+    let (client, server) = io::duplex(64 * 1024);
+    let parent = PARENT.parse::<SocketAddr>().unwrap();
+    tokio::spawn(stack.new_service(OrigDstAddr(parent)).oneshot(server));
+
+    let (mut client, conn) = hyper::client::conn::http1::handshake(TokioIo::new(client))
+        .await
+        .expect("handshake");
+    tokio::spawn(conn);
+    let req = http::Request::get(format!("http://web.ns.svc.cluster.local:8080{path}"))
+        .body(Empty::<bytes::Bytes>::new())
+        .unwrap();
+    match client.send_request(req).await {
+        Ok(rsp) => rsp.status(),
+        Err(error) => {
+            tracing::info!(%error, "Request failed");
+            http::StatusCode::BAD_GATEWAY
+        }
+    }
+}
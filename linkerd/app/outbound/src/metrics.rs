@@ -11,9 +11,11 @@
 use crate::{policy, BackendRef, ParentRef, RouteRef};
 use linkerd_app_core::{
     metrics::prom::{encoding::*, EncodeLabelSetMut},
-    svc,
+    proxy::tcp,
+    svc, NameAddr,
 };
-use std::fmt::Write;
+use parking_lot::Mutex;
+use std::{collections::HashSet, fmt::Write, sync::Arc};
 
 pub(crate) mod error;
 pub(crate) mod transport;
@@ -42,6 +44,18 @@ pub(crate) struct PromMetrics {
     pub(crate) opaq: crate::opaq::OpaqMetrics,
     pub(crate) tls: crate::tls::TlsMetrics,
     pub(crate) zone: crate::zone::TcpZoneMetrics,
+    pub(crate) socks5: crate::socks5::Socks5Metrics,
+    pub(crate) prewarm: crate::prewarm::PrewarmMetrics,
+    pub(crate) port_mappings: crate::port_map::PortMappingMetrics,
+    pub(crate) listener: crate::listener::ListenerMetrics,
+    pub(crate) tcp_close: tcp::CloseMetrics,
+    pub(crate) transport_errors: crate::tcp::TransportErrorMetrics,
+    pub(crate) lifetime: crate::lifetime::LifetimeMetrics,
+    pub(crate) topology: crate::topology::TopologyHintMetrics,
+    pub(crate) route_updates: crate::route_updates::RouteUpdateMetrics,
+    pub(crate) stack_builds: StackBuildMetrics,
+    pub(crate) forward: crate::discover::ForwardMetrics,
+    pub(crate) parents: crate::discover::ParentMetrics,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -50,6 +64,28 @@ pub struct ConcreteLabels(pub ParentRef, pub BackendRef);
 #[derive(Clone, Debug)]
 pub struct BalancerMetricsParams<K>(balance::MetricFamilies<K>);
 
+/// Bounds the number of distinct logical names that label metrics for
+/// destinations that are named only by their ServiceProfiles.
+///
+/// Names are never forgotten, since metric families retain their labels for
+/// the lifetime of the process.
+#[derive(Clone, Debug)]
+pub(crate) struct LogicalNameLabels {
+    limit: usize,
+    names: Arc<Mutex<HashSet<NameAddr>>>,
+}
+
+/// Counts the stacks that are being built in each cache.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StackBuildMetrics {
+    in_progress: prom::Family<StackBuildLabels, prom::Gauge>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct StackBuildLabels {
+    cache: String,
+}
+
 struct ScopedKey<'a, 'b>(&'a str, &'b str);
 
 // === impl BalancerMetricsParams ===
@@ -62,9 +98,19 @@ where
         Self(balance::MetricFamilies::register(reg))
     }
 
+    /// Configures whether balancers record each of their endpoints' load
+    /// estimates.
+    pub fn with_endpoint_load(self, enabled: bool) -> Self {
+        Self(self.0.with_endpoint_load(enabled))
+    }
+
     pub fn metrics(&self, labels: &K) -> balance::Metrics {
         self.0.metrics(labels)
     }
+
+    pub fn discovery_state(&self, labels: &K) -> Option<balance::DiscoveryState> {
+        self.0.discovery_state(labels)
+    }
 }
 
 impl<T> svc::ExtractParam<balance::Metrics, T> for BalancerMetricsParams<ConcreteLabels>
@@ -105,6 +151,21 @@ impl PromMetrics {
         let opaq = crate::opaq::OpaqMetrics::register(registry.sub_registry_with_prefix("tcp"));
         let zone = crate::zone::TcpZoneMetrics::register(registry.sub_registry_with_prefix("tcp"));
         let tls = crate::tls::TlsMetrics::register(registry.sub_registry_with_prefix("tls"));
+        let socks5 = crate::socks5::Socks5Metrics::register(registry);
+        let prewarm = crate::prewarm::PrewarmMetrics::register(registry);
+        let port_mappings = crate::port_map::PortMappingMetrics::register(registry);
+        let listener = crate::listener::ListenerMetrics::register(registry);
+        let tcp_close = tcp::CloseMetrics::register(registry.sub_registry_with_prefix("tcp"));
+        let transport_errors =
+            crate::tcp::TransportErrorMetrics::register(registry.sub_registry_with_prefix("tcp"));
+        let lifetime = crate::lifetime::LifetimeMetrics::register(registry);
+        let topology = crate::topology::TopologyHintMetrics::register(
+            registry.sub_registry_with_prefix("balancer_topology_hints"),
+        );
+        let route_updates = crate::route_updates::RouteUpdateMetrics::register(registry);
+        let stack_builds = StackBuildMetrics::register(registry);
+        let forward = crate::discover::ForwardMetrics::register(registry);
+        let parents = crate::discover::ParentMetrics::register(registry);
 
         Self {
             protocol,
@@ -113,10 +174,45 @@ impl PromMetrics {
             opaq,
             tls,
             zone,
+            socks5,
+            prewarm,
+            port_mappings,
+            listener,
+            tcp_close,
+            transport_errors,
+            lifetime,
+            topology,
+            route_updates,
+            stack_builds,
+            forward,
+            parents,
         }
     }
 }
 
+// === impl StackBuildMetrics ===
+
+impl StackBuildMetrics {
+    fn register(registry: &mut prom::Registry) -> Self {
+        let in_progress = prom::Family::default();
+        registry.register(
+            "stack_builds_in_progress",
+            "The number of stacks that are being built, by cache",
+            in_progress.clone(),
+        );
+        Self { in_progress }
+    }
+
+    /// Returns the gauge of stacks that are being built in `cache`.
+    pub(crate) fn in_progress(&self, cache: &str) -> prom::Gauge {
+        self.in_progress
+            .get_or_create(&StackBuildLabels {
+                cache: cache.to_string(),
+            })
+            .clone()
+    }
+}
+
 // === impl OutboundMetrics ===
 
 impl OutboundMetrics {
@@ -128,6 +224,21 @@ impl OutboundMetrics {
             prom: PromMetrics::register(registry),
         }
     }
+
+    /// Returns the registry of rollout guards for all HTTP and gRPC routes.
+    pub fn rollout_guards(&self) -> crate::http::policy::RolloutGuards {
+        self.prom.http.rollout_guards().clone()
+    }
+
+    /// Returns the registry of latency outlier detectors for all balancers.
+    pub fn breakers(&self) -> crate::http::Breakers {
+        self.prom.http.breakers().clone()
+    }
+
+    /// Returns the faults that are injected into requests to backends.
+    pub fn backend_faults(&self) -> crate::http::BackendFaults {
+        self.prom.http.backend_faults().clone()
+    }
 }
 
 impl legacy::FmtMetrics for OutboundMetrics {
@@ -241,6 +352,32 @@ impl EncodeLabelSet for RouteRef {
     }
 }
 
+// === impl LogicalNameLabels ===
+
+impl LogicalNameLabels {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            names: Default::default(),
+        }
+    }
+
+    /// Returns true if `name` may label metrics: either it already does, or
+    /// the limit has not yet been reached.
+    pub(crate) fn admit(&self, name: &NameAddr) -> bool {
+        let mut names = self.names.lock();
+        if names.contains(name) {
+            return true;
+        }
+        if names.len() >= self.limit {
+            tracing::debug!(%name, limit = self.limit, "Logical name label limit reached");
+            return false;
+        }
+        names.insert(name.clone());
+        true
+    }
+}
+
 // === impl ConcreteLabels ===
 
 impl legacy::FmtLabels for ConcreteLabels {
@@ -1,4 +1,7 @@
-use crate::{policy, service_meta, tcp, Outbound, ParentRef, UNKNOWN_META};
+use crate::{
+    policy, route_updates::Protocol, service_meta, tcp, Outbound, ParentRef, RouteUpdates,
+    UNKNOWN_META,
+};
 use linkerd_app_core::{
     io,
     metrics::prom,
@@ -17,6 +20,7 @@ use tokio::sync::watch;
 
 mod concrete;
 mod logical;
+mod retry;
 
 pub use self::logical::{route::filters::errors::*, Concrete, Logical, Routes};
 
@@ -27,6 +31,7 @@ struct Opaq<T>(T);
 pub struct OpaqMetrics {
     balance: concrete::BalancerMetrics,
     route: logical::route::TcpRouteMetrics,
+    connect_retries: retry::ConnectRetryMetrics,
 }
 
 // === impl Outbound ===
@@ -42,7 +47,7 @@ impl<C> Outbound<C> {
         T: Clone + Debug + PartialEq + Eq + Hash + Send + Sync + 'static,
         T: svc::Param<watch::Receiver<Routes>>,
         // Server-side connection
-        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr,
+        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + io::Splice,
         I: Debug + Send + Sync + Unpin + 'static,
         // Endpoint discovery
         R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
@@ -50,18 +55,21 @@ impl<C> Outbound<C> {
         // TCP endpoint stack.
         C: svc::MakeConnection<tcp::Connect, Metadata = Local<ClientAddr>, Error = io::Error>,
         C: Clone + Send + Sync + Unpin + 'static,
-        C::Connection: Send + Unpin,
+        C::Connection: io::Splice + Send + Unpin,
         C::Future: Send + Unpin,
     {
         self.push_tcp_endpoint()
             .push_opaq_concrete(resolve)
             .push_opaq_logical()
-            .map_stack(|config, _rt, stk| {
-                stk.push_new_idle_cached(config.discovery_idle_timeout)
-                    // Use a dedicated target type to configure parameters for
-                    // the opaque stack. It also helps narrow the cache key.
-                    .push_map_target(Opaq)
-                    .arc_new_clone_tcp()
+            .map_stack(|config, rt, stk| {
+                stk.push_new_idle_cached_with_builds(
+                    config.discovery_idle_timeout,
+                    rt.metrics.prom.stack_builds.in_progress("opaq"),
+                )
+                // Use a dedicated target type to configure parameters for
+                // the opaque stack. It also helps narrow the cache key.
+                .push_map_target(Opaq)
+                .arc_new_clone_tcp()
             })
     }
 }
@@ -85,7 +93,12 @@ impl OpaqMetrics {
             concrete::BalancerMetrics::register(registry.sub_registry_with_prefix("balancer"));
         let route =
             logical::route::TcpRouteMetrics::register(registry.sub_registry_with_prefix("route"));
-        Self { balance, route }
+        let connect_retries = retry::ConnectRetryMetrics::register(registry);
+        Self {
+            balance,
+            route,
+            connect_retries,
+        }
     }
 }
 
@@ -95,6 +108,7 @@ impl OpaqMetrics {
 /// in order to support traffic splits. Everything else should be delivered through client
 /// policy.
 pub fn routes_from_discovery(
+    updates: &RouteUpdates,
     addr: Addr,
     profile: Option<profiles::Receiver>,
     mut policy: policy::Receiver,
@@ -106,9 +120,14 @@ pub fn routes_from_discovery(
                 tracing::debug!(%addr, "Using ServiceProfile");
                 let init = routes_from_profile(addr.clone(), &profile);
                 drop(profile);
-                return spawn_routes(rx, init, move |profile: &profiles::Profile| {
-                    Some(routes_from_profile(addr.clone(), profile))
-                });
+                return updates.spawn(
+                    Protocol::opaq,
+                    rx,
+                    init,
+                    move |profile: &profiles::Profile| {
+                        Some(routes_from_profile(addr.clone(), profile))
+                    },
+                );
             }
         }
 
@@ -130,9 +149,12 @@ pub fn routes_from_discovery(
     let init = routes_from_policy(addr.clone(), &policy.borrow_and_update())
         .expect("initial policy must be opaque");
 
-    spawn_routes(policy, init, move |policy: &policy::ClientPolicy| {
-        routes_from_policy(addr.clone(), policy)
-    })
+    updates.spawn(
+        Protocol::opaq,
+        policy,
+        init,
+        move |policy: &policy::ClientPolicy| routes_from_policy(addr.clone(), policy),
+    )
 }
 
 fn routes_from_policy(addr: Addr, policy: &policy::ClientPolicy) -> Option<Routes> {
@@ -220,39 +242,3 @@ fn routes_from_profile(addr: NameAddr, profile: &profiles::Profile) -> Routes {
         routes: Some(route),
     }
 }
-
-fn spawn_routes<T>(
-    mut route_rx: watch::Receiver<T>,
-    init: Routes,
-    mut mk: impl FnMut(&T) -> Option<Routes> + Send + Sync + 'static,
-) -> watch::Receiver<Routes>
-where
-    T: Send + Sync + 'static,
-{
-    let (tx, rx) = watch::channel(init);
-
-    tokio::spawn(async move {
-        loop {
-            let res = tokio::select! {
-                biased;
-                _ = tx.closed() => return,
-                res = route_rx.changed() => res,
-            };
-
-            if res.is_err() {
-                // Drop the `tx` sender when the profile sender is
-                // dropped.
-                return;
-            }
-
-            if let Some(routes) = (mk)(&*route_rx.borrow_and_update()) {
-                if tx.send(routes).is_err() {
-                    // Drop the `tx` sender when all of its receivers are dropped.
-                    return;
-                }
-            }
-        }
-    });
-
-    rx
-}
@@ -1,7 +1,9 @@
 use super::Logical;
 use crate::{
+    lifetime::ConnectLifetime,
     metrics::BalancerMetricsParams,
     stack_labels,
+    topology::HintedResolve,
     zone::{tcp_zone_labels, TcpZoneLabels},
     BackendRef, Outbound, ParentRef,
 };
@@ -143,25 +145,33 @@ impl<C> Outbound<C> {
         T: svc::Param<BackendRef>,
         T: svc::Param<ParentRef>,
         // Server-side socket.
-        I: io::AsyncRead + io::AsyncWrite + Debug + Send + Unpin + 'static,
+        I: io::AsyncRead + io::AsyncWrite + io::Splice + Debug + Send + Unpin + 'static,
         // Endpoint resolution.
         R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
         R::Resolution: Unpin,
         // Endpoint connector.
         C: svc::MakeConnection<Endpoint<T>> + Clone + Send + 'static,
-        C::Connection: Send + Unpin,
+        C::Connection: io::Splice + Send + Unpin,
         C::Metadata: Send + Unpin,
         C::Future: Send,
         C: Send + Sync + 'static,
     {
-        let resolve =
-            svc::MapTargetLayer::new(|t: Balance<T>| -> ConcreteAddr { ConcreteAddr(t.addr) })
-                .layer(resolve.into_service());
-
         self.map_stack(|config, rt, inner| {
+            let resolve = HintedResolve::new(
+                config.topology_hints.clone(),
+                rt.metrics.prom.topology.clone(),
+                svc::MapTargetLayer::new(|t: Balance<T>| -> ConcreteAddr { ConcreteAddr(t.addr) })
+                    .layer(resolve.into_service()),
+            );
+
             let queue = config.tcp_connection_queue;
+            let ewma_overrides = config.balancer_ewma;
 
             let connect = inner
+                .push(ConnectLifetime::layer(
+                    config.connection_lifetimes.clone(),
+                    rt.metrics.prom.lifetime.tcp(),
+                ))
                 .push(svc::stack::WithoutConnectionMetadata::layer())
                 .push_new_thunk();
 
@@ -188,6 +198,14 @@ impl<C> Outbound<C> {
                 svc::mk(move |_| futures::future::ready(Err(DispatcherFailed(message.clone()))))
             });
 
+            let half_close = {
+                let ports = config.tcp_half_close.clone();
+                move |t: &T| -> tcp::HalfClose {
+                    let Logical { addr, .. } = t.param();
+                    ports.get(&addr.port()).copied().unwrap_or_default()
+                }
+            };
+
             let inbound_ips = config.inbound_ips.clone();
             let balance = endpoint
                 .push_map_target(
@@ -205,7 +223,12 @@ impl<C> Outbound<C> {
                 .lift_new_with_target()
                 .push(tcp::NewBalance::layer(
                     resolve,
-                    rt.metrics.prom.opaq.balance.clone(),
+                    rt.metrics
+                        .prom
+                        .opaq
+                        .balance
+                        .clone()
+                        .with_endpoint_load(config.balancer_endpoint_load_metrics),
                 ))
                 .push(svc::NewMapErr::layer_from_target::<ConcreteError, _>())
                 .push_on_service(
@@ -224,7 +247,7 @@ impl<C> Outbound<C> {
                             Dispatch::Balance(addr, ewma) => {
                                 svc::Either::Left(svc::Either::Left(Balance {
                                     addr,
-                                    ewma,
+                                    ewma: ewma_overrides.apply(ewma),
                                     queue,
                                     parent,
                                 }))
@@ -243,13 +266,41 @@ impl<C> Outbound<C> {
                     },
                     svc::stack(fail).check_new_clone().into_inner(),
                 )
-                .push_on_service(tcp::Forward::layer())
+                .push(super::retry::NewConnectRetry::layer(
+                    config.tcp_connect_retries,
+                    queue.failfast_timeout,
+                    rt.metrics.prom.opaq.connect_retries.clone(),
+                ))
+                .push(tcp::NewSpliceForward::layer_via(
+                    half_close,
+                    config.tcp_splice,
+                    rt.metrics.prom.tcp_close.clone(),
+                ))
+                .push(svc::stack::NewMonitor::layer(
+                    rt.metrics
+                        .prom
+                        .transport_errors
+                        .forward(|t: &T| svc::Param::<Dispatch>::param(t).port()),
+                ))
                 .push_on_service(drain::Retain::layer(rt.drain.clone()))
                 .push(svc::ArcNewService::layer())
         })
     }
 }
 
+// === impl Dispatch ===
+
+impl Dispatch {
+    /// Returns the port of the target to which connections are dispatched.
+    fn port(&self) -> Option<u16> {
+        match self {
+            Self::Balance(addr, _) => Some(addr.port()),
+            Self::Forward(Remote(ServerAddr(addr)), _) => Some(addr.port()),
+            Self::Fail { .. } => None,
+        }
+    }
+}
+
 // === impl ConcreteError ===
 
 impl<T> From<(&Balance<T>, Error)> for ConcreteError {
@@ -289,6 +340,13 @@ impl<T> svc::Param<svc::queue::Timeout> for Balance<T> {
     }
 }
 
+// Source affinity is only supported for HTTP balancers.
+impl<T, Req> svc::Param<Option<balance::SourceAffinity<Req>>> for Balance<T> {
+    fn param(&self) -> Option<balance::SourceAffinity<Req>> {
+        None
+    }
+}
+
 impl<T: svc::Param<ParentRef>> svc::Param<ParentRef> for Balance<T> {
     fn param(&self) -> ParentRef {
         self.parent.param()
@@ -305,7 +363,11 @@ impl<T: svc::Param<BackendRef>> svc::Param<BackendRef> for Balance<T> {
 
 impl<T> svc::Param<Remote<ServerAddr>> for Endpoint<T> {
     fn param(&self) -> Remote<ServerAddr> {
-        self.addr
+        // Endpoints with an override address are dialed through it.
+        match self.metadata.address_override() {
+            Some(addr) if !self.is_local => Remote(ServerAddr(addr)),
+            _ => self.addr,
+        }
     }
 }
 
@@ -320,6 +382,13 @@ impl<T> svc::Param<Option<crate::tcp::tagged_transport::PortOverride>> for Endpo
     }
 }
 
+impl<T> svc::Param<Option<crate::http::Connections>> for Endpoint<T> {
+    fn param(&self) -> Option<crate::http::Connections> {
+        // Opaque connections do not report request timings.
+        None
+    }
+}
+
 impl<T> svc::Param<Option<AuthorityOverride>> for Endpoint<T> {
     fn param(&self) -> Option<AuthorityOverride> {
         if self.is_local {
@@ -365,6 +434,7 @@ where
             zone_locality: self.param(),
             server_id: self.param(),
             target_addr: self.addr.into(),
+            http_version: None,
         }
     }
 }
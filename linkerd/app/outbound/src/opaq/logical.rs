@@ -1,5 +1,5 @@
 use super::concrete;
-use crate::{BackendRef, Outbound, ParentRef};
+use crate::{BackendRef, Outbound, ParentRef, RoutesFingerprint};
 use linkerd_app_core::{io, svc, Addr, Error};
 use linkerd_proxy_client_policy as client_policy;
 use std::{fmt::Debug, hash::Hash, sync::Arc};
@@ -82,6 +82,14 @@ impl<N> Outbound<N> {
     }
 }
 
+// === impl Routes ===
+
+impl svc::Param<RoutesFingerprint> for Routes {
+    fn param(&self) -> RoutesFingerprint {
+        RoutesFingerprint::of(self)
+    }
+}
+
 // === impl LogicalError ===
 
 impl<T> From<(&router::Router<T>, Error)> for LogicalError
@@ -261,7 +261,12 @@ fn default_service_policy(addr: NameAddr) -> policy::ClientPolicy {
 
 impl Target {
     pub fn new(policy: PolicyReceiver, profile: Option<profiles::Receiver>, addr: Addr) -> Self {
-        let routes = opaq::routes_from_discovery(addr.clone(), profile, policy);
+        let routes = opaq::routes_from_discovery(
+            &crate::RouteUpdates::new(time::Duration::ZERO, Default::default()),
+            addr.clone(),
+            profile,
+            policy,
+        );
         Self { addr, routes }
     }
 }
@@ -0,0 +1,361 @@
+//! Transparently reestablishes opaque connections that fail before any bytes
+//! are exchanged.
+//!
+//! During rollouts, endpoints may refuse or reset connections before they are
+//! removed from discovery. When a connection to a balanced backend fails--or is
+//! reset before any bytes have been exchanged with the endpoint--it is
+//! reestablished through the balancer, which may select another endpoint. Once
+//! any bytes have been exchanged, failures are never retried, since the
+//! endpoint may have acted on them.
+
+use super::concrete::Dispatch;
+use crate::{BackendRef, ParentRef};
+use linkerd_app_core::{
+    io, is_caused_by,
+    metrics::prom::{
+        self,
+        encoding::{EncodeLabelSet, LabelSetEncoder},
+    },
+    svc::{self, ServiceExt},
+    Error,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::time::{self, Duration};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Configures connection retries for each concrete opaque target.
+#[derive(Clone, Debug)]
+pub(crate) struct NewConnectRetry<N> {
+    retries: usize,
+    timeout: Duration,
+    metrics: ConnectRetryMetrics,
+    inner: N,
+}
+
+/// Connects through the inner service, retrying failed connections.
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectRetry<S> {
+    retries: usize,
+    timeout: Duration,
+    retried: prom::Counter,
+    inner: S,
+}
+
+/// A connection that is reestablished if it is reset before any bytes are
+/// exchanged.
+pub(crate) struct RetryIo<I> {
+    state: State<I>,
+    /// Cleared once bytes are exchanged.
+    budget: Option<Budget<I>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ConnectRetryMetrics {
+    retries: prom::Family<RetryLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct RetryLabels(ParentRef, BackendRef);
+
+type Connecting<I> = Pin<Box<dyn Future<Output = Result<I, Error>> + Send + 'static>>;
+
+enum State<I> {
+    Connected(I),
+    Connecting(Connecting<I>),
+}
+
+struct Budget<I> {
+    remaining: usize,
+    deadline: time::Instant,
+    retried: prom::Counter,
+    connect: Box<dyn Fn() -> Connecting<I> + Send>,
+}
+
+// === impl NewConnectRetry ===
+
+impl<N> NewConnectRetry<N> {
+    pub(crate) fn layer(
+        retries: usize,
+        timeout: Duration,
+        metrics: ConnectRetryMetrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            retries,
+            timeout,
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewConnectRetry<N>
+where
+    T: svc::Param<Dispatch> + svc::Param<ParentRef> + svc::Param<BackendRef>,
+    N: svc::NewService<T>,
+{
+    type Service = ConnectRetry<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        // Connections to forwarded targets would only be retried against the
+        // same endpoint.
+        let retries = match svc::Param::<Dispatch>::param(&target) {
+            Dispatch::Balance(..) => self.retries,
+            Dispatch::Forward(..) | Dispatch::Fail { .. } => 0,
+        };
+        let retried = self
+            .metrics
+            .retries
+            .get_or_create(&RetryLabels(target.param(), target.param()))
+            .clone();
+        ConnectRetry {
+            retries,
+            timeout: self.timeout,
+            retried,
+            inner: self.inner.new_service(target),
+        }
+    }
+}
+
+// === impl ConnectRetry ===
+
+impl<S> svc::Service<()> for ConnectRetry<S>
+where
+    S: svc::Service<()> + Clone + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+    S::Response: Send + 'static,
+{
+    type Response = RetryIo<S::Response>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Error>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, _: ()) -> Self::Future {
+        let connect = self.inner.call(());
+        if self.retries == 0 {
+            return Box::pin(async move {
+                let io = connect.await.map_err(Into::into)?;
+                Ok(RetryIo {
+                    state: State::Connected(io),
+                    budget: None,
+                })
+            });
+        }
+
+        let inner = self.inner.clone();
+        let mut budget = Budget {
+            remaining: self.retries,
+            deadline: time::Instant::now() + self.timeout,
+            retried: self.retried.clone(),
+            connect: Box::new(move || {
+                let connect = inner.clone().oneshot(());
+                Box::pin(async move { connect.await.map_err(Into::into) })
+            }),
+        };
+        Box::pin(async move {
+            let mut connected = connect.await.map_err(Into::into);
+            loop {
+                match connected {
+                    Ok(io) => {
+                        return Ok(RetryIo {
+                            state: State::Connected(io),
+                            budget: Some(budget),
+                        })
+                    }
+                    Err(error) => {
+                        if !is_caused_by::<io::Error>(&*error) {
+                            return Err(error);
+                        }
+                        let Some(connect) = budget.retry() else {
+                            return Err(error);
+                        };
+                        debug!(%error, "Retrying connection");
+                        connected = connect.await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+// === impl RetryIo ===
+
+impl<I: io::AsyncRead + io::AsyncWrite + Unpin> RetryIo<I> {
+    /// Drives a reconnection, if necessary, and returns the connection.
+    fn poll_connected(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&mut I>> {
+        while let State::Connecting(ref mut connecting) = self.state {
+            match ready!(connecting.as_mut().poll(cx)) {
+                Ok(io) => self.state = State::Connected(io),
+                Err(error) => {
+                    let connect = self.budget.as_mut().and_then(Budget::retry);
+                    let Some(connect) = connect else {
+                        return Poll::Ready(Err(io::Error::other(error)));
+                    };
+                    debug!(%error, "Retrying connection");
+                    self.state = State::Connecting(connect);
+                }
+            }
+        }
+        match self.state {
+            State::Connected(ref mut io) => Poll::Ready(Ok(io)),
+            State::Connecting(_) => unreachable!("the connection must be established"),
+        }
+    }
+
+    /// Reestablishes the connection if it was reset before any bytes were
+    /// exchanged. Otherwise, the error is returned.
+    fn reconnect(&mut self, error: io::Error) -> io::Result<()> {
+        let reset = matches!(
+            error.kind(),
+            io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+        );
+        if !reset {
+            return Err(error);
+        }
+        let Some(connect) = self.budget.as_mut().and_then(Budget::retry) else {
+            return Err(error);
+        };
+        debug!(%error, "Connection reset before any bytes were exchanged; reconnecting");
+        self.state = State::Connecting(connect);
+        Ok(())
+    }
+}
+
+impl<I: io::AsyncRead + io::AsyncWrite + Unpin> io::AsyncRead for RetryIo<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut io::ReadBuf<'_>,
+    ) -> io::Poll<()> {
+        let this = self.get_mut();
+        loop {
+            let io = ready!(this.poll_connected(cx))?;
+            let filled = buf.filled().len();
+            match ready!(Pin::new(io).poll_read(cx, buf)) {
+                Ok(()) => {
+                    if buf.filled().len() > filled {
+                        this.budget = None;
+                    }
+                    return Poll::Ready(Ok(()));
+                }
+                Err(error) => this.reconnect(error)?,
+            }
+        }
+    }
+}
+
+impl<I: io::AsyncRead + io::AsyncWrite + Unpin> io::AsyncWrite for RetryIo<I> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> io::Poll<usize> {
+        let this = self.get_mut();
+        loop {
+            let io = ready!(this.poll_connected(cx))?;
+            match ready!(Pin::new(io).poll_write(cx, buf)) {
+                Ok(sz) => {
+                    if sz > 0 {
+                        this.budget = None;
+                    }
+                    return Poll::Ready(Ok(sz));
+                }
+                Err(error) => this.reconnect(error)?,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        let this = self.get_mut();
+        let io = ready!(this.poll_connected(cx))?;
+        Pin::new(io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+        let this = self.get_mut();
+        let io = ready!(this.poll_connected(cx))?;
+        Pin::new(io).poll_shutdown(cx)
+    }
+}
+
+impl<I: io::Splice> io::Splice for RetryIo<I> {
+    fn splice_socket(&self) -> Option<&tokio::net::TcpStream> {
+        // Spliced bytes bypass this I/O, so a spliced connection could not be
+        // reestablished.
+        if self.budget.is_some() {
+            return None;
+        }
+        match self.state {
+            State::Connected(ref io) => io.splice_socket(),
+            State::Connecting(_) => None,
+        }
+    }
+
+    fn take_prefix(&mut self) -> bytes::Bytes {
+        match self.state {
+            State::Connected(ref mut io) => io.take_prefix(),
+            State::Connecting(_) => bytes::Bytes::new(),
+        }
+    }
+
+    fn record_spliced(&mut self, read: usize, written: usize) {
+        if let State::Connected(ref mut io) = self.state {
+            io.record_spliced(read, written)
+        }
+    }
+}
+
+impl<I> std::fmt::Debug for RetryIo<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryIo")
+            .field("connected", &matches!(self.state, State::Connected(_)))
+            .field("retries", &self.budget.as_ref().map(|b| b.remaining))
+            .finish()
+    }
+}
+
+// === impl Budget ===
+
+impl<I> Budget<I> {
+    /// Returns a new connection attempt, unless the budget is exhausted.
+    fn retry(&mut self) -> Option<Connecting<I>> {
+        if self.remaining == 0 || time::Instant::now() >= self.deadline {
+            return None;
+        }
+        self.remaining -= 1;
+        self.retried.inc();
+        Some((self.connect)())
+    }
+}
+
+// === impl ConnectRetryMetrics ===
+
+impl ConnectRetryMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let retries = prom::Family::default();
+        registry.register(
+            "connect_retries",
+            "The number of opaque connections that were transparently reestablished before any bytes were exchanged",
+            retries.clone(),
+        );
+        Self { retries }
+    }
+}
+
+// === impl RetryLabels ===
+
+impl EncodeLabelSet for RetryLabels {
+    fn encode(&self, mut enc: LabelSetEncoder<'_>) -> std::fmt::Result {
+        self.0.encode_label_set(&mut enc)?;
+        self.1.encode_label_set(&mut enc)
+    }
+}
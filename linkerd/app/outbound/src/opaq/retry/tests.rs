@@ -0,0 +1,101 @@
+use super::*;
+use linkerd_app_core::io::{AsyncReadExt, AsyncWriteExt};
+use parking_lot::Mutex;
+use std::{collections::VecDeque, sync::Arc};
+use tokio_test::io::{Builder, Mock};
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Builds a service that connects to each of `conns` in turn.
+fn mk_svc(
+    retries: usize,
+    conns: Vec<io::Result<Mock>>,
+) -> (
+    ConnectRetry<
+        impl svc::Service<
+                (),
+                Response = Mock,
+                Error = Error,
+                Future = futures::future::Ready<Result<Mock, Error>>,
+            > + Clone
+            + Send
+            + 'static,
+    >,
+    prom::Counter,
+) {
+    let conns = Arc::new(Mutex::new(VecDeque::from(conns)));
+    let connect = svc::mk(move |()| {
+        let conn = conns.lock().pop_front().expect("unexpected connection");
+        futures::future::ready(conn.map_err(Error::from))
+    });
+    let retried = prom::Counter::default();
+    let svc = ConnectRetry {
+        retries,
+        timeout: TIMEOUT,
+        retried: retried.clone(),
+        inner: connect,
+    };
+    (svc, retried)
+}
+
+fn refused() -> io::Result<Mock> {
+    Err(io::ErrorKind::ConnectionRefused.into())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn retries_connect_errors() {
+    let io = Builder::new().write(b"hello").read(b"world").build();
+    let (svc, retried) = mk_svc(1, vec![refused(), Ok(io)]);
+
+    let mut io = svc.oneshot(()).await.expect("must connect");
+    io.write_all(b"hello").await.expect("must write");
+    let mut buf = [0; 5];
+    io.read_exact(&mut buf).await.expect("must read");
+    assert_eq!(&buf, b"world");
+    assert_eq!(retried.get(), 1);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn reconnects_when_reset_before_bytes() {
+    let reset = Builder::new()
+        .read_error(io::ErrorKind::ConnectionReset.into())
+        .build();
+    let io = Builder::new().read(b"world").build();
+    let (svc, retried) = mk_svc(1, vec![Ok(reset), Ok(io)]);
+
+    let mut io = svc.oneshot(()).await.expect("must connect");
+    let mut buf = [0; 5];
+    io.read_exact(&mut buf).await.expect("must read");
+    assert_eq!(&buf, b"world");
+    assert_eq!(retried.get(), 1);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn does_not_reconnect_after_bytes() {
+    let io = Builder::new()
+        .read(b"world")
+        .read_error(io::ErrorKind::ConnectionReset.into())
+        .build();
+    let (svc, retried) = mk_svc(1, vec![Ok(io)]);
+
+    let mut io = svc.oneshot(()).await.expect("must connect");
+    let mut buf = [0; 5];
+    io.read_exact(&mut buf).await.expect("must read");
+    let error = io.read(&mut buf).await.expect_err("read must fail");
+    assert_eq!(error.kind(), io::ErrorKind::ConnectionReset);
+    assert_eq!(retried.get(), 0);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn does_not_retry_when_disabled() {
+    let (svc, retried) = mk_svc(0, vec![refused()]);
+    svc.oneshot(()).await.expect_err("must fail to connect");
+    assert_eq!(retried.get(), 0);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn exhausts_retries() {
+    let (svc, retried) = mk_svc(2, vec![refused(), refused(), refused()]);
+    svc.oneshot(()).await.expect_err("must fail to connect");
+    assert_eq!(retried.get(), 2);
+}
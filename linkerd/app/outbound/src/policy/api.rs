@@ -1,3 +1,4 @@
+use crate::snapshot::DiscoverySnapshot;
 use futures::prelude::*;
 use linkerd2_proxy_api::outbound::{
     self as api, outbound_policies_client::OutboundPoliciesClient as Client,
@@ -19,8 +20,9 @@ pub(crate) struct Api<S> {
     workload: Arc<str>,
     limits: ReceiveLimits,
     default_detect_timeout: time::Duration,
-    export_hostname_labels: bool,
+    overrides: ClientPolicyOverrides,
     client: Client<S>,
+    snapshot: Option<DiscoverySnapshot>,
 }
 
 #[derive(Clone)]
@@ -40,18 +42,25 @@ where
         workload: Arc<str>,
         limits: ReceiveLimits,
         default_detect_timeout: time::Duration,
-        export_hostname_labels: bool,
+        overrides: ClientPolicyOverrides,
         client: S,
     ) -> Self {
         Self {
             workload,
             limits,
             default_detect_timeout,
-            export_hostname_labels,
+            overrides,
             client: Client::new(client),
+            snapshot: None,
         }
     }
 
+    /// Records the policies discovered for original destination addresses in
+    /// the snapshot, if one is configured.
+    pub(crate) fn with_snapshot(self, snapshot: Option<DiscoverySnapshot>) -> Self {
+        Self { snapshot, ..self }
+    }
+
     pub(crate) fn into_watch(self, backoff: ExponentialBackoff) -> Watch<S> {
         StreamWatch::new(GrpcRecover(backoff), self)
     }
@@ -89,8 +98,10 @@ where
         };
 
         let detect_timeout = self.default_detect_timeout;
-        let overrides = ClientPolicyOverrides {
-            export_hostname_labels: self.export_hostname_labels,
+        let overrides = self.overrides.clone();
+        let mut record = match (self.snapshot.as_ref(), addr) {
+            (Some(snapshot), Addr::Socket(sock)) => Some(snapshot.record_parent(sock)),
+            _ => None,
         };
         let limits = self.limits;
         let mut client = self.client.clone();
@@ -98,16 +109,28 @@ where
             let rsp =
                 LimitReceiveFuture::new(limits, client.watch(tonic::Request::new(req))).await?;
             Ok(rsp.map(move |s| {
+                let mut prev = None::<ClientPolicy>;
                 s.map_ok(move |up| {
+                    if let Some(record) = record.as_mut() {
+                        record.update(up.clone());
+                    }
                     // If the server returned an invalid client policy, we
                     // default to using an invalid policy that causes all
                     // requests to report an internal error.
-                    let policy = ClientPolicy::try_from(overrides, up).unwrap_or_else(|error| {
-                        tracing::warn!(%error, "Client policy misconfigured");
-                        INVALID_POLICY
-                            .get_or_init(|| ClientPolicy::invalid(detect_timeout))
-                            .clone()
-                    });
+                    let mut policy =
+                        ClientPolicy::try_from(overrides.clone(), up).unwrap_or_else(|error| {
+                            tracing::warn!(%error, "Client policy misconfigured");
+                            INVALID_POLICY
+                                .get_or_init(|| ClientPolicy::invalid(detect_timeout))
+                                .clone()
+                        });
+                    // Share unchanged routes with the previous update so that
+                    // each routes watch can cheaply determine that they are
+                    // unchanged.
+                    if let Some(prev) = prev.as_ref() {
+                        policy.reuse_unchanged(prev);
+                    }
+                    prev = Some(policy.clone());
                     tracing::debug!(?policy);
                     policy
                 })
@@ -0,0 +1,154 @@
+//! Maps original destination ports to the logical services that are
+//! discovered for them.
+//!
+//! Some services are reached through a port that does not match the port of
+//! the logical service, e.g. a NodePort, so that discovery by the original
+//! destination address finds nothing. Port mappings substitute the key with
+//! which a destination is discovered. Connections are still forwarded to the
+//! original destination address when the mapped destination is not known to
+//! the control plane.
+
+use linkerd_app_core::{metrics::prom, Addr, IpNet, NameAddr};
+use std::{fmt, net::SocketAddr, sync::Arc};
+
+#[cfg(test)]
+mod tests;
+
+/// Configures the destination with which connections to a port are
+/// discovered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortMapping {
+    /// Limits the mapping to destination addresses in this network. Applies to
+    /// all destination addresses when unset.
+    pub net: Option<IpNet>,
+    pub port: u16,
+    pub target: PortMapTarget,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PortMapTarget {
+    /// Discovers the destination's IP address with another port.
+    Port(u16),
+
+    /// Discovers a logical service by name.
+    Authority(NameAddr),
+}
+
+/// The configured port mappings, each with the number of times it has been
+/// applied.
+#[derive(Clone, Debug, Default)]
+pub struct PortMappings(Arc<[(PortMapping, prom::Counter)]>);
+
+/// A snapshot of a port mapping's state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortMappingState {
+    pub mapping: PortMapping,
+
+    /// The number of destinations that have been discovered through the
+    /// mapping.
+    pub applied: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PortMappingMetrics {
+    applied: prom::Family<PortMappingLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct PortMappingLabels {
+    mapping: String,
+    target: String,
+}
+
+// === impl PortMapping ===
+
+impl PortMapping {
+    fn matches(&self, addr: SocketAddr) -> bool {
+        addr.port() == self.port && self.net.is_none_or(|net| net.contains(&addr.ip()))
+    }
+
+    /// Returns the address with which `addr` is discovered.
+    fn map(&self, addr: SocketAddr) -> Addr {
+        match self.target {
+            PortMapTarget::Port(port) => Addr::Socket(SocketAddr::new(addr.ip(), port)),
+            PortMapTarget::Authority(ref name) => Addr::Name(name.clone()),
+        }
+    }
+
+    /// Describes the destinations to which the mapping applies.
+    pub fn dst(&self) -> String {
+        match self.net {
+            Some(net) => format!("{net}:{}", self.port),
+            None => self.port.to_string(),
+        }
+    }
+}
+
+// === impl PortMapTarget ===
+
+impl fmt::Display for PortMapTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Port(port) => fmt::Display::fmt(port, f),
+            Self::Authority(name) => fmt::Display::fmt(name, f),
+        }
+    }
+}
+
+// === impl PortMappings ===
+
+impl PortMappings {
+    pub(crate) fn new(mappings: &[PortMapping], metrics: &PortMappingMetrics) -> Self {
+        Self(
+            mappings
+                .iter()
+                .map(|mapping| {
+                    let applied = metrics
+                        .applied
+                        .get_or_create(&PortMappingLabels {
+                            mapping: mapping.dst(),
+                            target: mapping.target.to_string(),
+                        })
+                        .clone();
+                    (mapping.clone(), applied)
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the address with which connections to `addr` are discovered,
+    /// if it is mapped.
+    ///
+    /// When multiple mappings apply, the first configured mapping is used.
+    pub(crate) fn get(&self, addr: SocketAddr) -> Option<Addr> {
+        let (mapping, applied) = self.0.iter().find(|(m, _)| m.matches(addr))?;
+        applied.inc();
+        Some(mapping.map(addr))
+    }
+
+    /// Returns a snapshot of all mappings, in the order in which they are
+    /// applied.
+    pub fn mappings(&self) -> Vec<PortMappingState> {
+        self.0
+            .iter()
+            .map(|(mapping, applied)| PortMappingState {
+                mapping: mapping.clone(),
+                applied: applied.get(),
+            })
+            .collect()
+    }
+}
+
+// === impl PortMappingMetrics ===
+
+impl PortMappingMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let applied = prom::Family::default();
+        registry.register(
+            "port_mappings_applied",
+            "The number of destinations discovered through a port mapping",
+            applied.clone(),
+        );
+        Self { applied }
+    }
+}
@@ -0,0 +1,43 @@
+use super::*;
+use std::str::FromStr;
+
+fn mapping(net: Option<&str>, port: u16, target: PortMapTarget) -> PortMapping {
+    PortMapping {
+        net: net.map(|n| IpNet::from_str(n).unwrap()),
+        port,
+        target,
+    }
+}
+
+#[test]
+fn maps_first_matching_port() {
+    let name = NameAddr::from_str("web.ns.svc.cluster.local:80").unwrap();
+    let mappings = PortMappings::new(
+        &[
+            mapping(
+                Some("10.0.0.0/8"),
+                30080,
+                PortMapTarget::Authority(name.clone()),
+            ),
+            mapping(None, 30080, PortMapTarget::Port(8080)),
+        ],
+        &PortMappingMetrics::default(),
+    );
+
+    assert_eq!(
+        mappings.get(([10, 1, 2, 3], 30080).into()),
+        Some(Addr::Name(name))
+    );
+    assert_eq!(
+        mappings.get(([192, 168, 1, 1], 30080).into()),
+        Some(Addr::Socket(([192, 168, 1, 1], 8080).into()))
+    );
+    assert_eq!(mappings.get(([10, 1, 2, 3], 8080).into()), None);
+
+    let applied = mappings
+        .mappings()
+        .into_iter()
+        .map(|s| s.applied)
+        .collect::<Vec<_>>();
+    assert_eq!(applied, [1, 1], "each applied mapping must be counted");
+}
@@ -0,0 +1,82 @@
+//! Eagerly discovers configured outbound destinations when the proxy starts,
+//! so that the first connections to them do not wait on the control plane.
+
+use futures::prelude::*;
+use linkerd_app_core::{metrics::prom, transport::OrigDstAddr, Error};
+use std::net::SocketAddr;
+use tracing::{debug, info, warn};
+
+#[cfg(test)]
+mod tests;
+
+/// Configures the destinations that are discovered when the proxy starts.
+#[derive(Clone, Debug, Default)]
+pub struct PrewarmConfig {
+    /// The original destination addresses to discover.
+    pub addrs: Vec<SocketAddr>,
+
+    /// Whether each destination's stack is also built once it is discovered,
+    /// so that its endpoints are resolved and connected before the first
+    /// connection is accepted.
+    pub endpoints: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PrewarmMetrics {
+    errors: prom::Counter,
+}
+
+// === impl PrewarmMetrics ===
+
+impl PrewarmMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let errors = prom::Counter::default();
+        registry.register(
+            "prewarm_errors",
+            "The number of destinations that could not be discovered when the proxy started",
+            errors.clone(),
+        );
+        Self { errors }
+    }
+}
+
+/// Discovers each address and builds a service for it with `warm`.
+///
+/// Discovery results and warmed services are held for the lifetime of the
+/// proxy so that they are not evicted from their caches. Failures are logged
+/// and counted, but are otherwise ignored.
+pub(crate) async fn prewarm<R, S, F>(
+    addrs: Vec<SocketAddr>,
+    discover: impl Fn(OrigDstAddr) -> F,
+    warm: impl Fn(OrigDstAddr, &R) -> S,
+    metrics: PrewarmMetrics,
+) where
+    F: Future<Output = Result<R, Error>>,
+{
+    let warmed = future::join_all(addrs.into_iter().map(|addr| {
+        let discovery = discover(OrigDstAddr(addr));
+        let (warm, metrics) = (&warm, &metrics);
+        async move {
+            match discovery.await {
+                Ok(rsp) => {
+                    debug!(%addr, "Discovered");
+                    let svc = warm(OrigDstAddr(addr), &rsp);
+                    Some((rsp, svc))
+                }
+                Err(error) => {
+                    warn!(%addr, %error, "Failed to prewarm destination");
+                    metrics.errors.inc();
+                    None
+                }
+            }
+        }
+    }))
+    .await;
+    info!(
+        destinations = warmed.iter().flatten().count(),
+        "Prewarmed outbound destinations"
+    );
+
+    future::pending::<()>().await;
+    drop(warmed);
+}
@@ -0,0 +1,200 @@
+use crate::{policy, tcp, test_util::*, Outbound};
+use linkerd_app_core::{
+    io::{self, AsyncReadExt, AsyncWriteExt},
+    proxy::api_resolve::ConcreteAddr,
+    svc::{self, NewService, ServiceExt},
+    transport::{addrs::*, OrigDstAddr},
+    Addr, Error, NameAddr,
+};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::{sync::watch, time};
+
+/// Tests that the first connection to a prewarmed destination is served
+/// without any discovery round-trips.
+#[tokio::test(flavor = "current_thread")]
+async fn first_connection_is_not_discovered() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let addr = SocketAddr::new([192, 0, 2, 10].into(), 4444);
+    let laddr = "xyz.example.com:4444".parse::<NameAddr>().unwrap();
+    let ep_addr = SocketAddr::new([192, 0, 2, 30].into(), 3333);
+
+    let (_tx, policy) = watch::channel(opaque_policy(laddr.clone()));
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let policies = {
+        let lookups = lookups.clone();
+        svc::mk(move |_: Addr| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            future::ok::<_, Error>(policy.clone())
+        })
+    };
+
+    let resolutions = Arc::new(AtomicUsize::new(0));
+    let resolver = support::resolver().endpoint_exists(laddr, ep_addr, Default::default());
+    let resolved = resolver.handle();
+    let resolve = {
+        let resolutions = resolutions.clone();
+        svc::mk(move |concrete: ConcreteAddr| {
+            resolutions.fetch_add(1, Ordering::SeqCst);
+            resolver.clone().oneshot(concrete)
+        })
+    };
+
+    let (rt, _shutdown) = runtime();
+    let mut config = default_config();
+    config.prewarm.addrs = vec![addr];
+    config.prewarm.endpoints = true;
+    let stack = Outbound::new(config, rt, &mut Default::default())
+        .with_stack(svc::mk(move |connect: tcp::Connect| {
+            let Remote(ServerAddr(ea)) = svc::Param::param(&connect);
+            assert_eq!(ea, ep_addr);
+            let (client, server) = io::duplex(100);
+            tokio::spawn(echo(server));
+            let local = Local(ClientAddr(([0, 0, 0, 0], 4444).into()));
+            future::ok::<_, io::Error>((client, local))
+        }))
+        .push_sidecar(support::profile::resolver(), policies, resolve)
+        .into_inner();
+
+    // Let the prewarm task discover the destination and resolve its
+    // endpoints.
+    time::sleep(time::Duration::from_secs(1)).await;
+    assert_eq!(
+        lookups.load(Ordering::SeqCst),
+        1,
+        "destination is prewarmed"
+    );
+    assert_eq!(
+        resolutions.load(Ordering::SeqCst),
+        1,
+        "endpoints are prewarmed"
+    );
+
+    let (mut client, server) = io::duplex(100);
+    let conn = tokio::spawn(stack.new_service(OrigDstAddr(addr)).oneshot(server));
+    client.write_all(b"hello").await.unwrap();
+    let mut buf = [0u8; 5];
+    client.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hello");
+    drop(client);
+    conn.await.unwrap().expect("connection must succeed");
+
+    assert_eq!(
+        lookups.load(Ordering::SeqCst),
+        1,
+        "the first connection must not be discovered"
+    );
+    assert_eq!(
+        resolutions.load(Ordering::SeqCst),
+        1,
+        "the first connection must not resolve endpoints"
+    );
+    assert!(resolved.only_configured(), "unexpected resolution");
+}
+
+/// Tests that destinations that cannot be discovered are counted, but do not
+/// prevent other destinations from being prewarmed.
+#[tokio::test(flavor = "current_thread")]
+async fn counts_failures() {
+    let _trace = linkerd_tracing::test::trace_init();
+    time::pause();
+
+    let ok = SocketAddr::new([192, 0, 2, 10].into(), 4444);
+    let unknown = SocketAddr::new([192, 0, 2, 11].into(), 4444);
+    let laddr = "xyz.example.com:4444".parse::<NameAddr>().unwrap();
+
+    let (_tx, policy) = watch::channel(opaque_policy(laddr));
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let policies = {
+        let lookups = lookups.clone();
+        svc::mk(move |addr: Addr| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            if addr == Addr::Socket(ok) {
+                future::ok(policy.clone())
+            } else {
+                future::err::<policy::Receiver, Error>("unknown destination".into())
+            }
+        })
+    };
+
+    let (rt, _shutdown) = runtime();
+    let mut config = default_config();
+    config.prewarm.addrs = vec![ok, unknown];
+    let outbound = Outbound::new(config, rt, &mut Default::default());
+    let _stack = outbound
+        .clone()
+        .with_stack(svc::mk(|_: tcp::Connect| {
+            future::err::<(io::DuplexStream, Local<ClientAddr>), _>(io::Error::from(
+                io::ErrorKind::ConnectionRefused,
+            ))
+        }))
+        .push_sidecar::<OrigDstAddr, io::DuplexStream, _>(
+            support::profile::resolver(),
+            policies,
+            support::resolver::no_destinations(),
+        );
+
+    time::sleep(time::Duration::from_secs(1)).await;
+    assert_eq!(
+        lookups.load(Ordering::SeqCst),
+        2,
+        "destinations are prewarmed"
+    );
+    assert_eq!(outbound.metrics().prom.prewarm.errors.get(), 1);
+}
+
+async fn echo(mut io: io::DuplexStream) {
+    let mut buf = [0u8; 100];
+    while let Ok(sz) = io.read(&mut buf).await {
+        if sz == 0 || io.write_all(&buf[..sz]).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn opaque_policy(addr: NameAddr) -> policy::ClientPolicy {
+    let meta = policy::Meta::new_default("test");
+    let backend = policy::Backend {
+        meta: meta.clone(),
+        queue: policy::Queue {
+            capacity: 100,
+            failfast_timeout: time::Duration::from_secs(3),
+        },
+        dispatcher: policy::BackendDispatcher::BalanceP2c(
+            policy::Load::PeakEwma(policy::PeakEwma {
+                default_rtt: time::Duration::from_millis(30),
+                decay: time::Duration::from_secs(10),
+            }),
+            policy::EndpointDiscovery::DestinationGet {
+                path: addr.to_string(),
+            },
+        ),
+    };
+    let opaque = policy::opaq::Opaque {
+        routes: Some(policy::opaq::Route {
+            policy: policy::opaq::Policy {
+                distribution: policy::RouteDistribution::FirstAvailable(Arc::new([
+                    policy::RouteBackend {
+                        backend: backend.clone(),
+                        filters: Arc::new([]),
+                    },
+                ])),
+                filters: Arc::new([]),
+                meta: meta.clone(),
+                params: (),
+            },
+        }),
+    };
+    policy::ClientPolicy {
+        parent: meta,
+        protocol: policy::Protocol::Opaque(opaque),
+        backends: Arc::new([backend]),
+    }
+}
@@ -3,10 +3,11 @@ use linkerd_app_core::{io, svc, Error, Infallible};
 use std::{fmt::Debug, hash::Hash};
 
 mod metrics;
+mod mismatch;
 #[cfg(test)]
 mod tests;
 
-pub use self::metrics::MetricsFamilies;
+pub use self::{metrics::MetricsFamilies, mismatch::HttpVersionMismatch};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Http<T> {
@@ -44,7 +45,7 @@ impl<N> Outbound<N> {
         T: svc::Param<ParentRef>,
         T: Eq + Hash + Clone + Debug + Send + Sync + 'static,
         // Server-side socket.
-        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr,
+        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr,
         I: Debug + Send + Sync + Unpin + 'static,
         // Opaque connection stack.
         N: svc::NewService<T, Service = NSvc>,
@@ -70,7 +71,8 @@ impl<N> Outbound<N> {
         });
 
         let detect = http.clone().map_stack(|config, rt, http| {
-            let read_timeout = config.proxy.detect_protocol_timeout;
+            let detect_timeout = config.proxy.detect_protocol_timeout;
+            let tunables = rt.tunables.clone();
             let metrics = rt.metrics.prom.http_detect.clone();
 
             http.push_switch(
@@ -95,6 +97,11 @@ impl<N> Outbound<N> {
             .push_on_service(svc::MapTargetLayer::new(io::EitherIo::Right))
             .lift_new_with_target::<(http::Detection, T)>()
             .push(http::NewDetect::layer(move |parent: &T| {
+                // The timeout may be changed while the proxy is running.
+                let read_timeout = tunables
+                    .borrow()
+                    .outbound_detect_timeout
+                    .unwrap_or(detect_timeout);
                 http::DetectParams {
                     read_timeout,
                     metrics: metrics.metrics(parent.param()),
@@ -103,7 +110,19 @@ impl<N> Outbound<N> {
             .arc_new_tcp()
         });
 
-        http.map_stack(|_, rt, http| {
+        http.map_stack(|config, rt, http| {
+            // Clients may speak a different HTTP version than policy
+            // configures, so the version is confirmed before the connection is
+            // served.
+            let http = http
+                .push(mismatch::NewCheckVersion::layer(
+                    config.proxy.detect_protocol_timeout,
+                    config.http_version_hint,
+                    config.emit_headers,
+                    rt.metrics.prom.protocol.clone(),
+                ))
+                .arc_new_tcp();
+
             // First separate traffic that needs protocol detection. Then switch
             // between traffic that is known to be HTTP or opaque.
             let known = http.push_switch(
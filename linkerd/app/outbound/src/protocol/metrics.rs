@@ -1,5 +1,5 @@
 use super::Protocol;
-use crate::ParentRef;
+use crate::{http, ParentRef};
 use linkerd_app_core::{
     metrics::prom::{self, EncodeLabelSetMut},
     svc,
@@ -20,6 +20,7 @@ pub struct Record<S> {
 #[derive(Clone, Debug, Default)]
 pub struct MetricsFamilies {
     connections: prom::Family<Labels, prom::Counter>,
+    version_mismatches: prom::Family<MismatchLabels, prom::Counter>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -28,6 +29,12 @@ struct Labels {
     parent_ref: ParentRef,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct MismatchLabels {
+    configured: http::Variant,
+    parent_ref: ParentRef,
+}
+
 // === impl MetricsFamilies ===
 
 impl MetricsFamilies {
@@ -39,7 +46,32 @@ impl MetricsFamilies {
             connections.clone(),
         );
 
-        Self { connections }
+        let version_mismatches = prom::Family::default();
+        reg.register(
+            "http_version_mismatches",
+            "Outbound HTTP connections on which the client spoke a different HTTP version than configured",
+            version_mismatches.clone(),
+        );
+
+        Self {
+            connections,
+            version_mismatches,
+        }
+    }
+
+    /// Returns the counter of connections to `parent_ref`, configured for
+    /// `configured`, on which the client spoke another HTTP version.
+    pub(crate) fn version_mismatch(
+        &self,
+        configured: http::Variant,
+        parent_ref: ParentRef,
+    ) -> prom::Counter {
+        self.version_mismatches
+            .get_or_create(&MismatchLabels {
+                configured,
+                parent_ref,
+            })
+            .clone()
     }
 }
 
@@ -123,3 +155,28 @@ impl prom::encoding::EncodeLabelSet for Labels {
         self.encode_label_set(&mut enc)
     }
 }
+
+// === impl MismatchLabels ===
+
+impl prom::EncodeLabelSetMut for MismatchLabels {
+    fn encode_label_set(&self, enc: &mut prom::encoding::LabelSetEncoder<'_>) -> std::fmt::Result {
+        use prom::encoding::EncodeLabel;
+
+        let (configured, detected) = match self.configured {
+            http::Variant::Http1 => ("http/1", "http/2"),
+            http::Variant::H2 => ("http/2", "http/1"),
+        };
+
+        ("configured", configured).encode(enc.encode_label())?;
+        ("detected", detected).encode(enc.encode_label())?;
+        self.parent_ref.encode_label_set(enc)?;
+
+        Ok(())
+    }
+}
+
+impl prom::encoding::EncodeLabelSet for MismatchLabels {
+    fn encode(&self, mut enc: prom::encoding::LabelSetEncoder<'_>) -> Result<(), std::fmt::Error> {
+        self.encode_label_set(&mut enc)
+    }
+}
@@ -0,0 +1,351 @@
+//! Detects clients that speak a different HTTP version than policy configures.
+//!
+//! When policy indicates that a destination speaks HTTP/1, a client that
+//! instead sends an HTTP/2 connection preface would otherwise see a confusing
+//! parse error (and vice versa). Instead, the first bytes of each connection
+//! are inspected: if they indicate another HTTP version, the mismatch is
+//! counted and the connection is either served with the client's version or
+//! answered with an HTTP 505 that describes the mismatch.
+
+use super::{metrics::MetricsFamilies, Http};
+use crate::{http, ParentRef};
+use linkerd_app_core::{
+    errors::{header::L5D_PROXY_ERROR, ErrorCode},
+    io::{self, AsyncWriteExt},
+    metrics::prom,
+    svc::{self, ServiceExt},
+    Error, Result,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::time;
+use tracing::{debug, info};
+
+/// Large enough to hold a typical request line.
+const PEEK_CAPACITY: usize = 512;
+
+const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0";
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewCheckVersion<N> {
+    params: Params,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct CheckVersion<T, N> {
+    params: Params,
+    target: Http<T>,
+    mismatched: prom::Counter,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+struct Params {
+    timeout: time::Duration,
+    hint: bool,
+    emit_headers: bool,
+    metrics: MetricsFamilies,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("client sent {detected:?} to a destination configured for {configured:?}")]
+pub struct HttpVersionMismatch {
+    configured: http::Variant,
+    detected: http::Variant,
+}
+
+// === impl NewCheckVersion ===
+
+impl<N> NewCheckVersion<N> {
+    /// Returns a layer that checks the HTTP version spoken by clients. When
+    /// `hint` is set, mismatched connections are served with the client's
+    /// version. Otherwise, they are rejected.
+    ///
+    /// The `timeout` bounds how long the proxy waits for the client to send
+    /// data, after which the connection is served with the configured version.
+    pub(crate) fn layer(
+        timeout: time::Duration,
+        hint: bool,
+        emit_headers: bool,
+        metrics: MetricsFamilies,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let params = Params {
+            timeout,
+            hint,
+            emit_headers,
+            metrics,
+        };
+        svc::layer::mk(move |inner| Self {
+            params: params.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N: Clone> svc::NewService<Http<T>> for NewCheckVersion<N>
+where
+    T: svc::Param<ParentRef>,
+{
+    type Service = CheckVersion<T, N>;
+
+    fn new_service(&self, target: Http<T>) -> Self::Service {
+        let mismatched = self
+            .params
+            .metrics
+            .version_mismatch(target.version, target.parent.param());
+        CheckVersion {
+            params: self.params.clone(),
+            target,
+            mismatched,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// === impl CheckVersion ===
+
+impl<T, I, N, S> svc::Service<I> for CheckVersion<T, N>
+where
+    T: Clone + Send + 'static,
+    I: io::AsyncRead + io::AsyncWrite + io::Peek + Send + Sync + Unpin + 'static,
+    N: svc::NewService<Http<T>, Service = S> + Clone + Send + 'static,
+    S: svc::Service<I, Response = ()> + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = ();
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut io: I) -> Self::Future {
+        let Params {
+            timeout,
+            hint,
+            emit_headers,
+            ..
+        } = self.params;
+        let target = self.target.clone();
+        let mismatched = self.mismatched.clone();
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            let mut buf = [0u8; PEEK_CAPACITY];
+            let detected = match time::timeout(timeout, io.peek(&mut buf)).await {
+                Ok(sz) => detect_version(&buf[..sz?]),
+                Err(_) => None,
+            };
+
+            let target = match detected {
+                Some(version) if version != target.version => {
+                    mismatched.inc();
+                    let error = HttpVersionMismatch {
+                        configured: target.version,
+                        detected: version,
+                    };
+                    if !hint {
+                        info!(%error, "Rejecting connection");
+                        respond(&mut io, &error, emit_headers).await;
+                        return Err(error.into());
+                    }
+                    debug!(%error, "Serving connection with the client's HTTP version");
+                    Http {
+                        version,
+                        parent: target.parent,
+                    }
+                }
+                _ => target,
+            };
+
+            let svc = inner.new_service(target);
+            svc.oneshot(io).await.map_err(Into::into)
+        })
+    }
+}
+
+/// Returns the HTTP version indicated by the first bytes of a connection, if
+/// any.
+fn detect_version(buf: &[u8]) -> Option<http::Variant> {
+    if buf.starts_with(H2_PREFACE) {
+        return Some(http::Variant::H2);
+    }
+
+    let end = buf.windows(2).position(|w| w == b"\r\n")?;
+    let line = &buf[..end + 2];
+    match httparse::Request::new(&mut [httparse::EMPTY_HEADER; 0]).parse(line) {
+        Ok(_) | Err(httparse::Error::TooManyHeaders) => Some(http::Variant::Http1),
+        Err(_) => None,
+    }
+}
+
+/// Answers the client with an HTTP 505 that describes the mismatch and closes
+/// the connection.
+async fn respond<I>(io: &mut I, error: &HttpVersionMismatch, emit_headers: bool)
+where
+    I: io::AsyncWrite + Unpin,
+{
+    let body = format!("{error}\n");
+    let header = if emit_headers {
+        format!(
+            "{L5D_PROXY_ERROR}: {}: {error}\r\n",
+            ErrorCode::ProtocolMismatch
+        )
+    } else {
+        String::new()
+    };
+    let rsp = format!(
+        "HTTP/1.1 505 HTTP Version Not Supported\r\n\
+         content-type: text/plain\r\n\
+         content-length: {}\r\n\
+         {header}\
+         connection: close\r\n\
+         \r\n\
+         {body}",
+        body.len(),
+    );
+    if let Err(error) = io.write_all(rsp.as_bytes()).await {
+        debug!(%error, "Failed to write response");
+    }
+    let _ = io.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use io::AsyncReadExt;
+    use linkerd_proxy_client_policy::Meta;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+    use svc::{Layer, NewService};
+
+    const H2_CLIENT_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+    const HTTP1_REQUEST: &[u8] = b"GET / HTTP/1.1\r\nhost: example.com\r\n\r\n";
+
+    #[derive(Clone, Debug)]
+    struct Target;
+
+    impl svc::Param<ParentRef> for Target {
+        fn param(&self) -> ParentRef {
+            ParentRef(Meta::new_default("test"))
+        }
+    }
+
+    #[test]
+    fn detects_versions() {
+        assert_eq!(detect_version(H2_CLIENT_PREFACE), Some(http::Variant::H2));
+        assert_eq!(detect_version(HTTP1_REQUEST), Some(http::Variant::Http1));
+        assert_eq!(
+            detect_version(b"POST /api HTTP/1.0\r\n"),
+            Some(http::Variant::Http1)
+        );
+
+        assert_eq!(detect_version(b""), None);
+        // Incomplete prefaces are served with the configured version.
+        assert_eq!(detect_version(b"PRI * HT"), None);
+        assert_eq!(detect_version(b"GET / HT"), None);
+        assert_eq!(detect_version(b"HELLO\r\n"), None);
+    }
+
+    async fn serve(
+        configured: http::Variant,
+        prefix: &'static [u8],
+        hint: bool,
+    ) -> (Result<()>, Option<http::Variant>, Vec<u8>, u64) {
+        let mut registry = prom::Registry::default();
+        let metrics = MetricsFamilies::register(&mut registry);
+        let served = Arc::new(Mutex::new(None));
+        let new_svc =
+            NewCheckVersion::layer(time::Duration::from_secs(1), hint, true, metrics.clone())
+                .layer({
+                    let served = served.clone();
+                    move |t: Http<Target>| {
+                        let served = served.clone();
+                        svc::mk(move |_: io::PrefixedIo<io::DuplexStream>| {
+                            *served.lock() = Some(t.version);
+                            futures::future::ok::<(), Error>(())
+                        })
+                    }
+                });
+
+        let (server, mut client) = io::duplex(1024);
+        let io = io::PrefixedIo::new(Bytes::from_static(prefix), server);
+        let res = new_svc
+            .new_service(Http {
+                version: configured,
+                parent: Target,
+            })
+            .oneshot(io)
+            .await;
+
+        let mut rsp = Vec::new();
+        client.read_to_end(&mut rsp).await.unwrap();
+        let mismatched = metrics
+            .version_mismatch(configured, svc::Param::param(&Target))
+            .get();
+        let served = *served.lock();
+        (res, served, rsp, mismatched)
+    }
+
+    #[tokio::test]
+    async fn serves_matching_versions() {
+        let (res, served, rsp, mismatched) =
+            serve(http::Variant::Http1, HTTP1_REQUEST, false).await;
+        assert!(res.is_ok());
+        assert_eq!(served, Some(http::Variant::Http1));
+        assert!(rsp.is_empty());
+        assert_eq!(mismatched, 0);
+
+        let (res, served, rsp, mismatched) =
+            serve(http::Variant::H2, H2_CLIENT_PREFACE, false).await;
+        assert!(res.is_ok());
+        assert_eq!(served, Some(http::Variant::H2));
+        assert!(rsp.is_empty());
+        assert_eq!(mismatched, 0);
+    }
+
+    #[tokio::test]
+    async fn rejects_h2_to_http1() {
+        let (res, served, rsp, mismatched) =
+            serve(http::Variant::Http1, H2_CLIENT_PREFACE, false).await;
+        assert!(res.unwrap_err().is::<HttpVersionMismatch>());
+        assert_eq!(served, None);
+        let rsp = String::from_utf8(rsp).unwrap();
+        assert!(rsp.starts_with("HTTP/1.1 505 HTTP Version Not Supported\r\n"));
+        assert!(rsp.contains(
+            "l5d-proxy-error: protocol_mismatch: client sent HTTP/2 to a destination configured for HTTP/1\r\n"
+        ));
+        assert_eq!(mismatched, 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_http1_to_h2() {
+        let (res, served, rsp, mismatched) = serve(http::Variant::H2, HTTP1_REQUEST, false).await;
+        assert!(res.unwrap_err().is::<HttpVersionMismatch>());
+        assert_eq!(served, None);
+        assert!(rsp.starts_with(b"HTTP/1.1 505 HTTP Version Not Supported\r\n"));
+        assert_eq!(mismatched, 1);
+    }
+
+    #[tokio::test]
+    async fn serves_client_version_as_hint() {
+        let (res, served, rsp, mismatched) =
+            serve(http::Variant::Http1, H2_CLIENT_PREFACE, true).await;
+        assert!(res.is_ok());
+        assert_eq!(served, Some(http::Variant::H2));
+        assert!(rsp.is_empty());
+        assert_eq!(mismatched, 1);
+
+        let (res, served, _, mismatched) = serve(http::Variant::H2, HTTP1_REQUEST, true).await;
+        assert!(res.is_ok());
+        assert_eq!(served, Some(http::Variant::Http1));
+        assert_eq!(mismatched, 1);
+    }
+}
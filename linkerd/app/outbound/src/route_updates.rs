@@ -0,0 +1,443 @@
+//! Coalesces route updates.
+//!
+//! Routes are recomputed from each policy or profile update, and each change
+//! published on a routes watch causes the stacks that observe it to be
+//! rebuilt. Control plane resyncs may deliver many updates in quick
+//! succession, few of which change the resulting routes. Updates received
+//! within a debounce window are applied together, and routes that are equal to
+//! the current routes are not published.
+//!
+//! Each routes configuration is identified by a [`RoutesFingerprint`], a hash
+//! of its contents, so that the configurations in use may be correlated with
+//! the proxy's behavior.
+//!
+//! Each published configuration is compared with the configuration it
+//! replaces, and the changes are counted and logged. Each watch logs changes at
+//! most once per [`CHANGE_LOG_INTERVAL`], so that resyncs do not flood the
+//! logs.
+
+use linkerd_app_core::metrics::prom;
+use parking_lot::Mutex;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+use tokio::{
+    sync::watch,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+mod diff;
+#[cfg(test)]
+mod tests;
+
+pub(crate) use self::diff::DiffRoutes;
+use self::diff::{ChangeKind, Changes, Field};
+
+/// The minimum interval between logged changes to a watch's routes.
+const CHANGE_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Spawns tasks that publish routes computed from policy or profile updates.
+#[derive(Clone, Debug)]
+pub struct RouteUpdates {
+    debounce: Duration,
+    metrics: RouteUpdateMetrics,
+}
+
+/// A hash of a routes configuration.
+///
+/// Fingerprints are computed from the contents of a configuration, so that
+/// equal configurations have equal fingerprints, even across restarts of the
+/// same proxy build.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RoutesFingerprint(u64);
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RouteUpdateMetrics {
+    published: prom::Family<ProtocolLabels, prom::Counter>,
+    suppressed: prom::Family<SuppressedLabels, prom::Counter>,
+    changes: prom::Family<ChangeLabels, prom::Counter>,
+    configs: ConfigMetrics,
+}
+
+/// Tracks the number of routes watches that use each configuration.
+#[derive(Clone, Debug, Default)]
+struct ConfigMetrics {
+    generations: prom::Family<ConfigLabels, prom::Gauge>,
+    // Serializes updates so that a configuration's series is removed when it
+    // is no longer used.
+    lock: Arc<Mutex<()>>,
+}
+
+/// Records a watch's configuration until its task completes.
+struct CurrentConfig {
+    protocol: Protocol,
+    fingerprint: RoutesFingerprint,
+    metrics: ConfigMetrics,
+}
+
+/// Logs changes to a watch's routes, at most once per [`CHANGE_LOG_INTERVAL`].
+#[derive(Debug, Default)]
+struct ChangeLog {
+    logged: Option<time::Instant>,
+    /// The number of changes that have not been logged since the last log.
+    elided: usize,
+}
+
+/// A 64-bit FNV-1a hasher.
+///
+/// Unlike the standard library's default hasher, this hasher is not keyed, so
+/// its output does not vary between processes.
+struct Fnv(u64);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+pub(crate) enum Protocol {
+    http,
+    tls,
+    opaq,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct ProtocolLabels {
+    protocol: Protocol,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct SuppressedLabels {
+    protocol: Protocol,
+    reason: Reason,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct ChangeLabels {
+    protocol: Protocol,
+    field: Field,
+    change: ChangeKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct ConfigLabels {
+    protocol: Protocol,
+    fingerprint: RoutesFingerprint,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum Reason {
+    /// The update was superseded by another update in the same debounce
+    /// window.
+    debounced,
+    /// The update did not change the routes.
+    unchanged,
+}
+
+// === impl RouteUpdates ===
+
+impl RouteUpdates {
+    pub(crate) fn new(debounce: Duration, metrics: RouteUpdateMetrics) -> Self {
+        Self { debounce, metrics }
+    }
+
+    /// Returns a watch of the routes computed by `mk` from each update on
+    /// `route_rx`.
+    ///
+    /// Updates for which `mk` returns `None` are ignored.
+    pub(crate) fn spawn<T, R>(
+        &self,
+        protocol: Protocol,
+        mut route_rx: watch::Receiver<T>,
+        init: R,
+        mut mk: impl FnMut(&T) -> Option<R> + Send + Sync + 'static,
+    ) -> watch::Receiver<R>
+    where
+        T: Send + Sync + 'static,
+        R: DiffRoutes + PartialEq + Hash + Send + Sync + 'static,
+    {
+        let mut current = CurrentConfig::new(
+            protocol,
+            RoutesFingerprint::of(&init),
+            self.metrics.configs.clone(),
+        );
+        let (tx, rx) = watch::channel(init);
+        let debounce = self.debounce;
+        let metrics = self.metrics.clone();
+
+        tokio::spawn(
+            async move {
+                let mut generation = 0u64;
+                let mut log = ChangeLog::default();
+                loop {
+                    let res = tokio::select! {
+                        biased;
+                        _ = tx.closed() => return,
+                        res = route_rx.changed() => res,
+                    };
+
+                    if res.is_err() {
+                        // Drop the `tx` sender when the profile sender is
+                        // dropped.
+                        return;
+                    }
+
+                    if !debounce.is_zero() {
+                        // The window is not extended by subsequent updates so that
+                        // a steady stream of updates cannot delay routes
+                        // indefinitely.
+                        let deadline = time::Instant::now() + debounce;
+                        loop {
+                            let res = tokio::select! {
+                                biased;
+                                _ = tx.closed() => return,
+                                _ = time::sleep_until(deadline) => break,
+                                res = route_rx.changed() => res,
+                            };
+                            if res.is_err() {
+                                return;
+                            }
+                            metrics.suppressed(protocol, Reason::debounced);
+                        }
+                    }
+
+                    let Some(routes) = (mk)(&*route_rx.borrow_and_update()) else {
+                        continue;
+                    };
+                    // Routes are only hashed when they change, since most
+                    // updates are unchanged.
+                    let mut fingerprint = current.fingerprint;
+                    let mut changes = Changes::default();
+                    let modified = tx.send_if_modified(|current| {
+                        if *current == routes {
+                            return false;
+                        }
+                        fingerprint = RoutesFingerprint::of(&routes);
+                        routes.diff(current, &mut changes);
+                        *current = routes;
+                        true
+                    });
+                    if modified {
+                        generation += 1;
+                        tracing::debug!(?protocol, %fingerprint, generation, "Routes updated");
+                        metrics.published(protocol);
+                        metrics.changed(protocol, &changes);
+                        log.changed(
+                            protocol,
+                            generation,
+                            current.fingerprint,
+                            fingerprint,
+                            &changes,
+                        );
+                        current.update(fingerprint);
+                    } else {
+                        tracing::trace!(?protocol, "Routes unchanged");
+                        metrics.suppressed(protocol, Reason::unchanged);
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+
+        rx
+    }
+}
+
+// === impl RoutesFingerprint ===
+
+impl RoutesFingerprint {
+    pub(crate) fn of<T: Hash + ?Sized>(routes: &T) -> Self {
+        let mut hasher = Fnv::default();
+        routes.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+impl fmt::Display for RoutesFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl prom::encoding::EncodeLabelValue for RoutesFingerprint {
+    fn encode(&self, enc: &mut prom::encoding::LabelValueEncoder<'_>) -> fmt::Result {
+        use fmt::Write;
+        write!(enc, "{self}")
+    }
+}
+
+// === impl Fnv ===
+
+impl Fnv {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+}
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// === impl RouteUpdateMetrics ===
+
+impl RouteUpdateMetrics {
+    /// Registers the configuration gauge in `registry` and update metrics in
+    /// its `route_updates` sub-registry.
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let configs = ConfigMetrics::default();
+        registry.register(
+            "route_config_generation",
+            "The number of routes watches that use each routes configuration",
+            configs.generations.clone(),
+        );
+
+        let registry = registry.sub_registry_with_prefix("route_updates");
+        let published = prom::Family::default();
+        registry.register(
+            "published",
+            "The number of route updates published to routing stacks",
+            published.clone(),
+        );
+        let suppressed = prom::Family::default();
+        registry.register(
+            "suppressed",
+            "The number of route updates that were not published to routing stacks",
+            suppressed.clone(),
+        );
+        let changes = prom::Family::default();
+        registry.register(
+            "changes",
+            "The number of changes to published routes configurations, by field",
+            changes.clone(),
+        );
+        Self {
+            published,
+            suppressed,
+            changes,
+            configs,
+        }
+    }
+
+    fn published(&self, protocol: Protocol) {
+        self.published
+            .get_or_create(&ProtocolLabels { protocol })
+            .inc();
+    }
+
+    fn suppressed(&self, protocol: Protocol, reason: Reason) {
+        self.suppressed
+            .get_or_create(&SuppressedLabels { protocol, reason })
+            .inc();
+    }
+
+    fn changed(&self, protocol: Protocol, changes: &Changes) {
+        for change in changes.iter() {
+            self.changes
+                .get_or_create(&ChangeLabels {
+                    protocol,
+                    field: change.field,
+                    change: change.kind,
+                })
+                .inc();
+        }
+    }
+}
+
+// === impl ChangeLog ===
+
+impl ChangeLog {
+    fn changed(
+        &mut self,
+        protocol: Protocol,
+        generation: u64,
+        prior: RoutesFingerprint,
+        fingerprint: RoutesFingerprint,
+        changes: &Changes,
+    ) {
+        let now = time::Instant::now();
+        if let Some(logged) = self.logged {
+            if now.saturating_duration_since(logged) < CHANGE_LOG_INTERVAL {
+                self.elided += 1;
+                return;
+            }
+        }
+        self.logged = Some(now);
+        let elided = std::mem::take(&mut self.elided);
+        tracing::info!(
+            ?protocol,
+            generation,
+            %prior,
+            %fingerprint,
+            elided,
+            %changes,
+            "Routes configuration changed"
+        );
+    }
+}
+
+// === impl ConfigMetrics ===
+
+impl ConfigMetrics {
+    fn inc(&self, protocol: Protocol, fingerprint: RoutesFingerprint) {
+        let _lock = self.lock.lock();
+        self.generations
+            .get_or_create(&ConfigLabels {
+                protocol,
+                fingerprint,
+            })
+            .inc();
+    }
+
+    fn dec(&self, protocol: Protocol, fingerprint: RoutesFingerprint) {
+        let labels = ConfigLabels {
+            protocol,
+            fingerprint,
+        };
+        let _lock = self.lock.lock();
+        if self.generations.get_or_create(&labels).dec() <= 1 {
+            self.generations.remove(&labels);
+        }
+    }
+}
+
+// === impl CurrentConfig ===
+
+impl CurrentConfig {
+    fn new(protocol: Protocol, fingerprint: RoutesFingerprint, metrics: ConfigMetrics) -> Self {
+        metrics.inc(protocol, fingerprint);
+        Self {
+            protocol,
+            fingerprint,
+            metrics,
+        }
+    }
+
+    fn update(&mut self, fingerprint: RoutesFingerprint) {
+        if fingerprint == self.fingerprint {
+            return;
+        }
+        self.metrics.inc(self.protocol, fingerprint);
+        self.metrics.dec(self.protocol, self.fingerprint);
+        self.fingerprint = fingerprint;
+    }
+}
+
+impl Drop for CurrentConfig {
+    fn drop(&mut self) {
+        self.metrics.dec(self.protocol, self.fingerprint);
+    }
+}
@@ -0,0 +1,382 @@
+//! Describes how routes configurations change.
+//!
+//! Each published routes configuration is compared with the configuration it
+//! replaces, so that changes to routing behavior (e.g. a backend being added
+//! or a route's retry policy changing) are recorded at the level of the
+//! fields that changed.
+
+use crate::{http, opaq, tls, ParentRef};
+use linkerd_app_core::metrics::prom;
+use linkerd_http_route as http_route;
+use linkerd_proxy_client_policy as policy;
+use std::fmt;
+
+/// Describes how a routes configuration differs from a prior configuration.
+pub(crate) trait DiffRoutes {
+    /// Records the changes from `prior` to `self`.
+    fn diff(&self, prior: &Self, changes: &mut Changes);
+}
+
+/// The changes between two routes configurations.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Changes(Vec<Change>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Change {
+    pub(crate) field: Field,
+    pub(crate) kind: ChangeKind,
+    /// Names the parent, route, or backend that changed.
+    pub(crate) name: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+pub(crate) enum Field {
+    /// The routes' parent or address, or the kind of routes, changed.
+    parent,
+    /// A route was added or removed, or its matches changed.
+    route,
+    backend,
+    filters,
+    distribution,
+    timeouts,
+    retry,
+    failure_accrual,
+    /// Any other route parameters.
+    params,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+pub(crate) enum ChangeKind {
+    added,
+    removed,
+    modified,
+}
+
+/// Describes how a route's parameters differ from its prior parameters.
+trait DiffParams {
+    fn diff(&self, prior: &Self, name: &str, changes: &mut Changes);
+}
+
+// === impl Changes ===
+
+impl Changes {
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Change> {
+        self.0.iter()
+    }
+
+    fn push(&mut self, field: Field, kind: ChangeKind, name: impl fmt::Display) {
+        self.0.push(Change {
+            field,
+            kind,
+            name: name.to_string(),
+        });
+    }
+
+    /// Records a modification of `field` if `prior` and `new` differ.
+    fn modified<T: PartialEq>(
+        &mut self,
+        field: Field,
+        name: impl fmt::Display,
+        prior: &T,
+        new: &T,
+    ) {
+        if prior != new {
+            self.push(field, ChangeKind::modified, name);
+        }
+    }
+
+    fn parent(&mut self, prior: &ParentRef, new: &ParentRef) {
+        self.modified(Field::parent, &**new, prior, new);
+    }
+
+    fn backends(&mut self, prior: &[policy::Backend], new: &[policy::Backend]) {
+        self.keyed(
+            Field::backend,
+            prior.iter().map(|b| (b.meta.to_string(), b)),
+            new.iter().map(|b| (b.meta.to_string(), b)),
+            |changes, name, prior, new| changes.modified(Field::backend, name, prior, new),
+        );
+    }
+
+    fn rule_policy<F, P>(
+        &mut self,
+        name: &str,
+        prior: &policy::RoutePolicy<F, P>,
+        new: &policy::RoutePolicy<F, P>,
+    ) where
+        F: PartialEq,
+        P: DiffParams,
+    {
+        self.modified(Field::filters, name, &prior.filters, &new.filters);
+        self.modified(
+            Field::distribution,
+            name,
+            &prior.distribution,
+            &new.distribution,
+        );
+        new.params.diff(&prior.params, name, self);
+    }
+
+    /// Records the changes between HTTP or gRPC policy routes.
+    fn http_routes<M, F, P>(
+        &mut self,
+        prior: &[http_route::Route<M, policy::RoutePolicy<F, P>>],
+        new: &[http_route::Route<M, policy::RoutePolicy<F, P>>],
+    ) where
+        M: PartialEq,
+        F: PartialEq,
+        P: PartialEq + DiffParams,
+    {
+        self.keyed(
+            Field::route,
+            prior.iter().filter_map(named_http_route),
+            new.iter().filter_map(named_http_route),
+            |changes, name, prior, new| {
+                if prior.hosts != new.hosts
+                    || prior.rules.len() != new.rules.len()
+                    || prior
+                        .rules
+                        .iter()
+                        .zip(&new.rules)
+                        .any(|(p, n)| p.matches != n.matches)
+                {
+                    changes.push(Field::route, ChangeKind::modified, name);
+                    return;
+                }
+                for (prior, new) in prior.rules.iter().zip(&new.rules) {
+                    changes.rule_policy(name, &prior.policy, &new.policy);
+                }
+            },
+        );
+    }
+
+    fn policy_params<M, F, P>(
+        &mut self,
+        prior: &http::policy::router::Params<M, F, P>,
+        new: &http::policy::router::Params<M, F, P>,
+    ) where
+        M: PartialEq,
+        F: PartialEq,
+        P: PartialEq + DiffParams,
+    {
+        self.parent(&prior.meta, &new.meta);
+        self.modified(Field::parent, &new.addr, &prior.addr, &new.addr);
+        self.modified(
+            Field::failure_accrual,
+            &*new.meta,
+            &prior.failure_accrual,
+            &new.failure_accrual,
+        );
+        self.backends(&prior.backends, &new.backends);
+        self.http_routes(&prior.routes, &new.routes);
+    }
+
+    /// Records added and removed items, which are identified by name, and
+    /// uses `modified` to record changes to items that remain.
+    ///
+    /// Items with the same name are matched in order.
+    fn keyed<'a, T: PartialEq + 'a>(
+        &mut self,
+        field: Field,
+        prior: impl IntoIterator<Item = (String, &'a T)>,
+        new: impl IntoIterator<Item = (String, &'a T)>,
+        mut modified: impl FnMut(&mut Self, &str, &'a T, &'a T),
+    ) {
+        let mut prior = prior.into_iter().map(Some).collect::<Vec<_>>();
+        for (name, new) in new {
+            let found = prior
+                .iter_mut()
+                .find(|p| matches!(p, Some((n, _)) if *n == name))
+                .and_then(Option::take);
+            match found {
+                Some((_, prior)) if prior != new => modified(self, &name, prior, new),
+                Some(_) => {}
+                None => self.push(field, ChangeKind::added, name),
+            }
+        }
+        for (name, _) in prior.into_iter().flatten() {
+            self.push(field, ChangeKind::removed, name);
+        }
+    }
+}
+
+impl fmt::Display for Changes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, Change { field, kind, name }) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{field:?} {name} {kind:?}")?;
+        }
+        Ok(())
+    }
+}
+
+// === impl DiffParams ===
+
+impl DiffParams for policy::http::RouteParams {
+    fn diff(&self, prior: &Self, name: &str, changes: &mut Changes) {
+        changes.modified(Field::timeouts, name, &prior.timeouts, &self.timeouts);
+        changes.modified(Field::retry, name, &prior.retry, &self.retry);
+        let rest = |p: &Self| {
+            (
+                p.allow_l5d_request_headers,
+                p.export_hostname_labels,
+                p.export_method_labels,
+                p.failure_statuses.clone(),
+                p.rollout_guard.clone(),
+                p.cache.clone(),
+                p.fault.clone(),
+            )
+        };
+        changes.modified(Field::params, name, &rest(prior), &rest(self));
+    }
+}
+
+impl DiffParams for policy::grpc::RouteParams {
+    fn diff(&self, prior: &Self, name: &str, changes: &mut Changes) {
+        changes.modified(Field::timeouts, name, &prior.timeouts, &self.timeouts);
+        changes.modified(Field::retry, name, &prior.retry, &self.retry);
+        let rest = |p: &Self| {
+            (
+                p.allow_l5d_request_headers,
+                p.export_hostname_labels,
+                p.failure_codes.clone(),
+                p.rollout_guard.clone(),
+                p.fault.clone(),
+            )
+        };
+        changes.modified(Field::params, name, &rest(prior), &rest(self));
+    }
+}
+
+impl DiffParams for policy::tls::RouteParams {
+    fn diff(&self, prior: &Self, name: &str, changes: &mut Changes) {
+        changes.modified(Field::params, name, prior, self);
+    }
+}
+
+impl DiffParams for () {
+    fn diff(&self, _: &Self, _: &str, _: &mut Changes) {}
+}
+
+// === impl DiffRoutes ===
+
+impl DiffRoutes for http::Routes {
+    fn diff(&self, prior: &Self, changes: &mut Changes) {
+        match (prior, self) {
+            (Self::Policy(prior), Self::Policy(new)) => new.diff(prior, changes),
+            (Self::Profile(prior), Self::Profile(new)) => new.diff(prior, changes),
+            (Self::Endpoint(prior, prior_meta), Self::Endpoint(new, new_meta)) => {
+                changes.modified(Field::parent, new, prior, new);
+                changes.modified(Field::backend, new, prior_meta, new_meta);
+            }
+            (_, Self::Policy(new)) => changes.push(Field::parent, ChangeKind::modified, new.addr()),
+            (_, Self::Profile(new)) => changes.push(Field::parent, ChangeKind::modified, &new.addr),
+            (_, Self::Endpoint(new, _)) => changes.push(Field::parent, ChangeKind::modified, new),
+        }
+    }
+}
+
+impl DiffRoutes for http::policy::Params {
+    fn diff(&self, prior: &Self, changes: &mut Changes) {
+        match (prior, self) {
+            (Self::Http(prior), Self::Http(new)) => changes.policy_params(prior, new),
+            (Self::Grpc(prior), Self::Grpc(new)) => changes.policy_params(prior, new),
+            _ => changes.push(Field::parent, ChangeKind::modified, self.addr()),
+        }
+    }
+}
+
+impl DiffRoutes for http::profile::Routes {
+    fn diff(&self, prior: &Self, changes: &mut Changes) {
+        let addr = &self.addr;
+        changes.modified(Field::parent, addr, &prior.addr, addr);
+        changes.keyed(
+            Field::backend,
+            prior.targets.iter().map(|t| (t.addr.to_string(), t)),
+            self.targets.iter().map(|t| (t.addr.to_string(), t)),
+            |changes, name, prior, new| changes.modified(Field::backend, name, prior, new),
+        );
+
+        // Profile routes are unnamed, so they are identified by their order.
+        if prior.routes.len() != self.routes.len()
+            || prior
+                .routes
+                .iter()
+                .zip(&*self.routes)
+                .any(|((p, _), (n, _))| p != n)
+        {
+            changes.push(Field::route, ChangeKind::modified, addr);
+            return;
+        }
+        for (i, ((_, prior), (_, new))) in prior.routes.iter().zip(&*self.routes).enumerate() {
+            let name = format!("{addr}#{i}");
+            changes.modified(Field::timeouts, &name, &prior.timeout(), &new.timeout());
+            changes.modified(Field::retry, &name, &prior.retries(), &new.retries());
+            let rest = |r: &crate::http::profile::Route| {
+                (r.labels().clone(), r.response_classes().clone())
+            };
+            changes.modified(Field::params, &name, &rest(prior), &rest(new));
+        }
+    }
+}
+
+impl DiffRoutes for tls::Routes {
+    fn diff(&self, prior: &Self, changes: &mut Changes) {
+        changes.parent(&prior.meta, &self.meta);
+        changes.modified(Field::parent, &self.addr, &prior.addr, &self.addr);
+        changes.backends(&prior.backends, &self.backends);
+        changes.modified(Field::params, &*self.meta, &prior.sni, &self.sni);
+        changes.keyed(
+            Field::route,
+            prior.routes.iter().map(named_tls_route),
+            self.routes.iter().map(named_tls_route),
+            |changes, name, prior, new| {
+                if prior.snis != new.snis {
+                    changes.push(Field::route, ChangeKind::modified, name);
+                    return;
+                }
+                changes.rule_policy(name, &prior.policy, &new.policy);
+            },
+        );
+    }
+}
+
+impl DiffRoutes for opaq::Routes {
+    fn diff(&self, prior: &Self, changes: &mut Changes) {
+        changes.parent(&prior.logical.meta, &self.logical.meta);
+        changes.modified(
+            Field::parent,
+            &self.logical.addr,
+            &prior.logical.addr,
+            &self.logical.addr,
+        );
+        changes.backends(&prior.backends, &self.backends);
+        changes.keyed(
+            Field::route,
+            prior.routes.iter().map(named_opaq_route),
+            self.routes.iter().map(named_opaq_route),
+            |changes, name, prior, new| changes.rule_policy(name, &prior.policy, &new.policy),
+        );
+    }
+}
+
+/// Identifies HTTP and gRPC routes by the metadata of their rules' policies.
+/// Routes without rules are ignored.
+fn named_http_route<M, F, P>(
+    route: &http_route::Route<M, policy::RoutePolicy<F, P>>,
+) -> Option<(String, &http_route::Route<M, policy::RoutePolicy<F, P>>)> {
+    let rule = route.rules.first()?;
+    Some((rule.policy.meta.to_string(), route))
+}
+
+fn named_tls_route(route: &policy::tls::Route) -> (String, &policy::tls::Route) {
+    (route.policy.meta.to_string(), route)
+}
+
+fn named_opaq_route(route: &policy::opaq::Route) -> (String, &policy::opaq::Route) {
+    (route.policy.meta.to_string(), route)
+}
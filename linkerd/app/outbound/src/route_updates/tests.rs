@@ -0,0 +1,218 @@
+use super::*;
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn debounces_updates() {
+    let metrics = RouteUpdateMetrics::register(&mut Default::default());
+    let updates = RouteUpdates::new(Duration::from_millis(100), metrics.clone());
+    let (tx, rx) = watch::channel(0);
+    let mut routes = updates.spawn(Protocol::http, rx, 0, |v: &u32| Some(*v));
+
+    for v in 1..=3 {
+        tx.send(v).unwrap();
+        tokio::task::yield_now().await;
+    }
+    assert!(!routes.has_changed().unwrap(), "routes must be debounced");
+
+    time::sleep(Duration::from_millis(100)).await;
+    routes.changed().await.unwrap();
+    assert_eq!(*routes.borrow_and_update(), 3);
+    assert_eq!(published(&metrics, Protocol::http), 1);
+    assert_eq!(suppressed(&metrics, Protocol::http, Reason::debounced), 2);
+
+    // Updates after the window are applied in a new window.
+    tx.send(4).unwrap();
+    time::sleep(Duration::from_millis(100)).await;
+    routes.changed().await.unwrap();
+    assert_eq!(*routes.borrow_and_update(), 4);
+    assert_eq!(published(&metrics, Protocol::http), 2);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn skips_unchanged_routes() {
+    let metrics = RouteUpdateMetrics::register(&mut Default::default());
+    let updates = RouteUpdates::new(Duration::ZERO, metrics.clone());
+    let (tx, rx) = watch::channel(0);
+    let mut routes = updates.spawn(Protocol::tls, rx, 0, |v: &u32| Some(*v / 10));
+
+    for v in 1..=3 {
+        tx.send(v).unwrap();
+        tokio::task::yield_now().await;
+    }
+    assert!(!routes.has_changed().unwrap(), "routes did not change");
+    assert_eq!(suppressed(&metrics, Protocol::tls, Reason::unchanged), 3);
+
+    tx.send(10).unwrap();
+    routes.changed().await.unwrap();
+    assert_eq!(*routes.borrow_and_update(), 1);
+    assert_eq!(published(&metrics, Protocol::tls), 1);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn ignores_updates_without_routes() {
+    let metrics = RouteUpdateMetrics::register(&mut Default::default());
+    let updates = RouteUpdates::new(Duration::ZERO, metrics.clone());
+    let (tx, rx) = watch::channel(0);
+    let mut routes = updates.spawn(Protocol::opaq, rx, 0, |v: &u32| (*v % 2 == 0).then_some(*v));
+
+    tx.send(1).unwrap();
+    tokio::task::yield_now().await;
+    assert!(!routes.has_changed().unwrap());
+
+    tx.send(2).unwrap();
+    routes.changed().await.unwrap();
+    assert_eq!(*routes.borrow_and_update(), 2);
+
+    // The routes watch is closed when its source is dropped.
+    drop(tx);
+    assert!(routes.changed().await.is_err());
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn tracks_configs_by_fingerprint() {
+    let metrics = RouteUpdateMetrics::register(&mut Default::default());
+    let updates = RouteUpdates::new(Duration::ZERO, metrics.clone());
+    let (tx, rx) = watch::channel(0);
+    let mut routes = updates.spawn(Protocol::http, rx, 0, |v: &u32| Some(*v));
+    let (first, second) = (RoutesFingerprint::of(&0u32), RoutesFingerprint::of(&1u32));
+    assert_ne!(first, second);
+    assert_eq!(configs(&metrics, Protocol::http, first), 1);
+
+    tx.send(1).unwrap();
+    routes.changed().await.unwrap();
+    assert_eq!(configs(&metrics, Protocol::http, first), 0);
+    assert_eq!(configs(&metrics, Protocol::http, second), 1);
+
+    // A configuration is no longer counted once its watch is closed.
+    drop(tx);
+    assert!(routes.changed().await.is_err());
+    assert_eq!(configs(&metrics, Protocol::http, second), 0);
+}
+
+#[test]
+fn fingerprints_hash_contents() {
+    use linkerd_app_core::profiles::http::{RequestMatch, Route};
+    use std::sync::Arc;
+    use tower::retry::budget::TpsBudget;
+
+    // Routes that are built separately are not equal, since they do not share
+    // retry budgets, but they have the same configuration.
+    let mk = || {
+        let mut route = Route::new(
+            [("route".to_string(), "a".to_string())].into_iter(),
+            Vec::new(),
+        );
+        route.set_retries(Arc::new(TpsBudget::new(Duration::from_secs(10), 10, 0.2)));
+        vec![(RequestMatch::default(), route)]
+    };
+    assert_eq!(RoutesFingerprint::of(&mk()), RoutesFingerprint::of(&mk()));
+
+    let mut other = mk();
+    other[0].1.set_timeout(Duration::from_secs(1));
+    assert_ne!(RoutesFingerprint::of(&mk()), RoutesFingerprint::of(&other));
+
+    // Fingerprints do not depend on the process's hasher keys.
+    let mut hasher = Fnv::default();
+    hasher.write(b"a");
+    assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+}
+
+#[test]
+fn diffs_backends_and_routes() {
+    use crate::{tls, ParentRef};
+    use linkerd_app_core::Addr;
+    use linkerd_proxy_client_policy as policy;
+    use std::sync::Arc;
+
+    let backend = |name: &'static str, capacity: usize| policy::Backend {
+        meta: policy::Meta::new_default(name),
+        queue: policy::Queue {
+            capacity,
+            failfast_timeout: Duration::from_secs(3),
+        },
+        dispatcher: policy::BackendDispatcher::Fail {
+            message: "fail".into(),
+        },
+    };
+    let route = |name: &'static str, export_hostname_labels: bool| policy::tls::Route {
+        snis: vec![],
+        policy: policy::RoutePolicy {
+            meta: policy::Meta::new_default(name),
+            filters: Arc::new([]),
+            distribution: policy::RouteDistribution::Empty,
+            params: policy::tls::RouteParams {
+                export_hostname_labels,
+            },
+        },
+    };
+    let routes = |backends: Vec<policy::Backend>, routes: Vec<policy::tls::Route>| tls::Routes {
+        addr: Addr::Socket(([192, 0, 2, 1], 8080).into()),
+        meta: ParentRef(policy::Meta::new_default("parent")),
+        routes: routes.into(),
+        backends: backends.into(),
+        sni: None,
+    };
+
+    let prior = routes(
+        vec![backend("a", 10), backend("b", 10)],
+        vec![route("r", false)],
+    );
+    let new = routes(
+        vec![backend("b", 20), backend("c", 10)],
+        vec![route("r", true), route("s", false)],
+    );
+    let mut changes = Changes::default();
+    new.diff(&prior, &mut changes);
+    let changes = changes
+        .iter()
+        .map(|c| (c.field, c.kind, c.name.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            (Field::backend, ChangeKind::modified, "default.b"),
+            (Field::backend, ChangeKind::added, "default.c"),
+            (Field::backend, ChangeKind::removed, "default.a"),
+            (Field::params, ChangeKind::modified, "default.r"),
+            (Field::route, ChangeKind::added, "default.s"),
+        ]
+    );
+
+    let mut changes = Changes::default();
+    prior.diff(&prior, &mut changes);
+    assert_eq!(changes, Changes::default(), "equal routes must not change");
+}
+
+// === Utils ===
+
+impl DiffRoutes for u32 {
+    fn diff(&self, _: &Self, _: &mut Changes) {}
+}
+
+fn published(metrics: &RouteUpdateMetrics, protocol: Protocol) -> u64 {
+    metrics
+        .published
+        .get_or_create(&ProtocolLabels { protocol })
+        .get()
+}
+
+fn suppressed(metrics: &RouteUpdateMetrics, protocol: Protocol, reason: Reason) -> u64 {
+    metrics
+        .suppressed
+        .get_or_create(&SuppressedLabels { protocol, reason })
+        .get()
+}
+
+fn configs(
+    metrics: &RouteUpdateMetrics,
+    protocol: Protocol,
+    fingerprint: RoutesFingerprint,
+) -> i64 {
+    metrics
+        .configs
+        .generations
+        .get_or_create(&ConfigLabels {
+            protocol,
+            fingerprint,
+        })
+        .get()
+}
@@ -1,9 +1,13 @@
 use crate::{
-    http, opaq, policy,
+    http,
+    metrics::LogicalNameLabels,
+    opaq, policy, prewarm,
     protocol::{self, Protocol},
-    tls, Discovery, Outbound, ParentRef,
+    snapshot, tcp, tls, Discovery, ForwardReason, Outbound, ParentRef, RouteUpdates,
 };
+use futures::future;
 use linkerd_app_core::{
+    disco_cache::NewCachedDiscover,
     io, profiles,
     proxy::{
         api_resolve::{ConcreteAddr, Metadata},
@@ -11,11 +15,14 @@ use linkerd_app_core::{
     },
     svc,
     transport::addrs::*,
-    Addr, Error,
+    Addr, Error, NameAddr,
 };
-use std::fmt::Debug;
+use std::{fmt::Debug, net::SocketAddr, sync::Arc};
 use tokio::sync::watch;
-use tracing::info_span;
+use tracing::{info_span, Instrument};
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests;
 
 /// A target type holding discovery information for a sidecar proxy.
 #[derive(Clone, Debug)]
@@ -23,6 +30,11 @@ struct Sidecar {
     orig_dst: OrigDstAddr,
     profile: Option<profiles::Receiver>,
     policy: policy::Receiver,
+    forward: Option<ForwardReason>,
+    detect_protocol: bool,
+    discover_profiles: bool,
+    route_updates: RouteUpdates,
+    logical_names: LogicalNameLabels,
 }
 
 #[derive(Clone, Debug)]
@@ -30,6 +42,7 @@ struct HttpSidecar {
     orig_dst: OrigDstAddr,
     version: http::Variant,
     routes: watch::Receiver<http::Routes>,
+    addrs: http::RoutesAddrs,
     provider: RouteProvider,
 }
 
@@ -74,14 +87,50 @@ impl Outbound<()> {
         T: svc::Param<OrigDstAddr>,
         T: Clone + Send + Sync + 'static,
         // Server-side socket.
-        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr,
+        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr + io::Splice,
         I: Debug + Unpin + Send + Sync + 'static,
         // Endpoint resolver.
         R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
         R::Resolution: Unpin,
     {
+        self.to_tcp_connect()
+            .push_sidecar(profiles, policies, resolve)
+            .into_inner()
+    }
+}
+
+impl<C> Outbound<C> {
+    /// Builds a sidecar stack that establishes endpoint connections with the
+    /// inner connector.
+    pub(crate) fn push_sidecar<T, I, R>(
+        self,
+        profiles: impl profiles::GetProfile<Error = Error>,
+        policies: impl policy::GetPolicy,
+        resolve: R,
+    ) -> Outbound<svc::ArcNewTcp<T, I>>
+    where
+        // Target describing an outbound connection.
+        T: svc::Param<OrigDstAddr>,
+        T: Clone + Send + Sync + 'static,
+        // Server-side socket.
+        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr + io::Splice,
+        I: Debug + Unpin + Send + Sync + 'static,
+        // Endpoint resolver.
+        R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
+        R::Resolution: Unpin,
+        // TCP endpoint connector.
+        C: svc::MakeConnection<tcp::Connect, Metadata = Local<ClientAddr>, Error = io::Error>,
+        C: Clone + Send + Sync + Unpin + 'static,
+        C::Connection: io::Splice + Send + Unpin,
+        C::Future: Send + Unpin,
+    {
+        // Destinations in the discovery snapshot are served provisional
+        // endpoints while they are resolved.
+        let resolve =
+            snapshot::ProvisionalResolve::new(resolve, self.runtime.discovery_snapshot.clone());
+
         let opaq = self.clone().with_stack(
-            self.to_tcp_connect()
+            self.clone()
                 .push_opaq_cached(resolve.clone())
                 .into_stack()
                 .push_map_target(OpaqSidecar::from)
@@ -89,14 +138,14 @@ impl Outbound<()> {
         );
 
         let tls = self
-            .to_tcp_connect()
+            .clone()
             .push_tls_cached(resolve.clone())
             .into_stack()
             .push_map_target(TlsSidecar::from)
             .arc_new_clone_tcp();
 
         let http = self
-            .to_tcp_connect()
+            .clone()
             .push_tcp_endpoint()
             .push_http_tcp_client()
             .push_http_cached(resolve)
@@ -105,34 +154,160 @@ impl Outbound<()> {
             .push_map_target(HttpSidecar::from)
             .arc_new_clone_http();
 
+        // When profile discovery is disabled, lookups resolve immediately
+        // without a profile.
+        let discover_profiles = self.config.discover_profiles;
+        let profiles = svc::mk(move |addr: profiles::LookupAddr| {
+            if discover_profiles {
+                future::Either::Left(profiles.clone().get_profile(addr))
+            } else {
+                future::Either::Right(future::ok(None))
+            }
+        });
+        let builds = &self.runtime.metrics.prom.stack_builds;
+        let discover = NewCachedDiscover::new(
+            (),
+            self.resolver(profiles, policies),
+            self.config.discovery_idle_timeout,
+        )
+        .with_builds_gauge(builds.in_progress("discover"));
+        let discover = match self.runtime.discovery_retention.clone() {
+            Some(periodic) => discover.with_periodic(periodic),
+            None => discover,
+        };
+        if !self.config.prewarm.addrs.is_empty() {
+            self.spawn_prewarm(
+                discover.clone(),
+                http.clone().into_inner(),
+                opaq.clone().into_inner(),
+                tls.clone().into_inner(),
+            );
+        }
+
         opaq.clone()
             .push_protocol(http.into_inner(), tls.into_inner())
             // Use a dedicated target type to bind discovery results to the
             // outbound sidecar stack configuration.
-            .map_stack(move |_, _, stk| stk.push_map_target(Sidecar::from))
+            .map_stack(move |config, rt, stk| {
+                let detect_protocol = config.listener.detect_protocol;
+                let discover_profiles = config.discover_profiles;
+                let route_updates = rt.route_updates.clone();
+                let logical_names = rt.logical_names.clone();
+                // Record why targets are forwarded on each connection.
+                stk.push(crate::discover::NewRecordForward::layer(
+                    rt.metrics.prom.forward.clone(),
+                ))
+                .push_map_target(move |discovery| {
+                    Sidecar::new(
+                        discovery,
+                        detect_protocol,
+                        discover_profiles,
+                        route_updates.clone(),
+                        logical_names.clone(),
+                    )
+                })
+            })
             // Access cached discovery information.
-            .push_discover(self.resolver(profiles, policies))
+            .push_discover_cache(discover)
             // Instrument server-side connections for telemetry.
             .push_tcp_instrument(|t: &T| {
                 let addr: OrigDstAddr = t.param();
-                info_span!("proxy", %addr)
+                info_span!("proxy", %addr, forward = tracing::field::Empty)
             })
-            .into_inner()
+    }
+
+    /// Spawns a task that discovers each of the configured prewarm addresses
+    /// through the sidecar's discovery cache.
+    ///
+    /// When endpoints are prewarmed, the cached HTTP, opaque, or TLS stack for
+    /// each destination is built as soon as its protocol is known, so that its
+    /// balancers resolve and connect to endpoints. Destinations that require
+    /// protocol detection are only discovered.
+    fn spawn_prewarm<D, H, O, L>(
+        &self,
+        discover: NewCachedDiscover<OrigDstAddr, D, ()>,
+        new_http: H,
+        new_opaq: O,
+        new_tls: L,
+    ) where
+        D: svc::Service<OrigDstAddr, Error = Error> + Clone + Send + Sync + 'static,
+        D::Response: Clone + Send + Sync + 'static,
+        D::Future: Send + Unpin,
+        Discovery<OrigDstAddr>: From<(D::Response, OrigDstAddr)>,
+        H: svc::NewService<protocol::Http<Sidecar>> + Send + Sync + 'static,
+        H::Service: Send,
+        O: svc::NewService<Sidecar> + Send + Sync + 'static,
+        O::Service: Send,
+        L: svc::NewService<Sidecar> + Send + Sync + 'static,
+        L::Service: Send,
+    {
+        let prewarm::PrewarmConfig { addrs, endpoints } = self.config.prewarm.clone();
+        let detect_protocol = self.config.listener.detect_protocol;
+        let discover_profiles = self.config.discover_profiles;
+        let route_updates = self.runtime.route_updates.clone();
+        let logical_names = self.runtime.logical_names.clone();
+        let metrics = self.runtime.metrics.prom.prewarm.clone();
+        let warm = move |addr: OrigDstAddr, rsp: &svc::idle_cache::Cached<D::Response>| {
+            if !endpoints {
+                return None;
+            }
+            let sidecar = Sidecar::new(
+                Discovery::from(((**rsp).clone(), addr)),
+                detect_protocol,
+                discover_profiles,
+                route_updates.clone(),
+                logical_names.clone(),
+            );
+            let svc = match svc::Param::<Protocol>::param(&sidecar) {
+                Protocol::Http1 => svc::Either::Left(
+                    new_http.new_service(protocol::Http::from((http::Variant::Http1, sidecar))),
+                ),
+                Protocol::Http2 => svc::Either::Left(
+                    new_http.new_service(protocol::Http::from((http::Variant::H2, sidecar))),
+                ),
+                Protocol::Opaque => {
+                    svc::Either::Right(svc::Either::Left(new_opaq.new_service(sidecar)))
+                }
+                Protocol::Tls => {
+                    svc::Either::Right(svc::Either::Right(new_tls.new_service(sidecar)))
+                }
+                Protocol::Detect => {
+                    tracing::debug!(addr = %addr.0, "Protocol detection required; only discovery is prewarmed");
+                    return None;
+                }
+            };
+            Some(svc)
+        };
+        tokio::spawn(
+            prewarm::prewarm(addrs, move |addr| discover.discover(addr), warm, metrics)
+                .instrument(info_span!("prewarm")),
+        );
     }
 }
 
 // === impl Sidecar ===
 
-impl<T> From<Discovery<T>> for Sidecar
-where
-    T: svc::Param<OrigDstAddr>,
-{
-    fn from(parent: Discovery<T>) -> Self {
+impl Sidecar {
+    fn new<T>(
+        parent: Discovery<T>,
+        detect_protocol: bool,
+        discover_profiles: bool,
+        route_updates: RouteUpdates,
+        logical_names: LogicalNameLabels,
+    ) -> Self
+    where
+        T: svc::Param<OrigDstAddr>,
+    {
         use svc::Param;
         Self {
             policy: parent.param(),
             profile: parent.param(),
+            forward: parent.param(),
             orig_dst: (*parent).param(),
+            detect_protocol,
+            discover_profiles,
+            route_updates,
+            logical_names,
         }
     }
 }
@@ -156,11 +331,19 @@ impl svc::Param<Option<profiles::Receiver>> for Sidecar {
     }
 }
 
+impl svc::Param<Option<ForwardReason>> for Sidecar {
+    fn param(&self) -> Option<ForwardReason> {
+        self.forward
+    }
+}
+
 impl svc::Param<Protocol> for Sidecar {
     fn param(&self) -> Protocol {
-        if let Some(rx) = svc::Param::<Option<profiles::Receiver>>::param(self) {
-            if rx.is_opaque_protocol() {
-                return Protocol::Opaque;
+        if self.discover_profiles {
+            if let Some(rx) = svc::Param::<Option<profiles::Receiver>>::param(self) {
+                if rx.is_opaque_protocol() {
+                    return Protocol::Opaque;
+                }
             }
         }
 
@@ -169,6 +352,9 @@ impl svc::Param<Protocol> for Sidecar {
             policy::Protocol::Http2(_) | policy::Protocol::Grpc(_) => Protocol::Http2,
             policy::Protocol::Opaque(_) => Protocol::Opaque,
             policy::Protocol::Tls(_) => Protocol::Tls,
+            // When detection is disabled, connections whose protocol is not
+            // known are handled as opaque.
+            policy::Protocol::Detect { .. } if !self.detect_protocol => Protocol::Opaque,
             policy::Protocol::Detect { .. } => Protocol::Detect,
         }
     }
@@ -208,30 +394,71 @@ impl From<protocol::Http<Sidecar>> for HttpSidecar {
             if let Some(addr) = http::profile::should_override_policy(&profile) {
                 tracing::debug!("Using ServiceProfile");
                 let init = Self::mk_profile_routes(addr.clone(), &profile.borrow_and_update());
-                let routes =
-                    http::spawn_routes(profile, init, move |profile: &profiles::Profile| {
+                let routes = http::spawn_routes(
+                    &parent.route_updates,
+                    profile,
+                    init,
+                    move |profile: &profiles::Profile| {
                         Some(Self::mk_profile_routes(addr.clone(), profile))
-                    });
+                    },
+                );
                 let provider = RouteProvider::ServiceProfile;
                 return HttpSidecar {
                     orig_dst,
                     version,
+                    addrs: http::RoutesAddrs::new(routes.clone()),
                     routes,
                     provider,
                 };
             }
         }
 
+        // When the original destination is an endpoint (e.g. a pod IP) rather
+        // than a service, its parent's routes are trimmed to forward requests
+        // to the endpoint, so that the routes' timeouts and retries apply.
+        let endpoint = parent
+            .profile
+            .as_ref()
+            .and_then(profiles::Receiver::endpoint)
+            .map(|(addr, meta)| (addr, Arc::new(meta)));
+
+        // Client policy does not name some destinations, e.g. ExternalName
+        // services, though their profiles may.
+        let logical_name = parent
+            .profile
+            .as_ref()
+            .and_then(profiles::Receiver::logical_addr)
+            .map(|profiles::LogicalAddr(name)| name)
+            .filter(|name| is_default_policy(&policy.borrow()) && parent.logical_names.admit(name));
+
         tracing::debug!("Using ClientPolicy routes");
-        let init = Self::mk_policy_routes(orig_dst, version, &policy.borrow_and_update())
-            .expect("initial policy must not be opaque");
-        let routes = http::spawn_routes(policy, init, move |policy: &policy::ClientPolicy| {
-            Self::mk_policy_routes(orig_dst, version, policy)
-        });
+        let init = Self::mk_policy_routes(
+            orig_dst,
+            logical_name.as_ref(),
+            version,
+            endpoint.as_ref(),
+            &policy.borrow_and_update(),
+        )
+        .expect("initial policy must not be opaque");
+        let routes = http::spawn_routes(
+            &parent.route_updates,
+            policy,
+            init,
+            move |policy: &policy::ClientPolicy| {
+                Self::mk_policy_routes(
+                    orig_dst,
+                    logical_name.as_ref(),
+                    version,
+                    endpoint.as_ref(),
+                    policy,
+                )
+            },
+        );
         let provider = RouteProvider::ClientPolicy;
         HttpSidecar {
             orig_dst,
             version,
+            addrs: http::RoutesAddrs::new(routes.clone()),
             routes,
             provider,
         }
@@ -240,12 +467,40 @@ impl From<protocol::Http<Sidecar>> for HttpSidecar {
 
 impl HttpSidecar {
     fn mk_policy_routes(
-        OrigDstAddr(orig_dst): OrigDstAddr,
+        orig_dst: OrigDstAddr,
+        logical_name: Option<&NameAddr>,
         version: http::Variant,
+        endpoint: Option<&(SocketAddr, Arc<Metadata>)>,
         policy: &policy::ClientPolicy,
     ) -> Option<http::Routes> {
+        let params = Self::mk_policy_params(orig_dst, logical_name, version, policy)?;
+        let params = match endpoint
+            .and_then(|(addr, meta)| params.forward_to_endpoint(*addr, meta.clone()))
+        {
+            Some(trimmed) => {
+                tracing::debug!("Forwarding the parent's routes to the endpoint");
+                trimmed
+            }
+            None => params,
+        };
+        Some(http::Routes::Policy(params))
+    }
+
+    fn mk_policy_params(
+        OrigDstAddr(orig_dst): OrigDstAddr,
+        logical_name: Option<&NameAddr>,
+        version: http::Variant,
+        policy: &policy::ClientPolicy,
+    ) -> Option<http::policy::Params> {
         let parent_ref = ParentRef(policy.parent.clone());
 
+        // Prefer the profile's logical name, so that metrics for traffic that
+        // is forwarded by a default policy are labeled with it.
+        let addr = match logical_name {
+            Some(name) if is_default_policy(policy) => Addr::Name(name.clone()),
+            _ => orig_dst.into(),
+        };
+
         // If we're doing HTTP policy routing, we've previously had a
         // protocol hint that made us think that was a good idea. If the
         // protocol changes but remains HTTP-ish, we propagate those
@@ -272,15 +527,13 @@ impl HttpSidecar {
                 ref routes,
                 failure_accrual,
             }) => {
-                return Some(http::Routes::Policy(http::policy::Params::Grpc(
-                    http::policy::GrpcParams {
-                        addr: orig_dst.into(),
-                        meta: parent_ref,
-                        backends: policy.backends.clone(),
-                        routes: routes.clone(),
-                        failure_accrual,
-                    },
-                )))
+                return Some(http::policy::Params::Grpc(http::policy::GrpcParams {
+                    addr,
+                    meta: parent_ref,
+                    backends: policy.backends.clone(),
+                    routes: routes.clone(),
+                    failure_accrual,
+                }))
             }
             policy::Protocol::Opaque(_) | policy::Protocol::Tls(_) => {
                 tracing::info!(
@@ -290,15 +543,13 @@ impl HttpSidecar {
             }
         };
 
-        Some(http::Routes::Policy(http::policy::Params::Http(
-            http::policy::HttpParams {
-                addr: orig_dst.into(),
-                meta: parent_ref,
-                routes,
-                backends: policy.backends.clone(),
-                failure_accrual,
-            },
-        )))
+        Some(http::policy::Params::Http(http::policy::HttpParams {
+            addr,
+            meta: parent_ref,
+            routes,
+            backends: policy.backends.clone(),
+            failure_accrual,
+        }))
     }
 
     fn mk_profile_routes(addr: profiles::LogicalAddr, profile: &profiles::Profile) -> http::Routes {
@@ -318,11 +569,7 @@ impl svc::Param<http::Variant> for HttpSidecar {
 
 impl svc::Param<http::LogicalAddr> for HttpSidecar {
     fn param(&self) -> http::LogicalAddr {
-        http::LogicalAddr(match *self.routes.borrow() {
-            http::Routes::Policy(ref policy) => policy.addr().clone(),
-            http::Routes::Profile(ref profile) => profile.addr.0.clone().into(),
-            http::Routes::Endpoint(Remote(ServerAddr(addr)), ..) => addr.into(),
-        })
+        self.addrs.logical_addr()
     }
 }
 
@@ -334,11 +581,7 @@ impl svc::Param<watch::Receiver<http::Routes>> for HttpSidecar {
 
 impl svc::Param<http::normalize_uri::DefaultAuthority> for HttpSidecar {
     fn param(&self) -> http::normalize_uri::DefaultAuthority {
-        http::normalize_uri::DefaultAuthority(match *self.routes.borrow() {
-            http::Routes::Policy(ref policy) => Some(policy.addr().to_http_authority()),
-            http::Routes::Profile(ref profile) => Some((*profile.addr).as_http_authority()),
-            http::Routes::Endpoint(..) => None,
-        })
+        http::normalize_uri::DefaultAuthority(self.addrs.default_authority())
     }
 }
 
@@ -367,9 +610,12 @@ impl From<Sidecar> for TlsSidecar {
 
         let init = Self::mk_policy_routes(orig_dst, &policy.borrow_and_update())
             .expect("initial policy must be tls");
-        let routes = tls::spawn_routes(policy, init, move |policy: &policy::ClientPolicy| {
-            Self::mk_policy_routes(orig_dst, policy)
-        });
+        let routes = tls::spawn_routes(
+            &parent.route_updates,
+            policy,
+            init,
+            move |policy: &policy::ClientPolicy| Self::mk_policy_routes(orig_dst, policy),
+        );
         TlsSidecar { orig_dst, routes }
     }
 }
@@ -380,8 +626,11 @@ impl TlsSidecar {
         policy: &policy::ClientPolicy,
     ) -> Option<tls::Routes> {
         let parent_ref = ParentRef(policy.parent.clone());
-        let routes = match policy.protocol {
-            policy::Protocol::Tls(policy::tls::Tls { ref routes }) => routes.clone(),
+        let (routes, sni) = match policy.protocol {
+            policy::Protocol::Tls(policy::tls::Tls {
+                ref routes,
+                ref sni,
+            }) => (routes.clone(), sni.clone()),
             _ => {
                 tracing::info!("Ignoring a discovery update that changed a route from TLS");
                 return None;
@@ -393,6 +642,7 @@ impl TlsSidecar {
             meta: parent_ref,
             routes,
             backends: policy.backends.clone(),
+            sni,
         })
     }
 }
@@ -422,6 +672,7 @@ impl std::hash::Hash for TlsSidecar {
 impl From<Sidecar> for OpaqSidecar {
     fn from(parent: Sidecar) -> Self {
         let routes = opaq::routes_from_discovery(
+            &parent.route_updates,
             Addr::Socket(parent.orig_dst.into()),
             parent.profile,
             parent.policy,
@@ -452,3 +703,8 @@ impl std::hash::Hash for OpaqSidecar {
         self.orig_dst.hash(state);
     }
 }
+
+/// Returns true if the control plane does not know the policy's destination.
+fn is_default_policy(policy: &policy::ClientPolicy) -> bool {
+    matches!(*policy.parent, policy::Meta::Default { .. })
+}
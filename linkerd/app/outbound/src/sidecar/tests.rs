@@ -0,0 +1,133 @@
+use super::*;
+use crate::test_util::{fixture::Fixture, *};
+use http_body_util::Empty;
+use hyper_util::rt::TokioIo;
+use linkerd_app_core::svc::{NewService, ServiceExt};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const PARENT: &str = "10.0.0.1:8080";
+
+const FIXTURE: &str = r#"{
+    "parents": [{
+        "addr": "10.0.0.1:8080",
+        "name": "web.ns.svc.cluster.local:8080",
+        "routes": [{
+            "path_prefix": "/",
+            "backends": [{ "name": "web.ns.svc.cluster.local:8080" }]
+        }]
+    }],
+    "endpoints": {
+        "web.ns.svc.cluster.local:8080": ["10.1.0.1:8080"]
+    }
+}"#;
+
+#[tokio::test(flavor = "current_thread")]
+async fn profiles_disabled() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    let fixture = Fixture::from_json(FIXTURE).unwrap();
+    let (mut outbound, _drain) = Outbound::for_test();
+    outbound.config_mut().discover_profiles = false;
+
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let profiles = {
+        let lookups = lookups.clone();
+        svc::mk(move |_: profiles::LookupAddr| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            future::ok::<Option<profiles::Receiver>, Error>(None)
+        })
+    };
+    let stack = outbound
+        .with_stack(fixture.connector())
+        .push_sidecar(profiles, fixture.policies(), fixture.resolver())
+        .into_inner();
+
+    let (client, server) = io::duplex(64 * 1024);
+    let parent = PARENT.parse::<SocketAddr>().unwrap();
+    tokio::spawn(stack.new_service(OrigDstAddr(parent)).oneshot(server));
+
+    let (mut client, conn) = hyper::client::conn::http1::handshake(TokioIo::new(client))
+        .await
+        .expect("handshake");
+    tokio::spawn(conn);
+    let req = ::http::Request::get("http://web.ns.svc.cluster.local:8080/")
+        .body(Empty::<bytes::Bytes>::new())
+        .unwrap();
+    let rsp = client
+        .send_request(req)
+        .await
+        .expect("request must succeed");
+    assert_eq!(rsp.status(), ::http::StatusCode::OK);
+
+    assert_eq!(
+        lookups.load(Ordering::SeqCst),
+        0,
+        "profiles must not be looked up"
+    );
+}
+
+/// Policy routes for an ExternalName-style destination, which the control
+/// plane only names through its profile.
+fn external_policy_routes(policy: &policy::ClientPolicy) -> http::policy::Params {
+    let orig_dst = OrigDstAddr("192.0.2.10:80".parse().unwrap());
+    let name = "api.example.com:80".parse::<NameAddr>().unwrap();
+    let routes =
+        HttpSidecar::mk_policy_routes(orig_dst, Some(&name), http::Variant::Http1, None, policy)
+            .expect("policy must be HTTP");
+    match routes {
+        http::Routes::Policy(params) => params,
+        routes => panic!("unexpected routes: {routes:?}"),
+    }
+}
+
+fn default_policy() -> policy::ClientPolicy {
+    let queue = policy::Queue {
+        capacity: 10,
+        failfast_timeout: std::time::Duration::from_secs(1),
+    };
+    crate::discover::synthesize_origdst_policy(
+        "192.0.2.10:80".parse().unwrap(),
+        queue,
+        std::time::Duration::from_secs(1),
+    )
+}
+
+#[test]
+fn default_policy_routes_use_logical_name() {
+    let params = external_policy_routes(&default_policy());
+    assert_eq!(
+        *params.addr(),
+        Addr::Name("api.example.com:80".parse().unwrap())
+    );
+}
+
+#[test]
+fn resource_policy_routes_use_orig_dst() {
+    let mut policy = default_policy();
+    policy.parent = Arc::new(policy::Meta::Resource {
+        group: "core".to_string(),
+        kind: "Service".to_string(),
+        name: "api".to_string(),
+        namespace: "ns".to_string(),
+        section: None,
+        port: None,
+    });
+    let params = external_policy_routes(&policy);
+    assert_eq!(
+        *params.addr(),
+        Addr::Socket("192.0.2.10:80".parse().unwrap())
+    );
+}
+
+#[test]
+fn logical_name_labels_are_bounded() {
+    let names = LogicalNameLabels::new(1);
+    let a = "a.example.com:80".parse::<NameAddr>().unwrap();
+    let b = "b.example.com:80".parse::<NameAddr>().unwrap();
+    assert!(names.admit(&a));
+    assert!(names.admit(&a), "admitted names must remain admitted");
+    assert!(
+        !names.admit(&b),
+        "names beyond the limit must not be admitted"
+    );
+}
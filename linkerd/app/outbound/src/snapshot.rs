@@ -0,0 +1,718 @@
+//! Persists discovery results so that they may be reused when the proxy
+//! restarts.
+//!
+//! A snapshot records the client policy of each discovered parent (i.e. each
+//! original destination address) and the endpoints of each resolved
+//! destination. When the proxy starts, entries from a recent snapshot are
+//! served provisionally while the control plane is queried, and they are
+//! replaced as its responses arrive. Provisional entries that the control
+//! plane has not confirmed once the snapshot's staleness bound elapses are
+//! dropped.
+
+use crate::{discover, policy};
+use futures::{prelude::*, stream::BoxStream};
+use linkerd2_proxy_api::{destination, net, outbound};
+use linkerd_app_core::{
+    proxy::{
+        api_resolve::{self, ConcreteAddr, Metadata},
+        core::{Resolve, Update},
+    },
+    svc, Addr, Error,
+};
+use parking_lot::Mutex;
+use prost::Message;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    sync::watch,
+    time::{self, Instant},
+};
+use tracing::{debug, info, warn, Instrument};
+
+#[cfg(test)]
+mod tests;
+
+/// The version of the snapshot file format. Snapshots written with other
+/// versions are ignored.
+const VERSION: u32 = 1;
+
+/// Configures how discovery snapshots are persisted.
+#[derive(Clone, Debug)]
+pub struct DiscoverySnapshotConfig {
+    /// The file to which snapshots are written and from which the snapshot is
+    /// loaded when the proxy starts.
+    pub path: PathBuf,
+
+    /// How often snapshots are written.
+    pub interval: Duration,
+
+    /// How long after a snapshot is written its entries may be served without
+    /// being confirmed by the control plane.
+    pub max_age: Duration,
+}
+
+/// Records discovery results and serves those loaded from a previous snapshot.
+#[derive(Clone, Debug)]
+pub struct DiscoverySnapshot(Arc<Inner>);
+
+/// Serves provisional policies for parents in the snapshot while their
+/// policies are discovered.
+#[derive(Clone, Debug)]
+pub(crate) struct ProvisionalPolicies<P> {
+    inner: P,
+    snapshot: Option<DiscoverySnapshot>,
+    overrides: policy::ClientPolicyOverrides,
+    queue: policy::Queue,
+    detect_timeout: Duration,
+}
+
+/// Serves provisional endpoints for destinations in the snapshot while their
+/// endpoints are resolved.
+#[derive(Clone, Debug)]
+pub(crate) struct ProvisionalResolve<R> {
+    inner: R,
+    snapshot: Option<DiscoverySnapshot>,
+}
+
+pub(crate) struct ProvisionalResolution {
+    /// An update to be yielded before polling the inner resolution.
+    pending: Option<Update<Metadata>>,
+    /// Set when the inner resolution is unavailable.
+    unavailable: bool,
+    expiry: Option<Pin<Box<time::Sleep>>>,
+    inner: BoxStream<'static, Result<Update<Metadata>, Error>>,
+}
+
+/// Records the policy of a parent for the lifetime of a policy watch.
+pub(crate) struct RecordParent {
+    snapshot: DiscoverySnapshot,
+    addr: SocketAddr,
+    generation: u64,
+}
+
+/// Records the endpoints of a destination for the lifetime of a resolution.
+struct RecordEndpoints {
+    snapshot: DiscoverySnapshot,
+    path: String,
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct Inner {
+    config: DiscoverySnapshotConfig,
+    provisional: Mutex<Option<Provisional>>,
+    parents: Mutex<HashMap<SocketAddr, Recorded<Parent>>>,
+    destinations: Mutex<HashMap<String, Recorded<Endpoints>>>,
+    generations: AtomicU64,
+}
+
+/// Entries loaded from a snapshot that have not yet been confirmed.
+#[derive(Debug)]
+struct Provisional {
+    expires_at: Instant,
+    parents: HashMap<SocketAddr, outbound::OutboundPolicy>,
+    destinations: HashMap<String, Vec<destination::WeightedAddr>>,
+}
+
+/// A value recorded by the most recent discovery of its key.
+#[derive(Debug)]
+struct Recorded<T> {
+    generation: u64,
+    value: T,
+}
+
+#[derive(Debug)]
+struct Parent {
+    policy: outbound::OutboundPolicy,
+
+    /// Parents that use a ServiceProfile are not persisted, since provisional
+    /// parents are served without one.
+    profiled: bool,
+}
+
+type Endpoints = HashMap<SocketAddr, destination::WeightedAddr>;
+
+// === Snapshot file format ===
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct SnapshotPb {
+    #[prost(uint32, tag = "1")]
+    version: u32,
+
+    /// When the snapshot was written, in seconds since the UNIX epoch.
+    #[prost(uint64, tag = "2")]
+    written_at: u64,
+
+    #[prost(message, repeated, tag = "3")]
+    parents: Vec<ParentPb>,
+
+    #[prost(message, repeated, tag = "4")]
+    destinations: Vec<DestinationPb>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ParentPb {
+    #[prost(message, optional, tag = "1")]
+    addr: Option<net::TcpAddress>,
+
+    #[prost(message, optional, tag = "2")]
+    policy: Option<outbound::OutboundPolicy>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct DestinationPb {
+    #[prost(string, tag = "1")]
+    path: String,
+
+    #[prost(message, repeated, tag = "2")]
+    endpoints: Vec<destination::WeightedAddr>,
+}
+
+// === impl DiscoverySnapshot ===
+
+impl DiscoverySnapshot {
+    /// Loads the snapshot at the configured path, if one exists.
+    ///
+    /// Snapshots that cannot be read, that were written with another version,
+    /// or that are older than the configured maximum age are ignored.
+    pub fn load(config: DiscoverySnapshotConfig) -> Self {
+        let provisional = match std::fs::read(&config.path) {
+            Ok(buf) => Provisional::decode(&buf, config.max_age, SystemTime::now()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                debug!(path = %config.path.display(), "No discovery snapshot");
+                None
+            }
+            Err(error) => {
+                warn!(path = %config.path.display(), %error, "Failed to read discovery snapshot");
+                None
+            }
+        };
+        if let Some(p) = provisional.as_ref() {
+            info!(
+                parents = p.parents.len(),
+                destinations = p.destinations.len(),
+                "Loaded discovery snapshot"
+            );
+        }
+        Self(Arc::new(Inner {
+            config,
+            provisional: Mutex::new(provisional),
+            parents: Default::default(),
+            destinations: Default::default(),
+            generations: AtomicU64::new(0),
+        }))
+    }
+
+    /// Writes a snapshot at the configured interval and once more when
+    /// `shutdown` completes.
+    pub async fn persist(self, shutdown: impl Future) {
+        let mut interval = time::interval(self.0.config.interval);
+        interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        // The first tick completes immediately.
+        interval.tick().await;
+
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => self.write().await,
+                _ = &mut shutdown => {
+                    self.write().await;
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn write(&self) {
+        let buf = self.encode(SystemTime::now());
+        let path = self.0.config.path.clone();
+        match tokio::task::spawn_blocking(move || write_file(&path, &buf)).await {
+            Ok(Ok(())) => debug!("Wrote discovery snapshot"),
+            Ok(Err(error)) => warn!(%error, "Failed to write discovery snapshot"),
+            Err(error) => warn!(%error, "Failed to write discovery snapshot"),
+        }
+    }
+
+    fn encode(&self, now: SystemTime) -> Vec<u8> {
+        let mut parents = self
+            .0
+            .parents
+            .lock()
+            .iter()
+            .filter(|(_, p)| !p.value.profiled)
+            .map(|(addr, p)| ParentPb {
+                addr: Some((*addr).into()),
+                policy: Some(p.value.policy.clone()),
+            })
+            .collect::<Vec<_>>();
+        parents.sort_by_key(|p| p.addr.and_then(api_resolve::pb::to_sock_addr));
+
+        let mut destinations = self
+            .0
+            .destinations
+            .lock()
+            .iter()
+            .filter(|(_, eps)| !eps.value.is_empty())
+            .map(|(path, eps)| DestinationPb {
+                path: path.clone(),
+                endpoints: eps.value.values().cloned().collect(),
+            })
+            .collect::<Vec<_>>();
+        destinations.sort_by(|a, b| a.path.cmp(&b.path));
+
+        SnapshotPb {
+            version: VERSION,
+            written_at: now
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            parents,
+            destinations,
+        }
+        .encode_to_vec()
+    }
+
+    /// Returns true if a provisional policy is available for the parent.
+    pub(crate) fn is_provisional(&self, addr: SocketAddr) -> bool {
+        self.provisional(|p| p.parents.contains_key(&addr).then_some(()))
+            .is_some()
+    }
+
+    fn provisional_policy(&self, addr: SocketAddr) -> Option<(outbound::OutboundPolicy, Instant)> {
+        self.provisional(|p| p.parents.get(&addr).cloned())
+    }
+
+    fn provisional_endpoints(&self, path: &str) -> Option<(Vec<(SocketAddr, Metadata)>, Instant)> {
+        let no_labels = HashMap::new();
+        self.provisional(|p| {
+            let eps = p.destinations.get(path)?;
+            Some(
+                eps.iter()
+                    .cloned()
+                    .filter_map(|ep| api_resolve::pb::to_addr_meta(ep, &no_labels))
+                    .collect(),
+            )
+        })
+    }
+
+    fn provisional<T>(&self, f: impl FnOnce(&Provisional) -> Option<T>) -> Option<(T, Instant)> {
+        let mut provisional = self.0.provisional.lock();
+        let expires_at = provisional.as_ref()?.expires_at;
+        if Instant::now() >= expires_at {
+            debug!("Discovery snapshot expired");
+            *provisional = None;
+            return None;
+        }
+        let value = f(provisional.as_ref()?)?;
+        Some((value, expires_at))
+    }
+
+    /// Records the policies discovered for the given parent until the returned
+    /// handle is dropped.
+    pub(crate) fn record_parent(&self, addr: SocketAddr) -> RecordParent {
+        RecordParent {
+            snapshot: self.clone(),
+            addr,
+            generation: self.next_generation(),
+        }
+    }
+
+    /// Marks the parent as using a ServiceProfile, so that it is not
+    /// persisted.
+    pub(crate) fn set_profiled(&self, addr: SocketAddr) {
+        if let Some(parent) = self.0.parents.lock().get_mut(&addr) {
+            parent.value.profiled = true;
+        }
+    }
+
+    fn next_generation(&self) -> u64 {
+        self.0.generations.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+impl api_resolve::Observe for DiscoverySnapshot {
+    fn observe(&self, path: &str) -> Box<dyn api_resolve::ObserveUpdates> {
+        Box::new(RecordEndpoints {
+            snapshot: self.clone(),
+            path: path.to_string(),
+            generation: self.next_generation(),
+        })
+    }
+}
+
+fn write_file(path: &Path, buf: &[u8]) -> std::io::Result<()> {
+    // Write to a temporary file and rename it so that a partially-written
+    // snapshot is never loaded.
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, buf)?;
+    std::fs::rename(&tmp, path)
+}
+
+// === impl Provisional ===
+
+impl Provisional {
+    fn decode(buf: &[u8], max_age: Duration, now: SystemTime) -> Option<Self> {
+        let snapshot = match SnapshotPb::decode(buf) {
+            Ok(snapshot) => snapshot,
+            Err(error) => {
+                warn!(%error, "Ignoring corrupt discovery snapshot");
+                return None;
+            }
+        };
+        if snapshot.version != VERSION {
+            warn!(
+                version = snapshot.version,
+                "Ignoring discovery snapshot with an unsupported version"
+            );
+            return None;
+        }
+
+        let written_at = SystemTime::UNIX_EPOCH + Duration::from_secs(snapshot.written_at);
+        let age = now.duration_since(written_at).unwrap_or_default();
+        let Some(remaining) = max_age.checked_sub(age).filter(|d| !d.is_zero()) else {
+            info!(age = ?age, "Ignoring stale discovery snapshot");
+            return None;
+        };
+
+        let parents = snapshot
+            .parents
+            .into_iter()
+            .filter_map(|p| Some((api_resolve::pb::to_sock_addr(p.addr?)?, p.policy?)))
+            .collect();
+        let destinations = snapshot
+            .destinations
+            .into_iter()
+            .map(|d| (d.path, d.endpoints))
+            .collect();
+        Some(Self {
+            expires_at: Instant::now() + remaining,
+            parents,
+            destinations,
+        })
+    }
+}
+
+// === impl RecordParent ===
+
+impl RecordParent {
+    pub(crate) fn update(&mut self, policy: outbound::OutboundPolicy) {
+        let mut parents = self.snapshot.0.parents.lock();
+        let parent = parents.entry(self.addr).or_insert_with(|| Recorded {
+            generation: self.generation,
+            value: Parent {
+                policy: policy.clone(),
+                profiled: false,
+            },
+        });
+        if parent.generation > self.generation {
+            // A more recent lookup has replaced this one.
+            return;
+        }
+        parent.generation = self.generation;
+        parent.value.policy = policy;
+        drop(parents);
+
+        // The parent is no longer provisional once it has been discovered.
+        if let Some(p) = self.snapshot.0.provisional.lock().as_mut() {
+            p.parents.remove(&self.addr);
+        }
+    }
+}
+
+impl Drop for RecordParent {
+    fn drop(&mut self) {
+        let mut parents = self.snapshot.0.parents.lock();
+        if parents
+            .get(&self.addr)
+            .is_some_and(|p| p.generation == self.generation)
+        {
+            parents.remove(&self.addr);
+        }
+    }
+}
+
+// === impl RecordEndpoints ===
+
+impl api_resolve::ObserveUpdates for RecordEndpoints {
+    fn update(&mut self, update: &destination::Update) {
+        use destination::update::Update as Up;
+
+        let mut destinations = self.snapshot.0.destinations.lock();
+        let eps = destinations
+            .entry(self.path.clone())
+            .or_insert_with(|| Recorded {
+                generation: self.generation,
+                value: Endpoints::default(),
+            });
+        if eps.generation > self.generation {
+            // A more recent resolution has replaced this one.
+            return;
+        }
+        if eps.generation < self.generation {
+            eps.generation = self.generation;
+            eps.value.clear();
+        }
+
+        match update.update.as_ref() {
+            Some(Up::Add(set)) => {
+                for ep in &set.addrs {
+                    let Some(addr) = ep.addr.and_then(api_resolve::pb::to_sock_addr) else {
+                        continue;
+                    };
+                    // Store the set's labels with each endpoint, preferring
+                    // the endpoint's own labels.
+                    let mut ep = ep.clone();
+                    let mut labels = set.metric_labels.clone();
+                    labels.extend(ep.metric_labels.drain());
+                    ep.metric_labels = labels;
+                    eps.value.insert(addr, ep);
+                }
+            }
+            Some(Up::Remove(set)) => {
+                for addr in set.addrs.iter().copied() {
+                    if let Some(addr) = api_resolve::pb::to_sock_addr(addr) {
+                        eps.value.remove(&addr);
+                    }
+                }
+            }
+            Some(Up::NoEndpoints(_)) => eps.value.clear(),
+            None => return,
+        }
+        drop(destinations);
+
+        // The destination is no longer provisional once it has been resolved.
+        if let Some(p) = self.snapshot.0.provisional.lock().as_mut() {
+            p.destinations.remove(&self.path);
+        }
+    }
+}
+
+impl Drop for RecordEndpoints {
+    fn drop(&mut self) {
+        let mut destinations = self.snapshot.0.destinations.lock();
+        if destinations
+            .get(&self.path)
+            .is_some_and(|eps| eps.generation == self.generation)
+        {
+            destinations.remove(&self.path);
+        }
+    }
+}
+
+// === impl ProvisionalPolicies ===
+
+impl<P> ProvisionalPolicies<P> {
+    pub(crate) fn new(
+        inner: P,
+        snapshot: Option<DiscoverySnapshot>,
+        overrides: policy::ClientPolicyOverrides,
+        queue: policy::Queue,
+        detect_timeout: Duration,
+    ) -> Self {
+        Self {
+            inner,
+            snapshot,
+            overrides,
+            queue,
+            detect_timeout,
+        }
+    }
+}
+
+impl<P: policy::GetPolicy> svc::Service<Addr> for ProvisionalPolicies<P> {
+    type Response = policy::Receiver;
+    type Error = Error;
+    type Future = future::Either<P::Future, future::Ready<Result<policy::Receiver, Error>>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, addr: Addr) -> Self::Future {
+        let provisional = match (self.snapshot.as_ref(), &addr) {
+            (Some(snapshot), Addr::Socket(orig_dst)) => snapshot
+                .provisional_policy(*orig_dst)
+                .and_then(|(policy, expires_at)| {
+                    match policy::ClientPolicy::try_from(self.overrides.clone(), policy) {
+                        Ok(policy) => Some((*orig_dst, policy, expires_at)),
+                        Err(error) => {
+                            debug!(%error, "Ignoring invalid provisional policy");
+                            None
+                        }
+                    }
+                }),
+            _ => None,
+        };
+        let Some((orig_dst, policy, expires_at)) = provisional else {
+            return future::Either::Left(self.inner.get_policy(addr));
+        };
+
+        debug!(addr = %orig_dst, "Using provisional policy");
+        let (tx, rx) = watch::channel(policy);
+        let fallback =
+            discover::synthesize_origdst_policy(orig_dst, self.queue, self.detect_timeout);
+        tokio::spawn(
+            confirm_policy(tx, self.inner.get_policy(addr), expires_at, fallback).in_current_span(),
+        );
+        future::Either::Right(future::ok(rx))
+    }
+}
+
+/// Replaces a provisional policy with the discovered policy.
+///
+/// If the policy is not discovered before the provisional policy expires, or
+/// if discovery fails, connections are forwarded to the original destination.
+async fn confirm_policy(
+    tx: watch::Sender<policy::ClientPolicy>,
+    lookup: impl Future<Output = Result<policy::Receiver, Error>>,
+    expires_at: Instant,
+    fallback: policy::ClientPolicy,
+) {
+    tokio::pin!(lookup);
+    let res = tokio::select! {
+        biased;
+        _ = tx.closed() => return,
+        res = &mut lookup => res,
+        _ = time::sleep_until(expires_at) => {
+            debug!("Provisional policy expired before it was discovered");
+            if tx.send(fallback.clone()).is_err() {
+                return;
+            }
+            tokio::select! {
+                biased;
+                _ = tx.closed() => return,
+                res = &mut lookup => res,
+            }
+        }
+    };
+
+    let mut rx = match res {
+        Ok(rx) => rx,
+        Err(error) => {
+            debug!(%error, "Failed to discover provisional policy");
+            tx.send_if_modified(|policy| {
+                if *policy == fallback {
+                    return false;
+                }
+                *policy = fallback;
+                true
+            });
+            tx.closed().await;
+            return;
+        }
+    };
+
+    loop {
+        let policy = rx.borrow_and_update().clone();
+        if tx.send(policy).is_err() {
+            return;
+        }
+        tokio::select! {
+            biased;
+            _ = tx.closed() => return,
+            res = rx.changed() => if res.is_err() {
+                return;
+            },
+        }
+    }
+}
+
+// === impl ProvisionalResolve ===
+
+impl<R> ProvisionalResolve<R> {
+    pub(crate) fn new(inner: R, snapshot: Option<DiscoverySnapshot>) -> Self {
+        Self { inner, snapshot }
+    }
+}
+
+impl<R> svc::Service<ConcreteAddr> for ProvisionalResolve<R>
+where
+    R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
+{
+    type Response = ProvisionalResolution;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<ProvisionalResolution, Error>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, addr: ConcreteAddr) -> Self::Future {
+        let provisional = self
+            .snapshot
+            .as_ref()
+            .and_then(|s| s.provisional_endpoints(&addr.to_string()));
+        let resolution = self.inner.resolve(addr.clone());
+
+        let Some((endpoints, expires_at)) = provisional else {
+            return Box::pin(async move {
+                let inner = resolution.await?.boxed();
+                Ok(ProvisionalResolution {
+                    pending: None,
+                    unavailable: false,
+                    expiry: None,
+                    inner,
+                })
+            });
+        };
+
+        debug!(%addr, endpoints = endpoints.len(), "Using provisional endpoints");
+        let inner = stream::once(resolution).try_flatten().boxed();
+        Box::pin(future::ok(ProvisionalResolution {
+            pending: Some(Update::Reset(endpoints)),
+            unavailable: false,
+            expiry: Some(Box::pin(time::sleep_until(expires_at))),
+            inner,
+        }))
+    }
+}
+
+// === impl ProvisionalResolution ===
+
+impl Stream for ProvisionalResolution {
+    type Item = Result<Update<Metadata>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(update) = this.pending.take() {
+            return Poll::Ready(Some(Ok(update)));
+        }
+
+        // Resolutions begin with a reset, so the first update replaces the
+        // provisional endpoints. An unavailable resolution retains them until
+        // they expire.
+        if let Poll::Ready(update) = this.inner.poll_next_unpin(cx) {
+            if matches!(update, Some(Ok(Update::Unavailable))) {
+                this.unavailable = true;
+            } else {
+                this.expiry = None;
+            }
+            return Poll::Ready(update);
+        }
+
+        if let Some(expiry) = this.expiry.as_mut() {
+            if expiry.as_mut().poll(cx).is_ready() {
+                debug!("Provisional endpoints expired before they were resolved");
+                this.expiry = None;
+                // Clearing the endpoints must not hide that the resolution is
+                // unavailable.
+                if this.unavailable {
+                    this.pending = Some(Update::Unavailable);
+                }
+                return Poll::Ready(Some(Ok(Update::Reset(Vec::new()))));
+            }
+        }
+
+        Poll::Pending
+    }
+}
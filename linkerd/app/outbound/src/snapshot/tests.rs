@@ -0,0 +1,376 @@
+use super::*;
+use api_resolve::Observe;
+use linkerd2_proxy_api::meta;
+use linkerd_app_core::svc::ServiceExt;
+use tokio::sync::mpsc;
+
+const MAX_AGE: Duration = Duration::from_secs(60);
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn round_trips() {
+    let parent = SocketAddr::new([192, 0, 2, 10].into(), 8080);
+    let ep = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let snapshot = DiscoverySnapshot::load(config("round-trips"));
+
+    let mut record = snapshot.record_parent(parent);
+    record.update(opaque_policy("foo.ns.svc.cluster.local:8080"));
+    let mut observer = snapshot.observe("foo.ns.svc.cluster.local:8080");
+    observer.update(&add(ep));
+
+    // Profiled parents and empty destinations are not persisted.
+    let profiled = SocketAddr::new([192, 0, 2, 11].into(), 8080);
+    let mut record_profiled = snapshot.record_parent(profiled);
+    record_profiled.update(opaque_policy("bar.ns.svc.cluster.local:8080"));
+    snapshot.set_profiled(profiled);
+    let mut empty = snapshot.observe("bar.ns.svc.cluster.local:8080");
+    empty.update(&add(ep));
+    empty.update(&remove(ep));
+
+    let now = SystemTime::now();
+    let provisional =
+        Provisional::decode(&snapshot.encode(now), MAX_AGE, now).expect("snapshot must decode");
+    assert_eq!(
+        provisional.parents.keys().collect::<Vec<_>>(),
+        vec![&parent]
+    );
+    assert_eq!(
+        provisional.destinations.keys().collect::<Vec<_>>(),
+        vec!["foo.ns.svc.cluster.local:8080"]
+    );
+    // Snapshots are timestamped with a granularity of one second.
+    assert!(provisional.expires_at <= Instant::now() + MAX_AGE);
+    assert!(provisional.expires_at > Instant::now() + MAX_AGE - Duration::from_secs(1));
+
+    // Entries are removed when their lookups end.
+    drop((record, observer));
+    let provisional =
+        Provisional::decode(&snapshot.encode(now), MAX_AGE, now).expect("snapshot must decode");
+    assert!(provisional.parents.is_empty());
+    assert!(provisional.destinations.is_empty());
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn ignores_invalid_snapshots() {
+    let snapshot = DiscoverySnapshot::load(config("invalid"));
+    let mut record = snapshot.record_parent(SocketAddr::new([192, 0, 2, 10].into(), 8080));
+    record.update(opaque_policy("foo.ns.svc.cluster.local:8080"));
+    let now = SystemTime::now();
+    let buf = snapshot.encode(now);
+
+    assert!(Provisional::decode(&buf, MAX_AGE, now + MAX_AGE - Duration::from_secs(1)).is_some());
+    assert!(
+        Provisional::decode(&buf, MAX_AGE, now + MAX_AGE).is_none(),
+        "stale snapshots must be ignored"
+    );
+    assert!(
+        Provisional::decode(&buf[..buf.len() - 1], MAX_AGE, now).is_none(),
+        "truncated snapshots must be ignored"
+    );
+
+    let unversioned = SnapshotPb {
+        version: VERSION + 1,
+        ..SnapshotPb::decode(&*buf).unwrap()
+    };
+    assert!(
+        Provisional::decode(&unversioned.encode_to_vec(), MAX_AGE, now).is_none(),
+        "snapshots with other versions must be ignored"
+    );
+
+    // Missing and corrupt files are tolerated.
+    let config = config("corrupt");
+    let snapshot = DiscoverySnapshot::load(config.clone());
+    assert!(!snapshot.is_provisional(SocketAddr::new([192, 0, 2, 10].into(), 8080)));
+    std::fs::write(&config.path, b"not a snapshot").unwrap();
+    let snapshot = DiscoverySnapshot::load(config.clone());
+    assert!(!snapshot.is_provisional(SocketAddr::new([192, 0, 2, 10].into(), 8080)));
+    std::fs::remove_file(&config.path).unwrap();
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn persists_and_loads() {
+    let config = config("persists");
+    let parent = SocketAddr::new([192, 0, 2, 10].into(), 8080);
+    let snapshot = DiscoverySnapshot::load(config.clone());
+    let mut record = snapshot.record_parent(parent);
+    record.update(opaque_policy("foo.ns.svc.cluster.local:8080"));
+
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let task = tokio::spawn(snapshot.persist(shutdown_rx));
+    drop(shutdown_tx);
+    task.await.unwrap();
+
+    let loaded = DiscoverySnapshot::load(config.clone());
+    assert!(loaded.is_provisional(parent));
+
+    // Confirmed parents are no longer provisional.
+    let mut record = loaded.record_parent(parent);
+    record.update(opaque_policy("foo.ns.svc.cluster.local:8080"));
+    assert!(!loaded.is_provisional(parent));
+
+    std::fs::remove_file(&config.path).unwrap();
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn provisional_policy_is_replaced() {
+    let parent = SocketAddr::new([192, 0, 2, 10].into(), 8080);
+    let snapshot = provisional(parent, opaque_policy("foo.ns.svc.cluster.local:8080"));
+
+    let (lookup_tx, lookup_rx) = tokio::sync::oneshot::channel::<policy::Receiver>();
+    let lookup_rx = Arc::new(Mutex::new(Some(lookup_rx)));
+    let policies = ProvisionalPolicies::new(
+        svc::mk(move |_: Addr| {
+            let rx = lookup_rx
+                .lock()
+                .take()
+                .expect("policy must be discovered once");
+            Box::pin(async move { Ok::<_, Error>(rx.await.unwrap()) })
+        }),
+        Some(snapshot),
+        policy::ClientPolicyOverrides::default(),
+        queue(),
+        Duration::from_secs(10),
+    );
+
+    let mut rx = policies.oneshot(Addr::Socket(parent)).await.unwrap();
+    let provisional = rx.borrow_and_update().clone();
+    assert!(matches!(provisional.protocol, policy::Protocol::Opaque(_)));
+
+    let discovered = discover::synthesize_origdst_policy(
+        SocketAddr::new([192, 0, 2, 20].into(), 8080),
+        queue(),
+        Duration::from_secs(10),
+    );
+    let (_discovered_tx, discovered_rx) = watch::channel(discovered.clone());
+    lookup_tx.send(discovered_rx).unwrap();
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow(), discovered);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn unconfirmed_policy_expires() {
+    let parent = SocketAddr::new([192, 0, 2, 10].into(), 8080);
+    let snapshot = provisional(parent, opaque_policy("foo.ns.svc.cluster.local:8080"));
+    let policies = ProvisionalPolicies::new(
+        svc::mk(|_: Addr| future::pending::<Result<policy::Receiver, Error>>()),
+        Some(snapshot.clone()),
+        policy::ClientPolicyOverrides::default(),
+        queue(),
+        Duration::from_secs(10),
+    );
+
+    let mut rx = policies.oneshot(Addr::Socket(parent)).await.unwrap();
+    rx.borrow_and_update();
+    rx.changed().await.unwrap();
+    assert_eq!(
+        *rx.borrow(),
+        discover::synthesize_origdst_policy(parent, queue(), Duration::from_secs(10)),
+        "unconfirmed policies must fall back to the original destination"
+    );
+    assert!(!snapshot.is_provisional(parent));
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn provisional_endpoints_are_replaced() {
+    let ep0 = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let ep1 = SocketAddr::new([192, 0, 2, 31].into(), 8080);
+    let path = "foo.ns.svc.cluster.local:8080";
+    let snapshot = DiscoverySnapshot::load(config("endpoints"));
+    {
+        let mut provisional = snapshot.0.provisional.lock();
+        *provisional = Some(Provisional {
+            expires_at: Instant::now() + MAX_AGE,
+            parents: HashMap::new(),
+            destinations: [(path.to_string(), vec![weighted_addr(ep0)])]
+                .into_iter()
+                .collect(),
+        });
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let rx = Arc::new(Mutex::new(Some(rx)));
+    let resolve = ProvisionalResolve::new(
+        svc::mk(move |_: ConcreteAddr| {
+            let rx = rx.lock().take().expect("destination must be resolved once");
+            future::ok::<_, Error>(stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|up| (up, rx))
+            }))
+        }),
+        Some(snapshot.clone()),
+    );
+
+    let mut resolution = resolve
+        .oneshot(ConcreteAddr(path.parse().unwrap()))
+        .await
+        .unwrap();
+    match resolution.next().await {
+        Some(Ok(Update::Reset(eps))) => {
+            assert_eq!(
+                eps.into_iter().map(|(a, _)| a).collect::<Vec<_>>(),
+                vec![ep0]
+            );
+        }
+        up => panic!("unexpected update: {up:?}"),
+    }
+
+    tx.send(Ok(Update::Reset(vec![(ep1, Metadata::default())])))
+        .unwrap();
+    match resolution.next().await {
+        Some(Ok(Update::Reset(eps))) => {
+            assert_eq!(
+                eps.into_iter().map(|(a, _)| a).collect::<Vec<_>>(),
+                vec![ep1]
+            );
+        }
+        up => panic!("unexpected update: {up:?}"),
+    }
+
+    // Once confirmed, provisional endpoints do not expire.
+    time::sleep(MAX_AGE * 2).await;
+    assert!(resolution.next().now_or_never().is_none());
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn unconfirmed_endpoints_expire() {
+    let ep = SocketAddr::new([192, 0, 2, 30].into(), 8080);
+    let path = "foo.ns.svc.cluster.local:8080";
+    let snapshot = DiscoverySnapshot::load(config("expire-endpoints"));
+    {
+        let mut provisional = snapshot.0.provisional.lock();
+        *provisional = Some(Provisional {
+            expires_at: Instant::now() + MAX_AGE,
+            parents: HashMap::new(),
+            destinations: [(path.to_string(), vec![weighted_addr(ep)])]
+                .into_iter()
+                .collect(),
+        });
+    }
+
+    let resolve = ProvisionalResolve::new(
+        svc::mk(|_: ConcreteAddr| {
+            future::ok::<_, Error>(stream::pending::<Result<Update<Metadata>, Error>>())
+        }),
+        Some(snapshot),
+    );
+    let mut resolution = resolve
+        .oneshot(ConcreteAddr(path.parse().unwrap()))
+        .await
+        .unwrap();
+    assert!(matches!(
+        resolution.next().await,
+        Some(Ok(Update::Reset(eps))) if eps.len() == 1
+    ));
+    assert!(matches!(
+        resolution.next().await,
+        Some(Ok(Update::Reset(eps))) if eps.is_empty()
+    ));
+}
+
+// === Utils ===
+
+fn config(name: &str) -> DiscoverySnapshotConfig {
+    DiscoverySnapshotConfig {
+        path: std::env::temp_dir().join(format!(
+            "linkerd-discovery-snapshot-{}-{name}",
+            std::process::id()
+        )),
+        interval: Duration::from_secs(10),
+        max_age: MAX_AGE,
+    }
+}
+
+fn provisional(parent: SocketAddr, policy: outbound::OutboundPolicy) -> DiscoverySnapshot {
+    let snapshot = DiscoverySnapshot::load(config("provisional"));
+    *snapshot.0.provisional.lock() = Some(Provisional {
+        expires_at: Instant::now() + MAX_AGE,
+        parents: [(parent, policy)].into_iter().collect(),
+        destinations: HashMap::new(),
+    });
+    snapshot
+}
+
+fn queue() -> policy::Queue {
+    policy::Queue {
+        capacity: 10,
+        failfast_timeout: Duration::from_secs(3),
+    }
+}
+
+fn opaque_policy(dst: &str) -> outbound::OutboundPolicy {
+    use outbound::{backend, opaque_route, proxy_protocol};
+
+    let meta = Some(meta::Metadata {
+        kind: Some(meta::metadata::Kind::Default("default".to_string())),
+    });
+    let backend = outbound::Backend {
+        metadata: meta.clone(),
+        queue: Some(outbound::Queue {
+            capacity: 100,
+            failfast_timeout: Some(Duration::from_secs(3).try_into().unwrap()),
+        }),
+        kind: Some(backend::Kind::Balancer(backend::BalanceP2c {
+            discovery: Some(backend::EndpointDiscovery {
+                kind: Some(backend::endpoint_discovery::Kind::Dst(
+                    backend::endpoint_discovery::DestinationGet {
+                        path: dst.to_string(),
+                    },
+                )),
+            }),
+            load: Some(backend::balance_p2c::Load::PeakEwma(
+                backend::balance_p2c::PeakEwma {
+                    default_rtt: Some(Duration::from_millis(30).try_into().unwrap()),
+                    decay: Some(Duration::from_secs(10).try_into().unwrap()),
+                },
+            )),
+        })),
+    };
+    outbound::OutboundPolicy {
+        metadata: meta.clone(),
+        protocol: Some(outbound::ProxyProtocol {
+            kind: Some(proxy_protocol::Kind::Opaque(proxy_protocol::Opaque {
+                routes: vec![outbound::OpaqueRoute {
+                    metadata: meta,
+                    rules: vec![opaque_route::Rule {
+                        backends: Some(opaque_route::Distribution {
+                            kind: Some(opaque_route::distribution::Kind::FirstAvailable(
+                                opaque_route::distribution::FirstAvailable {
+                                    backends: vec![opaque_route::RouteBackend {
+                                        backend: Some(backend),
+                                        filters: Vec::new(),
+                                    }],
+                                },
+                            )),
+                        }),
+                        filters: Vec::new(),
+                    }],
+                }],
+            })),
+        }),
+    }
+}
+
+fn weighted_addr(addr: SocketAddr) -> destination::WeightedAddr {
+    destination::WeightedAddr {
+        addr: Some(addr.into()),
+        weight: 1,
+        ..Default::default()
+    }
+}
+
+fn add(addr: SocketAddr) -> destination::Update {
+    destination::Update {
+        update: Some(destination::update::Update::Add(
+            destination::WeightedAddrSet {
+                addrs: vec![weighted_addr(addr)],
+                metric_labels: Default::default(),
+            },
+        )),
+    }
+}
+
+fn remove(addr: SocketAddr) -> destination::Update {
+    destination::Update {
+        update: Some(destination::update::Update::Remove(destination::AddrSet {
+            addrs: vec![addr.into()],
+        })),
+    }
+}
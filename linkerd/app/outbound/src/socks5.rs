@@ -0,0 +1,943 @@
+use crate::{
+    explicit::{Connect, Tunnel},
+    policy, Outbound,
+};
+use futures::prelude::*;
+use linkerd_app_core::{
+    config::ServerConfig,
+    io::{self, AsyncReadExt, AsyncWriteExt},
+    metrics::prom,
+    profiles,
+    proxy::{
+        api_resolve::{ConcreteAddr, Metadata},
+        core::Resolve,
+    },
+    svc::{self, ServiceExt},
+    transport::addrs::*,
+    Addr, Error, Result,
+};
+use std::{
+    fmt::Debug,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use thiserror::Error;
+use tokio::time;
+
+/// Configures a SOCKS5 listener.
+#[derive(Clone, Debug)]
+pub struct Socks5Config {
+    pub server: ServerConfig,
+
+    /// Credentials that clients must present, if any. When unset, clients are
+    /// not authenticated.
+    pub credentials: Option<Socks5Credentials>,
+}
+
+/// Username/password credentials (RFC 1929).
+#[derive(Clone, PartialEq, Eq)]
+pub struct Socks5Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Counts connections accepted on the SOCKS5 listener by the outcome of their
+/// handshake.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Socks5Metrics {
+    connections: prom::Family<ConnectionLabels, prom::Counter>,
+}
+
+/// A `CONNECT` request for an IP address, which is proxied as if the
+/// connection had been redirected to the requested address.
+#[derive(Clone, Debug)]
+struct Requested(OrigDstAddr);
+
+#[derive(Clone, Debug)]
+struct NewSocks5Server<A, N> {
+    by_addr: A,
+    by_name: N,
+    credentials: Option<Arc<Socks5Credentials>>,
+    read_timeout: time::Duration,
+    metrics: Socks5Metrics,
+}
+
+#[derive(Clone, Debug)]
+struct Socks5Server<A, N> {
+    by_addr: A,
+    by_name: N,
+    bound: SocketAddr,
+    credentials: Option<Arc<Socks5Credentials>>,
+    read_timeout: time::Duration,
+    metrics: Socks5Metrics,
+}
+
+#[derive(Debug, Error)]
+enum HandshakeError {
+    #[error("unsupported SOCKS version {0}")]
+    Version(u8),
+
+    #[error("client does not support an acceptable SOCKS authentication method")]
+    NoAcceptableMethod,
+
+    #[error("SOCKS authentication failed")]
+    Unauthorized,
+
+    #[error("unsupported SOCKS command {0}")]
+    Command(u8),
+
+    #[error("unsupported SOCKS address type {0}")]
+    AddrType(u8),
+
+    #[error("invalid SOCKS destination address")]
+    InvalidAddr,
+
+    #[error("SOCKS handshake timed out")]
+    Timeout,
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct ConnectionLabels {
+    ingress: Ingress,
+    result: ConnectionResult,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum Ingress {
+    socks5,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum ConnectionResult {
+    /// The tunnel was established.
+    established,
+    /// The client could not be authenticated.
+    unauthorized,
+    /// The client requested an unsupported command or address type.
+    unsupported,
+    /// The client did not complete a valid handshake.
+    invalid,
+    /// The requested destination could not be discovered.
+    unreachable,
+}
+
+const VERSION: u8 = 0x05;
+
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_USERNAME_PASSWORD: u8 = 0x02;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xff;
+
+const AUTH_VERSION: u8 = 0x01;
+const AUTH_SUCCESS: u8 = 0x00;
+const AUTH_FAILURE: u8 = 0x01;
+
+const CMD_CONNECT: u8 = 0x01;
+
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_NAME: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+const REP_SUCCEEDED: u8 = 0x00;
+const REP_GENERAL_FAILURE: u8 = 0x01;
+const REP_HOST_UNREACHABLE: u8 = 0x04;
+const REP_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const REP_ADDR_TYPE_NOT_SUPPORTED: u8 = 0x08;
+
+// === impl Outbound ===
+
+impl Outbound<()> {
+    /// Builds a SOCKS5 proxy stack.
+    ///
+    /// Clients address the SOCKS5 listener directly, which is useful for
+    /// debugging and for clients whose connections cannot be redirected to the
+    /// proxy. `CONNECT` requests for IP addresses are served by the sidecar
+    /// stack, as if the connection had been redirected to the requested
+    /// address. Requests for domain names are discovered by name and tunneled
+    /// through the opaque stack. `BIND` and `UDP ASSOCIATE` requests are not
+    /// supported.
+    pub fn mk_socks5<T, I, R>(
+        &self,
+        profiles: impl profiles::GetProfile<Error = Error>,
+        policies: impl policy::GetPolicy,
+        resolve: R,
+    ) -> svc::ArcNewTcp<T, I>
+    where
+        // Target describing a connection accepted on the SOCKS5 listener.
+        T: svc::Param<Local<ServerAddr>>,
+        T: Clone + Send + Sync + 'static,
+        // Server-side socket.
+        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr + io::Splice,
+        I: Debug + Unpin + Send + Sync + 'static,
+        // Endpoint resolver.
+        R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
+        R::Resolution: Unpin,
+    {
+        let profiles = profiles::WithAllowlist::new(profiles, self.config.allow_discovery.clone());
+
+        let by_addr = self.mk_sidecar(profiles.clone(), policies.clone(), resolve.clone());
+
+        self.to_tcp_connect()
+            .push_opaq_cached(resolve)
+            .map_stack(|_, rt, stk| {
+                let route_updates = rt.route_updates.clone();
+                stk.push_filter(move |discovery| Tunnel::from_discovery(discovery, &route_updates))
+            })
+            .push_discover(self.ingress_resolver(profiles, policies))
+            .push_socks5(by_addr)
+            .into_inner()
+    }
+}
+
+impl<N> Outbound<N> {
+    /// Serves SOCKS5 connections.
+    ///
+    /// Once a client has completed the SOCKS5 handshake, connections to IP
+    /// addresses are served by the `by_addr` stack and connections to domain
+    /// names are served by the inner stack.
+    fn push_socks5<T, I, A, ASvc, NSvc>(self, by_addr: A) -> Outbound<svc::ArcNewTcp<T, I>>
+    where
+        // Target type describing an accepted connection.
+        T: svc::Param<Local<ServerAddr>>,
+        T: Clone + Send + Sync + 'static,
+        // A server-side socket.
+        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + io::Splice,
+        I: Debug + Send + Unpin + 'static,
+        // Stack for connections to IP addresses.
+        A: svc::NewService<Requested, Service = ASvc> + Clone + Send + Sync + 'static,
+        ASvc: svc::Service<I, Response = (), Error = Error> + Send + 'static,
+        ASvc::Future: Send,
+        // Stack for connections to domain names.
+        N: svc::NewService<Connect, Service = NSvc> + Clone + Send + Sync + 'static,
+        NSvc: svc::Service<I, Response = (), Error = Error> + Send + 'static,
+        NSvc::Future: Send,
+    {
+        self.map_stack(|config, rt, by_name| {
+            let credentials = config
+                .socks5_proxy
+                .as_ref()
+                .and_then(|c| c.credentials.clone())
+                .map(Arc::new);
+            let layer = NewSocks5Server::layer(
+                by_addr,
+                credentials,
+                config.proxy.detect_protocol_timeout,
+                rt.metrics.prom.socks5.clone(),
+            );
+            by_name
+                .push(layer)
+                .instrument(|t: &T| {
+                    let Local(ServerAddr(addr)) = t.param();
+                    tracing::info_span!("socks5", listen.addr = %addr)
+                })
+                .arc_new_tcp()
+        })
+    }
+}
+
+// === impl Socks5Credentials ===
+
+impl Debug for Socks5Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Socks5Credentials")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+// === impl Socks5Metrics ===
+
+impl Socks5Metrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let connections = prom::Family::default();
+        registry.register(
+            "ingress_connections",
+            "The number of connections accepted on an ingress listener, by handshake result",
+            connections.clone(),
+        );
+        Self { connections }
+    }
+
+    fn inc(&self, result: ConnectionResult) {
+        self.connections
+            .get_or_create(&ConnectionLabels {
+                ingress: Ingress::socks5,
+                result,
+            })
+            .inc();
+    }
+}
+
+// === impl Requested ===
+
+impl svc::Param<OrigDstAddr> for Requested {
+    fn param(&self) -> OrigDstAddr {
+        self.0
+    }
+}
+
+// === impl NewSocks5Server ===
+
+impl<A, N> NewSocks5Server<A, N> {
+    fn layer(
+        by_addr: A,
+        credentials: Option<Arc<Socks5Credentials>>,
+        read_timeout: time::Duration,
+        metrics: Socks5Metrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone
+    where
+        A: Clone,
+    {
+        svc::layer::mk(move |by_name| Self {
+            by_addr: by_addr.clone(),
+            by_name,
+            credentials: credentials.clone(),
+            read_timeout,
+            metrics: metrics.clone(),
+        })
+    }
+}
+
+impl<T, A, N> svc::NewService<T> for NewSocks5Server<A, N>
+where
+    T: svc::Param<Local<ServerAddr>>,
+    A: Clone,
+    N: Clone,
+{
+    type Service = Socks5Server<A, N>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Local(ServerAddr(bound)) = target.param();
+        Socks5Server {
+            by_addr: self.by_addr.clone(),
+            by_name: self.by_name.clone(),
+            bound,
+            credentials: self.credentials.clone(),
+            read_timeout: self.read_timeout,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+// === impl Socks5Server ===
+
+impl<I, A, ASvc, N, NSvc> svc::Service<I> for Socks5Server<A, N>
+where
+    I: io::AsyncRead + io::AsyncWrite + Send + Unpin + 'static,
+    A: svc::NewService<Requested, Service = ASvc> + Clone + Send + 'static,
+    ASvc: svc::Service<I, Response = (), Error = Error> + Send + 'static,
+    ASvc::Future: Send,
+    N: svc::NewService<Connect, Service = NSvc> + Clone + Send + 'static,
+    NSvc: svc::Service<I, Response = (), Error = Error> + Send + 'static,
+    NSvc::Future: Send,
+{
+    type Response = ();
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut io: I) -> Self::Future {
+        let by_addr = self.by_addr.clone();
+        let by_name = self.by_name.clone();
+        let bound = self.bound;
+        let credentials = self.credentials.clone();
+        let read_timeout = self.read_timeout;
+        let metrics = self.metrics.clone();
+        Box::pin(async move {
+            let handshake = handshake(&mut io, credentials.as_deref());
+            let addr = match time::timeout(read_timeout, handshake).await {
+                Ok(Ok(addr)) => addr,
+                Ok(Err(error)) => {
+                    metrics.inc(error.result());
+                    return Err(error.into());
+                }
+                Err(_) => {
+                    metrics.inc(ConnectionResult::invalid);
+                    return Err(HandshakeError::Timeout.into());
+                }
+            };
+            tracing::debug!(%addr, "CONNECT");
+
+            match addr {
+                Addr::Socket(addr) => {
+                    let svc = by_addr.new_service(Requested(OrigDstAddr(addr)));
+                    tunnel(io, svc, bound, &metrics).await
+                }
+                addr @ Addr::Name(_) => {
+                    let svc = by_name.new_service(Connect { addr });
+                    tunnel(io, svc, bound, &metrics).await
+                }
+            }
+        })
+    }
+}
+
+/// Completes a `CONNECT` request once the tunnel's target has been discovered,
+/// so that discovery failures can be reported to the client.
+async fn tunnel<I, S>(
+    mut io: I,
+    mut svc: S,
+    bound: SocketAddr,
+    metrics: &Socks5Metrics,
+) -> Result<()>
+where
+    I: io::AsyncWrite + Unpin,
+    S: svc::Service<I, Response = (), Error = Error>,
+{
+    if let Err(error) = svc.ready().await {
+        metrics.inc(ConnectionResult::unreachable);
+        reply(&mut io, REP_HOST_UNREACHABLE, None).await?;
+        return Err(error);
+    }
+    metrics.inc(ConnectionResult::established);
+    reply(&mut io, REP_SUCCEEDED, Some(bound)).await?;
+    svc.call(io).await
+}
+
+/// Negotiates authentication and reads a `CONNECT` request, returning its
+/// destination address. Failures are reported to the client where the
+/// protocol permits it.
+async fn handshake<I>(
+    io: &mut I,
+    credentials: Option<&Socks5Credentials>,
+) -> Result<Addr, HandshakeError>
+where
+    I: io::AsyncRead + io::AsyncWrite + Unpin,
+{
+    let version = io.read_u8().await?;
+    if version != VERSION {
+        return Err(HandshakeError::Version(version));
+    }
+    let mut methods = [0u8; u8::MAX as usize];
+    let methods = &mut methods[..io.read_u8().await? as usize];
+    io.read_exact(methods).await?;
+
+    let method = if credentials.is_some() {
+        METHOD_USERNAME_PASSWORD
+    } else {
+        METHOD_NO_AUTH
+    };
+    if !methods.contains(&method) {
+        io.write_all(&[VERSION, METHOD_NONE_ACCEPTABLE]).await?;
+        return Err(HandshakeError::NoAcceptableMethod);
+    }
+    io.write_all(&[VERSION, method]).await?;
+    if let Some(credentials) = credentials {
+        authenticate(io, credentials).await?;
+    }
+
+    let mut head = [0u8; 4];
+    io.read_exact(&mut head).await?;
+    let [version, command, _, atyp] = head;
+    if version != VERSION {
+        return Err(HandshakeError::Version(version));
+    }
+    if command != CMD_CONNECT {
+        reply(io, REP_COMMAND_NOT_SUPPORTED, None).await?;
+        return Err(HandshakeError::Command(command));
+    }
+
+    let ip = match atyp {
+        ATYP_IPV4 => {
+            let mut octets = [0u8; 4];
+            io.read_exact(&mut octets).await?;
+            Some(IpAddr::from(octets))
+        }
+        ATYP_IPV6 => {
+            let mut octets = [0u8; 16];
+            io.read_exact(&mut octets).await?;
+            Some(IpAddr::from(octets))
+        }
+        ATYP_NAME => None,
+        _ => {
+            reply(io, REP_ADDR_TYPE_NOT_SUPPORTED, None).await?;
+            return Err(HandshakeError::AddrType(atyp));
+        }
+    };
+    let addr = match ip {
+        Some(ip) => Addr::Socket(SocketAddr::new(ip, io.read_u16().await?)),
+        None => {
+            let mut name = [0u8; u8::MAX as usize];
+            let name = &mut name[..io.read_u8().await? as usize];
+            io.read_exact(name).await?;
+            let port = io.read_u16().await?;
+            match std::str::from_utf8(name)
+                .ok()
+                .and_then(|name| Addr::from_str_and_port(name, port).ok())
+            {
+                Some(addr) => addr,
+                None => {
+                    reply(io, REP_GENERAL_FAILURE, None).await?;
+                    return Err(HandshakeError::InvalidAddr);
+                }
+            }
+        }
+    };
+
+    Ok(addr)
+}
+
+/// Performs username/password authentication (RFC 1929).
+async fn authenticate<I>(io: &mut I, credentials: &Socks5Credentials) -> Result<(), HandshakeError>
+where
+    I: io::AsyncRead + io::AsyncWrite + Unpin,
+{
+    let version = io.read_u8().await?;
+    if version != AUTH_VERSION {
+        return Err(HandshakeError::Version(version));
+    }
+    let mut username = [0u8; u8::MAX as usize];
+    let username = &mut username[..io.read_u8().await? as usize];
+    io.read_exact(username).await?;
+    let mut password = [0u8; u8::MAX as usize];
+    let password = &mut password[..io.read_u8().await? as usize];
+    io.read_exact(password).await?;
+
+    // Both credentials are always compared, so that the time taken does not
+    // reveal which of them is incorrect.
+    let username_ok = constant_time_eq(username, credentials.username.as_bytes());
+    let password_ok = constant_time_eq(password, credentials.password.as_bytes());
+    if !(username_ok & password_ok) {
+        io.write_all(&[AUTH_VERSION, AUTH_FAILURE]).await?;
+        return Err(HandshakeError::Unauthorized);
+    }
+    io.write_all(&[AUTH_VERSION, AUTH_SUCCESS]).await?;
+    Ok(())
+}
+
+/// Compares a received credential to the configured one in time that depends
+/// only on the length of the received credential.
+fn constant_time_eq(received: &[u8], expected: &[u8]) -> bool {
+    let diff = received
+        .iter()
+        .enumerate()
+        .fold(received.len() ^ expected.len(), |diff, (i, b)| {
+            diff | usize::from(b ^ expected.get(i).copied().unwrap_or(0))
+        });
+    std::hint::black_box(diff) == 0
+}
+
+/// Writes a reply to a request. Failure replies do not include a bound
+/// address.
+async fn reply<I>(io: &mut I, rep: u8, bound: Option<SocketAddr>) -> io::Result<()>
+where
+    I: io::AsyncWrite + Unpin,
+{
+    let bound = bound.unwrap_or_else(|| SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)));
+    let mut buf = Vec::with_capacity(22);
+    buf.extend_from_slice(&[VERSION, rep, 0x00]);
+    match bound.ip() {
+        IpAddr::V4(ip) => {
+            buf.push(ATYP_IPV4);
+            buf.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            buf.push(ATYP_IPV6);
+            buf.extend_from_slice(&Ipv6Addr::octets(&ip));
+        }
+    }
+    buf.extend_from_slice(&bound.port().to_be_bytes());
+    io.write_all(&buf).await
+}
+
+// === impl HandshakeError ===
+
+impl HandshakeError {
+    fn result(&self) -> ConnectionResult {
+        match self {
+            Self::NoAcceptableMethod | Self::Unauthorized => ConnectionResult::unauthorized,
+            Self::Command(_) | Self::AddrType(_) => ConnectionResult::unsupported,
+            Self::Version(_) | Self::InvalidAddr | Self::Timeout | Self::Io(_) => {
+                ConnectionResult::invalid
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use svc::NewService;
+    use tokio::io::DuplexStream;
+
+    const BOUND: ([u8; 4], u16) = ([127, 0, 0, 1], 4150);
+
+    fn socks5<A, ASvc, N, NSvc>(
+        credentials: Option<Socks5Credentials>,
+        by_addr: A,
+        by_name: N,
+    ) -> (
+        svc::ArcNewTcp<Local<ServerAddr>, DuplexStream>,
+        prom::Registry,
+    )
+    where
+        A: svc::NewService<Requested, Service = ASvc> + Clone + Send + Sync + 'static,
+        ASvc: svc::Service<DuplexStream, Response = (), Error = Error> + Send + 'static,
+        ASvc::Future: Send,
+        N: svc::NewService<Connect, Service = NSvc> + Clone + Send + Sync + 'static,
+        NSvc: svc::Service<DuplexStream, Response = (), Error = Error> + Send + 'static,
+        NSvc::Future: Send,
+    {
+        let mut config = crate::test_util::default_config();
+        config.socks5_proxy = Some(Socks5Config {
+            server: config.proxy.server.clone(),
+            credentials,
+        });
+        let (runtime, _drain) = crate::test_util::runtime();
+        let mut registry = prom::Registry::default();
+        let stack = Outbound::new(config, runtime, &mut registry)
+            .with_stack(by_name)
+            .push_socks5(by_addr)
+            .into_inner();
+        (stack, registry)
+    }
+
+    fn bound() -> Local<ServerAddr> {
+        Local(ServerAddr(BOUND.into()))
+    }
+
+    fn no_addr(
+    ) -> impl svc::NewService<Requested, Service = svc::BoxService<DuplexStream, (), Error>> + Clone
+    {
+        |_: Requested| -> svc::BoxService<DuplexStream, (), Error> {
+            panic!("unexpected connection by address")
+        }
+    }
+
+    fn no_name(
+    ) -> impl svc::NewService<Connect, Service = svc::BoxService<DuplexStream, (), Error>> + Clone
+    {
+        |_: Connect| -> svc::BoxService<DuplexStream, (), Error> {
+            panic!("unexpected connection by name")
+        }
+    }
+
+    /// Echoes a greeting, asserting that the client sends `hello`.
+    fn greet() -> svc::BoxService<DuplexStream, (), Error> {
+        svc::BoxService::new(svc::mk(|mut io: DuplexStream| {
+            Box::pin(async move {
+                let mut buf = [0u8; 5];
+                io.read_exact(&mut buf).await?;
+                assert_eq!(&buf, b"hello");
+                io.write_all(b"world").await?;
+                Ok::<_, Error>(())
+            })
+        }))
+    }
+
+    fn succeeded() -> Vec<u8> {
+        let ([a, b, c, d], port) = BOUND;
+        let [p0, p1] = port.to_be_bytes();
+        vec![VERSION, REP_SUCCEEDED, 0, ATYP_IPV4, a, b, c, d, p0, p1]
+    }
+
+    fn failed(rep: u8) -> Vec<u8> {
+        vec![VERSION, rep, 0, ATYP_IPV4, 0, 0, 0, 0, 0, 0]
+    }
+
+    /// A service whose target could not be discovered.
+    struct Undiscovered;
+
+    impl svc::Service<DuplexStream> for Undiscovered {
+        type Response = ();
+        type Error = Error;
+        type Future = future::Ready<Result<()>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<()>> {
+            Poll::Ready(Err(io::Error::other("discovery failed").into()))
+        }
+
+        fn call(&mut self, _: DuplexStream) -> Self::Future {
+            panic!("unexpected call")
+        }
+    }
+
+    fn encode(registry: &prom::Registry) -> String {
+        let mut text = String::new();
+        prom::encoding::text::encode(&mut text, registry).unwrap();
+        text
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn connect_by_addr() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let by_addr = |Requested(OrigDstAddr(addr)): Requested| {
+            assert_eq!(addr, SocketAddr::from(([192, 0, 2, 3], 8080)));
+            greet()
+        };
+        let (stack, registry) = socks5(None, by_addr, no_name());
+        let svc = stack.new_service(bound());
+
+        let (mut client, server) = io::duplex(1024);
+        let task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(&[VERSION, 1, METHOD_NO_AUTH])
+            .await
+            .unwrap();
+        client
+            .write_all(&[VERSION, CMD_CONNECT, 0, ATYP_IPV4, 192, 0, 2, 3, 0x1f, 0x90])
+            .await
+            .unwrap();
+        client.write_all(b"hello").await.unwrap();
+
+        task.await.unwrap().expect("tunnel must complete");
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(
+            buf,
+            [&[VERSION, METHOD_NO_AUTH][..], &succeeded(), b"world"].concat()
+        );
+
+        let text = encode(&registry);
+        assert!(
+            text.contains("ingress_connections_total{ingress=\"socks5\",result=\"established\"} 1"),
+            "{text}"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn connect_by_name_with_credentials() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let by_name = |Connect { addr }: Connect| {
+            assert_eq!(
+                addr,
+                Addr::from_str("foo.ns.svc.cluster.local:8080").unwrap()
+            );
+            greet()
+        };
+        let credentials = Socks5Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        };
+        let (stack, _registry) = socks5(Some(credentials), no_addr(), by_name);
+        let svc = stack.new_service(bound());
+
+        let (mut client, server) = io::duplex(1024);
+        let task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(&[VERSION, 2, METHOD_NO_AUTH, METHOD_USERNAME_PASSWORD])
+            .await
+            .unwrap();
+        client
+            .write_all(&[
+                AUTH_VERSION,
+                4,
+                b'u',
+                b's',
+                b'e',
+                b'r',
+                4,
+                b'p',
+                b'a',
+                b's',
+                b's',
+            ])
+            .await
+            .unwrap();
+        let name = b"foo.ns.svc.cluster.local";
+        client
+            .write_all(&[VERSION, CMD_CONNECT, 0, ATYP_NAME, name.len() as u8])
+            .await
+            .unwrap();
+        client.write_all(name).await.unwrap();
+        client.write_all(&8080u16.to_be_bytes()).await.unwrap();
+        client.write_all(b"hello").await.unwrap();
+
+        task.await.unwrap().expect("tunnel must complete");
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(
+            buf,
+            [
+                &[
+                    VERSION,
+                    METHOD_USERNAME_PASSWORD,
+                    AUTH_VERSION,
+                    AUTH_SUCCESS
+                ][..],
+                &succeeded(),
+                b"world"
+            ]
+            .concat()
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn rejects_invalid_credentials() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let credentials = Socks5Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        };
+        let (stack, registry) = socks5(Some(credentials), no_addr(), no_name());
+        let svc = stack.new_service(bound());
+
+        let (mut client, server) = io::duplex(1024);
+        let task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(&[VERSION, 1, METHOD_USERNAME_PASSWORD])
+            .await
+            .unwrap();
+        client
+            .write_all(&[
+                AUTH_VERSION,
+                4,
+                b'u',
+                b's',
+                b'e',
+                b'r',
+                4,
+                b'n',
+                b'o',
+                b'p',
+                b'e',
+            ])
+            .await
+            .unwrap();
+
+        task.await.unwrap().expect_err("handshake must fail");
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(
+            buf,
+            [
+                VERSION,
+                METHOD_USERNAME_PASSWORD,
+                AUTH_VERSION,
+                AUTH_FAILURE
+            ]
+        );
+
+        let text = encode(&registry);
+        assert!(
+            text.contains(
+                "ingress_connections_total{ingress=\"socks5\",result=\"unauthorized\"} 1"
+            ),
+            "{text}"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn requires_credentials_when_configured() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let credentials = Socks5Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        };
+        let (stack, _registry) = socks5(Some(credentials), no_addr(), no_name());
+        let svc = stack.new_service(bound());
+
+        let (mut client, server) = io::duplex(1024);
+        let task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(&[VERSION, 1, METHOD_NO_AUTH])
+            .await
+            .unwrap();
+
+        task.await.unwrap().expect_err("handshake must fail");
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, [VERSION, METHOD_NONE_ACCEPTABLE]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn bind_unsupported() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let (stack, registry) = socks5(None, no_addr(), no_name());
+        let svc = stack.new_service(bound());
+
+        let (mut client, server) = io::duplex(1024);
+        let task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(&[VERSION, 1, METHOD_NO_AUTH])
+            .await
+            .unwrap();
+        // BIND
+        client
+            .write_all(&[VERSION, 0x02, 0, ATYP_IPV4, 192, 0, 2, 3, 0x1f, 0x90])
+            .await
+            .unwrap();
+
+        task.await.unwrap().expect_err("request must fail");
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(
+            buf,
+            [
+                &[VERSION, METHOD_NO_AUTH][..],
+                &failed(REP_COMMAND_NOT_SUPPORTED)
+            ]
+            .concat()
+        );
+
+        let text = encode(&registry);
+        assert!(
+            text.contains("ingress_connections_total{ingress=\"socks5\",result=\"unsupported\"} 1"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn constant_time_eq_credentials() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secreT", b"secret"));
+        assert!(!constant_time_eq(b"secre", b"secret"));
+        assert!(!constant_time_eq(b"secrets", b"secret"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn discovery_failure_unreachable() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let by_addr = |_: Requested| svc::BoxService::new(Undiscovered);
+        let (stack, registry) = socks5(None, by_addr, no_name());
+        let svc = stack.new_service(bound());
+
+        let (mut client, server) = io::duplex(1024);
+        let task = tokio::spawn(svc.oneshot(server));
+        client
+            .write_all(&[VERSION, 1, METHOD_NO_AUTH])
+            .await
+            .unwrap();
+        client
+            .write_all(&[VERSION, CMD_CONNECT, 0, ATYP_IPV4, 192, 0, 2, 3, 0x1f, 0x90])
+            .await
+            .unwrap();
+
+        task.await.unwrap().expect_err("tunnel must fail");
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(
+            buf,
+            [
+                &[VERSION, METHOD_NO_AUTH][..],
+                &failed(REP_HOST_UNREACHABLE)
+            ]
+            .concat()
+        );
+
+        let text = encode(&registry);
+        assert!(
+            text.contains("ingress_connections_total{ingress=\"socks5\",result=\"unreachable\"} 1"),
+            "{text}"
+        );
+    }
+}
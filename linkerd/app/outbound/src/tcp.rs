@@ -1,4 +1,5 @@
-pub use self::connect::Connect;
+pub use self::connect::{Connect, PreventLoopback};
+pub(crate) use self::errors::TransportErrorMetrics;
 use crate::Outbound;
 use linkerd_app_core::{
     io, svc,
@@ -8,6 +9,7 @@ use linkerd_app_core::{
 
 mod connect;
 mod endpoint;
+mod errors;
 pub mod tagged_transport;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
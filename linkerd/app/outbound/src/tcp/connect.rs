@@ -21,11 +21,19 @@ pub struct PreventLoopback<S>(S);
 
 impl Outbound<()> {
     pub fn to_tcp_connect(&self) -> Outbound<PreventLoopback<ConnectTcp>> {
-        let connect = PreventLoopback(ConnectTcp::new(
+        let connect = self.tcp_connector();
+        self.clone().with_stack(connect)
+    }
+}
+
+impl<S> Outbound<S> {
+    /// Returns a connector that establishes TCP connections with remote
+    /// endpoints.
+    pub(crate) fn tcp_connector(&self) -> PreventLoopback<ConnectTcp> {
+        PreventLoopback(ConnectTcp::new(
             self.config.proxy.connect.keepalive,
             self.config.proxy.connect.user_timeout,
-        ));
-        self.clone().with_stack(connect)
+        ))
     }
 }
 
@@ -1,5 +1,9 @@
-use super::{tagged_transport::TaggedTransport, *};
-use crate::{zone::TcpZoneLabels, ConnectMeta};
+use super::{errors::ConnectError, tagged_transport::TaggedTransport, *};
+use crate::{
+    http::{Connections, RecordConnect},
+    zone::TcpZoneLabels,
+    ConnectMeta,
+};
 use linkerd_app_core::{proxy::http, tls, transport_header::SessionProtocol};
 
 impl<C> Outbound<C> {
@@ -8,7 +12,7 @@ impl<C> Outbound<C> {
     ) -> Outbound<
         impl svc::MakeConnection<
                 T,
-                Connection = impl io::AsyncRead + io::AsyncWrite + Send + Unpin,
+                Connection = impl io::AsyncRead + io::AsyncWrite + io::Splice + Send + Unpin,
                 Metadata = ConnectMeta,
                 Error = Error,
                 Future = impl Send,
@@ -23,15 +27,19 @@ impl<C> Outbound<C> {
         T: svc::Param<Option<SessionProtocol>>,
         T: svc::Param<transport::labels::Key>,
         T: svc::Param<TcpZoneLabels>,
+        T: svc::Param<Option<Connections>>,
         // Connector stack.
         C: svc::MakeConnection<Connect, Metadata = Local<ClientAddr>, Error = io::Error>,
         C: Clone + Send + 'static,
-        C::Connection: Send + Unpin,
+        C::Connection: io::Splice + Send + Unpin,
         C::Metadata: Send + Unpin,
         C::Future: Send + 'static,
     {
         self.map_stack(|config, rt, connect| {
             connect
+                // Distinguishes errors establishing TCP connections from TLS
+                // handshake errors.
+                .push(svc::MapErr::layer(ConnectError::wrap))
                 // Initiates mTLS if the target is configured with identity. The
                 // endpoint configures ALPN when there is an opaque transport hint OR
                 // when an authority override is present (indicating the target is a
@@ -40,8 +48,14 @@ impl<C> Outbound<C> {
                 // Encodes a transport header if the established connection is TLS'd and
                 // ALPN negotiation indicates support.
                 .push(TaggedTransport::layer())
+                // Records how long connections take to establish, for HTTP
+                // endpoints whose requests report their timings.
+                .push(RecordConnect::layer())
                 // Limits the time we wait for a connection to be established.
                 .push_connect_timeout(config.proxy.connect.timeout)
+                .push(svc::stack::Monitor::layer(
+                    rt.metrics.prom.transport_errors.connect(),
+                ))
                 .push(svc::stack::BoxFuture::layer())
                 .push(transport::metrics::Client::layer(
                     rt.metrics.proxy.transport.clone(),
@@ -0,0 +1,441 @@
+//! Classifies outbound TCP connection errors by the phase of the connection in
+//! which they occurred and the kind of failure.
+//!
+//! Errors establishing connections are counted by the endpoint stack, which is
+//! shared by opaque, TLS, and HTTP endpoints. Errors on established connections
+//! are counted by the opaque and TLS stacks as connections are forwarded. In
+//! both cases, only errors on the connection to the target are counted.
+
+use linkerd_app_core::{
+    dns, errors, io,
+    metrics::prom::{self, encoding::*},
+    proxy::tcp,
+    svc,
+    transport::{Remote, ServerAddr},
+    Error,
+};
+
+/// Counts errors on outbound TCP connections.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TransportErrorMetrics {
+    errors: prom::Family<ErrorLabels, prom::Counter>,
+}
+
+/// Monitors a connector, counting errors establishing connections.
+#[derive(Clone, Debug)]
+pub(crate) struct MonitorConnect(TransportErrorMetrics);
+
+/// Monitors a stack that forwards connections, counting errors on the
+/// connections to each target. Each target's port is extracted with `X`.
+#[derive(Clone, Debug)]
+pub(crate) struct MonitorForward<X> {
+    metrics: TransportErrorMetrics,
+    extract: X,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct MonitorTarget {
+    metrics: TransportErrorMetrics,
+    port: Option<u16>,
+    classify: fn(&(dyn std::error::Error + 'static)) -> Option<(Phase, Kind)>,
+}
+
+/// Distinguishes errors establishing TCP connections from errors that occur
+/// once the connection is established, e.g. during a TLS handshake.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub(crate) struct ConnectError(#[source] io::Error);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct ErrorLabels {
+    phase: Phase,
+    kind: Kind,
+    target_port: u16,
+}
+
+/// The phase of a connection in which an error occurred.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+pub(crate) enum Phase {
+    /// Resolving the target's name.
+    dns,
+    /// Establishing the TCP connection. Connect timeouts, which also bound
+    /// the TLS handshake, are attributed to this phase.
+    connect,
+    /// Negotiating a TLS session over an established TCP connection.
+    tls_handshake,
+    read,
+    write,
+    shutdown,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+pub(crate) enum Kind {
+    refused,
+    timeout,
+    reset,
+    eof,
+    other,
+}
+
+// === impl TransportErrorMetrics ===
+
+impl TransportErrorMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let errors = prom::Family::default();
+        registry.register(
+            "transport_errors",
+            "The number of errors on connections to targets, by the phase of the connection in which they occurred",
+            errors.clone(),
+        );
+        Self { errors }
+    }
+
+    pub(crate) fn connect(&self) -> MonitorConnect {
+        MonitorConnect(self.clone())
+    }
+
+    pub(crate) fn forward<X>(&self, extract: X) -> MonitorForward<X> {
+        MonitorForward {
+            metrics: self.clone(),
+            extract,
+        }
+    }
+
+    fn record(&self, target_port: u16, (phase, kind): (Phase, Kind)) {
+        self.errors
+            .get_or_create(&ErrorLabels {
+                phase,
+                kind,
+                target_port,
+            })
+            .inc();
+    }
+}
+
+// === impl MonitorConnect ===
+
+impl<T: svc::Param<Remote<ServerAddr>>> svc::stack::MonitorService<T> for MonitorConnect {
+    type MonitorResponse = MonitorTarget;
+
+    fn monitor_request(&mut self, target: &T) -> Self::MonitorResponse {
+        let Remote(ServerAddr(addr)) = target.param();
+        MonitorTarget {
+            metrics: self.0.clone(),
+            port: Some(addr.port()),
+            classify: |error| Some(classify_connect(error)),
+        }
+    }
+}
+
+impl svc::stack::MonitorError<Error> for MonitorConnect {
+    /// Connectors are always ready, so readiness errors are not attributed to
+    /// a target.
+    fn monitor_error(&mut self, _: &Error) {}
+}
+
+// === impl MonitorForward ===
+
+impl<T, X> svc::stack::MonitorNewService<T> for MonitorForward<X>
+where
+    X: svc::ExtractParam<Option<u16>, T>,
+{
+    type MonitorService = MonitorTarget;
+
+    fn monitor(&self, target: &T) -> Self::MonitorService {
+        MonitorTarget {
+            metrics: self.metrics.clone(),
+            port: self.extract.extract_param(target),
+            classify: classify_forward,
+        }
+    }
+}
+
+// === impl MonitorTarget ===
+
+impl<Req> svc::stack::MonitorService<Req> for MonitorTarget {
+    type MonitorResponse = Self;
+
+    #[inline]
+    fn monitor_request(&mut self, _: &Req) -> Self::MonitorResponse {
+        self.clone()
+    }
+}
+
+impl svc::stack::MonitorError<Error> for MonitorTarget {
+    fn monitor_error(&mut self, error: &Error) {
+        let Some(port) = self.port else {
+            return;
+        };
+        if let Some(labels) = (self.classify)(&**error) {
+            self.metrics.record(port, labels);
+        }
+    }
+}
+
+// === impl ConnectError ===
+
+impl ConnectError {
+    /// Wraps an error establishing a TCP connection, preserving its kind.
+    pub(crate) fn wrap(error: io::Error) -> io::Error {
+        io::Error::new(error.kind(), Self(error))
+    }
+}
+
+// === impl Kind ===
+
+impl Kind {
+    fn mk(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::ConnectionRefused => Self::refused,
+            io::ErrorKind::TimedOut => Self::timeout,
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => Self::reset,
+            io::ErrorKind::UnexpectedEof | io::ErrorKind::WriteZero => Self::eof,
+            _ => Self::other,
+        }
+    }
+}
+
+/// Classifies an error that prevented a connection from being established.
+///
+/// I/O errors are attributed to the TLS handshake unless they were raised
+/// while establishing the TCP connection.
+pub(crate) fn classify_connect(error: &(dyn std::error::Error + 'static)) -> (Phase, Kind) {
+    if errors::is_caused_by::<dns::ResolveError>(error) {
+        return (Phase::dns, Kind::other);
+    }
+    if errors::is_caused_by::<errors::ConnectTimeout>(error) {
+        return (Phase::connect, Kind::timeout);
+    }
+    match errors::cause_ref::<io::Error>(error) {
+        Some(e) if e.get_ref().is_some_and(|e| e.is::<ConnectError>()) => {
+            (Phase::connect, Kind::mk(e))
+        }
+        Some(e) => (Phase::tls_handshake, Kind::mk(e)),
+        None => (Phase::tls_handshake, Kind::other),
+    }
+}
+
+/// Classifies an error that occurred while forwarding an established
+/// connection, if it occurred on the connection to the target.
+pub(crate) fn classify_forward(error: &(dyn std::error::Error + 'static)) -> Option<(Phase, Kind)> {
+    let io = errors::cause_ref::<io::Error>(error)?;
+    let duplex = tcp::DuplexError::downcast_ref(io)?;
+    if duplex.side() != tcp::Side::Server {
+        return None;
+    }
+    // Connections that are closed once they exceed their maximum lifetime
+    // did not fail.
+    if duplex
+        .io()
+        .get_ref()
+        .is_some_and(|e| e.is::<crate::ConnectionExpired>())
+    {
+        return None;
+    }
+    let phase = match duplex.op() {
+        tcp::Op::Read => Phase::read,
+        tcp::Op::Write => Phase::write,
+        tcp::Op::Shutdown => Phase::shutdown,
+    };
+    Some((phase, Kind::mk(io)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use linkerd_app_core::svc::{Layer, NewService, ServiceExt};
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tokio::time;
+
+    const PORT: u16 = 8080;
+
+    #[derive(Clone, Debug)]
+    struct Target;
+
+    impl svc::Param<Remote<ServerAddr>> for Target {
+        fn param(&self) -> Remote<ServerAddr> {
+            Remote(ServerAddr(([10, 1, 2, 3], PORT).into()))
+        }
+    }
+
+    /// An I/O that fails the given operation with an error of the given kind.
+    #[derive(Copy, Clone, Debug)]
+    struct FailIo(tcp::Op, io::ErrorKind);
+
+    impl io::AsyncRead for FailIo {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            _: &mut io::ReadBuf<'_>,
+        ) -> io::Poll<()> {
+            match self.0 {
+                tcp::Op::Read => Poll::Ready(Err(self.1.into())),
+                _ => Poll::Pending,
+            }
+        }
+    }
+
+    impl io::AsyncWrite for FailIo {
+        fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> io::Poll<usize> {
+            match self.0 {
+                tcp::Op::Write if self.1 == io::ErrorKind::WriteZero => Poll::Ready(Ok(0)),
+                tcp::Op::Write => Poll::Ready(Err(self.1.into())),
+                _ => Poll::Ready(Ok(buf.len())),
+            }
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> io::Poll<()> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> io::Poll<()> {
+            match self.0 {
+                tcp::Op::Shutdown => Poll::Ready(Err(self.1.into())),
+                _ => Poll::Ready(Ok(())),
+            }
+        }
+    }
+
+    fn count(metrics: &TransportErrorMetrics, (phase, kind): (Phase, Kind)) -> u64 {
+        metrics
+            .errors
+            .get_or_create(&ErrorLabels {
+                phase,
+                kind,
+                target_port: PORT,
+            })
+            .get()
+    }
+
+    /// Connects through a TCP connector that fails with `tcp` and a TLS
+    /// handshake that fails with `handshake`. When the connector succeeds and
+    /// the handshake does not fail, the connection times out.
+    async fn connect(
+        tcp: Option<io::ErrorKind>,
+        handshake: Option<io::ErrorKind>,
+    ) -> (Phase, Kind) {
+        let tcp = svc::stack(svc::mk(move |_: Target| {
+            future::ready(match tcp {
+                Some(kind) => Err(io::Error::from(kind)),
+                None => Ok(()),
+            })
+        }))
+        .push(svc::MapErr::layer(ConnectError::wrap))
+        .into_inner();
+        let tls = svc::mk(move |target: Target| {
+            let tcp = tcp.clone();
+            async move {
+                tcp.oneshot(target).await?;
+                match handshake {
+                    Some(kind) => Err::<(), _>(io::Error::from(kind)),
+                    None => future::pending().await,
+                }
+            }
+        });
+
+        let metrics = TransportErrorMetrics::default();
+        let svc = svc::stack(tls)
+            .push_connect_timeout(time::Duration::from_secs(1))
+            .push(svc::stack::Monitor::layer(metrics.connect()))
+            .into_inner();
+        let error = svc.oneshot(Target).await.expect_err("connect must fail");
+
+        let labels = classify_connect(&*error);
+        assert_eq!(count(&metrics, labels), 1, "the error must be counted");
+        labels
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn classifies_connect_errors() {
+        use io::ErrorKind::*;
+        for (tcp, handshake, expected) in [
+            (
+                Some(ConnectionRefused),
+                None,
+                (Phase::connect, Kind::refused),
+            ),
+            (Some(ConnectionReset), None, (Phase::connect, Kind::reset)),
+            (Some(AddrNotAvailable), None, (Phase::connect, Kind::other)),
+            (None, None, (Phase::connect, Kind::timeout)),
+            (None, Some(UnexpectedEof), (Phase::tls_handshake, Kind::eof)),
+            (
+                None,
+                Some(ConnectionReset),
+                (Phase::tls_handshake, Kind::reset),
+            ),
+            (None, Some(TimedOut), (Phase::tls_handshake, Kind::timeout)),
+            (None, Some(InvalidData), (Phase::tls_handshake, Kind::other)),
+        ] {
+            assert_eq!(
+                connect(tcp, handshake).await,
+                expected,
+                "{tcp:?} {handshake:?}"
+            );
+        }
+    }
+
+    /// Forwards a client connection to a target connection, returning the
+    /// error that forwarding failed with.
+    async fn forward<I, O>(src: I, dst: O, metrics: &TransportErrorMetrics) -> Error
+    where
+        I: io::AsyncRead + io::AsyncWrite + Send + Unpin + 'static,
+        O: io::AsyncRead + io::AsyncWrite + Clone + Send + Unpin + 'static,
+    {
+        let connect = svc::mk(move |()| future::ok::<_, Error>(dst.clone()));
+        let new_svc = svc::stack::NewMonitor::layer(metrics.forward(|_: &Target| Some(PORT)))
+            .layer(move |_: Target| tcp::Forward::layer().layer(connect.clone()));
+        new_svc
+            .new_service(Target)
+            .oneshot(src)
+            .await
+            .expect_err("forwarding must fail")
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn classifies_forward_errors() {
+        use io::ErrorKind::*;
+        use tcp::Op::*;
+        for (op, kind, expected) in [
+            (Read, ConnectionReset, (Phase::read, Kind::reset)),
+            (Read, TimedOut, (Phase::read, Kind::timeout)),
+            (Read, UnexpectedEof, (Phase::read, Kind::eof)),
+            (Write, BrokenPipe, (Phase::write, Kind::reset)),
+            (Write, WriteZero, (Phase::write, Kind::eof)),
+            (Write, Other, (Phase::write, Kind::other)),
+            (Shutdown, BrokenPipe, (Phase::shutdown, Kind::reset)),
+            (Shutdown, NotConnected, (Phase::shutdown, Kind::other)),
+        ] {
+            let (mut client, src) = io::duplex(64);
+            io::AsyncWriteExt::write_all(&mut client, b"hello")
+                .await
+                .unwrap();
+            if op == Shutdown {
+                // The client's EOF is propagated to the target.
+                io::AsyncWriteExt::shutdown(&mut client).await.unwrap();
+            }
+
+            let metrics = TransportErrorMetrics::default();
+            let error = forward(src, FailIo(op, kind), &metrics).await;
+            assert_eq!(classify_forward(&*error), Some(expected), "{op:?} {kind:?}");
+            assert_eq!(count(&metrics, expected), 1, "the error must be counted");
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn ignores_client_errors() {
+        let src = FailIo(tcp::Op::Read, io::ErrorKind::ConnectionReset);
+        let dst = FailIo(tcp::Op::Shutdown, io::ErrorKind::Other);
+        let metrics = TransportErrorMetrics::default();
+        let error = forward(src, dst, &metrics).await;
+        assert_eq!(classify_forward(&*error), None);
+        assert_eq!(count(&metrics, (Phase::read, Kind::reset)), 0);
+    }
+}
@@ -211,6 +211,7 @@ mod test {
             let meta = tls::ConnectMeta {
                 socket: Local(ClientAddr(([0, 0, 0, 0], 0).into())),
                 tls: Conditional::Some(Some(tls::NegotiatedProtocolRef(PROTOCOL).into())),
+                handshake: None,
             };
             future::ready(Ok::<_, io::Error>((io, meta)))
         }
@@ -229,6 +230,7 @@ mod test {
                 let meta = tls::ConnectMeta {
                     socket: Local(ClientAddr(([0, 0, 0, 0], 0).into())),
                     tls: Conditional::Some(None),
+                    handshake: None,
                 };
                 future::ready(Ok::<_, io::Error>((io, meta)))
             }),
@@ -13,6 +13,9 @@ use linkerd_app_core::{
 pub use linkerd_app_test as support;
 use std::{str::FromStr, time::Duration};
 
+#[cfg(feature = "test-util")]
+pub mod fixture;
+
 pub(crate) fn default_config() -> Config {
     let buffer = QueueConfig {
         capacity: 10_000,
@@ -21,6 +24,35 @@ pub(crate) fn default_config() -> Config {
     Config {
         ingress_mode: false,
         emit_headers: true,
+        http_request_id: None,
+        http_deadline: None,
+        http_latency_outliers: None,
+        http_source_affinity: None,
+        http_hairpin: None,
+        http_workload_identity: None,
+        http2_mesh_adaptive_flow_control: false,
+        http_retry_buffer_bytes: 64 * 1024 * 1024,
+        http_response_cache_bytes: 1024 * 1024,
+        http_fault_injection_seed: None,
+        http_profile_retry_timeout: None,
+        http_route_debug_header: false,
+        http_upgrade_probe_ttl: None,
+        http_request_timings_threshold: None,
+        http_keepalive_exempt: Vec::new(),
+        http_logical_max_age: None,
+        http_request_body_buffer: Default::default(),
+        tcp_splice: false,
+        tls_plaintext_http_response: true,
+        http_version_hint: false,
+        tcp_half_close: Default::default(),
+        tcp_connect_retries: 0,
+        connection_lifetimes: Default::default(),
+        explicit_proxy: None,
+        socks5_proxy: None,
+        prewarm: Default::default(),
+        port_mappings: Vec::new(),
+        listener: Default::default(),
+        additional_listeners: Vec::new(),
         allow_discovery: IpMatch::new(Some(IpNet::from_str("0.0.0.0/0").unwrap())).into(),
         proxy: config::ProxyConfig {
             server: config::ServerConfig {
@@ -50,6 +82,15 @@ pub(crate) fn default_config() -> Config {
         },
         inbound_ips: Default::default(),
         discovery_idle_timeout: Duration::from_secs(60),
+        discovery_retention: None,
+        topology_hints: None,
+        balancer_ewma: Default::default(),
+        balancer_endpoint_load_metrics: false,
+        route_update_debounce: Duration::ZERO,
+        discover_profiles: true,
+        discovery_unmap_ipv4: true,
+        logical_name_labels_limit: 100,
+        discovery_snapshot: None,
         tcp_connection_queue: buffer,
         http_request_queue: buffer,
     }
@@ -65,6 +106,7 @@ pub(crate) fn runtime() -> (ProxyRuntime, drain::Signal) {
         tap,
         span_sink: None,
         drain,
+        tunables: tokio::sync::watch::channel(Default::default()).1,
     };
     (runtime, drain_tx)
 }
@@ -114,12 +156,14 @@ mod mock_body {
 
         /// Returns a [`MockBody`] that yields this gRPC code in its trailers section.
         pub fn grpc_status(code: u8) -> Self {
-            let trailers = {
-                let mut trailers = http::HeaderMap::with_capacity(1);
-                let status = code.to_string().parse().unwrap();
-                trailers.insert("grpc-status", status);
-                trailers
-            };
+            let mut trailers = http::HeaderMap::with_capacity(1);
+            let status = code.to_string().parse().unwrap();
+            trailers.insert("grpc-status", status);
+            Self::trailers(trailers)
+        }
+
+        /// Returns a [`MockBody`] that yields only a trailers section.
+        pub fn trailers(trailers: http::HeaderMap) -> Self {
             let fut = futures::future::ready(Some(Ok(trailers)));
 
             Self {
@@ -0,0 +1,546 @@
+//! In-memory discovery driven by fixture files.
+//!
+//! A [`Fixture`] describes a set of parent services, the routes configured on
+//! them, and the endpoints that each backend resolves to. It produces
+//! in-memory implementations of [`GetPolicy`](crate::policy::GetPolicy),
+//! [`GetProfile`](profiles::GetProfile) and
+//! [`Resolve`](linkerd_app_core::proxy::core::Resolve) along with a connector
+//! that serves every known endpoint from an in-process HTTP server. This makes
+//! it possible to drive a complete sidecar stack without a control plane or
+//! any sockets, e.g. from benchmarks.
+//!
+//! Fixtures are JSON documents of the form:
+//!
+//! ```json
+//! {
+//!   "parents": [{
+//!     "addr": "10.0.0.1:8080",
+//!     "name": "web.ns.svc.cluster.local:8080",
+//!     "protocol": "detect",
+//!     "routes": [{
+//!       "path_prefix": "/",
+//!       "backends": [{ "name": "web.ns.svc.cluster.local:8080", "weight": 1 }]
+//!     }]
+//!   }],
+//!   "endpoints": {
+//!     "web.ns.svc.cluster.local:8080": ["10.1.0.1:8080", "10.1.0.2:8080"]
+//!   }
+//! }
+//! ```
+
+use crate::{policy, tcp, Outbound};
+use futures::{future, stream, StreamExt};
+use linkerd_app_core::{
+    io, profiles,
+    proxy::{
+        api_resolve::{ConcreteAddr, Metadata},
+        core::Update,
+    },
+    svc,
+    transport::addrs::*,
+    Addr, Error, NameAddr,
+};
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    net::SocketAddr,
+    path::Path,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::sync::watch;
+
+/// Describes the services and endpoints known to in-memory discovery.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fixture {
+    pub parents: Vec<Parent>,
+
+    /// Maps each backend name to the endpoint addresses it resolves to.
+    #[serde(default, deserialize_with = "parse_endpoints")]
+    pub endpoints: HashMap<NameAddr, Vec<SocketAddr>>,
+}
+
+/// A parent service, discoverable by both its address and its name.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Parent {
+    pub addr: SocketAddr,
+    #[serde(deserialize_with = "parse")]
+    pub name: NameAddr,
+    #[serde(default)]
+    pub protocol: FixtureProtocol,
+    pub routes: Vec<Route>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixtureProtocol {
+    #[default]
+    Detect,
+    Http1,
+    Http2,
+    Opaque,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Route {
+    /// Restricts the route to request paths with this prefix. Ignored for
+    /// opaque traffic.
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    pub backends: Vec<WeightedBackend>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeightedBackend {
+    #[serde(deserialize_with = "parse")]
+    pub name: NameAddr,
+    #[serde(default = "WeightedBackend::default_weight")]
+    pub weight: u32,
+}
+
+/// Serves client policies for the parents described by a [`Fixture`].
+#[derive(Clone, Debug)]
+pub struct Policies(Arc<HashMap<Addr, policy::ClientPolicy>>);
+
+/// Serves no service profiles, so that stacks use client policies exclusively.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Profiles(());
+
+/// Resolves backends to the endpoints described by a [`Fixture`].
+#[derive(Clone, Debug)]
+pub struct Resolver(Arc<HashMap<NameAddr, Vec<SocketAddr>>>);
+
+/// Connects to the endpoints described by a [`Fixture`].
+///
+/// Each connection is served in-process by an HTTP/1 and HTTP/2 server that
+/// responds to every request with an empty `200 OK`.
+#[derive(Clone, Debug)]
+pub struct Endpoints(Arc<HashSet<SocketAddr>>);
+
+type Resolution = stream::BoxStream<'static, Result<Update<Metadata>, Error>>;
+
+static NO_HTTP_FILTERS: Lazy<Arc<[policy::http::Filter]>> = Lazy::new(|| Arc::new([]));
+static NO_OPAQ_FILTERS: Lazy<Arc<[policy::opaq::Filter]>> = Lazy::new(|| Arc::new([]));
+
+// === impl Fixture ===
+
+impl Fixture {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn policies(&self) -> Policies {
+        let mut policies = HashMap::with_capacity(self.parents.len() * 2);
+        for parent in &self.parents {
+            let policy = parent.client_policy();
+            policies.insert(Addr::Socket(parent.addr), policy.clone());
+            policies.insert(Addr::Name(parent.name.clone()), policy);
+        }
+        Policies(Arc::new(policies))
+    }
+
+    pub fn profiles(&self) -> Profiles {
+        Profiles(())
+    }
+
+    pub fn resolver(&self) -> Resolver {
+        Resolver(Arc::new(self.endpoints.clone()))
+    }
+
+    pub fn connector(&self) -> Endpoints {
+        Endpoints(Arc::new(
+            self.endpoints.values().flatten().copied().collect(),
+        ))
+    }
+
+    /// Builds a sidecar stack that discovers services and connects to
+    /// endpoints using this fixture.
+    pub fn mk_sidecar<I>(&self, outbound: &Outbound<()>) -> svc::ArcNewTcp<OrigDstAddr, I>
+    where
+        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr + io::Splice,
+        I: Debug + Unpin + Send + Sync + 'static,
+    {
+        outbound
+            .clone()
+            .with_stack(self.connector())
+            .push_sidecar(self.profiles(), self.policies(), self.resolver())
+            .into_inner()
+    }
+
+    /// Builds stacks for the outbound configuration's additional listeners,
+    /// as [`Outbound::mk_listeners`] does, using this fixture.
+    pub fn mk_listeners<I>(
+        &self,
+        outbound: &Outbound<()>,
+        primary: &svc::ArcNewTcp<OrigDstAddr, I>,
+    ) -> Vec<svc::ArcNewTcp<OrigDstAddr, I>>
+    where
+        I: io::AsyncRead + io::AsyncWrite + io::Peek + io::PeerAddr + io::Splice,
+        I: Debug + Unpin + Send + Sync + 'static,
+    {
+        outbound.build_listeners(primary, |outbound| self.mk_sidecar(&outbound))
+    }
+}
+
+// === impl Parent ===
+
+impl Parent {
+    /// Builds the client policy served for the parent. Each call allocates a
+    /// new policy, as if it had been decoded from a control plane update.
+    pub fn client_policy(&self) -> policy::ClientPolicy {
+        let meta = policy::Meta::new_default(self.name.to_string());
+
+        let mut backends = Vec::new();
+        let mut http_routes = Vec::with_capacity(self.routes.len());
+        for route in &self.routes {
+            let distribution = route.distribution(&mut backends, |backend| policy::RouteBackend {
+                filters: NO_HTTP_FILTERS.clone(),
+                backend,
+            });
+            let path = route
+                .path_prefix
+                .clone()
+                .map(policy::http::r#match::MatchPath::Prefix);
+            http_routes.push(policy::http::Route {
+                hosts: vec![],
+                rules: vec![policy::http::Rule {
+                    matches: vec![policy::http::r#match::MatchRequest {
+                        path,
+                        ..Default::default()
+                    }],
+                    policy: policy::http::Policy {
+                        meta: meta.clone(),
+                        filters: NO_HTTP_FILTERS.clone(),
+                        params: Default::default(),
+                        distribution,
+                    },
+                }],
+            });
+        }
+        let http_routes: Arc<[_]> = http_routes.into();
+
+        // Opaque traffic is only ever dispatched to the first route.
+        let opaque = policy::opaq::Opaque {
+            routes: self.routes.first().map(|route| policy::opaq::Route {
+                policy: policy::opaq::Policy {
+                    meta: meta.clone(),
+                    filters: NO_OPAQ_FILTERS.clone(),
+                    params: Default::default(),
+                    distribution: route.distribution(&mut backends, |backend| {
+                        policy::RouteBackend {
+                            filters: NO_OPAQ_FILTERS.clone(),
+                            backend,
+                        }
+                    }),
+                },
+            }),
+        };
+        let http1 = policy::http::Http1 {
+            routes: http_routes.clone(),
+            failure_accrual: Default::default(),
+        };
+        let http2 = policy::http::Http2 {
+            routes: http_routes,
+            failure_accrual: Default::default(),
+        };
+
+        let protocol = match self.protocol {
+            FixtureProtocol::Detect => policy::Protocol::Detect {
+                timeout: Duration::from_secs(10),
+                http1,
+                http2,
+                opaque,
+            },
+            FixtureProtocol::Http1 => policy::Protocol::Http1(http1),
+            FixtureProtocol::Http2 => policy::Protocol::Http2(http2),
+            FixtureProtocol::Opaque => policy::Protocol::Opaque(opaque),
+        };
+
+        policy::ClientPolicy {
+            parent: meta,
+            protocol,
+            backends: backends.into(),
+        }
+    }
+}
+
+// === impl Route ===
+
+impl Route {
+    fn distribution<F>(
+        &self,
+        backends: &mut Vec<policy::Backend>,
+        mk: impl Fn(policy::Backend) -> policy::RouteBackend<F>,
+    ) -> policy::RouteDistribution<F> {
+        let weighted = self
+            .backends
+            .iter()
+            .map(|WeightedBackend { name, weight }| {
+                let backend = mk_backend(name);
+                if !backends.contains(&backend) {
+                    backends.push(backend.clone());
+                }
+                (mk(backend), *weight)
+            })
+            .collect();
+        policy::RouteDistribution::RandomAvailable(weighted)
+    }
+}
+
+fn parse<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+    s.parse().map_err(serde::de::Error::custom)
+}
+
+fn parse_endpoints<'de, D>(deserializer: D) -> Result<HashMap<NameAddr, Vec<SocketAddr>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let endpoints =
+        <HashMap<String, Vec<SocketAddr>> as serde::Deserialize>::deserialize(deserializer)?;
+    endpoints
+        .into_iter()
+        .map(|(name, addrs)| Ok((name.parse().map_err(serde::de::Error::custom)?, addrs)))
+        .collect()
+}
+
+fn mk_backend(name: &NameAddr) -> policy::Backend {
+    policy::Backend {
+        meta: policy::Meta::new_default(name.to_string()),
+        queue: policy::Queue {
+            capacity: 10_000,
+            failfast_timeout: Duration::from_secs(3),
+        },
+        dispatcher: policy::BackendDispatcher::BalanceP2c(
+            policy::Load::PeakEwma(policy::PeakEwma {
+                decay: Duration::from_secs(10),
+                default_rtt: Duration::from_millis(30),
+            }),
+            policy::EndpointDiscovery::DestinationGet {
+                path: name.to_string(),
+            },
+        ),
+    }
+}
+
+// === impl WeightedBackend ===
+
+impl WeightedBackend {
+    fn default_weight() -> u32 {
+        1
+    }
+}
+
+// === impl Policies ===
+
+impl svc::Service<Addr> for Policies {
+    type Response = policy::Receiver;
+    type Error = Error;
+    type Future = future::Ready<Result<policy::Receiver, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, addr: Addr) -> Self::Future {
+        let Some(policy) = self.0.get(&addr) else {
+            return future::err(format!("no client policy for {addr}").into());
+        };
+
+        let (tx, rx) = watch::channel(policy.clone());
+        // Hold the sender until all receivers are dropped so that the policy
+        // appears to be served by a live watch.
+        tokio::spawn(async move { tx.closed().await });
+        future::ok(rx)
+    }
+}
+
+// === impl Profiles ===
+
+impl svc::Service<profiles::LookupAddr> for Profiles {
+    type Response = Option<profiles::Receiver>;
+    type Error = Error;
+    type Future = future::Ready<Result<Option<profiles::Receiver>, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: profiles::LookupAddr) -> Self::Future {
+        future::ok(None)
+    }
+}
+
+// === impl Resolver ===
+
+impl svc::Service<ConcreteAddr> for Resolver {
+    type Response = Resolution;
+    type Error = Error;
+    type Future = future::Ready<Result<Resolution, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, ConcreteAddr(addr): ConcreteAddr) -> Self::Future {
+        let update = match self.0.get(&addr) {
+            Some(addrs) => Update::Reset(
+                addrs
+                    .iter()
+                    .map(|addr| (*addr, Metadata::default()))
+                    .collect(),
+            ),
+            None => Update::DoesNotExist,
+        };
+        // Resolutions never complete, as with the destination controller.
+        let updates = stream::once(future::ok(update)).chain(stream::pending());
+        future::ok(Box::pin(updates))
+    }
+}
+
+// === impl Endpoints ===
+
+impl svc::Service<tcp::Connect> for Endpoints {
+    type Response = (io::DuplexStream, Local<ClientAddr>);
+    type Error = io::Error;
+    type Future = future::Ready<io::Result<Self::Response>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, connect: tcp::Connect) -> Self::Future {
+        let Remote(ServerAddr(addr)) = svc::Param::param(&connect);
+        if !self.0.contains(&addr) {
+            return future::err(io::ErrorKind::ConnectionRefused.into());
+        }
+
+        let (client, server) = io::duplex(8 * 1024);
+        tokio::spawn(serve(server));
+        let local = Local(ClientAddr(([127, 0, 0, 1], 0).into()));
+        future::ok((client, local))
+    }
+}
+
+async fn serve(io: io::DuplexStream) {
+    use hyper_util::{rt, server::conn::auto};
+
+    let svc = hyper::service::service_fn(|_: hyper::Request<hyper::body::Incoming>| {
+        future::ok::<_, std::convert::Infallible>(hyper::Response::new(http_body_util::Empty::<
+            bytes::Bytes,
+        >::new()))
+    });
+    let builder = auto::Builder::new(rt::TokioExecutor::new());
+    if let Err(error) = builder.serve_connection(rt::TokioIo::new(io), svc).await {
+        tracing::debug!(%error, "Endpoint connection failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_app_core::proxy::core::Resolve;
+    use svc::ServiceExt;
+
+    const FIXTURE: &str = r#"{
+        "parents": [{
+            "addr": "10.0.0.1:8080",
+            "name": "web.ns.svc.cluster.local:8080",
+            "routes": [
+                {
+                    "path_prefix": "/api",
+                    "backends": [
+                        { "name": "api-v1.ns.svc.cluster.local:8080", "weight": 9 },
+                        { "name": "api-v2.ns.svc.cluster.local:8080", "weight": 1 }
+                    ]
+                },
+                { "backends": [{ "name": "web.ns.svc.cluster.local:8080" }] }
+            ]
+        }],
+        "endpoints": {
+            "web.ns.svc.cluster.local:8080": ["10.1.0.1:8080"],
+            "api-v1.ns.svc.cluster.local:8080": ["10.1.0.2:8080"],
+            "api-v2.ns.svc.cluster.local:8080": ["10.1.0.3:8080"]
+        }
+    }"#;
+
+    #[tokio::test]
+    async fn policies_by_addr_and_name() {
+        let fixture = Fixture::from_json(FIXTURE).expect("fixture must parse");
+        let policies = fixture.policies();
+
+        let by_addr = policies
+            .clone()
+            .oneshot(Addr::Socket(([10, 0, 0, 1], 8080).into()))
+            .await
+            .expect("policy must be found by address");
+        let by_name = policies
+            .clone()
+            .oneshot("web.ns.svc.cluster.local:8080".parse::<Addr>().unwrap())
+            .await
+            .expect("policy must be found by name");
+        assert_eq!(*by_addr.borrow(), *by_name.borrow());
+
+        let policy = by_addr.borrow().clone();
+        assert_eq!(policy.backends.len(), 3, "backends are deduplicated");
+        let policy::Protocol::Detect { http1, opaque, .. } = policy.protocol else {
+            panic!("protocol must default to detect: {:?}", policy.protocol);
+        };
+        assert_eq!(http1.routes.len(), 2);
+        let policy::RouteDistribution::RandomAvailable(ref backends) =
+            http1.routes[0].rules[0].policy.distribution
+        else {
+            panic!("routes must be weighted");
+        };
+        assert_eq!(backends.iter().map(|(_, w)| *w).collect::<Vec<_>>(), [9, 1]);
+        assert!(opaque.routes.is_some());
+
+        policies
+            .oneshot(Addr::Socket(([10, 0, 0, 2], 8080).into()))
+            .await
+            .expect_err("unknown parents must not be discovered");
+    }
+
+    #[tokio::test]
+    async fn resolves_endpoints() {
+        let fixture = Fixture::from_json(FIXTURE).expect("fixture must parse");
+        let resolver = fixture.resolver();
+
+        let name = "api-v2.ns.svc.cluster.local:8080".parse().unwrap();
+        let mut updates = resolver.resolve(ConcreteAddr(name)).await.unwrap();
+        match updates.next().await {
+            Some(Ok(Update::Reset(eps))) => {
+                assert_eq!(eps, [(([10, 1, 0, 3], 8080).into(), Metadata::default())]);
+            }
+            update => panic!("unexpected update: {update:?}"),
+        }
+
+        let name = "unknown.ns.svc.cluster.local:8080".parse().unwrap();
+        let mut updates = resolver.resolve(ConcreteAddr(name)).await.unwrap();
+        assert!(matches!(
+            updates.next().await,
+            Some(Ok(Update::DoesNotExist))
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        Fixture::from_json(r#"{ "parents": [], "services": [] }"#)
+            .expect_err("unknown fields must be rejected");
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{tcp, Outbound};
+use crate::{route_updates, tcp, Outbound, RouteUpdates};
 use linkerd_app_core::{
     io,
     metrics::prom,
@@ -7,7 +7,7 @@ use linkerd_app_core::{
         core::Resolve,
     },
     svc,
-    tls::{NewDetectRequiredSni, ServerName},
+    tls::ServerName,
     transport::addrs::*,
     Error,
 };
@@ -16,8 +16,13 @@ use tokio::sync::watch;
 
 mod concrete;
 mod logical;
+mod plaintext;
+mod sni;
 
-pub use self::logical::{route::filters::errors::*, Concrete, Routes};
+pub use self::{
+    logical::{route::filters::errors::*, Concrete, Routes},
+    plaintext::PlaintextHttpError,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct Tls<T> {
@@ -25,46 +30,26 @@ struct Tls<T> {
     parent: T,
 }
 
+/// Spawns a task that publishes the routes computed by `mk` from each update
+/// on `route_rx`.
 pub fn spawn_routes<T>(
-    mut route_rx: watch::Receiver<T>,
+    updates: &RouteUpdates,
+    route_rx: watch::Receiver<T>,
     init: Routes,
-    mut mk: impl FnMut(&T) -> Option<Routes> + Send + Sync + 'static,
+    mk: impl FnMut(&T) -> Option<Routes> + Send + Sync + 'static,
 ) -> watch::Receiver<Routes>
 where
     T: Send + Sync + 'static,
 {
-    let (tx, rx) = watch::channel(init);
-
-    tokio::spawn(async move {
-        loop {
-            let res = tokio::select! {
-                biased;
-                _ = tx.closed() => return,
-                res = route_rx.changed() => res,
-            };
-
-            if res.is_err() {
-                // Drop the `tx` sender when the profile sender is
-                // dropped.
-                return;
-            }
-
-            if let Some(routes) = (mk)(&*route_rx.borrow_and_update()) {
-                if tx.send(routes).is_err() {
-                    // Drop the `tx` sender when all of its receivers are dropped.
-                    return;
-                }
-            }
-        }
-    });
-
-    rx
+    updates.spawn(route_updates::Protocol::tls, route_rx, init, mk)
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct TlsMetrics {
     balance: concrete::BalancerMetrics,
     route: logical::route::TlsRouteMetrics,
+    plaintext_http: prom::Counter,
+    sni: sni::SniMetrics,
 }
 
 // === impl Outbound ===
@@ -80,7 +65,7 @@ impl<C> Outbound<C> {
         T: Clone + Debug + PartialEq + Eq + Hash + Send + Sync + 'static,
         T: svc::Param<watch::Receiver<Routes>>,
         // Server-side connection
-        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + io::Peek,
+        I: io::AsyncRead + io::AsyncWrite + io::PeerAddr + io::Peek + io::Splice,
         I: Debug + Send + Sync + Unpin + 'static,
         // Endpoint discovery
         R: Resolve<ConcreteAddr, Endpoint = Metadata, Error = Error>,
@@ -88,21 +73,34 @@ impl<C> Outbound<C> {
         // TCP endpoint stack.
         C: svc::MakeConnection<tcp::Connect, Metadata = Local<ClientAddr>, Error = io::Error>,
         C: Clone + Send + Sync + Unpin + 'static,
-        C::Connection: Send + Unpin,
+        C::Connection: io::Splice + Send + Unpin,
         C::Future: Send + Unpin,
     {
         self.push_tcp_endpoint()
             .push_tls_concrete(resolve)
             .push_tls_logical()
-            .map_stack(|config, _rt, stk| {
-                stk.push_new_idle_cached(config.discovery_idle_timeout)
-                    // Use a dedicated target type to configure parameters for
-                    // the TLS stack. It also helps narrow the cache key.
-                    .push_map_target(|(sni, parent): (ServerName, T)| Tls { sni, parent })
-                    .push(NewDetectRequiredSni::layer(
-                        config.proxy.detect_protocol_timeout,
-                    ))
-                    .arc_new_clone_tcp()
+            .map_stack(|config, rt, stk| {
+                stk.push_new_idle_cached_with_builds(
+                    config.discovery_idle_timeout,
+                    rt.metrics.prom.stack_builds.in_progress("tls"),
+                )
+                // Use a dedicated target type to configure parameters for
+                // the TLS stack. It also helps narrow the cache key.
+                .push_map_target(|(sni, parent): (ServerName, T)| Tls { sni, parent })
+                // Detect the SNI, closing connections whose SNI is not
+                // allowed by policy before any backend is contacted.
+                .push(sni::NewEnforceSni::layer(
+                    config.proxy.detect_protocol_timeout,
+                    rt.metrics.prom.tls.sni.clone(),
+                ))
+                // Fail fast, rather than waiting for a ClientHello, when
+                // the client speaks plaintext HTTP.
+                .push(plaintext::NewRejectPlaintextHttp::layer(
+                    config.proxy.detect_protocol_timeout,
+                    config.tls_plaintext_http_response,
+                    rt.metrics.prom.tls.plaintext_http.clone(),
+                ))
+                .arc_new_clone_tcp()
             })
     }
 }
@@ -132,6 +130,18 @@ impl TlsMetrics {
             concrete::BalancerMetrics::register(registry.sub_registry_with_prefix("balancer"));
         let route =
             logical::route::TlsRouteMetrics::register(registry.sub_registry_with_prefix("route"));
-        Self { balance, route }
+        let plaintext_http = prom::Counter::default();
+        registry.register(
+            "plaintext_http_connections",
+            "The number of connections to TLS destinations on which the client sent a plaintext HTTP request",
+            plaintext_http.clone(),
+        );
+        let sni = sni::SniMetrics::register(registry);
+        Self {
+            balance,
+            route,
+            plaintext_http,
+            sni,
+        }
     }
 }
@@ -1,5 +1,5 @@
 use super::concrete;
-use crate::{BackendRef, Outbound, ParentRef};
+use crate::{BackendRef, Outbound, ParentRef, RoutesFingerprint};
 use linkerd_app_core::{io, svc, tls::ServerName, Addr, Error};
 use linkerd_proxy_client_policy as client_policy;
 use std::{fmt::Debug, hash::Hash, sync::Arc};
@@ -15,12 +15,13 @@ mod tests;
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct LogicalAddr(pub Addr);
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Routes {
     pub addr: Addr,
     pub meta: ParentRef,
     pub routes: Arc<[client_policy::tls::Route]>,
     pub backends: Arc<[client_policy::Backend]>,
+    pub sni: Option<client_policy::tls::SniEnforcement>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -86,6 +87,14 @@ impl<N> Outbound<N> {
     }
 }
 
+// === impl Routes ===
+
+impl svc::Param<RoutesFingerprint> for Routes {
+    fn param(&self) -> RoutesFingerprint {
+        RoutesFingerprint::of(self)
+    }
+}
+
 // === impl LogicalError ===
 
 impl<T> From<(&router::Router<T>, Error)> for LogicalError
@@ -112,3 +121,15 @@ where
         self.parent.param()
     }
 }
+
+impl<T> svc::Param<ParentRef> for Concrete<T> {
+    fn param(&self) -> ParentRef {
+        self.parent_ref.clone()
+    }
+}
+
+impl<T> svc::Param<BackendRef> for Concrete<T> {
+    fn param(&self) -> BackendRef {
+        self.backend_ref.clone()
+    }
+}
@@ -64,6 +64,7 @@ where
             meta: parent_ref,
             routes,
             backends,
+            sni: _,
         } = rts;
 
         let mk_concrete = {
@@ -62,6 +62,7 @@ async fn routes() {
         backends: Arc::new([correct_backend, wrong_backend]),
         routes: Arc::new([correct_route, wrong_route_1, wrong_route_2]),
         meta: ParentRef(client_policy::Meta::new_default("parent")),
+        sni: None,
     });
 
     let target = Target { num: 1, routes };
@@ -71,3 +72,62 @@ async fn routes() {
     let msg = rsp.await.unwrap().unwrap();
     assert_eq!(msg, AUTHORITY);
 }
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn rejects_disallowed_sni() {
+    let _trace = trace::test::trace_init();
+
+    const AUTHORITY: &str = "logical.test.svc.cluster.local";
+    const PORT: u16 = 666;
+    let addr = SocketAddr::new([192, 0, 2, 41].into(), PORT);
+    let dest: NameAddr = format!("{AUTHORITY}:{PORT}")
+        .parse::<NameAddr>()
+        .expect("dest addr is valid");
+    let resolve = support::resolver().endpoint_exists(dest.clone(), addr, Default::default());
+    let (rt, _shutdown) = runtime();
+    let metrics = crate::tls::sni::SniMetrics::default();
+
+    // No servers are added, so connecting to a backend fails the test.
+    let stack = Outbound::new(default_config(), rt, &mut Default::default())
+        .with_stack(ConnectTcp::default())
+        .push_tls_concrete(resolve)
+        .push_tls_logical()
+        .map_stack(|config, _rt, stk| {
+            stk.push_new_idle_cached(config.discovery_idle_timeout)
+                .push_map_target(|(sni, parent): (ServerName, _)| Tls { sni, parent })
+                .push(crate::tls::sni::NewEnforceSni::layer(
+                    Duration::from_secs(1),
+                    metrics.clone(),
+                ))
+                .arc_new_clone_tcp()
+        })
+        .into_inner();
+
+    let backend = default_backend(addr);
+    let route = sni_route(
+        backend.clone(),
+        sni::MatchSni::from_str("*.test.svc.cluster.local").unwrap(),
+    );
+    let parent_ref = ParentRef(client_policy::Meta::new_default("parent"));
+    let (_route_tx, routes) = watch::channel(Routes {
+        addr: addr.into(),
+        backends: Arc::new([backend]),
+        routes: Arc::new([route]),
+        meta: parent_ref.clone(),
+        sni: Some(client_policy::tls::SniEnforcement {
+            allowed: Arc::new([sni::MatchSni::Exact("other.test.svc.cluster.local".into())]),
+            allow_missing: false,
+        }),
+    });
+
+    let (io, rsp) = spawn_io(generate_client_hello(AUTHORITY));
+    let svc = stack.new_service(Target { num: 1, routes });
+    svc.oneshot(io)
+        .await
+        .expect("rejections close connections cleanly");
+
+    let rsp = rsp.await.unwrap();
+    assert!(rsp.map(|rsp| rsp.is_empty()).unwrap_or(true));
+    let sni = ServerName::from_str(AUTHORITY).unwrap();
+    assert_eq!(metrics.rejected(parent_ref, Some(&sni)), 1);
+}
@@ -0,0 +1,251 @@
+//! Rejects plaintext HTTP requests sent to destinations that expect TLS.
+//!
+//! When policy indicates that a destination speaks TLS, the proxy waits for a
+//! ClientHello so that the connection may be routed by its SNI. A client that
+//! instead sends a plaintext HTTP request (e.g. to port 443) would otherwise
+//! see its connection hang until SNI detection times out. Instead, the first
+//! bytes of each connection are inspected: if they are an HTTP/1 request line,
+//! the connection is counted and closed, optionally after responding with an
+//! HTTP 400 that describes the mismatch.
+
+use linkerd_app_core::{
+    io::{self, AsyncWriteExt},
+    metrics::prom,
+    svc::{self, ServiceExt},
+    tls::SniDetectionTimeoutError,
+    Error, Result,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::time;
+use tracing::{debug, info};
+
+/// Large enough to hold a typical request line.
+const PEEK_CAPACITY: usize = 512;
+
+const RESPONSE_BODY: &str =
+    "The destination expects TLS, but the client sent a plaintext HTTP request.\n";
+
+#[derive(Clone, Debug)]
+pub(crate) struct NewRejectPlaintextHttp<N> {
+    params: Params,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RejectPlaintextHttp<T, N> {
+    params: Params,
+    target: T,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+struct Params {
+    timeout: time::Duration,
+    respond: bool,
+    rejected: prom::Counter,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("client sent a plaintext HTTP request to a TLS destination")]
+pub struct PlaintextHttpError(());
+
+// === impl NewRejectPlaintextHttp ===
+
+impl<N> NewRejectPlaintextHttp<N> {
+    /// Returns a layer that rejects plaintext HTTP requests, responding with
+    /// an HTTP 400 if `respond` is set.
+    ///
+    /// The `timeout` bounds how long the proxy waits for the client to send
+    /// data and should match the SNI detection timeout.
+    pub(crate) fn layer(
+        timeout: time::Duration,
+        respond: bool,
+        rejected: prom::Counter,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let params = Params {
+            timeout,
+            respond,
+            rejected,
+        };
+        svc::layer::mk(move |inner| Self {
+            params: params.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N: Clone> svc::NewService<T> for NewRejectPlaintextHttp<N> {
+    type Service = RejectPlaintextHttp<T, N>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        RejectPlaintextHttp {
+            params: self.params.clone(),
+            target,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// === impl RejectPlaintextHttp ===
+
+impl<T, I, N, S> svc::Service<I> for RejectPlaintextHttp<T, N>
+where
+    T: Clone + Send + 'static,
+    I: io::AsyncRead + io::AsyncWrite + io::Peek + Send + Sync + Unpin + 'static,
+    N: svc::NewService<T, Service = S> + Clone + Send + 'static,
+    S: svc::Service<I, Response = ()> + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = ();
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut io: I) -> Self::Future {
+        let Params {
+            timeout,
+            respond,
+            rejected,
+        } = self.params.clone();
+        let target = self.target.clone();
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            let mut buf = [0u8; PEEK_CAPACITY];
+            let sz = time::timeout(timeout, io.peek(&mut buf))
+                .await
+                .map_err(|_| SniDetectionTimeoutError)??;
+            if !is_http_request(&buf[..sz]) {
+                let svc = inner.new_service(target);
+                return svc.oneshot(io).await.map_err(Into::into);
+            }
+
+            info!("Client sent a plaintext HTTP request to a TLS destination");
+            rejected.inc();
+            if respond {
+                let rsp = format!(
+                    "HTTP/1.1 400 Bad Request\r\n\
+                     content-type: text/plain\r\n\
+                     content-length: {}\r\n\
+                     connection: close\r\n\
+                     \r\n\
+                     {RESPONSE_BODY}",
+                    RESPONSE_BODY.len(),
+                );
+                if let Err(error) = io.write_all(rsp.as_bytes()).await {
+                    debug!(%error, "Failed to write response");
+                }
+                let _ = io.shutdown().await;
+            }
+            Err(PlaintextHttpError(()).into())
+        })
+    }
+}
+
+/// Returns true if `buf` begins with an HTTP/1 request line.
+///
+/// A TLS record begins with a content type byte (0x14-0x18), which is never
+/// a valid first byte of an HTTP method, so TLS connections are never
+/// misidentified.
+fn is_http_request(buf: &[u8]) -> bool {
+    if !buf.first().is_some_and(u8::is_ascii_uppercase) {
+        return false;
+    }
+    let Some(end) = buf.windows(2).position(|w| w == b"\r\n") else {
+        return false;
+    };
+    let line = &buf[..end + 2];
+    matches!(
+        httparse::Request::new(&mut [httparse::EMPTY_HEADER; 0]).parse(line),
+        Ok(_) | Err(httparse::Error::TooManyHeaders)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use io::AsyncReadExt;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+    use svc::{Layer, NewService};
+
+    /// The first bytes of a TLS 1.2 ClientHello.
+    const CLIENT_HELLO: &[u8] = b"\x16\x03\x01\x02\x00\x01\x00\x01\xfc\x03\x03";
+
+    #[test]
+    fn detects_request_lines() {
+        assert!(is_http_request(b"GET / HTTP/1.1\r\n"));
+        assert!(is_http_request(
+            b"POST /api HTTP/1.1\r\nhost: example.com\r\n\r\n"
+        ));
+        assert!(is_http_request(b"CONNECT example.com:443 HTTP/1.1\r\n"));
+
+        assert!(!is_http_request(CLIENT_HELLO));
+        assert!(!is_http_request(b""));
+        // Incomplete request lines are not rejected.
+        assert!(!is_http_request(b"GET / HT"));
+        assert!(!is_http_request(b"HELLO\r\n"));
+        assert!(!is_http_request(b"get / HTTP/1.1\r\n"));
+    }
+
+    async fn serve(prefix: &'static [u8], respond: bool) -> (Result<()>, bool, Vec<u8>, u64) {
+        let rejected = prom::Counter::default();
+        let forwarded = Arc::new(AtomicBool::new(false));
+        let new_svc =
+            NewRejectPlaintextHttp::layer(time::Duration::from_secs(1), respond, rejected.clone())
+                .layer({
+                    let forwarded = forwarded.clone();
+                    move |()| {
+                        let forwarded = forwarded.clone();
+                        svc::mk(move |_: io::PrefixedIo<io::DuplexStream>| {
+                            forwarded.store(true, Ordering::Release);
+                            futures::future::ok::<(), Error>(())
+                        })
+                    }
+                });
+
+        let (server, mut client) = io::duplex(1024);
+        let io = io::PrefixedIo::new(Bytes::from_static(prefix), server);
+        let res = new_svc.new_service(()).oneshot(io).await;
+
+        let mut rsp = Vec::new();
+        client.read_to_end(&mut rsp).await.unwrap();
+        (res, forwarded.load(Ordering::Acquire), rsp, rejected.get())
+    }
+
+    #[tokio::test]
+    async fn forwards_tls() {
+        let (res, forwarded, rsp, rejected) = serve(CLIENT_HELLO, true).await;
+        assert!(res.is_ok());
+        assert!(forwarded);
+        assert!(rsp.is_empty());
+        assert_eq!(rejected, 0);
+    }
+
+    #[tokio::test]
+    async fn rejects_plaintext_http() {
+        let (res, forwarded, rsp, rejected) = serve(b"GET / HTTP/1.1\r\n\r\n", true).await;
+        assert!(res.unwrap_err().is::<PlaintextHttpError>());
+        assert!(!forwarded);
+        assert!(rsp.starts_with(b"HTTP/1.1 400 Bad Request\r\n"));
+        assert!(rsp.ends_with(RESPONSE_BODY.as_bytes()));
+        assert_eq!(rejected, 1);
+
+        let (res, forwarded, rsp, rejected) = serve(b"GET / HTTP/1.1\r\n\r\n", false).await;
+        assert!(res.is_err());
+        assert!(!forwarded);
+        assert!(rsp.is_empty(), "no response must be written when disabled");
+        assert_eq!(rejected, 1);
+    }
+}
@@ -0,0 +1,280 @@
+//! Enforces the SNIs that clients may send to TLS destinations.
+//!
+//! Policy may restrict the SNIs that clients send to a parent. Each
+//! connection's ClientHello is inspected and, if its SNI is not allowed, the
+//! connection is counted and closed before any backend is contacted. Because
+//! SNIs are chosen by clients, rejections are labeled by a hash of the SNI
+//! that falls into a fixed number of buckets; the SNI itself is logged.
+
+use super::Routes;
+use crate::ParentRef;
+use linkerd_app_core::{
+    io::{self, AsyncWriteExt},
+    metrics::prom,
+    svc::{self, ServiceExt},
+    tls::{
+        server::{detect_sni, DetectIo},
+        NoSniFoundError, ServerName, SniDetectionTimeoutError,
+    },
+    Error, Result,
+};
+use linkerd_proxy_client_policy::tls::SniEnforcement;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::{sync::watch, time};
+use tracing::{debug, info};
+
+/// The number of buckets into which rejected SNIs are hashed.
+const SNI_BUCKETS: u32 = 256;
+
+/// Detects the SNI of each connection, rejecting connections whose SNI is not
+/// allowed by the parent's policy.
+///
+/// Connections without an SNI cannot be routed. Unless policy denies them,
+/// they fail with a [`NoSniFoundError`], as when SNI is not enforced.
+#[derive(Clone, Debug)]
+pub(crate) struct NewEnforceSni<N> {
+    timeout: time::Duration,
+    metrics: SniMetrics,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct EnforceSni<T, N> {
+    timeout: time::Duration,
+    metrics: SniMetrics,
+    target: T,
+    routes: watch::Receiver<Routes>,
+    inner: N,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SniMetrics {
+    rejected: prom::Family<RejectLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct RejectLabels {
+    parent_ref: ParentRef,
+    /// The bucket of the rejected SNI, or `None` if the client sent no SNI.
+    sni_bucket: Option<u32>,
+}
+
+// === impl NewEnforceSni ===
+
+impl<N> NewEnforceSni<N> {
+    /// Returns a layer that detects each connection's SNI and enforces the
+    /// parent's policy.
+    ///
+    /// The `timeout` bounds how long the proxy waits for a ClientHello.
+    pub(crate) fn layer(
+        timeout: time::Duration,
+        metrics: SniMetrics,
+    ) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| Self {
+            timeout,
+            metrics: metrics.clone(),
+            inner,
+        })
+    }
+}
+
+impl<T, N: Clone> svc::NewService<T> for NewEnforceSni<N>
+where
+    T: svc::Param<watch::Receiver<Routes>>,
+{
+    type Service = EnforceSni<T, N>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        EnforceSni {
+            timeout: self.timeout,
+            metrics: self.metrics.clone(),
+            routes: target.param(),
+            target,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// === impl EnforceSni ===
+
+impl<T, I, N, S> svc::Service<I> for EnforceSni<T, N>
+where
+    T: Clone + Send + Sync + 'static,
+    I: io::AsyncRead + io::AsyncWrite + io::Peek + Send + Sync + Unpin + 'static,
+    N: svc::NewService<(ServerName, T), Service = S> + Clone + Send + 'static,
+    S: svc::Service<DetectIo<I>, Response = ()> + Send,
+    S::Error: Into<Error>,
+    S::Future: Send,
+{
+    type Response = ();
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, io: I) -> Self::Future {
+        let target = self.target.clone();
+        let metrics = self.metrics.clone();
+        let routes = self.routes.clone();
+        let inner = self.inner.clone();
+
+        // Detect the SNI from a ClientHello (or timeout).
+        let detect = time::timeout(self.timeout, detect_sni(io));
+        Box::pin(async move {
+            let (sni, mut io) = detect.await.map_err(|_| SniDetectionTimeoutError)??;
+
+            // Read the policy once the ClientHello has been received so that
+            // the most recent policy is enforced.
+            let (enforcement, parent_ref) = {
+                let routes = routes.borrow();
+                (routes.sni.clone(), routes.meta.clone())
+            };
+            if let Some(enforcement) = enforcement {
+                if !is_allowed(&enforcement, sni.as_ref()) {
+                    info!(?sni, "Rejecting connection with a disallowed SNI");
+                    metrics.reject(parent_ref, sni.as_ref());
+                    let _ = io.shutdown().await;
+                    return Ok(());
+                }
+            }
+
+            let sni = sni.ok_or(NoSniFoundError)?;
+            debug!(?sni, "Detected TLS");
+            let svc = inner.new_service((sni, target));
+            svc.oneshot(io).await.map_err(Into::into)
+        })
+    }
+}
+
+fn is_allowed(enforcement: &SniEnforcement, sni: Option<&ServerName>) -> bool {
+    match sni {
+        Some(sni) => enforcement
+            .allowed
+            .iter()
+            .any(|m| m.summarize_match(sni).is_some()),
+        None => enforcement.allow_missing,
+    }
+}
+
+/// Hashes an SNI (with FNV-1a, so that buckets are stable across restarts)
+/// into one of a fixed number of buckets.
+fn sni_bucket(sni: &ServerName) -> u32 {
+    let hash = sni.as_str().bytes().fold(0x811c9dc5_u32, |h, b| {
+        (h ^ u32::from(b)).wrapping_mul(0x01000193)
+    });
+    hash % SNI_BUCKETS
+}
+
+// === impl SniMetrics ===
+
+impl SniMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let rejected = prom::Family::default();
+        registry.register(
+            "sni_rejected_connections",
+            "The number of TLS connections closed because policy does not allow their SNI, by parent and SNI bucket",
+            rejected.clone(),
+        );
+        Self { rejected }
+    }
+
+    fn reject(&self, parent_ref: ParentRef, sni: Option<&ServerName>) {
+        self.rejected
+            .get_or_create(&RejectLabels {
+                parent_ref,
+                sni_bucket: sni.map(sni_bucket),
+            })
+            .inc();
+    }
+
+    #[cfg(test)]
+    pub(crate) fn rejected(&self, parent_ref: ParentRef, sni: Option<&ServerName>) -> u64 {
+        self.rejected
+            .get_or_create(&RejectLabels {
+                parent_ref,
+                sni_bucket: sni.map(sni_bucket),
+            })
+            .get()
+    }
+}
+
+// === impl RejectLabels ===
+
+impl prom::EncodeLabelSetMut for RejectLabels {
+    fn encode_label_set(&self, enc: &mut prom::encoding::LabelSetEncoder<'_>) -> std::fmt::Result {
+        use prom::encoding::EncodeLabel;
+
+        self.parent_ref.encode_label_set(enc)?;
+        match self.sni_bucket {
+            Some(bucket) => ("sni_bucket", format!("{bucket:02x}")).encode(enc.encode_label())?,
+            None => ("sni_bucket", "none").encode(enc.encode_label())?,
+        }
+
+        Ok(())
+    }
+}
+
+impl prom::encoding::EncodeLabelSet for RejectLabels {
+    fn encode(&self, mut enc: prom::encoding::LabelSetEncoder<'_>) -> Result<(), std::fmt::Error> {
+        self.encode_label_set(&mut enc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_proxy_client_policy::tls::sni::MatchSni;
+    use std::str::FromStr;
+
+    fn enforcement(allowed: &[&str], allow_missing: bool) -> SniEnforcement {
+        SniEnforcement {
+            allowed: allowed
+                .iter()
+                .map(|s| MatchSni::from_str(s).unwrap())
+                .collect(),
+            allow_missing,
+        }
+    }
+
+    fn sni(s: &str) -> ServerName {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn allows_matching_snis() {
+        let enforcement = enforcement(&["api.example.com", "*.internal.example.com"], false);
+        assert!(is_allowed(&enforcement, Some(&sni("api.example.com"))));
+        assert!(is_allowed(&enforcement, Some(&sni("api.example.com."))));
+        assert!(is_allowed(
+            &enforcement,
+            Some(&sni("a.internal.example.com"))
+        ));
+
+        assert!(!is_allowed(&enforcement, Some(&sni("www.example.com"))));
+        assert!(!is_allowed(
+            &enforcement,
+            Some(&sni("internal.example.com"))
+        ));
+        assert!(!is_allowed(&enforcement, None));
+
+        let nothing = self::enforcement(&[], true);
+        assert!(!is_allowed(&nothing, Some(&sni("api.example.com"))));
+        assert!(is_allowed(&nothing, None));
+    }
+
+    #[test]
+    fn buckets_are_bounded_and_stable() {
+        let a = sni_bucket(&sni("a.example.com"));
+        assert_eq!(a, sni_bucket(&sni("a.example.com")));
+        for i in 0..1000 {
+            assert!(sni_bucket(&sni(&format!("host-{i}.example.com"))) < SNI_BUCKETS);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Filters each balancer's endpoints by their topology hints.
+//!
+//! When the proxy is configured with its local zone, endpoints that are hinted
+//! only for other zones are excluded from the balancer so that traffic remains
+//! in the local zone. Endpoints without hints are always included. If fewer
+//! than the configured minimum number of endpoints would remain, the hints are
+//! ignored and all endpoints are balanced.
+
+use crate::{metrics::ConcreteLabels, BackendRef, ParentRef};
+use futures::{prelude::*, ready, stream::BoxStream};
+use linkerd_app_core::{
+    metrics::prom,
+    proxy::{api_resolve::Metadata, core::Update},
+    svc, Error,
+};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tracing::debug;
+
+#[cfg(test)]
+mod tests;
+
+/// Configures how endpoints are filtered by their topology hints.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopologyHintsConfig {
+    /// The zone in which the proxy runs.
+    pub zone: String,
+
+    /// The minimum number of endpoints that must be hinted for the local zone
+    /// for the hints to be honored.
+    pub min_endpoints: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TopologyHintMetrics {
+    filtered: prom::Family<ConcreteLabels, prom::Gauge>,
+}
+
+/// Wraps a resolution service so that the endpoints of each resolution are
+/// filtered by their topology hints.
+#[derive(Clone, Debug)]
+pub(crate) struct HintedResolve<S> {
+    config: Option<Arc<TopologyHintsConfig>>,
+    metrics: TopologyHintMetrics,
+    inner: S,
+}
+
+/// A resolution that replaces its endpoints with those hinted for the local
+/// zone on each update.
+pub(crate) struct HintedResolution {
+    filter: Option<Filter>,
+    inner: BoxStream<'static, Result<Update<Metadata>, Error>>,
+}
+
+struct Filter {
+    config: Arc<TopologyHintsConfig>,
+    endpoints: HashMap<SocketAddr, Metadata>,
+    filtered: prom::Gauge,
+}
+
+// === impl TopologyHintMetrics ===
+
+impl TopologyHintMetrics {
+    pub(crate) fn register(registry: &mut prom::Registry) -> Self {
+        let filtered = prom::Family::default();
+        registry.register(
+            "filtered_endpoints",
+            "The number of a balancer's endpoints that are excluded by topology hints",
+            filtered.clone(),
+        );
+        Self { filtered }
+    }
+}
+
+// === impl HintedResolve ===
+
+impl<S> HintedResolve<S> {
+    pub(crate) fn new(
+        config: Option<TopologyHintsConfig>,
+        metrics: TopologyHintMetrics,
+        inner: S,
+    ) -> Self {
+        Self {
+            config: config.map(Arc::new),
+            metrics,
+            inner,
+        }
+    }
+}
+
+impl<T, S, R> svc::Service<T> for HintedResolve<S>
+where
+    T: svc::Param<ParentRef> + svc::Param<BackendRef>,
+    S: svc::Service<T, Response = R, Error = Error>,
+    S::Future: Send + 'static,
+    R: TryStream<Ok = Update<Metadata>, Error = Error> + Send + 'static,
+{
+    type Response = HintedResolution;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<HintedResolution, Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        let filter = self.config.clone().map(|config| {
+            let labels = ConcreteLabels(target.param(), target.param());
+            Filter {
+                config,
+                endpoints: HashMap::new(),
+                filtered: self.metrics.filtered.get_or_create(&labels).clone(),
+            }
+        });
+        let resolution = self.inner.call(target);
+        Box::pin(async move {
+            let inner = resolution.await?.into_stream().boxed();
+            Ok(HintedResolution { filter, inner })
+        })
+    }
+}
+
+// === impl HintedResolution ===
+
+impl Stream for HintedResolution {
+    type Item = Result<Update<Metadata>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let update = ready!(this.inner.poll_next_unpin(cx));
+        match this.filter.as_mut() {
+            Some(filter) => Poll::Ready(update.map(|res| res.map(|up| filter.update(up)))),
+            None => Poll::Ready(update),
+        }
+    }
+}
+
+// === impl Filter ===
+
+impl Filter {
+    /// Applies an update to the resolution's endpoints and returns an update
+    /// that resets the balancer to the endpoints that should be used.
+    fn update(&mut self, update: Update<Metadata>) -> Update<Metadata> {
+        match update {
+            Update::Reset(eps) => self.endpoints = eps.into_iter().collect(),
+            Update::Add(eps) => self.endpoints.extend(eps),
+            Update::Remove(addrs) => {
+                for addr in addrs {
+                    self.endpoints.remove(&addr);
+                }
+            }
+            Update::DoesNotExist => {
+                self.endpoints.clear();
+                self.filtered.set(0);
+                return Update::DoesNotExist;
+            }
+            Update::Unavailable => return Update::Unavailable,
+        }
+
+        let zone = &self.config.zone;
+        let hinted = self
+            .endpoints
+            .iter()
+            .filter(|(_, meta)| {
+                meta.zone_hints()
+                    .is_none_or(|zones| zones.iter().any(|z| z == zone))
+            })
+            .map(|(addr, meta)| (*addr, meta.clone()))
+            .collect::<Vec<_>>();
+
+        if hinted.len() < self.config.min_endpoints.max(1) {
+            if hinted.len() < self.endpoints.len() {
+                debug!(
+                    hinted = hinted.len(),
+                    endpoints = self.endpoints.len(),
+                    "Too few endpoints are hinted for the local zone; ignoring hints"
+                );
+            }
+            self.filtered.set(0);
+            return Update::Reset(
+                self.endpoints
+                    .iter()
+                    .map(|(addr, meta)| (*addr, meta.clone()))
+                    .collect(),
+            );
+        }
+
+        let filtered = self.endpoints.len() - hinted.len();
+        self.filtered.set(filtered as i64);
+        Update::Reset(hinted)
+    }
+}
+
+impl Drop for Filter {
+    fn drop(&mut self) {
+        self.filtered.set(0);
+    }
+}
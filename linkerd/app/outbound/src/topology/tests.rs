@@ -0,0 +1,178 @@
+use super::*;
+use linkerd2_proxy_api::destination;
+use linkerd_app_core::{proxy::api_resolve::pb, svc::ServiceExt};
+use linkerd_proxy_client_policy::Meta;
+use tokio::sync::mpsc;
+
+#[derive(Clone, Debug)]
+struct Target;
+
+impl svc::Param<ParentRef> for Target {
+    fn param(&self) -> ParentRef {
+        ParentRef(Meta::new_default("parent"))
+    }
+}
+
+impl svc::Param<BackendRef> for Target {
+    fn param(&self) -> BackendRef {
+        BackendRef(Meta::new_default("backend"))
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn filters_endpoints_hinted_for_other_zones() {
+    let (metrics, tx, mut resolution) = resolve(config(1)).await;
+
+    tx.send(Update::Reset(vec![
+        endpoint(1, Some("zone-a")),
+        endpoint(2, Some("zone-b")),
+        endpoint(3, Some("zone-b,zone-a")),
+        endpoint(4, None),
+    ]))
+    .unwrap();
+    assert_eq!(next(&mut resolution).await, vec![1, 3, 4]);
+    assert_eq!(filtered(&metrics), 1);
+
+    tx.send(Update::Add(vec![endpoint(5, Some("zone-c"))]))
+        .unwrap();
+    assert_eq!(next(&mut resolution).await, vec![1, 3, 4]);
+    assert_eq!(filtered(&metrics), 2);
+
+    tx.send(Update::Remove(vec![addr(2), addr(5)])).unwrap();
+    assert_eq!(next(&mut resolution).await, vec![1, 3, 4]);
+    assert_eq!(filtered(&metrics), 0);
+
+    drop(resolution);
+    assert_eq!(filtered(&metrics), 0, "gauge must be reset when dropped");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn ignores_hints_below_minimum() {
+    let (metrics, tx, mut resolution) = resolve(config(2)).await;
+
+    tx.send(Update::Reset(vec![
+        endpoint(1, Some("zone-a")),
+        endpoint(2, Some("zone-b")),
+        endpoint(3, Some("zone-b")),
+    ]))
+    .unwrap();
+    assert_eq!(
+        next(&mut resolution).await,
+        vec![1, 2, 3],
+        "too few endpoints are hinted for the local zone"
+    );
+    assert_eq!(filtered(&metrics), 0);
+
+    tx.send(Update::Add(vec![endpoint(4, Some("zone-a"))]))
+        .unwrap();
+    assert_eq!(next(&mut resolution).await, vec![1, 4]);
+    assert_eq!(filtered(&metrics), 2);
+
+    tx.send(Update::Remove(vec![addr(1)])).unwrap();
+    assert_eq!(next(&mut resolution).await, vec![2, 3, 4]);
+    assert_eq!(filtered(&metrics), 0);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn ignores_hints_when_no_endpoints_are_hinted_locally() {
+    let (metrics, tx, mut resolution) = resolve(config(0)).await;
+
+    tx.send(Update::Reset(vec![
+        endpoint(1, Some("zone-b")),
+        endpoint(2, Some("zone-c")),
+    ]))
+    .unwrap();
+    assert_eq!(next(&mut resolution).await, vec![1, 2]);
+    assert_eq!(filtered(&metrics), 0);
+
+    tx.send(Update::Unavailable).unwrap();
+    assert_eq!(
+        resolution.next().await.unwrap().unwrap(),
+        Update::Unavailable
+    );
+
+    tx.send(Update::DoesNotExist).unwrap();
+    assert_eq!(
+        resolution.next().await.unwrap().unwrap(),
+        Update::DoesNotExist
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn disabled_without_config() {
+    let (metrics, tx, mut resolution) = resolve(None).await;
+
+    let update = Update::Add(vec![endpoint(1, Some("zone-b"))]);
+    tx.send(update.clone()).unwrap();
+    assert_eq!(resolution.next().await.unwrap().unwrap(), update);
+    assert_eq!(filtered(&metrics), 0);
+}
+
+// === Utils ===
+
+type Tx = mpsc::UnboundedSender<Update<Metadata>>;
+
+fn config(min_endpoints: usize) -> Option<TopologyHintsConfig> {
+    Some(TopologyHintsConfig {
+        zone: "zone-a".to_string(),
+        min_endpoints,
+    })
+}
+
+async fn resolve(
+    config: Option<TopologyHintsConfig>,
+) -> (TopologyHintMetrics, Tx, HintedResolution) {
+    let metrics = TopologyHintMetrics::register(&mut Default::default());
+    let (tx, rx) = mpsc::unbounded_channel();
+    let rx = Arc::new(parking_lot::Mutex::new(Some(rx)));
+    let inner = svc::mk(move |_: Target| {
+        let mut rx = rx.lock().take().expect("resolved once");
+        future::ok::<_, Error>(stream::poll_fn(move |cx| {
+            rx.poll_recv(cx).map(|up| up.map(Ok))
+        }))
+    });
+    let resolution = HintedResolve::new(config, metrics.clone(), inner)
+        .oneshot(Target)
+        .await
+        .expect("resolution must succeed");
+    (metrics, tx, resolution)
+}
+
+async fn next(resolution: &mut HintedResolution) -> Vec<u8> {
+    match resolution.next().await.unwrap().unwrap() {
+        Update::Reset(eps) => {
+            let mut ids = eps
+                .into_iter()
+                .map(|(addr, _)| match addr.ip() {
+                    std::net::IpAddr::V4(ip) => ip.octets()[3],
+                    ip => panic!("unexpected address: {ip}"),
+                })
+                .collect::<Vec<_>>();
+            ids.sort_unstable();
+            ids
+        }
+        update => panic!("unexpected update: {update:?}"),
+    }
+}
+
+fn filtered(metrics: &TopologyHintMetrics) -> i64 {
+    let labels = ConcreteLabels(svc::Param::param(&Target), svc::Param::param(&Target));
+    metrics.filtered.get_or_create(&labels).get()
+}
+
+fn addr(id: u8) -> SocketAddr {
+    SocketAddr::new([192, 0, 2, id].into(), 8080)
+}
+
+fn endpoint(id: u8, hints: Option<&str>) -> (SocketAddr, Metadata) {
+    let pb = destination::WeightedAddr {
+        addr: Some(addr(id).into()),
+        weight: 1,
+        metric_labels: hints
+            .into_iter()
+            .map(|zones| ("for_zones".to_string(), zones.to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    pb::to_addr_meta(pb, &Default::default()).expect("endpoint must be valid")
+}
@@ -3,17 +3,22 @@ use linkerd_app_core::{
     exp_backoff::{ExponentialBackoff, ExponentialBackoffStream},
     identity, metrics,
     profiles::{self, DiscoveryRejected},
-    proxy::{api_resolve as api, http, resolve::recover},
+    proxy::{
+        api_resolve as api, http,
+        resolve::{recover, shared},
+    },
     svc::{self, NewService, ServiceExt},
     Error, Recover,
 };
 use linkerd_tonic_stream::ReceiveLimits;
+use std::{sync::Arc, time::Duration};
 
 #[derive(Clone, Debug)]
 pub struct Config {
     pub control: control::Config,
     pub context: String,
     pub limits: ReceiveLimits,
+    pub resolution_linger: Duration,
 }
 
 /// Handles to destination service clients.
@@ -24,8 +29,12 @@ pub struct Dst<S> {
     /// Resolves profiles.
     pub profiles: profiles::RecoverDefault<profiles::Client<BackoffUnlessInvalidArgument, S>>,
 
-    /// Resolves endpoints.
-    pub resolve: recover::Resolve<BackoffUnlessInvalidArgument, api::Resolve<S>>,
+    /// Resolves endpoints, sharing each target's resolution among its
+    /// consumers.
+    pub resolve: shared::Shared<
+        api::ConcreteAddr,
+        recover::Resolve<BackoffUnlessInvalidArgument, api::Resolve<S>>,
+    >,
 }
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -39,7 +48,9 @@ impl Config {
         dns: dns::Resolver,
         legacy_metrics: metrics::ControlHttp,
         control_metrics: control::Metrics,
+        resolution_metrics: shared::SharedMetrics,
         identity: identity::NewClient,
+        observe: Option<Arc<dyn api::Observe>>,
     ) -> Result<
         Dst<
             impl svc::Service<
@@ -66,12 +77,18 @@ impl Config {
             self.limits,
         );
 
+        let mut resolve = api::Resolve::new(svc, self.context, self.limits);
+        if let Some(observe) = observe {
+            resolve = resolve.with_observer(observe);
+        }
+
         Ok(Dst {
             addr,
             profiles,
-            resolve: recover::Resolve::new(
-                backoff,
-                api::Resolve::new(svc, self.context, self.limits),
+            resolve: shared::Shared::new(
+                recover::Resolve::new(backoff, resolve),
+                self.resolution_linger,
+                resolution_metrics,
             ),
         })
     }
@@ -1,17 +1,24 @@
-use crate::{dns, gateway, identity, inbound, outbound, policy, spire, trace_collector};
+#[cfg(feature = "spire")]
+use crate::spire;
+use crate::{
+    dns, ext_authz, gateway, identity, inbound, outbound, policy, self_check, startup,
+    trace_collector, tunables,
+};
 use linkerd_app_core::{
     addr,
     config::*,
     control::{Config as ControlConfig, ControlAddr},
     http_tracing::CollectorProtocol,
-    proxy::http::{h1, h2},
+    proxy::http::{self, compress, h1, h2, request_id, stream_timeouts},
+    svc::idle_cache::PeriodicConfig,
     tls,
-    transport::{DualListenAddr, Keepalive, ListenAddr, UserTimeout},
+    transport::{udp, DualListenAddr, Keepalive, ListenAddr, OrigDstFallback, UserTimeout},
     AddrMatch, Conditional, IpNet,
 };
 use std::{
     collections::{HashMap, HashSet},
     net::SocketAddr,
+    num::NonZeroU32,
     path::PathBuf,
     time::Duration,
 };
@@ -22,8 +29,10 @@ mod control;
 mod http2;
 mod trace;
 mod types;
+mod validate;
 
 use self::types::*;
+pub use self::validate::{Resolution, Validation};
 
 /// The strings used to build a configuration.
 pub trait Strings {
@@ -98,14 +107,90 @@ pub enum ParseError {
 
     #[error("authority labels may only be set to 'unsafe'")]
     NotAnAuthorityLabelsSetting,
+    #[error("not a valid content-type: {0}")]
+    NotAContentType(String),
+    #[error("not a valid header name: {0}")]
+    NotAHeaderName(String),
+    #[error("not a valid header value: {0}")]
+    NotAHeaderValue(String),
+    #[error("startup timeout mode must be 'release' or 'reject': {0}")]
+    NotAStartupTimeoutMode(String),
+    #[error("external authorization failure mode must be 'open' or 'closed': {0}")]
+    NotAnExtAuthzFailureMode(String),
+    #[error("half-close mode must be 'propagate', 'couple', or 'linger:<duration>': {0}")]
+    NotAHalfCloseMode(String),
+    #[error("not a valid TLS protocol version, cipher suite, or policy mode: {0}")]
+    NotATlsPolicySetting(String),
+    #[error("outbound listeners must be configured as 'NAME=ADDR[;opaque][;forward]' with unique names: {0}")]
+    NotAListener(String),
+    #[error("inbound forward targets must be configured as 'PORT=ADDR' with unique ports: {0}")]
+    NotAForwardTarget(String),
+    #[error("inbound HTTP/2 settings must be configured as 'PORT=SETTING:VALUE[;SETTING:VALUE]' with unique ports and valid values: {0}")]
+    NotHttp2PortSettings(String),
+    #[error("connection lifetimes must be configured as 'CIDR=DURATION': {0}")]
+    NotAConnectionLifetime(String),
+    #[error("port mappings must be configured as '[CIDR:]PORT=PORT|NAME:PORT': {0}")]
+    NotAPortMapping(String),
+    #[error("gateway authorizations must be configured as 'SUFFIX=SUFFIX[|SUFFIX]': {0}")]
+    NotAGatewayAuthorization(String),
+    #[error("keepalive-exempt requests must be configured as '[METHOD ]/PATH': {0}")]
+    NotAKeepaliveExemptRequest(String),
+    #[error("route overrides must be configured as 'NAME=SETTING[:VALUE][;SETTING[:VALUE]]' with unique names and valid settings: {0}")]
+    NotARouteOverride(String),
+    #[error("parent overrides must be configured as 'NAME=SETTING[:VALUE][;SETTING[:VALUE]]' with unique names and valid settings: {0}")]
+    NotAParentOverride(String),
+    #[error("server overrides must be configured as 'NAME=SETTING[:VALUE][;SETTING[:VALUE]]' with unique names and valid settings: {0}")]
+    NotAServerOverride(String),
+    #[error("{0}")]
+    NotAnAdminEndpoint(#[from] super::admin::InvalidEndpoint),
 }
 
 // Environment variables to look at when loading the configuration
 pub const ENV_OUTBOUND_LISTEN_ADDR: &str = "LINKERD2_PROXY_OUTBOUND_LISTEN_ADDR";
 pub const ENV_OUTBOUND_LISTEN_ADDRS: &str = "LINKERD2_PROXY_OUTBOUND_LISTEN_ADDRS";
+/// Configures an address on which the outbound proxy accepts explicitly
+/// proxied traffic (i.e. absolute-form HTTP requests and `CONNECT` tunnels)
+/// from clients that use the proxy as an HTTP proxy. Disabled when unset.
+pub const ENV_OUTBOUND_EXPLICIT_LISTEN_ADDR: &str = "LINKERD2_PROXY_OUTBOUND_EXPLICIT_LISTEN_ADDR";
+/// Configures an address on which the outbound proxy accepts SOCKS5
+/// connections, e.g. from debugging tools or clients whose connections cannot
+/// be redirected to the proxy. Disabled when unset.
+pub const ENV_OUTBOUND_SOCKS5_LISTEN_ADDR: &str = "LINKERD2_PROXY_OUTBOUND_SOCKS5_LISTEN_ADDR";
+/// The username that SOCKS5 clients must present. Must be set together with
+/// `LINKERD2_PROXY_OUTBOUND_SOCKS5_PASSWORD`. When neither is set, SOCKS5
+/// clients are not authenticated.
+pub const ENV_OUTBOUND_SOCKS5_USERNAME: &str = "LINKERD2_PROXY_OUTBOUND_SOCKS5_USERNAME";
+/// The password that SOCKS5 clients must present.
+pub const ENV_OUTBOUND_SOCKS5_PASSWORD: &str = "LINKERD2_PROXY_OUTBOUND_SOCKS5_PASSWORD";
+/// A comma-separated list of `NAME=ADDR[;OPTION...]` entries configuring
+/// additional listeners on which the outbound proxy accepts connections. The
+/// `opaque` option disables protocol detection, so that connections whose
+/// protocol is not known from discovery are proxied as opaque; and the
+/// `forward` option disables discovery, so that connections are forwarded to
+/// their original destination address. The name labels the listener's
+/// metrics.
+pub const ENV_OUTBOUND_ADDITIONAL_LISTENERS: &str = "LINKERD2_PROXY_OUTBOUND_ADDITIONAL_LISTENERS";
 pub const ENV_INBOUND_LISTEN_ADDR: &str = "LINKERD2_PROXY_INBOUND_LISTEN_ADDR";
 pub const ENV_CONTROL_LISTEN_ADDR: &str = "LINKERD2_PROXY_CONTROL_LISTEN_ADDR";
 pub const ENV_ADMIN_LISTEN_ADDR: &str = "LINKERD2_PROXY_ADMIN_LISTEN_ADDR";
+/// A comma-separated list of the endpoints served on the admin listener (e.g.
+/// `metrics,ready,live,shutdown`). All endpoints are served when unset.
+pub const ENV_ADMIN_ENDPOINTS: &str = "LINKERD2_PROXY_ADMIN_ENDPOINTS";
+/// Configures an additional admin listener that serves read-only endpoints
+/// over mesh mTLS to clients with one of the identities in
+/// `LINKERD2_PROXY_ADMIN_SCRAPE_IDENTITIES`. Disabled when unset. When enabled,
+/// the admin listener must bind to a loopback address if it serves endpoints
+/// that mutate the proxy or expose sensitive state, like `shutdown`,
+/// `proxy-log-level`, and `env`.
+pub const ENV_ADMIN_SCRAPE_LISTEN_ADDR: &str = "LINKERD2_PROXY_ADMIN_SCRAPE_LISTEN_ADDR";
+/// A comma-separated list of the identities permitted to connect to the admin
+/// scrape listener.
+pub const ENV_ADMIN_SCRAPE_IDENTITIES: &str = "LINKERD2_PROXY_ADMIN_SCRAPE_IDENTITIES";
+/// A comma-separated list of the endpoints served on the admin scrape
+/// listener. All read-only endpoints are served when unset. Endpoints that
+/// mutate the proxy or expose sensitive state, like `env`, may not be served
+/// on this listener.
+pub const ENV_ADMIN_SCRAPE_ENDPOINTS: &str = "LINKERD2_PROXY_ADMIN_SCRAPE_ENDPOINTS";
 
 pub const ENV_METRICS_RETAIN_IDLE: &str = "LINKERD2_PROXY_METRICS_RETAIN_IDLE";
 
@@ -116,13 +201,409 @@ const ENV_INGRESS_MODE: &str = "LINKERD2_PROXY_INGRESS_MODE";
 const ENV_INBOUND_HTTP_QUEUE_CAPACITY: &str = "LINKERD2_PROXY_INBOUND_HTTP_QUEUE_CAPACITY";
 const ENV_INBOUND_HTTP_FAILFAST_TIMEOUT: &str = "LINKERD2_PROXY_INBOUND_HTTP_FAILFAST_TIMEOUT";
 
+/// A comma-separated list of content-types (e.g. `application/json,text/*`)
+/// for which the inbound proxy compresses application responses. Compression
+/// is disabled when unset.
+pub const ENV_INBOUND_HTTP_COMPRESSION_CONTENT_TYPES: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_COMPRESSION_CONTENT_TYPES";
+/// The minimum size, in bytes, of responses compressed by the inbound proxy.
+pub const ENV_INBOUND_HTTP_COMPRESSION_MIN_SIZE: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_COMPRESSION_MIN_SIZE";
+
+/// A hex-encoded key, of at least 32 bytes, that signs the workload identity
+/// assertions set by outbound routes with an `AssertWorkloadIdentity` filter
+/// and that verifies assertions when inbound verification is enabled.
+pub const ENV_WORKLOAD_IDENTITY_KEY: &str = "LINKERD2_PROXY_WORKLOAD_IDENTITY_KEY";
+/// A hex-encoded key that is also accepted when verifying workload identity
+/// assertions, so that keys may be rotated.
+pub const ENV_WORKLOAD_IDENTITY_PREVIOUS_KEY: &str =
+    "LINKERD2_PROXY_WORKLOAD_IDENTITY_PREVIOUS_KEY";
+/// Whether the inbound proxy verifies workload identity assertions, using
+/// asserted identities in place of the client's identity for authorization.
+/// This is intended for gateways that terminate and re-originate connections.
+/// Defaults to false.
+pub const ENV_INBOUND_WORKLOAD_IDENTITY_VERIFY: &str =
+    "LINKERD2_PROXY_INBOUND_WORKLOAD_IDENTITY_VERIFY";
+/// The maximum age of workload identity assertions accepted by the inbound
+/// proxy.
+pub const ENV_INBOUND_WORKLOAD_IDENTITY_MAX_AGE: &str =
+    "LINKERD2_PROXY_INBOUND_WORKLOAD_IDENTITY_MAX_AGE";
+
+/// A comma-separated list of `RESOURCE=SETTING[:VALUE][;SETTING[:VALUE]...]`
+/// entries configuring discovered outbound routes with settings that the
+/// policy controller does not provide. Routes are referenced as
+/// `KIND.GROUP:NAMESPACE/NAME`, e.g.
+/// `httproute.gateway.networking.k8s.io:emojivoto/web`, or as `default:NAME`
+/// for the proxy's default routes:
+///
+/// - `assert-workload-identity` adds an `AssertWorkloadIdentity` filter to
+///   HTTP routes.
+/// - `failure-statuses:STATUSES` sets the HTTP response statuses that are
+///   classified as failures, e.g. `failure-statuses:404|500-599`.
+///   `success-statuses:STATUSES` removes statuses from the failures.
+/// - `failure-codes:CODES` sets the numeric `grpc-status` codes that are
+///   classified as failures. `success-codes:CODES` removes codes from the
+///   failures.
+/// - `rollout-guard:WINDOW|MIN_REQUESTS|MAX_FAILURE_RATE_INCREASE|COOLDOWN`
+///   guards a rollout from a route's first (primary) backend to its second
+///   (canary) backend, e.g. `rollout-guard:1m|100|5|10m`.
+/// - `cookie:NAME|VALUE` requires that requests have a cookie with the given
+///   (percent-decoded) value to match an HTTP route, in addition to each of
+///   the route's matches. `cookie-prefix:NAME|PREFIX` and
+///   `cookie-regex:NAME|REGEX` match values by prefix or by regular
+///   expression. Multiple cookie settings must all match.
+/// - `response-headers-timeout:DURATION` bounds the time until response
+///   headers are received, and `response-chunk-idle-timeout:DURATION` bounds
+///   the time between frames of the response body, on HTTP and gRPC routes.
+/// - `cache[:MAX_AGE]` caches responses to `GET` and `HEAD` requests on HTTP
+///   routes, storing each response for no longer than `MAX_AGE`, if set.
+///   `cache-allow-authorization` also caches responses to requests with an
+///   `authorization` header.
+/// - `coalesce:MAX_WAITERS|TIMEOUT` coalesces identical in-flight `GET` and
+///   `HEAD` requests on HTTP routes, e.g. `coalesce:100|5s`: up to
+///   `MAX_WAITERS` requests wait for each in-flight request's response, for
+///   no longer than `TIMEOUT`. `coalesce-headers:NAMES` sets the `|`-separated
+///   request headers whose values must also match.
+/// - `fault-delay:PERCENT|DURATION` delays the given percentage of requests on
+///   HTTP and gRPC routes, e.g. `fault-delay:10|100ms`. A `MIN-MAX` duration,
+///   e.g. `fault-delay:10|100ms-1s`, delays each request for a random duration
+///   in the range.
+///   `fault-abort:PERCENT|STATUS` fails the given percentage of requests on
+///   HTTP routes with a 4xx or 5xx `STATUS`, and `fault-abort-grpc:PERCENT|CODE`
+///   fails them on gRPC routes with a non-OK `grpc-status`. Aborted requests
+///   are not retried unless `fault-retry-aborts` is set.
+/// - `direct-response:STATUS` responds to requests on HTTP routes with
+///   `STATUS` rather than forwarding them. `direct-response-header:NAME|VALUE`
+///   adds a header to the response and `direct-response-body:BODY` sets its
+///   body, of at most 8KiB, which may not contain `,` or `;`.
+///   `direct-response-grpc:CODE[|MESSAGE]` responds to requests on gRPC routes
+///   with a trailers-only response.
+/// - `rewrite-authority:HOST[:PORT]` rewrites the authority of requests on
+///   HTTP routes, e.g. to forward requests for a vanity hostname to the service
+///   that serves it. `rewrite-authority-location` also maps the `location`
+///   header of redirects that refer to the rewritten authority back to the
+///   request's original authority.
+/// - `decompress-request[:MAX_BYTES]` decompresses gzip-encoded request bodies
+///   on HTTP routes before they are forwarded, failing requests whose
+///   decompressed bodies exceed `MAX_BYTES`, 10MiB by default.
+pub const ENV_OUTBOUND_ROUTE_OVERRIDES: &str = "LINKERD2_PROXY_OUTBOUND_ROUTE_OVERRIDES";
+
+/// A comma-separated list of `RESOURCE=SETTING[:VALUE][;SETTING[:VALUE]...]`
+/// entries configuring discovered outbound parents, e.g. services, with
+/// settings that the policy controller does not provide. Parents are
+/// referenced as `KIND[.GROUP]:NAMESPACE/NAME`, e.g. `service:emojivoto/web`,
+/// or as `default:NAME` for the proxy's default parents:
+///
+/// - `allowed-snis:PATTERNS` closes connections to a TLS parent unless their
+///   SNI matches one of the `|`-separated patterns, e.g.
+///   `allowed-snis:api.example.com|*.example.com`.
+/// - `allow-missing-sni` exempts connections without an SNI from
+///   `allowed-snis`.
+pub const ENV_OUTBOUND_PARENT_OVERRIDES: &str = "LINKERD2_PROXY_OUTBOUND_PARENT_OVERRIDES";
+
+/// A comma-separated list of `RESOURCE=SETTING[:VALUE][;SETTING[:VALUE]...]`
+/// entries configuring discovered inbound routes with settings that the policy
+/// controller does not provide. Routes, which are in the workload's namespace,
+/// are referenced as `KIND.GROUP:NAME`, e.g.
+/// `httproute.gateway.networking.k8s.io:web`, or as `default:NAME` for the
+/// proxy's default routes:
+///
+/// - `latency-objective:DURATION` sets the latency within which the route's
+///   requests are expected to complete, e.g. `latency-objective:250ms`.
+/// - `sheddable` allows the route's requests to be shed in preference to those
+///   of other routes when the application is overloaded.
+/// - `ext-authz` requires that the route's requests are also authorized by
+///   the external authorization service configured by
+///   `LINKERD2_PROXY_INBOUND_EXT_AUTHZ_SVC_ADDR`.
+pub const ENV_INBOUND_ROUTE_OVERRIDES: &str = "LINKERD2_PROXY_INBOUND_ROUTE_OVERRIDES";
+
+/// A comma-separated list of `RESOURCE=SETTING[:VALUE][;SETTING[:VALUE]...]`
+/// entries configuring discovered inbound servers with settings that the
+/// policy controller does not provide. Servers, which are in the workload's
+/// namespace, are referenced as `KIND.GROUP:NAME`, e.g.
+/// `server.policy.linkerd.io:web`:
+///
+/// - `termination-grace-period:DURATION` sets the amount of time that the
+///   server's connections and in-flight requests are permitted to continue
+///   after a policy update revokes their authorization, e.g.
+///   `termination-grace-period:30s`. By default, they are terminated
+///   immediately.
+pub const ENV_INBOUND_SERVER_OVERRIDES: &str = "LINKERD2_PROXY_INBOUND_SERVER_OVERRIDES";
+
+/// Whether the inbound proxy sets the verified client identity in the
+/// `l5d-client-id` header of HTTP requests. Defaults to true.
+pub const ENV_INBOUND_HTTP_CLIENT_ID_HEADER: &str = "LINKERD2_PROXY_INBOUND_HTTP_CLIENT_ID_HEADER";
+/// A value set in the `l5d-client-id` header of inbound HTTP requests from
+/// clients without a verified identity. When unset, no value is set.
+pub const ENV_INBOUND_HTTP_CLIENT_ID_UNAUTHENTICATED: &str =
+    "LINKERD2_PROXY_INBOUND_HTTP_CLIENT_ID_UNAUTHENTICATED";
+/// Whether client-supplied `l5d-client-id` headers are removed from inbound
+/// HTTP requests even when the header is not set by the proxy. Defaults to
+/// true.
+pub const ENV_INBOUND_HTTP_CLIENT_ID_STRICT: &str = "LINKERD2_PROXY_INBOUND_HTTP_CLIENT_ID_STRICT";
+
+/// The name of the header (e.g. `x-request-id`) that carries request IDs.
+/// When set, the inbound and outbound proxies ensure that each HTTP request
+/// has an ID, generating one when the request does not already have one.
+pub const ENV_HTTP_REQUEST_ID_HEADER: &str = "LINKERD2_PROXY_HTTP_REQUEST_ID_HEADER";
+/// Whether request IDs set by clients are replaced with new IDs. Defaults to
+/// false.
+pub const ENV_HTTP_REQUEST_ID_OVERWRITE: &str = "LINKERD2_PROXY_HTTP_REQUEST_ID_OVERWRITE";
+
+/// Enables propagation of request deadlines. When enabled, requests that
+/// arrive after their deadline are failed and outbound route timeouts are
+/// bounded by the request's deadline, which is rewritten for the next hop.
+/// Defaults to false.
+pub const ENV_HTTP_DEADLINE_PROPAGATION: &str = "LINKERD2_PROXY_HTTP_DEADLINE_PROPAGATION";
+/// The header that carries an absolute request deadline, in milliseconds since
+/// the Unix epoch. Defaults to `x-deadline`.
+pub const ENV_HTTP_DEADLINE_HEADER: &str = "LINKERD2_PROXY_HTTP_DEADLINE_HEADER";
+/// The header that carries a relative request timeout in the `grpc-timeout`
+/// format. Defaults to `grpc-timeout`.
+pub const ENV_HTTP_DEADLINE_TIMEOUT_HEADER: &str = "LINKERD2_PROXY_HTTP_DEADLINE_TIMEOUT_HEADER";
+/// Requests with less than this much time remaining before their deadline are
+/// failed without being dispatched. Defaults to 0ms.
+pub const ENV_HTTP_DEADLINE_MIN_BUDGET: &str = "LINKERD2_PROXY_HTTP_DEADLINE_MIN_BUDGET";
+
+/// Enables latency-based outlier detection for outbound load balancers. An
+/// endpoint is ejected when its latency exceeds the median latency of the
+/// balancer's endpoints by this multiple (e.g. `5`). Disabled when unset.
+pub const ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_RATIO: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_LATENCY_OUTLIER_RATIO";
+/// The duration for which an endpoint's latency must remain an outlier before
+/// it is ejected. Defaults to 10s.
+pub const ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_MIN_DURATION: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_LATENCY_OUTLIER_MIN_DURATION";
+/// The duration of an endpoint's first ejection, which doubles on each
+/// subsequent ejection. Defaults to 30s.
+pub const ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_EJECTION: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_LATENCY_OUTLIER_EJECTION";
+/// The maximum duration of an ejection. Defaults to 5m.
+pub const ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTION: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTION";
+/// The maximum percentage of a balancer's endpoints that may be ejected at
+/// once. Defaults to 50.
+pub const ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTED_PERCENT: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTED_PERCENT";
+
+/// A comma-separated list of suffixes matching the names of backends whose
+/// outbound HTTP balancers dispatch each client's requests to the endpoint most
+/// recently selected for it, as long as that endpoint remains ready. Disabled
+/// when unset.
+pub const ENV_OUTBOUND_HTTP_SOURCE_AFFINITY_SUFFIXES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_SOURCE_AFFINITY_SUFFIXES";
+/// The maximum number of clients whose endpoints are tracked by each balancer
+/// that uses source affinity. Defaults to 10000.
+pub const ENV_OUTBOUND_HTTP_SOURCE_AFFINITY_CAPACITY: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_SOURCE_AFFINITY_CAPACITY";
+
+/// The total number of bytes that outbound HTTP requests may buffer so that
+/// their bodies can be replayed on retries. Requests that can't be buffered
+/// within this budget are sent without being retried. Defaults to 64MiB.
+pub const ENV_OUTBOUND_HTTP_RETRY_BUFFER_BYTES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RETRY_BUFFER_BYTES";
+
+/// The total number of bytes that may be used to cache responses for routes
+/// that enable response caching. When storing a response would exceed this
+/// budget, the least recently used responses are evicted. Defaults to 16MiB.
+pub const ENV_OUTBOUND_HTTP_RESPONSE_CACHE_BYTES: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_RESPONSE_CACHE_BYTES";
+
+/// Seeds the generator that decides which outbound requests are faulted by
+/// routes that inject faults, so that injected faults are reproducible. By
+/// default, the generator is seeded randomly.
+pub const ENV_OUTBOUND_HTTP_FAULT_INJECTION_SEED: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_FAULT_INJECTION_SEED";
+
+/// Whether outbound requests may override their route's retries, timeouts, or
+/// rule with an `l5d-route-debug` header. The header is always stripped from
+/// requests. Defaults to false.
+pub const ENV_OUTBOUND_HTTP_ROUTE_DEBUG_HEADER: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_ROUTE_DEBUG_HEADER";
+
+/// When set, meshed endpoints without a protocol hint are probed for HTTP/2
+/// upgrade support, and each result is cached for this duration. Probing is
+/// disabled by default.
+pub const ENV_OUTBOUND_HTTP_UPGRADE_PROBE_TTL: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_UPGRADE_PROBE_TTL";
+
+/// When set, outbound HTTP requests whose responses take longer than this are
+/// written to the access log, and annotated on their spans, with the time
+/// spent in each layer of the proxy. Disabled by default.
+pub const ENV_OUTBOUND_HTTP_REQUEST_TIMINGS_THRESHOLD: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_REQUEST_TIMINGS_THRESHOLD";
+
+/// A comma-separated list of outbound HTTP requests that do not keep cached
+/// logical services alive, e.g. health checks. Each entry is either a path
+/// prefix (`/healthz`) or a method and exact path (`GET /ready`). When set,
+/// services that only serve these requests are evicted once the discovery idle
+/// timeout elapses, even if connections hold them.
+pub const ENV_OUTBOUND_HTTP_KEEPALIVE_EXEMPT: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP_KEEPALIVE_EXEMPT";
+
+/// When set, cached outbound logical HTTP services are rebuilt once they are
+/// this old, regardless of activity. Disabled by default.
+pub const ENV_OUTBOUND_HTTP_LOGICAL_MAX_AGE: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_LOGICAL_MAX_AGE";
+
+/// Whether outbound HTTP requests to endpoints on the local workload, i.e. on
+/// one of the proxy's inbound IPs, are forwarded directly to the application
+/// over the loopback interface. Defaults to true.
+pub const ENV_OUTBOUND_HTTP_HAIRPIN: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_HAIRPIN";
+
+/// The number of request body bytes that outbound HTTP/1 clients may buffer
+/// for each request before the proxy stops reading the request's body from
+/// the application. Unlimited by default.
+pub const ENV_OUTBOUND_HTTP1_REQUEST_BODY_BUFFER_LIMIT: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP1_REQUEST_BODY_BUFFER_LIMIT";
+
+/// The number of request body bytes that outbound HTTP/2 clients may buffer
+/// for each request before the proxy stops reading the request's body from
+/// the application. Unlimited by default.
+pub const ENV_OUTBOUND_HTTP2_REQUEST_BODY_BUFFER_LIMIT: &str =
+    "LINKERD2_PROXY_OUTBOUND_HTTP2_REQUEST_BODY_BUFFER_LIMIT";
+
+/// Whether opaque outbound connections may be spliced between sockets, without
+/// copying data through the proxy, when the proxy neither originates nor
+/// terminates TLS on them. Only supported on Linux. Defaults to false.
+pub const ENV_OUTBOUND_TCP_SPLICE: &str = "LINKERD2_PROXY_OUTBOUND_TCP_SPLICE";
+
+/// Whether connections to TLS destinations on which the client sends a
+/// plaintext HTTP request are answered with an HTTP 400 that describes the
+/// mismatch before they are closed. Defaults to true.
+pub const ENV_OUTBOUND_TLS_PLAINTEXT_HTTP_RESPONSE: &str =
+    "LINKERD2_PROXY_OUTBOUND_TLS_PLAINTEXT_HTTP_RESPONSE";
+
+/// Whether outbound HTTP connections on which the client speaks a different
+/// HTTP version than policy configures are served with the client's version.
+/// Otherwise, they are answered with an HTTP 505 that describes the mismatch.
+/// Defaults to false.
+pub const ENV_OUTBOUND_HTTP_VERSION_HINT: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_VERSION_HINT";
+
+/// A comma-separated list of `PORT=MODE` entries configuring how opaque
+/// connections to each destination port are closed when one peer closes its
+/// half of the connection. `propagate` forwards the half-close and leaves the
+/// other direction open; `couple` closes both directions; and
+/// `linger:<duration>` closes the other direction if it remains open after the
+/// timeout. Half-closes are propagated on ports that are not listed. Sockets
+/// are only spliced on ports that propagate half-closes.
+pub const ENV_OUTBOUND_TCP_HALF_CLOSE: &str = "LINKERD2_PROXY_OUTBOUND_TCP_HALF_CLOSE";
+
+/// The number of times an opaque connection to a load-balanced service is
+/// transparently reestablished, possibly with another endpoint, when it fails
+/// or is reset before any bytes are exchanged. Retries are bounded by the TCP
+/// failfast timeout. Connections that may be retried are not spliced.
+/// Defaults to 0, which disables retries.
+pub const ENV_OUTBOUND_TCP_CONNECT_RETRIES: &str = "LINKERD2_PROXY_OUTBOUND_TCP_CONNECT_RETRIES";
+
+/// A comma-separated list of `CIDR=DURATION` entries configuring the maximum
+/// lifetime of outbound connections to endpoints in each network. HTTP
+/// connections that exceed their lifetime are replaced once their in-flight
+/// requests complete; opaque connections are closed once they are quiet. The
+/// most specific matching network applies. Connections are not recycled when
+/// unset.
+pub const ENV_OUTBOUND_CONNECTION_MAX_LIFETIMES: &str =
+    "LINKERD2_PROXY_OUTBOUND_CONNECTION_MAX_LIFETIMES";
+/// The maximum fraction, between 0 and 1, by which each connection's lifetime
+/// is randomly shortened so that connections are not recycled together.
+/// Defaults to 0.1.
+pub const ENV_OUTBOUND_CONNECTION_LIFETIME_JITTER: &str =
+    "LINKERD2_PROXY_OUTBOUND_CONNECTION_LIFETIME_JITTER";
+/// How long an opaque connection that has exceeded its lifetime must be idle
+/// before it is closed. Defaults to 1s.
+pub const ENV_OUTBOUND_CONNECTION_QUIET_PERIOD: &str =
+    "LINKERD2_PROXY_OUTBOUND_CONNECTION_QUIET_PERIOD";
+
+/// A comma-separated list of IP:PORT outbound destinations that are discovered
+/// when the proxy starts, so that the first connections to them do not wait on
+/// the control plane. Failures are logged but do not affect readiness.
+pub const ENV_OUTBOUND_PREWARM_ADDRS: &str = "LINKERD2_PROXY_OUTBOUND_PREWARM_ADDRS";
+/// Whether the endpoints of prewarmed destinations are also resolved and
+/// connected when the proxy starts. Defaults to false.
+pub const ENV_OUTBOUND_PREWARM_ENDPOINTS: &str = "LINKERD2_PROXY_OUTBOUND_PREWARM_ENDPOINTS";
+
+/// A comma-separated list of `[CIDR:]PORT=TARGET` entries configuring how
+/// outbound destinations on each port are discovered, where the target is
+/// either a port, with which the destination's IP is discovered, or a logical
+/// `NAME:PORT` authority. Connections are forwarded to their original
+/// destination when the mapped destination is not known. The first matching
+/// entry applies.
+pub const ENV_OUTBOUND_PORT_MAPPINGS: &str = "LINKERD2_PROXY_OUTBOUND_PORT_MAPPINGS";
+
+/// Whether outbound discovery results are retained beyond the idle timeout for
+/// destinations that are accessed at regular intervals (e.g. by cron-style
+/// workloads). Defaults to true.
+pub const ENV_OUTBOUND_DISCOVERY_RETAIN_PERIODIC: &str =
+    "LINKERD2_PROXY_OUTBOUND_DISCOVERY_RETAIN_PERIODIC";
+/// The longest access interval for which discovery results are retained.
+/// Defaults to 1 hour.
+pub const ENV_OUTBOUND_DISCOVERY_RETAIN_MAX_PERIOD: &str =
+    "LINKERD2_PROXY_OUTBOUND_DISCOVERY_RETAIN_MAX_PERIOD";
+/// The maximum number of periodically accessed destinations whose discovery
+/// results are retained at any time. Defaults to 100.
+pub const ENV_OUTBOUND_DISCOVERY_RETAIN_MAX_ENTRIES: &str =
+    "LINKERD2_PROXY_OUTBOUND_DISCOVERY_RETAIN_MAX_ENTRIES";
+
+/// A file to which outbound discovery results are persisted, so that they may
+/// be served provisionally while destinations are rediscovered after the proxy
+/// restarts. Snapshots are not persisted if unset.
+pub const ENV_OUTBOUND_DISCOVERY_SNAPSHOT_PATH: &str =
+    "LINKERD2_PROXY_OUTBOUND_DISCOVERY_SNAPSHOT_PATH";
+/// How often outbound discovery snapshots are written. Defaults to 30 seconds.
+pub const ENV_OUTBOUND_DISCOVERY_SNAPSHOT_INTERVAL: &str =
+    "LINKERD2_PROXY_OUTBOUND_DISCOVERY_SNAPSHOT_INTERVAL";
+/// How long after a discovery snapshot is written its results may be served
+/// without being confirmed by the control plane. Defaults to 5 minutes.
+pub const ENV_OUTBOUND_DISCOVERY_SNAPSHOT_MAX_AGE: &str =
+    "LINKERD2_PROXY_OUTBOUND_DISCOVERY_SNAPSHOT_MAX_AGE";
+
+/// The zone in which the proxy runs. When set, outbound balancers exclude
+/// endpoints whose topology hints name only other zones.
+pub const ENV_OUTBOUND_TOPOLOGY_ZONE: &str = "LINKERD2_PROXY_OUTBOUND_TOPOLOGY_ZONE";
+/// The minimum number of endpoints that must be hinted for the local zone for
+/// topology hints to be honored. Defaults to 1.
+pub const ENV_OUTBOUND_TOPOLOGY_HINTS_MIN_ENDPOINTS: &str =
+    "LINKERD2_PROXY_OUTBOUND_TOPOLOGY_HINTS_MIN_ENDPOINTS";
+
+/// Overrides the time constant over which outbound balancers' endpoint latency
+/// estimates decay, which is otherwise configured by discovery. Longer decays
+/// are slower to send traffic to endpoints whose latency has recovered.
+pub const ENV_OUTBOUND_BALANCER_EWMA_DECAY: &str = "LINKERD2_PROXY_OUTBOUND_BALANCER_EWMA_DECAY";
+/// Overrides the latency estimate that outbound balancers assign to endpoints
+/// that have not yet responded, which is otherwise configured by discovery.
+pub const ENV_OUTBOUND_BALANCER_EWMA_DEFAULT_RTT: &str =
+    "LINKERD2_PROXY_OUTBOUND_BALANCER_EWMA_DEFAULT_RTT";
+/// Whether outbound balancers export each endpoint's load estimate, labeled by
+/// the endpoint's address. Intended for debugging. Defaults to false.
+pub const ENV_OUTBOUND_BALANCER_ENDPOINT_LOAD_METRICS: &str =
+    "LINKERD2_PROXY_OUTBOUND_BALANCER_ENDPOINT_LOAD_METRICS";
+
+/// The window within which outbound policy and profile updates are coalesced
+/// before routes are recomputed. Defaults to 100ms; updates are applied
+/// immediately when set to zero.
+pub const ENV_OUTBOUND_ROUTE_UPDATE_DEBOUNCE: &str =
+    "LINKERD2_PROXY_OUTBOUND_ROUTE_UPDATE_DEBOUNCE";
+
+/// Whether IPv4-mapped IPv6 original destination addresses (e.g.
+/// `::ffff:10.0.0.1`) are discovered by their IPv4 form. Defaults to true.
+pub const ENV_OUTBOUND_DISCOVERY_UNMAP_IPV4: &str = "LINKERD2_PROXY_OUTBOUND_DISCOVERY_UNMAP_IPV4";
+
+/// Disables ServiceProfile discovery for outbound connections, so that routes
+/// are configured exclusively by client policies.
+pub const ENV_OUTBOUND_PROFILES_DISABLED: &str = "LINKERD2_PROXY_OUTBOUND_PROFILES_DISABLED";
+
+/// The maximum number of distinct ServiceProfile names with which outbound
+/// HTTP metrics are labeled for destinations that client policy does not name,
+/// such as ExternalName services. Defaults to 1000.
+pub const ENV_OUTBOUND_LOGICAL_NAME_LABELS_LIMIT: &str =
+    "LINKERD2_PROXY_OUTBOUND_LOGICAL_NAME_LABELS_LIMIT";
+
 const ENV_OUTBOUND_TCP_QUEUE_CAPACITY: &str = "LINKERD2_PROXY_OUTBOUND_TCP_QUEUE_CAPACITY";
 const ENV_OUTBOUND_TCP_FAILFAST_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_TCP_FAILFAST_TIMEOUT";
 const ENV_OUTBOUND_HTTP_QUEUE_CAPACITY: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_QUEUE_CAPACITY";
 const ENV_OUTBOUND_HTTP_FAILFAST_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_HTTP_FAILFAST_TIMEOUT";
 
 pub const ENV_INBOUND_DETECT_TIMEOUT: &str = "LINKERD2_PROXY_INBOUND_DETECT_TIMEOUT";
-const ENV_OUTBOUND_DETECT_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_DETECT_TIMEOUT";
+pub const ENV_OUTBOUND_DETECT_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_DETECT_TIMEOUT";
 
 const ENV_INBOUND_CONNECT_TIMEOUT: &str = "LINKERD2_PROXY_INBOUND_CONNECT_TIMEOUT";
 const ENV_OUTBOUND_CONNECT_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_CONNECT_TIMEOUT";
@@ -146,11 +627,41 @@ const ENV_OUTBOUND_MAX_IDLE_CONNS_PER_ENDPOINT: &str =
 pub const ENV_INBOUND_MAX_IN_FLIGHT: &str = "LINKERD2_PROXY_INBOUND_MAX_IN_FLIGHT";
 pub const ENV_OUTBOUND_MAX_IN_FLIGHT: &str = "LINKERD2_PROXY_OUTBOUND_MAX_IN_FLIGHT";
 
+/// The number of inbound HTTP requests that may be in flight to the
+/// application before it is considered overloaded. When either this or the
+/// maximum p99 latency is set, a fraction of new requests are shed while the
+/// application is overloaded.
+pub const ENV_INBOUND_APP_PRESSURE_MAX_IN_FLIGHT: &str =
+    "LINKERD2_PROXY_INBOUND_APP_PRESSURE_MAX_IN_FLIGHT";
+/// The p99 latency of the application's responses beyond which it is
+/// considered overloaded.
+pub const ENV_INBOUND_APP_PRESSURE_MAX_P99_LATENCY: &str =
+    "LINKERD2_PROXY_INBOUND_APP_PRESSURE_MAX_P99_LATENCY";
+/// The window of time over which the application's response latencies are
+/// observed. Defaults to 10s.
+pub const ENV_INBOUND_APP_PRESSURE_LATENCY_WINDOW: &str =
+    "LINKERD2_PROXY_INBOUND_APP_PRESSURE_LATENCY_WINDOW";
+/// The fraction of new requests, between 0 and 1, that are shed while the
+/// application is overloaded. Defaults to 0.5.
+pub const ENV_INBOUND_APP_PRESSURE_SHED_RATIO: &str =
+    "LINKERD2_PROXY_INBOUND_APP_PRESSURE_SHED_RATIO";
+/// The delay that shed requests' `retry-after` headers instruct clients to
+/// wait before retrying. Defaults to 1s.
+pub const ENV_INBOUND_APP_PRESSURE_RETRY_AFTER: &str =
+    "LINKERD2_PROXY_INBOUND_APP_PRESSURE_RETRY_AFTER";
+
+/// If set, inbound connections on which the client sends no data within this
+/// timeout are closed. Must be shorter than the inbound detect timeout.
+/// Connections to opaque ports are never closed.
+pub const ENV_INBOUND_SILENT_CONNECTION_TIMEOUT: &str =
+    "LINKERD2_PROXY_INBOUND_SILENT_CONNECTION_TIMEOUT";
+
 const ENV_OUTBOUND_DISABLE_INFORMATIONAL_HEADERS: &str =
     "LINKERD2_PROXY_OUTBOUND_DISABLE_INFORMATIONAL_HEADERS";
 
 const ENV_OUTBOUND_METRICS_HOSTNAME_LABELS: &str =
     "LINKERD2_PROXY_OUTBOUND_METRICS_HOSTNAME_LABELS";
+const ENV_OUTBOUND_METRICS_METHOD_LABELS: &str = "LINKERD2_PROXY_OUTBOUND_METRICS_METHOD_LABELS";
 const ENV_INBOUND_METRICS_AUTHORITY_LABELS: &str =
     "LINKERD2_PROXY_INBOUND_METRICS_AUTHORITY_LABELS";
 
@@ -158,6 +669,9 @@ const ENV_TRACE_ATTRIBUTES_PATH: &str = "LINKERD2_PROXY_TRACE_ATTRIBUTES_PATH";
 const ENV_TRACE_PROTOCOL: &str = "LINKERD2_PROXY_TRACE_PROTOCOL";
 const ENV_TRACE_SERVICE_NAME: &str = "LINKERD2_PROXY_TRACE_SERVICE_NAME";
 const ENV_TRACE_EXTRA_ATTRIBUTES: &str = "LINKERD2_PROXY_TRACE_EXTRA_ATTRIBUTES";
+/// Limits the rate at which spans are exported. Spans recorded beyond this rate
+/// are counted but not exported.
+const ENV_TRACE_MAX_SPANS_PER_SECOND: &str = "LINKERD2_PROXY_TRACE_MAX_SPANS_PER_SECOND";
 // This doesn't have the LINKERD2_ prefix because it is a conventional env var from OpenTelemetry:
 // https://opentelemetry.io/docs/specs/otel/configuration/sdk-environment-variables/#general-sdk-configuration
 const ENV_OTEL_TRACE_ATTRIBUTES: &str = "OTEL_RESOURCE_ATTRIBUTES";
@@ -188,6 +702,50 @@ pub const ENV_DESTINATION_PROFILE_NETWORKS: &str = "LINKERD2_PROXY_DESTINATION_P
 /// If unspecified or empty, no inbound gateway is configured.
 pub const ENV_INBOUND_GATEWAY_SUFFIXES: &str = "LINKERD2_PROXY_INBOUND_GATEWAY_SUFFIXES";
 
+/// A comma-separated list of `SERVICE_SUFFIX=CLIENT_SUFFIX[|CLIENT_SUFFIX...]`
+/// entries restricting which client identities may reach the exported services
+/// whose names match each entry's suffix, e.g. to limit the remote clusters
+/// that may reach a service. Services that match no entry may be reached by
+/// any client.
+pub const ENV_INBOUND_GATEWAY_AUTHORIZATIONS: &str =
+    "LINKERD2_PROXY_INBOUND_GATEWAY_AUTHORIZATIONS";
+
+/// A comma-separated list of `PORT=ADDR` entries mapping inbound ports to the
+/// application addresses to which their connections are forwarded, e.g. when
+/// the application does not listen on the pod's loopback interface.
+/// Connections on other ports are forwarded to their original destination
+/// address. Targets must not refer to one of the proxy's own listeners.
+pub const ENV_INBOUND_FORWARD_TARGETS: &str = "LINKERD2_PROXY_INBOUND_FORWARD_TARGETS";
+
+/// A comma-separated list of `PORT=SETTING:VALUE[;SETTING:VALUE...]` entries
+/// overriding HTTP/2 settings for an inbound port, e.g. so that applications
+/// serving large messages are not stalled by flow control. The
+/// `initial-stream-window-size`, `initial-connection-window-size`, and
+/// `max-frame-size` settings apply to both the inbound server and the proxy's
+/// connections to the application; `max-concurrent-streams` only applies to
+/// the inbound server.
+pub const ENV_INBOUND_PORTS_HTTP2_SETTINGS: &str = "LINKERD2_PROXY_INBOUND_PORTS_HTTP2_SETTINGS";
+
+/// Enables adaptive HTTP/2 flow control on connections from meshed clients.
+/// Windows grow with each connection's estimated bandwidth-delay product, as
+/// measured with PINGs, instead of using the static server settings. Ports
+/// with `ENV_INBOUND_PORTS_HTTP2_SETTINGS` windows use those instead.
+///
+/// The windows are bounded by the HTTP/2 library's estimator, which is not
+/// configurable, and they are not reported in spans or metrics.
+pub const ENV_INBOUND_MESH_HTTP2_ADAPTIVE_FLOW_CONTROL: &str =
+    "LINKERD2_PROXY_INBOUND_MESH_HTTP2_ADAPTIVE_FLOW_CONTROL";
+
+/// Enables adaptive HTTP/2 flow control on connections to meshed endpoints.
+/// Windows grow with each connection's estimated bandwidth-delay product, as
+/// measured with PINGs, instead of using the static connect settings.
+/// Settings from discovery take precedence.
+///
+/// As with `ENV_INBOUND_MESH_HTTP2_ADAPTIVE_FLOW_CONTROL`, the windows are not
+/// configurable or reported.
+pub const ENV_OUTBOUND_MESH_HTTP2_ADAPTIVE_FLOW_CONTROL: &str =
+    "LINKERD2_PROXY_OUTBOUND_MESH_HTTP2_ADAPTIVE_FLOW_CONTROL";
+
 // These *disable* our protocol detection for connections whose SO_ORIGINAL_DST
 // has a port in the provided list.
 pub const ENV_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION: &str =
@@ -211,6 +769,22 @@ pub const ENV_POLICY_SVC_BASE: &str = "LINKERD2_PROXY_POLICY_SVC";
 pub const ENV_POLICY_WORKLOAD: &str = "LINKERD2_PROXY_POLICY_WORKLOAD";
 pub const ENV_POLICY_CLUSTER_NETWORKS: &str = "LINKERD2_PROXY_POLICY_CLUSTER_NETWORKS";
 
+/// Configures the address of an external authorization service that is
+/// consulted for inbound requests on routes that require it. Requests on such
+/// routes are denied when no service is configured.
+pub const ENV_INBOUND_EXT_AUTHZ_SVC_BASE: &str = "LINKERD2_PROXY_INBOUND_EXT_AUTHZ_SVC";
+/// The time within which the external authorization service must respond to
+/// a check. Defaults to 200ms.
+pub const ENV_INBOUND_EXT_AUTHZ_TIMEOUT: &str = "LINKERD2_PROXY_INBOUND_EXT_AUTHZ_TIMEOUT";
+/// Either `open`, to allow requests, or `closed`, to deny them, when the
+/// external authorization service cannot be consulted. Defaults to `closed`.
+pub const ENV_INBOUND_EXT_AUTHZ_FAILURE_MODE: &str =
+    "LINKERD2_PROXY_INBOUND_EXT_AUTHZ_FAILURE_MODE";
+/// The duration for which the external authorization service's decisions are
+/// cached for each client identity and route. Defaults to 10s; decisions are
+/// not cached if this is zero.
+pub const ENV_INBOUND_EXT_AUTHZ_CACHE_TTL: &str = "LINKERD2_PROXY_INBOUND_EXT_AUTHZ_CACHE_TTL";
+
 pub const ENV_INBOUND_IPS: &str = "LINKERD2_PROXY_INBOUND_IPS";
 
 pub const ENV_IDENTITY_DISABLED: &str = "LINKERD2_PROXY_IDENTITY_DISABLED";
@@ -220,6 +794,18 @@ pub const ENV_IDENTITY_TOKEN_FILE: &str = "LINKERD2_PROXY_IDENTITY_TOKEN_FILE";
 pub const ENV_IDENTITY_MIN_REFRESH: &str = "LINKERD2_PROXY_IDENTITY_MIN_REFRESH";
 pub const ENV_IDENTITY_MAX_REFRESH: &str = "LINKERD2_PROXY_IDENTITY_MAX_REFRESH";
 
+/// Configures the minimum TLS protocol version (`1.2` or `1.3`) of mesh
+/// connections. Defaults to `1.3`.
+pub const ENV_IDENTITY_TLS_MIN_VERSION: &str = "LINKERD2_PROXY_IDENTITY_TLS_MIN_VERSION";
+/// Configures a comma-separated list of the cipher suites that may be
+/// negotiated on mesh connections. When unset, all supported suites are
+/// permitted.
+pub const ENV_IDENTITY_TLS_CIPHER_SUITES: &str = "LINKERD2_PROXY_IDENTITY_TLS_CIPHER_SUITES";
+/// Configures whether the TLS version and cipher suite policy is enforced
+/// (`enforce`, the default) or only audited (`audit`), in which case sessions
+/// that do not satisfy the policy are accepted but counted.
+pub const ENV_IDENTITY_TLS_POLICY_MODE: &str = "LINKERD2_PROXY_IDENTITY_TLS_POLICY_MODE";
+
 /// This config is here for backwards compatibility. If set, both the tls id and the server
 /// name will be set to the value specified in this config. The values needs to be a DNS
 /// name
@@ -240,8 +826,10 @@ pub const ENV_IDENTITY_SPIRE_WORKLOAD_API_ADDRESS: &str =
 pub const ENV_IDENTITY_SPIRE_SOCKET: &str = "LINKERD2_PROXY_IDENTITY_SPIRE_SOCKET";
 
 pub const IDENTITY_SPIRE_BASE: &str = "LINKERD2_PROXY_IDENTITY_SPIRE";
+#[cfg(feature = "spire")]
 const DEFAULT_SPIRE_BACKOFF: ExponentialBackoff =
     ExponentialBackoff::new_unchecked(Duration::from_millis(100), Duration::from_secs(1), 0.1);
+#[cfg(feature = "spire")]
 const SPIFFE_ID_URI_SCHEME: &str = "spiffe";
 
 pub const ENV_IDENTITY_SVC_BASE: &str = "LINKERD2_PROXY_IDENTITY_SVC";
@@ -256,6 +844,16 @@ pub const ENV_DESTINATION_CONTEXT: &str = "LINKERD2_PROXY_DESTINATION_CONTEXT";
 pub const ENV_DESTINATION_PROFILE_INITIAL_TIMEOUT: &str =
     "LINKERD2_PROXY_DESTINATION_PROFILE_INITIAL_TIMEOUT";
 
+/// Bounds each attempt of retryable ServiceProfile routes, which do not
+/// configure their own per-try timeouts. The route's timeout, if any, continues
+/// to bound the total time across all attempts.
+pub const ENV_DESTINATION_PROFILE_RETRY_TIMEOUT: &str =
+    "LINKERD2_PROXY_DESTINATION_PROFILE_RETRY_TIMEOUT";
+
+/// Configures how long an endpoint resolution is retained after its last
+/// consumer is dropped, so that a rebuilt consumer shares it.
+pub const ENV_DESTINATION_RESOLUTION_LINGER: &str = "LINKERD2_PROXY_DESTINATION_RESOLUTION_LINGER";
+
 pub const ENV_TAP_SVC_NAME: &str = "LINKERD2_PROXY_TAP_SVC_NAME";
 
 /// Configures a minimum value for the TTL of DNS lookups.
@@ -279,8 +877,74 @@ const ENV_INBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT: &str =
 const ENV_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT: &str =
     "LINKERD2_PROXY_OUTBOUND_HTTP1_CONNECTION_POOL_IDLE_TIMEOUT";
 
+/// Enables falling back to a connection's local address when its original
+/// destination address cannot be determined (e.g. in host-network or
+/// explicit-proxy deployments, where connections are not redirected to the
+/// proxy). Only connections accepted on the ports listed in
+/// `LINKERD2_PROXY_ORIG_DST_FALLBACK_PORTS` fall back; all others are refused.
+/// Defaults to false.
+pub const ENV_ORIG_DST_FALLBACK: &str = "LINKERD2_PROXY_ORIG_DST_FALLBACK";
+/// The local ports (e.g. `4143,8080-8090`) on which connections may fall back
+/// to their local address when their original destination address cannot be
+/// determined.
+pub const ENV_ORIG_DST_FALLBACK_PORTS: &str = "LINKERD2_PROXY_ORIG_DST_FALLBACK_PORTS";
+
+/// Configures an address on which redirected UDP datagrams are received and
+/// forwarded to their original destinations. Disabled when unset.
+pub const ENV_OUTBOUND_UDP_LISTEN_ADDR: &str = "LINKERD2_PROXY_OUTBOUND_UDP_LISTEN_ADDR";
+/// The duration after which a UDP session with no traffic is dropped.
+pub const ENV_OUTBOUND_UDP_IDLE_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_UDP_IDLE_TIMEOUT";
+/// The maximum number of concurrent UDP sessions. Datagrams that would open a
+/// new session beyond this limit are dropped.
+pub const ENV_OUTBOUND_UDP_MAX_SESSIONS: &str = "LINKERD2_PROXY_OUTBOUND_UDP_MAX_SESSIONS";
+/// The number of datagrams buffered for each UDP session before datagrams are
+/// dropped.
+pub const ENV_OUTBOUND_UDP_SESSION_CAPACITY: &str = "LINKERD2_PROXY_OUTBOUND_UDP_SESSION_CAPACITY";
+
 const ENV_SHUTDOWN_GRACE_PERIOD: &str = "LINKERD2_PROXY_SHUTDOWN_GRACE_PERIOD";
 
+/// When true, application connections are held at startup until the policies
+/// of the proxy's inbound ports have been discovered, in addition to its
+/// identity being certified. Defaults to false.
+pub const ENV_STARTUP_AWAIT_POLICY: &str = "LINKERD2_PROXY_STARTUP_AWAIT_POLICY";
+/// The maximum duration for which application connections are held at
+/// startup. When unset, connections are held until the proxy is ready.
+pub const ENV_STARTUP_TIMEOUT: &str = "LINKERD2_PROXY_STARTUP_TIMEOUT";
+/// Either `release`, to serve held connections once the startup timeout
+/// elapses, or `reject`, to close connections until the proxy is ready.
+/// Defaults to `release`.
+pub const ENV_STARTUP_TIMEOUT_MODE: &str = "LINKERD2_PROXY_STARTUP_TIMEOUT_MODE";
+/// The number of connections each listener holds at startup. Further
+/// connections wait in the listener's accept backlog.
+pub const ENV_STARTUP_CONNECTION_CAPACITY: &str = "LINKERD2_PROXY_STARTUP_CONNECTION_CAPACITY";
+
+/// How often the proxy checks that its inbound and outbound listeners are
+/// serving connections. When unset, listeners are not checked.
+pub const ENV_SELF_CHECK_INTERVAL: &str = "LINKERD2_PROXY_SELF_CHECK_INTERVAL";
+/// How long a single listener check may take before it fails.
+pub const ENV_SELF_CHECK_TIMEOUT: &str = "LINKERD2_PROXY_SELF_CHECK_TIMEOUT";
+/// The number of consecutive failed checks after which the proxy reports that
+/// it is not ready.
+pub const ENV_SELF_CHECK_FAILURE_THRESHOLD: &str = "LINKERD2_PROXY_SELF_CHECK_FAILURE_THRESHOLD";
+
+/// The path of a file of settings that may be changed while the proxy is
+/// running. The file consists of `NAME=VALUE` lines, where each name is one of
+/// [`TUNABLE_VARS`]. Values set in the file override those set in the
+/// environment. When unset, these settings are fixed at startup.
+pub const ENV_TUNABLES_PATH: &str = "LINKERD2_PROXY_TUNABLES_PATH";
+/// How often the tunables file is checked for changes. Defaults to 10s.
+pub const ENV_TUNABLES_POLL_INTERVAL: &str = "LINKERD2_PROXY_TUNABLES_POLL_INTERVAL";
+/// The fraction of inbound HTTP requests that are recorded in the access log,
+/// between 0 and 1. May only be set in the tunables file.
+pub const ENV_ACCESS_LOG_SAMPLE_RATIO: &str = "LINKERD2_PROXY_ACCESS_LOG_SAMPLE_RATIO";
+
+/// The settings that may be set in the tunables file.
+pub const TUNABLE_VARS: [&str; 3] = [
+    ENV_INBOUND_DETECT_TIMEOUT,
+    ENV_OUTBOUND_DETECT_TIMEOUT,
+    ENV_ACCESS_LOG_SAMPLE_RATIO,
+];
+
 // Default values for various configuration fields
 const DEFAULT_OUTBOUND_LISTEN_ADDR: &str = "127.0.0.1:4140";
 pub const DEFAULT_INBOUND_LISTEN_ADDR: &str = "0.0.0.0:4143";
@@ -291,6 +955,8 @@ const DEFAULT_METRICS_RETAIN_IDLE: Duration = Duration::from_secs(10 * 60);
 
 const DEFAULT_INBOUND_HTTP_QUEUE_CAPACITY: usize = 10_000;
 const DEFAULT_INBOUND_HTTP_FAILFAST_TIMEOUT: Duration = Duration::from_secs(1);
+const DEFAULT_INBOUND_HTTP_COMPRESSION_MIN_SIZE: u64 = 1024;
+const DEFAULT_INBOUND_WORKLOAD_IDENTITY_MAX_AGE: Duration = Duration::from_secs(5 * 60);
 const DEFAULT_INBOUND_DETECT_TIMEOUT: Duration = Duration::from_secs(10);
 const DEFAULT_INBOUND_CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
 const DEFAULT_INBOUND_CONNECT_BACKOFF: ExponentialBackoff =
@@ -298,8 +964,20 @@ const DEFAULT_INBOUND_CONNECT_BACKOFF: ExponentialBackoff =
 
 const DEFAULT_OUTBOUND_TCP_QUEUE_CAPACITY: usize = 10_000;
 const DEFAULT_OUTBOUND_TCP_FAILFAST_TIMEOUT: Duration = Duration::from_secs(3);
+const DEFAULT_OUTBOUND_CONNECTION_LIFETIME_JITTER: f64 = 0.1;
+const DEFAULT_OUTBOUND_CONNECTION_QUIET_PERIOD: Duration = Duration::from_secs(1);
+const DEFAULT_OUTBOUND_LOGICAL_NAME_LABELS_LIMIT: usize = 1_000;
 const DEFAULT_OUTBOUND_HTTP_QUEUE_CAPACITY: usize = 10_000;
 const DEFAULT_OUTBOUND_HTTP_FAILFAST_TIMEOUT: Duration = Duration::from_secs(3);
+const DEFAULT_OUTBOUND_HTTP_LATENCY_OUTLIER_MIN_DURATION: Duration = Duration::from_secs(10);
+const DEFAULT_OUTBOUND_HTTP_LATENCY_OUTLIER_EJECTION: Duration = Duration::from_secs(30);
+const DEFAULT_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTION: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTED_PERCENT: f64 = 50.0;
+const DEFAULT_OUTBOUND_HTTP_RETRY_BUFFER_BYTES: usize = 64 * 1024 * 1024;
+const DEFAULT_OUTBOUND_HTTP_SOURCE_AFFINITY_CAPACITY: usize = 10_000;
+const DEFAULT_OUTBOUND_HTTP_RESPONSE_CACHE_BYTES: usize = 16 * 1024 * 1024;
+const DEFAULT_OUTBOUND_TOPOLOGY_HINTS_MIN_ENDPOINTS: usize = 1;
+const DEFAULT_OUTBOUND_ROUTE_UPDATE_DEBOUNCE: Duration = Duration::from_millis(100);
 const DEFAULT_OUTBOUND_DETECT_TIMEOUT: Duration = Duration::from_secs(10);
 const DEFAULT_OUTBOUND_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
 const DEFAULT_OUTBOUND_CONNECT_BACKOFF: ExponentialBackoff =
@@ -314,6 +992,18 @@ const DEFAULT_INITIAL_CONNECTION_WINDOW_SIZE: u32 = 1048576; // 1MB ~ 16 streams
 // 2 minutes seems like a reasonable amount of time to wait for connections to close...
 const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2 * 60);
 
+const DEFAULT_SELF_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+const DEFAULT_SELF_CHECK_FAILURE_THRESHOLD: u32 = 3;
+
+const DEFAULT_TUNABLES_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+// UDP sessions have no explicit end, so they are dropped after a period of
+// inactivity. The session limit and per-session buffer bound the forwarder's
+// memory use.
+const DEFAULT_OUTBOUND_UDP_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_OUTBOUND_UDP_MAX_SESSIONS: usize = 1_000;
+const DEFAULT_OUTBOUND_UDP_SESSION_CAPACITY: usize = 64;
+
 // This configuration limits the amount of time Linkerd retains cached clients &
 // connections for a given destination ip:port, as referenced by the application
 // client.
@@ -325,6 +1015,10 @@ const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2 * 60);
 // the application communicates with many destinations.
 const ENV_OUTBOUND_DISCOVERY_IDLE_TIMEOUT: &str = "LINKERD2_PROXY_OUTBOUND_DISCOVERY_IDLE_TIMEOUT";
 const DEFAULT_OUTBOUND_DISCOVERY_IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_OUTBOUND_DISCOVERY_RETAIN_MAX_PERIOD: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_OUTBOUND_DISCOVERY_RETAIN_MAX_ENTRIES: usize = 100;
+const DEFAULT_OUTBOUND_DISCOVERY_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_OUTBOUND_DISCOVERY_SNAPSHOT_MAX_AGE: Duration = Duration::from_secs(5 * 60);
 
 // On the inbound side, we may lookup per-port policy or per-service profile
 // configuration. We are more permissive in retaining inbound configuration,
@@ -356,8 +1050,16 @@ const DEFAULT_OUTBOUND_MAX_IDLE_CONNS_PER_ENDPOINT: usize = 10_000;
 const DEFAULT_INBOUND_MAX_IN_FLIGHT: usize = 100_000;
 const DEFAULT_OUTBOUND_MAX_IN_FLIGHT: usize = 100_000;
 
+const DEFAULT_INBOUND_APP_PRESSURE_LATENCY_WINDOW: Duration = Duration::from_secs(10);
+const DEFAULT_INBOUND_APP_PRESSURE_SHED_RATIO: f64 = 0.5;
+const DEFAULT_INBOUND_APP_PRESSURE_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+const DEFAULT_INBOUND_EXT_AUTHZ_TIMEOUT: Duration = Duration::from_millis(200);
+const DEFAULT_INBOUND_EXT_AUTHZ_CACHE_TTL: Duration = Duration::from_secs(10);
+
 const DEFAULT_DESTINATION_PROFILE_SUFFIXES: &str = "svc.cluster.local.";
 const DEFAULT_DESTINATION_PROFILE_SKIP_TIMEOUT: Duration = Duration::from_millis(500);
+const DEFAULT_DESTINATION_RESOLUTION_LINGER: Duration = Duration::from_secs(5);
 
 const DEFAULT_IDENTITY_MIN_REFRESH: Duration = Duration::from_secs(10);
 const DEFAULT_IDENTITY_MAX_REFRESH: Duration = Duration::from_secs(60 * 60 * 24);
@@ -371,16 +1073,208 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     // defer returning any errors until all of them have been parsed.
     let outbound_listener_addr = parse(strings, ENV_OUTBOUND_LISTEN_ADDR, parse_socket_addr);
     let outbound_listener_addrs = parse(strings, ENV_OUTBOUND_LISTEN_ADDRS, parse_socket_addrs);
+    let outbound_explicit_listener_addr = parse(
+        strings,
+        ENV_OUTBOUND_EXPLICIT_LISTEN_ADDR,
+        parse_socket_addr,
+    );
+    let outbound_socks5_listener_addr =
+        parse(strings, ENV_OUTBOUND_SOCKS5_LISTEN_ADDR, parse_socket_addr);
+    let outbound_additional_listeners = parse(
+        strings,
+        ENV_OUTBOUND_ADDITIONAL_LISTENERS,
+        parse_outbound_listeners,
+    );
     let inbound_listener_addr = parse(strings, ENV_INBOUND_LISTEN_ADDR, parse_socket_addr);
     let admin_listener_addr = parse(strings, ENV_ADMIN_LISTEN_ADDR, parse_socket_addr);
+    let admin_endpoints = parse(strings, ENV_ADMIN_ENDPOINTS, parse_admin_endpoints);
+    let admin_scrape_listener_addr =
+        parse(strings, ENV_ADMIN_SCRAPE_LISTEN_ADDR, parse_socket_addr);
 
     let inbound_detect_timeout = parse(strings, ENV_INBOUND_DETECT_TIMEOUT, parse_duration);
     let inbound_connect_timeout = parse(strings, ENV_INBOUND_CONNECT_TIMEOUT, parse_duration);
     let inbound_http_queue_capacity = parse(strings, ENV_INBOUND_HTTP_QUEUE_CAPACITY, parse_number);
     let inbound_http_failfast_timeout =
         parse(strings, ENV_INBOUND_HTTP_FAILFAST_TIMEOUT, parse_duration);
+    let inbound_http_compression_content_types = parse(
+        strings,
+        ENV_INBOUND_HTTP_COMPRESSION_CONTENT_TYPES,
+        parse_content_types,
+    );
+    let inbound_http_compression_min_size =
+        parse(strings, ENV_INBOUND_HTTP_COMPRESSION_MIN_SIZE, parse_number);
+
+    let inbound_http_client_id_header =
+        parse(strings, ENV_INBOUND_HTTP_CLIENT_ID_HEADER, parse_bool);
+    let inbound_http_client_id_unauthenticated = parse(
+        strings,
+        ENV_INBOUND_HTTP_CLIENT_ID_UNAUTHENTICATED,
+        parse_header_value,
+    );
+    let inbound_http_client_id_strict =
+        parse(strings, ENV_INBOUND_HTTP_CLIENT_ID_STRICT, parse_bool);
+    let inbound_forward_targets = parse(
+        strings,
+        ENV_INBOUND_FORWARD_TARGETS,
+        parse_inbound_forward_targets,
+    );
+    let inbound_http2_ports = parse(
+        strings,
+        ENV_INBOUND_PORTS_HTTP2_SETTINGS,
+        parse_inbound_http2_ports,
+    );
+
+    let http_request_id_header = parse(strings, ENV_HTTP_REQUEST_ID_HEADER, parse_header_name);
+    let http_request_id_overwrite = parse(strings, ENV_HTTP_REQUEST_ID_OVERWRITE, parse_bool);
+
+    let http_deadline_propagation = parse(strings, ENV_HTTP_DEADLINE_PROPAGATION, parse_bool);
+    let http_deadline_header = parse(strings, ENV_HTTP_DEADLINE_HEADER, parse_header_name);
+    let http_deadline_timeout_header =
+        parse(strings, ENV_HTTP_DEADLINE_TIMEOUT_HEADER, parse_header_name);
+    let http_deadline_min_budget = parse(strings, ENV_HTTP_DEADLINE_MIN_BUDGET, parse_duration);
 
     let outbound_detect_timeout = parse(strings, ENV_OUTBOUND_DETECT_TIMEOUT, parse_duration);
+    let outbound_latency_outlier_ratio = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_RATIO,
+        parse_number,
+    );
+    let outbound_latency_outlier_min_duration = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_MIN_DURATION,
+        parse_duration,
+    );
+    let outbound_latency_outlier_ejection = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_EJECTION,
+        parse_duration,
+    );
+    let outbound_latency_outlier_max_ejection = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTION,
+        parse_duration,
+    );
+    let outbound_latency_outlier_max_ejected_percent = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTED_PERCENT,
+        parse_number,
+    );
+    let outbound_source_affinity_suffixes = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_SOURCE_AFFINITY_SUFFIXES,
+        parse_dns_suffixes,
+    );
+    let outbound_source_affinity_capacity = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_SOURCE_AFFINITY_CAPACITY,
+        parse_number,
+    );
+    let outbound_http_retry_buffer_bytes =
+        parse(strings, ENV_OUTBOUND_HTTP_RETRY_BUFFER_BYTES, parse_number);
+    let outbound_http_response_cache_bytes = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_RESPONSE_CACHE_BYTES,
+        parse_number,
+    );
+    let outbound_http_fault_injection_seed = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_FAULT_INJECTION_SEED,
+        parse_number,
+    );
+    let outbound_http_route_debug_header =
+        parse(strings, ENV_OUTBOUND_HTTP_ROUTE_DEBUG_HEADER, parse_bool);
+    let outbound_http_hairpin = parse(strings, ENV_OUTBOUND_HTTP_HAIRPIN, parse_bool);
+    let outbound_http_upgrade_probe_ttl =
+        parse(strings, ENV_OUTBOUND_HTTP_UPGRADE_PROBE_TTL, parse_duration);
+    let outbound_http_request_timings_threshold = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_REQUEST_TIMINGS_THRESHOLD,
+        parse_duration,
+    );
+    let outbound_http_keepalive_exempt = parse(
+        strings,
+        ENV_OUTBOUND_HTTP_KEEPALIVE_EXEMPT,
+        parse_keepalive_exempt,
+    );
+    let outbound_http_logical_max_age =
+        parse(strings, ENV_OUTBOUND_HTTP_LOGICAL_MAX_AGE, parse_duration);
+    let outbound_http1_request_body_buffer_limit = parse(
+        strings,
+        ENV_OUTBOUND_HTTP1_REQUEST_BODY_BUFFER_LIMIT,
+        parse_number,
+    );
+    let outbound_http2_request_body_buffer_limit = parse(
+        strings,
+        ENV_OUTBOUND_HTTP2_REQUEST_BODY_BUFFER_LIMIT,
+        parse_number,
+    );
+    let outbound_tcp_splice = parse(strings, ENV_OUTBOUND_TCP_SPLICE, parse_bool);
+    let outbound_tls_plaintext_http_response = parse(
+        strings,
+        ENV_OUTBOUND_TLS_PLAINTEXT_HTTP_RESPONSE,
+        parse_bool,
+    );
+    let outbound_http_version_hint = parse(strings, ENV_OUTBOUND_HTTP_VERSION_HINT, parse_bool);
+    let outbound_topology_zone = strings.get(ENV_OUTBOUND_TOPOLOGY_ZONE);
+    let outbound_topology_hints_min_endpoints = parse(
+        strings,
+        ENV_OUTBOUND_TOPOLOGY_HINTS_MIN_ENDPOINTS,
+        parse_number,
+    );
+    let outbound_balancer_ewma_decay =
+        parse(strings, ENV_OUTBOUND_BALANCER_EWMA_DECAY, parse_duration);
+    let outbound_balancer_ewma_default_rtt = parse(
+        strings,
+        ENV_OUTBOUND_BALANCER_EWMA_DEFAULT_RTT,
+        parse_duration,
+    );
+    let outbound_balancer_endpoint_load_metrics = parse(
+        strings,
+        ENV_OUTBOUND_BALANCER_ENDPOINT_LOAD_METRICS,
+        parse_bool,
+    );
+    let outbound_route_update_debounce =
+        parse(strings, ENV_OUTBOUND_ROUTE_UPDATE_DEBOUNCE, parse_duration);
+    let outbound_profiles_disabled = parse(strings, ENV_OUTBOUND_PROFILES_DISABLED, parse_bool);
+    let outbound_discovery_unmap_ipv4 =
+        parse(strings, ENV_OUTBOUND_DISCOVERY_UNMAP_IPV4, parse_bool);
+    let outbound_logical_name_labels_limit = parse(
+        strings,
+        ENV_OUTBOUND_LOGICAL_NAME_LABELS_LIMIT,
+        parse_number,
+    );
+    let outbound_mesh_h2_adaptive = parse(
+        strings,
+        ENV_OUTBOUND_MESH_HTTP2_ADAPTIVE_FLOW_CONTROL,
+        parse_bool,
+    );
+    let inbound_mesh_h2_adaptive = parse(
+        strings,
+        ENV_INBOUND_MESH_HTTP2_ADAPTIVE_FLOW_CONTROL,
+        parse_bool,
+    );
+    let outbound_tcp_half_close =
+        parse(strings, ENV_OUTBOUND_TCP_HALF_CLOSE, parse_half_close_ports);
+    let outbound_tcp_connect_retries =
+        parse(strings, ENV_OUTBOUND_TCP_CONNECT_RETRIES, parse_number);
+    let outbound_connection_max_lifetimes = parse(
+        strings,
+        ENV_OUTBOUND_CONNECTION_MAX_LIFETIMES,
+        parse_connection_lifetimes,
+    );
+    let outbound_connection_lifetime_jitter = parse(
+        strings,
+        ENV_OUTBOUND_CONNECTION_LIFETIME_JITTER,
+        parse_number::<f64>,
+    );
+    let outbound_connection_quiet_period = parse(
+        strings,
+        ENV_OUTBOUND_CONNECTION_QUIET_PERIOD,
+        parse_duration,
+    );
+    let outbound_prewarm_addrs = parse(strings, ENV_OUTBOUND_PREWARM_ADDRS, parse_socket_addr_list);
+    let outbound_prewarm_endpoints = parse(strings, ENV_OUTBOUND_PREWARM_ENDPOINTS, parse_bool);
+    let outbound_port_mappings = parse(strings, ENV_OUTBOUND_PORT_MAPPINGS, parse_port_mappings);
     let outbound_tcp_queue_capacity = parse(strings, ENV_OUTBOUND_TCP_QUEUE_CAPACITY, parse_number);
     let outbound_tcp_failfast_timeout =
         parse(strings, ENV_OUTBOUND_TCP_FAILFAST_TIMEOUT, parse_duration);
@@ -408,10 +1302,58 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
     let shutdown_grace_period = parse(strings, ENV_SHUTDOWN_GRACE_PERIOD, parse_duration);
 
+    let startup_await_policy = parse(strings, ENV_STARTUP_AWAIT_POLICY, parse_bool);
+    let startup_timeout = parse(strings, ENV_STARTUP_TIMEOUT, parse_duration_opt);
+    let startup_timeout_mode = parse(strings, ENV_STARTUP_TIMEOUT_MODE, |s| {
+        if s.eq_ignore_ascii_case("release") {
+            Ok(startup::OnTimeout::Release)
+        } else if s.eq_ignore_ascii_case("reject") {
+            Ok(startup::OnTimeout::Reject)
+        } else {
+            Err(ParseError::NotAStartupTimeoutMode(s.to_string()))
+        }
+    });
+    let startup_connection_capacity = parse(strings, ENV_STARTUP_CONNECTION_CAPACITY, parse_number);
+
+    let orig_dst_fallback_enabled = parse(strings, ENV_ORIG_DST_FALLBACK, parse_bool);
+    let orig_dst_fallback_ports = parse(strings, ENV_ORIG_DST_FALLBACK_PORTS, parse_port_range_set);
+
+    let outbound_udp_addr = parse(strings, ENV_OUTBOUND_UDP_LISTEN_ADDR, parse_socket_addr);
+    let outbound_udp_idle_timeout = parse(strings, ENV_OUTBOUND_UDP_IDLE_TIMEOUT, parse_duration);
+    let outbound_udp_max_sessions = parse(strings, ENV_OUTBOUND_UDP_MAX_SESSIONS, parse_number);
+    let outbound_udp_session_capacity =
+        parse(strings, ENV_OUTBOUND_UDP_SESSION_CAPACITY, parse_number);
+
     let inbound_discovery_idle_timeout =
         parse(strings, ENV_INBOUND_DISCOVERY_IDLE_TIMEOUT, parse_duration);
     let outbound_discovery_idle_timeout =
         parse(strings, ENV_OUTBOUND_DISCOVERY_IDLE_TIMEOUT, parse_duration);
+    let outbound_discovery_retain_periodic =
+        parse(strings, ENV_OUTBOUND_DISCOVERY_RETAIN_PERIODIC, parse_bool);
+    let outbound_discovery_retain_max_period = parse(
+        strings,
+        ENV_OUTBOUND_DISCOVERY_RETAIN_MAX_PERIOD,
+        parse_duration,
+    );
+    let outbound_discovery_retain_max_entries = parse(
+        strings,
+        ENV_OUTBOUND_DISCOVERY_RETAIN_MAX_ENTRIES,
+        parse_number,
+    );
+    let outbound_discovery_snapshot_path =
+        parse(strings, ENV_OUTBOUND_DISCOVERY_SNAPSHOT_PATH, |s| {
+            Ok(PathBuf::from(s))
+        });
+    let outbound_discovery_snapshot_interval = parse(
+        strings,
+        ENV_OUTBOUND_DISCOVERY_SNAPSHOT_INTERVAL,
+        parse_duration,
+    );
+    let outbound_discovery_snapshot_max_age = parse(
+        strings,
+        ENV_OUTBOUND_DISCOVERY_SNAPSHOT_MAX_AGE,
+        parse_duration,
+    );
 
     let inbound_max_idle_per_endpoint = parse(
         strings,
@@ -426,6 +1368,33 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
 
     let inbound_max_in_flight = parse(strings, ENV_INBOUND_MAX_IN_FLIGHT, parse_number);
     let outbound_max_in_flight = parse(strings, ENV_OUTBOUND_MAX_IN_FLIGHT, parse_number);
+    let inbound_app_pressure_max_in_flight = parse(
+        strings,
+        ENV_INBOUND_APP_PRESSURE_MAX_IN_FLIGHT,
+        parse_number,
+    );
+    let inbound_app_pressure_max_p99_latency = parse(
+        strings,
+        ENV_INBOUND_APP_PRESSURE_MAX_P99_LATENCY,
+        parse_duration,
+    );
+    let inbound_app_pressure_latency_window = parse(
+        strings,
+        ENV_INBOUND_APP_PRESSURE_LATENCY_WINDOW,
+        parse_duration,
+    );
+    let inbound_app_pressure_shed_ratio =
+        parse(strings, ENV_INBOUND_APP_PRESSURE_SHED_RATIO, parse_number);
+    let inbound_app_pressure_retry_after = parse(
+        strings,
+        ENV_INBOUND_APP_PRESSURE_RETRY_AFTER,
+        parse_duration,
+    );
+    let inbound_silent_connection_timeout = parse(
+        strings,
+        ENV_INBOUND_SILENT_CONNECTION_TIMEOUT,
+        parse_duration,
+    );
 
     let metrics_retain_idle = parse(strings, ENV_METRICS_RETAIN_IDLE, parse_duration);
 
@@ -439,6 +1408,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     let dns_max_ttl = parse(strings, ENV_DNS_MAX_TTL, parse_duration);
 
     let tls = parse_tls_params(strings);
+    let workload_identity = parse_workload_identity(strings, tls.as_ref().ok());
 
     let hostname = strings.get(ENV_HOSTNAME);
 
@@ -447,10 +1417,33 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
     let trace_otel_attributes = strings.get(ENV_OTEL_TRACE_ATTRIBUTES);
     let trace_protocol = strings.get(ENV_TRACE_PROTOCOL);
     let trace_service_name = strings.get(ENV_TRACE_SERVICE_NAME);
+    let trace_max_spans_per_second = parse(
+        strings,
+        ENV_TRACE_MAX_SPANS_PER_SECOND,
+        parse_number::<NonZeroU32>,
+    );
 
     let trace_collector_addr = parse_control_addr(strings, ENV_TRACE_COLLECTOR_SVC_BASE);
 
     let gateway_suffixes = parse(strings, ENV_INBOUND_GATEWAY_SUFFIXES, parse_dns_suffixes);
+    let gateway_authorizations = parse(
+        strings,
+        ENV_INBOUND_GATEWAY_AUTHORIZATIONS,
+        parse_gateway_authorizations,
+    );
+
+    let ext_authz_addr = parse_control_addr(strings, ENV_INBOUND_EXT_AUTHZ_SVC_BASE);
+    let ext_authz_timeout = parse(strings, ENV_INBOUND_EXT_AUTHZ_TIMEOUT, parse_duration);
+    let ext_authz_failure_mode = parse(strings, ENV_INBOUND_EXT_AUTHZ_FAILURE_MODE, |s| {
+        if s.eq_ignore_ascii_case("open") {
+            Ok(inbound::ExtAuthzFailureMode::Open)
+        } else if s.eq_ignore_ascii_case("closed") {
+            Ok(inbound::ExtAuthzFailureMode::Closed)
+        } else {
+            Err(ParseError::NotAnExtAuthzFailureMode(s.to_string()))
+        }
+    });
+    let ext_authz_cache_ttl = parse(strings, ENV_INBOUND_EXT_AUTHZ_CACHE_TTL, parse_duration);
 
     let dst_addr = parse_control_addr(strings, ENV_DESTINATION_SVC_BASE);
     let dst_token = strings.get(ENV_DESTINATION_CONTEXT);
@@ -459,6 +1452,12 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         ENV_DESTINATION_PROFILE_INITIAL_TIMEOUT,
         parse_duration,
     );
+    let dst_profile_retry_timeout = parse(
+        strings,
+        ENV_DESTINATION_PROFILE_RETRY_TIMEOUT,
+        parse_duration,
+    );
+    let dst_resolution_linger = parse(strings, ENV_DESTINATION_RESOLUTION_LINGER, parse_duration);
     let dst_profile_suffixes = parse(
         strings,
         ENV_DESTINATION_PROFILE_SUFFIXES,
@@ -488,6 +1487,26 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         std::sync::Arc::new(ips)
     };
 
+    let http_request_id = {
+        let overwrite = http_request_id_overwrite?.unwrap_or(false);
+        http_request_id_header?.map(|header| request_id::Config { header, overwrite })
+    };
+
+    let http_deadline = {
+        let deadline_header =
+            http_deadline_header?.unwrap_or_else(|| http::HeaderName::from_static("x-deadline"));
+        let timeout_header = http_deadline_timeout_header?
+            .unwrap_or_else(|| http::HeaderName::from_static("grpc-timeout"));
+        let min_budget = http_deadline_min_budget?.unwrap_or_default();
+        http_deadline_propagation?
+            .unwrap_or(false)
+            .then(|| stream_timeouts::DeadlineConfig {
+                deadline_header,
+                timeout_header,
+                min_budget,
+            })
+    };
+
     let outbound = {
         let ingress_mode = parse(strings, ENV_INGRESS_MODE, parse_bool)?.unwrap_or(false);
 
@@ -518,8 +1537,63 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             user_timeout,
             http2: http2::parse_server(strings, "LINKERD2_PROXY_OUTBOUND_SERVER_HTTP2")?,
         };
+        let explicit_proxy = outbound_explicit_listener_addr?.map(|addr| ServerConfig {
+            addr: DualListenAddr(addr, None),
+            keepalive: server.keepalive,
+            user_timeout: server.user_timeout,
+            http2: server.http2.clone(),
+        });
+        let socks5_proxy = match outbound_socks5_listener_addr? {
+            None => None,
+            Some(addr) => Some(outbound::Socks5Config {
+                server: ServerConfig {
+                    addr: DualListenAddr(addr, None),
+                    keepalive: server.keepalive,
+                    user_timeout: server.user_timeout,
+                    http2: server.http2.clone(),
+                },
+                credentials: parse_socks5_credentials(strings)?,
+            }),
+        };
+        let additional_listeners = outbound_additional_listeners?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, addr, overrides)| outbound::ListenerConfig {
+                name,
+                server: ServerConfig {
+                    addr: DualListenAddr(addr, None),
+                    keepalive: server.keepalive,
+                    user_timeout: server.user_timeout,
+                    http2: server.http2.clone(),
+                },
+                overrides,
+            })
+            .collect();
         let discovery_idle_timeout =
             outbound_discovery_idle_timeout?.unwrap_or(DEFAULT_OUTBOUND_DISCOVERY_IDLE_TIMEOUT);
+        let discovery_retention = {
+            let max_period = outbound_discovery_retain_max_period?
+                .unwrap_or(DEFAULT_OUTBOUND_DISCOVERY_RETAIN_MAX_PERIOD);
+            let max_entries = outbound_discovery_retain_max_entries?
+                .unwrap_or(DEFAULT_OUTBOUND_DISCOVERY_RETAIN_MAX_ENTRIES);
+            outbound_discovery_retain_periodic?
+                .unwrap_or(true)
+                .then_some(PeriodicConfig {
+                    max_period,
+                    max_entries,
+                })
+        };
+        let discovery_snapshot = {
+            let interval = outbound_discovery_snapshot_interval?
+                .unwrap_or(DEFAULT_OUTBOUND_DISCOVERY_SNAPSHOT_INTERVAL);
+            let max_age = outbound_discovery_snapshot_max_age?
+                .unwrap_or(DEFAULT_OUTBOUND_DISCOVERY_SNAPSHOT_MAX_AGE);
+            outbound_discovery_snapshot_path?.map(|path| outbound::DiscoverySnapshotConfig {
+                path,
+                interval,
+                max_age,
+            })
+        };
         let max_idle =
             outbound_max_idle_per_endpoint?.unwrap_or(DEFAULT_OUTBOUND_MAX_IDLE_CONNS_PER_ENDPOINT);
         let keepalive = Keepalive(outbound_connect_keepalive?);
@@ -565,9 +1639,126 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         let http_failfast_timeout =
             outbound_http_failfast_timeout?.unwrap_or(DEFAULT_OUTBOUND_HTTP_FAILFAST_TIMEOUT);
 
+        let http_latency_outliers = {
+            let min_outlier_duration = outbound_latency_outlier_min_duration?
+                .unwrap_or(DEFAULT_OUTBOUND_HTTP_LATENCY_OUTLIER_MIN_DURATION);
+            let base_ejection = outbound_latency_outlier_ejection?
+                .unwrap_or(DEFAULT_OUTBOUND_HTTP_LATENCY_OUTLIER_EJECTION);
+            let max_ejection = outbound_latency_outlier_max_ejection?
+                .unwrap_or(DEFAULT_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTION);
+            let max_ejected_percent = outbound_latency_outlier_max_ejected_percent?
+                .unwrap_or(DEFAULT_OUTBOUND_HTTP_LATENCY_OUTLIER_MAX_EJECTED_PERCENT);
+            outbound_latency_outlier_ratio?.map(|max_latency_ratio| {
+                outbound::http::LatencyOutlierConfig {
+                    max_latency_ratio,
+                    min_outlier_duration,
+                    base_ejection,
+                    max_ejection: max_ejection.max(base_ejection),
+                    max_ejected_ratio: (max_ejected_percent / 100.0).clamp(0.0, 1.0),
+                }
+            })
+        };
+
+        let http_source_affinity = {
+            let capacity = outbound_source_affinity_capacity?
+                .unwrap_or(DEFAULT_OUTBOUND_HTTP_SOURCE_AFFINITY_CAPACITY);
+            outbound_source_affinity_suffixes?
+                .filter(|suffixes| !suffixes.is_empty())
+                .map(|suffixes| outbound::http::concrete::SourceAffinityConfig {
+                    backends: suffixes.into_iter().collect(),
+                    capacity,
+                })
+        };
+
+        let http_hairpin = outbound_http_hairpin?.unwrap_or(true).then(|| {
+            outbound::http::concrete::HairpinConfig {
+                local_id: tls.as_ref().ok().map(|tls| tls.id.clone()),
+            }
+        });
+
+        let connection_lifetimes = {
+            let jitter = outbound_connection_lifetime_jitter?
+                .unwrap_or(DEFAULT_OUTBOUND_CONNECTION_LIFETIME_JITTER);
+            if !(0.0..=1.0).contains(&jitter) {
+                error!("{ENV_OUTBOUND_CONNECTION_LIFETIME_JITTER} must be between 0 and 1");
+                return Err(EnvError::InvalidEnvVar);
+            }
+            let quiet = outbound_connection_quiet_period?
+                .unwrap_or(DEFAULT_OUTBOUND_CONNECTION_QUIET_PERIOD);
+            outbound::ConnectionLifetimes::new(
+                outbound_connection_max_lifetimes?.unwrap_or_default(),
+                jitter,
+                quiet,
+            )
+        };
+
+        let min_endpoints = outbound_topology_hints_min_endpoints?
+            .unwrap_or(DEFAULT_OUTBOUND_TOPOLOGY_HINTS_MIN_ENDPOINTS);
+        let topology_hints = outbound_topology_zone?
+            .filter(|zone| !zone.is_empty())
+            .map(|zone| outbound::TopologyHintsConfig {
+                zone,
+                min_endpoints,
+            });
+
+        let balancer_ewma = {
+            let decay = outbound_balancer_ewma_decay?;
+            let default_rtt = outbound_balancer_ewma_default_rtt?;
+            for (name, d) in [
+                (ENV_OUTBOUND_BALANCER_EWMA_DECAY, decay),
+                (ENV_OUTBOUND_BALANCER_EWMA_DEFAULT_RTT, default_rtt),
+            ] {
+                if d.is_some_and(|d| d.is_zero()) {
+                    error!("{name} must be greater than zero");
+                    return Err(EnvError::InvalidEnvVar);
+                }
+            }
+            outbound::http::balance::EwmaOverrides { decay, default_rtt }
+        };
+
         outbound::Config {
+            http_workload_identity: workload_identity
+                .as_ref()
+                .ok()
+                .and_then(|(signer, _)| signer.clone()),
             ingress_mode,
             emit_headers: !disable_headers,
+            http_request_id: http_request_id.clone(),
+            http_deadline: http_deadline.clone(),
+            http_latency_outliers,
+            http_source_affinity,
+            http_hairpin,
+            http2_mesh_adaptive_flow_control: outbound_mesh_h2_adaptive?.unwrap_or(false),
+            http_retry_buffer_bytes: outbound_http_retry_buffer_bytes?
+                .unwrap_or(DEFAULT_OUTBOUND_HTTP_RETRY_BUFFER_BYTES),
+            http_response_cache_bytes: outbound_http_response_cache_bytes?
+                .unwrap_or(DEFAULT_OUTBOUND_HTTP_RESPONSE_CACHE_BYTES),
+            http_fault_injection_seed: outbound_http_fault_injection_seed?,
+            http_profile_retry_timeout: dst_profile_retry_timeout?,
+            http_route_debug_header: outbound_http_route_debug_header?.unwrap_or(false),
+            http_upgrade_probe_ttl: outbound_http_upgrade_probe_ttl?,
+            http_request_timings_threshold: outbound_http_request_timings_threshold?,
+            http_keepalive_exempt: outbound_http_keepalive_exempt?.unwrap_or_default(),
+            http_logical_max_age: outbound_http_logical_max_age?,
+            http_request_body_buffer: outbound::http::BodyBufferLimits {
+                http1: outbound_http1_request_body_buffer_limit?,
+                http2: outbound_http2_request_body_buffer_limit?,
+            },
+            tcp_splice: outbound_tcp_splice?.unwrap_or(false),
+            tls_plaintext_http_response: outbound_tls_plaintext_http_response?.unwrap_or(true),
+            http_version_hint: outbound_http_version_hint?.unwrap_or(false),
+            tcp_half_close: std::sync::Arc::new(outbound_tcp_half_close?.unwrap_or_default()),
+            tcp_connect_retries: outbound_tcp_connect_retries?.unwrap_or(0),
+            connection_lifetimes,
+            explicit_proxy,
+            socks5_proxy,
+            listener: Default::default(),
+            additional_listeners,
+            prewarm: outbound::PrewarmConfig {
+                addrs: outbound_prewarm_addrs?.unwrap_or_default(),
+                endpoints: outbound_prewarm_endpoints?.unwrap_or(false),
+            },
+            port_mappings: outbound_port_mappings?.unwrap_or_default(),
             allow_discovery: AddrMatch::new(dst_profile_suffixes.clone(), dst_profile_networks),
             proxy: ProxyConfig {
                 server,
@@ -578,6 +1769,9 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             },
             inbound_ips: inbound_ips.clone(),
             discovery_idle_timeout,
+            discovery_retention,
+            discovery_snapshot,
+            discovery_unmap_ipv4: outbound_discovery_unmap_ipv4?.unwrap_or(true),
             tcp_connection_queue: QueueConfig {
                 capacity: tcp_queue_capacity,
                 failfast_timeout: tcp_failfast_timeout,
@@ -586,15 +1780,26 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 capacity: http_queue_capacity,
                 failfast_timeout: http_failfast_timeout,
             },
+            topology_hints,
+            balancer_ewma,
+            balancer_endpoint_load_metrics: outbound_balancer_endpoint_load_metrics?
+                .unwrap_or(false),
+            route_update_debounce: outbound_route_update_debounce?
+                .unwrap_or(DEFAULT_OUTBOUND_ROUTE_UPDATE_DEBOUNCE),
+            discover_profiles: !outbound_profiles_disabled?.unwrap_or(false),
+            logical_name_labels_limit: outbound_logical_name_labels_limit?
+                .unwrap_or(DEFAULT_OUTBOUND_LOGICAL_NAME_LABELS_LIMIT),
         }
     };
 
     let gateway = gateway::Config {
         allow_discovery: gateway_suffixes?.into_iter().flatten().collect(),
+        authorizations: gateway_authorizations?.unwrap_or_default(),
     };
 
     let admin_listener_addr = admin_listener_addr?
         .unwrap_or_else(|| parse_socket_addr(DEFAULT_ADMIN_LISTEN_ADDR).unwrap());
+    let admin_scrape_listener_addr = admin_scrape_listener_addr?;
 
     let inbound = {
         let addr = DualListenAddr(
@@ -701,8 +1906,11 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 ports.insert(inbound_port);
             }
 
-            // Ensure that the admin server port is included in policy discovery.
+            // Ensure that the admin server ports are included in policy discovery.
             ports.insert(admin_listener_addr.port());
+            if let Some(addr) = admin_scrape_listener_addr {
+                ports.insert(addr.port());
+            }
 
             // Determine any pre-configured opaque ports.
             let opaque_ports = parse(
@@ -715,14 +1923,102 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             // and that's fine.
             .unwrap_or_default();
 
+            // Connections on opaque ports are never served as HTTP, so HTTP/2
+            // settings for these ports have no effect.
+            if let Ok(Some(http2_ports)) = &inbound_http2_ports {
+                for port in http2_ports.keys().filter(|p| opaque_ports.contains(p)) {
+                    warn!(
+                        "{ENV_INBOUND_PORTS_HTTP2_SETTINGS} has no effect on port {port}, \
+                        which is set in {ENV_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION}"
+                    );
+                }
+            }
+
+            let routes = parse(
+                strings,
+                ENV_INBOUND_ROUTE_OVERRIDES,
+                parse_inbound_route_overrides,
+            )?
+            .unwrap_or_default();
+            let servers = parse(
+                strings,
+                ENV_INBOUND_SERVER_OVERRIDES,
+                parse_inbound_server_overrides,
+            )?
+            .unwrap_or_default();
+
             inbound::policy::Config::Discover {
                 default,
                 ports,
                 cache_max_idle_age: discovery_idle_timeout,
                 opaque_ports,
+                routes,
+                servers,
+            }
+        };
+
+        // Ensure that connections are never forwarded back to one of the
+        // proxy's own listeners.
+        let forward_targets = {
+            let mut proxy_ports =
+                vec![ListenAddr(server.addr.0).port(), admin_listener_addr.port()];
+            proxy_ports.extend(admin_scrape_listener_addr.map(|a| a.port()));
+            if let Ok(Some((addr, _))) = &tap {
+                proxy_ports.push(addr.port());
+            }
+            let outbound_servers = std::iter::once(&outbound.proxy.server)
+                .chain(outbound.explicit_proxy.as_ref())
+                .chain(outbound.socks5_proxy.as_ref().map(|socks5| &socks5.server))
+                .chain(outbound.additional_listeners.iter().map(|l| &l.server));
+            for server in outbound_servers {
+                let DualListenAddr(addr, addr2) = server.addr;
+                proxy_ports.extend(std::iter::once(addr).chain(addr2).map(|a| a.port()));
             }
+            inbound::ForwardTargets::new(
+                inbound_forward_targets?.unwrap_or_default(),
+                &inbound_ips,
+                &proxy_ports,
+            )
+            .map_err(|error| {
+                error!(%error, "Invalid {ENV_INBOUND_FORWARD_TARGETS}");
+                EnvError::InvalidEnvVar
+            })?
         };
 
+        let app_pressure = {
+            let max_in_flight = inbound_app_pressure_max_in_flight?;
+            let max_p99_latency = inbound_app_pressure_max_p99_latency?;
+            let shed_ratio =
+                inbound_app_pressure_shed_ratio?.unwrap_or(DEFAULT_INBOUND_APP_PRESSURE_SHED_RATIO);
+            if !(0.0..=1.0).contains(&shed_ratio) {
+                error!("{ENV_INBOUND_APP_PRESSURE_SHED_RATIO} must be between 0 and 1");
+                return Err(EnvError::InvalidEnvVar);
+            }
+            let latency_window = inbound_app_pressure_latency_window?
+                .unwrap_or(DEFAULT_INBOUND_APP_PRESSURE_LATENCY_WINDOW);
+            let retry_after = inbound_app_pressure_retry_after?
+                .unwrap_or(DEFAULT_INBOUND_APP_PRESSURE_RETRY_AFTER);
+            (max_in_flight.is_some() || max_p99_latency.is_some()).then_some(
+                inbound::AppPressureConfig {
+                    max_in_flight,
+                    max_p99_latency,
+                    latency_window,
+                    shed_ratio,
+                    retry_after,
+                },
+            )
+        };
+
+        // Silent connections are only worth reaping before detection would
+        // otherwise time out.
+        let silent_connection_timeout = inbound_silent_connection_timeout?;
+        if silent_connection_timeout.is_some_and(|t| t >= detect_protocol_timeout) {
+            error!(
+                "{ENV_INBOUND_SILENT_CONNECTION_TIMEOUT} must be shorter than {ENV_INBOUND_DETECT_TIMEOUT}"
+            );
+            return Err(EnvError::InvalidEnvVar);
+        }
+
         inbound::Config {
             allow_discovery: dst_profile_suffixes.into_iter().collect(),
             proxy: ProxyConfig {
@@ -745,6 +2041,24 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                     .unwrap_or(DEFAULT_INBOUND_HTTP_FAILFAST_TIMEOUT),
             },
             unsafe_authority_labels,
+            http_compression: compress::Config {
+                content_types: inbound_http_compression_content_types?.unwrap_or_default(),
+                min_size: inbound_http_compression_min_size?
+                    .unwrap_or(DEFAULT_INBOUND_HTTP_COMPRESSION_MIN_SIZE),
+            },
+            http_request_id,
+            http_deadline,
+            http_workload_identity: workload_identity?.1,
+            http_client_id_header: inbound::ClientIdHeaderConfig {
+                inject: inbound_http_client_id_header?.unwrap_or(true),
+                unauthenticated: inbound_http_client_id_unauthenticated?,
+                strict: inbound_http_client_id_strict?.unwrap_or(true),
+            },
+            forward_targets,
+            http2_ports: inbound::Http2PortSettings::new(inbound_http2_ports?.unwrap_or_default()),
+            http2_mesh_adaptive_flow_control: inbound_mesh_h2_adaptive?.unwrap_or(false),
+            app_pressure,
+            silent_connection_timeout,
         }
     };
 
@@ -776,6 +2090,8 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                 },
             },
             limits,
+            resolution_linger: dst_resolution_linger?
+                .unwrap_or(DEFAULT_DESTINATION_RESOLUTION_LINGER),
         }
     };
 
@@ -812,12 +2128,57 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         };
         let export_hostname_labels =
             parse(strings, ENV_OUTBOUND_METRICS_HOSTNAME_LABELS, parse_bool)?.unwrap_or(false);
+        let export_method_labels =
+            parse(strings, ENV_OUTBOUND_METRICS_METHOD_LABELS, parse_bool)?.unwrap_or(false);
+        let outbound_routes = parse(
+            strings,
+            ENV_OUTBOUND_ROUTE_OVERRIDES,
+            parse_outbound_route_overrides,
+        )?
+        .unwrap_or_default();
+        let outbound_parents = parse(
+            strings,
+            ENV_OUTBOUND_PARENT_OVERRIDES,
+            parse_outbound_parent_overrides,
+        )?
+        .unwrap_or_default();
 
         policy::Config {
             control,
             workload,
             limits,
             export_hostname_labels,
+            export_method_labels,
+            outbound_routes,
+            outbound_parents,
+        }
+    };
+
+    let admin_scrape = match admin_scrape_listener_addr {
+        None => None,
+        Some(addr) => {
+            let permitted_client_ids =
+                parse(strings, ENV_ADMIN_SCRAPE_IDENTITIES, parse_client_ids)?.unwrap_or_default();
+            if permitted_client_ids.is_empty() {
+                error!("{ENV_ADMIN_SCRAPE_IDENTITIES} must be set when {ENV_ADMIN_SCRAPE_LISTEN_ADDR} is set");
+                return Err(EnvError::InvalidEnvVar);
+            }
+            let endpoints = parse(strings, ENV_ADMIN_SCRAPE_ENDPOINTS, parse_admin_endpoints)?
+                .unwrap_or_else(super::admin::Endpoints::read_only);
+            if endpoints.has_local_only() {
+                error!("{ENV_ADMIN_SCRAPE_ENDPOINTS} may only include read-only endpoints");
+                return Err(EnvError::InvalidEnvVar);
+            }
+            Some(super::admin::ScrapeConfig {
+                server: ServerConfig {
+                    addr: DualListenAddr(addr, None),
+                    keepalive: inbound.proxy.server.keepalive,
+                    user_timeout: inbound.proxy.server.user_timeout,
+                    http2: inbound.proxy.server.http2.clone(),
+                },
+                endpoints,
+                permitted_client_ids,
+            })
         }
     };
 
@@ -829,6 +2190,8 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             user_timeout: inbound.proxy.server.user_timeout,
             http2: inbound.proxy.server.http2.clone(),
         },
+        endpoints: admin_endpoints?.unwrap_or_default(),
+        scrape: admin_scrape,
 
         // TODO(ver) Currently we always enable profiling when the pprof feature
         // is enabled. In the future, this should be driven by runtime
@@ -894,10 +2257,38 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                     },
                 },
                 kind: trace_protocol,
+                max_spans_per_second: trace_max_spans_per_second?,
             }))
         }
     };
 
+    let ext_authz = match ext_authz_addr? {
+        None => None,
+        Some(addr) => {
+            let connect = if addr.addr.is_loopback() {
+                inbound.proxy.connect.clone()
+            } else {
+                outbound.proxy.connect.clone()
+            };
+            Some(ext_authz::Config {
+                control: ControlConfig {
+                    addr,
+                    connect,
+                    buffer: QueueConfig {
+                        capacity: DEFAULT_CONTROL_QUEUE_CAPACITY,
+                        failfast_timeout: DEFAULT_CONTROL_FAILFAST_TIMEOUT,
+                    },
+                },
+                check: inbound::ExtAuthzConfig {
+                    timeout: ext_authz_timeout?.unwrap_or(DEFAULT_INBOUND_EXT_AUTHZ_TIMEOUT),
+                    failure_mode: ext_authz_failure_mode?
+                        .unwrap_or(inbound::ExtAuthzFailureMode::Closed),
+                    cache_ttl: ext_authz_cache_ttl?.unwrap_or(DEFAULT_INBOUND_EXT_AUTHZ_CACHE_TTL),
+                },
+            })
+        }
+    };
+
     let tap = tap?
         .map(|(addr, ids)| super::tap::Config::Enabled {
             permitted_client_ids: ids,
@@ -919,6 +2310,7 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
             ENV_IDENTITY_SPIRE_SOCKET,
             |s| Ok(s.to_string()),
         )? {
+            #[cfg(feature = "spire")]
             Some(workload_api_addr) => match &tls.id {
                 // TODO: perform stricter SPIFFE ID validation following:
                 // https://github.com/spiffe/spiffe/blob/27b59b81ba8c56885ac5d4be73b35b9b3305fd7a/standards/SPIFFE-ID.md
@@ -942,6 +2334,11 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
                     return Err(EnvError::InvalidEnvVar);
                 }
             },
+            #[cfg(not(feature = "spire"))]
+            Some(_) => {
+                error!("Spire support is not enabled in this build");
+                return Err(EnvError::InvalidEnvVar);
+            }
             None => {
                 let (addr, certify) = parse_linkerd_identity_config(strings)?;
 
@@ -973,17 +2370,86 @@ pub fn parse_config<S: Strings>(strings: &S) -> Result<super::Config, EnvError>
         }
     };
 
+    let orig_dst_fallback = if orig_dst_fallback_enabled?.unwrap_or(false) {
+        let ports = orig_dst_fallback_ports?.unwrap_or_else(|| {
+            warn!(
+                "{ENV_ORIG_DST_FALLBACK} is set without {ENV_ORIG_DST_FALLBACK_PORTS}; \
+                 no connections will fall back"
+            );
+            Default::default()
+        });
+        Some(OrigDstFallback {
+            ports: ports.into(),
+        })
+    } else {
+        None
+    };
+
+    let outbound_udp = match outbound_udp_addr? {
+        Some(addr) => Some(udp::Config {
+            addr,
+            idle_timeout: outbound_udp_idle_timeout?.unwrap_or(DEFAULT_OUTBOUND_UDP_IDLE_TIMEOUT),
+            max_sessions: outbound_udp_max_sessions?.unwrap_or(DEFAULT_OUTBOUND_UDP_MAX_SESSIONS),
+            session_capacity: outbound_udp_session_capacity?
+                .unwrap_or(DEFAULT_OUTBOUND_UDP_SESSION_CAPACITY),
+        }),
+        None => None,
+    };
+
+    let self_check = match parse(strings, ENV_SELF_CHECK_INTERVAL, parse_duration)? {
+        None => None,
+        Some(interval) => {
+            let failure_threshold = parse(strings, ENV_SELF_CHECK_FAILURE_THRESHOLD, parse_number)?
+                .unwrap_or(DEFAULT_SELF_CHECK_FAILURE_THRESHOLD);
+            if failure_threshold == 0 {
+                error!("{ENV_SELF_CHECK_FAILURE_THRESHOLD} must be greater than zero");
+                return Err(EnvError::InvalidEnvVar);
+            }
+            Some(self_check::Config {
+                interval,
+                timeout: parse(strings, ENV_SELF_CHECK_TIMEOUT, parse_duration)?
+                    .unwrap_or(DEFAULT_SELF_CHECK_TIMEOUT),
+                failure_threshold,
+            })
+        }
+    };
+
+    let tunables = match parse(strings, ENV_TUNABLES_PATH, |s| Ok(PathBuf::from(s)))? {
+        None => None,
+        Some(path) => Some(tunables::Config {
+            path,
+            poll_interval: parse(strings, ENV_TUNABLES_POLL_INTERVAL, parse_duration)?
+                .unwrap_or(DEFAULT_TUNABLES_POLL_INTERVAL),
+        }),
+    };
+
+    let startup = {
+        let defaults = startup::Config::default();
+        startup::Config {
+            await_policy: startup_await_policy?.unwrap_or(defaults.await_policy),
+            timeout: startup_timeout?.flatten(),
+            on_timeout: startup_timeout_mode?.unwrap_or(defaults.on_timeout),
+            capacity: startup_connection_capacity?.unwrap_or(defaults.capacity),
+        }
+    };
+
     Ok(super::Config {
         admin,
         dns,
         dst,
         tap,
         trace_collector,
+        ext_authz,
         policy,
         identity,
         outbound,
         gateway,
         inbound,
+        orig_dst_fallback,
+        outbound_udp,
+        startup,
+        self_check,
+        tunables,
         shutdown_grace_period: shutdown_grace_period?.unwrap_or(DEFAULT_SHUTDOWN_GRACE_PERIOD),
     })
 }
@@ -1009,10 +2475,54 @@ impl Env {
     pub fn try_config(&self) -> Result<super::Config, EnvError> {
         parse_config(self)
     }
+
+    /// Validates the configuration without reporting problems via the
+    /// process's logger.
+    pub fn validate(&self) -> Validation {
+        Validation::new(self)
+    }
 }
 
 // === Parsing ===
 
+/// Parses the contents of a tunables file.
+///
+/// Each non-empty line that does not begin with `#` must set one of the
+/// [`TUNABLE_VARS`]. The file is rejected if any line is invalid.
+pub fn parse_tunables(contents: &str) -> Result<Tunables, EnvError> {
+    let mut vars = HashMap::new();
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            error!("{line:?} is not a NAME=VALUE setting");
+            return Err(EnvError::InvalidEnvVar);
+        };
+        let name = name.trim();
+        if !TUNABLE_VARS.contains(&name) {
+            error!("{name} may not be set in {ENV_TUNABLES_PATH}");
+            return Err(EnvError::InvalidEnvVar);
+        }
+        if vars.insert(name, value.trim()).is_some() {
+            error!("{name} is set more than once");
+            return Err(EnvError::InvalidEnvVar);
+        }
+    }
+
+    let access_log_sample_ratio = parse(&vars, ENV_ACCESS_LOG_SAMPLE_RATIO, parse_number::<f64>)?;
+    if access_log_sample_ratio.is_some_and(|r| !(0.0..=1.0).contains(&r)) {
+        error!("{ENV_ACCESS_LOG_SAMPLE_RATIO} must be between 0 and 1");
+        return Err(EnvError::InvalidEnvVar);
+    }
+
+    Ok(Tunables {
+        inbound_detect_timeout: parse(&vars, ENV_INBOUND_DETECT_TIMEOUT, parse_duration)?,
+        outbound_detect_timeout: parse(&vars, ENV_OUTBOUND_DETECT_TIMEOUT, parse_duration)?,
+        access_log_sample_ratio,
+    })
+}
+
 /// There is a dependency on identity being enabled for tap to work. The
 /// status of tap is determined by the ENV_TAP_SVC_NAME env variable being set
 /// or not set.
@@ -1122,7 +2632,7 @@ pub fn parse_backoff<S: Strings>(
     let max_env = format!("LINKERD2_PROXY_{base}_EXP_BACKOFF_MAX");
     let max = parse(strings, &max_env, parse_duration);
     let jitter_env = format!("LINKERD2_PROXY_{base}_EXP_BACKOFF_JITTER");
-    let jitter = parse(strings, &jitter_env, parse_number::<f64>);
+    let jitter = parse(strings, &jitter_env, parse_number);
 
     match (min?, max?, jitter?) {
         (None, None, None) => Ok(default),
@@ -1139,6 +2649,85 @@ pub fn parse_backoff<S: Strings>(
     }
 }
 
+/// Parses the keys that sign and verify workload identity assertions.
+///
+/// Keys are not parsed with [`parse`] so that invalid keys are never logged.
+fn parse_workload_identity<S: Strings>(
+    strings: &S,
+    tls: Option<&identity::TlsParams>,
+) -> Result<
+    (
+        Option<http::workload_identity::Signer>,
+        Option<http::workload_identity::Verifier>,
+    ),
+    EnvError,
+> {
+    use http::workload_identity::{Key, Signer, Verifier};
+
+    let parse_key = |name: &str| match strings.get(name)? {
+        None => Ok(None),
+        Some(s) => Key::from_hex(&s).map(Some).map_err(|error| {
+            error!("{name} is not valid: {error}");
+            EnvError::InvalidEnvVar
+        }),
+    };
+    let key = parse_key(ENV_WORKLOAD_IDENTITY_KEY)?;
+    let previous_key = parse_key(ENV_WORKLOAD_IDENTITY_PREVIOUS_KEY)?;
+    let verify = parse(strings, ENV_INBOUND_WORKLOAD_IDENTITY_VERIFY, parse_bool)?;
+    let max_age = parse(
+        strings,
+        ENV_INBOUND_WORKLOAD_IDENTITY_MAX_AGE,
+        parse_duration,
+    )?;
+
+    let Some(key) = key else {
+        if previous_key.is_some() || verify == Some(true) {
+            error!(
+                "{ENV_WORKLOAD_IDENTITY_KEY} must be set to verify workload identity assertions"
+            );
+            return Err(EnvError::InvalidEnvVar);
+        }
+        return Ok((None, None));
+    };
+
+    // Assertions are only signed when the proxy has an identity.
+    let signer = tls
+        .map(|tls| Signer::new(tls.id.clone(), key.clone()))
+        .transpose()
+        .map_err(|error| {
+            error!(%error, "Identity cannot be asserted");
+            EnvError::InvalidEnvVar
+        })?;
+    let verifier = verify.unwrap_or(false).then(|| {
+        Verifier::new(
+            std::iter::once(key).chain(previous_key),
+            max_age.unwrap_or(DEFAULT_INBOUND_WORKLOAD_IDENTITY_MAX_AGE),
+        )
+    });
+    Ok((signer, verifier))
+}
+
+fn parse_socks5_credentials<S: Strings>(
+    strings: &S,
+) -> Result<Option<outbound::Socks5Credentials>, EnvError> {
+    let username = strings.get(ENV_OUTBOUND_SOCKS5_USERNAME)?;
+    let password = strings.get(ENV_OUTBOUND_SOCKS5_PASSWORD)?;
+    match (username, password) {
+        (None, None) => Ok(None),
+        (Some(username), Some(password)) if username.len() <= 255 && password.len() <= 255 => {
+            Ok(Some(outbound::Socks5Credentials { username, password }))
+        }
+        (Some(_), Some(_)) => {
+            error!("{ENV_OUTBOUND_SOCKS5_USERNAME} and {ENV_OUTBOUND_SOCKS5_PASSWORD} must not exceed 255 bytes");
+            Err(EnvError::InvalidEnvVar)
+        }
+        _ => {
+            error!("{ENV_OUTBOUND_SOCKS5_USERNAME} and {ENV_OUTBOUND_SOCKS5_PASSWORD} must be specified together");
+            Err(EnvError::InvalidEnvVar)
+        }
+    }
+}
+
 pub fn parse_control_addr<S: Strings>(
     strings: &S,
     base: &str,
@@ -1191,6 +2780,15 @@ pub fn parse_tls_params<S: Strings>(strings: &S) -> Result<identity::TlsParams,
     let server_id = parse(strings, server_id_env_var, parse_identity);
     let server_name = parse(strings, server_name_env_var, parse_dns_name);
 
+    let min_version = parse(strings, ENV_IDENTITY_TLS_MIN_VERSION, parse_tls_version);
+    let cipher_suites = parse(strings, ENV_IDENTITY_TLS_CIPHER_SUITES, parse_cipher_suites);
+    let policy_mode = parse(strings, ENV_IDENTITY_TLS_POLICY_MODE, parse_tls_policy_mode);
+    let policy = tls::TlsPolicy {
+        min_version: min_version?.unwrap_or(tls::TlsPolicy::default().min_version),
+        cipher_suites: cipher_suites?.flatten(),
+        mode: policy_mode?.unwrap_or_default(),
+    };
+
     if strings
         .get(ENV_IDENTITY_DISABLED)?
         .map(|d| !d.is_empty())
@@ -1206,6 +2804,7 @@ pub fn parse_tls_params<S: Strings>(strings: &S) -> Result<identity::TlsParams,
                 id: server_id,
                 server_name,
                 trust_anchors_pem,
+                policy,
             };
             Ok(params)
         }
@@ -1272,8 +2871,7 @@ pub fn parse_linkerd_identity_config<S: Strings>(
     }
 }
 
-#[cfg(test)]
-impl Strings for std::collections::HashMap<&'static str, &'static str> {
+impl Strings for HashMap<&str, &str> {
     fn get(&self, key: &str) -> Result<Option<String>, EnvError> {
         Ok(self.get(key).map(ToString::to_string))
     }
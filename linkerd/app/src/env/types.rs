@@ -1,10 +1,20 @@
 use super::ParseError;
-use linkerd_app_core::{dns, identity, Addr, IpNet};
+use crate::{gateway, inbound, outbound};
+use linkerd_app_core::{
+    dns, identity,
+    proxy::{
+        http::{HeaderName, HeaderValue, StatusCode},
+        tcp::HalfClose,
+    },
+    tls, Addr, IpNet, NameAddr,
+};
 use rangemap::RangeInclusiveSet;
 use std::{
-    collections::HashSet,
+    collections::{BTreeSet, HashMap, HashSet},
     net::{IpAddr, SocketAddr},
+    ops::RangeInclusive,
     str::FromStr,
+    sync::Arc,
     time::Duration,
 };
 use tracing::error;
@@ -79,6 +89,763 @@ pub(super) fn parse_socket_addrs(s: &str) -> Result<Vec<SocketAddr>, ParseError>
     addrs.iter().map(|s| parse_socket_addr(s)).collect()
 }
 
+pub(super) fn parse_socket_addr_list(s: &str) -> Result<Vec<SocketAddr>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(parse_socket_addr)
+        .collect()
+}
+
+pub(super) fn parse_content_types(s: &str) -> Result<Vec<String>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| match s.split_once('/') {
+            Some((ty, subty)) if !ty.is_empty() && !subty.is_empty() => Ok(s.to_ascii_lowercase()),
+            _ => Err(ParseError::NotAContentType(s.to_string())),
+        })
+        .collect()
+}
+
+pub(super) fn parse_header_name(s: &str) -> Result<HeaderName, ParseError> {
+    s.parse()
+        .map_err(|_| ParseError::NotAHeaderName(s.to_string()))
+}
+
+pub(super) fn parse_header_value(s: &str) -> Result<HeaderValue, ParseError> {
+    HeaderValue::from_str(s).map_err(|_| ParseError::NotAHeaderValue(s.to_string()))
+}
+
+/// Parses a comma-separated list of `PORT=MODE` entries.
+pub(super) fn parse_half_close_ports(s: &str) -> Result<HashMap<u16, HalfClose>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let (port, mode) = entry
+                .split_once('=')
+                .ok_or_else(|| ParseError::NotAHalfCloseMode(entry.to_string()))?;
+            let mode = match mode.trim() {
+                "propagate" => HalfClose::Propagate,
+                "couple" => HalfClose::Couple,
+                mode => match mode.strip_prefix("linger:") {
+                    Some(timeout) => HalfClose::Linger(parse_duration(timeout)?),
+                    None => return Err(ParseError::NotAHalfCloseMode(mode.to_string())),
+                },
+            };
+            Ok((parse_number(port.trim())?, mode))
+        })
+        .collect()
+}
+
+/// Parses a comma-separated list of `CIDR=DURATION` entries.
+pub(super) fn parse_connection_lifetimes(s: &str) -> Result<Vec<(IpNet, Duration)>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let invalid = || ParseError::NotAConnectionLifetime(entry.to_string());
+            let (net, lifetime) = entry.split_once('=').ok_or_else(invalid)?;
+            let net = IpNet::from_str(net.trim()).map_err(|_| invalid())?;
+            let lifetime = parse_duration(lifetime)?;
+            if lifetime.is_zero() {
+                return Err(invalid());
+            }
+            Ok((net, lifetime))
+        })
+        .collect()
+}
+
+/// Parses a comma-separated list of `[CIDR:]PORT=TARGET` entries, where each
+/// target is a port or a `NAME:PORT` authority.
+pub(super) fn parse_port_mappings(s: &str) -> Result<Vec<outbound::PortMapping>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let invalid = || ParseError::NotAPortMapping(entry.to_string());
+            let (dst, target) = entry.split_once('=').ok_or_else(invalid)?;
+            let (net, port) = match dst.trim().rsplit_once(':') {
+                Some((net, port)) => (Some(IpNet::from_str(net).map_err(|_| invalid())?), port),
+                None => (None, dst.trim()),
+            };
+            let port = parse_number::<u16>(port).map_err(|_| invalid())?;
+            let target = match parse_number::<u16>(target.trim()) {
+                Ok(port) => outbound::PortMapTarget::Port(port),
+                Err(_) => outbound::PortMapTarget::Authority(
+                    NameAddr::from_str(target.trim()).map_err(|_| invalid())?,
+                ),
+            };
+            Ok(outbound::PortMapping { net, port, target })
+        })
+        .collect()
+}
+
+/// Parses a comma-separated list of `[METHOD ]/PATH` entries. Entries without
+/// a method match requests by path prefix; entries with a method match
+/// requests with that method and exactly that path.
+pub(super) fn parse_keepalive_exempt(
+    s: &str,
+) -> Result<Vec<outbound::http::KeepaliveExempt>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let invalid = || ParseError::NotAKeepaliveExemptRequest(entry.to_string());
+            let (method, path) = match entry.split_once(char::is_whitespace) {
+                Some((method, path)) => (Some(method), path.trim()),
+                None => (None, entry),
+            };
+            if !path.starts_with('/') {
+                return Err(invalid());
+            }
+            match method {
+                None => Ok(outbound::http::KeepaliveExempt::PathPrefix(
+                    path.to_string(),
+                )),
+                Some(method) => {
+                    let method = method.parse().map_err(|_| invalid())?;
+                    Ok(outbound::http::KeepaliveExempt::Request(
+                        method,
+                        path.to_string(),
+                    ))
+                }
+            }
+        })
+        .collect()
+}
+
+/// Parses a comma-separated list of `SUFFIX=SUFFIX[|SUFFIX...]` entries, each
+/// of which permits clients whose identities match the suffixes on its right
+/// to reach the exported services that match the suffix on its left.
+pub(super) fn parse_gateway_authorizations(
+    s: &str,
+) -> Result<Vec<gateway::GatewayAuthorization>, ParseError> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let invalid = || ParseError::NotAGatewayAuthorization(entry.to_string());
+            let (services, clients) = entry.split_once('=').ok_or_else(invalid)?;
+            let services = parse_dns_suffix(services.trim()).map_err(|_| invalid())?;
+            let clients = clients
+                .split('|')
+                .map(|client| parse_dns_suffix(client.trim()).map_err(|_| invalid()))
+                .collect::<Result<_, _>>()?;
+            Ok(gateway::GatewayAuthorization { services, clients })
+        })
+        .collect()
+}
+
+/// Parses a comma-separated list of `NAME=ADDR[;OPTION...]` entries, where
+/// options are `opaque` or `forward`.
+pub(super) fn parse_outbound_listeners(
+    s: &str,
+) -> Result<Vec<(Arc<str>, SocketAddr, outbound::ListenerOverrides)>, ParseError> {
+    let mut names = HashSet::new();
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let (name, config) = entry
+                .split_once('=')
+                .ok_or_else(|| ParseError::NotAListener(entry.to_string()))?;
+            let name = name.trim();
+            if name.is_empty() || !names.insert(name) {
+                return Err(ParseError::NotAListener(entry.to_string()));
+            }
+            let mut options = config.split(';').map(str::trim);
+            let addr = parse_socket_addr(options.next().unwrap_or_default())?;
+            let mut overrides = outbound::ListenerOverrides::default();
+            for option in options {
+                match option {
+                    "opaque" => overrides.detect_protocol = false,
+                    "forward" => overrides.discover = false,
+                    _ => return Err(ParseError::NotAListener(entry.to_string())),
+                }
+            }
+            Ok((name.into(), addr, overrides))
+        })
+        .collect()
+}
+
+/// Parses a comma-separated list of `PORT=ADDR` entries.
+pub(super) fn parse_inbound_forward_targets(
+    s: &str,
+) -> Result<HashMap<u16, SocketAddr>, ParseError> {
+    let mut targets = HashMap::new();
+    for entry in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let invalid = || ParseError::NotAForwardTarget(entry.to_string());
+        let (port, addr) = entry.split_once('=').ok_or_else(invalid)?;
+        let port = parse_number::<u16>(port.trim()).map_err(|_| invalid())?;
+        let addr = parse_socket_addr(addr.trim())?;
+        if targets.insert(port, addr).is_some() {
+            return Err(invalid());
+        }
+    }
+    Ok(targets)
+}
+
+/// Parses a comma-separated list of `PORT=SETTING:VALUE[;SETTING:VALUE...]`
+/// entries.
+pub(super) fn parse_inbound_http2_ports(
+    s: &str,
+) -> Result<HashMap<u16, inbound::Http2Settings>, ParseError> {
+    // Bounds defined by RFC 9113.
+    const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+    const FRAME_SIZES: std::ops::RangeInclusive<u32> = (1 << 14)..=(1 << 24) - 1;
+
+    let mut ports = HashMap::new();
+    for entry in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let invalid = || ParseError::NotHttp2PortSettings(entry.to_string());
+        let (port, config) = entry.split_once('=').ok_or_else(invalid)?;
+        let port = parse_number::<u16>(port.trim()).map_err(|_| invalid())?;
+        let mut settings = inbound::Http2Settings::default();
+        for setting in config.split(';').map(str::trim) {
+            let (name, value) = setting.split_once(':').ok_or_else(invalid)?;
+            let value = parse_number::<u32>(value.trim()).map_err(|_| invalid())?;
+            match name.trim() {
+                "initial-stream-window-size" if value <= MAX_WINDOW_SIZE => {
+                    settings.initial_stream_window_size = Some(value)
+                }
+                "initial-connection-window-size" if value <= MAX_WINDOW_SIZE => {
+                    settings.initial_connection_window_size = Some(value)
+                }
+                "max-frame-size" if FRAME_SIZES.contains(&value) => {
+                    settings.max_frame_size = Some(value)
+                }
+                "max-concurrent-streams" => settings.max_concurrent_streams = Some(value),
+                _ => return Err(invalid()),
+            }
+        }
+        if ports.insert(port, settings).is_some() {
+            return Err(invalid());
+        }
+    }
+    Ok(ports)
+}
+
+/// Parses a comma-separated list of `RESOURCE=SETTING[:VALUE][;SETTING[:VALUE]]`
+/// entries.
+pub(super) fn parse_outbound_route_overrides(
+    s: &str,
+) -> Result<outbound::policy::RouteOverrides, ParseError> {
+    let routes = parse_overrides(
+        s,
+        ParseError::NotARouteOverride,
+        parse_outbound_resource,
+        parse_outbound_route,
+    )?;
+    Ok(outbound::policy::RouteOverrides::new(routes))
+}
+
+fn parse_outbound_route(
+    settings: Vec<(&str, Option<&str>)>,
+) -> Option<outbound::policy::RouteOverride> {
+    let mut route = outbound::policy::RouteOverride::default();
+    let mut success_statuses = None;
+    let mut success_codes = None;
+    let mut coalesce_headers = None;
+    let mut fault_delay = None;
+    let mut http_abort = None;
+    let mut grpc_abort = None;
+    let mut retry_aborts = false;
+    let mut direct_status = None;
+    let mut direct_headers = Vec::new();
+    let mut direct_body = None;
+    let mut rewrite_location = false;
+    for (setting, value) in settings {
+        match (setting, value) {
+            ("assert-workload-identity", None) => route.assert_workload_identity = true,
+            ("failure-statuses", Some(v)) => {
+                let ranges = parse_status_ranges(v)?;
+                route.failure_statuses = Some(outbound::policy::http::StatusRanges(ranges.into()));
+            }
+            ("success-statuses", Some(v)) => {
+                success_statuses = Some(parse_status_ranges(v)?);
+            }
+            ("failure-codes", Some(v)) => {
+                let codes = parse_grpc_codes(v)?;
+                route.failure_codes = Some(outbound::policy::grpc::Codes(codes.into()));
+            }
+            ("success-codes", Some(v)) => {
+                success_codes = Some(parse_grpc_codes(v)?);
+            }
+            ("rollout-guard", Some(v)) => {
+                route.rollout_guard = Some(parse_rollout_guard(v)?);
+            }
+            ("response-headers-timeout", Some(v)) => {
+                route.response_headers_timeout = Some(parse_nonzero_duration(v)?);
+            }
+            ("response-chunk-idle-timeout", Some(v)) => {
+                route.response_chunk_idle_timeout = Some(parse_nonzero_duration(v)?);
+            }
+            ("cookie" | "cookie-prefix" | "cookie-regex", Some(v)) => {
+                route.cookies.push(parse_match_cookie(setting, v)?);
+            }
+            ("cache", None) => {
+                route.cache.get_or_insert_with(Default::default);
+            }
+            ("cache", Some(v)) => {
+                route.cache.get_or_insert_with(Default::default).max_age =
+                    Some(parse_nonzero_duration(v)?);
+            }
+            ("cache-allow-authorization", None) => {
+                route
+                    .cache
+                    .get_or_insert_with(Default::default)
+                    .allow_authorization = true;
+            }
+            ("coalesce", Some(v)) => {
+                route.coalesce = Some(parse_coalesce(v)?);
+            }
+            ("coalesce-headers", Some(v)) => {
+                let headers = v
+                    .split('|')
+                    .map(|h| HeaderName::from_bytes(h.trim().as_bytes()).ok())
+                    .collect::<Option<Vec<_>>>()?;
+                coalesce_headers = Some(headers);
+            }
+            ("fault-delay", Some(v)) => {
+                let (distribution, duration) = parse_fault(v)?;
+                let duration = parse_fault_duration(duration)?;
+                fault_delay = Some(outbound::policy::http::FaultDelay {
+                    distribution,
+                    duration,
+                });
+            }
+            ("fault-abort", Some(v)) => {
+                let (distribution, status) = parse_fault(v)?;
+                let status = status
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|s| (400..=599).contains(s))?;
+                http_abort = Some(outbound::policy::http::FaultAbort {
+                    distribution,
+                    response: StatusCode::from_u16(status).ok()?,
+                });
+            }
+            ("fault-abort-grpc", Some(v)) => {
+                let (distribution, code) = parse_fault(v)?;
+                let code = code.parse::<i32>().ok().filter(|c| (1..=16).contains(c))?;
+                grpc_abort = Some(outbound::policy::http::FaultAbort {
+                    distribution,
+                    response: tonic::Code::from(code),
+                });
+            }
+            ("fault-retry-aborts", None) => retry_aborts = true,
+            ("direct-response", Some(v)) => {
+                let status = v.parse::<u16>().ok()?;
+                direct_status = Some(StatusCode::from_u16(status).ok()?);
+            }
+            ("direct-response-header", Some(v)) => {
+                let (name, value) = v.split_once('|')?;
+                let name = HeaderName::from_bytes(name.trim().as_bytes()).ok()?;
+                let value = HeaderValue::from_str(value.trim()).ok()?;
+                direct_headers.push((name, value));
+            }
+            ("direct-response-body", Some(v)) => direct_body = Some(v.to_string()),
+            ("direct-response-grpc", Some(v)) => {
+                let (code, message) = v.split_once('|').unwrap_or((v, ""));
+                let code = code.trim().parse::<u16>().ok().filter(|c| *c <= 16)?;
+                route.grpc_direct_response = Some(outbound::policy::grpc::filter::DirectResponse {
+                    code,
+                    message: message.into(),
+                });
+            }
+            ("rewrite-authority", Some(v)) => {
+                // Authorities with credentials are not valid hosts.
+                if v.contains('@') {
+                    return None;
+                }
+                route.rewrite_authority = Some(outbound::policy::http::filter::RewriteAuthority {
+                    authority: v.parse().ok()?,
+                    rewrite_location: false,
+                });
+            }
+            ("rewrite-authority-location", None) => rewrite_location = true,
+            ("decompress-request", None) => {
+                route.decompress_request = Some(Default::default());
+            }
+            ("decompress-request", Some(v)) => {
+                let max_bytes = v.parse::<usize>().ok().filter(|n| *n > 0)?;
+                route.decompress_request =
+                    Some(outbound::policy::http::filter::DecompressRequest { max_bytes });
+            }
+            _ => return None,
+        }
+    }
+    // Locations may only be mapped back on routes that rewrite authorities.
+    if rewrite_location {
+        route.rewrite_authority.as_mut()?.rewrite_location = true;
+    }
+    // Successes are removed from the configured, or default, failures.
+    if let Some(successes) = success_statuses {
+        let failures = route.failure_statuses.take().unwrap_or_default();
+        let ranges = (100..=599)
+            .filter(|s| {
+                failures.0.iter().any(|r| r.contains(s)) && !successes.iter().any(|r| r.contains(s))
+            })
+            .fold(Vec::<RangeInclusive<u16>>::new(), |mut ranges, s| {
+                match ranges.last_mut() {
+                    Some(r) if *r.end() + 1 == s => *r = *r.start()..=s,
+                    _ => ranges.push(s..=s),
+                }
+                ranges
+            });
+        route.failure_statuses = Some(outbound::policy::http::StatusRanges(ranges.into()));
+    }
+    if let Some(successes) = success_codes {
+        let failures = route.failure_codes.take().unwrap_or_default();
+        let codes = failures.0.difference(&successes).copied().collect();
+        route.failure_codes = Some(outbound::policy::grpc::Codes(Arc::new(codes)));
+    }
+    // Headers may only be set on routes that coalesce requests.
+    if let Some(headers) = coalesce_headers {
+        route.coalesce.as_mut()?.headers = headers;
+    }
+    // A direct response's headers and body may only be set with its status.
+    match direct_status {
+        Some(status) => {
+            let body = direct_body.unwrap_or_default();
+            let direct =
+                outbound::policy::http::filter::DirectResponse::new(status, direct_headers, body)
+                    .ok()?;
+            route.http_direct_response = Some(direct);
+        }
+        None if !direct_headers.is_empty() || direct_body.is_some() => return None,
+        None => {}
+    }
+    // Only aborted requests may be retried.
+    if retry_aborts && http_abort.is_none() && grpc_abort.is_none() {
+        return None;
+    }
+    if fault_delay.is_some() || http_abort.is_some() {
+        route.http_fault = Some(outbound::policy::http::Fault {
+            delay: fault_delay.clone(),
+            abort: http_abort,
+            retry_aborts,
+        });
+    }
+    if fault_delay.is_some() || grpc_abort.is_some() {
+        route.grpc_fault = Some(outbound::policy::http::Fault {
+            delay: fault_delay,
+            abort: grpc_abort,
+            retry_aborts,
+        });
+    }
+    Some(route)
+}
+
+pub(super) fn parse_inbound_route_overrides(
+    s: &str,
+) -> Result<inbound::policy::RouteOverrides, ParseError> {
+    let routes = parse_overrides(
+        s,
+        ParseError::NotARouteOverride,
+        parse_inbound_resource,
+        parse_inbound_route,
+    )?;
+    Ok(inbound::policy::RouteOverrides::new(routes))
+}
+
+fn parse_inbound_route(
+    settings: Vec<(&str, Option<&str>)>,
+) -> Option<inbound::policy::RouteOverride> {
+    let mut route = inbound::policy::RouteOverride::default();
+    for setting in settings {
+        match setting {
+            ("latency-objective", Some(v)) => {
+                route.latency_objective = Some(parse_nonzero_duration(v)?);
+            }
+            ("sheddable", None) => route.sheddable = true,
+            ("ext-authz", None) => route.ext_authz = true,
+            _ => return None,
+        }
+    }
+    Some(route)
+}
+
+pub(super) fn parse_inbound_server_overrides(
+    s: &str,
+) -> Result<inbound::policy::ServerOverrides, ParseError> {
+    let servers = parse_overrides(
+        s,
+        ParseError::NotAServerOverride,
+        parse_inbound_resource,
+        parse_inbound_server,
+    )?;
+    Ok(inbound::policy::ServerOverrides::new(servers))
+}
+
+fn parse_inbound_server(
+    settings: Vec<(&str, Option<&str>)>,
+) -> Option<inbound::policy::ServerOverride> {
+    let mut server = inbound::policy::ServerOverride::default();
+    for setting in settings {
+        match setting {
+            ("termination-grace-period", Some(v)) => {
+                server.termination_grace_period = parse_duration(v).ok()?;
+            }
+            _ => return None,
+        }
+    }
+    Some(server)
+}
+
+pub(super) fn parse_outbound_parent_overrides(
+    s: &str,
+) -> Result<outbound::policy::ParentOverrides, ParseError> {
+    let parents = parse_overrides(
+        s,
+        ParseError::NotAParentOverride,
+        parse_outbound_resource,
+        parse_outbound_parent,
+    )?;
+    Ok(outbound::policy::ParentOverrides::new(parents))
+}
+
+fn parse_outbound_parent(
+    settings: Vec<(&str, Option<&str>)>,
+) -> Option<outbound::policy::ParentOverride> {
+    use outbound::policy::tls::{sni::MatchSni, SniEnforcement};
+
+    let mut allowed_snis = None;
+    let mut allow_missing_sni = false;
+    for setting in settings {
+        match setting {
+            ("allowed-snis", Some(v)) => {
+                let snis = v
+                    .split('|')
+                    .map(|p| match p.trim() {
+                        "" => None,
+                        p => p.parse::<MatchSni>().ok(),
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                allowed_snis = Some(snis);
+            }
+            ("allow-missing-sni", None) => allow_missing_sni = true,
+            _ => return None,
+        }
+    }
+    let sni = match allowed_snis {
+        Some(allowed) => Some(SniEnforcement {
+            allowed: allowed.into(),
+            allow_missing: allow_missing_sni,
+        }),
+        None if allow_missing_sni => return None,
+        None => None,
+    };
+    Some(outbound::policy::ParentOverride { sni })
+}
+
+/// Parses a comma-separated list of `RESOURCE=SETTING[:VALUE][;SETTING[:VALUE]...]`
+/// entries, configuring each resource, as parsed by `parse_key`, with
+/// `parse_settings`.
+fn parse_overrides<'s, K, T>(
+    s: &'s str,
+    error: fn(String) -> ParseError,
+    parse_key: fn(&str) -> Option<K>,
+    mut parse_settings: impl FnMut(Vec<(&'s str, Option<&'s str>)>) -> Option<T>,
+) -> Result<HashMap<K, T>, ParseError>
+where
+    K: Eq + std::hash::Hash,
+{
+    let mut routes = HashMap::new();
+    for entry in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let invalid = || error(entry.to_string());
+        let (name, config) = entry.split_once('=').ok_or_else(invalid)?;
+        let name = parse_key(name.trim()).ok_or_else(invalid)?;
+        let settings = config
+            .split(';')
+            .map(|setting| match setting.split_once(':') {
+                Some((setting, value)) => (setting.trim(), Some(value.trim())),
+                None => (setting.trim(), None),
+            })
+            .collect();
+        let route = parse_settings(settings).ok_or_else(invalid)?;
+        if routes.insert(name, route).is_some() {
+            return Err(invalid());
+        }
+    }
+    Ok(routes)
+}
+
+/// Parses a `KIND[.GROUP]:NAMESPACE/NAME` reference to an outbound resource, or
+/// a `default:NAME` reference to one of the proxy's default resources. Bare
+/// names are rejected, since they would be ambiguous across kinds and
+/// namespaces.
+fn parse_outbound_resource(s: &str) -> Option<outbound::policy::ResourceKey> {
+    let (group, kind, name) = parse_resource_kind(s)?;
+    if kind.eq_ignore_ascii_case("default") {
+        if !group.is_empty() || name.contains('/') {
+            return None;
+        }
+        return Some(outbound::policy::ResourceKey::new_default(name));
+    }
+    let (namespace, name) = name.split_once('/')?;
+    if namespace.is_empty() || name.is_empty() || name.contains('/') {
+        return None;
+    }
+    Some(outbound::policy::ResourceKey::new(
+        group, kind, namespace, name,
+    ))
+}
+
+/// Parses a `KIND[.GROUP]:NAME` reference to an inbound resource, which is in
+/// the workload's namespace, or a `default:NAME` reference to one of the
+/// proxy's default resources.
+fn parse_inbound_resource(s: &str) -> Option<inbound::policy::ResourceKey> {
+    let (group, kind, name) = parse_resource_kind(s)?;
+    if name.contains('/') || (kind.eq_ignore_ascii_case("default") && !group.is_empty()) {
+        return None;
+    }
+    Some(inbound::policy::ResourceKey::new(group, kind, name))
+}
+
+/// Splits a `KIND[.GROUP]:NAME` reference into its group, kind, and name.
+fn parse_resource_kind(s: &str) -> Option<(&str, &str, &str)> {
+    let (kind, name) = s.split_once(':')?;
+    let (kind, group) = kind.trim().split_once('.').unwrap_or((kind.trim(), ""));
+    let name = name.trim();
+    if kind.is_empty() || name.is_empty() {
+        return None;
+    }
+    Some((group, kind, name))
+}
+
+fn parse_nonzero_duration(s: &str) -> Option<Duration> {
+    parse_duration(s).ok().filter(|d| !d.is_zero())
+}
+
+/// Parses a `NAME|VALUE` cookie match of the kind named by `setting`.
+fn parse_match_cookie(
+    setting: &str,
+    s: &str,
+) -> Option<outbound::policy::http::r#match::MatchCookie> {
+    use outbound::policy::http::r#match::MatchCookie;
+
+    let (name, value) = s.split_once('|')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let (name, value) = (name.to_string(), value.to_string());
+    match setting {
+        "cookie" => Some(MatchCookie::Exact(name, value)),
+        "cookie-prefix" => Some(MatchCookie::Prefix(name, value)),
+        "cookie-regex" => Some(MatchCookie::Regex(name, regex::Regex::new(&value).ok()?)),
+        _ => None,
+    }
+}
+
+/// Parses request coalescing as `MAX_WAITERS|TIMEOUT`.
+fn parse_coalesce(s: &str) -> Option<outbound::policy::http::Coalesce> {
+    let (max_waiters, timeout) = s.split_once('|')?;
+    let max_waiters = max_waiters
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|n| *n > 0)?;
+    let timeout = parse_nonzero_duration(timeout.trim())?;
+    Some(outbound::policy::http::Coalesce {
+        headers: vec![],
+        max_waiters,
+        timeout,
+    })
+}
+
+/// Parses a fault as `PERCENT|VALUE`, returning the proportion of requests to
+/// which the fault applies and its unparsed value.
+fn parse_fault(s: &str) -> Option<(outbound::policy::http::filter::Distribution, &str)> {
+    let (percent, value) = s.split_once('|')?;
+    let percent = percent.trim().parse::<u32>().ok().filter(|p| *p <= 100)?;
+    let distribution =
+        outbound::policy::http::filter::Distribution::from_ratio(percent, 100).ok()?;
+    Some((distribution, value.trim()))
+}
+
+/// Parses a fault delay as either `DURATION` or `MIN-MAX`.
+fn parse_fault_duration(s: &str) -> Option<outbound::policy::http::FaultDuration> {
+    match s.split_once('-') {
+        None => parse_nonzero_duration(s).map(outbound::policy::http::FaultDuration::Fixed),
+        Some((min, max)) => {
+            let min = parse_duration(min).ok()?;
+            let max = parse_nonzero_duration(max)?;
+            (min < max).then_some(outbound::policy::http::FaultDuration::Random(min..=max))
+        }
+    }
+}
+
+/// Parses a `|`-separated list of HTTP statuses and `START-END` ranges.
+fn parse_status_ranges(s: &str) -> Option<Vec<RangeInclusive<u16>>> {
+    s.split('|')
+        .map(|r| {
+            let (start, end) = r.split_once('-').unwrap_or((r, r));
+            let start = start.trim().parse::<u16>().ok()?;
+            let end = end.trim().parse::<u16>().ok()?;
+            (100 <= start && start <= end && end <= 599).then_some(start..=end)
+        })
+        .collect()
+}
+
+/// Parses a rollout guard as `WINDOW|MIN_REQUESTS|MAX_FAILURE_RATE_INCREASE|COOLDOWN`.
+fn parse_rollout_guard(s: &str) -> Option<outbound::policy::http::RolloutGuard> {
+    let mut parts = s.split('|').map(str::trim);
+    let window = parse_duration(parts.next()?).ok()?;
+    let min_requests = parts.next()?.parse::<u32>().ok()?;
+    let max_failure_rate_increase = parts.next()?.parse::<u8>().ok().filter(|p| *p <= 100)?;
+    let cooldown = parse_duration(parts.next()?).ok()?;
+    if parts.next().is_some() || window.is_zero() || cooldown.is_zero() {
+        return None;
+    }
+    Some(outbound::policy::http::RolloutGuard {
+        window,
+        min_requests,
+        max_failure_rate_increase,
+        cooldown,
+    })
+}
+
+/// Parses a `|`-separated list of numeric `grpc-status` codes.
+fn parse_grpc_codes(s: &str) -> Option<BTreeSet<u16>> {
+    s.split('|')
+        .map(|c| c.trim().parse::<u16>().ok().filter(|c| *c <= 16))
+        .collect()
+}
+
+pub(super) fn parse_tls_version(s: &str) -> Result<tls::metrics::ProtocolVersion, ParseError> {
+    s.trim()
+        .parse()
+        .map_err(|_| ParseError::NotATlsPolicySetting(s.to_string()))
+}
+
+/// Parses a comma-separated list of cipher suite names.
+pub(super) fn parse_cipher_suites(
+    s: &str,
+) -> Result<Option<Arc<[tls::metrics::CipherSuite]>>, ParseError> {
+    let suites = s
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .map_err(|_| ParseError::NotATlsPolicySetting(s.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if suites.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(suites.into()))
+}
+
+pub(super) fn parse_tls_policy_mode(s: &str) -> Result<tls::PolicyMode, ParseError> {
+    s.trim()
+        .parse()
+        .map_err(|_| ParseError::NotATlsPolicySetting(s.to_string()))
+}
+
 pub(super) fn parse_ip_set(s: &str) -> Result<HashSet<IpAddr>, ParseError> {
     s.split(',')
         .map(|s| s.parse::<IpAddr>().map_err(Into::into))
@@ -129,6 +896,22 @@ pub(super) fn parse_port_range_set(s: &str) -> Result<RangeInclusiveSet<u16>, Pa
     Ok(set)
 }
 
+pub(super) fn parse_admin_endpoints(list: &str) -> Result<crate::admin::Endpoints, ParseError> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<crate::admin::Endpoint>().map_err(Into::into))
+        .collect()
+}
+
+pub(super) fn parse_client_ids(list: &str) -> Result<HashSet<tls::ClientId>, ParseError> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| parse_identity(s).map(tls::ClientId))
+        .collect()
+}
+
 pub(super) fn parse_dns_suffixes(list: &str) -> Result<HashSet<dns::Suffix>, ParseError> {
     let mut suffixes = HashSet::new();
     for item in list.split(',') {
@@ -169,6 +952,34 @@ pub(super) fn parse_networks(list: &str) -> Result<HashSet<IpNet>, ParseError> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_tls_policy() {
+        use tls::metrics::{CipherSuite, ProtocolVersion};
+
+        assert_eq!(parse_tls_version("1.2"), Ok(ProtocolVersion::Tls12));
+        assert_eq!(parse_tls_version(" TLSv1_3 "), Ok(ProtocolVersion::Tls13));
+        assert!(parse_tls_version("1.1").is_err());
+
+        assert_eq!(
+            parse_cipher_suites(
+                "TLS13_AES_256_GCM_SHA384, TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256"
+            ),
+            Ok(Some(Arc::from([
+                CipherSuite::TLS13_AES_256_GCM_SHA384,
+                CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            ])))
+        );
+        assert_eq!(parse_cipher_suites(""), Ok(None));
+        assert!(parse_cipher_suites("TLS13_AES_256_GCM_SHA384,RC4").is_err());
+
+        assert_eq!(parse_tls_policy_mode("audit"), Ok(tls::PolicyMode::Audit));
+        assert_eq!(
+            parse_tls_policy_mode("Enforce"),
+            Ok(tls::PolicyMode::Enforce)
+        );
+        assert!(parse_tls_policy_mode("warn").is_err());
+    }
+
     fn test_unit<F: Fn(u64) -> Duration>(unit: &str, to_duration: F) {
         for v in &[0, 1, 23, 456_789] {
             let d = to_duration(*v);
@@ -281,6 +1092,883 @@ mod tests {
         );
     }
 
+    #[test]
+    fn content_types() {
+        assert_eq!(parse_content_types(""), Ok(vec![]));
+        assert_eq!(
+            parse_content_types(" Application/JSON , text/* "),
+            Ok(vec!["application/json".to_owned(), "text/*".to_owned()]),
+        );
+        assert_eq!(
+            parse_content_types("application/json,json"),
+            Err(ParseError::NotAContentType("json".to_owned())),
+        );
+        assert!(parse_content_types("text/").is_err());
+    }
+
+    #[test]
+    fn socket_addr_lists() {
+        assert_eq!(parse_socket_addr_list(""), Ok(vec![]));
+        assert_eq!(
+            parse_socket_addr_list(" 10.0.0.1:8080, 10.0.0.2:80 ,"),
+            Ok(vec![
+                SocketAddr::from(([10, 0, 0, 1], 8080)),
+                SocketAddr::from(([10, 0, 0, 2], 80)),
+            ]),
+        );
+        assert!(parse_socket_addr_list("web.ns.svc.cluster.local:8080").is_err());
+    }
+
+    #[test]
+    fn half_close_ports() {
+        assert_eq!(parse_half_close_ports(""), Ok(HashMap::new()));
+        assert_eq!(
+            parse_half_close_ports("5432=couple, 6000=linger:5s,80=propagate,"),
+            Ok([
+                (5432, HalfClose::Couple),
+                (6000, HalfClose::Linger(Duration::from_secs(5))),
+                (80, HalfClose::Propagate),
+            ]
+            .into_iter()
+            .collect()),
+        );
+        assert!(parse_half_close_ports("5432").is_err());
+        assert!(parse_half_close_ports("5432=close").is_err());
+        assert!(parse_half_close_ports("5432=linger").is_err());
+        assert!(parse_half_close_ports("http=couple").is_err());
+    }
+
+    #[test]
+    fn connection_lifetimes() {
+        assert_eq!(parse_connection_lifetimes(""), Ok(vec![]));
+        assert_eq!(
+            parse_connection_lifetimes("10.0.0.0/8=10m, 10.1.0.0/16=30s,"),
+            Ok(vec![
+                (
+                    IpNet::from_str("10.0.0.0/8").unwrap(),
+                    Duration::from_secs(600)
+                ),
+                (
+                    IpNet::from_str("10.1.0.0/16").unwrap(),
+                    Duration::from_secs(30)
+                ),
+            ]),
+        );
+        assert!(parse_connection_lifetimes("10.0.0.0/8").is_err());
+        assert!(parse_connection_lifetimes("10.0.0.0/33=10m").is_err());
+        assert!(parse_connection_lifetimes("10.0.0.0/8=0s").is_err());
+        assert!(parse_connection_lifetimes("10.0.0.0/8=forever").is_err());
+    }
+
+    #[test]
+    fn gateway_authorizations() {
+        assert!(parse_gateway_authorizations("").unwrap().is_empty());
+
+        let authzs = parse_gateway_authorizations(
+            "web.ns.svc.cluster.local=east.example.com, ns.svc.cluster.local=east.example.com|west.example.com",
+        )
+        .unwrap();
+        assert_eq!(authzs.len(), 2);
+        assert_eq!(
+            authzs[0].services,
+            dns::Suffix::from_str("web.ns.svc.cluster.local").unwrap()
+        );
+        let east =
+            dns::Name::from_str("default.ns.serviceaccount.identity.linkerd.east.example.com")
+                .unwrap();
+        let west =
+            dns::Name::from_str("default.ns.serviceaccount.identity.linkerd.west.example.com")
+                .unwrap();
+        assert!(authzs[0].clients.matches(&east));
+        assert!(!authzs[0].clients.matches(&west));
+        assert!(authzs[1].clients.matches(&west));
+
+        assert!(parse_gateway_authorizations("web.ns.svc.cluster.local").is_err());
+        assert!(parse_gateway_authorizations("web.ns.svc.cluster.local=").is_err());
+        assert!(parse_gateway_authorizations("web.ns.svc.cluster.local=a|").is_err());
+    }
+
+    #[test]
+    fn port_mappings() {
+        use outbound::{PortMapTarget, PortMapping};
+
+        assert_eq!(parse_port_mappings(""), Ok(vec![]));
+        assert_eq!(
+            parse_port_mappings("30080=80, 10.0.0.0/8:30443=web.ns.svc.cluster.local:443,"),
+            Ok(vec![
+                PortMapping {
+                    net: None,
+                    port: 30080,
+                    target: PortMapTarget::Port(80),
+                },
+                PortMapping {
+                    net: Some(IpNet::from_str("10.0.0.0/8").unwrap()),
+                    port: 30443,
+                    target: PortMapTarget::Authority(
+                        NameAddr::from_str("web.ns.svc.cluster.local:443").unwrap()
+                    ),
+                },
+            ]),
+        );
+        assert_eq!(
+            parse_port_mappings("fd00::/8:30080=80").map(|m| m[0].net),
+            Ok(Some(IpNet::from_str("fd00::/8").unwrap())),
+        );
+        assert!(parse_port_mappings("30080").is_err());
+        assert!(parse_port_mappings("30080=web.ns.svc.cluster.local").is_err());
+        assert!(parse_port_mappings("10.0.0.0/33:30080=80").is_err());
+        assert!(parse_port_mappings("http=80").is_err());
+    }
+
+    #[test]
+    fn keepalive_exempt() {
+        use outbound::http::KeepaliveExempt;
+
+        assert_eq!(parse_keepalive_exempt(""), Ok(vec![]));
+        assert_eq!(
+            parse_keepalive_exempt("/healthz, GET /ready,"),
+            Ok(vec![
+                KeepaliveExempt::PathPrefix("/healthz".to_string()),
+                KeepaliveExempt::Request(outbound::http::Method::GET, "/ready".to_string()),
+            ]),
+        );
+        assert!(parse_keepalive_exempt("healthz").is_err());
+        assert!(parse_keepalive_exempt("GET ready").is_err());
+        assert!(parse_keepalive_exempt("G(ET /ready").is_err());
+    }
+
+    #[test]
+    fn outbound_listeners() {
+        use outbound::ListenerOverrides;
+
+        let listeners =
+            parse_outbound_listeners("egress=127.0.0.1:4142;opaque;forward, plain=0.0.0.0:4143,")
+                .unwrap();
+        assert_eq!(
+            listeners,
+            [
+                (
+                    "egress".into(),
+                    ([127, 0, 0, 1], 4142).into(),
+                    ListenerOverrides {
+                        detect_protocol: false,
+                        discover: false,
+                    }
+                ),
+                (
+                    "plain".into(),
+                    ([0, 0, 0, 0], 4143).into(),
+                    ListenerOverrides::default()
+                ),
+            ]
+        );
+        assert_eq!(parse_outbound_listeners(""), Ok(vec![]));
+        assert!(parse_outbound_listeners("127.0.0.1:4142").is_err());
+        assert!(parse_outbound_listeners("=127.0.0.1:4142").is_err());
+        assert!(parse_outbound_listeners("egress=127.0.0.1").is_err());
+        assert!(parse_outbound_listeners("egress=127.0.0.1:4142;http").is_err());
+        assert!(parse_outbound_listeners("a=127.0.0.1:4142,a=127.0.0.1:4143").is_err());
+    }
+
+    #[test]
+    fn inbound_forward_targets() {
+        assert_eq!(
+            parse_inbound_forward_targets("8080=10.0.0.1:80, 9090=[::1]:9091,"),
+            Ok([
+                (8080, ([10, 0, 0, 1], 80).into()),
+                (9090, "[::1]:9091".parse().unwrap()),
+            ]
+            .into_iter()
+            .collect())
+        );
+        assert_eq!(parse_inbound_forward_targets(""), Ok(HashMap::new()));
+        assert!(parse_inbound_forward_targets("10.0.0.1:80").is_err());
+        assert!(parse_inbound_forward_targets("http=10.0.0.1:80").is_err());
+        assert!(parse_inbound_forward_targets("8080=10.0.0.1").is_err());
+        assert!(parse_inbound_forward_targets("8080=10.0.0.1:80,8080=10.0.0.2:80").is_err());
+    }
+
+    #[test]
+    fn inbound_http2_ports() {
+        let ports = parse_inbound_http2_ports(
+            "8080=initial-stream-window-size:16777216;initial-connection-window-size:33554432,\
+             9090=max-frame-size:65536;max-concurrent-streams:10",
+        )
+        .unwrap();
+        assert_eq!(
+            ports[&8080],
+            inbound::Http2Settings {
+                initial_stream_window_size: Some(16_777_216),
+                initial_connection_window_size: Some(33_554_432),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            ports[&9090],
+            inbound::Http2Settings {
+                max_frame_size: Some(65_536),
+                max_concurrent_streams: Some(10),
+                ..Default::default()
+            }
+        );
+        assert_eq!(parse_inbound_http2_ports(""), Ok(HashMap::new()));
+        assert!(parse_inbound_http2_ports("8080").is_err());
+        assert!(parse_inbound_http2_ports("8080=max-frame-size").is_err());
+        assert!(parse_inbound_http2_ports("8080=max-frame-size:1024").is_err());
+        assert!(parse_inbound_http2_ports("8080=initial-stream-window-size:4294967295").is_err());
+        assert!(parse_inbound_http2_ports("8080=window:1").is_err());
+        assert!(parse_inbound_http2_ports(
+            "8080=max-concurrent-streams:1,8080=max-concurrent-streams:2"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn outbound_route_overrides() {
+        use outbound::policy::{Meta, RouteOverride};
+
+        let routes = parse_outbound_route_overrides(
+            "default:foo=assert-workload-identity, default:bar=assert-workload-identity,",
+        )
+        .unwrap();
+        assert!(
+            routes
+                .get(&Meta::new_default("foo"))
+                .assert_workload_identity
+        );
+        assert!(
+            routes
+                .get(&Meta::new_default("bar"))
+                .assert_workload_identity
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("baz")),
+            &RouteOverride::default()
+        );
+        assert_eq!(
+            parse_outbound_route_overrides(""),
+            Ok(outbound::policy::RouteOverrides::default())
+        );
+        assert!(parse_outbound_route_overrides("foo").is_err());
+        assert!(parse_outbound_route_overrides("=assert-workload-identity").is_err());
+        assert!(
+            parse_outbound_route_overrides("default:foo=assert-workload-identity:true").is_err()
+        );
+        assert!(parse_outbound_route_overrides("default:foo=unknown").is_err());
+        assert!(parse_outbound_route_overrides(
+            "default:foo=assert-workload-identity,default:foo=assert-workload-identity"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn outbound_route_overrides_by_resource() {
+        use outbound::policy::Meta;
+
+        fn route(group: &str, kind: &str, namespace: &str, name: &str) -> Meta {
+            Meta::Resource {
+                group: group.to_string(),
+                kind: kind.to_string(),
+                namespace: namespace.to_string(),
+                name: name.to_string(),
+                section: None,
+                port: None,
+            }
+        }
+
+        let routes = parse_outbound_route_overrides(
+            "HTTPRoute.gateway.networking.k8s.io:emojivoto/web=assert-workload-identity",
+        )
+        .unwrap();
+        let configured = |meta: &Meta| routes.get(meta).assert_workload_identity;
+        assert!(configured(&route(
+            "gateway.networking.k8s.io",
+            "HTTPRoute",
+            "emojivoto",
+            "web"
+        )));
+        // Routes with the same name in other namespaces, of other kinds, or
+        // in other groups are not configured.
+        assert!(!configured(&route(
+            "gateway.networking.k8s.io",
+            "HTTPRoute",
+            "booksapp",
+            "web"
+        )));
+        assert!(!configured(&route(
+            "gateway.networking.k8s.io",
+            "GRPCRoute",
+            "emojivoto",
+            "web"
+        )));
+        assert!(!configured(&route(
+            "policy.linkerd.io",
+            "HTTPRoute",
+            "emojivoto",
+            "web"
+        )));
+        assert!(
+            !routes
+                .get(&Meta::new_default("web"))
+                .assert_workload_identity
+        );
+
+        // Bare names are ambiguous, and resources other than the proxy's
+        // defaults must be namespaced.
+        assert!(parse_outbound_route_overrides("web=assert-workload-identity").is_err());
+        assert!(parse_outbound_route_overrides(
+            "httproute.gateway.networking.k8s.io:web=assert-workload-identity"
+        )
+        .is_err());
+        assert!(parse_outbound_route_overrides(
+            "httproute.gateway.networking.k8s.io:/web=assert-workload-identity"
+        )
+        .is_err());
+        assert!(
+            parse_outbound_route_overrides("default:emojivoto/web=assert-workload-identity")
+                .is_err()
+        );
+        assert!(parse_outbound_route_overrides(":emojivoto/web=assert-workload-identity").is_err());
+    }
+
+    #[test]
+    fn outbound_route_classification_overrides() {
+        use outbound::policy::{grpc::Codes, http::StatusRanges, Meta};
+
+        let routes = parse_outbound_route_overrides(
+            "default:statuses=failure-statuses:404|500-503,\
+             default:successes=success-statuses:501|503-599,\
+             default:both=failure-statuses:400-499;success-statuses:404,\
+             default:codes=failure-codes:5|13;success-codes:13|14",
+        )
+        .unwrap();
+        assert_eq!(
+            routes.get(&Meta::new_default("statuses")).failure_statuses,
+            Some(StatusRanges(Arc::new([404..=404, 500..=503])))
+        );
+        // Successes are removed from the default failure statuses.
+        assert_eq!(
+            routes.get(&Meta::new_default("successes")).failure_statuses,
+            Some(StatusRanges(Arc::new([500..=500, 502..=502])))
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("both")).failure_statuses,
+            Some(StatusRanges(Arc::new([400..=403, 405..=499])))
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("codes")).failure_codes,
+            Some(Codes(Arc::new([5].into_iter().collect())))
+        );
+        assert!(parse_outbound_route_overrides("default:foo=failure-statuses").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=failure-statuses:600").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=failure-statuses:503-500").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=success-statuses:5xx").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=failure-codes:17").is_err());
+    }
+
+    #[test]
+    fn outbound_route_rollout_guards() {
+        use outbound::policy::{http::RolloutGuard, Meta};
+
+        let routes =
+            parse_outbound_route_overrides("default:foo=rollout-guard:1m|100|5|10m").unwrap();
+        assert_eq!(
+            routes.get(&Meta::new_default("foo")).rollout_guard,
+            Some(RolloutGuard {
+                window: Duration::from_secs(60),
+                min_requests: 100,
+                max_failure_rate_increase: 5,
+                cooldown: Duration::from_secs(600),
+            })
+        );
+        assert!(parse_outbound_route_overrides("default:foo=rollout-guard:1m|100|5").is_err());
+        assert!(
+            parse_outbound_route_overrides("default:foo=rollout-guard:1m|100|5|10m|1").is_err()
+        );
+        assert!(parse_outbound_route_overrides("default:foo=rollout-guard:0s|100|5|10m").is_err());
+        assert!(
+            parse_outbound_route_overrides("default:foo=rollout-guard:1m|100|101|10m").is_err()
+        );
+    }
+
+    #[test]
+    fn outbound_route_cookie_overrides() {
+        use outbound::policy::{http::r#match::MatchCookie, Meta};
+
+        let routes = parse_outbound_route_overrides(
+            "default:foo=cookie:group|beta;cookie-prefix:user|test-;cookie-regex:region|us-.*",
+        )
+        .unwrap();
+        assert_eq!(
+            routes.get(&Meta::new_default("foo")).cookies,
+            vec![
+                MatchCookie::Exact("group".to_string(), "beta".to_string()),
+                MatchCookie::Prefix("user".to_string(), "test-".to_string()),
+                MatchCookie::Regex("region".to_string(), regex::Regex::new("us-.*").unwrap()),
+            ]
+        );
+        assert!(parse_outbound_route_overrides("default:foo=cookie:group").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=cookie:|beta").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=cookie-regex:region|(").is_err());
+    }
+
+    #[test]
+    fn outbound_route_timeout_overrides() {
+        use outbound::policy::{Meta, RouteOverride};
+
+        let routes = parse_outbound_route_overrides(
+            "default:foo=response-headers-timeout:5s;response-chunk-idle-timeout:30s",
+        )
+        .unwrap();
+        assert_eq!(
+            routes.get(&Meta::new_default("foo")),
+            &RouteOverride {
+                response_headers_timeout: Some(Duration::from_secs(5)),
+                response_chunk_idle_timeout: Some(Duration::from_secs(30)),
+                ..Default::default()
+            }
+        );
+        assert!(parse_outbound_route_overrides("default:foo=response-headers-timeout").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=response-headers-timeout:0s").is_err());
+        assert!(
+            parse_outbound_route_overrides("default:foo=response-chunk-idle-timeout:1x").is_err()
+        );
+    }
+
+    #[test]
+    fn outbound_route_cache_overrides() {
+        use outbound::policy::{http::Cache, Meta};
+
+        let routes = parse_outbound_route_overrides(
+            "default:foo=cache, default:bar=cache:30s;cache-allow-authorization",
+        )
+        .unwrap();
+        assert_eq!(
+            routes.get(&Meta::new_default("foo")).cache,
+            Some(Cache::default())
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("bar")).cache,
+            Some(Cache {
+                allow_authorization: true,
+                max_age: Some(Duration::from_secs(30)),
+            })
+        );
+        assert_eq!(routes.get(&Meta::new_default("baz")).cache, None);
+        assert!(parse_outbound_route_overrides("default:foo=cache:0s").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=cache:soon").is_err());
+        assert!(
+            parse_outbound_route_overrides("default:foo=cache-allow-authorization:true").is_err()
+        );
+    }
+
+    #[test]
+    fn outbound_route_coalesce_overrides() {
+        use outbound::policy::{http::Coalesce, Meta};
+
+        let routes = parse_outbound_route_overrides(
+            "default:foo=coalesce:100|5s, default:bar=coalesce-headers:accept|x-tenant;coalesce:10|1s",
+        )
+        .unwrap();
+        assert_eq!(
+            routes.get(&Meta::new_default("foo")).coalesce,
+            Some(Coalesce {
+                headers: vec![],
+                max_waiters: 100,
+                timeout: Duration::from_secs(5),
+            })
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("bar")).coalesce,
+            Some(Coalesce {
+                headers: vec![
+                    HeaderName::from_static("accept"),
+                    HeaderName::from_static("x-tenant")
+                ],
+                max_waiters: 10,
+                timeout: Duration::from_secs(1),
+            })
+        );
+        assert_eq!(routes.get(&Meta::new_default("baz")).coalesce, None);
+        assert!(parse_outbound_route_overrides("default:foo=coalesce").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=coalesce:100").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=coalesce:0|5s").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=coalesce:100|0s").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=coalesce-headers:accept").is_err());
+        assert!(
+            parse_outbound_route_overrides("default:foo=coalesce:100|5s;coalesce-headers:a b")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn outbound_route_fault_overrides() {
+        use outbound::policy::{
+            http::{filter::Distribution, Fault, FaultAbort, FaultDelay, FaultDuration},
+            Meta,
+        };
+
+        let routes = parse_outbound_route_overrides(
+            "default:foo=fault-delay:10|100ms-1s, \
+             default:bar=fault-abort:5|503;fault-abort-grpc:5|14;fault-retry-aborts, \
+             default:baz=fault-abort-grpc:100|8",
+        )
+        .unwrap();
+        let delay = FaultDelay {
+            distribution: Distribution::from_ratio(10, 100).unwrap(),
+            duration: FaultDuration::Random(Duration::from_millis(100)..=Duration::from_secs(1)),
+        };
+        let foo = routes.get(&Meta::new_default("foo"));
+        assert_eq!(
+            foo.http_fault,
+            Some(Fault {
+                delay: Some(delay.clone()),
+                abort: None,
+                retry_aborts: false,
+            })
+        );
+        assert_eq!(
+            foo.grpc_fault,
+            Some(Fault {
+                delay: Some(delay),
+                abort: None,
+                retry_aborts: false,
+            })
+        );
+
+        let bar = routes.get(&Meta::new_default("bar"));
+        assert_eq!(
+            bar.http_fault,
+            Some(Fault {
+                delay: None,
+                abort: Some(FaultAbort {
+                    distribution: Distribution::from_ratio(5, 100).unwrap(),
+                    response: StatusCode::SERVICE_UNAVAILABLE,
+                }),
+                retry_aborts: true,
+            })
+        );
+        assert_eq!(
+            bar.grpc_fault,
+            Some(Fault {
+                delay: None,
+                abort: Some(FaultAbort {
+                    distribution: Distribution::from_ratio(5, 100).unwrap(),
+                    response: tonic::Code::Unavailable,
+                }),
+                retry_aborts: true,
+            })
+        );
+
+        let baz = routes.get(&Meta::new_default("baz"));
+        assert_eq!(baz.http_fault, None);
+        assert_eq!(
+            baz.grpc_fault,
+            Some(Fault {
+                delay: None,
+                abort: Some(FaultAbort {
+                    distribution: Distribution::default(),
+                    response: tonic::Code::ResourceExhausted,
+                }),
+                retry_aborts: false,
+            })
+        );
+
+        for invalid in [
+            "default:foo=fault-delay:10",
+            "default:foo=fault-delay:101|1s",
+            "default:foo=fault-delay:10|0s",
+            "default:foo=fault-delay:10|1s-1s",
+            "default:foo=fault-abort:10|200",
+            "default:foo=fault-abort-grpc:10|0",
+            "default:foo=fault-abort-grpc:10|17",
+            "default:foo=fault-delay:10|1s;fault-retry-aborts",
+        ] {
+            assert!(
+                parse_outbound_route_overrides(invalid).is_err(),
+                "{invalid} must be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn outbound_route_direct_response_overrides() {
+        use outbound::policy::{grpc, http::filter::DirectResponse, Meta};
+
+        let routes = parse_outbound_route_overrides(
+            "default:foo=direct-response:503;direct-response-header:retry-after|60;\
+             direct-response-body:down for maintenance, \
+             default:bar=direct-response-grpc:14|down for maintenance, \
+             default:baz=direct-response-grpc:12",
+        )
+        .unwrap();
+        let foo = routes.get(&Meta::new_default("foo"));
+        assert_eq!(
+            foo.http_direct_response,
+            Some(
+                DirectResponse::new(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    vec![(
+                        HeaderName::from_static("retry-after"),
+                        HeaderValue::from_static("60")
+                    )],
+                    "down for maintenance",
+                )
+                .unwrap()
+            )
+        );
+        assert_eq!(foo.grpc_direct_response, None);
+
+        let bar = routes.get(&Meta::new_default("bar"));
+        assert_eq!(bar.http_direct_response, None);
+        assert_eq!(
+            bar.grpc_direct_response,
+            Some(grpc::filter::DirectResponse {
+                code: 14,
+                message: "down for maintenance".into(),
+            })
+        );
+        assert_eq!(
+            routes
+                .get(&Meta::new_default("baz"))
+                .grpc_direct_response
+                .map(|d| d.message),
+            Some("".into())
+        );
+
+        let too_large = format!(
+            "default:foo=direct-response:200;direct-response-body:{}",
+            "a".repeat(DirectResponse::MAX_BODY_BYTES + 1)
+        );
+        for invalid in [
+            "default:foo=direct-response",
+            "default:foo=direct-response:600",
+            "default:foo=direct-response-body:hello",
+            "default:foo=direct-response-header:retry-after|60",
+            "default:foo=direct-response:503;direct-response-header:retry-after",
+            "default:foo=direct-response-grpc:17",
+            &too_large,
+        ] {
+            assert!(
+                parse_outbound_route_overrides(invalid).is_err(),
+                "{invalid} must be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn outbound_route_rewrite_authority_overrides() {
+        use outbound::policy::{http::filter::RewriteAuthority, Meta};
+
+        let routes = parse_outbound_route_overrides(
+            "default:foo=rewrite-authority:api.prod.svc.cluster.local:8080, \
+             default:bar=rewrite-authority-location;rewrite-authority:api.prod.svc.cluster.local",
+        )
+        .unwrap();
+        assert_eq!(
+            routes.get(&Meta::new_default("foo")).rewrite_authority,
+            Some(RewriteAuthority {
+                authority: "api.prod.svc.cluster.local:8080".parse().unwrap(),
+                rewrite_location: false,
+            })
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("bar")).rewrite_authority,
+            Some(RewriteAuthority {
+                authority: "api.prod.svc.cluster.local".parse().unwrap(),
+                rewrite_location: true,
+            })
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("baz")).rewrite_authority,
+            None
+        );
+
+        for invalid in [
+            "default:foo=rewrite-authority",
+            "default:foo=rewrite-authority:api prod",
+            "default:foo=rewrite-authority:user@api.prod.svc.cluster.local",
+            "default:foo=rewrite-authority-location",
+        ] {
+            assert!(
+                parse_outbound_route_overrides(invalid).is_err(),
+                "{invalid} must be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn outbound_route_decompress_request_overrides() {
+        use outbound::policy::{http::filter::DecompressRequest, Meta};
+
+        let routes = parse_outbound_route_overrides(
+            "default:foo=decompress-request, default:bar=decompress-request:65536",
+        )
+        .unwrap();
+        assert_eq!(
+            routes.get(&Meta::new_default("foo")).decompress_request,
+            Some(DecompressRequest {
+                max_bytes: DecompressRequest::DEFAULT_MAX_BYTES,
+            })
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("bar")).decompress_request,
+            Some(DecompressRequest { max_bytes: 65536 })
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("baz")).decompress_request,
+            None
+        );
+        assert!(parse_outbound_route_overrides("default:foo=decompress-request:0").is_err());
+        assert!(parse_outbound_route_overrides("default:foo=decompress-request:1MB").is_err());
+    }
+
+    #[test]
+    fn outbound_parent_overrides() {
+        use outbound::policy::{
+            tls::{sni::MatchSni, SniEnforcement},
+            Meta, ParentOverride,
+        };
+
+        let parents = parse_outbound_parent_overrides(
+            "default:foo=allowed-snis:api.example.com|*.example.com, default:bar=allowed-snis:api.example.com;allow-missing-sni",
+        )
+        .unwrap();
+        assert_eq!(
+            parents.get(&Meta::new_default("foo")).sni,
+            Some(SniEnforcement {
+                allowed: vec![
+                    MatchSni::Exact("api.example.com".to_string()),
+                    MatchSni::Suffix(vec!["com".to_string(), "example".to_string()]),
+                ]
+                .into(),
+                allow_missing: false,
+            })
+        );
+        assert!(
+            parents
+                .get(&Meta::new_default("bar"))
+                .sni
+                .as_ref()
+                .unwrap()
+                .allow_missing
+        );
+        assert_eq!(
+            parents.get(&Meta::new_default("baz")),
+            &ParentOverride::default()
+        );
+        assert!(parse_outbound_parent_overrides("default:foo=allowed-snis:").is_err());
+        assert!(
+            parse_outbound_parent_overrides("default:foo=allowed-snis:a.example.com|").is_err()
+        );
+        assert!(parse_outbound_parent_overrides("default:foo=allow-missing-sni").is_err());
+        assert!(parse_outbound_parent_overrides("default:foo=assert-workload-identity").is_err());
+    }
+
+    #[test]
+    fn inbound_route_overrides() {
+        use inbound::policy::{Meta, RouteOverride};
+
+        let routes = parse_inbound_route_overrides(
+            "default:foo=ext-authz;sheddable, default:bar=latency-objective:250ms",
+        )
+        .unwrap();
+        assert!(routes.get(&Meta::new_default("foo")).ext_authz);
+        assert!(routes.get(&Meta::new_default("foo")).sheddable);
+        assert!(!routes.get(&Meta::new_default("bar")).sheddable);
+        assert_eq!(
+            routes.get(&Meta::new_default("bar")).latency_objective,
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            routes.get(&Meta::new_default("baz")),
+            &RouteOverride::default()
+        );
+        assert_eq!(
+            parse_inbound_route_overrides(""),
+            Ok(inbound::policy::RouteOverrides::default())
+        );
+        assert!(parse_inbound_route_overrides("foo").is_err());
+        assert!(parse_inbound_route_overrides("default:foo=ext-authz:true").is_err());
+        assert!(parse_inbound_route_overrides("default:foo=latency-objective").is_err());
+        assert!(parse_inbound_route_overrides("default:foo=latency-objective:0s").is_err());
+        assert!(parse_inbound_route_overrides("default:foo=sheddable:true").is_err());
+        assert!(parse_inbound_route_overrides("default:foo=assert-workload-identity").is_err());
+        assert!(
+            parse_inbound_route_overrides("default:foo=ext-authz,default:foo=ext-authz").is_err()
+        );
+    }
+
+    #[test]
+    fn inbound_route_overrides_by_resource() {
+        use inbound::policy::Meta;
+
+        fn route(group: &str, kind: &str, name: &str) -> Meta {
+            Meta::Resource {
+                group: group.to_string(),
+                kind: kind.to_string(),
+                name: name.to_string(),
+            }
+        }
+
+        let routes =
+            parse_inbound_route_overrides("grpcroute.gateway.networking.k8s.io:api=sheddable")
+                .unwrap();
+        assert!(
+            routes
+                .get(&route("gateway.networking.k8s.io", "GRPCRoute", "api"))
+                .sheddable
+        );
+        assert!(
+            !routes
+                .get(&route("gateway.networking.k8s.io", "HTTPRoute", "api"))
+                .sheddable
+        );
+        assert!(!routes.get(&Meta::new_default("api")).sheddable);
+        assert!(parse_inbound_route_overrides("api=sheddable").is_err());
+        assert!(parse_inbound_route_overrides(
+            "grpcroute.gateway.networking.k8s.io:default/api=sheddable"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn inbound_server_overrides() {
+        use inbound::policy::Meta;
+
+        let server = |name: &str| Meta::Resource {
+            group: "policy.linkerd.io".to_string(),
+            kind: "Server".to_string(),
+            name: name.to_string(),
+        };
+
+        let servers = parse_inbound_server_overrides(
+            "server.policy.linkerd.io:web=termination-grace-period:30s",
+        )
+        .unwrap();
+        assert_eq!(
+            servers.get(&server("web")).termination_grace_period,
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            servers.get(&server("api")).termination_grace_period,
+            Duration::ZERO
+        );
+        assert_eq!(
+            parse_inbound_server_overrides(""),
+            Ok(inbound::policy::ServerOverrides::default())
+        );
+        assert!(parse_inbound_server_overrides("web=termination-grace-period:30s").is_err());
+        assert!(parse_inbound_server_overrides(
+            "server.policy.linkerd.io:web=termination-grace-period"
+        )
+        .is_err());
+        assert!(parse_inbound_server_overrides(
+            "server.policy.linkerd.io:web=termination-grace-period:soon"
+        )
+        .is_err());
+    }
+
     #[test]
     fn ip_sets() {
         let ips = &[
@@ -357,4 +2045,17 @@ mod tests {
         assert!(dbg!(parse_port_range_set("69420")).is_err());
         assert!(dbg!(parse_port_range_set("1-69420")).is_err());
     }
+
+    #[test]
+    fn admin_endpoints() {
+        use crate::admin::{Endpoint, Endpoints};
+
+        assert_eq!(
+            parse_admin_endpoints("metrics, ready,,live").unwrap(),
+            [Endpoint::Metrics, Endpoint::Ready, Endpoint::Live]
+                .into_iter()
+                .collect::<Endpoints>()
+        );
+        assert!(parse_admin_endpoints("metrics,tasks").is_err());
+    }
 }
@@ -0,0 +1,344 @@
+//! Validates a configuration without starting the proxy.
+//!
+//! Validation parses the configuration exactly as the proxy does at startup.
+//! The parser reports problems through `tracing`, so validation collects the
+//! warnings and errors that are emitted while parsing into a [`Validation`]
+//! report rather than logging them.
+
+use super::{parse_config, EnvError, Strings};
+use linkerd_app_core::{control::ControlAddr, Addr};
+use parking_lot::Mutex;
+use std::{fmt, net::SocketAddr, sync::Arc};
+use tracing::{field, span, Event, Level, Metadata};
+
+/// The result of validating a configuration.
+#[derive(Debug)]
+pub struct Validation {
+    /// The parsed configuration, if it is valid.
+    pub config: Option<crate::Config>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub resolutions: Vec<Resolution>,
+}
+
+/// The result of resolving a control plane component's name.
+#[derive(Debug)]
+pub struct Resolution {
+    pub component: &'static str,
+    pub addr: String,
+    pub result: Result<Vec<SocketAddr>, String>,
+}
+
+/// Collects the warnings and errors emitted while parsing.
+#[derive(Clone, Default)]
+struct Collect(Arc<Mutex<Diagnostics>>);
+
+#[derive(Default)]
+struct Diagnostics {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Formats an event's message followed by its other fields.
+#[derive(Default)]
+struct Message {
+    message: String,
+    fields: Vec<String>,
+}
+
+// === impl Validation ===
+
+impl Validation {
+    /// Parses the configuration from `strings` as the proxy does at startup.
+    pub fn new<S: Strings>(strings: &S) -> Self {
+        let collect = Collect::default();
+        let config = tracing::subscriber::with_default(collect.clone(), || parse_config(strings));
+
+        let Diagnostics {
+            mut errors,
+            warnings,
+        } = std::mem::take(&mut *collect.0.lock());
+        let config = match config {
+            Ok(config) => Some(config),
+            Err(error) => {
+                // Invalid variables have already been described by the parser.
+                if errors.is_empty() || !matches!(error, EnvError::InvalidEnvVar) {
+                    errors.push(error.to_string());
+                }
+                None
+            }
+        };
+
+        Self {
+            config,
+            errors,
+            warnings,
+            resolutions: Vec::new(),
+        }
+    }
+
+    /// Resolves the names of the control plane components in the
+    /// configuration. Names that cannot be resolved are reported as errors.
+    pub async fn resolve(&mut self) {
+        let Some(config) = self.config.as_ref() else {
+            return;
+        };
+
+        let mut addrs = vec![
+            ("destination", &config.dst.control.addr),
+            ("policy", &config.policy.control.addr),
+        ];
+        if let crate::identity::Config::Linkerd { client, .. } = &config.identity {
+            addrs.push(("identity", &client.addr));
+        }
+        if let crate::trace_collector::Config::Enabled(collector) = &config.trace_collector {
+            addrs.push(("trace collector", &collector.control.addr));
+        }
+
+        for (component, ControlAddr { addr, .. }) in addrs {
+            let Addr::Name(name) = addr else {
+                continue;
+            };
+            let result = match tokio::net::lookup_host(name.to_string()).await {
+                Ok(addrs) => Ok(addrs.collect()),
+                Err(error) => {
+                    self.errors.push(format!(
+                        "Failed to resolve {component} address {name}: {error}"
+                    ));
+                    Err(error.to_string())
+                }
+            };
+            self.resolutions.push(Resolution {
+                component,
+                addr: name.to_string(),
+                result,
+            });
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.config.is_some() && self.errors.is_empty()
+    }
+
+    /// Returns a JSON report of the validation.
+    pub fn to_json(&self) -> String {
+        let resolutions = self
+            .resolutions
+            .iter()
+            .map(|r| match &r.result {
+                Ok(addrs) => serde_json::json!({
+                    "component": r.component,
+                    "addr": r.addr,
+                    "resolved": addrs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                }),
+                Err(error) => serde_json::json!({
+                    "component": r.component,
+                    "addr": r.addr,
+                    "error": error,
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        let report = serde_json::json!({
+            "valid": self.is_valid(),
+            "errors": self.errors,
+            "warnings": self.warnings,
+            "resolutions": resolutions,
+        });
+        serde_json::to_string_pretty(&report).expect("report must serialize")
+    }
+}
+
+// === impl Collect ===
+
+impl tracing::Subscriber for Collect {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_event() && *metadata.level() <= Level::WARN
+    }
+
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = Message::default();
+        event.record(&mut message);
+        let mut diagnostics = self.0.lock();
+        if *event.metadata().level() == Level::ERROR {
+            diagnostics.errors.push(message.to_string());
+        } else {
+            diagnostics.warnings.push(message.to_string());
+        }
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+// === impl Message ===
+
+impl field::Visit for Message {
+    fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields.push(format!("{}={value:?}", field.name()));
+        }
+    }
+
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.push(format!("{}={value}", field.name()));
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        for field in &self.fields {
+            write!(f, " {field}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn reports_invalid_values() {
+        let mut env = HashMap::default();
+        env.insert(ENV_INBOUND_DETECT_TIMEOUT, "10 parsecs");
+        env.insert(ENV_DESTINATION_PROFILE_NETWORKS, "10.0.0.0/33");
+        let validation = Validation::new(&env);
+        assert!(!validation.is_valid());
+        assert!(validation.config.is_none());
+        for name in [ENV_INBOUND_DETECT_TIMEOUT, ENV_DESTINATION_PROFILE_NETWORKS] {
+            assert!(
+                validation.errors.iter().any(|e| e.contains(name)),
+                "{name} must be reported: {:?}",
+                validation.errors
+            );
+        }
+    }
+
+    #[test]
+    fn reports_missing_configuration() {
+        let validation = Validation::new(&HashMap::<&str, &str>::default());
+        assert!(!validation.is_valid());
+        assert!(!validation.errors.is_empty());
+
+        let report = serde_json::from_str::<serde_json::Value>(&validation.to_json()).unwrap();
+        assert_eq!(report["valid"], false);
+    }
+
+    /// Returns true if `value` is reported as an invalid outbound route
+    /// override when the configuration is parsed.
+    fn reports_route_overrides(value: &str) -> bool {
+        let mut env = HashMap::default();
+        env.insert("LINKERD2_PROXY_DESTINATION_SVC_ADDR", "127.0.0.1:8086");
+        env.insert("LINKERD2_PROXY_POLICY_SVC_ADDR", "127.0.0.1:8090");
+        env.insert(ENV_POLICY_WORKLOAD, "test:test");
+        env.insert(ENV_OUTBOUND_ROUTE_OVERRIDES, value);
+        Validation::new(&env)
+            .errors
+            .iter()
+            .any(|e| e.contains(ENV_OUTBOUND_ROUTE_OVERRIDES))
+    }
+
+    #[test]
+    fn reports_invalid_route_cache_overrides() {
+        assert!(!reports_route_overrides(
+            "default:foo=cache:30s;cache-allow-authorization"
+        ));
+        assert!(reports_route_overrides("default:foo=cache:0s"));
+        assert!(reports_route_overrides("foo=cache"));
+    }
+
+    #[test]
+    fn reports_invalid_route_coalesce_overrides() {
+        assert!(!reports_route_overrides(
+            "default:foo=coalesce:100|5s;coalesce-headers:accept"
+        ));
+        assert!(reports_route_overrides("default:foo=coalesce:100"));
+        assert!(reports_route_overrides(
+            "default:foo=coalesce-headers:accept"
+        ));
+    }
+
+    #[test]
+    fn reports_invalid_route_fault_overrides() {
+        assert!(!reports_route_overrides(
+            "default:foo=fault-delay:10|100ms;fault-abort:5|503;fault-retry-aborts"
+        ));
+        assert!(reports_route_overrides("default:foo=fault-abort:5|200"));
+        assert!(reports_route_overrides("default:foo=fault-retry-aborts"));
+    }
+
+    #[test]
+    fn reports_invalid_route_direct_response_overrides() {
+        assert!(!reports_route_overrides(
+            "default:foo=direct-response:503;direct-response-body:down, \
+             default:bar=direct-response-grpc:14|down"
+        ));
+        assert!(reports_route_overrides(&format!(
+            "default:foo=direct-response:200;direct-response-body:{}",
+            "a".repeat(9 * 1024)
+        )));
+        assert!(reports_route_overrides(
+            "default:foo=direct-response-header:retry-after|60"
+        ));
+    }
+
+    #[test]
+    fn reports_invalid_route_rewrite_authority_overrides() {
+        assert!(!reports_route_overrides(
+            "default:foo=rewrite-authority:api.prod.svc.cluster.local:8080;\
+             rewrite-authority-location"
+        ));
+        assert!(reports_route_overrides(
+            "default:foo=rewrite-authority:api prod"
+        ));
+        assert!(reports_route_overrides(
+            "default:foo=rewrite-authority-location"
+        ));
+    }
+
+    #[test]
+    fn reports_invalid_route_decompress_request_overrides() {
+        assert!(!reports_route_overrides(
+            "default:foo=decompress-request, default:bar=decompress-request:65536"
+        ));
+        assert!(reports_route_overrides("default:foo=decompress-request:0"));
+    }
+
+    #[test]
+    fn warns_on_conflicting_ports() {
+        let mut env = HashMap::default();
+        env.insert(ENV_INBOUND_PORTS_DISABLE_PROTOCOL_DETECTION, "3306,8080");
+        env.insert(
+            ENV_INBOUND_PORTS_HTTP2_SETTINGS,
+            "8080=max-concurrent-streams:10",
+        );
+        let validation = Validation::new(&env);
+        assert!(
+            validation
+                .warnings
+                .iter()
+                .any(|w| w.contains(ENV_INBOUND_PORTS_HTTP2_SETTINGS) && w.contains("8080")),
+            "{:?}",
+            validation.warnings
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use linkerd_app_core::{
+    control, dns, identity, metrics,
+    svc::{NewService, ServiceExt},
+    Error,
+};
+use linkerd_app_inbound::{ExtAuthzConfig, Inbound};
+
+/// Configures the external authorization service consulted by the inbound
+/// proxy.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub control: control::Config,
+    pub check: ExtAuthzConfig,
+}
+
+// === impl Config ===
+
+impl Config {
+    /// Configures `inbound` to consult the external authorization service.
+    pub fn build(
+        self,
+        inbound: Inbound<()>,
+        dns: dns::Resolver,
+        legacy_metrics: metrics::ControlHttp,
+        control_metrics: control::Metrics,
+        identity: identity::NewClient,
+    ) -> Inbound<()> {
+        let client = self
+            .control
+            .build(dns, legacy_metrics, control_metrics, identity)
+            .new_service(())
+            .map_err(Error::from);
+        inbound.with_ext_authz(self.check, client)
+    }
+}
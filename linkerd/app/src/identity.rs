@@ -1,13 +1,16 @@
+#[cfg(feature = "spire")]
 use crate::spire;
 
 pub use linkerd_app_core::identity::{client, Id};
 use linkerd_app_core::{
     control, dns,
     identity::{
-        client::linkerd::Certify, creds, CertMetrics, Credentials, DerX509, Mode, WithCertMetrics,
+        client::linkerd::Certify, creds, CertMetrics, Credentials, DerX509, Mode, Provider,
+        WithCertMetrics,
     },
     metrics::{prom, ControlHttp as ClientMetrics},
-    Result,
+    proxy::http,
+    svc, tls, Result,
 };
 use std::{future::Future, pin::Pin, time::SystemTime};
 use tokio::sync::watch;
@@ -25,6 +28,7 @@ pub enum Config {
         certify: client::linkerd::Config,
         tls: TlsParams,
     },
+    #[cfg(feature = "spire")]
     Spire {
         client: spire::Config,
         tls: TlsParams,
@@ -36,6 +40,7 @@ pub struct TlsParams {
     pub id: Id,
     pub server_name: dns::Name,
     pub trust_anchors_pem: String,
+    pub policy: tls::TlsPolicy,
 }
 
 pub struct Identity {
@@ -52,6 +57,30 @@ pub struct IdentityMetrics {
     client: control::Metrics,
 }
 
+/// Obtains certificates from the Linkerd identity controller, refreshing them
+/// before they expire.
+struct LinkerdProvider {
+    addr: control::ControlAddr,
+    certify: Certify,
+    name: dns::Name,
+    client: ControlClient,
+}
+
+/// Obtains certificates from a SPIFFE Workload API, publishing them as they
+/// are rotated.
+#[cfg(feature = "spire")]
+struct SpireProvider {
+    spire: spire::client::Spire,
+    client: spire::Config,
+}
+
+type ControlClient = svc::ArcNewService<
+    (),
+    svc::BoxCloneSyncService<http::Request<tonic::body::BoxBody>, http::Response<control::RspBody>>,
+>;
+
+type Store = WithCertMetrics<NotifyReady>;
+
 /// Wraps a credential with a watch sender that notifies receivers when the store has been updated
 /// at least once.
 struct NotifyReady {
@@ -75,8 +104,9 @@ impl Config {
         dns: dns::Resolver,
         client_metrics: ClientMetrics,
         metrics: IdentityMetrics,
+        handshakes: tls::HandshakeMetrics,
     ) -> Result<Identity> {
-        Ok(match self {
+        match self {
             Self::Linkerd {
                 client,
                 certify,
@@ -90,57 +120,86 @@ impl Config {
                     }
                 };
 
-                let certify = Certify::from(certify);
-                let (store, receiver, ready) = watch(tls, metrics.cert)?;
-
-                let task = {
-                    let addr = client.addr.clone();
-                    let svc =
-                        client.build(dns, client_metrics, metrics.client, receiver.new_client());
-
-                    Box::pin(certify.run(name, store, svc).instrument(
-                        tracing::info_span!("identity", server.addr = %addr).or_current(),
-                    ))
+                let (store, receiver, ready) = watch(tls, metrics.cert, handshakes)?;
+                let provider = LinkerdProvider {
+                    addr: client.addr.clone(),
+                    certify: Certify::from(certify),
+                    name,
+                    client: client.build(
+                        dns,
+                        client_metrics,
+                        metrics.client,
+                        receiver.new_client(),
+                    ),
                 };
-                Identity {
-                    receiver,
-                    ready,
-                    task,
-                }
+                Ok(Identity::new(provider, store, receiver, ready))
             }
+            #[cfg(feature = "spire")]
             Self::Spire { client, tls } => {
-                let addr = client.workload_api_addr.clone();
-                let spire = spire::client::Spire::new(tls.id.clone());
-
-                let (store, receiver, ready) = watch(tls, metrics.cert)?;
-                let task =
-                    Box::pin(spire.run(store, spire::Client::from(client)).instrument(
-                        tracing::info_span!("spire", server.addr = %addr).or_current(),
-                    ));
-
-                Identity {
-                    receiver,
-                    ready,
-                    task,
-                }
+                let provider = SpireProvider {
+                    spire: spire::client::Spire::new(tls.id.clone()),
+                    client,
+                };
+                let (store, receiver, ready) = watch(tls, metrics.cert, handshakes)?;
+                Ok(Identity::new(provider, store, receiver, ready))
             }
-        })
+        }
+    }
+}
+
+// === impl LinkerdProvider ===
+
+impl<C> Provider<C> for LinkerdProvider
+where
+    C: Credentials + Send + 'static,
+{
+    type Future = Task;
+
+    fn run(self, credentials: C) -> Task {
+        let Self {
+            addr,
+            certify,
+            name,
+            client,
+        } = self;
+        Box::pin(
+            certify
+                .run(name, credentials, client)
+                .instrument(tracing::info_span!("identity", server.addr = %addr).or_current()),
+        )
+    }
+}
+
+// === impl SpireProvider ===
+
+#[cfg(feature = "spire")]
+impl<C> Provider<C> for SpireProvider
+where
+    C: Credentials + Send + 'static,
+{
+    type Future = Task;
+
+    fn run(self, credentials: C) -> Task {
+        let Self { spire, client } = self;
+        let addr = client.workload_api_addr.clone();
+        Box::pin(
+            spire
+                .run(credentials, spire::Client::from(client))
+                .instrument(tracing::info_span!("spire", server.addr = %addr).or_current()),
+        )
     }
 }
 
 fn watch(
     tls: TlsParams,
     metrics: CertMetrics,
-) -> Result<(
-    WithCertMetrics<NotifyReady>,
-    creds::Receiver,
-    watch::Receiver<bool>,
-)> {
+    handshakes: tls::HandshakeMetrics,
+) -> Result<(Store, creds::Receiver, watch::Receiver<bool>)> {
     let (tx, ready) = watch::channel(false);
     let (store, receiver) =
-        Mode::default().watch(tls.id, tls.server_name, &tls.trust_anchors_pem)?;
+        Mode::default().watch(tls.id, tls.server_name, &tls.trust_anchors_pem, tls.policy)?;
     let cred = WithCertMetrics::new(metrics, NotifyReady { store, tx });
-    Ok((cred, receiver, ready))
+    Ok((cred, receiver.with_handshake_metrics(handshakes), ready))
 }
 
 // === impl NotifyReady ===
@@ -162,6 +221,24 @@ impl Credentials for NotifyReady {
 // === impl Identity ===
 
 impl Identity {
+    /// Drives the provider's credentials into the store from which TLS
+    /// clients and servers read them.
+    fn new<P>(
+        provider: P,
+        store: Store,
+        receiver: creds::Receiver,
+        ready: watch::Receiver<bool>,
+    ) -> Self
+    where
+        P: Provider<Store>,
+    {
+        Self {
+            receiver,
+            ready,
+            task: Box::pin(provider.run(store)),
+        }
+    }
+
     /// Returns a future that is satisfied once certificates have been provisioned.
     pub fn ready(&self) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>> {
         let mut ready = self.ready.clone();
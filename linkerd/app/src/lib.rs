@@ -6,11 +6,16 @@
 
 pub mod dst;
 pub mod env;
+pub mod ext_authz;
 pub mod identity;
 pub mod policy;
+pub mod self_check;
+#[cfg(feature = "spire")]
 pub mod spire;
+pub mod startup;
 pub mod tap;
 pub mod trace_collector;
+pub mod tunables;
 
 pub use self::metrics::Metrics;
 use futures::{future, Future, FutureExt};
@@ -18,20 +23,25 @@ use linkerd_app_admin as admin;
 use linkerd_app_core::{
     config::ServerConfig,
     control::{ControlAddr, Metrics as ControlMetrics},
-    dns, drain,
+    dns, drain, http_tracing,
     metrics::{legacy::FmtMetrics, prom},
+    proxy::{api_resolve, resolve},
     serve,
     svc::Param,
-    tls_info,
-    transport::{addrs::*, listen::Bind},
+    tls, tls_info,
+    transport::{addrs::*, listen::Bind, udp, OrigDstFallback},
     Error, ProxyRuntime,
 };
-pub use linkerd_app_core::{metrics, trace, transport::BindTcp, BUILD_INFO};
+pub use linkerd_app_core::{
+    metrics, panics, trace,
+    transport::{BindTcp, OrigDstMetrics},
+    BUILD_INFO,
+};
 use linkerd_app_gateway as gateway;
 use linkerd_app_inbound::{self as inbound, Inbound};
 use linkerd_app_outbound::{self as outbound, Outbound};
 pub use linkerd_workers::Workers;
-use std::pin::Pin;
+use std::{collections::HashSet, net::SocketAddr, pin::Pin, sync::Arc};
 use tokio::{
     sync::mpsc,
     time::{self, Duration},
@@ -64,6 +74,29 @@ pub struct Config {
     pub tap: tap::Config,
     pub trace_collector: trace_collector::Config,
 
+    /// Configures the external authorization service consulted for inbound
+    /// requests on routes that require it, if at all.
+    pub ext_authz: Option<ext_authz::Config>,
+
+    /// Configures how accepted connections are handled when their original
+    /// destination address cannot be determined, if at all.
+    pub orig_dst_fallback: Option<OrigDstFallback>,
+
+    /// Configures forwarding of redirected UDP datagrams, if at all.
+    pub outbound_udp: Option<udp::Config>,
+
+    /// Configures how application connections are handled before the proxy
+    /// is ready.
+    pub startup: startup::Config,
+
+    /// Configures checks that the inbound and outbound listeners are serving
+    /// connections, if at all.
+    pub self_check: Option<self_check::Config>,
+
+    /// Configures a file of settings that may be changed while the proxy is
+    /// running, if at all.
+    pub tunables: Option<tunables::Config>,
+
     /// Grace period for graceful shutdowns.
     ///
     /// If the proxy does not shut down gracefully within this timeout, it will
@@ -80,7 +113,13 @@ pub struct App {
     trace_collector: trace_collector::TraceCollector,
     outbound_addr: Local<ServerAddr>,
     outbound_addr_additional: Option<Local<ServerAddr>>,
+    outbound_explicit_addr: Option<Local<ServerAddr>>,
+    outbound_socks5_addr: Option<Local<ServerAddr>>,
+    outbound_listener_addrs: Vec<Local<ServerAddr>>,
+    outbound_udp_addr: Option<Local<ServerAddr>>,
+    self_check: Option<self_check::Config>,
     start_proxy: Pin<Box<dyn std::future::Future<Output = ()> + Send + 'static>>,
+    startup: startup::Gate,
     tap: tap::Tap,
 }
 
@@ -110,7 +149,7 @@ impl Config {
             + Param<Local<ServerAddr>>
             + Param<OrigDstAddr>
             + Param<AddrPair>,
-        BOut: Bind<ServerConfig, BoundAddrs = DualLocal<ServerAddr>> + 'static,
+        BOut: Bind<ServerConfig, BoundAddrs = DualLocal<ServerAddr>> + Clone + 'static,
         BOut::Addrs: Param<Remote<ClientAddr>>
             + Param<Local<ServerAddr>>
             + Param<OrigDstAddr>
@@ -129,6 +168,11 @@ impl Config {
             outbound,
             gateway,
             tap,
+            outbound_udp,
+            startup,
+            self_check,
+            tunables,
+            ext_authz,
             ..
         } = self;
         debug!("Building app");
@@ -143,12 +187,15 @@ impl Config {
             let id_metrics = identity::IdentityMetrics::register(
                 registry.sub_registry_with_prefix("control_identity"),
             );
+            let handshakes =
+                tls::HandshakeMetrics::register(registry.sub_registry_with_prefix("tls"));
 
             info_span!("identity").in_scope(|| {
                 identity.build(
                     dns.resolver("identity"),
                     metrics.control.clone(),
                     id_metrics,
+                    handshakes,
                 )
             })?
         };
@@ -162,24 +209,13 @@ impl Config {
                 .in_scope(|| tap.build(bind, identity.receiver().server(), drain_rx.clone()))?
         };
 
-        debug!("Building Destination client");
-        let dst = {
-            let control_metrics =
-                ControlMetrics::register(registry.sub_registry_with_prefix("control_destination"));
-            let metrics = metrics.control.clone();
-            let dns = dns.resolver("destination");
-            info_span!("dst").in_scope(|| {
-                dst.build(
-                    dns,
-                    metrics,
-                    control_metrics,
-                    identity.receiver().new_client(),
-                )
-            })
-        }?;
-
         debug!("Building Policy client");
-        let export_hostname_labels = policy.export_hostname_labels;
+        let overrides = outbound::policy::ClientPolicyOverrides {
+            export_hostname_labels: policy.export_hostname_labels,
+            export_method_labels: policy.export_method_labels,
+            routes: policy.outbound_routes.clone(),
+            parents: policy.outbound_parents.clone(),
+        };
         let policies = {
             let control_metrics =
                 ControlMetrics::register(registry.sub_registry_with_prefix("control_policy"));
@@ -197,11 +233,19 @@ impl Config {
 
         debug!(config = ?trace_collector, "Building trace collector");
         let trace_collector = {
-            let control_metrics = if let Some(prefix) = trace_collector.metrics_prefix() {
-                ControlMetrics::register(registry.sub_registry_with_prefix(prefix))
-            } else {
-                ControlMetrics::register(&mut prom::Registry::default())
-            };
+            let (control_metrics, span_metrics) =
+                if let Some(prefix) = trace_collector.metrics_prefix() {
+                    let registry = registry.sub_registry_with_prefix(prefix);
+                    (
+                        ControlMetrics::register(registry),
+                        http_tracing::SpanQueueMetrics::register(registry),
+                    )
+                } else {
+                    (
+                        ControlMetrics::register(&mut prom::Registry::default()),
+                        Default::default(),
+                    )
+                };
             let identity = identity.receiver().new_client();
             let dns = dns.resolver("trace_collector");
             let client_metrics = metrics.control.clone();
@@ -215,28 +259,82 @@ impl Config {
                     otel_metrics,
                     control_metrics,
                     client_metrics,
+                    span_metrics,
                 )
             })
         }?;
 
+        let tunables = match tunables {
+            Some(config) => config.build(registry.sub_registry_with_prefix("tunables")),
+            None => tokio::sync::watch::channel(Default::default()).1,
+        };
+
         let runtime = ProxyRuntime {
             identity: identity.receiver(),
             metrics: metrics.proxy,
             tap: tap.registry(),
             span_sink: trace_collector.span_sink(),
             drain: drain_rx.clone(),
+            tunables,
         };
         let inbound = Inbound::new(
             inbound,
             runtime.clone(),
             registry.sub_registry_with_prefix("inbound"),
         );
+        let inbound = match ext_authz {
+            None => inbound,
+            Some(ext_authz) => {
+                debug!("Building external authorization client");
+                let control_metrics = ControlMetrics::register(
+                    registry.sub_registry_with_prefix("control_ext_authz"),
+                );
+                let dns = dns.resolver("ext_authz");
+                let metrics = metrics.control.clone();
+                info_span!("ext_authz").in_scope(|| {
+                    ext_authz.build(
+                        inbound,
+                        dns,
+                        metrics,
+                        control_metrics,
+                        identity.receiver().new_client(),
+                    )
+                })
+            }
+        };
         let outbound = Outbound::new(
             outbound,
             runtime,
             registry.sub_registry_with_prefix("outbound"),
         );
 
+        // Endpoint resolutions are recorded in the outbound discovery
+        // snapshot, if one is configured.
+        let discovery_snapshot = outbound.discovery_snapshot();
+        debug!("Building Destination client");
+        let dst = {
+            let control_metrics =
+                ControlMetrics::register(registry.sub_registry_with_prefix("control_destination"));
+            let resolution_metrics = resolve::shared::SharedMetrics::register(
+                registry.sub_registry_with_prefix("destination_shared_resolutions"),
+            );
+            let metrics = metrics.control.clone();
+            let dns = dns.resolver("destination");
+            let observe = discovery_snapshot
+                .clone()
+                .map(|s| Arc::new(s) as Arc<dyn api_resolve::Observe>);
+            info_span!("dst").in_scope(|| {
+                dst.build(
+                    dns,
+                    metrics,
+                    control_metrics,
+                    resolution_metrics,
+                    identity.receiver().new_client(),
+                    observe,
+                )
+            })
+        }?;
+
         let inbound_policies = inbound.build_policies(
             policies.workload.clone(),
             policies.client.clone(),
@@ -244,18 +342,29 @@ impl Config {
             policies.limits,
         );
 
+        // The ports whose inbound policies are discovered at startup.
+        let inbound_policy_ports = match inbound.config().policy {
+            inbound::policy::Config::Discover { ref ports, .. } => ports.clone(),
+            inbound::policy::Config::Fixed { .. } => Default::default(),
+        };
+
         let outbound_policies = outbound.build_policies(
             policies.workload.clone(),
             policies.client.clone(),
             policies.backoff,
             policies.limits,
-            export_hostname_labels,
+            overrides,
         );
 
         let dst_addr = dst.addr.clone();
-        // registry.sub_registry_with_prefix("gateway"),
 
-        let gateway = gateway::Gateway::new(gateway, inbound.clone(), outbound.clone()).stack(
+        let gateway = gateway::Gateway::new(
+            gateway,
+            inbound.clone(),
+            outbound.clone(),
+            registry.sub_registry_with_prefix("gateway"),
+        )
+        .stack(
             dst.resolve.clone(),
             dst.profiles.clone(),
             outbound_policies.clone(),
@@ -273,28 +382,178 @@ impl Config {
             dst.profiles.clone(),
             gateway.into_inner(),
         );
+        let inbound = self_check::NewAcceptSelfCheck::layer(self_check.as_ref()).layer(inbound);
 
         let ((outbound_addr, outbound_addr_additional), outbound_listen) = bind_out
+            .clone()
             .bind(&outbound.config().proxy.server)
             .expect("Failed to bind outbound listener");
         let outbound_metrics = outbound.metrics();
-        let outbound = outbound.mk(dst.profiles.clone(), outbound_policies, dst.resolve.clone());
+        let rollout_guards = outbound_metrics.rollout_guards();
+        let breakers = outbound_metrics.breakers();
+        let backend_faults = outbound_metrics.backend_faults();
+        let discovery_retention = outbound.discovery_retention();
+        let port_mappings = outbound.port_mappings();
+        let outbound_explicit = match outbound.config().explicit_proxy.clone() {
+            None => None,
+            Some(server) => {
+                let (addr, listen) = bind_admin
+                    .clone()
+                    .bind(&server)
+                    .expect("Failed to bind explicit outbound listener");
+                let explicit = outbound.mk_explicit(
+                    dst.profiles.clone(),
+                    outbound_policies.clone(),
+                    dst.resolve.clone(),
+                );
+                Some((addr, listen, explicit))
+            }
+        };
+        let outbound_explicit_addr = outbound_explicit.as_ref().map(|(addr, _, _)| *addr);
+        let outbound_socks5 = match outbound.config().socks5_proxy.clone() {
+            None => None,
+            Some(socks5) => {
+                let (addr, listen) = bind_admin
+                    .clone()
+                    .bind(&socks5.server)
+                    .expect("Failed to bind SOCKS5 outbound listener");
+                let socks5 = outbound.mk_socks5(
+                    dst.profiles.clone(),
+                    outbound_policies.clone(),
+                    dst.resolve.clone(),
+                );
+                Some((addr, listen, socks5))
+            }
+        };
+        let outbound_socks5_addr = outbound_socks5.as_ref().map(|(addr, _, _)| *addr);
+        let outbound_listeners = outbound
+            .config()
+            .additional_listeners
+            .iter()
+            .map(|listener| {
+                let ((addr, _), listen) = bind_out
+                    .clone()
+                    .bind(&listener.server)
+                    .expect("Failed to bind additional outbound listener");
+                (listener.name.clone(), addr, listen)
+            })
+            .collect::<Vec<_>>();
+        let outbound_listener_addrs = outbound_listeners
+            .iter()
+            .map(|(_, addr, _)| *addr)
+            .collect();
+        // Additional listeners share the primary outbound stack, and its
+        // discovery caches, unless they override its configuration.
+        let primary = outbound.clone().mk(
+            dst.profiles.clone(),
+            outbound_policies.clone(),
+            dst.resolve.clone(),
+        );
+        let outbound_listeners = outbound_listeners
+            .into_iter()
+            .zip(outbound.mk_listeners(
+                &primary,
+                dst.profiles.clone(),
+                outbound_policies,
+                dst.resolve.clone(),
+            ))
+            .map(|((name, _, listen), stack)| (name, listen, stack))
+            .collect::<Vec<_>>();
+        let outbound = self_check::NewAcceptSelfCheck::layer(self_check.as_ref()).layer(primary);
+
+        let outbound_udp = outbound_udp
+            .map(|config| {
+                let metrics =
+                    udp::UdpMetrics::register(registry.sub_registry_with_prefix("outbound_udp"));
+                udp::UdpForwarder::bind(config, metrics)
+            })
+            .transpose()?;
+        let outbound_udp_addr = outbound_udp
+            .as_ref()
+            .map(|udp| Local(ServerAddr(udp.local_addr())));
+
+        // Hold application connections until the proxy is ready, i.e. its
+        // identity has been certified and, if configured, its inbound policies
+        // have been discovered.
+        let (startup, run_startup) = {
+            let identity_ready = identity.ready();
+            let policy_ready = if startup.await_policy {
+                Some(Self::await_inbound_policies(
+                    inbound_addr,
+                    inbound_policy_ports,
+                    inbound_policies.clone(),
+                ))
+            } else {
+                None
+            };
+            startup.build(async move {
+                Self::await_identity(identity_ready).await;
+                if let Some(policy_ready) = policy_ready {
+                    policy_ready.await;
+                }
+            })
+        };
 
         // Build a task that initializes and runs the proxy stacks.
         let start_proxy = {
             let drain_rx = drain_rx.clone();
-            let identity_ready = identity.ready();
+            let startup = startup.clone();
 
             Box::pin(async move {
-                Self::await_identity(identity_ready).await;
+                tokio::spawn(run_startup.instrument(info_span!("startup").or_current()));
+
+                if let Some(snapshot) = discovery_snapshot {
+                    tokio::spawn(
+                        snapshot
+                            .persist(drain_rx.clone().signaled())
+                            .instrument(info_span!("discovery_snapshot").or_current()),
+                    );
+                }
 
                 tokio::spawn(
-                    serve::serve(outbound_listen, outbound, drain_rx.clone().signaled())
-                        .instrument(info_span!("outbound").or_current()),
+                    serve::serve(
+                        startup.hold(outbound_listen),
+                        outbound,
+                        drain_rx.clone().signaled(),
+                    )
+                    .instrument(info_span!("outbound").or_current()),
                 );
 
+                if let Some((_, listen, explicit)) = outbound_explicit {
+                    tokio::spawn(
+                        serve::serve(startup.hold(listen), explicit, drain_rx.clone().signaled())
+                            .instrument(info_span!("outbound").or_current()),
+                    );
+                }
+
+                if let Some((_, listen, socks5)) = outbound_socks5 {
+                    tokio::spawn(
+                        serve::serve(startup.hold(listen), socks5, drain_rx.clone().signaled())
+                            .instrument(info_span!("outbound").or_current()),
+                    );
+                }
+
+                for (name, listen, stack) in outbound_listeners {
+                    tokio::spawn(
+                        serve::serve(startup.hold(listen), stack, drain_rx.clone().signaled())
+                            .instrument(info_span!("outbound", listener = %name).or_current()),
+                    );
+                }
+
+                if let Some(udp) = outbound_udp {
+                    let released = startup.clone().released();
+                    let shutdown = drain_rx.clone().signaled();
+                    tokio::spawn(
+                        async move {
+                            released.await;
+                            udp.serve(shutdown).await
+                        }
+                        .instrument(info_span!("outbound_udp").or_current()),
+                    );
+                }
+
                 tokio::spawn(
-                    serve::serve(inbound_listen, inbound, drain_rx.signaled())
+                    serve::serve(startup.hold(inbound_listen), inbound, drain_rx.signaled())
                         .instrument(info_span!("inbound").or_current()),
                 );
             })
@@ -306,10 +565,17 @@ impl Config {
         }
         registry.register("proxy_build_info", "Proxy build info", BUILD_INFO.metric());
         registry.register("rustls_info", "Proxy TLS info", tls_info::metric());
+        registry.register(
+            "proxy_task_panics",
+            "The number of panics in the proxy's tasks, by source file",
+            panics::metric(),
+        );
 
         let admin = {
             let identity = identity.receiver().server();
             let metrics = inbound_metrics.clone();
+            let scrape_metrics =
+                admin::ScrapeMetrics::register(registry.sub_registry_with_prefix("admin_scrape"));
             let report = inbound_metrics
                 .and_report(outbound_metrics)
                 .and_report(report)
@@ -323,6 +589,12 @@ impl Config {
                     identity,
                     report,
                     metrics,
+                    scrape_metrics,
+                    rollout_guards,
+                    breakers,
+                    backend_faults,
+                    discovery_retention,
+                    port_mappings,
                     log_level,
                     drain_rx,
                     shutdown_tx,
@@ -339,11 +611,36 @@ impl Config {
             trace_collector,
             outbound_addr,
             outbound_addr_additional,
+            outbound_explicit_addr,
+            outbound_socks5_addr,
+            outbound_listener_addrs,
+            outbound_udp_addr,
+            self_check,
             start_proxy,
+            startup,
             tap,
         })
     }
 
+    /// Waits for the policies of the given inbound ports to be discovered.
+    fn await_inbound_policies(
+        Local(ServerAddr(addr)): Local<ServerAddr>,
+        ports: HashSet<u16>,
+        policies: impl inbound::policy::GetPolicy,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let policies = ports
+            .into_iter()
+            .map(|port| {
+                let mut policy = policies.get_policy(OrigDstAddr(SocketAddr::new(addr.ip(), port)));
+                async move {
+                    policy.changed().await;
+                    debug!(port, "Discovered inbound policy");
+                }
+            })
+            .collect::<Vec<_>>();
+        future::join_all(policies).map(|_| ())
+    }
+
     /// Waits for the proxy's identity to be certified.
     ///
     /// If this does not complete in a timely fashion, warnings are logged every 15s
@@ -367,6 +664,10 @@ impl App {
         self.admin.listen_addr
     }
 
+    pub fn admin_scrape_addr(&self) -> Option<Local<ServerAddr>> {
+        self.admin.scrape_addr
+    }
+
     pub fn inbound_addr(&self) -> Local<ServerAddr> {
         self.inbound_addr
     }
@@ -379,6 +680,24 @@ impl App {
         self.outbound_addr_additional
     }
 
+    pub fn outbound_explicit_addr(&self) -> Option<Local<ServerAddr>> {
+        self.outbound_explicit_addr
+    }
+
+    pub fn outbound_socks5_addr(&self) -> Option<Local<ServerAddr>> {
+        self.outbound_socks5_addr
+    }
+
+    /// Returns the addresses of the additional outbound listeners, in the
+    /// order in which they are configured.
+    pub fn outbound_listener_addrs(&self) -> &[Local<ServerAddr>] {
+        &self.outbound_listener_addrs
+    }
+
+    pub fn outbound_udp_addr(&self) -> Option<Local<ServerAddr>> {
+        self.outbound_udp_addr
+    }
+
     pub fn tap_addr(&self) -> Option<Local<ServerAddr>> {
         match self.tap {
             tap::Tap::Disabled { .. } => None,
@@ -406,12 +725,36 @@ impl App {
     }
 
     pub fn spawn(self) -> drain::Signal {
+        // Each direction's listeners are checked independently so that, e.g.,
+        // an outbound failure does not stop the proxy from receiving inbound
+        // traffic.
+        let self_check = self.self_check.clone().map(|config| {
+            let Local(ServerAddr(inbound)) = self.inbound_addr;
+            let outbound = [self.outbound_addr]
+                .into_iter()
+                .chain(self.outbound_addr_additional)
+                .map(|Local(ServerAddr(addr))| addr)
+                .collect::<Vec<_>>();
+            let checks = &self.admin.checks;
+            let listeners = vec![
+                (
+                    checks.register(admin::Direction::Inbound, "listener"),
+                    vec![inbound],
+                ),
+                (
+                    checks.register(admin::Direction::Outbound, "listener"),
+                    outbound,
+                ),
+            ];
+            (config, listeners)
+        });
         let App {
             admin,
             drain,
             identity,
             trace_collector: collector,
             start_proxy,
+            startup,
             tap,
             ..
         } = self;
@@ -451,16 +794,30 @@ impl App {
                                 .instrument(info_span!("identity").or_current()),
                         );
 
-                        let latch = admin.latch;
                         tokio::spawn(
                             ready
-                                .map(move |()| {
-                                    latch.release();
-                                    info!(id = %local_id, "Certified identity");
-                                })
+                                .map(move |()| info!(id = %local_id, "Certified identity"))
                                 .instrument(info_span!("identity").or_current()),
                         );
 
+                        // The process is ready once application connections
+                        // are no longer held.
+                        let latch = admin.latch;
+                        let ready = startup.ready();
+                        tokio::spawn(async move {
+                            ready.await;
+                            latch.release();
+
+                            // Once the proxy is ready, check that its
+                            // listeners continue to serve connections.
+                            if let Some((config, listeners)) = self_check {
+                                config
+                                    .run(listeners)
+                                    .instrument(info_span!("self_check").or_current())
+                                    .await;
+                            }
+                        });
+
                         if let tap::Tap::Enabled {
                             registry, serve, ..
                         } = tap
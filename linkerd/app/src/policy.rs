@@ -16,6 +16,13 @@ pub struct Config {
     pub workload: String,
     pub limits: ReceiveLimits,
     pub export_hostname_labels: bool,
+    pub export_method_labels: bool,
+
+    /// Configures discovered outbound routes by name.
+    pub outbound_routes: linkerd_app_outbound::policy::RouteOverrides,
+
+    /// Configures discovered outbound parents by name.
+    pub outbound_parents: linkerd_app_outbound::policy::ParentOverrides,
 }
 
 /// Handles to policy service clients.
@@ -0,0 +1,358 @@
+//! Checks that the proxy's data-path listeners are serving connections.
+//!
+//! The proxy's readiness only reflects its startup state, so a listener whose
+//! accept loop is wedged would otherwise go unnoticed. When enabled, a
+//! background task periodically connects to each data-path listener over the
+//! loopback interface and writes a [`MARKER`]. The listeners' accept stacks
+//! recognize the marker before any discovery or protocol detection and reply
+//! without forwarding the connection, so these checks are not reflected in the
+//! proxy's traffic metrics.
+//!
+//! If a listener fails several consecutive checks, its direction's readiness
+//! check is marked as unhealthy, which the admin server's readiness endpoints
+//! report until a check succeeds.
+
+use futures::{
+    future::{self, Either},
+    TryFutureExt,
+};
+use linkerd_app_admin as admin;
+use linkerd_app_core::{
+    io::{self, AsyncReadExt, AsyncWriteExt},
+    svc::{self, Param, ServiceExt},
+    transport::{ClientAddr, Local, OrigDstAddr, Remote, ServerAddr},
+    Error, Result,
+};
+use std::{
+    future::Future,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::time;
+use tracing::{debug, info, warn};
+
+/// Written by the self-check client to identify its connections.
+pub const MARKER: &[u8] = b"l5d-self-check\r\n";
+
+/// Written by the proxy in response to a self-check.
+const RESPONSE: &[u8] = b"ok\r\n";
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// How often each listener is checked.
+    pub interval: time::Duration,
+
+    /// How long a single check may take before it fails.
+    pub timeout: time::Duration,
+
+    /// The number of consecutive failed checks after which a listener is
+    /// considered unhealthy.
+    pub failure_threshold: u32,
+}
+
+/// Answers self-check connections before they reach a listener's stack.
+///
+/// The inner stack is only built for connections that are not self-checks.
+#[derive(Clone, Debug)]
+pub struct NewAcceptSelfCheck<N> {
+    timeout: Option<time::Duration>,
+    inner: N,
+}
+
+#[derive(Clone, Debug)]
+pub struct AcceptSelfCheck<T, N> {
+    /// Set when the connection may be a self-check, i.e. it was made from the
+    /// loopback interface directly to the listener.
+    timeout: Option<time::Duration>,
+    target: T,
+    inner: N,
+}
+
+// === impl Config ===
+
+impl Config {
+    /// Checks each group of listeners until the process ends. Each group's
+    /// `health` is unhealthy while any of its listeners is failing.
+    pub(crate) async fn run(self, listeners: Vec<(admin::Health, Vec<SocketAddr>)>) {
+        let mut listeners = listeners
+            .into_iter()
+            .map(|(health, addrs)| {
+                let addrs = addrs
+                    .into_iter()
+                    .map(|addr| (loopback(addr), 0u32))
+                    .collect::<Vec<_>>();
+                (health, addrs)
+            })
+            .collect::<Vec<_>>();
+        debug!(?listeners, interval = ?self.interval, "Checking data-path listeners");
+
+        let mut interval = time::interval(self.interval);
+        interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            for (health, addrs) in listeners.iter_mut() {
+                self.check_all(addrs).await;
+                let healthy = addrs.iter().all(|(_, f)| *f < self.failure_threshold);
+                health.set_healthy(healthy);
+            }
+        }
+    }
+
+    /// Checks each listener once, updating its count of consecutive failures.
+    async fn check_all(&self, addrs: &mut [(SocketAddr, u32)]) {
+        for (addr, failures) in addrs.iter_mut() {
+            match time::timeout(self.timeout, check(*addr)).await {
+                Ok(Ok(())) => {
+                    if *failures >= self.failure_threshold {
+                        info!(%addr, "Listener recovered");
+                    }
+                    *failures = 0;
+                }
+                Ok(Err(error)) => {
+                    *failures += 1;
+                    debug!(%addr, %error, failures = *failures, "Self-check failed");
+                }
+                Err(_) => {
+                    *failures += 1;
+                    debug!(%addr, failures = *failures, "Self-check timed out");
+                }
+            }
+            if *failures == self.failure_threshold {
+                warn!(%addr, failures = *failures, "Listener is not serving connections");
+            }
+        }
+    }
+}
+
+/// Connects to a listener and completes a self-check exchange.
+async fn check(addr: SocketAddr) -> io::Result<()> {
+    let mut io = tokio::net::TcpStream::connect(addr).await?;
+    io.write_all(MARKER).await?;
+    let mut rsp = [0u8; RESPONSE.len()];
+    io.read_exact(&mut rsp).await?;
+    if rsp != RESPONSE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected self-check response",
+        ));
+    }
+    Ok(())
+}
+
+/// Listeners bound to an unspecified address are checked over loopback.
+fn loopback(addr: SocketAddr) -> SocketAddr {
+    match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => (Ipv4Addr::LOCALHOST, addr.port()).into(),
+        IpAddr::V6(ip) if ip.is_unspecified() => (Ipv6Addr::LOCALHOST, addr.port()).into(),
+        _ => addr,
+    }
+}
+
+// === impl NewAcceptSelfCheck ===
+
+impl<N> NewAcceptSelfCheck<N> {
+    /// Returns a layer that answers self-checks if `config` is set, or passes
+    /// all connections through otherwise.
+    pub fn layer(config: Option<&Config>) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        let timeout = config.map(|c| c.timeout);
+        svc::layer::mk(move |inner| Self { timeout, inner })
+    }
+}
+
+impl<T, N> svc::NewService<T> for NewAcceptSelfCheck<N>
+where
+    T: Param<Remote<ClientAddr>> + Param<Local<ServerAddr>> + Param<OrigDstAddr>,
+    N: Clone,
+{
+    type Service = AcceptSelfCheck<T, N>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let Remote(ClientAddr(client)) = target.param();
+        let Local(ServerAddr(server)) = target.param();
+        let OrigDstAddr(orig_dst) = target.param();
+        // Self-checks connect to the listener directly, so connections that
+        // were redirected to the proxy are never inspected.
+        let candidate = client.ip().is_loopback() && orig_dst == server;
+        AcceptSelfCheck {
+            timeout: self.timeout.filter(|_| candidate),
+            target,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// === impl AcceptSelfCheck ===
+
+impl<T, I, N, S> svc::Service<I> for AcceptSelfCheck<T, N>
+where
+    T: Clone + Send + 'static,
+    I: io::AsyncRead + io::AsyncWrite + io::Peek + Send + Sync + Unpin + 'static,
+    N: svc::NewService<T, Service = S> + Clone + Send + 'static,
+    S: svc::Service<I, Response = ()> + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send + 'static,
+{
+    type Response = ();
+    type Error = Error;
+    type Future = Either<
+        future::ErrInto<svc::Oneshot<S, I>, Error>,
+        Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut io: I) -> Self::Future {
+        let Some(timeout) = self.timeout else {
+            let svc = self.inner.new_service(self.target.clone());
+            return Either::Left(svc.oneshot(io).err_into());
+        };
+
+        let target = self.target.clone();
+        let inner = self.inner.clone();
+        Either::Right(Box::pin(async move {
+            // Peek rather than read so that other connections are forwarded
+            // unmodified. The marker is written in a single write, so it is
+            // expected to be received in its entirety.
+            let mut buf = [0u8; MARKER.len()];
+            let is_check = match time::timeout(timeout, io.peek(&mut buf)).await {
+                Ok(Ok(sz)) => buf[..sz] == *MARKER,
+                Ok(Err(_)) | Err(_) => false,
+            };
+            if !is_check {
+                let svc = inner.new_service(target);
+                return svc.oneshot(io).await.map_err(Into::into);
+            }
+
+            debug!("Answering self-check");
+            io.read_exact(&mut buf).await?;
+            io.write_all(RESPONSE).await?;
+            io.shutdown().await?;
+            Ok(())
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+    use svc::{Layer, NewService};
+    use tokio::net::{TcpListener, TcpStream};
+
+    #[derive(Clone, Debug)]
+    struct Target {
+        client: SocketAddr,
+        server: SocketAddr,
+        orig_dst: SocketAddr,
+    }
+
+    impl Param<Remote<ClientAddr>> for Target {
+        fn param(&self) -> Remote<ClientAddr> {
+            Remote(ClientAddr(self.client))
+        }
+    }
+
+    impl Param<Local<ServerAddr>> for Target {
+        fn param(&self) -> Local<ServerAddr> {
+            Local(ServerAddr(self.server))
+        }
+    }
+
+    impl Param<OrigDstAddr> for Target {
+        fn param(&self) -> OrigDstAddr {
+            OrigDstAddr(self.orig_dst)
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            interval: time::Duration::from_millis(10),
+            timeout: time::Duration::from_secs(1),
+            failure_threshold: 2,
+        }
+    }
+
+    /// Serves connections on a loopback listener, recording whether any
+    /// connection was forwarded to the inner stack. If `redirected` is true,
+    /// connections are treated as though they were redirected to the
+    /// listener.
+    async fn serve(redirected: bool) -> (SocketAddr, Arc<AtomicBool>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = listener.local_addr().unwrap();
+        let forwarded = Arc::new(AtomicBool::new(false));
+        let new_accept = NewAcceptSelfCheck::layer(Some(&config())).layer({
+            let forwarded = forwarded.clone();
+            move |_: Target| {
+                let forwarded = forwarded.clone();
+                svc::mk(move |_: TcpStream| {
+                    forwarded.store(true, Ordering::Release);
+                    future::ok::<(), Error>(())
+                })
+            }
+        });
+        tokio::spawn(async move {
+            loop {
+                let (io, client) = listener.accept().await.unwrap();
+                let orig_dst = if redirected {
+                    "10.0.0.1:8080".parse().unwrap()
+                } else {
+                    server
+                };
+                let svc = new_accept.new_service(Target {
+                    client,
+                    server,
+                    orig_dst,
+                });
+                tokio::spawn(svc.oneshot(io));
+            }
+        });
+        (server, forwarded)
+    }
+
+    #[tokio::test]
+    async fn answers_self_checks() {
+        let (addr, forwarded) = serve(false).await;
+        check(addr).await.expect("self-check must succeed");
+        assert!(!forwarded.load(Ordering::Acquire));
+
+        let mut io = TcpStream::connect(addr).await.unwrap();
+        io.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let _ = io.read(&mut [0u8; 8]).await;
+        assert!(
+            forwarded.load(Ordering::Acquire),
+            "other connections must be forwarded"
+        );
+    }
+
+    #[tokio::test]
+    async fn forwards_redirected_connections() {
+        let (addr, forwarded) = serve(true).await;
+        check(addr)
+            .await
+            .expect_err("redirected connections must not be checked");
+        assert!(forwarded.load(Ordering::Acquire));
+    }
+
+    #[tokio::test]
+    async fn marks_unhealthy_after_failures() {
+        let (addr, _) = serve(true).await;
+        let health = admin::Health::default();
+        tokio::spawn(config().run(vec![(health.clone(), vec![addr])]));
+
+        time::timeout(time::Duration::from_secs(10), async {
+            while health.is_healthy() {
+                time::sleep(time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("data path must become unhealthy");
+    }
+}
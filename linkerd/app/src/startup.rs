@@ -0,0 +1,346 @@
+//! Holds application connections at startup until the proxy is ready to serve
+//! them.
+//!
+//! Applications that start before the proxy has obtained its identity (and,
+//! optionally, its inbound policies) would otherwise see their connections
+//! fail. Instead, each listener accepts connections into a bounded queue and
+//! releases them once the proxy is ready. If the proxy does not become ready
+//! before a deadline, held connections are either released or rejected.
+
+use futures::{prelude::*, stream};
+use linkerd_app_core::Result;
+use std::{collections::VecDeque, pin::Pin};
+use tokio::{
+    sync::watch,
+    time::{self, Duration},
+};
+use tracing::{debug, info, warn};
+
+/// Configures how connections are handled before the proxy is ready.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Whether the proxy waits for the inbound policies of its configured
+    /// ports to be discovered, in addition to its identity.
+    pub await_policy: bool,
+
+    /// The maximum time for which connections are held. When unset,
+    /// connections are held until the proxy is ready.
+    pub timeout: Option<Duration>,
+
+    /// Determines how connections are handled once the timeout elapses.
+    pub on_timeout: OnTimeout,
+
+    /// The number of connections held by each listener. Once this limit is
+    /// reached, further connections wait in the listener's accept backlog.
+    pub capacity: usize,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OnTimeout {
+    /// Serve connections before the proxy is ready.
+    #[default]
+    Release,
+
+    /// Close connections until the proxy is ready.
+    Reject,
+}
+
+/// Observes the state of the startup gate.
+#[derive(Clone, Debug)]
+pub(crate) struct Gate {
+    rx: watch::Receiver<State>,
+    capacity: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    /// The proxy is not yet ready and connections are held.
+    Pending,
+
+    /// The proxy is not yet ready, but the timeout elapsed and connections are
+    /// served.
+    Released,
+
+    /// The proxy is not yet ready and the timeout elapsed, so connections are
+    /// closed.
+    Rejecting,
+
+    /// The proxy is ready.
+    Ready,
+}
+
+/// A listener that holds connections while the gate is pending.
+struct Held<S, T> {
+    listen: Pin<Box<S>>,
+    rx: watch::Receiver<State>,
+    queue: VecDeque<T>,
+    capacity: usize,
+}
+
+// === impl Config ===
+
+impl Config {
+    /// Returns a gate that opens when `ready` completes, or when the timeout
+    /// elapses, and a future that drives it.
+    pub(crate) fn build(
+        self,
+        ready: impl Future<Output = ()> + Send + 'static,
+    ) -> (Gate, impl Future<Output = ()> + Send + 'static) {
+        let (tx, rx) = watch::channel(State::Pending);
+        let Self {
+            timeout,
+            on_timeout,
+            capacity,
+            ..
+        } = self;
+
+        let task = async move {
+            tokio::pin!(ready);
+            if let Some(timeout) = timeout {
+                tokio::select! {
+                    () = &mut ready => {
+                        tx.send_replace(State::Ready);
+                        return;
+                    }
+                    () = time::sleep(timeout) => {}
+                }
+                let state = match on_timeout {
+                    OnTimeout::Release => {
+                        warn!(?timeout, "Proxy is not ready; serving held connections");
+                        State::Released
+                    }
+                    OnTimeout::Reject => {
+                        warn!(?timeout, "Proxy is not ready; rejecting connections");
+                        State::Rejecting
+                    }
+                };
+                tx.send_replace(state);
+            }
+            ready.await;
+            info!("Proxy is ready");
+            tx.send_replace(State::Ready);
+        };
+
+        (Gate { rx, capacity }, task)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            await_policy: false,
+            timeout: None,
+            on_timeout: OnTimeout::default(),
+            capacity: 100,
+        }
+    }
+}
+
+// === impl Gate ===
+
+impl Gate {
+    /// Holds connections accepted by `listen` until the gate opens.
+    pub(crate) fn hold<T>(
+        &self,
+        listen: impl Stream<Item = Result<T>> + Send + 'static,
+    ) -> impl Stream<Item = Result<T>> + Send + 'static
+    where
+        T: Send + 'static,
+    {
+        let held = Held {
+            listen: Box::pin(listen),
+            rx: self.rx.clone(),
+            queue: VecDeque::new(),
+            capacity: self.capacity,
+        };
+        stream::unfold(held, |mut held| async move {
+            let next = held.next().await?;
+            Some((next, held))
+        })
+    }
+
+    /// Completes when connections may be served, whether or not the proxy is
+    /// ready.
+    pub(crate) async fn released(mut self) {
+        let _ = self
+            .rx
+            .wait_for(|s| matches!(s, State::Released | State::Ready))
+            .await;
+    }
+
+    /// Completes when the proxy is ready.
+    pub(crate) async fn ready(mut self) {
+        let _ = self.rx.wait_for(|s| *s == State::Ready).await;
+    }
+}
+
+// === impl Held ===
+
+impl<S, T> Held<S, T>
+where
+    S: Stream<Item = Result<T>>,
+{
+    async fn next(&mut self) -> Option<Result<T>> {
+        loop {
+            let state = *self.rx.borrow_and_update();
+            match state {
+                State::Released | State::Ready => {
+                    if let Some(conn) = self.queue.pop_front() {
+                        return Some(Ok(conn));
+                    }
+                    return self.listen.next().await;
+                }
+
+                State::Rejecting => {
+                    if !self.queue.is_empty() {
+                        debug!(connections = self.queue.len(), "Rejecting held connections");
+                        self.queue.clear();
+                    }
+                    tokio::select! {
+                        conn = self.listen.next() => match conn? {
+                            Ok(_) => debug!("Rejecting connection; the proxy is not ready"),
+                            Err(error) => return Some(Err(error)),
+                        },
+                        res = self.rx.changed() => res.ok()?,
+                    }
+                }
+
+                State::Pending => {
+                    tokio::select! {
+                        conn = self.listen.next(), if self.queue.len() < self.capacity => {
+                            match conn? {
+                                Ok(conn) => {
+                                    self.queue.push_back(conn);
+                                    debug!(connections = self.queue.len(), "Holding connection until the proxy is ready");
+                                }
+                                Err(error) => return Some(Err(error)),
+                            }
+                        }
+                        res = self.rx.changed() => res.ok()?,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::{mpsc, oneshot};
+    use tokio_stream::wrappers::UnboundedReceiverStream;
+
+    /// Returns a listener and a function that connects to it. Each connection
+    /// reports whether it was closed without being served.
+    fn listener() -> (
+        impl Stream<Item = Result<oneshot::Sender<()>>> + Send + 'static,
+        impl Fn() -> oneshot::Receiver<()>,
+    ) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let connect = move || {
+            let (conn_tx, conn_rx) = oneshot::channel();
+            tx.send(Ok(conn_tx)).expect("listener must be held");
+            conn_rx
+        };
+        (UnboundedReceiverStream::new(rx), connect)
+    }
+
+    fn config(on_timeout: OnTimeout) -> Config {
+        Config {
+            timeout: Some(Duration::from_secs(10)),
+            on_timeout,
+            capacity: 2,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn holds_until_ready() {
+        let (ready_tx, ready_rx) = oneshot::channel::<()>();
+        let (gate, task) = Config::default().build(ready_rx.map(|_| ()));
+        tokio::spawn(task);
+
+        let (listen, connect) = listener();
+        let mut conns = Box::pin(gate.hold(listen));
+        let _c0 = connect();
+        let _c1 = connect();
+        tokio::select! {
+            _ = conns.next() => panic!("connections must be held"),
+            _ = time::sleep(Duration::from_secs(60 * 60)) => {}
+        }
+
+        let _ = ready_tx.send(());
+        for _ in 0..2 {
+            conns.next().await.expect("connection").expect("ok");
+        }
+        gate.ready().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn releases_after_timeout() {
+        let (ready_tx, ready_rx) = oneshot::channel::<()>();
+        let (gate, task) = config(OnTimeout::Release).build(ready_rx.map(|_| ()));
+        tokio::spawn(task);
+
+        let (listen, connect) = listener();
+        let mut conns = Box::pin(gate.hold(listen));
+        let _c0 = connect();
+        let _c1 = connect();
+        let _c2 = connect();
+
+        // Only `capacity` connections are accepted while the gate is pending.
+        let t0 = time::Instant::now();
+        conns.next().await.expect("connection").expect("ok");
+        assert_eq!(
+            time::Instant::now().saturating_duration_since(t0),
+            Duration::from_secs(10),
+            "connections must be held until the timeout"
+        );
+        conns.next().await.expect("connection").expect("ok");
+        conns.next().await.expect("connection").expect("ok");
+
+        // New connections are served immediately.
+        let _c3 = connect();
+        conns.next().await.expect("connection").expect("ok");
+
+        // Released connections don't make the proxy ready.
+        tokio::select! {
+            _ = gate.clone().ready() => panic!("proxy must not be ready"),
+            _ = gate.clone().released() => {}
+        }
+        let _ = ready_tx.send(());
+        gate.ready().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rejects_after_timeout() {
+        let (ready_tx, ready_rx) = oneshot::channel::<()>();
+        let (gate, task) = config(OnTimeout::Reject).build(ready_rx.map(|_| ()));
+        tokio::spawn(task);
+
+        let (listen, connect) = listener();
+        let mut conns = Box::pin(gate.hold(listen));
+        let c0 = connect();
+        let c1 = connect();
+        let serve = tokio::spawn(async move {
+            let conn = conns.next().await.expect("connection").expect("ok");
+            (conn, conns)
+        });
+
+        // Held connections are closed once the timeout elapses, as are new
+        // connections.
+        c0.await.expect_err("held connection must be closed");
+        c1.await.expect_err("held connection must be closed");
+        connect()
+            .await
+            .expect_err("connection must be rejected until the proxy is ready");
+        assert!(!serve.is_finished(), "no connection may be served");
+
+        let _ = ready_tx.send(());
+        gate.clone().ready().await;
+        let c3 = connect();
+        let (conn, _conns) = serve.await.unwrap();
+        let _ = conn.send(());
+        c3.await.expect("connection must be served once ready");
+    }
+}
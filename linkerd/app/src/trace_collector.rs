@@ -1,6 +1,6 @@
 use linkerd_app_core::{
     control, dns,
-    http_tracing::{CollectorProtocol, SpanSink},
+    http_tracing::{self, CollectorProtocol, SpanQueueMetrics, SpanSink},
     identity,
     metrics::ControlHttp as HttpMetrics,
     opencensus, opentelemetry,
@@ -8,7 +8,7 @@ use linkerd_app_core::{
 };
 use linkerd_error::Error;
 use otel_collector::OtelCollectorAttributes;
-use std::{collections::HashMap, future::Future, pin::Pin};
+use std::{collections::HashMap, future::Future, num::NonZeroU32, pin::Pin};
 
 pub mod oc_collector;
 pub mod otel_collector;
@@ -29,6 +29,9 @@ pub struct EnabledConfig {
     pub hostname: Option<String>,
     pub service_name: Option<String>,
     pub kind: CollectorProtocol,
+    /// The maximum number of spans exported each second. Spans recorded beyond
+    /// this rate are counted but not exported.
+    pub max_spans_per_second: Option<NonZeroU32>,
 }
 
 pub type Task = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
@@ -73,6 +76,7 @@ impl Config {
         legacy_otel_metrics: opentelemetry::metrics::Registry,
         control_metrics: control::Metrics,
         client_metrics: HttpMetrics,
+        span_metrics: SpanQueueMetrics,
     ) -> Result<TraceCollector, Error> {
         match self {
             Config::Disabled => Ok(TraceCollector::Disabled),
@@ -85,6 +89,11 @@ impl Config {
                 let svc_name = inner
                     .service_name
                     .unwrap_or_else(|| SERVICE_NAME.to_string());
+                let spans = http_tracing::span_queue(
+                    SPAN_BUFFER_CAPACITY,
+                    inner.max_spans_per_second,
+                    span_metrics,
+                );
 
                 let collector = match inner.kind {
                     CollectorProtocol::OpenCensus => oc_collector::create_collector(
@@ -93,6 +102,7 @@ impl Config {
                         svc_name,
                         inner.attributes,
                         svc,
+                        spans,
                         legacy_oc_metrics,
                     ),
                     CollectorProtocol::OpenTelemetry => {
@@ -105,6 +115,7 @@ impl Config {
                             addr.clone(),
                             attributes,
                             svc,
+                            spans,
                             legacy_otel_metrics,
                         )
                     }
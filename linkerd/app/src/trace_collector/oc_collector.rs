@@ -1,11 +1,12 @@
 use crate::trace_collector::EnabledCollector;
 use linkerd_app_core::{
-    control::ControlAddr, http_tracing::CollectorProtocol, proxy::http::Body, Error,
+    control::ControlAddr,
+    http_tracing::{CollectorProtocol, SpanQueue, SpanSink},
+    proxy::http::Body,
+    Error,
 };
 use linkerd_opencensus::{self as opencensus, metrics, proto};
 use std::{collections::HashMap, time::SystemTime};
-use tokio::sync::mpsc;
-use tokio_stream::wrappers::ReceiverStream;
 use tonic::{body::BoxBody, client::GrpcService};
 use tracing::Instrument;
 
@@ -15,6 +16,7 @@ pub(super) fn create_collector<S>(
     service_name: String,
     attributes: HashMap<String, String>,
     svc: S,
+    (span_sink, spans): (SpanSink, SpanQueue),
     legacy_metrics: metrics::Registry,
 ) -> EnabledCollector
 where
@@ -24,9 +26,6 @@ where
     S::ResponseBody: Body<Data = tonic::codegen::Bytes> + Send + 'static,
     <S::ResponseBody as Body>::Error: Into<Error> + Send,
 {
-    let (span_sink, spans_rx) = mpsc::channel(crate::trace_collector::SPAN_BUFFER_CAPACITY);
-    let spans_rx = ReceiverStream::new(spans_rx);
-
     let task = {
         use self::proto::agent::common::v1 as oc;
 
@@ -43,7 +42,7 @@ where
 
         let addr = addr.clone();
         Box::pin(
-            opencensus::export_spans(svc, node, spans_rx, legacy_metrics)
+            opencensus::export_spans(svc, node, spans, legacy_metrics)
                 .instrument(tracing::debug_span!("opencensus", peer.addr = %addr).or_current()),
         )
     };
@@ -1,6 +1,9 @@
 use super::EnabledCollector;
 use linkerd_app_core::{
-    control::ControlAddr, http_tracing::CollectorProtocol, proxy::http::Body, Error,
+    control::ControlAddr,
+    http_tracing::{CollectorProtocol, SpanQueue, SpanSink},
+    proxy::http::Body,
+    Error,
 };
 use linkerd_opentelemetry::{
     self as opentelemetry, metrics,
@@ -13,8 +16,6 @@ use std::{
     collections::HashMap,
     time::{SystemTime, UNIX_EPOCH},
 };
-use tokio::sync::mpsc;
-use tokio_stream::wrappers::ReceiverStream;
 use tonic::{body::BoxBody, client::GrpcService};
 use tracing::Instrument;
 
@@ -28,6 +29,7 @@ pub(super) fn create_collector<S>(
     addr: ControlAddr,
     attributes: OtelCollectorAttributes,
     svc: S,
+    (span_sink, spans): (SpanSink, SpanQueue),
     legacy_metrics: metrics::Registry,
 ) -> EnabledCollector
 where
@@ -37,9 +39,6 @@ where
     S::ResponseBody: Body<Data = tonic::codegen::Bytes> + Send + 'static,
     <S::ResponseBody as Body>::Error: Into<Error> + Send,
 {
-    let (span_sink, spans_rx) = mpsc::channel(crate::trace_collector::SPAN_BUFFER_CAPACITY);
-    let spans_rx = ReceiverStream::new(spans_rx);
-
     let mut resources = ResourceAttributesWithSchema::default();
 
     resources
@@ -74,7 +73,7 @@ where
 
     let addr = addr.clone();
     let task = Box::pin(
-        opentelemetry::export_spans(svc, spans_rx, resources, legacy_metrics)
+        opentelemetry::export_spans(svc, spans, resources, legacy_metrics)
             .instrument(tracing::debug_span!("opentelemetry", peer.addr = %addr).or_current()),
     );
 
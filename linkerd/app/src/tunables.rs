@@ -0,0 +1,189 @@
+//! Applies settings that may be changed while the proxy is running.
+//!
+//! When configured, the proxy reads a file of [`Tunables`] at startup and
+//! polls it for changes. Each version of the file is parsed in its entirety: a
+//! valid file replaces all of the current settings at once, while an invalid
+//! file is rejected and the current settings remain in place.
+//!
+//! Settings that may not be changed at runtime are configured via the
+//! environment; see [`env::TUNABLE_VARS`] for the settings that may be set in
+//! the file.
+
+use crate::env;
+use linkerd_app_core::{config::Tunables, metrics::prom};
+use std::{io, path::PathBuf};
+use tokio::{sync::watch, time};
+use tracing::{debug, info, info_span, warn, Instrument};
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The path of the tunables file.
+    pub path: PathBuf,
+
+    /// How often the file is checked for changes.
+    pub poll_interval: time::Duration,
+}
+
+#[derive(Clone, Debug)]
+struct Metrics {
+    applied: prom::Counter,
+    rejected: prom::Counter,
+}
+
+// === impl Config ===
+
+impl Config {
+    /// Loads the file's current settings and spawns a task that applies
+    /// subsequent changes to the file.
+    pub(crate) fn build(self, registry: &mut prom::Registry) -> watch::Receiver<Tunables> {
+        let metrics = Metrics::register(registry);
+        let (tx, rx) = watch::channel(Tunables::default());
+        let mut contents = None;
+        self.poll(&mut contents, &tx, &metrics);
+        tokio::spawn(
+            self.run(contents, tx, metrics)
+                .instrument(info_span!("tunables").or_current()),
+        );
+        rx
+    }
+
+    async fn run(
+        self,
+        mut contents: Option<String>,
+        tx: watch::Sender<Tunables>,
+        metrics: Metrics,
+    ) {
+        let mut interval = time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        // The first tick completes immediately, and the file was just loaded.
+        interval.tick().await;
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = tx.closed() => {
+                    debug!("Tunables are no longer observed");
+                    return;
+                }
+            }
+            self.poll(&mut contents, &tx, &metrics);
+        }
+    }
+
+    /// Reads the file and, if its contents have changed since they were last
+    /// read, applies its settings.
+    ///
+    /// A missing file is treated as an empty file, so that removing the file
+    /// restores the static configuration.
+    fn poll(&self, contents: &mut Option<String>, tx: &watch::Sender<Tunables>, metrics: &Metrics) {
+        // The file is expected to be small, so it is read synchronously.
+        let update = match std::fs::read_to_string(&self.path) {
+            Ok(update) => update,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => {
+                warn!(path = %self.path.display(), %error, "Failed to read tunables");
+                return;
+            }
+        };
+        if contents.as_ref() == Some(&update) {
+            return;
+        }
+
+        match env::parse_tunables(&update) {
+            Ok(tunables) => {
+                info!(?tunables, "Applying tunables");
+                tx.send_replace(tunables);
+                metrics.applied.inc();
+            }
+            Err(error) => {
+                warn!(
+                    path = %self.path.display(),
+                    %error,
+                    "Rejected invalid tunables; the current settings remain in place",
+                );
+                metrics.rejected.inc();
+            }
+        }
+        // Rejected contents are retained so that they are only reported once.
+        *contents = Some(update);
+    }
+}
+
+// === impl Metrics ===
+
+impl Metrics {
+    fn register(registry: &mut prom::Registry) -> Self {
+        let applied = prom::Counter::default();
+        registry.register(
+            "updates_applied",
+            "The number of updates to the tunables file that were applied",
+            applied.clone(),
+        );
+        let rejected = prom::Counter::default();
+        registry.register(
+            "updates_rejected",
+            "The number of updates to the tunables file that were rejected as invalid",
+            rejected.clone(),
+        );
+        Self { applied, rejected }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_valid_updates() {
+        let path = std::env::temp_dir().join(format!("tunables-{}", std::process::id()));
+        let config = Config {
+            path: path.clone(),
+            poll_interval: time::Duration::from_secs(1),
+        };
+        let metrics = Metrics::register(&mut prom::Registry::default());
+        let (tx, rx) = watch::channel(Tunables::default());
+        let mut contents = None;
+
+        std::fs::write(
+            &path,
+            "# Comments are ignored.\n\
+             LINKERD2_PROXY_INBOUND_DETECT_TIMEOUT=5s\n\
+             LINKERD2_PROXY_ACCESS_LOG_SAMPLE_RATIO=0.1\n",
+        )
+        .unwrap();
+        config.poll(&mut contents, &tx, &metrics);
+        assert_eq!(
+            *rx.borrow(),
+            Tunables {
+                inbound_detect_timeout: Some(time::Duration::from_secs(5)),
+                outbound_detect_timeout: None,
+                access_log_sample_ratio: Some(0.1),
+            }
+        );
+        assert_eq!(metrics.applied.get(), 1);
+
+        // Invalid files are rejected in their entirety.
+        std::fs::write(
+            &path,
+            "LINKERD2_PROXY_INBOUND_DETECT_TIMEOUT=1s\n\
+             LINKERD2_PROXY_ACCESS_LOG_SAMPLE_RATIO=2\n",
+        )
+        .unwrap();
+        config.poll(&mut contents, &tx, &metrics);
+        assert_eq!(
+            rx.borrow().inbound_detect_timeout,
+            Some(time::Duration::from_secs(5))
+        );
+        assert_eq!(metrics.rejected.get(), 1);
+
+        // Only settings that may be changed at runtime may be set.
+        std::fs::write(&path, "LINKERD2_PROXY_INBOUND_LISTEN_ADDR=0.0.0.0:4143\n").unwrap();
+        config.poll(&mut contents, &tx, &metrics);
+        assert_eq!(metrics.rejected.get(), 2);
+
+        // Removing the file restores the static configuration.
+        std::fs::remove_file(&path).unwrap();
+        config.poll(&mut contents, &tx, &metrics);
+        assert_eq!(*rx.borrow(), Tunables::default());
+        assert_eq!(metrics.applied.get(), 2);
+    }
+}
@@ -246,6 +246,10 @@ impl<E> DstSender<E> {
         self.update(Update::DoesNotExist)
     }
 
+    pub fn unavailable(&mut self) -> Result<(), SendFailed> {
+        self.update(Update::Unavailable)
+    }
+
     pub fn err(&mut self, e: impl Into<Error>) -> Result<(), SendFailed> {
         self.0.send(Err(e.into())).map_err(|_| SendFailed(()))
     }
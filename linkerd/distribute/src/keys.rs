@@ -116,6 +116,11 @@ impl<K> ServiceKeys<K> {
     pub(crate) fn iter(&self) -> impl Iterator<Item = &KeyId> {
         self.ids.iter()
     }
+
+    /// Returns the keys in the order in which they were configured.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.ids.iter().map(|&id| self.get(id))
+    }
 }
 
 // === impl WeightedKeys ===
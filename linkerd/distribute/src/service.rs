@@ -26,7 +26,8 @@ enum Selection<K, S> {
 // === impl Distribute ===
 
 impl<K: Hash + Eq, S> Distribute<K, S> {
-    pub(crate) fn new<N>(dist: Distribution<K>, make_svc: N) -> Self
+    /// Builds a service for each of the distribution's backends.
+    pub fn new<N>(dist: Distribution<K>, make_svc: N) -> Self
     where
         N: for<'a> NewService<&'a K, Service = S>,
     {
@@ -0,0 +1,148 @@
+//! Measures the allocations and throughput of copying connections with
+//! [`Duplex`], compared to [`tokio::io::copy_bidirectional`], which allocates
+//! fresh buffers for each connection, and to [`linkerd_duplex::splice`], which
+//! moves bytes between sockets without copying them through userspace on
+//! Linux.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use linkerd_duplex::Duplex;
+use linkerd_io::{self as io, AsyncReadExt, AsyncWriteExt};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    future::Future,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Counts the bytes allocated by the benchmark.
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+const LEN: usize = 256 * 1024;
+const CONNECTIONS: usize = 100;
+
+fn copy(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("runtime");
+
+    // Report the bytes allocated per connection once buffers have been pooled.
+    for (name, bytes) in [
+        ("duplex", rt.block_on(allocated(duplex))),
+        ("splice", rt.block_on(allocated(splice))),
+        (
+            "copy_bidirectional",
+            rt.block_on(allocated(|mut i, mut o| async move {
+                tokio::io::copy_bidirectional(&mut i, &mut o).await?;
+                Ok(())
+            })),
+        ),
+    ] {
+        println!("{name}: {bytes} bytes allocated per connection");
+    }
+
+    let mut group = c.benchmark_group("copy");
+    group.throughput(Throughput::Bytes(2 * LEN as u64));
+    group.bench_function("duplex", |b| b.to_async(&rt).iter(|| proxy(duplex)));
+    group.bench_function("splice", |b| b.to_async(&rt).iter(|| proxy(splice)));
+    group.bench_function("copy_bidirectional", |b| {
+        b.to_async(&rt).iter(|| {
+            proxy(|mut i, mut o| async move {
+                tokio::io::copy_bidirectional(&mut i, &mut o).await?;
+                Ok(())
+            })
+        })
+    });
+    group.finish();
+}
+
+async fn duplex(i: TcpStream, o: TcpStream) -> io::Result<()> {
+    Duplex::new(i, o).await?;
+    Ok(())
+}
+
+async fn splice(i: TcpStream, o: TcpStream) -> io::Result<()> {
+    linkerd_duplex::splice(i, o, Default::default()).await?;
+    Ok(())
+}
+
+/// Returns the average number of bytes allocated to proxy a connection, after
+/// a warm-up connection.
+async fn allocated<F, P>(mk: P) -> usize
+where
+    P: Fn(TcpStream, TcpStream) -> F,
+    F: Future<Output = io::Result<()>> + Send + 'static,
+{
+    proxy(&mk).await;
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    for _ in 0..CONNECTIONS {
+        proxy(&mk).await;
+    }
+    (ALLOCATED.load(Ordering::Relaxed) - before) / CONNECTIONS
+}
+
+/// Sends `LEN` bytes in each direction through a proxied loopback connection.
+async fn proxy<F>(mk: impl FnOnce(TcpStream, TcpStream) -> F)
+where
+    F: Future<Output = io::Result<()>> + Send + 'static,
+{
+    let (mut client, in_io) = pair().await;
+    let (out_io, mut server) = pair().await;
+    let proxy = tokio::spawn(mk(in_io, out_io));
+
+    let client = async move {
+        let data = [0u8; 8 * 1024];
+        for _ in 0..LEN / data.len() {
+            client.write_all(&data).await.expect("write");
+        }
+        client.shutdown().await.expect("shutdown");
+        let mut buf = [0u8; 8 * 1024];
+        while client.read(&mut buf).await.expect("read") != 0 {}
+    };
+    let server = async move {
+        let mut buf = [0u8; 8 * 1024];
+        let mut read = 0;
+        loop {
+            let sz = server.read(&mut buf).await.expect("read");
+            if sz == 0 {
+                break;
+            }
+            read += sz;
+        }
+        assert_eq!(read, LEN);
+        let data = [0u8; 8 * 1024];
+        for _ in 0..LEN / data.len() {
+            server.write_all(&data).await.expect("write");
+        }
+        server.shutdown().await.expect("shutdown");
+    };
+    tokio::join!(client, server);
+    proxy.await.unwrap().expect("proxy");
+}
+
+/// Returns both ends of a loopback TCP connection.
+async fn pair() -> (TcpStream, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let (client, server) = tokio::join!(TcpStream::connect(addr), listener.accept());
+    (client.expect("connect"), server.expect("accept").0)
+}
+
+criterion_group!(benches, copy);
+criterion_main!(benches);
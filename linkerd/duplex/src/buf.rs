@@ -0,0 +1,311 @@
+//! Buffers used to copy bytes from one IO to another.
+
+use bytes::{Buf, BufMut};
+use std::{cell::RefCell, io::IoSlice};
+
+/// The smallest and initial size of a copy buffer.
+pub(crate) const MIN_SIZE: usize = 8 * 1024;
+
+/// The largest size to which a copy buffer may grow.
+pub(crate) const MAX_SIZE: usize = 64 * 1024;
+
+/// The number of consecutive reads that must fill (or fail to fill a quarter
+/// of) the buffer before its size is doubled (or halved).
+const ADAPT_AFTER: usize = 4;
+
+/// The number of buffers of each size retained by each thread's pool.
+const POOL_CAPACITY: usize = 32;
+
+/// The number of distinct buffer sizes, from `MIN_SIZE` to `MAX_SIZE`.
+const SIZES: usize = (MAX_SIZE / MIN_SIZE).trailing_zeros() as usize + 1;
+
+thread_local! {
+    /// Buffers that are not in use by any connection on this thread (i.e. on
+    /// this runtime worker), indexed by size.
+    static POOL: RefCell<[Vec<Box<[u8]>>; SIZES]> = RefCell::new(Default::default());
+}
+
+/// A ring buffer used to copy bytes from one IO to another.
+///
+/// Storage is taken from a per-thread pool when data is read and returned to it
+/// whenever the buffer is empty and its IO is idle, so idle connections don't
+/// hold buffers. The buffer's size adapts to the connection's throughput: it
+/// grows when reads repeatedly fill it and shrinks when they repeatedly don't.
+///
+/// When buffered data wraps around the end of the storage, it is exposed as two
+/// chunks so that it may be written with a single vectored write.
+pub(crate) struct CopyBuf {
+    storage: Option<Box<[u8]>>,
+    // The position of the first buffered byte in `storage`.
+    head: usize,
+    // The number of buffered bytes.
+    len: usize,
+    // The size of storage to use the next time storage is taken.
+    size: usize,
+    // Positive when consecutive reads filled the buffer; negative when
+    // consecutive reads filled less than a quarter of it.
+    trend: isize,
+}
+
+// === impl CopyBuf ===
+
+impl CopyBuf {
+    pub(crate) fn new() -> Self {
+        Self {
+            storage: None,
+            head: 0,
+            len: 0,
+            size: MIN_SIZE,
+            trend: 0,
+        }
+    }
+
+    /// Prepares to read into the buffer, taking storage from the pool if
+    /// necessary.
+    pub(crate) fn reserve(&mut self) {
+        if self.storage.as_ref().map(|s| s.len()) == Some(self.size) {
+            return;
+        }
+        // Storage is only resized when it holds no data.
+        if self.len == 0 {
+            if let Some(storage) = self.storage.take() {
+                release(storage);
+            }
+            self.head = 0;
+            self.storage = Some(take(self.size));
+        }
+    }
+
+    /// Returns the number of contiguous bytes that may be read into the
+    /// buffer.
+    pub(crate) fn contiguous_capacity(&self) -> usize {
+        match self.storage.as_ref() {
+            Some(storage) => self.free_region(storage.len()).len(),
+            None => 0,
+        }
+    }
+
+    /// Adapts the buffer's size after a read of `read` bytes into `offered`
+    /// bytes of contiguous capacity.
+    pub(crate) fn record_read(&mut self, read: usize, offered: usize) {
+        if read == offered && offered == self.capacity() {
+            self.trend = self.trend.max(0) + 1;
+            if self.trend >= ADAPT_AFTER as isize {
+                self.size = (self.size * 2).min(MAX_SIZE);
+                self.trend = 0;
+            }
+        } else if read < offered / 4 {
+            self.trend = self.trend.min(0) - 1;
+            if self.trend <= -(ADAPT_AFTER as isize) {
+                self.size = (self.size / 2).max(MIN_SIZE);
+                self.trend = 0;
+            }
+        } else {
+            self.trend = 0;
+        }
+    }
+
+    /// Returns the buffer's storage to the pool if it holds no data.
+    pub(crate) fn release_if_empty(&mut self) {
+        if self.len == 0 {
+            if let Some(storage) = self.storage.take() {
+                release(storage);
+            }
+            self.head = 0;
+        }
+    }
+
+    /// Returns the size of the buffer's current storage.
+    pub(crate) fn capacity(&self) -> usize {
+        self.storage.as_ref().map_or(0, |s| s.len())
+    }
+
+    fn free_region(&self, cap: usize) -> std::ops::Range<usize> {
+        if self.len == cap {
+            return 0..0;
+        }
+        let tail = (self.head + self.len) % cap;
+        if tail >= self.head {
+            tail..cap
+        } else {
+            tail..self.head
+        }
+    }
+}
+
+impl Drop for CopyBuf {
+    fn drop(&mut self) {
+        if let Some(storage) = self.storage.take() {
+            release(storage);
+        }
+    }
+}
+
+impl Buf for CopyBuf {
+    fn remaining(&self) -> usize {
+        self.len
+    }
+
+    fn chunk(&self) -> &[u8] {
+        match self.storage.as_ref() {
+            Some(storage) => {
+                let end = (self.head + self.len).min(storage.len());
+                &storage[self.head..end]
+            }
+            None => &[],
+        }
+    }
+
+    fn chunks_vectored<'a>(&'a self, dst: &mut [IoSlice<'a>]) -> usize {
+        let Some(storage) = self.storage.as_ref() else {
+            return 0;
+        };
+        if dst.is_empty() || self.len == 0 {
+            return 0;
+        }
+
+        let end = self.head + self.len;
+        if end <= storage.len() {
+            dst[0] = IoSlice::new(&storage[self.head..end]);
+            return 1;
+        }
+
+        dst[0] = IoSlice::new(&storage[self.head..]);
+        if dst.len() == 1 {
+            return 1;
+        }
+        dst[1] = IoSlice::new(&storage[..end - storage.len()]);
+        2
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        assert!(cnt <= self.len);
+        self.len -= cnt;
+        if self.len == 0 {
+            self.head = 0;
+        } else {
+            self.head = (self.head + cnt) % self.capacity();
+        }
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe impl BufMut for CopyBuf {
+    fn remaining_mut(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    fn chunk_mut(&mut self) -> &mut bytes::buf::UninitSlice {
+        let cap = self.capacity();
+        let region = self.free_region(cap);
+        let storage = self
+            .storage
+            .as_mut()
+            .expect("storage must be reserved before reading");
+        let slice = &mut storage[region];
+        // Safety: The memory is initialized. This is the only way to turn a
+        // `&[T]` into a `&[MaybeUninit<T>]` without ptr casting.
+        unsafe { bytes::buf::UninitSlice::from_raw_parts_mut(slice.as_mut_ptr(), slice.len()) }
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        assert!(self.free_region(self.capacity()).len() >= cnt);
+        self.len += cnt;
+    }
+}
+
+// === pool ===
+
+fn index(size: usize) -> usize {
+    (size / MIN_SIZE).trailing_zeros() as usize
+}
+
+fn take(size: usize) -> Box<[u8]> {
+    POOL.with(|pool| pool.borrow_mut()[index(size)].pop())
+        .unwrap_or_else(|| vec![0; size].into_boxed_slice())
+}
+
+fn release(storage: Box<[u8]>) {
+    // The pool may not be accessible while the thread is being torn down, in
+    // which case the buffer is simply freed.
+    let _ = POOL.try_with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let bufs = &mut pool[index(storage.len())];
+        if bufs.len() < POOL_CAPACITY {
+            bufs.push(storage);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(buf: &mut CopyBuf, bytes: &[u8]) {
+        buf.reserve();
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let n = buf.contiguous_capacity().min(rest.len());
+            assert!(n > 0, "buffer must have capacity");
+            buf.put_slice(&rest[..n]);
+            rest = &rest[n..];
+        }
+    }
+
+    #[test]
+    fn wraps_into_two_chunks() {
+        let mut buf = CopyBuf::new();
+        write(&mut buf, &vec![1; MIN_SIZE - 10]);
+        buf.advance(MIN_SIZE - 20);
+        assert_eq!(buf.remaining(), 10);
+
+        write(&mut buf, &[2; 15]);
+        assert_eq!(buf.remaining(), 25);
+        let mut slices = [IoSlice::new(&[]); 4];
+        assert_eq!(buf.chunks_vectored(&mut slices), 2);
+        assert_eq!(slices[0].len(), 20);
+        assert_eq!(&slices[0][..10], &[1; 10]);
+        assert_eq!(&slices[0][10..], &[2; 10]);
+        assert_eq!(&*slices[1], &[2; 5]);
+
+        buf.advance(20);
+        assert_eq!(buf.chunk(), &[2; 5]);
+        buf.advance(5);
+        assert!(!buf.has_remaining());
+    }
+
+    #[test]
+    fn adapts_size() {
+        let mut buf = CopyBuf::new();
+        for _ in 0..ADAPT_AFTER {
+            buf.reserve();
+            assert_eq!(buf.capacity(), MIN_SIZE);
+            buf.record_read(MIN_SIZE, MIN_SIZE);
+        }
+        buf.reserve();
+        assert_eq!(buf.capacity(), 2 * MIN_SIZE, "must grow");
+
+        for _ in 0..ADAPT_AFTER {
+            buf.record_read(1, 2 * MIN_SIZE);
+        }
+        buf.reserve();
+        assert_eq!(buf.capacity(), MIN_SIZE, "must shrink");
+    }
+
+    #[test]
+    fn reuses_released_storage() {
+        let mut buf = CopyBuf::new();
+        buf.reserve();
+        let ptr = buf.chunk_mut().as_mut_ptr();
+        buf.release_if_empty();
+        assert_eq!(buf.capacity(), 0);
+
+        let mut other = CopyBuf::new();
+        other.reserve();
+        assert_eq!(
+            other.chunk_mut().as_mut_ptr(),
+            ptr,
+            "storage must be reused"
+        );
+    }
+}
@@ -9,48 +9,105 @@
     unsafe_code
 )]
 
-use bytes::{Buf, BufMut};
+mod buf;
+#[cfg(target_os = "linux")]
+mod splice;
+
+use self::buf::CopyBuf;
+use bytes::Buf;
 use futures::ready;
-use linkerd_io::{self as io, AsyncRead, AsyncWrite};
+use linkerd_io::{self as io, AsyncRead, AsyncWrite, AsyncWriteExt};
 use pin_project::pin_project;
 use std::task::{Context, Poll};
-use std::{future::Future, pin::Pin};
-use tracing::{error, trace};
+use std::{fmt, future::Future, pin::Pin, time::Duration};
+use tokio::time;
+use tracing::{debug, error, trace};
 
 /// A future piping data bi-directionally to In and Out.
 #[pin_project]
 pub struct Duplex<In, Out> {
     half_in: HalfDuplex<In>,
     half_out: HalfDuplex<Out>,
+    half_close: HalfClose,
+    // Set when the first side's EOF has been propagated.
+    first: Option<Side>,
+    // Set when the proxy closes the remaining direction.
+    forced: bool,
+    linger: Option<Pin<Box<time::Sleep>>>,
+}
+
+/// Configures how the proxy handles a peer closing its half of the connection.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum HalfClose {
+    /// The EOF is propagated to the other peer, and the other direction
+    /// remains open until the other peer closes it.
+    #[default]
+    Propagate,
+
+    /// The EOF is propagated to the other peer, and the other direction is
+    /// closed immediately.
+    Couple,
+
+    /// The EOF is propagated to the other peer, and the other direction is
+    /// closed if the other peer has not closed it within the timeout.
+    Linger(Duration),
+}
+
+/// A peer of a duplexed connection.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Client,
+    Server,
+}
+
+/// An operation on one peer of a duplexed connection.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    Read,
+    Write,
+    Shutdown,
+}
+
+/// Annotates an I/O error with the peer and operation that encountered it.
+///
+/// The errors returned by [`Duplex`] and [`splice`] wrap this type, so that
+/// callers may determine where a connection failed with
+/// [`DuplexError::downcast_ref`].
+#[derive(Debug)]
+pub struct DuplexError {
+    side: Side,
+    op: Op,
+    error: io::Error,
+}
+
+/// Describes how a duplexed connection was closed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Closed {
+    /// The peer that closed its half of the connection first.
+    pub first: Side,
+
+    /// True when the proxy closed the other direction, rather than the other
+    /// peer.
+    pub forced: bool,
 }
 
 #[pin_project]
 struct HalfDuplex<T> {
-    // None means socket met eof, and bytes have been drained into other half.
-    buf: Option<CopyBuf>,
+    buf: CopyBuf,
+    // Set when the socket met eof. Once the buffer has been drained into the
+    // other half, it is shut down.
+    eof: bool,
     is_shutdown: bool,
     #[pin]
     io: T,
+    side: Side,
     direction: &'static str,
     flushing: bool,
 }
 
-/// A buffer used to copy bytes from one IO to another.
-///
-/// Keeps read and write positions.
-struct CopyBuf {
-    // TODO:
-    // In linkerd-tcp, a shared buffer is used to start, and an allocation is
-    // only made if NotReady is found trying to flush the buffer. We could
-    // consider making the same optimization here.
-    buf: Box<[u8]>,
-    read_pos: usize,
-    write_pos: usize,
-}
-
 #[allow(dead_code)]
 enum Buffered {
-    NotEmpty,
+    Full,
     Read(usize),
     Eof,
 }
@@ -69,10 +126,72 @@ where
 {
     pub fn new(in_io: In, out_io: Out) -> Self {
         Duplex {
-            half_in: HalfDuplex::new(in_io, "client->server"),
-            half_out: HalfDuplex::new(out_io, "server->client"),
+            half_in: HalfDuplex::new(in_io, Side::Client),
+            half_out: HalfDuplex::new(out_io, Side::Server),
+            half_close: HalfClose::default(),
+            first: None,
+            forced: false,
+            linger: None,
         }
     }
+
+    /// Configures how the connection is closed once either peer closes its
+    /// half of the connection.
+    pub fn with_half_close(mut self, half_close: HalfClose) -> Self {
+        self.half_close = half_close;
+        self
+    }
+}
+
+/// Moves data bi-directionally between `in_io` and `out_io`.
+///
+/// Bytes buffered ahead of either I/O's socket are written first. Then, on
+/// Linux, if both I/Os expose their sockets and half-closes are propagated,
+/// data is spliced between the sockets without being copied through userspace.
+/// Otherwise, data is copied as with [`Duplex`].
+pub async fn splice<In, Out>(
+    mut in_io: In,
+    mut out_io: Out,
+    half_close: HalfClose,
+) -> io::Result<Closed>
+where
+    In: AsyncRead + AsyncWrite + io::Splice + Unpin,
+    Out: AsyncRead + AsyncWrite + io::Splice + Unpin,
+{
+    write_prefix(&mut in_io, &mut out_io)
+        .await
+        .map_err(DuplexError::wrap(Side::Server, Op::Write))?;
+    write_prefix(&mut out_io, &mut in_io)
+        .await
+        .map_err(DuplexError::wrap(Side::Client, Op::Write))?;
+
+    // The spliced sockets are only ever shut down by their peers' EOFs.
+    #[cfg(target_os = "linux")]
+    let (in_io, out_io) = match half_close {
+        HalfClose::Propagate => match splice::Splice::try_new(in_io, out_io) {
+            Ok(splice) => {
+                trace!("splicing");
+                return splice.await;
+            }
+            Err(ios) => ios,
+        },
+        _ => (in_io, out_io),
+    };
+
+    Duplex::new(in_io, out_io).with_half_close(half_close).await
+}
+
+async fn write_prefix<S, D>(src: &mut S, dst: &mut D) -> io::Result<()>
+where
+    S: io::Splice,
+    D: AsyncWrite + Unpin,
+{
+    let prefix = src.take_prefix();
+    if !prefix.is_empty() {
+        dst.write_all(&prefix).await?;
+        dst.flush().await?;
+    }
+    Ok(())
 }
 
 impl<In, Out> Future for Duplex<In, Out>
@@ -80,9 +199,9 @@ where
     In: AsyncRead + AsyncWrite + Unpin,
     Out: AsyncRead + AsyncWrite + Unpin,
 {
-    type Output = io::Result<()>;
+    type Output = io::Result<Closed>;
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<Closed> {
         let this = self.project();
         // This purposefully ignores the Async part, since we don't want to
         // return early if the first half isn't ready, but the other half
@@ -90,8 +209,64 @@ where
         trace!("poll");
         let _ = this.half_in.copy_into(this.half_out, cx)?;
         let _ = this.half_out.copy_into(this.half_in, cx)?;
+
+        // Each half is shut down once the opposite peer's EOF has been
+        // propagated to it.
+        let first = match *this.first {
+            Some(first) => first,
+            None => {
+                let first = if this.half_out.is_done() {
+                    Side::Client
+                } else if this.half_in.is_done() {
+                    Side::Server
+                } else {
+                    return Poll::Pending;
+                };
+                *this.first = Some(first);
+                match *this.half_close {
+                    HalfClose::Propagate => {}
+                    HalfClose::Couple => {
+                        *this.forced = !(this.half_in.is_done() && this.half_out.is_done());
+                    }
+                    HalfClose::Linger(timeout) => {
+                        *this.linger = Some(Box::pin(time::sleep(timeout)));
+                    }
+                }
+                first
+            }
+        };
+
+        let done = this.half_in.is_done() && this.half_out.is_done();
+        if !done && !*this.forced {
+            if let Some(linger) = this.linger.as_mut() {
+                if linger.as_mut().poll(cx).is_ready() {
+                    debug!(?first, "Linger timeout expired");
+                    *this.forced = true;
+                }
+            }
+        }
+
+        // Stop reading from the remaining peer so that the data it has
+        // already sent is written before its half is shut down.
+        if !done && *this.forced {
+            trace!(?first, "closing");
+            match first {
+                Side::Client => {
+                    this.half_out.eof = true;
+                    let _ = this.half_out.copy_into(this.half_in, cx)?;
+                }
+                Side::Server => {
+                    this.half_in.eof = true;
+                    let _ = this.half_in.copy_into(this.half_out, cx)?;
+                }
+            }
+        }
+
         if this.half_in.is_done() && this.half_out.is_done() {
-            Poll::Ready(Ok(()))
+            Poll::Ready(Ok(Closed {
+                first,
+                forced: *this.forced,
+            }))
         } else {
             Poll::Pending
         }
@@ -102,12 +277,17 @@ impl<T> HalfDuplex<T>
 where
     T: AsyncRead + Unpin,
 {
-    fn new(io: T, direction: &'static str) -> Self {
+    fn new(io: T, side: Side) -> Self {
         Self {
-            buf: Some(CopyBuf::new()),
+            buf: CopyBuf::new(),
+            eof: false,
             is_shutdown: false,
             io,
-            direction,
+            side,
+            direction: match side {
+                Side::Client => "client->server",
+                Side::Server => "server->client",
+            },
             flushing: false,
         }
     }
@@ -142,9 +322,47 @@ where
         let mut needs_flush = false;
 
         loop {
-            // As long as the underlying socket is alive, ensure we've read data
-            // from it into the local buffer.
-            match self.poll_buffer(cx)? {
+            // As long as the underlying socket is alive and there's capacity in
+            // the buffer, read more data from it.
+            let buffered = self.poll_buffer(cx)?;
+
+            if self.buf.has_remaining() {
+                // Write buffered data to the destination. When the buffered
+                // data wraps around the end of the buffer, it's written with a
+                // vectored write if the destination supports it.
+                match self.drain_into(dst, cx)? {
+                    // All of the buffered data was written, so continue reading more.
+                    Drained::All(sz) => {
+                        debug_assert!(sz > 0);
+                        needs_flush = true;
+                    }
+                    // Only some of the buffered data could be written before
+                    // the destination became pending. Try to flush the written
+                    // data to get capacity.
+                    Drained::Partial(_) => {
+                        ready!(self.poll_flush(dst, cx))?;
+                        // If the flush completed, try writing again to ensure
+                        // that we have a notification registered. If all of the
+                        // buffered data still cannot be written, return
+                        // pending. Otherwise, continue.
+                        if let Drained::Partial(_) = self.drain_into(dst, cx)? {
+                            return Poll::Pending;
+                        }
+                        needs_flush = false;
+                    }
+                    Drained::BufferEmpty => {
+                        error!(
+                            direction = self.direction,
+                            "Invalid state: attempted to write from an empty buffer"
+                        );
+                        debug_assert!(false, "The write buffer should never be empty");
+                        return Poll::Ready(Ok(()));
+                    }
+                }
+                continue;
+            }
+
+            match buffered {
                 Poll::Pending => {
                     // If there's no data to be read and we've written data, try
                     // flushing before returning pending.
@@ -159,80 +377,63 @@ where
                     return Poll::Pending;
                 }
 
-                Poll::Ready(Buffered::NotEmpty) | Poll::Ready(Buffered::Read(_)) => {
-                    // Write buffered data to the destination.
-                    match self.drain_into(dst, cx)? {
-                        // All of the buffered data was written, so continue reading more.
-                        Drained::All(sz) => {
-                            debug_assert!(sz > 0);
-                            needs_flush = true;
-                        }
-                        // Only some of the buffered data could be written
-                        // before the destination became pending. Try to flush
-                        // the written data to get capacity.
-                        Drained::Partial(_) => {
-                            ready!(self.poll_flush(dst, cx))?;
-                            // If the flush completed, try writing again to
-                            // ensure that we have a notification registered. If
-                            // all of the buffered data still cannot be written,
-                            // return pending. Otherwise, continue.
-                            if let Drained::Partial(_) = self.drain_into(dst, cx)? {
-                                return Poll::Pending;
-                            }
-                            needs_flush = false;
-                        }
-                        Drained::BufferEmpty => {
-                            error!(
-                                direction = self.direction,
-                                "Invalid state: attempted to write from an empty buffer"
-                            );
-                            debug_assert!(false, "The write buffer should never be empty");
-                            return Poll::Ready(Ok(()));
-                        }
-                    }
-                }
-
-                // The socket closed, so initiate shutdown on the destination.
+                // The socket closed and all of its data has been written, so
+                // initiate shutdown on the destination.
                 Poll::Ready(Buffered::Eof) => {
                     trace!(direction = %self.direction, "shutting down");
                     debug_assert!(!dst.is_shutdown, "attempted to shut down destination twice");
-                    ready!(Pin::new(&mut dst.io).poll_shutdown(cx))?;
+                    ready!(Pin::new(&mut dst.io).poll_shutdown(cx))
+                        .map_err(DuplexError::wrap(dst.side, Op::Shutdown))?;
                     dst.is_shutdown = true;
                     return Poll::Ready(Ok(()));
                 }
+
+                Poll::Ready(Buffered::Full) | Poll::Ready(Buffered::Read(_)) => {
+                    debug_assert!(false, "data must have been buffered");
+                }
             }
         }
     }
 
     /// Attempts to read and buffer data from the underlying stream, returning
-    /// the number of bytes read. If the buffer already has data, no new data
-    /// will be read.
+    /// the number of bytes read. Data is only read while the buffer has
+    /// capacity.
     fn poll_buffer(&mut self, cx: &mut Context<'_>) -> io::Poll<Buffered> {
-        // Buffer data only if no data is buffered.
-        //
-        // TODO should we read more data as long as there's buffer capacity?
-        // To do this, we'd have to get more complex about handling EOF.
-        if let Some(buf) = self.buf.as_mut() {
-            if buf.has_remaining() {
-                // Data was already buffered, so just return immediately.
-                trace!(direction = %self.direction, remaining = buf.remaining(), "skipping read");
-                return Poll::Ready(Ok(Buffered::NotEmpty));
-            }
+        if self.eof {
+            return Poll::Ready(Ok(Buffered::Eof));
+        }
 
-            buf.reset();
-            trace!(direction = %self.direction, "reading");
-            let sz = ready!(io::poll_read_buf(Pin::new(&mut self.io), cx, buf))?;
-            trace!(direction = %self.direction, "read {}B", sz);
+        self.buf.reserve();
+        let offered = self.buf.contiguous_capacity();
+        if offered == 0 {
+            // Data was already buffered, so just return immediately.
+            trace!(direction = %self.direction, remaining = self.buf.remaining(), "skipping read");
+            return Poll::Ready(Ok(Buffered::Full));
+        }
 
-            // If data was read, return the number of bytes read.
-            if sz > 0 {
-                return Poll::Ready(Ok(Buffered::Read(sz)));
+        trace!(direction = %self.direction, "reading");
+        let sz = match io::poll_read_buf(Pin::new(&mut self.io), cx, &mut self.buf)
+            .map_err(DuplexError::wrap(self.side, Op::Read))?
+        {
+            Poll::Ready(sz) => sz,
+            Poll::Pending => {
+                // Don't hold a buffer while the socket is idle.
+                self.buf.release_if_empty();
+                return Poll::Pending;
             }
+        };
+        trace!(direction = %self.direction, "read {}B", sz);
+
+        // If data was read, return the number of bytes read.
+        if sz > 0 {
+            self.buf.record_read(sz, offered);
+            return Poll::Ready(Ok(Buffered::Read(sz)));
         }
 
         // No more data can be read.
         trace!("eof");
-        self.buf = None;
+        self.eof = true;
+        self.buf.release_if_empty();
         Poll::Ready(Ok(Buffered::Eof))
     }
 
@@ -244,7 +445,9 @@ where
         cx: &mut Context<'_>,
     ) -> io::Poll<()> {
         trace!(direction = %self.direction, "flushing");
-        let poll = Pin::new(&mut dst.io).poll_flush(cx);
+        let poll = Pin::new(&mut dst.io)
+            .poll_flush(cx)
+            .map_err(DuplexError::wrap(dst.side, Op::Write));
         self.flushing = poll.is_pending();
         if poll.is_ready() {
             trace!(direction = %self.direction, "flushed");
@@ -260,19 +463,19 @@ where
     ) -> io::Result<Drained> {
         let mut sz = 0;
 
-        if let Some(buf) = self.buf.as_mut() {
-            while buf.has_remaining() {
-                trace!(direction = %self.direction, "writing {}B", buf.remaining());
-                let n = match io::poll_write_buf(Pin::new(&mut dst.io), cx, buf)? {
-                    Poll::Pending => return Ok(Drained::Partial(sz)),
-                    Poll::Ready(n) => n,
-                };
-                trace!(direction = %self.direction, "wrote {}B", n);
-                if n == 0 {
-                    return Err(write_zero());
-                }
-                sz += n;
+        while self.buf.has_remaining() {
+            trace!(direction = %self.direction, "writing {}B", self.buf.remaining());
+            let n = match io::poll_write_buf(Pin::new(&mut dst.io), cx, &mut self.buf)
+                .map_err(DuplexError::wrap(dst.side, Op::Write))?
+            {
+                Poll::Pending => return Ok(Drained::Partial(sz)),
+                Poll::Ready(n) => n,
+            };
+            trace!(direction = %self.direction, "wrote {}B", n);
+            if n == 0 {
+                return Err(DuplexError::wrap(dst.side, Op::Write)(write_zero()));
             }
+            sz += n;
         }
 
         if sz == 0 {
@@ -291,56 +494,295 @@ fn write_zero() -> io::Error {
     io::Error::new(io::ErrorKind::WriteZero, "write zero bytes")
 }
 
-impl CopyBuf {
-    fn new() -> Self {
-        CopyBuf {
-            buf: Box::new([0; 8 * 1024]),
-            read_pos: 0,
-            write_pos: 0,
+// === impl Side ===
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Client => f.write_str("client"),
+            Self::Server => f.write_str("server"),
         }
     }
+}
+
+// === impl DuplexError ===
 
-    fn reset(&mut self) {
-        debug_assert_eq!(self.read_pos, self.write_pos);
-        self.read_pos = 0;
-        self.write_pos = 0;
+impl DuplexError {
+    /// Returns a function that wraps an I/O error, preserving its kind.
+    pub(crate) fn wrap(side: Side, op: Op) -> impl FnOnce(io::Error) -> io::Error {
+        move |error| io::Error::new(error.kind(), Self { side, op, error })
     }
-}
 
-impl Buf for CopyBuf {
-    fn remaining(&self) -> usize {
-        self.write_pos - self.read_pos
+    /// Returns the annotation of an error returned by [`Duplex`] or [`splice`].
+    pub fn downcast_ref(error: &io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref()
     }
 
-    fn chunk(&self) -> &[u8] {
-        &self.buf[self.read_pos..self.write_pos]
+    /// The peer whose connection encountered the error.
+    pub fn side(&self) -> Side {
+        self.side
     }
 
-    fn advance(&mut self, cnt: usize) {
-        assert!(self.write_pos >= self.read_pos + cnt);
-        self.read_pos += cnt;
+    pub fn op(&self) -> Op {
+        self.op
     }
-}
 
-#[allow(unsafe_code)]
-unsafe impl BufMut for CopyBuf {
-    fn remaining_mut(&self) -> usize {
-        self.buf.len() - self.write_pos
+    /// The underlying I/O error.
+    pub fn io(&self) -> &io::Error {
+        &self.error
     }
+}
 
-    fn chunk_mut(&mut self) -> &mut bytes::buf::UninitSlice {
-        // Safety: The memory is initialized. This is the only way to turn a
-        // `&[T]` into a `&[MaybeUninit<T>]` without ptr casting.
-        unsafe {
-            bytes::buf::UninitSlice::from_raw_parts_mut(
-                &mut self.buf[self.write_pos] as *mut _,
-                self.buf.len() - self.write_pos,
-            )
+impl fmt::Display for DuplexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { side, op, error } = self;
+        match op {
+            Op::Read => write!(f, "failed to read from {side}: {error}"),
+            Op::Write => write!(f, "failed to write to {side}: {error}"),
+            Op::Shutdown => write!(f, "failed to shut down {side}: {error}"),
         }
     }
+}
+
+impl std::error::Error for DuplexError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_io::AsyncReadExt;
+
+    /// Shutting down one direction doesn't prevent data from being proxied in
+    /// the other, and data written before a shutdown is received before EOF.
+    #[tokio::test(flavor = "current_thread")]
+    async fn half_close_ordering() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let (mut client, in_io) = io::duplex(1024);
+        let (out_io, mut server) = io::duplex(1024);
+        let duplex = tokio::spawn(Duplex::new(in_io, out_io));
+
+        client.write_all(b"ping").await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut req = Vec::new();
+        server.read_to_end(&mut req).await.unwrap();
+        assert_eq!(req, b"ping");
+        assert!(!duplex.is_finished(), "the other half must remain open");
+
+        server.write_all(b"pong").await.unwrap();
+        server.shutdown().await.unwrap();
+        let mut rsp = Vec::new();
+        client.read_to_end(&mut rsp).await.unwrap();
+        assert_eq!(rsp, b"pong");
+
+        let closed = duplex.await.unwrap().expect("duplex must complete cleanly");
+        assert_eq!(
+            closed,
+            Closed {
+                first: Side::Client,
+                forced: false,
+            }
+        );
+    }
+
+    /// When half-closes are coupled, either peer's EOF closes both directions,
+    /// after data already sent by the other peer is written.
+    #[tokio::test(flavor = "current_thread")]
+    async fn couple_closes_both_halves() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let (mut client, in_io) = io::duplex(1024);
+        let (out_io, mut server) = io::duplex(1024);
+        let duplex = tokio::spawn(Duplex::new(in_io, out_io).with_half_close(HalfClose::Couple));
+
+        client.write_all(b"ping").await.unwrap();
+        server.write_all(b"pong").await.unwrap();
+        server.shutdown().await.unwrap();
+
+        // The server's EOF closes the client's half, even though the client has
+        // not closed it.
+        let mut req = Vec::new();
+        server.read_to_end(&mut req).await.unwrap();
+        assert_eq!(req, b"ping");
+        let mut rsp = Vec::new();
+        client.read_to_end(&mut rsp).await.unwrap();
+        assert_eq!(rsp, b"pong");
+
+        let closed = duplex.await.unwrap().expect("duplex must complete cleanly");
+        assert_eq!(
+            closed,
+            Closed {
+                first: Side::Server,
+                forced: true,
+            }
+        );
+    }
 
-    unsafe fn advance_mut(&mut self, cnt: usize) {
-        assert!(self.buf.len() >= self.write_pos + cnt);
-        self.write_pos += cnt;
+    /// When half-closes linger, the other direction remains open until the
+    /// linger timeout expires.
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn linger_closes_after_timeout() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        const LINGER: Duration = Duration::from_secs(10);
+        let (mut client, in_io) = io::duplex(1024);
+        let (out_io, mut server) = io::duplex(1024);
+        let duplex =
+            tokio::spawn(Duplex::new(in_io, out_io).with_half_close(HalfClose::Linger(LINGER)));
+
+        client.write_all(b"ping").await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut req = Vec::new();
+        server.read_to_end(&mut req).await.unwrap();
+        assert_eq!(req, b"ping");
+
+        // Data sent before the timeout expires is proxied.
+        time::sleep(LINGER / 2).await;
+        assert!(!duplex.is_finished(), "the other half must remain open");
+        server.write_all(b"pong").await.unwrap();
+
+        let mut rsp = Vec::new();
+        client.read_to_end(&mut rsp).await.unwrap();
+        assert_eq!(rsp, b"pong");
+
+        let closed = duplex.await.unwrap().expect("duplex must complete cleanly");
+        assert_eq!(
+            closed,
+            Closed {
+                first: Side::Client,
+                forced: true,
+            }
+        );
+        assert!(
+            server.write_all(b"late").await.is_err(),
+            "the server's connection must be closed"
+        );
+    }
+
+    /// When half-closes linger, a peer that closes its half before the linger
+    /// timeout expires is not forced.
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn linger_completes_when_peer_closes() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        const LINGER: Duration = Duration::from_secs(10);
+        let (mut client, in_io) = io::duplex(1024);
+        let (out_io, mut server) = io::duplex(1024);
+        let duplex =
+            tokio::spawn(Duplex::new(in_io, out_io).with_half_close(HalfClose::Linger(LINGER)));
+
+        server.shutdown().await.unwrap();
+        let mut rsp = Vec::new();
+        client.read_to_end(&mut rsp).await.unwrap();
+        assert!(rsp.is_empty());
+
+        time::sleep(LINGER / 2).await;
+        client.write_all(b"bye").await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut req = Vec::new();
+        server.read_to_end(&mut req).await.unwrap();
+        assert_eq!(req, b"bye");
+
+        let closed = duplex.await.unwrap().expect("duplex must complete cleanly");
+        assert_eq!(
+            closed,
+            Closed {
+                first: Side::Server,
+                forced: false,
+            }
+        );
+    }
+
+    /// Data buffered when the source reaches EOF is written before the
+    /// destination is shut down.
+    #[tokio::test(flavor = "current_thread")]
+    async fn eof_after_buffered_data() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let in_io = tokio_test::io::Builder::new()
+            .read(b"hello ")
+            .read(b"world")
+            .build();
+        // The destination accepts fewer bytes than are read, so data remains
+        // buffered when EOF is read.
+        let (out_io, mut server) = io::duplex(4);
+        let duplex = tokio::spawn(async move {
+            let mut half_in = HalfDuplex::new(in_io, Side::Client);
+            let mut half_out = HalfDuplex::new(out_io, Side::Server);
+            futures::future::poll_fn(|cx| half_in.copy_into(&mut half_out, cx)).await
+        });
+
+        let mut req = Vec::new();
+        server.read_to_end(&mut req).await.unwrap();
+        assert_eq!(req, b"hello world");
+        duplex.await.unwrap().expect("copy must complete cleanly");
+    }
+
+    /// I/O errors identify the peer and the operation that failed.
+    #[tokio::test(flavor = "current_thread")]
+    async fn errors_identify_side_and_op() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let (_client, in_io) = io::duplex(1024);
+        let out_io = tokio_test::io::Builder::new()
+            .read_error(io::ErrorKind::ConnectionReset.into())
+            .build();
+        let error = Duplex::new(in_io, out_io)
+            .await
+            .expect_err("duplex must fail");
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionReset);
+        let error = DuplexError::downcast_ref(&error).expect("error must be annotated");
+        assert_eq!((error.side(), error.op()), (Side::Server, Op::Read));
+
+        let (mut client, in_io) = io::duplex(1024);
+        let out_io = tokio_test::io::Builder::new()
+            .write_error(io::ErrorKind::BrokenPipe.into())
+            .build();
+        client.write_all(b"ping").await.unwrap();
+        let error = Duplex::new(in_io, out_io)
+            .await
+            .expect_err("duplex must fail");
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        let error = DuplexError::downcast_ref(&error).expect("error must be annotated");
+        assert_eq!((error.side(), error.op()), (Side::Server, Op::Write));
+    }
+
+    /// Bytes are proxied unchanged through small transports that force
+    /// partial writes and wrap the copy buffer.
+    #[tokio::test(flavor = "current_thread")]
+    async fn large_transfer_is_byte_identical() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        const LEN: usize = 1024 * 1024 + 17;
+        let data = (0..LEN).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        let (mut client, in_io) = io::duplex(3 * 1024 + 1);
+        let (out_io, mut server) = io::duplex(5 * 1024 + 3);
+        let duplex = tokio::spawn(Duplex::new(in_io, out_io));
+
+        let client = {
+            let data = data.clone();
+            async move {
+                client.write_all(&data).await?;
+                client.shutdown().await?;
+                let mut rsp = Vec::new();
+                client.read_to_end(&mut rsp).await?;
+                io::Result::Ok(rsp)
+            }
+        };
+        let server = async move {
+            let mut req = Vec::new();
+            server.read_to_end(&mut req).await?;
+            server.write_all(&req).await?;
+            server.shutdown().await?;
+            io::Result::Ok(req)
+        };
+        let (rsp, req) = tokio::join!(client, server);
+        assert!(req.unwrap() == data, "request must be unchanged");
+        assert!(rsp.unwrap() == data, "response must be unchanged");
+        duplex.await.unwrap().expect("duplex must complete cleanly");
     }
 }
@@ -0,0 +1,423 @@
+//! Moves bytes bi-directionally between two TCP sockets through kernel pipes,
+//! with `splice(2)`, so that they are never copied through userspace.
+
+use crate::{Closed, DuplexError, Op, Side};
+use futures::ready;
+use linkerd_io as io;
+use nix::{
+    fcntl::{splice, SpliceFFlags},
+    sys::socket::{shutdown, Shutdown},
+};
+use std::{
+    future::Future,
+    io::{PipeReader, PipeWriter},
+    os::fd::{AsRawFd, RawFd},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::{io::Interest, net::TcpStream};
+use tracing::{debug, trace};
+
+/// The maximum number of bytes moved by each call to `splice(2)`. This matches
+/// the default capacity of a Linux pipe.
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// A future splicing data bi-directionally between the sockets underlying
+/// `In` and `Out`.
+pub(crate) struct Splice<In, Out> {
+    in_io: In,
+    out_io: Out,
+    half_in: HalfSplice,
+    half_out: HalfSplice,
+    first: Option<Side>,
+}
+
+/// Splices bytes read from one socket into a pipe and from that pipe to the
+/// other socket.
+struct HalfSplice {
+    pipe_rx: PipeReader,
+    pipe_tx: PipeWriter,
+    // The number of bytes in the pipe that have not yet been written.
+    buffered: usize,
+    eof: bool,
+    is_shutdown: bool,
+    src: Side,
+    dst: Side,
+    direction: &'static str,
+}
+
+// === impl Splice ===
+
+impl<In, Out> Splice<In, Out>
+where
+    In: io::Splice + Unpin,
+    Out: io::Splice + Unpin,
+{
+    /// Returns a splicing future if both I/Os expose their sockets.
+    pub(crate) fn try_new(in_io: In, out_io: Out) -> Result<Self, (In, Out)> {
+        if in_io.splice_socket().is_none() || out_io.splice_socket().is_none() {
+            return Err((in_io, out_io));
+        }
+        let halves = HalfSplice::new(Side::Client, Side::Server)
+            .and_then(|half_in| Ok((half_in, HalfSplice::new(Side::Server, Side::Client)?)));
+        match halves {
+            Ok((half_in, half_out)) => Ok(Self {
+                in_io,
+                out_io,
+                half_in,
+                half_out,
+                first: None,
+            }),
+            Err(error) => {
+                debug!(%error, "Failed to create pipes; copying");
+                Err((in_io, out_io))
+            }
+        }
+    }
+}
+
+impl<In, Out> Future for Splice<In, Out>
+where
+    In: io::Splice + Unpin,
+    Out: io::Splice + Unpin,
+{
+    type Output = io::Result<Closed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<Closed> {
+        let this = self.get_mut();
+        // As with `Duplex`, the readiness of each half is ignored so that one
+        // half may make progress while the other is pending.
+        let _ = this
+            .half_in
+            .splice_into(&mut this.in_io, &mut this.out_io, cx)?;
+        let _ = this
+            .half_out
+            .splice_into(&mut this.out_io, &mut this.in_io, cx)?;
+        if this.first.is_none() {
+            if this.half_in.is_shutdown {
+                this.first = Some(Side::Client);
+            } else if this.half_out.is_shutdown {
+                this.first = Some(Side::Server);
+            }
+        }
+        match this.first {
+            Some(first) if this.half_in.is_shutdown && this.half_out.is_shutdown => {
+                Poll::Ready(Ok(Closed {
+                    first,
+                    forced: false,
+                }))
+            }
+            _ => Poll::Pending,
+        }
+    }
+}
+
+// === impl HalfSplice ===
+
+impl HalfSplice {
+    fn new(src: Side, dst: Side) -> io::Result<Self> {
+        let (pipe_rx, pipe_tx) = std::io::pipe()?;
+        Ok(Self {
+            pipe_rx,
+            pipe_tx,
+            buffered: 0,
+            eof: false,
+            is_shutdown: false,
+            src,
+            dst,
+            direction: match src {
+                Side::Client => "client->server",
+                Side::Server => "server->client",
+            },
+        })
+    }
+
+    /// Splices data from `src` into `dst` until `src` reaches EOF and `dst`
+    /// has been shut down.
+    fn splice_into(
+        &mut self,
+        src: &mut impl io::Splice,
+        dst: &mut impl io::Splice,
+        cx: &mut Context<'_>,
+    ) -> io::Poll<()> {
+        loop {
+            if self.is_shutdown {
+                return Poll::Ready(Ok(()));
+            }
+
+            if self.buffered > 0 {
+                let sock = socket(dst).map_err(DuplexError::wrap(self.dst, Op::Write))?;
+                ready!(sock.poll_write_ready(cx))
+                    .map_err(DuplexError::wrap(self.dst, Op::Write))?;
+                let rx = self.pipe_rx.as_raw_fd();
+                let len = self.buffered;
+                let written = match try_splice(sock, Interest::WRITABLE, rx, sock.as_raw_fd(), len)
+                {
+                    Ok(sz) => sz,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                    Err(e) => return Poll::Ready(Err(DuplexError::wrap(self.dst, Op::Write)(e))),
+                };
+                trace!(direction = %self.direction, written);
+                self.buffered -= written;
+                dst.record_spliced(0, written);
+                continue;
+            }
+
+            if self.eof {
+                trace!(direction = %self.direction, "shutting down");
+                socket(dst)
+                    .and_then(|sock| Ok(shutdown(sock.as_raw_fd(), Shutdown::Write)?))
+                    .map_err(DuplexError::wrap(self.dst, Op::Shutdown))?;
+                self.is_shutdown = true;
+                return Poll::Ready(Ok(()));
+            }
+
+            let sock = socket(src).map_err(DuplexError::wrap(self.src, Op::Read))?;
+            ready!(sock.poll_read_ready(cx)).map_err(DuplexError::wrap(self.src, Op::Read))?;
+            let tx = self.pipe_tx.as_raw_fd();
+            let read = match try_splice(
+                sock,
+                Interest::READABLE,
+                sock.as_raw_fd(),
+                tx,
+                PIPE_CAPACITY,
+            ) {
+                Ok(sz) => sz,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Poll::Ready(Err(DuplexError::wrap(self.src, Op::Read)(e))),
+            };
+            trace!(direction = %self.direction, read);
+            if read == 0 {
+                self.eof = true;
+            } else {
+                self.buffered += read;
+                src.record_spliced(read, 0);
+            }
+        }
+    }
+}
+
+fn socket(io: &impl io::Splice) -> io::Result<&TcpStream> {
+    io.splice_socket()
+        .ok_or_else(|| io::Error::other("socket may no longer be spliced"))
+}
+
+/// Splices up to `len` bytes from `fd_in` to `fd_out`, clearing the socket's
+/// readiness if the operation would block.
+fn try_splice(
+    sock: &TcpStream,
+    interest: Interest,
+    fd_in: RawFd,
+    fd_out: RawFd,
+    len: usize,
+) -> io::Result<usize> {
+    sock.try_io(interest, || {
+        let flags = SpliceFFlags::SPLICE_F_MOVE | SpliceFFlags::SPLICE_F_NONBLOCK;
+        splice(fd_in, None, fd_out, None, len, flags).map_err(io::Error::from)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_io::{AsyncReadExt, AsyncWriteExt, PrefixedIo, Sensor, SensorIo, Splice as _};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use tokio::net::TcpListener;
+
+    #[derive(Clone, Debug, Default)]
+    struct Counts {
+        read: Arc<AtomicUsize>,
+        written: Arc<AtomicUsize>,
+    }
+
+    impl Sensor for Counts {
+        fn record_read(&mut self, sz: usize) {
+            self.read.fetch_add(sz, Ordering::Relaxed);
+        }
+
+        fn record_write(&mut self, sz: usize) {
+            self.written.fetch_add(sz, Ordering::Relaxed);
+        }
+
+        fn record_close(&mut self, _: Option<linkerd_errno::Errno>) {}
+
+        fn record_error<T>(&mut self, op: io::Poll<T>) -> io::Poll<T> {
+            op
+        }
+    }
+
+    impl Counts {
+        fn get(&self) -> (usize, usize) {
+            (
+                self.read.load(Ordering::Relaxed),
+                self.written.load(Ordering::Relaxed),
+            )
+        }
+    }
+
+    /// Returns both ends of a loopback TCP connection.
+    async fn pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (client, server) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        (client.unwrap(), server.unwrap().0)
+    }
+
+    /// A socket that records the number of bytes spliced to and from it.
+    struct Tracked {
+        io: TcpStream,
+        spliced: Arc<AtomicUsize>,
+    }
+
+    impl io::AsyncRead for Tracked {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut io::ReadBuf<'_>,
+        ) -> io::Poll<()> {
+            Pin::new(&mut self.io).poll_read(cx, buf)
+        }
+    }
+
+    impl io::AsyncWrite for Tracked {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> io::Poll<usize> {
+            Pin::new(&mut self.io).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+            Pin::new(&mut self.io).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> io::Poll<()> {
+            Pin::new(&mut self.io).poll_shutdown(cx)
+        }
+    }
+
+    impl io::Splice for Tracked {
+        fn splice_socket(&self) -> Option<&TcpStream> {
+            Some(&self.io)
+        }
+
+        fn record_spliced(&mut self, read: usize, written: usize) {
+            self.spliced.fetch_add(read + written, Ordering::Relaxed);
+        }
+    }
+
+    /// Sends `len` bytes from a client through the proxied connection to a
+    /// server, which responds with `len` bytes.
+    async fn transfer<F>(len: usize, proxy: impl FnOnce(TcpStream, TcpStream) -> F)
+    where
+        F: Future<Output = io::Result<Closed>> + Send + 'static,
+    {
+        let (mut client, proxy_in) = pair().await;
+        let (proxy_out, mut server) = pair().await;
+        let proxy = tokio::spawn(proxy(proxy_in, proxy_out));
+
+        let data = vec![7u8; len];
+        let client = async move {
+            client.write_all(&data).await?;
+            client.shutdown().await?;
+            let mut rsp = Vec::with_capacity(len);
+            client.read_to_end(&mut rsp).await?;
+            assert_eq!(rsp.len(), len, "client must read the full response");
+            io::Result::Ok(())
+        };
+        let server = async move {
+            let mut req = Vec::with_capacity(len);
+            server.read_to_end(&mut req).await?;
+            assert_eq!(req.len(), len, "server must read the full request");
+            server.write_all(&req).await?;
+            server.shutdown().await?;
+            io::Result::Ok(())
+        };
+        let (c, s) = tokio::join!(client, server);
+        c.expect("client must succeed");
+        s.expect("server must succeed");
+        proxy.await.unwrap().expect("proxy must complete cleanly");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn splices_prefixed_sockets() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        let (mut client, proxy_in) = pair().await;
+        let (proxy_out, mut server) = pair().await;
+
+        let in_bytes = Counts::default();
+        let out_bytes = Counts::default();
+        let in_io = SensorIo::new(PrefixedIo::new("hello ", proxy_in), in_bytes.clone());
+        let out_io = SensorIo::new(proxy_out, out_bytes.clone());
+        assert!(
+            in_io.splice_socket().is_none(),
+            "prefixed I/O must not be spliced"
+        );
+        let proxy = tokio::spawn(crate::splice(in_io, out_io, Default::default()));
+
+        client.write_all(b"world").await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut req = String::new();
+        server.read_to_string(&mut req).await.unwrap();
+        assert_eq!(req, "hello world");
+
+        server.write_all(b"goodbye").await.unwrap();
+        server.shutdown().await.unwrap();
+        let mut rsp = String::new();
+        client.read_to_string(&mut rsp).await.unwrap();
+        assert_eq!(rsp, "goodbye");
+
+        let closed = proxy.await.unwrap().expect("proxy must complete cleanly");
+        assert_eq!(closed.first, Side::Client);
+        assert_eq!(in_bytes.get(), (11, 7), "server-side bytes");
+        assert_eq!(out_bytes.get(), (7, 11), "client-side bytes");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn splices_sockets() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        const LEN: usize = 1024 * 1024;
+        let spliced = Arc::new(AtomicUsize::new(0));
+        transfer(LEN, |i, io| {
+            let o = Tracked {
+                io,
+                spliced: spliced.clone(),
+            };
+            crate::splice(i, o, Default::default())
+        })
+        .await;
+        assert_eq!(
+            spliced.load(Ordering::Relaxed),
+            2 * LEN,
+            "all bytes must be spliced"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn copies_unspliceable_io() {
+        let _trace = linkerd_tracing::test::trace_init();
+
+        // Boxed I/O never exposes its socket, so bytes must be copied even
+        // though the other I/O could be spliced.
+        let spliced = Arc::new(AtomicUsize::new(0));
+        transfer(1024 * 1024, |i, io| {
+            let o = Tracked {
+                io,
+                spliced: spliced.clone(),
+            };
+            crate::splice(linkerd_io::BoxedIo::new(i), o, Default::default())
+        })
+        .await;
+        assert_eq!(
+            spliced.load(Ordering::Relaxed),
+            0,
+            "bytes must not be spliced"
+        );
+    }
+}
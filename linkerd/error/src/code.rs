@@ -0,0 +1,170 @@
+use std::fmt;
+
+macro_rules! error_codes {
+    ($($(#[$attr:meta])* $variant:ident => $name:literal,)+) => {
+        /// Classifies the errors that the proxy reports to its clients, e.g. in
+        /// the `l5d-proxy-error` header.
+        ///
+        /// A code's identifier is stable so that clients may handle errors by
+        /// their codes: codes may be added, but an existing code's identifier
+        /// must never be changed or removed.
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum ErrorCode {
+            $($(#[$attr])* $variant,)+
+        }
+
+        impl ErrorCode {
+            /// All codes, in the order in which they were introduced.
+            pub const ALL: &'static [Self] = &[$(Self::$variant,)+];
+
+            /// Returns the code's stable, snake_case identifier.
+            pub const fn as_str(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)+
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    /// The proxy could not classify the error.
+    Unexpected => "unexpected",
+    /// No endpoint became available before the failfast timeout, e.g.
+    /// because none were discovered or all of their breakers are open.
+    Failfast => "failfast",
+    /// The request was shed because the proxy is at capacity.
+    LoadShed => "load_shed",
+    /// The application is overloaded; the request may be retried later.
+    Overloaded => "overloaded",
+    /// No response was received before a configured timeout.
+    ResponseTimeout => "response_timeout",
+    /// The request's deadline elapsed before it was processed.
+    DeadlineExceeded => "deadline_exceeded",
+    /// A connection could not be established with the endpoint.
+    ConnectFailed => "connect_failed",
+    /// A connection was not established before the connect timeout.
+    ConnectTimeout => "connect_timeout",
+    /// The endpoint reset the request's stream.
+    StreamReset => "stream_reset",
+    /// The request's client and destination disagree on the HTTP version.
+    ProtocolMismatch => "protocol_mismatch",
+    /// The endpoint's identity did not match the `l5d-require-id` header.
+    IdentityRequired => "identity_required",
+    /// The request's backend is configured to fail requests.
+    DispatcherFailed => "dispatcher_failed",
+    /// No route matched the request.
+    NoRoute => "no_route",
+    /// The request was redirected by a route.
+    Redirect => "redirect",
+    /// A route's redirect could not be applied to the request.
+    InvalidRedirect => "invalid_redirect",
+    /// The request's policy is invalid.
+    InvalidPolicy => "invalid_policy",
+    /// The request failed as configured by a route.
+    InjectedFailure => "injected_failure",
+    /// The request was aborted by a route's fault injection.
+    FaultAbort => "fault_abort",
+    /// The request was failed by a backend fault configured through the admin
+    /// server.
+    BackendFault => "backend_fault",
+    /// The request's body exceeds a configured limit.
+    RequestBodyTooLarge => "request_body_too_large",
+    /// The request's body could not be decoded.
+    InvalidRequestEncoding => "invalid_request_encoding",
+    /// The request is not authorized by policy.
+    Unauthorized => "unauthorized",
+    /// The request's client is not authenticated.
+    Unauthenticated => "unauthenticated",
+    /// The request exceeds a rate limit.
+    RateLimited => "rate_limited",
+    /// The request would loop through a gateway.
+    LoopDetected => "loop_detected",
+    /// The request targets a domain that a gateway does not serve.
+    InvalidGatewayDomain => "invalid_gateway_domain",
+    /// The request must be sent in proxy form.
+    ProxyFormRequired => "proxy_form_required",
+    /// The request's destination could not be discovered.
+    DiscoveryFailed => "discovery_failed",
+    /// The requested resource does not exist.
+    NotFound => "not_found",
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorCode;
+
+    /// The codes that have been released. Codes may only be appended.
+    const RELEASED: &[&str] = &[
+        "unexpected",
+        "failfast",
+        "load_shed",
+        "overloaded",
+        "response_timeout",
+        "deadline_exceeded",
+        "connect_failed",
+        "connect_timeout",
+        "stream_reset",
+        "protocol_mismatch",
+        "identity_required",
+        "dispatcher_failed",
+        "no_route",
+        "redirect",
+        "invalid_redirect",
+        "invalid_policy",
+        "injected_failure",
+        "fault_abort",
+        "backend_fault",
+        "request_body_too_large",
+        "invalid_request_encoding",
+        "unauthorized",
+        "unauthenticated",
+        "rate_limited",
+        "loop_detected",
+        "invalid_gateway_domain",
+        "proxy_form_required",
+        "discovery_failed",
+        "not_found",
+    ];
+
+    #[test]
+    fn codes_are_additive() {
+        let codes = ErrorCode::ALL
+            .iter()
+            .map(ErrorCode::as_str)
+            .collect::<Vec<_>>();
+        assert!(
+            codes.starts_with(RELEASED),
+            "released codes must not be changed or removed: {codes:?}"
+        );
+        assert_eq!(
+            codes.len(),
+            RELEASED.len(),
+            "new codes must be added to the released codes"
+        );
+    }
+
+    #[test]
+    fn codes_are_unique_snake_case() {
+        let mut seen = std::collections::HashSet::new();
+        for code in ErrorCode::ALL {
+            let name = code.as_str();
+            assert!(seen.insert(name), "duplicate code: {name}");
+            assert!(
+                !name.is_empty()
+                    && !name.starts_with('_')
+                    && !name.ends_with('_')
+                    && name.chars().all(|c| c.is_ascii_lowercase() || c == '_'),
+                "code must be snake_case: {name}"
+            );
+            assert_eq!(code.to_string(), name);
+        }
+    }
+}
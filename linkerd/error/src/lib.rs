@@ -1,9 +1,10 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
+mod code;
 pub mod recover;
 
-pub use self::recover::Recover;
+pub use self::{code::ErrorCode, recover::Recover};
 pub use std::convert::Infallible;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
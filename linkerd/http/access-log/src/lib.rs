@@ -2,12 +2,15 @@
 #![forbid(unsafe_code)]
 
 use futures_core::TryFuture;
+use linkerd_error::ErrorCode;
+use linkerd_http_request_id::RequestId;
 use linkerd_identity as identity;
 use linkerd_proxy_transport::{ClientAddr, Remote};
 use linkerd_stack as svc;
 use linkerd_tls as tls;
 use linkerd_tracing::access_log::TRACE_TARGET;
 use pin_project::pin_project;
+use rand::Rng;
 use std::{
     future::Future,
     net::SocketAddr,
@@ -19,14 +22,24 @@ use svc::{NewService, Param};
 use tokio::time::Instant;
 use tracing::{field, span, Level, Span};
 
+/// The fraction of requests that are recorded in the access log.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SampleRatio(pub f64);
+
+/// Records all requests in the access log.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RecordAll(());
+
 #[derive(Clone, Debug)]
-pub struct NewAccessLog<N> {
+pub struct NewAccessLog<N, X = RecordAll> {
     inner: N,
+    sample: X,
 }
 
 #[derive(Clone, Debug)]
-pub struct AccessLogContext<S> {
+pub struct AccessLogContext<S, X = RecordAll> {
     inner: S,
+    sample: X,
     client_addr: SocketAddr,
     client_id: Option<identity::Id>,
 }
@@ -60,16 +73,33 @@ impl<N> NewAccessLog<N> {
     /// enabled.
     #[inline]
     pub fn layer() -> impl svc::layer::Layer<N, Service = Self> {
-        svc::layer::mk(|inner| NewAccessLog { inner })
+        svc::layer::mk(|inner| NewAccessLog {
+            inner,
+            sample: RecordAll(()),
+        })
     }
 }
 
-impl<N, T> NewService<T> for NewAccessLog<N>
+impl<N, X: Clone> NewAccessLog<N, X> {
+    /// Returns a new `NewAccessLog` layer that records a sample of requests.
+    ///
+    /// The [`SampleRatio`] is extracted from `sample` as each request is
+    /// handled, so it may change over the life of the service.
+    pub fn layer_sampled(sample: X) -> impl svc::layer::Layer<N, Service = Self> + Clone {
+        svc::layer::mk(move |inner| NewAccessLog {
+            inner,
+            sample: sample.clone(),
+        })
+    }
+}
+
+impl<N, X, T> NewService<T> for NewAccessLog<N, X>
 where
     T: Param<tls::ConditionalServerTls> + Param<Remote<ClientAddr>>,
     N: NewService<T>,
+    X: Clone,
 {
-    type Service = AccessLogContext<N::Service>;
+    type Service = AccessLogContext<N::Service, X>;
 
     fn new_service(&self, target: T) -> Self::Service {
         let Remote(ClientAddr(client_addr)) = target.param();
@@ -80,15 +110,17 @@ where
         let inner = self.inner.new_service(target);
         AccessLogContext {
             inner,
+            sample: self.sample.clone(),
             client_addr,
             client_id,
         }
     }
 }
 
-impl<S, B1, B2> svc::Service<http::Request<B1>> for AccessLogContext<S>
+impl<S, X, B1, B2> svc::Service<http::Request<B1>> for AccessLogContext<S, X>
 where
     S: svc::Service<http::Request<B1>, Response = http::Response<B2>>,
+    X: svc::ExtractParam<SampleRatio, ()>,
 {
     type Response = S::Response;
     type Error = S::Error;
@@ -100,6 +132,14 @@ where
     }
 
     fn call(&mut self, request: http::Request<B1>) -> Self::Future {
+        let SampleRatio(ratio) = self.sample.extract_param(&());
+        if ratio < 1.0 && !rand::rng().random_bool(ratio.clamp(0.0, 1.0)) {
+            return AccessLogFuture {
+                data: None,
+                inner: self.inner.call(request),
+            };
+        }
+
         let get_header = |name: http::header::HeaderName| {
             request
                 .headers()
@@ -118,6 +158,13 @@ where
                 .unwrap_or_default()
         };
 
+        // Set by the request ID layer, if one is configured.
+        let request_id = request
+            .extensions()
+            .get::<RequestId>()
+            .and_then(|id| id.value().to_str().ok())
+            .unwrap_or_default();
+
         let client_id: std::borrow::Cow<'_, str> = self
             .client_id
             .as_ref()
@@ -132,8 +179,10 @@ where
             uri =  %request.uri(),
             version = ?request.version(),
             trace_id = trace_id(),
+            request_id,
             request_bytes = get_header(http::header::CONTENT_LENGTH),
             status = field::Empty,
+            error_code = field::Empty,
             response_bytes = field::Empty,
             total_ns = field::Empty,
             processing_ns = field::Empty,
@@ -162,6 +211,15 @@ where
     }
 }
 
+// === impl RecordAll ===
+
+impl<T> svc::ExtractParam<SampleRatio, T> for RecordAll {
+    #[inline]
+    fn extract_param(&self, _: &T) -> SampleRatio {
+        SampleRatio(1.0)
+    }
+}
+
 impl<F, B2> Future for AccessLogFuture<F>
 where
     F: TryFuture<Ok = http::Response<B2>>,
@@ -202,6 +260,10 @@ where
             .map(|x| span.record("response_bytes", x));
 
         span.record("status", response.status().as_u16());
+        // Set on responses that the proxy synthesizes to describe an error.
+        if let Some(code) = response.extensions().get::<ErrorCode>() {
+            span.record("error_code", code.as_str());
+        }
         span.record("total_ns", field::display(total_ns));
         span.record("processing_ns", field::display(processing_ns));
 
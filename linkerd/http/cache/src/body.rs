@@ -0,0 +1,123 @@
+use crate::store::{Entry, Key, ResponseCache};
+use bytes::{Buf, Bytes, BytesMut};
+use http_body::{Body, Frame};
+use linkerd_error::Error;
+use linkerd_http_box::BoxBody;
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+/// A response body that stores the response in the cache once it completes.
+///
+/// The response is not stored if its body exceeds the cache's entry limit,
+/// includes trailers, or fails.
+#[pin_project]
+pub struct RecordBody {
+    #[pin]
+    inner: BoxBody,
+    recording: Option<Recording>,
+}
+
+pub(crate) struct Recording {
+    pub(crate) cache: ResponseCache,
+    pub(crate) key: Key,
+    pub(crate) entry: Entry,
+    pub(crate) max_bytes: usize,
+    pub(crate) buf: BytesMut,
+}
+
+// === impl RecordBody ===
+
+impl RecordBody {
+    pub(crate) fn new(inner: BoxBody, recording: Recording) -> Self {
+        // Responses without a body are stored immediately.
+        if inner.is_end_stream() {
+            recording.finish();
+            return Self {
+                inner,
+                recording: None,
+            };
+        }
+        Self {
+            inner,
+            recording: Some(recording),
+        }
+    }
+}
+
+impl Body for RecordBody {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        let frame = match ready!(this.inner.as_mut().poll_frame(cx)) {
+            Some(Ok(frame)) => frame.map_data(|mut data| data.copy_to_bytes(data.remaining())),
+            Some(Err(error)) => {
+                this.recording.take();
+                return Poll::Ready(Some(Err(error)));
+            }
+            None => {
+                if let Some(recording) = this.recording.take() {
+                    recording.finish();
+                }
+                return Poll::Ready(None);
+            }
+        };
+
+        match frame.data_ref() {
+            Some(data) => {
+                if let Some(recording) = this.recording.as_mut() {
+                    if recording.buf.len() + data.len() > recording.max_bytes {
+                        tracing::debug!("Response body is too large to be cached");
+                        this.recording.take();
+                    } else {
+                        recording.buf.extend_from_slice(data);
+                    }
+                }
+                if this.inner.is_end_stream() {
+                    if let Some(recording) = this.recording.take() {
+                        recording.finish();
+                    }
+                }
+            }
+            None => {
+                // Responses with trailers are not stored.
+                this.recording.take();
+            }
+        }
+
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl Recording ===
+
+impl Recording {
+    fn finish(self) {
+        let Self {
+            cache,
+            key,
+            mut entry,
+            buf,
+            ..
+        } = self;
+        entry.body = buf.freeze();
+        cache.insert(key, entry);
+    }
+}
@@ -0,0 +1,307 @@
+//! Tower middleware to cache HTTP responses.
+//!
+//! This implements the subset of RFC 9111 that applies to a shared cache:
+//! responses to `GET` requests are stored when they have a cacheable status
+//! and an explicit freshness lifetime (`s-maxage` or `max-age`), and they are
+//! reused for `GET` and `HEAD` requests that select the same representation
+//! (per `vary`). Stale responses are not served; requests for them are
+//! forwarded so that the stored response is replaced.
+//!
+//! See [`Cache<L, ReqX, S>`].
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![forbid(unsafe_code)]
+
+use self::{
+    body::Recording,
+    store::{Entry, Key, Lookup},
+};
+use bytes::{Bytes, BytesMut};
+use futures::future;
+use http::header::{self, HeaderValue};
+use linkerd_error::{Error, Result};
+use linkerd_http_box::BoxBody;
+use linkerd_metrics::prom;
+use linkerd_stack::{layer, ExtractParam, NewService, Param, Service};
+use std::{
+    future::Future,
+    hash::Hash,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tracing::{debug, trace};
+
+mod body;
+mod policy;
+mod store;
+#[cfg(test)]
+mod tests;
+
+pub use self::{body::RecordBody, store::ResponseCache};
+
+/// Configures response caching for a target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Params {
+    /// Distinguishes the responses stored for different targets.
+    pub scope: u64,
+
+    /// Whether requests with an `authorization` header may be served from,
+    /// and stored in, the cache.
+    pub allow_authorization: bool,
+
+    /// Bounds how long responses are stored, regardless of their freshness
+    /// lifetime, if set.
+    pub max_age: Option<Duration>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewCache<L: Clone, X, ReqX, N> {
+    inner: N,
+    cache: ResponseCache,
+    metrics: MetricFamilies<L>,
+    extract: X,
+    _marker: PhantomData<fn() -> ReqX>,
+}
+
+/// Serves requests from a shared [`ResponseCache`] when the target's
+/// [`Params`] enable caching.
+#[derive(Clone, Debug)]
+pub struct Cache<L: Clone, ReqX, S> {
+    inner: S,
+    params: Option<Params>,
+    cache: ResponseCache,
+    metrics: MetricFamilies<L>,
+    extract: ReqX,
+}
+
+#[derive(Clone, Debug)]
+pub struct MetricFamilies<L: Clone> {
+    hits: prom::Family<L, prom::Counter>,
+    misses: prom::Family<L, prom::Counter>,
+    stale: prom::Family<L, prom::Counter>,
+}
+
+#[derive(Clone, Debug)]
+struct Metrics {
+    hits: prom::Counter,
+    misses: prom::Counter,
+    stale: prom::Counter,
+}
+
+// === impl NewCache ===
+
+impl<L: Clone, ReqX, N> NewCache<L, (), ReqX, N> {
+    pub fn layer(
+        cache: ResponseCache,
+        metrics: MetricFamilies<L>,
+    ) -> impl layer::Layer<N, Service = Self> + Clone {
+        layer::mk(move |inner| Self {
+            inner,
+            cache: cache.clone(),
+            metrics: metrics.clone(),
+            extract: (),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T, L, X, ReqX, N> NewService<T> for NewCache<L, X, ReqX, N>
+where
+    T: Param<Option<Params>>,
+    L: Clone,
+    X: ExtractParam<ReqX, T>,
+    N: NewService<T>,
+{
+    type Service = Cache<L, ReqX, N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let params = target.param();
+        let extract = self.extract.extract_param(&target);
+        Cache {
+            inner: self.inner.new_service(target),
+            params,
+            cache: self.cache.clone(),
+            metrics: self.metrics.clone(),
+            extract,
+        }
+    }
+}
+
+// === impl MetricFamilies ===
+
+impl<L> Default for MetricFamilies<L>
+where
+    L: Clone + std::fmt::Debug + Hash + Eq + Send + Sync + prom::encoding::EncodeLabelSet + 'static,
+{
+    fn default() -> Self {
+        Self {
+            hits: prom::Family::default(),
+            misses: prom::Family::default(),
+            stale: prom::Family::default(),
+        }
+    }
+}
+
+impl<L> MetricFamilies<L>
+where
+    L: Clone + std::fmt::Debug + Hash + Eq + Send + Sync + prom::encoding::EncodeLabelSet + 'static,
+{
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let hits = prom::Family::default();
+        registry.register("hits", "Requests served from the cache", hits.clone());
+
+        let misses = prom::Family::default();
+        registry.register(
+            "misses",
+            "Cacheable requests without a stored response",
+            misses.clone(),
+        );
+
+        let stale = prom::Family::default();
+        registry.register(
+            "stale",
+            "Cacheable requests whose stored response had expired",
+            stale.clone(),
+        );
+
+        Self {
+            hits,
+            misses,
+            stale,
+        }
+    }
+
+    fn metrics(&self, labels: &L) -> Metrics {
+        Metrics {
+            hits: (*self.hits.get_or_create(labels)).clone(),
+            misses: (*self.misses.get_or_create(labels)).clone(),
+            stale: (*self.stale.get_or_create(labels)).clone(),
+        }
+    }
+}
+
+// === impl Cache ===
+
+impl<L, ReqX, S> Service<http::Request<BoxBody>> for Cache<L, ReqX, S>
+where
+    L: Clone + std::fmt::Debug + Hash + Eq + Send + Sync + prom::encoding::EncodeLabelSet + 'static,
+    ReqX: ExtractParam<L, http::Request<BoxBody>>,
+    S: Service<http::Request<BoxBody>, Response = http::Response<BoxBody>, Error = Error>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Error;
+    type Future = future::Either<
+        S::Future,
+        Pin<Box<dyn Future<Output = Result<http::Response<BoxBody>>> + Send + 'static>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        let Some(params) = self.params.as_ref() else {
+            return future::Either::Left(self.inner.call(req));
+        };
+
+        let is_head = req.method() == http::Method::HEAD;
+        if req.method() != http::Method::GET && !is_head {
+            trace!(method = %req.method(), "Request is not cacheable");
+            return future::Either::Left(self.inner.call(req));
+        }
+        if !params.allow_authorization && req.headers().contains_key(header::AUTHORIZATION) {
+            trace!("Bypassing the cache for an authorized request");
+            return future::Either::Left(self.inner.call(req));
+        }
+
+        let metrics = self.metrics.metrics(&self.extract.extract_param(&req));
+        let key = Key {
+            scope: params.scope,
+            uri: req.uri().clone(),
+        };
+        let now = Instant::now();
+        if policy::allows_lookup(req.headers()) {
+            match self.cache.lookup(&key, req.headers(), now) {
+                Lookup::Fresh(entry) => {
+                    debug!(uri = %key.uri, "Serving cached response");
+                    metrics.hits.inc();
+                    let rsp = respond(*entry, is_head, now);
+                    return future::Either::Right(Box::pin(future::ok(rsp)));
+                }
+                Lookup::Stale => {
+                    metrics.stale.inc();
+                }
+                Lookup::Miss => {
+                    metrics.misses.inc();
+                }
+            }
+        } else {
+            metrics.misses.inc();
+        }
+
+        // Only complete responses to GET requests are stored.
+        let store = (!is_head && policy::allows_store(req.headers()))
+            .then(|| (self.cache.clone(), key, req.headers().clone()));
+        let max_age = params.max_age;
+        let call = self.inner.call(req);
+        future::Either::Right(Box::pin(async move {
+            let rsp = call.await?;
+            let Some((cache, key, req_headers)) = store else {
+                return Ok(rsp);
+            };
+            let Some(storable) = policy::storable(rsp.status(), rsp.headers()) else {
+                trace!(status = %rsp.status(), "Response is not storable");
+                return Ok(rsp);
+            };
+
+            let max_bytes = cache.max_entry_bytes();
+            let (head, body) = rsp.into_parts();
+            let entry = Entry {
+                status: head.status,
+                headers: head.headers.clone(),
+                body: Bytes::new(),
+                stored_at: Instant::now(),
+                fresh_for: max_age.map_or(storable.fresh_for, |max| storable.fresh_for.min(max)),
+                vary_values: policy::vary_values(&storable.vary, &req_headers),
+                vary: storable.vary,
+            };
+            let recording = Recording {
+                cache,
+                key,
+                entry,
+                max_bytes,
+                buf: BytesMut::new(),
+            };
+            let body = BoxBody::new(RecordBody::new(body, recording));
+            Ok(http::Response::from_parts(head, body))
+        }))
+    }
+}
+
+/// Builds a response from a stored entry, noting how long it has been stored.
+fn respond(entry: Entry, is_head: bool, now: Instant) -> http::Response<BoxBody> {
+    let Entry {
+        status,
+        mut headers,
+        body,
+        stored_at,
+        ..
+    } = entry;
+    let age = policy::age(&headers) + now.saturating_duration_since(stored_at);
+    headers.insert(header::AGE, HeaderValue::from(age.as_secs()));
+
+    let body = if is_head {
+        BoxBody::empty()
+    } else {
+        BoxBody::new(http_body_util::Full::new(body))
+    };
+    let mut rsp = http::Response::new(body);
+    *rsp.status_mut() = status;
+    *rsp.headers_mut() = headers;
+    rsp
+}
@@ -0,0 +1,230 @@
+//! Determines whether requests and responses may use the cache, following the
+//! subset of RFC 9111 that applies to a shared cache.
+
+use http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+
+/// The maximum number of headers that a response may vary on to be stored.
+pub(crate) const MAX_VARY_HEADERS: usize = 4;
+
+/// Describes how a storable response may be reused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Storable {
+    /// How long the response is fresh once stored.
+    pub(crate) fresh_for: Duration,
+
+    /// The request headers that select this response.
+    pub(crate) vary: Vec<HeaderName>,
+}
+
+#[derive(Debug, Default)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    private: bool,
+    max_age: Option<u64>,
+    s_maxage: Option<u64>,
+}
+
+/// Returns true if a lookup may be served from the cache for this request.
+///
+/// Requests with `no-cache` are still forwarded so that their responses may
+/// replace the stored response.
+pub(crate) fn allows_lookup(req: &HeaderMap) -> bool {
+    let cc = CacheControl::parse(req);
+    !cc.no_cache && !cc.no_store && !is_pragma_no_cache(req)
+}
+
+/// Returns true if the response to this request may be stored.
+pub(crate) fn allows_store(req: &HeaderMap) -> bool {
+    !CacheControl::parse(req).no_store
+}
+
+/// Returns how a response may be stored, if at all.
+///
+/// Only responses with a cacheable status and an explicit freshness lifetime
+/// are stored. Responses that vary on more than [`MAX_VARY_HEADERS`] headers,
+/// or on `*`, are not stored.
+pub(crate) fn storable(status: http::StatusCode, headers: &HeaderMap) -> Option<Storable> {
+    if !is_cacheable_status(status) || headers.contains_key(header::SET_COOKIE) {
+        return None;
+    }
+
+    let cc = CacheControl::parse(headers);
+    if cc.no_store || cc.no_cache || cc.private {
+        return None;
+    }
+    // A shared cache prefers `s-maxage` to `max-age`.
+    let max_age = Duration::from_secs(cc.s_maxage.or(cc.max_age)?);
+    let fresh_for = max_age.saturating_sub(age(headers));
+    if fresh_for.is_zero() {
+        return None;
+    }
+
+    let mut vary = Vec::new();
+    for value in headers.get_all(header::VARY) {
+        for name in value.to_str().ok()?.split(',') {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            if name == "*" {
+                return None;
+            }
+            let name = HeaderName::from_bytes(name.as_bytes()).ok()?;
+            if !vary.contains(&name) {
+                vary.push(name);
+            }
+        }
+    }
+    if vary.len() > MAX_VARY_HEADERS {
+        return None;
+    }
+
+    Some(Storable { fresh_for, vary })
+}
+
+/// Returns the `age` of a response, as reported by upstream caches.
+pub(crate) fn age(headers: &HeaderMap) -> Duration {
+    headers
+        .get(header::AGE)
+        .and_then(|v| v.to_str().ok()?.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_default()
+}
+
+/// Returns the values of the `vary` headers in a request.
+pub(crate) fn vary_values(vary: &[HeaderName], req: &HeaderMap) -> Vec<Option<HeaderValue>> {
+    vary.iter().map(|name| req.get(name).cloned()).collect()
+}
+
+/// Status codes that are cacheable by default, per RFC 9110 §15.1.
+fn is_cacheable_status(status: http::StatusCode) -> bool {
+    matches!(
+        status.as_u16(),
+        200 | 203 | 204 | 300 | 301 | 308 | 404 | 405 | 410 | 414 | 501
+    )
+}
+
+fn is_pragma_no_cache(req: &HeaderMap) -> bool {
+    !req.contains_key(header::CACHE_CONTROL)
+        && req
+            .get_all(header::PRAGMA)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .any(|v| {
+                v.split(',')
+                    .any(|d| d.trim().eq_ignore_ascii_case("no-cache"))
+            })
+}
+
+// === impl CacheControl ===
+
+impl CacheControl {
+    fn parse(headers: &HeaderMap) -> Self {
+        let mut cc = Self::default();
+        let directives = headers
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','));
+        for directive in directives {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            let seconds = || value.and_then(|v| v.parse::<u64>().ok());
+            if name.eq_ignore_ascii_case("no-store") {
+                cc.no_store = true;
+            } else if name.eq_ignore_ascii_case("no-cache") {
+                cc.no_cache = true;
+            } else if name.eq_ignore_ascii_case("private") {
+                cc.private = true;
+            } else if name.eq_ignore_ascii_case("max-age") {
+                cc.max_age = seconds();
+            } else if name.eq_ignore_ascii_case("s-maxage") {
+                cc.s_maxage = seconds();
+            }
+        }
+        cc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(k, v)| (HeaderName::from_static(k), HeaderValue::from_static(v)))
+            .collect()
+    }
+
+    #[test]
+    fn freshness() {
+        let ok = http::StatusCode::OK;
+        let fresh = |h| storable(ok, &headers(h)).map(|s| s.fresh_for.as_secs());
+
+        assert_eq!(fresh(&[("cache-control", "max-age=60")]), Some(60));
+        assert_eq!(
+            fresh(&[("cache-control", "public, max-age=60, s-maxage=10")]),
+            Some(10)
+        );
+        assert_eq!(
+            fresh(&[("cache-control", "max-age=60"), ("age", "15")]),
+            Some(45)
+        );
+        assert_eq!(fresh(&[("cache-control", "max-age=\"30\"")]), Some(30));
+        assert_eq!(fresh(&[]), None, "freshness must be explicit");
+        assert_eq!(fresh(&[("cache-control", "max-age=0")]), None);
+        assert_eq!(
+            fresh(&[("cache-control", "max-age=10"), ("age", "20")]),
+            None
+        );
+        assert_eq!(fresh(&[("cache-control", "max-age=60, no-store")]), None);
+        assert_eq!(fresh(&[("cache-control", "private, max-age=60")]), None);
+        assert_eq!(fresh(&[("cache-control", "no-cache, max-age=60")]), None);
+        assert_eq!(
+            fresh(&[("cache-control", "max-age=60"), ("set-cookie", "a=b")]),
+            None
+        );
+
+        let cc = headers(&[("cache-control", "max-age=60")]);
+        assert!(storable(http::StatusCode::NOT_FOUND, &cc).is_some());
+        assert!(storable(http::StatusCode::INTERNAL_SERVER_ERROR, &cc).is_none());
+        assert!(storable(http::StatusCode::FOUND, &cc).is_none());
+    }
+
+    #[test]
+    fn vary() {
+        let vary = |v| {
+            storable(
+                http::StatusCode::OK,
+                &headers(&[("cache-control", "max-age=60"), ("vary", v)]),
+            )
+            .map(|s| s.vary)
+        };
+        assert_eq!(
+            vary("Accept-Encoding, accept-encoding,x-tenant"),
+            Some(vec![
+                header::ACCEPT_ENCODING,
+                HeaderName::from_static("x-tenant")
+            ])
+        );
+        assert_eq!(vary("*"), None);
+        assert_eq!(vary("a, b, c, d, e"), None, "vary must be bounded");
+    }
+
+    #[test]
+    fn requests() {
+        assert!(allows_lookup(&headers(&[])));
+        assert!(allows_lookup(&headers(&[("cache-control", "max-age=10")])));
+        assert!(!allows_lookup(&headers(&[("cache-control", "no-cache")])));
+        assert!(!allows_lookup(&headers(&[("pragma", "no-cache")])));
+        assert!(!allows_lookup(&headers(&[("cache-control", "no-store")])));
+
+        assert!(allows_store(&headers(&[("cache-control", "no-cache")])));
+        assert!(!allows_store(&headers(&[("cache-control", "no-store")])));
+    }
+}
@@ -0,0 +1,245 @@
+use bytes::Bytes;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Responses are not stored if they would use more than this fraction of the
+/// cache's budget, so that a single response can't evict every other entry.
+const MAX_ENTRY_FRACTION: usize = 16;
+
+/// A response cache shared by all cached routes, bounded by a memory budget.
+///
+/// When storing a response would exceed the budget, the least recently used
+/// responses are evicted.
+#[derive(Clone, Debug, Default)]
+pub struct ResponseCache(Option<Arc<Mutex<Store>>>);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Key {
+    pub(crate) scope: u64,
+    pub(crate) uri: http::Uri,
+}
+
+/// A stored response.
+#[derive(Clone, Debug)]
+pub(crate) struct Entry {
+    pub(crate) status: http::StatusCode,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Bytes,
+    pub(crate) stored_at: Instant,
+    pub(crate) fresh_for: Duration,
+    pub(crate) vary: Vec<HeaderName>,
+    pub(crate) vary_values: Vec<Option<HeaderValue>>,
+}
+
+/// The result of a cache lookup.
+#[derive(Debug)]
+pub(crate) enum Lookup {
+    Fresh(Box<Entry>),
+    Stale,
+    Miss,
+}
+
+#[derive(Debug)]
+struct Store {
+    max_bytes: usize,
+    used_bytes: usize,
+    entries: HashMap<Key, Stored>,
+    /// Orders keys from least to most recently used.
+    lru: BTreeMap<u64, Key>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct Stored {
+    entry: Entry,
+    size: usize,
+    tick: u64,
+}
+
+// === impl ResponseCache ===
+
+impl ResponseCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self(Some(Arc::new(Mutex::new(Store {
+            max_bytes,
+            used_bytes: 0,
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+            tick: 0,
+        }))))
+    }
+
+    /// Returns the number of bytes used by stored responses.
+    pub fn used_bytes(&self) -> usize {
+        self.0.as_ref().map_or(0, |s| s.lock().used_bytes)
+    }
+
+    /// Returns the largest response body that may be stored.
+    pub(crate) fn max_entry_bytes(&self) -> usize {
+        self.0
+            .as_ref()
+            .map_or(0, |s| s.lock().max_bytes / MAX_ENTRY_FRACTION)
+    }
+
+    pub(crate) fn lookup(&self, key: &Key, req: &HeaderMap, now: Instant) -> Lookup {
+        let Some(store) = self.0.as_ref() else {
+            return Lookup::Miss;
+        };
+        let mut store = store.lock();
+        let Some(stored) = store.entries.get(key) else {
+            return Lookup::Miss;
+        };
+        if crate::policy::vary_values(&stored.entry.vary, req) != stored.entry.vary_values {
+            return Lookup::Miss;
+        }
+        if now.saturating_duration_since(stored.entry.stored_at) >= stored.entry.fresh_for {
+            return Lookup::Stale;
+        }
+
+        let entry = Box::new(stored.entry.clone());
+        store.touch(key);
+        Lookup::Fresh(entry)
+    }
+
+    pub(crate) fn insert(&self, key: Key, entry: Entry) {
+        let Some(store) = self.0.as_ref() else {
+            return;
+        };
+        let size = entry.size();
+        let mut store = store.lock();
+        if size > store.max_bytes / MAX_ENTRY_FRACTION {
+            tracing::debug!(size, "Response is too large to be cached");
+            return;
+        }
+
+        store.remove(&key);
+        while store.used_bytes + size > store.max_bytes {
+            let Some((_, lru)) = store.lru.pop_first() else {
+                break;
+            };
+            tracing::debug!(uri = %lru.uri, "Evicting cached response");
+            store.remove(&lru);
+        }
+
+        store.tick += 1;
+        let tick = store.tick;
+        store.lru.insert(tick, key.clone());
+        store.used_bytes += size;
+        store.entries.insert(key, Stored { entry, size, tick });
+    }
+}
+
+// === impl Store ===
+
+impl Store {
+    fn touch(&mut self, key: &Key) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(stored) = self.entries.get_mut(key) {
+            self.lru.remove(&stored.tick);
+            stored.tick = tick;
+            self.lru.insert(tick, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(stored) = self.entries.remove(key) {
+            self.lru.remove(&stored.tick);
+            self.used_bytes -= stored.size;
+        }
+    }
+}
+
+// === impl Entry ===
+
+impl Entry {
+    /// Approximates the memory used by the entry.
+    fn size(&self) -> usize {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(k, v)| k.as_str().len() + v.len())
+            .sum::<usize>();
+        self.body.len() + headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(path: &str) -> Key {
+        Key {
+            scope: 1,
+            uri: path.parse().unwrap(),
+        }
+    }
+
+    fn entry(len: usize, now: Instant) -> Entry {
+        Entry {
+            status: http::StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from(vec![0; len]),
+            stored_at: now,
+            fresh_for: Duration::from_secs(10),
+            vary: vec![],
+            vary_values: vec![],
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn evicts_least_recently_used() {
+        let now = Instant::now();
+        let hdrs = HeaderMap::new();
+        let is_fresh = |cache: &ResponseCache, path: &str| {
+            matches!(cache.lookup(&key(path), &hdrs, now), Lookup::Fresh(_))
+        };
+
+        // Entries may use up to 30 bytes of the 480 byte budget.
+        let cache = ResponseCache::new(16 * 30);
+        for i in 0..16 {
+            cache.insert(key(&format!("/{i}")), entry(30, now));
+        }
+        assert_eq!(cache.used_bytes(), 16 * 30);
+
+        // Touch /0 so that /1 is the least recently used entry.
+        assert!(is_fresh(&cache, "/0"));
+        cache.insert(key("/16"), entry(30, now));
+        assert_eq!(cache.used_bytes(), 16 * 30);
+        assert!(!is_fresh(&cache, "/1"));
+        assert!(is_fresh(&cache, "/0"));
+        assert!(is_fresh(&cache, "/16"));
+
+        // Replacing an entry releases its bytes.
+        cache.insert(key("/16"), entry(10, now));
+        assert_eq!(cache.used_bytes(), 15 * 30 + 10);
+
+        // Entries larger than a fraction of the budget are not stored.
+        cache.insert(key("/big"), entry(31, now));
+        assert!(!is_fresh(&cache, "/big"));
+        assert!(is_fresh(&cache, "/2"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expires() {
+        let now = Instant::now();
+        let cache = ResponseCache::new(1024);
+        cache.insert(key("/a"), entry(1, now));
+
+        let hdrs = HeaderMap::new();
+        assert!(matches!(
+            cache.lookup(&key("/a"), &hdrs, now + Duration::from_secs(9)),
+            Lookup::Fresh(_)
+        ));
+        assert!(matches!(
+            cache.lookup(&key("/a"), &hdrs, now + Duration::from_secs(10)),
+            Lookup::Stale
+        ));
+    }
+}
@@ -0,0 +1,308 @@
+use super::*;
+use futures::FutureExt;
+use http_body_util::BodyExt;
+use std::time::Duration;
+use tower::ServiceExt;
+
+type Labels = Vec<(String, String)>;
+type Handle = tower_test::mock::Handle<http::Request<BoxBody>, http::Response<BoxBody>>;
+
+#[derive(Clone, Debug)]
+struct ExtractLabels;
+
+impl ExtractParam<Labels, http::Request<BoxBody>> for ExtractLabels {
+    fn extract_param(&self, _: &http::Request<BoxBody>) -> Labels {
+        vec![("route".to_string(), "test".to_string())]
+    }
+}
+
+struct Test {
+    svc: Cache<
+        Labels,
+        ExtractLabels,
+        tower_test::mock::Mock<http::Request<BoxBody>, http::Response<BoxBody>>,
+    >,
+    handle: Handle,
+    metrics: MetricFamilies<Labels>,
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn serves_fresh_responses() {
+    let mut test = Test::new(false);
+
+    // The first request is a miss, and its response is stored.
+    assert_eq!(
+        test.send(get("/config"), || ok("max-age=60", "v1")).await,
+        "v1"
+    );
+    assert_eq!(test.counts(), (0, 1, 0));
+
+    // Subsequent requests are served from the cache.
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    let rsp = test.cached(get("/config")).await;
+    assert_eq!(rsp.headers()[header::AGE], "5");
+    assert_eq!(body(rsp).await, "v1");
+    let rsp = test.cached(head("/config")).await;
+    assert_eq!(body(rsp).await, "");
+    assert_eq!(test.counts(), (2, 1, 0));
+
+    // Other URIs are not served from the cache.
+    test.send(get("/other"), || ok("max-age=60", "other")).await;
+    assert_eq!(test.counts(), (2, 2, 0));
+
+    // Once the response expires, requests are forwarded and the stored
+    // response is replaced.
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    assert_eq!(
+        test.send(get("/config"), || ok("s-maxage=10, max-age=60", "v2"))
+            .await,
+        "v2"
+    );
+    assert_eq!(test.counts(), (2, 2, 1));
+    let rsp = test.cached(get("/config")).await;
+    assert_eq!(body(rsp).await, "v2");
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    test.send(get("/config"), || ok("max-age=60", "v3")).await;
+    assert_eq!(test.counts(), (3, 2, 2));
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn bypasses_uncacheable_requests() {
+    let mut test = Test::new(false);
+
+    // Uncacheable methods and authorized requests aren't counted.
+    for req in [
+        http::Request::post("/config")
+            .body(BoxBody::empty())
+            .unwrap(),
+        http::Request::get("/config")
+            .header(header::AUTHORIZATION, "Bearer foo")
+            .body(BoxBody::empty())
+            .unwrap(),
+    ] {
+        test.send(req, || ok("max-age=60", "v1")).await;
+    }
+    assert_eq!(test.counts(), (0, 0, 0));
+
+    // Neither are their responses stored.
+    test.send(get("/config"), || ok("max-age=60", "v1")).await;
+    assert_eq!(test.counts(), (0, 1, 0));
+
+    // Requests may refuse cached responses, but their responses are stored.
+    let no_cache = || {
+        http::Request::get("/config")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(BoxBody::empty())
+            .unwrap()
+    };
+    test.send(no_cache(), || ok("max-age=60", "v2")).await;
+    let rsp = test.cached(get("/config")).await;
+    assert_eq!(body(rsp).await, "v2");
+
+    // Responses to requests that forbid storage are not stored.
+    let no_store = http::Request::get("/no-store")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(BoxBody::empty())
+        .unwrap();
+    test.send(no_store, || ok("max-age=60", "v1")).await;
+    test.send(get("/no-store"), || ok("no-store", "v1")).await;
+    test.send(get("/no-store"), || ok("max-age=60", "v1")).await;
+    test.cached(get("/no-store")).await;
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn allows_authorization() {
+    let mut test = Test::new(true);
+    let authorized = || {
+        http::Request::get("/config")
+            .header(header::AUTHORIZATION, "Bearer foo")
+            .body(BoxBody::empty())
+            .unwrap()
+    };
+    test.send(authorized(), || ok("max-age=60", "v1")).await;
+    let rsp = test.cached(authorized()).await;
+    assert_eq!(body(rsp).await, "v1");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn bounds_max_age() {
+    let mut test = Test::with_params(Params {
+        scope: 1,
+        allow_authorization: false,
+        max_age: Some(Duration::from_secs(10)),
+    });
+
+    // Responses are stored for no longer than the configured max age, even
+    // if they are fresh for longer.
+    test.send(get("/config"), || ok("max-age=60", "v1")).await;
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    let rsp = test.cached(get("/config")).await;
+    assert_eq!(body(rsp).await, "v1");
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    test.send(get("/config"), || ok("max-age=60", "v2")).await;
+    assert_eq!(test.counts(), (1, 1, 1));
+
+    // Responses that are fresh for less time are unaffected.
+    test.send(get("/short"), || ok("max-age=2", "v1")).await;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    test.send(get("/short"), || ok("max-age=2", "v2")).await;
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn varies() {
+    let mut test = Test::new(false);
+    let tenant = |t: &'static str| {
+        http::Request::get("/config")
+            .header("x-tenant", t)
+            .body(BoxBody::empty())
+            .unwrap()
+    };
+    let varied = |body: &'static str| {
+        move || {
+            http::Response::builder()
+                .header(header::CACHE_CONTROL, "max-age=60")
+                .header(header::VARY, "x-tenant")
+                .body(BoxBody::from_static(body))
+                .unwrap()
+        }
+    };
+
+    test.send(tenant("a"), varied("a")).await;
+    let rsp = test.cached(tenant("a")).await;
+    assert_eq!(body(rsp).await, "a");
+
+    // A request with a different tenant is forwarded.
+    assert_eq!(test.send(tenant("b"), varied("b")).await, "b");
+    let rsp = test.cached(tenant("b")).await;
+    assert_eq!(body(rsp).await, "b");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn does_not_store_incomplete_responses() {
+    let mut test = Test::new(false);
+
+    // Bodies that exceed the entry limit are streamed but not stored.
+    let large = "x".repeat(1024 / 16 + 1);
+    assert_eq!(
+        test.send(get("/large"), || {
+            http::Response::builder()
+                .header(header::CACHE_CONTROL, "max-age=60")
+                .body(BoxBody::new(large.clone()))
+                .unwrap()
+        })
+        .await,
+        large
+    );
+    test.send(get("/large"), || ok("max-age=60", "small")).await;
+    test.cached(get("/large")).await;
+
+    // Bodies that aren't read to completion aren't stored.
+    let rsp = test
+        .send_rsp(get("/dropped"), || ok("max-age=60", "v1"))
+        .await;
+    drop(rsp);
+    test.send(get("/dropped"), || ok("max-age=60", "v1")).await;
+}
+
+// === Utils ===
+
+impl Test {
+    fn new(allow_authorization: bool) -> Self {
+        Self::with_params(Params {
+            scope: 1,
+            allow_authorization,
+            max_age: None,
+        })
+    }
+
+    fn with_params(params: Params) -> Self {
+        let (mock, handle) = tower_test::mock::pair();
+        let metrics = MetricFamilies::<Labels>::default();
+        let svc = Cache {
+            inner: mock,
+            params: Some(params),
+            cache: ResponseCache::new(1024),
+            metrics: metrics.clone(),
+            extract: ExtractLabels,
+        };
+        Self {
+            svc,
+            handle,
+            metrics,
+        }
+    }
+
+    /// Sends a request that must be forwarded to the inner service, returning
+    /// the response body.
+    async fn send(
+        &mut self,
+        req: http::Request<BoxBody>,
+        rsp: impl FnOnce() -> http::Response<BoxBody>,
+    ) -> String {
+        let rsp = self.send_rsp(req, rsp).await;
+        body(rsp).await
+    }
+
+    /// Sends a request that must be forwarded to the inner service.
+    async fn send_rsp(
+        &mut self,
+        req: http::Request<BoxBody>,
+        rsp: impl FnOnce() -> http::Response<BoxBody>,
+    ) -> http::Response<BoxBody> {
+        self.handle.allow(1);
+        let call = self.svc.ready().await.unwrap().call(req);
+        let (_, tx) = self
+            .handle
+            .next_request()
+            .await
+            .expect("request must be forwarded");
+        tx.send_response(rsp());
+        call.await.expect("response must succeed")
+    }
+
+    /// Sends a request that must be served from the cache.
+    async fn cached(&mut self, req: http::Request<BoxBody>) -> http::Response<BoxBody> {
+        self.handle.allow(1);
+        let rsp = self
+            .svc
+            .ready()
+            .await
+            .unwrap()
+            .call(req)
+            .await
+            .expect("response must succeed");
+        assert!(
+            self.handle.next_request().now_or_never().is_none(),
+            "request must not be forwarded"
+        );
+        assert!(rsp.headers().contains_key(header::AGE));
+        rsp
+    }
+
+    /// Returns the hit, miss, and stale counts.
+    fn counts(&self) -> (u64, u64, u64) {
+        let labels = vec![("route".to_string(), "test".to_string())];
+        let m = self.metrics.metrics(&labels);
+        (m.hits.get(), m.misses.get(), m.stale.get())
+    }
+}
+
+fn get(path: &'static str) -> http::Request<BoxBody> {
+    http::Request::get(path).body(BoxBody::empty()).unwrap()
+}
+
+fn head(path: &'static str) -> http::Request<BoxBody> {
+    http::Request::head(path).body(BoxBody::empty()).unwrap()
+}
+
+fn ok(cache_control: &'static str, body: &'static str) -> http::Response<BoxBody> {
+    http::Response::builder()
+        .header(header::CACHE_CONTROL, cache_control)
+        .body(BoxBody::from_static(body))
+        .unwrap()
+}
+
+async fn body(rsp: http::Response<BoxBody>) -> String {
+    let bytes = rsp.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
@@ -1,6 +1,9 @@
 use crate::{channel::BroadcastClassification, ClassifyResponse};
-use linkerd_stack::{gate, layer, ExtractParam, Gate, NewService};
-use std::marker::PhantomData;
+use linkerd_stack::{gate, layer, ExtractParam, Gate, NewService, Service};
+use std::{
+    marker::PhantomData,
+    task::{Context, Poll},
+};
 use tokio::sync::mpsc;
 
 pub use linkerd_stack::gate::{Rx, State, Tx};
@@ -49,6 +52,22 @@ pub struct NewClassifyGate<C, X, N> {
     _marker: PhantomData<fn() -> C>,
 }
 
+/// Marks requests that are admitted while a [`Gate`] is limited with a
+/// [`Probe`] extension.
+///
+/// A limited gate admits a bounded number of requests to probe whether an
+/// inner service has recovered (i.e. a half-open circuit breaker).
+#[derive(Clone, Debug)]
+pub struct MarkProbes<S> {
+    gate: gate::Rx,
+    inner: S,
+}
+
+/// A request extension indicating that the request was admitted by a limited
+/// gate to probe an inner service.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Probe(());
+
 // === impl NewClassifyGateSet ===
 
 impl<C, P, X: Clone, N> NewClassifyGateSet<C, P, X, N> {
@@ -124,12 +143,16 @@ where
     X: ExtractParam<Params<C::Class>, T>,
     N: NewService<T>,
 {
-    type Service = Gate<BroadcastClassification<C, N::Service>>;
+    type Service = Gate<MarkProbes<BroadcastClassification<C, N::Service>>>;
 
     fn new_service(&self, target: T) -> Self::Service {
         let Params { responses, gate } = self.extract.extract_param(&target);
         let inner = self.inner.new_service(target);
-        Gate::new(gate, BroadcastClassification::new(responses, inner))
+        let inner = MarkProbes {
+            gate: gate.clone(),
+            inner: BroadcastClassification::new(responses, inner),
+        };
+        Gate::new(gate, inner)
     }
 }
 
@@ -143,6 +166,31 @@ impl<C, X: Clone, N: Clone> Clone for NewClassifyGate<C, X, N> {
     }
 }
 
+// === impl MarkProbes ===
+
+impl<B, S> Service<http::Request<B>> for MarkProbes<S>
+where
+    S: Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        // Only requests that acquired a permit are admitted while the gate is
+        // limited.
+        if self.gate.is_limited() {
+            req.extensions_mut().insert(Probe(()));
+        }
+        self.inner.call(req)
+    }
+}
+
 // === impl Params ===
 
 impl<C> Params<C> {
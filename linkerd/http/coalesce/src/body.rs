@@ -0,0 +1,168 @@
+use crate::flight::{Leader, Shared};
+use bytes::{Buf, Bytes, BytesMut};
+use http::HeaderMap;
+use http_body::{Body, Frame};
+use linkerd_error::{Error, Result};
+use linkerd_http_box::BoxBody;
+use linkerd_metrics::prom;
+use pin_project::pin_project;
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+/// Buffers the leading request's response as it is streamed, so that it may
+/// be shared with waiting requests once it is complete.
+///
+/// When dropped before the response is complete, e.g. because the body failed
+/// or was too large to buffer, waiting requests are released.
+pub(crate) struct Share {
+    leader: Leader,
+    status: http::StatusCode,
+    version: http::Version,
+    headers: HeaderMap,
+    data: BytesMut,
+    max_bytes: usize,
+    fanout: prom::Histogram,
+}
+
+/// A body that streams the leading request's response while buffering it to
+/// be shared.
+#[pin_project]
+struct Tee {
+    #[pin]
+    inner: BoxBody,
+    share: Option<Share>,
+}
+
+/// Streams `body` to the leading request, sharing it with waiting requests
+/// once it completes.
+pub(crate) fn tee(body: BoxBody, share: Share) -> BoxBody {
+    // Bodies that are already complete (e.g. in response to HEAD requests)
+    // may never be polled.
+    if body.is_end_stream() {
+        share.complete(None);
+        return body;
+    }
+    BoxBody::new(Tee {
+        inner: body,
+        share: Some(share),
+    })
+}
+
+/// Builds a body from buffered data and trailers.
+pub(crate) fn replay(body: Bytes, trailers: Option<HeaderMap>) -> BoxBody {
+    let Some(trailers) = trailers else {
+        return BoxBody::new(http_body_util::Full::new(body));
+    };
+    let frames = [Frame::data(body), Frame::trailers(trailers)]
+        .into_iter()
+        .filter(|f| !f.data_ref().is_some_and(Bytes::is_empty))
+        .map(Ok::<_, Infallible>);
+    BoxBody::new(http_body_util::StreamBody::new(futures::stream::iter(
+        frames,
+    )))
+}
+
+// === impl Share ===
+
+impl Share {
+    pub(crate) fn new(
+        leader: Leader,
+        parts: &http::response::Parts,
+        max_bytes: usize,
+        fanout: prom::Histogram,
+    ) -> Self {
+        Self {
+            leader,
+            status: parts.status,
+            version: parts.version,
+            headers: parts.headers.clone(),
+            data: BytesMut::new(),
+            max_bytes,
+            fanout,
+        }
+    }
+
+    /// Buffers data, returning false if the body is too large to share.
+    fn push(&mut self, data: &Bytes) -> bool {
+        if self.data.len() + data.len() > self.max_bytes {
+            return false;
+        }
+        self.data.extend_from_slice(data);
+        true
+    }
+
+    fn complete(self, trailers: Option<HeaderMap>) {
+        let shared = Arc::new(Shared {
+            status: self.status,
+            version: self.version,
+            headers: self.headers,
+            body: self.data.freeze(),
+            trailers,
+        });
+        let waiters = self.leader.share(shared);
+        self.fanout.observe((waiters + 1) as f64);
+    }
+}
+
+// === impl Tee ===
+
+impl Body for Tee {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = ready!(this.inner.poll_frame(cx))
+            .map(|res| res.map(|f| f.map_data(|mut d| d.copy_to_bytes(d.remaining()))));
+
+        match frame.as_ref() {
+            None => {
+                if let Some(share) = this.share.take() {
+                    share.complete(None);
+                }
+            }
+            Some(Ok(frame)) => {
+                if let Some(data) = frame.data_ref() {
+                    if let Some(share) = this.share.as_mut() {
+                        if !share.push(data) {
+                            tracing::debug!("Response body is too large to be shared");
+                            *this.share = None;
+                        }
+                    }
+                    if this.inner.is_end_stream() {
+                        if let Some(share) = this.share.take() {
+                            share.complete(None);
+                        }
+                    }
+                } else if let Some(trailers) = frame.trailers_ref() {
+                    if let Some(share) = this.share.take() {
+                        share.complete(Some(trailers.clone()));
+                    }
+                }
+            }
+            Some(Err(error)) => {
+                tracing::debug!(%error, "Response body failed; it will not be shared");
+                *this.share = None;
+            }
+        }
+
+        Poll::Ready(frame)
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
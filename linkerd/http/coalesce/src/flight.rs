@@ -0,0 +1,196 @@
+use bytes::Bytes;
+use http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{
+    sync::watch,
+    time::{self, Instant},
+};
+
+/// Tracks the coalescable requests that are in flight, shared by all
+/// coalescing routes.
+#[derive(Clone, Debug, Default)]
+pub struct Coalescer(Arc<Mutex<HashMap<Key, Flight>>>);
+
+/// Identifies requests that may share a response.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Key {
+    scope: u64,
+    method: http::Method,
+    authority: Option<http::uri::Authority>,
+    path: Option<http::uri::PathAndQuery>,
+    headers: Vec<Option<HeaderValue>>,
+}
+
+/// A buffered response that is shared with each waiting request.
+#[derive(Debug)]
+pub(crate) struct Shared {
+    pub(crate) status: http::StatusCode,
+    pub(crate) version: http::Version,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Bytes,
+    pub(crate) trailers: Option<HeaderMap>,
+}
+
+/// The result of joining the requests in flight for a key.
+#[derive(Debug)]
+pub(crate) enum Join {
+    /// No identical request is in flight, so the request must be forwarded
+    /// and its response shared.
+    Lead(Leader),
+    /// An identical request is in flight.
+    Wait(Waiter),
+    /// An identical request is in flight, but too many requests are already
+    /// waiting for it.
+    Full,
+}
+
+/// Shares a response with the requests that wait for it.
+///
+/// When the leader is dropped without sharing a response, e.g. because the
+/// request failed, waiting requests are released.
+#[derive(Debug)]
+pub(crate) struct Leader {
+    coalescer: Coalescer,
+    key: Key,
+    tx: Arc<watch::Sender<Option<Arc<Shared>>>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Waiter {
+    rx: watch::Receiver<Option<Arc<Shared>>>,
+    deadline: Instant,
+}
+
+/// Why a waiting request did not receive a shared response.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Released {
+    /// The response was not available before the flight's deadline.
+    Timeout,
+    /// The in-flight request did not produce a shareable response.
+    Unshared,
+}
+
+#[derive(Debug)]
+struct Flight {
+    tx: Arc<watch::Sender<Option<Arc<Shared>>>>,
+    /// Requests may only wait for the flight until this deadline.
+    deadline: Instant,
+}
+
+// === impl Coalescer ===
+
+impl Coalescer {
+    /// Joins the flight for `key`, starting a new flight if no identical
+    /// request is in flight or if the in-flight request's deadline has passed.
+    pub(crate) fn join(&self, key: Key, max_waiters: usize, timeout: Duration) -> Join {
+        let now = Instant::now();
+        let mut flights = self.0.lock();
+        if let Some(flight) = flights.get(&key) {
+            if flight.deadline > now {
+                if flight.tx.receiver_count() >= max_waiters {
+                    return Join::Full;
+                }
+                return Join::Wait(Waiter {
+                    rx: flight.tx.subscribe(),
+                    deadline: flight.deadline,
+                });
+            }
+        }
+
+        let (tx, _) = watch::channel(None);
+        let tx = Arc::new(tx);
+        flights.insert(
+            key.clone(),
+            Flight {
+                tx: tx.clone(),
+                deadline: now + timeout,
+            },
+        );
+        Join::Lead(Leader {
+            coalescer: self.clone(),
+            key,
+            tx,
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn in_flight(&self) -> usize {
+        self.0.lock().len()
+    }
+}
+
+// === impl Key ===
+
+impl Key {
+    pub(crate) fn new<B>(scope: u64, headers: &[HeaderName], req: &http::Request<B>) -> Self {
+        let authority = req.uri().authority().cloned().or_else(|| {
+            let host = req.headers().get(header::HOST)?;
+            host.to_str().ok()?.parse().ok()
+        });
+        Self {
+            scope,
+            method: req.method().clone(),
+            authority,
+            path: req.uri().path_and_query().cloned(),
+            headers: headers
+                .iter()
+                .map(|h| req.headers().get(h).cloned())
+                .collect(),
+        }
+    }
+}
+
+// === impl Leader ===
+
+impl Leader {
+    /// Shares a response with all waiting requests, returning the number of
+    /// requests that were waiting for it.
+    pub(crate) fn share(self, shared: Arc<Shared>) -> usize {
+        let waiters = self.tx.receiver_count();
+        self.tx.send_replace(Some(shared));
+        waiters
+    }
+}
+
+impl Drop for Leader {
+    fn drop(&mut self) {
+        let mut flights = self.coalescer.0.lock();
+        // The flight may have been replaced after its deadline passed.
+        if flights
+            .get(&self.key)
+            .is_some_and(|f| Arc::ptr_eq(&f.tx, &self.tx))
+        {
+            flights.remove(&self.key);
+        }
+    }
+}
+
+// === impl Waiter ===
+
+impl Waiter {
+    /// Waits for the in-flight request's response until the flight's deadline.
+    pub(crate) async fn wait(mut self) -> Result<Arc<Shared>, Released> {
+        let deadline = self.deadline;
+        match time::timeout_at(deadline, self.rx.wait_for(Option::is_some)).await {
+            Ok(Ok(shared)) => Ok(shared.clone().expect("response must be set")),
+            Ok(Err(_)) => Err(Released::Unshared),
+            Err(_) => Err(Released::Timeout),
+        }
+    }
+}
+
+// === impl Shared ===
+
+impl Shared {
+    pub(crate) fn to_response(&self) -> http::Response<linkerd_http_box::BoxBody> {
+        let mut rsp = http::Response::new(crate::body::replay(
+            self.body.clone(),
+            self.trailers.clone(),
+        ));
+        *rsp.status_mut() = self.status;
+        *rsp.version_mut() = self.version;
+        *rsp.headers_mut() = self.headers.clone();
+        rsp
+    }
+}
@@ -0,0 +1,348 @@
+//! Tower middleware to coalesce identical in-flight HTTP requests.
+//!
+//! When a request is identical to one that is already in flight--it has the
+//! same method, authority, path, and configured headers--it waits for the
+//! in-flight request's response rather than being forwarded. The response is
+//! streamed to the in-flight request and buffered as it is read, and each
+//! waiting request receives a copy of it once it is complete.
+//!
+//! Only `GET` and `HEAD` requests without a body, credentials, or cookies are
+//! coalesced. As with a shared cache, responses that set cookies, that are
+//! marked `private` or `no-store`, or that vary on headers other than those
+//! that distinguish coalesced requests are never shared.
+//!
+//! Requests wait for a bounded time after the in-flight request was sent; if
+//! its response is not available by then, or it can't be shared (e.g. because
+//! the request failed or its body is too large), waiting requests are
+//! forwarded individually.
+//!
+//! See [`Coalesce<L, ReqX, S>`].
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![forbid(unsafe_code)]
+
+use self::flight::{Join, Key, Released};
+use futures::future;
+use http::header::{self, HeaderMap, HeaderName};
+use linkerd_error::{Error, Result};
+use linkerd_http_box::BoxBody;
+use linkerd_metrics::prom;
+use linkerd_stack::{layer, ExtractParam, NewService, Param, Service, ServiceExt};
+use std::{
+    future::Future,
+    hash::Hash,
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tracing::{debug, trace};
+
+mod body;
+mod flight;
+#[cfg(test)]
+mod tests;
+
+pub use self::flight::Coalescer;
+
+/// The largest response body that is buffered to be shared with waiting
+/// requests.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Configures request coalescing for a target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Params {
+    /// Distinguishes the requests coalesced for different targets.
+    pub scope: u64,
+
+    /// Headers whose values must match for requests to be coalesced.
+    pub headers: Vec<http::HeaderName>,
+
+    /// The maximum number of requests that may wait for each in-flight
+    /// request. Further identical requests are forwarded.
+    pub max_waiters: usize,
+
+    /// How long requests may wait for an in-flight request, measured from
+    /// when the in-flight request was sent.
+    pub timeout: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewCoalesce<L: Clone, X, ReqX, N> {
+    inner: N,
+    coalescer: Coalescer,
+    metrics: MetricFamilies<L>,
+    extract: X,
+    _marker: PhantomData<fn() -> ReqX>,
+}
+
+/// Coalesces identical requests via a shared [`Coalescer`] when the target's
+/// [`Params`] enable coalescing.
+#[derive(Clone, Debug)]
+pub struct Coalesce<L: Clone, ReqX, S> {
+    inner: S,
+    params: Option<Arc<Params>>,
+    coalescer: Coalescer,
+    metrics: MetricFamilies<L>,
+    extract: ReqX,
+}
+
+#[derive(Clone, Debug)]
+pub struct MetricFamilies<L: Clone> {
+    coalesced: prom::Family<L, prom::Counter>,
+    timeouts: prom::Family<L, prom::Counter>,
+    overflows: prom::Family<L, prom::Counter>,
+    fanout: prom::Family<L, prom::Histogram, fn() -> prom::Histogram>,
+}
+
+#[derive(Clone, Debug)]
+struct Metrics {
+    coalesced: prom::Counter,
+    timeouts: prom::Counter,
+    overflows: prom::Counter,
+    fanout: prom::Histogram,
+}
+
+// === impl NewCoalesce ===
+
+impl<L: Clone, ReqX, N> NewCoalesce<L, (), ReqX, N> {
+    pub fn layer(
+        coalescer: Coalescer,
+        metrics: MetricFamilies<L>,
+    ) -> impl layer::Layer<N, Service = Self> + Clone {
+        layer::mk(move |inner| Self {
+            inner,
+            coalescer: coalescer.clone(),
+            metrics: metrics.clone(),
+            extract: (),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T, L, X, ReqX, N> NewService<T> for NewCoalesce<L, X, ReqX, N>
+where
+    T: Param<Option<Params>>,
+    L: Clone,
+    X: ExtractParam<ReqX, T>,
+    N: NewService<T>,
+{
+    type Service = Coalesce<L, ReqX, N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let params = target.param().map(Arc::new);
+        let extract = self.extract.extract_param(&target);
+        Coalesce {
+            inner: self.inner.new_service(target),
+            params,
+            coalescer: self.coalescer.clone(),
+            metrics: self.metrics.clone(),
+            extract,
+        }
+    }
+}
+
+// === impl MetricFamilies ===
+
+impl<L> Default for MetricFamilies<L>
+where
+    L: Clone + std::fmt::Debug + Hash + Eq + Send + Sync + prom::encoding::EncodeLabelSet + 'static,
+{
+    fn default() -> Self {
+        Self {
+            coalesced: prom::Family::default(),
+            timeouts: prom::Family::default(),
+            overflows: prom::Family::default(),
+            fanout: prom::Family::new_with_constructor(mk_fanout as fn() -> _),
+        }
+    }
+}
+
+impl<L> MetricFamilies<L>
+where
+    L: Clone + std::fmt::Debug + Hash + Eq + Send + Sync + prom::encoding::EncodeLabelSet + 'static,
+{
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let coalesced = prom::Family::default();
+        registry.register(
+            "coalesced",
+            "Requests served with the response to an identical in-flight request",
+            coalesced.clone(),
+        );
+
+        let timeouts = prom::Family::default();
+        registry.register(
+            "timeouts",
+            "Requests forwarded because an identical in-flight request did not respond in time",
+            timeouts.clone(),
+        );
+
+        let overflows = prom::Family::default();
+        registry.register(
+            "overflows",
+            "Requests forwarded because too many requests were waiting for an identical in-flight request",
+            overflows.clone(),
+        );
+
+        let fanout = prom::Family::new_with_constructor(mk_fanout as fn() -> _);
+        registry.register(
+            "fanout",
+            "The number of requests served by each shared response",
+            fanout.clone(),
+        );
+
+        Self {
+            coalesced,
+            timeouts,
+            overflows,
+            fanout,
+        }
+    }
+
+    fn metrics(&self, labels: &L) -> Metrics {
+        Metrics {
+            coalesced: (*self.coalesced.get_or_create(labels)).clone(),
+            timeouts: (*self.timeouts.get_or_create(labels)).clone(),
+            overflows: (*self.overflows.get_or_create(labels)).clone(),
+            fanout: (*self.fanout.get_or_create(labels)).clone(),
+        }
+    }
+}
+
+fn mk_fanout() -> prom::Histogram {
+    prom::Histogram::new([1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0])
+}
+
+// === impl Coalesce ===
+
+impl<L, ReqX, S> Service<http::Request<BoxBody>> for Coalesce<L, ReqX, S>
+where
+    L: Clone + std::fmt::Debug + Hash + Eq + Send + Sync + prom::encoding::EncodeLabelSet + 'static,
+    ReqX: ExtractParam<L, http::Request<BoxBody>>,
+    S: Service<http::Request<BoxBody>, Response = http::Response<BoxBody>, Error = Error>,
+    S: Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Error;
+    type Future = future::Either<
+        S::Future,
+        Pin<Box<dyn Future<Output = Result<http::Response<BoxBody>>> + Send + 'static>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        let Some(params) = self.params.as_ref() else {
+            return future::Either::Left(self.inner.call(req));
+        };
+        if !is_coalescable(&req) {
+            trace!(method = %req.method(), "Request is not coalescable");
+            return future::Either::Left(self.inner.call(req));
+        }
+
+        let metrics = self.metrics.metrics(&self.extract.extract_param(&req));
+        let key = Key::new(params.scope, &params.headers, &req);
+        match self.coalescer.join(key, params.max_waiters, params.timeout) {
+            Join::Lead(leader) => {
+                let call = self.inner.call(req);
+                let params = params.clone();
+                future::Either::Right(Box::pin(async move {
+                    let rsp = call.await?;
+                    if !is_shareable(rsp.headers(), &params.headers) {
+                        // Waiting requests are released when the leader is
+                        // dropped.
+                        debug!("Response may not be shared");
+                        drop(leader);
+                        return Ok(rsp);
+                    }
+
+                    let (parts, body) = rsp.into_parts();
+                    let share = body::Share::new(leader, &parts, MAX_BODY_BYTES, metrics.fanout);
+                    Ok(http::Response::from_parts(parts, body::tee(body, share)))
+                }))
+            }
+
+            Join::Wait(waiter) => {
+                trace!("Waiting for an identical in-flight request");
+                let inner = self.inner.clone();
+                future::Either::Right(Box::pin(async move {
+                    match waiter.wait().await {
+                        Ok(shared) => {
+                            metrics.coalesced.inc();
+                            Ok(shared.to_response())
+                        }
+                        Err(released) => {
+                            debug!(?released, "Forwarding a coalesced request");
+                            if released == Released::Timeout {
+                                metrics.timeouts.inc();
+                            }
+                            inner.oneshot(req).await
+                        }
+                    }
+                }))
+            }
+
+            Join::Full => {
+                debug!("Too many requests are waiting for an identical in-flight request");
+                metrics.overflows.inc();
+                future::Either::Left(self.inner.call(req))
+            }
+        }
+    }
+}
+
+/// Returns true if the request has no side effects, body, credentials, or
+/// cookies.
+fn is_coalescable(req: &http::Request<BoxBody>) -> bool {
+    use http_body::Body;
+
+    (req.method() == http::Method::GET || req.method() == http::Method::HEAD)
+        && req.body().is_end_stream()
+        && !req.headers().contains_key(header::AUTHORIZATION)
+        && !req.headers().contains_key(header::PROXY_AUTHORIZATION)
+        && !req.headers().contains_key(header::COOKIE)
+}
+
+/// Returns true if a response may be shared with other clients, following the
+/// rules that apply to a shared cache.
+///
+/// Responses that set cookies or that are marked `private` or `no-store` are
+/// specific to the client that requested them. Responses may only vary on the
+/// headers that distinguish coalesced requests, since waiting requests are
+/// otherwise free to differ.
+fn is_shareable(headers: &HeaderMap, coalesced: &[HeaderName]) -> bool {
+    if headers.contains_key(header::SET_COOKIE) {
+        return false;
+    }
+
+    for value in headers.get_all(header::CACHE_CONTROL) {
+        let Ok(value) = value.to_str() else {
+            return false;
+        };
+        for directive in value.split(',') {
+            let name = directive.split('=').next().unwrap_or_default().trim();
+            if name.eq_ignore_ascii_case("private") || name.eq_ignore_ascii_case("no-store") {
+                return false;
+            }
+        }
+    }
+
+    for value in headers.get_all(header::VARY) {
+        let Ok(value) = value.to_str() else {
+            return false;
+        };
+        for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            match HeaderName::from_bytes(name.as_bytes()) {
+                Ok(name) if coalesced.contains(&name) => {}
+                _ => return false,
+            }
+        }
+    }
+
+    true
+}
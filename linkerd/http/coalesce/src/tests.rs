@@ -0,0 +1,421 @@
+use super::*;
+use bytes::{Buf, Bytes};
+use http_body_util::BodyExt;
+use tokio::task::JoinHandle;
+
+type Labels = Vec<(String, String)>;
+type Handle = tower_test::mock::Handle<http::Request<BoxBody>, http::Response<BoxBody>>;
+
+#[derive(Clone, Debug)]
+struct ExtractLabels;
+
+impl ExtractParam<Labels, http::Request<BoxBody>> for ExtractLabels {
+    fn extract_param(&self, _: &http::Request<BoxBody>) -> Labels {
+        vec![("route".to_string(), "test".to_string())]
+    }
+}
+
+struct Test {
+    svc: Coalesce<
+        Labels,
+        ExtractLabels,
+        tower_test::mock::Mock<http::Request<BoxBody>, http::Response<BoxBody>>,
+    >,
+    handle: Handle,
+    coalescer: Coalescer,
+    metrics: MetricFamilies<Labels>,
+}
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn coalesces_identical_requests() {
+    let mut test = Test::new(10);
+
+    let calls = [
+        test.call(get("/config")).await,
+        test.call(get("/config")).await,
+        test.call(get("/config")).await,
+    ];
+
+    // Only the first request is forwarded.
+    let tx = test.next_request().await;
+    let mut rsp = ok("v1");
+    rsp.headers_mut()
+        .insert("x-version", http::HeaderValue::from_static("v1"));
+    tx.send_response(rsp);
+    for call in calls {
+        let rsp = call.await.unwrap().expect("response must succeed");
+        assert_eq!(rsp.headers()["x-version"], "v1");
+        assert_eq!(body(rsp).await, "v1");
+    }
+    test.assert_no_request();
+    assert_eq!(test.counts(), (2, 0, 0));
+    assert_eq!(test.coalescer.in_flight(), 0);
+
+    // Once the response has been shared, identical requests are forwarded.
+    let call = test.call(get("/config")).await;
+    let tx = test.next_request().await;
+    tx.send_response(ok("v2"));
+    assert_eq!(body(call.await.unwrap().unwrap()).await, "v2");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn shares_trailers() {
+    let mut test = Test::new(10);
+
+    let calls = [
+        test.call(get("/config")).await,
+        test.call(get("/config")).await,
+    ];
+    let tx = test.next_request().await;
+    let mut trailers = http::HeaderMap::new();
+    trailers.insert("grpc-status", "0".parse().unwrap());
+    tx.send_response(http::Response::new(body::replay(
+        "v1".into(),
+        Some(trailers.clone()),
+    )));
+    for call in calls {
+        let rsp = call.await.unwrap().expect("response must succeed");
+        let collected = rsp.into_body().collect().await.unwrap();
+        assert_eq!(collected.trailers(), Some(&trailers));
+        assert_eq!(collected.to_bytes(), "v1");
+    }
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn forwards_distinct_requests() {
+    let mut test = Test::new(10);
+
+    let mut json = get("/config");
+    json.headers_mut()
+        .insert(header::ACCEPT, "application/json".parse().unwrap());
+    let mut other_host = get("/config");
+    other_host
+        .headers_mut()
+        .insert(header::HOST, "other.example.com".parse().unwrap());
+    let calls = [
+        test.call(get("/config")).await,
+        test.call(get("/other")).await,
+        test.call(json).await,
+        test.call(other_host).await,
+        test.call(head("/config")).await,
+    ];
+
+    for _ in 0..calls.len() {
+        let tx = test.next_request().await;
+        tx.send_response(ok("v1"));
+    }
+    for call in calls {
+        call.await.unwrap().expect("response must succeed");
+    }
+    assert_eq!(test.counts(), (0, 0, 0));
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn does_not_coalesce_bodies_credentials_or_cookies() {
+    let mut test = Test::new(10);
+
+    let post = http::Request::post("/config")
+        .body(BoxBody::empty())
+        .unwrap();
+    let with_body = http::Request::get("/config")
+        .body(BoxBody::from_static("hello"))
+        .unwrap();
+    let authorized = http::Request::get("/config")
+        .header(header::AUTHORIZATION, "Bearer token")
+        .body(BoxBody::empty())
+        .unwrap();
+    let with_cookie = http::Request::get("/config")
+        .header(header::COOKIE, "session=abc")
+        .body(BoxBody::empty())
+        .unwrap();
+    let calls = [
+        test.call(get("/config")).await,
+        test.call(post).await,
+        test.call(with_body).await,
+        test.call(authorized).await,
+        test.call(with_cookie).await,
+    ];
+
+    for _ in 0..calls.len() {
+        let tx = test.next_request().await;
+        tx.send_response(ok("v1"));
+    }
+    for call in calls {
+        call.await.unwrap().expect("response must succeed");
+    }
+    assert_eq!(test.counts(), (0, 0, 0));
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn bounds_waiters() {
+    let mut test = Test::new(1);
+
+    let calls = [
+        test.call(get("/config")).await,
+        test.call(get("/config")).await,
+        test.call(get("/config")).await,
+    ];
+
+    // The third request is forwarded because the first already has a waiter.
+    for _ in 0..2 {
+        let tx = test.next_request().await;
+        tx.send_response(ok("v1"));
+    }
+    // The leader's response is shared once its body has been read.
+    for call in calls {
+        let rsp = call.await.unwrap().expect("response must succeed");
+        assert_eq!(body(rsp).await, "v1");
+    }
+    test.assert_no_request();
+    assert_eq!(test.counts(), (1, 0, 1));
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn forwards_waiters_after_timeout() {
+    let mut test = Test::new(10);
+
+    let leader = test.call(get("/config")).await;
+    let waiter = test.call(get("/config")).await;
+    let leader_tx = test.next_request().await;
+
+    // The waiter is forwarded once the timeout elapses.
+    let waiter_tx = test.next_request().await;
+    assert_eq!(test.counts(), (0, 1, 0));
+    waiter_tx.send_response(ok("waiter"));
+    assert_eq!(body(waiter.await.unwrap().unwrap()).await, "waiter");
+
+    // Requests made after the deadline start a new flight.
+    let next = test.call(get("/config")).await;
+    let next_tx = test.next_request().await;
+    leader_tx.send_response(ok("leader"));
+    assert_eq!(body(leader.await.unwrap().unwrap()).await, "leader");
+    assert_eq!(test.coalescer.in_flight(), 1);
+    next_tx.send_response(ok("next"));
+    assert_eq!(body(next.await.unwrap().unwrap()).await, "next");
+    assert_eq!(test.coalescer.in_flight(), 0);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn forwards_waiters_when_leader_fails() {
+    let mut test = Test::new(10);
+
+    let leader = test.call(get("/config")).await;
+    let waiter = test.call(get("/config")).await;
+    let tx = test.next_request().await;
+    tx.send_error("boom");
+    assert!(leader.await.unwrap().is_err(), "leader's request must fail");
+
+    let tx = test.next_request().await;
+    tx.send_response(ok("v1"));
+    assert_eq!(body(waiter.await.unwrap().unwrap()).await, "v1");
+    assert_eq!(test.counts(), (0, 0, 0));
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn forwards_waiters_when_body_is_too_large() {
+    let mut test = Test::new(10);
+
+    let large = "a".repeat(MAX_BODY_BYTES + 1);
+    let leader = test.call(get("/config")).await;
+    let waiter = test.call(get("/config")).await;
+    let tx = test.next_request().await;
+    tx.send_response(ok(large.clone()));
+    assert_eq!(body(leader.await.unwrap().unwrap()).await, large);
+
+    let tx = test.next_request().await;
+    tx.send_response(ok("v1"));
+    assert_eq!(body(waiter.await.unwrap().unwrap()).await, "v1");
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn does_not_share_client_specific_responses() {
+    let mut test = Test::new(10);
+
+    for (name, value) in [
+        (header::SET_COOKIE, "session=abc"),
+        (header::CACHE_CONTROL, "private, max-age=60"),
+        (header::CACHE_CONTROL, "no-store"),
+        (header::VARY, "accept, accept-language"),
+        (header::VARY, "*"),
+    ] {
+        let leader = test.call(get("/config")).await;
+        let waiter = test.call(get("/config")).await;
+        let tx = test.next_request().await;
+        let mut rsp = ok("leader");
+        rsp.headers_mut()
+            .insert(name.clone(), value.parse().unwrap());
+        tx.send_response(rsp);
+        assert_eq!(body(leader.await.unwrap().unwrap()).await, "leader");
+
+        // The waiter is forwarded rather than served the leader's response.
+        let tx = test.next_request().await;
+        tx.send_response(ok("waiter"));
+        assert_eq!(
+            body(waiter.await.unwrap().unwrap()).await,
+            "waiter",
+            "response with {name}: {value} must not be shared"
+        );
+    }
+    assert_eq!(test.counts(), (0, 0, 0));
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn shares_responses_that_vary_on_coalesced_headers() {
+    let mut test = Test::new(10);
+
+    let calls = [
+        test.call(get("/config")).await,
+        test.call(get("/config")).await,
+    ];
+    let tx = test.next_request().await;
+    let mut rsp = ok("v1");
+    rsp.headers_mut()
+        .insert(header::VARY, "Accept".parse().unwrap());
+    rsp.headers_mut()
+        .insert(header::CACHE_CONTROL, "public, max-age=60".parse().unwrap());
+    tx.send_response(rsp);
+    for call in calls {
+        assert_eq!(body(call.await.unwrap().unwrap()).await, "v1");
+    }
+    test.assert_no_request();
+    assert_eq!(test.counts(), (1, 0, 0));
+}
+
+/// Tests that the leader's response is streamed rather than buffered, and
+/// that it is shared once complete.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn streams_leader_response() {
+    let mut test = Test::new(10);
+
+    let leader = test.call(get("/config")).await;
+    let waiter = test.call(get("/config")).await;
+    let tx = test.next_request().await;
+    let (mut body_tx, rsp_body) = http_body_util::channel::Channel::<Bytes, Error>::new(4);
+    tx.send_response(http::Response::new(BoxBody::new(rsp_body)));
+
+    // The leader's response headers are returned before its body completes.
+    let mut leader_body = leader.await.unwrap().unwrap().into_body();
+    body_tx.send_data("hello ".into()).await.unwrap();
+    let frame = leader_body.frame().await.unwrap().unwrap();
+    assert_eq!(frame.into_data().unwrap().chunk(), b"hello ");
+    assert!(
+        !waiter.is_finished(),
+        "waiter must wait for the complete body"
+    );
+
+    body_tx.send_data("world".into()).await.unwrap();
+    drop(body_tx);
+    let rest = leader_body.collect().await.unwrap().to_bytes();
+    assert_eq!(rest, "world");
+    assert_eq!(body(waiter.await.unwrap().unwrap()).await, "hello world");
+    test.assert_no_request();
+    assert_eq!(test.counts(), (1, 0, 0));
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn forwards_waiters_when_leader_body_fails() {
+    let mut test = Test::new(10);
+
+    let leader = test.call(get("/config")).await;
+    let waiter = test.call(get("/config")).await;
+    let tx = test.next_request().await;
+    let (mut body_tx, rsp_body) = http_body_util::channel::Channel::<Bytes, Error>::new(4);
+    tx.send_response(http::Response::new(BoxBody::new(rsp_body)));
+
+    let leader_body = leader.await.unwrap().unwrap().into_body();
+    body_tx.send_data("partial".into()).await.unwrap();
+    body_tx.abort("boom".into());
+    assert!(leader_body.collect().await.is_err());
+
+    let tx = test.next_request().await;
+    tx.send_response(ok("v1"));
+    assert_eq!(body(waiter.await.unwrap().unwrap()).await, "v1");
+    assert_eq!(test.counts(), (0, 0, 0));
+}
+
+// === Utils ===
+
+impl Test {
+    fn new(max_waiters: usize) -> Self {
+        let (mock, mut handle) = tower_test::mock::pair();
+        handle.allow(100);
+        let coalescer = Coalescer::default();
+        let metrics = MetricFamilies::<Labels>::default();
+        let svc = Coalesce {
+            inner: mock,
+            params: Some(Arc::new(Params {
+                scope: 1,
+                headers: vec![header::ACCEPT],
+                max_waiters,
+                timeout: TIMEOUT,
+            })),
+            coalescer: coalescer.clone(),
+            metrics: metrics.clone(),
+            extract: ExtractLabels,
+        };
+        Self {
+            svc,
+            handle,
+            coalescer,
+            metrics,
+        }
+    }
+
+    /// Dispatches a request on a background task.
+    async fn call(
+        &mut self,
+        req: http::Request<BoxBody>,
+    ) -> JoinHandle<Result<http::Response<BoxBody>>> {
+        let call = self.svc.ready().await.unwrap().call(req);
+        tokio::spawn(call)
+    }
+
+    async fn next_request(&mut self) -> tower_test::mock::SendResponse<http::Response<BoxBody>> {
+        let (_, tx) = self
+            .handle
+            .next_request()
+            .await
+            .expect("request must be forwarded");
+        tx
+    }
+
+    fn assert_no_request(&mut self) {
+        use futures::FutureExt;
+        assert!(
+            self.handle.next_request().now_or_never().is_none(),
+            "request must not be forwarded"
+        );
+    }
+
+    /// Returns the coalesced, timeout, and overflow counts.
+    fn counts(&self) -> (u64, u64, u64) {
+        let labels = vec![("route".to_string(), "test".to_string())];
+        let m = self.metrics.metrics(&labels);
+        (m.coalesced.get(), m.timeouts.get(), m.overflows.get())
+    }
+}
+
+fn get(path: &'static str) -> http::Request<BoxBody> {
+    http::Request::get(path)
+        .header(header::HOST, "example.com")
+        .body(BoxBody::empty())
+        .unwrap()
+}
+
+fn head(path: &'static str) -> http::Request<BoxBody> {
+    http::Request::head(path)
+        .header(header::HOST, "example.com")
+        .body(BoxBody::empty())
+        .unwrap()
+}
+
+fn ok(body: impl Into<Bytes>) -> http::Response<BoxBody> {
+    http::Response::new(BoxBody::new(http_body_util::Full::new(body.into())))
+}
+
+async fn body(rsp: http::Response<BoxBody>) -> String {
+    let body = rsp.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(body.to_vec()).unwrap()
+}
@@ -0,0 +1,179 @@
+use crate::Metrics;
+use bytes::{Buf, Bytes};
+use flate2::{write::GzEncoder, Compression};
+use http::HeaderMap;
+use http_body::{Body, Frame};
+use linkerd_error::Error;
+use pin_project::pin_project;
+use std::{
+    io::Write,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A response body that is optionally gzip-compressed as it is streamed.
+#[pin_project]
+#[derive(Debug)]
+pub struct CompressBody<B> {
+    #[pin]
+    inner: B,
+    gzip: Option<Box<Gzip>>,
+}
+
+#[derive(Debug)]
+struct Gzip {
+    encoder: Option<GzEncoder<Vec<u8>>>,
+    /// Trailers received from the inner body, to be emitted once the
+    /// compressed stream has been finished.
+    trailers: Option<HeaderMap>,
+    /// Set when data has been written to the encoder but not flushed.
+    unflushed: bool,
+    /// Set for streaming responses, so that each chunk is flushed to the
+    /// client as it is received.
+    flush_chunks: bool,
+    uncompressed: u64,
+    compressed: u64,
+    metrics: Metrics,
+}
+
+// === impl CompressBody ===
+
+impl<B> CompressBody<B> {
+    pub(crate) fn passthru(inner: B) -> Self {
+        Self { inner, gzip: None }
+    }
+
+    pub(crate) fn gzip(inner: B, metrics: Metrics, flush_chunks: bool) -> Self {
+        Self {
+            inner,
+            gzip: Some(Box::new(Gzip {
+                encoder: Some(GzEncoder::new(Vec::new(), Compression::default())),
+                trailers: None,
+                unflushed: false,
+                flush_chunks,
+                uncompressed: 0,
+                compressed: 0,
+                metrics,
+            })),
+        }
+    }
+}
+
+impl<B> Body for CompressBody<B>
+where
+    B: Body,
+    B::Error: Into<Error>,
+{
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+        let mut this = self.project();
+        let Some(gzip) = this.gzip.as_mut() else {
+            return this.inner.poll_frame(cx).map(|f| {
+                f.map(|f| {
+                    f.map(|f| f.map_data(|mut d| d.copy_to_bytes(d.remaining())))
+                        .map_err(Into::into)
+                })
+            });
+        };
+
+        loop {
+            if let Some(trailers) = gzip.trailers.take() {
+                return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+            }
+            if gzip.encoder.is_none() {
+                return Poll::Ready(None);
+            }
+
+            let frame = match this.inner.as_mut().poll_frame(cx) {
+                Poll::Ready(Some(Ok(frame))) => frame,
+                Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error.into()))),
+                Poll::Ready(None) => {
+                    let buf = gzip.finish()?;
+                    return Poll::Ready(Some(Ok(Frame::data(buf))));
+                }
+                Poll::Pending => {
+                    // Don't hold compressed data while the inner body is idle.
+                    if gzip.unflushed {
+                        let buf = gzip.flush()?;
+                        if !buf.is_empty() {
+                            return Poll::Ready(Some(Ok(Frame::data(buf))));
+                        }
+                    }
+                    return Poll::Pending;
+                }
+            };
+
+            let buf = match frame.into_data() {
+                Ok(mut data) => gzip.write(data.copy_to_bytes(data.remaining()))?,
+                Err(frame) => {
+                    gzip.trailers = frame.into_trailers().ok();
+                    gzip.finish()?
+                }
+            };
+            if !buf.is_empty() {
+                return Poll::Ready(Some(Ok(Frame::data(buf))));
+            }
+        }
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        match self.gzip.as_deref() {
+            None => self.inner.is_end_stream(),
+            Some(gzip) => gzip.encoder.is_none() && gzip.trailers.is_none(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        match self.gzip {
+            None => self.inner.size_hint(),
+            Some(_) => http_body::SizeHint::default(),
+        }
+    }
+}
+
+// === impl Gzip ===
+
+impl Gzip {
+    fn write(&mut self, data: Bytes) -> std::io::Result<Bytes> {
+        let encoder = self.encoder.as_mut().expect("encoder must not be finished");
+        encoder.write_all(&data)?;
+        self.uncompressed += data.len() as u64;
+        if self.flush_chunks {
+            encoder.flush()?;
+        } else {
+            self.unflushed |= !data.is_empty();
+        }
+        Ok(self.take())
+    }
+
+    fn flush(&mut self) -> std::io::Result<Bytes> {
+        let encoder = self.encoder.as_mut().expect("encoder must not be finished");
+        encoder.flush()?;
+        self.unflushed = false;
+        Ok(self.take())
+    }
+
+    /// Completes the compressed stream and records metrics.
+    fn finish(&mut self) -> std::io::Result<Bytes> {
+        let encoder = self.encoder.take().expect("encoder must not be finished");
+        let buf = Bytes::from(encoder.finish()?);
+        self.compressed += buf.len() as u64;
+        self.metrics.record(self.uncompressed, self.compressed);
+        Ok(buf)
+    }
+
+    /// Takes the compressed output produced so far.
+    fn take(&mut self) -> Bytes {
+        let encoder = self.encoder.as_mut().expect("encoder must not be finished");
+        let buf = Bytes::from(std::mem::take(encoder.get_mut()));
+        self.compressed += buf.len() as u64;
+        buf
+    }
+}
@@ -0,0 +1,337 @@
+//! Tower middleware to compress HTTP responses.
+//!
+//! See [`Compress<S>`].
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![forbid(unsafe_code)]
+
+use http::header::{self, HeaderMap, HeaderValue};
+use http_body::Body;
+use linkerd_metrics::prom;
+use linkerd_stack::{layer, Service};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+use tracing::debug;
+
+mod body;
+#[cfg(test)]
+mod tests;
+
+pub use self::body::CompressBody;
+
+/// Configures response compression.
+///
+/// Compression is disabled when no content-types are configured.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Content-types that may be compressed. Entries may be exact media
+    /// types (e.g. `application/json`) or wildcard subtypes (e.g. `text/*`).
+    pub content_types: Vec<String>,
+
+    /// Responses with a known size smaller than this are not compressed.
+    pub min_size: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    responses: prom::Counter,
+    uncompressed_bytes: prom::Counter,
+    saved_bytes: prom::Counter,
+}
+
+/// Compresses responses with gzip when the client accepts it and the
+/// response is eligible under the configured [`Config`].
+///
+/// Responses that are already encoded, gRPC responses, server-sent event
+/// streams, and responses that forbid transformation are never compressed.
+/// Strong entity tags are weakened on compressed responses, since the
+/// compressed representation is not byte-for-byte identical to the original.
+#[derive(Clone, Debug)]
+pub struct Compress<S> {
+    config: Arc<Config>,
+    metrics: Metrics,
+    inner: S,
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F> {
+    #[pin]
+    inner: F,
+    compress: Option<(Arc<Config>, Metrics)>,
+}
+
+const EVENT_STREAM: &str = "text/event-stream";
+
+/// gRPC messages are framed and compressed by gRPC itself, e.g.
+/// `application/grpc+proto` or `application/grpc-web`.
+const GRPC_PREFIX: &str = "application/grpc";
+
+// === impl Config ===
+
+impl Config {
+    fn is_enabled(&self) -> bool {
+        !self.content_types.is_empty()
+    }
+
+    /// Returns true if the response may be compressed.
+    fn allows<B: Body>(&self, rsp: &http::Response<B>) -> bool {
+        let status = rsp.status();
+        if status.is_informational()
+            || status == http::StatusCode::NO_CONTENT
+            || status == http::StatusCode::PARTIAL_CONTENT
+            || status == http::StatusCode::NOT_MODIFIED
+            || rsp.body().is_end_stream()
+        {
+            return false;
+        }
+
+        let headers = rsp.headers();
+        if headers.contains_key(header::CONTENT_ENCODING) || has_no_transform(headers) {
+            return false;
+        }
+
+        let Some(content_type) = media_type(headers) else {
+            return false;
+        };
+        if content_type.eq_ignore_ascii_case(EVENT_STREAM)
+            || is_grpc(content_type)
+            || !self
+                .content_types
+                .iter()
+                .any(|allowed| matches_media_type(allowed, content_type))
+        {
+            return false;
+        }
+
+        // Use the content-length, if one is set, and otherwise fall back to
+        // the body's size hint. Responses of unknown size are compressed.
+        let size = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok()?.parse::<u64>().ok())
+            .or_else(|| rsp.body().size_hint().exact());
+        size.is_none_or(|sz| sz >= self.min_size)
+    }
+}
+
+// === impl Metrics ===
+
+impl Metrics {
+    pub fn register(reg: &mut prom::Registry) -> Self {
+        let responses = prom::Counter::default();
+        reg.register(
+            "responses",
+            "The number of responses compressed by the proxy",
+            responses.clone(),
+        );
+
+        let uncompressed_bytes = prom::Counter::default();
+        reg.register_with_unit(
+            "uncompressed",
+            "The total size of compressed response bodies before compression",
+            prom::Unit::Bytes,
+            uncompressed_bytes.clone(),
+        );
+
+        let saved_bytes = prom::Counter::default();
+        reg.register_with_unit(
+            "saved",
+            "The number of response body bytes saved by compression",
+            prom::Unit::Bytes,
+            saved_bytes.clone(),
+        );
+
+        Self {
+            responses,
+            uncompressed_bytes,
+            saved_bytes,
+        }
+    }
+
+    fn record(&self, uncompressed: u64, compressed: u64) {
+        self.responses.inc();
+        self.uncompressed_bytes.inc_by(uncompressed);
+        self.saved_bytes
+            .inc_by(uncompressed.saturating_sub(compressed));
+    }
+}
+
+// === impl Compress ===
+
+impl<S> Compress<S> {
+    pub fn new(config: Arc<Config>, metrics: Metrics, inner: S) -> Self {
+        Self {
+            config,
+            metrics,
+            inner,
+        }
+    }
+
+    pub fn layer(config: Config, metrics: Metrics) -> impl layer::Layer<S, Service = Self> + Clone {
+        let config = Arc::new(config);
+        layer::mk(move |inner| Self::new(config.clone(), metrics.clone(), inner))
+    }
+}
+
+impl<S, A, B> Service<http::Request<A>> for Compress<S>
+where
+    S: Service<http::Request<A>, Response = http::Response<B>>,
+    B: Body,
+{
+    type Response = http::Response<CompressBody<B>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<A>) -> Self::Future {
+        let compress = (self.config.is_enabled()
+            && req.method() != http::Method::HEAD
+            && accepts_gzip(req.headers()))
+        .then(|| (self.config.clone(), self.metrics.clone()));
+
+        ResponseFuture {
+            inner: self.inner.call(req),
+            compress,
+        }
+    }
+}
+
+// === impl ResponseFuture ===
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+    B: Body,
+{
+    type Output = Result<http::Response<CompressBody<B>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let rsp = ready!(this.inner.poll(cx))?;
+
+        let compress = this
+            .compress
+            .take()
+            .filter(|(config, _)| config.allows(&rsp));
+        let Some((_, metrics)) = compress else {
+            return Poll::Ready(Ok(rsp.map(CompressBody::passthru)));
+        };
+
+        let streaming = is_streaming(rsp.headers());
+        debug!(streaming, "Compressing response");
+        let (mut parts, body) = rsp.into_parts();
+        parts
+            .headers
+            .insert(header::CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        parts.headers.remove(header::CONTENT_LENGTH);
+        parts
+            .headers
+            .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+        weaken_etag(&mut parts.headers);
+        Poll::Ready(Ok(http::Response::from_parts(
+            parts,
+            CompressBody::gzip(body, metrics, streaming),
+        )))
+    }
+}
+
+/// Returns true if the request's `accept-encoding` header permits gzip.
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    let mut accepts = false;
+    for value in headers.get_all(header::ACCEPT_ENCODING) {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        for coding in value.split(',') {
+            let mut params = coding.split(';');
+            let name = params.next().unwrap_or_default().trim();
+            if !(name.eq_ignore_ascii_case("gzip") || name == "*") {
+                continue;
+            }
+            let q = params
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if name.eq_ignore_ascii_case("gzip") {
+                // An explicit gzip preference overrides a wildcard.
+                return q > 0.0;
+            }
+            accepts = q > 0.0;
+        }
+    }
+    accepts
+}
+
+/// Returns true if the response has no content-length, e.g. a long-poll
+/// response, so that chunks must be delivered as they are received.
+///
+/// HTTP/2 responses have no transfer-encoding, so the content-length alone
+/// determines whether a response is streamed.
+fn is_streaming(headers: &HeaderMap) -> bool {
+    !headers.contains_key(header::CONTENT_LENGTH)
+}
+
+/// Weakens a strong entity tag, since the compressed representation is not
+/// byte-for-byte identical to the uncompressed one. Malformed tags are removed.
+fn weaken_etag(headers: &mut HeaderMap) {
+    let Some(etag) = headers.get(header::ETAG) else {
+        return;
+    };
+    let bytes = etag.as_bytes();
+    if bytes.starts_with(b"W/") {
+        return;
+    }
+    let weak = bytes
+        .starts_with(b"\"")
+        .then(|| HeaderValue::from_bytes(&[b"W/", bytes].concat()).ok())
+        .flatten();
+    match weak {
+        Some(weak) => {
+            headers.insert(header::ETAG, weak);
+        }
+        None => {
+            headers.remove(header::ETAG);
+        }
+    }
+}
+
+fn is_grpc(media_type: &str) -> bool {
+    media_type
+        .get(..GRPC_PREFIX.len())
+        .is_some_and(|p| p.eq_ignore_ascii_case(GRPC_PREFIX))
+}
+
+fn has_no_transform(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|d| d.trim().eq_ignore_ascii_case("no-transform"))
+}
+
+/// Returns the media type of the response's content-type, without
+/// parameters.
+fn media_type(headers: &HeaderMap) -> Option<&str> {
+    let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    let media_type = content_type.split(';').next()?.trim();
+    (!media_type.is_empty()).then_some(media_type)
+}
+
+fn matches_media_type(allowed: &str, media_type: &str) -> bool {
+    match allowed.strip_suffix("/*") {
+        Some(ty) => media_type
+            .split_once('/')
+            .is_some_and(|(t, _)| t.eq_ignore_ascii_case(ty)),
+        None => allowed.eq_ignore_ascii_case(media_type),
+    }
+}
@@ -0,0 +1,241 @@
+use super::*;
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use futures::StreamExt;
+use http_body::Frame;
+use http_body_util::{BodyExt, Full, StreamBody};
+use linkerd_error::Error;
+use std::io::{Read, Write};
+use tower::ServiceExt;
+
+const BODY: &str = "hello hello hello hello hello hello hello hello hello hello";
+
+#[tokio::test]
+async fn compresses_allowed_content_types() {
+    let metrics = Metrics::default();
+    let rsp = call(config(), metrics.clone(), "gzip, br", "application/json").await;
+    assert_eq!(rsp.headers()[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(rsp.headers()[header::VARY], "accept-encoding");
+    assert!(rsp.headers().get(header::CONTENT_LENGTH).is_none());
+
+    let body = rsp.into_body().collect().await.unwrap().to_bytes();
+    let mut decoded = String::new();
+    GzDecoder::new(&body[..])
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, BODY);
+
+    assert_eq!(metrics.responses.get(), 1);
+    assert_eq!(metrics.uncompressed_bytes.get(), BODY.len() as u64);
+    assert_eq!(
+        metrics.saved_bytes.get(),
+        BODY.len() as u64 - body.len() as u64
+    );
+}
+
+#[tokio::test]
+async fn wildcard_content_types() {
+    let rsp = call(
+        config(),
+        Metrics::default(),
+        "gzip",
+        "text/html; charset=utf-8",
+    )
+    .await;
+    assert_eq!(rsp.headers()[header::CONTENT_ENCODING], "gzip");
+}
+
+#[tokio::test]
+async fn skips_ineligible_responses() {
+    // The client does not accept gzip.
+    let rsp = call(config(), Metrics::default(), "br", "application/json").await;
+    assert!(rsp.headers().get(header::CONTENT_ENCODING).is_none());
+    let rsp = call(
+        config(),
+        Metrics::default(),
+        "*, gzip;q=0",
+        "application/json",
+    )
+    .await;
+    assert!(rsp.headers().get(header::CONTENT_ENCODING).is_none());
+
+    // The content-type is not allowed.
+    let rsp = call(config(), Metrics::default(), "gzip", "image/png").await;
+    assert!(rsp.headers().get(header::CONTENT_ENCODING).is_none());
+
+    // Server-sent events are never compressed.
+    let mut cfg = config();
+    cfg.content_types.push("text/event-stream".to_string());
+    let rsp = call(cfg, Metrics::default(), "gzip", "text/event-stream").await;
+    assert!(rsp.headers().get(header::CONTENT_ENCODING).is_none());
+
+    // gRPC responses are never compressed, even if their content-type is
+    // allowed.
+    let mut cfg = config();
+    cfg.content_types.push("application/*".to_string());
+    for content_type in [
+        "application/grpc",
+        "application/grpc+proto",
+        "application/grpc-web",
+    ] {
+        let rsp = call(cfg.clone(), Metrics::default(), "gzip", content_type).await;
+        assert!(
+            rsp.headers().get(header::CONTENT_ENCODING).is_none(),
+            "{content_type} must not be compressed"
+        );
+    }
+
+    // The response is smaller than the minimum size.
+    let mut cfg = config();
+    cfg.min_size = BODY.len() as u64 + 1;
+    let rsp = call(cfg, Metrics::default(), "gzip", "application/json").await;
+    assert!(rsp.headers().get(header::CONTENT_ENCODING).is_none());
+    assert_eq!(
+        rsp.headers()[header::CONTENT_LENGTH],
+        BODY.len().to_string()
+    );
+    let body = rsp.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, BODY);
+
+    // Compression is disabled.
+    let rsp = call(
+        Config::default(),
+        Metrics::default(),
+        "gzip",
+        "application/json",
+    )
+    .await;
+    assert!(rsp.headers().get(header::CONTENT_ENCODING).is_none());
+}
+
+#[tokio::test]
+async fn skips_encoded_responses() {
+    let svc = Compress::new(
+        Arc::new(config()),
+        Metrics::default(),
+        tower::service_fn(|_: http::Request<()>| async move {
+            http::Response::builder()
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::CONTENT_ENCODING, "br")
+                .body(Full::new(Bytes::from_static(BODY.as_bytes())))
+        }),
+    );
+    let req = http::Request::builder()
+        .header(header::ACCEPT_ENCODING, "gzip, br")
+        .body(())
+        .unwrap();
+    let rsp = svc.oneshot(req).await.unwrap();
+    assert_eq!(rsp.headers()[header::CONTENT_ENCODING], "br");
+}
+
+#[tokio::test]
+async fn flushes_streaming_chunks() {
+    // HTTP/2 responses have no transfer-encoding, so responses without a
+    // content-length are streamed regardless of their transfer-encoding.
+    for chunked in [true, false] {
+        let svc = Compress::new(
+            Arc::new(config()),
+            Metrics::default(),
+            tower::service_fn(move |_: http::Request<()>| async move {
+                let chunks = futures::stream::iter(["first chunk", "second chunk"])
+                    .map(|c| Ok::<_, Error>(Frame::data(Bytes::from_static(c.as_bytes()))));
+                let mut rsp =
+                    http::Response::builder().header(header::CONTENT_TYPE, "application/json");
+                if chunked {
+                    rsp = rsp.header(header::TRANSFER_ENCODING, "chunked");
+                }
+                rsp.body(StreamBody::new(chunks))
+            }),
+        );
+        let req = http::Request::builder()
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(())
+            .unwrap();
+        let mut body = svc.oneshot(req).await.unwrap().into_body();
+
+        // The first chunk must be decodable before the second chunk is read.
+        let mut decoder = flate2::write::GzDecoder::new(Vec::new());
+        let frame = body.frame().await.unwrap().unwrap();
+        decoder
+            .write_all(&frame.into_data().expect("data frame"))
+            .unwrap();
+        decoder.flush().unwrap();
+        assert_eq!(decoder.get_ref(), b"first chunk", "chunked={chunked}");
+    }
+}
+
+#[tokio::test]
+async fn weakens_strong_etags() {
+    let etag = |etag: &'static str| async move {
+        let svc = Compress::new(
+            Arc::new(config()),
+            Metrics::default(),
+            tower::service_fn(move |_: http::Request<()>| async move {
+                http::Response::builder()
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::ETAG, etag)
+                    .body(Full::new(Bytes::from_static(BODY.as_bytes())))
+            }),
+        );
+        let req = http::Request::builder()
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(())
+            .unwrap();
+        let rsp = svc.oneshot(req).await.unwrap();
+        assert_eq!(rsp.headers()[header::CONTENT_ENCODING], "gzip");
+        rsp.headers().get(header::ETAG).cloned()
+    };
+
+    assert_eq!(etag("\"abc\"").await.unwrap(), "W/\"abc\"");
+    assert_eq!(etag("W/\"abc\"").await.unwrap(), "W/\"abc\"");
+    assert_eq!(etag("abc").await, None);
+}
+
+#[test]
+fn accept_encoding() {
+    let accepts = |v: &'static str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_ENCODING, HeaderValue::from_static(v));
+        accepts_gzip(&headers)
+    };
+    assert!(accepts("gzip"));
+    assert!(accepts("deflate, GZIP;q=0.5"));
+    assert!(accepts("*"));
+    assert!(!accepts("identity"));
+    assert!(!accepts("gzip;q=0"));
+    assert!(!accepts("*;q=0"));
+    assert!(!accepts("*, gzip;q=0.0"));
+    assert!(!accepts_gzip(&HeaderMap::new()));
+}
+
+// === Utils ===
+
+fn config() -> Config {
+    Config {
+        content_types: vec!["application/json".to_string(), "text/*".to_string()],
+        min_size: 16,
+    }
+}
+
+async fn call(
+    config: Config,
+    metrics: Metrics,
+    accept_encoding: &'static str,
+    content_type: &'static str,
+) -> http::Response<CompressBody<Full<Bytes>>> {
+    let svc = Compress::new(
+        Arc::new(config),
+        metrics,
+        tower::service_fn(move |_: http::Request<()>| async move {
+            http::Response::builder()
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::CONTENT_LENGTH, BODY.len())
+                .body(Full::new(Bytes::from_static(BODY.as_bytes())))
+        }),
+    );
+    let req = http::Request::builder()
+        .header(header::ACCEPT_ENCODING, accept_encoding)
+        .body(())
+        .unwrap();
+    svc.oneshot(req).await.unwrap()
+}
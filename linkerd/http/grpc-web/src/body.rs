@@ -0,0 +1,128 @@
+use crate::{Encoding, TRAILERS_FLAG};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use http::HeaderMap;
+use http_body::{Body, Frame};
+use linkerd_error::Error;
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+/// A gRPC-Web response body.
+///
+/// Trailers received from the inner body are encoded as a final trailers
+/// frame. When the `-text` encoding is used, all frames are base64-encoded.
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseBody<B> {
+    #[pin]
+    inner: B,
+    encoding: Encoding,
+    /// Bytes that have not yet been base64-encoded, since they do not fill a
+    /// complete 3-byte quantum.
+    pending: BytesMut,
+}
+
+// === impl ResponseBody ===
+
+impl<B> ResponseBody<B> {
+    pub fn new(inner: B, encoding: Encoding) -> Self {
+        Self {
+            inner,
+            encoding,
+            pending: BytesMut::new(),
+        }
+    }
+}
+
+impl<B> Body for ResponseBody<B>
+where
+    B: Body,
+    B::Error: Into<Error>,
+{
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+        let mut this = self.project();
+        loop {
+            let frame = match ready!(this.inner.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => frame,
+                Some(Err(error)) => return Poll::Ready(Some(Err(error.into()))),
+                None => {
+                    if this.pending.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    let buf = encode(*this.encoding, this.pending, Bytes::new(), true);
+                    return Poll::Ready(Some(Ok(Frame::data(buf))));
+                }
+            };
+
+            let buf = match frame.into_data() {
+                Ok(mut data) => {
+                    let data = data.copy_to_bytes(data.remaining());
+                    encode(*this.encoding, this.pending, data, false)
+                }
+                Err(frame) => match frame.into_trailers() {
+                    Ok(trailers) => encode(
+                        *this.encoding,
+                        this.pending,
+                        trailers_frame(&trailers),
+                        true,
+                    ),
+                    // Unknown frame types are dropped.
+                    Err(_) => continue,
+                },
+            };
+            if !buf.is_empty() {
+                return Poll::Ready(Some(Ok(Frame::data(buf))));
+            }
+        }
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.pending.is_empty() && self.inner.is_end_stream()
+    }
+}
+
+/// Encodes response data. In `-text` mode, only complete quanta are encoded
+/// unless `flush` is set, so that the resulting base64 stream is not padded
+/// mid-stream.
+fn encode(encoding: Encoding, pending: &mut BytesMut, data: Bytes, flush: bool) -> Bytes {
+    match encoding {
+        Encoding::Binary => data,
+        Encoding::Text => {
+            pending.extend_from_slice(&data);
+            let len = if flush {
+                pending.len()
+            } else {
+                pending.len() - pending.len() % 3
+            };
+            let quanta = pending.split_to(len);
+            STANDARD.encode(quanta).into()
+        }
+    }
+}
+
+/// Encodes trailers as a gRPC-Web trailers frame.
+pub(crate) fn trailers_frame(trailers: &HeaderMap) -> Bytes {
+    let mut block = BytesMut::new();
+    for (name, value) in trailers {
+        block.put_slice(name.as_str().as_bytes());
+        block.put_slice(b": ");
+        block.put_slice(value.as_bytes());
+        block.put_slice(b"\r\n");
+    }
+
+    let mut frame = BytesMut::with_capacity(5 + block.len());
+    frame.put_u8(TRAILERS_FLAG);
+    frame.put_u32(block.len() as u32);
+    frame.put(block);
+    frame.freeze()
+}
@@ -0,0 +1,298 @@
+//! Tower middleware to translate gRPC-Web requests into native gRPC.
+//!
+//! Browser clients cannot speak native gRPC, since they have no access to
+//! HTTP/2 trailers. The [gRPC-Web protocol][spec] instead encodes trailers as
+//! a final, specially-flagged message in the response body (and, for the
+//! `-text` variants, base64-encodes the entire body).
+//!
+//! [`GrpcWeb`] translates gRPC-Web requests, identified by their
+//! `content-type`, into native HTTP/2 gRPC requests and translates the
+//! responses back into gRPC-Web. All other requests--including CORS
+//! preflight requests--are passed through untouched.
+//!
+//! Request bodies are buffered so that client-streaming calls, which gRPC-Web
+//! does not support, can be rejected with an `UNIMPLEMENTED` status before
+//! the request is dispatched. Response bodies are streamed.
+//!
+//! [spec]: https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-WEB.md
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![forbid(unsafe_code)]
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::{Buf, Bytes};
+use futures::{
+    future::{self, BoxFuture, Either},
+    FutureExt, TryFutureExt,
+};
+use http::header::{self, HeaderMap, HeaderValue};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use linkerd_error::{is_caused_by, Error, Result};
+use linkerd_http_box::BoxBody;
+use linkerd_stack::{layer, Service};
+use std::task::{Context, Poll};
+use tracing::debug;
+
+mod body;
+#[cfg(test)]
+mod tests;
+
+pub use self::body::ResponseBody;
+
+/// The maximum size of a buffered gRPC-Web request body.
+///
+/// This matches the default maximum message size of most gRPC servers.
+pub const MAX_REQUEST_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// Translates gRPC-Web requests into native gRPC.
+#[derive(Clone, Debug)]
+pub struct GrpcWeb<S> {
+    inner: S,
+}
+
+/// Describes how a gRPC-Web message stream is encoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// `application/grpc-web[+proto|+json]`: messages are framed exactly as
+    /// in native gRPC.
+    Binary,
+    /// `application/grpc-web-text[+proto]`: the framed message stream is
+    /// base64-encoded.
+    Text,
+}
+
+const GRPC_WEB: &str = "application/grpc-web";
+const GRPC: &str = "application/grpc";
+
+/// The frame flag indicating that a gRPC-Web frame holds trailers.
+const TRAILERS_FLAG: u8 = 0x80;
+
+/// gRPC status codes used when rejecting requests.
+const RESOURCE_EXHAUSTED: &str = "8";
+const UNIMPLEMENTED: &str = "12";
+const INTERNAL: &str = "13";
+
+// === impl GrpcWeb ===
+
+impl<S> GrpcWeb<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    pub fn layer() -> impl layer::Layer<S, Service = Self> + Copy {
+        layer::mk(Self::new)
+    }
+}
+
+impl<S> Service<http::Request<BoxBody>> for GrpcWeb<S>
+where
+    S: Service<http::Request<BoxBody>, Response = http::Response<BoxBody>>,
+    S: Clone + Send + 'static,
+    S::Error: Into<Error>,
+    S::Future: Send,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Error;
+    type Future = Either<
+        future::ErrInto<S::Future, Error>,
+        BoxFuture<'static, Result<http::Response<BoxBody>>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        // CORS preflight requests are handled by the application.
+        if req.method() == http::Method::OPTIONS {
+            return Either::Left(self.inner.call(req).err_into());
+        }
+
+        let Some((encoding, content_type)) = native_content_type(req.headers()) else {
+            return Either::Left(self.inner.call(req).err_into());
+        };
+
+        // The inner service has been driven to readiness, so it must be used
+        // to dispatch the request once the body has been buffered.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Either::Right(
+            async move {
+                let version = req.version();
+                let (mut parts, body) = req.into_parts();
+
+                let body = match Limited::new(body, MAX_REQUEST_BODY_BYTES).collect().await {
+                    Ok(body) => body.to_bytes(),
+                    Err(error) if is_caused_by::<LengthLimitError>(&*error) => {
+                        debug!("gRPC-Web request body too large");
+                        return Ok(reject(
+                            version,
+                            &parts.headers,
+                            RESOURCE_EXHAUSTED,
+                            "request body too large",
+                        ));
+                    }
+                    Err(error) => return Err(error),
+                };
+
+                let body = match encoding {
+                    Encoding::Binary => body,
+                    Encoding::Text => match decode_text(&body) {
+                        Some(body) => body,
+                        None => {
+                            debug!("Invalid base64 in gRPC-Web request body");
+                            return Ok(reject(
+                                version,
+                                &parts.headers,
+                                INTERNAL,
+                                "invalid base64 request body",
+                            ));
+                        }
+                    },
+                };
+
+                match count_messages(&body) {
+                    None => {
+                        debug!("Malformed gRPC-Web request body");
+                        return Ok(reject(
+                            version,
+                            &parts.headers,
+                            INTERNAL,
+                            "malformed request body",
+                        ));
+                    }
+                    Some(n) if n > 1 => {
+                        debug!(messages = n, "Rejecting client-streaming gRPC-Web request");
+                        return Ok(reject(
+                            version,
+                            &parts.headers,
+                            UNIMPLEMENTED,
+                            "client-streaming gRPC-Web requests are not supported",
+                        ));
+                    }
+                    Some(_) => {}
+                }
+
+                debug!(?encoding, "Translating gRPC-Web request");
+                parts.version = http::Version::HTTP_2;
+                parts.headers.insert(header::CONTENT_TYPE, content_type);
+                parts
+                    .headers
+                    .insert(header::TE, HeaderValue::from_static("trailers"));
+                // The body may have been re-encoded, and connection-level
+                // headers are illegal in HTTP/2.
+                parts.headers.remove(header::CONTENT_LENGTH);
+                parts.headers.remove(header::TRANSFER_ENCODING);
+                parts.headers.remove(header::CONNECTION);
+                let req = http::Request::from_parts(parts, BoxBody::new(Full::new(body)));
+
+                let rsp = inner.call(req).await.map_err(Into::into)?;
+                Ok(translate_response(rsp, encoding, version))
+            }
+            .boxed(),
+        )
+    }
+}
+
+/// If the headers describe a gRPC-Web request, returns its encoding and the
+/// equivalent native gRPC content-type.
+fn native_content_type(headers: &HeaderMap) -> Option<(Encoding, HeaderValue)> {
+    let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    let rest = content_type.strip_prefix(GRPC_WEB)?;
+    let (encoding, rest) = match rest.strip_prefix("-text") {
+        Some(rest) => (Encoding::Text, rest),
+        None => (Encoding::Binary, rest),
+    };
+    if !(rest.is_empty() || rest.starts_with('+') || rest.starts_with(';')) {
+        return None;
+    }
+    let native = HeaderValue::try_from(format!("{GRPC}{rest}")).ok()?;
+    Some((encoding, native))
+}
+
+/// Returns the gRPC-Web content-type equivalent to a native gRPC
+/// content-type.
+fn web_content_type(content_type: &HeaderValue, encoding: Encoding) -> Option<HeaderValue> {
+    let rest = content_type.to_str().ok()?.strip_prefix(GRPC)?;
+    if !(rest.is_empty() || rest.starts_with('+') || rest.starts_with(';')) {
+        return None;
+    }
+    let web = match encoding {
+        Encoding::Binary => format!("{GRPC_WEB}{rest}"),
+        Encoding::Text => format!("{GRPC_WEB}-text{rest}"),
+    };
+    HeaderValue::try_from(web).ok()
+}
+
+fn translate_response(
+    rsp: http::Response<BoxBody>,
+    encoding: Encoding,
+    version: http::Version,
+) -> http::Response<BoxBody> {
+    let (mut parts, body) = rsp.into_parts();
+    parts.version = version;
+    if let Some(ct) = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|ct| web_content_type(ct, encoding))
+    {
+        parts.headers.insert(header::CONTENT_TYPE, ct);
+    }
+    // Trailers are encoded into the body, so its length changes.
+    parts.headers.remove(header::CONTENT_LENGTH);
+    http::Response::from_parts(parts, BoxBody::new(ResponseBody::new(body, encoding)))
+}
+
+/// Builds a trailers-only gRPC-Web response with the given status.
+fn reject(
+    version: http::Version,
+    req_headers: &HeaderMap,
+    status: &'static str,
+    message: &'static str,
+) -> http::Response<BoxBody> {
+    let content_type = req_headers
+        .get(header::CONTENT_TYPE)
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_static(GRPC_WEB));
+    http::Response::builder()
+        .version(version)
+        .status(http::StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header("grpc-status", status)
+        .header("grpc-message", message)
+        .body(BoxBody::empty())
+        .expect("response must be valid")
+}
+
+/// Decodes a `-text` body.
+///
+/// Each 4-byte quantum is decoded independently, since clients may
+/// concatenate separately-padded base64 chunks.
+fn decode_text(buf: &[u8]) -> Option<Bytes> {
+    let mut out = Vec::with_capacity(buf.len() / 4 * 3);
+    for quantum in buf.chunks(4) {
+        STANDARD.decode_vec(quantum, &mut out).ok()?;
+    }
+    Some(out.into())
+}
+
+/// Returns the number of messages in a framed gRPC message stream, or `None`
+/// if the stream is not well-formed.
+fn count_messages(mut buf: &[u8]) -> Option<usize> {
+    let mut messages = 0;
+    while buf.has_remaining() {
+        if buf.remaining() < 5 {
+            return None;
+        }
+        let flags = buf.get_u8();
+        let len = buf.get_u32() as usize;
+        if flags & TRAILERS_FLAG != 0 || buf.remaining() < len {
+            return None;
+        }
+        buf.advance(len);
+        messages += 1;
+    }
+    Some(messages)
+}
@@ -0,0 +1,179 @@
+use super::*;
+use http_body::Frame;
+use http_body_util::StreamBody;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn binary_unary() {
+    let svc = GrpcWeb::new(tower::service_fn(
+        |req: http::Request<BoxBody>| async move {
+            assert_eq!(req.version(), http::Version::HTTP_2);
+            assert_eq!(
+                req.headers()[header::CONTENT_TYPE],
+                "application/grpc+proto"
+            );
+            assert_eq!(req.headers()[header::TE], "trailers");
+            assert_eq!(collect(req.into_body()).await, message(b"hello"));
+            Ok::<_, Error>(grpc_rsp("application/grpc+proto", b"world"))
+        },
+    ));
+
+    let req = http::Request::post("/svc/method")
+        .version(http::Version::HTTP_11)
+        .header(header::CONTENT_TYPE, "application/grpc-web+proto")
+        .body(BoxBody::new(Full::new(message(b"hello"))))
+        .unwrap();
+    let rsp = svc.oneshot(req).await.unwrap();
+    assert_eq!(rsp.version(), http::Version::HTTP_11);
+    assert_eq!(
+        rsp.headers()[header::CONTENT_TYPE],
+        "application/grpc-web+proto"
+    );
+
+    let mut expected = message(b"world").to_vec();
+    expected.extend_from_slice(&trailers_frame());
+    assert_eq!(collect(rsp.into_body()).await, expected);
+}
+
+#[tokio::test]
+async fn text_unary() {
+    let svc = GrpcWeb::new(tower::service_fn(
+        |req: http::Request<BoxBody>| async move {
+            assert_eq!(req.headers()[header::CONTENT_TYPE], "application/grpc");
+            assert_eq!(collect(req.into_body()).await, message(b"hello"));
+            Ok::<_, Error>(grpc_rsp("application/grpc", b"world"))
+        },
+    ));
+
+    // Clients may send separately-padded base64 chunks.
+    let msg = message(b"hello");
+    let mut body = STANDARD.encode(&msg[..4]);
+    body.push_str(&STANDARD.encode(&msg[4..]));
+    let req = http::Request::post("/svc/method")
+        .header(header::CONTENT_TYPE, "application/grpc-web-text")
+        .body(BoxBody::new(Full::new(Bytes::from(body))))
+        .unwrap();
+    let rsp = svc.oneshot(req).await.unwrap();
+    assert_eq!(
+        rsp.headers()[header::CONTENT_TYPE],
+        "application/grpc-web-text"
+    );
+
+    let mut expected = message(b"world").to_vec();
+    expected.extend_from_slice(&trailers_frame());
+    let body = collect(rsp.into_body()).await;
+    assert_eq!(STANDARD.decode(&body).unwrap(), expected);
+}
+
+#[tokio::test]
+async fn rejects_client_streaming() {
+    let svc = GrpcWeb::new(tower::service_fn(|_: http::Request<BoxBody>| {
+        future::err::<http::Response<BoxBody>, Error>(
+            "client-streaming requests must not be dispatched".into(),
+        )
+    }));
+
+    let mut body = message(b"hello").to_vec();
+    body.extend_from_slice(&message(b"world"));
+    let req = http::Request::post("/svc/method")
+        .header(header::CONTENT_TYPE, "application/grpc-web")
+        .body(BoxBody::new(Full::new(Bytes::from(body))))
+        .unwrap();
+    let rsp = svc.oneshot(req).await.unwrap();
+    assert_eq!(rsp.status(), http::StatusCode::OK);
+    assert_eq!(rsp.headers()[header::CONTENT_TYPE], "application/grpc-web");
+    assert_eq!(rsp.headers()["grpc-status"], UNIMPLEMENTED);
+}
+
+#[tokio::test]
+async fn passes_through_other_requests() {
+    let svc = GrpcWeb::new(tower::service_fn(
+        |req: http::Request<BoxBody>| async move {
+            assert_eq!(req.version(), http::Version::HTTP_11);
+            assert!(req.headers().get(header::TE).is_none());
+            Ok::<_, Error>(http::Response::new(BoxBody::empty()))
+        },
+    ));
+
+    // A CORS preflight request.
+    let req = http::Request::options("/svc/method")
+        .version(http::Version::HTTP_11)
+        .header("access-control-request-headers", "content-type,x-grpc-web")
+        .body(BoxBody::empty())
+        .unwrap();
+    svc.clone().oneshot(req).await.unwrap();
+
+    let req = http::Request::post("/svc/method")
+        .version(http::Version::HTTP_11)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(BoxBody::empty())
+        .unwrap();
+    svc.oneshot(req).await.unwrap();
+}
+
+#[test]
+fn content_types() {
+    let native = |ct: &'static str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(ct));
+        native_content_type(&headers)
+    };
+    assert_eq!(
+        native("application/grpc-web"),
+        Some((
+            Encoding::Binary,
+            HeaderValue::from_static("application/grpc")
+        ))
+    );
+    assert_eq!(
+        native("application/grpc-web+json"),
+        Some((
+            Encoding::Binary,
+            HeaderValue::from_static("application/grpc+json")
+        ))
+    );
+    assert_eq!(
+        native("application/grpc-web-text+proto"),
+        Some((
+            Encoding::Text,
+            HeaderValue::from_static("application/grpc+proto")
+        ))
+    );
+    assert_eq!(native("application/grpc"), None);
+    assert_eq!(native("application/grpc-webby"), None);
+}
+
+// === Utils ===
+
+fn message(payload: &[u8]) -> Bytes {
+    let mut buf = vec![0];
+    buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    buf.extend_from_slice(payload);
+    buf.into()
+}
+
+fn trailers_frame() -> Bytes {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from_static("0"));
+    body::trailers_frame(&trailers)
+}
+
+fn grpc_rsp(content_type: &'static str, payload: &[u8]) -> http::Response<BoxBody> {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from_static("0"));
+    let frames = vec![
+        Ok::<_, Error>(Frame::data(message(payload))),
+        Ok(Frame::trailers(trailers)),
+    ];
+    http::Response::builder()
+        .version(http::Version::HTTP_2)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(BoxBody::new(StreamBody::new(futures::stream::iter(frames))))
+        .unwrap()
+}
+
+async fn collect(body: BoxBody) -> Bytes {
+    let collected = body.collect().await.unwrap();
+    assert!(collected.trailers().is_none(), "trailers must be encoded");
+    collected.to_bytes()
+}
@@ -1,5 +1,7 @@
 use super::{Prefixed, Registry, Report};
-use linkerd_metrics::legacy::{Counter, FmtLabels, FmtMetric, FmtMetrics, LastUpdate, Metric};
+use linkerd_metrics::legacy::{
+    Bounds, Bucket, Counter, FmtLabels, FmtMetric, FmtMetrics, Histogram, LastUpdate, Metric,
+};
 use parking_lot::Mutex;
 use std::{fmt, hash::Hash, sync::Arc};
 use tokio::time::{Duration, Instant};
@@ -18,10 +20,21 @@ pub struct Metrics {
     last_update: Instant,
     retryable: Counter,
     no_budget: Counter,
+    attempts: Histogram<u64>,
 }
 
 struct NoBudgetLabel;
 
+/// Buckets for the number of attempts made for each retryable request.
+const ATTEMPT_BOUNDS: &Bounds = &Bounds(&[
+    Bucket::Le(1.0),
+    Bucket::Le(2.0),
+    Bucket::Le(3.0),
+    Bucket::Le(5.0),
+    Bucket::Le(10.0),
+    Bucket::Inf,
+]);
+
 // === impl Retries ===
 
 impl<T: Hash + Eq> Default for Retries<T> {
@@ -58,6 +71,13 @@ impl Handle {
             m.no_budget.incr();
         }
     }
+
+    /// Records the total number of attempts made for a retryable request.
+    pub fn observe_attempts(&self, attempts: u64) {
+        let mut m = self.0.lock();
+        m.last_update = Instant::now();
+        m.attempts.add(attempts);
+    }
 }
 
 // === impl Metrics ===
@@ -68,6 +88,7 @@ impl Default for Metrics {
             last_update: Instant::now(),
             retryable: Counter::default(),
             no_budget: Counter::default(),
+            attempts: Histogram::new(ATTEMPT_BOUNDS),
         }
     }
 }
@@ -90,6 +111,13 @@ where
             "Total count of retryable HTTP responses.",
         )
     }
+
+    fn retry_attempts(&self) -> Metric<'_, Prefixed<'_, &'static str>, Histogram<u64>> {
+        Metric::new(
+            self.prefix_key("retry_attempts"),
+            "The number of attempts made for each retryable HTTP request.",
+        )
+    }
 }
 
 impl<T> FmtMetrics for Report<T, Metrics>
@@ -117,6 +145,14 @@ where
                 .fmt_metric_labeled(f, &metric.name, (tgt, NoBudgetLabel))?;
         }
 
+        let metric = self.retry_attempts();
+        metric.fmt_help(f)?;
+        for (tgt, tm) in registry.iter() {
+            tm.lock()
+                .attempts
+                .fmt_metric_labeled(f, &metric.name, tgt)?;
+        }
+
         registry.retain_since(Instant::now() - self.retain_idle);
 
         Ok(())
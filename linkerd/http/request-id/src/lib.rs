@@ -0,0 +1,151 @@
+//! Tower middleware to identify HTTP requests with a request ID.
+//!
+//! See [`SetRequestId<S>`].
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![forbid(unsafe_code)]
+
+use http::header::{HeaderName, HeaderValue};
+use linkerd_stack::{layer, Service};
+use std::{
+    fmt,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{info_span, instrument::Instrumented, Instrument, Span};
+
+#[cfg(test)]
+mod tests;
+
+/// Configures request IDs.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The header that carries the request ID, e.g. `x-request-id`.
+    pub header: HeaderName,
+
+    /// Whether IDs set by clients are replaced with a new ID.
+    pub overwrite: bool,
+}
+
+/// Identifies a request.
+///
+/// This is set as a request extension by [`SetRequestId`] so that it may be
+/// referenced by inner stacks (e.g. to set the ID on synthesized responses).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestId {
+    header: HeaderName,
+    value: HeaderValue,
+}
+
+/// Ensures that each request has an ID, generating a ULID when the request
+/// does not already have one.
+///
+/// The ID is set on the request's headers (so that it is propagated to the
+/// next hop and is the same on every retry attempt), on the request's
+/// extensions, and on a tracing span that wraps the request's handling.
+#[derive(Clone, Debug)]
+pub struct SetRequestId<S> {
+    config: Option<Arc<Config>>,
+    inner: S,
+}
+
+/// The Crockford base32 alphabet used to encode ULIDs.
+const ULID_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Generates a new ULID.
+///
+/// A ULID is a 48-bit millisecond timestamp followed by 80 random bits,
+/// encoded as 26 Crockford base32 characters so that IDs sort by the time at
+/// which they were generated.
+pub fn generate() -> HeaderValue {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    ulid(millis, rand::random())
+}
+
+fn ulid(millis: u64, random: u128) -> HeaderValue {
+    const TIMESTAMP_MASK: u64 = (1 << 48) - 1;
+    const RANDOM_MASK: u128 = (1 << 80) - 1;
+    let id = (u128::from(millis & TIMESTAMP_MASK) << 80) | (random & RANDOM_MASK);
+
+    let mut buf = [0u8; 26];
+    for (i, c) in buf.iter_mut().rev().enumerate() {
+        *c = ULID_ALPHABET[((id >> (5 * i)) & 0x1f) as usize];
+    }
+    HeaderValue::from_bytes(&buf).expect("ULIDs must be valid header values")
+}
+
+// === impl RequestId ===
+
+impl RequestId {
+    /// The header that carries the request ID.
+    pub fn header(&self) -> &HeaderName {
+        &self.header
+    }
+
+    pub fn value(&self) -> &HeaderValue {
+        &self.value
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value.to_str() {
+            Ok(v) => v.fmt(f),
+            Err(_) => write!(f, "{:?}", self.value),
+        }
+    }
+}
+
+// === impl SetRequestId ===
+
+impl<S> SetRequestId<S> {
+    /// Returns a layer that sets request IDs. Requests are not modified when
+    /// no configuration is provided.
+    pub fn layer(config: Option<Config>) -> impl layer::Layer<S, Service = Self> + Clone {
+        let config = config.map(Arc::new);
+        layer::mk(move |inner| Self {
+            config: config.clone(),
+            inner,
+        })
+    }
+}
+
+impl<B, S> Service<http::Request<B>> for SetRequestId<S>
+where
+    S: Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Instrumented<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let Some(config) = self.config.as_deref() else {
+            return self.inner.call(req).instrument(Span::none());
+        };
+
+        let value = match req.headers().get(&config.header) {
+            Some(value) if !config.overwrite => value.clone(),
+            _ => generate(),
+        };
+        // Replace any (possibly repeated) values set by the client.
+        req.headers_mut()
+            .insert(config.header.clone(), value.clone());
+
+        let id = RequestId {
+            header: config.header.clone(),
+            value,
+        };
+        let span = info_span!("request", id = %id);
+        req.extensions_mut().insert(id);
+        self.inner.call(req).instrument(span)
+    }
+}
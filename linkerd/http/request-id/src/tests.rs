@@ -0,0 +1,92 @@
+use super::*;
+use std::convert::Infallible;
+use tower::{service_fn, Layer, ServiceExt};
+
+const HEADER: &str = "x-request-id";
+
+/// Sends a request through a `SetRequestId` service, returning the request
+/// observed by the inner service.
+async fn send(config: Option<Config>, req: http::Request<()>) -> http::Request<()> {
+    let svc = SetRequestId::layer(config).layer(service_fn(|req: http::Request<()>| async move {
+        Ok::<_, Infallible>(req)
+    }));
+    svc.oneshot(req).await.unwrap()
+}
+
+fn config(overwrite: bool) -> Option<Config> {
+    Some(Config {
+        header: HeaderName::from_static(HEADER),
+        overwrite,
+    })
+}
+
+#[tokio::test]
+async fn generates_missing_id() {
+    let req = send(config(false), http::Request::new(())).await;
+
+    let value = req.headers().get(HEADER).expect("request must have an ID");
+    assert_eq!(value.len(), 26);
+    let id = req
+        .extensions()
+        .get::<RequestId>()
+        .expect("request must have an ID extension");
+    assert_eq!(id.header(), HEADER);
+    assert_eq!(id.value(), value);
+}
+
+#[tokio::test]
+async fn preserves_client_id() {
+    let req = http::Request::builder()
+        .header(HEADER, "client-id")
+        .body(())
+        .unwrap();
+    let req = send(config(false), req).await;
+
+    assert_eq!(req.headers().get(HEADER).unwrap(), "client-id");
+    assert_eq!(
+        req.extensions().get::<RequestId>().unwrap().to_string(),
+        "client-id"
+    );
+}
+
+#[tokio::test]
+async fn overwrites_client_id() {
+    let req = http::Request::builder()
+        .header(HEADER, "client-id")
+        .header(HEADER, "other-client-id")
+        .body(())
+        .unwrap();
+    let req = send(config(true), req).await;
+
+    let values = req.headers().get_all(HEADER).iter().collect::<Vec<_>>();
+    assert_eq!(values.len(), 1, "client values must be replaced");
+    assert_ne!(values[0], "client-id");
+    assert_eq!(
+        req.extensions().get::<RequestId>().unwrap().value(),
+        values[0]
+    );
+}
+
+#[tokio::test]
+async fn disabled() {
+    let req = send(None, http::Request::new(())).await;
+
+    assert!(req.headers().get(HEADER).is_none());
+    assert!(req.extensions().get::<RequestId>().is_none());
+}
+
+#[test]
+fn ulid_encoding() {
+    assert_eq!(ulid(0, 0), "00000000000000000000000000");
+    assert_eq!(ulid(u64::MAX, u128::MAX), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+    // A known ULID from the spec's examples.
+    assert_eq!(
+        ulid(1469918176385, 0xd676_4c61_efb9_9302_bd5b),
+        "01ARYZ6S41TSV4RRFFQ69G5FAV"
+    );
+
+    // IDs sort by timestamp.
+    let earlier = ulid(1_000, u128::MAX);
+    let later = ulid(1_001, 0);
+    assert!(earlier.as_bytes() < later.as_bytes());
+}
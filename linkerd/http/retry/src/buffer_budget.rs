@@ -0,0 +1,106 @@
+use http_body::SizeHint;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A budget, in bytes, shared by all retryable requests to buffer their bodies.
+///
+/// Each retryable request reserves the number of bytes it may buffer for the
+/// lifetime of its body: the body's exact length, if it is known, or the
+/// per-request limit otherwise. When the budget can't accommodate a request,
+/// the request is sent without being retryable.
+#[derive(Clone, Debug, Default)]
+pub struct BufferBudget(Option<Arc<Semaphore>>);
+
+/// Bytes reserved from a [`BufferBudget`], returned when dropped.
+#[derive(Debug)]
+pub(crate) struct Reservation(#[allow(dead_code)] Option<OwnedSemaphorePermit>);
+
+// === impl BufferBudget ===
+
+impl BufferBudget {
+    pub fn new(max_bytes: usize) -> Self {
+        let permits = max_bytes.min(Semaphore::MAX_PERMITS);
+        Self(Some(Arc::new(Semaphore::new(permits))))
+    }
+
+    /// Returns a budget that never limits buffering.
+    pub fn unlimited() -> Self {
+        Self(None)
+    }
+
+    /// Returns the number of bytes that may currently be reserved, if the
+    /// budget is limited.
+    pub fn available(&self) -> Option<usize> {
+        self.0.as_ref().map(|s| s.available_permits())
+    }
+
+    /// Attempts to reserve enough of the budget to buffer a body with the
+    /// given size hint, up to `max_bytes`.
+    pub(crate) fn try_reserve(&self, hint: &SizeHint, max_bytes: usize) -> Option<Reservation> {
+        let Some(semaphore) = self.0.as_ref() else {
+            return Some(Reservation(None));
+        };
+
+        let bytes = hint
+            .upper()
+            .map_or(max_bytes, |upper| upper.min(max_bytes as u64) as usize);
+        let permits = u32::try_from(bytes).ok()?;
+        let permit = semaphore.clone().try_acquire_many_owned(permits).ok()?;
+        Some(Reservation(Some(permit)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserves_known_lengths() {
+        let budget = BufferBudget::new(100);
+
+        let r0 = budget
+            .try_reserve(&SizeHint::with_exact(60), 1000)
+            .expect("must reserve");
+        assert_eq!(budget.available(), Some(40));
+        assert!(
+            budget
+                .try_reserve(&SizeHint::with_exact(60), 1000)
+                .is_none(),
+            "must not exceed the budget"
+        );
+
+        let _r1 = budget
+            .try_reserve(&SizeHint::with_exact(0), 1000)
+            .expect("empty bodies must always be reserved");
+        let _r2 = budget
+            .try_reserve(&SizeHint::with_exact(40), 1000)
+            .expect("must reserve");
+        assert_eq!(budget.available(), Some(0));
+
+        drop(r0);
+        assert_eq!(budget.available(), Some(60));
+    }
+
+    #[test]
+    fn reserves_limit_for_unknown_lengths() {
+        let budget = BufferBudget::new(100);
+        let _r = budget
+            .try_reserve(&SizeHint::default(), 30)
+            .expect("must reserve");
+        assert_eq!(budget.available(), Some(70));
+
+        let _r = budget
+            .try_reserve(&SizeHint::with_exact(1000), 30)
+            .expect("must reserve");
+        assert_eq!(budget.available(), Some(40));
+    }
+
+    #[test]
+    fn unlimited() {
+        let budget = BufferBudget::unlimited();
+        assert!(budget
+            .try_reserve(&SizeHint::default(), usize::MAX)
+            .is_some());
+        assert_eq!(budget.available(), None);
+    }
+}
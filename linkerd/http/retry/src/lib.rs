@@ -1,18 +1,23 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
+mod buffer_budget;
 pub mod peek_trailers;
 pub mod replay;
+#[cfg(test)]
+mod tests;
 
-pub use self::{peek_trailers::PeekTrailersBody, replay::ReplayBody};
+pub use self::{buffer_budget::BufferBudget, peek_trailers::PeekTrailersBody, replay::ReplayBody};
 pub use tower::retry::budget::Budget;
 
 use futures::{future, prelude::*};
+use http_body::Body;
 use linkerd_error::{Error, Result};
 use linkerd_exp_backoff::ExponentialBackoff;
 use linkerd_http_box::BoxBody;
 use linkerd_metrics::prom;
 use linkerd_stack::{layer, ExtractParam, NewService, Param, Service};
+use linkerd_trace_context::SpanRecorder;
 use std::{
     future::Future,
     hash::Hash,
@@ -20,6 +25,7 @@ use std::{
     pin::Pin,
     task::{Context, Poll},
 };
+use tokio::time;
 use tower::ServiceExt;
 use tracing::{debug, trace};
 
@@ -50,6 +56,7 @@ pub struct Params {
 pub struct NewHttpRetry<P, L: Clone, X, ReqX, N> {
     inner: N,
     metrics: MetricFamilies<L>,
+    budget: BufferBudget,
     extract: X,
     _marker: PhantomData<fn() -> (ReqX, P)>,
 }
@@ -61,6 +68,7 @@ pub struct NewHttpRetry<P, L: Clone, X, ReqX, N> {
 pub struct HttpRetry<P, L: Clone, ReqX, S> {
     inner: S,
     metrics: MetricFamilies<L>,
+    budget: BufferBudget,
     extract: ReqX,
     _marker: PhantomData<fn() -> P>,
 }
@@ -69,16 +77,20 @@ pub struct HttpRetry<P, L: Clone, ReqX, S> {
 pub struct MetricFamilies<L: Clone> {
     limit_exceeded: prom::Family<L, prom::Counter>,
     overflow: prom::Family<L, prom::Counter>,
+    buffer_exhausted: prom::Family<L, prom::Counter>,
     requests: prom::Family<L, prom::Counter>,
     successes: prom::Family<L, prom::Counter>,
+    attempts: prom::Family<L, prom::Histogram, fn() -> prom::Histogram>,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 struct Metrics {
     requests: prom::Counter,
     successes: prom::Counter,
     limit_exceeded: prom::Counter,
     overflow: prom::Counter,
+    buffer_exhausted: prom::Counter,
+    attempts: prom::Histogram,
 }
 
 // === impl NewHttpRetry ===
@@ -86,8 +98,9 @@ struct Metrics {
 impl<P, L: Clone, ReqX, N> NewHttpRetry<P, L, (), ReqX, N> {
     pub fn layer(
         metrics: MetricFamilies<L>,
+        budget: BufferBudget,
     ) -> impl tower::layer::Layer<N, Service = Self> + Clone {
-        Self::layer_via_mk((), metrics)
+        Self::layer_via_mk((), metrics, budget)
     }
 }
 
@@ -95,11 +108,13 @@ impl<P, L: Clone, X: Clone, ReqX, N> NewHttpRetry<P, L, X, ReqX, N> {
     pub fn layer_via_mk(
         extract: X,
         metrics: MetricFamilies<L>,
+        budget: BufferBudget,
     ) -> impl tower::layer::Layer<N, Service = Self> + Clone {
         layer::mk(move |inner| Self {
             inner,
             extract: extract.clone(),
             metrics: metrics.clone(),
+            budget: budget.clone(),
             _marker: PhantomData,
         })
     }
@@ -118,17 +133,20 @@ where
         let Self {
             inner,
             metrics,
+            budget,
             extract,
             _marker,
         } = self;
 
         let metrics = metrics.clone();
+        let budget = budget.clone();
         let extract = extract.extract_param(&target);
         let svc = inner.new_service(target);
 
         HttpRetry {
             inner: svc,
             metrics,
+            budget,
             extract,
             _marker: PhantomData,
         }
@@ -145,8 +163,10 @@ where
         Self {
             limit_exceeded: prom::Family::default(),
             overflow: prom::Family::default(),
+            buffer_exhausted: prom::Family::default(),
             requests: prom::Family::default(),
             successes: prom::Family::default(),
+            attempts: prom::Family::new_with_constructor(mk_attempts as fn() -> _),
         }
     }
 }
@@ -170,6 +190,13 @@ where
             overflow.clone(),
         );
 
+        let buffer_exhausted = prom::Family::default();
+        registry.register(
+            "buffer_exhausted",
+            "Retryable requests not retried because the body buffering budget was exhausted",
+            buffer_exhausted.clone(),
+        );
+
         let requests = prom::Family::default();
         registry.register("requests", "Retry requests emitted", requests.clone());
 
@@ -179,11 +206,21 @@ where
             "Successful responses to retry requests",
             successes.clone(),
         );
+
+        let attempts = prom::Family::new_with_constructor(mk_attempts as fn() -> _);
+        registry.register(
+            "attempts",
+            "The number of attempts made for each retryable request",
+            attempts.clone(),
+        );
+
         Self {
             limit_exceeded,
             overflow,
+            buffer_exhausted,
             requests,
             successes,
+            attempts,
         }
     }
 
@@ -192,15 +229,23 @@ where
         let successes = (*self.successes.get_or_create(labels)).clone();
         let limit_exceeded = (*self.limit_exceeded.get_or_create(labels)).clone();
         let overflow = (*self.overflow.get_or_create(labels)).clone();
+        let buffer_exhausted = (*self.buffer_exhausted.get_or_create(labels)).clone();
+        let attempts = (*self.attempts.get_or_create(labels)).clone();
         Metrics {
             requests,
             successes,
             limit_exceeded,
             overflow,
+            buffer_exhausted,
+            attempts,
         }
     }
 }
 
+fn mk_attempts() -> prom::Histogram {
+    prom::Histogram::new([1.0, 2.0, 3.0, 4.0, 5.0, 10.0])
+}
+
 // === impl HttpRetry ===
 
 impl<P, L, ReqX, S> Service<http::Request<BoxBody>> for HttpRetry<P, L, ReqX, S>
@@ -246,10 +291,19 @@ where
 
         // Since this request is retryable, we need to setup the request body to
         // be buffered/cloneable. If the request body is too large to be cloned,
-        // the retry policy is ignored.
+        // or if the proxy is already buffering too many bodies, the retry
+        // policy is ignored.
         let req = {
             let (head, body) = req.into_parts();
-            match ReplayBody::try_new(body, params.max_request_bytes) {
+            let max_bytes = params.max_request_bytes;
+            let Some(reservation) = self.budget.try_reserve(&body.size_hint(), max_bytes) else {
+                debug!(retryable = false, "Retry buffering budget exhausted");
+                metrics.buffer_exhausted.inc();
+                return future::Either::Left(
+                    self.inner.call(http::Request::from_parts(head, body)),
+                );
+            };
+            match ReplayBody::try_new_reserved(body, max_bytes, Some(reservation)) {
                 Ok(body) => http::Request::from_parts(head, body),
                 Err(body) => {
                     debug!(retryable = false, "Request body is too large to be retried");
@@ -281,17 +335,22 @@ async fn send_req_with_retries(
     metrics: Metrics,
     params: Params,
 ) -> Result<http::Response<BoxBody>> {
+    // Retries are recorded on the request's span, if it is sampled.
+    let recorder = SpanRecorder::get(&request).cloned();
+
     // Initial request.
     let mut backup = mk_backup(&request, &policy);
     let mut result = send_req(&mut svc, request).await;
     if !policy.is_retryable(result.as_ref()) {
         tracing::trace!("Success on first attempt");
+        metrics.attempts.observe(1.0);
         return result.map(|rsp| rsp.map(BoxBody::new));
     }
     if matches!(backup.body().is_capped(), None | Some(true)) {
         // The body was either too large, or we received an early response
         // before the request body was completed read. We cannot safely
         // attempt to send this request again.
+        metrics.attempts.observe(1.0);
         return result.map(|rsp| rsp.map(BoxBody::new));
     }
 
@@ -299,6 +358,9 @@ async fn send_req_with_retries(
     // requests.
     let mut backoff = params.backoff.map(|b| b.stream());
     for n in 1..=params.max_retries {
+        let sampled = recorder
+            .as_ref()
+            .map(|_| (retry_reason(&result), time::Instant::now()));
         if let Some(backoff) = backoff.as_mut() {
             backoff.next().await;
         }
@@ -308,10 +370,27 @@ async fn send_req_with_retries(
         let Some(svc) = svc.ready().now_or_never().transpose()? else {
             tracing::debug!("Retry overflow; service is not ready");
             metrics.overflow.inc();
+            metrics.attempts.observe(n as f64);
             return result.map(|rsp| rsp.map(BoxBody::new));
         };
 
         tracing::debug!(retry.attempt = n);
+        if let (Some(recorder), Some((reason, backoff_start))) = (recorder.as_ref(), sampled) {
+            recorder.add_event(
+                "retry",
+                vec![
+                    ("retry.attempt", n.to_string()),
+                    (
+                        "retry.backoff_ms",
+                        time::Instant::now()
+                            .saturating_duration_since(backoff_start)
+                            .as_millis()
+                            .to_string(),
+                    ),
+                    ("retry.reason", reason),
+                ],
+            );
+        }
         let request = backup;
         backup = mk_backup(&request, &policy);
         metrics.requests.inc();
@@ -321,9 +400,11 @@ async fn send_req_with_retries(
                 metrics.successes.inc();
             }
             tracing::debug!("Retry success");
+            metrics.attempts.observe((n + 1) as f64);
             return result.map(|rsp| rsp.map(BoxBody::new));
         }
         if matches!(backup.body().is_capped(), None | Some(true)) {
+            metrics.attempts.observe((n + 1) as f64);
             return result.map(|rsp| rsp.map(BoxBody::new));
         }
     }
@@ -331,6 +412,7 @@ async fn send_req_with_retries(
     // The result is retryable but we've run out of attempts.
     tracing::debug!("Retry limit exceeded");
     metrics.limit_exceeded.inc();
+    metrics.attempts.observe((params.max_retries + 1) as f64);
     result.map(|rsp| rsp.map(BoxBody::new))
 }
 
@@ -349,6 +431,24 @@ async fn send_req(
         .await
 }
 
+/// Describes why a result is retried.
+fn retry_reason(result: &Result<http::Response<PeekTrailersBody>>) -> String {
+    match result {
+        Ok(rsp) => {
+            let grpc_status = rsp
+                .headers()
+                .get("grpc-status")
+                .or_else(|| rsp.body().peek_trailers()?.get("grpc-status"))
+                .and_then(|v| v.to_str().ok());
+            match grpc_status {
+                Some(code) => format!("grpc-status {code}"),
+                None => format!("status {}", rsp.status().as_u16()),
+            }
+        }
+        Err(error) => error.to_string(),
+    }
+}
+
 fn mk_backup(orig: &http::Request<ReplayBody>, policy: &impl Policy) -> http::Request<ReplayBody> {
     let mut dst = http::Request::new(orig.body().clone());
     *dst.method_mut() = orig.method().clone();
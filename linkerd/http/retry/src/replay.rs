@@ -1,3 +1,4 @@
+use crate::buffer_budget::Reservation;
 use bytes::Buf;
 use http::HeaderMap;
 use http_body::{Body, Frame, SizeHint};
@@ -62,6 +63,10 @@ struct SharedState<B> {
     was_empty: bool,
 
     orig_size_hint: SizeHint,
+
+    /// Holds a share of the buffering budget for as long as any clone of the
+    /// body may buffer data.
+    _reservation: Option<Reservation>,
 }
 
 #[derive(Debug)]
@@ -89,6 +94,16 @@ impl<B: Body> ReplayBody<B> {
     /// If the body has a size hint with a lower bound greater than `max_bytes`, the original body
     /// is returned in the error variant.
     pub fn try_new(body: B, max_bytes: usize) -> Result<Self, B> {
+        Self::try_new_reserved(body, max_bytes, None)
+    }
+
+    /// Like [`ReplayBody::try_new`], but holds a reservation from a
+    /// [`BufferBudget`](crate::BufferBudget) until all clones are dropped.
+    pub(crate) fn try_new_reserved(
+        body: B,
+        max_bytes: usize,
+        reservation: Option<Reservation>,
+    ) -> Result<Self, B> {
         let orig_size_hint = body.size_hint();
         tracing::trace!(body.size_hint = %orig_size_hint.lower(), %max_bytes);
         if orig_size_hint.lower() > max_bytes as u64 {
@@ -100,6 +115,7 @@ impl<B: Body> ReplayBody<B> {
                 body: Mutex::new(None),
                 orig_size_hint,
                 was_empty: body.is_end_stream(),
+                _reservation: reservation,
             }),
             state: Some(BodyState {
                 replay: Default::default(),
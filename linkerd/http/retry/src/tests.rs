@@ -0,0 +1,131 @@
+//! Unit tests for [`HttpRetry`].
+
+use super::*;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use tokio::sync::Semaphore;
+
+#[derive(Clone, Debug)]
+struct RetryServerErrors(Params);
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Clone, Debug)]
+struct ExtractLabels;
+
+impl Policy for RetryServerErrors {
+    type Future = future::Ready<()>;
+
+    fn is_retryable(&self, res: Result<&http::Response<PeekTrailersBody>, &Error>) -> bool {
+        res.map_or(true, |rsp| rsp.status().is_server_error())
+    }
+}
+
+impl Param<Params> for RetryServerErrors {
+    fn param(&self) -> Params {
+        self.0.clone()
+    }
+}
+
+impl ExtractParam<Labels, http::Request<BoxBody>> for ExtractLabels {
+    fn extract_param(&self, _: &http::Request<BoxBody>) -> Labels {
+        labels()
+    }
+}
+
+/// Concurrent retryable requests with bodies that, together, exceed the
+/// global budget are all proxied, but only those that fit in the budget may be
+/// retried.
+#[tokio::test(flavor = "current_thread")]
+async fn budget_limits_concurrent_retryable_bodies() {
+    let _trace = linkerd_tracing::test::trace_init();
+
+    const BODY_LEN: usize = 1000;
+    const REQUESTS: usize = 5;
+    const BUDGETED: usize = 3;
+
+    let budget = BufferBudget::new(BUDGETED * BODY_LEN);
+    let metrics = MetricFamilies::<Labels>::default();
+
+    // Responses are withheld until all of the initial requests have been
+    // dispatched, so that their bodies are buffered concurrently.
+    let gate = Arc::new(Semaphore::new(0));
+    let calls = Arc::new(AtomicUsize::new(0));
+    let inner = {
+        let gate = gate.clone();
+        let calls = calls.clone();
+        tower::service_fn(move |req: http::Request<BoxBody>| {
+            let gate = gate.clone();
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let body = req.into_body().collect().await?.to_bytes();
+                assert_eq!(body.len(), BODY_LEN, "the full body must be sent");
+                let _permit = gate.acquire().await?;
+                let rsp = http::Response::builder()
+                    .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(BoxBody::empty())?;
+                Ok::<_, Error>(rsp)
+            }
+        })
+    };
+    let svc = HttpRetry::<RetryServerErrors, _, _, _> {
+        inner,
+        metrics: metrics.clone(),
+        budget: budget.clone(),
+        extract: ExtractLabels,
+        _marker: PhantomData,
+    };
+
+    let rsps = (0..REQUESTS)
+        .map(|_| {
+            let mut req =
+                http::Request::new(BoxBody::new(Full::new(Bytes::from(vec![0u8; BODY_LEN]))));
+            req.extensions_mut().insert(RetryServerErrors(Params {
+                max_retries: 1,
+                max_request_bytes: BODY_LEN,
+                backoff: None,
+            }));
+            let mut svc = svc.clone();
+            tokio::spawn(async move { svc.ready().await?.call(req).await })
+        })
+        .collect::<Vec<_>>();
+
+    while calls.load(Ordering::SeqCst) < REQUESTS {
+        tokio::task::yield_now().await;
+    }
+    assert_eq!(budget.available(), Some(0), "the budget must be exhausted");
+    gate.add_permits(REQUESTS * 2);
+
+    for rsp in rsps {
+        let rsp = rsp.await.unwrap().expect("request must be proxied");
+        assert_eq!(rsp.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    let labels = labels();
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        REQUESTS + BUDGETED,
+        "only requests within the budget may be retried"
+    );
+    assert_eq!(
+        metrics.buffer_exhausted.get_or_create(&labels).get(),
+        (REQUESTS - BUDGETED) as u64
+    );
+    assert_eq!(
+        metrics.requests.get_or_create(&labels).get(),
+        BUDGETED as u64
+    );
+    assert_eq!(
+        budget.available(),
+        Some(BUDGETED * BODY_LEN),
+        "the budget must be restored once requests complete"
+    );
+}
+
+fn labels() -> Labels {
+    vec![("route".to_string(), "test".to_string())]
+}
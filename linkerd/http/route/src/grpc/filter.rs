@@ -1,3 +1,7 @@
+pub mod direct_response;
 pub mod inject_failure;
 
-pub use self::inject_failure::{Distribution, FailureResponse, InjectFailure};
+pub use self::{
+    direct_response::DirectResponse,
+    inject_failure::{Distribution, FailureResponse, InjectFailure},
+};
@@ -0,0 +1,7 @@
+/// A filter that responds to gRPC requests with a trailers-only response,
+/// without forwarding them to a backend.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct DirectResponse {
+    pub code: u16,
+    pub message: std::sync::Arc<str>,
+}
@@ -38,7 +38,8 @@ pub(crate) struct RpcMatch {
 impl crate::Match for MatchRoute {
     type Summary = RouteMatch;
 
-    fn match_request<B>(&self, req: &http::Request<B>) -> Option<RouteMatch> {
+    fn match_parsed<B>(&self, req: &crate::ParsedRequest<'_, B>) -> Option<RouteMatch> {
+        let req = req.request();
         if req.method() != http::Method::POST {
             return None;
         }
@@ -1,11 +1,19 @@
+pub mod assert_workload_identity;
+pub mod decompress_request;
+pub mod direct_response;
 pub mod inject_failure;
 pub mod modify_header;
 pub mod redirect;
+pub mod rewrite_authority;
 
 pub use self::{
+    assert_workload_identity::AssertWorkloadIdentity,
+    decompress_request::DecompressRequest,
+    direct_response::{DirectResponse, DirectResponseTooLarge},
     inject_failure::{Distribution, FailureResponse, InjectFailure},
     modify_header::ModifyHeader,
     redirect::{InvalidRedirect, RedirectRequest, Redirection},
+    rewrite_authority::{OriginalAuthority, RewriteAuthority},
 };
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -0,0 +1,8 @@
+/// A filter that sets a signed assertion of the local workload's identity on
+/// requests, so that intermediaries that terminate and re-originate
+/// connections (e.g. egress gateways) can authorize requests by the identity
+/// of the workload that sent them.
+///
+/// Assertions are signed with a key from the proxy's configuration.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct AssertWorkloadIdentity;
@@ -0,0 +1,67 @@
+use http::header::{HeaderMap, CONTENT_ENCODING};
+
+/// A filter that decompresses gzip-encoded request bodies before they are
+/// forwarded, for upstreams that cannot handle compressed requests.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct DecompressRequest {
+    /// The maximum size of a decompressed request body. Larger requests are
+    /// failed.
+    pub max_bytes: usize,
+}
+
+// === impl DecompressRequest ===
+
+impl DecompressRequest {
+    /// The default maximum size of a decompressed request body.
+    pub const DEFAULT_MAX_BYTES: usize = 10 * 1024 * 1024;
+
+    /// Returns true if the request headers indicate a gzip-encoded body that
+    /// should be decompressed.
+    pub fn applies(&self, headers: &HeaderMap) -> bool {
+        // Only a single gzip coding is supported. Bodies with multiple (or
+        // other) codings are forwarded as-is.
+        let mut codings = headers.get_all(CONTENT_ENCODING).iter();
+        match (codings.next(), codings.next()) {
+            (Some(coding), None) => coding
+                .to_str()
+                .map(|c| {
+                    let c = c.trim();
+                    c.eq_ignore_ascii_case("gzip") || c.eq_ignore_ascii_case("x-gzip")
+                })
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+impl Default for DecompressRequest {
+    fn default() -> Self {
+        Self {
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    #[test]
+    fn applies_to_gzip() {
+        let filter = DecompressRequest { max_bytes: 1024 };
+        let applies = |codings: &[&'static str]| {
+            let mut headers = HeaderMap::new();
+            for c in codings {
+                headers.append(CONTENT_ENCODING, HeaderValue::from_static(c));
+            }
+            filter.applies(&headers)
+        };
+        assert!(applies(&["gzip"]));
+        assert!(applies(&["X-GZIP"]));
+        assert!(!applies(&[]));
+        assert!(!applies(&["br"]));
+        assert!(!applies(&["gzip, br"]));
+        assert!(!applies(&["gzip", "br"]));
+    }
+}
@@ -0,0 +1,68 @@
+use bytes::Bytes;
+use http::header::{HeaderName, HeaderValue};
+
+/// A filter that responds to requests without forwarding them to a backend,
+/// e.g. to serve a maintenance page.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct DirectResponse {
+    pub status: http::StatusCode,
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    body: Bytes,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("direct response body of {0} bytes exceeds {max} bytes", max = DirectResponse::MAX_BODY_BYTES)]
+pub struct DirectResponseTooLarge(pub usize);
+
+// === impl DirectResponse ===
+
+impl DirectResponse {
+    /// The largest body that may be served by a direct response.
+    pub const MAX_BODY_BYTES: usize = 8 * 1024;
+
+    pub fn new(
+        status: http::StatusCode,
+        headers: Vec<(HeaderName, HeaderValue)>,
+        body: impl Into<Bytes>,
+    ) -> Result<Self, DirectResponseTooLarge> {
+        let body = body.into();
+        if body.len() > Self::MAX_BODY_BYTES {
+            return Err(DirectResponseTooLarge(body.len()));
+        }
+        Ok(Self {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    #[inline]
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps_body_size() {
+        let ok = DirectResponse::new(
+            http::StatusCode::SERVICE_UNAVAILABLE,
+            vec![],
+            vec![b'a'; DirectResponse::MAX_BODY_BYTES],
+        );
+        assert!(ok.is_ok());
+
+        let err = DirectResponse::new(
+            http::StatusCode::SERVICE_UNAVAILABLE,
+            vec![],
+            vec![b'a'; DirectResponse::MAX_BODY_BYTES + 1],
+        );
+        assert!(matches!(
+            err,
+            Err(DirectResponseTooLarge(n)) if n == DirectResponse::MAX_BODY_BYTES + 1
+        ));
+    }
+}
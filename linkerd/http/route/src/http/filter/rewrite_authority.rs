@@ -0,0 +1,193 @@
+use http::{
+    header::{HeaderMap, HeaderValue, HOST, LOCATION},
+    uri::{Authority, Uri},
+};
+
+/// A filter that rewrites a request's authority to a canonical name, e.g. so
+/// that requests to a vanity hostname are forwarded to the in-cluster service
+/// that serves it.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct RewriteAuthority {
+    /// The authority with which requests are forwarded.
+    pub authority: Authority,
+
+    /// Whether redirect responses that refer to the canonical authority are
+    /// mapped back to the request's original authority.
+    pub rewrite_location: bool,
+}
+
+/// The authority with which a request was sent, before it was rewritten by a
+/// [`RewriteAuthority`] filter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OriginalAuthority(pub Authority);
+
+// === impl RewriteAuthority ===
+
+impl RewriteAuthority {
+    /// Rewrites the request's URI and `host` header to the canonical
+    /// authority.
+    ///
+    /// Returns the request's original authority, if it had one and it differs
+    /// from the canonical authority.
+    pub fn apply<B>(&self, req: &mut http::Request<B>) -> Option<OriginalAuthority> {
+        let original = req
+            .uri()
+            .authority()
+            .cloned()
+            .or_else(|| {
+                let host = req.headers().get(HOST)?.to_str().ok()?;
+                host.parse::<Authority>().ok()
+            })
+            .filter(|a| a != &self.authority);
+
+        let mut parts = req.uri().clone().into_parts();
+        if parts.authority.is_some() {
+            parts.authority = Some(self.authority.clone());
+            match Uri::from_parts(parts) {
+                Ok(uri) => *req.uri_mut() = uri,
+                Err(error) => tracing::debug!(%error, "Failed to rewrite the request URI"),
+            }
+        }
+        if req.headers().contains_key(HOST) {
+            if let Ok(host) = HeaderValue::from_str(self.authority.as_str()) {
+                req.headers_mut().insert(HOST, host);
+            }
+        }
+
+        original.map(OriginalAuthority)
+    }
+
+    /// Maps a redirect's `location` header back to the original authority if
+    /// it refers to the canonical authority.
+    pub fn restore_location(
+        &self,
+        status: http::StatusCode,
+        headers: &mut HeaderMap,
+        OriginalAuthority(original): &OriginalAuthority,
+    ) {
+        if !self.rewrite_location || !status.is_redirection() {
+            return;
+        }
+        let Some(location) = headers
+            .get(LOCATION)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|l| l.parse::<Uri>().ok())
+        else {
+            return;
+        };
+        if !location.authority().is_some_and(|a| self.is_canonical(a)) {
+            return;
+        }
+
+        let mut parts = location.into_parts();
+        parts.authority = Some(original.clone());
+        let Some(location) = Uri::from_parts(parts)
+            .ok()
+            .and_then(|uri| HeaderValue::from_str(&uri.to_string()).ok())
+        else {
+            return;
+        };
+        headers.insert(LOCATION, location);
+    }
+
+    /// Returns true if the authority refers to the canonical authority. If the
+    /// canonical authority has no port, authorities with any port match.
+    fn is_canonical(&self, authority: &Authority) -> bool {
+        authority.host().eq_ignore_ascii_case(self.authority.host())
+            && self
+                .authority
+                .port_u16()
+                .is_none_or(|port| authority.port_u16() == Some(port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(rewrite_location: bool) -> RewriteAuthority {
+        RewriteAuthority {
+            authority: Authority::from_static("api.prod.svc.cluster.local:8080"),
+            rewrite_location,
+        }
+    }
+
+    #[test]
+    fn rewrites_uri_and_host() {
+        let mut req = http::Request::get("http://api.internal.example.com/users?id=1")
+            .header(HOST, "api.internal.example.com")
+            .body(())
+            .unwrap();
+        let original = filter(false).apply(&mut req);
+        assert_eq!(
+            original,
+            Some(OriginalAuthority(Authority::from_static(
+                "api.internal.example.com"
+            )))
+        );
+        assert_eq!(
+            req.uri(),
+            "http://api.prod.svc.cluster.local:8080/users?id=1"
+        );
+        assert_eq!(req.headers()[HOST], "api.prod.svc.cluster.local:8080");
+    }
+
+    #[test]
+    fn rewrites_origin_form_host() {
+        let mut req = http::Request::get("/users")
+            .header(HOST, "api.internal.example.com")
+            .body(())
+            .unwrap();
+        let original = filter(false).apply(&mut req);
+        assert_eq!(
+            original,
+            Some(OriginalAuthority(Authority::from_static(
+                "api.internal.example.com"
+            )))
+        );
+        assert_eq!(req.uri(), "/users");
+        assert_eq!(req.headers()[HOST], "api.prod.svc.cluster.local:8080");
+    }
+
+    #[test]
+    fn restores_location() {
+        let original = OriginalAuthority(Authority::from_static("api.internal.example.com"));
+        let restore = |filter: RewriteAuthority, status, location: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(LOCATION, HeaderValue::from_static(location));
+            filter.restore_location(status, &mut headers, &original);
+            headers[LOCATION].to_str().unwrap().to_string()
+        };
+
+        assert_eq!(
+            restore(
+                filter(true),
+                http::StatusCode::FOUND,
+                "https://API.prod.svc.cluster.local:8080/login?next=%2F"
+            ),
+            "https://api.internal.example.com/login?next=%2F"
+        );
+        // Redirects to other authorities, other ports, or relative locations
+        // are not modified.
+        for location in [
+            "https://auth.example.com/login",
+            "https://api.prod.svc.cluster.local:9090/login",
+            "/login",
+        ] {
+            assert_eq!(
+                restore(filter(true), http::StatusCode::FOUND, location),
+                location
+            );
+        }
+        // Locations are only restored for redirects, when configured.
+        let location = "https://api.prod.svc.cluster.local:8080/login";
+        assert_eq!(
+            restore(filter(true), http::StatusCode::CREATED, location),
+            location
+        );
+        assert_eq!(
+            restore(filter(false), http::StatusCode::FOUND, location),
+            location
+        );
+    }
+}
@@ -1,3 +1,4 @@
+pub mod cookie;
 pub mod header;
 pub mod host;
 pub mod path;
@@ -7,6 +8,7 @@ mod tests;
 
 pub(crate) use self::path::PathMatch;
 pub use self::{
+    cookie::{Cookies, MatchCookie},
     header::MatchHeader,
     host::{HostMatch, InvalidHost, MatchHost},
     path::MatchPath,
@@ -19,6 +21,7 @@ pub struct MatchRequest {
     pub path: Option<MatchPath>,
     pub headers: Vec<MatchHeader>,
     pub query_params: Vec<MatchQueryParam>,
+    pub cookies: Vec<MatchCookie>,
     pub method: Option<http::Method>,
 }
 
@@ -28,9 +31,32 @@ pub struct RequestMatch {
     path_match: PathMatch,
     headers: usize,
     query_params: usize,
+    cookies: usize,
     method: bool,
 }
 
+/// Normalizes a configured method so that standard methods match regardless of
+/// how they are cased, e.g. `get` matches `GET` requests.
+///
+/// Extension methods are case-sensitive, per RFC 9110, and are not modified.
+pub fn normalize_method(method: http::Method) -> http::Method {
+    const STANDARD: [http::Method; 9] = [
+        http::Method::GET,
+        http::Method::HEAD,
+        http::Method::POST,
+        http::Method::PUT,
+        http::Method::DELETE,
+        http::Method::CONNECT,
+        http::Method::OPTIONS,
+        http::Method::TRACE,
+        http::Method::PATCH,
+    ];
+    STANDARD
+        .into_iter()
+        .find(|m| m.as_str().eq_ignore_ascii_case(method.as_str()))
+        .unwrap_or(method)
+}
+
 // === impl MatchRequest ===
 
 impl RequestMatch {
@@ -42,7 +68,8 @@ impl RequestMatch {
 impl crate::Match for MatchRequest {
     type Summary = RequestMatch;
 
-    fn match_request<B>(&self, req: &http::Request<B>) -> Option<RequestMatch> {
+    fn match_parsed<B>(&self, parsed: &crate::ParsedRequest<'_, B>) -> Option<RequestMatch> {
+        let req = parsed.request();
         let mut summary = RequestMatch::default();
 
         if let Some(method) = &self.method {
@@ -66,6 +93,14 @@ impl crate::Match for MatchRequest {
         }
         summary.query_params = self.query_params.len();
 
+        if !self.cookies.is_empty() {
+            let cookies = parsed.cookies();
+            if !self.cookies.iter().all(|c| c.is_match(cookies)) {
+                return None;
+            }
+            summary.cookies = self.cookies.len();
+        }
+
         Some(summary)
     }
 }
@@ -80,6 +115,7 @@ impl Default for RequestMatch {
             path_match: PathMatch::Prefix("/".len()),
             headers: 0,
             query_params: 0,
+            cookies: 0,
             method: false,
         }
     }
@@ -99,6 +135,7 @@ impl std::cmp::Ord for RequestMatch {
             .cmp(&other.path_match)
             .then_with(|| self.headers.cmp(&other.headers))
             .then_with(|| self.query_params.cmp(&other.query_params))
+            .then_with(|| self.cookies.cmp(&other.cookies))
             .then_with(|| self.method.cmp(&other.method))
     }
 }
@@ -135,16 +172,26 @@ pub mod proto {
                 .into_iter()
                 .map(|h| h.try_into())
                 .collect::<Result<Vec<_>, _>>()?;
-            let query_params = rm
+            let mut query_params = rm
                 .query_params
                 .into_iter()
                 .map(|h| h.try_into())
-                .collect::<Result<Vec<_>, _>>()?;
-            let method = rm.method.map(http::Method::try_from).transpose()?;
+                .collect::<Result<Vec<MatchQueryParam>, _>>()?;
+            // Per the Gateway API, only the first match for each parameter
+            // name is considered.
+            let mut names = std::collections::HashSet::new();
+            query_params.retain(|m| names.insert(m.name().to_string()));
+            let method = rm
+                .method
+                .map(http::Method::try_from)
+                .transpose()?
+                .map(normalize_method);
             Ok(MatchRequest {
                 path,
                 headers,
                 query_params,
+                // The policy API does not yet configure cookie matches.
+                cookies: vec![],
                 method,
             })
         }
@@ -0,0 +1,197 @@
+use http::header::{HeaderMap, COOKIE};
+use regex::Regex;
+use std::borrow::Cow;
+
+/// Matches a single cookie value.
+///
+/// Values are compared after they have been percent-decoded.
+#[derive(Clone, Debug)]
+pub enum MatchCookie {
+    Exact(String, String),
+    Prefix(String, String),
+    Regex(String, Regex),
+}
+
+/// The cookies set on a request.
+///
+/// All `cookie` headers are parsed into a single list of name/value pairs.
+/// Values are only copied when they must be percent-decoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cookies<'r>(Vec<(&'r str, Cow<'r, str>)>);
+
+// === impl MatchCookie ===
+
+impl MatchCookie {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Exact(n, _) | Self::Prefix(n, _) | Self::Regex(n, _) => n,
+        }
+    }
+
+    pub fn is_match(&self, cookies: &Cookies<'_>) -> bool {
+        cookies.get_all(self.name()).any(|v| match self {
+            Self::Exact(_, e) => v == e,
+            Self::Prefix(_, p) => v.starts_with(p.as_str()),
+            Self::Regex(_, re) => {
+                if let Some(m) = re.find(v) {
+                    // Check that the regex is anchored at the start and end of
+                    // the value.
+                    m.start() == 0 && m.end() == v.len()
+                } else {
+                    false
+                }
+            }
+        })
+    }
+}
+
+impl std::hash::Hash for MatchCookie {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Exact(n, s) | Self::Prefix(n, s) => {
+                n.hash(state);
+                s.hash(state)
+            }
+            Self::Regex(n, r) => {
+                n.hash(state);
+                r.as_str().hash(state);
+            }
+        }
+    }
+}
+
+impl std::cmp::Eq for MatchCookie {}
+
+impl std::cmp::PartialEq for MatchCookie {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Exact(n, s), Self::Exact(m, o)) => n == m && s == o,
+            (Self::Prefix(n, s), Self::Prefix(m, o)) => n == m && s == o,
+            (Self::Regex(n, s), Self::Regex(m, o)) => n == m && s.as_str() == o.as_str(),
+            _ => false,
+        }
+    }
+}
+
+// === impl Cookies ===
+
+impl<'r> Cookies<'r> {
+    /// Parses all of the `cookie` headers in a request.
+    ///
+    /// Malformed cookie pairs (and headers that are not valid UTF-8) are
+    /// ignored.
+    pub fn parse(headers: &'r HeaderMap) -> Self {
+        let cookies = headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|h| h.to_str().ok())
+            .flat_map(|h| h.split(';'))
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                let name = name.trim();
+                if name.is_empty() {
+                    return None;
+                }
+                let value = value.trim();
+                // Values may be enclosed in double quotes.
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                let value = percent_encoding::percent_decode_str(value)
+                    .decode_utf8()
+                    .unwrap_or(Cow::Borrowed(value));
+                Some((name, value))
+            })
+            .collect();
+        Self(cookies)
+    }
+
+    /// Returns the values of all cookies with the given name.
+    pub fn get_all<'c>(&'c self, name: &'c str) -> impl Iterator<Item = &'c str> + 'c {
+        self.0
+            .iter()
+            .filter(move |(n, _)| *n == name)
+            .map(|(_, v)| v.as_ref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header::HeaderValue;
+
+    fn cookies(values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for v in values {
+            headers.append(COOKIE, HeaderValue::from_static(v));
+        }
+        headers
+    }
+
+    #[test]
+    fn parses_multiple_headers() {
+        let headers = cookies(&["a=1; b=2", "c=3;d = 4 ", "malformed; =5; e=\"6\""]);
+        let cookies = Cookies::parse(&headers);
+        let get = |n| cookies.get_all(n).collect::<Vec<_>>();
+        assert_eq!(get("a"), vec!["1"]);
+        assert_eq!(get("b"), vec!["2"]);
+        assert_eq!(get("c"), vec!["3"]);
+        assert_eq!(get("d"), vec!["4"]);
+        assert_eq!(get("e"), vec!["6"]);
+        assert_eq!(get("malformed"), Vec::<&str>::new());
+        assert_eq!(get(""), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn decodes_values() {
+        let headers = cookies(&["arm=group%20b%3Bx; raw=%zz; plus=a+b"]);
+        let cookies = Cookies::parse(&headers);
+        assert_eq!(
+            cookies.get_all("arm").collect::<Vec<_>>(),
+            vec!["group b;x"]
+        );
+        // Invalid escapes are left as-is.
+        assert_eq!(cookies.get_all("raw").collect::<Vec<_>>(), vec!["%zz"]);
+        // Unlike form-encoding, `+` is not a space.
+        assert_eq!(cookies.get_all("plus").collect::<Vec<_>>(), vec!["a+b"]);
+    }
+
+    #[test]
+    fn decoding_only_copies_encoded_values() {
+        let headers = cookies(&["a=plain; b=en%63oded"]);
+        let Cookies(cookies) = Cookies::parse(&headers);
+        assert!(matches!(cookies[0].1, Cow::Borrowed("plain")));
+        assert!(matches!(cookies[1].1, Cow::Owned(ref v) if v == "encoded"));
+    }
+
+    #[test]
+    fn matches() {
+        let headers = cookies(&["session=abc", "arm=beta-2; arm=alpha"]);
+        let cookies = Cookies::parse(&headers);
+
+        let exact = MatchCookie::Exact("arm".into(), "alpha".into());
+        assert!(exact.is_match(&cookies));
+        let exact = MatchCookie::Exact("arm".into(), "beta".into());
+        assert!(!exact.is_match(&cookies));
+
+        let prefix = MatchCookie::Prefix("arm".into(), "beta-".into());
+        assert!(prefix.is_match(&cookies));
+        let prefix = MatchCookie::Prefix("session".into(), "beta-".into());
+        assert!(!prefix.is_match(&cookies));
+
+        let regex = MatchCookie::Regex("arm".into(), "beta-[0-9]".parse().unwrap());
+        assert!(regex.is_match(&cookies));
+        // Regexes must match the entire value.
+        let regex = MatchCookie::Regex("arm".into(), "beta".parse().unwrap());
+        assert!(!regex.is_match(&cookies));
+
+        let missing = MatchCookie::Exact("missing".into(), "".into());
+        assert!(!missing.is_match(&cookies));
+    }
+}
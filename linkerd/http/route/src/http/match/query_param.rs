@@ -10,22 +10,31 @@ pub enum MatchQueryParam {
 // === impl MatchQueryParam ===
 
 impl MatchQueryParam {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Exact(n, _) | Self::Regex(n, _) => n,
+        }
+    }
+
+    /// Matches the value of the first occurrence of the parameter in the
+    /// request's query string. Names and values are percent-decoded before
+    /// they are compared.
     pub fn is_match(&self, uri: &Uri) -> bool {
-        uri.query().is_some_and(|qs| {
-            url::form_urlencoded::parse(qs.as_bytes()).any(|(q, p)| match self {
-                Self::Exact(n, v) => *n == *q && *v == *p,
-                Self::Regex(n, r) => {
-                    if *n == *q {
-                        if let Some(m) = r.find(&p) {
-                            // Check that the regex is anchored at the start and
-                            // end of the value.
-                            return m.start() == 0 && m.end() == p.len();
-                        }
-                    }
-                    false
-                }
-            })
-        })
+        let Some(value) = uri.query().and_then(|qs| {
+            url::form_urlencoded::parse(qs.as_bytes())
+                .find(|(q, _)| q == self.name())
+                .map(|(_, p)| p)
+        }) else {
+            return false;
+        };
+        match self {
+            Self::Exact(_, v) => *v == *value,
+            // Check that the regex is anchored at the start and end of the
+            // value.
+            Self::Regex(_, r) => r
+                .find(&value)
+                .is_some_and(|m| m.start() == 0 && m.end() == value.len()),
+        }
     }
 }
 
@@ -117,7 +126,6 @@ mod tests {
         assert!(m.is_match(&"/?foo=ba&fah=bah".parse().unwrap()));
         assert!(m.is_match(&"/?foo=barr&fah=bah".parse().unwrap()));
         assert!(m.is_match(&"/?bar=foo&foo=bar".parse().unwrap()));
-        assert!(m.is_match(&"/?foo=bah&foo=bar".parse().unwrap()));
         assert!(!m.is_match(&"/?foo=bah".parse().unwrap()));
         assert!(!m.is_match(&"/?bar=foo".parse().unwrap()));
         assert!(!m.is_match(&"/?foo=barro".parse().unwrap()));
@@ -129,4 +137,29 @@ mod tests {
         let m = MatchQueryParam::Regex("foo".to_string(), ".*".parse().unwrap());
         assert!(m.is_match(&"/?foo".parse().unwrap()));
     }
+
+    #[test]
+    fn query_param_repeated() {
+        // Only the first occurrence of a parameter is matched.
+        let m = MatchQueryParam::Exact("foo".to_string(), "bar".to_string());
+        assert!(m.is_match(&"/?foo=bar&foo=bah".parse().unwrap()));
+        assert!(!m.is_match(&"/?foo=bah&foo=bar".parse().unwrap()));
+        assert!(m.is_match(&"/?fah=bah&foo=bar&foo=bah".parse().unwrap()));
+
+        let m = MatchQueryParam::Regex("foo".to_string(), "bar*".parse().unwrap());
+        assert!(m.is_match(&"/?foo=barr&foo=bah".parse().unwrap()));
+        assert!(!m.is_match(&"/?foo=bah&foo=bar".parse().unwrap()));
+    }
+
+    #[test]
+    fn query_param_encoded() {
+        let m = MatchQueryParam::Exact("foo bar".to_string(), "a/b c".to_string());
+        assert!(m.is_match(&"/?foo%20bar=a%2Fb%20c".parse().unwrap()));
+        assert!(m.is_match(&"/?foo+bar=a%2fb+c".parse().unwrap()));
+        assert!(!m.is_match(&"/?foo%20bar=a%2Fb%2520c".parse().unwrap()));
+
+        let m = MatchQueryParam::Regex("foo".to_string(), "[a-z]+/[0-9]+".parse().unwrap());
+        assert!(m.is_match(&"/?f%6Fo=ab%2F12".parse().unwrap()));
+        assert!(!m.is_match(&"/?foo=ab%2F12%26".parse().unwrap()));
+    }
 }
@@ -44,6 +44,41 @@ fn method() {
     assert_eq!(m.match_request(&req), None);
 }
 
+#[test]
+fn extension_method() {
+    let purge = http::Method::from_bytes(b"PURGE").unwrap();
+    let m = MatchRequest {
+        method: Some(normalize_method(purge.clone())),
+        ..MatchRequest::default()
+    };
+
+    let req = http::Request::builder().method(purge).body(()).unwrap();
+    assert_eq!(
+        m.match_request(&req),
+        Some(RequestMatch {
+            method: true,
+            ..Default::default()
+        })
+    );
+
+    // Extension methods are case-sensitive.
+    let req = http::Request::builder()
+        .method(http::Method::from_bytes(b"purge").unwrap())
+        .body(())
+        .unwrap();
+    assert_eq!(m.match_request(&req), None);
+}
+
+#[test]
+fn normalizes_standard_methods() {
+    let get = normalize_method(http::Method::from_bytes(b"get").unwrap());
+    assert_eq!(get, http::Method::GET);
+    assert_eq!(normalize_method(http::Method::POST), http::Method::POST);
+
+    let purge = normalize_method(http::Method::from_bytes(b"purge").unwrap());
+    assert_eq!(purge.as_str(), "purge");
+}
+
 #[test]
 fn headers() {
     let m = MatchRequest {
@@ -122,6 +157,48 @@ fn path() {
     );
 }
 
+#[test]
+fn cookie() {
+    let m = MatchRequest {
+        cookies: vec![
+            MatchCookie::Exact("arm".to_string(), "b".to_string()),
+            MatchCookie::Prefix("user".to_string(), "beta-".to_string()),
+        ],
+        ..MatchRequest::default()
+    };
+
+    // Cookies may be split across multiple headers.
+    let req = http::Request::builder()
+        .uri("http://example.com/foo")
+        .header("cookie", "session=abc; arm=b")
+        .header("cookie", "user=beta-%61lice")
+        .body(())
+        .unwrap();
+    assert_eq!(
+        m.match_request(&req),
+        Some(RequestMatch {
+            cookies: 2,
+            ..Default::default()
+        })
+    );
+
+    // All cookie matches must apply.
+    let req = http::Request::builder()
+        .uri("http://example.com/foo")
+        .header("cookie", "arm=b; user=alice")
+        .body(())
+        .unwrap();
+    assert_eq!(m.match_request(&req), None);
+
+    // Cookies are not matched against other headers.
+    let req = http::Request::builder()
+        .uri("http://example.com/foo")
+        .header("set-cookie", "arm=b; user=beta-alice")
+        .body(())
+        .unwrap();
+    assert_eq!(m.match_request(&req), None);
+}
+
 #[test]
 fn multiple() {
     let m = MatchRequest {
@@ -131,12 +208,14 @@ fn multiple() {
             HeaderValue::from_static("bar"),
         )],
         query_params: vec![MatchQueryParam::Exact("foo".to_string(), "bar".to_string())],
+        cookies: vec![MatchCookie::Exact("arm".to_string(), "b".to_string())],
         method: Some(http::Method::GET),
     };
 
     let req = http::Request::builder()
         .uri("https://example.org/foo/bar?foo=bar")
         .header("x-foo", "bar")
+        .header("cookie", "arm=b")
         .body(())
         .unwrap();
     assert_eq!(
@@ -145,6 +224,7 @@ fn multiple() {
             path_match: PathMatch::Exact("/foo/bar".len()),
             headers: 1,
             query_params: 1,
+            cookies: 1,
             method: true,
         })
     );
@@ -154,6 +234,7 @@ fn multiple() {
         .method(http::Method::HEAD)
         .uri("https://example.org/foo/bar?foo=bar")
         .header("x-foo", "bar")
+        .header("cookie", "arm=b")
         .body(())
         .unwrap();
     assert_eq!(m.match_request(&req), None);
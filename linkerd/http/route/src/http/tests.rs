@@ -150,6 +150,47 @@ fn first_identical_wins() {
     ];
 
     let req = http::Request::builder().body(()).unwrap();
-    let (_, policy) = find(&rts, &req).expect("must match");
+    let (m, policy) = find(&rts, &req).expect("must match");
     assert_eq!(*policy, Policy::Expected, "incorrect rule matched");
+    assert_eq!(m.rule(), 0, "incorrect rule index");
+}
+
+/// Requests are routed to the rule whose cookie matches, and the index of the
+/// matched rule is reported.
+#[test]
+fn cookie_rule_index() {
+    let arm = |value: &str| Rule {
+        matches: vec![MatchRequest {
+            cookies: vec![MatchCookie::Exact("arm".to_string(), value.to_string())],
+            ..MatchRequest::default()
+        }],
+        policy: value.to_string(),
+    };
+    let rts = vec![Route {
+        rules: vec![
+            arm("a"),
+            arm("b"),
+            Rule {
+                matches: vec![],
+                policy: "default".to_string(),
+            },
+        ],
+        hosts: vec![],
+    }];
+
+    for (cookie, expected, rule) in [
+        (Some("arm=a"), "a", 0),
+        (Some("session=x; arm=b"), "b", 1),
+        (Some("arm=c"), "default", 2),
+        (None, "default", 2),
+    ] {
+        let mut req = http::Request::builder();
+        if let Some(cookie) = cookie {
+            req = req.header("cookie", cookie);
+        }
+        let req = req.body(()).unwrap();
+        let (m, policy) = find(&rts, &req).expect("must match");
+        assert_eq!(policy, expected, "incorrect rule matched");
+        assert_eq!(m.rule(), rule, "incorrect rule index");
+    }
 }
@@ -4,13 +4,14 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
+use std::cell::OnceCell;
 use tracing::trace;
 
 pub mod grpc;
 pub mod http;
 
 // Matchers used by both HTTP and gRPC routes.
-pub use self::http::{HostMatch, MatchHeader, MatchHost};
+pub use self::http::{Cookies, HostMatch, MatchHeader, MatchHost};
 
 /// Groups routing rules under a common set of hostnames.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -44,13 +45,28 @@ pub struct Rule<M, P> {
 pub struct RouteMatch<T> {
     host: Option<http::HostMatch>,
     route: T,
+    /// The index of the matched rule within its route.
+    rule: usize,
+}
+
+/// A request being matched against routes.
+///
+/// Values that are expensive to extract from the request (i.e. cookies) are
+/// parsed lazily, at most once per request.
+pub struct ParsedRequest<'r, B> {
+    req: &'r ::http::Request<B>,
+    cookies: OnceCell<http::Cookies<'r>>,
 }
 
 /// A strategy for matching a request to a route.
 pub trait Match {
     type Summary: Default + Ord;
 
-    fn match_request<B>(&self, req: &::http::Request<B>) -> Option<Self::Summary>;
+    fn match_parsed<B>(&self, req: &ParsedRequest<'_, B>) -> Option<Self::Summary>;
+
+    fn match_request<B>(&self, req: &::http::Request<B>) -> Option<Self::Summary> {
+        self.match_parsed(&ParsedRequest::new(req))
+    }
 }
 
 /// Finds the best matching route policy for a request.
@@ -59,13 +75,16 @@ pub fn find<'r, M: Match + 'r, P, B>(
     req: &::http::Request<B>,
 ) -> Option<(RouteMatch<M::Summary>, &'r P)> {
     trace!(routes = ?routes.len(), "Finding matching route");
+    let req = ParsedRequest::new(req);
 
-    best(routes.iter().filter_map(|rt| {
+    // The rule index is carried alongside the policy so that it does not
+    // affect precedence.
+    let ((host, route), (rule, policy)) = best(routes.iter().filter_map(|rt| {
         trace!(hosts = ?rt.hosts);
         let host = if rt.hosts.is_empty() {
             None
         } else {
-            let uri = req.uri();
+            let uri = req.request().uri();
             trace!(%uri, "matching host");
             let hm = rt
                 .hosts
@@ -76,27 +95,30 @@ pub fn find<'r, M: Match + 'r, P, B>(
         };
 
         trace!(rules = %rt.rules.len());
-        let (route, policy) = best(rt.rules.iter().filter_map(|rule| {
-            // If there are no matches in the list, then the rule has an
-            // implicit default match.
-            if rule.matches.is_empty() {
-                trace!("implicit match");
-                return Some((M::Summary::default(), &rule.policy));
-            }
-            // Find the best match to compare against other rules/routes
-            // (if any apply). The order/precedence of matches is not
-            // relevant.
-            let summary = rule
-                .matches
-                .iter()
-                .filter_map(|m| m.match_request(req))
-                .max()?;
-            trace!("matches!");
-            Some((summary, &rule.policy))
-        }))?;
-
-        Some((RouteMatch { host, route }, policy))
-    }))
+        let (route, (rule, policy)) =
+            best(rt.rules.iter().enumerate().filter_map(|(idx, rule)| {
+                // If there are no matches in the list, then the rule has an
+                // implicit default match.
+                if rule.matches.is_empty() {
+                    trace!("implicit match");
+                    return Some((M::Summary::default(), (idx, &rule.policy)));
+                }
+                // Find the best match to compare against other rules/routes
+                // (if any apply). The order/precedence of matches is not
+                // relevant.
+                let summary = rule
+                    .matches
+                    .iter()
+                    .filter_map(|m| m.match_parsed(&req))
+                    .max()?;
+                trace!("matches!");
+                Some((summary, (idx, &rule.policy)))
+            }))?;
+
+        Some(((host, route), (rule, policy)))
+    }))?;
+
+    Some((RouteMatch { host, route, rule }, policy))
 }
 
 #[inline]
@@ -105,3 +127,33 @@ fn best<M: Ord, P>(matches: impl Iterator<Item = (M, P)>) -> Option<(M, P)> {
     // that the first match wins.
     matches.reduce(|(m0, p0), (m1, p1)| if m0 >= m1 { (m0, p0) } else { (m1, p1) })
 }
+
+// === impl RouteMatch ===
+
+impl<T> RouteMatch<T> {
+    /// Returns the index of the matched rule within its route.
+    pub fn rule(&self) -> usize {
+        self.rule
+    }
+}
+
+// === impl ParsedRequest ===
+
+impl<'r, B> ParsedRequest<'r, B> {
+    pub fn new(req: &'r ::http::Request<B>) -> Self {
+        Self {
+            req,
+            cookies: OnceCell::new(),
+        }
+    }
+
+    pub fn request(&self) -> &'r ::http::Request<B> {
+        self.req
+    }
+
+    /// Returns the request's cookies, parsing them on first use.
+    pub fn cookies(&self) -> &http::Cookies<'r> {
+        self.cookies
+            .get_or_init(|| http::Cookies::parse(self.req.headers()))
+    }
+}
@@ -1,6 +1,6 @@
 //! Tower middleware to express deadlines on streams.
 //!
-//! See [`EnforceTimeouts<S>`].
+//! See [`EnforceTimeouts<S>`] and [`PropagateDeadline<S>`].
 
 use futures::FutureExt;
 use http_body::Frame;
@@ -17,6 +17,12 @@ use std::{
 use thiserror::Error;
 use tokio::{sync::oneshot, time};
 
+mod propagate;
+
+pub use self::propagate::{
+    DeadlineConfig, DeadlineExceededError, PropagateDeadline, RequestDeadline,
+};
+
 /// A request extension set on HTTP requests that expresses deadlines to be
 /// enforced by the proxy.
 #[derive(Clone, Debug, Default)]
@@ -30,6 +36,16 @@ pub struct StreamTimeouts {
     /// and the response being fully received.
     pub response_end: Option<time::Duration>,
 
+    /// The maximum amount of time between the body of the request being fully
+    /// flushed and the response headers being received, as configured by the
+    /// route. Unlike `response_headers`, this is not relaxed when retries are
+    /// exhausted.
+    pub response_first_byte: Option<time::Duration>,
+
+    /// The maximum amount of time between the response headers being received
+    /// and the first frame of the response body, or between subsequent frames.
+    pub response_chunk_idle: Option<time::Duration>,
+
     /// The maximum amount of time the stream may be idle.
     pub idle: Option<time::Duration>,
 
@@ -54,10 +70,18 @@ pub struct EnforceTimeouts<S> {
 #[error("response header timeout: {0:?}")]
 pub struct ResponseHeadersTimeoutError(time::Duration);
 
+#[derive(Clone, Copy, Debug, Error)]
+#[error("response first byte timeout: {0:?}")]
+pub struct ResponseFirstByteTimeoutError(time::Duration);
+
 #[derive(Clone, Copy, Debug, Error)]
 #[error("response stream timeout: {0:?}")]
 pub struct ResponseStreamTimeoutError(time::Duration);
 
+#[derive(Clone, Copy, Debug, Error)]
+#[error("response chunk idle timeout: {0:?}")]
+pub struct ResponseChunkIdleError(time::Duration);
+
 #[derive(Clone, Copy, Debug, Error)]
 #[error("request timeout: {0:?}")]
 pub struct StreamDeadlineError(time::Duration);
@@ -71,6 +95,9 @@ pub enum ResponseTimeoutError {
     #[error("timed out waiting for response headers: {0}")]
     Headers(#[from] ResponseHeadersTimeoutError),
 
+    #[error("timed out waiting for response headers: {0}")]
+    FirstByte(#[from] ResponseFirstByteTimeoutError),
+
     #[error("timed out waiting for response headers: {0}")]
     Response(#[from] ResponseStreamTimeoutError),
 
@@ -88,6 +115,9 @@ pub enum BodyTimeoutError {
 
     #[error("timed out processing response stream: {0}")]
     Idle(#[from] StreamIdleError),
+
+    #[error("timed out processing response stream: {0}")]
+    ChunkIdle(#[from] ResponseChunkIdleError),
 }
 
 #[derive(Debug)]
@@ -130,6 +160,7 @@ pub struct ResponseBody<B> {
     #[pin]
     deadline: Option<Deadline<BodyTimeoutError>>,
     idle: Option<Idle>,
+    chunk_idle: Option<ChunkIdle>,
 
     timeouts: StreamTimeouts,
 }
@@ -151,6 +182,14 @@ struct Idle {
     timeout: time::Duration,
 }
 
+/// Bounds the time between frames of a response body. Unlike [`Idle`], this is
+/// not reset by the request body.
+#[derive(Debug)]
+struct ChunkIdle {
+    sleep: Pin<Box<time::Sleep>>,
+    timeout: time::Duration,
+}
+
 // === impl StreamLifetime ===
 
 impl From<time::Duration> for StreamLifetime {
@@ -184,12 +223,23 @@ where
         self.inner.poll_ready(cx).map_err(Into::into)
     }
 
-    fn call(&mut self, req: http::Request<B>) -> Self::Future {
-        let timeouts = req
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let mut timeouts = req
             .extensions()
             .get::<StreamTimeouts>()
             .cloned()
             .unwrap_or_default();
+
+        // If the client expressed a deadline, it bounds the stream's lifetime,
+        // however the request was routed, and is propagated to the next hop.
+        if let Some(deadline) = req.extensions().get::<RequestDeadline>().cloned() {
+            let limit = match timeouts.limit {
+                Some(limit) if limit.deadline <= deadline.deadline() => limit,
+                _ => deadline.lifetime(),
+            };
+            deadline.set_headers(limit.deadline, req.headers_mut());
+            timeouts.limit = Some(limit);
+        }
         tracing::trace!(?timeouts, "Enforcing timeouts on stream");
 
         let (req_idle, rsp_idle) = if let Some(timeout) = timeouts.idle {
@@ -261,6 +311,16 @@ where
                     (_, Some(eos)) => Some((eos, ResponseStreamTimeoutError(eos).into())),
                     _ => None,
                 };
+                // The route's first-byte timeout applies if it is the most
+                // restrictive.
+                let timeout = match (timeout, this.timeouts.response_first_byte) {
+                    (Some((t, error)), Some(ttfb)) if t <= ttfb => Some((t, error)),
+                    (_, Some(ttfb)) => Some((
+                        ttfb,
+                        ResponseTimeoutError::from(ResponseFirstByteTimeoutError(ttfb)),
+                    )),
+                    (timeout, None) => timeout,
+                };
                 if let Some((timeout, error)) = timeout {
                     tracing::debug!(?timeout);
                     let headers_by = start + timeout;
@@ -316,6 +376,12 @@ where
             }
         });
 
+        // Bound the time until each frame of the response body is received.
+        let chunk_idle = this.timeouts.response_chunk_idle.map(|timeout| ChunkIdle {
+            sleep: Box::pin(time::sleep(timeout)),
+            timeout,
+        });
+
         // We use the more restrictive of the response-end timeout (as
         // measured since the request body was fully flushed) and the stream
         // lifetime limit.
@@ -343,6 +409,7 @@ where
                 error,
             }),
             idle,
+            chunk_idle,
             timeouts: this.timeouts.clone(),
         })))
     }
@@ -413,6 +480,9 @@ where
             if let Some(idle) = this.idle {
                 idle.reset(now);
             }
+            if let Some(chunk_idle) = this.chunk_idle {
+                chunk_idle.reset(now);
+            }
             return Poll::Ready(res);
         }
 
@@ -421,6 +491,11 @@ where
             // TODO telemetry
             return Poll::Ready(Some(Err(Error::from(e))));
         }
+        if let Some(chunk_idle) = this.chunk_idle {
+            if let Poll::Ready(e) = chunk_idle.poll_idle(cx) {
+                return Poll::Ready(Some(Err(BodyTimeoutError::from(e).into())));
+            }
+        }
 
         Poll::Pending
     }
@@ -479,3 +554,17 @@ impl Idle {
         }
     }
 }
+
+// === impl ChunkIdle ===
+
+impl ChunkIdle {
+    fn reset(&mut self, now: time::Instant) {
+        self.sleep.as_mut().reset(now + self.timeout);
+    }
+
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> Poll<ResponseChunkIdleError> {
+        self.sleep
+            .poll_unpin(cx)
+            .map(|()| ResponseChunkIdleError(self.timeout))
+    }
+}
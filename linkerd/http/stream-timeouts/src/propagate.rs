@@ -0,0 +1,298 @@
+//! Propagates request deadlines across hops.
+//!
+//! Clients may express a deadline for a request either as an absolute time
+//! (in milliseconds since the Unix epoch) or, as gRPC clients do, as a timeout
+//! relative to when the request is received. [`PropagateDeadline`] fails
+//! requests that arrive after their deadline has passed and records the
+//! deadline as a [`RequestDeadline`] request extension, so that
+//! [`EnforceTimeouts`](super::EnforceTimeouts) bounds the stream's lifetime
+//! and rewrites the headers for the next hop.
+
+use super::StreamLifetime;
+use futures::{future, TryFutureExt};
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use linkerd_error::Error;
+use linkerd_stack as svc;
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+use tokio::time;
+
+/// Configures deadline propagation.
+#[derive(Clone, Debug)]
+pub struct DeadlineConfig {
+    /// A header that expresses a deadline in milliseconds since the Unix
+    /// epoch, e.g. `x-deadline`.
+    pub deadline_header: HeaderName,
+
+    /// A header that expresses a timeout in the `grpc-timeout` format, e.g.
+    /// `grpc-timeout`.
+    pub timeout_header: HeaderName,
+
+    /// Requests with less than this much time remaining before their deadline
+    /// are failed without being dispatched.
+    pub min_budget: time::Duration,
+}
+
+/// A request extension that records the deadline set by a request's headers.
+#[derive(Clone, Debug)]
+pub struct RequestDeadline {
+    deadline: time::Instant,
+    config: Arc<DeadlineConfig>,
+}
+
+/// Fails requests that have exceeded their deadline and records the
+/// deadlines of all other requests as a [`RequestDeadline`] extension.
+#[derive(Clone, Debug)]
+pub struct PropagateDeadline<S> {
+    config: Option<Arc<DeadlineConfig>>,
+    inner: S,
+}
+
+#[derive(Clone, Copy, Debug, Error)]
+#[error("request deadline exceeded: {remaining:?} remaining")]
+pub struct DeadlineExceededError {
+    remaining: time::Duration,
+}
+
+// === impl RequestDeadline ===
+
+impl RequestDeadline {
+    pub fn deadline(&self) -> time::Instant {
+        self.deadline
+    }
+
+    /// Returns a stream lifetime that expires at the request's deadline.
+    pub fn lifetime(&self) -> StreamLifetime {
+        StreamLifetime {
+            deadline: self.deadline,
+            lifetime: self
+                .deadline
+                .saturating_duration_since(time::Instant::now()),
+        }
+    }
+
+    /// Rewrites the request's deadline headers so that the next hop observes
+    /// the given deadline, accounting for the time that has already elapsed.
+    ///
+    /// Only the headers that were set by the client are rewritten.
+    pub fn set_headers(&self, deadline: time::Instant, headers: &mut HeaderMap) {
+        let remaining = deadline.saturating_duration_since(time::Instant::now());
+
+        if headers.contains_key(&self.config.deadline_header) {
+            let millis = (unix_now() + remaining).as_millis();
+            headers.insert(
+                self.config.deadline_header.clone(),
+                HeaderValue::from(millis as u64),
+            );
+        }
+
+        if headers.contains_key(&self.config.timeout_header) {
+            headers.insert(
+                self.config.timeout_header.clone(),
+                encode_grpc_timeout(remaining),
+            );
+        }
+    }
+}
+
+// === impl PropagateDeadline ===
+
+impl<S> PropagateDeadline<S> {
+    /// Returns a layer that propagates deadlines. Requests are not modified
+    /// when no configuration is provided.
+    pub fn layer(
+        config: Option<DeadlineConfig>,
+    ) -> impl svc::layer::Layer<S, Service = Self> + Clone {
+        let config = config.map(Arc::new);
+        svc::layer::mk(move |inner| Self {
+            config: config.clone(),
+            inner,
+        })
+    }
+}
+
+impl<B, S> svc::Service<http::Request<B>> for PropagateDeadline<S>
+where
+    S: svc::Service<http::Request<B>>,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = future::Either<
+        future::ErrInto<S::Future, Error>,
+        future::Ready<Result<S::Response, Error>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        if let Some(config) = self.config.as_ref() {
+            let now = time::Instant::now();
+            if let Some(deadline) = parse_deadline(config, req.headers(), now, unix_now()) {
+                let remaining = deadline.saturating_duration_since(now);
+                if remaining.is_zero() || remaining < config.min_budget {
+                    tracing::debug!(?remaining, "Request deadline exceeded");
+                    return future::Either::Right(future::err(
+                        DeadlineExceededError { remaining }.into(),
+                    ));
+                }
+
+                tracing::trace!(?remaining, "Request deadline");
+                req.extensions_mut().insert(RequestDeadline {
+                    deadline,
+                    config: config.clone(),
+                });
+            }
+        }
+
+        future::Either::Left(self.inner.call(req).err_into())
+    }
+}
+
+/// Returns the earliest deadline expressed by the request's headers.
+fn parse_deadline(
+    config: &DeadlineConfig,
+    headers: &HeaderMap,
+    now: time::Instant,
+    unix_now: time::Duration,
+) -> Option<time::Instant> {
+    let deadline = headers
+        .get(&config.deadline_header)
+        .and_then(|v| v.to_str().ok()?.trim().parse::<u64>().ok())
+        .and_then(|millis| {
+            // Deadlines in the past have no time remaining.
+            let remaining = time::Duration::from_millis(millis).saturating_sub(unix_now);
+            now.checked_add(remaining)
+        });
+
+    let timeout = headers
+        .get(&config.timeout_header)
+        .and_then(|v| parse_grpc_timeout(v.to_str().ok()?))
+        .and_then(|timeout| now.checked_add(timeout));
+
+    deadline.into_iter().chain(timeout).min()
+}
+
+/// Parses a timeout in the `grpc-timeout` format: at most 8 digits followed by
+/// a unit.
+fn parse_grpc_timeout(s: &str) -> Option<time::Duration> {
+    let s = s.trim();
+    if s.len() < 2 || s.len() > 9 {
+        return None;
+    }
+    let (value, unit) = s.split_at(s.len() - 1);
+    if !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value = value.parse::<u64>().ok()?;
+    let timeout = match unit {
+        "H" => time::Duration::from_secs(value * 60 * 60),
+        "M" => time::Duration::from_secs(value * 60),
+        "S" => time::Duration::from_secs(value),
+        "m" => time::Duration::from_millis(value),
+        "u" => time::Duration::from_micros(value),
+        "n" => time::Duration::from_nanos(value),
+        _ => return None,
+    };
+    Some(timeout)
+}
+
+/// Encodes a timeout in the `grpc-timeout` format, using the most precise unit
+/// that fits in 8 digits. Timeouts are rounded down.
+fn encode_grpc_timeout(timeout: time::Duration) -> HeaderValue {
+    const MAX: u128 = 99_999_999;
+    let millis = timeout.as_millis();
+    let (value, unit) = if millis <= MAX {
+        (millis, 'm')
+    } else if timeout.as_secs() as u128 <= MAX {
+        (timeout.as_secs() as u128, 'S')
+    } else {
+        ((timeout.as_secs() / 60).min(MAX as u64) as u128, 'M')
+    };
+    HeaderValue::from_str(&format!("{value}{unit}")).expect("timeouts must be valid headers")
+}
+
+fn unix_now() -> time::Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> DeadlineConfig {
+        DeadlineConfig {
+            deadline_header: HeaderName::from_static("x-deadline"),
+            timeout_header: HeaderName::from_static("grpc-timeout"),
+            min_budget: time::Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn grpc_timeouts() {
+        for (s, expected) in [
+            ("1H", Some(time::Duration::from_secs(3600))),
+            ("2M", Some(time::Duration::from_secs(120))),
+            ("3S", Some(time::Duration::from_secs(3))),
+            ("99999999m", Some(time::Duration::from_millis(99_999_999))),
+            ("5u", Some(time::Duration::from_micros(5))),
+            ("6n", Some(time::Duration::from_nanos(6))),
+            ("100000000m", None),
+            ("-1S", None),
+            ("1", None),
+            ("S", None),
+            ("1s", None),
+        ] {
+            assert_eq!(parse_grpc_timeout(s), expected, "{s}");
+        }
+
+        for (timeout, expected) in [
+            (time::Duration::from_millis(1500), "1500m"),
+            (time::Duration::from_micros(999), "0m"),
+            (time::Duration::from_secs(100_000), "100000S"),
+            (time::Duration::from_secs(100_000_000 * 60), "99999999M"),
+        ] {
+            assert_eq!(encode_grpc_timeout(timeout), expected);
+        }
+    }
+
+    #[test]
+    fn earliest_deadline() {
+        let config = config();
+        let now = time::Instant::now();
+        let unix_now = time::Duration::from_secs(1_700_000_000);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_deadline(&config, &headers, now, unix_now), None);
+
+        headers.insert("x-deadline", HeaderValue::from(1_700_000_010_000u64));
+        assert_eq!(
+            parse_deadline(&config, &headers, now, unix_now),
+            Some(now + time::Duration::from_secs(10))
+        );
+
+        headers.insert("grpc-timeout", HeaderValue::from_static("5S"));
+        assert_eq!(
+            parse_deadline(&config, &headers, now, unix_now),
+            Some(now + time::Duration::from_secs(5))
+        );
+
+        // Deadlines in the past leave no time remaining.
+        headers.insert("x-deadline", HeaderValue::from(1_699_999_999_000u64));
+        assert_eq!(parse_deadline(&config, &headers, now, unix_now), Some(now));
+
+        // Invalid headers are ignored.
+        headers.insert("x-deadline", HeaderValue::from_static("soon"));
+        headers.insert("grpc-timeout", HeaderValue::from_static("5 seconds"));
+        assert_eq!(parse_deadline(&config, &headers, now, unix_now), None);
+    }
+}
@@ -18,6 +18,16 @@ pub enum Variant {
 #[error("unsupported HTTP version {:?}", self.0)]
 pub struct Unsupported(http::Version);
 
+impl Variant {
+    /// Returns the version's name, as used in metric labels.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Http1 => "http/1",
+            Self::H2 => "http/2",
+        }
+    }
+}
+
 impl std::convert::TryFrom<http::Version> for Variant {
     type Error = Unsupported;
     fn try_from(v: http::Version) -> Result<Self, Unsupported> {
@@ -0,0 +1,179 @@
+//! Signed assertions of a workload's identity.
+//!
+//! HTTP intermediaries, like egress gateways, terminate and re-originate
+//! connections, so the identity of the workload that originated a request is
+//! not known from the intermediary's mTLS connections. A workload's outbound
+//! proxy may instead set an `l5d-workload-identity` header, signed with a key
+//! that is shared with the intermediary, which the intermediary's inbound proxy
+//! verifies.
+//!
+//! Assertions have the form `<identity>;<issued-at>;<signature>`, where the
+//! issue time is in seconds since the Unix epoch and the signature is the
+//! hex-encoded HMAC-SHA256 of `<identity>;<issued-at>`.
+
+#![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
+#![forbid(unsafe_code)]
+
+use http::header::{HeaderName, HeaderValue, InvalidHeaderValue};
+use linkerd_identity::Id;
+use ring::hmac;
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The header that carries a workload identity assertion.
+pub static HEADER: HeaderName = HeaderName::from_static("l5d-workload-identity");
+
+/// A key used to sign and verify assertions.
+#[derive(Clone)]
+pub struct Key(hmac::Key);
+
+/// Signs assertions of the local workload's identity.
+#[derive(Clone, Debug)]
+pub struct Signer {
+    id: Id,
+    key: Key,
+}
+
+/// Verifies assertions signed by any of a set of keys.
+///
+/// Multiple keys are accepted so that keys may be rotated: intermediaries
+/// accept both the new and previous key until every workload signs with the
+/// new key.
+#[derive(Clone, Debug)]
+pub struct Verifier {
+    keys: Arc<[Key]>,
+    max_age: Duration,
+}
+
+/// A workload identity that was verified from a signed assertion.
+///
+/// This is set as a request extension when an assertion is verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedIdentity(pub Id);
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "workload identity keys must be hex-encoded and at least {} bytes",
+    Key::MIN_LEN
+)]
+pub struct InvalidKey(());
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidAssertion {
+    #[error("malformed workload identity assertion")]
+    Malformed,
+
+    #[error("workload identity assertion signature is not valid")]
+    InvalidSignature,
+
+    #[error("workload identity assertion has expired")]
+    Expired,
+}
+
+// === impl Key ===
+
+impl Key {
+    /// The minimum length of a key, in bytes.
+    pub const MIN_LEN: usize = 32;
+
+    /// Parses a hex-encoded key.
+    pub fn from_hex(s: &str) -> Result<Self, InvalidKey> {
+        let bytes = hex::decode(s.trim()).map_err(|_| InvalidKey(()))?;
+        if bytes.len() < Self::MIN_LEN {
+            return Err(InvalidKey(()));
+        }
+        Ok(Self::new(&bytes))
+    }
+
+    fn new(bytes: &[u8]) -> Self {
+        Self(hmac::Key::new(hmac::HMAC_SHA256, bytes))
+    }
+
+    /// Computes the HMAC-SHA256 of `msg`.
+    fn sign(&self, msg: &[u8]) -> hmac::Tag {
+        hmac::sign(&self.0, msg)
+    }
+
+    /// Verifies the HMAC-SHA256 of `msg` in constant time.
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> bool {
+        hmac::verify(&self.0, msg, sig).is_ok()
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Keys are never logged.
+        f.write_str("Key(..)")
+    }
+}
+
+// === impl Signer ===
+
+impl Signer {
+    pub fn new(id: Id, key: Key) -> Result<Self, InvalidHeaderValue> {
+        // Ensure that assertions of this identity are valid header values, so
+        // that signing is infallible.
+        HeaderValue::try_from(id.to_str().as_ref())?;
+        Ok(Self { id, key })
+    }
+
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+
+    /// Returns an assertion of the local identity, issued at `now`.
+    pub fn sign(&self, now: SystemTime) -> HeaderValue {
+        let issued_at = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let msg = format!("{};{}", self.id, issued_at);
+        let sig = hex::encode(self.key.sign(msg.as_bytes()));
+        HeaderValue::try_from(format!("{msg};{sig}"))
+            .expect("identity must be a valid header value")
+    }
+}
+
+// === impl Verifier ===
+
+impl Verifier {
+    /// Returns a verifier that accepts assertions signed by any of `keys`
+    /// and issued no more than `max_age` from the time they are verified.
+    pub fn new(keys: impl IntoIterator<Item = Key>, max_age: Duration) -> Self {
+        Self {
+            keys: keys.into_iter().collect(),
+            max_age,
+        }
+    }
+
+    /// Verifies an assertion at time `now`, returning the asserted identity.
+    pub fn verify(&self, value: &HeaderValue, now: SystemTime) -> Result<Id, InvalidAssertion> {
+        let value = value.to_str().map_err(|_| InvalidAssertion::Malformed)?;
+
+        // Identities may themselves contain semicolons, so the assertion is
+        // split from the right.
+        let (msg, sig) = value.rsplit_once(';').ok_or(InvalidAssertion::Malformed)?;
+        let (id, issued_at) = msg.rsplit_once(';').ok_or(InvalidAssertion::Malformed)?;
+        let sig = hex::decode(sig).map_err(|_| InvalidAssertion::Malformed)?;
+        if !self.keys.iter().any(|key| key.verify(msg.as_bytes(), &sig)) {
+            return Err(InvalidAssertion::InvalidSignature);
+        }
+
+        let issued_at = issued_at
+            .parse::<u64>()
+            .map_err(|_| InvalidAssertion::Malformed)?;
+        let issued_at = UNIX_EPOCH + Duration::from_secs(issued_at);
+        // Tolerate clock skew in either direction.
+        let age = now
+            .duration_since(issued_at)
+            .unwrap_or_else(|e| e.duration());
+        if age > self.max_age {
+            return Err(InvalidAssertion::Expired);
+        }
+
+        id.parse().map_err(|_| InvalidAssertion::Malformed)
+    }
+}
@@ -0,0 +1,107 @@
+use super::*;
+
+fn key(byte: u8) -> Key {
+    Key::new(&[byte; Key::MIN_LEN])
+}
+
+fn signer(key: Key) -> Signer {
+    Signer::new(
+        "foo.ns1.serviceaccount.identity.linkerd.cluster.local"
+            .parse()
+            .unwrap(),
+        key,
+    )
+    .unwrap()
+}
+
+#[test]
+fn hmac_sha256() {
+    // RFC 4231, test cases 2 and 6.
+    let key = Key::new(b"Jefe");
+    assert_eq!(
+        hex::encode(key.sign(b"what do ya want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    let key = Key::new(&[0xaa; 131]);
+    assert_eq!(
+        hex::encode(key.sign(b"Test Using Larger Than Block-Size Key - Hash Key First")),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+}
+
+#[test]
+fn parses_keys() {
+    assert!(Key::from_hex(&"ab".repeat(Key::MIN_LEN)).is_ok());
+    assert!(Key::from_hex(&"ab".repeat(Key::MIN_LEN - 1)).is_err());
+    assert!(Key::from_hex(&"zz".repeat(Key::MIN_LEN)).is_err());
+}
+
+#[test]
+fn verifies_assertions() {
+    let now = SystemTime::now();
+    let signer = signer(key(1));
+    let verifier = Verifier::new([key(1)], Duration::from_secs(60));
+
+    let value = signer.sign(now);
+    assert_eq!(verifier.verify(&value, now).as_ref(), Ok(signer.id()));
+    assert_eq!(
+        verifier
+            .verify(&value, now + Duration::from_secs(30))
+            .as_ref(),
+        Ok(signer.id())
+    );
+    assert_eq!(
+        verifier.verify(&value, now + Duration::from_secs(120)),
+        Err(InvalidAssertion::Expired)
+    );
+    assert_eq!(
+        verifier.verify(&value, now - Duration::from_secs(120)),
+        Err(InvalidAssertion::Expired)
+    );
+}
+
+#[test]
+fn rejects_forged_assertions() {
+    let now = SystemTime::now();
+    let verifier = Verifier::new([key(1)], Duration::from_secs(60));
+
+    // Signed by an unknown key.
+    let value = signer(key(2)).sign(now);
+    assert_eq!(
+        verifier.verify(&value, now),
+        Err(InvalidAssertion::InvalidSignature)
+    );
+
+    // A valid signature for another identity.
+    let value = signer(key(1)).sign(now);
+    let forged = value.to_str().unwrap().replacen("foo.ns1", "bar.ns1", 1);
+    assert_eq!(
+        verifier.verify(&HeaderValue::try_from(forged).unwrap(), now),
+        Err(InvalidAssertion::InvalidSignature)
+    );
+
+    for malformed in ["", "foo", "foo;1", "foo;1;zz"] {
+        assert_eq!(
+            verifier.verify(&HeaderValue::from_static(malformed), now),
+            Err(InvalidAssertion::Malformed),
+            "{malformed}"
+        );
+    }
+}
+
+#[test]
+fn accepts_previous_keys() {
+    let now = SystemTime::now();
+    let verifier = Verifier::new([key(2), key(1)], Duration::from_secs(60));
+    for k in [key(1), key(2)] {
+        let signer = signer(k);
+        assert_eq!(
+            verifier.verify(&signer.sign(now), now).as_ref(),
+            Ok(signer.id())
+        );
+    }
+    assert_eq!(
+        verifier.verify(&signer(key(3)).sign(now), now),
+        Err(InvalidAssertion::InvalidSignature)
+    );
+}
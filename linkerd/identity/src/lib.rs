@@ -3,6 +3,7 @@
 
 mod credentials;
 mod metrics;
+mod provider;
 
 use linkerd_error::{Error, Result};
 use std::str::FromStr;
@@ -10,6 +11,7 @@ use std::str::FromStr;
 pub use self::{
     credentials::{Credentials, DerX509},
     metrics::{CertMetrics, WithCertMetrics},
+    provider::Provider,
 };
 
 /// An endpoint identity descriptor used for authentication.
@@ -0,0 +1,18 @@
+use crate::Credentials;
+use std::future::Future;
+
+/// Obtains credentials from an external source and publishes them to a
+/// [`Credentials`] store.
+///
+/// A provider publishes the current certificate chain and key when they are
+/// first issued and again each time they are rotated, so TLS implementations
+/// read credentials from the store without regard to where they came from.
+/// Providers are responsible for validating that issued certificates match the
+/// local identity and for retrying when a source is unavailable.
+pub trait Provider<C: Credentials> {
+    type Future: Future<Output = ()> + Send + 'static;
+
+    /// Returns a future that publishes credentials until the source is
+    /// exhausted.
+    fn run(self, credentials: C) -> Self::Future;
+}
@@ -1,7 +1,8 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
-use parking_lot::RwLock;
+use linkerd_metrics::prom;
+use parking_lot::{Mutex, RwLock};
 use std::{
     borrow::Borrow,
     collections::{
@@ -18,8 +19,12 @@ use tokio::{sync::Notify, time};
 use tracing::{debug, instrument, trace};
 
 mod new_service;
+mod periodic;
 
-pub use new_service::NewIdleCached;
+pub use self::{
+    new_service::NewIdleCached,
+    periodic::{Periodic, PeriodicConfig, Retained},
+};
 
 pub struct IdleCache<K, V, S = RandomState>
 where
@@ -29,7 +34,27 @@ where
     /// evicted.
     idle: time::Duration,
 
+    /// The amount of time after which an entry is evicted, even if it is in
+    /// use, so that it is rebuilt when it is next accessed.
+    max_age: Option<time::Duration>,
+
+    /// When set, entries are considered idle once they have not been marked
+    /// active (with [`Cached::mark_active`]) for the idle timeout, whether or
+    /// not handles to them are held.
+    track_activity: bool,
+
     inner: Arc<InnerMap<K, V, S>>,
+
+    /// Determines whether idle entries that are accessed at regular intervals
+    /// are retained, if at all.
+    periodic: Option<Arc<Periodic<K>>>,
+
+    /// Holds a lock for each key whose value is being built, so that
+    /// concurrent misses for a key wait for a single value to be built.
+    building: Arc<Building<K>>,
+
+    /// The number of values that are being built.
+    builds: prom::Gauge,
 }
 
 /// A handle that that holds the referenced value in the cache. When dropped,
@@ -41,6 +66,10 @@ pub struct Cached<V> {
     // Notifies entry's eviction task that a drop has occurred. If no handle is
     // set, then the entry is permanent and will not be evicted.
     handle: Option<Arc<Notify>>,
+
+    // Notifies entry's eviction task that the value was used, if the cache
+    // tracks activity.
+    activity: Option<Arc<Notify>>,
 }
 
 #[derive(Debug)]
@@ -50,6 +79,21 @@ struct CacheEntry<V> {
     ///
     /// If this is unset, the entry is permanent and will not be evicted.
     handle: Option<Weak<Notify>>,
+    /// A handle to mark the entry active, if the cache tracks activity.
+    activity: Option<Arc<Notify>>,
+}
+
+/// The build locks of keys whose values are being built.
+type Building<K> = Mutex<HashMap<K, Arc<Mutex<()>>>>;
+
+/// Releases a key's build lock and decrements the builds gauge once the key's
+/// value is built, or if building it panics, so that the key may be built
+/// again.
+struct BuildGuard<'a, K: Eq + Hash> {
+    key: &'a K,
+    lock: Arc<Mutex<()>>,
+    building: &'a Building<K>,
+    builds: &'a prom::Gauge,
 }
 
 /// A locked cache map holding values and an optional handle. When the handle is
@@ -84,6 +128,11 @@ where
                 capacity,
                 BuildHasherDefault::default(),
             ))),
+            max_age: None,
+            track_activity: false,
+            periodic: None,
+            building: Default::default(),
+            builds: Default::default(),
         }
     }
 
@@ -101,7 +150,15 @@ where
             .map(|(k, v)| (k, CacheEntry::permanent(v)))
             .collect();
         let inner = Arc::new(RwLock::new(entries));
-        Self { inner, idle }
+        Self {
+            inner,
+            idle,
+            max_age: None,
+            track_activity: false,
+            periodic: None,
+            building: Default::default(),
+            builds: Default::default(),
+        }
     }
 }
 
@@ -113,7 +170,42 @@ where
 {
     pub fn with_hasher(idle: time::Duration, hasher: S) -> Self {
         let inner = Arc::new(RwLock::new(HashMap::with_hasher(hasher)));
-        Self { inner, idle }
+        Self {
+            inner,
+            idle,
+            max_age: None,
+            track_activity: false,
+            periodic: None,
+            building: Default::default(),
+            builds: Default::default(),
+        }
+    }
+
+    /// Retains idle entries that are accessed at regular intervals, as
+    /// determined by `periodic`.
+    pub fn with_periodic(mut self, periodic: Arc<Periodic<K>>) -> Self {
+        self.periodic = Some(periodic);
+        self
+    }
+
+    /// Records the number of values that are being built in `builds`.
+    pub fn with_builds_gauge(mut self, builds: prom::Gauge) -> Self {
+        self.builds = builds;
+        self
+    }
+
+    /// Evicts entries once they are `max_age` old, regardless of activity.
+    pub fn with_max_age(mut self, max_age: time::Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Evicts entries that have not been marked active (with
+    /// [`Cached::mark_active`]) within the idle timeout, even if handles to
+    /// them are held. Handles that are held continue to use the evicted value.
+    pub fn with_activity_tracking(mut self) -> Self {
+        self.track_activity = true;
+        self
     }
 
     pub fn get<Q>(&self, key: &Q) -> Option<Cached<V>>
@@ -134,39 +226,71 @@ where
         Some(cached)
     }
 
+    /// Returns the cached value for `key`, building it with `f` if it is not
+    /// cached.
+    ///
+    /// Concurrent misses for the same key block the calling thread until the
+    /// first caller's `f` returns, so `f` should not block or wait on other
+    /// tasks. Lookups of other keys are not blocked. If `f` panics, waiters
+    /// build the value themselves.
     pub fn get_or_insert_with(&self, key: K, f: impl FnOnce(&K) -> V) -> Cached<V>
     where
         V: Clone,
     {
+        if let Some(periodic) = self.periodic.as_ref() {
+            periodic.accessed(&key, self.idle);
+        }
+
         // We expect the item to be available in most cases, so initially obtain
         // only a read lock.
         if let Some(val) = self.get(&key) {
             return val;
         }
 
-        // Otherwise, obtain a write lock to insert a new value.
-        let mut cache = self.inner.write();
-        match cache.entry(key) {
+        // Otherwise, build a new value. Concurrent misses for the same key wait
+        // for a single value to be built. The value is built without holding
+        // the cache's lock so that lookups of other keys are not blocked.
+        let lock = self.building.lock().entry(key.clone()).or_default().clone();
+        let _locked = lock.lock();
+        if let Some(val) = self.get(&key) {
+            // Another thread built the value while we waited.
+            return val;
+        }
+
+        debug!(?key, "Caching new value");
+        self.builds.inc();
+        let guard = BuildGuard {
+            key: &key,
+            lock: lock.clone(),
+            building: &self.building,
+            builds: &self.builds,
+        };
+        let inner = f(&key);
+
+        let cached = match self.inner.write().entry(key.clone()) {
             Entry::Vacant(entry) => {
-                debug!(key = ?entry.key(), "Caching new value");
-                let inner = f(entry.key());
-                let handle = self.spawn_idle(entry.key().clone());
+                let (handle, activity) = self.spawn_idle(entry.key().clone());
                 entry.insert(CacheEntry {
                     value: inner.clone(),
                     handle: Some(Arc::downgrade(&handle)),
+                    activity: activity.clone(),
                 });
                 Cached {
                     inner,
                     handle: Some(handle),
+                    activity,
                 }
             }
 
             Entry::Occupied(entry) => {
-                // Another thread raced us to create a value for this target.
+                // A permanent value was inserted while the value was built.
                 trace!(key = ?entry.key(), "Using cached value");
                 entry.get().cached()
             }
-        }
+        };
+        // Waiters that have not yet observed the value find it in the cache.
+        drop(guard);
+        cached
     }
 
     /// Adds or overwrites a value in the cache that will never be evicted from
@@ -186,47 +310,89 @@ where
         }
     }
 
-    fn spawn_idle(&self, key: K) -> Arc<Notify> {
+    fn spawn_idle(&self, key: K) -> (Arc<Notify>, Option<Arc<Notify>>) {
         // Spawn a background task that holds the handle. Every time the handle
         // is notified, it resets the idle timeout. Every time teh idle timeout
         // expires, the handle is checked and the service is dropped if there
         // are no active handles.
+        //
+        // If activity is tracked, the idle timeout is instead reset whenever
+        // the entry is marked active, and the entry is dropped when the idle
+        // timeout expires regardless of the handles that are held.
         let handle = Arc::new(Notify::new());
+        let activity = self.track_activity.then(|| Arc::new(Notify::new()));
         tokio::spawn(Self::evict(
             key,
             self.idle,
+            self.max_age,
             handle.clone(),
+            activity.clone(),
             Arc::downgrade(&self.inner),
+            self.periodic.clone(),
         ));
-        handle
+        (handle, activity)
     }
 
-    #[instrument(level = "debug", skip(idle, reset, cache))]
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(level = "debug", skip(idle, max_age, reset, activity, cache, periodic))]
     async fn evict(
         key: K,
         idle: time::Duration,
+        max_age: Option<time::Duration>,
         mut reset: Arc<Notify>,
+        activity: Option<Arc<Notify>>,
         cache: Weak<InnerMap<K, V, S>>,
+        periodic: Option<Arc<Periodic<K>>>,
     ) {
-        // Wait for the handle to be notified before starting to track idleness.
-        reset.notified().await;
+        // Once the entry reaches its maximum age, it is dropped even if it is
+        // in use.
+        let expired = async move {
+            match max_age {
+                Some(max_age) => time::sleep(max_age).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(expired);
+
+        // Unless activity is tracked, wait for the handle to be notified
+        // before starting to track idleness.
+        if activity.is_none() {
+            tokio::select! {
+                biased;
+                _ = &mut expired => return Self::expire(key, cache),
+                _ = reset.notified() => {}
+            }
+        }
         debug!("Awaiting idleness");
 
+        let mut timeout = idle;
         loop {
             // Wait until the idle timeout expires to check to see if the entry
             // should be evicted from the cache.
             let cache = tokio::select! {
                 biased;
 
-                // If the reset was notified, restart the timer (and skip
+                _ = &mut expired => return Self::expire(key, cache),
+
+                // If the entry was marked active, restart the timer (and skip
                 // checking the cache).
-                _ = reset.notified() => {
+                _ = notified(activity.as_deref()) => {
+                    trace!("Active");
+                    timeout = idle;
+                    continue;
+                }
+
+                // If the reset was notified, restart the timer (and skip
+                // checking the cache). Handles are ignored when activity is
+                // tracked.
+                _ = reset.notified(), if activity.is_none() => {
                     trace!("Reset");
+                    timeout = idle;
                     continue;
                 }
 
                 // If the timeout expires, try to clear the key from the cache...
-                _ = time::sleep(idle) => match cache.upgrade() {
+                _ = time::sleep(timeout) => match cache.upgrade() {
                     Some(c) => c,
                     None => {
                         trace!("Cache already dropped");
@@ -242,13 +408,31 @@ where
             // cache.
             let mut cache = cache.write();
 
+            // If no other handles are held (or activity is tracked) and the
+            // entry is accessed at regular intervals, retain it until its next
+            // expected access.
+            if activity.is_some() || Arc::strong_count(&reset) == 1 {
+                if let Some(until) = periodic.as_ref().and_then(|p| p.retain(&key)) {
+                    let now = time::Instant::now();
+                    debug!(
+                        retain = ?until.saturating_duration_since(now),
+                        "Retaining periodically accessed entry"
+                    );
+                    timeout = until.saturating_duration_since(now);
+                    continue;
+                }
+            }
+            timeout = idle;
+
             // Try to consume the reset handle to ensure no other tasks are
             // holding a clone
-            if let Err(r) = Arc::try_unwrap(reset) {
-                // The handle is still being held elsewhere, So wait for another
-                // idle timeout to check again.
-                reset = r;
-                continue;
+            if activity.is_none() {
+                if let Err(r) = Arc::try_unwrap(reset) {
+                    // The handle is still being held elsewhere, So wait for
+                    // another idle timeout to check again.
+                    reset = r;
+                    continue;
+                }
             }
 
             // If this was the last handle, attempt to clear the key from the
@@ -277,6 +461,28 @@ where
             return;
         }
     }
+
+    /// Drops the entry for `key` from the cache when it reaches its maximum
+    /// age, unless it was replaced by a permanent value.
+    fn expire(key: K, cache: Weak<InnerMap<K, V, S>>) {
+        let Some(cache) = cache.upgrade() else {
+            trace!("Cache already dropped");
+            return;
+        };
+        if let Entry::Occupied(entry) = cache.write().entry(key) {
+            if !entry.get().is_permanent() {
+                debug!(key = ?entry.key(), "Dropping expired cache entry");
+                entry.remove();
+            }
+        };
+    }
+}
+
+async fn notified(notify: Option<&Notify>) {
+    match notify {
+        Some(notify) => notify.notified().await,
+        None => std::future::pending().await,
+    }
 }
 
 impl<V> CacheEntry<V> {
@@ -284,6 +490,7 @@ impl<V> CacheEntry<V> {
         Self {
             value,
             handle: None,
+            activity: None,
         }
     }
 
@@ -299,6 +506,7 @@ impl<V> CacheEntry<V> {
         Cached {
             inner: self.value.clone(),
             handle,
+            activity: self.activity.clone(),
         }
     }
 
@@ -314,6 +522,28 @@ where
         Self {
             inner: self.inner.clone(),
             idle: self.idle,
+            max_age: self.max_age,
+            track_activity: self.track_activity,
+            periodic: self.periodic.clone(),
+            building: self.building.clone(),
+            builds: self.builds.clone(),
+        }
+    }
+}
+
+// === impl BuildGuard ===
+
+impl<K: Eq + Hash> Drop for BuildGuard<'_, K> {
+    fn drop(&mut self) {
+        self.builds.dec();
+        let mut building = self.building.lock();
+        // The key may have been rebuilt with a new lock if a prior build
+        // panicked, so only this build's lock is released.
+        if building
+            .get(self.key)
+            .is_some_and(|lock| Arc::ptr_eq(lock, &self.lock))
+        {
+            building.remove(self.key);
         }
     }
 }
@@ -335,6 +565,7 @@ impl<V> Cached<V> {
         Self {
             inner,
             handle: None,
+            activity: None,
         }
     }
 }
@@ -346,6 +577,15 @@ impl<T> Cached<T> {
         Cached {
             inner,
             handle: self.handle.clone(),
+            activity: self.activity.clone(),
+        }
+    }
+
+    /// Marks the cached value as active, resetting its idle timeout if the
+    /// cache tracks activity.
+    pub fn mark_active(&self) {
+        if let Some(activity) = self.activity.as_ref() {
+            activity.notify_one();
         }
     }
 }
@@ -410,18 +650,20 @@ async fn test_idle_retain() {
     let idle = time::Duration::from_secs(10);
     let cache = IdleCache::new(idle);
 
-    let handle = cache.spawn_idle(());
+    let (handle, _) = cache.spawn_idle(());
     let weak = Arc::downgrade(&handle);
     cache.inner.write().insert(
         (),
         CacheEntry {
             value: (),
             handle: Some(weak.clone()),
+            activity: None,
         },
     );
     let c0 = Cached {
         inner: (),
         handle: Some(handle),
+        activity: None,
     };
 
     // Let an idle timeout elapse and ensured the held service has not been
@@ -443,6 +685,7 @@ async fn test_idle_retain() {
         inner: (),
         // Retain the handle from the first instance.
         handle: Some(weak.upgrade().unwrap()),
+        activity: None,
     };
 
     // Drop the new cache instance. Wait the remainder of the first idle timeout
@@ -458,3 +701,139 @@ async fn test_idle_retain() {
     assert!(weak.upgrade().is_none());
     assert!(!cache.inner.read().contains_key(&()));
 }
+
+#[cfg(test)]
+#[tokio::test(flavor = "current_thread")]
+async fn test_periodic_retain() {
+    time::pause();
+
+    let idle = time::Duration::from_secs(10);
+    let period = time::Duration::from_secs(60);
+    let cache = IdleCache::new(idle).with_periodic(Arc::new(Periodic::new(PeriodicConfig {
+        max_period: time::Duration::from_secs(60 * 60),
+        max_entries: 10,
+    })));
+
+    // Access the key at a regular interval that exceeds the idle timeout. The
+    // entry is evicted between accesses until its period is established.
+    for _ in 0..3 {
+        drop(cache.get_or_insert_with((), |_| ()));
+        time::sleep(period).await;
+        assert!(!cache.inner.read().contains_key(&()));
+    }
+    drop(cache.get_or_insert_with((), |_| ()));
+
+    // The entry is retained past its idle timeout until the next access is
+    // expected.
+    time::sleep(period).await;
+    assert!(cache.inner.read().contains_key(&()));
+
+    // If the next access is missed, the entry is evicted.
+    time::sleep(period).await;
+    assert!(!cache.inner.read().contains_key(&()));
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "current_thread")]
+async fn test_activity_evicts_held() {
+    time::pause();
+
+    let idle = time::Duration::from_secs(10);
+    let cache = IdleCache::new(idle).with_activity_tracking();
+
+    // While the entry is marked active, it is retained.
+    let held = cache.get_or_insert_with((), |_| ());
+    for _ in 0..3 {
+        time::sleep(idle / 2).await;
+        held.mark_active();
+    }
+    assert!(cache.inner.read().contains_key(&()));
+
+    // Once it is no longer marked active, it is evicted even though a handle
+    // is still held.
+    time::sleep(idle * 2).await;
+    assert!(!cache.inner.read().contains_key(&()));
+    drop(held);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "current_thread")]
+async fn test_max_age() {
+    time::pause();
+
+    let idle = time::Duration::from_secs(10);
+    let max_age = time::Duration::from_secs(60);
+    let cache = IdleCache::new(idle).with_max_age(max_age);
+
+    // The entry is held, so it is not evicted when idle...
+    let held = cache.get_or_insert_with((), |_| 0);
+    time::sleep(max_age - idle).await;
+    assert!(cache.inner.read().contains_key(&()));
+
+    // ...but it is evicted once it reaches its maximum age, and rebuilt when
+    // it is next accessed.
+    time::sleep(idle * 2).await;
+    assert!(!cache.inner.read().contains_key(&()));
+    assert_eq!(*cache.get_or_insert_with((), |_| 1), 1);
+    assert_eq!(*held, 0);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "current_thread")]
+async fn test_concurrent_misses_build_once() {
+    use std::sync::{atomic::AtomicUsize, Barrier};
+
+    const THREADS: usize = 100;
+
+    let gauge = prom::Gauge::default();
+    let cache = IdleCache::new(time::Duration::from_secs(10)).with_builds_gauge(gauge.clone());
+    let built = Arc::new(AtomicUsize::new(0));
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    // Race all threads to build the value for the same key.
+    let threads = (0..THREADS)
+        .map(|_| {
+            let cache = cache.clone();
+            let gauge = gauge.clone();
+            let built = built.clone();
+            let barrier = barrier.clone();
+            let rt = tokio::runtime::Handle::current();
+            std::thread::spawn(move || {
+                let _rt = rt.enter();
+                barrier.wait();
+                cache.get_or_insert_with((), |_| {
+                    assert_eq!(gauge.get(), 1, "the value must be in progress");
+                    built.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    let cached = threads
+        .into_iter()
+        .map(|t| t.join().expect("thread must not panic"))
+        .collect::<Vec<_>>();
+
+    assert_eq!(cached.len(), THREADS);
+    assert_eq!(built.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert_eq!(gauge.get(), 0);
+    assert!(cache.building.lock().is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "current_thread")]
+async fn test_build_panic_releases_key() {
+    let gauge = prom::Gauge::default();
+    let cache = IdleCache::new(time::Duration::from_secs(10)).with_builds_gauge(gauge.clone());
+
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        cache.get_or_insert_with((), |_| -> usize { panic!("build failed") })
+    }));
+    assert!(panicked.is_err());
+    assert_eq!(gauge.get(), 0, "the failed build must not be counted");
+    assert!(cache.building.lock().is_empty());
+
+    // The key may be built again.
+    assert_eq!(*cache.get_or_insert_with((), |_| 1), 1);
+    assert_eq!(gauge.get(), 0);
+}
@@ -28,6 +28,33 @@ where
     pub fn layer(idle: time::Duration) -> impl layer::Layer<N, Service = Self> + Clone {
         layer::mk(move |new_svc| Self::new(new_svc, idle))
     }
+
+    /// Retains idle services that are built at regular intervals, as
+    /// determined by `periodic`.
+    pub fn with_periodic(mut self, periodic: Arc<Periodic<T>>) -> Self {
+        self.cache = self.cache.with_periodic(periodic);
+        self
+    }
+
+    /// Records the number of services that are being built in `builds`.
+    pub fn with_builds_gauge(mut self, builds: prom::Gauge) -> Self {
+        self.cache = self.cache.with_builds_gauge(builds);
+        self
+    }
+
+    /// Rebuilds services once they are `max_age` old, regardless of activity.
+    pub fn with_max_age(mut self, max_age: time::Duration) -> Self {
+        self.cache = self.cache.with_max_age(max_age);
+        self
+    }
+
+    /// Evicts services that have not been marked active (with
+    /// [`Cached::mark_active`]) within the idle timeout, even if they are
+    /// held.
+    pub fn with_activity_tracking(mut self) -> Self {
+        self.cache = self.cache.with_activity_tracking();
+        self
+    }
 }
 
 impl<T, N> NewService<T> for NewIdleCached<T, N>
@@ -0,0 +1,261 @@
+//! Retains idle entries that are accessed at regular intervals.
+//!
+//! Clients that connect to a destination on a fixed schedule (e.g. every 15
+//! minutes) find that it has been evicted from the cache before each access
+//! whenever the interval is longer than the cache's idle timeout. [`Periodic`]
+//! tracks the intervals between accesses of each key and, when they are
+//! regular, retains the entry until shortly after its next expected access.
+
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+use tokio::time::{Duration, Instant};
+
+/// Configures which idle entries are retained.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PeriodicConfig {
+    /// The longest access interval for which an entry is retained.
+    pub max_period: Duration,
+
+    /// The maximum number of entries retained at any time.
+    pub max_entries: usize,
+}
+
+/// Tracks the access intervals of cache keys to determine whether idle entries
+/// should be retained.
+#[derive(Debug)]
+pub struct Periodic<K> {
+    config: PeriodicConfig,
+    histories: Mutex<HashMap<K, History>>,
+}
+
+/// Describes a retained entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Retained<K> {
+    pub key: K,
+
+    /// The average interval between accesses.
+    pub period: Duration,
+
+    /// The intervals between the most recent accesses.
+    pub intervals: Vec<Duration>,
+
+    /// The time at which the entry would otherwise have been evicted.
+    pub since: Instant,
+
+    /// The time at which the entry will be evicted unless it is accessed.
+    pub until: Instant,
+}
+
+#[derive(Debug)]
+struct History {
+    last: Instant,
+    intervals: VecDeque<Duration>,
+    retained: Option<(Instant, Instant)>,
+}
+
+/// The number of intervals that must be observed before an entry is retained.
+const SAMPLES: usize = 3;
+
+/// The number of keys tracked for each retained entry. Keys that have not been
+/// accessed within the maximum period are forgotten when this limit is
+/// reached.
+const HISTORIES_PER_ENTRY: usize = 16;
+
+// === impl Periodic ===
+
+impl<K: Clone + Eq + Hash> Periodic<K> {
+    pub fn new(config: PeriodicConfig) -> Self {
+        Self {
+            config,
+            histories: Default::default(),
+        }
+    }
+
+    /// Returns the entries that are currently retained.
+    pub fn retained(&self) -> Vec<Retained<K>> {
+        self.histories
+            .lock()
+            .iter()
+            .filter_map(|(key, h)| {
+                let (since, until) = h.retained?;
+                Some(Retained {
+                    key: key.clone(),
+                    period: h.period(),
+                    intervals: h.intervals.iter().copied().collect(),
+                    since,
+                    until,
+                })
+            })
+            .collect()
+    }
+
+    /// Records an access of `key`. Intervals no longer than the cache's idle
+    /// timeout are not recorded, since the entry would not have been evicted.
+    pub(crate) fn accessed(&self, key: &K, idle: Duration) {
+        let now = Instant::now();
+        let mut histories = self.histories.lock();
+        if let Some(h) = histories.get_mut(key) {
+            let interval = now.saturating_duration_since(h.last);
+            h.last = now;
+            if interval > idle {
+                if h.intervals.len() == SAMPLES {
+                    h.intervals.pop_front();
+                }
+                h.intervals.push_back(interval);
+            }
+            return;
+        }
+
+        let capacity = self.config.max_entries.saturating_mul(HISTORIES_PER_ENTRY);
+        if histories.len() >= capacity {
+            let max = self.config.max_period;
+            histories.retain(|_, h| {
+                h.retained.is_some() || now.saturating_duration_since(h.last) <= max
+            });
+            if histories.len() >= capacity {
+                return;
+            }
+        }
+        histories.insert(
+            key.clone(),
+            History {
+                last: now,
+                intervals: VecDeque::with_capacity(SAMPLES),
+                retained: None,
+            },
+        );
+    }
+
+    /// Returns the time until which an otherwise-idle entry for `key` should be
+    /// retained, if at all.
+    pub(crate) fn retain(&self, key: &K) -> Option<Instant> {
+        let now = Instant::now();
+        let mut histories = self.histories.lock();
+        let retained = histories.values().filter(|h| h.retained.is_some()).count();
+        let h = histories.get_mut(key)?;
+
+        if h.is_regular() {
+            let period = h.period();
+            // Allow the next access to arrive up to half a period late.
+            let until = h.last + period + period / 2;
+            if period <= self.config.max_period
+                && until > now
+                && (h.retained.is_some() || retained < self.config.max_entries)
+            {
+                let since = h.retained.map_or(now, |(since, _)| since);
+                h.retained = Some((since, until));
+                return Some(until);
+            }
+        }
+
+        h.retained = None;
+        None
+    }
+}
+
+// === impl History ===
+
+impl History {
+    fn period(&self) -> Duration {
+        let total = self.intervals.iter().sum::<Duration>();
+        total / self.intervals.len().max(1) as u32
+    }
+
+    /// Returns true if enough intervals have been observed and each is within
+    /// 25% of their average.
+    fn is_regular(&self) -> bool {
+        if self.intervals.len() < SAMPLES {
+            return false;
+        }
+        let period = self.period();
+        let tolerance = period / 4;
+        self.intervals
+            .iter()
+            .all(|i| i.abs_diff(period) <= tolerance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time;
+
+    const IDLE: Duration = Duration::from_secs(5);
+    const PERIOD: Duration = Duration::from_secs(15 * 60);
+
+    fn periodic(max_entries: usize) -> Periodic<&'static str> {
+        Periodic::new(PeriodicConfig {
+            max_period: Duration::from_secs(60 * 60),
+            max_entries,
+        })
+    }
+
+    async fn access(p: &Periodic<&'static str>, key: &'static str, times: usize) {
+        for _ in 0..times {
+            p.accessed(&key, IDLE);
+            time::sleep(PERIOD).await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retains_regular_intervals() {
+        let p = periodic(10);
+
+        // Three intervals must be observed.
+        access(&p, "cron", 3).await;
+        assert_eq!(p.retain(&"cron"), None);
+        p.accessed(&"cron", IDLE);
+        let until = p.retain(&"cron").expect("entry must be retained");
+        assert_eq!(until, Instant::now() + PERIOD + PERIOD / 2);
+
+        let retained = p.retained();
+        assert_eq!(retained.len(), 1);
+        assert_eq!(retained[0].key, "cron");
+        assert_eq!(retained[0].period, PERIOD);
+
+        // If the next access is missed, the entry is released.
+        time::sleep(PERIOD * 2).await;
+        assert_eq!(p.retain(&"cron"), None);
+        assert!(p.retained().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ignores_irregular_intervals() {
+        let p = periodic(10);
+        for interval in [60, 600, 60, 600] {
+            p.accessed(&"bursty", IDLE);
+            time::sleep(Duration::from_secs(interval)).await;
+        }
+        p.accessed(&"bursty", IDLE);
+        assert_eq!(p.retain(&"bursty"), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ignores_long_periods() {
+        let p = Periodic::new(PeriodicConfig {
+            max_period: Duration::from_secs(60),
+            max_entries: 10,
+        });
+        access(&p, "hourly", 4).await;
+        p.accessed(&"hourly", IDLE);
+        assert_eq!(p.retain(&"hourly"), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bounds_retained_entries() {
+        let p = periodic(1);
+        for _ in 0..4 {
+            p.accessed(&"a", IDLE);
+            p.accessed(&"b", IDLE);
+            time::sleep(PERIOD).await;
+        }
+        p.accessed(&"a", IDLE);
+        p.accessed(&"b", IDLE);
+        assert!(p.retain(&"a").is_some());
+        assert_eq!(p.retain(&"b"), None, "only one entry may be retained");
+        assert!(p.retain(&"a").is_some(), "retained entries are extended");
+    }
+}
@@ -1,4 +1,4 @@
-use super::{AsyncRead, AsyncWrite, IoSlice, PeerAddr, Poll, ReadBuf, Result};
+use super::{AsyncRead, AsyncWrite, IoSlice, PeerAddr, Poll, ReadBuf, Result, Splice};
 use std::{pin::Pin, task::Context};
 
 /// A public wrapper around a `Box<Io>`.
@@ -29,6 +29,9 @@ impl PeerAddr for BoxedIo {
     }
 }
 
+/// Boxed I/O is always copied.
+impl Splice for BoxedIo {}
+
 impl AsyncRead for BoxedIo {
     fn poll_read(
         mut self: Pin<&mut Self>,
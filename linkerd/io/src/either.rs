@@ -33,6 +33,32 @@ impl<L: io::PeerAddr, R: io::PeerAddr> io::PeerAddr for EitherIo<L, R> {
     }
 }
 
+impl<L: io::Splice, R: io::Splice> io::Splice for EitherIo<L, R> {
+    #[inline]
+    fn splice_socket(&self) -> Option<&tokio::net::TcpStream> {
+        match self {
+            Self::Left(l) => l.splice_socket(),
+            Self::Right(r) => r.splice_socket(),
+        }
+    }
+
+    #[inline]
+    fn take_prefix(&mut self) -> bytes::Bytes {
+        match self {
+            Self::Left(l) => l.take_prefix(),
+            Self::Right(r) => r.take_prefix(),
+        }
+    }
+
+    #[inline]
+    fn record_spliced(&mut self, read: usize, written: usize) {
+        match self {
+            Self::Left(l) => l.record_spliced(read, written),
+            Self::Right(r) => r.record_spliced(read, written),
+        }
+    }
+}
+
 impl<L: io::AsyncRead, R: io::AsyncRead> io::AsyncRead for EitherIo<L, R> {
     #[inline]
     fn poll_read(
@@ -52,6 +52,42 @@ impl Peek for tokio::io::DuplexStream {
     }
 }
 
+// === Splice ===
+
+/// Exposes the TCP socket underlying an I/O type so that bytes may be moved
+/// between sockets without being copied through userspace.
+///
+/// By default, I/O types expose no socket and must be copied.
+pub trait Splice {
+    /// Returns the socket underlying this I/O, if the bytes read from and
+    /// written to this I/O are exactly those received and sent on the socket.
+    fn splice_socket(&self) -> Option<&tokio::net::TcpStream> {
+        None
+    }
+
+    /// Takes any bytes that were buffered ahead of the socket (e.g. during
+    /// protocol detection) so that they may be written before the socket is
+    /// spliced.
+    fn take_prefix(&mut self) -> bytes::Bytes {
+        bytes::Bytes::new()
+    }
+
+    /// Records bytes that were received from and sent on the underlying socket
+    /// without passing through this I/O.
+    fn record_spliced(&mut self, _read: usize, _written: usize) {}
+}
+
+impl Splice for tokio::net::TcpStream {
+    fn splice_socket(&self) -> Option<&tokio::net::TcpStream> {
+        Some(self)
+    }
+}
+
+impl Splice for tokio::io::DuplexStream {}
+
+#[cfg(feature = "tokio-test")]
+impl Splice for tokio_test::io::Mock {}
+
 // === PeerAddr ===
 
 pub trait PeerAddr {
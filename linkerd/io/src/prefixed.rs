@@ -50,6 +50,30 @@ impl<I: io::PeerAddr> io::PeerAddr for PrefixedIo<I> {
     }
 }
 
+impl<I: io::Splice> io::Splice for PrefixedIo<I> {
+    /// The socket is only exposed once the prefix has been consumed.
+    fn splice_socket(&self) -> Option<&tokio::net::TcpStream> {
+        if !self.prefix.is_empty() {
+            return None;
+        }
+        self.io.splice_socket()
+    }
+
+    fn take_prefix(&mut self) -> Bytes {
+        let mut prefix = std::mem::take(&mut self.prefix);
+        let inner = self.io.take_prefix();
+        if !inner.is_empty() {
+            // Bytes buffered by the inner I/O are read after this prefix.
+            prefix = [prefix, inner].concat().into();
+        }
+        prefix
+    }
+
+    fn record_spliced(&mut self, read: usize, written: usize) {
+        self.io.record_spliced(read, written)
+    }
+}
+
 impl<I: io::AsyncRead> io::AsyncRead for PrefixedIo<I> {
     fn poll_read(
         self: Pin<&mut Self>,
@@ -77,6 +77,23 @@ impl<I: io::PeerAddr> io::PeerAddr for ScopedIo<I> {
     }
 }
 
+impl<I: io::Splice> io::Splice for ScopedIo<I> {
+    #[inline]
+    fn splice_socket(&self) -> Option<&tokio::net::TcpStream> {
+        self.io.splice_socket()
+    }
+
+    #[inline]
+    fn take_prefix(&mut self) -> bytes::Bytes {
+        self.io.take_prefix()
+    }
+
+    #[inline]
+    fn record_spliced(&mut self, read: usize, written: usize) {
+        self.io.record_spliced(read, written)
+    }
+}
+
 impl<I: io::AsyncRead> io::AsyncRead for ScopedIo<I> {
     #[inline]
     fn poll_read(
@@ -1,4 +1,4 @@
-use crate::{IoSlice, Peek, PeerAddr, Poll};
+use crate::{IoSlice, Peek, PeerAddr, Poll, Splice};
 use futures::ready;
 use linkerd_errno::Errno;
 use pin_project::pin_project;
@@ -89,3 +89,29 @@ impl<I: Peek + Send + Sync, S: Sensor + Sync> Peek for SensorIo<I, S> {
         self.io.peek(buf).await
     }
 }
+
+impl<T: Splice, S: Sensor> Splice for SensorIo<T, S> {
+    fn splice_socket(&self) -> Option<&tokio::net::TcpStream> {
+        self.io.splice_socket()
+    }
+
+    fn take_prefix(&mut self) -> bytes::Bytes {
+        // The prefix is read through this I/O, even though it's not read via
+        // `poll_read`.
+        let prefix = self.io.take_prefix();
+        if !prefix.is_empty() {
+            self.sensor.record_read(prefix.len());
+        }
+        prefix
+    }
+
+    fn record_spliced(&mut self, read: usize, written: usize) {
+        if read > 0 {
+            self.sensor.record_read(read);
+        }
+        if written > 0 {
+            self.sensor.record_write(written);
+        }
+        self.io.record_spliced(read, written);
+    }
+}
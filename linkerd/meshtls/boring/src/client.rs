@@ -3,12 +3,20 @@ use linkerd_identity as id;
 use linkerd_io as io;
 use linkerd_meshtls_verifier as verifier;
 use linkerd_stack::{NewService, Service};
-use linkerd_tls::{client::AlpnProtocols, ClientTls, NegotiatedProtocolRef, ServerName};
+use linkerd_tls::{
+    client::AlpnProtocols,
+    metrics::{FailureReason, HandshakeMetrics, Side},
+    ClientTls, NegotiatedProtocolRef, ServerName, TlsPolicy,
+};
 use std::{future::Future, pin::Pin, sync::Arc, task::Context};
 use tracing::{debug, trace};
 
 #[derive(Clone)]
-pub struct NewClient(CredsRx);
+pub struct NewClient {
+    rx: CredsRx,
+    policy: Arc<TlsPolicy>,
+    metrics: HandshakeMetrics,
+}
 
 #[derive(Clone)]
 pub struct Connect {
@@ -16,6 +24,8 @@ pub struct Connect {
     alpn: Option<Arc<[Vec<u8>]>>,
     id: id::Id,
     server: ServerName,
+    policy: Arc<TlsPolicy>,
+    metrics: HandshakeMetrics,
 }
 
 pub type ConnectFuture<I> = Pin<Box<dyn Future<Output = io::Result<ClientIo<I>>> + Send>>;
@@ -26,8 +36,12 @@ pub struct ClientIo<I>(tokio_boring::SslStream<I>);
 // === impl NewClient ===
 
 impl NewClient {
-    pub(crate) fn new(rx: CredsRx) -> Self {
-        Self(rx)
+    pub(crate) fn new(rx: CredsRx, policy: Arc<TlsPolicy>, metrics: HandshakeMetrics) -> Self {
+        Self {
+            rx,
+            policy,
+            metrics,
+        }
     }
 }
 
@@ -35,7 +49,12 @@ impl NewService<ClientTls> for NewClient {
     type Service = Connect;
 
     fn new_service(&self, target: ClientTls) -> Self::Service {
-        Connect::new(target, self.0.clone())
+        Connect::new(
+            target,
+            self.rx.clone(),
+            self.policy.clone(),
+            self.metrics.clone(),
+        )
     }
 }
 
@@ -48,9 +67,16 @@ impl std::fmt::Debug for NewClient {
 // === impl Connect ===
 
 impl Connect {
-    pub(crate) fn new(client_tls: ClientTls, rx: CredsRx) -> Self {
+    pub(crate) fn new(
+        client_tls: ClientTls,
+        rx: CredsRx,
+        policy: Arc<TlsPolicy>,
+        metrics: HandshakeMetrics,
+    ) -> Self {
         Self {
             rx,
+            policy,
+            metrics,
             alpn: client_tls.alpn.map(|AlpnProtocols(ps)| ps.into()),
             server: client_tls.server_name,
             id: client_tls.server_id.into(),
@@ -73,6 +99,8 @@ where
     fn call(&mut self, io: I) -> Self::Future {
         let server_name = self.server.clone();
         let server_id = self.id.clone();
+        let policy = self.policy.clone();
+        let metrics = self.metrics.clone();
         let connector = self
             .rx
             .borrow()
@@ -92,23 +120,32 @@ where
             // verification after the session is established.
             let io = tokio_boring::connect(config.verify_hostname(false), server_name.as_str(), io)
                 .await
-                .map_err(|e| match e.as_io_error() {
-                    // TODO(ver) boring should let us take ownership of the error directly.
-                    Some(ioe) => io::Error::new(ioe.kind(), ioe.to_string()),
-                    // XXX(ver) to use the boring error directly here we have to
-                    // constrain the socket on Sync + std::fmt::Debug, which is
-                    // a pain.
-                    None => io::Error::other("unexpected TLS handshake error"),
+                .map_err(|e| {
+                    let reason = crate::metrics::failure_reason(&e);
+                    metrics.failed(Side::Client, reason);
+                    let e = match e.as_io_error() {
+                        // TODO(ver) boring should let us take ownership of the error directly.
+                        Some(ioe) => io::Error::new(ioe.kind(), ioe.to_string()),
+                        // XXX(ver) to use the boring error directly here we have to
+                        // constrain the socket on Sync + std::fmt::Debug, which is
+                        // a pain.
+                        None => io::Error::other("unexpected TLS handshake error"),
+                    };
+                    policy.handshake_failed(Side::Client, reason, e, &metrics)
                 })?;
 
             // Servers must present a peer certificate. We extract the x509 cert
             // and verify it manually against the `server_id`.
-            let cert = io
-                .ssl()
-                .peer_certificate()
-                .ok_or_else(|| io::Error::other("could not extract peer cert"))?;
+            let cert = io.ssl().peer_certificate().ok_or_else(|| {
+                metrics.failed(Side::Client, FailureReason::NoCertificate);
+                io::Error::other("could not extract peer cert")
+            })?;
             let cert_der = id::DerX509(cert.to_der()?);
-            verifier::verify_id(&cert_der, &server_id)?;
+            verifier::verify_id(&cert_der, &server_id)
+                .inspect_err(|_| metrics.failed(Side::Client, FailureReason::IdentityMismatch))?;
+            let session = crate::metrics::session(io.ssl(), true);
+            policy.check_established(Side::Client, &session, &metrics)?;
+            metrics.established(Side::Client, session);
 
             debug!(
                 tls = io.ssl().version_str(),
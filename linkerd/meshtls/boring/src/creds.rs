@@ -10,6 +10,7 @@ use boring::{
 use linkerd_dns_name as dns;
 use linkerd_error::Result;
 use linkerd_identity as id;
+use linkerd_tls::{metrics::ProtocolVersion, PolicyMode, TlsPolicy};
 use std::sync::Arc;
 use tokio::sync::watch;
 
@@ -17,14 +18,20 @@ pub fn watch(
     local_id: id::Id,
     server_name: dns::Name,
     roots_pem: &str,
+    policy: TlsPolicy,
 ) -> Result<(Store, Receiver)> {
     let creds = {
         let roots = X509::stack_from_pem(roots_pem.as_bytes())?;
-        Arc::new(BaseCreds { roots })
+        let accept_tls12 =
+            policy.mode == PolicyMode::Audit || policy.permits_version(ProtocolVersion::Tls12);
+        Arc::new(BaseCreds {
+            roots,
+            accept_tls12,
+        })
     };
 
     let (tx, rx) = watch::channel(Creds::from(creds.clone()));
-    let rx = Receiver::new(local_id.clone(), server_name, rx);
+    let rx = Receiver::new(local_id.clone(), server_name, rx, policy);
     let store = Store::new(creds, local_id, tx);
 
     Ok((store, rx))
@@ -37,6 +44,9 @@ pub(crate) struct Creds {
 
 struct BaseCreds {
     roots: Vec<X509>,
+
+    /// Whether TLSv1.2 is accepted, as permitted by the mesh TLS policy.
+    accept_tls12: bool,
 }
 
 struct Certs {
@@ -63,8 +73,10 @@ impl Creds {
         // mozilla_intermediate_v5 is the only variant that enables TLSv1.3, so we use that.
         let mut conn = ssl::SslAcceptor::mozilla_intermediate_v5(ssl::SslMethod::tls_server())?;
 
-        // Force use of TLSv1.3.
-        conn.set_options(ssl::SslOptions::NO_TLSV1_2);
+        // Force use of TLSv1.3, unless the policy permits TLSv1.2.
+        if !self.base.accept_tls12 {
+            conn.set_options(ssl::SslOptions::NO_TLSV1_2);
+        }
         conn.clear_options(ssl::SslOptions::NO_TLSV1_3);
 
         let roots = self.root_store()?;
@@ -2,17 +2,33 @@ use super::CredsRx;
 use crate::{NewClient, Server};
 use linkerd_dns_name as dns;
 use linkerd_identity as id;
+use linkerd_tls::{HandshakeMetrics, TlsPolicy};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct Receiver {
     id: id::Id,
     name: dns::Name,
     rx: CredsRx,
+    policy: Arc<TlsPolicy>,
+    metrics: HandshakeMetrics,
 }
 
 impl Receiver {
-    pub(crate) fn new(id: id::Id, name: dns::Name, rx: CredsRx) -> Self {
-        Self { id, name, rx }
+    pub(crate) fn new(id: id::Id, name: dns::Name, rx: CredsRx, policy: TlsPolicy) -> Self {
+        Self {
+            id,
+            name,
+            rx,
+            policy: Arc::new(policy),
+            metrics: HandshakeMetrics::default(),
+        }
+    }
+
+    /// Records the handshakes of all clients and servers built from this
+    /// receiver.
+    pub fn with_handshake_metrics(self, metrics: HandshakeMetrics) -> Self {
+        Self { metrics, ..self }
     }
 
     /// Returns the local identity.
@@ -27,12 +43,17 @@ impl Receiver {
 
     /// Returns a `NewClient` that can be used to establish TLS on client connections.
     pub fn new_client(&self) -> NewClient {
-        NewClient::new(self.rx.clone())
+        NewClient::new(self.rx.clone(), self.policy.clone(), self.metrics.clone())
     }
 
     /// Returns a `Server` that can be used to terminate TLS on server connections.
     pub fn server(&self) -> Server {
-        Server::new(self.name.clone(), self.rx.clone())
+        Server::new(
+            self.name.clone(),
+            self.rx.clone(),
+            self.policy.clone(),
+            self.metrics.clone(),
+        )
     }
 }
 
@@ -25,6 +25,7 @@
 
 mod client;
 pub mod creds;
+mod metrics;
 mod server;
 #[cfg(test)]
 mod tests;
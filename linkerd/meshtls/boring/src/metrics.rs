@@ -0,0 +1,36 @@
+use boring::ssl::{SslRef, SslVersion};
+use linkerd_tls::metrics::{CipherSuite, FailureReason, ProtocolVersion, Session};
+
+/// Describes the session negotiated on a connection.
+pub(crate) fn session(ssl: &SslRef, peer_identity: bool) -> Session {
+    let version = match ssl.version2() {
+        Some(SslVersion::TLS1_2) => ProtocolVersion::Tls12,
+        Some(SslVersion::TLS1_3) => ProtocolVersion::Tls13,
+        _ => ProtocolVersion::Other,
+    };
+    let cipher_suite = ssl
+        .current_cipher()
+        .and_then(|c| c.standard_name())
+        .map_or(CipherSuite::Other, CipherSuite::from_standard_name);
+    Session {
+        version,
+        cipher_suite,
+        resumed: ssl.session_reused(),
+        peer_identity,
+    }
+}
+
+/// Determines why a handshake failed.
+///
+/// Boring does not expose the alerts received from peers through
+/// `tokio-boring`, so failures that are not caused by I/O or certificate
+/// verification are not classified further.
+pub(crate) fn failure_reason<S>(error: &tokio_boring::HandshakeError<S>) -> FailureReason {
+    if error.as_io_error().is_some() {
+        return FailureReason::Io;
+    }
+    match error.ssl() {
+        Some(ssl) if ssl.verify_result().is_err() => FailureReason::InvalidCertificate,
+        _ => FailureReason::Other,
+    }
+}
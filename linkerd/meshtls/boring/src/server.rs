@@ -3,7 +3,10 @@ use linkerd_dns_name as dns;
 use linkerd_io as io;
 use linkerd_meshtls_verifier as verifier;
 use linkerd_stack::{Param, Service};
-use linkerd_tls::{ClientId, NegotiatedProtocol, ServerName, ServerTls};
+use linkerd_tls::{
+    metrics::{HandshakeMetrics, Side},
+    ClientId, NegotiatedProtocol, ServerName, ServerTls, TlsPolicy,
+};
 use std::{future::Future, pin::Pin, sync::Arc, task::Context};
 use tracing::debug;
 
@@ -12,6 +15,8 @@ pub struct Server {
     name: dns::Name,
     rx: CredsRx,
     alpn: Option<Arc<[Vec<u8>]>>,
+    policy: Arc<TlsPolicy>,
+    metrics: HandshakeMetrics,
 }
 
 pub type TerminateFuture<I> =
@@ -23,11 +28,18 @@ pub struct ServerIo<I>(tokio_boring::SslStream<I>);
 // === impl Server ===
 
 impl Server {
-    pub(crate) fn new(name: dns::Name, rx: CredsRx) -> Self {
+    pub(crate) fn new(
+        name: dns::Name,
+        rx: CredsRx,
+        policy: Arc<TlsPolicy>,
+        metrics: HandshakeMetrics,
+    ) -> Self {
         Self {
             name,
             rx,
             alpn: None,
+            policy,
+            metrics,
         }
     }
 
@@ -67,20 +79,30 @@ where
             .rx
             .borrow()
             .acceptor(self.alpn.as_deref().unwrap_or(&[]));
+        let policy = self.policy.clone();
+        let metrics = self.metrics.clone();
         Box::pin(async move {
             let acc = acceptor.map_err(io::Error::other)?;
             let io = tokio_boring::accept(&acc, io)
                 .await
                 .map(ServerIo)
-                .map_err(|e| match e.as_io_error() {
-                    Some(ioe) => io::Error::new(ioe.kind(), ioe.to_string()),
-                    // XXX(ver) to use the boring error directly here we have to constraint the
-                    // socket on Sync + std::fmt::Debug, which is a pain.
-                    None => io::Error::other("unexpected TLS handshake error"),
+                .map_err(|e| {
+                    let reason = crate::metrics::failure_reason(&e);
+                    metrics.failed(Side::Server, reason);
+                    let e = match e.as_io_error() {
+                        Some(ioe) => io::Error::new(ioe.kind(), ioe.to_string()),
+                        // XXX(ver) to use the boring error directly here we have to constraint the
+                        // socket on Sync + std::fmt::Debug, which is a pain.
+                        None => io::Error::other("unexpected TLS handshake error"),
+                    };
+                    policy.handshake_failed(Side::Server, reason, e, &metrics)
                 })?;
 
             let client_id = io.client_identity();
             let negotiated_protocol = io.negotiated_protocol();
+            let session = crate::metrics::session(io.0.ssl(), client_id.is_some());
+            policy.check_established(Side::Server, &session, &metrics)?;
+            metrics.established(Side::Server, session);
 
             debug!(
                 tls = io.0.ssl().version_str(),
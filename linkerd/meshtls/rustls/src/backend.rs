@@ -11,6 +11,10 @@ mod aws_lc;
 mod ring;
 
 #[cfg(feature = "aws-lc")]
-pub use aws_lc::{default_provider, SUPPORTED_SIG_ALGS, TLS_SUPPORTED_CIPHERSUITES};
+pub use aws_lc::{
+    default_provider, SUPPORTED_SIG_ALGS, TLS12_CIPHERSUITES, TLS_SUPPORTED_CIPHERSUITES,
+};
 #[cfg(feature = "ring")]
-pub use ring::{default_provider, SUPPORTED_SIG_ALGS, TLS_SUPPORTED_CIPHERSUITES};
+pub use ring::{
+    default_provider, SUPPORTED_SIG_ALGS, TLS12_CIPHERSUITES, TLS_SUPPORTED_CIPHERSUITES,
+};
@@ -9,6 +9,12 @@ pub static TLS_SUPPORTED_CIPHERSUITES: &[rustls::SupportedCipherSuite] = &[
     ring::cipher_suite::TLS13_AES_256_GCM_SHA384,
     ring::cipher_suite::TLS13_CHACHA20_POLY1305_SHA256,
 ];
+/// TLS 1.2 cipher suites, offered only when permitted by the mesh TLS policy.
+pub static TLS12_CIPHERSUITES: &[rustls::SupportedCipherSuite] = &[
+    ring::cipher_suite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+    ring::cipher_suite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+    ring::cipher_suite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+];
 // A subset of the algorithms supported by rustls+ring, imported from
 // https://github.com/rustls/rustls/blob/v/0.23.21/rustls/src/crypto/ring/mod.rs#L107
 pub static SUPPORTED_SIG_ALGS: &WebPkiSupportedAlgorithms = &WebPkiSupportedAlgorithms {
@@ -3,7 +3,11 @@ use linkerd_identity as id;
 use linkerd_io as io;
 use linkerd_meshtls_verifier as verifier;
 use linkerd_stack::{NewService, Service};
-use linkerd_tls::{client::AlpnProtocols, ClientTls, NegotiatedProtocolRef};
+use linkerd_tls::{
+    client::AlpnProtocols,
+    metrics::{FailureReason, HandshakeMetrics, Side},
+    ClientTls, NegotiatedProtocolRef, TlsPolicy,
+};
 use std::{convert::TryFrom, pin::Pin, sync::Arc, task::Context};
 use tokio::sync::watch;
 use tokio_rustls::rustls::{self, pki_types::CertificateDer, ClientConfig};
@@ -12,6 +16,8 @@ use tokio_rustls::rustls::{self, pki_types::CertificateDer, ClientConfig};
 #[derive(Clone)]
 pub struct NewClient {
     config: watch::Receiver<Arc<ClientConfig>>,
+    policy: Arc<TlsPolicy>,
+    metrics: HandshakeMetrics,
 }
 
 /// A `Service` that initiates client-side TLS connections.
@@ -20,6 +26,8 @@ pub struct Connect {
     server_id: id::Id,
     server_name: rustls::pki_types::ServerName<'static>,
     config: Arc<ClientConfig>,
+    policy: Arc<TlsPolicy>,
+    metrics: HandshakeMetrics,
 }
 
 pub type ConnectFuture<I> = Pin<Box<dyn Future<Output = io::Result<ClientIo<I>>> + Send>>;
@@ -30,8 +38,16 @@ pub struct ClientIo<I>(tokio_rustls::client::TlsStream<I>);
 // === impl NewClient ===
 
 impl NewClient {
-    pub(crate) fn new(config: watch::Receiver<Arc<ClientConfig>>) -> Self {
-        Self { config }
+    pub(crate) fn new(
+        config: watch::Receiver<Arc<ClientConfig>>,
+        policy: Arc<TlsPolicy>,
+        metrics: HandshakeMetrics,
+    ) -> Self {
+        Self {
+            config,
+            policy,
+            metrics,
+        }
     }
 }
 
@@ -39,7 +55,12 @@ impl NewService<ClientTls> for NewClient {
     type Service = Connect;
 
     fn new_service(&self, target: ClientTls) -> Self::Service {
-        Connect::new(target, (*self.config.borrow()).clone())
+        Connect::new(
+            target,
+            (*self.config.borrow()).clone(),
+            self.policy.clone(),
+            self.metrics.clone(),
+        )
     }
 }
 
@@ -52,7 +73,12 @@ impl std::fmt::Debug for NewClient {
 // === impl Connect ===
 
 impl Connect {
-    pub(crate) fn new(client_tls: ClientTls, config: Arc<ClientConfig>) -> Self {
+    pub(crate) fn new(
+        client_tls: ClientTls,
+        config: Arc<ClientConfig>,
+        policy: Arc<TlsPolicy>,
+        metrics: HandshakeMetrics,
+    ) -> Self {
         // If ALPN protocols are configured by the endpoint, we have to clone the entire
         // configuration and set the protocols. If there are no ALPN options, clone the Arc'd base
         // configuration without extra allocation.
@@ -76,6 +102,8 @@ impl Connect {
             server_id: client_tls.server_id.into(),
             server_name,
             config,
+            policy,
+            metrics,
         }
     }
 }
@@ -101,6 +129,8 @@ where
 
     fn call(&mut self, io: I) -> Self::Future {
         let server_id = self.server_id.clone();
+        let policy = self.policy.clone();
+        let metrics = self.metrics.clone();
         Box::pin(
             // Connect to the server, sending the `server_name` SNI in the
             // client handshake. The provided config should use the
@@ -111,10 +141,21 @@ where
                 // XXX(eliza): it's a bummer that the server name has to be cloned here...
                 .connect(self.server_name.clone(), io)
                 .map(move |s| {
-                    let s = s?;
+                    let s = s.map_err(|e| {
+                        let reason = crate::metrics::failure_reason(&e);
+                        metrics.failed(Side::Client, reason);
+                        policy.handshake_failed(Side::Client, reason, e, &metrics)
+                    })?;
                     let (_, conn) = s.get_ref();
-                    let end_cert = extract_cert(conn)?;
-                    verifier::verify_id(end_cert, &server_id)?;
+                    let end_cert = extract_cert(conn).inspect_err(|_| {
+                        metrics.failed(Side::Client, FailureReason::NoCertificate)
+                    })?;
+                    verifier::verify_id(end_cert, &server_id).inspect_err(|_| {
+                        metrics.failed(Side::Client, FailureReason::IdentityMismatch)
+                    })?;
+                    let session = crate::metrics::session(conn, true);
+                    policy.check_established(Side::Client, &session, &metrics)?;
+                    metrics.established(Side::Client, session);
                     Ok(ClientIo(s))
                 }),
         )
@@ -8,6 +8,10 @@ pub use self::{receiver::Receiver, store::Store};
 use linkerd_dns_name as dns;
 use linkerd_error::Result;
 use linkerd_identity as id;
+use linkerd_tls::{
+    metrics::{CipherSuite, ProtocolVersion},
+    PolicyMode, TlsPolicy,
+};
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::watch;
@@ -18,11 +22,25 @@ use tracing::warn;
 #[error("invalid trust roots")]
 pub struct InvalidTrustRoots(());
 
+#[derive(Debug, Error)]
+#[error("the TLS policy does not permit any supported cipher suites")]
+pub struct NoPermittedCipherSuites(());
+
+/// The protocol versions and cipher suites offered in handshakes.
+#[derive(Clone, Debug)]
+pub(crate) struct Negotiable {
+    versions: Vec<&'static rustls::SupportedProtocolVersion>,
+    provider: Arc<CryptoProvider>,
+}
+
 pub fn watch(
     local_id: id::Id,
     server_name: dns::Name,
     roots_pem: &str,
+    policy: TlsPolicy,
 ) -> Result<(Store, Receiver)> {
+    let negotiable = Negotiable::new(&policy)?;
+
     let mut roots = rustls::RootCertStore::empty();
     let certs = match rustls_pemfile::certs(&mut std::io::Cursor::new(roots_pem))
         .collect::<Result<Vec<_>, _>>()
@@ -57,8 +75,8 @@ pub fn watch(
         // that doesn't attempt client authentication. Once we get a
         // certificate, the `Store` will publish a new configuration with a
         // client certificate resolver.
-        let mut c =
-            store::client_config_builder(server_cert_verifier.clone()).with_no_client_auth();
+        let mut c = store::client_config_builder(server_cert_verifier.clone(), &negotiable)
+            .with_no_client_auth();
 
         // Disable session resumption for the time-being until resumption is
         // more tested.
@@ -71,10 +89,20 @@ pub fn watch(
         // that handshaking always fails. Once we get a certificate, the `Store`
         // will publish a new configuration with a server certificate resolver.
         let empty_resolver = Arc::new(rustls::server::ResolvesServerCertUsingSni::new());
-        watch::channel(store::server_config(roots.clone(), empty_resolver))
+        watch::channel(store::server_config(
+            roots.clone(),
+            empty_resolver,
+            &negotiable,
+        ))
     };
 
-    let rx = Receiver::new(local_id.clone(), server_name.clone(), client_rx, server_rx);
+    let rx = Receiver::new(
+        local_id.clone(),
+        server_name.clone(),
+        client_rx,
+        server_rx,
+        policy,
+    );
     let store = Store::new(
         roots,
         server_cert_verifier,
@@ -82,6 +110,7 @@ pub fn watch(
         server_name,
         client_tx,
         server_tx,
+        negotiable,
     );
 
     Ok((store, rx))
@@ -111,6 +140,7 @@ pub fn for_test(ent: &linkerd_tls_test_util::Entity) -> (Store, Receiver) {
         ent.name.parse().expect("id must be valid"),
         ent.name.parse().expect("name must be valid"),
         std::str::from_utf8(ent.trust_anchors).expect("roots must be PEM"),
+        TlsPolicy::default(),
     )
     .expect("credentials must be valid")
 }
@@ -120,6 +150,41 @@ pub fn default_for_test() -> (Store, Receiver) {
     for_test(&linkerd_tls_test_util::FOO_NS1)
 }
 
+// === impl Negotiable ===
+
+impl Negotiable {
+    /// Determines what is offered in handshakes. When the policy is enforced,
+    /// only the versions and suites it permits are offered, so that peers that
+    /// cannot satisfy it fail to handshake. When it is audited, every version
+    /// and suite supported by the backend is offered, and sessions are checked
+    /// once they are established.
+    fn new(policy: &TlsPolicy) -> Result<Self> {
+        let audit = policy.mode == PolicyMode::Audit;
+        let mut versions = vec![&rustls::version::TLS13];
+        let mut cipher_suites = params::TLS_SUPPORTED_CIPHERSUITES.to_vec();
+        if audit || policy.permits_version(ProtocolVersion::Tls12) {
+            versions.push(&rustls::version::TLS12);
+            cipher_suites.extend_from_slice(params::TLS12_CIPHERSUITES);
+        }
+        if !audit {
+            cipher_suites
+                .retain(|cs| policy.permits_cipher_suite(CipherSuite::from_id(cs.suite().into())));
+        }
+        if cipher_suites.is_empty() {
+            return Err(NoPermittedCipherSuites(()).into());
+        }
+
+        let provider = CryptoProvider {
+            cipher_suites,
+            ..(*default_provider()).clone()
+        };
+        Ok(Self {
+            versions,
+            provider: Arc::new(provider),
+        })
+    }
+}
+
 mod params {
     use crate::backend;
     use tokio_rustls::rustls::{self, crypto::WebPkiSupportedAlgorithms};
@@ -128,7 +193,7 @@ mod params {
     pub const SIGNATURE_ALG_RUSTLS_SCHEME: rustls::SignatureScheme =
         rustls::SignatureScheme::ECDSA_NISTP256_SHA256;
     pub static SUPPORTED_SIG_ALGS: &WebPkiSupportedAlgorithms = backend::SUPPORTED_SIG_ALGS;
-    pub static TLS_VERSIONS: &[&rustls::SupportedProtocolVersion] = &[&rustls::version::TLS13];
     pub static TLS_SUPPORTED_CIPHERSUITES: &[rustls::SupportedCipherSuite] =
         backend::TLS_SUPPORTED_CIPHERSUITES;
+    pub static TLS12_CIPHERSUITES: &[rustls::SupportedCipherSuite] = backend::TLS12_CIPHERSUITES;
 }
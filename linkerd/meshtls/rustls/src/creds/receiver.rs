@@ -1,6 +1,7 @@
 use crate::{NewClient, Server};
 use linkerd_dns_name as dns;
 use linkerd_identity::Id;
+use linkerd_tls::{HandshakeMetrics, TlsPolicy};
 use std::sync::Arc;
 use tokio::sync::watch;
 use tokio_rustls::rustls;
@@ -12,6 +13,8 @@ pub struct Receiver {
     name: dns::Name,
     client_rx: watch::Receiver<Arc<rustls::ClientConfig>>,
     server_rx: watch::Receiver<Arc<rustls::ServerConfig>>,
+    policy: Arc<TlsPolicy>,
+    metrics: HandshakeMetrics,
 }
 
 // === impl Receiver ===
@@ -22,15 +25,24 @@ impl Receiver {
         name: dns::Name,
         client_rx: watch::Receiver<Arc<rustls::ClientConfig>>,
         server_rx: watch::Receiver<Arc<rustls::ServerConfig>>,
+        policy: TlsPolicy,
     ) -> Self {
         Self {
             id,
             name,
             client_rx,
             server_rx,
+            policy: Arc::new(policy),
+            metrics: HandshakeMetrics::default(),
         }
     }
 
+    /// Records the handshakes of all clients and servers built from this
+    /// receiver.
+    pub fn with_handshake_metrics(self, metrics: HandshakeMetrics) -> Self {
+        Self { metrics, ..self }
+    }
+
     /// Returns the local server name (i.e. used in mTLS).
     pub fn local_id(&self) -> &Id {
         &self.id
@@ -43,12 +55,21 @@ impl Receiver {
 
     /// Returns a `NewClient` that can be used to establish TLS on client connections.
     pub fn new_client(&self) -> NewClient {
-        NewClient::new(self.client_rx.clone())
+        NewClient::new(
+            self.client_rx.clone(),
+            self.policy.clone(),
+            self.metrics.clone(),
+        )
     }
 
     /// Returns a `Server` that can be used to terminate TLS on server connections.
     pub fn server(&self) -> Server {
-        Server::new(self.name.clone(), self.server_rx.clone())
+        Server::new(
+            self.name.clone(),
+            self.server_rx.clone(),
+            self.policy.clone(),
+            self.metrics.clone(),
+        )
     }
 }
 
@@ -101,6 +122,8 @@ mod tests {
             id: "example".parse().unwrap(),
             server_rx,
             client_rx,
+            policy: Default::default(),
+            metrics: Default::default(),
         };
 
         let server = receiver.server();
@@ -125,6 +148,8 @@ mod tests {
             name: "example".parse().unwrap(),
             server_rx,
             client_rx,
+            policy: Default::default(),
+            metrics: Default::default(),
         };
 
         let server = receiver
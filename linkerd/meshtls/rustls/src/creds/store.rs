@@ -1,4 +1,4 @@
-use super::{default_provider, params::*};
+use super::{params::*, Negotiable};
 use linkerd_dns_name as dns;
 use linkerd_error::Result;
 use linkerd_identity as id;
@@ -20,6 +20,7 @@ pub struct Store {
     server_name: dns::Name,
     client_tx: watch::Sender<Arc<rustls::ClientConfig>>,
     server_tx: watch::Sender<Arc<rustls::ServerConfig>>,
+    negotiable: Negotiable,
 }
 
 #[derive(Clone, Debug)]
@@ -27,9 +28,10 @@ struct CertResolver(Arc<rustls::sign::CertifiedKey>);
 
 pub(super) fn client_config_builder(
     cert_verifier: Arc<dyn rustls::client::danger::ServerCertVerifier>,
+    negotiable: &Negotiable,
 ) -> rustls::ConfigBuilder<rustls::ClientConfig, rustls::client::WantsClientCert> {
-    rustls::ClientConfig::builder_with_provider(default_provider())
-        .with_protocol_versions(TLS_VERSIONS)
+    rustls::ClientConfig::builder_with_provider(negotiable.provider.clone())
+        .with_protocol_versions(&negotiable.versions)
         .expect("client config must be valid")
         // XXX: Rustls's built-in verifiers don't let us tweak things as fully
         // as we'd like (e.g. controlling the set of trusted signature
@@ -48,6 +50,7 @@ pub(super) fn client_config_builder(
 pub(super) fn server_config(
     roots: rustls::RootCertStore,
     resolver: Arc<dyn rustls::server::ResolvesServerCert>,
+    negotiable: &Negotiable,
 ) -> Arc<rustls::ServerConfig> {
     // Ask TLS clients for a certificate and accept any certificate issued by our trusted CA(s).
     //
@@ -55,7 +58,7 @@ pub(super) fn server_config(
     // controlling the set of trusted signature algorithms), but they provide good enough
     // defaults for now.
     // TODO: lock down the verification further.
-    let provider = default_provider();
+    let provider = negotiable.provider.clone();
 
     let client_cert_verifier =
         WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
@@ -64,7 +67,7 @@ pub(super) fn server_config(
             .expect("server verifier must be valid");
 
     rustls::ServerConfig::builder_with_provider(provider)
-        .with_protocol_versions(TLS_VERSIONS)
+        .with_protocol_versions(&negotiable.versions)
         .expect("server config must be valid")
         .with_client_cert_verifier(client_cert_verifier)
         .with_cert_resolver(resolver)
@@ -82,6 +85,7 @@ impl Store {
         server_name: dns::Name,
         client_tx: watch::Sender<Arc<rustls::ClientConfig>>,
         server_tx: watch::Sender<Arc<rustls::ServerConfig>>,
+        negotiable: Negotiable,
     ) -> Self {
         Self {
             roots,
@@ -90,12 +94,13 @@ impl Store {
             server_name,
             client_tx,
             server_tx,
+            negotiable,
         }
     }
 
     /// Builds a new TLS client configuration.
     fn client_config(&self, resolver: Arc<CertResolver>) -> Arc<rustls::ClientConfig> {
-        let mut cfg = client_config_builder(self.server_cert_verifier.clone())
+        let mut cfg = client_config_builder(self.server_cert_verifier.clone(), &self.negotiable)
             .with_client_cert_resolver(resolver);
 
         // Disable session resumption for the time-being until resumption is
@@ -154,7 +159,7 @@ impl id::Credentials for Store {
 
         // Build new client and server TLS configs.
         let client = self.client_config(resolver.clone());
-        let server = server_config(self.roots.clone(), resolver);
+        let server = server_config(self.roots.clone(), resolver, &self.negotiable);
 
         // Publish the new configs.
         let _ = self.client_tx.send(client);
@@ -4,6 +4,7 @@
 mod backend;
 mod client;
 pub mod creds;
+mod metrics;
 mod server;
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,46 @@
+use linkerd_io as io;
+use linkerd_tls::metrics::{Alert, CipherSuite, FailureReason, ProtocolVersion, Session};
+use tokio_rustls::rustls::{self, CommonState, HandshakeKind};
+
+/// Describes the session negotiated on a connection.
+pub(crate) fn session(conn: &CommonState, peer_identity: bool) -> Session {
+    let version = match conn.protocol_version() {
+        Some(rustls::ProtocolVersion::TLSv1_2) => ProtocolVersion::Tls12,
+        Some(rustls::ProtocolVersion::TLSv1_3) => ProtocolVersion::Tls13,
+        _ => ProtocolVersion::Other,
+    };
+    let cipher_suite = conn
+        .negotiated_cipher_suite()
+        .map_or(CipherSuite::Other, |cs| {
+            CipherSuite::from_id(cs.suite().into())
+        });
+    Session {
+        version,
+        cipher_suite,
+        resumed: conn.handshake_kind() == Some(HandshakeKind::Resumed),
+        peer_identity,
+    }
+}
+
+/// Determines why a handshake failed from the error returned by rustls.
+pub(crate) fn failure_reason(error: &io::Error) -> FailureReason {
+    let Some(error) = error
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<rustls::Error>())
+    else {
+        return FailureReason::Io;
+    };
+    match error {
+        rustls::Error::AlertReceived(alert) => {
+            FailureReason::Alert(Alert::from_code((*alert).into()))
+        }
+        rustls::Error::InvalidCertificate(_) => FailureReason::InvalidCertificate,
+        rustls::Error::NoCertificatesPresented => FailureReason::NoCertificate,
+        rustls::Error::PeerIncompatible(_) => FailureReason::PeerIncompatible,
+        rustls::Error::PeerMisbehaved(_)
+        | rustls::Error::InappropriateMessage { .. }
+        | rustls::Error::InappropriateHandshakeMessage { .. }
+        | rustls::Error::InvalidMessage(_) => FailureReason::PeerMisbehaved,
+        _ => FailureReason::Other,
+    }
+}
@@ -3,7 +3,10 @@ use linkerd_dns_name as dns;
 use linkerd_io as io;
 use linkerd_meshtls_verifier as verifier;
 use linkerd_stack::{Param, Service};
-use linkerd_tls::{ClientId, NegotiatedProtocol, NegotiatedProtocolRef, ServerName, ServerTls};
+use linkerd_tls::{
+    metrics::{HandshakeMetrics, Side},
+    ClientId, NegotiatedProtocol, NegotiatedProtocolRef, ServerName, ServerTls, TlsPolicy,
+};
 use std::{pin::Pin, sync::Arc, task::Context};
 use thiserror::Error;
 use tokio::sync::watch;
@@ -15,12 +18,12 @@ use tracing::debug;
 pub struct Server {
     name: dns::Name,
     rx: watch::Receiver<Arc<ServerConfig>>,
+    policy: Arc<TlsPolicy>,
+    metrics: HandshakeMetrics,
 }
 
-pub type TerminateFuture<I> = futures::future::MapOk<
-    tokio_rustls::Accept<I>,
-    fn(tokio_rustls::server::TlsStream<I>) -> (ServerTls, ServerIo<I>),
->;
+pub type TerminateFuture<I> =
+    Pin<Box<dyn Future<Output = io::Result<(ServerTls, ServerIo<I>)>> + Send>>;
 
 #[derive(Debug)]
 pub struct ServerIo<I>(tokio_rustls::server::TlsStream<I>);
@@ -30,8 +33,18 @@ pub struct ServerIo<I>(tokio_rustls::server::TlsStream<I>);
 pub struct LostStore(());
 
 impl Server {
-    pub(crate) fn new(name: dns::Name, rx: watch::Receiver<Arc<ServerConfig>>) -> Self {
-        Self { name, rx }
+    pub(crate) fn new(
+        name: dns::Name,
+        rx: watch::Receiver<Arc<ServerConfig>>,
+        policy: Arc<TlsPolicy>,
+        metrics: HandshakeMetrics,
+    ) -> Self {
+        Self {
+            name,
+            rx,
+            policy,
+            metrics,
+        }
     }
 
     #[cfg(test)]
@@ -79,7 +92,7 @@ impl Server {
             }
         });
 
-        Ok(Self::new(self.name, rx))
+        Ok(Self::new(self.name, rx, self.policy, self.metrics))
     }
 }
 
@@ -91,7 +104,7 @@ impl Param<ServerName> for Server {
 
 impl<I> Service<I> for Server
 where
-    I: io::AsyncRead + io::AsyncWrite + Send + Unpin,
+    I: io::AsyncRead + io::AsyncWrite + Send + Unpin + 'static,
 {
     type Response = (ServerTls, ServerIo<I>);
     type Error = std::io::Error;
@@ -104,25 +117,35 @@ where
 
     #[inline]
     fn call(&mut self, io: I) -> Self::Future {
-        tokio_rustls::TlsAcceptor::from((*self.rx.borrow()).clone())
-            .accept(io)
-            .map_ok(|io| {
-                // Determine the peer's identity, if it exist.
-                let client_id = client_identity(&io);
-
-                let negotiated_protocol = io
-                    .get_ref()
-                    .1
-                    .alpn_protocol()
-                    .map(|b| NegotiatedProtocol(b.into()));
-
-                debug!(client.id = ?client_id, alpn = ?negotiated_protocol, "Accepted TLS connection");
-                let tls = ServerTls::Established {
-                    client_id,
-                    negotiated_protocol,
-                };
-                (tls, ServerIo(io))
-            })
+        let accept = tokio_rustls::TlsAcceptor::from((*self.rx.borrow()).clone()).accept(io);
+        let policy = self.policy.clone();
+        let metrics = self.metrics.clone();
+        Box::pin(async move {
+            let io = accept.await.map_err(|e| {
+                let reason = crate::metrics::failure_reason(&e);
+                metrics.failed(Side::Server, reason);
+                policy.handshake_failed(Side::Server, reason, e, &metrics)
+            })?;
+
+            // Determine the peer's identity, if it exist.
+            let client_id = client_identity(&io);
+
+            let negotiated_protocol = io
+                .get_ref()
+                .1
+                .alpn_protocol()
+                .map(|b| NegotiatedProtocol(b.into()));
+
+            let session = crate::metrics::session(io.get_ref().1, client_id.is_some());
+            policy.check_established(Side::Server, &session, &metrics)?;
+            metrics.established(Side::Server, session);
+            debug!(client.id = ?client_id, alpn = ?negotiated_protocol, "Accepted TLS connection");
+            let tls = ServerTls::Established {
+                client_id,
+                negotiated_protocol,
+            };
+            Ok((tls, ServerIo(io)))
+        })
     }
 }
 
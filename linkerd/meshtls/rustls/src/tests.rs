@@ -8,6 +8,7 @@ fn load(ent: &Entity) -> crate::creds::Store {
         ent.name.parse().unwrap(),
         ent.name.parse().unwrap(),
         roots_pem,
+        Default::default(),
     )
     .expect("credentials must be readable");
     store
@@ -48,3 +49,182 @@ fn recognize_cert_is_not_valid_for_identity() {
         )
         .is_err());
 }
+
+#[test]
+fn classifies_handshake_failures() {
+    use crate::metrics::failure_reason;
+    use linkerd_tls::metrics::{Alert, FailureReason};
+    use std::io;
+    use tokio_rustls::rustls;
+
+    let err = |e: rustls::Error| io::Error::new(io::ErrorKind::InvalidData, e);
+    assert_eq!(
+        failure_reason(&err(rustls::Error::AlertReceived(
+            rustls::AlertDescription::UnknownCA
+        ))),
+        FailureReason::Alert(Alert::UnknownCa)
+    );
+    assert_eq!(
+        failure_reason(&err(rustls::Error::InvalidCertificate(
+            rustls::CertificateError::Expired
+        ))),
+        FailureReason::InvalidCertificate
+    );
+    assert_eq!(
+        failure_reason(&err(rustls::Error::NoCertificatesPresented)),
+        FailureReason::NoCertificate
+    );
+    assert_eq!(
+        failure_reason(&io::Error::from(io::ErrorKind::UnexpectedEof)),
+        FailureReason::Io
+    );
+}
+
+/// A policy that only permits TLS 1.2, so that peers built with it are pinned
+/// to TLS 1.2.
+fn tls12_only() -> linkerd_tls::TlsPolicy {
+    use linkerd_tls::metrics::{CipherSuite, ProtocolVersion};
+    linkerd_tls::TlsPolicy {
+        min_version: ProtocolVersion::Tls12,
+        cipher_suites: Some(std::sync::Arc::new([
+            CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+        ])),
+        mode: linkerd_tls::PolicyMode::Enforce,
+    }
+}
+
+fn load_with_policy(
+    ent: &Entity,
+    policy: linkerd_tls::TlsPolicy,
+    metrics: linkerd_tls::HandshakeMetrics,
+) -> (crate::creds::Store, crate::creds::Receiver) {
+    let roots_pem = std::str::from_utf8(ent.trust_anchors).expect("valid PEM");
+    let (mut store, rx) = crate::creds::watch(
+        ent.name.parse().unwrap(),
+        ent.name.parse().unwrap(),
+        roots_pem,
+        policy,
+    )
+    .expect("credentials must be readable");
+    store
+        .set_certificate(
+            DerX509(ent.crt.to_vec()),
+            vec![],
+            ent.key.to_vec(),
+            SystemTime::now() + Duration::from_secs(1000),
+        )
+        .expect("certificate must be valid");
+    (store, rx.with_handshake_metrics(metrics))
+}
+
+/// Handshakes a client configured with `client` to a server configured with
+/// `server`, returning the results of both sides and the metrics they
+/// recorded.
+async fn handshake(
+    client: linkerd_tls::TlsPolicy,
+    server: linkerd_tls::TlsPolicy,
+) -> (std::io::Result<()>, std::io::Result<()>, String) {
+    use linkerd_metrics::prom;
+    use linkerd_stack::{NewService, ServiceExt};
+
+    let mut registry = prom::Registry::default();
+    let metrics = linkerd_tls::HandshakeMetrics::register(&mut registry);
+    let (_foo, server_rx) = load_with_policy(&FOO_NS1, server, metrics.clone());
+    let (_bar, client_rx) = load_with_policy(&BAR_NS1, client, metrics);
+
+    let target = linkerd_tls::ClientTls::new(
+        linkerd_tls::ServerId(FOO_NS1.id.parse().unwrap()),
+        linkerd_tls::ServerName(FOO_NS1.name.parse().unwrap()),
+    );
+    let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+    let (client_res, server_res) = tokio::join!(
+        client_rx
+            .new_client()
+            .new_service(target)
+            .oneshot(client_io),
+        server_rx.server().oneshot(server_io),
+    );
+
+    let mut text = String::new();
+    prom::encoding::text::encode(&mut text, &registry).unwrap();
+    (client_res.map(drop), server_res.map(drop), text)
+}
+
+fn is_policy_violation(error: &std::io::Error) -> bool {
+    error
+        .get_ref()
+        .is_some_and(|e| e.is::<linkerd_tls::TlsPolicyViolation>())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn enforces_tls_policy() {
+    use linkerd_tls::TlsPolicy;
+
+    // A server that requires TLS 1.3 rejects a client pinned to TLS 1.2.
+    let (client, server, text) = handshake(tls12_only(), TlsPolicy::default()).await;
+    assert!(client.is_err());
+    let error = server.expect_err("server must reject TLS 1.2");
+    assert!(is_policy_violation(&error), "{}", error);
+    assert!(
+        text.contains("policy_violations_total{side=\"server\",mode=\"enforce\"} 1"),
+        "{}",
+        text
+    );
+    assert!(!text.contains("handshakes_total{"), "{}", text);
+
+    // A client that requires TLS 1.3 rejects a server pinned to TLS 1.2.
+    let (client, server, text) = handshake(TlsPolicy::default(), tls12_only()).await;
+    assert!(server.is_err());
+    let error = client.expect_err("client must reject TLS 1.2");
+    assert!(is_policy_violation(&error), "{}", error);
+    assert!(
+        text.contains("policy_violations_total{side=\"client\",mode=\"enforce\"} 1"),
+        "{}",
+        text
+    );
+    assert!(!text.contains("handshakes_total{"), "{}", text);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn audits_tls_policy() {
+    use linkerd_tls::{PolicyMode, TlsPolicy};
+
+    let audit = TlsPolicy {
+        mode: PolicyMode::Audit,
+        ..TlsPolicy::default()
+    };
+
+    // A server that audits the policy accepts a client pinned to TLS 1.2.
+    let (client, server, text) = handshake(tls12_only(), audit.clone()).await;
+    client.expect("client must connect");
+    server.expect("server must accept TLS 1.2");
+    assert!(
+        text.contains("policy_violations_total{side=\"server\",mode=\"audit\"} 1"),
+        "{}",
+        text
+    );
+    assert!(
+        !text.contains("policy_violations_total{side=\"client\""),
+        "{}",
+        text
+    );
+
+    // A client that audits the policy connects to a server pinned to TLS 1.2.
+    let (client, server, text) = handshake(audit, tls12_only()).await;
+    server.expect("server must accept");
+    client.expect("client must accept TLS 1.2");
+    assert!(
+        text.contains("policy_violations_total{side=\"client\",mode=\"audit\"} 1"),
+        "{}",
+        text
+    );
+    assert!(
+        !text.contains("policy_violations_total{side=\"server\""),
+        "{}",
+        text
+    );
+
+    for line in text.lines().filter(|l| l.starts_with("handshakes_total{")) {
+        assert!(line.contains("protocol_version=\"TLSv1_2\""), "{}", line);
+    }
+}
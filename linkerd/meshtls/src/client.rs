@@ -251,6 +251,9 @@ impl<I: io::AsyncRead + io::AsyncWrite + Unpin> io::AsyncWrite for ClientIo<I> {
     }
 }
 
+// Encrypted bytes may never be spliced.
+impl<I> io::Splice for ClientIo<I> {}
+
 impl<I: io::PeerAddr> io::PeerAddr for ClientIo<I> {
     #[inline]
     fn peer_addr(&self) -> io::Result<std::net::SocketAddr> {
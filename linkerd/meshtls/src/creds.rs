@@ -4,6 +4,7 @@ use crate::{NewClient, Server};
 use linkerd_dns_name as dns;
 use linkerd_error::Result;
 use linkerd_identity::{Credentials, DerX509, Id};
+use linkerd_tls::HandshakeMetrics;
 
 #[cfg(feature = "boring")]
 pub use crate::boring;
@@ -119,4 +120,18 @@ impl Receiver {
             _ => crate::no_tls!(),
         }
     }
+
+    /// Records the handshakes of all clients and servers built from this
+    /// receiver.
+    pub fn with_handshake_metrics(self, metrics: HandshakeMetrics) -> Self {
+        match self {
+            #[cfg(feature = "boring")]
+            Self::Boring(receiver) => Self::Boring(receiver.with_handshake_metrics(metrics)),
+
+            #[cfg(feature = "rustls")]
+            Self::Rustls(receiver) => Self::Rustls(receiver.with_handshake_metrics(metrics)),
+            #[cfg(not(feature = "__has_any_tls_impls"))]
+            _ => crate::no_tls!(metrics),
+        }
+    }
 }
@@ -23,6 +23,7 @@ pub use self::{
 use linkerd_dns_name as dns;
 use linkerd_error::{Error, Result};
 use linkerd_identity as id;
+use linkerd_tls::TlsPolicy;
 use std::str::FromStr;
 
 #[cfg(feature = "boring")]
@@ -86,11 +87,13 @@ impl Mode {
         local_id: id::Id,
         server_name: dns::Name,
         roots_pem: &str,
+        policy: TlsPolicy,
     ) -> Result<(creds::Store, creds::Receiver)> {
         match self {
             #[cfg(feature = "boring")]
             Self::Boring => {
-                let (store, receiver) = boring::creds::watch(local_id, server_name, roots_pem)?;
+                let (store, receiver) =
+                    boring::creds::watch(local_id, server_name, roots_pem, policy)?;
                 Ok((
                     creds::Store::Boring(store),
                     creds::Receiver::Boring(receiver),
@@ -99,7 +102,8 @@ impl Mode {
 
             #[cfg(feature = "rustls")]
             Self::Rustls => {
-                let (store, receiver) = rustls::creds::watch(local_id, server_name, roots_pem)?;
+                let (store, receiver) =
+                    rustls::creds::watch(local_id, server_name, roots_pem, policy)?;
                 Ok((
                     creds::Store::Rustls(store),
                     creds::Receiver::Rustls(receiver),
@@ -107,7 +111,7 @@ impl Mode {
             }
 
             #[cfg(not(feature = "__has_any_tls_impls"))]
-            _ => no_tls!(local_id, server_name, roots_pem),
+            _ => no_tls!(local_id, server_name, roots_pem, policy),
         }
     }
 }
@@ -249,6 +249,9 @@ impl<I: io::AsyncRead + io::AsyncWrite + Unpin> io::AsyncWrite for ServerIo<I> {
     }
 }
 
+// Encrypted bytes may never be spliced.
+impl<I> io::Splice for ServerIo<I> {}
+
 impl<I: io::PeerAddr> io::PeerAddr for ServerIo<I> {
     #[inline]
     fn peer_addr(&self) -> io::Result<std::net::SocketAddr> {
@@ -25,3 +25,8 @@ async fn proxy_to_proxy_tls_works() {
 async fn proxy_to_proxy_tls_pass_through_when_identity_does_not_match() {
     util::proxy_to_proxy_tls_pass_through_when_identity_does_not_match(Mode::Rustls).await;
 }
+
+#[tokio::test(flavor = "current_thread")]
+async fn proxy_to_proxy_tls_records_handshakes() {
+    util::proxy_to_proxy_tls_records_handshakes(Mode::Rustls).await;
+}
@@ -8,6 +8,7 @@ use linkerd_error::Infallible;
 use linkerd_identity::{Credentials, DerX509, Id};
 use linkerd_io::{self as io, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use linkerd_meshtls as meshtls;
+use linkerd_metrics::prom;
 use linkerd_proxy_transport::{
     addrs::*,
     listen::{Addrs, Bind, BindTcp},
@@ -59,7 +60,7 @@ pub fn fails_processing_cert_when_wrong_id_configured(mode: meshtls::Mode) {
     let (cert, key, roots) =
         generate_cert_with_name(vec![SanType::URI("spiffe://system/local".parse().unwrap())]);
     let (mut store, _) = mode
-        .watch(id, server_name.clone(), &roots)
+        .watch(id, server_name.clone(), &roots, Default::default())
         .expect("should construct");
 
     let err = store
@@ -156,6 +157,45 @@ pub async fn proxy_to_proxy_tls_pass_through_when_identity_does_not_match(mode:
     assert_eq!(&server_result.result.unwrap()[..], START_OF_TLS);
 }
 
+pub async fn proxy_to_proxy_tls_records_handshakes(mode: meshtls::Mode) {
+    let mut registry = prom::Registry::default();
+    let metrics = tls::HandshakeMetrics::register(&mut registry);
+    let (_foo, _, server_tls) = load_with_metrics(mode, &test_util::FOO_NS1, metrics.clone());
+    let (_bar, client_tls, _) = load_with_metrics(mode, &test_util::BAR_NS1, metrics);
+    let server_id = tls::ServerId(test_util::FOO_NS1.id.parse().unwrap());
+    let server_name = tls::ServerName(test_util::FOO_NS1.name.parse().unwrap());
+    let (client_result, server_result) = run_test(
+        client_tls,
+        Conditional::Some(tls::ClientTls::new(server_id, server_name)),
+        |conn| write_then_read(conn, PING),
+        server_tls,
+        |(_, conn)| read_then_write(conn, PING.len(), PONG),
+    )
+    .await;
+    assert_eq!(&client_result.result.expect("pong")[..], PONG);
+    assert_eq!(&server_result.result.expect("ping")[..], PING);
+
+    let mut text = String::new();
+    prom::encoding::text::encode(&mut text, &registry).unwrap();
+    let handshakes = text
+        .lines()
+        .filter(|l| l.starts_with("handshakes_total{"))
+        .collect::<Vec<_>>();
+    assert_eq!(handshakes.len(), 2, "{text}");
+    for side in ["client", "server"] {
+        let line = handshakes
+            .iter()
+            .find(|l| l.contains(&format!("side=\"{side}\"")))
+            .unwrap_or_else(|| panic!("missing {} handshake: {}", side, text));
+        assert!(line.contains("protocol_version=\"TLSv1_3\""), "{}", line);
+        assert!(line.contains("cipher_suite=\"TLS13_"), "{}", line);
+        assert!(line.contains("session=\"new\""), "{}", line);
+        assert!(line.contains("peer_identity=\"verified\""), "{}", line);
+        assert!(line.ends_with(" 1"), "{}", line);
+    }
+    assert!(!text.contains("handshake_failures_total{"), "{}", text);
+}
+
 type ServerConn<T, I> = (
     (tls::ConditionalServerTls, T),
     io::EitherIo<meshtls::ServerIo<tls::server::DetectIo<I>>, tls::server::DetectIo<I>>,
@@ -164,6 +204,14 @@ type ServerConn<T, I> = (
 fn load(
     mode: meshtls::Mode,
     ent: &test_util::Entity,
+) -> (meshtls::creds::Store, meshtls::NewClient, meshtls::Server) {
+    load_with_metrics(mode, ent, Default::default())
+}
+
+fn load_with_metrics(
+    mode: meshtls::Mode,
+    ent: &test_util::Entity,
+    metrics: tls::HandshakeMetrics,
 ) -> (meshtls::creds::Store, meshtls::NewClient, meshtls::Server) {
     let roots_pem = std::str::from_utf8(ent.trust_anchors).expect("valid PEM");
     let (mut store, rx) = mode
@@ -171,6 +219,7 @@ fn load(
             ent.name.parse().unwrap(),
             ent.name.parse().unwrap(),
             roots_pem,
+            Default::default(),
         )
         .expect("credentials must be readable");
 
@@ -183,6 +232,7 @@ fn load(
         )
         .expect("certificate must be valid");
 
+    let rx = rx.with_handshake_metrics(metrics);
     (store, rx.new_client(), rx.server())
 }
 
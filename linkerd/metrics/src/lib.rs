@@ -28,7 +28,7 @@ pub mod legacy {
         counter::Counter,
         fmt::{FmtLabels, FmtMetric, FmtMetrics, Metric},
         gauge::Gauge,
-        histogram::Histogram,
+        histogram::{Bounds, Bucket, Histogram},
         serve::Serve,
         store::{LastUpdate, SharedStore, Store},
     };
@@ -232,6 +232,37 @@ fn convert_span(span: ExportSpan) -> Result<Span, Error> {
             },
         );
     }
+    let time_events = (!span.events.is_empty()).then(|| oc::span::TimeEvents {
+        time_event: span
+            .events
+            .drain(..)
+            .map(|event| oc::span::TimeEvent {
+                time: Some(event.time.into()),
+                value: Some(oc::span::time_event::Value::Annotation(
+                    oc::span::time_event::Annotation {
+                        description: Some(truncatable(event.name.to_string())),
+                        attributes: Some(oc::span::Attributes {
+                            attribute_map: event
+                                .attributes
+                                .into_iter()
+                                .map(|(k, v)| {
+                                    let value = oc::AttributeValue {
+                                        value: Some(oc::attribute_value::Value::StringValue(
+                                            truncatable(v),
+                                        )),
+                                    };
+                                    (k.to_string(), value)
+                                })
+                                .collect(),
+                            dropped_attributes_count: 0,
+                        }),
+                    },
+                )),
+            })
+            .collect(),
+        dropped_annotations_count: 0,
+        dropped_message_events_count: 0,
+    });
     Ok(Span {
         trace_id: span.trace_id.into_bytes::<16>()?.to_vec(),
         span_id: span.span_id.into_bytes::<8>()?.to_vec(),
@@ -246,7 +277,7 @@ fn convert_span(span: ExportSpan) -> Result<Span, Error> {
             dropped_attributes_count: 0,
         }),
         stack_trace: None,
-        time_events: None,
+        time_events,
         links: None,
         status: None, // TODO: this is gRPC status; we must read response trailers to populate this
         resource: None,
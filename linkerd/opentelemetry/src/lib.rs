@@ -10,7 +10,7 @@ use linkerd_error::Error;
 use linkerd_trace_context::{self as trace_context, export::ExportSpan};
 pub use opentelemetry as otel;
 use opentelemetry::{
-    trace::{SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceId, TraceState},
+    trace::{Event, SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceId, TraceState},
     KeyValue,
 };
 pub use opentelemetry_proto as proto;
@@ -23,7 +23,7 @@ use opentelemetry_proto::{
     },
     transform::{common::ResourceAttributesWithSchema, trace::group_spans_by_resource_and_scope},
 };
-use opentelemetry_sdk::trace::SpanLinks;
+use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
 pub use opentelemetry_sdk::{self as sdk, trace::SpanData};
 use tokio::{sync::mpsc, time};
 use tonic::{self as grpc, body::BoxBody, client::GrpcService};
@@ -240,6 +240,19 @@ fn convert_span(span: ExportSpan) -> Result<SpanData, Error> {
     for (k, v) in span.labels.iter() {
         attributes.push(KeyValue::new(*k, v.clone()));
     }
+    let mut events = SpanEvents::default();
+    events.events = span
+        .events
+        .into_iter()
+        .map(|event| {
+            let attributes = event
+                .attributes
+                .into_iter()
+                .map(|(k, v)| KeyValue::new(k, v))
+                .collect();
+            Event::new(event.name, event.time, attributes, 0)
+        })
+        .collect();
     let is_remote = kind != trace_context::export::SpanKind::Client;
     Ok(SpanData {
         parent_span_id: SpanId::from_bytes(span.parent_id.into_bytes()?),
@@ -261,7 +274,53 @@ fn convert_span(span: ExportSpan) -> Result<SpanData, Error> {
             is_remote,
             TraceState::NONE,
         ),
-        events: Default::default(),
+        events,
         instrumentation_scope: Default::default(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use linkerd_trace_context::{export, Id, Span, SpanEvent};
+    use std::{collections::HashMap, sync::Arc, time::SystemTime};
+
+    #[test]
+    fn converts_span_events() {
+        let start = SystemTime::now();
+        let span = Span {
+            trace_id: Id::from(Bytes::from_static(&[1; 16])),
+            span_id: Id::from(Bytes::from_static(&[2; 8])),
+            parent_id: Id::from(Bytes::from_static(&[3; 8])),
+            span_name: "/path".to_string(),
+            start,
+            end: start,
+            labels: HashMap::from([("proxy.endpoint.address", "192.0.2.1:80".to_string())]),
+            events: vec![SpanEvent {
+                name: "retry",
+                time: start,
+                attributes: vec![("retry.attempt", "1".to_string())],
+            }],
+        };
+        let span = convert_span(export::ExportSpan {
+            span,
+            kind: export::SpanKind::Server,
+            labels: Arc::new(HashMap::new()),
+        })
+        .expect("span must convert");
+
+        assert!(span.attributes.contains(&KeyValue::new(
+            "proxy.endpoint.address",
+            "192.0.2.1:80".to_string()
+        )));
+        let events = span.events.events;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, "retry");
+        assert_eq!(events[0].timestamp, start);
+        assert_eq!(
+            events[0].attributes,
+            vec![KeyValue::new("retry.attempt", "1".to_string())]
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Tracks the endpoint most recently selected for each client.
+
+use ahash::AHashMap;
+use std::{
+    collections::VecDeque,
+    net::{IpAddr, SocketAddr},
+};
+
+/// Configures a pool to prefer the endpoint most recently selected for each
+/// client, as identified by its source IP.
+pub struct SourceAffinity<Req> {
+    /// The maximum number of clients whose endpoints are tracked. When
+    /// exceeded, the least recently used clients are forgotten.
+    pub capacity: usize,
+
+    /// Returns the source IP of the client that issued a request, if known.
+    pub source: fn(&Req) -> Option<IpAddr>,
+}
+
+/// A bounded, least-recently-used map of client IPs to endpoint addresses.
+#[derive(Debug)]
+pub(crate) struct AffinityMap {
+    capacity: usize,
+    entries: AHashMap<IpAddr, Entry>,
+    /// Records each use of a client's entry in order. Uses that have been
+    /// superseded by a later use are skipped on eviction.
+    uses: VecDeque<(IpAddr, u64)>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    endpoint: SocketAddr,
+    used: u64,
+}
+
+// === impl SourceAffinity ===
+
+impl<Req> Clone for SourceAffinity<Req> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Req> Copy for SourceAffinity<Req> {}
+
+impl<Req> std::fmt::Debug for SourceAffinity<Req> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SourceAffinity")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+// === impl AffinityMap ===
+
+impl AffinityMap {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: AHashMap::default(),
+            uses: VecDeque::default(),
+            tick: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the endpoint most recently selected for the client, marking
+    /// the client as recently used.
+    pub(crate) fn get(&mut self, client: IpAddr) -> Option<SocketAddr> {
+        let tick = self.next_tick();
+        let entry = self.entries.get_mut(&client)?;
+        entry.used = tick;
+        let endpoint = entry.endpoint;
+        self.record_use(client, tick);
+        Some(endpoint)
+    }
+
+    /// Records the endpoint selected for the client, evicting the least
+    /// recently used client if the map is full.
+    pub(crate) fn insert(&mut self, client: IpAddr, endpoint: SocketAddr) {
+        let used = self.next_tick();
+        self.entries.insert(client, Entry { endpoint, used });
+        self.record_use(client, used);
+
+        while self.entries.len() > self.capacity {
+            let Some((client, used)) = self.uses.pop_front() else {
+                break;
+            };
+            if self.entries.get(&client).map(|e| e.used) == Some(used) {
+                self.entries.remove(&client);
+            }
+        }
+    }
+
+    /// Forgets all clients that were assigned to the endpoint.
+    pub(crate) fn remove_endpoint(&mut self, endpoint: SocketAddr) {
+        self.entries.retain(|_, e| e.endpoint != endpoint);
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn record_use(&mut self, client: IpAddr, used: u64) {
+        self.uses.push_back((client, used));
+
+        // Drop superseded uses so that the queue stays proportional to the
+        // number of entries.
+        if self.uses.len() > 2 * self.capacity.max(self.entries.len()) + 1 {
+            let entries = &self.entries;
+            self.uses
+                .retain(|(client, used)| entries.get(client).map(|e| e.used) == Some(*used));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(n: u8) -> IpAddr {
+        IpAddr::from([10, 0, 0, n])
+    }
+
+    fn endpoint(n: u8) -> SocketAddr {
+        SocketAddr::from(([192, 168, 10, n], 80))
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut map = AffinityMap::new(2);
+        map.insert(client(1), endpoint(1));
+        map.insert(client(2), endpoint(2));
+        assert_eq!(map.get(client(1)), Some(endpoint(1)));
+
+        map.insert(client(3), endpoint(1));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(client(2)), None);
+        assert_eq!(map.get(client(1)), Some(endpoint(1)));
+        assert_eq!(map.get(client(3)), Some(endpoint(1)));
+
+        // Repeated uses do not grow the map's bookkeeping unboundedly.
+        for _ in 0..100 {
+            map.get(client(1));
+        }
+        assert!(map.uses.len() <= 5, "{}", map.uses.len());
+    }
+
+    #[test]
+    fn removes_endpoints() {
+        let mut map = AffinityMap::new(4);
+        map.insert(client(1), endpoint(1));
+        map.insert(client(2), endpoint(2));
+        map.insert(client(3), endpoint(1));
+
+        map.remove_endpoint(endpoint(1));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(client(1)), None);
+        assert_eq!(map.get(client(2)), Some(endpoint(2)));
+    }
+}
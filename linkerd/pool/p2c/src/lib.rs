@@ -5,6 +5,7 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
+use self::affinity::AffinityMap;
 use ahash::AHashMap;
 use futures::prelude::*;
 use linkerd_error::Error;
@@ -14,7 +15,7 @@ use linkerd_stack::{NewService, Service};
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 use std::{
     collections::hash_map::Entry,
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     task::{Context, Poll},
 };
 use tower::{
@@ -22,6 +23,10 @@ use tower::{
     ready_cache::{error::Failed, ReadyCache},
 };
 
+mod affinity;
+
+pub use self::affinity::SourceAffinity;
+
 /// Dispatches requests to a pool of services selected by the
 /// power-of-two-choices algorithm.
 #[derive(Debug)]
@@ -32,12 +37,34 @@ pub struct P2cPool<T, N, Req, S> {
     rng: SmallRng,
     metrics: P2cMetrics,
     next_idx: Option<usize>,
+    affinity: Option<Affinity<Req>>,
+    weights: Option<Weights<T>>,
+}
+
+/// Biases endpoint selection by each endpoint's weight.
+#[derive(Debug)]
+struct Weights<T> {
+    weight: fn(&T) -> u32,
+
+    /// Set when endpoints' weights differ. Otherwise, endpoints are selected
+    /// uniformly.
+    uneven: bool,
+}
+
+/// Prefers the endpoint most recently selected for each client.
+#[derive(Debug)]
+struct Affinity<Req> {
+    config: SourceAffinity<Req>,
+    clients: AffinityMap,
 }
 
 #[derive(Clone, Debug)]
 pub struct P2cMetricFamilies<L> {
     endpoints: prom::Family<L, prom::Gauge>,
     updates: prom::Family<UpdateLabels<L>, prom::Counter>,
+    affinity_clients: prom::Family<L, prom::Gauge>,
+    affinity_lookups: prom::Family<AffinityLabels<L>, prom::Counter>,
+    weight: prom::Family<L, prom::Gauge>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -52,6 +79,21 @@ pub struct P2cMetrics {
 
     /// Measures the number of Remove updates received from service discovery.
     updates_rm: prom::Counter,
+
+    /// Measures the number of clients whose endpoints are tracked for source
+    /// affinity.
+    affinity_clients: prom::Gauge,
+
+    /// Measures the number of requests dispatched to the endpoint previously
+    /// selected for their client.
+    affinity_hits: prom::Counter,
+
+    /// Measures the number of requests for which the endpoint previously
+    /// selected for their client was unknown or unavailable.
+    affinity_misses: prom::Counter,
+
+    /// Measures the total weight of the endpoints in the balancer.
+    weight: prom::Gauge,
 }
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
@@ -67,6 +109,18 @@ enum UpdateOp {
     Remove,
 }
 
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+struct AffinityLabels<L> {
+    result: AffinityResult,
+    labels: L,
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, prom::encoding::EncodeLabelValue)]
+enum AffinityResult {
+    Hit,
+    Miss,
+}
+
 impl<T, N, Req, S> P2cPool<T, N, Req, S>
 where
     T: Clone + Eq,
@@ -84,6 +138,104 @@ where
             next_idx: None,
             pool: ReadyCache::default(),
             endpoints: Default::default(),
+            affinity: None,
+            weights: None,
+        }
+    }
+
+    /// Selects endpoints in proportion to their weights.
+    ///
+    /// Weights are read from each endpoint's target, so an update that only
+    /// changes an endpoint's weight is applied without rebuilding the
+    /// endpoint's service. Targets should therefore not consider weights when
+    /// compared. Endpoints with a weight of zero are only selected when no
+    /// weighted endpoint is ready.
+    pub fn with_weights(mut self, weight: fn(&T) -> u32) -> Self {
+        self.weights = Some(Weights {
+            weight,
+            uneven: false,
+        });
+        self.update_weights();
+        self
+    }
+
+    /// Records the endpoints' total weight and determines whether selection
+    /// must account for weights.
+    fn update_weights(&mut self) {
+        let Some(weights) = self.weights.as_mut() else {
+            return;
+        };
+
+        let mut total = 0u64;
+        let mut min = u32::MAX;
+        let mut max = 0;
+        for target in self.endpoints.values() {
+            let w = (weights.weight)(target);
+            total += u64::from(w);
+            min = min.min(w);
+            max = max.max(w);
+        }
+        weights.uneven = min < max;
+        self.metrics.weight.set(total as i64);
+    }
+
+    /// Returns the weight of the endpoint at the given address, if weights
+    /// are uneven.
+    fn addr_weight(&self, addr: &SocketAddr) -> Option<u64> {
+        self.weights.as_ref()?.get(self.endpoints.get(addr)?)
+    }
+
+    /// Dispatches each client's requests to the endpoint most recently
+    /// selected for it, so long as that endpoint remains ready.
+    pub fn with_source_affinity(mut self, config: SourceAffinity<Req>) -> Self {
+        self.affinity = Some(Affinity {
+            clients: AffinityMap::new(config.capacity),
+            config,
+        });
+        self
+    }
+
+    /// Returns the index of the ready endpoint most recently selected for the
+    /// client. Otherwise, the p2c-selected endpoint is assigned to the client.
+    fn affine_ready_index(&mut self, client: IpAddr, p2c_idx: usize) -> usize {
+        let Some(affinity) = self.affinity.as_mut() else {
+            return p2c_idx;
+        };
+        let clients = &mut affinity.clients;
+
+        if let Some(addr) = clients.get(client) {
+            // Clients are moved off of drained endpoints.
+            let weight = self
+                .weights
+                .as_ref()
+                .zip(self.endpoints.get(&addr))
+                .and_then(|(weights, target)| weights.get(target));
+            if weight == Some(0) {
+                tracing::trace!(%client, ?addr, "Affine endpoint drained");
+            } else if let Some((idx, _, _)) = self.pool.get_ready(&addr) {
+                tracing::trace!(%client, ?addr, ready.index = idx, "Affine endpoint");
+                self.metrics.affinity_hits.inc();
+                return idx;
+            } else {
+                tracing::trace!(%client, ?addr, "Affine endpoint not ready");
+            }
+        }
+
+        self.metrics.affinity_misses.inc();
+        if let Some((addr, _)) = self.pool.get_ready_index(p2c_idx) {
+            clients.insert(client, *addr);
+            self.metrics.affinity_clients.set(clients.len() as i64);
+        }
+        p2c_idx
+    }
+
+    /// Forgets clients that were assigned to a removed endpoint.
+    fn remove_affinity(&mut self, addr: SocketAddr) {
+        if let Some(affinity) = self.affinity.as_mut() {
+            affinity.clients.remove_endpoint(addr);
+            self.metrics
+                .affinity_clients
+                .set(affinity.clients.len() as i64);
         }
     }
 
@@ -92,7 +244,14 @@ where
             0 => None,
             1 => Some(0),
             len => {
-                let (aidx, bidx) = gen_pair(&mut self.rng, len);
+                let (aidx, bidx) = match self.gen_weighted_pair(len) {
+                    Some(WeightedPair::Pair(aidx, bidx)) => (aidx, bidx),
+                    Some(WeightedPair::Only(idx)) => {
+                        tracing::trace!(ready.index = idx, "Only weighted endpoint");
+                        return Some(idx);
+                    }
+                    None => gen_pair(&mut self.rng, len),
+                };
                 let aload = self.ready_index_load(aidx);
                 let bload = self.ready_index_load(bidx);
                 let chosen = if aload <= bload { aidx } else { bidx };
@@ -109,6 +268,57 @@ where
         }
     }
 
+    /// Picks two distinct ready endpoints, each drawn in proportion to its
+    /// weight, when endpoints' weights are uneven. Because ties are resolved
+    /// in favor of the first endpoint, endpoints with equal loads receive
+    /// requests in proportion to their weights.
+    ///
+    /// Returns `None` if weights are not uneven or no ready endpoint has a
+    /// weight, in which case endpoints are selected uniformly.
+    fn gen_weighted_pair(&mut self, len: usize) -> Option<WeightedPair> {
+        if !self.weights.as_ref().is_some_and(|w| w.uneven) {
+            return None;
+        }
+
+        let total = (0..len).map(|i| self.ready_index_weight(i)).sum::<u64>();
+        if total == 0 {
+            return None;
+        }
+        let aidx = self.pick_weighted(len, total, None);
+        let rest = total - self.ready_index_weight(aidx);
+        if rest == 0 {
+            return Some(WeightedPair::Only(aidx));
+        }
+        let bidx = self.pick_weighted(len, rest, Some(aidx));
+        debug_assert_ne!(aidx, bidx, "weighted indices must be distinct");
+        Some(WeightedPair::Pair(aidx, bidx))
+    }
+
+    /// Picks a ready endpoint in proportion to its weight, given the total
+    /// weight of the ready endpoints other than `skip`.
+    fn pick_weighted(&mut self, len: usize, total: u64, skip: Option<usize>) -> usize {
+        let mut point = self.rng.random_range(0..total);
+        let mut last = 0;
+        for idx in (0..len).filter(|i| Some(*i) != skip) {
+            let w = self.ready_index_weight(idx);
+            if w == 0 {
+                continue;
+            }
+            if point < w {
+                return idx;
+            }
+            point -= w;
+            last = idx;
+        }
+        last
+    }
+
+    /// Returns the weight of a ready endpoint by index.
+    fn ready_index_weight(&self, index: usize) -> u64 {
+        let (addr, _) = self.pool.get_ready_index(index).expect("invalid index");
+        self.addr_weight(addr).unwrap_or(1)
+    }
+
     /// Accesses a ready endpoint by index and returns its current load.
     fn ready_index_load(&self, index: usize) -> S::Metric {
         let (_, svc) = self.pool.get_ready_index(index).expect("invalid index");
@@ -116,6 +326,12 @@ where
     }
 }
 
+enum WeightedPair {
+    Pair(usize, usize),
+    /// Only one ready endpoint has a weight.
+    Only(usize),
+}
+
 fn gen_pair(rng: &mut SmallRng, len: usize) -> (usize, usize) {
     debug_assert!(len >= 2, "must have at least two endpoints");
     // Get two distinct random indexes (in a random order) and
@@ -163,9 +379,11 @@ where
         for (addr, _) in remaining.drain() {
             tracing::info!(?addr, "Removing endpoint");
             self.pool.evict(&addr);
+            self.remove_affinity(addr);
             changed = true;
         }
 
+        self.update_weights();
         if changed {
             self.metrics.endpoints.set(self.endpoints.len() as i64);
             self.metrics.updates_reset.inc();
@@ -175,8 +393,12 @@ where
 
     fn add_endpoint(&mut self, addr: SocketAddr, target: T) {
         match self.endpoints.entry(addr) {
-            Entry::Occupied(e) if e.get() == &target => {
+            Entry::Occupied(mut e) if e.get() == &target => {
+                // The target may differ in ways that do not require a new
+                // service (e.g. its weight), so it is still replaced.
                 tracing::debug!(?addr, "Endpoint unchanged");
+                e.insert(target);
+                self.update_weights();
                 return;
             }
             Entry::Occupied(mut e) => {
@@ -191,6 +413,7 @@ where
         tracing::info!(?addr, "Adding endpoint");
         let svc = self.new_endpoint.new_service((addr, target));
         self.pool.push(addr, svc);
+        self.update_weights();
         self.metrics.updates_add.inc();
     }
 
@@ -202,6 +425,8 @@ where
 
         tracing::info!(?addr, "Removing endpoint");
         self.pool.evict(&addr);
+        self.remove_affinity(addr);
+        self.update_weights();
         self.metrics.endpoints.dec();
         self.metrics.updates_rm.inc();
         self.next_idx = None;
@@ -268,8 +493,20 @@ where
         }
     }
 
+    /// Dispatches the request to the selected endpoint.
+    ///
+    /// When source affinity is configured, the request is instead dispatched
+    /// to the endpoint most recently selected for its client, if that endpoint
+    /// is ready.
     fn call(&mut self, req: Req) -> Self::Future {
-        let idx = self.next_idx.take().expect("call before ready");
+        let mut idx = self.next_idx.take().expect("call before ready");
+        let client = self
+            .affinity
+            .as_ref()
+            .and_then(|Affinity { config, .. }| (config.source)(&req));
+        if let Some(client) = client {
+            idx = self.affine_ready_index(client, idx);
+        }
         self.pool.call_ready_index(idx, req).err_into()
     }
 }
@@ -277,6 +514,17 @@ where
 impl<T, N, Req, S> Drop for P2cPool<T, N, Req, S> {
     fn drop(&mut self) {
         self.metrics.endpoints.set(0);
+        self.metrics.affinity_clients.set(0);
+        self.metrics.weight.set(0);
+    }
+}
+
+// === impl Weights ===
+
+impl<T> Weights<T> {
+    /// Returns the target's weight, if endpoints' weights are uneven.
+    fn get(&self, target: &T) -> Option<u64> {
+        self.uneven.then(|| u64::from((self.weight)(target)))
     }
 }
 
@@ -291,6 +539,9 @@ where
         Self {
             endpoints: prom::Family::default(),
             updates: prom::Family::default(),
+            affinity_clients: prom::Family::default(),
+            affinity_lookups: prom::Family::default(),
+            weight: prom::Family::default(),
         }
     }
 }
@@ -315,7 +566,34 @@ where
             updates.clone(),
         );
 
-        Self { endpoints, updates }
+        let affinity_clients = prom::Family::default();
+        reg.register(
+            "affinity_clients",
+            "The number of clients whose most recently selected endpoints are tracked by a balancer",
+            affinity_clients.clone(),
+        );
+
+        let affinity_lookups = prom::Family::default();
+        reg.register(
+            "affinity_lookups",
+            "The total number of requests for which a balancer looked up the endpoint most recently selected for the client",
+            affinity_lookups.clone(),
+        );
+
+        let weight = prom::Family::default();
+        reg.register(
+            "weight",
+            "The total weight of the endpoints currently in the balancer",
+            weight.clone(),
+        );
+
+        Self {
+            endpoints,
+            updates,
+            affinity_clients,
+            affinity_lookups,
+            weight,
+        }
     }
 
     pub fn metrics(&self, labels: &L) -> P2cMetrics {
@@ -341,11 +619,31 @@ where
                 labels: labels.clone(),
             })
             .clone();
+        let affinity_clients: prom::Gauge = self.affinity_clients.get_or_create(labels).clone();
+        let affinity_hits: prom::Counter = self
+            .affinity_lookups
+            .get_or_create(&AffinityLabels {
+                result: AffinityResult::Hit,
+                labels: labels.clone(),
+            })
+            .clone();
+        let affinity_misses: prom::Counter = self
+            .affinity_lookups
+            .get_or_create(&AffinityLabels {
+                result: AffinityResult::Miss,
+                labels: labels.clone(),
+            })
+            .clone();
+        let weight: prom::Gauge = self.weight.get_or_create(labels).clone();
         P2cMetrics {
             endpoints,
             updates_reset,
             updates_add,
             updates_rm,
+            affinity_clients,
+            affinity_hits,
+            affinity_misses,
+            weight,
         }
     }
 }
@@ -360,6 +658,14 @@ impl<L: prom::encoding::EncodeLabelSet> prom::encoding::EncodeLabelSet for Updat
     }
 }
 
+impl<L: prom::encoding::EncodeLabelSet> prom::encoding::EncodeLabelSet for AffinityLabels<L> {
+    fn encode(&self, mut enc: prom::encoding::LabelSetEncoder<'_>) -> std::fmt::Result {
+        use prom::encoding::EncodeLabel;
+        ("result", self.result).encode(enc.encode_label())?;
+        self.labels.encode(enc)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -369,7 +675,7 @@ mod tests {
     use std::sync::Arc;
     use tokio::time;
     use tokio_test::{assert_pending, assert_ready_ok};
-    use tower::load::{CompleteOnResponse, PeakEwma};
+    use tower::load::{CompleteOnResponse, Constant, PeakEwma};
 
     quickcheck::quickcheck! {
         fn gen_pair_distinct(len: usize) -> quickcheck::TestResult {
@@ -591,4 +897,157 @@ mod tests {
         assert_eq!(pool.pool.ready_len(), 3);
         assert_eq!(pool.pool.pending_len(), 0);
     }
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn source_affinity() {
+        let _trace = linkerd_tracing::test::with_default_filter("trace");
+
+        let addr0 = "192.168.10.10:80".parse().unwrap();
+        let (svc0, mut h0) = tower_test::mock::pair::<IpAddr, ()>();
+        h0.allow(100);
+
+        let addr1 = "192.168.10.11:80".parse().unwrap();
+        let (svc1, mut h1) = tower_test::mock::pair::<IpAddr, ()>();
+        h1.allow(100);
+
+        let metrics = P2cMetrics::default();
+        let mut pool = P2cPool::new(metrics.clone(), |(a, ())| {
+            PeakEwma::new(
+                if a == addr0 {
+                    svc0.clone()
+                } else if a == addr1 {
+                    svc1.clone()
+                } else {
+                    panic!("unexpected address: {a}");
+                },
+                time::Duration::from_secs(1),
+                1.0 * 1000.0 * 1000.0,
+                CompleteOnResponse::default(),
+            )
+        })
+        .with_source_affinity(SourceAffinity {
+            capacity: 10,
+            source: |client: &IpAddr| Some(*client),
+        });
+        pool.reset_pool(vec![(addr0, ()), (addr1, ())]);
+
+        let client = IpAddr::from([10, 0, 0, 1]);
+        let mut send = async |pool: &mut P2cPool<_, _, _, _>| {
+            pool.ready().await.expect("pool must be ready");
+            let call = pool.call(client);
+            let (addr, (req, respond)) = tokio::select! {
+                r = h0.next_request() => (addr0, r.unwrap()),
+                r = h1.next_request() => (addr1, r.unwrap()),
+            };
+            assert_eq!(req, client);
+            respond.send_response(());
+            call.await.expect("call should succeed");
+            addr
+        };
+
+        // Requests from the client are dispatched to the endpoint selected for
+        // its first request.
+        let selected = send(&mut pool).await;
+        for _ in 0..10 {
+            assert_eq!(send(&mut pool).await, selected);
+        }
+        assert_eq!(metrics.affinity_clients.get(), 1);
+        assert_eq!(metrics.affinity_hits.get(), 10);
+        assert_eq!(metrics.affinity_misses.get(), 1);
+
+        // When the endpoint is removed, the client is assigned another.
+        pool.remove_endpoint(selected);
+        assert_eq!(metrics.affinity_clients.get(), 0);
+        let other = send(&mut pool).await;
+        assert_ne!(other, selected);
+        assert_eq!(metrics.affinity_clients.get(), 1);
+        assert_eq!(metrics.affinity_misses.get(), 2);
+        assert_eq!(send(&mut pool).await, other);
+        assert_eq!(metrics.affinity_hits.get(), 11);
+    }
+
+    /// A target whose weight is not considered when compared.
+    #[derive(Copy, Clone, Debug)]
+    struct Weighted(u32);
+
+    impl PartialEq for Weighted {
+        fn eq(&self, _: &Self) -> bool {
+            true
+        }
+    }
+
+    impl Eq for Weighted {}
+
+    /// Tests that weight updates shift requests between endpoints without
+    /// rebuilding their services.
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn weight_updates() {
+        let _trace = linkerd_tracing::test::with_default_filter("trace");
+        const REQUESTS: usize = 1_000;
+
+        let addr0 = "192.168.10.10:80".parse().unwrap();
+        let (svc0, mut h0) = tower_test::mock::pair::<(), ()>();
+        h0.allow(REQUESTS as u64 * 3);
+
+        let addr1 = "192.168.10.11:80".parse().unwrap();
+        let (svc1, mut h1) = tower_test::mock::pair::<(), ()>();
+        h1.allow(REQUESTS as u64 * 3);
+
+        let built = Arc::new(Mutex::new(Vec::<SocketAddr>::new()));
+        let metrics = P2cMetrics::default();
+        let mut pool = P2cPool::new(metrics.clone(), {
+            let built = built.clone();
+            move |(a, _): (SocketAddr, Weighted)| {
+                built.lock().push(a);
+                // Endpoints have equal loads, so that requests are distributed
+                // by weight alone.
+                Constant::new(
+                    if a == addr0 {
+                        svc0.clone()
+                    } else if a == addr1 {
+                        svc1.clone()
+                    } else {
+                        panic!("unexpected address: {a}");
+                    },
+                    0,
+                )
+            }
+        })
+        .with_weights(|&Weighted(w)| w);
+        pool.reset_pool(vec![(addr0, Weighted(100)), (addr1, Weighted(100))]);
+
+        let mut send = async |pool: &mut P2cPool<_, _, _, _>| {
+            pool.ready().await.expect("pool must be ready");
+            let call = pool.call(());
+            let (addr, ((), respond)) = tokio::select! {
+                r = h0.next_request() => (addr0, r.unwrap()),
+                r = h1.next_request() => (addr1, r.unwrap()),
+            };
+            respond.send_response(());
+            call.await.expect("call should succeed");
+            addr
+        };
+
+        for (weight, expected) in [(100, 400..600), (10, 40..150), (0, 0..1)] {
+            pool.add_endpoint(addr0, Weighted(weight));
+            assert_eq!(metrics.weight.get(), i64::from(weight) + 100);
+
+            let mut sent = 0;
+            for _ in 0..REQUESTS {
+                if send(&mut pool).await == addr0 {
+                    sent += 1;
+                }
+            }
+            assert!(
+                expected.contains(&sent),
+                "{sent} of {REQUESTS} requests sent to an endpoint with weight {weight}"
+            );
+        }
+
+        // The drained endpoint is retained and no services were rebuilt.
+        let ctx = &mut Context::from_waker(futures_util::task::noop_waker_ref());
+        assert_ready_ok!(pool.poll_pool(ctx));
+        assert_eq!(pool.pool.ready_len(), 2);
+        assert_eq!(*built.lock(), [addr0, addr1]);
+        assert_eq!(metrics.endpoints.get(), 2);
+    }
 }
@@ -9,8 +9,8 @@ mod metadata;
 pub mod pb;
 mod resolve;
 
-pub use self::metadata::{Metadata, ProtocolHint};
-pub use self::resolve::Resolve;
+pub use self::metadata::{Metadata, ParentPort, ProtocolHint};
+pub use self::resolve::{Observe, ObserveUpdates, Resolve};
 
 // TODO(ver) this should hold a structured address reference and not just a FQDN:port.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -1,17 +1,32 @@
 use http::uri::Authority;
+use linkerd_addr::NameAddr;
 use linkerd_http_h2::ClientParams as HTTP2ClientParams;
+use linkerd_proxy_core::resolve::Weight;
+use linkerd_stack::Param;
 use linkerd_tls::client::ClientTls;
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+    sync::Arc,
+};
 
 /// Endpoint labels are lexigraphically ordered by key.
-pub type Labels = std::sync::Arc<BTreeMap<String, String>>;
+pub type Labels = Arc<BTreeMap<String, String>>;
 
 /// Metadata describing an endpoint.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+///
+/// An endpoint's weight may be updated without otherwise changing the
+/// endpoint, so weights are not considered when comparing or hashing
+/// metadata. This allows balancers to apply weight updates without rebuilding
+/// the endpoint's stack.
+#[derive(Clone, Debug)]
 pub struct Metadata {
     /// Arbitrary endpoint labels. Primarily used for telemetry.
     labels: Labels,
 
+    /// The endpoint's share of its balancer's requests, relative to the
+    /// balancer's other endpoints.
     weight: u32,
 
     /// A hint from the controller about what protocol (HTTP1, HTTP2, etc) the
@@ -26,8 +41,35 @@ pub struct Metadata {
     /// Used to override the the authority if needed
     authority_override: Option<Authority>,
 
+    /// The address to which connections are established, when it differs
+    /// from the endpoint's address (e.g. for external workloads that are
+    /// reachable through another address).
+    address_override: Option<SocketAddr>,
+
     http2: HTTP2ClientParams,
     is_zone_local: Option<bool>,
+
+    /// The zones for which the endpoint is hinted, if the controller provides
+    /// topology hints.
+    zone_hints: Option<Arc<[String]>>,
+
+    /// The services that expose the endpoint's ports, if the controller
+    /// provides them.
+    parent_ports: Option<Arc<[ParentPort]>>,
+}
+
+/// A service port that targets one of an endpoint's ports.
+///
+/// Endpoints of headless services are dialed by their own addresses, so the
+/// port that a client dials may differ from the service's port.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ParentPort {
+    /// The endpoint's port.
+    pub target_port: u16,
+
+    /// The service's authority, with the service port that targets
+    /// `target_port`.
+    pub parent: NameAddr,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
@@ -51,10 +93,13 @@ impl Default for Metadata {
             weight: 1,
             identity: None,
             authority_override: None,
+            address_override: None,
             tagged_transport_port: None,
             protocol_hint: ProtocolHint::Unknown,
             http2: HTTP2ClientParams::default(),
             is_zone_local: None,
+            zone_hints: None,
+            parent_ports: None,
         }
     }
 }
@@ -67,9 +112,12 @@ impl Metadata {
         tagged_transport_port: Option<u16>,
         identity: Option<ClientTls>,
         authority_override: Option<Authority>,
+        address_override: Option<SocketAddr>,
         weight: u32,
         http2: HTTP2ClientParams,
         is_zone_local: Option<bool>,
+        zone_hints: Option<Arc<[String]>>,
+        parent_ports: Option<Arc<[ParentPort]>>,
     ) -> Self {
         Self {
             labels: labels.into_iter().collect::<BTreeMap<_, _>>().into(),
@@ -77,9 +125,29 @@ impl Metadata {
             tagged_transport_port,
             identity,
             authority_override,
+            address_override,
             weight,
             http2,
             is_zone_local,
+            zone_hints,
+            parent_ports,
+        }
+    }
+
+    /// Sets the services that expose the endpoint's ports.
+    pub fn with_parent_ports(self, parent_ports: impl IntoIterator<Item = ParentPort>) -> Self {
+        let parent_ports = parent_ports.into_iter().collect::<Arc<[_]>>();
+        Self {
+            parent_ports: (!parent_ports.is_empty()).then_some(parent_ports),
+            ..self
+        }
+    }
+
+    /// Sets the address to which connections to the endpoint are established.
+    pub fn with_address_override(self, addr: SocketAddr) -> Self {
+        Self {
+            address_override: Some(addr),
+            ..self
         }
     }
 
@@ -96,6 +164,17 @@ impl Metadata {
         self.is_zone_local
     }
 
+    /// Returns the zones for which the endpoint is hinted, if it has topology
+    /// hints.
+    pub fn zone_hints(&self) -> Option<&[String]> {
+        self.zone_hints.as_deref()
+    }
+
+    /// Returns the services that expose the endpoint's ports.
+    pub fn parent_ports(&self) -> &[ParentPort] {
+        self.parent_ports.as_deref().unwrap_or_default()
+    }
+
     pub fn protocol_hint(&self) -> ProtocolHint {
         self.protocol_hint
     }
@@ -112,7 +191,77 @@ impl Metadata {
         self.authority_override.as_ref()
     }
 
+    /// Returns the address to which connections to the endpoint should be
+    /// established, if it differs from the endpoint's address.
+    pub fn address_override(&self) -> Option<SocketAddr> {
+        self.address_override
+    }
+
     pub fn http2_client_params(&self) -> &HTTP2ClientParams {
         &self.http2
     }
 }
+
+impl Param<Weight> for Metadata {
+    fn param(&self) -> Weight {
+        Weight(self.weight)
+    }
+}
+
+impl PartialEq for Metadata {
+    fn eq(&self, other: &Self) -> bool {
+        let Self {
+            labels,
+            weight: _,
+            protocol_hint,
+            tagged_transport_port,
+            identity,
+            authority_override,
+            address_override,
+            http2,
+            is_zone_local,
+            zone_hints,
+            parent_ports,
+        } = self;
+        *labels == other.labels
+            && *protocol_hint == other.protocol_hint
+            && *tagged_transport_port == other.tagged_transport_port
+            && *identity == other.identity
+            && *authority_override == other.authority_override
+            && *address_override == other.address_override
+            && *http2 == other.http2
+            && *is_zone_local == other.is_zone_local
+            && *zone_hints == other.zone_hints
+            && *parent_ports == other.parent_ports
+    }
+}
+
+impl Eq for Metadata {}
+
+impl Hash for Metadata {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Self {
+            labels,
+            weight: _,
+            protocol_hint,
+            tagged_transport_port,
+            identity,
+            authority_override,
+            address_override,
+            http2,
+            is_zone_local,
+            zone_hints,
+            parent_ports,
+        } = self;
+        labels.hash(state);
+        protocol_hint.hash(state);
+        tagged_transport_port.hash(state);
+        identity.hash(state);
+        authority_override.hash(state);
+        address_override.hash(state);
+        http2.hash(state);
+        is_zone_local.hash(state);
+        zone_hints.hash(state);
+        parent_ports.hash(state);
+    }
+}
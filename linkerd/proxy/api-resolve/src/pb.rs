@@ -4,12 +4,12 @@ use crate::{
         AuthorityOverride, Http2ClientParams, TlsIdentity, WeightedAddr,
     },
     api::net::TcpAddress,
-    metadata::{Metadata, ProtocolHint},
+    metadata::{Metadata, ParentPort, ProtocolHint},
 };
 use http::uri::Authority;
 use linkerd_identity::Id;
 use linkerd_tls::{client::ServerId, ClientTls, ServerName};
-use std::{collections::HashMap, net::SocketAddr, str::FromStr, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, str::FromStr, sync::Arc, time::Duration};
 
 /// Construct a new labeled `SocketAddr `from a protobuf `WeightedAddr`.
 pub fn to_addr_meta(
@@ -34,6 +34,37 @@ pub fn to_addr_meta(
         }
     });
 
+    // Topology hints are a comma-separated list of the zones that should
+    // prefer the endpoint.
+    let zone_hints = pb.metric_labels.get("for_zones").and_then(|zones| {
+        let zones = zones
+            .split(',')
+            .map(str::trim)
+            .filter(|z| !z.is_empty())
+            .map(String::from)
+            .collect::<Arc<[_]>>();
+        (!zones.is_empty()).then_some(zones)
+    });
+
+    // Parent ports are a comma-separated list of `<target port>=<authority>`
+    // pairs, e.g. `8080=web.ns.svc.cluster.local:80`.
+    let parent_ports = pb.metric_labels.get("parent_ports").and_then(|ports| {
+        let ports = ports
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .filter_map(to_parent_port)
+            .collect::<Arc<[_]>>();
+        (!ports.is_empty()).then_some(ports)
+    });
+
+    // External workloads may be reachable through an address other than the
+    // one that identifies the endpoint, e.g. `10.2.0.5:8080`.
+    let address_override = pb
+        .metric_labels
+        .get("address_override")
+        .and_then(|addr| to_address_override(addr));
+
     let mut proto_hint = ProtocolHint::Unknown;
     let mut tagged_transport_port = None;
     if let Some(hint) = pb.protocol_hint {
@@ -59,13 +90,41 @@ pub fn to_addr_meta(
         tagged_transport_port,
         tls_id,
         authority_override,
+        address_override,
         pb.weight,
         http2,
         zone_locality,
+        zone_hints,
+        parent_ports,
     );
     Some((addr, meta))
 }
 
+fn to_parent_port(port: &str) -> Option<ParentPort> {
+    let parsed = port.split_once('=').and_then(|(target_port, parent)| {
+        Some(ParentPort {
+            target_port: target_port.parse().ok().filter(|p| *p != 0)?,
+            parent: parent.parse().ok()?,
+        })
+    });
+    if parsed.is_none() {
+        tracing::debug!("Ignoring invalid parent port: {port}");
+    }
+    parsed
+}
+
+fn to_address_override(addr: &str) -> Option<SocketAddr> {
+    let parsed = addr
+        .trim()
+        .parse::<SocketAddr>()
+        .ok()
+        .filter(|a| a.port() != 0 && !a.ip().is_unspecified());
+    if parsed.is_none() {
+        tracing::debug!("Ignoring invalid address override: {addr}");
+    }
+    parsed
+}
+
 fn to_identity(pb: TlsIdentity) -> Option<ClientTls> {
     use crate::api::destination::tls_identity::Strategy;
 
@@ -108,7 +167,8 @@ pub(crate) fn to_authority(o: AuthorityOverride) -> Option<Authority> {
     }
 }
 
-pub(crate) fn to_sock_addr(pb: TcpAddress) -> Option<SocketAddr> {
+/// Converts a protobuf `TcpAddress` to a `SocketAddr`, if it is valid.
+pub fn to_sock_addr(pb: TcpAddress) -> Option<SocketAddr> {
     use crate::api::net::ip_address::Ip;
     use std::net::{Ipv4Addr, Ipv6Addr};
     /*
@@ -463,4 +523,130 @@ mod tests {
         .unwrap();
         assert_eq!(meta.is_zone_local(), None);
     }
+
+    #[test]
+    fn zone_hints() {
+        let addr = WeightedAddr {
+            addr: Some(TcpAddress {
+                ip: Some(IpAddress {
+                    ip: Some(Ip::Ipv4(0)),
+                }),
+                port: 0,
+            }),
+            ..Default::default()
+        };
+
+        let (_, meta) = to_addr_meta(addr.clone(), &HashMap::new()).unwrap();
+        assert_eq!(meta.zone_hints(), None);
+
+        let (_, meta) = to_addr_meta(
+            WeightedAddr {
+                metric_labels: HashMap::from_iter([(
+                    "for_zones".to_string(),
+                    "zone-a, zone-b".to_string(),
+                )]),
+                ..addr.clone()
+            },
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            meta.zone_hints(),
+            Some(&["zone-a".to_string(), "zone-b".to_string()][..])
+        );
+
+        let (_, meta) = to_addr_meta(
+            WeightedAddr {
+                metric_labels: HashMap::from_iter([("for_zones".to_string(), ",".to_string())]),
+                ..addr
+            },
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(meta.zone_hints(), None);
+    }
+
+    #[test]
+    fn parent_ports() {
+        let addr = WeightedAddr {
+            addr: Some(TcpAddress {
+                ip: Some(IpAddress {
+                    ip: Some(Ip::Ipv4(0)),
+                }),
+                port: 0,
+            }),
+            ..Default::default()
+        };
+
+        let (_, meta) = to_addr_meta(addr.clone(), &HashMap::new()).unwrap();
+        assert_eq!(meta.parent_ports(), &[]);
+
+        let (_, meta) = to_addr_meta(
+            WeightedAddr {
+                metric_labels: HashMap::from_iter([(
+                    "parent_ports".to_string(),
+                    "8080=web.ns.svc.cluster.local:80, 0=zero.ns.svc.cluster.local:80,9090=bad,8080=api.ns.svc.cluster.local:8000".to_string(),
+                )]),
+                ..addr
+            },
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            meta.parent_ports(),
+            &[
+                ParentPort {
+                    target_port: 8080,
+                    parent: "web.ns.svc.cluster.local:80".parse().unwrap(),
+                },
+                ParentPort {
+                    target_port: 8080,
+                    parent: "api.ns.svc.cluster.local:8000".parse().unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn address_override() {
+        let addr = WeightedAddr {
+            addr: Some(TcpAddress {
+                ip: Some(IpAddress {
+                    ip: Some(Ip::Ipv4(0)),
+                }),
+                port: 0,
+            }),
+            ..Default::default()
+        };
+
+        let (_, meta) = to_addr_meta(addr.clone(), &HashMap::new()).unwrap();
+        assert_eq!(meta.address_override(), None);
+
+        let with_label = |value: &str| {
+            let (_, meta) = to_addr_meta(
+                WeightedAddr {
+                    metric_labels: HashMap::from_iter([(
+                        "address_override".to_string(),
+                        value.to_string(),
+                    )]),
+                    ..addr.clone()
+                },
+                &HashMap::new(),
+            )
+            .unwrap();
+            meta.address_override()
+        };
+        assert_eq!(
+            with_label("10.2.0.5:8080"),
+            Some("10.2.0.5:8080".parse().unwrap())
+        );
+        assert_eq!(
+            with_label("[fd00::5]:8080"),
+            Some("[fd00::5]:8080".parse().unwrap())
+        );
+        assert_eq!(with_label("10.2.0.5"), None);
+        assert_eq!(with_label("10.2.0.5:0"), None);
+        assert_eq!(with_label("0.0.0.0:8080"), None);
+        assert_eq!(with_label("vm.example.com:8080"), None);
+    }
 }
@@ -6,6 +6,7 @@ use linkerd_error::Error;
 use linkerd_stack::Param;
 use linkerd_tonic_stream::{LimitReceiveFuture, ReceiveLimits};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use tonic::{self as grpc, body::BoxBody, client::GrpcService};
 use tower::Service;
@@ -16,6 +17,20 @@ pub struct Resolve<S> {
     client: DestinationClient<S>,
     context_token: String,
     limits: ReceiveLimits,
+    observe: Option<Arc<dyn Observe>>,
+}
+
+/// Observes the updates received for each resolution, e.g. so that they may be
+/// persisted.
+pub trait Observe: Send + Sync + 'static {
+    /// Begins observing a resolution of the given destination path. The
+    /// resolution ends when the returned handle is dropped.
+    fn observe(&self, path: &str) -> Box<dyn ObserveUpdates>;
+}
+
+/// Observes the updates received for a single resolution.
+pub trait ObserveUpdates: Send + 'static {
+    fn update(&mut self, update: &api::Update);
 }
 
 // === impl Resolve ===
@@ -33,6 +48,15 @@ where
             client: DestinationClient::new(svc),
             context_token,
             limits,
+            observe: None,
+        }
+    }
+
+    /// Notifies `observe` of the updates received for each resolution.
+    pub fn with_observer(self, observe: Arc<dyn Observe>) -> Self {
+        Self {
+            observe: Some(observe),
+            ..self
         }
     }
 }
@@ -72,11 +96,19 @@ where
 
         let limits = self.limits;
         let mut client = self.client.clone();
+        let observe = self.observe.clone();
         Box::pin(async move {
+            let path = req.path.clone();
             let rsp = LimitReceiveFuture::new(limits, client.get(grpc::Request::new(req))).await?;
             trace!(metadata = ?rsp.metadata());
+            let mut observer = observe.map(|o| o.observe(&path));
             Ok(rsp
                 .into_inner()
+                .inspect_ok(move |up| {
+                    if let Some(observer) = observer.as_mut() {
+                        observer.update(up);
+                    }
+                })
                 .try_filter_map(|up| futures::future::ok::<_, _>(mk_update(up)))
                 .boxed())
         })
@@ -0,0 +1,60 @@
+use std::fmt;
+
+/// The state of a pool's service discovery resolution.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DiscoveryState {
+    /// No update has been received from the resolution.
+    Pending,
+
+    /// The resolution failed and is being recovered.
+    Unavailable,
+
+    /// The resolution succeeded without any endpoints.
+    Empty,
+
+    /// The resolution includes at least one endpoint.
+    Resolved,
+}
+
+// === impl DiscoveryState ===
+
+impl DiscoveryState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Unavailable => "unavailable",
+            Self::Empty => "empty",
+            Self::Resolved => "resolved",
+        }
+    }
+
+    pub(crate) fn as_i64(&self) -> i64 {
+        match self {
+            Self::Pending => 0,
+            Self::Unavailable => 1,
+            Self::Empty => 2,
+            Self::Resolved => 3,
+        }
+    }
+
+    pub(crate) fn from_i64(v: i64) -> Option<Self> {
+        match v {
+            0 => Some(Self::Pending),
+            1 => Some(Self::Unavailable),
+            2 => Some(Self::Empty),
+            3 => Some(Self::Resolved),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DiscoveryState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => "endpoints have not been resolved".fmt(f),
+            Self::Unavailable => "endpoint discovery is unavailable".fmt(f),
+            Self::Empty => "resolved to zero endpoints".fmt(f),
+            Self::Resolved => "no endpoints are ready".fmt(f),
+        }
+    }
+}
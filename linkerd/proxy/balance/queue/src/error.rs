@@ -1,6 +1,8 @@
 //! Error types for the `PoolQueue` middleware.
 
+use crate::DiscoveryState;
 use linkerd_error::Error;
+use linkerd_stack::FailFastError;
 use std::{fmt, sync::Arc};
 
 /// A shareable, terminal error produced by either a service or discovery
@@ -27,3 +29,28 @@ impl std::error::Error for TerminalFailure {
         Some(&**self.0)
     }
 }
+
+/// Indicates that a pool in failfast could not dispatch a request, qualified
+/// by the state of its discovery resolution.
+#[derive(Debug, thiserror::Error)]
+#[error("{source}: {discovery}")]
+pub struct NoReadyEndpoints {
+    discovery: DiscoveryState,
+    #[source]
+    source: FailFastError,
+}
+
+// === impl NoReadyEndpoints ===
+
+impl NoReadyEndpoints {
+    pub fn new(discovery: DiscoveryState) -> Self {
+        Self {
+            discovery,
+            source: FailFastError::default(),
+        }
+    }
+
+    pub fn discovery(&self) -> DiscoveryState {
+        self.discovery
+    }
+}
@@ -9,6 +9,7 @@
 
 use linkerd_metrics::prom;
 
+mod discovery;
 mod error;
 mod failfast;
 mod future;
@@ -18,7 +19,7 @@ mod service;
 mod tests;
 mod worker;
 
-pub use self::service::PoolQueue;
+pub use self::{discovery::DiscoveryState, error::NoReadyEndpoints, service::PoolQueue};
 pub use linkerd_pool::Pool;
 pub use linkerd_proxy_core::Update;
 
@@ -29,6 +30,7 @@ pub struct QueueMetricFamilies<L> {
     length: prom::Family<L, prom::Gauge>,
     requests: prom::Family<L, prom::Counter>,
     latency: prom::Family<L, prom::Histogram, fn() -> prom::Histogram>,
+    discovery: prom::Family<L, prom::Gauge>,
     gate: GateMetricFamilies<L>,
 }
 
@@ -38,6 +40,7 @@ pub struct QueueMetrics {
     length: prom::Gauge,
     requests: prom::Counter,
     latency: prom::Histogram,
+    discovery: prom::Gauge,
     gate: GateMetrics,
 }
 
@@ -58,6 +61,7 @@ where
                 // of buckets.
                 prom::Histogram::new([0.0005, 0.005, 0.05, 0.5, 1.0, 3.0].iter().copied())
             }),
+            discovery: prom::Family::default(),
             gate: GateMetricFamilies::default(),
         }
     }
@@ -96,12 +100,20 @@ where
             latency.clone(),
         );
 
+        let discovery = prom::Family::default();
+        reg.register(
+            "discovery_state",
+            "The state of the queue's endpoint discovery: 0 if pending, 1 if unavailable, 2 if empty, or 3 if resolved",
+            discovery.clone(),
+        );
+
         let gate = GateMetricFamilies::register(reg.sub_registry_with_prefix("gate"));
 
         Self {
             length,
             requests,
             latency,
+            discovery,
             gate,
         }
     }
@@ -110,14 +122,23 @@ where
         let length = self.length.get_or_create(labels).clone();
         let requests = self.requests.get_or_create(labels).clone();
         let latency = self.latency.get_or_create(labels).clone();
+        let discovery = self.discovery.get_or_create(labels).clone();
         let gate = self.gate.metrics(labels);
         QueueMetrics {
             length,
             requests,
             latency,
+            discovery,
             gate,
         }
     }
+
+    /// Returns the discovery state of the queue with the given labels, if one
+    /// has been built.
+    pub fn discovery_state(&self, labels: &L) -> Option<DiscoveryState> {
+        let gauge = self.discovery.get(labels)?;
+        DiscoveryState::from_i64(gauge.get())
+    }
 }
 
 // === impl QueueMetrics ===
@@ -128,6 +149,7 @@ impl Default for QueueMetrics {
             length: prom::Gauge::default(),
             requests: prom::Counter::default(),
             latency: prom::Histogram::new(std::iter::empty()),
+            discovery: prom::Gauge::default(),
             gate: GateMetrics::default(),
         }
     }
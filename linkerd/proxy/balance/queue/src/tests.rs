@@ -1,6 +1,6 @@
 #![allow(clippy::ok_expect)]
 
-use crate::PoolQueue;
+use crate::{DiscoveryState, NoReadyEndpoints, PoolQueue, QueueMetricFamilies};
 use futures::prelude::*;
 use linkerd_pool_mock as mock;
 use linkerd_proxy_core::Update;
@@ -394,3 +394,60 @@ async fn failfast_interrupted() {
     assert!(call.await.is_ok(), "call should not failfast");
     assert!(poolq.ready().await.is_ok(), "poolq must be ready");
 }
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn failfast_discovery_states() {
+    let _trace = linkerd_tracing::test::with_default_filter("linkerd=trace");
+
+    let addr = std::net::SocketAddr::new([192, 0, 2, 1].into(), 80);
+    let cases = [
+        (vec![], DiscoveryState::Pending),
+        (vec![Update::Unavailable], DiscoveryState::Unavailable),
+        (vec![Update::Reset(vec![])], DiscoveryState::Empty),
+        (vec![Update::DoesNotExist], DiscoveryState::Empty),
+        (
+            vec![Update::Add(vec![(addr, ())]), Update::Remove(vec![addr])],
+            DiscoveryState::Empty,
+        ),
+        (
+            vec![Update::Reset(vec![(addr, ())]), Update::Unavailable],
+            DiscoveryState::Unavailable,
+        ),
+        (
+            vec![Update::Reset(vec![(addr, ())])],
+            DiscoveryState::Resolved,
+        ),
+    ];
+
+    let families = QueueMetricFamilies::<Vec<(String, String)>>::default();
+    for (i, (updates, state)) in cases.into_iter().enumerate() {
+        let labels = vec![("case".to_string(), i.to_string())];
+        let (pool, mut handle) = mock::pool::<(), (), ()>();
+        let (tx, u) = mpsc::channel::<Result<Update<()>, mock::ResolutionError>>(10);
+        for up in updates {
+            tx.try_send(Ok(up)).ok().expect("send update");
+        }
+        let mut poolq = PoolQueue::spawn(
+            10,
+            time::Duration::from_secs(1),
+            families.metrics(&labels),
+            ReceiverStream::from(u),
+            pool,
+        );
+
+        handle.svc.allow(0);
+        assert!(poolq.ready().await.is_ok(), "poolq must be ready");
+        let call = poolq.call(());
+        time::sleep(time::Duration::from_secs(1)).await;
+        let error = call.await.expect_err("call should failfast");
+        let no_endpoints = error
+            .downcast_ref::<NoReadyEndpoints>()
+            .expect("call must fail with NoReadyEndpoints");
+        assert_eq!(no_endpoints.discovery(), state, "case {i}");
+        assert!(
+            linkerd_error::is_caused_by::<linkerd_stack::FailFastError>(&*error),
+            "case {i}: error must be caused by failfast"
+        );
+        assert_eq!(families.discovery_state(&labels), Some(state), "case {i}");
+    }
+}
@@ -4,14 +4,15 @@ use crate::{
     error,
     failfast::{self, Failfast},
     message::Message,
-    Pool, QueueMetrics,
+    DiscoveryState, Pool, QueueMetrics,
 };
 use futures::{future, TryStream, TryStreamExt};
 use linkerd_error::{Error, Result};
+use linkerd_metrics::prom;
 use linkerd_proxy_core::Update;
-use linkerd_stack::{gate, FailFastError, ServiceExt};
+use linkerd_stack::{gate, ServiceExt};
 use parking_lot::RwLock;
-use std::sync::Arc;
+use std::{collections::HashSet, net::SocketAddr, sync::Arc};
 use tokio::{sync::mpsc, task::JoinHandle, time};
 use tracing::{debug_span, Instrument};
 
@@ -39,6 +40,11 @@ struct PoolDriver<P> {
 struct Discovery<R> {
     resolution: R,
     closed: bool,
+    /// The addresses of the resolution's endpoints, so that an empty
+    /// resolution can be distinguished from one that has not been resolved.
+    endpoints: HashSet<SocketAddr>,
+    state: DiscoveryState,
+    gauge: prom::Gauge,
 }
 
 /// Spawns a task that simultaneously updates a pool of services from a
@@ -70,7 +76,7 @@ where
         async move {
             let mut worker = Worker {
                 pool: PoolDriver::new(pool, Failfast::new(failfast, gate, metrics.gate.clone())),
-                discovery: Discovery::new(updates_rx),
+                discovery: Discovery::new(updates_rx, metrics.discovery.clone()),
             };
 
             loop {
@@ -122,7 +128,7 @@ where
                     // Preserve the original request's tracing context in
                     // the inner call.
                     let _enter = span.enter();
-                    worker.pool.call(req)
+                    worker.pool.call(req, worker.discovery.state)
                 };
 
                 if tx.send(call).is_ok() {
@@ -164,24 +170,40 @@ where
             };
 
             tracing::debug!(?update, "Discovered");
-            match update {
-                Update::Reset(eps) => {
-                    self.pool.pool.reset_pool(eps);
-                }
-                Update::Add(eps) => {
-                    for (addr, ep) in eps.into_iter() {
-                        self.pool.pool.add_endpoint(addr, ep);
-                    }
-                }
-                Update::Remove(addrs) => {
-                    for addr in addrs.into_iter() {
-                        self.pool.pool.remove_endpoint(addr);
-                    }
+            self.update_pool(update);
+        }
+    }
+
+    /// Applies a discovery update to the pool.
+    fn update_pool<Req>(&mut self, update: Update<T>)
+    where
+        P: Pool<T, Req>,
+    {
+        match update {
+            Update::Reset(eps) => {
+                self.discovery.reset(eps.iter().map(|(addr, _)| *addr));
+                self.pool.pool.reset_pool(eps);
+            }
+            Update::Add(eps) => {
+                for (addr, ep) in eps.into_iter() {
+                    self.discovery.add(addr);
+                    self.pool.pool.add_endpoint(addr, ep);
                 }
-                Update::DoesNotExist => {
-                    self.pool.pool.reset_pool(vec![]);
+            }
+            Update::Remove(addrs) => {
+                for addr in addrs.into_iter() {
+                    self.discovery.remove(addr);
+                    self.pool.pool.remove_endpoint(addr);
                 }
             }
+            Update::DoesNotExist => {
+                self.discovery.reset(None);
+                self.pool.pool.reset_pool(vec![]);
+            }
+            Update::Unavailable => {
+                // Retain the pool's endpoints until the resolution recovers.
+                self.discovery.set_unavailable();
+            }
         }
     }
 
@@ -202,24 +224,7 @@ where
             };
 
             tracing::debug!(?update, "Discovered");
-            match update {
-                Update::Reset(eps) => {
-                    self.pool.pool.reset_pool(eps);
-                }
-                Update::Add(eps) => {
-                    for (addr, ep) in eps.into_iter() {
-                        self.pool.pool.add_endpoint(addr, ep);
-                    }
-                }
-                Update::Remove(addrs) => {
-                    for addr in addrs.into_iter() {
-                        self.pool.pool.remove_endpoint(addr);
-                    }
-                }
-                Update::DoesNotExist => {
-                    self.pool.pool.reset_pool(vec![]);
-                }
-            }
+            self.update_pool(update);
         }
     }
 }
@@ -232,10 +237,15 @@ where
     R: TryStream<Ok = Update<T>> + Unpin,
     R::Error: Into<Error>,
 {
-    fn new(resolution: R) -> Self {
+    fn new(resolution: R, gauge: prom::Gauge) -> Self {
+        let state = DiscoveryState::Pending;
+        gauge.set(state.as_i64());
         Self {
             resolution,
             closed: false,
+            endpoints: HashSet::new(),
+            state,
+            gauge,
         }
     }
 
@@ -268,6 +278,44 @@ where
     }
 }
 
+impl<R> Discovery<R> {
+    fn reset(&mut self, addrs: impl IntoIterator<Item = SocketAddr>) {
+        self.endpoints.clear();
+        self.endpoints.extend(addrs);
+        self.update_state();
+    }
+
+    fn add(&mut self, addr: SocketAddr) {
+        self.endpoints.insert(addr);
+        self.update_state();
+    }
+
+    fn remove(&mut self, addr: SocketAddr) {
+        self.endpoints.remove(&addr);
+        self.update_state();
+    }
+
+    fn set_unavailable(&mut self) {
+        self.set_state(DiscoveryState::Unavailable);
+    }
+
+    fn update_state(&mut self) {
+        self.set_state(if self.endpoints.is_empty() {
+            DiscoveryState::Empty
+        } else {
+            DiscoveryState::Resolved
+        });
+    }
+
+    fn set_state(&mut self, state: DiscoveryState) {
+        if self.state != state {
+            tracing::debug!(state = state.as_str(), "Discovery state changed");
+            self.state = state;
+            self.gauge.set(state.as_i64());
+        }
+    }
+}
+
 // === impl PoolDriver ===
 
 impl<P> PoolDriver<P> {
@@ -355,14 +403,16 @@ impl<P> PoolDriver<P> {
         Ok(())
     }
 
-    fn call<T, Req>(&mut self, req: Req) -> Result<P::Future, Error>
+    fn call<T, Req>(&mut self, req: Req, discovery: DiscoveryState) -> Result<P::Future, Error>
     where
         P: Pool<T, Req>,
         P::Error: Into<Error>,
     {
-        // If we've tripped failfast, fail the request.
+        // If we've tripped failfast, fail the request, describing the state
+        // of discovery so that callers can distinguish an empty resolution
+        // from one that hasn't been (or can't be) resolved.
         if self.failfast.is_active() {
-            return Err(FailFastError::default().into());
+            return Err(error::NoReadyEndpoints::new(discovery).into());
         }
 
         // Otherwise dispatch the request to the pool.
@@ -9,26 +9,47 @@ use linkerd_proxy_balance_gauge_endpoints::{
     EndpointsGauges, EndpointsGaugesFamilies, NewGaugeBalancerEndpoint,
 };
 use linkerd_proxy_balance_queue::PoolQueue;
-use linkerd_proxy_core::Resolve;
+use linkerd_proxy_core::{Resolve, Weight};
 use linkerd_stack::{layer, queue, ExtractParam, Gate, NewService, Param, Service};
-use std::{fmt::Debug, marker::PhantomData, net::SocketAddr};
-use tokio::time;
-use tower::load::{self, PeakEwma};
+use std::{fmt::Debug, marker::PhantomData, net::SocketAddr, sync::atomic::AtomicU64};
+use tower::load::TrackCompletion;
 
-pub use linkerd_proxy_balance_queue::{Pool, QueueMetricFamilies, QueueMetrics, Update};
-pub use tower::load::peak_ewma;
+pub mod peak_ewma;
 
+use self::peak_ewma::{LoadGauges, PeakEwma};
+
+pub use linkerd_pool_p2c::SourceAffinity;
+pub use linkerd_proxy_balance_queue::{
+    DiscoveryState, NoReadyEndpoints, Pool, QueueMetricFamilies, QueueMetrics, Update,
+};
+
+/// Configures how a balancer estimates its endpoints' loads. See
+/// [`PeakEwma`].
+///
+/// A balancer's configuration is fixed when it is built.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct EwmaConfig {
+    /// The latency estimate of endpoints that have not yet responded.
     pub default_rtt: std::time::Duration,
+
+    /// The time constant over which latency estimates decay.
     pub decay: std::time::Duration,
 }
 
+/// Overrides the [`EwmaConfig`] parameters that are set.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EwmaOverrides {
+    pub default_rtt: Option<std::time::Duration>,
+    pub decay: Option<std::time::Duration>,
+}
+
 #[derive(Clone, Debug)]
 pub struct MetricFamilies<L> {
     queue: QueueMetricFamilies<L>,
     p2c: P2cMetricFamilies<L>,
     endpoints: EndpointsGaugesFamilies<L>,
+    endpoint_load: prom::Family<peak_ewma::EndpointLoadLabels<L>, prom::Gauge<f64, AtomicU64>>,
+    export_endpoint_load: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -36,6 +57,7 @@ pub struct Metrics {
     queue: QueueMetrics,
     p2c: P2cMetrics,
     endpoints: EndpointsGauges,
+    endpoint_load: Option<LoadGauges>,
 }
 
 /// Configures a stack to resolve targets to balance requests over `N`-typed
@@ -55,6 +77,7 @@ pub type Balance<Req, F> = Gate<PoolQueue<Req, F>>;
 #[derive(Debug)]
 struct NewPeakEwma<C, Req, N> {
     config: EwmaConfig,
+    gauges: Option<LoadGauges>,
     inner: N,
     _marker: PhantomData<fn(Req) -> C>,
 }
@@ -84,16 +107,18 @@ impl<C, Req, X, R, N> NewBalance<C, Req, X, R, N> {
 impl<C, T, Req, X, R, M, N, S> NewService<T> for NewBalance<C, Req, X, R, M>
 where
     T: Param<EwmaConfig> + Param<queue::Capacity> + Param<queue::Timeout> + Clone + Send,
+    T: Param<Option<SourceAffinity<Req>>>,
     X: ExtractParam<Metrics, T>,
     R: Resolve<T>,
     R::Resolution: Unpin,
     R::Error: Send,
+    R::Endpoint: Param<Weight>,
     M: NewService<T, Service = N> + Clone,
     N: NewService<(SocketAddr, R::Endpoint), Service = S> + Send + 'static,
     S: Service<Req> + Send + 'static,
     S::Future: Send + 'static,
     S::Error: Into<Error>,
-    C: load::TrackCompletion<load::peak_ewma::Handle, S::Response> + Default + Send + 'static,
+    C: TrackCompletion<peak_ewma::Handle, S::Response> + Clone + Default + Send + 'static,
     Req: Send + 'static,
     Balance<Req, future::ErrInto<<PeakEwma<S, C> as Service<Req>>::Future, Error>>: Service<Req>,
 {
@@ -113,15 +138,24 @@ where
 
         let queue::Capacity(capacity) = target.param();
         let queue::Timeout(failfast) = target.param();
+        let affinity: Option<SourceAffinity<Req>> = target.param();
         let metrics = self.params.extract_param(&target);
 
         // The pool wraps the inner endpoint stack so that its inner ready cache
         // can be updated without requiring the service to process requests.
         let new_endpoint = NewPeakEwma::new(
             target.param(),
+            metrics.endpoint_load,
             NewGaugeBalancerEndpoint::new(metrics.endpoints, self.inner.new_service(target)),
         );
-        let pool = P2cPool::new(metrics.p2c, new_endpoint);
+        let mut pool = P2cPool::new(metrics.p2c, new_endpoint).with_weights(|ep| {
+            let Weight(weight) = ep.param();
+            weight
+        });
+        if let Some(affinity) = affinity {
+            tracing::debug!(capacity = affinity.capacity, "Enabling source affinity");
+            pool = pool.with_source_affinity(affinity);
+        }
 
         // The queue runs on a dedicated task, owning the resolution stream and
         // all of the inner endpoint services. A cloneable Service is returned
@@ -144,45 +178,49 @@ impl<C, Req, X: Clone, R: Clone, N: Clone> Clone for NewBalance<C, Req, X, R, N>
     }
 }
 
+// === impl EwmaOverrides ===
+
+impl EwmaOverrides {
+    /// Returns the configuration with the overridden parameters replaced.
+    pub fn apply(&self, config: EwmaConfig) -> EwmaConfig {
+        EwmaConfig {
+            default_rtt: self.default_rtt.unwrap_or(config.default_rtt),
+            decay: self.decay.unwrap_or(config.decay),
+        }
+    }
+}
+
 // === impl NewPeakEwma ===
 
 impl<C, Req, N> NewPeakEwma<C, Req, N> {
-    fn new(config: EwmaConfig, inner: N) -> Self {
+    fn new(config: EwmaConfig, gauges: Option<LoadGauges>, inner: N) -> Self {
         Self {
             config,
+            gauges,
             inner,
             _marker: PhantomData,
         }
     }
 }
 
-impl<C, T, N, Req, S> NewService<T> for NewPeakEwma<C, Req, N>
+impl<C, E, N, Req, S> NewService<(SocketAddr, E)> for NewPeakEwma<C, Req, N>
 where
-    C: load::TrackCompletion<load::peak_ewma::Handle, S::Response> + Default,
-    N: NewService<T, Service = S>,
+    C: TrackCompletion<peak_ewma::Handle, S::Response> + Default,
+    N: NewService<(SocketAddr, E), Service = S>,
     S: Service<Req>,
 {
     type Service = PeakEwma<S, C>;
 
-    fn new_service(&self, target: T) -> Self::Service {
-        // Converts durations to nanos in f64.
-        //
-        // Due to a lossy transformation, the maximum value that can be
-        // represented is ~585 years, which, I hope, is more than enough to
-        // represent request latencies.
-        fn nanos(d: time::Duration) -> f64 {
-            const NANOS_PER_SEC: u64 = 1_000_000_000;
-            let n = f64::from(d.subsec_nanos());
-            let s = d.as_secs().saturating_mul(NANOS_PER_SEC) as f64;
-            n + s
-        }
-
-        PeakEwma::new(
-            self.inner.new_service(target),
-            self.config.default_rtt,
-            nanos(self.config.decay),
+    fn new_service(&self, (addr, endpoint): (SocketAddr, E)) -> Self::Service {
+        let svc = PeakEwma::new(
+            self.inner.new_service((addr, endpoint)),
+            self.config,
             C::default(),
-        )
+        );
+        match self.gauges.as_ref() {
+            Some(gauges) => svc.with_gauge(gauges, addr),
+            None => svc,
+        }
     }
 }
 
@@ -197,10 +235,30 @@ where
         let p2c = P2cMetricFamilies::register(reg.sub_registry_with_prefix("p2c"));
         let queue = QueueMetricFamilies::register(reg.sub_registry_with_prefix("queue"));
         let endpoints = EndpointsGaugesFamilies::register(reg);
+        let endpoint_load = prom::Family::default();
+        reg.register(
+            "endpoint_load",
+            "The peak-EWMA load estimate of each endpoint in a balancer, i.e. its estimated latency in seconds multiplied by its pending requests plus one. Only recorded when enabled",
+            endpoint_load.clone(),
+        );
         Self {
             p2c,
             queue,
             endpoints,
+            endpoint_load,
+            export_endpoint_load: false,
+        }
+    }
+
+    /// Configures whether balancers built with these metrics record each of
+    /// their endpoints' load estimates.
+    ///
+    /// Each endpoint's estimate is labeled by its address, so this is
+    /// intended for debugging.
+    pub fn with_endpoint_load(self, export_endpoint_load: bool) -> Self {
+        Self {
+            export_endpoint_load,
+            ..self
         }
     }
 
@@ -210,8 +268,17 @@ where
             p2c: self.p2c.metrics(labels),
             queue: self.queue.metrics(labels),
             endpoints: self.endpoints.metrics(labels),
+            endpoint_load: self
+                .export_endpoint_load
+                .then(|| LoadGauges::new(self.endpoint_load.clone(), labels.clone())),
         }
     }
+
+    /// Returns the discovery state of the balancer with the given labels, if
+    /// one has been built.
+    pub fn discovery_state(&self, labels: &L) -> Option<DiscoveryState> {
+        self.queue.discovery_state(labels)
+    }
 }
 
 impl<L> Default for MetricFamilies<L>
@@ -224,6 +291,8 @@ where
             p2c: P2cMetricFamilies::default(),
             queue: QueueMetricFamilies::default(),
             endpoints: EndpointsGaugesFamilies::default(),
+            endpoint_load: prom::Family::default(),
+            export_endpoint_load: false,
         }
     }
 }
@@ -0,0 +1,459 @@
+//! Measures load using a peak-sensitive, exponentially weighted moving average
+//! of endpoints' response latencies.
+//!
+//! This is a port of tower's `PeakEwma` that may record each endpoint's load
+//! estimate in a gauge so that balancers' decisions can be inspected.
+
+use super::EwmaConfig;
+use linkerd_metrics::prom;
+use linkerd_stack::Service;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{atomic::AtomicU64, Arc},
+    task::{Context, Poll},
+};
+use tokio::time::{Duration, Instant};
+use tower::load::{completion::TrackCompletionFuture, Load, TrackCompletion};
+
+/// Wraps an endpoint's service to measure its load.
+///
+/// An endpoint's load is its latency estimate multiplied by the number of
+/// requests pending on it, plus one. Its latency estimate is set to each
+/// response's latency that exceeds it, so that endpoints are penalized
+/// immediately when they become slow. Otherwise, responses' latencies are
+/// averaged into the estimate, so that lower latencies are recovered more
+/// slowly, over the configured decay time constant. The estimate also decays
+/// towards zero while the endpoint is idle, so that slow endpoints are
+/// eventually probed again.
+///
+/// Endpoints without any samples are assigned the configured default RTT,
+/// which decays in the same way. Because the balancer does not otherwise warm
+/// up new endpoints, this is the only penalty that keeps new endpoints from
+/// being selected over endpoints with known, lower latencies.
+#[derive(Debug)]
+pub struct PeakEwma<S, C> {
+    service: S,
+    decay_ns: f64,
+    rtt_estimate: Arc<Mutex<RttEstimate>>,
+    completion: C,
+    gauge: Option<LoadGauge>,
+}
+
+/// Updates an endpoint's latency estimate when a request completes.
+#[derive(Debug)]
+pub struct Handle {
+    sent_at: Instant,
+    decay_ns: f64,
+    rtt_estimate: Arc<Mutex<RttEstimate>>,
+}
+
+/// The load of an endpoint, in nanoseconds.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Cost(f64);
+
+/// Builds gauges that record each of a balancer's endpoints' loads, in
+/// seconds.
+#[derive(Clone)]
+pub struct LoadGauges(Arc<dyn LoadGaugeFamily>);
+
+/// Records an endpoint's load, removing it from its family when the endpoint
+/// is dropped.
+struct LoadGauge {
+    addr: SocketAddr,
+    gauge: prom::Gauge<f64, AtomicU64>,
+    family: Arc<dyn LoadGaugeFamily>,
+}
+
+#[derive(Debug)]
+struct RttEstimate {
+    update_at: Instant,
+    rtt_ns: f64,
+}
+
+trait LoadGaugeFamily: Send + Sync + 'static {
+    fn get_or_create(&self, addr: SocketAddr) -> prom::Gauge<f64, AtomicU64>;
+
+    fn remove(&self, addr: SocketAddr);
+}
+
+/// A balancer's load gauges, labeled by endpoint address and the balancer's
+/// labels.
+struct LabeledLoadGauges<L> {
+    family: prom::Family<EndpointLoadLabels<L>, prom::Gauge<f64, AtomicU64>>,
+    labels: L,
+    /// Counts the services that share each endpoint's gauge, since an
+    /// endpoint may be replaced before its prior service is dropped.
+    endpoints: Mutex<HashMap<SocketAddr, usize>>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub(crate) struct EndpointLoadLabels<L> {
+    addr: SocketAddr,
+    labels: L,
+}
+
+// === impl PeakEwma ===
+
+impl<S, C> PeakEwma<S, C> {
+    pub fn new(service: S, config: EwmaConfig, completion: C) -> Self {
+        Self {
+            service,
+            decay_ns: nanos(config.decay),
+            rtt_estimate: Arc::new(Mutex::new(RttEstimate::new(nanos(config.default_rtt)))),
+            completion,
+            gauge: None,
+        }
+    }
+
+    /// Records the endpoint's load in a gauge each time it is measured.
+    pub fn with_gauge(mut self, gauges: &LoadGauges, addr: SocketAddr) -> Self {
+        self.gauge = Some(LoadGauge::new(gauges, addr));
+        self
+    }
+
+    fn handle(&self) -> Handle {
+        Handle {
+            sent_at: Instant::now(),
+            decay_ns: self.decay_ns,
+            rtt_estimate: self.rtt_estimate.clone(),
+        }
+    }
+}
+
+impl<S, C, Req> Service<Req> for PeakEwma<S, C>
+where
+    S: Service<Req>,
+    C: TrackCompletion<Handle, S::Response> + Clone,
+{
+    type Response = C::Output;
+    type Error = S::Error;
+    type Future = TrackCompletionFuture<S::Future, C, Handle>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        TrackCompletionFuture::new(
+            self.completion.clone(),
+            self.handle(),
+            self.service.call(req),
+        )
+    }
+}
+
+impl<S, C> Load for PeakEwma<S, C> {
+    type Metric = Cost;
+
+    fn load(&self) -> Self::Metric {
+        // Each pending request holds a handle that references the estimate.
+        let pending = Arc::strong_count(&self.rtt_estimate) - 1;
+        let estimate = self.rtt_estimate.lock().decay(self.decay_ns);
+        let cost = estimate * (pending + 1) as f64;
+        tracing::trace!(pending, estimate, cost, "Load");
+        if let Some(gauge) = self.gauge.as_ref() {
+            gauge.gauge.set(cost / 1_000_000_000.0);
+        }
+        Cost(cost)
+    }
+}
+
+// === impl Handle ===
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        let recv_at = Instant::now();
+        self.rtt_estimate
+            .lock()
+            .update(self.sent_at, recv_at, self.decay_ns);
+    }
+}
+
+// === impl RttEstimate ===
+
+impl RttEstimate {
+    fn new(rtt_ns: f64) -> Self {
+        debug_assert!(0.0 < rtt_ns, "rtt must be positive");
+        Self {
+            rtt_ns,
+            update_at: Instant::now(),
+        }
+    }
+
+    /// Decays the estimate towards zero, as if a request had completed
+    /// instantaneously, and returns the updated estimate.
+    fn decay(&mut self, decay_ns: f64) -> f64 {
+        let now = Instant::now();
+        self.update(now, now, decay_ns)
+    }
+
+    /// Updates the estimate with a request's latency and returns the updated
+    /// estimate.
+    fn update(&mut self, sent_at: Instant, recv_at: Instant, decay_ns: f64) -> f64 {
+        let rtt = nanos(recv_at.saturating_duration_since(sent_at));
+        let now = Instant::now();
+        self.rtt_ns = if self.rtt_ns < rtt {
+            // Latency increases are applied immediately.
+            rtt
+        } else {
+            // Otherwise, the sample is weighted by the time elapsed since the
+            // estimate was last updated, so that the previous estimate decays
+            // exponentially over the decay time constant.
+            let elapsed = nanos(now.saturating_duration_since(self.update_at));
+            let decay = (-elapsed / decay_ns).exp();
+            let recency = 1.0 - decay;
+            (self.rtt_ns * decay) + (rtt * recency)
+        };
+        self.update_at = now;
+        self.rtt_ns
+    }
+}
+
+// === impl LoadGauges ===
+
+impl LoadGauges {
+    pub(crate) fn new<L>(
+        family: prom::Family<EndpointLoadLabels<L>, prom::Gauge<f64, AtomicU64>>,
+        labels: L,
+    ) -> Self
+    where
+        L: prom::encoding::EncodeLabelSet + std::fmt::Debug + std::hash::Hash,
+        L: Eq + Clone + Send + Sync + 'static,
+    {
+        Self(Arc::new(LabeledLoadGauges {
+            family,
+            labels,
+            endpoints: Default::default(),
+        }))
+    }
+}
+
+impl std::fmt::Debug for LoadGauges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("LoadGauges").finish_non_exhaustive()
+    }
+}
+
+// === impl LoadGauge ===
+
+impl LoadGauge {
+    fn new(LoadGauges(family): &LoadGauges, addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            gauge: family.get_or_create(addr),
+            family: family.clone(),
+        }
+    }
+}
+
+impl Drop for LoadGauge {
+    fn drop(&mut self) {
+        self.family.remove(self.addr);
+    }
+}
+
+impl std::fmt::Debug for LoadGauge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadGauge")
+            .field("addr", &self.addr)
+            .field("gauge", &self.gauge)
+            .finish_non_exhaustive()
+    }
+}
+
+// === impl LabeledLoadGauges ===
+
+impl<L> LoadGaugeFamily for LabeledLoadGauges<L>
+where
+    L: prom::encoding::EncodeLabelSet + std::fmt::Debug + std::hash::Hash,
+    L: Eq + Clone + Send + Sync + 'static,
+{
+    fn get_or_create(&self, addr: SocketAddr) -> prom::Gauge<f64, AtomicU64> {
+        let mut endpoints = self.endpoints.lock();
+        *endpoints.entry(addr).or_default() += 1;
+        self.family
+            .get_or_create(&EndpointLoadLabels {
+                addr,
+                labels: self.labels.clone(),
+            })
+            .clone()
+    }
+
+    fn remove(&self, addr: SocketAddr) {
+        let mut endpoints = self.endpoints.lock();
+        let Some(count) = endpoints.get_mut(&addr) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            endpoints.remove(&addr);
+            self.family.remove(&EndpointLoadLabels {
+                addr,
+                labels: self.labels.clone(),
+            });
+        }
+    }
+}
+
+// === impl EndpointLoadLabels ===
+
+impl<L: prom::encoding::EncodeLabelSet> prom::encoding::EncodeLabelSet for EndpointLoadLabels<L> {
+    fn encode(&self, mut enc: prom::encoding::LabelSetEncoder<'_>) -> std::fmt::Result {
+        use prom::encoding::EncodeLabel;
+        ("endpoint_addr", self.addr.to_string()).encode(enc.encode_label())?;
+        self.labels.encode(enc)
+    }
+}
+
+// Converts durations to nanos in f64.
+//
+// Due to a lossy transformation, the maximum value that can be represented is
+// ~585 years, which, I hope, is more than enough to represent request
+// latencies.
+fn nanos(d: Duration) -> f64 {
+    const NANOS_PER_SEC: u64 = 1_000_000_000;
+    let n = f64::from(d.subsec_nanos());
+    let s = d.as_secs().saturating_mul(NANOS_PER_SEC) as f64;
+    n + s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::load::CompleteOnResponse;
+
+    const TICK: Duration = Duration::from_millis(10);
+    const FAST: Duration = Duration::from_millis(15);
+    const SLOW: Duration = Duration::from_millis(255);
+
+    fn config(decay: Duration) -> EwmaConfig {
+        EwmaConfig {
+            default_rtt: Duration::from_millis(30),
+            decay,
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn default_rtt_decays() {
+        let svc = PeakEwma::new((), config(Duration::from_secs(10)), CompleteOnResponse);
+        assert_eq!(svc.load(), Cost(30_000_000.0));
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        let Cost(cost) = svc.load();
+        assert!((cost - 30_000_000.0 / std::f64::consts::E).abs() < 1.0);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn peak_sensitive() {
+        let svc = PeakEwma::new((), config(Duration::from_secs(10)), CompleteOnResponse);
+
+        let handle = svc.handle();
+        tokio::time::advance(Duration::from_millis(100)).await;
+        // A pending request doubles the endpoint's load.
+        assert!(svc.load() > Cost(2.0 * 29_000_000.0));
+        drop(handle);
+        // A slower response replaces the estimate immediately.
+        assert_eq!(svc.load(), Cost(100_000_000.0));
+
+        // Faster responses are averaged into the estimate.
+        let handle = svc.handle();
+        tokio::time::advance(Duration::from_millis(10)).await;
+        drop(handle);
+        let Cost(cost) = svc.load();
+        assert!(90_000_000.0 < cost && cost < 100_000_000.0, "{cost}");
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn records_load() {
+        let family = prom::Family::default();
+        let gauges = LoadGauges::new(
+            family.clone(),
+            vec![("backend".to_string(), "web".to_string())],
+        );
+        let addr = SocketAddr::from(([192, 0, 2, 1], 8080));
+        let labels = EndpointLoadLabels {
+            addr,
+            labels: vec![("backend".to_string(), "web".to_string())],
+        };
+
+        let svc = PeakEwma::new((), config(Duration::from_secs(10)), CompleteOnResponse)
+            .with_gauge(&gauges, addr);
+        svc.load();
+        assert_eq!(family.get(&labels).expect("gauge").get(), 0.030);
+
+        // The gauge is not removed when a replaced endpoint is dropped.
+        let replacement = PeakEwma::new((), config(Duration::from_secs(10)), CompleteOnResponse)
+            .with_gauge(&gauges, addr);
+        drop(svc);
+        assert!(family.get(&labels).is_some());
+
+        drop(replacement);
+        assert!(family.get(&labels).is_none());
+    }
+
+    /// Simulates a balancer that sends a request every tick to the least
+    /// loaded of two endpoints. The first endpoint is slow until `recover`.
+    ///
+    /// Returns the number of requests sent to the first endpoint before and
+    /// after it recovers.
+    async fn simulate(decay: Duration, recover: Duration, end: Duration) -> (usize, usize) {
+        let a = PeakEwma::new((), config(decay), CompleteOnResponse);
+        let b = PeakEwma::new((), config(decay), CompleteOnResponse);
+
+        let start = Instant::now();
+        let (mut before, mut after) = (0, 0);
+        loop {
+            let elapsed = Instant::now().saturating_duration_since(start);
+            if end <= elapsed {
+                break;
+            }
+            let recovered = recover <= elapsed;
+            let (handle, latency) = if a.load() <= b.load() {
+                if recovered {
+                    after += 1;
+                } else {
+                    before += 1;
+                }
+                (a.handle(), if recovered { FAST } else { SLOW })
+            } else {
+                (b.handle(), FAST)
+            };
+            tokio::spawn(async move {
+                tokio::time::sleep(latency).await;
+                drop(handle);
+            });
+            tokio::time::sleep(TICK).await;
+        }
+        (before, after)
+    }
+
+    /// Demonstrates how the decay time constant determines how quickly an
+    /// endpoint whose latency has recovered is sent traffic again.
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn decay_after_latency_step() {
+        const RECOVER: Duration = Duration::from_secs(10);
+        const END: Duration = Duration::from_secs(20);
+        // Each simulation sends 1000 requests before and after the step.
+        const REQUESTS: usize = 1000;
+
+        // Regardless of the decay, the slow endpoint is avoided as soon as it
+        // is observed to be slow.
+        let (short_before, short_after) = simulate(Duration::from_secs(1), RECOVER, END).await;
+        let (long_before, long_after) = simulate(Duration::from_secs(30), RECOVER, END).await;
+        assert!(short_before < REQUESTS / 20, "{short_before}");
+        assert!(long_before < REQUESTS / 20, "{long_before}");
+
+        // With a short decay, the slow endpoint's estimate falls below the
+        // fast endpoint's within a few seconds of idling, and requests are
+        // balanced over both endpoints once it has recovered.
+        assert!(short_after > REQUESTS / 4, "{short_after}");
+
+        // With a long decay, the slow endpoint's estimate takes over a minute
+        // to fall below the fast endpoint's, so it receives no traffic for the
+        // remainder of the simulation.
+        assert_eq!(long_after, 0);
+    }
+}
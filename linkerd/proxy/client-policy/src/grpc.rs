@@ -15,6 +15,20 @@ pub struct RouteParams {
     pub retry: Option<Retry>,
     pub allow_l5d_request_headers: bool,
     pub export_hostname_labels: bool,
+
+    /// Overrides the `grpc-status` codes that are classified as failures, both
+    /// in response metrics and for failure accrual. When unset, the default
+    /// set of failure codes is used.
+    ///
+    /// This does not affect retries, which are configured independently.
+    pub failure_codes: Option<Codes>,
+
+    /// Guards a weighted rollout from a primary backend to a canary backend.
+    pub rollout_guard: Option<crate::http::RolloutGuard>,
+
+    /// Injects delays and aborts into the route's requests, if set. Aborted
+    /// requests fail with the configured `grpc-status`.
+    pub fault: Option<crate::http::Fault<tonic::Code>>,
 }
 
 // TODO HTTP2 settings
@@ -31,6 +45,7 @@ pub struct Grpc {
 pub enum Filter {
     InjectFailure(filter::InjectFailure),
     RequestHeaders(http::filter::ModifyHeader),
+    DirectResponse(filter::DirectResponse),
     InternalError(&'static str),
 }
 
@@ -105,7 +120,7 @@ pub mod proto {
         proto::{
             BackendSet, InvalidBackend, InvalidDistribution, InvalidFailureAccrual, InvalidMeta,
         },
-        ClientPolicyOverrides, Meta, RouteBackend, RouteDistribution,
+        ClientPolicyOverrides, Meta, RouteBackend, RouteDistribution, RouteOverride,
     };
     use linkerd2_proxy_api::outbound::{self, grpc_route};
     use linkerd_http_route::{
@@ -186,16 +201,17 @@ pub mod proto {
 
     impl Grpc {
         pub fn try_from(
-            overrides: ClientPolicyOverrides,
+            overrides: &ClientPolicyOverrides,
             proto: outbound::proxy_protocol::Grpc,
         ) -> Result<Self, InvalidGrpcRoute> {
-            let routes = proto
+            let mut routes = proto
                 .routes
                 .into_iter()
                 .map(|p| try_route(overrides, p))
-                .collect::<Result<Arc<[_]>, _>>()?;
+                .collect::<Result<Vec<_>, _>>()?;
+            crate::order_routes(&mut routes);
             Ok(Self {
-                routes,
+                routes: routes.into(),
                 failure_accrual: proto.failure_accrual.try_into()?,
             })
         }
@@ -210,7 +226,7 @@ pub mod proto {
     }
 
     fn try_route(
-        overrides: ClientPolicyOverrides,
+        overrides: &ClientPolicyOverrides,
         proto: outbound::GrpcRoute,
     ) -> Result<Route, InvalidGrpcRoute> {
         let outbound::GrpcRoute {
@@ -238,7 +254,7 @@ pub mod proto {
 
     fn try_rule(
         meta: &Arc<Meta>,
-        overrides: ClientPolicyOverrides,
+        overrides: &ClientPolicyOverrides,
         proto: outbound::grpc_route::Rule,
     ) -> Result<Rule, InvalidGrpcRoute> {
         #[allow(deprecated)]
@@ -257,17 +273,27 @@ pub mod proto {
             .map(r#match::MatchRoute::try_from)
             .collect::<Result<Vec<_>, InvalidRouteMatch>>()?;
 
-        let filters = filters
+        let route = overrides.routes.get(meta);
+
+        let mut filters = filters
             .into_iter()
             .map(Filter::try_from)
-            .collect::<Result<Arc<[_]>, _>>()?;
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(direct) = route.grpc_direct_response.clone() {
+            filters.push(Filter::DirectResponse(direct));
+        }
 
         let distribution = backends
             .ok_or(InvalidGrpcRoute::Missing("distribution"))?
             .try_into()?;
 
-        let mut params =
-            RouteParams::try_from_proto(timeouts, retry, allow_l5d_request_headers, overrides)?;
+        let mut params = RouteParams::try_from_proto(
+            timeouts,
+            retry,
+            allow_l5d_request_headers,
+            overrides,
+            route,
+        )?;
         let legacy = request_timeout.map(TryInto::try_into).transpose()?;
         params.timeouts.request = params.timeouts.request.or(legacy);
 
@@ -275,7 +301,7 @@ pub mod proto {
             matches,
             policy: Policy {
                 meta: meta.clone(),
-                filters,
+                filters: filters.into(),
                 distribution,
                 params,
             },
@@ -287,16 +313,17 @@ pub mod proto {
             timeouts: Option<linkerd2_proxy_api::http_route::Timeouts>,
             retry: Option<grpc_route::Retry>,
             allow_l5d_request_headers: bool,
-            overrides: ClientPolicyOverrides,
+            overrides: &ClientPolicyOverrides,
+            route: &RouteOverride,
         ) -> Result<Self, InvalidGrpcRoute> {
             Ok(Self {
                 retry: retry.map(Retry::try_from).transpose()?,
-                timeouts: timeouts
-                    .map(crate::http::Timeouts::try_from)
-                    .transpose()?
-                    .unwrap_or_default(),
+                timeouts: crate::http::Timeouts::try_from_proto(timeouts, route)?,
                 allow_l5d_request_headers,
                 export_hostname_labels: overrides.export_hostname_labels,
+                failure_codes: route.failure_codes.clone(),
+                rollout_guard: route.rollout_guard.clone(),
+                fault: route.grpc_fault.clone(),
             })
         }
     }
@@ -15,6 +15,29 @@ pub struct RouteParams {
     pub retry: Option<Retry>,
     pub allow_l5d_request_headers: bool,
     pub export_hostname_labels: bool,
+
+    /// Labels route metrics with requests' methods. Methods other than the
+    /// standard methods are labeled as `other`.
+    pub export_method_labels: bool,
+
+    /// Overrides the response statuses that are classified as failures, both
+    /// in response metrics and for failure accrual. When unset, 5XX responses
+    /// are failures.
+    ///
+    /// This does not affect retries, which are configured independently.
+    pub failure_statuses: Option<StatusRanges>,
+
+    /// Guards a weighted rollout from a primary backend to a canary backend.
+    pub rollout_guard: Option<RolloutGuard>,
+
+    /// Caches responses to `GET` and `HEAD` requests, if set.
+    pub cache: Option<Cache>,
+
+    /// Coalesces identical in-flight `GET` and `HEAD` requests, if set.
+    pub coalesce: Option<Coalesce>,
+
+    /// Injects delays and aborts into the route's requests, if set.
+    pub fault: Option<Fault>,
 }
 
 // TODO: keepalive settings, etc.
@@ -41,6 +64,10 @@ pub enum Filter {
     Redirect(filter::RedirectRequest),
     RequestHeaders(filter::ModifyHeader),
     ResponseHeaders(filter::ModifyHeader),
+    DecompressRequest(filter::DecompressRequest),
+    AssertWorkloadIdentity(filter::AssertWorkloadIdentity),
+    DirectResponse(filter::DirectResponse),
+    RewriteAuthority(filter::RewriteAuthority),
     InternalError(&'static str),
 }
 
@@ -61,6 +88,114 @@ pub struct Timeouts {
     pub response: Option<time::Duration>,
     pub idle: Option<time::Duration>,
     pub request: Option<time::Duration>,
+
+    /// Bounds the time until response headers are received, e.g. so that
+    /// streaming responses are established promptly.
+    pub response_headers: Option<time::Duration>,
+
+    /// Bounds the time between frames of the response body, once response
+    /// headers have been received.
+    pub response_chunk_idle: Option<time::Duration>,
+}
+
+/// Monitors a route whose distribution splits traffic between exactly two
+/// weighted backends: the first is the primary and the second is the canary.
+///
+/// When the canary's failure rate exceeds the primary's by more than
+/// `max_failure_rate_increase` over a `window`, all of the route's traffic is
+/// sent to the primary for the `cooldown` duration.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RolloutGuard {
+    /// The duration over which backend responses are observed.
+    pub window: time::Duration,
+
+    /// The number of canary responses that must be observed within the window
+    /// before the guard may trip.
+    pub min_requests: u32,
+
+    /// The number of percentage points by which the canary's failure rate may
+    /// exceed the primary's failure rate.
+    pub max_failure_rate_increase: u8,
+
+    /// The duration for which the canary is removed from the distribution
+    /// once the guard trips.
+    pub cooldown: time::Duration,
+}
+
+/// Enables response caching for a route.
+///
+/// Responses are cached according to their `cache-control` and `vary`
+/// headers, within a memory budget shared by all routes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Cache {
+    /// Whether requests with an `authorization` header may be served from,
+    /// and stored in, the cache.
+    pub allow_authorization: bool,
+
+    /// Bounds how long responses are stored, regardless of their freshness
+    /// lifetime, if set.
+    pub max_age: Option<time::Duration>,
+}
+
+/// Enables request coalescing for a route.
+///
+/// `GET` and `HEAD` requests without a body or an `authorization` header are
+/// identical when their method, authority, path, and configured headers match.
+/// While such a request is in flight, identical requests wait for its response
+/// rather than being forwarded.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Coalesce {
+    /// Headers whose values must also match for requests to be coalesced.
+    pub headers: Vec<::http::HeaderName>,
+
+    /// The maximum number of requests that may wait for each in-flight
+    /// request. Further identical requests are forwarded.
+    pub max_waiters: usize,
+
+    /// How long requests may wait for an in-flight request, measured from
+    /// when the in-flight request was sent. Requests that are still waiting
+    /// are then forwarded.
+    pub timeout: time::Duration,
+}
+
+/// Injects faults into a route's requests, for chaos testing.
+///
+/// Faults are applied once a request matches the route, before it may be
+/// retried. `A` describes the response with which aborted requests fail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Fault<A = ::http::StatusCode> {
+    /// Delays a percentage of requests before they are forwarded.
+    pub delay: Option<FaultDelay>,
+
+    /// Fails a percentage of requests without forwarding them.
+    pub abort: Option<FaultAbort<A>>,
+
+    /// Whether aborted requests may be retried by the route's retry policy.
+    /// When unset, aborted requests are never retried.
+    pub retry_aborts: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FaultDelay {
+    /// The proportion of requests that are delayed.
+    pub distribution: filter::Distribution,
+    pub duration: FaultDuration,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FaultDuration {
+    Fixed(time::Duration),
+
+    /// Each delayed request waits for a duration chosen uniformly from the
+    /// range.
+    Random(RangeInclusive<time::Duration>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FaultAbort<A> {
+    /// The proportion of requests that are aborted.
+    pub distribution: filter::Distribution,
+    pub response: A,
 }
 
 pub fn default(distribution: crate::RouteDistribution<Filter>) -> Route {
@@ -125,7 +260,7 @@ pub mod proto {
         proto::{
             BackendSet, InvalidBackend, InvalidDistribution, InvalidFailureAccrual, InvalidMeta,
         },
-        ClientPolicyOverrides, Meta, RouteBackend, RouteDistribution,
+        ClientPolicyOverrides, Meta, RouteBackend, RouteDistribution, RouteOverride,
     };
     use linkerd2_proxy_api::outbound::{self, http_route};
     use linkerd_http_route::http::{
@@ -219,16 +354,17 @@ pub mod proto {
 
     impl Http1 {
         pub fn try_from(
-            overrides: ClientPolicyOverrides,
+            overrides: &ClientPolicyOverrides,
             proto: outbound::proxy_protocol::Http1,
         ) -> Result<Self, InvalidHttpRoute> {
-            let routes = proto
+            let mut routes = proto
                 .routes
                 .into_iter()
                 .map(|p| try_route(overrides, p))
-                .collect::<Result<Arc<[_]>, _>>()?;
+                .collect::<Result<Vec<_>, _>>()?;
+            crate::order_routes(&mut routes);
             Ok(Self {
-                routes,
+                routes: routes.into(),
                 failure_accrual: proto.failure_accrual.try_into()?,
             })
         }
@@ -236,23 +372,24 @@ pub mod proto {
 
     impl Http2 {
         pub fn try_from(
-            overrides: ClientPolicyOverrides,
+            overrides: &ClientPolicyOverrides,
             proto: outbound::proxy_protocol::Http2,
         ) -> Result<Self, InvalidHttpRoute> {
-            let routes = proto
+            let mut routes = proto
                 .routes
                 .into_iter()
                 .map(|p| try_route(overrides, p))
-                .collect::<Result<Arc<[_]>, _>>()?;
+                .collect::<Result<Vec<_>, _>>()?;
+            crate::order_routes(&mut routes);
             Ok(Self {
-                routes,
+                routes: routes.into(),
                 failure_accrual: proto.failure_accrual.try_into()?,
             })
         }
     }
 
     fn try_route(
-        overrides: ClientPolicyOverrides,
+        overrides: &ClientPolicyOverrides,
         proto: outbound::HttpRoute,
     ) -> Result<Route, InvalidHttpRoute> {
         let outbound::HttpRoute {
@@ -280,7 +417,7 @@ pub mod proto {
 
     fn try_rule(
         meta: &Arc<Meta>,
-        overrides: ClientPolicyOverrides,
+        overrides: &ClientPolicyOverrides,
         proto: outbound::http_route::Rule,
     ) -> Result<Rule, InvalidHttpRoute> {
         #[allow(deprecated)]
@@ -294,22 +431,49 @@ pub mod proto {
             request_timeout,
         } = proto;
 
-        let matches = matches
+        let route = overrides.routes.get(meta);
+
+        let mut matches = matches
             .into_iter()
             .map(r#match::MatchRequest::try_from)
             .collect::<Result<Vec<_>, InvalidRouteMatch>>()?;
-
-        let filters = filters
+        if !route.cookies.is_empty() {
+            // A rule without matches matches all requests.
+            if matches.is_empty() {
+                matches.push(r#match::MatchRequest::default());
+            }
+            for m in &mut matches {
+                m.cookies.extend(route.cookies.iter().cloned());
+            }
+        }
+        let mut filters = filters
             .into_iter()
             .map(Filter::try_from)
-            .collect::<Result<Arc<[_]>, _>>()?;
+            .collect::<Result<Vec<_>, _>>()?;
+        if route.assert_workload_identity {
+            filters.push(Filter::AssertWorkloadIdentity(Default::default()));
+        }
+        if let Some(direct) = route.http_direct_response.clone() {
+            filters.push(Filter::DirectResponse(direct));
+        }
+        if let Some(rewrite) = route.rewrite_authority.clone() {
+            filters.push(Filter::RewriteAuthority(rewrite));
+        }
+        if let Some(decompress) = route.decompress_request.clone() {
+            filters.push(Filter::DecompressRequest(decompress));
+        }
 
         let distribution = backends
             .ok_or(InvalidHttpRoute::Missing("distribution"))?
             .try_into()?;
 
-        let mut params =
-            RouteParams::try_from_proto(timeouts, retry, allow_l5d_request_headers, overrides)?;
+        let mut params = RouteParams::try_from_proto(
+            timeouts,
+            retry,
+            allow_l5d_request_headers,
+            overrides,
+            route,
+        )?;
         let legacy = request_timeout.map(TryInto::try_into).transpose()?;
         params.timeouts.request = params.timeouts.request.or(legacy);
 
@@ -317,7 +481,7 @@ pub mod proto {
             matches,
             policy: Policy {
                 meta: meta.clone(),
-                filters,
+                filters: filters.into(),
                 distribution,
                 params,
             },
@@ -329,16 +493,34 @@ pub mod proto {
             timeouts: Option<linkerd2_proxy_api::http_route::Timeouts>,
             retry: Option<http_route::Retry>,
             allow_l5d_request_headers: bool,
-            overrides: ClientPolicyOverrides,
+            overrides: &ClientPolicyOverrides,
+            route: &RouteOverride,
         ) -> Result<Self, InvalidHttpRoute> {
             Ok(Self {
                 retry: retry.map(Retry::try_from).transpose()?,
-                timeouts: timeouts
-                    .map(Timeouts::try_from)
-                    .transpose()?
-                    .unwrap_or_default(),
+                timeouts: Timeouts::try_from_proto(timeouts, route)?,
                 allow_l5d_request_headers,
                 export_hostname_labels: overrides.export_hostname_labels,
+                export_method_labels: overrides.export_method_labels,
+                failure_statuses: route.failure_statuses.clone(),
+                rollout_guard: route.rollout_guard.clone(),
+                cache: route.cache.clone(),
+                coalesce: route.coalesce.clone(),
+                fault: route.http_fault.clone(),
+            })
+        }
+    }
+
+    impl Timeouts {
+        pub(crate) fn try_from_proto(
+            timeouts: Option<linkerd2_proxy_api::http_route::Timeouts>,
+            route: &RouteOverride,
+        ) -> Result<Self, InvalidTimeouts> {
+            let timeouts = timeouts.map(Self::try_from).transpose()?;
+            Ok(Self {
+                response_headers: route.response_headers_timeout,
+                response_chunk_idle: route.response_chunk_idle_timeout,
+                ..timeouts.unwrap_or_default()
             })
         }
     }
@@ -364,6 +546,10 @@ pub mod proto {
                     .map(time::Duration::try_from)
                     .transpose()
                     .map_err(InvalidTimeouts::Request)?,
+                // The policy API does not yet configure streaming timeouts, so
+                // they are set from route overrides.
+                response_headers: None,
+                response_chunk_idle: None,
             })
         }
     }
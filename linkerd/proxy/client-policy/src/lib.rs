@@ -2,13 +2,19 @@
 #![forbid(unsafe_code)]
 
 use once_cell::sync::Lazy;
-use std::{borrow::Cow, fmt, hash::Hash, net::SocketAddr, num::NonZeroU16, sync::Arc, time};
+use std::{
+    borrow::Cow, collections::HashSet, fmt, hash::Hash, net::SocketAddr, num::NonZeroU16,
+    sync::Arc, time,
+};
 
 pub mod grpc;
 pub mod http;
 pub mod opaq;
 pub mod tls;
 
+#[cfg(test)]
+mod tests;
+
 pub use linkerd_http_route as route;
 pub use linkerd_proxy_api_resolve::Metadata as EndpointMetadata;
 
@@ -19,9 +25,100 @@ pub struct ClientPolicy {
     pub backends: Arc<[Backend]>,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ClientPolicyOverrides {
     pub export_hostname_labels: bool,
+
+    /// Labels HTTP route metrics with requests' methods.
+    pub export_method_labels: bool,
+
+    /// Configures routes, by resource, with settings that the policy API does
+    /// not provide.
+    pub routes: RouteOverrides,
+
+    /// Configures parents, by resource, with settings that the policy API does
+    /// not provide.
+    pub parents: ParentOverrides,
+}
+
+/// Identifies a discovered resource by its group, kind, namespace, and name, so
+/// that overrides apply only to that resource. The proxy's default resources
+/// have the `default` kind and no group or namespace.
+///
+/// Groups and kinds are compared case-insensitively.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceKey {
+    pub group: String,
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+}
+
+/// Parent settings, keyed by parent resource.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParentOverrides(Arc<ahash::AHashMap<ResourceKey, ParentOverride>>);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParentOverride {
+    /// Restricts the SNIs that clients may send to TLS parents.
+    pub sni: Option<tls::SniEnforcement>,
+}
+
+/// Route settings, keyed by route resource, that are applied to every rule of
+/// the discovered route.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteOverrides(Arc<ahash::AHashMap<ResourceKey, RouteOverride>>);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteOverride {
+    /// Adds an `AssertWorkloadIdentity` filter to HTTP routes.
+    pub assert_workload_identity: bool,
+
+    /// Overrides the response statuses that HTTP routes classify as failures.
+    pub failure_statuses: Option<http::StatusRanges>,
+
+    /// Overrides the `grpc-status` codes that gRPC routes classify as
+    /// failures.
+    pub failure_codes: Option<grpc::Codes>,
+
+    /// Guards weighted rollouts on HTTP and gRPC routes.
+    pub rollout_guard: Option<http::RolloutGuard>,
+
+    /// Bounds the time until response headers are received on HTTP and gRPC
+    /// routes.
+    pub response_headers_timeout: Option<time::Duration>,
+
+    /// Bounds the time between frames of response bodies on HTTP and gRPC
+    /// routes.
+    pub response_chunk_idle_timeout: Option<time::Duration>,
+
+    /// Cookies that requests must have, in addition to each of a route's
+    /// matches, to match HTTP routes.
+    pub cookies: Vec<http::r#match::MatchCookie>,
+
+    /// Caches responses on HTTP routes.
+    pub cache: Option<http::Cache>,
+
+    /// Coalesces identical in-flight requests on HTTP routes.
+    pub coalesce: Option<http::Coalesce>,
+
+    /// Delays or aborts requests on HTTP routes.
+    pub http_fault: Option<http::Fault>,
+
+    /// Delays or aborts requests on gRPC routes.
+    pub grpc_fault: Option<http::Fault<tonic::Code>>,
+
+    /// Responds to requests on HTTP routes without forwarding them.
+    pub http_direct_response: Option<http::filter::DirectResponse>,
+
+    /// Responds to requests on gRPC routes without forwarding them.
+    pub grpc_direct_response: Option<grpc::filter::DirectResponse>,
+
+    /// Rewrites the authority of requests on HTTP routes.
+    pub rewrite_authority: Option<http::filter::RewriteAuthority>,
+
+    /// Decompresses gzip-encoded request bodies on HTTP routes.
+    pub decompress_request: Option<http::filter::DecompressRequest>,
 }
 
 // TODO additional server configs (e.g. concurrency limits, window sizes, etc)
@@ -222,6 +319,222 @@ impl ClientPolicy {
             backends: NO_BACKENDS.clone(),
         }
     }
+
+    /// Shares the routes and backends of `prev` that are unchanged in this
+    /// policy.
+    ///
+    /// Each policy update is decoded in full, even when few of its routes have
+    /// changed. Reusing the previous policy's allocations makes it cheap to
+    /// compare unchanged routes, since `Arc`s that point to the same allocation
+    /// are equal without comparing their contents, and lets route stacks
+    /// recognize an unchanged route by its metadata's allocation.
+    pub fn reuse_unchanged(&mut self, prev: &Self) {
+        reuse_unchanged(&mut self.backends, &prev.backends);
+        match (&mut self.protocol, &prev.protocol) {
+            (
+                Protocol::Detect { http1, http2, .. },
+                Protocol::Detect {
+                    http1: prev_http1,
+                    http2: prev_http2,
+                    ..
+                },
+            ) => {
+                reuse_unchanged(&mut http1.routes, &prev_http1.routes);
+                reuse_unchanged(&mut http2.routes, &prev_http2.routes);
+            }
+            (Protocol::Http1(http1), Protocol::Http1(prev)) => {
+                reuse_unchanged(&mut http1.routes, &prev.routes);
+            }
+            (Protocol::Http2(http2), Protocol::Http2(prev)) => {
+                reuse_unchanged(&mut http2.routes, &prev.routes);
+            }
+            (Protocol::Grpc(grpc), Protocol::Grpc(prev)) => {
+                reuse_unchanged(&mut grpc.routes, &prev.routes);
+            }
+            (Protocol::Tls(tls), Protocol::Tls(prev)) => {
+                reuse_unchanged(&mut tls.routes, &prev.routes);
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces each of the policy's backends, including those referenced by
+    /// routes, with the result of `f`.
+    pub fn map_backends(&mut self, f: impl Fn(&Backend) -> Backend) {
+        fn map_distribution<T>(
+            distribution: &mut RouteDistribution<T>,
+            f: &impl Fn(&Backend) -> Backend,
+        ) {
+            match distribution {
+                RouteDistribution::Empty => {}
+                RouteDistribution::FirstAvailable(backends) => {
+                    *backends = backends
+                        .iter()
+                        .map(|rb| RouteBackend {
+                            filters: rb.filters.clone(),
+                            backend: f(&rb.backend),
+                        })
+                        .collect();
+                }
+                RouteDistribution::RandomAvailable(backends) => {
+                    *backends = backends
+                        .iter()
+                        .map(|(rb, weight)| {
+                            let rb = RouteBackend {
+                                filters: rb.filters.clone(),
+                                backend: f(&rb.backend),
+                            };
+                            (rb, *weight)
+                        })
+                        .collect();
+                }
+            }
+        }
+
+        fn map_routes<M: Clone, F: Clone, P: Clone>(
+            routes: &mut Arc<[route::Route<M, RoutePolicy<F, P>>]>,
+            f: &impl Fn(&Backend) -> Backend,
+        ) {
+            *routes = routes
+                .iter()
+                .map(|route| {
+                    let mut route = route.clone();
+                    for rule in &mut route.rules {
+                        map_distribution(&mut rule.policy.distribution, f);
+                    }
+                    route
+                })
+                .collect();
+        }
+
+        fn map_opaque(opaque: &mut opaq::Opaque, f: &impl Fn(&Backend) -> Backend) {
+            if let Some(route) = opaque.routes.as_mut() {
+                map_distribution(&mut route.policy.distribution, f);
+            }
+        }
+
+        self.backends = self.backends.iter().map(&f).collect();
+        match &mut self.protocol {
+            Protocol::Detect {
+                http1,
+                http2,
+                opaque,
+                ..
+            } => {
+                map_routes(&mut http1.routes, &f);
+                map_routes(&mut http2.routes, &f);
+                map_opaque(opaque, &f);
+            }
+            Protocol::Http1(http1) => map_routes(&mut http1.routes, &f),
+            Protocol::Http2(http2) => map_routes(&mut http2.routes, &f),
+            Protocol::Grpc(grpc) => map_routes(&mut grpc.routes, &f),
+            Protocol::Opaque(opaque) => map_opaque(opaque, &f),
+            Protocol::Tls(tls) => {
+                tls.routes = tls
+                    .routes
+                    .iter()
+                    .map(|route| {
+                        let mut route = route.clone();
+                        map_distribution(&mut route.policy.distribution, &f);
+                        route
+                    })
+                    .collect();
+            }
+        }
+    }
+}
+
+/// Replaces `items` with `prev` if they are equal. Otherwise, each item that is
+/// equal to an item in `prev` is replaced with a clone of that item, so that
+/// they share allocations.
+fn reuse_unchanged<T: Clone + Eq + Hash>(items: &mut Arc<[T]>, prev: &Arc<[T]>) {
+    if *items == *prev {
+        *items = prev.clone();
+        return;
+    }
+
+    let prev = prev.iter().collect::<HashSet<&T>>();
+    if !items.iter().any(|item| prev.contains(item)) {
+        return;
+    }
+    *items = items
+        .iter()
+        .map(|item| prev.get(item).copied().unwrap_or(item).clone())
+        .collect();
+}
+
+/// Orders routes so that ties between equally specific matches do not depend
+/// on the order in which routes are discovered.
+///
+/// Requests are routed by the most specific match and ties are resolved in
+/// favor of the first route. Per the Gateway API, ties are resolved in favor of
+/// the oldest route and then by the routes' `{namespace}/{name}`. Routes'
+/// creation timestamps are not discovered, so routes are ordered by namespace
+/// and name, and then by group and kind. The sort is stable, so the order of
+/// each route's rules is preserved.
+pub fn order_routes<M, F, P>(routes: &mut [route::Route<M, RoutePolicy<F, P>>]) {
+    fn key<M, F, P>(route: &route::Route<M, RoutePolicy<F, P>>) -> Option<[&str; 4]> {
+        let meta = &route.rules.first()?.policy.meta;
+        Some([meta.namespace(), meta.name(), meta.group(), meta.kind()])
+    }
+    routes.sort_by(|a, b| key(a).cmp(&key(b)));
+}
+
+// === impl ParentOverrides ===
+
+impl ParentOverrides {
+    pub fn new(parents: impl IntoIterator<Item = (ResourceKey, ParentOverride)>) -> Self {
+        Self(Arc::new(parents.into_iter().collect()))
+    }
+
+    /// Returns the settings configured for the parent, or the default
+    /// settings if none are configured.
+    pub fn get(&self, meta: &Meta) -> &ParentOverride {
+        static DEFAULT: Lazy<ParentOverride> = Lazy::new(ParentOverride::default);
+        self.0.get(&ResourceKey::of(meta)).unwrap_or(&DEFAULT)
+    }
+}
+
+// === impl RouteOverrides ===
+
+impl RouteOverrides {
+    pub fn new(routes: impl IntoIterator<Item = (ResourceKey, RouteOverride)>) -> Self {
+        Self(Arc::new(routes.into_iter().collect()))
+    }
+
+    /// Returns the settings configured for the route, or the default
+    /// settings if none are configured.
+    pub fn get(&self, meta: &Meta) -> &RouteOverride {
+        static DEFAULT: Lazy<RouteOverride> = Lazy::new(RouteOverride::default);
+        self.0.get(&ResourceKey::of(meta)).unwrap_or(&DEFAULT)
+    }
+}
+
+// === impl ResourceKey ===
+
+impl ResourceKey {
+    pub fn new(
+        group: impl Into<String>,
+        kind: impl Into<String>,
+        namespace: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        Self {
+            group: group.into().to_ascii_lowercase(),
+            kind: kind.into().to_ascii_lowercase(),
+            namespace: namespace.into(),
+            name: name.into(),
+        }
+    }
+
+    /// Identifies one of the proxy's default resources.
+    pub fn new_default(name: impl Into<String>) -> Self {
+        Self::new("", "default", "", name)
+    }
+
+    pub fn of(meta: &Meta) -> Self {
+        Self::new(meta.group(), meta.kind(), meta.namespace(), meta.name())
+    }
 }
 
 // === impl Meta ===
@@ -274,6 +587,24 @@ impl Meta {
     }
 }
 
+// === impl Backend ===
+
+impl Backend {
+    /// Returns the port to which the backend dispatches traffic, as declared
+    /// by its discovery path or its forwarding address, if any.
+    pub fn port(&self) -> Option<u16> {
+        match self.dispatcher {
+            BackendDispatcher::Forward(addr, _) => Some(addr.port()),
+            BackendDispatcher::BalanceP2c(_, EndpointDiscovery::DestinationGet { ref path }) => {
+                path.rsplit_once(':')
+                    .and_then(|(_, port)| port.parse().ok())
+                    .or_else(|| self.meta.port().map(u16::from))
+            }
+            BackendDispatcher::Fail { .. } => None,
+        }
+    }
+}
+
 impl std::cmp::PartialEq for Meta {
     fn eq(&self, other: &Self) -> bool {
         // Resources that look like Defaults are considered equal.
@@ -442,7 +773,7 @@ pub mod proto {
         ) -> Result<Self, InvalidPolicy> {
             use outbound::proxy_protocol;
 
-            let parent = policy
+            let parent: Meta = policy
                 .metadata
                 .ok_or(InvalidPolicy::MissingMeta)?
                 .try_into()?;
@@ -466,13 +797,13 @@ pub mod proto {
                         ))?
                         .try_into()?;
                     let http1 = http::Http1::try_from(
-                        overrides,
+                        &overrides,
                         http1.ok_or(InvalidPolicy::Protocol(
                             "Detect missing HTTP/1 configuration",
                         ))?,
                     )?;
                     let http2 = http::Http2::try_from(
-                        overrides,
+                        &overrides,
                         http2.ok_or(InvalidPolicy::Protocol(
                             "Detect missing HTTP/2 configuration",
                         ))?,
@@ -492,17 +823,18 @@ pub mod proto {
                 }
 
                 proxy_protocol::Kind::Http1(http) => {
-                    Protocol::Http1(http::Http1::try_from(overrides, http)?)
+                    Protocol::Http1(http::Http1::try_from(&overrides, http)?)
                 }
                 proxy_protocol::Kind::Http2(http) => {
-                    Protocol::Http2(http::Http2::try_from(overrides, http)?)
+                    Protocol::Http2(http::Http2::try_from(&overrides, http)?)
                 }
                 proxy_protocol::Kind::Opaque(opaque) => Protocol::Opaque(opaque.try_into()?),
                 proxy_protocol::Kind::Grpc(grpc) => {
-                    Protocol::Grpc(grpc::Grpc::try_from(overrides, grpc)?)
+                    Protocol::Grpc(grpc::Grpc::try_from(&overrides, grpc)?)
                 }
                 proxy_protocol::Kind::Tls(tls) => {
-                    Protocol::Tls(tls::Tls::try_from(overrides, tls)?)
+                    let sni = overrides.parents.get(&parent).sni.clone();
+                    Protocol::Tls(tls::Tls::try_from(&overrides, sni, tls)?)
                 }
             };
 
@@ -0,0 +1,286 @@
+use super::*;
+
+#[test]
+fn reuses_unchanged_routes() {
+    let prev = mk_policy(&["/a", "/b"]);
+    let mut policy = mk_policy(&["/a", "/b"]);
+    assert!(!Arc::ptr_eq(http1_routes(&policy), http1_routes(&prev)));
+
+    policy.reuse_unchanged(&prev);
+    assert_eq!(policy, prev);
+    assert!(Arc::ptr_eq(http1_routes(&policy), http1_routes(&prev)));
+    assert!(Arc::ptr_eq(&policy.backends, &prev.backends));
+}
+
+#[test]
+fn reuses_unchanged_routes_in_changed_policy() {
+    let prev = mk_policy(&["/a", "/b"]);
+    let mut policy = mk_policy(&["/a", "/c"]);
+
+    policy.reuse_unchanged(&prev);
+    assert_eq!(policy, mk_policy(&["/a", "/c"]));
+    let (routes, prev_routes) = (http1_routes(&policy), http1_routes(&prev));
+    assert!(!Arc::ptr_eq(routes, prev_routes));
+    assert!(Arc::ptr_eq(
+        &route_meta(&routes[0]),
+        &route_meta(&prev_routes[0])
+    ));
+    assert!(!Arc::ptr_eq(
+        &route_meta(&routes[1]),
+        &route_meta(&prev_routes[1])
+    ));
+}
+
+#[test]
+fn does_not_reuse_routes_across_protocols() {
+    let prev = mk_policy(&["/a"]);
+    let mut policy = ClientPolicy::invalid(time::Duration::from_secs(10));
+
+    policy.reuse_unchanged(&prev);
+    assert_eq!(policy, ClientPolicy::invalid(time::Duration::from_secs(10)));
+}
+
+#[test]
+fn orders_equally_specific_routes_by_name() {
+    let routes = [
+        mk_route("ns", "b", mk_match("/api", None)),
+        mk_route("ns", "a", mk_match("/api", None)),
+        mk_route("other", "a", mk_match("/api", None)),
+    ];
+    for routes in permutations(&routes) {
+        assert_eq!(find_route(routes, mk_req("/api/v1", None)), "ns/a");
+    }
+}
+
+#[test]
+fn orders_routes_by_specificity() {
+    let routes = [
+        mk_route("ns", "a", mk_match("/", None)),
+        mk_route("ns", "b", mk_match("/api", None)),
+        mk_route("ns", "c", mk_match("/api", Some("x-canary"))),
+    ];
+    for routes in permutations(&routes) {
+        assert_eq!(find_route(routes.clone(), mk_req("/", None)), "ns/a");
+        assert_eq!(find_route(routes.clone(), mk_req("/api", None)), "ns/b");
+        assert_eq!(find_route(routes, mk_req("/api", Some("x-canary"))), "ns/c");
+    }
+}
+
+#[test]
+fn preserves_rule_order() {
+    let mut route = mk_route("ns", "a", mk_match("/api", None));
+    let mut rule = route.rules[0].clone();
+    rule.policy.params.allow_l5d_request_headers = true;
+    route.rules.insert(0, rule);
+    let mut routes = vec![mk_route("ns", "b", mk_match("/api", None)), route];
+
+    order_routes(&mut routes);
+    let (_, policy) = http::find(&routes, &mk_req("/api", None)).expect("must match");
+    assert_eq!(policy.meta.name(), "a");
+    assert!(policy.params.allow_l5d_request_headers);
+}
+
+#[test]
+fn maps_backends() {
+    let backend = Backend {
+        meta: Meta::new_default("backend"),
+        queue: Queue {
+            capacity: 10,
+            failfast_timeout: time::Duration::from_secs(1),
+        },
+        dispatcher: BackendDispatcher::Fail {
+            message: "unmapped".into(),
+        },
+    };
+    let mut policy = mk_policy(&["/a", "/b"]);
+    policy.backends = Arc::new([backend.clone()]);
+    if let Protocol::Http1(ref mut http1) = policy.protocol {
+        http1.routes = http1
+            .routes
+            .iter()
+            .map(|route| {
+                let mut route = route.clone();
+                route.rules[0].policy.distribution =
+                    RouteDistribution::RandomAvailable(Arc::new([(
+                        RouteBackend {
+                            filters: Arc::new([]),
+                            backend: backend.clone(),
+                        },
+                        1,
+                    )]));
+                route
+            })
+            .collect();
+    }
+
+    let addr = SocketAddr::new([192, 0, 2, 1].into(), 8080);
+    policy.map_backends(|backend| Backend {
+        dispatcher: BackendDispatcher::Forward(addr, Default::default()),
+        ..backend.clone()
+    });
+
+    let forward = BackendDispatcher::Forward(addr, Default::default());
+    assert_eq!(policy.backends[0].dispatcher, forward);
+    assert_eq!(policy.backends[0].meta, backend.meta);
+    for route in http1_routes(&policy).iter() {
+        match route.rules[0].policy.distribution {
+            RouteDistribution::RandomAvailable(ref backends) => {
+                assert_eq!(backends[0].0.backend.dispatcher, forward);
+                assert_eq!(backends[0].1, 1);
+            }
+            ref distribution => panic!("unexpected distribution: {distribution:?}"),
+        }
+    }
+}
+
+#[test]
+fn backend_ports() {
+    let mk = |dispatcher| Backend {
+        meta: Meta::new_default("backend"),
+        queue: Queue {
+            capacity: 10,
+            failfast_timeout: time::Duration::from_secs(1),
+        },
+        dispatcher,
+    };
+    let balance = |path: &str| {
+        BackendDispatcher::BalanceP2c(
+            Load::PeakEwma(PeakEwma {
+                decay: time::Duration::from_secs(10),
+                default_rtt: time::Duration::from_millis(30),
+            }),
+            EndpointDiscovery::DestinationGet {
+                path: path.to_string(),
+            },
+        )
+    };
+
+    assert_eq!(
+        mk(balance("web.ns.svc.cluster.local:9090")).port(),
+        Some(9090)
+    );
+    assert_eq!(
+        mk(BackendDispatcher::Forward(
+            SocketAddr::new([192, 0, 2, 1].into(), 8080),
+            Default::default(),
+        ))
+        .port(),
+        Some(8080)
+    );
+    assert_eq!(mk(balance("web.ns.svc.cluster.local")).port(), None);
+    assert_eq!(
+        mk(BackendDispatcher::Fail {
+            message: "fail".into()
+        })
+        .port(),
+        None
+    );
+}
+
+fn mk_policy(paths: &[&str]) -> ClientPolicy {
+    let routes = paths
+        .iter()
+        .map(|path| http::Route {
+            hosts: vec![],
+            rules: vec![http::Rule {
+                matches: vec![http::r#match::MatchRequest {
+                    path: Some(http::r#match::MatchPath::Prefix(path.to_string())),
+                    ..Default::default()
+                }],
+                policy: http::Policy {
+                    meta: Meta::new_default(path.to_string()),
+                    filters: Arc::new([]),
+                    distribution: RouteDistribution::Empty,
+                    params: Default::default(),
+                },
+            }],
+        })
+        .collect();
+    ClientPolicy {
+        parent: Meta::new_default("parent"),
+        protocol: Protocol::Http1(http::Http1 {
+            routes,
+            failure_accrual: Default::default(),
+        }),
+        backends: Arc::new([]),
+    }
+}
+
+fn http1_routes(policy: &ClientPolicy) -> &Arc<[http::Route]> {
+    match policy.protocol {
+        Protocol::Http1(ref http1) => &http1.routes,
+        _ => panic!("unexpected protocol: {:?}", policy.protocol),
+    }
+}
+
+fn route_meta(route: &http::Route) -> Arc<Meta> {
+    route.rules[0].policy.meta.clone()
+}
+
+fn mk_route(namespace: &str, name: &str, m: http::r#match::MatchRequest) -> http::Route {
+    http::Route {
+        hosts: vec![],
+        rules: vec![http::Rule {
+            matches: vec![m],
+            policy: http::Policy {
+                meta: Arc::new(Meta::Resource {
+                    group: "gateway.networking.k8s.io".to_string(),
+                    kind: "HTTPRoute".to_string(),
+                    name: name.to_string(),
+                    namespace: namespace.to_string(),
+                    section: None,
+                    port: None,
+                }),
+                filters: Arc::new([]),
+                distribution: RouteDistribution::Empty,
+                params: Default::default(),
+            },
+        }],
+    }
+}
+
+fn mk_match(path: &str, header: Option<&'static str>) -> http::r#match::MatchRequest {
+    http::r#match::MatchRequest {
+        path: Some(http::r#match::MatchPath::Prefix(path.to_string())),
+        headers: header
+            .into_iter()
+            .map(|name| {
+                http::r#match::MatchHeader::Exact(
+                    ::http::HeaderName::from_static(name),
+                    ::http::HeaderValue::from_static("true"),
+                )
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn mk_req(path: &str, header: Option<&'static str>) -> ::http::Request<()> {
+    let mut req = ::http::Request::get(path);
+    if let Some(name) = header {
+        req = req.header(name, "true");
+    }
+    req.body(()).unwrap()
+}
+
+/// Orders the routes, as they are when discovered, and returns the
+/// `{namespace}/{name}` of the route that matches the request.
+fn find_route(mut routes: Vec<http::Route>, req: ::http::Request<()>) -> String {
+    order_routes(&mut routes);
+    let (_, policy) = http::find(&routes, &req).expect("must match");
+    format!("{}/{}", policy.meta.namespace(), policy.meta.name())
+}
+
+fn permutations(routes: &[http::Route; 3]) -> Vec<Vec<http::Route>> {
+    [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ]
+    .into_iter()
+    .map(|order| order.into_iter().map(|i| routes[i].clone()).collect())
+    .collect()
+}
@@ -13,6 +13,20 @@ pub struct RouteParams {
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Tls {
     pub routes: Arc<[Route]>,
+
+    /// Restricts the SNIs that clients may send, if set.
+    pub sni: Option<SniEnforcement>,
+}
+
+/// Restricts the SNIs that clients may send to a parent. Connections that do
+/// not match are closed before any backend is contacted.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SniEnforcement {
+    /// Patterns that a client's SNI must match.
+    pub allowed: Arc<[sni::MatchSni]>,
+
+    /// Whether connections without an SNI are exempt from enforcement.
+    pub allow_missing: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -38,6 +52,7 @@ impl Default for Tls {
     fn default() -> Self {
         Self {
             routes: Arc::new([]),
+            sni: None,
         }
     }
 }
@@ -90,7 +105,8 @@ pub(crate) mod proto {
 
     impl Tls {
         pub fn try_from(
-            overrides: ClientPolicyOverrides,
+            overrides: &ClientPolicyOverrides,
+            sni: Option<SniEnforcement>,
             proto: outbound::proxy_protocol::Tls,
         ) -> Result<Self, InvalidTlsRoute> {
             let routes = proto
@@ -98,7 +114,7 @@ pub(crate) mod proto {
                 .into_iter()
                 .map(|p| try_route(p, overrides))
                 .collect::<Result<Arc<[_]>, _>>()?;
-            Ok(Self { routes })
+            Ok(Self { routes, sni })
         }
 
         pub fn fill_backends(&self, set: &mut BackendSet) {
@@ -110,7 +126,7 @@ pub(crate) mod proto {
 
     fn try_route(
         proto: outbound::TlsRoute,
-        overrides: ClientPolicyOverrides,
+        overrides: &ClientPolicyOverrides,
     ) -> Result<Route, InvalidTlsRoute> {
         let outbound::TlsRoute {
             rules,
@@ -147,7 +163,7 @@ pub(crate) mod proto {
     fn try_rule(
         meta: &Arc<Meta>,
         tls_route::Rule { backends, filters }: tls_route::Rule,
-        overrides: ClientPolicyOverrides,
+        overrides: &ClientPolicyOverrides,
     ) -> Result<Policy, InvalidTlsRoute> {
         let distribution = backends
             .ok_or(InvalidTlsRoute::Missing("distribution"))?
@@ -167,14 +183,9 @@ pub(crate) mod proto {
     }
 
     impl RouteParams {
-        fn try_from_proto(
-            ClientPolicyOverrides {
-                export_hostname_labels,
-                ..
-            }: ClientPolicyOverrides,
-        ) -> Result<Self, InvalidTlsRoute> {
+        fn try_from_proto(overrides: &ClientPolicyOverrides) -> Result<Self, InvalidTlsRoute> {
             Ok(Self {
-                export_hostname_labels,
+                export_hostname_labels: overrides.export_hostname_labels,
             })
         }
     }
@@ -3,4 +3,4 @@
 
 pub mod resolve;
 
-pub use self::resolve::{Resolve, ResolveService, Update};
+pub use self::resolve::{Resolve, ResolveService, Update, Weight};
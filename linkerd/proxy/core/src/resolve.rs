@@ -1,5 +1,6 @@
 use futures::prelude::*;
 use linkerd_error::Error;
+use linkerd_stack::Param;
 use std::{
     fmt::Debug,
     net::SocketAddr,
@@ -27,12 +28,25 @@ pub trait Resolve<T>: Clone + Send + Sync + Unpin + 'static {
 #[derive(Clone, Debug)]
 pub struct ResolveService<S>(S);
 
+/// An endpoint's share of its balancer's requests, relative to the balancer's
+/// other endpoints.
+///
+/// Endpoints with a weight of zero are drained: they receive no new requests
+/// while other endpoints are available, but they are retained so that their
+/// existing connections are not disrupted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Weight(pub u32);
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Update<T> {
     Reset(Vec<(SocketAddr, T)>),
     Add(Vec<(SocketAddr, T)>),
     Remove(Vec<SocketAddr>),
     DoesNotExist,
+    /// Indicates that the resolution failed and is being recovered.
+    /// Previously-resolved endpoints should be retained until the resolution
+    /// is reestablished.
+    Unavailable,
 }
 
 // === impl Resolve ===
@@ -57,6 +71,15 @@ where
     }
 }
 
+// === impl Weight ===
+
+/// Endpoints that are resolved without metadata are weighted uniformly.
+impl Param<Weight> for () {
+    fn param(&self) -> Weight {
+        Weight(1)
+    }
+}
+
 // === impl Service ===
 
 impl<R, T> tower::Service<T> for ResolveService<R>
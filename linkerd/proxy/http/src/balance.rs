@@ -1,3 +1,6 @@
+use crate::ClientHandle;
+use std::net::IpAddr;
+
 pub use hyper_balance::{PendingUntilFirstData, PendingUntilFirstDataBody};
 pub use linkerd_proxy_balance::*;
 
@@ -5,3 +8,9 @@ pub type Body<B> = PendingUntilFirstDataBody<peak_ewma::Handle, B>;
 
 pub type NewBalance<B, X, R, N> =
     linkerd_proxy_balance::NewBalance<PendingUntilFirstData, http::Request<B>, X, R, N>;
+
+/// Returns the IP address of the client that issued the request, as recorded
+/// by the server that received it.
+pub fn client_ip<B>(req: &http::Request<B>) -> Option<IpAddr> {
+    req.extensions().get::<ClientHandle>().map(|h| h.addr.ip())
+}
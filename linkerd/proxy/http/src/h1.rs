@@ -11,6 +11,10 @@ use linkerd_stack::MakeConnection;
 use std::{pin::Pin, time::Duration};
 use tracing::{debug, trace};
 
+mod expect_continue;
+
+use self::expect_continue::ExpectContinue;
+
 #[derive(Copy, Clone, Debug)]
 pub struct WasAbsoluteForm(pub(crate) ());
 
@@ -30,8 +34,8 @@ pub struct PoolSettings {
 pub struct Client<C, T, B> {
     connect: C,
     target: T,
-    absolute_form: Option<hyper_util::client::legacy::Client<HyperConnect<C, T>, B>>,
-    origin_form: Option<hyper_util::client::legacy::Client<HyperConnect<C, T>, B>>,
+    absolute_form: Option<HyperClient<C, T, B>>,
+    origin_form: Option<HyperClient<C, T, B>>,
     pool: PoolSettings,
 }
 
@@ -59,6 +63,9 @@ impl<C: Clone, T: Clone, B> Clone for Client<C, T, B> {
     }
 }
 
+type HyperClient<C, T, B> =
+    hyper_util::client::legacy::Client<HyperConnect<C, T>, ExpectContinue<B>>;
+
 type RspFuture = Pin<Box<dyn Future<Output = Result<http::Response<BoxBody>>> + Send + 'static>>;
 
 impl<C, T, B> tower::Service<http::Request<B>> for Client<C, T, B>
@@ -94,6 +101,10 @@ where
             .map(|v| v.is_empty())
             .unwrap_or(true);
 
+        // Hold the body of a request that expects a `100 Continue` until the
+        // server sends one, so that the interim response is relayed.
+        let req = expect_continue::gate(req);
+
         let rsp_fut = if req.version() == http::Version::HTTP_10 || is_missing_host {
             // If there's no authority, we assume we're on some weird HTTP/1.0
             // ish, so we just build a one-off client for the connection.
@@ -0,0 +1,133 @@
+//! Relays `100 Continue` interim responses from HTTP/1 servers.
+//!
+//! The proxy's server sends a `100 Continue` to a client that expects one as
+//! soon as the request body is first polled. So that the interim response
+//! reflects the upstream server's decision, the body of a request that expects
+//! a `100 Continue` is not polled until the upstream server sends one. This
+//! way, a final response (e.g. `417 Expectation Failed`) is received by the
+//! client before it sends its body.
+
+use linkerd_error::Result;
+use parking_lot::Mutex;
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+use tokio::time::{self, Duration, Sleep};
+
+/// Clients typically wait about a second for a `100 Continue` before sending
+/// the body anyway, so servers that do not support the expectation are not
+/// left waiting for the body indefinitely.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A request body that is not polled until the server permits it.
+#[pin_project]
+#[derive(Debug)]
+pub struct ExpectContinue<B> {
+    #[pin]
+    inner: B,
+    gate: Option<Gate>,
+}
+
+#[derive(Debug)]
+struct Gate {
+    state: Arc<Mutex<State>>,
+    timeout: Pin<Box<Sleep>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    open: bool,
+    waker: Option<Waker>,
+}
+
+/// Holds the body of a request that expects a `100 Continue` until the server
+/// sends one.
+///
+/// Other interim responses cannot be sent by the proxy's server, so they are
+/// discarded.
+pub(super) fn gate<B>(req: http::Request<B>) -> http::Request<ExpectContinue<B>> {
+    let expects_continue = req
+        .headers()
+        .get(http::header::EXPECT)
+        .is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"100-continue"));
+    if !expects_continue {
+        return req.map(|inner| ExpectContinue { inner, gate: None });
+    }
+
+    let state = Arc::new(Mutex::new(State::default()));
+    let mut req = req.map(|inner| ExpectContinue {
+        inner,
+        gate: Some(Gate {
+            state: state.clone(),
+            timeout: Box::pin(time::sleep(TIMEOUT)),
+        }),
+    });
+    hyper::ext::on_informational(&mut req, move |rsp| {
+        if rsp.status() != http::StatusCode::CONTINUE {
+            tracing::debug!(status = %rsp.status(), "Discarding interim response");
+            return;
+        }
+        tracing::trace!("Server sent 100 Continue");
+        let mut state = state.lock();
+        state.open = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    req
+}
+
+// === impl ExpectContinue ===
+
+impl<B: http_body::Body> http_body::Body for ExpectContinue<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        if let Some(gate) = this.gate.as_mut() {
+            if !gate.poll_open(cx) {
+                return Poll::Pending;
+            }
+            *this.gate = None;
+        }
+        this.inner.poll_frame(cx)
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// === impl Gate ===
+
+impl Gate {
+    fn poll_open(&mut self, cx: &mut Context<'_>) -> bool {
+        {
+            let mut state = self.state.lock();
+            if state.open {
+                return true;
+            }
+            state.waker = Some(cx.waker().clone());
+        }
+
+        if self.timeout.as_mut().poll(cx).is_ready() {
+            tracing::debug!("Server did not send 100 Continue; sending request body");
+            return true;
+        }
+        false
+    }
+}
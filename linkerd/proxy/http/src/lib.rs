@@ -37,15 +37,21 @@ pub use http_body::Body;
 pub use hyper_util::rt::tokio::TokioExecutor;
 pub use linkerd_http_box::{BoxBody, BoxRequest, BoxResponse, EraseResponse};
 pub use linkerd_http_classify as classify;
+pub use linkerd_http_compress::{self as compress, Compress};
 pub use linkerd_http_detect::{
     DetectMetrics, DetectMetricsFamilies, DetectParams, Detection, NewDetect,
 };
+pub use linkerd_http_grpc_web::{self as grpc_web, GrpcWeb};
 pub use linkerd_http_insert as insert;
 pub use linkerd_http_override_authority::{AuthorityOverride, NewOverrideAuthority};
+pub use linkerd_http_request_id::{self as request_id, RequestId, SetRequestId};
 pub use linkerd_http_retain::{self as retain, Retain};
-pub use linkerd_http_stream_timeouts::{self as stream_timeouts, EnforceTimeouts, StreamTimeouts};
+pub use linkerd_http_stream_timeouts::{
+    self as stream_timeouts, EnforceTimeouts, PropagateDeadline, RequestDeadline, StreamTimeouts,
+};
 pub use linkerd_http_upgrade as upgrade;
 pub use linkerd_http_variant::{Unsupported as UnsupportedVariant, Variant};
+pub use linkerd_http_workload_identity as workload_identity;
 
 #[derive(Clone, Debug)]
 pub struct HeaderPair(pub HeaderName, pub HeaderValue);
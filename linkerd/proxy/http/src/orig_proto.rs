@@ -1,6 +1,6 @@
 use super::{h1, h2, Body};
 use futures::prelude::*;
-use http::header::{HeaderValue, TRANSFER_ENCODING};
+use http::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, TRANSFER_ENCODING};
 use http_body::Frame;
 use linkerd_error::Result;
 use linkerd_http_box::BoxBody;
@@ -14,6 +14,9 @@ use tracing::{debug, trace, warn};
 
 pub const L5D_ORIG_PROTO: &str = "l5d-orig-proto";
 
+static GRPC_STATUS: HeaderName = HeaderName::from_static("grpc-status");
+static GRPC_MESSAGE: HeaderName = HeaderName::from_static("grpc-message");
+
 /// Upgrades HTTP requests from their original protocol to HTTP2.
 #[derive(Debug)]
 pub struct Upgrade<C, T, B> {
@@ -39,6 +42,19 @@ pub struct Downgrade<S> {
     inner: S,
 }
 
+/// The body of a response to a downgraded request.
+///
+/// HTTP/1 upstreams can only reliably report a gRPC status in the response
+/// headers. When such a response has a body, its status is also sent in the
+/// HTTP/2 trailers so that gRPC peers find it where they expect it.
+#[pin_project::pin_project]
+#[derive(Debug, Default)]
+pub struct DowngradeResponseBody<B> {
+    #[pin]
+    inner: B,
+    trailers: Option<HeaderMap>,
+}
+
 /// Extension that indicates a request was an orig-proto upgrade.
 #[derive(Clone, Debug)]
 pub struct WasUpgrade(());
@@ -237,6 +253,59 @@ fn test_downgrade_error_source() {
     assert!(linkerd_error::is_caused_by::<DowngradedH2Error>(&err));
 }
 
+#[cfg(test)]
+#[tokio::test]
+async fn test_downgrade_grpc_status_trailers() {
+    use http_body_util::{BodyExt, Full};
+    use tower::ServiceExt;
+
+    async fn downgrade(
+        rsp: http::response::Builder,
+        body: &'static str,
+    ) -> (http::HeaderMap, Option<http::HeaderMap>) {
+        let (mock, mut handle) =
+            tower_test::mock::pair::<http::Request<()>, http::Response<Full<bytes::Bytes>>>();
+        handle.allow(1);
+        let req = http::Request::builder()
+            .version(http::Version::HTTP_2)
+            .header(L5D_ORIG_PROTO, "HTTP/1.1")
+            .body(())
+            .unwrap();
+        let call = tokio::spawn(Downgrade { inner: mock }.oneshot(req));
+        let (_, tx) = handle.next_request().await.expect("request must be sent");
+        tx.send_response(
+            rsp.version(http::Version::HTTP_11)
+                .header(CONTENT_TYPE, "application/grpc")
+                .body(Full::new(bytes::Bytes::from_static(body.as_bytes())))
+                .unwrap(),
+        );
+        let rsp = call.await.unwrap().expect("response must succeed");
+        let (parts, body) = rsp.into_parts();
+        assert_eq!(parts.version, http::Version::HTTP_2);
+        let trailers = body.collect().await.unwrap().trailers().cloned();
+        (parts.headers, trailers)
+    }
+
+    // A response with a body also carries its status in the trailers.
+    let (headers, trailers) = downgrade(
+        http::Response::builder()
+            .header(&GRPC_STATUS, "13")
+            .header(&GRPC_MESSAGE, "oops"),
+        "\0\0\0\0\0",
+    )
+    .await;
+    assert_eq!(headers[&GRPC_STATUS], "13");
+    let trailers = trailers.expect("status must be sent in trailers");
+    assert_eq!(trailers[&GRPC_STATUS], "13");
+    assert_eq!(trailers[&GRPC_MESSAGE], "oops");
+
+    // Trailers-only responses are unchanged.
+    let (headers, trailers) =
+        downgrade(http::Response::builder().header(&GRPC_STATUS, "14"), "").await;
+    assert_eq!(headers[&GRPC_STATUS], "14");
+    assert!(trailers.is_none());
+}
+
 // === impl UpgradeResponseBody ===
 
 impl<B> Body for UpgradeResponseBody<B>
@@ -269,6 +338,50 @@ where
     }
 }
 
+// === impl DowngradeResponseBody ===
+
+impl<B> DowngradeResponseBody<B> {
+    fn new(inner: B) -> Self {
+        Self {
+            inner,
+            trailers: None,
+        }
+    }
+}
+
+impl<B: Body> Body for DowngradeResponseBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.trailers.is_none() && self.inner.is_end_stream()
+    }
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        match futures::ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => {
+                // Trailers sent by the upstream take precedence.
+                if frame.is_trailers() {
+                    this.trailers.take();
+                }
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Some(Err(error)) => Poll::Ready(Some(Err(error))),
+            None => Poll::Ready(this.trailers.take().map(|t| Ok(Frame::trailers(t)))),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
 // === impl Downgrade ===
 
 impl<S> Downgrade<S> {
@@ -277,15 +390,17 @@ impl<S> Downgrade<S> {
     }
 }
 
-type DowngradeFuture<F, T> = future::MapOk<F, fn(T) -> T>;
+type DowngradeFuture<F, B> =
+    future::MapOk<F, fn(http::Response<B>) -> http::Response<DowngradeResponseBody<B>>>;
 
 impl<S, A, B> Service<http::Request<A>> for Downgrade<S>
 where
     S: Service<http::Request<A>, Response = http::Response<B>>,
+    B: Body,
 {
-    type Response = S::Response;
+    type Response = http::Response<DowngradeResponseBody<B>>;
     type Error = S::Error;
-    type Future = DowngradeFuture<S::Future, S::Response>;
+    type Future = DowngradeFuture<S::Future, B>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         self.inner.poll_ready(cx)
@@ -323,7 +438,7 @@ where
                 let orig_proto = match res.version() {
                     http::Version::HTTP_11 => "HTTP/1.1",
                     http::Version::HTTP_10 => "HTTP/1.0",
-                    _ => return res,
+                    _ => return res.map(DowngradeResponseBody::new),
                 };
 
                 res.headers_mut()
@@ -333,14 +448,40 @@ where
                 res.headers_mut().remove(TRANSFER_ENCODING);
 
                 *res.version_mut() = http::Version::HTTP_2;
-                res
+
+                // The status header is retained for HTTP/1 clients.
+                let trailers = grpc_status_trailers(&res);
+                res.map(|inner| DowngradeResponseBody { inner, trailers })
             })
         } else {
-            fut.map_ok(|res| res)
+            fut.map_ok(|res| res.map(DowngradeResponseBody::new))
         }
     }
 }
 
+/// Returns trailers carrying the gRPC status of a response with a body, if
+/// the status was only sent in its headers.
+///
+/// Trailers-only responses are left as-is.
+fn grpc_status_trailers<B: Body>(res: &http::Response<B>) -> Option<HeaderMap> {
+    let is_grpc = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/grpc"));
+    if !is_grpc || res.body().is_end_stream() {
+        return None;
+    }
+
+    let status = res.headers().get(&GRPC_STATUS)?;
+    let mut trailers = HeaderMap::with_capacity(2);
+    trailers.insert(GRPC_STATUS.clone(), status.clone());
+    if let Some(message) = res.headers().get(&GRPC_MESSAGE) {
+        trailers.insert(GRPC_MESSAGE.clone(), message.clone());
+    }
+    Some(trailers)
+}
+
 fn was_absolute_form(val: &[u8]) -> bool {
     val.len() >= "HTTP/1.1; absolute-form".len() && &val[10..23] == b"absolute-form"
 }
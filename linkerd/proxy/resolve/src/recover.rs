@@ -34,6 +34,9 @@ struct Inner<T, E: Recover, R: resolve::Resolve<T>> {
     resolve: R,
     recover: E,
     state: State<R::Future, R::Resolution, E::Backoff>,
+    /// Set when a failure is recovered so that the resolution may notify its
+    /// consumer that it is unavailable.
+    failed: bool,
 }
 
 #[pin_project]
@@ -99,6 +102,7 @@ where
                 target,
                 recover: self.recover.clone(),
                 resolve: self.resolve.clone(),
+                failed: false,
             }),
         }
     }
@@ -119,12 +123,15 @@ where
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
-        // Wait until the resolution is connected.
-        ready!(this
-            .inner
-            .as_mut()
-            .expect("polled after complete")
-            .poll_connected(cx))?;
+        // Wait until the resolution is connected or the initial connection
+        // fails recoverably, so that the resolution can report that it is
+        // unavailable while it continues to recover.
+        let inner = this.inner.as_mut().expect("polled after complete");
+        match inner.poll_connected(cx) {
+            Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+            Poll::Pending if !inner.failed => return Poll::Pending,
+            _ => {}
+        }
         let inner = this.inner.take().expect("polled after complete");
         Poll::Ready(Ok(Resolution { inner }))
     }
@@ -189,7 +196,12 @@ where
                 }
             }
 
-            ready!(this.inner.poll_connected(cx))?;
+            let connected = this.inner.poll_connected(cx)?;
+            if std::mem::take(&mut this.inner.failed) {
+                tracing::debug!("Resolution unavailable");
+                return Poll::Ready(Some(Ok(Update::Unavailable)));
+            }
+            ready!(connected);
         }
     }
 }
@@ -248,6 +260,7 @@ where
                     let err = error.take().expect("illegal state");
                     tracing::debug!(%err, "recovering");
                     let new_backoff = self.recover.recover(err)?;
+                    self.failed = true;
                     State::Backoff(backoff.take().or(Some(new_backoff)))
                 }
 
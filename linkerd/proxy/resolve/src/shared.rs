@@ -0,0 +1,348 @@
+//! A middleware that shares a single resolution of each target among all of
+//! the resolution's consumers.
+//!
+//! The first consumer of a target starts an upstream resolution in a
+//! background task. Consumers that subscribe later are first sent the
+//! resolution's current state, as a `Reset` or `DoesNotExist` update, and then
+//! each subsequent update. When the last consumer is dropped, the upstream
+//! resolution is retained for a brief linger period so that consumers that
+//! are rebuilt quickly do not restart it.
+
+use futures::prelude::*;
+use linkerd_error::Error;
+use linkerd_metrics::prom;
+use linkerd_proxy_core::resolve::{self, Update};
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    hash::Hash,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::{
+    sync::{mpsc, watch},
+    time,
+};
+use tracing::Instrument;
+
+#[cfg(test)]
+mod tests;
+
+pub struct Shared<T, R: resolve::Resolve<T>> {
+    inner: Arc<Inner<T, R>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SharedMetrics {
+    active: prom::Gauge,
+    subscribers: prom::Gauge,
+}
+
+/// A consumer's subscription to a shared resolution.
+pub struct Resolution<E> {
+    rx: mpsc::UnboundedReceiver<Result<Update<E>, Error>>,
+    _subscription: Subscription<E>,
+}
+
+/// The error with which each consumer's resolution fails when the shared
+/// resolution fails.
+#[derive(Clone, Debug)]
+pub struct SharedError(Arc<Error>);
+
+struct Inner<T, R: resolve::Resolve<T>> {
+    resolve: R,
+    linger: time::Duration,
+    entries: Mutex<HashMap<T, Arc<Entry<R::Endpoint>>>>,
+    metrics: SharedMetrics,
+}
+
+struct Entry<E> {
+    state: Mutex<State<E>>,
+    subscribers: watch::Sender<usize>,
+}
+
+struct State<E> {
+    current: Option<Current<E>>,
+    senders: Vec<mpsc::UnboundedSender<Result<Update<E>, Error>>>,
+}
+
+enum Current<E> {
+    Endpoints(BTreeMap<SocketAddr, E>),
+    DoesNotExist,
+}
+
+struct Subscription<E> {
+    entry: Arc<Entry<E>>,
+    metrics: SharedMetrics,
+}
+
+// === impl Shared ===
+
+impl<T, R> Shared<T, R>
+where
+    T: Clone + Eq + Hash + fmt::Debug + Send + Sync + 'static,
+    R: resolve::Resolve<T>,
+{
+    /// Shares resolutions from `resolve`, retaining each for `linger` after
+    /// its last consumer is dropped.
+    pub fn new(resolve: R, linger: time::Duration, metrics: SharedMetrics) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                resolve,
+                linger,
+                entries: Default::default(),
+                metrics,
+            }),
+        }
+    }
+
+    fn subscribe(&self, target: T) -> Resolution<R::Endpoint> {
+        let mut entries = self.inner.entries.lock();
+        let entry = match entries.get(&target) {
+            Some(entry) => {
+                tracing::debug!(?target, "Sharing resolution");
+                entry.clone()
+            }
+            None => {
+                tracing::debug!(?target, "Starting resolution");
+                let entry = Arc::new(Entry {
+                    state: Mutex::new(State {
+                        current: None,
+                        senders: vec![],
+                    }),
+                    subscribers: watch::channel(0).0,
+                });
+                entries.insert(target.clone(), entry.clone());
+                self.inner.metrics.active.inc();
+                tokio::spawn(self.clone().run(target, entry.clone()).in_current_span());
+                entry
+            }
+        };
+        // The subscription is registered while the entries are locked so that
+        // the resolution is not removed as it is subscribed.
+        let (tx, rx) = mpsc::unbounded_channel();
+        entry.subscribers.send_modify(|n| *n += 1);
+        entry.state.lock().subscribe(tx);
+        drop(entries);
+
+        self.inner.metrics.subscribers.inc();
+        Resolution {
+            rx,
+            _subscription: Subscription {
+                entry,
+                metrics: self.inner.metrics.clone(),
+            },
+        }
+    }
+
+    /// Drives the upstream resolution of `target`, publishing its updates to
+    /// the entry's subscribers until the resolution ends or the entry has no
+    /// subscribers for the linger period.
+    async fn run(self, target: T, entry: Arc<Entry<R::Endpoint>>) {
+        let updates = self
+            .inner
+            .resolve
+            .resolve(target.clone())
+            .map_ok(|resolution| resolution.map_err(Into::into))
+            .map_err(Into::into)
+            .try_flatten_stream();
+        let lingering = idle(entry.subscribers.subscribe(), self.inner.linger);
+        tokio::pin!(updates, lingering);
+        loop {
+            tokio::select! {
+                next = updates.next() => match next {
+                    Some(Ok(update)) => entry.state.lock().update(update),
+                    Some(Err(error)) => {
+                        tracing::debug!(?target, %error, "Resolution failed");
+                        self.remove(&target, &entry, |_| true);
+                        entry.state.lock().fail(error);
+                        return;
+                    }
+                    None => {
+                        tracing::debug!(?target, "Resolution ended");
+                        self.remove(&target, &entry, |_| true);
+                        entry.state.lock().senders.clear();
+                        return;
+                    }
+                },
+                () = &mut lingering => {
+                    if self.remove(&target, &entry, |e| *e.subscribers.borrow() == 0) {
+                        tracing::debug!(?target, "Resolution idle");
+                        return;
+                    }
+                    lingering.set(idle(entry.subscribers.subscribe(), self.inner.linger));
+                }
+            }
+        }
+    }
+
+    /// Removes `entry` if it is still the entry for `target` and `f` returns
+    /// true.
+    fn remove(
+        &self,
+        target: &T,
+        entry: &Arc<Entry<R::Endpoint>>,
+        f: impl FnOnce(&Entry<R::Endpoint>) -> bool,
+    ) -> bool {
+        let mut entries = self.inner.entries.lock();
+        if !entries.get(target).is_some_and(|e| Arc::ptr_eq(e, entry)) || !f(entry) {
+            return false;
+        }
+        entries.remove(target);
+        self.inner.metrics.active.dec();
+        true
+    }
+}
+
+impl<T, R: resolve::Resolve<T>> Clone for Shared<T, R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T, R> tower::Service<T> for Shared<T, R>
+where
+    T: Clone + Eq + Hash + fmt::Debug + Send + Sync + 'static,
+    R: resolve::Resolve<T>,
+{
+    type Response = Resolution<R::Endpoint>;
+    type Error = Error;
+    type Future = future::Ready<Result<Self::Response, Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        future::ok(self.subscribe(target))
+    }
+}
+
+/// Completes when there have been no subscribers for `linger`.
+///
+/// Any subscription or release restarts the linger period.
+async fn idle(mut subscribers: watch::Receiver<usize>, linger: time::Duration) {
+    loop {
+        if subscribers.wait_for(|n| *n == 0).await.is_err() {
+            return;
+        }
+        match time::timeout(linger, subscribers.changed()).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(_)) | Err(_) => return,
+        }
+    }
+}
+
+// === impl SharedMetrics ===
+
+impl SharedMetrics {
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let active = prom::Gauge::default();
+        registry.register(
+            "active",
+            "The number of targets with a shared resolution",
+            active.clone(),
+        );
+        let subscribers = prom::Gauge::default();
+        registry.register(
+            "subscribers",
+            "The number of consumers of shared resolutions",
+            subscribers.clone(),
+        );
+        Self {
+            active,
+            subscribers,
+        }
+    }
+}
+
+// === impl State ===
+
+impl<E: Clone> State<E> {
+    /// Sends the current state to a new subscriber before it receives updates.
+    fn subscribe(&mut self, tx: mpsc::UnboundedSender<Result<Update<E>, Error>>) {
+        let replay = match self.current {
+            Some(Current::Endpoints(ref eps)) => Some(Update::Reset(
+                eps.iter().map(|(addr, ep)| (*addr, ep.clone())).collect(),
+            )),
+            Some(Current::DoesNotExist) => Some(Update::DoesNotExist),
+            None => None,
+        };
+        if let Some(update) = replay {
+            if tx.send(Ok(update)).is_err() {
+                return;
+            }
+        }
+        self.senders.push(tx);
+    }
+
+    fn update(&mut self, update: Update<E>) {
+        match update {
+            Update::Reset(ref eps) => {
+                self.current = Some(Current::Endpoints(eps.iter().cloned().collect()));
+            }
+            Update::Add(ref eps) => match self.current {
+                Some(Current::Endpoints(ref mut current)) => current.extend(eps.iter().cloned()),
+                _ => self.current = Some(Current::Endpoints(eps.iter().cloned().collect())),
+            },
+            Update::Remove(ref addrs) => {
+                if let Some(Current::Endpoints(ref mut current)) = self.current {
+                    for addr in addrs {
+                        current.remove(addr);
+                    }
+                }
+            }
+            Update::DoesNotExist => self.current = Some(Current::DoesNotExist),
+            // Endpoints are retained while the resolution is unavailable.
+            Update::Unavailable => {}
+        }
+        self.senders
+            .retain(|tx| tx.send(Ok(update.clone())).is_ok());
+    }
+
+    fn fail(&mut self, error: Error) {
+        let error = SharedError(Arc::new(error));
+        for tx in self.senders.drain(..) {
+            let _ = tx.send(Err(error.clone().into()));
+        }
+    }
+}
+
+// === impl Resolution ===
+
+impl<E> Stream for Resolution<E> {
+    type Item = Result<Update<E>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+// === impl Subscription ===
+
+impl<E> Drop for Subscription<E> {
+    fn drop(&mut self) {
+        self.entry.subscribers.send_modify(|n| *n -= 1);
+        self.metrics.subscribers.dec();
+    }
+}
+
+// === impl SharedError ===
+
+impl fmt::Display for SharedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for SharedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&**self.0)
+    }
+}
@@ -0,0 +1,178 @@
+use super::*;
+use linkerd_proxy_core::Resolve;
+use std::{io, net::Ipv4Addr};
+
+const LINGER: time::Duration = time::Duration::from_secs(10);
+
+type Tx = mpsc::UnboundedSender<Result<Update<u16>, Error>>;
+
+/// Records each upstream resolution so that tests may publish its updates.
+#[derive(Clone, Default)]
+struct MockResolve(Arc<Mutex<Vec<(&'static str, Tx)>>>);
+
+struct MockResolution(mpsc::UnboundedReceiver<Result<Update<u16>, Error>>);
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn replays_current_state_to_late_subscribers() {
+    let upstream = MockResolve::default();
+    let metrics = SharedMetrics::default();
+    let shared = mk_shared(&upstream, &metrics);
+
+    let mut first = shared.resolve("web").await.unwrap();
+    settle().await;
+    let tx = upstream.only("web");
+    tx.send(Ok(Update::Reset(vec![(addr(1), 1), (addr(2), 2)])))
+        .unwrap();
+    tx.send(Ok(Update::Remove(vec![addr(1)]))).unwrap();
+    tx.send(Ok(Update::Add(vec![(addr(3), 3)]))).unwrap();
+    assert_eq!(
+        next(&mut first).await,
+        Update::Reset(vec![(addr(1), 1), (addr(2), 2)])
+    );
+    assert_eq!(next(&mut first).await, Update::Remove(vec![addr(1)]));
+    assert_eq!(next(&mut first).await, Update::Add(vec![(addr(3), 3)]));
+
+    let mut second = shared.resolve("web").await.unwrap();
+    assert_eq!(
+        next(&mut second).await,
+        Update::Reset(vec![(addr(2), 2), (addr(3), 3)])
+    );
+
+    tx.send(Ok(Update::DoesNotExist)).unwrap();
+    assert_eq!(next(&mut first).await, Update::DoesNotExist);
+    assert_eq!(next(&mut second).await, Update::DoesNotExist);
+
+    let mut third = shared.resolve("web").await.unwrap();
+    assert_eq!(next(&mut third).await, Update::DoesNotExist);
+
+    assert_eq!(upstream.calls(), 1);
+    assert_eq!(metrics.active.get(), 1);
+    assert_eq!(metrics.subscribers.get(), 3);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn lingers_after_last_subscriber() {
+    let upstream = MockResolve::default();
+    let metrics = SharedMetrics::default();
+    let shared = mk_shared(&upstream, &metrics);
+
+    let mut first = shared.resolve("web").await.unwrap();
+    settle().await;
+    let tx = upstream.only("web");
+    tx.send(Ok(Update::Reset(vec![(addr(1), 1)]))).unwrap();
+    assert_eq!(next(&mut first).await, Update::Reset(vec![(addr(1), 1)]));
+    drop(first);
+    assert_eq!(metrics.subscribers.get(), 0);
+
+    // A consumer that is rebuilt within the linger period reuses the upstream
+    // resolution.
+    time::sleep(LINGER / 2).await;
+    let mut second = shared.resolve("web").await.unwrap();
+    assert_eq!(next(&mut second).await, Update::Reset(vec![(addr(1), 1)]));
+    assert_eq!(upstream.calls(), 1);
+    drop(second);
+
+    // The linger period restarts when the last subscriber is dropped.
+    time::sleep(LINGER / 2 + time::Duration::from_secs(1)).await;
+    assert_eq!(metrics.active.get(), 1);
+    assert!(!tx.is_closed());
+
+    time::sleep(LINGER).await;
+    assert_eq!(metrics.active.get(), 0);
+    assert!(tx.is_closed(), "upstream resolution must be dropped");
+
+    let _third = shared.resolve("web").await.unwrap();
+    settle().await;
+    assert_eq!(upstream.calls(), 2);
+    assert_eq!(metrics.active.get(), 1);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn fails_all_subscribers() {
+    let upstream = MockResolve::default();
+    let metrics = SharedMetrics::default();
+    let shared = mk_shared(&upstream, &metrics);
+
+    let mut first = shared.resolve("web").await.unwrap();
+    let mut second = shared.resolve("web").await.unwrap();
+    settle().await;
+    upstream
+        .only("web")
+        .send(Err(io::Error::other("boom").into()))
+        .unwrap();
+    for resolution in [&mut first, &mut second] {
+        let error = resolution.next().await.unwrap().unwrap_err();
+        let source = std::error::Error::source(&*error).expect("must have a source");
+        assert!(source.is::<io::Error>());
+        assert!(resolution.next().await.is_none());
+    }
+    assert_eq!(metrics.active.get(), 0);
+
+    // The failed resolution is not shared with new subscribers.
+    let _third = shared.resolve("web").await.unwrap();
+    settle().await;
+    assert_eq!(upstream.calls(), 2);
+}
+
+fn mk_shared(upstream: &MockResolve, metrics: &SharedMetrics) -> Shared<&'static str, MockResolve> {
+    Shared::new(upstream.clone(), LINGER, metrics.clone())
+}
+
+async fn settle() {
+    for _ in 0..3 {
+        tokio::task::yield_now().await;
+    }
+}
+
+async fn next(resolution: &mut Resolution<u16>) -> Update<u16> {
+    resolution
+        .next()
+        .await
+        .expect("resolution must not end")
+        .expect("resolution must not fail")
+}
+
+fn addr(n: u8) -> SocketAddr {
+    (Ipv4Addr::new(192, 0, 2, n), 8080).into()
+}
+
+// === impl MockResolve ===
+
+impl MockResolve {
+    fn calls(&self) -> usize {
+        self.0.lock().len()
+    }
+
+    /// Returns the sender of the only resolution of `target`.
+    fn only(&self, target: &'static str) -> Tx {
+        let resolutions = self.0.lock();
+        let mut txs = resolutions.iter().filter(|(t, _)| *t == target);
+        let (_, tx) = txs.next().expect("target must be resolved");
+        assert!(txs.next().is_none(), "target must be resolved once");
+        tx.clone()
+    }
+}
+
+impl tower::Service<&'static str> for MockResolve {
+    type Response = MockResolution;
+    type Error = Error;
+    type Future = future::Ready<Result<Self::Response, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, target: &'static str) -> Self::Future {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.0.lock().push((target, tx));
+        future::ok(MockResolution(rx))
+    }
+}
+
+impl Stream for MockResolution {
+    type Item = Result<Update<u16>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx)
+    }
+}
@@ -29,6 +29,9 @@ pub fn default(authorizations: std::sync::Arc<[crate::Authorization]>) -> Route
                 meta: crate::Meta::new_default("default"),
                 authorizations,
                 filters: vec![],
+                latency_objective: None,
+                sheddable: false,
+                ext_authz: false,
             },
         }],
     }
@@ -40,7 +43,7 @@ pub mod proto {
     use crate::{
         authz::{self, proto::InvalidAuthz},
         meta::proto::InvalidMeta,
-        Authorization, Meta,
+        Authorization, Meta, RouteOverrides,
     };
     use linkerd2_proxy_api::inbound as api;
     use linkerd_http_route::{
@@ -79,6 +82,7 @@ pub mod proto {
     pub fn try_route(
         proto: api::GrpcRoute,
         server_authorizations: &[Authorization],
+        overrides: &RouteOverrides,
     ) -> Result<Route, InvalidGrpcRoute> {
         let api::GrpcRoute {
             hosts,
@@ -96,7 +100,7 @@ pub mod proto {
         let meta = Arc::new(Meta::try_from(metadata.ok_or(InvalidMeta::Missing)?)?);
         let rules = rules
             .into_iter()
-            .map(|r| try_rule(authzs.clone(), meta.clone(), r))
+            .map(|r| try_rule(authzs.clone(), meta.clone(), overrides, r))
             .collect::<Result<Vec<_>, InvalidGrpcRoute>>()?;
 
         Ok(Route { hosts, rules })
@@ -105,6 +109,7 @@ pub mod proto {
     fn try_rule(
         authorizations: Arc<[authz::Authorization]>,
         meta: Arc<Meta>,
+        overrides: &RouteOverrides,
         proto: api::grpc_route::Rule,
     ) -> Result<Rule, InvalidGrpcRoute> {
         let matches = proto
@@ -132,10 +137,14 @@ pub mod proto {
                 })
                 .collect::<Result<Vec<_>, InvalidGrpcRoute>>()?;
 
+            let route = overrides.get(&meta);
             crate::RoutePolicy {
                 authorizations,
                 filters,
                 meta,
+                latency_objective: route.latency_objective,
+                sheddable: route.sheddable,
+                ext_authz: route.ext_authz,
             }
         };
 
@@ -30,6 +30,9 @@ pub fn default(authorizations: std::sync::Arc<[crate::Authorization]>) -> Route
                 meta: crate::Meta::new_default("default"),
                 authorizations,
                 filters: vec![],
+                latency_objective: None,
+                sheddable: false,
+                ext_authz: false,
             },
         }],
     }
@@ -41,7 +44,7 @@ pub mod proto {
     use crate::{
         authz::{self, proto::InvalidAuthz},
         meta::proto::InvalidMeta,
-        Authorization, Meta,
+        Authorization, Meta, RouteOverrides,
     };
     use linkerd2_proxy_api::inbound as api;
     use linkerd_http_route::http::{
@@ -80,6 +83,7 @@ pub mod proto {
     pub fn try_route(
         proto: api::HttpRoute,
         server_authorizations: &[Authorization],
+        overrides: &RouteOverrides,
     ) -> Result<Route, InvalidHttpRoute> {
         let api::HttpRoute {
             hosts,
@@ -97,7 +101,7 @@ pub mod proto {
         let meta = Arc::new(Meta::try_from(metadata.ok_or(InvalidMeta::Missing)?)?);
         let rules = rules
             .into_iter()
-            .map(|r| try_rule(authzs.clone(), meta.clone(), r))
+            .map(|r| try_rule(authzs.clone(), meta.clone(), overrides, r))
             .collect::<Result<Vec<_>, InvalidHttpRoute>>()?;
 
         Ok(Route { hosts, rules })
@@ -106,6 +110,7 @@ pub mod proto {
     fn try_rule(
         authorizations: Arc<[authz::Authorization]>,
         meta: Arc<Meta>,
+        overrides: &RouteOverrides,
         proto: api::http_route::Rule,
     ) -> Result<Rule, InvalidHttpRoute> {
         let matches = proto
@@ -134,10 +139,14 @@ pub mod proto {
                 })
                 .collect::<Result<Vec<_>, InvalidHttpRoute>>()?;
 
+            let route = overrides.get(&meta);
             crate::RoutePolicy {
                 authorizations,
                 filters,
                 meta,
+                latency_objective: route.latency_objective,
+                sheddable: route.sheddable,
+                ext_authz: route.ext_authz,
             }
         };
 
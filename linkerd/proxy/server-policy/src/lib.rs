@@ -1,7 +1,7 @@
 #![deny(rust_2018_idioms, clippy::disallowed_methods, clippy::disallowed_types)]
 #![forbid(unsafe_code)]
 
-use std::{hash::Hash, sync::Arc, time};
+use std::{collections::HashMap, hash::Hash, sync::Arc, time};
 
 pub mod authz;
 pub mod grpc;
@@ -21,6 +21,11 @@ pub struct ServerPolicy {
     pub protocol: Protocol,
     pub meta: Arc<Meta>,
     pub local_rate_limit: Arc<LocalRateLimit>,
+
+    /// The amount of time that open connections and streams are permitted to
+    /// continue after a policy update revokes their authorization. When zero,
+    /// they are terminated immediately.
+    pub termination_grace_period: time::Duration,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -42,6 +47,63 @@ pub struct RoutePolicy<T> {
     pub meta: Arc<Meta>,
     pub authorizations: Arc<[Authorization]>,
     pub filters: Vec<T>,
+
+    /// The latency within which the route's requests are expected to complete,
+    /// if one is configured.
+    pub latency_objective: Option<time::Duration>,
+
+    /// Indicates that the route's requests may be shed in preference to those
+    /// of other routes when the application is overloaded.
+    pub sheddable: bool,
+
+    /// Indicates that the route's requests must also be authorized by an
+    /// external authorization service.
+    pub ext_authz: bool,
+}
+
+/// Configures discovered routes, by route resource, with settings that the
+/// policy controller does not provide.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteOverrides(Arc<HashMap<ResourceKey, RouteOverride>>);
+
+/// Configures discovered servers, by server resource, with settings that the
+/// policy controller does not provide.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerOverrides(Arc<HashMap<ResourceKey, ServerOverride>>);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerOverride {
+    /// The amount of time that the server's connections and streams are
+    /// permitted to continue after a policy update revokes their
+    /// authorization.
+    pub termination_grace_period: time::Duration,
+}
+
+/// Identifies a discovered resource by its group, kind, and name, so that
+/// overrides apply only to that resource. Inbound resources are all in the
+/// workload's namespace. The proxy's default resources have the `default` kind
+/// and no group.
+///
+/// Groups and kinds are compared case-insensitively.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceKey {
+    pub group: String,
+    pub kind: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteOverride {
+    /// The latency within which the route's requests are expected to complete.
+    pub latency_objective: Option<time::Duration>,
+
+    /// Allows the route's requests to be shed in preference to those of other
+    /// routes when the application is overloaded.
+    pub sheddable: bool,
+
+    /// Requires that the route's requests are also authorized by an external
+    /// authorization service.
+    pub ext_authz: bool,
 }
 
 impl ServerPolicy {
@@ -63,16 +125,77 @@ impl ServerPolicy {
                             filters: vec![http::Filter::InternalError(
                                 "invalid server configuration",
                             )],
+                            latency_objective: None,
+                            sheddable: false,
+                            ext_authz: false,
                         },
                     }],
                 }]),
                 tcp_authorizations: Arc::new([]),
             },
             local_rate_limit: Arc::new(LocalRateLimit::default()),
+            termination_grace_period: time::Duration::ZERO,
         }
     }
 }
 
+// === impl ServerOverrides ===
+
+impl ServerOverrides {
+    pub fn new(servers: impl IntoIterator<Item = (ResourceKey, ServerOverride)>) -> Self {
+        Self(Arc::new(servers.into_iter().collect()))
+    }
+
+    /// Returns the settings configured for the server, or the default
+    /// settings if none are configured.
+    pub fn get(&self, meta: &Meta) -> &ServerOverride {
+        static DEFAULT: ServerOverride = ServerOverride {
+            termination_grace_period: time::Duration::ZERO,
+        };
+        self.0.get(&ResourceKey::of(meta)).unwrap_or(&DEFAULT)
+    }
+}
+
+// === impl RouteOverrides ===
+
+impl RouteOverrides {
+    pub fn new(routes: impl IntoIterator<Item = (ResourceKey, RouteOverride)>) -> Self {
+        Self(Arc::new(routes.into_iter().collect()))
+    }
+
+    /// Returns the settings configured for the route, or the default
+    /// settings if none are configured.
+    pub fn get(&self, meta: &Meta) -> &RouteOverride {
+        static DEFAULT: RouteOverride = RouteOverride {
+            latency_objective: None,
+            sheddable: false,
+            ext_authz: false,
+        };
+        self.0.get(&ResourceKey::of(meta)).unwrap_or(&DEFAULT)
+    }
+}
+
+// === impl ResourceKey ===
+
+impl ResourceKey {
+    pub fn new(group: impl Into<String>, kind: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            group: group.into().to_ascii_lowercase(),
+            kind: kind.into().to_ascii_lowercase(),
+            name: name.into(),
+        }
+    }
+
+    /// Identifies one of the proxy's default resources.
+    pub fn new_default(name: impl Into<String>) -> Self {
+        Self::new("", "default", name)
+    }
+
+    pub fn of(meta: &Meta) -> Self {
+        Self::new(meta.group(), meta.kind(), meta.name())
+    }
+}
+
 #[cfg(feature = "proto")]
 pub mod proto {
     use super::*;
@@ -106,7 +229,7 @@ pub mod proto {
     // === impl ServerPolicy ===
 
     macro_rules! mk_routes {
-        ($kind:ident, $routes:ident, $server_authzs:expr) => {{
+        ($kind:ident, $routes:ident, $server_authzs:expr, $overrides:expr) => {{
             // If no routes are specified, then we are probably talking to an
             // older policy controller version that does not support routes. In
             // this case, we use a default route (that matches all requests).
@@ -118,16 +241,18 @@ pub mod proto {
             } else {
                 $routes
                     .into_iter()
-                    .map(|r| $kind::proto::try_route(r, &*$server_authzs))
+                    .map(|r| $kind::proto::try_route(r, &*$server_authzs, $overrides))
                     .collect::<Result<Arc<[_]>, _>>()
             }
         }};
     }
 
-    impl TryFrom<api::Server> for ServerPolicy {
-        type Error = InvalidServer;
-
-        fn try_from(proto: api::Server) -> Result<Self, Self::Error> {
+    impl ServerPolicy {
+        pub fn try_from(
+            overrides: &RouteOverrides,
+            servers: &ServerOverrides,
+            proto: api::Server,
+        ) -> Result<Self, InvalidServer> {
             let api::Server {
                 protocol,
                 authorizations,
@@ -181,7 +306,7 @@ pub mod proto {
                     timeout,
                     http_local_rate_limit: _,
                 }) => Protocol::Detect {
-                    http: mk_routes!(http, http_routes, authorizations.clone())?,
+                    http: mk_routes!(http, http_routes, authorizations.clone(), overrides)?,
                     timeout: timeout
                         .ok_or(InvalidServer::MissingDetectTimeout)?
                         .try_into()?,
@@ -191,15 +316,15 @@ pub mod proto {
                 api::proxy_protocol::Kind::Http1(api::proxy_protocol::Http1 {
                     routes,
                     local_rate_limit: _,
-                }) => Protocol::Http1(mk_routes!(http, routes, authorizations)?),
+                }) => Protocol::Http1(mk_routes!(http, routes, authorizations, overrides)?),
 
                 api::proxy_protocol::Kind::Http2(api::proxy_protocol::Http2 {
                     routes,
                     local_rate_limit: _,
-                }) => Protocol::Http2(mk_routes!(http, routes, authorizations)?),
+                }) => Protocol::Http2(mk_routes!(http, routes, authorizations, overrides)?),
 
                 api::proxy_protocol::Kind::Grpc(api::proxy_protocol::Grpc { routes }) => {
-                    Protocol::Grpc(mk_routes!(grpc, routes, authorizations)?)
+                    Protocol::Grpc(mk_routes!(grpc, routes, authorizations, overrides)?)
                 }
 
                 api::proxy_protocol::Kind::Tls(_) => Protocol::Tls(authorizations),
@@ -210,10 +335,16 @@ pub mod proto {
             // avoid label inference.
             let meta = Meta::try_new_with_default(labels, "policy.linkerd.io", "server")?;
 
+            // The policy API does not yet configure a grace period, so
+            // unauthorized connections are terminated immediately unless the
+            // server overrides it.
+            let termination_grace_period = servers.get(&meta).termination_grace_period;
+
             Ok(ServerPolicy {
                 protocol,
                 meta,
                 local_rate_limit: Arc::new(local_rate_limit),
+                termination_grace_period,
             })
         }
     }
@@ -1,7 +1,9 @@
 use futures::prelude::*;
-use linkerd_duplex::Duplex;
+use linkerd_duplex::{Closed, Duplex, HalfClose, Side};
 use linkerd_error::{Error, Result};
-use linkerd_stack::layer;
+use linkerd_io as io;
+use linkerd_metrics::prom;
+use linkerd_stack::{layer, ExtractParam, NewService};
 use std::{
     pin::Pin,
     task::{Context, Poll},
@@ -14,6 +16,67 @@ pub struct Forward<C> {
     connect: C,
 }
 
+/// Like [`Forward`], but splices data between connections' sockets when
+/// enabled and both connections permit it.
+///
+/// See [`linkerd_duplex::splice`].
+#[derive(Clone, Debug)]
+pub struct SpliceForward<C> {
+    connect: C,
+    enabled: bool,
+    half_close: HalfClose,
+    metrics: CloseMetrics,
+}
+
+/// Builds a [`SpliceForward`] for each target, configured with the
+/// [`HalfClose`] mode extracted from the target.
+#[derive(Clone, Debug)]
+pub struct NewSpliceForward<X, N> {
+    inner: N,
+    extract: X,
+    enabled: bool,
+    metrics: CloseMetrics,
+}
+
+/// Counts forwarded connections by how they were closed.
+#[derive(Clone, Debug, Default)]
+pub struct CloseMetrics {
+    closes: prom::Family<CloseLabels, prom::Counter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct CloseLabels {
+    /// The peer that closed its half of the connection first.
+    first: CloseSide,
+    half_close: HalfCloseMode,
+    /// Whether the other half was closed by its peer or by the proxy.
+    closed_by: ClosedBy,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum CloseSide {
+    client,
+    server,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum HalfCloseMode {
+    propagate,
+    couple,
+    linger,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum ClosedBy {
+    peer,
+    proxy,
+}
+
+// === impl Forward ===
+
 impl<C> Forward<C> {
     fn new(connect: C) -> Self {
         Self { connect }
@@ -46,7 +109,142 @@ where
             self.connect
                 .call(())
                 .err_into::<Error>()
-                .and_then(|dst_io| Duplex::new(src_io, dst_io).err_into::<Error>()),
+                .and_then(|dst_io| {
+                    Duplex::new(src_io, dst_io)
+                        .map_ok(|_: Closed| ())
+                        .err_into::<Error>()
+                }),
+        )
+    }
+}
+
+// === impl SpliceForward ===
+
+impl<C> SpliceForward<C> {
+    fn new(connect: C, enabled: bool, half_close: HalfClose, metrics: CloseMetrics) -> Self {
+        Self {
+            connect,
+            enabled,
+            half_close,
+            metrics,
+        }
+    }
+
+    /// Forwards connections, propagating each peer's half-close to the other.
+    pub fn layer(
+        enabled: bool,
+        metrics: CloseMetrics,
+    ) -> impl layer::Layer<C, Service = Self> + Clone {
+        layer::mk(move |connect| Self::new(connect, enabled, HalfClose::Propagate, metrics.clone()))
+    }
+}
+
+impl<C, I> Service<I> for SpliceForward<C>
+where
+    I: io::AsyncRead + io::AsyncWrite + io::Splice + Send + Unpin + 'static,
+    C: tower::Service<()> + Send + 'static,
+    C::Error: Into<Error>,
+    C::Future: Send + 'static,
+    C::Response: io::AsyncRead + io::AsyncWrite + io::Splice + Send + Unpin + 'static,
+{
+    type Response = ();
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), self::Error>> {
+        self.connect.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, src_io: I) -> Self::Future {
+        let enabled = self.enabled;
+        let half_close = self.half_close;
+        let metrics = self.metrics.clone();
+        Box::pin(
+            self.connect
+                .call(())
+                .err_into::<Error>()
+                .and_then(move |dst_io| {
+                    let closed = if enabled {
+                        linkerd_duplex::splice(src_io, dst_io, half_close).left_future()
+                    } else {
+                        Duplex::new(src_io, dst_io)
+                            .with_half_close(half_close)
+                            .right_future()
+                    };
+                    closed
+                        .map_ok(move |closed| metrics.record(half_close, closed))
+                        .err_into::<Error>()
+                }),
+        )
+    }
+}
+
+// === impl NewSpliceForward ===
+
+impl<X: Clone, N> NewSpliceForward<X, N> {
+    pub fn layer_via(
+        extract: X,
+        enabled: bool,
+        metrics: CloseMetrics,
+    ) -> impl layer::Layer<N, Service = Self> + Clone {
+        layer::mk(move |inner| Self {
+            inner,
+            extract: extract.clone(),
+            enabled,
+            metrics: metrics.clone(),
+        })
+    }
+}
+
+impl<T, X, N> NewService<T> for NewSpliceForward<X, N>
+where
+    X: ExtractParam<HalfClose, T>,
+    N: NewService<T>,
+{
+    type Service = SpliceForward<N::Service>;
+
+    fn new_service(&self, target: T) -> Self::Service {
+        let half_close = self.extract.extract_param(&target);
+        SpliceForward::new(
+            self.inner.new_service(target),
+            self.enabled,
+            half_close,
+            self.metrics.clone(),
         )
     }
 }
+
+// === impl CloseMetrics ===
+
+impl CloseMetrics {
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let closes = prom::Family::default();
+        registry.register(
+            "forward_close",
+            "The number of forwarded connections, by the peer that closed its half of the connection first and how the other half was closed",
+            closes.clone(),
+        );
+        Self { closes }
+    }
+
+    fn record(&self, half_close: HalfClose, closed: Closed) {
+        let labels = CloseLabels {
+            first: match closed.first {
+                Side::Client => CloseSide::client,
+                Side::Server => CloseSide::server,
+            },
+            half_close: match half_close {
+                HalfClose::Propagate => HalfCloseMode::propagate,
+                HalfClose::Couple => HalfCloseMode::couple,
+                HalfClose::Linger(_) => HalfCloseMode::linger,
+            },
+            closed_by: if closed.forced {
+                ClosedBy::proxy
+            } else {
+                ClosedBy::peer
+            },
+        };
+        self.closes.get_or_create(&labels).inc();
+    }
+}
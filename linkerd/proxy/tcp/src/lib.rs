@@ -4,4 +4,8 @@
 pub mod balance;
 pub mod forward;
 
-pub use self::{balance::NewBalance, forward::Forward};
+pub use self::{
+    balance::NewBalance,
+    forward::{CloseMetrics, Forward, NewSpliceForward, SpliceForward},
+};
+pub use linkerd_duplex::{DuplexError, HalfClose, Op, Side};
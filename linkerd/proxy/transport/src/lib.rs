@@ -1,4 +1,4 @@
-//! Utilities for use TCP servers & clients.
+//! Utilities for use TCP servers & clients and UDP forwarding.
 //!
 //! Uses unsafe code to interact with socket options for SO_ORIGINAL_DST.
 
@@ -13,6 +13,7 @@ pub mod addrs;
 mod connect;
 pub mod listen;
 pub mod orig_dst;
+pub mod udp;
 
 pub use self::{
     addrs::{
@@ -20,7 +21,7 @@ pub use self::{
     },
     connect::ConnectTcp,
     listen::{Bind, BindTcp},
-    orig_dst::BindWithOrigDst,
+    orig_dst::{BindWithOrigDst, OrigDstFallback, OrigDstLookupError, OrigDstMetrics},
 };
 use linkerd_io as io;
 use socket2::TcpKeepalive;
@@ -20,6 +20,7 @@ pub trait Bind<T> {
         + io::AsyncWrite
         + io::Peek
         + io::PeerAddr
+        + io::Splice
         + fmt::Debug
         + Unpin
         + Send
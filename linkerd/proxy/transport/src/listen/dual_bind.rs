@@ -1,4 +1,9 @@
-use crate::{addrs::DualListenAddr, listen::Bind, Keepalive, ListenAddr, UserTimeout};
+use crate::{
+    addrs::DualListenAddr,
+    listen::Bind,
+    orig_dst::{BindWithOrigDst, OrigDstFallback, OrigDstMetrics},
+    Keepalive, ListenAddr, UserTimeout,
+};
 use futures::Stream;
 use linkerd_error::Result;
 use linkerd_stack::Param;
@@ -6,7 +11,7 @@ use std::{net::SocketAddr, pin::Pin};
 use tokio::net::TcpStream;
 use tokio_stream::StreamExt;
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct DualBind<B> {
     inner: B,
 }
@@ -24,6 +29,16 @@ impl<B> From<B> for DualBind<B> {
     }
 }
 
+impl<B> DualBind<BindWithOrigDst<B>> {
+    /// Configures how connections are handled when their original destination
+    /// address cannot be determined.
+    pub fn with_fallback(self, fallback: Option<OrigDstFallback>, metrics: OrigDstMetrics) -> Self {
+        Self {
+            inner: self.inner.with_fallback(fallback, metrics),
+        }
+    }
+}
+
 impl<T, B> Bind<T> for DualBind<B>
 where
     T: Param<DualListenAddr> + Param<Keepalive> + Param<UserTimeout> + Clone,
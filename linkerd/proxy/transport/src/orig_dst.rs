@@ -5,13 +5,18 @@ use crate::{
 use futures::prelude::*;
 use linkerd_error::Result;
 use linkerd_io as io;
+use linkerd_metrics::prom;
 use linkerd_stack::Param;
-use std::{net::SocketAddr, pin::Pin};
+use rangemap::RangeInclusiveSet;
+use std::{net::SocketAddr, pin::Pin, sync::Arc};
+use thiserror::Error;
 use tokio::net::TcpStream;
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct BindWithOrigDst<B = listen::BindTcp> {
     inner: B,
+    fallback: Option<OrigDstFallback>,
+    metrics: OrigDstMetrics,
 }
 
 #[derive(Clone, Debug)]
@@ -20,6 +25,46 @@ pub struct Addrs<A = listen::Addrs> {
     pub orig_dst: OrigDstAddr,
 }
 
+/// Configures how connections are handled when their original destination
+/// address cannot be determined (e.g. because the connection was not
+/// redirected to the proxy).
+///
+/// Connections accepted on an allowed local port use the local address as
+/// their original destination address. All other connections are refused.
+#[derive(Clone, Debug, Default)]
+pub struct OrigDstFallback {
+    pub ports: Arc<RangeInclusiveSet<u16>>,
+}
+
+/// Counts connections whose original destination address could not be
+/// determined.
+#[derive(Clone, Debug, Default)]
+pub struct OrigDstMetrics {
+    fallback: prom::Counter,
+    refused: prom::Counter,
+}
+
+#[derive(Debug, Error)]
+#[error("failed to determine original destination address of connection from {client} to {server}: {source}")]
+pub struct OrigDstLookupError {
+    client: ClientAddr,
+    server: ServerAddr,
+    #[source]
+    source: io::Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct LookupFailureLabels {
+    outcome: LookupFailureOutcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum LookupFailureOutcome {
+    fallback,
+    refused,
+}
+
 // === impl Addrs ===
 
 impl<A> Param<OrigDstAddr> for Addrs<A> {
@@ -63,14 +108,30 @@ where
 
 impl<B> From<B> for BindWithOrigDst<B> {
     fn from(inner: B) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            fallback: None,
+            metrics: OrigDstMetrics::default(),
+        }
+    }
+}
+
+impl<B> BindWithOrigDst<B> {
+    /// Configures how connections are handled when their original destination
+    /// address cannot be determined.
+    pub fn with_fallback(self, fallback: Option<OrigDstFallback>, metrics: OrigDstMetrics) -> Self {
+        Self {
+            fallback,
+            metrics,
+            ..self
+        }
     }
 }
 
 impl<T, B> Bind<T> for BindWithOrigDst<B>
 where
     B: Bind<T, Io = TcpStream> + 'static,
-    B::Addrs: Param<Remote<ClientAddr>>,
+    B::Addrs: Param<Remote<ClientAddr>> + Param<Local<ServerAddr>>,
 {
     type Addrs = Addrs<B::Addrs>;
     type BoundAddrs = B::BoundAddrs;
@@ -79,12 +140,24 @@ where
         Pin<Box<dyn Stream<Item = Result<(Self::Addrs, TcpStream)>> + Send + Sync + 'static>>;
 
     fn bind(self, t: &T) -> Result<(Self::BoundAddrs, Self::Incoming)> {
-        let (addr, incoming) = self.inner.bind(t)?;
+        let Self {
+            inner,
+            fallback,
+            metrics,
+        } = self;
+        let (addr, incoming) = inner.bind(t)?;
 
-        let incoming = incoming.map(|res| {
+        let incoming = incoming.map(move |res| {
             let (inner, tcp) = res?;
             let Remote(client_addr) = inner.param();
-            let (orig_dst, tcp) = orig_dst(tcp, client_addr)?;
+            let (lookup, tcp) = orig_dst(tcp, client_addr)?;
+            let orig_dst = match lookup {
+                Ok(orig_dst) => orig_dst,
+                Err(error) => {
+                    let server = inner.param();
+                    lookup_failed(error, client_addr, server, fallback.as_ref(), &metrics)?
+                }
+            };
             let addrs = Addrs { inner, orig_dst };
             Ok((addrs, tcp))
         });
@@ -93,7 +166,12 @@ where
     }
 }
 
-fn orig_dst(sock: TcpStream, client_addr: ClientAddr) -> io::Result<(OrigDstAddr, TcpStream)> {
+/// Looks up the socket's original destination address. The outer result fails
+/// only if the socket cannot be used.
+fn orig_dst(
+    sock: TcpStream,
+    client_addr: ClientAddr,
+) -> io::Result<(io::Result<OrigDstAddr>, TcpStream)> {
     let sock = {
         let stream = tokio::net::TcpStream::into_std(sock)?;
         socket2::Socket::from(stream)
@@ -103,16 +181,190 @@ fn orig_dst(sock: TcpStream, client_addr: ClientAddr) -> io::Result<(OrigDstAddr
         // IPv4-mapped IPv6 addresses are unwrapped by BindTcp::bind() and received here as
         // SocketAddr::V4. We must call getsockopt with IPv4 constants (via
         // orig_dst_addr_v4) even if it originally was an IPv6
-        ClientAddr(SocketAddr::V4(_)) => sock.original_dst_v4()?,
-        ClientAddr(SocketAddr::V6(_)) => sock.original_dst_v6()?,
+        ClientAddr(SocketAddr::V4(_)) => sock.original_dst_v4(),
+        ClientAddr(SocketAddr::V6(_)) => sock.original_dst_v6(),
     };
+    // The connection may have been redirected by rules for the other family
+    // (e.g. on a dual-stack socket), so fall back to looking it up there.
+    let orig_dst = orig_dst.or_else(|error| {
+        let other = match client_addr {
+            ClientAddr(SocketAddr::V4(_)) => sock.original_dst_v6(),
+            ClientAddr(SocketAddr::V6(_)) => sock.original_dst_v4(),
+        };
+        other.map_err(|_| error)
+    });
 
-    let orig_dst = orig_dst.as_socket().ok_or(io::Error::new(
-        io::ErrorKind::InvalidInput,
-        "Invalid address format",
-    ))?;
+    let orig_dst = orig_dst.and_then(|addr| {
+        addr.as_socket().map(OrigDstAddr).ok_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Invalid address format",
+        ))
+    });
 
     let stream: std::net::TcpStream = socket2::Socket::into(sock);
     let stream = tokio::net::TcpStream::from_std(stream)?;
-    Ok((OrigDstAddr(orig_dst), stream))
+    Ok((orig_dst, stream))
+}
+
+/// Handles a failed original destination lookup, either falling back to the
+/// connection's local address or refusing the connection.
+fn lookup_failed(
+    source: io::Error,
+    client: ClientAddr,
+    Local(server): Local<ServerAddr>,
+    fallback: Option<&OrigDstFallback>,
+    metrics: &OrigDstMetrics,
+) -> Result<OrigDstAddr, OrigDstLookupError> {
+    if let Some(fallback) = fallback {
+        if fallback.ports.contains(&server.port()) {
+            tracing::debug!(
+                %client,
+                %server,
+                error = %source,
+                "Original destination lookup failed; using local address",
+            );
+            metrics.fallback.inc();
+            return Ok(OrigDstAddr(server.into()));
+        }
+    }
+
+    metrics.refused.inc();
+    Err(OrigDstLookupError {
+        client,
+        server,
+        source,
+    })
+}
+
+// === impl OrigDstMetrics ===
+
+impl OrigDstMetrics {
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let failures = prom::Family::<LookupFailureLabels, prom::Counter>::default();
+        registry.register(
+            "orig_dst_lookup_failures",
+            "The number of accepted connections whose original destination address could not be determined",
+            failures.clone(),
+        );
+        let counter = |outcome| {
+            failures
+                .get_or_create(&LookupFailureLabels { outcome })
+                .clone()
+        };
+        Self {
+            fallback: counter(LookupFailureOutcome::fallback),
+            refused: counter(LookupFailureOutcome::refused),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> ClientAddr {
+        ClientAddr(([192, 0, 2, 10], 41_000).into())
+    }
+
+    fn server(port: u16) -> Local<ServerAddr> {
+        Local(ServerAddr(([192, 0, 2, 20], port).into()))
+    }
+
+    fn not_redirected() -> io::Error {
+        io::Error::from(io::ErrorKind::NotFound)
+    }
+
+    fn fallback(ports: impl IntoIterator<Item = std::ops::RangeInclusive<u16>>) -> OrigDstFallback {
+        OrigDstFallback {
+            ports: Arc::new(ports.into_iter().collect()),
+        }
+    }
+
+    #[test]
+    fn refused_without_fallback() {
+        let metrics = OrigDstMetrics::default();
+        let error = lookup_failed(not_redirected(), client(), server(4143), None, &metrics)
+            .expect_err("lookup must fail without a fallback");
+        assert_eq!(error.server, server(4143).0);
+        assert_eq!(error.source.kind(), io::ErrorKind::NotFound);
+        assert_eq!(metrics.fallback.get(), 0);
+        assert_eq!(metrics.refused.get(), 1);
+    }
+
+    #[test]
+    fn falls_back_on_allowed_ports() {
+        let metrics = OrigDstMetrics::default();
+        let fallback = fallback([4143..=4143, 8000..=8999]);
+
+        for port in [4143, 8000, 8080] {
+            let OrigDstAddr(addr) = lookup_failed(
+                not_redirected(),
+                client(),
+                server(port),
+                Some(&fallback),
+                &metrics,
+            )
+            .expect("lookup must fall back on allowed ports");
+            assert_eq!(addr, server(port).0.into());
+        }
+        assert_eq!(metrics.fallback.get(), 3);
+        assert_eq!(metrics.refused.get(), 0);
+    }
+
+    #[test]
+    fn refused_on_disallowed_ports() {
+        let metrics = OrigDstMetrics::default();
+        let fallback = fallback([4143..=4143]);
+
+        for port in [4140, 9000] {
+            lookup_failed(
+                not_redirected(),
+                client(),
+                server(port),
+                Some(&fallback),
+                &metrics,
+            )
+            .expect_err("lookup must fail on disallowed ports");
+        }
+
+        // An empty allowlist permits no fallbacks.
+        lookup_failed(
+            not_redirected(),
+            client(),
+            server(4143),
+            Some(&OrigDstFallback::default()),
+            &metrics,
+        )
+        .expect_err("lookup must fail with an empty allowlist");
+
+        assert_eq!(metrics.fallback.get(), 0);
+        assert_eq!(metrics.refused.get(), 3);
+    }
+
+    #[test]
+    fn metrics_by_outcome() {
+        let mut registry = prom::Registry::default();
+        let metrics = OrigDstMetrics::register(&mut registry);
+        let fallback = fallback([4143..=4143]);
+        for port in [4143, 4143, 4140] {
+            let _ = lookup_failed(
+                not_redirected(),
+                client(),
+                server(port),
+                Some(&fallback),
+                &metrics,
+            );
+        }
+
+        let mut text = String::new();
+        prom::encoding::text::encode(&mut text, &registry).unwrap();
+        assert!(
+            text.contains("orig_dst_lookup_failures_total{outcome=\"fallback\"} 2"),
+            "{text}"
+        );
+        assert!(
+            text.contains("orig_dst_lookup_failures_total{outcome=\"refused\"} 1"),
+            "{text}"
+        );
+    }
 }
@@ -0,0 +1,646 @@
+//! Forwards redirected UDP datagrams to their original destinations.
+//!
+//! Datagrams are redirected to the forwarder's socket (e.g. by an iptables
+//! `TPROXY` rule) and their original destination addresses are recovered from
+//! `IP_ORIGDSTADDR` control messages. Each client/destination pair is tracked
+//! as a session with its own upstream socket so that replies can be returned to
+//! the client. Sessions expire after a period of inactivity.
+
+use crate::addrs::{ClientAddr, OrigDstAddr};
+use linkerd_metrics::prom;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    io::Interest,
+    net::UdpSocket,
+    sync::mpsc,
+    time::{self, Instant},
+};
+use tracing::{debug, info, Instrument};
+
+/// The largest datagram that may be forwarded.
+const MAX_DATAGRAM_LEN: usize = 64 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The address on which redirected datagrams are received.
+    pub addr: SocketAddr,
+
+    /// The duration after which a session with no traffic is dropped.
+    pub idle_timeout: Duration,
+
+    /// The maximum number of concurrent sessions.
+    pub max_sessions: usize,
+
+    /// The number of datagrams that may be buffered for each session before
+    /// datagrams are dropped.
+    pub session_capacity: usize,
+}
+
+/// Receives redirected datagrams and forwards them to their original
+/// destinations.
+#[derive(Debug)]
+pub struct UdpForwarder {
+    socket: Arc<UdpSocket>,
+    local_addr: SocketAddr,
+    transparent: bool,
+    sessions: Sessions,
+}
+
+/// Counts the datagrams and bytes forwarded, by original destination port.
+#[derive(Clone, Debug, Default)]
+pub struct UdpMetrics {
+    datagrams: prom::Family<DatagramLabels, prom::Counter>,
+    bytes: prom::Family<DatagramLabels, prom::Counter>,
+    dropped: prom::Family<DropLabels, prom::Counter>,
+    sessions: prom::Gauge,
+}
+
+#[derive(Debug)]
+struct Sessions {
+    sessions: HashMap<SessionKey, mpsc::Sender<Vec<u8>>>,
+    config: Config,
+    metrics: UdpMetrics,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct SessionKey {
+    client: ClientAddr,
+    orig_dst: OrigDstAddr,
+}
+
+/// The socket from which replies are sent to a client.
+#[derive(Debug)]
+enum Reply {
+    /// A transparent socket bound to the original destination address, so
+    /// that replies appear to be sent by the original destination.
+    Transparent(UdpSocket),
+    /// The forwarder's socket.
+    Listener(Arc<UdpSocket>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct DatagramLabels {
+    port: u16,
+    direction: Direction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum Direction {
+    /// Datagrams sent by clients to their original destinations.
+    upstream,
+    /// Datagrams sent by original destinations back to clients.
+    downstream,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelSet)]
+struct DropLabels {
+    port: u16,
+    reason: DropReason,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum DropReason {
+    /// The datagram's original destination could not be determined.
+    no_orig_dst,
+    /// The maximum number of sessions has been reached.
+    session_limit,
+    /// The session's buffer is full.
+    session_full,
+}
+
+// === impl UdpForwarder ===
+
+impl UdpForwarder {
+    /// Binds a socket that receives redirected datagrams.
+    ///
+    /// The socket is made transparent if the process is permitted to do so.
+    /// Otherwise, replies are sent from the forwarder's own address.
+    pub fn bind(config: Config, metrics: UdpMetrics) -> std::io::Result<Self> {
+        let socket = socket2::Socket::new(
+            socket2::Domain::for_address(config.addr),
+            socket2::Type::DGRAM,
+            Some(socket2::Protocol::UDP),
+        )?;
+        let transparent = sys::configure(&socket, config.addr)?;
+        socket.set_nonblocking(true)?;
+        socket.bind(&config.addr.into())?;
+        let socket = UdpSocket::from_std(socket.into())?;
+        let local_addr = socket.local_addr()?;
+        debug!(addr = %local_addr, transparent, "Bound UDP forwarder");
+
+        Ok(Self {
+            socket: Arc::new(socket),
+            local_addr,
+            transparent,
+            sessions: Sessions {
+                sessions: HashMap::default(),
+                config,
+                metrics,
+            },
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Forwards datagrams until `shutdown` completes. All sessions are closed
+    /// when the forwarder stops.
+    pub async fn serve(mut self, shutdown: impl std::future::Future) {
+        tokio::pin!(shutdown);
+        let mut buf = vec![0; MAX_DATAGRAM_LEN];
+        loop {
+            let recv = self
+                .socket
+                .async_io(Interest::READABLE, || sys::recv(&self.socket, &mut buf));
+            let (len, client, orig_dst) = tokio::select! {
+                res = recv => match res {
+                    Ok(recvd) => recvd,
+                    Err(error) => {
+                        info!(%error, "Failed to receive datagram");
+                        continue;
+                    }
+                },
+                _ = &mut shutdown => {
+                    debug!("Shutting down UDP forwarder");
+                    return;
+                }
+            };
+
+            // Datagrams that were not redirected would be forwarded back to
+            // the forwarder itself.
+            let orig_dst = match orig_dst {
+                Some(addr) if addr != self.local_addr => OrigDstAddr(addr),
+                _ => {
+                    debug!(client.addr = %client, "Datagram was not redirected");
+                    self.sessions
+                        .metrics
+                        .drop(self.local_addr.port(), DropReason::no_orig_dst);
+                    continue;
+                }
+            };
+
+            let reply = self.reply(orig_dst);
+            self.sessions
+                .dispatch(ClientAddr(client), orig_dst, buf[..len].to_vec(), reply);
+        }
+    }
+
+    /// Returns a closure that obtains the socket used to send replies for a
+    /// new session.
+    fn reply(&self, OrigDstAddr(addr): OrigDstAddr) -> impl FnOnce() -> Reply {
+        let transparent = self.transparent;
+        let socket = self.socket.clone();
+        move || {
+            if transparent {
+                match sys::bind_transparent(addr) {
+                    Ok(socket) => return Reply::Transparent(socket),
+                    Err(error) => {
+                        debug!(%addr, %error, "Failed to bind transparent reply socket")
+                    }
+                }
+            }
+            Reply::Listener(socket)
+        }
+    }
+}
+
+// === impl Sessions ===
+
+impl Sessions {
+    /// Dispatches a datagram to the client's session for the original
+    /// destination, creating the session if necessary.
+    fn dispatch(
+        &mut self,
+        client: ClientAddr,
+        orig_dst: OrigDstAddr,
+        datagram: Vec<u8>,
+        mk_reply: impl FnOnce() -> Reply,
+    ) {
+        let key = SessionKey { client, orig_dst };
+        let port = orig_dst.0.port();
+
+        // Sessions that have expired are only removed when their key is
+        // reused or when capacity is needed.
+        if let Entry::Occupied(entry) = self.sessions.entry(key) {
+            if entry.get().is_closed() {
+                entry.remove();
+            }
+        }
+        if !self.sessions.contains_key(&key) && self.sessions.len() >= self.config.max_sessions {
+            self.sessions.retain(|_, tx| !tx.is_closed());
+            if self.sessions.len() >= self.config.max_sessions {
+                debug!(%client, %orig_dst, "Session limit reached");
+                self.metrics.drop(port, DropReason::session_limit);
+                return;
+            }
+        }
+
+        let tx = self.sessions.entry(key).or_insert_with(|| {
+            let (tx, rx) = mpsc::channel(self.config.session_capacity.max(1));
+            let idle_timeout = self.config.idle_timeout;
+            let metrics = self.metrics.clone();
+            let reply = mk_reply();
+            metrics.sessions.inc();
+            tokio::spawn(
+                async move {
+                    debug!("Session started");
+                    if let Err(error) = run_session(key, rx, reply, idle_timeout, &metrics).await {
+                        info!(%error, "Session failed");
+                    }
+                    metrics.sessions.dec();
+                }
+                .instrument(
+                    tracing::debug_span!("udp", client.addr = %client, orig_dst = %orig_dst),
+                ),
+            );
+            tx
+        });
+
+        if tx.try_send(datagram).is_err() {
+            debug!(%client, %orig_dst, "Session buffer full");
+            self.metrics.drop(port, DropReason::session_full);
+        }
+    }
+}
+
+/// Forwards a session's datagrams to the original destination and its replies
+/// to the client until the session is idle for `idle_timeout`.
+async fn run_session(
+    SessionKey {
+        client: ClientAddr(client),
+        orig_dst: OrigDstAddr(orig_dst),
+    }: SessionKey,
+    mut rx: mpsc::Receiver<Vec<u8>>,
+    reply: Reply,
+    idle_timeout: Duration,
+    metrics: &UdpMetrics,
+) -> std::io::Result<()> {
+    let unspecified = match orig_dst {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    };
+    let upstream = UdpSocket::bind(unspecified).await?;
+    upstream.connect(orig_dst).await?;
+
+    let port = orig_dst.port();
+    let mut buf = vec![0; MAX_DATAGRAM_LEN];
+    let idle = time::sleep(idle_timeout);
+    tokio::pin!(idle);
+    loop {
+        tokio::select! {
+            datagram = rx.recv() => {
+                // The forwarder has shut down.
+                let Some(datagram) = datagram else { return Ok(()) };
+                let sz = upstream.send(&datagram).await?;
+                metrics.forwarded(port, Direction::upstream, sz);
+            }
+            res = upstream.recv(&mut buf) => {
+                let len = res?;
+                let sz = reply.send_to(&buf[..len], client).await?;
+                metrics.forwarded(port, Direction::downstream, sz);
+            }
+            _ = &mut idle => {
+                debug!("Session idle");
+                return Ok(());
+            }
+        }
+        idle.as_mut().reset(Instant::now() + idle_timeout);
+    }
+}
+
+// === impl Reply ===
+
+impl Reply {
+    async fn send_to(&self, buf: &[u8], client: SocketAddr) -> std::io::Result<usize> {
+        match self {
+            Self::Transparent(socket) => socket.send_to(buf, client).await,
+            Self::Listener(socket) => socket.send_to(buf, client).await,
+        }
+    }
+}
+
+// === impl UdpMetrics ===
+
+impl UdpMetrics {
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let datagrams = prom::Family::default();
+        registry.register(
+            "datagrams",
+            "The number of UDP datagrams forwarded",
+            datagrams.clone(),
+        );
+
+        let bytes = prom::Family::default();
+        registry.register_with_unit(
+            "forwarded",
+            "The number of UDP payload bytes forwarded",
+            prom::Unit::Bytes,
+            bytes.clone(),
+        );
+
+        let dropped = prom::Family::default();
+        registry.register(
+            "dropped_datagrams",
+            "The number of UDP datagrams dropped without being forwarded",
+            dropped.clone(),
+        );
+
+        let sessions = prom::Gauge::default();
+        registry.register(
+            "sessions",
+            "The number of open UDP sessions",
+            sessions.clone(),
+        );
+
+        Self {
+            datagrams,
+            bytes,
+            dropped,
+            sessions,
+        }
+    }
+
+    fn forwarded(&self, port: u16, direction: Direction, sz: usize) {
+        let labels = DatagramLabels { port, direction };
+        self.datagrams.get_or_create(&labels).inc();
+        self.bytes.get_or_create(&labels).inc_by(sz as u64);
+    }
+
+    fn drop(&self, port: u16, reason: DropReason) {
+        self.dropped
+            .get_or_create(&DropLabels { port, reason })
+            .inc();
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use nix::sys::socket::{
+        recvmsg, setsockopt, sockopt, ControlMessageOwned, MsgFlags, SockaddrStorage,
+    };
+    use std::{
+        io,
+        net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+        os::fd::AsRawFd,
+    };
+    use tokio::net::UdpSocket;
+
+    /// Configures the socket to report the original destination address of
+    /// each datagram and attempts to make the socket transparent, returning
+    /// whether it is.
+    pub(super) fn configure(socket: &socket2::Socket, addr: SocketAddr) -> io::Result<bool> {
+        let fd = socket.as_raw_fd();
+        match addr {
+            SocketAddr::V4(_) => setsockopt(fd, sockopt::Ipv4OrigDstAddr, &true)?,
+            SocketAddr::V6(_) => setsockopt(fd, sockopt::Ipv6OrigDstAddr, &true)?,
+        }
+        Ok(setsockopt(fd, sockopt::IpTransparent, &true).is_ok())
+    }
+
+    /// Receives a datagram, returning its length, the client's address, and
+    /// the datagram's original destination address, if known.
+    pub(super) fn recv(
+        socket: &UdpSocket,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr, Option<SocketAddr>)> {
+        let mut cmsgs = nix::cmsg_space!(libc::sockaddr_in6);
+        let mut iov = [io::IoSliceMut::new(buf)];
+        let msg = recvmsg::<SockaddrStorage>(
+            socket.as_raw_fd(),
+            &mut iov,
+            Some(&mut cmsgs),
+            MsgFlags::empty(),
+        )?;
+
+        let client = msg
+            .address
+            .as_ref()
+            .and_then(|addr| {
+                if let Some(sin) = addr.as_sockaddr_in() {
+                    return Some(SocketAddr::from(SocketAddrV4::from(*sin)));
+                }
+                addr.as_sockaddr_in6()
+                    .map(|sin6| SocketAddr::from(SocketAddrV6::from(*sin6)))
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing client address"))?;
+
+        let orig_dst = msg.cmsgs().find_map(|cmsg| match cmsg {
+            ControlMessageOwned::Ipv4OrigDstAddr(sin) => Some(SocketAddr::from(SocketAddrV4::new(
+                Ipv4Addr::from(sin.sin_addr.s_addr.to_ne_bytes()),
+                u16::from_be(sin.sin_port),
+            ))),
+            ControlMessageOwned::Ipv6OrigDstAddr(sin6) => {
+                Some(SocketAddr::from(SocketAddrV6::new(
+                    Ipv6Addr::from(sin6.sin6_addr.s6_addr),
+                    u16::from_be(sin6.sin6_port),
+                    sin6.sin6_flowinfo,
+                    sin6.sin6_scope_id,
+                )))
+            }
+            _ => None,
+        });
+
+        Ok((msg.bytes, client, orig_dst))
+    }
+
+    /// Binds a transparent socket to a (possibly non-local) address.
+    pub(super) fn bind_transparent(addr: SocketAddr) -> io::Result<UdpSocket> {
+        let socket = socket2::Socket::new(
+            socket2::Domain::for_address(addr),
+            socket2::Type::DGRAM,
+            Some(socket2::Protocol::UDP),
+        )?;
+        socket.set_reuse_address(true)?;
+        setsockopt(socket.as_raw_fd(), sockopt::IpTransparent, &true)?;
+        socket.set_nonblocking(true)?;
+        socket.bind(&addr.into())?;
+        UdpSocket::from_std(socket.into())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::{io, net::SocketAddr};
+    use tokio::net::UdpSocket;
+
+    pub(super) fn configure(_: &socket2::Socket, _: SocketAddr) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    pub(super) fn recv(
+        socket: &UdpSocket,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr, Option<SocketAddr>)> {
+        let (len, client) = socket.try_recv_from(buf)?;
+        Ok((len, client, None))
+    }
+
+    pub(super) fn bind_transparent(_: SocketAddr) -> io::Result<UdpSocket> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "transparent sockets are only supported on Linux",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_sessions: usize) -> Config {
+        Config {
+            addr: ([127, 0, 0, 1], 0).into(),
+            idle_timeout: Duration::from_secs(60),
+            max_sessions,
+            session_capacity: 4,
+        }
+    }
+
+    async fn echo_server() -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = vec![0; MAX_DATAGRAM_LEN];
+            loop {
+                let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+                socket.send_to(&buf[..len], from).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    async fn client() -> (UdpSocket, ClientAddr) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = ClientAddr(socket.local_addr().unwrap());
+        (socket, addr)
+    }
+
+    fn encode(registry: &prom::Registry) -> String {
+        let mut text = String::new();
+        prom::encoding::text::encode(&mut text, registry).unwrap();
+        text
+    }
+
+    #[tokio::test]
+    async fn forwards_datagrams_and_replies() {
+        let mut registry = prom::Registry::default();
+        let metrics = UdpMetrics::register(&mut registry);
+        let mut fwd = UdpForwarder::bind(config(10), metrics).unwrap();
+        let server = echo_server().await;
+        let (client, client_addr) = client().await;
+
+        let reply = fwd.reply(OrigDstAddr(server));
+        fwd.sessions
+            .dispatch(client_addr, OrigDstAddr(server), b"ping".to_vec(), reply);
+
+        let mut buf = [0; 16];
+        let (len, from) = client.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"ping");
+        // Without a transparent socket, replies are sent from the forwarder.
+        assert_eq!(from, fwd.local_addr());
+
+        let text = encode(&registry);
+        let port = server.port();
+        for line in [
+            format!("datagrams_total{{port=\"{port}\",direction=\"upstream\"}} 1"),
+            format!("datagrams_total{{port=\"{port}\",direction=\"downstream\"}} 1"),
+            format!("forwarded_bytes_total{{port=\"{port}\",direction=\"upstream\"}} 4"),
+            "sessions 1".to_string(),
+        ] {
+            assert!(text.contains(&line), "{line} not in:\n{text}");
+        }
+    }
+
+    #[tokio::test]
+    async fn sessions_are_limited() {
+        let mut registry = prom::Registry::default();
+        let metrics = UdpMetrics::register(&mut registry);
+        let mut fwd = UdpForwarder::bind(config(1), metrics).unwrap();
+        let server = echo_server().await;
+        let (client0, addr0) = client().await;
+        let (_client1, addr1) = client().await;
+
+        for addr in [addr0, addr1] {
+            let reply = fwd.reply(OrigDstAddr(server));
+            fwd.sessions
+                .dispatch(addr, OrigDstAddr(server), b"ping".to_vec(), reply);
+        }
+        assert_eq!(fwd.sessions.sessions.len(), 1);
+
+        let mut buf = [0; 16];
+        let len = client0.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"ping");
+
+        let text = encode(&registry);
+        let line = format!(
+            "dropped_datagrams_total{{port=\"{}\",reason=\"session_limit\"}} 1",
+            server.port()
+        );
+        assert!(text.contains(&line), "{line} not in:\n{text}");
+    }
+
+    #[tokio::test]
+    async fn idle_sessions_expire() {
+        let metrics = UdpMetrics::default();
+        let mut config = config(1);
+        config.idle_timeout = Duration::from_millis(100);
+        let mut fwd = UdpForwarder::bind(config, metrics.clone()).unwrap();
+        let server = echo_server().await;
+        let (client0, addr0) = client().await;
+        let (client1, addr1) = client().await;
+
+        let reply = fwd.reply(OrigDstAddr(server));
+        fwd.sessions
+            .dispatch(addr0, OrigDstAddr(server), b"ping".to_vec(), reply);
+        let mut buf = [0; 16];
+        client0.recv(&mut buf).await.unwrap();
+        assert_eq!(metrics.sessions.get(), 1);
+
+        time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(metrics.sessions.get(), 0);
+
+        // The expired session no longer counts against the limit.
+        let reply = fwd.reply(OrigDstAddr(server));
+        fwd.sessions
+            .dispatch(addr1, OrigDstAddr(server), b"pong".to_vec(), reply);
+        let len = client1.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"pong");
+        assert_eq!(fwd.sessions.sessions.len(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn drops_datagrams_that_were_not_redirected() {
+        let metrics = UdpMetrics::default();
+        let fwd = UdpForwarder::bind(config(10), metrics.clone()).unwrap();
+        let addr = fwd.local_addr();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let task = tokio::spawn(fwd.serve(shutdown_rx));
+
+        let (client, _) = client().await;
+        client.send_to(b"ping", addr).await.unwrap();
+
+        let dropped = metrics.dropped.get_or_create(&DropLabels {
+            port: addr.port(),
+            reason: DropReason::no_orig_dst,
+        });
+        time::timeout(Duration::from_secs(5), async {
+            while dropped.get() == 0 {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("datagram must be dropped");
+
+        drop(shutdown_tx);
+        task.await.unwrap();
+    }
+}
@@ -19,7 +19,7 @@ pub struct Route {
     timeout: Option<Duration>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum RequestMatch {
     All(Vec<RequestMatch>),
     Any(Vec<RequestMatch>),
@@ -30,11 +30,12 @@ pub enum RequestMatch {
     Default,
 }
 
-/// Wraps a `regex::Regex` to implement `PartialEq` via the original string.
+/// Wraps a `regex::Regex` to implement `PartialEq` and `Hash` via the original
+/// string.
 #[derive(Clone, Debug)]
 pub struct Regex(regex::Regex);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Hash)]
 pub struct ResponseClass {
     is_failure: bool,
     match_: ResponseMatch,
@@ -43,7 +44,7 @@ pub struct ResponseClass {
 #[derive(Clone, Default)]
 pub struct ResponseClasses(Arc<Vec<ResponseClass>>);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Hash)]
 pub enum ResponseMatch {
     All(Vec<ResponseMatch>),
     Any(Vec<ResponseMatch>),
@@ -171,9 +172,12 @@ impl PartialEq for ResponseClasses {
 
 impl Eq for ResponseClasses {}
 
+// Classes are hashed by value so that hashes are stable across processes.
+// Classes that are equal have the same contents, so this is consistent with
+// `PartialEq`.
 impl Hash for ResponseClasses {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        state.write_usize(Arc::as_ref(&self.0) as *const _ as usize);
+        self.0.hash(state);
     }
 }
 
@@ -214,10 +218,10 @@ impl PartialEq for Retries {
 
 impl Eq for Retries {}
 
+// The budget's state changes as it is used, so it is not hashed. Retries that
+// share a budget are equal, so this is consistent with `PartialEq`.
 impl Hash for Retries {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        state.write_usize(Arc::as_ref(&self.budget) as *const _ as usize);
-    }
+    fn hash<H: Hasher>(&self, _: &mut H) {}
 }
 
 // === impl Labels ===
@@ -232,7 +236,7 @@ impl Eq for Labels {}
 
 impl Hash for Labels {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        state.write_usize(Arc::as_ref(&self.0) as *const _ as usize);
+        self.0.hash(state);
     }
 }
 
@@ -257,3 +261,9 @@ impl PartialEq for Regex {
 }
 
 impl Eq for Regex {}
+
+impl Hash for Regex {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_str().hash(state);
+    }
+}
@@ -13,7 +13,7 @@ pub struct LoadShed<S> {
 }
 
 /// An error representing that a service is shedding load.
-#[derive(Debug, Error)]
+#[derive(Debug, Default, Error)]
 #[error("service unavailable")]
 pub struct LoadShedError(());
 
@@ -10,7 +10,9 @@ use std::{
     pin::Pin,
     str::FromStr,
     task::{Context, Poll},
+    time::Duration,
 };
+use tokio::time::Instant;
 use tracing::debug;
 
 /// Describes the authenticated identity of a remote server.
@@ -72,6 +74,7 @@ pub enum Connect<F, I, H: Service<I>, M> {
         #[pin]
         inner: Oneshot<H, I>,
         state: Option<(Conditional<(), NoClientTls>, M)>,
+        started: Instant,
     },
 }
 
@@ -79,6 +82,8 @@ pub enum Connect<F, I, H: Service<I>, M> {
 pub struct ConnectMeta<M> {
     pub socket: M,
     pub tls: Conditional<Option<NegotiatedProtocol>, NoClientTls>,
+    /// The time spent on the TLS handshake, if one was performed.
+    pub handshake: Option<Duration>,
 }
 
 // === impl ClientTls ===
@@ -164,17 +169,23 @@ where
                         Conditional::Some(tls) => self.set(Connect::Handshake {
                             inner: tls.oneshot(io),
                             state: Some((Conditional::Some(()), socket)),
+                            started: Instant::now(),
                         }),
                         Conditional::None(reason) => {
                             let meta = ConnectMeta {
                                 socket,
                                 tls: Conditional::None(reason),
+                                handshake: None,
                             };
                             return Poll::Ready(Ok((io::EitherIo::Left(io), meta)));
                         }
                     }
                 }
-                ConnectProj::Handshake { inner, state } => {
+                ConnectProj::Handshake {
+                    inner,
+                    state,
+                    started,
+                } => {
                     let (io, alpn) = futures::ready!(inner.try_poll(cx))?;
                     debug!(
                         alpn = alpn
@@ -186,6 +197,7 @@ where
                     let meta = ConnectMeta {
                         socket,
                         tls: tls.map(move |()| alpn),
+                        handshake: Some(Instant::now().saturating_duration_since(*started)),
                     };
                     return Poll::Ready(Ok((io::EitherIo::Right(io), meta)));
                 }
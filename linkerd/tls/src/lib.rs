@@ -2,6 +2,8 @@
 #![forbid(unsafe_code)]
 
 pub mod client;
+pub mod metrics;
+pub mod policy;
 pub mod server;
 
 pub use self::{
@@ -9,6 +11,8 @@ pub use self::{
         Client, ClientTls, ClientTlsLabels, ConditionalClientTls, ConditionalClientTlsLabels,
         ConnectMeta, NoClientTls, ServerId,
     },
+    metrics::HandshakeMetrics,
+    policy::{PolicyMode, TlsPolicy, TlsPolicyViolation},
     server::{
         ClientId, ConditionalServerTls, ConditionalServerTlsLabels, NewDetectRequiredSni,
         NewDetectTls, NoServerTls, NoSniFoundError, ServerTls, ServerTlsLabels,
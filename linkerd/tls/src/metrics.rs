@@ -0,0 +1,473 @@
+//! Metrics describing the TLS sessions established by the proxy.
+//!
+//! Every label is drawn from a fixed set of values so that the cardinality of
+//! these metrics is bounded, regardless of what peers negotiate. Connections
+//! that are passed through without being terminated are not recorded.
+
+use crate::policy::PolicyMode;
+use linkerd_metrics::prom::{
+    self,
+    encoding::{EncodeLabelSet, EncodeLabelValue, LabelValueEncoder},
+};
+use std::{fmt::Write, str::FromStr};
+
+/// Counts TLS handshakes by their negotiated parameters and failures by their
+/// cause.
+#[derive(Clone, Debug, Default)]
+pub struct HandshakeMetrics {
+    handshakes: prom::Family<HandshakeLabels, prom::Counter>,
+    failures: prom::Family<FailureLabels, prom::Counter>,
+    policy_violations: prom::Family<PolicyLabels, prom::Counter>,
+}
+
+/// Describes a successfully established TLS session.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Session {
+    pub version: ProtocolVersion,
+    pub cipher_suite: CipherSuite,
+    /// Whether the session was resumed rather than fully negotiated.
+    pub resumed: bool,
+    /// Whether the peer presented a verified identity.
+    pub peer_identity: bool,
+}
+
+/// The side of the connection on which a handshake was performed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Client,
+    Server,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProtocolVersion {
+    Tls12,
+    Tls13,
+    Other,
+}
+
+/// The cipher suites supported by the proxy's TLS backends.
+///
+/// Values use the same names as the `rustls_info` metric.
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CipherSuite {
+    TLS13_AES_128_GCM_SHA256,
+    TLS13_AES_256_GCM_SHA384,
+    TLS13_CHACHA20_POLY1305_SHA256,
+    TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+    TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+    TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+    TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+    TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+    TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+    Other,
+}
+
+/// Describes why a handshake failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FailureReason {
+    /// The peer sent an alert.
+    Alert(Alert),
+    /// The peer's certificate could not be verified.
+    InvalidCertificate,
+    /// The peer did not present a certificate.
+    NoCertificate,
+    /// The peer's certificate did not match the expected identity.
+    IdentityMismatch,
+    /// The peer does not support any of the configured protocol versions,
+    /// cipher suites, or key exchange groups.
+    PeerIncompatible,
+    /// The peer violated the TLS protocol.
+    PeerMisbehaved,
+    /// The connection failed before the handshake completed.
+    Io,
+    Other,
+}
+
+/// TLS alert descriptions received from peers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Alert {
+    HandshakeFailure,
+    BadCertificate,
+    CertificateExpired,
+    UnknownCa,
+    DecryptError,
+    ProtocolVersion,
+    InsufficientSecurity,
+    CertificateRequired,
+    Other,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown TLS protocol version or cipher suite: {0}")]
+pub struct InvalidName(String);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct HandshakeLabels {
+    side: Side,
+    protocol_version: ProtocolVersion,
+    cipher_suite: CipherSuite,
+    session: SessionKind,
+    peer_identity: PeerIdentity,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct FailureLabels {
+    side: Side,
+    reason: FailureReason,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, EncodeLabelSet)]
+struct PolicyLabels {
+    side: Side,
+    mode: PolicyMode,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum SessionKind {
+    new,
+    resumed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, prom::encoding::EncodeLabelValue)]
+#[allow(non_camel_case_types)]
+enum PeerIdentity {
+    verified,
+    none,
+}
+
+/// IANA cipher suite identifiers and names.
+const CIPHER_SUITES: &[(u16, &str, CipherSuite)] = &[
+    (
+        0x1301,
+        "TLS_AES_128_GCM_SHA256",
+        CipherSuite::TLS13_AES_128_GCM_SHA256,
+    ),
+    (
+        0x1302,
+        "TLS_AES_256_GCM_SHA384",
+        CipherSuite::TLS13_AES_256_GCM_SHA384,
+    ),
+    (
+        0x1303,
+        "TLS_CHACHA20_POLY1305_SHA256",
+        CipherSuite::TLS13_CHACHA20_POLY1305_SHA256,
+    ),
+    (
+        0xc02b,
+        "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        0xc02c,
+        "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+        CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+    ),
+    (
+        0xcca9,
+        "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256",
+        CipherSuite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+    ),
+    (
+        0xc02f,
+        "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+        CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        0xc030,
+        "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+        CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+    ),
+    (
+        0xcca8,
+        "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        CipherSuite::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+    ),
+];
+
+// === impl HandshakeMetrics ===
+
+impl HandshakeMetrics {
+    pub fn register(registry: &mut prom::Registry) -> Self {
+        let handshakes = prom::Family::default();
+        registry.register(
+            "handshakes",
+            "The number of TLS handshakes completed by the proxy",
+            handshakes.clone(),
+        );
+
+        let failures = prom::Family::default();
+        registry.register(
+            "handshake_failures",
+            "The number of TLS handshakes that failed",
+            failures.clone(),
+        );
+
+        let policy_violations = prom::Family::default();
+        registry.register(
+            "policy_violations",
+            "The number of TLS sessions that did not satisfy the mesh TLS policy",
+            policy_violations.clone(),
+        );
+
+        Self {
+            handshakes,
+            failures,
+            policy_violations,
+        }
+    }
+
+    pub fn established(&self, side: Side, session: Session) {
+        self.handshakes
+            .get_or_create(&HandshakeLabels {
+                side,
+                protocol_version: session.version,
+                cipher_suite: session.cipher_suite,
+                session: if session.resumed {
+                    SessionKind::resumed
+                } else {
+                    SessionKind::new
+                },
+                peer_identity: if session.peer_identity {
+                    PeerIdentity::verified
+                } else {
+                    PeerIdentity::none
+                },
+            })
+            .inc();
+    }
+
+    pub fn failed(&self, side: Side, reason: FailureReason) {
+        self.failures
+            .get_or_create(&FailureLabels { side, reason })
+            .inc();
+    }
+
+    pub fn policy_violation(&self, side: Side, mode: PolicyMode) {
+        self.policy_violations
+            .get_or_create(&PolicyLabels { side, mode })
+            .inc();
+    }
+}
+
+// === impl Side ===
+
+impl Side {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Client => "client",
+            Self::Server => "server",
+        }
+    }
+}
+
+impl EncodeLabelValue for Side {
+    fn encode(&self, enc: &mut LabelValueEncoder<'_>) -> std::fmt::Result {
+        enc.write_str(self.as_str())
+    }
+}
+
+// === impl ProtocolVersion ===
+
+impl ProtocolVersion {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tls12 => "TLSv1_2",
+            Self::Tls13 => "TLSv1_3",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = InvalidName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1.2" | "TLSv1_2" | "TLSv1.2" => Ok(Self::Tls12),
+            "1.3" | "TLSv1_3" | "TLSv1.3" => Ok(Self::Tls13),
+            _ => Err(InvalidName(s.to_string())),
+        }
+    }
+}
+
+impl EncodeLabelValue for ProtocolVersion {
+    fn encode(&self, enc: &mut LabelValueEncoder<'_>) -> std::fmt::Result {
+        enc.write_str(self.as_str())
+    }
+}
+
+// === impl CipherSuite ===
+
+impl CipherSuite {
+    /// Returns the cipher suite with the given IANA identifier.
+    pub fn from_id(id: u16) -> Self {
+        CIPHER_SUITES
+            .iter()
+            .find(|(i, _, _)| *i == id)
+            .map_or(Self::Other, |(_, _, cs)| *cs)
+    }
+
+    /// Returns the cipher suite with the given IANA (RFC) name.
+    pub fn from_standard_name(name: &str) -> Self {
+        CIPHER_SUITES
+            .iter()
+            .find(|(_, n, _)| *n == name)
+            .map_or(Self::Other, |(_, _, cs)| *cs)
+    }
+}
+
+/// Parses a cipher suite from either its label value or its IANA name.
+impl FromStr for CipherSuite {
+    type Err = InvalidName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CIPHER_SUITES
+            .iter()
+            .find(|(_, n, cs)| *n == s || format!("{cs:?}") == s)
+            .map(|(_, _, cs)| *cs)
+            .ok_or_else(|| InvalidName(s.to_string()))
+    }
+}
+
+impl EncodeLabelValue for CipherSuite {
+    fn encode(&self, enc: &mut LabelValueEncoder<'_>) -> std::fmt::Result {
+        match self {
+            Self::Other => enc.write_str("other"),
+            cs => write!(enc, "{cs:?}"),
+        }
+    }
+}
+
+// === impl PolicyMode ===
+
+impl EncodeLabelValue for PolicyMode {
+    fn encode(&self, enc: &mut LabelValueEncoder<'_>) -> std::fmt::Result {
+        match self {
+            Self::Enforce => enc.write_str("enforce"),
+            Self::Audit => enc.write_str("audit"),
+        }
+    }
+}
+
+// === impl FailureReason ===
+
+impl FailureReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Alert(alert) => alert.as_str(),
+            Self::InvalidCertificate => "invalid_certificate",
+            Self::NoCertificate => "no_certificate",
+            Self::IdentityMismatch => "identity_mismatch",
+            Self::PeerIncompatible => "peer_incompatible",
+            Self::PeerMisbehaved => "peer_misbehaved",
+            Self::Io => "io",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl EncodeLabelValue for FailureReason {
+    fn encode(&self, enc: &mut LabelValueEncoder<'_>) -> std::fmt::Result {
+        enc.write_str(self.as_str())
+    }
+}
+
+// === impl Alert ===
+
+impl Alert {
+    /// Returns the alert with the given description code, as defined by RFC
+    /// 8446.
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            40 => Self::HandshakeFailure,
+            42 => Self::BadCertificate,
+            45 => Self::CertificateExpired,
+            48 => Self::UnknownCa,
+            51 => Self::DecryptError,
+            70 => Self::ProtocolVersion,
+            71 => Self::InsufficientSecurity,
+            116 => Self::CertificateRequired,
+            _ => Self::Other,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::HandshakeFailure => "alert_handshake_failure",
+            Self::BadCertificate => "alert_bad_certificate",
+            Self::CertificateExpired => "alert_certificate_expired",
+            Self::UnknownCa => "alert_unknown_ca",
+            Self::DecryptError => "alert_decrypt_error",
+            Self::ProtocolVersion => "alert_protocol_version",
+            Self::InsufficientSecurity => "alert_insufficient_security",
+            Self::CertificateRequired => "alert_certificate_required",
+            Self::Other => "alert_other",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cipher_suites() {
+        assert_eq!(
+            CipherSuite::from_id(0x1301),
+            CipherSuite::TLS13_AES_128_GCM_SHA256
+        );
+        assert_eq!(
+            CipherSuite::from_standard_name("TLS_AES_128_GCM_SHA256"),
+            CipherSuite::TLS13_AES_128_GCM_SHA256
+        );
+        assert_eq!(
+            CipherSuite::from_standard_name("TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384"),
+            CipherSuite::from_id(0xc030)
+        );
+        assert_eq!(CipherSuite::from_id(0x0005), CipherSuite::Other);
+        assert_eq!(
+            CipherSuite::from_standard_name("TLS_RSA_WITH_RC4_128_SHA"),
+            CipherSuite::Other
+        );
+
+        assert_eq!(
+            "TLS13_AES_256_GCM_SHA384".parse::<CipherSuite>().unwrap(),
+            CipherSuite::TLS13_AES_256_GCM_SHA384
+        );
+        assert_eq!(
+            "TLS_AES_256_GCM_SHA384".parse::<CipherSuite>().unwrap(),
+            CipherSuite::TLS13_AES_256_GCM_SHA384
+        );
+        assert!("TLS_RSA_WITH_RC4_128_SHA".parse::<CipherSuite>().is_err());
+        assert!("Other".parse::<CipherSuite>().is_err());
+    }
+
+    #[test]
+    fn encodes_bounded_labels() {
+        let mut registry = prom::Registry::default();
+        let metrics = HandshakeMetrics::register(&mut registry);
+        metrics.established(
+            Side::Server,
+            Session {
+                version: ProtocolVersion::Tls13,
+                cipher_suite: CipherSuite::TLS13_AES_256_GCM_SHA384,
+                resumed: true,
+                peer_identity: false,
+            },
+        );
+        metrics.failed(Side::Client, FailureReason::Alert(Alert::from_code(48)));
+
+        let mut out = String::new();
+        prom::encoding::text::encode(&mut out, &registry).unwrap();
+        assert!(
+            out.contains("handshakes_total{side=\"server\",protocol_version=\"TLSv1_3\",cipher_suite=\"TLS13_AES_256_GCM_SHA384\",session=\"resumed\",peer_identity=\"none\"} 1"),
+            "{out}"
+        );
+        assert!(
+            out.contains("handshake_failures_total{side=\"client\",reason=\"alert_unknown_ca\"} 1"),
+            "{out}"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Constrains the TLS protocol versions and cipher suites negotiated by mesh
+//! connections.
+
+use crate::metrics::{
+    Alert, CipherSuite, FailureReason, HandshakeMetrics, ProtocolVersion, Session, Side,
+};
+use linkerd_io as io;
+use std::{str::FromStr, sync::Arc};
+use tracing::debug;
+
+/// Describes the minimum protocol version and the cipher suites permitted on
+/// mesh connections.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsPolicy {
+    pub min_version: ProtocolVersion,
+
+    /// The cipher suites that may be negotiated. When unset, all suites
+    /// supported by the TLS backend are permitted.
+    pub cipher_suites: Option<Arc<[CipherSuite]>>,
+
+    pub mode: PolicyMode,
+}
+
+/// Determines how sessions that do not satisfy a [`TlsPolicy`] are handled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PolicyMode {
+    /// Only sessions that satisfy the policy may be established.
+    #[default]
+    Enforce,
+
+    /// Sessions that do not satisfy the policy are established, but are
+    /// counted, so that peers can be found before the policy is enforced.
+    Audit,
+}
+
+/// Indicates that a TLS handshake could not satisfy the mesh TLS policy.
+#[derive(Clone, Debug, thiserror::Error)]
+#[error("TLS handshake does not satisfy the mesh TLS policy: {0}")]
+pub struct TlsPolicyViolation(pub String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid TLS policy mode: {0}")]
+pub struct InvalidPolicyMode(String);
+
+// === impl TlsPolicy ===
+
+impl Default for TlsPolicy {
+    fn default() -> Self {
+        Self {
+            min_version: ProtocolVersion::Tls13,
+            cipher_suites: None,
+            mode: PolicyMode::Enforce,
+        }
+    }
+}
+
+impl TlsPolicy {
+    pub fn permits_version(&self, version: ProtocolVersion) -> bool {
+        !matches!(
+            (self.min_version, version),
+            (_, ProtocolVersion::Other) | (ProtocolVersion::Tls13, ProtocolVersion::Tls12)
+        )
+    }
+
+    pub fn permits_cipher_suite(&self, cipher_suite: CipherSuite) -> bool {
+        match self.cipher_suites {
+            None => true,
+            Some(ref permitted) => permitted.contains(&cipher_suite),
+        }
+    }
+
+    /// Returns an error describing why the session does not satisfy this
+    /// policy, if it does not.
+    pub fn check(&self, session: &Session) -> Result<(), TlsPolicyViolation> {
+        if !self.permits_version(session.version) {
+            return Err(TlsPolicyViolation(format!(
+                "negotiated {:?}, but at least {:?} is required",
+                session.version, self.min_version
+            )));
+        }
+        if !self.permits_cipher_suite(session.cipher_suite) {
+            return Err(TlsPolicyViolation(format!(
+                "negotiated cipher suite {:?} is not permitted",
+                session.cipher_suite
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks an established session, counting it if it does not satisfy
+    /// this policy. Returns an error if the session must be rejected.
+    pub fn check_established(
+        &self,
+        side: Side,
+        session: &Session,
+        metrics: &HandshakeMetrics,
+    ) -> io::Result<()> {
+        let Err(violation) = self.check(session) else {
+            return Ok(());
+        };
+        metrics.policy_violation(side, self.mode);
+        match self.mode {
+            PolicyMode::Enforce => {
+                debug!(?side, %violation, "Rejecting TLS session");
+                Err(io::Error::other(violation))
+            }
+            PolicyMode::Audit => {
+                debug!(?side, %violation, "Accepting TLS session that does not satisfy policy");
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the error for a failed handshake. When the policy is enforced
+    /// and the peer could not negotiate a permitted version or cipher suite,
+    /// the failure is counted and reported as a [`TlsPolicyViolation`].
+    pub fn handshake_failed(
+        &self,
+        side: Side,
+        reason: FailureReason,
+        error: io::Error,
+        metrics: &HandshakeMetrics,
+    ) -> io::Error {
+        let incompatible = matches!(
+            reason,
+            FailureReason::PeerIncompatible
+                | FailureReason::Alert(
+                    Alert::HandshakeFailure | Alert::ProtocolVersion | Alert::InsufficientSecurity
+                )
+        );
+        if self.mode != PolicyMode::Enforce || !incompatible {
+            return error;
+        }
+        metrics.policy_violation(side, self.mode);
+        io::Error::other(TlsPolicyViolation(error.to_string()))
+    }
+}
+
+// === impl PolicyMode ===
+
+impl FromStr for PolicyMode {
+    type Err = InvalidPolicyMode;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("enforce") {
+            return Ok(Self::Enforce);
+        }
+        if s.eq_ignore_ascii_case("audit") {
+            return Ok(Self::Audit);
+        }
+        Err(InvalidPolicyMode(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(version: ProtocolVersion, cipher_suite: CipherSuite) -> Session {
+        Session {
+            version,
+            cipher_suite,
+            resumed: false,
+            peer_identity: true,
+        }
+    }
+
+    #[test]
+    fn default_requires_tls13() {
+        let policy = TlsPolicy::default();
+        assert!(policy
+            .check(&session(
+                ProtocolVersion::Tls13,
+                CipherSuite::TLS13_AES_128_GCM_SHA256
+            ))
+            .is_ok());
+        assert!(policy
+            .check(&session(
+                ProtocolVersion::Tls12,
+                CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256
+            ))
+            .is_err());
+        assert!(policy
+            .check(&session(ProtocolVersion::Other, CipherSuite::Other))
+            .is_err());
+    }
+
+    #[test]
+    fn restricts_cipher_suites() {
+        let policy = TlsPolicy {
+            min_version: ProtocolVersion::Tls12,
+            cipher_suites: Some(Arc::new([CipherSuite::TLS13_AES_256_GCM_SHA384])),
+            mode: PolicyMode::Enforce,
+        };
+        assert!(policy
+            .check(&session(
+                ProtocolVersion::Tls13,
+                CipherSuite::TLS13_AES_256_GCM_SHA384
+            ))
+            .is_ok());
+        assert!(policy
+            .check(&session(
+                ProtocolVersion::Tls13,
+                CipherSuite::TLS13_AES_128_GCM_SHA256
+            ))
+            .is_err());
+    }
+}
@@ -205,22 +205,29 @@ where
 }
 
 /// Peek or buffer the provided stream to determine an SNI value.
-pub(crate) async fn detect_sni<I>(mut io: I) -> io::Result<(Option<ServerName>, DetectIo<I>)>
+pub async fn detect_sni<I>(mut io: I) -> io::Result<(Option<ServerName>, DetectIo<I>)>
 where
     I: io::Peek + io::AsyncRead + io::AsyncWrite + Send + Sync + Unpin,
 {
-    // First, try to use MSG_PEEK to read the SNI from the TLS ClientHello. We
+    // Wait for the client to send data before allocating any buffers, so that
+    // connections on which the client never writes hold as little memory as
+    // possible.
+    let mut byte = [0u8; 1];
+    let sz = io.peek(&mut byte).await?;
+
+    // Then, try to use MSG_PEEK to read the SNI from the TLS ClientHello. We
     // use a heap-allocated buffer to avoid creating a large `Future` (since we
     // need to hold the buffer across an await).
     //
     // Anecdotally, the ClientHello sent by Linkerd proxies is <300B. So a ~500B
     // byte buffer is more than enough.
-    let mut buf = BytesMut::with_capacity(PEEK_CAPACITY);
-    let sz = io.peek(&mut buf).await?;
-    debug!(sz, "Peeked bytes from TCP stream");
+    //
     // Peek may return 0 bytes if the socket is not peekable.
     if sz > 0 {
-        if let Ok(sni) = client_hello::parse_sni(buf.as_ref()) {
+        let mut buf = BytesMut::zeroed(PEEK_CAPACITY);
+        let sz = io.peek(&mut buf).await?;
+        debug!(sz, "Peeked bytes from TCP stream");
+        if let Ok(sni) = client_hello::parse_sni(&buf[..sz]) {
             return Ok((sni, EitherIo::Left(io)));
         }
     }
@@ -106,7 +106,11 @@ fn extract_sni<'a>(
                     let extension_type = read_u16(input)?;
                     if extension_type != 0 {
                         // ExtensionType::server_name
-                        skip_vector(input)?;
+                        if !skip_vector(input)? {
+                            // The extension's length is implausible, so the
+                            // remaining extensions can't be located.
+                            return Ok(None);
+                        }
                         continue;
                     }
 
@@ -161,7 +165,12 @@ where
         return Ok(None);
     }
     let r = input.read_bytes(usize::from(length))?;
-    r.read_all(untrusted::EndOfInput, f)
+
+    // The entire vector is available, so running out of input while parsing
+    // its contents means that an inner length disagrees with this one. The
+    // input is malformed, and waiting for more of it would never help.
+    let mut r = untrusted::Reader::new(r);
+    Ok(f(&mut r).unwrap_or(None))
 }
 
 /// Like `read_vector` except the contents are ignored.
@@ -199,6 +208,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mismatch_inconsistent_sni_lengths() {
+        let input = include_bytes!("testdata/example-com-client-hello.bin");
+        let name = input
+            .windows(b"example.com".len())
+            .position(|w| w == b"example.com")
+            .expect("test data must include the SNI");
+
+        // The server_name_list and host_name lengths each claim more bytes
+        // than the enclosing extension holds.
+        for len_at in [name - 5, name - 2] {
+            let mut input = input.to_vec();
+            input[len_at..len_at + 2].copy_from_slice(&0x00ffu16.to_be_bytes());
+            assert_eq!(Ok(None), parse_sni(&input));
+        }
+    }
+
+    #[test]
+    fn mismatch_implausible_extension_length() {
+        let input = include_bytes!("testdata/example-com-client-hello.bin");
+        let mut input = input.to_vec();
+        // The first extension (supported_versions) precedes the SNI extension.
+        let ext = input
+            .windows(2)
+            .position(|w| w == [0x00, 0x2b])
+            .expect("test data must include supported_versions");
+        input[ext + 2..ext + 4].copy_from_slice(&0xffffu16.to_be_bytes());
+        assert_eq!(Ok(None), parse_sni(&input));
+    }
+
     #[test]
     fn check_all_prefixes() {
         let input = include_bytes!("testdata/example-com-client-hello.bin");
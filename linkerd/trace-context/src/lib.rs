@@ -3,9 +3,13 @@
 
 pub mod export;
 mod propagation;
+mod recorder;
 mod service;
 
-pub use self::service::TraceContext;
+pub use self::{
+    recorder::{SpanEvent, SpanRecorder},
+    service::TraceContext,
+};
 use bytes::Bytes;
 use linkerd_error::Error;
 use rand::Rng;
@@ -56,6 +60,7 @@ pub struct Span {
     pub start: SystemTime,
     pub end: SystemTime,
     pub labels: HashMap<&'static str, String>,
+    pub events: Vec<SpanEvent>,
 }
 
 pub trait SpanSink {
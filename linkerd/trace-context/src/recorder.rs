@@ -0,0 +1,63 @@
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc, time::SystemTime};
+
+/// Bounds the number of events recorded on a single span.
+const MAX_EVENTS: usize = 64;
+
+/// A timestamped event that occurred while a span was active.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpanEvent {
+    pub name: &'static str,
+    pub time: SystemTime,
+    pub attributes: Vec<(&'static str, String)>,
+}
+
+/// Annotates a sampled span from inner layers of a stack.
+///
+/// A recorder is set as a request extension only when the request's span is
+/// sampled, so layers that annotate spans do no work for unsampled requests.
+#[derive(Clone, Debug, Default)]
+pub struct SpanRecorder(Arc<Mutex<Recorded>>);
+
+#[derive(Debug, Default)]
+struct Recorded {
+    labels: HashMap<&'static str, String>,
+    events: Vec<SpanEvent>,
+}
+
+// === impl SpanRecorder ===
+
+impl SpanRecorder {
+    /// Returns the recorder for the request's span, if it is sampled.
+    pub fn get<B>(req: &http::Request<B>) -> Option<&Self> {
+        req.extensions().get()
+    }
+
+    /// Sets an attribute on the span, replacing any prior value.
+    pub fn set_attribute(&self, key: &'static str, value: impl ToString) {
+        self.0.lock().labels.insert(key, value.to_string());
+    }
+
+    /// Records an event on the span.
+    pub fn add_event(&self, name: &'static str, attributes: Vec<(&'static str, String)>) {
+        let mut recorded = self.0.lock();
+        if recorded.events.len() == MAX_EVENTS {
+            tracing::debug!(name, "Dropping span event");
+            return;
+        }
+        recorded.events.push(SpanEvent {
+            name,
+            time: SystemTime::now(),
+            attributes,
+        });
+    }
+
+    /// Takes the attributes and events recorded so far.
+    pub(crate) fn take(&self) -> (HashMap<&'static str, String>, Vec<SpanEvent>) {
+        let mut recorded = self.0.lock();
+        (
+            std::mem::take(&mut recorded.labels),
+            std::mem::take(&mut recorded.events),
+        )
+    }
+}
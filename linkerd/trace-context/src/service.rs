@@ -1,6 +1,7 @@
-use crate::{propagation, Span, SpanSink};
+use crate::{propagation, Span, SpanRecorder, SpanSink};
 use futures::{future::Either, prelude::*};
 use http::Uri;
+use linkerd_http_request_id::RequestId;
 use linkerd_stack::layer;
 use std::{
     collections::HashMap,
@@ -18,7 +19,8 @@ use tracing::{debug, info, trace};
 /// random span id setting it into the `traceparent` header before forwarding
 /// the request. If the sampled bit of the header was set, we emit metadata
 /// about the span to the given SpanSink when the span is complete, i.e. when
-/// we receive the response.
+/// we receive the response. Sampled requests carry a [`SpanRecorder`]
+/// extension so that inner layers may annotate the span.
 #[derive(Clone, Debug)]
 pub struct TraceContext<K, S> {
     inner: S,
@@ -41,7 +43,7 @@ impl<K: Clone, S> TraceContext<K, S> {
     /// services should use for the labels included in traces:
     /// https://opentelemetry.io/docs/specs/semconv/http/http-spans/
     fn request_labels<B>(req: &http::Request<B>) -> HashMap<&'static str, String> {
-        let mut labels = HashMap::with_capacity(7);
+        let mut labels = HashMap::with_capacity(8);
         labels.insert("http.request.method", format!("{}", req.method()));
         let url = req.uri();
         if let Some(scheme) = url.scheme_str() {
@@ -72,6 +74,11 @@ impl<K: Clone, S> TraceContext<K, S> {
                 }
             }
         }
+
+        // Set by the request ID layer, if one is configured.
+        if let Some(id) = req.extensions().get::<RequestId>() {
+            labels.insert("http.request.id", id.to_string());
+        }
         labels
     }
 
@@ -120,8 +127,14 @@ where
                     let req_labels = Self::request_labels(&req);
                     let mut sink = self.sink.clone();
                     let span_name = req.uri().path().to_owned();
+                    let recorder = SpanRecorder::default();
+                    req.extensions_mut().insert(recorder.clone());
                     return Either::Right(Box::pin(self.inner.call(req).map_ok(move |rsp| {
-                        // Emit the completed span with the response metadata.
+                        // Emit the completed span with the response metadata
+                        // and any annotations recorded by inner layers.
+                        let (recorded, events) = recorder.take();
+                        let mut labels = Self::add_response_labels(req_labels, &rsp);
+                        labels.extend(recorded);
                         let span = Span {
                             span_id,
                             trace_id: context.trace_id,
@@ -129,7 +142,8 @@ where
                             span_name,
                             start,
                             end: SystemTime::now(),
-                            labels: Self::add_response_labels(req_labels, &rsp),
+                            labels,
+                            events,
                         };
                         trace!(?span);
                         if let Err(error) = sink.try_send(span) {
@@ -145,3 +159,83 @@ where
         Either::Left(self.inner.call(req))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linkerd_error::Error;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+    use tower::{layer::Layer, service_fn, ServiceExt};
+
+    #[derive(Clone, Default)]
+    struct Spans(Arc<Mutex<Vec<Span>>>);
+
+    impl SpanSink for Spans {
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        fn try_send(&mut self, span: Span) -> Result<(), Error> {
+            self.0.lock().push(span);
+            Ok(())
+        }
+    }
+
+    async fn send(spans: &Spans, traceparent: &'static str) -> bool {
+        let svc = TraceContext::layer(spans.clone()).layer(service_fn(
+            |req: http::Request<()>| async move {
+                let recorded = SpanRecorder::get(&req).is_some();
+                if let Some(recorder) = SpanRecorder::get(&req) {
+                    recorder.set_attribute("proxy.test", "value");
+                    recorder.add_event("test", vec![("attempt", "1".to_string())]);
+                }
+                Ok::<_, Error>(http::Response::new(recorded))
+            },
+        ));
+        let req = http::Request::get("/path")
+            .header("traceparent", traceparent)
+            .body(())
+            .unwrap();
+        svc.oneshot(req).await.unwrap().into_body()
+    }
+
+    #[tokio::test]
+    async fn records_sampled_spans() {
+        let spans = Spans::default();
+        let recorded = send(
+            &spans,
+            "00-94d7f6ec6b95f3e916179cb6cfd01390-55ccfce77f972614-01",
+        )
+        .await;
+        assert!(recorded);
+
+        let spans = spans.0.lock();
+        let [span] = &spans[..] else {
+            panic!("expected one span: {spans:?}");
+        };
+        assert_eq!(span.span_name, "/path");
+        assert_eq!(span.labels.get("proxy.test").map(|v| &**v), Some("value"));
+        assert_eq!(
+            span.labels.get("http.response.status_code").map(|v| &**v),
+            Some("200")
+        );
+        let [event] = &span.events[..] else {
+            panic!("expected one event: {:?}", span.events);
+        };
+        assert_eq!(event.name, "test");
+        assert_eq!(event.attributes, vec![("attempt", "1".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn ignores_unsampled_spans() {
+        let spans = Spans::default();
+        let recorded = send(
+            &spans,
+            "00-94d7f6ec6b95f3e916179cb6cfd01390-55ccfce77f972614-00",
+        )
+        .await;
+        assert!(!recorded);
+        assert!(spans.0.lock().is_empty());
+    }
+}
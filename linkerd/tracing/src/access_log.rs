@@ -105,10 +105,12 @@ where
 impl ApacheCommon {
     const SKIPPED_FIELDS: &'static [&'static str] = &[
         "trace_id",
+        "request_id",
         "request_bytes",
         "total_ns",
         "processing_ns",
         "response_bytes",
+        "error_code",
         "user_agent",
         "host",
     ];
@@ -38,10 +38,43 @@ pub enum SessionProtocol {
     Http2,
 }
 
+/// The result of parsing a buffer that may start with a connection header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Parsed {
+    /// The buffer does not start with a connection header.
+    NoHeader,
+
+    /// The buffer may start with a connection header, but at least `needed`
+    /// bytes must be buffered before it can be parsed.
+    Incomplete { needed: usize },
+
+    /// A connection header was decoded from the first `len` bytes of the
+    /// buffer.
+    Header { header: TransportHeader, len: usize },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeError {
+    #[error("header length {0} exceeds capacity")]
+    TooLarge(usize),
+
+    #[error("invalid header message: {0}")]
+    Message(#[from] prost::DecodeError),
+
+    #[error("invalid name")]
+    InvalidName,
+
+    #[error("invalid port value: {0}")]
+    InvalidPort(i32),
+}
+
 pub const PROTOCOL: &[u8] = b"transport.l5d.io/v1";
 const PREFACE: &[u8] = b"transport.l5d.io/v1\r\n\r\n";
 const PREFACE_AND_SIZE_LEN: usize = PREFACE.len() + 4;
 
+/// The largest header message that will be buffered.
+const MAX_HEADER_LEN: usize = 64 * 1024;
+
 impl TransportHeader {
     pub async fn write(&self, io: &mut (impl io::AsyncWrite + Unpin)) -> Result<usize, Error> {
         let mut buf = self.encode_prefaced_buf()?;
@@ -117,67 +150,81 @@ impl TransportHeader {
         io: &mut (impl io::AsyncRead + Unpin),
         buf: &mut BytesMut,
     ) -> io::Result<Option<Self>> {
-        // Read at least enough data to determine whether a connection header is
-        // present and, if so, how long it is.
-        while buf.len() < PREFACE_AND_SIZE_LEN {
-            if io.read_buf(buf).await? == 0 {
-                return Ok(None);
+        loop {
+            match Self::parse_prefaced(buf.chunk())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            {
+                Parsed::NoHeader => return Ok(None),
+                Parsed::Header { header, len } => {
+                    // Leave the remaining bytes in the caller-provided buffer.
+                    buf.advance(len);
+                    return Ok(Some(header));
+                }
+                Parsed::Incomplete { .. } => {}
             }
-        }
 
-        // Advance the buffer past the preface if it matches.
-        if &buf.chunk()[..PREFACE.len()] != PREFACE {
-            return Ok(None);
-        }
-        buf.advance(PREFACE.len());
-
-        // Read the message length. If it is larger than our allowed buffer
-        // capacity, fail the connection.
-        let msg_len = buf.get_u32() as usize;
-        if msg_len > buf.capacity() + PREFACE_AND_SIZE_LEN {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Message length exceeds capacity",
-            ));
-        }
-
-        // Free up parsed preface data and ensure there's enough capacity for
-        // the message.
-        buf.reserve(msg_len);
-        while buf.len() < msg_len {
             if io.read_buf(buf).await? == 0 {
+                if buf.len() < PREFACE_AND_SIZE_LEN {
+                    return Ok(None);
+                }
                 return Err(io::Error::new(
                     io::ErrorKind::UnexpectedEof,
                     "Full header message not provided",
                 ));
             }
         }
+    }
+
+    /// Attempts to parse a prefaced connection header from the start of
+    /// `input`.
+    ///
+    /// This performs no I/O, so callers are responsible for buffering input
+    /// until a header is parsed or ruled out.
+    pub fn parse_prefaced(input: &[u8]) -> Result<Parsed, DecodeError> {
+        // Determine whether a connection header may be present before waiting
+        // for enough data to know how long it is.
+        let preface_len = input.len().min(PREFACE.len());
+        if input[..preface_len] != PREFACE[..preface_len] {
+            return Ok(Parsed::NoHeader);
+        }
+        if input.len() < PREFACE_AND_SIZE_LEN {
+            return Ok(Parsed::Incomplete {
+                needed: PREFACE_AND_SIZE_LEN,
+            });
+        }
+
+        // Read the message length. If it is larger than we allow, fail the
+        // connection.
+        let mut size = &input[PREFACE.len()..PREFACE_AND_SIZE_LEN];
+        let msg_len = size.get_u32() as usize;
+        if msg_len > MAX_HEADER_LEN {
+            return Err(DecodeError::TooLarge(msg_len));
+        }
+
+        let len = PREFACE_AND_SIZE_LEN + msg_len;
+        if input.len() < len {
+            return Ok(Parsed::Incomplete { needed: len });
+        }
 
-        // Take the bytes needed to parse the message and leave the remaining
-        // bytes in the caller-provided buffer.
-        let msg = buf.split_to(msg_len);
-        Self::decode(msg.freeze())
+        let header = Self::decode(&input[PREFACE_AND_SIZE_LEN..len])?;
+        Ok(Parsed::Header { header, len })
     }
 
     // Decodes a protobuf message from the buffer.
-    fn decode<B: Buf>(buf: B) -> io::Result<Option<Self>> {
-        let h = proto::Header::decode(buf)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fn decode<B: Buf>(buf: B) -> Result<Self, DecodeError> {
+        let h = proto::Header::decode(buf)?;
 
         let name = if h.name.is_empty() {
             None
         } else {
-            let n = Name::from_str(&h.name)
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let n = Name::from_str(&h.name).map_err(|_| DecodeError::InvalidName)?;
             Some(n)
         };
 
-        if h.port <= 0 || h.port > u16::MAX as i32 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid port value",
-            ));
-        }
+        let port = match u16::try_from(h.port) {
+            Ok(port) if port > 0 => port,
+            _ => return Err(DecodeError::InvalidPort(h.port)),
+        };
 
         let protocol = h.session_protocol.and_then(|p| {
             p.kind.map(|k| match k {
@@ -186,11 +233,11 @@ impl TransportHeader {
             })
         });
 
-        Ok(Some(Self {
-            port: h.port as u16,
+        Ok(Self {
+            port,
             name,
             protocol,
-        }))
+        })
     }
 }
 
@@ -272,6 +319,73 @@ mod tests {
             .expect("I/O must still have data");
         assert_eq!(&buf, b"12345");
     }
+
+    #[test]
+    fn parse_incomplete() {
+        let header = TransportHeader {
+            port: 4040,
+            name: Some(Name::from_str("foo.bar.example.com").unwrap()),
+            protocol: Some(SessionProtocol::Http1),
+        };
+        let buf = header.encode_prefaced_buf().expect("must encode");
+
+        for i in 0..PREFACE_AND_SIZE_LEN {
+            assert_eq!(
+                TransportHeader::parse_prefaced(&buf[..i]).expect("must not fail"),
+                Parsed::Incomplete {
+                    needed: PREFACE_AND_SIZE_LEN
+                },
+            );
+        }
+        for i in PREFACE_AND_SIZE_LEN..buf.len() {
+            assert_eq!(
+                TransportHeader::parse_prefaced(&buf[..i]).expect("must not fail"),
+                Parsed::Incomplete { needed: buf.len() },
+            );
+        }
+        assert_eq!(
+            TransportHeader::parse_prefaced(&buf).expect("must not fail"),
+            Parsed::Header {
+                header,
+                len: buf.len()
+            },
+        );
+    }
+
+    #[test]
+    fn parse_no_header() {
+        assert_eq!(
+            TransportHeader::parse_prefaced(b"transport.l5d.io/v2").expect("must not fail"),
+            Parsed::NoHeader,
+        );
+    }
+
+    #[test]
+    fn parse_rejects_oversized_length() {
+        let mut buf = BytesMut::from(PREFACE);
+        buf.put_u32(u32::MAX);
+        assert!(matches!(
+            TransportHeader::parse_prefaced(&buf),
+            Err(DecodeError::TooLarge(len)) if len == u32::MAX as usize
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_invalid_port() {
+        for port in [0, -1, i32::from(u16::MAX) + 1] {
+            let msg = proto::Header {
+                port,
+                ..Default::default()
+            };
+            let mut buf = BytesMut::from(PREFACE);
+            buf.put_u32(msg.encoded_len() as u32);
+            msg.encode(&mut buf).expect("must encode");
+            assert!(matches!(
+                TransportHeader::parse_prefaced(&buf),
+                Err(DecodeError::InvalidPort(p)) if p == port
+            ));
+        }
+    }
 }
 
 #[cfg(fuzzing)]
@@ -308,6 +422,21 @@ pub mod fuzz_logic {
         }
     }
 
+    pub fn fuzz_entry_parse(fuzz_data: &[u8]) {
+        if let Ok(Parsed::Header { header, len }) = TransportHeader::parse_prefaced(fuzz_data) {
+            assert!(len <= fuzz_data.len());
+            let buf = header.encode_prefaced_buf().expect("must encode");
+            let reparsed = TransportHeader::parse_prefaced(&buf).expect("must reparse");
+            assert_eq!(
+                reparsed,
+                Parsed::Header {
+                    header,
+                    len: buf.len()
+                }
+            );
+        }
+    }
+
     pub async fn fuzz_entry_raw(fuzz_data: &[u8]) {
         let mut rx = {
             let mut buf = BytesMut::new();
@@ -16,7 +16,7 @@ compile_error!(
     "at least one of the following TLS implementations must be enabled: 'meshtls-boring', 'meshtls-rustls'"
 );
 
-use linkerd_app::{trace, BindTcp, Config, BUILD_INFO};
+use linkerd_app::{env, panics, trace, BindTcp, Config, OrigDstMetrics, BUILD_INFO};
 use linkerd_signal as signal;
 use tokio::{sync::mpsc, time};
 use tracing::{debug, info, warn};
@@ -30,6 +30,13 @@ mod rt;
 const EX_USAGE: i32 = 64;
 
 fn main() {
+    // `--validate` checks the configuration and exits without starting the
+    // proxy. `--resolve` additionally resolves control plane names.
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|a| a == "--validate") {
+        validate(args.iter().any(|a| a == "--resolve"));
+    }
+
     let trace = match trace::Settings::from_env().init() {
         Ok(t) => t,
         Err(e) => {
@@ -38,6 +45,10 @@ fn main() {
         }
     };
 
+    // Record panics in the proxy's tasks so that they are reported via
+    // metrics and the admin server.
+    panics::install();
+
     info!(
         "{profile} {version} ({sha}) by {vendor} on {date}",
         date = BUILD_INFO.date,
@@ -68,8 +79,16 @@ fn main() {
         let (shutdown_tx, mut shutdown_rx) = mpsc::unbounded_channel();
         let shutdown_grace_period = config.shutdown_grace_period;
 
-        let bind_in = BindTcp::with_orig_dst();
-        let bind_out = BindTcp::dual_with_orig_dst();
+        // Connections whose original destination address cannot be
+        // determined are refused unless a fallback is configured.
+        let bind_in = BindTcp::with_orig_dst().with_fallback(
+            config.orig_dst_fallback.clone(),
+            OrigDstMetrics::register(metrics.sub_registry_with_prefix("inbound")),
+        );
+        let bind_out = BindTcp::dual_with_orig_dst().with_fallback(
+            config.orig_dst_fallback.clone(),
+            OrigDstMetrics::register(metrics.sub_registry_with_prefix("outbound")),
+        );
         let app = match config
             .build(
                 bind_in,
@@ -89,11 +108,23 @@ fn main() {
         };
 
         info!("Admin interface on {}", app.admin_addr());
+        if let Some(addr) = app.admin_scrape_addr() {
+            info!("Admin scrape interface on {addr}");
+        }
         info!("Inbound interface on {}", app.inbound_addr());
         info!("Outbound interface on {}", app.outbound_addr());
         if let Some(addr) = app.outbound_addr_additional() {
             info!("Outbound interface on {addr}");
         }
+        if let Some(addr) = app.outbound_explicit_addr() {
+            info!("Explicit outbound interface on {addr}");
+        }
+        if let Some(addr) = app.outbound_socks5_addr() {
+            info!("SOCKS5 outbound interface on {addr}");
+        }
+        if let Some(addr) = app.outbound_udp_addr() {
+            info!("Outbound UDP interface on {addr}");
+        }
 
         match app.tap_addr() {
             None => info!("Tap DISABLED"),
@@ -139,3 +170,18 @@ fn main() {
         }
     });
 }
+
+/// Prints a report on the configuration's validity and exits.
+fn validate(resolve: bool) -> ! {
+    let mut validation = env::Env.validate();
+    if resolve {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime must build")
+            .block_on(validation.resolve());
+    }
+
+    println!("{}", validation.to_json());
+    std::process::exit(if validation.is_valid() { 0 } else { EX_USAGE });
+}